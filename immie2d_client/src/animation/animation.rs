@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use immie2d_shared::gameplay::battle::battle_event::BattleEvent;
+pub use immie2d_shared::gameplay::battle::event_timeline::get_event_duration;
+
+/* A single timed animation. Progress depends only on the time it has been advanced by, never on frames. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Animation {
+    pub event: BattleEvent,
+    duration: Duration,
+    elapsed: Duration
+}
+
+impl Animation {
+    pub fn new(event: BattleEvent, duration: Duration) -> Animation {
+        return Animation { event, duration, elapsed: Duration::ZERO };
+    }
+
+    /// Create an animation using the default duration for its event.
+    pub fn for_event(event: BattleEvent) -> Animation {
+        return Animation::new(event, get_event_duration(&event));
+    }
+
+    pub fn get_duration(&self) -> Duration {
+        return self.duration;
+    }
+
+    pub fn get_elapsed(&self) -> Duration {
+        return self.elapsed;
+    }
+
+    /// How far through the animation it is, from 0 to 1.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_shared::gameplay::battle::battle_event::BattleEvent;
+    /// use immie2d_client::animation::animation::Animation;
+    ///
+    /// let mut animation = Animation::new(BattleEvent::TurnEnded { turn: 1 }, Duration::from_millis(200));
+    /// let leftover = animation.advance(Duration::from_millis(50));
+    /// assert_eq!(leftover, Duration::ZERO);
+    /// assert_eq!(animation.get_progress(), 0.25);
+    /// let leftover = animation.advance(Duration::from_millis(200));
+    /// assert_eq!(leftover, Duration::from_millis(50));
+    /// assert!(animation.is_finished());
+    /// ```
+    pub fn get_progress(&self) -> f32 {
+        if self.duration.is_zero() {
+            return 1.0;
+        }
+        return (self.elapsed.as_secs_f32() / self.duration.as_secs_f32()).min(1.0);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.elapsed >= self.duration;
+    }
+
+    /// Advance the animation. Returns the time left over after it finished, so the next animation can use it.
+    pub fn advance(&mut self, delta: Duration) -> Duration {
+        let remaining = self.duration - self.elapsed;
+        if delta >= remaining {
+            self.elapsed = self.duration;
+            return delta - remaining;
+        }
+        self.elapsed += delta;
+        return Duration::ZERO;
+    }
+}
@@ -0,0 +1,51 @@
+use std::time::{Duration, Instant};
+
+/// Longest wall-clock delta a single update can advance animations by.
+/// After a long hitch, such as dragging the window, animations catch up by at most this much instead of skipping to the end.
+pub const MAX_ANIMATION_DELTA: Duration = Duration::from_millis(250);
+
+/* Measures the wall-clock time between updates so animations play at the same speed regardless of frame rate. */
+pub struct AnimationClock {
+    last_update: Option<Instant>,
+    max_delta: Duration
+}
+
+impl AnimationClock {
+    pub fn new() -> AnimationClock {
+        return AnimationClock::with_max_delta(MAX_ANIMATION_DELTA);
+    }
+
+    pub fn with_max_delta(max_delta: Duration) -> AnimationClock {
+        return AnimationClock { last_update: None, max_delta };
+    }
+
+    pub fn get_max_delta(&self) -> Duration {
+        return self.max_delta;
+    }
+
+    /// Time animations should advance by since the last tick, capped to the max delta. The first tick is zero.
+    pub fn tick(&mut self) -> Duration {
+        return self.tick_at(Instant::now());
+    }
+
+    /// Same as tick(), using an explicit time.
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use immie2d_client::animation::animation_clock::AnimationClock;
+    ///
+    /// let mut clock = AnimationClock::with_max_delta(Duration::from_millis(100));
+    /// let start = Instant::now();
+    /// assert_eq!(clock.tick_at(start), Duration::ZERO);
+    /// assert_eq!(clock.tick_at(start + Duration::from_millis(16)), Duration::from_millis(16));
+    /// // A long stall only advances by the cap.
+    /// assert_eq!(clock.tick_at(start + Duration::from_secs(5)), Duration::from_millis(100));
+    /// ```
+    pub fn tick_at(&mut self, now: Instant) -> Duration {
+        let delta = match self.last_update {
+            Some(last) => now.saturating_duration_since(last),
+            None => Duration::ZERO
+        };
+        self.last_update = Some(now);
+        return delta.min(self.max_delta);
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use immie2d_shared::gameplay::battle::battle_event::BattleEvent;
+
+use super::animation::Animation;
+
+/// Identifies an animation queued on a battle view model.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct AnimationHandle(pub u64);
+
+/// Called with the event of the completed animation, or None if it had already completed when registered.
+type CompletionCallback = Box<dyn FnOnce(Option<&BattleEvent>) + Send>;
+
+struct QueuedAnimation {
+    handle: AnimationHandle,
+    animation: Animation,
+    callbacks: Vec<CompletionCallback>
+}
+
+/* Plays battle events as animations one after another. Game flow never blocks on it: updates are driven by the
+frame loop with a wall-clock delta, and callers either poll is_complete() or register a completion callback, leaving
+the network pump free to run between frames. */
+pub struct BattleViewModel {
+    queue: VecDeque<QueuedAnimation>,
+    next_handle: u64
+}
+
+impl BattleViewModel {
+    pub fn new() -> BattleViewModel {
+        return BattleViewModel { queue: VecDeque::new(), next_handle: 0 };
+    }
+
+    /// Queue an animation after every currently queued one.
+    pub fn play(&mut self, animation: Animation) -> AnimationHandle {
+        let handle = AnimationHandle(self.next_handle);
+        self.next_handle += 1;
+        self.queue.push_back(QueuedAnimation { handle, animation, callbacks: Vec::new() });
+        return handle;
+    }
+
+    /// Queue the default animation for every event, in order. Returns the handle of the last one, which completes once they all have.
+    pub fn play_events(&mut self, events: Vec<BattleEvent>) -> Option<AnimationHandle> {
+        let mut last = None;
+        for event in events {
+            last = Some(self.play(Animation::for_event(event)));
+        }
+        return last;
+    }
+
+    /// Whether an animation has finished playing. Handles that were never queued count as complete.
+    pub fn is_complete(&self, handle: AnimationHandle) -> bool {
+        return !self.queue.iter().any(|queued| queued.handle == handle);
+    }
+
+    /// Whether there is nothing left to animate.
+    pub fn is_idle(&self) -> bool {
+        return self.queue.is_empty();
+    }
+
+    /// The animation currently playing, if any.
+    pub fn get_current(&self) -> Option<&Animation> {
+        return self.queue.front().map(|queued| &queued.animation);
+    }
+
+    pub fn get_queued_count(&self) -> usize {
+        return self.queue.len();
+    }
+
+    /// Call a function once an animation completes. If it already has, the function is called immediately with no event.
+    /// Returns whether the callback is waiting on the animation, or false if it was already called.
+    /// ```
+    /// use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+    /// use std::time::Duration;
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    /// use immie2d_client::animation::{animation::Animation, battle_view_model::BattleViewModel};
+    ///
+    /// let mut view = BattleViewModel::new();
+    /// let fainted = view.play(Animation::new(BattleEvent::Fainted { battler: BattlerId { side: 1, slot: 0 } }, Duration::from_millis(300)));
+    /// view.update(Duration::from_millis(300));
+    ///
+    /// let done = Arc::new(AtomicBool::new(false));
+    /// let flag = done.clone();
+    /// assert!(!view.on_complete(fainted, move |event| flag.store(event.is_none(), Ordering::SeqCst)));
+    /// assert!(done.load(Ordering::SeqCst));
+    /// ```
+    pub fn on_complete<F: FnOnce(Option<&BattleEvent>) + Send + 'static>(&mut self, handle: AnimationHandle, callback: F) -> bool {
+        match self.queue.iter_mut().find(|queued| queued.handle == handle) {
+            Some(queued) => {
+                queued.callbacks.push(Box::new(callback));
+                return true;
+            },
+            None => {
+                callback(None);
+                return false;
+            }
+        }
+    }
+
+    /// Advance animations by a wall-clock delta. Time left over from a finished animation carries into the next,
+    /// so several short animations can complete in one slow frame. Returns the handles that completed, in order.
+    /// ```
+    /// use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
+    /// use std::time::Duration;
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    /// use immie2d_client::animation::{animation::Animation, battle_view_model::BattleViewModel};
+    ///
+    /// let battler = BattlerId { side: 1, slot: 0 };
+    /// let mut view = BattleViewModel::new();
+    /// let damaged = view.play(Animation::new(BattleEvent::Damaged { battler, amount: 5, remaining_health: 10 }, Duration::from_millis(300)));
+    /// let fainted = view.play(Animation::new(BattleEvent::Fainted { battler }, Duration::from_millis(300)));
+    ///
+    /// let done = Arc::new(AtomicBool::new(false));
+    /// let flag = done.clone();
+    /// view.on_complete(fainted, move |_| flag.store(true, Ordering::SeqCst));
+    ///
+    /// // Frame rate doesn't matter, only the total time.
+    /// for _ in 0..10 {
+    ///     view.update(Duration::from_millis(20));
+    /// }
+    /// assert!(!view.is_complete(damaged));
+    /// assert_eq!(view.update(Duration::from_millis(350)), vec![damaged]);
+    /// assert!(!done.load(Ordering::SeqCst));
+    /// assert_eq!(view.update(Duration::from_millis(250)), vec![fainted]);
+    /// assert!(done.load(Ordering::SeqCst));
+    /// assert!(view.is_idle());
+    /// ```
+    pub fn update(&mut self, delta: Duration) -> Vec<AnimationHandle> {
+        let mut completed = Vec::new();
+        let mut remaining = delta;
+        while let Some(front) = self.queue.front_mut() {
+            remaining = front.animation.advance(remaining);
+            if !front.animation.is_finished() {
+                break;
+            }
+            let finished = self.queue.pop_front().unwrap();
+            for callback in finished.callbacks {
+                callback(Some(&finished.animation.event));
+            }
+            completed.push(finished.handle);
+        }
+        return completed;
+    }
+
+    /// Finish every queued animation straight away, calling their callbacks.
+    pub fn skip_all(&mut self) -> Vec<AnimationHandle> {
+        let mut completed = Vec::new();
+        while let Some(finished) = self.queue.pop_front() {
+            for callback in finished.callbacks {
+                callback(Some(&finished.animation.event));
+            }
+            completed.push(finished.handle);
+        }
+        return completed;
+    }
+}
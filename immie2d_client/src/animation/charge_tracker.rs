@@ -0,0 +1,77 @@
+use std::collections::HashMap;
+
+use immie2d_shared::gameplay::battle::battle_event::BattleEvent;
+use immie2d_shared::gameplay::battle::battler_id::BattlerId;
+use immie2d_shared::gameplay::battle::forced_action::ForcedActionKind;
+
+/* How far a battler is through a multi-turn ability, as of the last event played. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChargeProgress {
+    pub kind: ForcedActionKind,
+    pub turn: u32,
+    pub total_turns: u32
+}
+
+impl ChargeProgress {
+    /// How full the charging bar is, from 0 to 1.
+    pub fn get_fraction(&self) -> f32 {
+        return self.turn as f32 / self.total_turns as f32;
+    }
+}
+
+/* The multi-turn abilities battlers are partway through, built from events as they are played. Drives the charging
+bars, and which battlers' command menus only offer Continue. */
+pub struct ChargeTracker {
+    progress: HashMap<BattlerId, ChargeProgress>
+}
+
+impl ChargeTracker {
+    pub fn new() -> ChargeTracker {
+        return ChargeTracker { progress: HashMap::new() };
+    }
+
+    /// Update from an event once it is played. A battler stays tracked until its last turn, cancellation or fainting.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId, forced_action::ForcedActionKind};
+    /// use immie2d_client::animation::charge_tracker::ChargeTracker;
+    ///
+    /// let battler = BattlerId::new(0, 0);
+    /// let mut tracker = ChargeTracker::new();
+    /// tracker.apply_event(&BattleEvent::MultiTurnProgress { battler, kind: ForcedActionKind::Charging, turn: 1, total_turns: 2 });
+    /// assert!(tracker.is_forced(battler));
+    /// assert_eq!(tracker.get_progress(battler).unwrap().get_fraction(), 0.5);
+    ///
+    /// // Hitting, then having to recharge
+    /// tracker.apply_event(&BattleEvent::MultiTurnProgress { battler, kind: ForcedActionKind::Charging, turn: 2, total_turns: 2 });
+    /// assert!(!tracker.is_forced(battler));
+    /// tracker.apply_event(&BattleEvent::MultiTurnProgress { battler, kind: ForcedActionKind::Recharging, turn: 0, total_turns: 1 });
+    /// assert_eq!(tracker.get_progress(battler).unwrap().kind, ForcedActionKind::Recharging);
+    /// tracker.apply_event(&BattleEvent::Fainted { battler });
+    /// assert!(!tracker.is_forced(battler));
+    /// ```
+    pub fn apply_event(&mut self, event: &BattleEvent) {
+        match *event {
+            BattleEvent::MultiTurnProgress { battler, kind, turn, total_turns } => {
+                if turn < total_turns {
+                    self.progress.insert(battler, ChargeProgress { kind, turn, total_turns });
+                } else {
+                    self.progress.remove(&battler);
+                }
+            },
+            BattleEvent::MultiTurnCancelled { battler } | BattleEvent::Fainted { battler } => {
+                self.progress.remove(&battler);
+            },
+            BattleEvent::BattleEnded { .. } => self.progress.clear(),
+            _ => ()
+        }
+    }
+
+    pub fn get_progress(&self, battler: BattlerId) -> Option<ChargeProgress> {
+        return self.progress.get(&battler).copied();
+    }
+
+    /// Whether the battler must Continue next turn, so its command menu should be disabled.
+    pub fn is_forced(&self, battler: BattlerId) -> bool {
+        return self.progress.contains_key(&battler);
+    }
+}
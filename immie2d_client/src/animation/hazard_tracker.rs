@@ -0,0 +1,53 @@
+use std::collections::HashMap;
+
+use immie2d_shared::gameplay::battle::battle_event::BattleEvent;
+use immie2d_shared::gameplay::battle::entry_hazard::HazardKind;
+
+/* The hazards on each side of the field, built from events as they are played. Drives the spikes and webs drawn on
+the field, which stay until the side is cleared or the battle ends. */
+pub struct HazardTracker {
+    /// Layers of each hazard by side and kind. Kinds a side has no layers of aren't tracked.
+    layers: HashMap<(usize, HazardKind), u32>
+}
+
+impl HazardTracker {
+    pub fn new() -> HazardTracker {
+        return HazardTracker { layers: HashMap::new() };
+    }
+
+    /// Update from an event once it is played.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, entry_hazard::HazardKind};
+    /// use immie2d_client::animation::hazard_tracker::HazardTracker;
+    ///
+    /// let mut tracker = HazardTracker::new();
+    /// tracker.apply_event(&BattleEvent::HazardSet { side: 1, kind: HazardKind::Spikes, layers: 1 });
+    /// tracker.apply_event(&BattleEvent::HazardSet { side: 1, kind: HazardKind::Spikes, layers: 2 });
+    /// tracker.apply_event(&BattleEvent::HazardSet { side: 1, kind: HazardKind::Webs, layers: 1 });
+    /// assert_eq!(tracker.get_layers(1, HazardKind::Spikes), 2);
+    /// assert_eq!(tracker.get_hazards(1), vec![(HazardKind::Spikes, 2), (HazardKind::Webs, 1)]);
+    /// assert!(tracker.get_hazards(0).is_empty());
+    ///
+    /// tracker.apply_event(&BattleEvent::HazardsCleared { side: 1 });
+    /// assert_eq!(tracker.get_layers(1, HazardKind::Spikes), 0);
+    /// ```
+    pub fn apply_event(&mut self, event: &BattleEvent) {
+        match *event {
+            BattleEvent::HazardSet { side, kind, layers } => {
+                self.layers.insert((side, kind), layers);
+            },
+            BattleEvent::HazardsCleared { side } => self.layers.retain(|(hazard_side, _), _| *hazard_side != side),
+            BattleEvent::BattleEnded { .. } => self.layers.clear(),
+            _ => ()
+        }
+    }
+
+    pub fn get_layers(&self, side: usize, kind: HazardKind) -> u32 {
+        return self.layers.get(&(side, kind)).copied().unwrap_or(0);
+    }
+
+    /// Every hazard on a side with its layers, in the order they affect a battler switching in.
+    pub fn get_hazards(&self, side: usize) -> Vec<(HazardKind, u32)> {
+        return HazardKind::ALL.into_iter().map(|kind| (kind, self.get_layers(side, kind))).filter(|(_, layers)| *layers > 0).collect();
+    }
+}
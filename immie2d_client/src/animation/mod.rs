@@ -0,0 +1,6 @@
+pub mod animation_clock;
+pub mod animation;
+pub mod battle_view_model;
+pub mod timeline_player;
+pub mod charge_tracker;
+pub mod hazard_tracker;
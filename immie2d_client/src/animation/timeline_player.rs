@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use immie2d_shared::gameplay::battle::event_timeline::{get_event_duration, EventTimeline, TimelineAck};
+use immie2d_shared::gameplay::synced_settings::BattlePace;
+
+use super::animation::Animation;
+use super::battle_view_model::{AnimationHandle, BattleViewModel};
+
+/* Schedules the events of server timelines onto a battle view model at the server times they were tagged with.
+A client that falls behind, such as after a lag spike, plays late events shortened to finish when the server
+scheduled them to, so it catches back up with the other viewers. The battle pace only shortens animations locally,
+and timelines are still acknowledged once the server clock passes their end, so every viewer moves on together.
+Times are microseconds on the server clock. See ClockSync::to_server_time() */
+pub struct TimelinePlayer {
+    pending: VecDeque<EventTimeline>,
+    /// Index of the next event to start in the front pending timeline.
+    next_event: usize,
+    /// Handle of the last event started from the front pending timeline.
+    last_handle: Option<AnimationHandle>,
+    /// Timelines whose events have all started, with the server time they finish and the handle of their last
+    /// animation, waiting to be acknowledged.
+    unacknowledged: VecDeque<(u32, u64, Option<AnimationHandle>)>,
+    pace: BattlePace,
+    is_skipping: bool
+}
+
+impl TimelinePlayer {
+    pub fn new() -> TimelinePlayer {
+        return TimelinePlayer {
+            pending: VecDeque::new(),
+            next_event: 0,
+            last_handle: None,
+            unacknowledged: VecDeque::new(),
+            pace: BattlePace::Full,
+            is_skipping: false
+        };
+    }
+
+    pub fn with_pace(mut self, pace: BattlePace) -> TimelinePlayer {
+        self.pace = pace;
+        return self;
+    }
+
+    /// Change the pace of events started from now on, such as when the settings change mid battle.
+    pub fn set_pace(&mut self, pace: BattlePace) {
+        self.pace = pace;
+    }
+
+    pub fn get_pace(&self) -> BattlePace {
+        return self.pace;
+    }
+
+    /// Toggle skipping every animation. Turning it on fast-forwards every animation queued on the view model, and
+    /// while it is on every received event plays straight away with no animation.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    /// use immie2d_shared::gameplay::battle::event_timeline::{EventTimeline, TimelineAck};
+    /// use immie2d_client::animation::{battle_view_model::BattleViewModel, timeline_player::TimelinePlayer};
+    ///
+    /// let battler = BattlerId::new(1, 0);
+    /// let mut player = TimelinePlayer::new();
+    /// let mut view = BattleViewModel::new();
+    /// player.receive(EventTimeline::from_events(0, 1_000_000, vec![
+    ///     BattleEvent::Damaged { battler, amount: 50, remaining_health: 0 },
+    ///     BattleEvent::Fainted { battler }
+    /// ]));
+    /// player.update(&mut view, 1_000_000);
+    /// assert!(!view.is_idle());
+    ///
+    /// player.set_skip_all(&mut view, true);
+    /// assert!(view.is_idle());
+    /// // The faint isn't due yet, but plays straight away
+    /// player.update(&mut view, 1_000_000);
+    /// assert!(view.is_idle());
+    /// // Still acknowledged when the server timeline ends, not before
+    /// assert!(player.update(&mut view, 1_100_000).is_empty());
+    /// assert_eq!(player.update(&mut view, 10_000_000), vec![TimelineAck { batch: 0 }]);
+    /// ```
+    pub fn set_skip_all(&mut self, view: &mut BattleViewModel, skip: bool) {
+        self.is_skipping = skip;
+        if skip {
+            view.skip_all();
+        }
+    }
+
+    pub fn is_skipping(&self) -> bool {
+        return self.is_skipping;
+    }
+
+    /// Queue a timeline received from the server. Timelines are played in the order they are received.
+    pub fn receive(&mut self, timeline: EventTimeline) {
+        self.pending.push_back(timeline);
+    }
+
+    /// Whether every received timeline has been played and acknowledged.
+    pub fn is_idle(&self) -> bool {
+        return self.pending.is_empty() && self.unacknowledged.is_empty();
+    }
+
+    /// Start every event whose time has come on the view model, and acknowledge timelines that have finished
+    /// both on the server clock and on the view model. Events play for their length scaled by the pace, or less if
+    /// they are late. Returns the acknowledgements to send to the server.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    /// use immie2d_shared::gameplay::battle::event_timeline::{EventTimeline, TimelineAck};
+    /// use immie2d_shared::gameplay::synced_settings::BattlePace;
+    /// use immie2d_client::animation::{battle_view_model::BattleViewModel, timeline_player::TimelinePlayer};
+    ///
+    /// let battler = BattlerId::new(1, 0);
+    /// let mut player = TimelinePlayer::new();
+    /// let mut view = BattleViewModel::new();
+    /// player.receive(EventTimeline::from_events(0, 1_000_000, vec![
+    ///     BattleEvent::Damaged { battler, amount: 50, remaining_health: 0 },
+    ///     BattleEvent::Fainted { battler }
+    /// ]));
+    ///
+    /// // Nothing plays before the timeline starts
+    /// assert!(player.update(&mut view, 900_000).is_empty());
+    /// assert!(view.is_idle());
+    /// player.update(&mut view, 1_000_000);
+    /// assert_eq!(view.get_current().unwrap().get_duration(), Duration::from_millis(500));
+    ///
+    /// // Arriving late, the faint is shortened to end on time
+    /// view.update(Duration::from_millis(500));
+    /// player.update(&mut view, 1_600_000);
+    /// assert_eq!(view.get_current().unwrap().get_duration(), Duration::from_millis(600));
+    /// view.update(Duration::from_millis(600));
+    /// assert_eq!(player.update(&mut view, 2_200_000), vec![TimelineAck { batch: 0 }]);
+    /// assert!(player.is_idle());
+    ///
+    /// // At the fast pace the damage plays for half as long
+    /// let mut player = TimelinePlayer::new().with_pace(BattlePace::Fast);
+    /// player.receive(EventTimeline::from_events(1, 3_000_000, vec![BattleEvent::Damaged { battler, amount: 5, remaining_health: 0 }]));
+    /// player.update(&mut view, 3_000_000);
+    /// assert_eq!(view.get_current().unwrap().get_duration(), Duration::from_millis(250));
+    /// ```
+    pub fn update(&mut self, view: &mut BattleViewModel, server_time: u64) -> Vec<TimelineAck> {
+        while let Some(timeline) = self.pending.front() {
+            while self.next_event < timeline.events.len() && (self.is_skipping || timeline.get_event_time(self.next_event) <= server_time) {
+                let event = timeline.events[self.next_event].event;
+                let full_duration = get_event_duration(&event);
+                let scheduled_end = timeline.get_event_time(self.next_event) + full_duration.as_micros() as u64;
+                let duration = match self.is_skipping {
+                    true => Duration::ZERO,
+                    false => full_duration.mul_f32(self.pace.get_animation_scale()).min(Duration::from_micros(scheduled_end.saturating_sub(server_time)))
+                };
+                self.last_handle = Some(view.play(Animation::new(event, duration)));
+                self.next_event += 1;
+            }
+            if self.next_event < timeline.events.len() {
+                break;
+            }
+            self.unacknowledged.push_back((timeline.batch, timeline.get_end_time(), self.last_handle.take()));
+            self.pending.pop_front();
+            self.next_event = 0;
+        }
+        if self.is_skipping {
+            view.skip_all();
+        }
+        let mut acks = Vec::new();
+        while let Some((batch, end_time, last_handle)) = self.unacknowledged.front().copied() {
+            if end_time > server_time || last_handle.is_some_and(|handle| !view.is_complete(handle)) {
+                break;
+            }
+            acks.push(TimelineAck { batch });
+            self.unacknowledged.pop_front();
+        }
+        return acks;
+    }
+}
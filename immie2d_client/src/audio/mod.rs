@@ -0,0 +1 @@
+pub mod music_mixer;
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::world::audio_cue::{AudioCue, AudioCueHint, AUDIO_CUE_LAYER_COUNT};
+
+/// How long the old track fades out while the new one fades in.
+pub const CROSS_FADE_DURATION: Duration = Duration::from_millis(1500);
+
+/* The two tracks that play at once. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AudioChannel {
+    Music,
+    Ambience
+}
+
+/* The track a channel is fading in, and the one it is fading out. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+struct ChannelState {
+    current: Option<GlobalString>,
+    fading_out: Option<GlobalString>,
+    fade_elapsed: Duration
+}
+
+/* Decides what music and ambience to play from the cue hints sent by the server, and cross-fades between tracks.
+Each channel plays the track of the highest layer that sets one, so a battle overrides a scripted event, which
+overrides the region. A track that keeps playing across a change isn't restarted. */
+pub struct MusicMixer {
+    layers: [Option<AudioCue>; AUDIO_CUE_LAYER_COUNT],
+    music: ChannelState,
+    ambience: ChannelState
+}
+
+impl MusicMixer {
+    pub fn new() -> MusicMixer {
+        return MusicMixer { layers: [None; AUDIO_CUE_LAYER_COUNT], music: ChannelState::default(), ambience: ChannelState::default() };
+    }
+
+    /// Set or clear the cue of a layer, starting a cross-fade on each channel whose track changes. A fade already in
+    /// progress is cut short, dropping the track that was fading out.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::audio_cue::{AudioCue, AudioCueHint, AudioCueLayer};
+    /// use immie2d_client::audio::music_mixer::{AudioChannel, MusicMixer, CROSS_FADE_DURATION};
+    ///
+    /// let track = |name: &str| Some(GlobalString::new(&name.to_string()));
+    /// let mut mixer = MusicMixer::new();
+    /// mixer.apply_hint(AudioCueHint { layer: AudioCueLayer::Region, cue: Some(AudioCue { music: track("route_theme"), ambience: track("birds") }) });
+    /// mixer.update(CROSS_FADE_DURATION);
+    /// assert_eq!(mixer.get_volumes(AudioChannel::Music), vec![(track("route_theme").unwrap(), 1.0)]);
+    ///
+    /// // A battle takes over the music, while the region's ambience keeps playing
+    /// mixer.apply_hint(AudioCueHint { layer: AudioCueLayer::Battle, cue: Some(AudioCue { music: track("wild_battle"), ambience: None }) });
+    /// mixer.update(CROSS_FADE_DURATION / 2);
+    /// assert_eq!(mixer.get_volumes(AudioChannel::Music), vec![(track("wild_battle").unwrap(), 0.5), (track("route_theme").unwrap(), 0.5)]);
+    /// assert_eq!(mixer.get_volumes(AudioChannel::Ambience), vec![(track("birds").unwrap(), 1.0)]);
+    ///
+    /// // Scripted events don't override battles
+    /// mixer.apply_hint(AudioCueHint { layer: AudioCueLayer::ScriptedEvent, cue: Some(AudioCue { music: track("rival_theme"), ambience: None }) });
+    /// assert_eq!(mixer.get_track(AudioChannel::Music), track("wild_battle"));
+    /// mixer.apply_hint(AudioCueHint { layer: AudioCueLayer::Battle, cue: None });
+    /// assert_eq!(mixer.get_track(AudioChannel::Music), track("rival_theme"));
+    /// ```
+    pub fn apply_hint(&mut self, hint: AudioCueHint) {
+        self.layers[hint.layer as usize] = hint.cue;
+        let music = self.get_target(|cue| cue.music);
+        let ambience = self.get_target(|cue| cue.ambience);
+        fade_to(&mut self.music, music);
+        fade_to(&mut self.ambience, ambience);
+    }
+
+    fn get_target(&self, get_track: impl Fn(&AudioCue) -> Option<GlobalString>) -> Option<GlobalString> {
+        return self.layers.iter().rev().flatten().find_map(get_track);
+    }
+
+    /// Advance the cross-fades.
+    pub fn update(&mut self, elapsed: Duration) {
+        for channel in [&mut self.music, &mut self.ambience] {
+            channel.fade_elapsed = (channel.fade_elapsed + elapsed).min(CROSS_FADE_DURATION);
+            if channel.fade_elapsed == CROSS_FADE_DURATION {
+                channel.fading_out = None;
+            }
+        }
+    }
+
+    /// The track a channel is playing or fading in, if any.
+    pub fn get_track(&self, channel: AudioChannel) -> Option<GlobalString> {
+        return self.get_channel(channel).current;
+    }
+
+    /// Every track that should be audible on a channel with its volume from 0 to 1, the track fading in first.
+    pub fn get_volumes(&self, channel: AudioChannel) -> Vec<(GlobalString, f32)> {
+        let state = self.get_channel(channel);
+        let progress = state.fade_elapsed.as_secs_f32() / CROSS_FADE_DURATION.as_secs_f32();
+        let mut volumes = Vec::new();
+        if let Some(track) = state.current {
+            volumes.push((track, progress));
+        }
+        if let Some(track) = state.fading_out {
+            volumes.push((track, 1.0 - progress));
+        }
+        return volumes;
+    }
+
+    fn get_channel(&self, channel: AudioChannel) -> &ChannelState {
+        return match channel {
+            AudioChannel::Music => &self.music,
+            AudioChannel::Ambience => &self.ambience
+        };
+    }
+}
+
+fn fade_to(channel: &mut ChannelState, track: Option<GlobalString>) {
+    if channel.current == track {
+        return;
+    }
+    channel.fading_out = channel.current;
+    channel.current = track;
+    channel.fade_elapsed = Duration::ZERO;
+}
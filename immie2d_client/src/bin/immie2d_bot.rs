@@ -0,0 +1,126 @@
+#![allow(clippy::needless_return)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::time::{Duration, Instant};
+use std::{env, process, thread};
+
+use immie2d_client::bot::{bot_client::BotClient, load_stats::LoadStats};
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames, abilities::fireball::Fireball};
+use immie2d_shared::gameplay::battle::{battle_side::BattleSide, battler::Battler};
+use immie2d_shared::gameplay::elements::{element_kinds::ElementKind, elements_data::Elements};
+use immie2d_shared::gameplay::game_data::GameData;
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::item::item_map::ItemMap;
+use immie2d_shared::gameplay::species::{base_stats::BaseStats, species_data::SpeciesData, species_map::SpeciesMap};
+
+const BOT_USAGE: &str = "Usage: immie2d_bot [--server <address>] [--clients <count>] [--messages <count per client>] [--seed <seed>]";
+
+/// How long a bot waits for the server to answer a message before counting it as an error.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+struct BotOptions {
+    server: String,
+    clients: u64,
+    messages: u64,
+    seed: u64
+}
+
+fn parse_args(args: &[String]) -> Result<BotOptions, String> {
+    let mut options = BotOptions { server: "127.0.0.1:7878".to_string(), clients: 10, messages: 100, seed: 0 };
+    let mut rest = args.iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("Missing value for {}", flag))?;
+        let parse_number = || value.parse::<u64>().map_err(|_| format!("Invalid number [{}] for {}", value, flag));
+        match flag.as_str() {
+            "--server" => options.server = value.clone(),
+            "--clients" => options.clients = parse_number()?,
+            "--messages" => options.messages = parse_number()?,
+            "--seed" => options.seed = parse_number()?,
+            _ => return Err(format!("Unknown option [{}]", flag))
+        }
+    }
+    return Ok(options);
+}
+
+/// Game data and team the bots battle with locally, independent of the server's data files.
+fn create_bot_data() -> (GameData, BattleSide) {
+    let species = SpeciesData::new(GlobalString::new(&"botpup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    let mut species_map = SpeciesMap::new();
+    species_map.add_species(species);
+    let mut ability_map = AbilityMap::new();
+    ability_map.add_ability::<Fireball>();
+    let immie = Immie::new(species.name, 10, AbilityNames::new(vec![GlobalString::new(&"fireball".to_string())]));
+    let team = BattleSide::new(vec![Battler::new(immie, &species), Battler::new(immie, &species)]);
+    return (GameData::new(0, species_map, ability_map, ItemMap::new()), team);
+}
+
+/// Connect a single bot and send its messages one at a time, timing how long each takes to be answered. Stops at
+/// the first error, since the connection can't be trusted after it.
+fn run_bot(server: &str, messages: u64, seed: u64) -> LoadStats {
+    let mut stats = LoadStats::new();
+    let stream = match TcpStream::connect(server) {
+        Ok(stream) => stream,
+        Err(_) => {
+            stats.record_error();
+            return stats;
+        }
+    };
+    if stream.set_read_timeout(Some(RESPONSE_TIMEOUT)).is_err() {
+        stats.record_error();
+        return stats;
+    }
+    let (data, team) = create_bot_data();
+    let mut bot = BotClient::new(seed);
+    let mut writer = &stream;
+    let mut reader = BufReader::new(&stream);
+    let mut response = Vec::new();
+    for _ in 0..messages {
+        let line = bot.next_message(&data, &team).to_line();
+        let sent_at = Instant::now();
+        stats.sent += 1;
+        if writer.write_all(line.as_bytes()).is_err() {
+            stats.record_error();
+            break;
+        }
+        response.clear();
+        match reader.read_until(b'\n', &mut response) {
+            Ok(read) if read > 0 => stats.record_latency(sent_at.elapsed()),
+            _ => {
+                stats.record_error();
+                break;
+            }
+        }
+    }
+    return stats;
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{}\n{}", err, BOT_USAGE);
+            process::exit(2);
+        }
+    };
+    println!("Running {} bots against {}, {} messages each", options.clients, options.server, options.messages);
+    let started_at = Instant::now();
+    let handles: Vec<_> = (0..options.clients).map(|client| {
+        let server = options.server.clone();
+        let (messages, seed) = (options.messages, options.seed.wrapping_add(client));
+        return thread::spawn(move || run_bot(&server, messages, seed));
+    }).collect();
+    let mut total = LoadStats::new();
+    for handle in handles {
+        match handle.join() {
+            Ok(stats) => total.merge(&stats),
+            Err(_) => total.record_error()
+        }
+    }
+    println!("Finished in {:.2}s\n{}", started_at.elapsed().as_secs_f64(), total.to_report_string());
+    if total.errors > 0 {
+        process::exit(1);
+    }
+}
@@ -0,0 +1,44 @@
+#![allow(clippy::needless_return)]
+
+use std::path::Path;
+use std::{env, process};
+
+use immie2d_shared::localization::catalog_validation::validate_catalog;
+use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+
+const LOCALE_CHECK_USAGE: &str = "Usage: immie2d_locale_check <reference catalog> <translated catalog>...";
+
+/// Load a catalog, named after its file such as `fr.json` for French.
+fn load_catalog(path: &str) -> Result<LocalizationCatalog, String> {
+    let path = Path::new(path);
+    let language = path.file_stem().and_then(|stem| stem.to_str()).ok_or(format!("Invalid catalog path {}", path.display()))?;
+    return LocalizationCatalog::load(language, path).map_err(|err| format!("{}: {}", path.display(), err));
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if args.is_empty() {
+        eprintln!("{}", LOCALE_CHECK_USAGE);
+        process::exit(2);
+    }
+    let mut issue_count = 0;
+    let mut reference = None;
+    for path in args.iter() {
+        let catalog = match load_catalog(path) {
+            Ok(catalog) => catalog,
+            Err(err) => {
+                eprintln!("{}", err);
+                process::exit(1);
+            }
+        };
+        let reference = reference.get_or_insert_with(|| catalog.clone());
+        for issue in validate_catalog(reference, &catalog) {
+            println!("{}: {}", path, issue);
+            issue_count += 1;
+        }
+    }
+    if issue_count > 0 {
+        eprintln!("{} issue(s) found", issue_count);
+        process::exit(1);
+    }
+}
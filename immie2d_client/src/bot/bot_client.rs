@@ -0,0 +1,167 @@
+use immie2d_shared::engine_types::game_rng::GameRng;
+use immie2d_shared::gameplay::battle::{battle::Battle, battle_command::BattleCommand, battle_format::BattleFormat, battle_side::BattleSide};
+use immie2d_shared::gameplay::battle::battle_evaluation::choose_heuristic_command;
+use immie2d_shared::gameplay::battle::state_hash::TurnHash;
+use immie2d_shared::gameplay::game_data::GameData;
+use immie2d_shared::world::tile_position::{Direction, TilePosition};
+
+/// Most steps a bot walks between battles.
+pub const MAX_WALK_STEPS: u32 = 20;
+
+/// Turns after which a bot gives up on a battle, in case both sides run out of ability uses.
+pub const MAX_BATTLE_TURNS: u32 = 100;
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+fn to_hex(bytes: &[u8]) -> String {
+    return bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+/* A message a bot sends to the server. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BotMessage {
+    Walk(Direction),
+    QueueBattle,
+    Battle(BattleCommand),
+    /// The hash of the bot's copy of the battle once a turn resolved, for the server to check for a desync.
+    TurnHash(TurnHash)
+}
+
+impl BotMessage {
+    /// Encode as a single newline terminated line of text.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::battle_command::BattleCommand;
+    /// use immie2d_shared::gameplay::battle::state_hash::TurnHash;
+    /// use immie2d_shared::world::tile_position::Direction;
+    /// use immie2d_client::bot::bot_client::BotMessage;
+    ///
+    /// assert_eq!(BotMessage::Walk(Direction::Left).to_line(), "walk left\n");
+    /// assert_eq!(BotMessage::Battle(BattleCommand::Switch { side: 0, slot: 1 }).to_line(), "battle 010001\n");
+    /// assert_eq!(BotMessage::TurnHash(TurnHash { turn: 2, hash: [0xab; 32] }).to_line(), format!("turn_hash 02000000{}\n", "ab".repeat(32)));
+    /// ```
+    pub fn to_line(&self) -> String {
+        return match self {
+            BotMessage::Walk(direction) => format!("walk {}\n", format!("{:?}", direction).to_lowercase()),
+            BotMessage::QueueBattle => "queue quick\n".to_string(),
+            BotMessage::Battle(command) => format!("battle {}\n", to_hex(&command.encode())),
+            BotMessage::TurnHash(hash) => format!("turn_hash {}\n", to_hex(&hash.to_bytes()))
+        };
+    }
+}
+
+enum BotPhase {
+    Walking(u32),
+    Queued,
+    Battling(Battle)
+}
+
+/* A simulated player for load testing. Walks a random path, queues for a battle, then plays it with the heuristic AI
+against a local copy of the battle, and repeats. After each turn it sends the hash of its copy, like any client
+mirroring a battle, so the server checks it for desyncs. */
+pub struct BotClient {
+    rng: GameRng,
+    position: TilePosition,
+    phase: BotPhase,
+    battles_finished: u32,
+    /// Hash of the last resolved turn, sent before the next message.
+    pending_hash: Option<TurnHash>
+}
+
+impl BotClient {
+    pub fn new(seed: u64) -> BotClient {
+        let mut rng = GameRng::new(seed);
+        let steps = 1 + rng.next_below(MAX_WALK_STEPS);
+        return BotClient { rng, position: TilePosition::new(0, 0), phase: BotPhase::Walking(steps), battles_finished: 0, pending_hash: None };
+    }
+
+    pub fn get_position(&self) -> TilePosition {
+        return self.position;
+    }
+
+    pub fn get_battles_finished(&self) -> u32 {
+        return self.battles_finished;
+    }
+
+    pub fn is_battling(&self) -> bool {
+        return matches!(self.phase, BotPhase::Battling(_));
+    }
+
+    /// Advance the bot, returning the next message to send. Battles are between two copies of the team, and each
+    /// battle command is followed by the hash of the turn it resolved.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames, abilities::fireball::Fireball};
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::item::item_map::ItemMap;
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::battle::{battle_side::BattleSide, battler::Battler};
+    /// use immie2d_client::bot::bot_client::{BotClient, BotMessage};
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Fireball>();
+    /// let data = GameData::new(1, species_map, ability_map, ItemMap::new());
+    /// let team = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::new(vec![GlobalString::new(&"fireball".to_string())])), &species)]);
+    ///
+    /// let mut bot = BotClient::new(7);
+    /// let mut message = bot.next_message(&data, &team);
+    /// assert!(matches!(message, BotMessage::Walk(_)));
+    /// while message != BotMessage::QueueBattle {
+    ///     message = bot.next_message(&data, &team);
+    /// }
+    /// assert!(matches!(bot.next_message(&data, &team), BotMessage::Battle(_)));
+    /// assert!(matches!(bot.next_message(&data, &team), BotMessage::TurnHash(hash) if hash.turn == 2));
+    /// while bot.get_battles_finished() == 0 {
+    ///     bot.next_message(&data, &team);
+    /// }
+    /// assert!(!bot.is_battling());
+    /// ```
+    pub fn next_message(&mut self, data: &GameData, team: &BattleSide) -> BotMessage {
+        if let Some(hash) = self.pending_hash.take() {
+            return BotMessage::TurnHash(hash);
+        }
+        match &mut self.phase {
+            BotPhase::Walking(steps) => {
+                let direction = DIRECTIONS[self.rng.next_below(DIRECTIONS.len() as u32) as usize];
+                self.position = self.position.offset(direction);
+                *steps -= 1;
+                if *steps == 0 {
+                    self.phase = BotPhase::Queued;
+                }
+                return BotMessage::Walk(direction);
+            },
+            BotPhase::Queued => {
+                // The server sends no battle state yet, so the bot plays a local battle as soon as it has queued.
+                let battle = Battle::new(BattleFormat::Single, vec![team.clone(), team.clone()]).with_seed(self.rng.next_u64());
+                self.phase = BotPhase::Battling(battle);
+                return BotMessage::QueueBattle;
+            },
+            BotPhase::Battling(battle) => {
+                let ability_map = data.get_ability_map();
+                let mut own_command = BattleCommand::EndTurn;
+                for side in battle.get_turn_order() {
+                    let command = choose_heuristic_command(battle, side, ability_map);
+                    if side == 0 {
+                        own_command = command;
+                    }
+                    if !battle.is_finished() && command != BattleCommand::EndTurn {
+                        let _ = battle.apply_command(command, ability_map, data.get_species_map());
+                    }
+                }
+                if !battle.is_finished() {
+                    let _ = battle.apply_command(BattleCommand::EndTurn, ability_map, data.get_species_map());
+                }
+                self.pending_hash = Some(TurnHash { turn: battle.get_turn(), hash: battle.get_state_hash() });
+                if battle.is_finished() || battle.get_turn() >= MAX_BATTLE_TURNS {
+                    self.battles_finished += 1;
+                    self.phase = BotPhase::Walking(1 + self.rng.next_below(MAX_WALK_STEPS));
+                }
+                return BotMessage::Battle(own_command);
+            }
+        }
+    }
+}
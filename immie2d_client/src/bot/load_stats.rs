@@ -0,0 +1,95 @@
+use std::time::Duration;
+
+/* Latency and error counts of the messages sent by load testing bots. Each bot keeps its own stats, which are merged
+for the report. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct LoadStats {
+    pub sent: u64,
+    pub errors: u64,
+    /// Round trip of every answered message, in microseconds.
+    latencies: Vec<u64>
+}
+
+impl LoadStats {
+    pub fn new() -> LoadStats {
+        return LoadStats { sent: 0, errors: 0, latencies: Vec::new() };
+    }
+
+    /// Record a message that was answered after some time.
+    pub fn record_latency(&mut self, latency: Duration) {
+        self.latencies.push(latency.as_micros() as u64);
+    }
+
+    /// Record a failed connection, send or receive.
+    pub fn record_error(&mut self) {
+        self.errors += 1;
+    }
+
+    pub fn merge(&mut self, other: &LoadStats) {
+        self.sent += other.sent;
+        self.errors += other.errors;
+        self.latencies.extend_from_slice(&other.latencies);
+    }
+
+    pub fn get_received(&self) -> u64 {
+        return self.latencies.len() as u64;
+    }
+
+    pub fn get_mean_latency(&self) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        return Some(Duration::from_micros(self.latencies.iter().sum::<u64>() / self.latencies.len() as u64));
+    }
+
+    /// Latency that a percentage of answered messages were at or below, using the nearest rank. None if no message
+    /// was answered. Will panic if the percentile is not between 0 and 100.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_client::bot::load_stats::LoadStats;
+    ///
+    /// let mut stats = LoadStats::new();
+    /// assert_eq!(stats.get_percentile(50.0), None);
+    /// for millis in 1..=100 {
+    ///     stats.record_latency(Duration::from_millis(millis));
+    /// }
+    /// assert_eq!(stats.get_percentile(50.0), Some(Duration::from_millis(50)));
+    /// assert_eq!(stats.get_percentile(99.0), Some(Duration::from_millis(99)));
+    /// assert_eq!(stats.get_percentile(100.0), Some(Duration::from_millis(100)));
+    /// ```
+    pub fn get_percentile(&self, percentile: f64) -> Option<Duration> {
+        assert!((0.0..=100.0).contains(&percentile), "Percentile {} is not between 0 and 100", percentile);
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let mut sorted = self.latencies.clone();
+        sorted.sort_unstable();
+        let rank = ((percentile / 100.0 * sorted.len() as f64).ceil() as usize).max(1);
+        return Some(Duration::from_micros(sorted[rank - 1]));
+    }
+
+    /// Summary for printing at the end of a load test.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_client::bot::load_stats::LoadStats;
+    ///
+    /// let mut stats = LoadStats::new();
+    /// stats.sent = 3;
+    /// stats.record_latency(Duration::from_millis(2));
+    /// stats.record_latency(Duration::from_millis(4));
+    /// stats.record_error();
+    /// let mut total = LoadStats::new();
+    /// total.merge(&stats);
+    /// assert_eq!(total.to_report_string(), "sent 3, received 2, errors 1\nlatency mean 3.000ms, p50 2.000ms, p95 4.000ms, p99 4.000ms, max 4.000ms");
+    /// assert_eq!(LoadStats::new().to_report_string(), "sent 0, received 0, errors 0\nno messages were answered");
+    /// ```
+    pub fn to_report_string(&self) -> String {
+        let counts = format!("sent {}, received {}, errors {}", self.sent, self.get_received(), self.errors);
+        let Some(mean) = self.get_mean_latency() else {
+            return format!("{}\nno messages were answered", counts);
+        };
+        let millis = |latency: Option<Duration>| format!("{:.3}ms", latency.unwrap().as_secs_f64() * 1000.0);
+        return format!("{}\nlatency mean {}, p50 {}, p95 {}, p99 {}, max {}", counts, millis(Some(mean)), millis(self.get_percentile(50.0)),
+            millis(self.get_percentile(95.0)), millis(self.get_percentile(99.0)), millis(self.get_percentile(100.0)));
+    }
+}
@@ -0,0 +1,2 @@
+pub mod bot_client;
+pub mod load_stats;
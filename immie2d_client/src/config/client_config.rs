@@ -0,0 +1,161 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use immie2d_shared::gameplay::synced_settings::{BattlePace, SyncedSettings, TextSpeed};
+
+use crate::input::{input_action::{InputAction, ALL_INPUT_ACTIONS}, key::Key, key_bindings::KeyBindings};
+use crate::world::dead_reckoning::DeadReckoningConfig;
+use crate::world::walk_animator::DEFAULT_WALK_SPEED;
+
+/* User editable client settings, persisted as `name=value` lines. The synced settings are also stored in the player's
+profile and replaced by the server's copy on login unless they were changed since the revision the server has. See SyncedSettings::merge() */
+#[derive(Clone, PartialEq, Debug)]
+pub struct ClientConfig {
+    pub key_bindings: KeyBindings,
+    pub synced: SyncedSettings,
+    /// From 0 to 1.
+    pub master_volume: f32,
+    /// From 0 to 1.
+    pub music_volume: f32,
+    /// Tiles walked per second in the overworld.
+    pub walk_speed: f32,
+    /// Whether battles suggest a command to new players. See get_tutor_hint()
+    pub tutor_mode: bool,
+    /// Whether crash reports are uploaded as well as written locally. Off unless the player opts in. See CrashReporter
+    pub upload_crash_reports: bool,
+    /// How other players and NPCs are extrapolated between snapshots. See DeadReckoning
+    pub dead_reckoning: DeadReckoningConfig
+}
+
+impl ClientConfig {
+    pub fn default() -> ClientConfig {
+        return ClientConfig {
+            key_bindings: KeyBindings::default(),
+            synced: SyncedSettings::default(),
+            master_volume: 1.0,
+            music_volume: 0.7,
+            walk_speed: DEFAULT_WALK_SPEED,
+            tutor_mode: false,
+            upload_crash_reports: false,
+            dead_reckoning: DeadReckoningConfig::default()
+        };
+    }
+
+    /// Convert the config into the text stored on disk.
+    /// ```
+    /// use immie2d_client::config::client_config::ClientConfig;
+    /// let config = ClientConfig::default();
+    /// assert!(config.to_config_string().contains("master_volume=1"));
+    /// assert!(config.to_config_string().contains("bind.move_up=w"));
+    /// ```
+    pub fn to_config_string(&self) -> String {
+        let mut out = String::new();
+        out.push_str(&format!("master_volume={}\n", self.master_volume));
+        out.push_str(&format!("music_volume={}\n", self.music_volume));
+        out.push_str(&format!("walk_speed={}\n", self.walk_speed));
+        out.push_str(&format!("tutor_mode={}\n", self.tutor_mode));
+        out.push_str(&format!("upload_crash_reports={}\n", self.upload_crash_reports));
+        out.push_str(&format!("dead_reckoning.max_extrapolation_ms={}\n", self.dead_reckoning.max_extrapolation.as_millis()));
+        out.push_str(&format!("dead_reckoning.max_speed={}\n", self.dead_reckoning.max_speed));
+        out.push_str(&format!("dead_reckoning.correction_speed={}\n", self.dead_reckoning.correction_speed));
+        out.push_str(&format!("dead_reckoning.snap_distance={}\n", self.dead_reckoning.snap_distance));
+        out.push_str(&format!("language={}\n", self.synced.language));
+        out.push_str(&format!("text_speed={}\n", self.synced.text_speed.get_name()));
+        out.push_str(&format!("battle_pace={}\n", self.synced.battle_pace.get_name()));
+        out.push_str(&format!("settings_revision={}\n", self.synced.revision));
+        out.push_str(&format!("settings_modified={}\n", self.synced.is_modified));
+        for action in ALL_INPUT_ACTIONS {
+            out.push_str(&format!("bind.{}={}\n", action.get_name(), self.key_bindings.get_key(action)));
+        }
+        return out;
+    }
+
+    /// Parse config text. Unknown, malformed, or conflicting lines are ignored, keeping the default for that value.
+    /// Configs from before battle paces have `battle_animations` instead, which is read as the Full or Instant pace.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_client::config::client_config::ClientConfig;
+    /// use immie2d_client::input::{input_action::InputAction, key::Key};
+    ///
+    /// use immie2d_shared::gameplay::synced_settings::{BattlePace, TextSpeed};
+    ///
+    /// let config = ClientConfig::from_config_string("music_volume=0.25\nbind.confirm=space\ntext_speed=fast\nwalk_speed=6\ntutor_mode=true\nnonsense\n");
+    /// assert_eq!(config.music_volume, 0.25);
+    /// assert!(config.tutor_mode);
+    /// assert!(!config.upload_crash_reports);
+    /// assert_eq!(config.walk_speed, 6.0);
+    /// assert_eq!(config.synced.text_speed, TextSpeed::Fast);
+    /// assert_eq!(config.key_bindings.get_key(InputAction::Confirm), Key::Space);
+    /// assert_eq!(ClientConfig::from_config_string(&config.to_config_string()), config);
+    ///
+    /// assert_eq!(ClientConfig::from_config_string("battle_pace=fast\n").synced.battle_pace, BattlePace::Fast);
+    ///
+    /// let smoothed = ClientConfig::from_config_string("dead_reckoning.max_extrapolation_ms=400\ndead_reckoning.correction_speed=2.5\ndead_reckoning.snap_distance=-1\n");
+    /// assert_eq!(smoothed.dead_reckoning.max_extrapolation, Duration::from_millis(400));
+    /// assert_eq!(smoothed.dead_reckoning.correction_speed, 2.5);
+    /// assert_eq!(smoothed.dead_reckoning.snap_distance, ClientConfig::default().dead_reckoning.snap_distance);
+    /// assert_eq!(ClientConfig::from_config_string(&smoothed.to_config_string()), smoothed);
+    /// assert_eq!(ClientConfig::from_config_string("battle_animations=false\n").synced.battle_pace, BattlePace::Instant);
+    /// ```
+    pub fn from_config_string(text: &str) -> ClientConfig {
+        let mut config = ClientConfig::default();
+        for line in text.lines() {
+            let Some((name, value)) = line.split_once('=') else {
+                continue;
+            };
+            let (name, value) = (name.trim(), value.trim());
+            match name {
+                "master_volume" => if let Ok(volume) = value.parse::<f32>() { config.master_volume = volume.clamp(0.0, 1.0); },
+                "music_volume" => if let Ok(volume) = value.parse::<f32>() { config.music_volume = volume.clamp(0.0, 1.0); },
+                "walk_speed" => if let Some(speed) = parse_positive(value) { config.walk_speed = speed; },
+                "tutor_mode" => if let Ok(enabled) = value.parse::<bool>() { config.tutor_mode = enabled; },
+                "upload_crash_reports" => if let Ok(enabled) = value.parse::<bool>() { config.upload_crash_reports = enabled; },
+                "dead_reckoning.max_extrapolation_ms" => if let Ok(millis) = value.parse::<u64>() { config.dead_reckoning.max_extrapolation = Duration::from_millis(millis); },
+                "dead_reckoning.max_speed" => if let Some(speed) = parse_positive(value) { config.dead_reckoning.max_speed = speed; },
+                "dead_reckoning.correction_speed" => if let Some(speed) = parse_positive(value) { config.dead_reckoning.correction_speed = speed; },
+                "dead_reckoning.snap_distance" => if let Some(distance) = parse_positive(value) { config.dead_reckoning.snap_distance = distance; },
+                "language" => if SyncedSettings::is_valid_language(value) { config.synced.language = value.to_string(); },
+                "text_speed" => if let Some(speed) = TextSpeed::from_name(value) { config.synced.text_speed = speed; },
+                "battle_pace" => if let Some(pace) = BattlePace::from_name(value) { config.synced.battle_pace = pace; },
+                "battle_animations" => if let Ok(enabled) = value.parse::<bool>() {
+                    config.synced.battle_pace = if enabled { BattlePace::Full } else { BattlePace::Instant };
+                },
+                "settings_revision" => if let Ok(revision) = value.parse::<u64>() { config.synced.revision = revision; },
+                "settings_modified" => if let Ok(is_modified) = value.parse::<bool>() { config.synced.is_modified = is_modified; },
+                _ => {
+                    let action = name.strip_prefix("bind.").and_then(InputAction::from_name);
+                    let key = Key::from_name(value);
+                    if let (Some(action), Some(key)) = (action, key) {
+                        config.key_bindings.rebind(action, key);
+                    }
+                }
+            }
+        }
+        return config;
+    }
+
+    /// Replace the synced settings with the settings the server sent on login. Local only settings are kept.
+    pub fn apply_synced(&mut self, settings: SyncedSettings) {
+        self.synced = settings;
+    }
+
+    /// Load the config from a file, using the default config if the file does not exist.
+    pub fn load(path: &Path) -> io::Result<ClientConfig> {
+        return match fs::read_to_string(path) {
+            Ok(text) => Ok(ClientConfig::from_config_string(&text)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(ClientConfig::default()),
+            Err(err) => Err(err)
+        };
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        return fs::write(path, self.to_config_string());
+    }
+}
+
+/// A finite number above 0, such as a speed.
+fn parse_positive(value: &str) -> Option<f32> {
+    return value.parse::<f32>().ok().filter(|number| *number > 0.0 && number.is_finite());
+}
@@ -0,0 +1,171 @@
+use crate::config::client_config::ClientConfig;
+
+/// Version of the client, included in every crash report.
+pub const CLIENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Config values included in crash reports. Anything that could tell players apart, such as the revision their synced
+/// settings are at, is left out.
+pub const REPORTED_CONFIG_VALUES: [&str; 8] = ["master_volume", "music_volume", "walk_speed", "tutor_mode", "upload_crash_reports", "language", "text_speed", "battle_pace"];
+
+/// The config as written to disk, with only the values in REPORTED_CONFIG_VALUES.
+/// ```
+/// use immie2d_client::config::client_config::ClientConfig;
+/// use immie2d_client::crash::crash_report::anonymize_config;
+///
+/// let mut config = ClientConfig::default();
+/// config.synced.revision = 17;
+/// let anonymized = anonymize_config(&config);
+/// assert!(anonymized.contains("music_volume=0.7\n"));
+/// assert!(!anonymized.contains("settings_revision"));
+/// assert!(!anonymized.contains("bind."));
+/// ```
+pub fn anonymize_config(config: &ClientConfig) -> String {
+    let mut out = String::new();
+    for line in config.to_config_string().lines() {
+        let is_reported = line.split_once('=').is_some_and(|(name, _)| REPORTED_CONFIG_VALUES.contains(&name));
+        if is_reported {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    return out;
+}
+
+/// Replace the player's home directory with `~` and their username with `<user>`, so paths in panic messages and
+/// backtraces don't say who the player is. Only whole path segments and words are replaced, so a short username
+/// doesn't garble the words that happen to contain it. Single character usernames are left alone.
+/// ```
+/// use immie2d_client::crash::crash_report::strip_identity;
+///
+/// let text = "failed to open /home/ash/.local/share/immie2d/save.dat for ash";
+/// assert_eq!(strip_identity(text, Some("/home/ash"), Some("ash")), "failed to open ~/.local/share/immie2d/save.dat for <user>");
+/// assert_eq!(strip_identity("C:\\Users\\al\\save.dat has no value", None, Some("al")), "C:\\Users\\<user>\\save.dat has no value");
+/// assert_eq!(strip_identity("/home/ashley/save.dat", Some("/home/ash"), Some("ash")), "/home/ashley/save.dat");
+/// assert_eq!(strip_identity("a map of 5 cells", None, Some("a")), "a map of 5 cells");
+/// ```
+pub fn strip_identity(text: &str, home: Option<&str>, username: Option<&str>) -> String {
+    let mut out = text.to_string();
+    if let Some(home) = home.map(|home| home.trim_end_matches(['/', '\\'])).filter(|home| home.len() > 1) {
+        out = replace_whole(&out, home, "~");
+    }
+    if let Some(username) = username.filter(|username| username.chars().count() >= 2) {
+        out = replace_whole(&out, username, "<user>");
+    }
+    return out;
+}
+
+/// Replace every occurrence of a needle that doesn't run into a longer word on either side.
+fn replace_whole(text: &str, needle: &str, replacement: &str) -> String {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for (index, _) in text.match_indices(needle) {
+        let end = index + needle.len();
+        let starts_inside_word = needle.starts_with(is_word_char) && text[..index].ends_with(is_word_char);
+        let ends_inside_word = needle.ends_with(is_word_char) && text[end..].starts_with(is_word_char);
+        if !starts_inside_word && !ends_inside_word {
+            out.push_str(&text[copied..index]);
+            out.push_str(replacement);
+            copied = end;
+        }
+    }
+    out.push_str(&text[copied..]);
+    return out;
+}
+
+/* Everything known about a crash or error, written locally and optionally uploaded. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CrashReport {
+    pub client_version: String,
+    /// Unix microseconds.
+    pub created_at: u64,
+    pub message: String,
+    /// Source file, line and column the panic was raised at, or None for errors that weren't panics.
+    pub location: Option<String>,
+    pub backtrace: String,
+    /// The last lines logged before the crash, oldest first.
+    pub log_lines: Vec<String>,
+    /// See anonymize_config()
+    pub config: String
+}
+
+impl CrashReport {
+    /// The report as text, with a header of single line values followed by the config, log and backtrace sections.
+    /// ```
+    /// use immie2d_client::crash::crash_report::CrashReport;
+    ///
+    /// let report = CrashReport {
+    ///     client_version: "0.1.0".to_string(),
+    ///     created_at: 5,
+    ///     message: "index out of bounds\nthe len is 3".to_string(),
+    ///     location: Some("src/main.rs:10:5".to_string()),
+    ///     backtrace: "0: main".to_string(),
+    ///     log_lines: vec!["connecting".to_string()],
+    ///     config: "tutor_mode=false\n".to_string()
+    /// };
+    /// let text = report.to_text();
+    /// assert!(text.starts_with("immie2d crash report\nversion=0.1.0\ncreated_at=5\n"));
+    /// // Messages stay on one line
+    /// assert!(text.contains("message=index out of bounds the len is 3\nlocation=src/main.rs:10:5\n"));
+    /// assert!(text.contains("[log]\nconnecting\n[backtrace]\n0: main\n"));
+    /// ```
+    pub fn to_text(&self) -> String {
+        let mut out = String::from("immie2d crash report\n");
+        out.push_str(&format!("version={}\n", self.client_version));
+        out.push_str(&format!("created_at={}\n", self.created_at));
+        out.push_str(&format!("message={}\n", self.message.replace(['\r', '\n'], " ")));
+        out.push_str(&format!("location={}\n", self.location.as_deref().unwrap_or("none")));
+        out.push_str("[config]\n");
+        out.push_str(&self.config);
+        out.push_str("[log]\n");
+        for line in self.log_lines.iter() {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push_str("[backtrace]\n");
+        out.push_str(&self.backtrace);
+        out.push('\n');
+        return out;
+    }
+
+    /// Read a report written by to_text(), such as one left by a crash to upload on the next launch.
+    /// ```
+    /// use immie2d_client::crash::crash_report::CrashReport;
+    ///
+    /// let report = CrashReport {
+    ///     client_version: "0.1.0".to_string(),
+    ///     created_at: 5,
+    ///     message: "index out of bounds".to_string(),
+    ///     location: Some("src/main.rs:10:5".to_string()),
+    ///     backtrace: "0: main\n1: start".to_string(),
+    ///     log_lines: vec!["connecting".to_string(), "logged in".to_string()],
+    ///     config: "tutor_mode=false\n".to_string()
+    /// };
+    /// assert_eq!(CrashReport::from_text(&report.to_text()), Some(report));
+    /// assert_eq!(CrashReport::from_text("not a report"), None);
+    /// ```
+    pub fn from_text(text: &str) -> Option<CrashReport> {
+        let rest = text.strip_prefix("immie2d crash report\n")?;
+        let (header, rest) = rest.split_once("[config]\n")?;
+        let (config, rest) = rest.split_once("[log]\n")?;
+        let (log, backtrace) = match rest.strip_prefix("[backtrace]\n") {
+            Some(backtrace) => ("", backtrace),
+            None => rest.split_once("\n[backtrace]\n")?
+        };
+        let mut values = header.lines().filter_map(|line| line.split_once('='));
+        let mut value = |name: &str| values.next().filter(|(key, _)| *key == name).map(|(_, value)| value.to_string());
+        let client_version = value("version")?;
+        let created_at = value("created_at")?.parse::<u64>().ok()?;
+        let message = value("message")?;
+        let location = value("location").filter(|location| location != "none");
+        return Some(CrashReport {
+            client_version,
+            created_at,
+            message,
+            location,
+            backtrace: backtrace.strip_suffix('\n').unwrap_or(backtrace).to_string(),
+            log_lines: log.lines().map(|line| line.to_string()).collect(),
+            config: config.to_string()
+        });
+    }
+}
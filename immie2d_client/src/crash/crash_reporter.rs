@@ -0,0 +1,330 @@
+use std::backtrace::Backtrace;
+use std::fmt;
+use std::fs;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::panic::{self, PanicHookInfo};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{env, thread};
+
+use immie2d_shared::engine_types::time_sync::get_unix_micros;
+
+use crate::config::client_config::ClientConfig;
+use crate::settings::settings_menu::SettingsFeedback;
+
+use super::crash_report::{anonymize_config, strip_identity, CrashReport, CLIENT_VERSION};
+use super::log_buffer::LogBuffer;
+
+/// How long an upload may take before it's abandoned, so a crashing client doesn't hang on a slow server.
+pub const UPLOAD_TIMEOUT: Duration = Duration::from_secs(5);
+
+/* Sends crash reports somewhere they can be aggregated. */
+pub trait CrashUploader: Send + Sync {
+    fn upload(&self, report: &CrashReport) -> io::Result<()>;
+}
+
+/* Uploads crash reports as the plain text body of an HTTP POST. */
+pub struct HttpCrashUploader {
+    /// Host and port, such as `reports.example.com:80`.
+    address: String,
+    path: String
+}
+
+impl HttpCrashUploader {
+    pub fn new(address: String, path: String) -> HttpCrashUploader {
+        return HttpCrashUploader { address, path };
+    }
+}
+
+impl CrashUploader for HttpCrashUploader {
+    /// Fails unless the endpoint answers with a 2xx status.
+    /// ```
+    /// use std::io::{Read, Write};
+    /// use std::net::TcpListener;
+    /// use immie2d_client::crash::crash_report::CrashReport;
+    /// use immie2d_client::crash::crash_reporter::{CrashUploader, HttpCrashUploader};
+    ///
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let address = listener.local_addr().unwrap().to_string();
+    /// let endpoint = std::thread::spawn(move || {
+    ///     let (mut connection, _) = listener.accept().unwrap();
+    ///     let mut request = Vec::new();
+    ///     let mut buffer = [0u8; 1024];
+    ///     while !String::from_utf8_lossy(&request).contains("[backtrace]\nnone\n") {
+    ///         let read = connection.read(&mut buffer).unwrap();
+    ///         request.extend_from_slice(&buffer[..read]);
+    ///     }
+    ///     connection.write_all(b"HTTP/1.1 204 No Content\r\n\r\n").unwrap();
+    ///     return String::from_utf8(request).unwrap();
+    /// });
+    ///
+    /// let report = CrashReport { client_version: "0.1.0".to_string(), created_at: 1, message: "oops".to_string(), location: None, backtrace: "none".to_string(), log_lines: Vec::new(), config: String::new() };
+    /// HttpCrashUploader::new(address, "/crash_reports".to_string()).upload(&report).unwrap();
+    /// let request = endpoint.join().unwrap();
+    /// assert!(request.starts_with("POST /crash_reports HTTP/1.1\r\n"));
+    /// assert!(request.ends_with(&report.to_text()));
+    /// ```
+    fn upload(&self, report: &CrashReport) -> io::Result<()> {
+        let address = self.address.to_socket_addrs()?.next().ok_or(io::Error::new(ErrorKind::NotFound, format!("No address for {}", self.address)))?;
+        let mut stream = TcpStream::connect_timeout(&address, UPLOAD_TIMEOUT)?;
+        stream.set_read_timeout(Some(UPLOAD_TIMEOUT))?;
+        stream.set_write_timeout(Some(UPLOAD_TIMEOUT))?;
+        let body = report.to_text();
+        let host = self.address.split(':').next().unwrap_or(&self.address);
+        let request = format!(
+            "POST {} HTTP/1.1\r\nHost: {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            self.path, host, body.len(), body
+        );
+        stream.write_all(request.as_bytes())?;
+        let mut status_line = [0u8; 12];
+        stream.read_exact(&mut status_line)?;
+        // "HTTP/1.1 2xx"
+        if status_line[9] != b'2' {
+            return Err(io::Error::other(format!("Crash report upload refused: {}", String::from_utf8_lossy(&status_line))));
+        }
+        return Ok(());
+    }
+}
+
+/// Subdirectory of the crash report directory that reports are moved into once uploaded.
+pub const UPLOADED_DIRECTORY: &str = "uploaded";
+
+/* Whether crash reports are uploaded, shared with the reporter so changing the setting takes effect straight away,
+even after the reporter is installed. */
+#[derive(Clone)]
+pub struct CrashUploadSwitch {
+    enabled: Arc<AtomicBool>
+}
+
+impl CrashUploadSwitch {
+    pub fn is_enabled(&self) -> bool {
+        return self.enabled.load(Ordering::Relaxed);
+    }
+
+    pub fn set_enabled(&self, enabled: bool) {
+        self.enabled.store(enabled, Ordering::Relaxed);
+    }
+
+    /// Follow the settings menu, including when it's cancelled and the config is restored.
+    /// ```
+    /// use std::path::PathBuf;
+    /// use immie2d_client::config::client_config::ClientConfig;
+    /// use immie2d_client::crash::{crash_reporter::CrashReporter, log_buffer::LogBuffer};
+    /// use immie2d_client::input::input_action::InputAction;
+    /// use immie2d_client::settings::settings_menu::{SettingsEntry, SettingsMenu};
+    ///
+    /// let reporter = CrashReporter::new(PathBuf::from("crash_reports"), &ClientConfig::default(), LogBuffer::new(10));
+    /// let switch = reporter.get_upload_switch();
+    /// let mut menu = SettingsMenu::new(ClientConfig::default(), PathBuf::from("unused.cfg"));
+    /// while menu.get_selected() != SettingsEntry::CrashReports {
+    ///     menu.handle_action(InputAction::MoveDown);
+    /// }
+    /// switch.handle_feedback(menu.handle_action(InputAction::Confirm), menu.get_config());
+    /// assert!(switch.is_enabled());
+    /// switch.handle_feedback(menu.handle_action(InputAction::Cancel), menu.get_config());
+    /// assert!(!switch.is_enabled());
+    /// ```
+    pub fn handle_feedback(&self, feedback: SettingsFeedback, config: &ClientConfig) {
+        match feedback {
+            SettingsFeedback::CrashReportsChanged(enabled) => self.set_enabled(enabled),
+            SettingsFeedback::Cancelled => self.set_enabled(config.upload_crash_reports),
+            _ => {}
+        }
+    }
+}
+
+/* Writes a crash report for every panic and reported error into a directory, and uploads it too if the player opted in.
+Panics are only written, since the client may be in no state to use the network, and are uploaded on the next launch.
+Paths and usernames are stripped from every report. See ClientConfig::upload_crash_reports and strip_identity() */
+pub struct CrashReporter {
+    directory: PathBuf,
+    logs: LogBuffer,
+    config: String,
+    upload_switch: CrashUploadSwitch,
+    uploader: Option<Box<dyn CrashUploader>>,
+    home: Option<String>,
+    username: Option<String>
+}
+
+impl CrashReporter {
+    pub fn new(directory: PathBuf, config: &ClientConfig, logs: LogBuffer) -> CrashReporter {
+        return CrashReporter {
+            directory,
+            logs,
+            config: anonymize_config(config),
+            upload_switch: CrashUploadSwitch { enabled: Arc::new(AtomicBool::new(config.upload_crash_reports)) },
+            uploader: None,
+            home: env::var("HOME").or_else(|_| env::var("USERPROFILE")).ok(),
+            username: env::var("USER").or_else(|_| env::var("USERNAME")).ok()
+        };
+    }
+
+    /// Where reports are uploaded to, if the player opted in.
+    pub fn with_uploader(mut self, uploader: Box<dyn CrashUploader>) -> CrashReporter {
+        self.uploader = Some(uploader);
+        return self;
+    }
+
+    /// Strip this home directory and username from reports instead of the ones of the current user.
+    pub fn with_identity(mut self, home: Option<String>, username: Option<String>) -> CrashReporter {
+        self.home = home;
+        self.username = username;
+        return self;
+    }
+
+    pub fn get_directory(&self) -> &Path {
+        return &self.directory;
+    }
+
+    /// The switch to turn uploads on or off with, such as from the settings menu.
+    pub fn get_upload_switch(&self) -> CrashUploadSwitch {
+        return self.upload_switch.clone();
+    }
+
+    fn strip(&self, text: &str) -> String {
+        return strip_identity(text, self.home.as_deref(), self.username.as_deref());
+    }
+
+    /// A report of the client's state right now, with a backtrace of the calling thread.
+    pub fn create_report(&self, message: String, location: Option<String>) -> CrashReport {
+        return CrashReport {
+            client_version: CLIENT_VERSION.to_string(),
+            created_at: get_unix_micros(),
+            message: self.strip(&message),
+            location: location.map(|location| self.strip(&location)),
+            backtrace: self.strip(&Backtrace::force_capture().to_string()),
+            log_lines: self.logs.get_lines().iter().map(|line| self.strip(line)).collect(),
+            config: self.config.clone()
+        };
+    }
+
+    fn write(&self, report: &CrashReport) -> io::Result<PathBuf> {
+        fs::create_dir_all(&self.directory)?;
+        let path = self.directory.join(format!("crash_{}.txt", report.created_at));
+        fs::write(&path, report.to_text())?;
+        return Ok(path);
+    }
+
+    /// Upload a written report if the player opted in, then move it into UPLOADED_DIRECTORY. Returns whether it was
+    /// uploaded.
+    fn upload(&self, report: &CrashReport, path: &Path) -> io::Result<bool> {
+        let Some(uploader) = self.uploader.as_ref().filter(|_| self.upload_switch.is_enabled()) else {
+            return Ok(false);
+        };
+        uploader.upload(report)?;
+        let uploaded = self.directory.join(UPLOADED_DIRECTORY);
+        fs::create_dir_all(&uploaded)?;
+        fs::rename(path, uploaded.join(path.file_name().unwrap_or_default()))?;
+        return Ok(true);
+    }
+
+    /// Write a report into the directory, then upload it if the player opted in. A failed upload is only logged, as
+    /// the report is kept locally and tried again by upload_pending(). Returns the path the report was written to.
+    /// ```
+    /// use std::io;
+    /// use std::sync::{Arc, Mutex};
+    /// use immie2d_client::config::client_config::ClientConfig;
+    /// use immie2d_client::crash::{crash_report::CrashReport, crash_reporter::{CrashReporter, CrashUploader}, log_buffer::LogBuffer};
+    ///
+    /// struct Recorder(Arc<Mutex<Vec<CrashReport>>>);
+    /// impl CrashUploader for Recorder {
+    ///     fn upload(&self, report: &CrashReport) -> io::Result<()> {
+    ///         self.0.lock().unwrap().push(report.clone());
+    ///         return Ok(());
+    ///     }
+    /// }
+    ///
+    /// let directory = std::env::temp_dir().join(format!("immie2d_crash_doctest_{}", std::process::id()));
+    /// let logs = LogBuffer::new(10);
+    /// logs.push("opened /home/ash/immie2d/bag.dat");
+    /// let uploaded = Arc::new(Mutex::new(Vec::new()));
+    ///
+    /// let reporter = CrashReporter::new(directory.clone(), &ClientConfig::default(), logs.clone())
+    ///     .with_uploader(Box::new(Recorder(uploaded.clone())))
+    ///     .with_identity(Some("/home/ash".to_string()), Some("ash".to_string()));
+    /// let path = reporter.report_error(&"bag data for ash is corrupt").unwrap();
+    /// let text = std::fs::read_to_string(&path).unwrap();
+    /// assert!(text.contains("message=bag data for <user> is corrupt\n"));
+    /// assert!(text.contains("[log]\nopened ~/immie2d/bag.dat\n"));
+    /// assert!(uploaded.lock().unwrap().is_empty());
+    ///
+    /// // Opting in later uploads the report left behind, as well as new ones
+    /// reporter.get_upload_switch().set_enabled(true);
+    /// assert_eq!(reporter.upload_pending().unwrap(), 1);
+    /// reporter.report_error(&"bag data is corrupt").unwrap();
+    /// assert_eq!(uploaded.lock().unwrap().len(), 2);
+    /// assert_eq!(reporter.upload_pending().unwrap(), 0);
+    /// std::fs::remove_dir_all(&directory).unwrap();
+    /// ```
+    pub fn submit(&self, report: &CrashReport) -> io::Result<PathBuf> {
+        let path = self.write(report)?;
+        if let Err(err) = self.upload(report, &path) {
+            eprintln!("Failed to upload crash report {}: {}", path.display(), err);
+        }
+        return Ok(path);
+    }
+
+    /// Upload every report written but not uploaded yet, such as those written by a panic. Does nothing unless the
+    /// player opted in. Stops at the first failed upload, leaving the rest for next time. Returns how many were
+    /// uploaded.
+    pub fn upload_pending(&self) -> io::Result<usize> {
+        if self.uploader.is_none() || !self.upload_switch.is_enabled() {
+            return Ok(0);
+        }
+        let entries = match fs::read_dir(&self.directory) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err)
+        };
+        let mut pending: Vec<PathBuf> = entries.filter_map(|entry| entry.ok()).map(|entry| entry.path())
+            .filter(|path| path.is_file() && path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with("crash_") && name.ends_with(".txt")))
+            .collect();
+        pending.sort();
+        let mut uploaded = 0;
+        for path in pending.iter() {
+            let Some(report) = CrashReport::from_text(&fs::read_to_string(path)?) else {
+                eprintln!("Skipping unreadable crash report {}", path.display());
+                continue;
+            };
+            if !self.upload(&report, path)? {
+                break;
+            }
+            uploaded += 1;
+        }
+        return Ok(uploaded);
+    }
+
+    /// Report an error the client recovered from, but that should never happen.
+    pub fn report_error(&self, error: &dyn fmt::Display) -> io::Result<PathBuf> {
+        return self.submit(&self.create_report(error.to_string(), None));
+    }
+
+    /// Only written, and uploaded on the next launch.
+    fn report_panic(&self, info: &PanicHookInfo) {
+        let message = info.payload_as_str().unwrap_or("unknown panic payload").to_string();
+        let location = info.location().map(|location| location.to_string());
+        if let Err(err) = self.write(&self.create_report(message, location)) {
+            eprintln!("Failed to write crash report: {}", err);
+        }
+    }
+
+    /// Report every panic from now on, before the panic is printed as usual. Reports left by earlier launches are
+    /// uploaded in the background.
+    pub fn install(self) {
+        let reporter = Arc::new(self);
+        let uploading = reporter.clone();
+        thread::spawn(move || {
+            if let Err(err) = uploading.upload_pending() {
+                eprintln!("Failed to upload crash reports from earlier launches: {}", err);
+            }
+        });
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            reporter.report_panic(info);
+            previous(info);
+        }));
+    }
+}
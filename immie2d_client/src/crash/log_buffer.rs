@@ -0,0 +1,46 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Log lines kept for crash reports by default.
+pub const DEFAULT_LOG_LINES: usize = 100;
+
+/* The most recent log lines of the client, kept so a crash report can show what led up to it. Clones share the same
+lines, so the logger and the crash reporter can each hold one. */
+#[derive(Clone)]
+pub struct LogBuffer {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    capacity: usize
+}
+
+impl LogBuffer {
+    /// Will panic if the capacity is 0.
+    pub fn new(capacity: usize) -> LogBuffer {
+        assert!(capacity > 0, "Log buffer must keep at least 1 line");
+        return LogBuffer { lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))), capacity };
+    }
+
+    /// Add a line, dropping the oldest once full.
+    /// ```
+    /// use immie2d_client::crash::log_buffer::LogBuffer;
+    ///
+    /// let logs = LogBuffer::new(2);
+    /// let logger = logs.clone();
+    /// logger.push("connecting");
+    /// logger.push("logged in");
+    /// logger.push("battle started");
+    /// assert_eq!(logs.get_lines(), vec!["logged in", "battle started"]);
+    /// ```
+    pub fn push(&self, line: &str) {
+        // A poisoned buffer still holds valid lines, and the crash reporter needs them most after a panic
+        let mut lines = self.lines.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line.to_string());
+    }
+
+    /// Every kept line, oldest first.
+    pub fn get_lines(&self) -> Vec<String> {
+        return self.lines.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).iter().cloned().collect();
+    }
+}
@@ -0,0 +1,3 @@
+pub mod log_buffer;
+pub mod crash_report;
+pub mod crash_reporter;
@@ -0,0 +1,290 @@
+use std::fmt;
+use std::time::Duration;
+
+use immie2d_shared::gameplay::battle::battle::Battle;
+use immie2d_shared::gameplay::battle::battle_command::{BattleCommand, BattleCommandError};
+use immie2d_shared::gameplay::battle::battle_event::BattleEvent;
+use immie2d_shared::gameplay::battle::event_timeline::EventTimeline;
+use immie2d_shared::gameplay::game_data::GameDataHandle;
+use immie2d_shared::gameplay::synced_settings::BattlePace;
+
+use crate::animation::battle_view_model::BattleViewModel;
+use crate::animation::timeline_player::TimelinePlayer;
+use crate::config::client_config::ClientConfig;
+use crate::tutor::battle_tutor;
+
+/* Where a hot seat battle is, which decides what the UI may show. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HotSeatState {
+    /// The privacy screen asking for the device to be passed to the player of the side. Nothing private to any side
+    /// may be shown until they confirm.
+    PassDevice { side: usize },
+    /// The player of the side is choosing their command.
+    Choosing { side: usize },
+    Finished { winner: Option<usize> }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HotSeatError {
+    /// The device is waiting to be passed, or the battle is over.
+    NotChoosing,
+    /// The command is for a different side than the player choosing.
+    WrongSide { choosing: usize, side: usize }
+}
+
+impl fmt::Display for HotSeatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            HotSeatError::NotChoosing => write!(f, "No player is choosing a command"),
+            HotSeatError::WrongSide { choosing, side } => write!(f, "Side {} is choosing, but the command is for side {}", choosing, side)
+        };
+    }
+}
+
+/* What happened once every side had chosen. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct TurnResult {
+    pub events: Vec<BattleEvent>,
+    /// Side and error of every command the battle rejected when the turn resolved.
+    pub rejected: Vec<(usize, BattleCommandError)>
+}
+
+/* An offline battle between players sharing one client, run on the shared battle engine without a server. Each
+player chooses their command in secret, with a privacy screen between players, and the turn resolves once every side
+has chosen. Sides that have been eliminated are skipped, and sides locked into a multi-turn ability continue it
+without being asked. The events of each turn are animated on a view model at the battle pace, timed by a local clock
+in place of a server's. */
+pub struct HotSeatBattle {
+    battle: Battle,
+    data: GameDataHandle,
+    state: HotSeatState,
+    /// Side and command of every choice so far this turn, in the order they were chosen.
+    commands: Vec<(usize, BattleCommand)>,
+    view: BattleViewModel,
+    timeline: TimelinePlayer,
+    /// Microseconds since the battle started.
+    clock: u64,
+    next_batch: u32,
+    /// Time the last turn's timeline finishes.
+    end_time: u64
+}
+
+impl HotSeatBattle {
+    /// Will panic if the battle has already finished.
+    pub fn new(battle: Battle, data: GameDataHandle) -> HotSeatBattle {
+        assert!(!battle.is_finished(), "Cannot start a hot seat battle that has finished");
+        let mut hot_seat = HotSeatBattle {
+            battle,
+            data,
+            state: HotSeatState::Finished { winner: None },
+            commands: Vec::new(),
+            view: BattleViewModel::new(),
+            timeline: TimelinePlayer::new(),
+            clock: 0,
+            next_batch: 0,
+            end_time: 0
+        };
+        // No side can be locked into a multi-turn ability before the first turn, so this only hands over.
+        hot_seat.pass_to_next(0);
+        return hot_seat;
+    }
+
+    pub fn with_pace(mut self, pace: BattlePace) -> HotSeatBattle {
+        self.timeline = self.timeline.with_pace(pace);
+        return self;
+    }
+
+    /// Change the pace of turns animated from now on. See TimelinePlayer::set_pace()
+    pub fn set_pace(&mut self, pace: BattlePace) {
+        self.timeline.set_pace(pace);
+    }
+
+    /// Toggle skipping every animation, fast-forwarding any that are queued. See TimelinePlayer::set_skip_all()
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::{ability::Ability, ability_map::AbilityMap, ability_names::AbilityNames, abilities::pursuit::Pursuit};
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// # use immie2d_shared::gameplay::item::item_map::ItemMap;
+    /// use std::time::Duration;
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::battle_command::BattleCommand;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::synced_settings::BattlePace;
+    /// use immie2d_client::hotseat::hotseat_battle::HotSeatBattle;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(500, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Pursuit>();
+    /// let abilities = AbilityNames::new(vec![GlobalString::new(&Pursuit::static_name().to_string())]);
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, abilities), &species)]);
+    /// let data = GameData::new(0, species_map, ability_map, ItemMap::new()).into_handle();
+    /// let mut hot_seat = HotSeatBattle::new(Battle::new(BattleFormat::Single, vec![side.clone(), side]), data).with_pace(BattlePace::Fast);
+    ///
+    /// for side in 0..2 {
+    ///     hot_seat.confirm_handover();
+    ///     hot_seat.submit(BattleCommand::UseAbility { side, ability_slot: 0, target_side: 1 - side }).unwrap();
+    /// }
+    /// hot_seat.update(Duration::from_millis(200));
+    /// assert!(!hot_seat.get_view().is_idle());
+    ///
+    /// hot_seat.set_skip_all(true);
+    /// assert!(hot_seat.get_view().is_idle());
+    /// ```
+    pub fn set_skip_all(&mut self, skip: bool) {
+        self.timeline.set_skip_all(&mut self.view, skip);
+    }
+
+    /// Advance the animations of the turns that have resolved by the time since the last update.
+    pub fn update(&mut self, delta: Duration) {
+        self.clock += delta.as_micros() as u64;
+        self.view.update(delta);
+        // With no server to acknowledge to, finished timelines are only dropped.
+        self.timeline.update(&mut self.view, self.clock);
+    }
+
+    /// The animations of the turns so far, for the battle screen to draw.
+    pub fn get_view(&self) -> &BattleViewModel {
+        return &self.view;
+    }
+
+    pub fn get_state(&self) -> HotSeatState {
+        return self.state;
+    }
+
+    pub fn get_battle(&self) -> &Battle {
+        return &self.battle;
+    }
+
+    /// The side whose private information, such as their team and ability uses, may be shown right now.
+    pub fn get_viewer(&self) -> Option<usize> {
+        return match self.state {
+            HotSeatState::Choosing { side } => Some(side),
+            _ => None
+        };
+    }
+
+    /// The command tutor mode suggests to the player choosing. None while nobody is choosing, or if tutor mode is off.
+    /// See battle_tutor::get_tutor_hint()
+    pub fn get_tutor_hint(&self, config: &ClientConfig) -> Option<BattleCommand> {
+        let side = self.get_viewer()?;
+        return battle_tutor::get_tutor_hint(config, &self.battle, side, self.data.get_ability_map());
+    }
+
+    /// The player the device was passed to confirms they are the only one looking. Does nothing unless the privacy
+    /// screen is shown.
+    pub fn confirm_handover(&mut self) {
+        if let HotSeatState::PassDevice { side } = self.state {
+            self.state = HotSeatState::Choosing { side };
+        }
+    }
+
+    /// Choose the command of the side whose player is choosing, then hand over to the next side. EndTurn chooses to do
+    /// nothing. Once the last side has chosen, the turn is resolved and returned.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::{ability::Ability, ability_map::AbilityMap, ability_names::AbilityNames, abilities::pursuit::Pursuit};
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// # use immie2d_shared::gameplay::item::item_map::ItemMap;
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::battle_command::BattleCommand;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_client::config::client_config::ClientConfig;
+    /// use immie2d_client::hotseat::hotseat_battle::{HotSeatBattle, HotSeatError, HotSeatState};
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(500, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Pursuit>();
+    /// let abilities = AbilityNames::new(vec![GlobalString::new(&Pursuit::static_name().to_string())]);
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, abilities), &species)]);
+    /// let data = GameData::new(0, species_map, ability_map, ItemMap::new()).into_handle();
+    /// let mut hot_seat = HotSeatBattle::new(Battle::new(BattleFormat::Single, vec![side.clone(), side]), data);
+    ///
+    /// assert_eq!(hot_seat.get_state(), HotSeatState::PassDevice { side: 0 });
+    /// assert_eq!(hot_seat.submit(BattleCommand::EndTurn), Err(HotSeatError::NotChoosing));
+    /// let mut config = ClientConfig::default();
+    /// config.tutor_mode = true;
+    /// assert_eq!(hot_seat.get_tutor_hint(&config), None);
+    /// hot_seat.confirm_handover();
+    /// assert_eq!(hot_seat.get_viewer(), Some(0));
+    /// assert_eq!(hot_seat.get_tutor_hint(&config), Some(BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 }));
+    /// assert_eq!(hot_seat.submit(BattleCommand::UseAbility { side: 1, ability_slot: 0, target_side: 0 }), Err(HotSeatError::WrongSide { choosing: 0, side: 1 }));
+    /// assert_eq!(hot_seat.submit(BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 }), Ok(None));
+    ///
+    /// // The second player can't see what the first chose, and nothing has happened yet
+    /// assert_eq!(hot_seat.get_state(), HotSeatState::PassDevice { side: 1 });
+    /// assert_eq!(hot_seat.get_viewer(), None);
+    /// assert_eq!(hot_seat.get_battle().get_battler(BattlerId::new(1, 0)).get_health(), 500);
+    ///
+    /// hot_seat.confirm_handover();
+    /// let turn = hot_seat.submit(BattleCommand::UseAbility { side: 1, ability_slot: 5, target_side: 0 }).unwrap().unwrap();
+    /// assert!(turn.rejected.len() == 1 && turn.rejected[0].0 == 1);
+    /// assert!(hot_seat.get_battle().get_battler(BattlerId::new(1, 0)).get_health() < 500);
+    /// assert_eq!(hot_seat.get_battle().get_turn(), 2);
+    /// assert_eq!(hot_seat.get_state(), HotSeatState::PassDevice { side: 0 });
+    /// ```
+    pub fn submit(&mut self, command: BattleCommand) -> Result<Option<TurnResult>, HotSeatError> {
+        let HotSeatState::Choosing { side: choosing } = self.state else {
+            return Err(HotSeatError::NotChoosing);
+        };
+        let side = command.get_side().unwrap_or(choosing);
+        if side != choosing {
+            return Err(HotSeatError::WrongSide { choosing, side });
+        }
+        self.commands.push((side, command));
+        let result = self.pass_to_next(choosing + 1);
+        if let Some(turn) = &result {
+            self.animate(turn.events.clone());
+        }
+        return Ok(result);
+    }
+
+    /// Queue the events of a turn to start once the previous turn's have finished.
+    fn animate(&mut self, events: Vec<BattleEvent>) {
+        let timeline = EventTimeline::from_events(self.next_batch, self.end_time.max(self.clock), events);
+        self.next_batch += 1;
+        self.end_time = timeline.get_end_time();
+        self.timeline.receive(timeline);
+    }
+
+    /// Hand over to the first side from `side` that has to choose, or resolve the turn if none are left.
+    fn pass_to_next(&mut self, side: usize) -> Option<TurnResult> {
+        for next in side..self.battle.get_side_count() {
+            if self.battle.get_side(next).is_eliminated() {
+                continue;
+            }
+            if self.battle.get_forced_action(next).is_some() {
+                self.commands.push((next, BattleCommand::Continue { side: next }));
+                continue;
+            }
+            self.state = HotSeatState::PassDevice { side: next };
+            return None;
+        }
+        return Some(self.resolve_turn());
+    }
+
+    fn resolve_turn(&mut self) -> TurnResult {
+        let (sides, commands): (Vec<usize>, Vec<BattleCommand>) = std::mem::take(&mut self.commands).into_iter().unzip();
+        let rejected = self.battle.resolve_turn(&commands, self.data.get_ability_map(), self.data.get_species_map());
+        let mut result = TurnResult {
+            events: self.battle.take_events(),
+            rejected: rejected.into_iter().map(|(index, err)| (sides[index], err)).collect()
+        };
+        if self.battle.is_finished() {
+            self.state = HotSeatState::Finished { winner: self.battle.get_winner() };
+        } else if let Some(next_turn) = self.pass_to_next(0) {
+            // Every side is locked into a multi-turn ability, so the next turn resolves without asking anyone.
+            result.events.extend(next_turn.events);
+            result.rejected.extend(next_turn.rejected);
+            return result;
+        }
+        return result;
+    }
+}
@@ -0,0 +1 @@
+pub mod hotseat_battle;
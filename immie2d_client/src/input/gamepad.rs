@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use super::input_action::InputAction;
+
+/// Stick deflection below which input is ignored, as a fraction of full deflection.
+pub const DEFAULT_DEADZONE: f32 = 0.2;
+
+/* A gamepad button, named by position so it is the same across controller brands. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GamepadButton {
+    South,
+    East,
+    West,
+    North,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    LeftShoulder,
+    RightShoulder,
+    Start,
+    Select
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY
+}
+
+/* Which action each gamepad button triggers. Unlike keys, several buttons can trigger the same action. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct GamepadBindings {
+    bindings: HashMap<GamepadButton, InputAction>,
+    pub deadzone: f32
+}
+
+impl GamepadBindings {
+    pub fn default() -> GamepadBindings {
+        let mut bindings = HashMap::new();
+        bindings.insert(GamepadButton::DPadUp, InputAction::MoveUp);
+        bindings.insert(GamepadButton::DPadDown, InputAction::MoveDown);
+        bindings.insert(GamepadButton::DPadLeft, InputAction::MoveLeft);
+        bindings.insert(GamepadButton::DPadRight, InputAction::MoveRight);
+        bindings.insert(GamepadButton::South, InputAction::Confirm);
+        bindings.insert(GamepadButton::East, InputAction::Cancel);
+        bindings.insert(GamepadButton::Start, InputAction::Menu);
+        return GamepadBindings { bindings, deadzone: DEFAULT_DEADZONE };
+    }
+
+    pub fn get_action(&self, button: GamepadButton) -> Option<InputAction> {
+        return self.bindings.get(&button).copied();
+    }
+
+    /// Bind a button to an action, replacing what the button was bound to before.
+    pub fn bind(&mut self, button: GamepadButton, action: InputAction) {
+        self.bindings.insert(button, action);
+    }
+
+    pub fn unbind(&mut self, button: GamepadButton) {
+        self.bindings.remove(&button);
+    }
+}
+
+/// Apply a radial deadzone to a stick, rescaling so movement starts from 0 at the edge of the deadzone
+/// and the result never exceeds a length of 1.
+/// ```
+/// use immie2d_client::input::gamepad::apply_deadzone;
+///
+/// assert_eq!(apply_deadzone(0.1, 0.1, 0.2), (0.0, 0.0));
+/// let (x, y) = apply_deadzone(0.6, 0.0, 0.2);
+/// assert!((x - 0.5).abs() < 0.001 && y == 0.0);
+/// let (x, y) = apply_deadzone(1.0, 1.0, 0.2);
+/// assert!(((x * x + y * y).sqrt() - 1.0).abs() < 0.001);
+/// ```
+pub fn apply_deadzone(x: f32, y: f32, deadzone: f32) -> (f32, f32) {
+    let length = (x * x + y * y).sqrt();
+    if length <= deadzone || deadzone >= 1.0 {
+        return (0.0, 0.0);
+    }
+    let scaled_length = ((length - deadzone) / (1.0 - deadzone)).min(1.0);
+    return (x / length * scaled_length, y / length * scaled_length);
+}
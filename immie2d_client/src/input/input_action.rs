@@ -0,0 +1,44 @@
+/* Game actions that keys are bound to. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum InputAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Confirm,
+    Cancel,
+    Menu
+}
+
+pub const ALL_INPUT_ACTIONS: [InputAction; 7] = [
+    InputAction::MoveUp,
+    InputAction::MoveDown,
+    InputAction::MoveLeft,
+    InputAction::MoveRight,
+    InputAction::Confirm,
+    InputAction::Cancel,
+    InputAction::Menu
+];
+
+impl InputAction {
+    /// Get the name of the action as used in config files.
+    /// ```
+    /// use immie2d_client::input::input_action::InputAction;
+    /// assert_eq!(InputAction::from_name(InputAction::MoveUp.get_name()), Some(InputAction::MoveUp));
+    /// ```
+    pub fn get_name(&self) -> &'static str {
+        return match *self {
+            InputAction::MoveUp => "move_up",
+            InputAction::MoveDown => "move_down",
+            InputAction::MoveLeft => "move_left",
+            InputAction::MoveRight => "move_right",
+            InputAction::Confirm => "confirm",
+            InputAction::Cancel => "cancel",
+            InputAction::Menu => "menu"
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<InputAction> {
+        return ALL_INPUT_ACTIONS.iter().find(|action| action.get_name() == name).copied();
+    }
+}
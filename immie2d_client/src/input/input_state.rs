@@ -0,0 +1,186 @@
+use std::collections::{HashMap, HashSet};
+
+use immie2d_shared::world::tile_position::Direction;
+
+use super::gamepad::{apply_deadzone, GamepadAxis, GamepadBindings, GamepadButton};
+use super::input_action::InputAction;
+use super::key::Key;
+use super::key_bindings::KeyBindings;
+
+/* The kind of device the player is using, so UI prompts can show the matching key or button. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InputDevice {
+    Keyboard,
+    Gamepad
+}
+
+/* A raw input from the windowing backend. Axis values range from -1 to 1, with positive Y being down. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InputEvent {
+    KeyPressed(Key),
+    KeyReleased(Key),
+    ButtonPressed(GamepadButton),
+    ButtonReleased(GamepadButton),
+    AxisMoved(GamepadAxis, f32)
+}
+
+/* Which way and how fast the player wants to move, sent to the server as a movement intent.
+The vector has a length between 0 and 1, where 1 is full speed. Y is down. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct MovementIntent {
+    pub x: f32,
+    pub y: f32
+}
+
+impl MovementIntent {
+    pub fn get_magnitude(&self) -> f32 {
+        return (self.x * self.x + self.y * self.y).sqrt();
+    }
+
+    /// The tile direction closest to the vector, or None if there is no movement. Ties favour horizontal movement.
+    /// ```
+    /// use immie2d_shared::world::tile_position::Direction;
+    /// use immie2d_client::input::input_state::MovementIntent;
+    ///
+    /// assert_eq!(MovementIntent { x: 0.3, y: -0.8 }.get_direction(), Some(Direction::Up));
+    /// assert_eq!(MovementIntent { x: -0.5, y: 0.5 }.get_direction(), Some(Direction::Left));
+    /// assert_eq!(MovementIntent { x: 0.0, y: 0.0 }.get_direction(), None);
+    /// ```
+    pub fn get_direction(&self) -> Option<Direction> {
+        if self.x == 0.0 && self.y == 0.0 {
+            return None;
+        }
+        if self.x.abs() >= self.y.abs() {
+            return Some(if self.x > 0.0 { Direction::Right } else { Direction::Left });
+        }
+        return Some(if self.y > 0.0 { Direction::Down } else { Direction::Up });
+    }
+}
+
+/* Combined keyboard and gamepad state. Both devices work at the same time: an action is held if it is held on either,
+and movement uses whichever device gives the stronger input. */
+pub struct InputState {
+    key_bindings: KeyBindings,
+    gamepad_bindings: GamepadBindings,
+    held_keys: HashSet<Key>,
+    held_buttons: HashSet<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+    last_used_device: InputDevice
+}
+
+impl InputState {
+    pub fn new(key_bindings: KeyBindings, gamepad_bindings: GamepadBindings) -> InputState {
+        return InputState {
+            key_bindings,
+            gamepad_bindings,
+            held_keys: HashSet::new(),
+            held_buttons: HashSet::new(),
+            axes: HashMap::new(),
+            last_used_device: InputDevice::Keyboard
+        };
+    }
+
+    pub fn get_key_bindings(&self) -> &KeyBindings {
+        return &self.key_bindings;
+    }
+
+    pub fn set_key_bindings(&mut self, key_bindings: KeyBindings) {
+        self.key_bindings = key_bindings;
+    }
+
+    pub fn get_gamepad_bindings(&self) -> &GamepadBindings {
+        return &self.gamepad_bindings;
+    }
+
+    pub fn set_gamepad_bindings(&mut self, gamepad_bindings: GamepadBindings) {
+        self.gamepad_bindings = gamepad_bindings;
+    }
+
+    /// The device that most recently gave meaningful input. Stick movement inside the deadzone doesn't count,
+    /// so a drifting stick doesn't switch prompts away from the keyboard.
+    pub fn get_last_used_device(&self) -> InputDevice {
+        return self.last_used_device;
+    }
+
+    /// Update the state from a raw input. Returns the action that was just pressed, if any.
+    pub fn handle_event(&mut self, event: InputEvent) -> Option<InputAction> {
+        match event {
+            InputEvent::KeyPressed(key) => {
+                self.last_used_device = InputDevice::Keyboard;
+                if !self.held_keys.insert(key) {
+                    return None;
+                }
+                return self.key_bindings.get_action(key);
+            },
+            InputEvent::KeyReleased(key) => {
+                self.held_keys.remove(&key);
+            },
+            InputEvent::ButtonPressed(button) => {
+                self.last_used_device = InputDevice::Gamepad;
+                if !self.held_buttons.insert(button) {
+                    return None;
+                }
+                return self.gamepad_bindings.get_action(button);
+            },
+            InputEvent::ButtonReleased(button) => {
+                self.held_buttons.remove(&button);
+            },
+            InputEvent::AxisMoved(axis, value) => {
+                self.axes.insert(axis, value.clamp(-1.0, 1.0));
+                if value.abs() > self.gamepad_bindings.deadzone {
+                    self.last_used_device = InputDevice::Gamepad;
+                }
+            }
+        }
+        return None;
+    }
+
+    /// Whether an action is held on either device.
+    pub fn is_held(&self, action: InputAction) -> bool {
+        return self.held_keys.iter().any(|key| self.key_bindings.get_action(*key) == Some(action))
+            || self.held_buttons.iter().any(|button| self.gamepad_bindings.get_action(*button) == Some(action));
+    }
+
+    /// The movement the player wants, from the left stick or the digital move actions, whichever is stronger.
+    /// ```
+    /// use immie2d_client::input::{key::Key, key_bindings::KeyBindings, gamepad::{GamepadBindings, GamepadAxis, GamepadButton}};
+    /// use immie2d_client::input::input_state::{InputState, InputEvent, InputDevice};
+    ///
+    /// let mut input = InputState::new(KeyBindings::default(), GamepadBindings::default());
+    /// input.handle_event(InputEvent::AxisMoved(GamepadAxis::LeftStickX, 0.1));
+    /// assert_eq!(input.get_movement_intent().get_magnitude(), 0.0);
+    /// assert_eq!(input.get_last_used_device(), InputDevice::Keyboard);
+    ///
+    /// input.handle_event(InputEvent::AxisMoved(GamepadAxis::LeftStickX, 0.6));
+    /// let intent = input.get_movement_intent();
+    /// assert!(intent.x > 0.0 && intent.x < 1.0);
+    /// assert_eq!(input.get_last_used_device(), InputDevice::Gamepad);
+    ///
+    /// // Keyboard movement is always full speed, so it takes over from the half-pushed stick.
+    /// input.handle_event(InputEvent::KeyPressed(KeyBindings::default().get_key(immie2d_client::input::input_action::InputAction::MoveLeft)));
+    /// assert_eq!(input.get_movement_intent().x, -1.0);
+    /// assert_eq!(input.get_last_used_device(), InputDevice::Keyboard);
+    /// ```
+    pub fn get_movement_intent(&self) -> MovementIntent {
+        let stick_x = self.axes.get(&GamepadAxis::LeftStickX).copied().unwrap_or(0.0);
+        let stick_y = self.axes.get(&GamepadAxis::LeftStickY).copied().unwrap_or(0.0);
+        let (stick_x, stick_y) = apply_deadzone(stick_x, stick_y, self.gamepad_bindings.deadzone);
+        let analog = MovementIntent { x: stick_x, y: stick_y };
+
+        let axis = |negative: InputAction, positive: InputAction| (self.is_held(positive) as i32 - self.is_held(negative) as i32) as f32;
+        let digital_x = axis(InputAction::MoveLeft, InputAction::MoveRight);
+        let digital_y = axis(InputAction::MoveUp, InputAction::MoveDown);
+        let digital_length = (digital_x * digital_x + digital_y * digital_y).sqrt();
+        let digital = if digital_length > 0.0 {
+            MovementIntent { x: digital_x / digital_length, y: digital_y / digital_length }
+        }
+        else {
+            MovementIntent { x: 0.0, y: 0.0 }
+        };
+
+        if digital.get_magnitude() >= analog.get_magnitude() {
+            return digital;
+        }
+        return analog;
+    }
+}
@@ -0,0 +1,76 @@
+use std::fmt;
+
+/* A physical keyboard key, independent of the windowing backend. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Escape,
+    Space,
+    Tab,
+    Backspace
+}
+
+impl Key {
+    /// Parse a key from the name used in config files. Characters are case insensitive.
+    /// ```
+    /// use immie2d_client::input::key::Key;
+    /// assert_eq!(Key::from_name("up"), Some(Key::Up));
+    /// assert_eq!(Key::from_name("Z"), Some(Key::Char('z')));
+    /// assert_eq!(Key::from_name("not a key"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Key> {
+        let lowercase = name.to_lowercase();
+        let key = match lowercase.as_str() {
+            "up" => Key::Up,
+            "down" => Key::Down,
+            "left" => Key::Left,
+            "right" => Key::Right,
+            "enter" => Key::Enter,
+            "escape" => Key::Escape,
+            "space" => Key::Space,
+            "tab" => Key::Tab,
+            "backspace" => Key::Backspace,
+            _ => {
+                let mut chars = lowercase.chars();
+                let first = chars.next()?;
+                if chars.next().is_some() {
+                    return None;
+                }
+                Key::Char(first)
+            }
+        };
+        return Some(key);
+    }
+
+    /// Get the name of the key as used in config files.
+    /// ```
+    /// use immie2d_client::input::key::Key;
+    /// assert_eq!(Key::Escape.get_name(), "escape");
+    /// assert_eq!(Key::Char('w').get_name(), "w");
+    /// ```
+    pub fn get_name(&self) -> String {
+        return match *self {
+            Key::Char(c) => c.to_string(),
+            Key::Up => "up".to_string(),
+            Key::Down => "down".to_string(),
+            Key::Left => "left".to_string(),
+            Key::Right => "right".to_string(),
+            Key::Enter => "enter".to_string(),
+            Key::Escape => "escape".to_string(),
+            Key::Space => "space".to_string(),
+            Key::Tab => "tab".to_string(),
+            Key::Backspace => "backspace".to_string()
+        };
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}", self.get_name());
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+
+use super::input_action::InputAction;
+use super::key::Key;
+
+/* Which key triggers each input action. Every action has exactly one key, and no key is shared between actions. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct KeyBindings {
+    bindings: HashMap<InputAction, Key>
+}
+
+impl KeyBindings {
+    /// The default WASD style bindings.
+    /// ```
+    /// use immie2d_client::input::{key_bindings::KeyBindings, input_action::InputAction, key::Key};
+    /// let bindings = KeyBindings::default();
+    /// assert_eq!(bindings.get_key(InputAction::MoveUp), Key::Char('w'));
+    /// ```
+    pub fn default() -> KeyBindings {
+        let mut bindings = HashMap::new();
+        bindings.insert(InputAction::MoveUp, Key::Char('w'));
+        bindings.insert(InputAction::MoveDown, Key::Char('s'));
+        bindings.insert(InputAction::MoveLeft, Key::Char('a'));
+        bindings.insert(InputAction::MoveRight, Key::Char('d'));
+        bindings.insert(InputAction::Confirm, Key::Enter);
+        bindings.insert(InputAction::Cancel, Key::Escape);
+        bindings.insert(InputAction::Menu, Key::Tab);
+        return KeyBindings { bindings };
+    }
+
+    pub fn get_key(&self, action: InputAction) -> Key {
+        return self.bindings[&action];
+    }
+
+    /// Get the action a key is bound to, if any.
+    /// ```
+    /// use immie2d_client::input::{key_bindings::KeyBindings, input_action::InputAction, key::Key};
+    /// let bindings = KeyBindings::default();
+    /// assert_eq!(bindings.get_action(Key::Enter), Some(InputAction::Confirm));
+    /// assert_eq!(bindings.get_action(Key::Char('q')), None);
+    /// ```
+    pub fn get_action(&self, key: Key) -> Option<InputAction> {
+        return self.bindings.iter().find(|(_, bound)| **bound == key).map(|(action, _)| *action);
+    }
+
+    /// Get the action, other than the given one, that is already bound to a key.
+    pub fn find_conflict(&self, action: InputAction, key: Key) -> Option<InputAction> {
+        return self.get_action(key).filter(|bound_action| *bound_action != action);
+    }
+
+    /// Bind a key to an action. If the key is already bound to a different action, nothing changes and the conflicting action is returned.
+    /// ```
+    /// use immie2d_client::input::{key_bindings::KeyBindings, input_action::InputAction, key::Key};
+    /// let mut bindings = KeyBindings::default();
+    /// assert_eq!(bindings.rebind(InputAction::MoveUp, Key::Up), None);
+    /// assert_eq!(bindings.get_key(InputAction::MoveUp), Key::Up);
+    /// assert_eq!(bindings.rebind(InputAction::MoveDown, Key::Up), Some(InputAction::MoveUp));
+    /// assert_eq!(bindings.get_key(InputAction::MoveDown), Key::Char('s'));
+    /// ```
+    pub fn rebind(&mut self, action: InputAction, key: Key) -> Option<InputAction> {
+        let conflict = self.find_conflict(action, key);
+        if conflict.is_none() {
+            self.bindings.insert(action, key);
+        }
+        return conflict;
+    }
+}
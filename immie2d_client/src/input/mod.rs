@@ -0,0 +1,5 @@
+pub mod key;
+pub mod input_action;
+pub mod key_bindings;
+pub mod gamepad;
+pub mod input_state;
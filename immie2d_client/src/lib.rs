@@ -0,0 +1,22 @@
+// The codebase favours explicit returns and inherent `default()` constructors.
+#![allow(
+    clippy::needless_return,
+    clippy::should_implement_trait,
+    clippy::new_without_default,
+    clippy::module_inception,
+    clippy::len_zero
+)]
+
+pub mod animation;
+pub mod config;
+pub mod input;
+pub mod settings;
+pub mod network;
+pub mod bot;
+pub mod world;
+pub mod team;
+pub mod audio;
+pub mod tutor;
+pub mod crash;
+pub mod hotseat;
+pub mod ui;
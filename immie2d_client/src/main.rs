@@ -1,33 +1,55 @@
-use std::{net::TcpStream, io::{self, Write, BufReader, BufRead, ErrorKind}};
-use std::str;
+#![allow(clippy::needless_return, clippy::unnecessary_unwrap)]
 
+use std::{net::TcpStream, io::{self, Write, BufReader, BufRead, ErrorKind}, thread};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use immie2d_client::config::client_config::ClientConfig;
+use immie2d_shared::engine_types::game_protocol::{decode_message_line, ClientRequest, MessageKind};
+use immie2d_shared::gameplay::encounter::encounter_roller::WildEncounter;
+use immie2d_shared::gameplay::synced_settings::SyncedSettings;
+use immie2d_shared::modding::{data_pack::MANIFEST_FILE, pack_advertisement::PackAdvertisement, pack_manifest::PackManifest};
+use immie2d_shared::world::{fast_travel::FastTravelEvent, move_result::MoveResult, simulation_status::SimulationStatus};
+use immie2d_shared::world::cutscene::{CutsceneCue, CutsceneCueMessage};
+use immie2d_client::world::walk_animator::WalkAnimator;
+use immie2d_client::crash::{crash_reporter::{CrashReporter, HttpCrashUploader}, log_buffer::{LogBuffer, DEFAULT_LOG_LINES}};
+
+const CONFIG_PATH: &str = "client.cfg";
+const CRASH_REPORT_DIRECTORY: &str = "crash_reports";
+/// The server's HTTP API, which crash reports are uploaded to if the player opted in.
+const CRASH_REPORT_ADDRESS: &str = "127.0.0.1:8080";
+const CRASH_REPORT_PATH: &str = "/api/crash_reports";
+/// Directory of installed data packs, each in its own subdirectory, checked against the packs the server advertises.
+const PACK_DIRECTORY: &str = "data_packs";
 
 fn main() {
+    let config = ClientConfig::load(Path::new(CONFIG_PATH)).unwrap_or_else(|err| {
+        println!("Failed to load {}, using the default config: {}", CONFIG_PATH, err);
+        return ClientConfig::default();
+    });
+    let logs = LogBuffer::new(DEFAULT_LOG_LINES);
+    CrashReporter::new(PathBuf::from(CRASH_REPORT_DIRECTORY), &config, logs.clone())
+        .with_uploader(Box::new(HttpCrashUploader::new(CRASH_REPORT_ADDRESS.to_string(), CRASH_REPORT_PATH.to_string())))
+        .install();
+
     let mut stream = TcpStream::connect("127.0.0.1:7878").expect("failed to connect");
+    let reader_stream = stream.try_clone().expect("failed to clone the connection");
+    let reader_logs = logs.clone();
+    // The server sends whenever something happens, not only in reply to a request, so it is read on its own thread
+    let reader_config = config.clone();
+    thread::spawn(move || read_messages(reader_stream, reader_logs, reader_config));
 
-    for _ in 0..7 {
+    loop {
         let mut user_input = String::new();
-        io::stdin().read_line(&mut user_input).expect("failed to read user input");
-        
-        // write to the tcp connection
-        let stream_write_result = stream.write(user_input.as_bytes());
-        if(stream_write_result.is_err()) {
-            let err = stream_write_result.unwrap_err();
-            if err.kind() == ErrorKind::ConnectionAborted {
-                println!("Server aborted connection");
-            }
-            else {
-                println!("Some write error occurred {err}");
-            } 
+        if io::stdin().read_line(&mut user_input).expect("failed to read user input") == 0 {
             break;
         }
-
-        let mut reader = BufReader::new(&stream);
-
-        let mut buffer: Vec<u8> = Vec::new();
-        let stream_read_result = reader.read_until(b'\n', &mut buffer);
-        if(stream_read_result.is_err()) {
-            let err = stream_read_result.unwrap_err();
+        if let Err(err) = ClientRequest::parse(&user_input) {
+            println!("{}", err);
+            continue;
+        }
+        logs.push(&format!("sent {}", user_input.split(' ').next().unwrap_or("").trim_end()));
+        if let Err(err) = stream.write_all(user_input.as_bytes()) {
             if err.kind() == ErrorKind::ConnectionAborted {
                 println!("Server aborted connection");
             }
@@ -36,9 +58,112 @@ fn main() {
             }
             break;
         }
-
-        println!("read from server: {}\n", str::from_utf8(&buffer).unwrap());   
     }
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+}
 
-    //stream.shutdown(std::net::Shutdown::Both).expect("lmao");
+/// Manifests of the installed data packs. Packs without a readable manifest are skipped, as the server will then
+/// list them as missing.
+fn load_installed_manifests(directory: &Path) -> Vec<PackManifest> {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Vec::new();
+    };
+    return entries.filter_map(|entry| entry.ok())
+        .filter_map(|entry| fs::read_to_string(entry.path().join(MANIFEST_FILE)).ok())
+        .filter_map(|json| PackManifest::from_json(&json).ok())
+        .collect();
+}
+
+/// Print each message from the server until the connection closes. Settings are synced once logged in, and the
+/// settings the server sends back are saved to the config.
+fn read_messages(stream: TcpStream, logs: LogBuffer, mut config: ClientConfig) {
+    let mut writer = &stream;
+    let reader = BufReader::new(&stream);
+    // Where the server has the player, once it sent their first position
+    let mut walker: Option<WalkAnimator> = None;
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => {
+                println!("Some read error occurred {err}");
+                return;
+            }
+        };
+        let Some((kind, payload)) = decode_message_line(&line) else {
+            println!("read unknown message from server: {}", line);
+            continue;
+        };
+        logs.push(&format!("received {}", kind.get_keyword()));
+        match kind {
+            MessageKind::AccountCreated | MessageKind::LoggedIn if payload.len() == 8 => {
+                let player = u64::from_le_bytes(payload.try_into().unwrap());
+                println!("{}: player {}", kind.get_keyword(), player);
+                if kind == MessageKind::LoggedIn {
+                    let _ = writer.write_all(ClientRequest::SyncSettings(config.synced.clone()).to_line().as_bytes());
+                }
+            },
+            MessageKind::SyncedSettings => match SyncedSettings::from_bytes(&payload) {
+                Some(settings) => {
+                    config.apply_synced(settings);
+                    if let Err(err) = config.save(Path::new(CONFIG_PATH)) {
+                        println!("Failed to save the synced settings to {}: {}", CONFIG_PATH, err);
+                    }
+                },
+                None => println!("read invalid synced settings from server")
+            },
+            MessageKind::PackAdvertisement => match PackAdvertisement::from_bytes(&payload) {
+                Some(advertisement) => {
+                    for (namespace, version) in advertisement.get_missing(&load_installed_manifests(Path::new(PACK_DIRECTORY))) {
+                        println!("The server uses data pack {} {}, which isn't installed in {}", namespace, version, PACK_DIRECTORY);
+                    }
+                },
+                None => println!("read invalid pack advertisement from server")
+            },
+            MessageKind::SimulationStatus => match SimulationStatus::from_bytes(&payload) {
+                Some(status) => println!("{}", status.get_notice().unwrap_or(format!("Simulation running at tick {}", status.tick))),
+                None => println!("read invalid simulation status from server")
+            },
+            MessageKind::MoveResult => match MoveResult::from_bytes(&payload) {
+                Some(result) => {
+                    match walker.as_mut() {
+                        Some(walker) if result.is_granted() => walker.apply_server_position(result.tile),
+                        Some(walker) => walker.apply_move_result(&result),
+                        None => walker = Some(WalkAnimator::new(result.tile, config.walk_speed))
+                    }
+                    match result.denial {
+                        Some(denial) => println!("Couldn't step, {:?}, staying at {}, {}", denial, result.tile.x, result.tile.y),
+                        None => println!("At {}, {}", result.tile.x, result.tile.y)
+                    }
+                },
+                None => println!("read invalid move result from server")
+            },
+            MessageKind::FastTravel => match FastTravelEvent::from_bytes(&payload) {
+                Some(FastTravelEvent::Unlocked { point }) => println!("Unlocked fast travel to {}", point.to_string()),
+                Some(FastTravelEvent::Travelled { point, .. }) => println!("Travelled to {}", point.to_string()),
+                Some(FastTravelEvent::Denied { point, denial }) => println!("Couldn't travel to {}, {:?}", point.to_string(), denial),
+                None => println!("read invalid fast travel event from server")
+            },
+            MessageKind::Cutscene => match CutsceneCueMessage::from_bytes(&payload).map(|message| message.cue) {
+                Some(CutsceneCue::ShowDialogue { speaker, text }) if speaker.is_empty() => println!("{} (close_dialogue to go on)", text),
+                Some(CutsceneCue::ShowDialogue { speaker, text }) => println!("{}: {} (close_dialogue to go on)", speaker, text),
+                Some(CutsceneCue::Started) => println!("A cutscene started"),
+                Some(CutsceneCue::Ended) => println!("The cutscene ended"),
+                Some(_) => {},
+                None => println!("read invalid cutscene cue from server")
+            },
+            MessageKind::WildEncounter => match WildEncounter::from_bytes(&payload) {
+                Some(encounter) => println!("A wild level {} {} appeared! (sprite {})", encounter.level, encounter.species.to_string(), encounter.to_immie().get_sprite_id()),
+                None => println!("read invalid wild encounter from server")
+            },
+            MessageKind::GiftReceived => println!("You received the gift {}, check your box and bag", String::from_utf8_lossy(&payload)),
+            MessageKind::BattleQueued => println!("Waiting for an opponent"),
+            MessageKind::Traded => println!("Traded for a {}, which was sent to your box", String::from_utf8_lossy(&payload)),
+            MessageKind::TwoFactorSetup => {
+                println!("Add this secret to your authenticator and keep the recovery codes somewhere safe:");
+                println!("{}", String::from_utf8_lossy(&payload));
+            },
+            _ => println!("{}: {}", kind.get_keyword(), String::from_utf8_lossy(&payload))
+        }
+    }
+    println!("Server closed the connection");
 }
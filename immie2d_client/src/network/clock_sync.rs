@@ -0,0 +1,115 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use immie2d_shared::engine_types::time_sync::{TimeSyncPing, TimeSyncPong};
+
+/// How many of the most recent time sync samples are kept.
+pub const MAX_CLOCK_SAMPLES: usize = 8;
+
+/* One ping to the server and back. Times are microseconds. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ClockSample {
+    /// Server clock minus the client clock, assuming the ping took as long to arrive as the pong.
+    pub offset: i64,
+    pub round_trip: u64
+}
+
+/* Estimates the offset between the client and server clocks from ping samples, NTP style. The sample with the
+shortest round trip is trusted most, since it had the least time for the network to skew it. Times are microseconds
+since the unix epoch. */
+pub struct ClockSync {
+    samples: VecDeque<ClockSample>
+}
+
+impl ClockSync {
+    pub fn new() -> ClockSync {
+        return ClockSync { samples: VecDeque::with_capacity(MAX_CLOCK_SAMPLES) };
+    }
+
+    /// A ping to send to the server.
+    pub fn create_ping(&self, local_time: u64) -> TimeSyncPing {
+        return TimeSyncPing { client_send: local_time };
+    }
+
+    /// Add the sample of a pong received at a local time, replacing the oldest sample once there are
+    /// MAX_CLOCK_SAMPLES. Returns false, adding nothing, if the pong was received before it was sent.
+    /// ```
+    /// use immie2d_shared::engine_types::time_sync::TimeSyncPing;
+    /// use immie2d_client::network::clock_sync::ClockSync;
+    ///
+    /// let mut sync = ClockSync::new();
+    /// assert_eq!(sync.get_offset(), None);
+    /// // The server clock is 5000 ahead. The first ping is delayed on the way back.
+    /// sync.add_pong(TimeSyncPing { client_send: 1000 }.respond(6100), 1900);
+    /// sync.add_pong(TimeSyncPing { client_send: 2000 }.respond(7100), 2200);
+    /// assert_eq!(sync.get_offset(), Some(5000));
+    /// assert_eq!(sync.get_round_trip(), Some(200));
+    /// assert_eq!(sync.to_server_time(3000), 8000);
+    /// assert_eq!(sync.to_local_time(8000), 3000);
+    ///
+    /// assert!(!sync.add_pong(TimeSyncPing { client_send: 5000 }.respond(0), 4000));
+    /// ```
+    pub fn add_pong(&mut self, pong: TimeSyncPong, local_receive: u64) -> bool {
+        if local_receive < pong.client_send {
+            return false;
+        }
+        let midpoint = pong.client_send + (local_receive - pong.client_send) / 2;
+        let sample = ClockSample { offset: pong.server_time as i64 - midpoint as i64, round_trip: local_receive - pong.client_send };
+        if self.samples.len() == MAX_CLOCK_SAMPLES {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+        return true;
+    }
+
+    pub fn get_samples(&self) -> &VecDeque<ClockSample> {
+        return &self.samples;
+    }
+
+    fn get_best_sample(&self) -> Option<&ClockSample> {
+        return self.samples.iter().min_by_key(|sample| sample.round_trip);
+    }
+
+    /// Server clock minus the client clock, or None before any pong was received.
+    pub fn get_offset(&self) -> Option<i64> {
+        return self.get_best_sample().map(|sample| sample.offset);
+    }
+
+    /// Shortest round trip of the samples.
+    pub fn get_round_trip(&self) -> Option<u64> {
+        return self.get_best_sample().map(|sample| sample.round_trip);
+    }
+
+    /// Convert a local time to the server clock. Before any pong was received, the clocks are assumed to match.
+    pub fn to_server_time(&self, local_time: u64) -> u64 {
+        return local_time.saturating_add_signed(self.get_offset().unwrap_or(0));
+    }
+
+    /// Convert a server time to the local clock, such as for when to start an animation the server scheduled.
+    pub fn to_local_time(&self, server_time: u64) -> u64 {
+        return server_time.saturating_add_signed(-self.get_offset().unwrap_or(0));
+    }
+
+    /// Timestamp an input in server time, so the server can compensate for the time it took to arrive.
+    pub fn timestamp_input(&self, local_time: u64) -> u64 {
+        return self.to_server_time(local_time);
+    }
+
+    /// Time left until a server time, such as the deadline of a turn timer or the start of a scheduled animation.
+    /// Zero once it has passed. Call each frame to smoothly count down between server updates.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_shared::engine_types::time_sync::TimeSyncPing;
+    /// use immie2d_client::network::clock_sync::ClockSync;
+    ///
+    /// let mut sync = ClockSync::new();
+    /// sync.add_pong(TimeSyncPing { client_send: 1_000_000 }.respond(11_000_000), 1_000_000);
+    /// let deadline = 20_000_000;
+    /// assert_eq!(sync.get_time_until(deadline, 2_000_000), Duration::from_secs(8));
+    /// assert_eq!(sync.get_time_until(deadline, 2_500_000), Duration::from_millis(7500));
+    /// assert_eq!(sync.get_time_until(deadline, 10_000_000), Duration::ZERO);
+    /// ```
+    pub fn get_time_until(&self, server_time: u64, local_time: u64) -> Duration {
+        return Duration::from_micros(server_time.saturating_sub(self.to_server_time(local_time)));
+    }
+}
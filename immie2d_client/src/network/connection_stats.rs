@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use super::clock_sync::ClockSync;
+
+/// Microseconds of traffic the byte rates are averaged over.
+pub const TRAFFIC_WINDOW_MICROS: u64 = 1_000_000;
+/// How many of the most recent packet sequence numbers packet loss is measured over.
+pub const MAX_LOSS_SAMPLES: usize = 128;
+/// How many of the most recent world snapshots the snapshot delay and tick rate are measured over.
+pub const MAX_SNAPSHOT_SAMPLES: usize = 20;
+
+/* The state of a connection at some moment, for a debug overlay. Values that can't be known yet, such as the round
+trip before the first pong, are None. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct NetworkStats {
+    /// Average round trip of the recent time sync pings.
+    pub round_trip: Option<Duration>,
+    /// Fraction of the recent packets from the server that never arrived, from 0 to 1.
+    pub packet_loss: f32,
+    pub bytes_in_per_second: u64,
+    pub bytes_out_per_second: u64,
+    /// How long ago, in server time, the latest world snapshot was taken when it arrived.
+    pub snapshot_delay: Option<Duration>,
+    /// World ticks per second, measured from the recent snapshots.
+    pub tick_rate: Option<f32>
+}
+
+/* A world snapshot as received. Times are microseconds. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+struct SnapshotSample {
+    tick: u64,
+    server_time: u64,
+    local_receive: u64
+}
+
+/* Gathers the statistics of a connection as the connection code sends and receives, so a debug overlay can read
+them every frame. Times are local microseconds, as used by ClockSync. */
+pub struct ConnectionStats {
+    bytes_in: VecDeque<(u64, usize)>,
+    bytes_out: VecDeque<(u64, usize)>,
+    /// Whether each sequence number from `first_sequence` on has arrived.
+    received: VecDeque<bool>,
+    first_sequence: u32,
+    snapshots: VecDeque<SnapshotSample>
+}
+
+fn record_traffic(traffic: &mut VecDeque<(u64, usize)>, bytes: usize, local_time: u64) {
+    traffic.push_back((local_time, bytes));
+    while traffic.front().is_some_and(|(time, _)| time + TRAFFIC_WINDOW_MICROS <= local_time) {
+        traffic.pop_front();
+    }
+}
+
+fn get_rate(traffic: &VecDeque<(u64, usize)>, local_time: u64) -> u64 {
+    let bytes: usize = traffic.iter().filter(|(time, _)| time + TRAFFIC_WINDOW_MICROS > local_time).map(|(_, bytes)| *bytes).sum();
+    return bytes as u64 * 1_000_000 / TRAFFIC_WINDOW_MICROS;
+}
+
+impl ConnectionStats {
+    pub fn new() -> ConnectionStats {
+        return ConnectionStats {
+            bytes_in: VecDeque::new(),
+            bytes_out: VecDeque::new(),
+            received: VecDeque::with_capacity(MAX_LOSS_SAMPLES),
+            first_sequence: 0,
+            snapshots: VecDeque::with_capacity(MAX_SNAPSHOT_SAMPLES)
+        };
+    }
+
+    pub fn record_sent(&mut self, bytes: usize, local_time: u64) {
+        record_traffic(&mut self.bytes_out, bytes, local_time);
+    }
+
+    /// Record a packet from the server with its sequence number. Sequence numbers that were skipped count as lost
+    /// until they arrive, which they still can out of order. Sequence numbers wrap around, so a number up to half the
+    /// u32 range behind the tracked ones is an old packet, and anything else is newer.
+    /// ```
+    /// use immie2d_client::network::connection_stats::ConnectionStats;
+    ///
+    /// let mut stats = ConnectionStats::new();
+    /// // Packet 0 is lost as the sequence wraps, and an old packet arrives late
+    /// for sequence in [u32::MAX - 1, u32::MAX, 1, 2, u32::MAX - 100] {
+    ///     stats.record_received(sequence, 100, 0);
+    /// }
+    /// assert_eq!(stats.get_packet_loss(), 0.2);
+    /// stats.record_received(0, 100, 0);
+    /// assert_eq!(stats.get_packet_loss(), 0.0);
+    /// ```
+    pub fn record_received(&mut self, sequence: u32, bytes: usize, local_time: u64) {
+        record_traffic(&mut self.bytes_in, bytes, local_time);
+        if self.received.is_empty() {
+            self.first_sequence = sequence;
+        }
+        let index = sequence.wrapping_sub(self.first_sequence);
+        if index > u32::MAX / 2 {
+            // Too old to still be tracked
+            return;
+        }
+        let index = index as usize;
+        if index < self.received.len() {
+            self.received[index] = true;
+            return;
+        }
+        // A long gap is only tracked as far back as the samples go
+        let skipped = (index - self.received.len()).min(MAX_LOSS_SAMPLES - 1);
+        self.first_sequence = sequence.wrapping_sub((self.received.len() + skipped) as u32);
+        self.received.extend(std::iter::repeat_n(false, skipped));
+        self.received.push_back(true);
+        while self.received.len() > MAX_LOSS_SAMPLES {
+            self.received.pop_front();
+            self.first_sequence = self.first_sequence.wrapping_add(1);
+        }
+    }
+
+    /// Record a world snapshot the server took on a tick at a server time.
+    pub fn record_snapshot(&mut self, tick: u64, server_time: u64, local_receive: u64) {
+        if self.snapshots.len() == MAX_SNAPSHOT_SAMPLES {
+            self.snapshots.pop_front();
+        }
+        self.snapshots.push_back(SnapshotSample { tick, server_time, local_receive });
+    }
+
+    /// Fraction of the tracked packets that haven't arrived, from 0 to 1.
+    pub fn get_packet_loss(&self) -> f32 {
+        if self.received.is_empty() {
+            return 0.0;
+        }
+        return self.received.iter().filter(|received| !**received).count() as f32 / self.received.len() as f32;
+    }
+
+    /// The statistics at a local time, using the clock sync of the connection for the round trip and to compare
+    /// snapshot times to the local clock.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_shared::engine_types::time_sync::TimeSyncPing;
+    /// use immie2d_client::network::{clock_sync::ClockSync, connection_stats::ConnectionStats};
+    ///
+    /// let mut clock = ClockSync::new();
+    /// let mut stats = ConnectionStats::new();
+    /// assert_eq!(stats.get_stats(&clock, 0).round_trip, None);
+    ///
+    /// // The server clock is 5000 ahead, and pings take 40ms and 60ms
+    /// clock.add_pong(TimeSyncPing { client_send: 0 }.respond(25_000), 40_000);
+    /// clock.add_pong(TimeSyncPing { client_send: 100_000 }.respond(135_000), 160_000);
+    /// // Packet 3 is lost, and 5 arrives late
+    /// for (sequence, time) in [(1, 200_000), (2, 250_000), (4, 300_000), (6, 350_000), (5, 400_000), (7, 450_000), (8, 500_000)] {
+    ///     stats.record_received(sequence, 1000, time);
+    ///     stats.record_sent(200, time);
+    /// }
+    /// // 20 ticks a second, arriving 30ms after the server takes them
+    /// stats.record_snapshot(100, 300_000, 325_000);
+    /// stats.record_snapshot(110, 800_000, 825_000);
+    ///
+    /// let overlay = stats.get_stats(&clock, 900_000);
+    /// assert_eq!(overlay.round_trip, Some(Duration::from_millis(50)));
+    /// assert_eq!(overlay.packet_loss, 0.125);
+    /// assert_eq!(overlay.bytes_in_per_second, 7000);
+    /// assert_eq!(overlay.bytes_out_per_second, 1400);
+    /// assert_eq!(overlay.snapshot_delay, Some(Duration::from_millis(30)));
+    /// assert_eq!(overlay.tick_rate, Some(20.0));
+    ///
+    /// // Traffic older than a second no longer counts
+    /// assert_eq!(stats.get_stats(&clock, 1_350_000).bytes_in_per_second, 3000);
+    /// ```
+    pub fn get_stats(&self, clock: &ClockSync, local_time: u64) -> NetworkStats {
+        let samples = clock.get_samples();
+        let round_trip = match samples.len() {
+            0 => None,
+            count => Some(Duration::from_micros(samples.iter().map(|sample| sample.round_trip).sum::<u64>() / count as u64))
+        };
+        let snapshot_delay = self.snapshots.back().map(|latest| Duration::from_micros(clock.to_server_time(latest.local_receive).saturating_sub(latest.server_time)));
+        let tick_rate = match (self.snapshots.front(), self.snapshots.back()) {
+            (Some(first), Some(last)) if last.server_time > first.server_time => {
+                Some(last.tick.saturating_sub(first.tick) as f32 * 1_000_000.0 / (last.server_time - first.server_time) as f32)
+            },
+            _ => None
+        };
+        return NetworkStats {
+            round_trip,
+            packet_loss: self.get_packet_loss(),
+            bytes_in_per_second: get_rate(&self.bytes_in, local_time),
+            bytes_out_per_second: get_rate(&self.bytes_out, local_time),
+            snapshot_delay,
+            tick_rate
+        };
+    }
+}
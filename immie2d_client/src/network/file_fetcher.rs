@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+use std::fs;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use immie2d_shared::engine_types::file_download::download_file;
+use immie2d_shared::engine_types::file_transfer::{is_valid_transfer_name, TransferError, TransferKind};
+
+/// How long connecting to the transfer channel, or a single read or write on it, may take before the download fails.
+pub const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/* Downloads replays, maps and data pack files from the server's file transfer channel into a local directory per
+kind, off the gameplay connection. See serve_transfer() */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FileFetcher {
+    address: String,
+    directories: HashMap<TransferKind, PathBuf>
+}
+
+impl FileFetcher {
+    pub fn new(address: &str) -> FileFetcher {
+        return FileFetcher { address: address.to_string(), directories: HashMap::new() };
+    }
+
+    pub fn with_directory(mut self, kind: TransferKind, directory: PathBuf) -> FileFetcher {
+        self.directories.insert(kind, directory);
+        return self;
+    }
+
+    /// Where a downloaded file is kept. Returns TransferError::Invalid for names that would escape the directory, or
+    /// kinds without a directory.
+    pub fn get_local_path(&self, kind: TransferKind, name: &str) -> Result<PathBuf, TransferError> {
+        if !is_valid_transfer_name(name) {
+            return Err(TransferError::Invalid(format!("Invalid file name [{}]", name)));
+        }
+        let directory = self.directories.get(&kind).ok_or_else(|| TransferError::Invalid(format!("No directory to download {:?} files to", kind)))?;
+        return Ok(name.split('/').fold(directory.clone(), |path, part| path.join(part)));
+    }
+
+    /// Download a file unless it was already downloaded, resuming an earlier interrupted attempt. Returns where the
+    /// file is kept. See download_file()
+    /// ```
+    /// use std::net::TcpListener;
+    /// use std::{fs, thread};
+    /// use immie2d_shared::engine_types::file_transfer::{hash_reader, write_chunk, TransferError, TransferHeader, TransferKind, TransferRequest};
+    /// use immie2d_client::network::file_fetcher::FileFetcher;
+    ///
+    /// let root = std::env::temp_dir().join(format!("immie2d_fetch_doctest_{}", std::process::id()));
+    /// let replay = b"turn 1: ember\nturn 2: tackle\n".to_vec();
+    /// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    /// let address = listener.local_addr().unwrap().to_string();
+    /// let served = replay.clone();
+    /// let server = thread::spawn(move || {
+    ///     let (mut stream, _) = listener.accept().unwrap();
+    ///     let request = TransferRequest::read_from(&mut stream).unwrap();
+    ///     assert_eq!(request.name, "battle_1.replay");
+    ///     let header = TransferHeader { total_size: served.len() as u64, hash: hash_reader(&mut served.as_slice()).unwrap(), offset: 0 };
+    ///     TransferHeader::write_to(Some(&header), &mut stream).unwrap();
+    ///     write_chunk(&served, &mut stream).unwrap();
+    /// });
+    ///
+    /// let fetcher = FileFetcher::new(&address).with_directory(TransferKind::Replay, root.join("replays"));
+    /// let path = fetcher.fetch(TransferKind::Replay, "battle_1.replay").unwrap();
+    /// server.join().unwrap();
+    /// assert_eq!(fs::read(&path).unwrap(), replay);
+    /// // Already downloaded, so the server isn't asked again
+    /// assert_eq!(fetcher.fetch(TransferKind::Replay, "battle_1.replay"), Ok(path));
+    ///
+    /// assert!(matches!(fetcher.fetch(TransferKind::Replay, "../escape"), Err(TransferError::Invalid(_))));
+    /// assert!(matches!(fetcher.fetch(TransferKind::Map, "route_1.map"), Err(TransferError::Invalid(_))));
+    /// fs::remove_dir_all(&root).unwrap();
+    /// ```
+    pub fn fetch(&self, kind: TransferKind, name: &str) -> Result<PathBuf, TransferError> {
+        let destination = self.get_local_path(kind, name)?;
+        if destination.is_file() {
+            return Ok(destination);
+        }
+        if let Some(parent) = destination.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut stream = self.connect()?;
+        download_file(&mut stream, kind, name, &destination)?;
+        return Ok(destination);
+    }
+
+    fn connect(&self) -> Result<TcpStream, TransferError> {
+        let mut last_error = TransferError::Io(format!("No address found for [{}]", self.address));
+        for address in self.address.to_socket_addrs()? {
+            match TcpStream::connect_timeout(&address, FETCH_TIMEOUT) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(FETCH_TIMEOUT))?;
+                    stream.set_write_timeout(Some(FETCH_TIMEOUT))?;
+                    return Ok(stream);
+                },
+                Err(err) => last_error = TransferError::from(err)
+            }
+        }
+        return Err(last_error);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod clock_sync;
+pub mod connection_stats;
+pub mod file_fetcher;
@@ -0,0 +1,254 @@
+use std::path::PathBuf;
+
+use immie2d_shared::gameplay::synced_settings::{BattlePace, TextSpeed, ALL_BATTLE_PACES, ALL_TEXT_SPEEDS};
+
+use crate::config::client_config::ClientConfig;
+use crate::input::{input_action::{InputAction, ALL_INPUT_ACTIONS}, key::Key};
+
+/// How much a single left or right press changes a volume.
+pub const VOLUME_STEP: f32 = 0.1;
+
+/* A selectable row of the settings menu. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SettingsEntry {
+    Binding(InputAction),
+    TextSpeed,
+    BattlePace,
+    TutorMode,
+    CrashReports,
+    MasterVolume,
+    MusicVolume,
+    Save
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SettingsMenuState {
+    /// Moving between entries.
+    Browsing,
+    /// The next key pressed will be bound to the action.
+    AwaitingKey(InputAction),
+    Closed
+}
+
+/* What changed as the result of an input, for the UI and audio system to react to immediately. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SettingsFeedback {
+    Nothing,
+    Selected(SettingsEntry),
+    AwaitingKey(InputAction),
+    Rebound { action: InputAction, key: Key },
+    /// The key is already used by another action, so the binding was not changed.
+    BindingConflict { action: InputAction, key: Key, conflicting: InputAction },
+    VolumeChanged { entry: SettingsEntry, volume: f32 },
+    TextSpeedChanged(TextSpeed),
+    BattlePaceChanged(BattlePace),
+    TutorModeChanged(bool),
+    CrashReportsChanged(bool),
+    Saved,
+    SaveFailed,
+    /// The menu was closed without saving, restoring the config from when it was opened.
+    Cancelled
+}
+
+/* Settings menu state machine. Edits apply to the live config straight away and are only written to disk when saved.
+Saving an edit to a synced setting marks the synced settings as modified, so they win the next merge with the
+player's profile. */
+pub struct SettingsMenu {
+    config: ClientConfig,
+    original_config: ClientConfig,
+    config_path: PathBuf,
+    entries: Vec<SettingsEntry>,
+    selected: usize,
+    state: SettingsMenuState
+}
+
+impl SettingsMenu {
+    pub fn new(config: ClientConfig, config_path: PathBuf) -> SettingsMenu {
+        let mut entries: Vec<SettingsEntry> = ALL_INPUT_ACTIONS.iter().map(|action| SettingsEntry::Binding(*action)).collect();
+        entries.push(SettingsEntry::TextSpeed);
+        entries.push(SettingsEntry::BattlePace);
+        entries.push(SettingsEntry::TutorMode);
+        entries.push(SettingsEntry::CrashReports);
+        entries.push(SettingsEntry::MasterVolume);
+        entries.push(SettingsEntry::MusicVolume);
+        entries.push(SettingsEntry::Save);
+        return SettingsMenu {
+            original_config: config.clone(),
+            config,
+            config_path,
+            entries,
+            selected: 0,
+            state: SettingsMenuState::Browsing
+        };
+    }
+
+    /// The live config, including unsaved edits.
+    pub fn get_config(&self) -> &ClientConfig {
+        return &self.config;
+    }
+
+    pub fn get_state(&self) -> SettingsMenuState {
+        return self.state;
+    }
+
+    pub fn get_entries(&self) -> &[SettingsEntry] {
+        return &self.entries;
+    }
+
+    pub fn get_selected(&self) -> SettingsEntry {
+        return self.entries[self.selected];
+    }
+
+    /// Handle a raw key press. While awaiting a rebind the key is bound directly,
+    /// otherwise it is translated through the current bindings.
+    /// ```
+    /// use std::path::PathBuf;
+    /// use immie2d_client::config::client_config::ClientConfig;
+    /// use immie2d_client::input::{input_action::InputAction, key::Key};
+    /// use immie2d_client::settings::settings_menu::{SettingsMenu, SettingsFeedback, SettingsEntry};
+    ///
+    /// let mut menu = SettingsMenu::new(ClientConfig::default(), PathBuf::from("unused.cfg"));
+    /// assert_eq!(menu.get_selected(), SettingsEntry::Binding(InputAction::MoveUp));
+    /// assert_eq!(menu.handle_key(Key::Enter), SettingsFeedback::AwaitingKey(InputAction::MoveUp));
+    /// // 's' is already bound to moving down
+    /// assert_eq!(menu.handle_key(Key::Char('s')), SettingsFeedback::BindingConflict { action: InputAction::MoveUp, key: Key::Char('s'), conflicting: InputAction::MoveDown });
+    ///
+    /// menu.handle_key(Key::Enter);
+    /// assert_eq!(menu.handle_key(Key::Up), SettingsFeedback::Rebound { action: InputAction::MoveUp, key: Key::Up });
+    /// // The rebind applies immediately, so moving down is still 's'
+    /// assert_eq!(menu.handle_key(Key::Char('s')), SettingsFeedback::Selected(SettingsEntry::Binding(InputAction::MoveDown)));
+    /// assert_eq!(menu.handle_key(Key::Escape), SettingsFeedback::Cancelled);
+    /// assert_eq!(*menu.get_config(), ClientConfig::default());
+    /// ```
+    pub fn handle_key(&mut self, key: Key) -> SettingsFeedback {
+        if let SettingsMenuState::AwaitingKey(action) = self.state {
+            self.state = SettingsMenuState::Browsing;
+            return match self.config.key_bindings.rebind(action, key) {
+                None => SettingsFeedback::Rebound { action, key },
+                Some(conflicting) => SettingsFeedback::BindingConflict { action, key, conflicting }
+            };
+        }
+        return match self.config.key_bindings.get_action(key) {
+            Some(action) => self.handle_action(action),
+            None => SettingsFeedback::Nothing
+        };
+    }
+
+    /// Handle an input action.
+    /// ```
+    /// use std::path::PathBuf;
+    /// use immie2d_client::config::client_config::ClientConfig;
+    /// use immie2d_client::input::input_action::InputAction;
+    /// use immie2d_client::settings::settings_menu::{SettingsMenu, SettingsFeedback, SettingsEntry};
+    ///
+    /// let mut menu = SettingsMenu::new(ClientConfig::default(), PathBuf::from("unused.cfg"));
+    /// // Wraps around to the last entry
+    /// menu.handle_action(InputAction::MoveUp);
+    /// assert_eq!(menu.handle_action(InputAction::MoveUp), SettingsFeedback::Selected(SettingsEntry::MusicVolume));
+    /// assert_eq!(menu.handle_action(InputAction::MoveLeft), SettingsFeedback::VolumeChanged { entry: SettingsEntry::MusicVolume, volume: 0.6 });
+    /// assert_eq!(menu.get_config().music_volume, 0.6);
+    /// ```
+    pub fn handle_action(&mut self, action: InputAction) -> SettingsFeedback {
+        if self.state == SettingsMenuState::Closed {
+            return SettingsFeedback::Nothing;
+        }
+        return match action {
+            InputAction::MoveUp => {
+                self.selected = (self.selected + self.entries.len() - 1) % self.entries.len();
+                SettingsFeedback::Selected(self.get_selected())
+            },
+            InputAction::MoveDown => {
+                self.selected = (self.selected + 1) % self.entries.len();
+                SettingsFeedback::Selected(self.get_selected())
+            },
+            InputAction::MoveLeft => self.adjust(-1),
+            InputAction::MoveRight => self.adjust(1),
+            InputAction::Confirm => match self.get_selected() {
+                SettingsEntry::Binding(bound_action) => {
+                    self.state = SettingsMenuState::AwaitingKey(bound_action);
+                    SettingsFeedback::AwaitingKey(bound_action)
+                },
+                SettingsEntry::TutorMode => {
+                    self.config.tutor_mode = !self.config.tutor_mode;
+                    SettingsFeedback::TutorModeChanged(self.config.tutor_mode)
+                },
+                SettingsEntry::CrashReports => {
+                    self.config.upload_crash_reports = !self.config.upload_crash_reports;
+                    SettingsFeedback::CrashReportsChanged(self.config.upload_crash_reports)
+                },
+                SettingsEntry::Save => self.save(),
+                _ => SettingsFeedback::Nothing
+            },
+            InputAction::Cancel | InputAction::Menu => {
+                self.config = self.original_config.clone();
+                self.state = SettingsMenuState::Closed;
+                SettingsFeedback::Cancelled
+            }
+        };
+    }
+
+    /// Step the selected entry left (-1) or right (1).
+    /// ```
+    /// use std::path::PathBuf;
+    /// use immie2d_shared::gameplay::synced_settings::TextSpeed;
+    /// use immie2d_client::config::client_config::ClientConfig;
+    /// use immie2d_client::input::input_action::InputAction;
+    /// use immie2d_client::settings::settings_menu::{SettingsMenu, SettingsFeedback, SettingsEntry};
+    ///
+    /// let path = std::env::temp_dir().join(format!("immie2d_settings_doctest_{}.cfg", std::process::id()));
+    /// let mut menu = SettingsMenu::new(ClientConfig::default(), path.clone());
+    /// while menu.get_selected() != SettingsEntry::TextSpeed {
+    ///     menu.handle_action(InputAction::MoveDown);
+    /// }
+    /// assert_eq!(menu.handle_action(InputAction::MoveRight), SettingsFeedback::TextSpeedChanged(TextSpeed::Fast));
+    /// // Doesn't wrap around
+    /// assert_eq!(menu.handle_action(InputAction::MoveRight), SettingsFeedback::TextSpeedChanged(TextSpeed::Fast));
+    /// while menu.get_selected() != SettingsEntry::TutorMode {
+    ///     menu.handle_action(InputAction::MoveDown);
+    /// }
+    /// assert_eq!(menu.handle_action(InputAction::Confirm), SettingsFeedback::TutorModeChanged(true));
+    /// assert_eq!(menu.handle_action(InputAction::MoveDown), SettingsFeedback::Selected(SettingsEntry::CrashReports));
+    /// assert_eq!(menu.handle_action(InputAction::Confirm), SettingsFeedback::CrashReportsChanged(true));
+    /// while menu.get_selected() != SettingsEntry::Save {
+    ///     menu.handle_action(InputAction::MoveDown);
+    /// }
+    /// assert_eq!(menu.handle_action(InputAction::Confirm), SettingsFeedback::Saved);
+    /// assert!(menu.get_config().synced.is_modified);
+    /// assert_eq!(ClientConfig::load(&path).unwrap(), *menu.get_config());
+    /// std::fs::remove_file(&path).unwrap();
+    /// ```
+    fn adjust(&mut self, step: i32) -> SettingsFeedback {
+        let entry = self.get_selected();
+        if entry == SettingsEntry::TextSpeed {
+            let index = ALL_TEXT_SPEEDS.iter().position(|speed| *speed == self.config.synced.text_speed).unwrap() as i32;
+            self.config.synced.text_speed = ALL_TEXT_SPEEDS[(index + step).clamp(0, ALL_TEXT_SPEEDS.len() as i32 - 1) as usize];
+            return SettingsFeedback::TextSpeedChanged(self.config.synced.text_speed);
+        }
+        if entry == SettingsEntry::BattlePace {
+            let index = ALL_BATTLE_PACES.iter().position(|pace| *pace == self.config.synced.battle_pace).unwrap() as i32;
+            self.config.synced.battle_pace = ALL_BATTLE_PACES[(index + step).clamp(0, ALL_BATTLE_PACES.len() as i32 - 1) as usize];
+            return SettingsFeedback::BattlePaceChanged(self.config.synced.battle_pace);
+        }
+        let delta = step as f32 * VOLUME_STEP;
+        let volume = match entry {
+            SettingsEntry::MasterVolume => &mut self.config.master_volume,
+            SettingsEntry::MusicVolume => &mut self.config.music_volume,
+            _ => return SettingsFeedback::Nothing
+        };
+        // Round to the step so repeated presses don't accumulate float error.
+        *volume = ((*volume + delta).clamp(0.0, 1.0) / VOLUME_STEP).round() * VOLUME_STEP;
+        return SettingsFeedback::VolumeChanged { entry, volume: *volume };
+    }
+
+    fn save(&mut self) -> SettingsFeedback {
+        if self.config.synced != self.original_config.synced {
+            self.config.synced.is_modified = true;
+        }
+        if self.config.save(&self.config_path).is_err() {
+            return SettingsFeedback::SaveFailed;
+        }
+        self.original_config = self.config.clone();
+        self.state = SettingsMenuState::Closed;
+        return SettingsFeedback::Saved;
+    }
+}
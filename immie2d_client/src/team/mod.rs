@@ -0,0 +1 @@
+pub mod team_editor;
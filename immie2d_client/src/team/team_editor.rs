@@ -0,0 +1,84 @@
+use immie2d_shared::gameplay::game_data::GameDataHandle;
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::immie::ability_edit::{AbilityEdit, AbilityEditError};
+use immie2d_shared::gameplay::immie::legality_ruleset::{LegalityRuleset, RulesetViolation};
+
+/* A team being edited on the client for a queue. Every edit rechecks the team against the queue's ruleset, with the
+same check the server enforces when queueing, so problems can be shown next to the offending Immie before the team is
+ever sent. */
+pub struct TeamEditor {
+    immies: Vec<Immie>,
+    data: GameDataHandle,
+    ruleset: LegalityRuleset,
+    errors: Vec<(usize, RulesetViolation)>
+}
+
+impl TeamEditor {
+    pub fn new(immies: Vec<Immie>, data: GameDataHandle, ruleset: LegalityRuleset) -> TeamEditor {
+        let mut editor = TeamEditor { immies, data, ruleset, errors: Vec::new() };
+        editor.recheck();
+        return editor;
+    }
+
+    pub fn get_immies(&self) -> &[Immie] {
+        return &self.immies;
+    }
+
+    /// Replace the Immie in a party slot. Will panic if the slot is out of range.
+    pub fn set_immie(&mut self, slot: usize, immie: Immie) {
+        self.immies[slot] = immie;
+        self.recheck();
+    }
+
+    /// Swap or forget an ability of the Immie in a party slot.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames, abilities::{fireball::Fireball, pursuit::Pursuit}};
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// use immie2d_shared::gameplay::item::item_map::ItemMap;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::immie::{immie::Immie, ability_edit::AbilityEdit, legality::LegalityError};
+    /// use immie2d_shared::gameplay::immie::legality_ruleset::{LegalityRuleset, RulesetPreset, RulesetViolation};
+    /// use immie2d_client::team::team_editor::TeamEditor;
+    ///
+    /// let name = |name: &str| GlobalString::new(&name.to_string());
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(SpeciesData::new(name("lavapup"), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Fireball>();
+    /// ability_map.add_ability::<Pursuit>();
+    /// let data = GameData::new(1, species_map, ability_map, ItemMap::new()).into_handle();
+    ///
+    /// let immie = Immie::new(name("lavapup"), 5, AbilityNames::new(vec![name("fireball"), name("pursuit")]));
+    /// let mut editor = TeamEditor::new(vec![immie], data, LegalityRuleset::from_preset(RulesetPreset::Standard));
+    /// assert_eq!(editor.get_slot_error(0), Some(&RulesetViolation::Illegal(LegalityError::ElementMismatch(name("pursuit")))));
+    /// editor.edit_abilities(0, AbilityEdit::Forget { slot: 1 }).unwrap();
+    /// assert!(editor.is_legal());
+    /// ```
+    pub fn edit_abilities(&mut self, slot: usize, edit: AbilityEdit) -> Result<(), AbilityEditError> {
+        let immie = self.immies.get_mut(slot).ok_or(AbilityEditError::InvalidPartySlot)?;
+        immie.apply_ability_edit(edit, self.data.get_ability_map())?;
+        self.recheck();
+        return Ok(());
+    }
+
+    /// Problems with the team as of the last edit, in slot order.
+    pub fn get_errors(&self) -> &[(usize, RulesetViolation)] {
+        return &self.errors;
+    }
+
+    /// The first problem with the Immie in a party slot.
+    pub fn get_slot_error(&self, slot: usize) -> Option<&RulesetViolation> {
+        return self.errors.iter().find(|(error_slot, _)| *error_slot == slot).map(|(_, error)| error);
+    }
+
+    /// Whether the server will accept the team.
+    pub fn is_legal(&self) -> bool {
+        return self.errors.is_empty();
+    }
+
+    fn recheck(&mut self) {
+        self.errors = self.ruleset.check_team(&self.immies, &self.data);
+    }
+}
@@ -0,0 +1,40 @@
+use immie2d_shared::gameplay::ability::ability_map::AbilityMap;
+use immie2d_shared::gameplay::battle::{battle::Battle, battle_command::BattleCommand, battle_evaluation::choose_heuristic_command};
+
+use crate::config::client_config::ClientConfig;
+
+/// The command tutor mode suggests to the player controlling a side, using the same evaluation as the AI so hints
+/// match what the server's battle logic rewards. None if tutor mode is off or there is nothing to suggest.
+/// ```
+/// # use immie2d_shared::engine_types::global_string::GlobalString;
+/// # use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames, abilities::{fireball::Fireball, pursuit::Pursuit}};
+/// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+/// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+/// # use immie2d_shared::gameplay::immie::immie::Immie;
+/// use immie2d_shared::gameplay::battle::{battle::Battle, battle_format::BattleFormat, battle_side::BattleSide, battler::Battler};
+/// use immie2d_shared::gameplay::battle::battle_command::BattleCommand;
+/// use immie2d_client::config::client_config::ClientConfig;
+/// use immie2d_client::tutor::battle_tutor::get_tutor_hint;
+///
+/// let name = |name: &str| GlobalString::new(&name.to_string());
+/// let species = SpeciesData::new(name("sproutle"), Elements::new(vec![ElementKind::Nature]), BaseStats::new(80, 60, 40, 70));
+/// let mut ability_map = AbilityMap::new();
+/// ability_map.add_ability::<Fireball>();
+/// ability_map.add_ability::<Pursuit>();
+/// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, AbilityNames::new(vec![name("pursuit"), name("fireball")])), &species)]);
+/// let battle = Battle::new(BattleFormat::Single, vec![side.clone(), side]);
+///
+/// let mut config = ClientConfig::default();
+/// assert_eq!(get_tutor_hint(&config, &battle, 0, &ability_map), None);
+/// config.tutor_mode = true;
+/// assert_eq!(get_tutor_hint(&config, &battle, 0, &ability_map), Some(BattleCommand::UseAbility { side: 0, ability_slot: 1, target_side: 1 }));
+/// ```
+pub fn get_tutor_hint(config: &ClientConfig, battle: &Battle, side: usize, ability_map: &AbilityMap) -> Option<BattleCommand> {
+    if !config.tutor_mode {
+        return None;
+    }
+    return match choose_heuristic_command(battle, side, ability_map) {
+        BattleCommand::EndTurn => None,
+        command => Some(command)
+    };
+}
@@ -0,0 +1,154 @@
+use std::collections::VecDeque;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId, entry_hazard::HazardKind, state_diff::BattlerStatus};
+use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+use immie2d_shared::localization::message_format::{MessageError, MessageValue};
+
+use super::text_box::TextBox;
+
+/// Most messages a battle's log keeps, dropping the oldest.
+pub const MAX_LOG_MESSAGES: usize = 100;
+
+/* The catalog key and arguments of the message shown for a battle event. Battlers are the `battler` argument, named by
+the `species.<name>.name` entry of their species. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BattleMessage {
+    pub key: String,
+    pub values: Vec<(&'static str, MessageValue)>
+}
+
+fn get_species_noun(species: GlobalString) -> MessageValue {
+    return MessageValue::Noun(format!("species.{}.name", species.to_string()));
+}
+
+fn get_status_name(status: BattlerStatus) -> &'static str {
+    return match status {
+        BattlerStatus::Protected => "protected",
+        BattlerStatus::Substitute => "substitute",
+        BattlerStatus::SemiInvulnerable => "semi_invulnerable"
+    };
+}
+
+fn get_hazard_name(kind: HazardKind) -> &'static str {
+    return match kind {
+        HazardKind::Spikes => "spikes",
+        HazardKind::Webs => "webs"
+    };
+}
+
+impl BattleMessage {
+    /// The message for an event, or None for events that are only animated, such as a capture device shaking.
+    /// get_species gives the species a battler is shown as.
+    pub fn from_event(event: &BattleEvent, get_species: &dyn Fn(BattlerId) -> GlobalString) -> Option<BattleMessage> {
+        let about = |key: String, battler: BattlerId, mut values: Vec<(&'static str, MessageValue)>| {
+            values.insert(0, ("battler", get_species_noun(get_species(battler))));
+            return Some(BattleMessage { key, values });
+        };
+        return match *event {
+            BattleEvent::Transformed { battler, .. } => about("battle.transformed".to_string(), battler, vec![]),
+            BattleEvent::Attuned { battler, element } => {
+                about("battle.attuned".to_string(), battler, vec![("element", MessageValue::Noun(format!("element.{}.name", element.get_name())))])
+            },
+            BattleEvent::Captured { battler } => about("battle.captured".to_string(), battler, vec![]),
+            BattleEvent::CaptureFailed { battler } => about("battle.capture_failed".to_string(), battler, vec![]),
+            BattleEvent::Switched { side, slot } => about("battle.switched".to_string(), BattlerId::new(side, slot), vec![]),
+            BattleEvent::AbilityBlocked { defender, .. } => about("battle.blocked".to_string(), defender, vec![]),
+            BattleEvent::AbilityMissed { attacker, .. } => about("battle.missed".to_string(), attacker, vec![]),
+            BattleEvent::Damaged { battler, amount, .. } => about("battle.damaged".to_string(), battler, vec![("amount", MessageValue::Number(amount as u64))]),
+            BattleEvent::Healed { battler, amount, .. } => about("battle.healed".to_string(), battler, vec![("amount", MessageValue::Number(amount as u64))]),
+            BattleEvent::Fainted { battler } => about("battle.fainted".to_string(), battler, vec![]),
+            BattleEvent::IdentityCopied { battler, species, .. } => about("battle.identity_copied".to_string(), battler, vec![("species", get_species_noun(species))]),
+            BattleEvent::AbilityCopied { battler, ability, .. } => {
+                about("battle.ability_copied".to_string(), battler, vec![("ability", MessageValue::Noun(format!("ability.{}.name", ability.to_string())))])
+            },
+            BattleEvent::CopyFailed { battler, .. } => about("battle.copy_failed".to_string(), battler, vec![]),
+            BattleEvent::HazardSet { side, kind, .. } => {
+                Some(BattleMessage { key: format!("battle.{}_set", get_hazard_name(kind)), values: vec![("side", MessageValue::Number(side as u64 + 1))] })
+            },
+            BattleEvent::HazardsCleared { side } => Some(BattleMessage { key: "battle.hazards_cleared".to_string(), values: vec![("side", MessageValue::Number(side as u64 + 1))] }),
+            BattleEvent::HazardTriggered { battler, kind, .. } => about(format!("battle.{}_triggered", get_hazard_name(kind)), battler, vec![]),
+            BattleEvent::EvasionStageChanged { battler, stage } => {
+                let key = match stage {
+                    0 => "battle.evasion_reset",
+                    stage if stage > 0 => "battle.evasion_raised",
+                    _ => "battle.evasion_lowered"
+                };
+                about(key.to_string(), battler, vec![("stages", MessageValue::Number(stage.unsigned_abs() as u64))])
+            },
+            BattleEvent::StatusChanged { battler, status, is_active } => {
+                about(format!("battle.{}_{}", get_status_name(status), if is_active { "started" } else { "ended" }), battler, vec![])
+            },
+            BattleEvent::BattleEnded { winner: Some(side) } => Some(BattleMessage { key: "battle.won".to_string(), values: vec![("side", MessageValue::Number(side as u64 + 1))] }),
+            BattleEvent::BattleEnded { winner: None } => Some(BattleMessage { key: "battle.draw".to_string(), values: vec![] }),
+            _ => None
+        };
+    }
+
+    pub fn format(&self, catalog: &LocalizationCatalog) -> Result<String, MessageError> {
+        return catalog.format(&self.key, &self.values);
+    }
+}
+
+/* The text of a battle as it plays, shown in a text box a message at a time and kept for scrolling back through. */
+pub struct BattleMessageLog {
+    messages: VecDeque<String>
+}
+
+impl BattleMessageLog {
+    pub fn new() -> BattleMessageLog {
+        return BattleMessageLog { messages: VecDeque::new() };
+    }
+
+    /// Log the message for an event once it is played, and queue it in a text box. Events without a message are
+    /// skipped. A message the catalog can't format isn't logged or shown.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    /// use immie2d_shared::gameplay::synced_settings::TextSpeed;
+    /// use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+    /// use immie2d_shared::localization::message_format::MessageError;
+    /// use immie2d_client::ui::{battle_message_log::BattleMessageLog, text_box::{TextBox, TextBoxLayout}};
+    ///
+    /// let catalog = LocalizationCatalog::from_json("en", r#"{
+    ///     "species.lavapup.name": "Lavapup",
+    ///     "battle.damaged": "{battler} lost {amount} health!",
+    ///     "battle.fainted": "{battler} fainted!"
+    /// }"#).unwrap();
+    /// let species = |_: BattlerId| GlobalString::new(&"lavapup".to_string());
+    /// let battler = BattlerId::new(1, 0);
+    /// let mut log = BattleMessageLog::new();
+    /// let mut text_box = TextBox::new(TextBoxLayout::default(), TextSpeed::Fast);
+    ///
+    /// log.add_event(&BattleEvent::Damaged { battler, amount: 12, remaining_health: 0 }, &catalog, &species, &mut text_box).unwrap();
+    /// log.add_event(&BattleEvent::TurnEnded { turn: 1 }, &catalog, &species, &mut text_box).unwrap();
+    /// log.add_event(&BattleEvent::Fainted { battler }, &catalog, &species, &mut text_box).unwrap();
+    /// assert_eq!(log.get_messages(), vec!["Lavapup lost 12 health!", "Lavapup fainted!"]);
+    /// assert_eq!(text_box.get_remaining_pages(), 1);
+    ///
+    /// let missing = log.add_event(&BattleEvent::BattleEnded { winner: Some(0) }, &catalog, &species, &mut text_box);
+    /// assert_eq!(missing, Err(MessageError::MissingEntry("battle.won".to_string())));
+    /// assert_eq!(log.get_messages().len(), 2);
+    /// ```
+    pub fn add_event(&mut self, event: &BattleEvent, catalog: &LocalizationCatalog, get_species: &dyn Fn(BattlerId) -> GlobalString, text_box: &mut TextBox) -> Result<(), MessageError> {
+        let Some(message) = BattleMessage::from_event(event, get_species) else {
+            return Ok(());
+        };
+        let text = message.format(catalog)?;
+        text_box.push_message(&text);
+        if self.messages.len() == MAX_LOG_MESSAGES {
+            self.messages.pop_front();
+        }
+        self.messages.push_back(text);
+        return Ok(());
+    }
+
+    /// Every message kept, oldest first.
+    pub fn get_messages(&self) -> Vec<&str> {
+        return self.messages.iter().map(|message| message.as_str()).collect();
+    }
+
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+}
@@ -0,0 +1,2 @@
+pub mod text_box;
+pub mod battle_message_log;
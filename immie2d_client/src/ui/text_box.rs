@@ -0,0 +1,221 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use immie2d_shared::gameplay::synced_settings::TextSpeed;
+use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+use immie2d_shared::localization::message_format::{MessageError, MessageValue};
+
+use crate::input::input_action::InputAction;
+
+/* How much text fits in a text box, in characters. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TextBoxLayout {
+    pub columns: usize,
+    pub rows: usize
+}
+
+impl TextBoxLayout {
+    /// The dialogue box at the bottom of the screen.
+    pub fn default() -> TextBoxLayout {
+        return TextBoxLayout { columns: 40, rows: 2 };
+    }
+
+    /// Split a message into pages of lines. Lines wrap at spaces, words longer than a line are broken across lines,
+    /// and newlines in the message always start a new line.
+    /// Will panic if the layout has no columns or rows.
+    /// ```
+    /// use immie2d_client::ui::text_box::TextBoxLayout;
+    ///
+    /// let layout = TextBoxLayout { columns: 12, rows: 2 };
+    /// assert_eq!(layout.paginate("The wild lavapup used fireball!\nIt's super effective!"), vec![
+    ///     vec!["The wild".to_string(), "lavapup used".to_string()],
+    ///     vec!["fireball!".to_string(), "It's super".to_string()],
+    ///     vec!["effective!".to_string()]
+    /// ]);
+    /// assert_eq!(layout.paginate("Aaaaaaaaaaaaaaaah"), vec![vec!["Aaaaaaaaaaaa".to_string(), "aaaah".to_string()]]);
+    /// assert!(layout.paginate("").is_empty());
+    /// ```
+    pub fn paginate(&self, text: &str) -> Vec<Vec<String>> {
+        assert!(self.columns > 0 && self.rows > 0, "Text box layout {:?} has no room for text", self);
+        let mut lines = Vec::new();
+        for paragraph in text.lines() {
+            let mut line = String::new();
+            for word in paragraph.split_whitespace() {
+                let mut word: Vec<char> = word.chars().collect();
+                let line_length = line.chars().count();
+                if line_length > 0 && line_length + 1 + word.len() <= self.columns {
+                    line.push(' ');
+                    line.extend(word.iter());
+                    continue;
+                }
+                if line_length > 0 {
+                    lines.push(std::mem::take(&mut line));
+                }
+                while word.len() > self.columns {
+                    lines.push(word.drain(..self.columns).collect());
+                }
+                line.extend(word.iter());
+            }
+            if !line.is_empty() {
+                lines.push(line);
+            }
+        }
+        return lines.chunks(self.rows).map(|page| page.to_vec()).collect();
+    }
+}
+
+/* What an input did to a text box, for the UI and audio to react to. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TextBoxFeedback {
+    Nothing,
+    /// The rest of the page was shown at once, skipping the typewriter.
+    Revealed,
+    NextPage,
+    /// The last page was dismissed and the text box has nothing more to show.
+    Closed
+}
+
+/* A dialogue box revealing messages a character at a time, like a typewriter. Messages too long for the box are split
+into pages, and each page waits for the player to advance once revealed. Confirm shows the rest of a page being
+revealed or advances past a revealed one. Cancel only skips the typewriter, so mashing it never skips unread text. */
+pub struct TextBox {
+    layout: TextBoxLayout,
+    /// 0 reveals whole pages at once.
+    characters_per_second: u32,
+    pages: VecDeque<Vec<String>>,
+    /// How long the current page has been revealing for.
+    elapsed: Duration,
+    is_revealed: bool
+}
+
+impl TextBox {
+    pub fn new(layout: TextBoxLayout, speed: TextSpeed) -> TextBox {
+        return TextBox { layout, characters_per_second: speed.get_characters_per_second(), pages: VecDeque::new(), elapsed: Duration::ZERO, is_revealed: false };
+    }
+
+    /// Reveal at a speed other than the player's text speed, such as 0 for text that appears at once.
+    pub fn with_characters_per_second(mut self, characters_per_second: u32) -> TextBox {
+        self.characters_per_second = characters_per_second;
+        return self;
+    }
+
+    /// Change how fast text is revealed, such as when the player changes their text speed.
+    pub fn set_speed(&mut self, speed: TextSpeed) {
+        self.characters_per_second = speed.get_characters_per_second();
+    }
+
+    pub fn get_layout(&self) -> TextBoxLayout {
+        return self.layout;
+    }
+
+    /// Queue a message to show once the messages before it are dismissed. It always starts on a new page.
+    pub fn push_message(&mut self, text: &str) {
+        self.pages.extend(self.layout.paginate(text));
+    }
+
+    /// Queue a message from the catalog, formatted with values. See LocalizationCatalog::format()
+    pub fn push_localized(&mut self, catalog: &LocalizationCatalog, key: &str, values: &[(&str, MessageValue)]) -> Result<(), MessageError> {
+        self.push_message(&catalog.format(key, values)?);
+        return Ok(());
+    }
+
+    /// Whether there is a page to show.
+    pub fn is_open(&self) -> bool {
+        return !self.pages.is_empty();
+    }
+
+    /// Pages queued after the current one.
+    pub fn get_remaining_pages(&self) -> usize {
+        return self.pages.len().saturating_sub(1);
+    }
+
+    /// Reveal more of the current page as time passes.
+    pub fn update(&mut self, delta: Duration) {
+        if self.is_open() {
+            self.elapsed += delta;
+        }
+    }
+
+    fn get_page_length(&self) -> usize {
+        return self.pages.front().map(|page| page.iter().map(|line| line.chars().count()).sum()).unwrap_or(0);
+    }
+
+    fn get_revealed_count(&self) -> usize {
+        let length = self.get_page_length();
+        if self.is_revealed || self.characters_per_second == 0 {
+            return length;
+        }
+        let revealed = self.elapsed.as_micros() * self.characters_per_second as u128 / 1_000_000;
+        return (revealed as usize).min(length);
+    }
+
+    /// Whether the whole current page is shown, so the player can advance.
+    pub fn is_page_revealed(&self) -> bool {
+        return self.get_revealed_count() == self.get_page_length();
+    }
+
+    /// The lines of the current page as far as they have been revealed. Lines not reached yet are left out.
+    pub fn get_visible_lines(&self) -> Vec<String> {
+        let Some(page) = self.pages.front() else {
+            return Vec::new();
+        };
+        let mut remaining = self.get_revealed_count();
+        let mut lines = Vec::new();
+        for line in page {
+            if remaining == 0 && !lines.is_empty() {
+                break;
+            }
+            let shown: String = line.chars().take(remaining).collect();
+            remaining -= shown.chars().count();
+            lines.push(shown);
+        }
+        return lines;
+    }
+
+    /// React to an input action.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_shared::gameplay::synced_settings::TextSpeed;
+    /// use immie2d_client::input::input_action::InputAction;
+    /// use immie2d_client::ui::text_box::{TextBox, TextBoxFeedback, TextBoxLayout};
+    ///
+    /// // Normal speed reveals 40 characters a second
+    /// let mut text_box = TextBox::new(TextBoxLayout { columns: 12, rows: 1 }, TextSpeed::Normal);
+    /// text_box.push_message("Lavapup used fireball!");
+    /// assert_eq!(text_box.get_remaining_pages(), 1);
+    /// text_box.update(Duration::from_millis(250));
+    /// assert_eq!(text_box.get_visible_lines(), vec!["Lavapup us".to_string()]);
+    ///
+    /// // Cancel skips the typewriter but never the page
+    /// assert_eq!(text_box.handle_action(InputAction::Cancel), TextBoxFeedback::Revealed);
+    /// assert_eq!(text_box.handle_action(InputAction::Cancel), TextBoxFeedback::Nothing);
+    /// assert_eq!(text_box.get_visible_lines(), vec!["Lavapup used".to_string()]);
+    /// assert_eq!(text_box.handle_action(InputAction::Confirm), TextBoxFeedback::NextPage);
+    /// assert_eq!(text_box.get_visible_lines(), vec!["".to_string()]);
+    ///
+    /// // Confirm reveals the rest of a page, then advances
+    /// assert_eq!(text_box.handle_action(InputAction::Confirm), TextBoxFeedback::Revealed);
+    /// assert_eq!(text_box.get_visible_lines(), vec!["fireball!".to_string()]);
+    /// assert_eq!(text_box.handle_action(InputAction::Confirm), TextBoxFeedback::Closed);
+    /// assert!(!text_box.is_open());
+    /// assert_eq!(text_box.handle_action(InputAction::Confirm), TextBoxFeedback::Nothing);
+    /// ```
+    pub fn handle_action(&mut self, action: InputAction) -> TextBoxFeedback {
+        if !self.is_open() {
+            return TextBoxFeedback::Nothing;
+        }
+        return match action {
+            InputAction::Confirm | InputAction::Cancel if !self.is_page_revealed() => {
+                self.is_revealed = true;
+                TextBoxFeedback::Revealed
+            },
+            InputAction::Confirm => {
+                self.pages.pop_front();
+                self.elapsed = Duration::ZERO;
+                self.is_revealed = false;
+                if self.is_open() { TextBoxFeedback::NextPage } else { TextBoxFeedback::Closed }
+            },
+            _ => TextBoxFeedback::Nothing
+        };
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::world::entity_snapshot::EntitySnapshot;
+use immie2d_shared::world::tile_position::{Direction, TilePosition};
+
+use super::walk_animator::MAX_CORRECTION_DISTANCE;
+
+/* How remote entities are extrapolated between snapshots. See DeadReckoning */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DeadReckoningConfig {
+    /// Longest an entity keeps moving past its last snapshot. It then waits in place for the next one, so an entity
+    /// that stopped doesn't walk through walls while snapshots are late.
+    pub max_extrapolation: Duration,
+    /// Fastest an entity is extrapolated, in tiles per second, so snapshots bunched up by the network don't fling it.
+    pub max_speed: f32,
+    /// Fastest the error between where an entity was drawn and where a snapshot put it is blended out, in tiles per
+    /// second.
+    pub correction_speed: f32,
+    /// Errors further than this many tiles, such as warps, snap instead of blending.
+    pub snap_distance: f32
+}
+
+impl DeadReckoningConfig {
+    pub fn default() -> DeadReckoningConfig {
+        return DeadReckoningConfig { max_extrapolation: Duration::from_millis(250), max_speed: 8.0, correction_speed: 4.0, snap_distance: MAX_CORRECTION_DISTANCE };
+    }
+}
+
+struct RemoteEntity {
+    map: GlobalString,
+    tile: TilePosition,
+    facing: Direction,
+    /// In tiles per second, from the last two snapshots.
+    velocity: (f32, f32),
+    since_snapshot: Duration,
+    /// Offset from the snapshot position that is still being blended out.
+    error: (f32, f32)
+}
+
+impl RemoteEntity {
+    fn get_render_position(&self, config: &DeadReckoningConfig) -> (f32, f32) {
+        let elapsed = self.since_snapshot.min(config.max_extrapolation).as_secs_f32();
+        return (
+            self.tile.x as f32 + self.velocity.0 * elapsed + self.error.0,
+            self.tile.y as f32 + self.velocity.1 * elapsed + self.error.1
+        );
+    }
+}
+
+/* Smooths the movement of NPCs and other players between snapshots, which arrive far less often than frames are drawn.
+Each entity keeps moving at the velocity of its last two snapshots, and when the next snapshot disagrees with where it
+was extrapolated to, the difference is blended out at a clamped speed instead of snapping. Render positions are in
+tiles, like WalkAnimator. */
+pub struct DeadReckoning {
+    config: DeadReckoningConfig,
+    entities: HashMap<u32, RemoteEntity>
+}
+
+impl DeadReckoning {
+    pub fn new(config: DeadReckoningConfig) -> DeadReckoning {
+        return DeadReckoning { config, entities: HashMap::new() };
+    }
+
+    pub fn get_config(&self) -> DeadReckoningConfig {
+        return self.config;
+    }
+
+    pub fn set_config(&mut self, config: DeadReckoningConfig) {
+        self.config = config;
+    }
+
+    /// Apply the latest snapshot of an entity. Its velocity is taken from how far it moved since the previous snapshot,
+    /// and it keeps being drawn where it was, blending over to the new position.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::entity_snapshot::{EntitySnapshot, EntityKind};
+    /// use immie2d_shared::world::tile_position::{Direction, TilePosition, WorldPosition};
+    /// use immie2d_client::world::dead_reckoning::{DeadReckoning, DeadReckoningConfig};
+    ///
+    /// let town = GlobalString::new(&"town".to_string());
+    /// let at = |x: i32| EntitySnapshot { network_id: 3, kind: EntityKind::Npc, position: WorldPosition::new(town, TilePosition::new(x, 0)), facing: Direction::Right, is_visible: true };
+    /// // Extrapolates for up to 250ms and blends errors out at 4 tiles a second
+    /// let mut reckoning = DeadReckoning::new(DeadReckoningConfig::default());
+    /// reckoning.apply_snapshot(&at(0));
+    /// reckoning.update(Duration::from_millis(250));
+    /// reckoning.apply_snapshot(&at(1));
+    /// // Moved a tile in 250ms, so moves at 4 tiles a second, starting from where it was drawn
+    /// assert_eq!(reckoning.get_render_position(3), Some((0.0, 0.0)));
+    /// reckoning.update(Duration::from_millis(125));
+    /// assert_eq!(reckoning.get_render_position(3), Some((1.0, 0.0)));
+    /// reckoning.update(Duration::from_millis(125));
+    /// assert_eq!(reckoning.get_render_position(3), Some((2.0, 0.0)));
+    /// // Waits once the next snapshot is overdue
+    /// reckoning.update(Duration::from_millis(125));
+    /// assert_eq!(reckoning.get_render_position(3), Some((2.0, 0.0)));
+    ///
+    /// // Warps snap
+    /// reckoning.apply_snapshot(&at(30));
+    /// assert_eq!(reckoning.get_render_position(3), Some((30.0, 0.0)));
+    /// ```
+    pub fn apply_snapshot(&mut self, snapshot: &EntitySnapshot) {
+        let config = self.config;
+        let position = snapshot.position;
+        let fresh = RemoteEntity { map: position.map, tile: position.tile, facing: snapshot.facing, velocity: (0.0, 0.0), since_snapshot: Duration::ZERO, error: (0.0, 0.0) };
+        let Some(entity) = self.entities.get_mut(&snapshot.network_id).filter(|entity| entity.map == position.map) else {
+            self.entities.insert(snapshot.network_id, fresh);
+            return;
+        };
+        let (drawn_x, drawn_y) = entity.get_render_position(&config);
+        let moved = ((position.tile.x - entity.tile.x) as f32, (position.tile.y - entity.tile.y) as f32);
+        let interval = entity.since_snapshot.as_secs_f32();
+        let error = (drawn_x - position.tile.x as f32, drawn_y - position.tile.y as f32);
+        if interval <= 0.0 || moved.0.abs().max(moved.1.abs()) > config.snap_distance || error.0.abs().max(error.1.abs()) > config.snap_distance {
+            *entity = fresh;
+            return;
+        }
+        let clamp_speed = |speed: f32| speed.clamp(-config.max_speed, config.max_speed);
+        *entity = RemoteEntity { velocity: (clamp_speed(moved.0 / interval), clamp_speed(moved.1 / interval)), error, ..fresh };
+    }
+
+    /// Stop drawing an entity, such as when it leaves the area or despawns.
+    pub fn remove(&mut self, network_id: u32) {
+        self.entities.remove(&network_id);
+    }
+
+    /// Advance extrapolation and error correction by a wall-clock delta.
+    pub fn update(&mut self, delta: Duration) {
+        let correction = self.config.correction_speed * delta.as_secs_f32();
+        for entity in self.entities.values_mut() {
+            entity.since_snapshot += delta;
+            let distance = (entity.error.0 * entity.error.0 + entity.error.1 * entity.error.1).sqrt();
+            let scale = if distance > correction { (distance - correction) / distance } else { 0.0 };
+            entity.error = (entity.error.0 * scale, entity.error.1 * scale);
+        }
+    }
+
+    /// Where to draw an entity, in tiles.
+    pub fn get_render_position(&self, network_id: u32) -> Option<(f32, f32)> {
+        return self.entities.get(&network_id).map(|entity| entity.get_render_position(&self.config));
+    }
+
+    pub fn get_facing(&self, network_id: u32) -> Option<Direction> {
+        return self.entities.get(&network_id).map(|entity| entity.facing);
+    }
+}
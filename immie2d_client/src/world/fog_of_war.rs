@@ -0,0 +1,49 @@
+use std::collections::{HashMap, HashSet};
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::world::explored_area::ExploredAreaUpdate;
+
+/* The minimap cells the server said the player has explored on each map. Anything else is drawn under fog of war. */
+pub struct FogOfWar {
+    explored: HashMap<GlobalString, HashSet<(u32, u32)>>
+}
+
+impl FogOfWar {
+    pub fn new() -> FogOfWar {
+        return FogOfWar { explored: HashMap::new() };
+    }
+
+    /// Clear the fog over the cells of an update from the server. Returns how many cells weren't explored before, as
+    /// updates may repeat cells after reconnecting.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::explored_area::ExploredAreaUpdate;
+    /// use immie2d_client::world::fog_of_war::FogOfWar;
+    ///
+    /// let town = GlobalString::new(&"town".to_string());
+    /// let mut fog = FogOfWar::new();
+    /// assert_eq!(fog.apply_update(&ExploredAreaUpdate { map: town, cells: vec![(0, 0), (1, 0)] }), 2);
+    /// assert_eq!(fog.apply_update(&ExploredAreaUpdate { map: town, cells: vec![(1, 0), (2, 0)] }), 1);
+    /// assert!(fog.is_explored(town, 2, 0));
+    /// assert!(!fog.is_explored(town, 0, 1));
+    /// assert!(!fog.is_explored(GlobalString::new(&"cave".to_string()), 0, 0));
+    /// assert_eq!(fog.get_explored_count(town), 3);
+    /// ```
+    pub fn apply_update(&mut self, update: &ExploredAreaUpdate) -> usize {
+        let cells = self.explored.entry(update.map).or_default();
+        return update.cells.iter().filter(|cell| cells.insert(**cell)).count();
+    }
+
+    pub fn is_explored(&self, map: GlobalString, x: u32, y: u32) -> bool {
+        return self.explored.get(&map).is_some_and(|cells| cells.contains(&(x, y)));
+    }
+
+    pub fn get_explored_count(&self, map: GlobalString) -> usize {
+        return self.explored.get(&map).map_or(0, |cells| cells.len());
+    }
+
+    /// Forget every explored cell, such as when logging out.
+    pub fn clear(&mut self) {
+        self.explored.clear();
+    }
+}
@@ -0,0 +1,3 @@
+pub mod walk_animator;
+pub mod dead_reckoning;
+pub mod fog_of_war;
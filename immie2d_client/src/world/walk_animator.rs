@@ -0,0 +1,227 @@
+use std::time::Duration;
+
+use immie2d_shared::world::move_result::MoveResult;
+use immie2d_shared::world::tile_map::TileMap;
+use immie2d_shared::world::tile_position::{Direction, TilePosition};
+
+/// Tiles walked per second unless configured otherwise. See ClientConfig::walk_speed
+pub const DEFAULT_WALK_SPEED: f32 = 4.0;
+/// How long walking into a blocked tile animates for. The bump sound plays at most once per bump.
+pub const BUMP_DURATION: Duration = Duration::from_millis(250);
+/// How far towards the blocked tile the bump animation leans, in tiles.
+pub const BUMP_DISTANCE: f32 = 0.15;
+/// How long a correction from a server position update takes to blend out.
+pub const CORRECTION_DURATION: Duration = Duration::from_millis(200);
+/// Corrections further than this many tiles, such as warps, snap instead of blending.
+pub const MAX_CORRECTION_DISTANCE: f32 = 2.0;
+/// Sound cue played when walking into a blocked tile.
+pub const BUMP_SOUND: &str = "bump";
+
+/* What happened when the player tried to step. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StepOutcome {
+    /// Started walking to the neighbouring tile.
+    Moved,
+    /// The tile was blocked. Carries whether the bump sound should play, which it doesn't while already bumping.
+    Bumped { play_sound: bool },
+    /// Still walking to the previous tile.
+    Busy
+}
+
+/* Smoothly animates the local player between tiles. Movement is predicted locally, walking tile to tile at the walk
+speed, and position updates from the server blend in over CORRECTION_DURATION instead of snapping.
+Render positions are in tiles, where the centre of a tile is its integer coordinate. */
+pub struct WalkAnimator {
+    tile: TilePosition,
+    from: TilePosition,
+    facing: Direction,
+    walk_speed: f32,
+    /// Progress of the step from `from` to `tile`, from 0 to 1. 1 when standing still.
+    step_progress: f32,
+    bump_elapsed: Option<Duration>,
+    /// Offset from a server correction that is blended out over time, along with the time left to do so.
+    correction: (f32, f32),
+    correction_remaining: Duration
+}
+
+impl WalkAnimator {
+    pub fn new(tile: TilePosition, walk_speed: f32) -> WalkAnimator {
+        return WalkAnimator {
+            tile,
+            from: tile,
+            facing: Direction::Down,
+            walk_speed,
+            step_progress: 1.0,
+            bump_elapsed: None,
+            correction: (0.0, 0.0),
+            correction_remaining: Duration::ZERO
+        };
+    }
+
+    /// The tile the player is at, or walking to.
+    pub fn get_tile(&self) -> TilePosition {
+        return self.tile;
+    }
+
+    pub fn get_facing(&self) -> Direction {
+        return self.facing;
+    }
+
+    pub fn set_walk_speed(&mut self, walk_speed: f32) {
+        self.walk_speed = walk_speed;
+    }
+
+    pub fn is_walking(&self) -> bool {
+        return self.step_progress < 1.0;
+    }
+
+    pub fn is_bumping(&self) -> bool {
+        return self.bump_elapsed.is_some();
+    }
+
+    /// Try to step to a neighbouring tile, turning to face it. Walking into a blocked tile plays the bump animation.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::{tile_map::TileMap, tile_position::{TilePosition, Direction}};
+    /// use immie2d_client::world::walk_animator::{WalkAnimator, StepOutcome};
+    ///
+    /// let mut map = TileMap::new(GlobalString::new(&"town".to_string()), 4, 4);
+    /// map.set_blocked(TilePosition::new(2, 0), true);
+    /// let mut walker = WalkAnimator::new(TilePosition::new(0, 0), 4.0);
+    ///
+    /// assert_eq!(walker.try_step(Direction::Right, &map), StepOutcome::Moved);
+    /// assert_eq!(walker.try_step(Direction::Right, &map), StepOutcome::Busy);
+    /// walker.update(Duration::from_millis(125));
+    /// assert_eq!(walker.get_render_position(), (0.5, 0.0));
+    /// walker.update(Duration::from_millis(125));
+    /// assert_eq!(walker.get_render_position(), (1.0, 0.0));
+    ///
+    /// assert_eq!(walker.try_step(Direction::Right, &map), StepOutcome::Bumped { play_sound: true });
+    /// assert_eq!(walker.try_step(Direction::Right, &map), StepOutcome::Bumped { play_sound: false });
+    /// assert_eq!(walker.get_tile(), TilePosition::new(1, 0));
+    /// ```
+    pub fn try_step(&mut self, direction: Direction, map: &TileMap) -> StepOutcome {
+        if self.is_walking() {
+            return StepOutcome::Busy;
+        }
+        self.facing = direction;
+        let target = self.tile.offset(direction);
+        if map.is_blocked(target) {
+            let play_sound = self.bump_elapsed.is_none();
+            if play_sound {
+                self.bump_elapsed = Some(Duration::ZERO);
+            }
+            return StepOutcome::Bumped { play_sound };
+        }
+        self.bump_elapsed = None;
+        self.from = self.tile;
+        self.tile = target;
+        self.step_progress = 0.0;
+        return StepOutcome::Moved;
+    }
+
+    /// Advance the step, bump and correction animations by a wall-clock delta.
+    pub fn update(&mut self, delta: Duration) {
+        if self.is_walking() {
+            self.step_progress = (self.step_progress + delta.as_secs_f32() * self.walk_speed).min(1.0);
+        }
+        if let Some(elapsed) = self.bump_elapsed {
+            let elapsed = elapsed + delta;
+            self.bump_elapsed = if elapsed >= BUMP_DURATION { None } else { Some(elapsed) };
+        }
+        self.correction_remaining = self.correction_remaining.saturating_sub(delta);
+    }
+
+    /// Where to draw the player, in tiles.
+    pub fn get_render_position(&self) -> (f32, f32) {
+        let (mut x, mut y) = self.get_walk_position();
+        if let Some(elapsed) = self.bump_elapsed {
+            // Leans towards the blocked tile and back
+            let lean = BUMP_DISTANCE * (elapsed.as_secs_f32() / BUMP_DURATION.as_secs_f32() * std::f32::consts::PI).sin();
+            let (dx, dy) = get_direction_vector(self.facing);
+            x += dx * lean;
+            y += dy * lean;
+        }
+        let blend = self.get_correction_blend();
+        return (x + self.correction.0 * blend, y + self.correction.1 * blend);
+    }
+
+    /// Apply the position the server says the player is at. A small disagreement is blended out from where the player
+    /// is currently drawn, and a large one, such as a warp, snaps.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_shared::world::tile_position::TilePosition;
+    /// use immie2d_client::world::walk_animator::{WalkAnimator, CORRECTION_DURATION};
+    ///
+    /// let mut walker = WalkAnimator::new(TilePosition::new(3, 3), 4.0);
+    /// walker.apply_server_position(TilePosition::new(4, 3));
+    /// // Still drawn where it was, then blends over to the server position
+    /// assert_eq!(walker.get_render_position(), (3.0, 3.0));
+    /// walker.update(CORRECTION_DURATION / 2);
+    /// assert_eq!(walker.get_render_position(), (3.5, 3.0));
+    /// walker.update(CORRECTION_DURATION);
+    /// assert_eq!(walker.get_render_position(), (4.0, 3.0));
+    ///
+    /// walker.apply_server_position(TilePosition::new(20, 3));
+    /// assert_eq!(walker.get_render_position(), (20.0, 3.0));
+    /// ```
+    pub fn apply_server_position(&mut self, tile: TilePosition) {
+        if tile == self.tile {
+            return;
+        }
+        let (drawn_x, drawn_y) = self.get_render_position();
+        self.tile = tile;
+        self.from = tile;
+        self.step_progress = 1.0;
+        self.bump_elapsed = None;
+        let offset = (drawn_x - tile.x as f32, drawn_y - tile.y as f32);
+        if offset.0.abs().max(offset.1.abs()) > MAX_CORRECTION_DISTANCE {
+            self.correction = (0.0, 0.0);
+            self.correction_remaining = Duration::ZERO;
+            return;
+        }
+        self.correction = offset;
+        self.correction_remaining = CORRECTION_DURATION;
+    }
+
+    /// Apply the server's answer to a predicted step. A denied step is rolled back to the tile the server has the
+    /// player at, blending like any other correction.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::{tile_map::TileMap, tile_position::{TilePosition, Direction}};
+    /// use immie2d_shared::world::move_result::{MoveResult, MoveDenial};
+    /// use immie2d_client::world::walk_animator::WalkAnimator;
+    ///
+    /// let map = TileMap::new(GlobalString::new(&"town".to_string()), 4, 4);
+    /// let mut walker = WalkAnimator::new(TilePosition::new(0, 0), 4.0);
+    /// walker.try_step(Direction::Right, &map);
+    /// walker.apply_move_result(&MoveResult { network_id: 1, tile: TilePosition::new(0, 0), denial: Some(MoveDenial::Occupied) });
+    /// assert_eq!(walker.get_tile(), TilePosition::new(0, 0));
+    /// ```
+    pub fn apply_move_result(&mut self, result: &MoveResult) {
+        if !result.is_granted() {
+            self.apply_server_position(result.tile);
+        }
+    }
+
+    fn get_walk_position(&self) -> (f32, f32) {
+        let t = self.step_progress;
+        return (self.from.x as f32 + (self.tile.x - self.from.x) as f32 * t, self.from.y as f32 + (self.tile.y - self.from.y) as f32 * t);
+    }
+
+    /// How much of the correction offset is still applied, from 1 when it started to 0 once blended out.
+    fn get_correction_blend(&self) -> f32 {
+        return self.correction_remaining.as_secs_f32() / CORRECTION_DURATION.as_secs_f32();
+    }
+}
+
+/// Unit vector of a direction in tiles, with Y down.
+fn get_direction_vector(direction: Direction) -> (f32, f32) {
+    return match direction {
+        Direction::Up => (0.0, -1.0),
+        Direction::Down => (0.0, 1.0),
+        Direction::Left => (-1.0, 0.0),
+        Direction::Right => (1.0, 0.0)
+    };
+}
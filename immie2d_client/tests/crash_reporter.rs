@@ -0,0 +1,32 @@
+#![allow(clippy::needless_return)]
+
+use std::fs;
+use std::panic;
+
+use immie2d_client::config::client_config::ClientConfig;
+use immie2d_client::crash::{crash_report::CLIENT_VERSION, crash_reporter::CrashReporter, log_buffer::LogBuffer};
+
+/// Panic hooks are global to the process, so this is the only test in this file.
+#[test]
+fn installed_reporter_writes_a_report_for_every_panic() {
+    let directory = std::env::temp_dir().join(format!("immie2d_crash_hook_test_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    let logs = LogBuffer::new(3);
+    CrashReporter::new(directory.clone(), &ClientConfig::default(), logs.clone()).install();
+    logs.push("entered battle");
+
+    let result = panic::catch_unwind(|| {
+        panic!("battler {} has no species", 4);
+    });
+    assert!(result.is_err());
+
+    let reports: Vec<_> = fs::read_dir(&directory).unwrap().map(|entry| entry.unwrap().path()).collect();
+    assert_eq!(reports.len(), 1);
+    let text = fs::read_to_string(&reports[0]).unwrap();
+    assert!(text.contains(&format!("version={}\n", CLIENT_VERSION)));
+    assert!(text.contains("message=battler 4 has no species\n"));
+    assert!(text.contains("location=immie2d_client/tests/crash_reporter.rs:"));
+    assert!(text.contains("[log]\nentered battle\n[backtrace]\n"));
+    assert!(text.contains("installed_reporter_writes_a_report_for_every_panic"));
+    fs::remove_dir_all(&directory).unwrap();
+}
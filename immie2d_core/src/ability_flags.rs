@@ -0,0 +1,115 @@
+use core::ops::BitOr;
+
+/* Properties of an ability that other mechanics react to, such as sound abilities bypassing protection. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AbilityFlags(pub u32);
+
+impl AbilityFlags {
+    pub const NONE: AbilityFlags = AbilityFlags(0);
+    pub const SOUND: AbilityFlags = AbilityFlags(1 << 0);
+    pub const PROJECTILE: AbilityFlags = AbilityFlags(1 << 1);
+    pub const CONTACT: AbilityFlags = AbilityFlags(1 << 2);
+    /// Power scales with the user's bond. See get_bond_power_multiplier()
+    pub const BOND_SCALED: AbilityFlags = AbilityFlags(1 << 3);
+    /// Needed to get around the world, so an Immie can't forget it.
+    pub const CANNOT_FORGET: AbilityFlags = AbilityFlags(1 << 4);
+    /// Hits a target that is switching out before it leaves, with extra power. See Battle::resolve_turn()
+    pub const INTERCEPTS_SWITCH: AbilityFlags = AbilityFlags(1 << 5);
+    /// Element and power come from the user's individual values instead of the ability data. See resolve_ability_elements()
+    pub const HIDDEN_POWER: AbilityFlags = AbilityFlags(1 << 6);
+    /// Spends a turn charging, then hits on the next turn. See ForcedAction
+    pub const CHARGES: AbilityFlags = AbilityFlags(1 << 7);
+    /// The user must spend the turn after hitting recharging. See ForcedAction
+    pub const RECHARGES: AbilityFlags = AbilityFlags(1 << 8);
+    /// The user keeps using the ability for LOCKED_IN_TURNS turns in a row. See ForcedAction
+    pub const LOCKS_IN: AbilityFlags = AbilityFlags(1 << 9);
+    /// Hit chance ignores the target's evasion stages. See get_hit_chance()
+    pub const IGNORES_EVASION: AbilityFlags = AbilityFlags(1 << 10);
+    /// The user's next ability on the target can't miss, if used by the end of the next turn. See LockOn
+    pub const LOCKS_ON: AbilityFlags = AbilityFlags(1 << 11);
+    /// The user flies up while charging, so only HITS_AIRBORNE abilities can hit it. See SemiInvulnerability
+    pub const FLIES: AbilityFlags = AbilityFlags(1 << 12);
+    /// The user digs underground while charging, so only HITS_UNDERGROUND abilities can hit it. See SemiInvulnerability
+    pub const DIGS: AbilityFlags = AbilityFlags(1 << 13);
+    pub const HITS_AIRBORNE: AbilityFlags = AbilityFlags(1 << 14);
+    pub const HITS_UNDERGROUND: AbilityFlags = AbilityFlags(1 << 15);
+    /// The user poses as the target until it switches out, instead of hitting. See CopiedIdentity
+    pub const COPIES_TARGET: AbilityFlags = AbilityFlags(1 << 16);
+    /// The ability is replaced by the target's last used ability until the user switches out. See CopiedAbility
+    pub const COPIES_LAST_ABILITY: AbilityFlags = AbilityFlags(1 << 17);
+    /// Lays a layer of spikes on the target's side instead of hitting. See HazardKind
+    pub const SETS_SPIKES: AbilityFlags = AbilityFlags(1 << 18);
+    /// Lays webs on the target's side instead of hitting. See HazardKind
+    pub const SETS_WEBS: AbilityFlags = AbilityFlags(1 << 19);
+    /// Removes every hazard from the user's side instead of hitting. See EntryHazards
+    pub const CLEARS_HAZARDS: AbilityFlags = AbilityFlags(1 << 20);
+
+    /// Every single flag with the lowercase name used in data files, in bit order.
+    pub const NAMED: [(AbilityFlags, &'static str); 21] = [
+        (AbilityFlags::SOUND, "sound"),
+        (AbilityFlags::PROJECTILE, "projectile"),
+        (AbilityFlags::CONTACT, "contact"),
+        (AbilityFlags::BOND_SCALED, "bond_scaled"),
+        (AbilityFlags::CANNOT_FORGET, "cannot_forget"),
+        (AbilityFlags::INTERCEPTS_SWITCH, "intercepts_switch"),
+        (AbilityFlags::HIDDEN_POWER, "hidden_power"),
+        (AbilityFlags::CHARGES, "charges"),
+        (AbilityFlags::RECHARGES, "recharges"),
+        (AbilityFlags::LOCKS_IN, "locks_in"),
+        (AbilityFlags::IGNORES_EVASION, "ignores_evasion"),
+        (AbilityFlags::LOCKS_ON, "locks_on"),
+        (AbilityFlags::FLIES, "flies"),
+        (AbilityFlags::DIGS, "digs"),
+        (AbilityFlags::HITS_AIRBORNE, "hits_airborne"),
+        (AbilityFlags::HITS_UNDERGROUND, "hits_underground"),
+        (AbilityFlags::COPIES_TARGET, "copies_target"),
+        (AbilityFlags::COPIES_LAST_ABILITY, "copies_last_ability"),
+        (AbilityFlags::SETS_SPIKES, "sets_spikes"),
+        (AbilityFlags::SETS_WEBS, "sets_webs"),
+        (AbilityFlags::CLEARS_HAZARDS, "clears_hazards")
+    ];
+
+    /// Parse the lowercase name of a single flag from a data file.
+    /// ```
+    /// use immie2d_core::ability_flags::AbilityFlags;
+    /// assert_eq!(AbilityFlags::from_name("contact"), Some(AbilityFlags::CONTACT));
+    /// assert_eq!(AbilityFlags::from_name("none"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<AbilityFlags> {
+        return AbilityFlags::NAMED.iter().find(|(_, flag_name)| *flag_name == name).map(|(flag, _)| *flag);
+    }
+
+    /// Names of every set flag, in bit order.
+    /// ```
+    /// use immie2d_core::ability_flags::AbilityFlags;
+    /// assert_eq!((AbilityFlags::CONTACT | AbilityFlags::SOUND).get_names().collect::<Vec<_>>(), ["sound", "contact"]);
+    /// ```
+    pub fn get_names(&self) -> impl Iterator<Item = &'static str> + '_ {
+        return AbilityFlags::NAMED.iter().filter(|(flag, _)| self.contains(*flag)).map(|(_, name)| *name);
+    }
+
+    /// Check if every flag of other is set.
+    /// ```
+    /// use immie2d_core::ability_flags::AbilityFlags;
+    /// let flags = AbilityFlags::SOUND | AbilityFlags::CONTACT;
+    /// assert!(flags.contains(AbilityFlags::SOUND));
+    /// assert!(!flags.contains(AbilityFlags::PROJECTILE));
+    /// ```
+    pub fn contains(&self, other: AbilityFlags) -> bool {
+        return self.0 & other.0 == other.0;
+    }
+
+    /// Check if any flag of other is set.
+    pub fn intersects(&self, other: AbilityFlags) -> bool {
+        return self.0 & other.0 != 0;
+    }
+}
+
+impl BitOr for AbilityFlags {
+    type Output = AbilityFlags;
+
+    fn bitor(self, rhs: AbilityFlags) -> AbilityFlags {
+        return AbilityFlags(self.0 | rhs.0);
+    }
+}
@@ -0,0 +1,23 @@
+use crate::ability_flags::AbilityFlags;
+
+/// Most evasion stages a battler can gain or lose.
+pub const MAX_EVASION_STAGE: i32 = 6;
+
+/// Percent chance of an ability hitting a defender with some evasion stages. Each stage of evasion divides the chance
+/// by an extra third, and each stage below 0 multiplies it by an extra third. Abilities flagged
+/// AbilityFlags::IGNORES_EVASION ignore every stage.
+/// ```
+/// use immie2d_core::ability_flags::AbilityFlags;
+/// use immie2d_core::accuracy::{get_hit_chance, MAX_EVASION_STAGE};
+///
+/// assert_eq!(get_hit_chance(90, 0, AbilityFlags::NONE), 90);
+/// assert_eq!(get_hit_chance(90, 3, AbilityFlags::NONE), 45);
+/// assert_eq!(get_hit_chance(90, 3, AbilityFlags::IGNORES_EVASION), 90);
+/// assert_eq!(get_hit_chance(60, -3, AbilityFlags::NONE), 100);
+/// assert_eq!(get_hit_chance(u32::MAX, -MAX_EVASION_STAGE, AbilityFlags::NONE), 100);
+/// ```
+pub fn get_hit_chance(accuracy: u32, evasion_stage: i32, ability_flags: AbilityFlags) -> u32 {
+    let stage = if ability_flags.contains(AbilityFlags::IGNORES_EVASION) { 0 } else { evasion_stage.clamp(-MAX_EVASION_STAGE, MAX_EVASION_STAGE) };
+    let (numerator, denominator) = if stage >= 0 { (3, 3 + stage as u32) } else { (3 + stage.unsigned_abs(), 3) };
+    return (accuracy.saturating_mul(numerator) / denominator).min(100);
+}
@@ -0,0 +1,52 @@
+use crate::elements::elements_data::Elements;
+use crate::elements::type_chart::get_elements_effectiveness;
+
+/// Damage multiplier when an attacker uses an ability sharing one of its own elements.
+pub const SAME_ELEMENT_BONUS: f32 = 1.5;
+
+/// Type chart multiplier of every element of an ability against a defender's elements.
+/// ```
+/// use immie2d_core::damage::get_combined_effectiveness;
+/// use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
+///
+/// let defender = Elements::new(vec![ElementKind::Nature, ElementKind::Metal]);
+/// assert_eq!(get_combined_effectiveness(&Elements::new(vec![ElementKind::Fire]), &defender), 4.0);
+/// assert_eq!(get_combined_effectiveness(&Elements::new(vec![ElementKind::Fire, ElementKind::Water]), &defender), 2.0);
+/// ```
+pub fn get_combined_effectiveness(ability_elements: &Elements, defender_elements: &Elements) -> f32 {
+    return ability_elements.iter().map(|element| get_elements_effectiveness(element, defender_elements)).product();
+}
+
+/* Each intermediate value of a damage calculation, for inspecting how the damage was reached. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DamageBreakdown {
+    pub level_factor: f32,
+    /// Damage before any multipliers are applied.
+    pub base: f32,
+    pub effectiveness: f32,
+    pub multiplier: f32,
+    pub damage: u32
+}
+
+/// Calculate damage from its inputs, keeping every intermediate value. Abilities that have any effect always deal at
+/// least 1 damage. Multiplier is every multiplier other than effectiveness, such as the same element bonus.
+/// ```
+/// use immie2d_core::damage::get_damage_breakdown;
+///
+/// let breakdown = get_damage_breakdown(50, 40.0, 60, 60, 2.0, 1.5);
+/// assert_eq!(breakdown.level_factor, 22.0);
+/// assert_eq!(breakdown.damage, (breakdown.base * 2.0 * 1.5) as u32);
+/// assert_eq!(get_damage_breakdown(50, 40.0, 60, 60, 0.0, 1.5).damage, 0);
+/// assert_eq!(get_damage_breakdown(1, 1.0, 1, 999, 0.5, 1.0).damage, 1);
+/// ```
+pub fn get_damage_breakdown(attacker_level: u32, power: f32, attack: u32, defense: u32, effectiveness: f32, multiplier: f32) -> DamageBreakdown {
+    let level_factor = (2.0 * attacker_level as f32) / 5.0 + 2.0;
+    let base = (level_factor * power * attack as f32 / defense.max(1) as f32) / 50.0 + 2.0;
+    let damage = if effectiveness == 0.0 || power <= 0.0 {
+        0
+    }
+    else {
+        ((base * effectiveness * multiplier) as u32).max(1)
+    };
+    return DamageBreakdown { level_factor, base, effectiveness, multiplier, damage };
+}
@@ -1,4 +1,5 @@
-use std::fmt;
+use alloc::vec::Vec;
+use core::fmt;
 
 use super::element_kinds::ElementKind;
 use super::element_kinds::ELEMENT_COUNT;
@@ -13,7 +14,7 @@ pub struct Elements {
 impl Elements {
     /// We create an instance of Elements using a vector of ElementKind.
     /// ```
-    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
     /// 
     /// let elements = Elements::new(vec![ElementKind::Fire, ElementKind::Standard]);
     /// let other_elements = Elements::new(vec![ElementKind::Water]);
@@ -21,13 +22,13 @@ impl Elements {
     /// The elements will be set in the vec order.
     /// Elements::new() will not accept duplicate entries and will panic.
     /// ``` should_panic
-    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
     /// // Will panic
     /// let elements = Elements::new(vec![ElementKind::Fire, ElementKind::Standard, ElementKind::Fire]);
     /// ```
     /// You also cannot use ElementKind::Invalid. Doing so will cause a panic.
     /// ``` should_panic
-    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
     /// // Will panic
     /// let elements = Elements::new(vec![ElementKind::Invalid]);
     /// ```
@@ -46,14 +47,14 @@ impl Elements {
 
     /// Check if the Elements instance has a specific Elements.
     /// ```
-    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
     /// let elements = Elements::new(vec![ElementKind::Fire]);
     /// let is_Elements_present = elements.has_elements(ElementKind::Fire);
     /// assert!(is_Elements_present);
     /// ```
     /// It will check through the entire array.
     /// ```
-    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
     /// let elements = Elements::new(vec![ElementKind::Water, ElementKind::Metal, ElementKind::Dragon]);
     /// assert!(elements.has_elements(ElementKind::Dragon));
     /// ```
@@ -66,21 +67,21 @@ impl Elements {
 
     /// Adds a ElementKind to a mutable instance of Elements.
     /// ```
-    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
     /// let mut elements = Elements::new(vec![ElementKind::Ground]);
     /// elements.add_elements(ElementKind::Water);
     /// assert!(elements.has_elements(ElementKind::Water));
     /// ```
     /// Will panic if the Elements is already present, as duplicates are not allowed
     /// ``` should_panic
-    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
     /// let mut elements = Elements::new(vec![ElementKind::Air]);
     /// // Will panic
     /// elements.add_elements(ElementKind::Air);
     /// ```
     /// Will also panic if the Elements enum variant used is ElementKind::Invalid
     /// ``` should_panic
-    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
     /// let mut elements = Elements::new(vec![ElementKind::Fire]);
     /// // Will panic
     /// elements.add_elements(ElementKind::Invalid);
@@ -95,7 +96,7 @@ impl Elements {
 
     /// Get the elements held within the Elements instance as a new vector.
     /// ```
-    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
     /// let elements = Elements::new(vec![ElementKind::Light, ElementKind::Dark]);
     /// let v = elements.get_elements();
     /// assert!(v[0] == ElementKind::Light);
@@ -111,7 +112,7 @@ impl Elements {
 
     /// Get the number of elements held within this Elements instance.
     /// ```
-    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
     /// let elements = Elements::new(vec![ElementKind::Electric, ElementKind::Air, ElementKind::Metal, ElementKind::Dragon]);
     /// assert_eq!(elements.get_elements_count(), 4);
     /// ```
@@ -121,7 +122,7 @@ impl Elements {
 
     /// Get an iterator to the elements held by this Elements instance.
     /// ```
-    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
     /// let elements = Elements::new(vec![ElementKind::Fire, ElementKind::Water, ElementKind::Nature]);
     /// for t in elements.iter() {
     ///     // Do some stuff
@@ -129,7 +130,7 @@ impl Elements {
     /// ```
     /// Will not exceed the number of elements.
     /// ```
-    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_core::elements::{elements_data::Elements, element_kinds::ElementKind};
     /// let elements = Elements::new(vec![ElementKind::Fire, ElementKind::Water, ElementKind::Nature]);
     /// let mut iterator = elements.iter();
     /// assert_eq!(iterator.next().unwrap(), ElementKind::Fire);
@@ -179,4 +180,28 @@ impl<'a> Iterator for ElementIter<'a> {
         self.index += 1;
         return Some(self.elements.elements[self.index as usize - 1]);
     }
-}
\ No newline at end of file
+}
+// A list of element names. Lists that Elements::new() would panic on are errors instead, since they may come from a
+// client or a damaged save.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Elements {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_seq(self.iter());
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Elements {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Elements, D::Error> {
+        let elements = Vec::<ElementKind>::deserialize(deserializer)?;
+        if elements.is_empty() {
+            return Err(serde::de::Error::custom("elements cannot be empty"));
+        }
+        for (i, element) in elements.iter().enumerate() {
+            if elements[..i].contains(element) {
+                return Err(serde::de::Error::custom(alloc::format!("duplicate element {}", element.get_name())));
+            }
+        }
+        return Ok(Elements::new(elements));
+    }
+}
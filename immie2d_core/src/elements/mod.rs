@@ -0,0 +1,3 @@
+pub mod elements_data;
+pub mod element_kinds;
+pub mod type_chart;
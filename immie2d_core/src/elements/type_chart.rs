@@ -0,0 +1,64 @@
+use super::element_kinds::ElementKind;
+use super::elements_data::Elements;
+
+pub const SUPER_EFFECTIVE: f32 = 2.0;
+pub const NEUTRAL: f32 = 1.0;
+pub const NOT_VERY_EFFECTIVE: f32 = 0.5;
+pub const NO_EFFECT: f32 = 0.0;
+
+/// Get the damage multiplier of an attacking element against a single defending element.
+/// ```
+/// use immie2d_core::elements::{element_kinds::ElementKind, type_chart::{get_effectiveness, SUPER_EFFECTIVE, NO_EFFECT}};
+/// assert_eq!(get_effectiveness(ElementKind::Water, ElementKind::Fire), SUPER_EFFECTIVE);
+/// assert_eq!(get_effectiveness(ElementKind::Electric, ElementKind::Ground), NO_EFFECT);
+/// ```
+pub fn get_effectiveness(attacking: ElementKind, defending: ElementKind) -> f32 {
+    use ElementKind::*;
+    return match (attacking, defending) {
+        (Standard, Metal) => NOT_VERY_EFFECTIVE,
+
+        (Fire, Nature) | (Fire, Metal) => SUPER_EFFECTIVE,
+        (Fire, Fire) | (Fire, Water) | (Fire, Ground) | (Fire, Dragon) => NOT_VERY_EFFECTIVE,
+
+        (Water, Fire) | (Water, Ground) => SUPER_EFFECTIVE,
+        (Water, Water) | (Water, Nature) | (Water, Dragon) => NOT_VERY_EFFECTIVE,
+
+        (Nature, Water) | (Nature, Ground) => SUPER_EFFECTIVE,
+        (Nature, Fire) | (Nature, Nature) | (Nature, Air) | (Nature, Metal) | (Nature, Dragon) => NOT_VERY_EFFECTIVE,
+
+        (Electric, Water) | (Electric, Air) => SUPER_EFFECTIVE,
+        (Electric, Electric) | (Electric, Nature) | (Electric, Dragon) => NOT_VERY_EFFECTIVE,
+        (Electric, Ground) => NO_EFFECT,
+
+        (Air, Nature) => SUPER_EFFECTIVE,
+        (Air, Electric) | (Air, Metal) => NOT_VERY_EFFECTIVE,
+
+        (Ground, Fire) | (Ground, Electric) | (Ground, Metal) => SUPER_EFFECTIVE,
+        (Ground, Nature) => NOT_VERY_EFFECTIVE,
+        (Ground, Air) => NO_EFFECT,
+
+        (Metal, Light) => SUPER_EFFECTIVE,
+        (Metal, Fire) | (Metal, Water) | (Metal, Electric) | (Metal, Metal) => NOT_VERY_EFFECTIVE,
+
+        (Light, Dark) | (Light, Dragon) => SUPER_EFFECTIVE,
+        (Light, Metal) | (Light, Light) => NOT_VERY_EFFECTIVE,
+
+        (Dark, Light) => SUPER_EFFECTIVE,
+        (Dark, Dark) => NOT_VERY_EFFECTIVE,
+
+        (Dragon, Dragon) => SUPER_EFFECTIVE,
+        (Dragon, Metal) => NOT_VERY_EFFECTIVE,
+
+        _ => NEUTRAL
+    };
+}
+
+/// Get the combined damage multiplier of an attacking element against every element of a defender.
+/// ```
+/// use immie2d_core::elements::{element_kinds::ElementKind, elements_data::Elements, type_chart::get_elements_effectiveness};
+/// let defender = Elements::new(vec![ElementKind::Fire, ElementKind::Metal]);
+/// assert_eq!(get_elements_effectiveness(ElementKind::Ground, &defender), 4.0);
+/// ```
+pub fn get_elements_effectiveness(attacking: ElementKind, defending: &Elements) -> f32 {
+    return defending.iter().map(|element| get_effectiveness(attacking, element)).product();
+}
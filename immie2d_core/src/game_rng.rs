@@ -0,0 +1,126 @@
+use alloc::vec::Vec;
+
+/// Amount the state advances by on every draw.
+const STATE_INCREMENT: u64 = 0x9E3779B97F4A7C15;
+
+/// Multiplicative inverse of STATE_INCREMENT modulo 2^64, found with Newton's method.
+const STATE_INCREMENT_INVERSE: u64 = {
+    let mut inverse = STATE_INCREMENT;
+    let mut i = 0;
+    while i < 6 {
+        inverse = inverse.wrapping_mul(2u64.wrapping_sub(STATE_INCREMENT.wrapping_mul(inverse)));
+        i += 1;
+    }
+    inverse
+};
+
+/* Deterministic pseudo random number generator (splitmix64). Every gameplay roll goes through
+this so that the same seed always produces the same results on every platform. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameRng {
+    state: u64
+}
+
+impl GameRng {
+    /// Create a new rng from a seed.
+    /// ```
+    /// use immie2d_core::game_rng::GameRng;
+    /// let mut a = GameRng::new(1234);
+    /// let mut b = GameRng::new(1234);
+    /// assert_eq!(a.next_u64(), b.next_u64());
+    /// ```
+    pub fn new(seed: u64) -> GameRng {
+        return GameRng { state: seed };
+    }
+
+    /// The current state. Creating an rng with the state as its seed continues the same sequence.
+    /// ```
+    /// use immie2d_core::game_rng::GameRng;
+    /// let mut rng = GameRng::new(7);
+    /// rng.next_u64();
+    /// let mut restored = GameRng::new(rng.get_state());
+    /// assert_eq!(restored.next_u64(), rng.next_u64());
+    /// ```
+    pub fn get_state(&self) -> u64 {
+        return self.state;
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(STATE_INCREMENT);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        return z ^ (z >> 31);
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        return (self.next_u64() >> 32) as u32;
+    }
+
+    /// Get a random number in the range [0, max). Will panic if max is 0.
+    /// ```
+    /// use immie2d_core::game_rng::GameRng;
+    /// let mut rng = GameRng::new(5);
+    /// for _ in 0..100 {
+    ///     assert!(rng.next_below(10) < 10);
+    /// }
+    /// ```
+    pub fn next_below(&mut self, max: u32) -> u32 {
+        assert!(max > 0, "Cannot get a random number below 0");
+        return ((self.next_u32() as u64 * max as u64) >> 32) as u32;
+    }
+
+    /// Get a random float in the range [0, 1).
+    pub fn next_f32(&mut self) -> f32 {
+        return (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32;
+    }
+
+    /// Returns true with the given probability, where 0 is never and 1 is always.
+    /// ```
+    /// use immie2d_core::game_rng::GameRng;
+    /// let mut rng = GameRng::new(5);
+    /// assert!(rng.chance(1.0));
+    /// assert!(!rng.chance(0.0));
+    /// ```
+    pub fn chance(&mut self, probability: f32) -> bool {
+        return self.next_f32() < probability;
+    }
+
+    /// Get the values drawn since an earlier copy of this rng, in the order they were drawn.
+    /// Will panic if more than max_rolls were drawn, which also catches copies of an unrelated rng.
+    /// ```
+    /// use immie2d_core::game_rng::GameRng;
+    /// let mut rng = GameRng::new(42);
+    /// let earlier = rng;
+    /// let first = rng.next_u64();
+    /// let second = rng.next_u64();
+    /// assert_eq!(rng.get_rolls_since(earlier, 8), vec![first, second]);
+    /// assert!(rng.get_rolls_since(rng, 8).is_empty());
+    /// ```
+    pub fn get_rolls_since(&self, earlier: GameRng, max_rolls: u64) -> Vec<u64> {
+        let count = self.state.wrapping_sub(earlier.state).wrapping_mul(STATE_INCREMENT_INVERSE);
+        assert!(count <= max_rolls, "{} rolls were drawn, more than the maximum of {}", count, max_rolls);
+        return self.try_get_rolls_since(earlier, max_rolls).unwrap();
+    }
+
+    /// Like get_rolls_since(), but None instead of panicking if more than max_rolls were drawn, for debugging tools
+    /// that must not crash what they inspect.
+    /// ```
+    /// use immie2d_core::game_rng::GameRng;
+    /// let mut rng = GameRng::new(42);
+    /// let earlier = rng;
+    /// rng.next_u64();
+    /// rng.next_u64();
+    /// assert_eq!(rng.try_get_rolls_since(earlier, 2).map(|rolls| rolls.len()), Some(2));
+    /// assert_eq!(rng.try_get_rolls_since(earlier, 1), None);
+    /// ```
+    pub fn try_get_rolls_since(&self, earlier: GameRng, max_rolls: u64) -> Option<Vec<u64>> {
+        let count = self.state.wrapping_sub(earlier.state).wrapping_mul(STATE_INCREMENT_INVERSE);
+        if count > max_rolls {
+            return None;
+        }
+        let mut replay = earlier;
+        return Some((0..count).map(|_| replay.next_u64()).collect());
+    }
+}
@@ -0,0 +1,19 @@
+// The codebase favours explicit returns and inherent `default()`/`to_string()` constructors.
+#![allow(
+    clippy::needless_return,
+    clippy::len_zero,
+    clippy::needless_borrow
+)]
+// Only needs alloc. See the std feature in Cargo.toml.
+#![no_std]
+
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+pub mod elements;
+pub mod ability_flags;
+pub mod status_condition;
+pub mod game_rng;
+pub mod damage;
+pub mod accuracy;
@@ -0,0 +1,23 @@
+/* A non-volatile status that stays on an Immie after battle until cured. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum StatusCondition {
+    Burn,
+    Poison,
+    Paralysis,
+    Sleep,
+    Freeze
+}
+
+impl StatusCondition {
+    /// Lowercase name used in data files and localization keys.
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            StatusCondition::Burn => "burn",
+            StatusCondition::Poison => "poison",
+            StatusCondition::Paralysis => "paralysis",
+            StatusCondition::Sleep => "sleep",
+            StatusCondition::Freeze => "freeze"
+        };
+    }
+}
@@ -0,0 +1,342 @@
+use std::io::{self, ErrorKind};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::storage::backup::{create_backup, list_backups, prune_backups, read_backup, restore_backup, BackupConfig};
+use crate::storage::player_profile::DEFAULT_RATING;
+
+use super::ban_list::{BanTarget, IpRange};
+use crate::storage::storage::Storage;
+
+/* An operator action on a player's saved profile, the ban list or storage backups. These run directly against storage,
+so the player should be offline, otherwise their next save from a running server will overwrite the change. Running
+servers pick up ban list changes the next time they load it, and should be stopped before restoring a backup. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AdminCommand {
+    Ban(PlayerId),
+    Unban(PlayerId),
+    GrantItem { player: PlayerId, item: String, count: u32 },
+    ResetRating(PlayerId),
+    /// Ban an account until a number of seconds from now.
+    TempBan { player: PlayerId, seconds: u64 },
+    /// Ban an address range, permanently or for a number of seconds.
+    BanIp { range: IpRange, seconds: Option<u64> },
+    UnbanIp(IpRange),
+    /// Lift a temporary account ban. Permanent account bans are lifted with Unban.
+    UnbanAccount(PlayerId),
+    AllowIp(IpRange),
+    DisallowIp(IpRange),
+    /// Describe every ban, allowed range and ban list change.
+    ListBans,
+    /// Back up storage now, then delete the backups the retention policy doesn't keep.
+    Backup,
+    /// Replace everything in storage with a backup archive, once the archive is verified.
+    Restore(PathBuf),
+    /// Check a backup archive is intact without restoring it.
+    VerifyBackup(PathBuf),
+    ListBackups,
+    /// Delete the backups the retention policy doesn't keep.
+    PruneBackups
+}
+
+/// Usage text for the admin subcommands.
+pub const ADMIN_USAGE: &str = "usage: immie2d_server admin [--data <directory>] <command>
+commands:
+    ban <player>
+    unban <player>
+    grant-item <player> <item> [count]
+    reset-rating <player>
+    temp-ban <player> <seconds>
+    unban-account <player>
+    ban-ip <address[/prefix]> [seconds]
+    unban-ip <address[/prefix]>
+    allow-ip <address[/prefix]>
+    disallow-ip <address[/prefix]>
+    list-bans
+    backup
+    restore <archive>
+    verify-backup <archive>
+    list-backups
+    prune-backups";
+
+fn parse_player(arg: Option<&String>) -> Result<PlayerId, String> {
+    let arg = arg.ok_or("Missing player id".to_string())?;
+    return arg.parse::<u64>().map(PlayerId).map_err(|_| format!("Invalid player id [{}]", arg));
+}
+
+fn parse_range(arg: Option<&String>) -> Result<IpRange, String> {
+    return IpRange::parse(arg.ok_or("Missing IP address".to_string())?);
+}
+
+fn parse_seconds(arg: &String) -> Result<u64, String> {
+    return match arg.parse::<u64>() {
+        Ok(seconds) if seconds > 0 => Ok(seconds),
+        _ => Err(format!("Invalid ban duration [{}], expected a number of seconds", arg))
+    };
+}
+
+fn get_unix_time() -> u64 {
+    return SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+}
+
+impl AdminCommand {
+    /// Parse the arguments after `admin`, not including the data directory option.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::admin::admin_command::AdminCommand;
+    ///
+    /// let args = |line: &str| line.split(' ').map(|arg| arg.to_string()).collect::<Vec<String>>();
+    /// assert_eq!(AdminCommand::parse(&args("ban 12")), Ok(AdminCommand::Ban(PlayerId(12))));
+    /// assert_eq!(AdminCommand::parse(&args("grant-item 3 potion 5")), Ok(AdminCommand::GrantItem { player: PlayerId(3), item: "potion".to_string(), count: 5 }));
+    /// assert_eq!(AdminCommand::parse(&args("grant-item 3 potion")), Ok(AdminCommand::GrantItem { player: PlayerId(3), item: "potion".to_string(), count: 1 }));
+    /// assert_eq!(AdminCommand::parse(&args("temp-ban 3 3600")), Ok(AdminCommand::TempBan { player: PlayerId(3), seconds: 3600 }));
+    /// assert!(matches!(AdminCommand::parse(&args("ban-ip 10.0.0.0/8")), Ok(AdminCommand::BanIp { seconds: None, .. })));
+    /// assert!(AdminCommand::parse(&args("ban-ip 10.0.0.0/40")).is_err());
+    /// assert_eq!(AdminCommand::parse(&args("restore backups/backup-1000.backup")), Ok(AdminCommand::Restore("backups/backup-1000.backup".into())));
+    /// assert!(AdminCommand::parse(&args("verify-backup")).is_err());
+    /// assert!(AdminCommand::parse(&args("ban ash")).is_err());
+    /// assert!(AdminCommand::parse(&args("delete-everything")).is_err());
+    /// ```
+    pub fn parse(args: &[String]) -> Result<AdminCommand, String> {
+        let subcommand = args.first().ok_or("Missing admin command".to_string())?;
+        let command = match subcommand.as_str() {
+            "ban" => AdminCommand::Ban(parse_player(args.get(1))?),
+            "unban" => AdminCommand::Unban(parse_player(args.get(1))?),
+            "reset-rating" => AdminCommand::ResetRating(parse_player(args.get(1))?),
+            "grant-item" => {
+                let player = parse_player(args.get(1))?;
+                let item = args.get(2).ok_or("Missing item name".to_string())?.clone();
+                let count = match args.get(3) {
+                    Some(count) => count.parse::<u32>().map_err(|_| format!("Invalid item count [{}]", count))?,
+                    None => 1
+                };
+                if count == 0 {
+                    return Err("Item count must be at least 1".to_string());
+                }
+                AdminCommand::GrantItem { player, item, count }
+            },
+            "temp-ban" => {
+                let player = parse_player(args.get(1))?;
+                let seconds = parse_seconds(args.get(2).ok_or("Missing ban duration".to_string())?)?;
+                AdminCommand::TempBan { player, seconds }
+            },
+            "unban-account" => AdminCommand::UnbanAccount(parse_player(args.get(1))?),
+            "ban-ip" => {
+                let range = parse_range(args.get(1))?;
+                let seconds = match args.get(2) {
+                    Some(seconds) => Some(parse_seconds(seconds)?),
+                    None => None
+                };
+                AdminCommand::BanIp { range, seconds }
+            },
+            "unban-ip" => AdminCommand::UnbanIp(parse_range(args.get(1))?),
+            "allow-ip" => AdminCommand::AllowIp(parse_range(args.get(1))?),
+            "disallow-ip" => AdminCommand::DisallowIp(parse_range(args.get(1))?),
+            "list-bans" => AdminCommand::ListBans,
+            "backup" => AdminCommand::Backup,
+            "restore" => AdminCommand::Restore(PathBuf::from(args.get(1).ok_or("Missing backup archive".to_string())?)),
+            "verify-backup" => AdminCommand::VerifyBackup(PathBuf::from(args.get(1).ok_or("Missing backup archive".to_string())?)),
+            "list-backups" => AdminCommand::ListBackups,
+            "prune-backups" => AdminCommand::PruneBackups,
+            _ => return Err(format!("Unknown admin command [{}]", subcommand))
+        };
+        return Ok(command);
+    }
+
+    /// Whether the command works on backups, so should be run with run_backup().
+    pub fn is_backup_command(&self) -> bool {
+        return matches!(self, AdminCommand::Backup | AdminCommand::Restore(_) | AdminCommand::VerifyBackup(_) | AdminCommand::ListBackups | AdminCommand::PruneBackups);
+    }
+
+    /// The player whose profile the command changes, or None if it only changes the ban list or backups.
+    pub fn get_player(&self) -> Option<PlayerId> {
+        return match self {
+            AdminCommand::Ban(player) | AdminCommand::Unban(player) | AdminCommand::ResetRating(player) => Some(*player),
+            AdminCommand::GrantItem { player, .. } => Some(*player),
+            _ => None
+        };
+    }
+
+    /// Apply the command to the player's saved profile or the ban list. Returns a description of what changed.
+    /// Banning or unbanning a profile is also recorded in the ban list's audit entries. Fails with ErrorKind::NotFound
+    /// if a profile command targets a player that has never been saved.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::admin::admin_command::AdminCommand;
+    /// use immie2d_server::storage::{memory_storage::MemoryStorage, player_profile::PlayerProfile, storage::Storage};
+    ///
+    /// let mut storage = MemoryStorage::new();
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// profile.rating = 1500;
+    /// storage.save_profiles(&[profile]).unwrap();
+    ///
+    /// AdminCommand::Ban(PlayerId(1)).run(&mut storage).unwrap();
+    /// AdminCommand::ResetRating(PlayerId(1)).run(&mut storage).unwrap();
+    /// AdminCommand::GrantItem { player: PlayerId(1), item: "potion".to_string(), count: 2 }.run(&mut storage).unwrap();
+    /// let profile = storage.load_profile(PlayerId(1)).unwrap().unwrap();
+    /// assert!(profile.is_banned);
+    /// assert_eq!(profile.rating, 1000);
+    /// assert_eq!(profile.inventory.get_count(GlobalString::new(&"potion".to_string())), 2);
+    /// assert!(AdminCommand::Ban(PlayerId(2)).run(&mut storage).is_err());
+    ///
+    /// AdminCommand::Unban(PlayerId(1)).run(&mut storage).unwrap();
+    /// let audit = storage.load_ban_list().unwrap().audit;
+    /// assert_eq!(audit.len(), 2);
+    /// assert_eq!(audit[0].description, "Banned player 1 (ash)");
+    /// assert_eq!(audit[1].description, "Unbanned player 1 (ash)");
+    /// ```
+    pub fn run<S: Storage + ?Sized>(&self, storage: &mut S) -> io::Result<String> {
+        let player = match self.get_player() {
+            Some(player) => player,
+            None => return self.run_ban_list(storage, get_unix_time())
+        };
+        let mut profile = match storage.load_profile(player)? {
+            Some(profile) => profile,
+            None => return Err(io::Error::new(ErrorKind::NotFound, format!("Player {} has no saved profile", player)))
+        };
+        let description = match self {
+            AdminCommand::Ban(_) => {
+                profile.is_banned = true;
+                format!("Banned player {} ({})", player, profile.name)
+            },
+            AdminCommand::Unban(_) => {
+                profile.is_banned = false;
+                format!("Unbanned player {} ({})", player, profile.name)
+            },
+            AdminCommand::GrantItem { item, count, .. } => {
+                profile.inventory.add_item(GlobalString::new(item), *count);
+                format!("Granted {} {} to player {} ({})", count, item, player, profile.name)
+            },
+            AdminCommand::ResetRating(_) => {
+                let previous = profile.rating;
+                profile.rating = DEFAULT_RATING;
+                format!("Reset rating of player {} ({}) from {} to {}", player, profile.name, previous, DEFAULT_RATING)
+            },
+            _ => unreachable!("Only profile commands have a player")
+        };
+        storage.save_profiles(&[profile])?;
+        if let AdminCommand::Ban(_) | AdminCommand::Unban(_) = self {
+            let mut bans = storage.load_ban_list()?;
+            bans.record(description.clone(), get_unix_time());
+            storage.save_ban_list(&bans)?;
+        }
+        return Ok(description);
+    }
+
+    /// Apply a ban list command at a unix time in seconds, recording the change in the ban list's audit entries.
+    /// Expired bans are dropped whenever the list is changed.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::admin::admin_command::AdminCommand;
+    /// use immie2d_server::admin::ban_list::IpRange;
+    /// use immie2d_server::storage::{memory_storage::MemoryStorage, storage::Storage};
+    ///
+    /// let mut storage = MemoryStorage::new();
+    /// let range = IpRange::parse("10.0.0.0/8").unwrap();
+    /// AdminCommand::BanIp { range, seconds: Some(60) }.run_ban_list(&mut storage, 1000).unwrap();
+    /// AdminCommand::TempBan { player: PlayerId(2), seconds: 60 }.run_ban_list(&mut storage, 1000).unwrap();
+    ///
+    /// let bans = storage.load_ban_list().unwrap();
+    /// assert!(bans.check_connection("10.4.4.4".parse().unwrap(), 1059).is_err());
+    /// assert!(bans.check_account(PlayerId(2), 1059).is_err());
+    /// assert_eq!(bans.audit.len(), 2);
+    ///
+    /// AdminCommand::UnbanIp(range).run_ban_list(&mut storage, 1010).unwrap();
+    /// assert!(AdminCommand::UnbanIp(range).run_ban_list(&mut storage, 1010).is_err());
+    /// assert!(storage.load_ban_list().unwrap().check_connection("10.4.4.4".parse().unwrap(), 1020).is_ok());
+    /// ```
+    pub fn run_ban_list<S: Storage + ?Sized>(&self, storage: &mut S, now: u64) -> io::Result<String> {
+        let mut bans = storage.load_ban_list()?;
+        if let AdminCommand::ListBans = self {
+            let mut lines: Vec<String> = bans.bans.iter().filter(|entry| !entry.is_expired(now)).map(|entry| match entry.expires_at {
+                Some(expires_at) => format!("banned {} for {} more seconds", entry.target, expires_at - now),
+                None => format!("banned {}", entry.target)
+            }).collect();
+            lines.extend(bans.allowed.iter().map(|range| format!("allowed address {}", range)));
+            lines.extend(bans.audit.iter().map(|entry| format!("[{}] {}", entry.time, entry.description)));
+            return Ok(lines.join("\n"));
+        }
+        bans.remove_expired(now);
+        let changed = match self {
+            AdminCommand::TempBan { player, seconds } => {
+                bans.ban(BanTarget::Account(*player), Some(now + seconds), now);
+                true
+            },
+            AdminCommand::BanIp { range, seconds } => {
+                bans.ban(BanTarget::Ip(*range), seconds.map(|seconds| now + seconds), now);
+                true
+            },
+            AdminCommand::UnbanIp(range) => bans.unban(BanTarget::Ip(*range), now),
+            AdminCommand::UnbanAccount(player) => bans.unban(BanTarget::Account(*player), now),
+            AdminCommand::AllowIp(range) => bans.allow(*range, now),
+            AdminCommand::DisallowIp(range) => bans.disallow(*range, now),
+            _ => return Err(io::Error::new(ErrorKind::InvalidInput, "Not a ban list command"))
+        };
+        if !changed {
+            return Err(io::Error::new(ErrorKind::NotFound, "The ban list already matches the command, so nothing was changed"));
+        }
+        let description = bans.audit.last().map(|entry| entry.description.clone()).unwrap_or_default();
+        storage.save_ban_list(&bans)?;
+        return Ok(description);
+    }
+
+    /// Apply a backup command at a unix time in seconds, with backups in the configured directory. Restoring backs
+    /// up the current storage first, so a restore of the wrong archive can be undone. Returns a description of what
+    /// was done.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::admin::admin_command::AdminCommand;
+    /// use immie2d_server::storage::{backup::{list_backups, BackupConfig}, memory_storage::MemoryStorage, player_profile::PlayerProfile, storage::Storage};
+    ///
+    /// let directory = std::env::temp_dir().join("immie2d_admin_backup_doctest");
+    /// # let _ = std::fs::remove_dir_all(&directory);
+    /// let config = BackupConfig { directory: directory.clone(), ..BackupConfig::default() };
+    /// let mut storage = MemoryStorage::new();
+    /// storage.save_profiles(&[PlayerProfile::new(PlayerId(1), "ash".to_string())]).unwrap();
+    /// AdminCommand::Backup.run_backup(&mut storage, &config, 1000).unwrap();
+    /// let archive = list_backups(&directory).unwrap()[0].path.clone();
+    /// assert!(AdminCommand::VerifyBackup(archive.clone()).run_backup(&mut storage, &config, 1100).unwrap().contains("1 profiles"));
+    ///
+    /// AdminCommand::Ban(PlayerId(1)).run(&mut storage).unwrap();
+    /// AdminCommand::Restore(archive).run_backup(&mut storage, &config, 1200).unwrap();
+    /// assert!(!storage.load_profile(PlayerId(1)).unwrap().unwrap().is_banned);
+    /// // The banned state from before the restore was kept
+    /// assert_eq!(list_backups(&directory).unwrap().len(), 2);
+    /// ```
+    pub fn run_backup<S: Storage + ?Sized>(&self, storage: &mut S, config: &BackupConfig, now: u64) -> io::Result<String> {
+        return match self {
+            AdminCommand::Backup => {
+                let backup = create_backup(storage, &config.directory, now)?;
+                let pruned = prune_backups(&config.directory, &config.retention, now)?;
+                Ok(format!("Backed up to {}, deleting {} old backups", backup.path.display(), pruned.len()))
+            },
+            AdminCommand::Restore(path) => {
+                // Verified before taking the safety backup, so a damaged archive changes nothing at all
+                read_backup(path)?;
+                let previous = create_backup(storage, &config.directory, now)?;
+                let restored = restore_backup(storage, path)?;
+                Ok(format!("Restored the backup taken at {}. Storage as it was before is backed up to {}", restored.created_at, previous.path.display()))
+            },
+            AdminCommand::VerifyBackup(path) => {
+                let (info, snapshot) = read_backup(path)?;
+                Ok(format!(
+                    "Backup taken at {} is intact, with {} profiles, {} regions and {} accounts",
+                    info.created_at, snapshot.profiles.len(), snapshot.regions.len(), snapshot.credentials.len()
+                ))
+            },
+            AdminCommand::ListBackups => {
+                let lines: Vec<String> = list_backups(&config.directory)?.iter().map(|backup| format!("[{}] {}", backup.created_at, backup.path.display())).collect();
+                Ok(lines.join("\n"))
+            },
+            AdminCommand::PruneBackups => {
+                let pruned = prune_backups(&config.directory, &config.retention, now)?;
+                Ok(format!("Deleted {} old backups", pruned.len()))
+            },
+            _ => Err(io::Error::new(ErrorKind::InvalidInput, "Not a backup command"))
+        };
+    }
+}
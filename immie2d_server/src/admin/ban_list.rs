@@ -0,0 +1,366 @@
+use std::fmt;
+use std::io::{self, ErrorKind};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::storage::player_profile::{write_string, ByteReader, PlayerProfile};
+
+const IPV4_TAG: u8 = 4;
+const IPV6_TAG: u8 = 6;
+const IP_TARGET_TAG: u8 = 0;
+const ACCOUNT_TARGET_TAG: u8 = 1;
+
+/* A single address, or a CIDR range of addresses such as `10.0.0.0/8`. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct IpRange {
+    pub address: IpAddr,
+    pub prefix_len: u8
+}
+
+impl IpRange {
+    /// A range of just one address.
+    pub fn single(address: IpAddr) -> IpRange {
+        let prefix_len = if address.is_ipv4() { 32 } else { 128 };
+        return IpRange { address, prefix_len };
+    }
+
+    /// Parse an address, or an address followed by `/` and a prefix length.
+    /// ```
+    /// use immie2d_server::admin::ban_list::IpRange;
+    ///
+    /// assert_eq!(IpRange::parse("10.0.0.0/8").unwrap().prefix_len, 8);
+    /// assert_eq!(IpRange::parse("::1").unwrap().prefix_len, 128);
+    /// assert!(IpRange::parse("10.0.0.0/33").is_err());
+    /// assert!(IpRange::parse("localhost").is_err());
+    /// ```
+    pub fn parse(text: &str) -> Result<IpRange, String> {
+        let (address_text, prefix_text) = match text.split_once('/') {
+            Some((address, prefix)) => (address, Some(prefix)),
+            None => (text, None)
+        };
+        let address = address_text.parse::<IpAddr>().map_err(|_| format!("Invalid IP address [{}]", address_text))?;
+        let range = IpRange::single(address);
+        let prefix_len = match prefix_text {
+            Some(prefix) => prefix.parse::<u8>().map_err(|_| format!("Invalid prefix length [{}]", prefix))?,
+            None => range.prefix_len
+        };
+        if prefix_len > range.prefix_len {
+            return Err(format!("Prefix length {} is too long for {}", prefix_len, address));
+        }
+        return Ok(IpRange { address, prefix_len });
+    }
+
+    /// Whether an address is within the range. IPv4 ranges never contain IPv6 addresses, and the reverse.
+    /// ```
+    /// use immie2d_server::admin::ban_list::IpRange;
+    ///
+    /// let range = IpRange::parse("192.168.4.0/22").unwrap();
+    /// assert!(range.contains("192.168.7.255".parse().unwrap()));
+    /// assert!(!range.contains("192.168.8.0".parse().unwrap()));
+    /// assert!(!range.contains("::1".parse().unwrap()));
+    /// assert!(IpRange::parse("0.0.0.0/0").unwrap().contains("8.8.8.8".parse().unwrap()));
+    /// ```
+    pub fn contains(&self, address: IpAddr) -> bool {
+        return match (self.address, address) {
+            (IpAddr::V4(range), IpAddr::V4(address)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+                u32::from(range) & mask == u32::from(address) & mask
+            },
+            (IpAddr::V6(range), IpAddr::V6(address)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len as u32).unwrap_or(0);
+                u128::from(range) & mask == u128::from(address) & mask
+            },
+            _ => false
+        };
+    }
+}
+
+impl fmt::Display for IpRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == IpRange::single(self.address) {
+            return write!(f, "{}", self.address);
+        }
+        return write!(f, "{}/{}", self.address, self.prefix_len);
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BanTarget {
+    Ip(IpRange),
+    Account(PlayerId)
+}
+
+impl fmt::Display for BanTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            BanTarget::Ip(range) => write!(f, "address {}", range),
+            BanTarget::Account(player) => write!(f, "player {}", player)
+        };
+    }
+}
+
+/* A ban on an address range or account. Temporary bans carry the unix time in seconds they expire at. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BanEntry {
+    pub target: BanTarget,
+    pub expires_at: Option<u64>
+}
+
+impl BanEntry {
+    pub fn is_expired(&self, now: u64) -> bool {
+        return self.expires_at.is_some_and(|expires_at| expires_at <= now);
+    }
+}
+
+/* A change made to a ban list, kept alongside it so operators can see who was banned when. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BanAuditEntry {
+    /// Unix time in seconds.
+    pub time: u64,
+    pub description: String
+}
+
+/* Why a connection or login was refused. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ConnectionDenial {
+    /// The allow list isn't empty and doesn't contain the address.
+    NotAllowed,
+    Banned(BanEntry)
+}
+
+/* Persistent connection filtering. Addresses are checked when a connection is accepted and accounts once they have
+authenticated. When the allow list has any ranges, only addresses within them may connect at all. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct BanList {
+    pub bans: Vec<BanEntry>,
+    pub allowed: Vec<IpRange>,
+    /// Every change, oldest first.
+    pub audit: Vec<BanAuditEntry>
+}
+
+impl BanList {
+    pub fn new() -> BanList {
+        return BanList { bans: Vec::new(), allowed: Vec::new(), audit: Vec::new() };
+    }
+
+    /// Ban a target, replacing any existing ban on exactly the same target.
+    pub fn ban(&mut self, target: BanTarget, expires_at: Option<u64>, now: u64) {
+        self.bans.retain(|entry| entry.target != target);
+        self.bans.push(BanEntry { target, expires_at });
+        let description = match expires_at {
+            Some(expires_at) => format!("Banned {} until {}", target, expires_at),
+            None => format!("Banned {}", target)
+        };
+        self.record(description, now);
+    }
+
+    /// Lift the ban on exactly a target. Returns false if it wasn't banned.
+    pub fn unban(&mut self, target: BanTarget, now: u64) -> bool {
+        let count = self.bans.len();
+        self.bans.retain(|entry| entry.target != target);
+        if self.bans.len() == count {
+            return false;
+        }
+        self.record(format!("Unbanned {}", target), now);
+        return true;
+    }
+
+    /// Add a range to the allow list. Returns false if it was already there.
+    pub fn allow(&mut self, range: IpRange, now: u64) -> bool {
+        if self.allowed.contains(&range) {
+            return false;
+        }
+        self.allowed.push(range);
+        self.record(format!("Allowed address {}", range), now);
+        return true;
+    }
+
+    /// Remove a range from the allow list. Returns false if it wasn't there.
+    pub fn disallow(&mut self, range: IpRange, now: u64) -> bool {
+        let count = self.allowed.len();
+        self.allowed.retain(|allowed| *allowed != range);
+        if self.allowed.len() == count {
+            return false;
+        }
+        self.record(format!("Removed address {} from the allow list", range), now);
+        return true;
+    }
+
+    /// Add an audit entry for a change made outside the list, such as banning a saved profile.
+    pub fn record(&mut self, description: String, now: u64) {
+        self.audit.push(BanAuditEntry { time: now, description });
+    }
+
+    /// Check whether an address may connect. Expired bans are ignored.
+    /// ```
+    /// use immie2d_server::admin::ban_list::{BanList, BanTarget, IpRange, ConnectionDenial};
+    ///
+    /// let mut bans = BanList::new();
+    /// bans.ban(BanTarget::Ip(IpRange::parse("10.1.0.0/16").unwrap()), Some(500), 100);
+    /// assert!(matches!(bans.check_connection("10.1.2.3".parse().unwrap(), 200), Err(ConnectionDenial::Banned(_))));
+    /// assert!(bans.check_connection("10.1.2.3".parse().unwrap(), 500).is_ok());
+    /// assert!(bans.check_connection("10.2.0.1".parse().unwrap(), 200).is_ok());
+    ///
+    /// bans.allow(IpRange::parse("10.0.0.0/8").unwrap(), 100);
+    /// assert_eq!(bans.check_connection("172.16.0.1".parse().unwrap(), 200), Err(ConnectionDenial::NotAllowed));
+    /// assert!(bans.check_connection("10.2.0.1".parse().unwrap(), 200).is_ok());
+    /// assert_eq!(bans.audit.len(), 2);
+    /// ```
+    pub fn check_connection(&self, address: IpAddr, now: u64) -> Result<(), ConnectionDenial> {
+        if !self.allowed.is_empty() && !self.allowed.iter().any(|range| range.contains(address)) {
+            return Err(ConnectionDenial::NotAllowed);
+        }
+        let ban = self.bans.iter().find(|entry| !entry.is_expired(now) && match entry.target {
+            BanTarget::Ip(range) => range.contains(address),
+            BanTarget::Account(_) => false
+        });
+        return match ban {
+            Some(ban) => Err(ConnectionDenial::Banned(*ban)),
+            None => Ok(())
+        };
+    }
+
+    /// Check whether an authenticated account may play. Permanent account bans are also stored on the profile,
+    /// see PlayerProfile::is_banned
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::admin::ban_list::{BanList, BanTarget};
+    ///
+    /// let mut bans = BanList::new();
+    /// bans.ban(BanTarget::Account(PlayerId(4)), Some(1000), 0);
+    /// assert!(bans.check_account(PlayerId(4), 999).is_err());
+    /// assert!(bans.check_account(PlayerId(4), 1000).is_ok());
+    /// assert!(bans.check_account(PlayerId(5), 999).is_ok());
+    /// ```
+    pub fn check_account(&self, player: PlayerId, now: u64) -> Result<(), ConnectionDenial> {
+        let ban = self.bans.iter().find(|entry| !entry.is_expired(now) && entry.target == BanTarget::Account(player));
+        return match ban {
+            Some(ban) => Err(ConnectionDenial::Banned(*ban)),
+            None => Ok(())
+        };
+    }
+
+    /// Check whether an authenticated account may play, including the permanent ban stored on its profile.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::admin::ban_list::{BanList, BanTarget, BanEntry, ConnectionDenial};
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    ///
+    /// let mut profile = PlayerProfile::new(PlayerId(4), "gary".to_string());
+    /// assert!(BanList::new().check_profile(&profile, 0).is_ok());
+    /// profile.is_banned = true;
+    /// let ban = BanEntry { target: BanTarget::Account(PlayerId(4)), expires_at: None };
+    /// assert_eq!(BanList::new().check_profile(&profile, 0), Err(ConnectionDenial::Banned(ban)));
+    /// ```
+    pub fn check_profile(&self, profile: &PlayerProfile, now: u64) -> Result<(), ConnectionDenial> {
+        if profile.is_banned {
+            return Err(ConnectionDenial::Banned(BanEntry { target: BanTarget::Account(profile.player), expires_at: None }));
+        }
+        return self.check_account(profile.player, now);
+    }
+
+    /// Remove every expired ban. Returns the bans removed.
+    pub fn remove_expired(&mut self, now: u64) -> Vec<BanEntry> {
+        let (expired, remaining) = self.bans.iter().partition(|entry| entry.is_expired(now));
+        self.bans = remaining;
+        return expired;
+    }
+
+    /// Encode the ban list in the binary format used by storage.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::admin::ban_list::{BanList, BanTarget, IpRange};
+    ///
+    /// let mut bans = BanList::new();
+    /// bans.ban(BanTarget::Ip(IpRange::parse("2001:db8::/32").unwrap()), None, 10);
+    /// bans.ban(BanTarget::Account(PlayerId(7)), Some(90), 10);
+    /// bans.allow(IpRange::parse("127.0.0.1").unwrap(), 20);
+    /// assert_eq!(BanList::from_bytes(&bans.to_bytes()).unwrap(), bans);
+    /// assert!(BanList::from_bytes(&bans.to_bytes()[..12]).is_err());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend_from_slice(&(self.bans.len() as u32).to_le_bytes());
+        for entry in self.bans.iter() {
+            match entry.target {
+                BanTarget::Ip(range) => {
+                    bytes.push(IP_TARGET_TAG);
+                    write_range(&mut bytes, range);
+                },
+                BanTarget::Account(player) => {
+                    bytes.push(ACCOUNT_TARGET_TAG);
+                    bytes.extend_from_slice(&player.0.to_le_bytes());
+                }
+            }
+            // 0 is never a valid expiry time, so it stands in for permanent bans.
+            bytes.extend_from_slice(&entry.expires_at.unwrap_or(0).to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.allowed.len() as u32).to_le_bytes());
+        for range in self.allowed.iter() {
+            write_range(&mut bytes, *range);
+        }
+        bytes.extend_from_slice(&(self.audit.len() as u32).to_le_bytes());
+        for entry in self.audit.iter() {
+            bytes.extend_from_slice(&entry.time.to_le_bytes());
+            write_string(&mut bytes, &entry.description);
+        }
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<BanList> {
+        let mut reader = ByteReader::new(bytes);
+        let mut bans = Vec::new();
+        for _ in 0..u32::from_le_bytes(reader.take_array()?) {
+            let [tag] = reader.take_array::<1>()?;
+            let target = match tag {
+                IP_TARGET_TAG => BanTarget::Ip(read_range(&mut reader)?),
+                ACCOUNT_TARGET_TAG => BanTarget::Account(PlayerId(u64::from_le_bytes(reader.take_array()?))),
+                _ => return Err(io::Error::new(ErrorKind::InvalidData, format!("Unknown ban target tag {}", tag)))
+            };
+            let expires_at = match u64::from_le_bytes(reader.take_array()?) {
+                0 => None,
+                expires_at => Some(expires_at)
+            };
+            bans.push(BanEntry { target, expires_at });
+        }
+        let mut allowed = Vec::new();
+        for _ in 0..u32::from_le_bytes(reader.take_array()?) {
+            allowed.push(read_range(&mut reader)?);
+        }
+        let mut audit = Vec::new();
+        for _ in 0..u32::from_le_bytes(reader.take_array()?) {
+            let time = u64::from_le_bytes(reader.take_array()?);
+            audit.push(BanAuditEntry { time, description: reader.take_string()? });
+        }
+        return Ok(BanList { bans, allowed, audit });
+    }
+}
+
+fn write_range(bytes: &mut Vec<u8>, range: IpRange) {
+    match range.address {
+        IpAddr::V4(address) => {
+            bytes.push(IPV4_TAG);
+            bytes.extend_from_slice(&address.octets());
+        },
+        IpAddr::V6(address) => {
+            bytes.push(IPV6_TAG);
+            bytes.extend_from_slice(&address.octets());
+        }
+    }
+    bytes.push(range.prefix_len);
+}
+
+fn read_range(reader: &mut ByteReader) -> io::Result<IpRange> {
+    let [tag] = reader.take_array::<1>()?;
+    let address = match tag {
+        IPV4_TAG => IpAddr::V4(Ipv4Addr::from(reader.take_array::<4>()?)),
+        IPV6_TAG => IpAddr::V6(Ipv6Addr::from(reader.take_array::<16>()?)),
+        _ => return Err(io::Error::new(ErrorKind::InvalidData, format!("Unknown address tag {}", tag)))
+    };
+    let [prefix_len] = reader.take_array::<1>()?;
+    if prefix_len > IpRange::single(address).prefix_len {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("Prefix length {} is too long for {}", prefix_len, address)));
+    }
+    return Ok(IpRange { address, prefix_len });
+}
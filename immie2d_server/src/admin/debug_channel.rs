@@ -0,0 +1,65 @@
+use std::io::{self, BufRead, Write};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::network::send_queue::OutboundMessage;
+use crate::world::simulation_clock::{SimulationClock, SimulationControl};
+
+/// Serve one connection to the debug channel of a dev server until it closes. Every line is a SimulationControl,
+/// answered with a line starting with `ok` and the simulation status, or `error` and why. Status messages for clients
+/// are passed to `broadcast`. The channel has no authentication, so only listen on it locally.
+/// ```
+/// use std::io::Cursor;
+/// use std::sync::Mutex;
+/// use std::time::Instant;
+/// use immie2d_shared::world::simulation_status::SimulationStatus;
+/// use immie2d_server::admin::debug_channel::serve_debug_connection;
+/// use immie2d_server::world::simulation_clock::SimulationClock;
+///
+/// let clock = Mutex::new(SimulationClock::new(Instant::now()));
+/// let mut replies = Vec::new();
+/// let mut broadcasts = Vec::new();
+/// serve_debug_connection(Cursor::new("pause\nstep 2\nrewind\n"), &mut replies, &clock, &mut |message| broadcasts.push(message)).unwrap();
+///
+/// let replies = String::from_utf8(replies).unwrap();
+/// let lines: Vec<&str> = replies.lines().collect();
+/// assert_eq!(lines[0], "ok paused=true tick=0 tick_rate=20");
+/// assert_eq!(lines[1], "ok paused=true tick=0 tick_rate=20 pending_steps=2");
+/// assert_eq!(lines[2], "error Unknown simulation command [rewind]");
+/// assert_eq!(SimulationStatus::from_bytes(&broadcasts[0].payload).unwrap().get_notice(), Some("Simulation paused at tick 0".to_string()));
+///
+/// // The world loop runs the steps
+/// assert_eq!(clock.lock().unwrap().poll(Instant::now()), 2);
+/// ```
+pub fn serve_debug_connection<R: BufRead, W: Write>(reader: R, writer: &mut W, clock: &Mutex<SimulationClock>, broadcast: &mut dyn FnMut(OutboundMessage)) -> io::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let control = match SimulationControl::parse(&line) {
+            Ok(control) => control,
+            Err(err) => {
+                writeln!(writer, "error {}", err)?;
+                continue;
+            }
+        };
+        // A poisoned clock still holds a valid state, and the channel is most needed when something went wrong
+        let mut clock = clock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match clock.apply(control, Instant::now()) {
+            Ok(message) => {
+                let status = clock.get_status();
+                let mut reply = format!("ok paused={} tick={} tick_rate={}", status.is_paused, status.tick, status.tick_rate);
+                if let SimulationControl::Step(_) = control {
+                    reply.push_str(&format!(" pending_steps={}", clock.get_pending_steps()));
+                }
+                writeln!(writer, "{}", reply)?;
+                if let Some(message) = message {
+                    broadcast(message);
+                }
+            },
+            Err(err) => writeln!(writer, "error {}", err)?
+        }
+    }
+    return Ok(());
+}
@@ -0,0 +1,4 @@
+pub mod admin_command;
+pub mod ban_list;
+pub mod stat_audit;
+pub mod debug_channel;
@@ -0,0 +1,389 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::immie::individual_values::IndividualValues;
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::gameplay::species::base_stats::BaseStats;
+use immie2d_shared::gameplay::species::species_form::FormError;
+use immie2d_shared::gameplay::species::species_map::SpeciesMap;
+
+use crate::storage::player_profile::{write_string, ByteReader};
+
+const SIGNATURE_DOMAIN: &[u8] = b"immie2d-stat-audit";
+/// Entries a log keeps before forgetting the oldest. Entries that matter are persisted with their Immie, so the log
+/// only needs to cover recent changes for investigating.
+pub const DEFAULT_MAX_AUDIT_ENTRIES: usize = 100_000;
+
+/* Why the server recomputed an Immie's stats. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StatChange {
+    LevelUp,
+    Evolution,
+    /// Training changed the Immie's effort. Effort isn't a stat input yet, so the recomputed stats stay the same.
+    EffortChange,
+    /// The server gave the player a new Immie, such as a gift.
+    Obtained
+}
+
+impl StatChange {
+    pub fn get_id(&self) -> u8 {
+        return match self {
+            StatChange::LevelUp => 0,
+            StatChange::Evolution => 1,
+            StatChange::EffortChange => 2,
+            StatChange::Obtained => 3
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<StatChange> {
+        return match id {
+            0 => Some(StatChange::LevelUp),
+            1 => Some(StatChange::Evolution),
+            2 => Some(StatChange::EffortChange),
+            3 => Some(StatChange::Obtained),
+            _ => None
+        };
+    }
+}
+
+/* Everything an Immie's stats are computed from. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StatInputs {
+    pub species: GlobalString,
+    pub form: Option<GlobalString>,
+    pub level: u32,
+    pub individual_values: IndividualValues
+}
+
+impl StatInputs {
+    pub fn of(immie: &Immie) -> StatInputs {
+        return StatInputs { species: immie.species, form: immie.form, level: immie.level, individual_values: immie.individual_values };
+    }
+}
+
+/* A signed record of one stat recomputation. The latest entry of an Immie is persisted with it, so the stats it was
+last given by the server can be checked later. See StatAuditLog::validate() */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct StatAuditEntry {
+    pub player: PlayerId,
+    pub change: StatChange,
+    pub inputs: StatInputs,
+    pub stats: BaseStats,
+    pub unix_seconds: u64,
+    /// HMAC-SHA256 of every other field, keyed with the server's audit key.
+    pub signature: [u8; 32]
+}
+
+impl StatAuditEntry {
+    /// Everything but the signature, which is what the signature covers.
+    fn get_signed_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.player.0.to_le_bytes());
+        bytes.push(self.change.get_id());
+        write_string(&mut bytes, &self.inputs.species.to_string());
+        match self.inputs.form {
+            Some(form) => {
+                bytes.push(1);
+                write_string(&mut bytes, &form.to_string());
+            },
+            None => bytes.push(0)
+        }
+        bytes.extend_from_slice(&self.inputs.level.to_le_bytes());
+        let values = self.inputs.individual_values;
+        bytes.extend_from_slice(&[values.health, values.attack, values.defense, values.speed]);
+        for stat in [self.stats.health, self.stats.attack, self.stats.defense, self.stats.speed] {
+            bytes.extend_from_slice(&stat.to_le_bytes());
+        }
+        bytes.extend_from_slice(&self.unix_seconds.to_le_bytes());
+        return bytes;
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.get_signed_bytes();
+        bytes.extend_from_slice(&self.signature);
+        return bytes;
+    }
+
+    /// Read an entry written by to_bytes(). The signature isn't checked. See StatAuditLog::verify()
+    /// ```
+    /// use immie2d_server::admin::stat_audit::{StatAuditEntry, StatAuditLog, StatChange};
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    ///
+    /// let lavapup = GlobalString::new(&"lavapup".to_string());
+    /// let mut species = SpeciesMap::new();
+    /// species.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut log = StatAuditLog::new(b"audit key".to_vec());
+    /// let entry = log.record(PlayerId(3), &Immie::new(lavapup, 12, AbilityNames::default()), StatChange::LevelUp, &species, 1_700_000_000).unwrap();
+    ///
+    /// assert_eq!(StatAuditEntry::from_bytes(&entry.to_bytes()).unwrap(), entry);
+    /// assert!(StatAuditEntry::from_bytes(&entry.to_bytes()[1..]).is_err());
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<StatAuditEntry> {
+        let mut reader = ByteReader::new(bytes);
+        let player = PlayerId(u64::from_le_bytes(reader.take_array()?));
+        let change = StatChange::from_id(reader.take_array::<1>()?[0]).ok_or(io::Error::new(ErrorKind::InvalidData, "Unknown stat change"))?;
+        let species = GlobalString::new(&reader.take_string()?);
+        let form = match reader.take_array::<1>()?[0] {
+            0 => None,
+            1 => Some(GlobalString::new(&reader.take_string()?)),
+            _ => return Err(io::Error::new(ErrorKind::InvalidData, "Invalid form flag"))
+        };
+        let level = u32::from_le_bytes(reader.take_array()?);
+        let [health, attack, defense, speed] = reader.take_array::<4>()?;
+        let individual_values = IndividualValues { health, attack, defense, speed };
+        let mut stats = [0u32; 4];
+        for stat in stats.iter_mut() {
+            *stat = u32::from_le_bytes(reader.take_array()?);
+        }
+        let unix_seconds = u64::from_le_bytes(reader.take_array()?);
+        let signature = reader.take_array()?;
+        if reader.get_remaining() != 0 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Trailing bytes after stat audit entry"));
+        }
+        return Ok(StatAuditEntry {
+            player,
+            change,
+            inputs: StatInputs { species, form, level, individual_values },
+            stats: BaseStats::new(stats[0], stats[1], stats[2], stats[3]),
+            unix_seconds,
+            signature
+        });
+    }
+}
+
+/* Why an Immie's persisted stats were rejected. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StatTamper {
+    /// The entry wasn't signed with this server's audit key, or was edited after signing.
+    BadSignature,
+    /// The Immie no longer matches the inputs its stats were computed from.
+    InputsChanged { recorded: StatInputs, current: StatInputs },
+    /// The persisted stats aren't what the server computes from the same inputs.
+    StatsMismatch { recorded: BaseStats, recomputed: BaseStats },
+    /// The Immie has no entry at all.
+    Missing,
+    /// The Immie's species or form doesn't exist, so its stats can't be computed.
+    UnknownSpecies { species: GlobalString, form: Option<GlobalString> }
+}
+
+impl fmt::Display for StatTamper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            StatTamper::BadSignature => write!(f, "The stat audit signature is invalid"),
+            StatTamper::InputsChanged { recorded, current } => write!(f, "Stats were computed for {} at level {}, but it is {} at level {}", recorded.species, recorded.level, current.species, current.level),
+            StatTamper::StatsMismatch { recorded, recomputed } => write!(f, "Persisted stats {:?} don't match the recomputed {:?}", recorded, recomputed),
+            StatTamper::Missing => write!(f, "The Immie has no stat audit entry"),
+            StatTamper::UnknownSpecies { species, form: Some(form) } => write!(f, "Species {} has no form {}", species, form),
+            StatTamper::UnknownSpecies { species, form: None } => write!(f, "There is no species {}", species)
+        };
+    }
+}
+
+/* Append-only record of recent stat recomputations, signed so the stats persisted with an Immie can be trusted
+when it is traded or brought into matchmaking. Only the latest DEFAULT_MAX_AUDIT_ENTRIES are kept. */
+pub struct StatAuditLog {
+    key: Vec<u8>,
+    entries: VecDeque<StatAuditEntry>,
+    max_entries: usize
+}
+
+impl StatAuditLog {
+    /// Will panic if the key is empty. See ServerConfig::load_stat_audit_key()
+    pub fn new(key: Vec<u8>) -> StatAuditLog {
+        assert!(!key.is_empty(), "Stat audit key must not be empty");
+        return StatAuditLog { key, entries: VecDeque::new(), max_entries: DEFAULT_MAX_AUDIT_ENTRIES };
+    }
+
+    /// Will panic if the limit is 0.
+    pub fn with_max_entries(mut self, max_entries: usize) -> StatAuditLog {
+        assert!(max_entries > 0, "A stat audit log must keep at least one entry");
+        self.max_entries = max_entries;
+        return self;
+    }
+
+    fn get_mac(&self, entry: &StatAuditEntry) -> Hmac<Sha256> {
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.key).expect("HMAC accepts keys of any length");
+        mac.update(SIGNATURE_DOMAIN);
+        mac.update(&entry.get_signed_bytes());
+        return mac;
+    }
+
+    /// Recompute the stats of an Immie after it changed, record the signed inputs and outputs, and return the entry
+    /// to persist with the Immie. Returns an error if the Immie's species or form doesn't exist.
+    pub fn record(&mut self, player: PlayerId, immie: &Immie, change: StatChange, species_map: &SpeciesMap, unix_seconds: u64) -> Result<StatAuditEntry, FormError> {
+        let mut entry = StatAuditEntry {
+            player,
+            change,
+            inputs: StatInputs::of(immie),
+            stats: species_map.find_species_of(immie)?.base_stats,
+            unix_seconds,
+            signature: [0; 32]
+        };
+        entry.signature = self.get_mac(&entry).finalize().into_bytes().into();
+        if self.entries.len() == self.max_entries {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+        return Ok(entry);
+    }
+
+    /// Level an Immie up and record its new stats. Every level up on the server should go through here so the
+    /// Immie keeps a valid entry. Leaves the Immie unchanged if its species or form doesn't exist.
+    /// ```
+    /// use immie2d_server::admin::stat_audit::{StatAuditLog, StatChange};
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    ///
+    /// let lavapup = GlobalString::new(&"lavapup".to_string());
+    /// let mut species = SpeciesMap::new();
+    /// species.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut log = StatAuditLog::new(b"audit key".to_vec()).with_max_entries(1);
+    /// let mut immie = Immie::new(lavapup, 12, AbilityNames::default());
+    /// log.level_up(PlayerId(3), &mut immie, 13, &species, 10).unwrap();
+    /// let entry = log.level_up(PlayerId(3), &mut immie, 14, &species, 11).unwrap();
+    /// assert_eq!((entry.change, immie.level), (StatChange::LevelUp, 14));
+    /// assert_eq!(log.validate(&immie, &entry, &species), Ok(()));
+    /// // Only the latest entry is kept
+    /// assert_eq!(log.get_entries(), vec![entry]);
+    ///
+    /// let mut unknown = Immie::new(GlobalString::new(&"missingno".to_string()), 1, AbilityNames::default());
+    /// assert!(log.level_up(PlayerId(3), &mut unknown, 2, &species, 12).is_err());
+    /// assert_eq!(unknown.level, 1);
+    /// ```
+    pub fn level_up(&mut self, player: PlayerId, immie: &mut Immie, level: u32, species_map: &SpeciesMap, unix_seconds: u64) -> Result<StatAuditEntry, FormError> {
+        let mut leveled = *immie;
+        leveled.level = level;
+        let entry = self.record(player, &leveled, StatChange::LevelUp, species_map, unix_seconds)?;
+        *immie = leveled;
+        return Ok(entry);
+    }
+
+    /// Evolve an Immie and record its new stats. Every evolution on the server should go through here so the Immie
+    /// keeps a valid entry. Leaves the Immie unchanged if it or what it evolves into isn't a known species.
+    /// Will panic if the Immie cannot evolve. See Immie::can_evolve()
+    pub fn evolve(&mut self, player: PlayerId, immie: &mut Immie, species_map: &SpeciesMap, unix_seconds: u64) -> Result<StatAuditEntry, FormError> {
+        let mut evolved = *immie;
+        evolved.evolve(&species_map.find_species_of(immie)?);
+        let entry = self.record(player, &evolved, StatChange::Evolution, species_map, unix_seconds)?;
+        *immie = evolved;
+        return Ok(entry);
+    }
+
+    /// Check that an entry was signed by this log's key and hasn't been edited since.
+    pub fn verify(&self, entry: &StatAuditEntry) -> bool {
+        return self.get_mac(entry).verify_slice(&entry.signature).is_ok();
+    }
+
+    /// Check an Immie against the entry persisted with it: the entry must be signed by this server, describe the
+    /// Immie as it is now, and hold the stats the server computes for it now.
+    /// ```
+    /// use immie2d_server::admin::stat_audit::{StatAuditLog, StatChange, StatTamper};
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    ///
+    /// let lavapup = GlobalString::new(&"lavapup".to_string());
+    /// let mut species = SpeciesMap::new();
+    /// species.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut log = StatAuditLog::new(b"audit key".to_vec());
+    /// let mut immie = Immie::new(lavapup, 12, AbilityNames::default());
+    /// let entry = log.record(PlayerId(3), &immie, StatChange::LevelUp, &species, 1_700_000_000).unwrap();
+    /// assert_eq!(log.validate(&immie, &entry, &species), Ok(()));
+    ///
+    /// let mut boosted = entry;
+    /// boosted.stats.attack = 255;
+    /// assert_eq!(log.validate(&immie, &boosted, &species), Err(StatTamper::BadSignature));
+    /// assert_eq!(StatAuditLog::new(b"another key".to_vec()).validate(&immie, &entry, &species), Err(StatTamper::BadSignature));
+    ///
+    /// immie.level = 50;
+    /// assert!(matches!(log.validate(&immie, &entry, &species), Err(StatTamper::InputsChanged { .. })));
+    /// immie.level = 12;
+    ///
+    /// // A rebalance since the entry was signed also invalidates it, until the stats are recomputed
+    /// species.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(55, 60, 40, 70)));
+    /// assert!(matches!(log.validate(&immie, &entry, &species), Err(StatTamper::StatsMismatch { .. })));
+    /// ```
+    pub fn validate(&self, immie: &Immie, entry: &StatAuditEntry, species_map: &SpeciesMap) -> Result<(), StatTamper> {
+        if !self.verify(entry) {
+            return Err(StatTamper::BadSignature);
+        }
+        let current = StatInputs::of(immie);
+        if entry.inputs != current {
+            return Err(StatTamper::InputsChanged { recorded: entry.inputs, current });
+        }
+        let recomputed = species_map.find_species_of(immie).map_err(|_| StatTamper::UnknownSpecies { species: immie.species, form: immie.form })?.base_stats;
+        if entry.stats != recomputed {
+            return Err(StatTamper::StatsMismatch { recorded: entry.stats, recomputed });
+        }
+        return Ok(());
+    }
+
+    /// Validate a whole team before a trade or Matchmaker::enqueue_team(), with the entry persisted for each Immie in
+    /// the same order, or None if it has none. Returns the index and reason of every rejected Immie.
+    /// See PlayerProfile::get_stat_audits()
+    /// ```
+    /// use immie2d_server::admin::stat_audit::{StatAuditLog, StatChange, StatTamper};
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    ///
+    /// let lavapup = GlobalString::new(&"lavapup".to_string());
+    /// let mut species = SpeciesMap::new();
+    /// species.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut log = StatAuditLog::new(b"audit key".to_vec());
+    /// let team = [Immie::new(lavapup, 5, AbilityNames::default()), Immie::new(lavapup, 30, AbilityNames::default()), Immie::new(lavapup, 9, AbilityNames::default())];
+    /// let entries = [
+    ///     Some(log.record(PlayerId(3), &team[0], StatChange::LevelUp, &species, 10).unwrap()),
+    ///     Some(log.record(PlayerId(3), &team[0], StatChange::LevelUp, &species, 11).unwrap()),
+    ///     None
+    /// ];
+    ///
+    /// let rejected = log.validate_team(&team, &entries, &species);
+    /// assert!(matches!(rejected[..], [(1, StatTamper::InputsChanged { .. }), (2, StatTamper::Missing)]));
+    /// assert_eq!(log.get_entries_for(PlayerId(3)).len(), 2);
+    /// ```
+    pub fn validate_team(&self, team: &[Immie], entries: &[Option<StatAuditEntry>], species_map: &SpeciesMap) -> Vec<(usize, StatTamper)> {
+        let mut rejected = Vec::new();
+        for (index, immie) in team.iter().enumerate() {
+            let result = match entries.get(index).copied().flatten() {
+                Some(entry) => self.validate(immie, &entry, species_map),
+                None => Err(StatTamper::Missing)
+            };
+            if let Err(tamper) = result {
+                rejected.push((index, tamper));
+            }
+        }
+        return rejected;
+    }
+
+    /// Every entry still kept, oldest first.
+    pub fn get_entries(&self) -> Vec<StatAuditEntry> {
+        return self.entries.iter().copied().collect();
+    }
+
+    /// Every entry for a player's Immies, oldest first.
+    pub fn get_entries_for(&self, player: PlayerId) -> Vec<StatAuditEntry> {
+        return self.entries.iter().filter(|entry| entry.player == player).copied().collect();
+    }
+}
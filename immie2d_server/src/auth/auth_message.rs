@@ -0,0 +1,236 @@
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::storage::player_profile::{write_string, ByteReader};
+
+const CREATE_ACCOUNT_TAG: u8 = 0;
+const LOGIN_TAG: u8 = 1;
+const CHANGE_PASSWORD_TAG: u8 = 2;
+const REQUEST_RECOVERY_TAG: u8 = 3;
+const RECOVER_TAG: u8 = 4;
+const LOGIN_WITH_CODE_TAG: u8 = 5;
+const BEGIN_TWO_FACTOR_TAG: u8 = 6;
+const CONFIRM_TWO_FACTOR_TAG: u8 = 7;
+const DISABLE_TWO_FACTOR_TAG: u8 = 8;
+
+/* A message from a client that isn't logged in yet, or is changing how it logs in. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AuthRequest {
+    CreateAccount { username: String, password: String, email: Option<String> },
+    Login { username: String, password: String },
+    /// Accounts with two factor authentication also need a code from the authenticator or a recovery code.
+    ChangePassword { username: String, old_password: String, new_password: String, code: Option<String> },
+    /// Send a recovery token to the account's email.
+    RequestRecovery { username: String },
+    /// Set a new password with a token from RequestRecovery.
+    Recover { username: String, token: String, new_password: String },
+    /// Log in to an account with two factor authentication, with a code from its authenticator or a recovery code.
+    LoginWithCode { username: String, password: String, code: String },
+    /// Start setting up two factor authentication, replacing any setup that wasn't confirmed.
+    BeginTwoFactor { username: String, password: String },
+    /// Finish setting up two factor authentication with a code from the authenticator given the secret.
+    ConfirmTwoFactor { username: String, password: String, code: String },
+    DisableTwoFactor { username: String, password: String, code: String }
+}
+
+impl AuthRequest {
+    /// Encode as a tag byte followed by each field as a length prefixed string.
+    /// ```
+    /// use immie2d_server::auth::auth_message::AuthRequest;
+    ///
+    /// let requests = [
+    ///     AuthRequest::CreateAccount { username: "misty".to_string(), password: "starmie123".to_string(), email: None },
+    ///     AuthRequest::CreateAccount { username: "brock".to_string(), password: "onix12345".to_string(), email: Some("brock@example.com".to_string()) },
+    ///     AuthRequest::Recover { username: "misty".to_string(), token: "00ff".to_string(), new_password: "psyduck99".to_string() },
+    ///     AuthRequest::ChangePassword { username: "misty".to_string(), old_password: "starmie123".to_string(), new_password: "psyduck99".to_string(), code: None },
+    ///     AuthRequest::ChangePassword { username: "misty".to_string(), old_password: "starmie123".to_string(), new_password: "psyduck99".to_string(), code: Some("287082".to_string()) },
+    ///     AuthRequest::LoginWithCode { username: "misty".to_string(), password: "starmie123".to_string(), code: "287082".to_string() },
+    ///     AuthRequest::BeginTwoFactor { username: "misty".to_string(), password: "starmie123".to_string() },
+    ///     AuthRequest::DisableTwoFactor { username: "misty".to_string(), password: "starmie123".to_string(), code: "3f9a0-c71e2".to_string() }
+    /// ];
+    /// for request in requests {
+    ///     assert_eq!(AuthRequest::from_bytes(&request.to_bytes()).unwrap(), request);
+    /// }
+    /// assert!(AuthRequest::from_bytes(&[9]).is_err());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match self {
+            AuthRequest::CreateAccount { username, password, email } => {
+                bytes.push(CREATE_ACCOUNT_TAG);
+                write_string(&mut bytes, username);
+                write_string(&mut bytes, password);
+                // An empty email stands for none, as it could never be valid
+                write_string(&mut bytes, email.as_deref().unwrap_or(""));
+            },
+            AuthRequest::Login { username, password } => {
+                bytes.push(LOGIN_TAG);
+                write_string(&mut bytes, username);
+                write_string(&mut bytes, password);
+            },
+            AuthRequest::ChangePassword { username, old_password, new_password, code } => {
+                bytes.push(CHANGE_PASSWORD_TAG);
+                write_string(&mut bytes, username);
+                write_string(&mut bytes, old_password);
+                write_string(&mut bytes, new_password);
+                // An empty code stands for none, as it could never be valid
+                write_string(&mut bytes, code.as_deref().unwrap_or(""));
+            },
+            AuthRequest::RequestRecovery { username } => {
+                bytes.push(REQUEST_RECOVERY_TAG);
+                write_string(&mut bytes, username);
+            },
+            AuthRequest::Recover { username, token, new_password } => {
+                bytes.push(RECOVER_TAG);
+                write_string(&mut bytes, username);
+                write_string(&mut bytes, token);
+                write_string(&mut bytes, new_password);
+            },
+            AuthRequest::LoginWithCode { username, password, code } => {
+                bytes.push(LOGIN_WITH_CODE_TAG);
+                write_string(&mut bytes, username);
+                write_string(&mut bytes, password);
+                write_string(&mut bytes, code);
+            },
+            AuthRequest::BeginTwoFactor { username, password } => {
+                bytes.push(BEGIN_TWO_FACTOR_TAG);
+                write_string(&mut bytes, username);
+                write_string(&mut bytes, password);
+            },
+            AuthRequest::ConfirmTwoFactor { username, password, code } => {
+                bytes.push(CONFIRM_TWO_FACTOR_TAG);
+                write_string(&mut bytes, username);
+                write_string(&mut bytes, password);
+                write_string(&mut bytes, code);
+            },
+            AuthRequest::DisableTwoFactor { username, password, code } => {
+                bytes.push(DISABLE_TWO_FACTOR_TAG);
+                write_string(&mut bytes, username);
+                write_string(&mut bytes, password);
+                write_string(&mut bytes, code);
+            }
+        }
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<AuthRequest> {
+        let mut reader = ByteReader::new(bytes);
+        let [tag] = reader.take_array::<1>()?;
+        let request = match tag {
+            CREATE_ACCOUNT_TAG => {
+                let username = reader.take_string()?;
+                let password = reader.take_string()?;
+                let email = Some(reader.take_string()?).filter(|email| !email.is_empty());
+                AuthRequest::CreateAccount { username, password, email }
+            },
+            LOGIN_TAG => AuthRequest::Login { username: reader.take_string()?, password: reader.take_string()? },
+            CHANGE_PASSWORD_TAG => {
+                let username = reader.take_string()?;
+                let old_password = reader.take_string()?;
+                let new_password = reader.take_string()?;
+                let code = Some(reader.take_string()?).filter(|code| !code.is_empty());
+                AuthRequest::ChangePassword { username, old_password, new_password, code }
+            },
+            REQUEST_RECOVERY_TAG => AuthRequest::RequestRecovery { username: reader.take_string()? },
+            RECOVER_TAG => AuthRequest::Recover { username: reader.take_string()?, token: reader.take_string()?, new_password: reader.take_string()? },
+            LOGIN_WITH_CODE_TAG => AuthRequest::LoginWithCode { username: reader.take_string()?, password: reader.take_string()?, code: reader.take_string()? },
+            BEGIN_TWO_FACTOR_TAG => AuthRequest::BeginTwoFactor { username: reader.take_string()?, password: reader.take_string()? },
+            CONFIRM_TWO_FACTOR_TAG => AuthRequest::ConfirmTwoFactor { username: reader.take_string()?, password: reader.take_string()?, code: reader.take_string()? },
+            DISABLE_TWO_FACTOR_TAG => AuthRequest::DisableTwoFactor { username: reader.take_string()?, password: reader.take_string()?, code: reader.take_string()? },
+            _ => return Err(io::Error::new(ErrorKind::InvalidData, format!("Unknown auth request tag {}", tag)))
+        };
+        if reader.get_remaining() != 0 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Trailing bytes after auth request"));
+        }
+        return Ok(request);
+    }
+}
+
+/* What a player needs to set up two factor authentication, shown once. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TwoFactorSetup {
+    /// Base32 secret, for typing into an authenticator.
+    pub secret: String,
+    /// otpauth URI of the secret, for showing as a QR code. See get_provisioning_uri()
+    pub uri: String,
+    /// Single use codes that log in in place of an authenticator code. Only their hashes are kept.
+    pub recovery_codes: Vec<String>
+}
+
+/* The server's answer to an AuthRequest that succeeded. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AuthResponse {
+    AccountCreated(PlayerId),
+    LoggedIn(PlayerId),
+    PasswordChanged,
+    /// Sent whether or not the account exists or has an email, so recovery can't be used to find accounts.
+    RecoveryRequested,
+    TwoFactorSetup(TwoFactorSetup),
+    TwoFactorEnabled,
+    TwoFactorDisabled
+}
+
+/* Why an AuthRequest was refused. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AuthError {
+    InvalidUsername,
+    InvalidEmail,
+    /// The password is too short or too long. See is_valid_password()
+    InvalidPassword,
+    UsernameTaken,
+    /// The username or password is wrong. Doesn't say which, so usernames can't be probed.
+    InvalidCredentials,
+    /// Too many failed logins. Logins are refused until the unix time in seconds.
+    LockedOut { until: u64 },
+    /// The server has no mailer, so accounts can't be recovered.
+    RecoveryUnavailable,
+    /// The recovery token is wrong, expired or already used.
+    InvalidRecoveryToken,
+    /// The password was right, but the account has two factor authentication, so logging in needs LoginWithCode.
+    TwoFactorRequired,
+    /// The server requires two factor authentication, and the account hasn't set it up yet.
+    TwoFactorSetupRequired,
+    /// The code is wrong, expired, or was already used.
+    InvalidTwoFactorCode,
+    /// Two factor authentication isn't set up, or for confirming, setup wasn't begun.
+    TwoFactorNotEnabled,
+    TwoFactorAlreadyEnabled,
+    /// The server requires two factor authentication, so it can't be disabled.
+    TwoFactorEnforced,
+    /// The account is banned, until the unix time in seconds or permanently. Only given for the right password.
+    Banned { until: Option<u64> },
+    /// Storage failed, with its message for the server's logs.
+    Storage(String)
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            AuthError::InvalidUsername => write!(f, "Usernames must be 3 to 24 letters, digits or underscores"),
+            AuthError::InvalidEmail => write!(f, "The email address is not valid"),
+            AuthError::InvalidPassword => write!(f, "Passwords must be 8 to 128 characters"),
+            AuthError::UsernameTaken => write!(f, "The username is already taken"),
+            AuthError::InvalidCredentials => write!(f, "The username or password is wrong"),
+            AuthError::LockedOut { until } => write!(f, "Too many failed logins, try again after {}", until),
+            AuthError::RecoveryUnavailable => write!(f, "Account recovery is not available on this server"),
+            AuthError::InvalidRecoveryToken => write!(f, "The recovery token is wrong or has expired"),
+            AuthError::TwoFactorRequired => write!(f, "Enter the code from your authenticator"),
+            AuthError::TwoFactorSetupRequired => write!(f, "This server requires two factor authentication to be set up"),
+            AuthError::InvalidTwoFactorCode => write!(f, "The code is wrong or has expired"),
+            AuthError::TwoFactorNotEnabled => write!(f, "Two factor authentication is not set up"),
+            AuthError::TwoFactorAlreadyEnabled => write!(f, "Two factor authentication is already set up"),
+            AuthError::TwoFactorEnforced => write!(f, "This server requires two factor authentication"),
+            AuthError::Banned { until: Some(until) } => write!(f, "This account is banned until {}", until),
+            AuthError::Banned { until: None } => write!(f, "This account is banned"),
+            AuthError::Storage(message) => write!(f, "Storage failed: {}", message)
+        };
+    }
+}
+
+impl From<io::Error> for AuthError {
+    fn from(err: io::Error) -> AuthError {
+        return AuthError::Storage(err.to_string());
+    }
+}
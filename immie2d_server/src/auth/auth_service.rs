@@ -0,0 +1,499 @@
+use std::io;
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::admin::ban_list::ConnectionDenial;
+use crate::auth::auth_message::{AuthError, AuthRequest, AuthResponse, TwoFactorSetup};
+use crate::auth::credentials::{is_valid_email, normalize_username, Credentials, RecoveryToken, TwoFactor};
+use crate::auth::mailer::Mailer;
+use crate::auth::password_hash::{generate_token, hash_password, is_valid_password, verify_password, HashingCost};
+use crate::auth::two_factor::{encode_base32, find_step, generate_recovery_code, generate_secret, get_provisioning_uri, get_recovery_code_tag, normalize_recovery_code, TwoFactorPolicy, RECOVERY_CODE_COUNT};
+use crate::storage::player_profile::PlayerProfile;
+use crate::storage::storage::Storage;
+
+/// How long a recovery token works for after it's sent.
+pub const RECOVERY_TOKEN_SECONDS: u64 = 60 * 60;
+
+/* How many failed logins in a row lock an account, and for how long. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LockoutPolicy {
+    pub max_failures: u32,
+    pub lockout_seconds: u64
+}
+
+impl LockoutPolicy {
+    pub fn default() -> LockoutPolicy {
+        return LockoutPolicy { max_failures: 5, lockout_seconds: 15 * 60 };
+    }
+}
+
+/* Handles account creation, logins, password changes, recovery and two factor authentication against storage. Times are passed in as unix
+seconds rather than read from the clock, so lockouts and token expiry are deterministic. */
+pub struct AuthService {
+    cost: HashingCost,
+    lockout: LockoutPolicy,
+    mailer: Option<Box<dyn Mailer>>,
+    two_factor_policy: TwoFactorPolicy,
+    /// Hash of no real password, checked against for unknown usernames so they take as long to refuse as wrong
+    /// passwords. Made on first use, at the current cost.
+    dummy_hash: Option<String>
+}
+
+impl AuthService {
+    /// An auth service with the default hashing cost and lockout policy, and no mailer, so recovery is unavailable.
+    /// Two factor authentication is optional.
+    pub fn new() -> AuthService {
+        return AuthService { cost: HashingCost::default(), lockout: LockoutPolicy::default(), mailer: None, two_factor_policy: TwoFactorPolicy::Optional, dummy_hash: None };
+    }
+
+    pub fn with_hashing_cost(mut self, cost: HashingCost) -> AuthService {
+        self.cost = cost;
+        self.dummy_hash = None;
+        return self;
+    }
+
+    pub fn with_lockout(mut self, lockout: LockoutPolicy) -> AuthService {
+        self.lockout = lockout;
+        return self;
+    }
+
+    pub fn with_mailer(mut self, mailer: Box<dyn Mailer>) -> AuthService {
+        self.mailer = Some(mailer);
+        return self;
+    }
+
+    pub fn with_two_factor_policy(mut self, policy: TwoFactorPolicy) -> AuthService {
+        self.two_factor_policy = policy;
+        return self;
+    }
+
+    pub fn get_lockout(&self) -> LockoutPolicy {
+        return self.lockout;
+    }
+
+    pub fn get_two_factor_policy(&self) -> TwoFactorPolicy {
+        return self.two_factor_policy;
+    }
+
+    /// Spend as long as checking a password would, for requests about accounts that don't exist, so response times
+    /// don't reveal which usernames are taken.
+    fn check_dummy_password(&mut self, password: &str) {
+        let cost = self.cost;
+        let dummy_hash = self.dummy_hash.get_or_insert_with(|| hash_password(&generate_token(), cost));
+        verify_password(password, dummy_hash);
+    }
+
+    /// Handle a request from a client. Creating an account also saves a new profile for it. A player is only logged
+    /// in, and so given a session, by a LoggedIn response.
+    /// ```
+    /// use immie2d_server::admin::ban_list::BanTarget;
+    /// use immie2d_server::auth::auth_message::{AuthError, AuthRequest, AuthResponse};
+    /// use immie2d_server::auth::auth_service::AuthService;
+    /// use immie2d_server::auth::password_hash::HashingCost;
+    /// use immie2d_server::storage::{memory_storage::MemoryStorage, storage::Storage};
+    ///
+    /// let mut storage = MemoryStorage::new();
+    /// let mut auth = AuthService::new().with_hashing_cost(HashingCost::minimum());
+    /// let create = AuthRequest::CreateAccount { username: "Misty".to_string(), password: "starmie123".to_string(), email: None };
+    /// let Ok(AuthResponse::AccountCreated(player)) = auth.handle(&mut storage, create.clone(), 0) else { panic!() };
+    /// assert_eq!(storage.load_profile(player).unwrap().unwrap().name, "Misty");
+    /// assert_eq!(auth.handle(&mut storage, create, 0), Err(AuthError::UsernameTaken));
+    ///
+    /// // Usernames don't depend on case
+    /// let login = AuthRequest::Login { username: "misty".to_string(), password: "starmie123".to_string() };
+    /// assert_eq!(auth.handle(&mut storage, login, 0), Ok(AuthResponse::LoggedIn(player)));
+    ///
+    /// let change = AuthRequest::ChangePassword { username: "misty".to_string(), old_password: "starmie123".to_string(), new_password: "psyduck99".to_string(), code: None };
+    /// assert_eq!(auth.handle(&mut storage, change, 0), Ok(AuthResponse::PasswordChanged));
+    /// let old_login = AuthRequest::Login { username: "misty".to_string(), password: "starmie123".to_string() };
+    /// assert_eq!(auth.handle(&mut storage, old_login, 0), Err(AuthError::InvalidCredentials));
+    /// // Without a mailer, accounts can't be recovered
+    /// assert_eq!(auth.handle(&mut storage, AuthRequest::RequestRecovery { username: "misty".to_string() }, 0), Err(AuthError::RecoveryUnavailable));
+    ///
+    /// // Banned accounts can't log in until their ban expires
+    /// let mut bans = storage.load_ban_list().unwrap();
+    /// bans.ban(BanTarget::Account(player), Some(100), 0);
+    /// storage.save_ban_list(&bans).unwrap();
+    /// let new_login = AuthRequest::Login { username: "misty".to_string(), password: "psyduck99".to_string() };
+    /// assert_eq!(auth.handle(&mut storage, new_login.clone(), 50), Err(AuthError::Banned { until: Some(100) }));
+    /// assert_eq!(auth.handle(&mut storage, new_login, 100), Ok(AuthResponse::LoggedIn(player)));
+    /// ```
+    pub fn handle<S: Storage + ?Sized>(&mut self, storage: &mut S, request: AuthRequest, unix_seconds: u64) -> Result<AuthResponse, AuthError> {
+        return match request {
+            AuthRequest::CreateAccount { username, password, email } => self.create_account(storage, &username, &password, email),
+            AuthRequest::Login { username, password } => self.login(storage, &username, &password, None, unix_seconds),
+            AuthRequest::LoginWithCode { username, password, code } => self.login(storage, &username, &password, Some(&code), unix_seconds),
+            AuthRequest::ChangePassword { username, old_password, new_password, code } => self.change_password(storage, &username, &old_password, &new_password, code.as_deref(), unix_seconds),
+            AuthRequest::RequestRecovery { username } => self.request_recovery(storage, &username, unix_seconds),
+            AuthRequest::Recover { username, token, new_password } => self.recover(storage, &username, &token, &new_password, unix_seconds),
+            AuthRequest::BeginTwoFactor { username, password } => self.begin_two_factor(storage, &username, &password, unix_seconds),
+            AuthRequest::ConfirmTwoFactor { username, password, code } => self.confirm_two_factor(storage, &username, &password, &code, unix_seconds),
+            AuthRequest::DisableTwoFactor { username, password, code } => self.disable_two_factor(storage, &username, &password, &code, unix_seconds)
+        };
+    }
+
+    fn create_account<S: Storage + ?Sized>(&mut self, storage: &mut S, username: &str, password: &str, email: Option<String>) -> Result<AuthResponse, AuthError> {
+        let normalized = normalize_username(username).ok_or(AuthError::InvalidUsername)?;
+        if !is_valid_password(password) {
+            return Err(AuthError::InvalidPassword);
+        }
+        if email.as_deref().is_some_and(|email| !is_valid_email(email)) {
+            return Err(AuthError::InvalidEmail);
+        }
+        if storage.load_credentials(&normalized)?.is_some() {
+            return Err(AuthError::UsernameTaken);
+        }
+        let player = storage.allocate_player_id()?;
+        let credentials = Credentials::new(normalized, player, hash_password(password, self.cost), email);
+        // Another server may have created the same username since it was checked
+        if !storage.create_credentials(&credentials)? {
+            return Err(AuthError::UsernameTaken);
+        }
+        // The profile keeps the name as it was typed, for display
+        storage.save_profiles(&[PlayerProfile::new(player, username.to_string())])?;
+        return Ok(AuthResponse::AccountCreated(player));
+    }
+
+    /// Check a password, counting failures toward a lockout. Locked accounts are refused before the password is
+    /// checked, so guesses made while locked out can't succeed.
+    /// ```
+    /// use immie2d_server::auth::auth_message::{AuthError, AuthRequest};
+    /// use immie2d_server::auth::auth_service::{AuthService, LockoutPolicy};
+    /// use immie2d_server::auth::password_hash::HashingCost;
+    /// use immie2d_server::storage::memory_storage::MemoryStorage;
+    ///
+    /// let mut storage = MemoryStorage::new();
+    /// let mut auth = AuthService::new().with_hashing_cost(HashingCost::minimum()).with_lockout(LockoutPolicy { max_failures: 3, lockout_seconds: 60 });
+    /// auth.handle(&mut storage, AuthRequest::CreateAccount { username: "brock".to_string(), password: "onix12345".to_string(), email: None }, 0).unwrap();
+    /// let login = |password: &str| AuthRequest::Login { username: "brock".to_string(), password: password.to_string() };
+    ///
+    /// assert_eq!(auth.handle(&mut storage, login("wrong pass"), 100), Err(AuthError::InvalidCredentials));
+    /// assert_eq!(auth.handle(&mut storage, login("wrong pass"), 100), Err(AuthError::InvalidCredentials));
+    /// assert_eq!(auth.handle(&mut storage, login("wrong pass"), 100), Err(AuthError::LockedOut { until: 160 }));
+    /// assert_eq!(auth.handle(&mut storage, login("onix12345"), 159), Err(AuthError::LockedOut { until: 160 }));
+    /// assert!(auth.handle(&mut storage, login("onix12345"), 160).is_ok());
+    /// ```
+    fn authenticate<S: Storage + ?Sized>(&mut self, storage: &mut S, username: &str, password: &str, unix_seconds: u64) -> Result<Credentials, AuthError> {
+        let mut credentials = self.check_password(storage, username, password, unix_seconds)?;
+        clear_failures(storage, &mut credentials)?;
+        return Ok(credentials);
+    }
+
+    /// Check a password without clearing earlier failures, for when a code is still to be checked. Unknown usernames
+    /// are refused only after checking a dummy hash, so they can't be told apart from wrong passwords by timing.
+    fn check_password<S: Storage + ?Sized>(&mut self, storage: &mut S, username: &str, password: &str, unix_seconds: u64) -> Result<Credentials, AuthError> {
+        let credentials = match normalize_username(username) {
+            Some(normalized) => storage.load_credentials(&normalized)?,
+            None => None
+        };
+        let Some(credentials) = credentials else {
+            self.check_dummy_password(password);
+            return Err(AuthError::InvalidCredentials);
+        };
+        if credentials.is_locked_out(unix_seconds) {
+            return Err(AuthError::LockedOut { until: credentials.locked_until });
+        }
+        if verify_password(password, &credentials.password_hash) {
+            return Ok(credentials);
+        }
+        return Err(self.record_failure(storage, credentials, AuthError::InvalidCredentials, unix_seconds));
+    }
+
+    /// Count a failed password or code toward a lockout, returning the error to refuse it with.
+    fn record_failure<S: Storage + ?Sized>(&mut self, storage: &mut S, mut credentials: Credentials, error: AuthError, unix_seconds: u64) -> AuthError {
+        credentials.failed_attempts += 1;
+        let error = if credentials.failed_attempts >= self.lockout.max_failures {
+            credentials.failed_attempts = 0;
+            credentials.locked_until = unix_seconds + self.lockout.lockout_seconds;
+            AuthError::LockedOut { until: credentials.locked_until }
+        } else {
+            error
+        };
+        return match storage.save_credentials(&credentials) {
+            Ok(()) => error,
+            Err(err) => err.into()
+        };
+    }
+
+    /// Log in, with a code if the account has two factor authentication. Wrong codes count toward a lockout like
+    /// wrong passwords, so codes can't be guessed either.
+    /// ```
+    /// use immie2d_server::auth::auth_message::{AuthError, AuthRequest, AuthResponse};
+    /// use immie2d_server::auth::auth_service::{AuthService, LockoutPolicy};
+    /// use immie2d_server::auth::password_hash::HashingCost;
+    /// use immie2d_server::auth::two_factor::{get_code, get_step, TwoFactorPolicy};
+    /// use immie2d_server::storage::{memory_storage::MemoryStorage, storage::Storage};
+    ///
+    /// let mut storage = MemoryStorage::new();
+    /// let mut auth = AuthService::new().with_hashing_cost(HashingCost::minimum()).with_lockout(LockoutPolicy { max_failures: 3, lockout_seconds: 60 });
+    /// auth.handle(&mut storage, AuthRequest::CreateAccount { username: "erika".to_string(), password: "tangela1".to_string(), email: None }, 0).unwrap();
+    /// let request = |code: &str| AuthRequest::LoginWithCode { username: "erika".to_string(), password: "tangela1".to_string(), code: code.to_string() };
+    /// let login = AuthRequest::Login { username: "erika".to_string(), password: "tangela1".to_string() };
+    ///
+    /// let Ok(AuthResponse::TwoFactorSetup(setup)) = auth.handle(&mut storage, AuthRequest::BeginTwoFactor { username: "erika".to_string(), password: "tangela1".to_string() }, 0) else { panic!() };
+    /// // Until confirmed, logins don't need a code
+    /// assert!(auth.handle(&mut storage, login.clone(), 0).is_ok());
+    /// let secret = storage.load_credentials("erika").unwrap().unwrap().two_factor.unwrap().secret;
+    /// let confirm = |code: String| AuthRequest::ConfirmTwoFactor { username: "erika".to_string(), password: "tangela1".to_string(), code };
+    /// assert_eq!(auth.handle(&mut storage, confirm("000000".to_string()), 1000), Err(AuthError::InvalidTwoFactorCode));
+    /// assert_eq!(auth.handle(&mut storage, confirm(get_code(&secret, get_step(1000))), 1000), Ok(AuthResponse::TwoFactorEnabled));
+    ///
+    /// assert_eq!(auth.handle(&mut storage, login.clone(), 2000), Err(AuthError::TwoFactorRequired));
+    /// let Ok(AuthResponse::LoggedIn(player)) = auth.handle(&mut storage, request(&get_code(&secret, get_step(2000))), 2000) else { panic!() };
+    /// // Each code works once
+    /// assert_eq!(auth.handle(&mut storage, request(&get_code(&secret, get_step(2000))), 2000), Err(AuthError::InvalidTwoFactorCode));
+    /// // Recovery codes work in place of the authenticator, once each
+    /// assert_eq!(auth.handle(&mut storage, request(&setup.recovery_codes[0].to_uppercase()), 3000), Ok(AuthResponse::LoggedIn(player)));
+    /// assert_eq!(auth.handle(&mut storage, request(&setup.recovery_codes[0]), 3000), Err(AuthError::InvalidTwoFactorCode));
+    /// assert_eq!(auth.handle(&mut storage, request("123456"), 3000), Err(AuthError::InvalidTwoFactorCode));
+    /// assert_eq!(auth.handle(&mut storage, request("123456"), 3000), Err(AuthError::LockedOut { until: 3060 }));
+    ///
+    /// // A server that requires two factor authentication won't let it be disabled, nor accounts without it log in
+    /// let mut strict = AuthService::new().with_hashing_cost(HashingCost::minimum()).with_two_factor_policy(TwoFactorPolicy::Required);
+    /// let disable = AuthRequest::DisableTwoFactor { username: "erika".to_string(), password: "tangela1".to_string(), code: setup.recovery_codes[1].clone() };
+    /// assert_eq!(strict.handle(&mut storage, disable.clone(), 4000), Err(AuthError::TwoFactorEnforced));
+    /// assert_eq!(auth.handle(&mut storage, disable, 4000), Ok(AuthResponse::TwoFactorDisabled));
+    /// assert_eq!(strict.handle(&mut storage, login.clone(), 4000), Err(AuthError::TwoFactorSetupRequired));
+    /// assert_eq!(auth.handle(&mut storage, login, 4000), Ok(AuthResponse::LoggedIn(player)));
+    /// ```
+    fn login<S: Storage + ?Sized>(&mut self, storage: &mut S, username: &str, password: &str, code: Option<&str>, unix_seconds: u64) -> Result<AuthResponse, AuthError> {
+        let mut credentials = self.check_password(storage, username, password, unix_seconds)?;
+        if !credentials.has_two_factor() {
+            if self.two_factor_policy == TwoFactorPolicy::Required {
+                return Err(AuthError::TwoFactorSetupRequired);
+            }
+            clear_failures(storage, &mut credentials)?;
+            check_bans(storage, credentials.player, unix_seconds)?;
+            return Ok(AuthResponse::LoggedIn(credentials.player));
+        }
+        let Some(code) = code else {
+            return Err(AuthError::TwoFactorRequired);
+        };
+        if !use_code(&mut credentials, code, unix_seconds) {
+            return Err(self.record_failure(storage, credentials, AuthError::InvalidTwoFactorCode, unix_seconds));
+        }
+        credentials.failed_attempts = 0;
+        credentials.locked_until = 0;
+        storage.save_credentials(&credentials)?;
+        check_bans(storage, credentials.player, unix_seconds)?;
+        return Ok(AuthResponse::LoggedIn(credentials.player));
+    }
+
+    /// Change a password, with a code as well if the account has two factor authentication, so a stolen password alone
+    /// can't take over the account. Wrong codes count toward a lockout like they do for logins.
+    /// ```
+    /// use immie2d_server::auth::auth_message::{AuthError, AuthRequest, AuthResponse};
+    /// use immie2d_server::auth::auth_service::AuthService;
+    /// use immie2d_server::auth::password_hash::HashingCost;
+    /// use immie2d_server::auth::two_factor::{get_code, get_step};
+    /// use immie2d_server::storage::{memory_storage::MemoryStorage, storage::Storage};
+    ///
+    /// let mut storage = MemoryStorage::new();
+    /// let mut auth = AuthService::new().with_hashing_cost(HashingCost::minimum());
+    /// auth.handle(&mut storage, AuthRequest::CreateAccount { username: "sabrina".to_string(), password: "kadabra12".to_string(), email: None }, 0).unwrap();
+    /// let Ok(AuthResponse::TwoFactorSetup(setup)) = auth.handle(&mut storage, AuthRequest::BeginTwoFactor { username: "sabrina".to_string(), password: "kadabra12".to_string() }, 0) else { panic!() };
+    /// let secret = storage.load_credentials("sabrina").unwrap().unwrap().two_factor.unwrap().secret;
+    /// let confirm = AuthRequest::ConfirmTwoFactor { username: "sabrina".to_string(), password: "kadabra12".to_string(), code: get_code(&secret, get_step(1000)) };
+    /// auth.handle(&mut storage, confirm, 1000).unwrap();
+    ///
+    /// let change = |code: Option<&str>| AuthRequest::ChangePassword { username: "sabrina".to_string(), old_password: "kadabra12".to_string(), new_password: "alakazam1".to_string(), code: code.map(|code| code.to_string()) };
+    /// assert_eq!(auth.handle(&mut storage, change(None), 2000), Err(AuthError::TwoFactorRequired));
+    /// assert_eq!(auth.handle(&mut storage, change(Some("00000-00000")), 2000), Err(AuthError::InvalidTwoFactorCode));
+    /// assert_eq!(auth.handle(&mut storage, change(Some(&setup.recovery_codes[3])), 2000), Ok(AuthResponse::PasswordChanged));
+    /// ```
+    fn change_password<S: Storage + ?Sized>(&mut self, storage: &mut S, username: &str, old_password: &str, new_password: &str, code: Option<&str>, unix_seconds: u64) -> Result<AuthResponse, AuthError> {
+        if !is_valid_password(new_password) {
+            return Err(AuthError::InvalidPassword);
+        }
+        let mut credentials = self.check_password(storage, username, old_password, unix_seconds)?;
+        if credentials.has_two_factor() {
+            let Some(code) = code else {
+                return Err(AuthError::TwoFactorRequired);
+            };
+            if !use_code(&mut credentials, code, unix_seconds) {
+                return Err(self.record_failure(storage, credentials, AuthError::InvalidTwoFactorCode, unix_seconds));
+            }
+        }
+        credentials.password_hash = hash_password(new_password, self.cost);
+        credentials.recovery = None;
+        credentials.failed_attempts = 0;
+        credentials.locked_until = 0;
+        storage.save_credentials(&credentials)?;
+        return Ok(AuthResponse::PasswordChanged);
+    }
+
+    fn begin_two_factor<S: Storage + ?Sized>(&mut self, storage: &mut S, username: &str, password: &str, unix_seconds: u64) -> Result<AuthResponse, AuthError> {
+        let mut credentials = self.authenticate(storage, username, password, unix_seconds)?;
+        if credentials.has_two_factor() {
+            return Err(AuthError::TwoFactorAlreadyEnabled);
+        }
+        let secret = generate_secret();
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| generate_recovery_code()).collect();
+        let recovery_code_hashes = recovery_codes.iter().map(|code| {
+            let normalized = normalize_recovery_code(code);
+            return format!("{}{}", get_recovery_code_tag(&secret, &normalized), hash_password(&normalized, self.cost));
+        }).collect();
+        credentials.two_factor = Some(TwoFactor { secret, confirmed: false, recovery_code_hashes, last_used_step: 0 });
+        storage.save_credentials(&credentials)?;
+        return Ok(AuthResponse::TwoFactorSetup(TwoFactorSetup { secret: encode_base32(&secret), uri: get_provisioning_uri(&credentials.username, &secret), recovery_codes }));
+    }
+
+    /// Confirm setup with a code from the authenticator. Recovery codes don't confirm, as they don't prove the
+    /// authenticator has the secret.
+    fn confirm_two_factor<S: Storage + ?Sized>(&mut self, storage: &mut S, username: &str, password: &str, code: &str, unix_seconds: u64) -> Result<AuthResponse, AuthError> {
+        let mut credentials = self.authenticate(storage, username, password, unix_seconds)?;
+        let Some(two_factor) = credentials.two_factor.as_mut() else {
+            return Err(AuthError::TwoFactorNotEnabled);
+        };
+        if two_factor.confirmed {
+            return Err(AuthError::TwoFactorAlreadyEnabled);
+        }
+        two_factor.last_used_step = find_step(&two_factor.secret, code, unix_seconds).ok_or(AuthError::InvalidTwoFactorCode)?;
+        two_factor.confirmed = true;
+        storage.save_credentials(&credentials)?;
+        return Ok(AuthResponse::TwoFactorEnabled);
+    }
+
+    fn disable_two_factor<S: Storage + ?Sized>(&mut self, storage: &mut S, username: &str, password: &str, code: &str, unix_seconds: u64) -> Result<AuthResponse, AuthError> {
+        if self.two_factor_policy == TwoFactorPolicy::Required {
+            return Err(AuthError::TwoFactorEnforced);
+        }
+        let mut credentials = self.check_password(storage, username, password, unix_seconds)?;
+        if !credentials.has_two_factor() {
+            return Err(AuthError::TwoFactorNotEnabled);
+        }
+        if !use_code(&mut credentials, code, unix_seconds) {
+            return Err(self.record_failure(storage, credentials, AuthError::InvalidTwoFactorCode, unix_seconds));
+        }
+        credentials.two_factor = None;
+        credentials.failed_attempts = 0;
+        credentials.locked_until = 0;
+        storage.save_credentials(&credentials)?;
+        return Ok(AuthResponse::TwoFactorDisabled);
+    }
+
+    fn request_recovery<S: Storage + ?Sized>(&mut self, storage: &mut S, username: &str, unix_seconds: u64) -> Result<AuthResponse, AuthError> {
+        let Some(mailer) = self.mailer.as_mut() else {
+            return Err(AuthError::RecoveryUnavailable);
+        };
+        let Some(normalized) = normalize_username(username) else {
+            return Ok(AuthResponse::RecoveryRequested);
+        };
+        let Some(mut credentials) = storage.load_credentials(&normalized)? else {
+            return Ok(AuthResponse::RecoveryRequested);
+        };
+        let Some(email) = credentials.email.clone() else {
+            return Ok(AuthResponse::RecoveryRequested);
+        };
+        let token = generate_token();
+        credentials.recovery = Some(RecoveryToken { token_hash: hash_password(&token, self.cost), expires_at: unix_seconds + RECOVERY_TOKEN_SECONDS });
+        storage.save_credentials(&credentials)?;
+        mailer.send_recovery_token(&email, &credentials.username, &token)?;
+        return Ok(AuthResponse::RecoveryRequested);
+    }
+
+    /// Set a new password with an emailed token. Tokens work once, and recovering also lifts a lockout.
+    /// ```
+    /// use immie2d_server::auth::auth_message::{AuthError, AuthRequest, AuthResponse};
+    /// use immie2d_server::auth::auth_service::{AuthService, RECOVERY_TOKEN_SECONDS};
+    /// use immie2d_server::auth::mailer::RecordingMailer;
+    /// use immie2d_server::auth::password_hash::HashingCost;
+    /// use immie2d_server::storage::memory_storage::MemoryStorage;
+    ///
+    /// let mut storage = MemoryStorage::new();
+    /// let mailer = RecordingMailer::new();
+    /// let mut auth = AuthService::new().with_hashing_cost(HashingCost::minimum()).with_mailer(Box::new(mailer.clone()));
+    /// let create = AuthRequest::CreateAccount { username: "misty".to_string(), password: "starmie123".to_string(), email: Some("misty@example.com".to_string()) };
+    /// auth.handle(&mut storage, create, 0).unwrap();
+    /// // Unknown accounts get the same response, but no email
+    /// assert_eq!(auth.handle(&mut storage, AuthRequest::RequestRecovery { username: "nobody".to_string() }, 0), Ok(AuthResponse::RecoveryRequested));
+    /// assert!(mailer.get_sent().is_empty());
+    ///
+    /// assert_eq!(auth.handle(&mut storage, AuthRequest::RequestRecovery { username: "misty".to_string() }, 0), Ok(AuthResponse::RecoveryRequested));
+    /// let sent = mailer.get_sent();
+    /// assert_eq!(sent[0].email, "misty@example.com");
+    /// let recover = |token: &str, at: u64| (AuthRequest::Recover { username: "misty".to_string(), token: token.to_string(), new_password: "psyduck99".to_string() }, at);
+    ///
+    /// let (wrong, at) = recover("0000", 10);
+    /// assert_eq!(auth.handle(&mut storage, wrong, at), Err(AuthError::InvalidRecoveryToken));
+    /// let (expired, at) = recover(&sent[0].token, RECOVERY_TOKEN_SECONDS);
+    /// assert_eq!(auth.handle(&mut storage, expired, at), Err(AuthError::InvalidRecoveryToken));
+    /// let (valid, at) = recover(&sent[0].token, 10);
+    /// assert_eq!(auth.handle(&mut storage, valid.clone(), at), Ok(AuthResponse::PasswordChanged));
+    /// assert_eq!(auth.handle(&mut storage, valid, at), Err(AuthError::InvalidRecoveryToken));
+    /// let login = AuthRequest::Login { username: "misty".to_string(), password: "psyduck99".to_string() };
+    /// assert!(auth.handle(&mut storage, login, 10).is_ok());
+    /// ```
+    fn recover<S: Storage + ?Sized>(&mut self, storage: &mut S, username: &str, token: &str, new_password: &str, unix_seconds: u64) -> Result<AuthResponse, AuthError> {
+        if self.mailer.is_none() {
+            return Err(AuthError::RecoveryUnavailable);
+        }
+        if !is_valid_password(new_password) {
+            return Err(AuthError::InvalidPassword);
+        }
+        let credentials = match normalize_username(username) {
+            Some(normalized) => storage.load_credentials(&normalized)?,
+            None => None
+        };
+        let Some(mut credentials) = credentials else {
+            self.check_dummy_password(token);
+            return Err(AuthError::InvalidRecoveryToken);
+        };
+        let is_valid = credentials.recovery.as_ref().is_some_and(|recovery| unix_seconds < recovery.expires_at && verify_password(token, &recovery.token_hash));
+        if !is_valid {
+            return Err(AuthError::InvalidRecoveryToken);
+        }
+        credentials.password_hash = hash_password(new_password, self.cost);
+        credentials.recovery = None;
+        credentials.failed_attempts = 0;
+        credentials.locked_until = 0;
+        storage.save_credentials(&credentials)?;
+        return Ok(AuthResponse::PasswordChanged);
+    }
+}
+
+/// Refuse banned accounts, reading the ban list from storage each time so bans take effect without a restart.
+fn check_bans<S: Storage + ?Sized>(storage: &mut S, player: PlayerId, unix_seconds: u64) -> Result<(), AuthError> {
+    let bans = storage.load_ban_list()?;
+    let denial = match storage.load_profile(player)? {
+        Some(profile) => bans.check_profile(&profile, unix_seconds),
+        None => bans.check_account(player, unix_seconds)
+    };
+    return match denial {
+        Ok(()) => Ok(()),
+        Err(ConnectionDenial::Banned(ban)) => Err(AuthError::Banned { until: ban.expires_at }),
+        Err(ConnectionDenial::NotAllowed) => Err(AuthError::Banned { until: None })
+    };
+}
+
+fn clear_failures<S: Storage + ?Sized>(storage: &mut S, credentials: &mut Credentials) -> io::Result<()> {
+    if credentials.failed_attempts != 0 || credentials.locked_until != 0 {
+        credentials.failed_attempts = 0;
+        credentials.locked_until = 0;
+        storage.save_credentials(credentials)?;
+    }
+    return Ok(());
+}
+
+/// Check a code against confirmed two factor authentication, using up the code's time step or the recovery code so
+/// it can't be used again.
+fn use_code(credentials: &mut Credentials, code: &str, unix_seconds: u64) -> bool {
+    let Some(two_factor) = credentials.two_factor.as_mut() else {
+        return false;
+    };
+    if let Some(step) = find_step(&two_factor.secret, code, unix_seconds) {
+        if step <= two_factor.last_used_step {
+            return false;
+        }
+        two_factor.last_used_step = step;
+        return true;
+    }
+    let recovery_code = normalize_recovery_code(code);
+    let tag = get_recovery_code_tag(&two_factor.secret, &recovery_code);
+    // Only codes with the same tag are hashed, so a wrong code usually costs no slow hash rather than one per code
+    let Some(index) = two_factor.recovery_code_hashes.iter().position(|stored| stored.strip_prefix(tag.as_str()).is_some_and(|hash| verify_password(&recovery_code, hash))) else {
+        return false;
+    };
+    two_factor.recovery_code_hashes.remove(index);
+    return true;
+}
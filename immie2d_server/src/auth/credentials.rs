@@ -0,0 +1,198 @@
+use std::io::{self, ErrorKind};
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::auth::two_factor::TOTP_SECRET_BYTES;
+use crate::storage::player_profile::{write_string, ByteReader};
+
+/// Shortest and longest usernames.
+pub const MIN_USERNAME_LENGTH: usize = 3;
+pub const MAX_USERNAME_LENGTH: usize = 24;
+/// Longest email address, as limited by SMTP.
+pub const MAX_EMAIL_LENGTH: usize = 254;
+
+/// The form a username is stored and looked up in, or None if it isn't a valid username. Usernames are letters,
+/// digits and underscores, and don't depend on case.
+/// ```
+/// use immie2d_server::auth::credentials::normalize_username;
+///
+/// assert_eq!(normalize_username("Ash_Ketchum"), Some("ash_ketchum".to_string()));
+/// assert_eq!(normalize_username("ab"), None);
+/// assert_eq!(normalize_username("no spaces"), None);
+/// ```
+pub fn normalize_username(username: &str) -> Option<String> {
+    let length = username.chars().count();
+    if !(MIN_USERNAME_LENGTH..=MAX_USERNAME_LENGTH).contains(&length) {
+        return None;
+    }
+    if !username.chars().all(|character| character.is_ascii_alphanumeric() || character == '_') {
+        return None;
+    }
+    return Some(username.to_ascii_lowercase());
+}
+
+/// Whether an email address looks deliverable: something before and after a single `@`, with a dot in the domain.
+pub fn is_valid_email(email: &str) -> bool {
+    if email.len() > MAX_EMAIL_LENGTH || email.chars().any(|character| character.is_whitespace()) {
+        return false;
+    }
+    return match email.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && !domain.contains('@') && domain.contains('.') && !domain.starts_with('.') && !domain.ends_with('.'),
+        None => false
+    };
+}
+
+/* A pending account recovery. Only a hash of the token is stored, like a password. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct RecoveryToken {
+    pub token_hash: String,
+    /// Unix seconds the token stops working at.
+    pub expires_at: u64
+}
+
+/* An account's two factor authentication. The secret is stored as is, since codes are generated from it, but recovery
+codes are only stored hashed, like a password. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TwoFactor {
+    pub secret: [u8; TOTP_SECRET_BYTES],
+    /// Whether a code has proven the player's authenticator has the secret. Until then, logins don't ask for codes.
+    pub confirmed: bool,
+    /// Hashes of the recovery codes not used yet, each after the code's tag. See hash_password() and
+    /// get_recovery_code_tag()
+    pub recovery_code_hashes: Vec<String>,
+    /// Latest time step a code was accepted for. Codes for it or earlier steps are refused, so a code seen over someone's
+    /// shoulder can't be used again.
+    pub last_used_step: u64
+}
+
+/* How an account logs in, stored apart from the player's profile by normalized username. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Credentials {
+    pub username: String,
+    pub player: PlayerId,
+    /// PHC string of the password. See hash_password()
+    pub password_hash: String,
+    /// Where recovery tokens are sent. Accounts without one can't be recovered.
+    pub email: Option<String>,
+    /// Failed logins in a row since the last success or lockout.
+    pub failed_attempts: u32,
+    /// Unix seconds logins are refused until, or 0 if not locked out.
+    pub locked_until: u64,
+    pub recovery: Option<RecoveryToken>,
+    pub two_factor: Option<TwoFactor>
+}
+
+impl Credentials {
+    /// Will panic if the username isn't normalized. See normalize_username()
+    pub fn new(username: String, player: PlayerId, password_hash: String, email: Option<String>) -> Credentials {
+        assert!(normalize_username(&username).as_ref() == Some(&username), "Username [{}] is not normalized", username);
+        return Credentials { username, player, password_hash, email, failed_attempts: 0, locked_until: 0, recovery: None, two_factor: None };
+    }
+
+    pub fn is_locked_out(&self, unix_seconds: u64) -> bool {
+        return unix_seconds < self.locked_until;
+    }
+
+    /// Whether logins need a code, which is only once two factor authentication is confirmed.
+    pub fn has_two_factor(&self) -> bool {
+        return self.two_factor.as_ref().is_some_and(|two_factor| two_factor.confirmed);
+    }
+
+    /// Encode for storage.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::auth::credentials::{Credentials, RecoveryToken, TwoFactor};
+    ///
+    /// let mut credentials = Credentials::new("misty".to_string(), PlayerId(4), "$argon2id$hash".to_string(), Some("misty@example.com".to_string()));
+    /// assert_eq!(Credentials::from_bytes(&credentials.to_bytes()).unwrap(), credentials);
+    /// credentials.email = None;
+    /// credentials.recovery = Some(RecoveryToken { token_hash: "$argon2id$token".to_string(), expires_at: 1_700_000_000 });
+    /// assert_eq!(Credentials::from_bytes(&credentials.to_bytes()).unwrap(), credentials);
+    /// credentials.two_factor = Some(TwoFactor { secret: [7; 20], confirmed: true, recovery_code_hashes: vec!["$argon2id$code".to_string()], last_used_step: 56_666_666 });
+    /// assert_eq!(Credentials::from_bytes(&credentials.to_bytes()).unwrap(), credentials);
+    /// assert!(Credentials::from_bytes(&credentials.to_bytes()[..10]).is_err());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        write_string(&mut bytes, &self.username);
+        bytes.extend_from_slice(&self.player.0.to_le_bytes());
+        write_string(&mut bytes, &self.password_hash);
+        match &self.email {
+            Some(email) => {
+                bytes.push(1);
+                write_string(&mut bytes, email);
+            },
+            None => bytes.push(0)
+        }
+        bytes.extend_from_slice(&self.failed_attempts.to_le_bytes());
+        bytes.extend_from_slice(&self.locked_until.to_le_bytes());
+        match &self.recovery {
+            Some(recovery) => {
+                bytes.push(1);
+                write_string(&mut bytes, &recovery.token_hash);
+                bytes.extend_from_slice(&recovery.expires_at.to_le_bytes());
+            },
+            None => bytes.push(0)
+        }
+        match &self.two_factor {
+            Some(two_factor) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&two_factor.secret);
+                bytes.push(two_factor.confirmed as u8);
+                bytes.extend_from_slice(&(two_factor.recovery_code_hashes.len() as u32).to_le_bytes());
+                for hash in two_factor.recovery_code_hashes.iter() {
+                    write_string(&mut bytes, hash);
+                }
+                bytes.extend_from_slice(&two_factor.last_used_step.to_le_bytes());
+            },
+            None => bytes.push(0)
+        }
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<Credentials> {
+        let mut reader = ByteReader::new(bytes);
+        let username = reader.take_string()?;
+        let player = PlayerId(u64::from_le_bytes(reader.take_array()?));
+        let password_hash = reader.take_string()?;
+        let email = match reader.take_array::<1>()? {
+            [0] => None,
+            [1] => Some(reader.take_string()?),
+            [tag] => return Err(io::Error::new(ErrorKind::InvalidData, format!("Invalid email tag {}", tag)))
+        };
+        let failed_attempts = u32::from_le_bytes(reader.take_array()?);
+        let locked_until = u64::from_le_bytes(reader.take_array()?);
+        let recovery = match reader.take_array::<1>()? {
+            [0] => None,
+            [1] => Some(RecoveryToken { token_hash: reader.take_string()?, expires_at: u64::from_le_bytes(reader.take_array()?) }),
+            [tag] => return Err(io::Error::new(ErrorKind::InvalidData, format!("Invalid recovery tag {}", tag)))
+        };
+        // Credentials saved before two factor authentication existed end here
+        let two_factor = match reader.get_remaining() {
+            0 => None,
+            _ => read_two_factor(&mut reader)?
+        };
+        if reader.get_remaining() != 0 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Trailing bytes after credentials"));
+        }
+        return Ok(Credentials { username, player, password_hash, email, failed_attempts, locked_until, recovery, two_factor });
+    }
+}
+
+fn read_two_factor(reader: &mut ByteReader) -> io::Result<Option<TwoFactor>> {
+    return match reader.take_array::<1>()? {
+        [0] => Ok(None),
+        [1] => {
+            let secret = reader.take_array()?;
+            let confirmed = match reader.take_array::<1>()? {
+                [0] => false,
+                [1] => true,
+                [value] => return Err(io::Error::new(ErrorKind::InvalidData, format!("Invalid two factor confirmed flag {}", value)))
+            };
+            let count = u32::from_le_bytes(reader.take_array()?);
+            let recovery_code_hashes = (0..count).map(|_| reader.take_string()).collect::<io::Result<Vec<String>>>()?;
+            Ok(Some(TwoFactor { secret, confirmed, recovery_code_hashes, last_used_step: u64::from_le_bytes(reader.take_array()?) }))
+        },
+        [tag] => Err(io::Error::new(ErrorKind::InvalidData, format!("Invalid two factor tag {}", tag)))
+    };
+}
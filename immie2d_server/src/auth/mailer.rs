@@ -0,0 +1,40 @@
+use std::io;
+use std::sync::{Arc, Mutex};
+
+/* Sends account emails. Servers without a mailer can't recover accounts, so the email provider stays pluggable. */
+pub trait Mailer: Send {
+    /// Send a recovery token to the email address of an account.
+    fn send_recovery_token(&mut self, email: &str, username: &str, token: &str) -> io::Result<()>;
+}
+
+/* An email a RecordingMailer was asked to send. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SentRecovery {
+    pub email: String,
+    pub username: String,
+    pub token: String
+}
+
+/* A mailer that keeps what it sends instead of sending it, for tests and local development servers. Clones share the
+same outbox, so a clone kept outside of the AuthService sees every email it sent. */
+#[derive(Clone)]
+pub struct RecordingMailer {
+    sent: Arc<Mutex<Vec<SentRecovery>>>
+}
+
+impl RecordingMailer {
+    pub fn new() -> RecordingMailer {
+        return RecordingMailer { sent: Arc::new(Mutex::new(Vec::new())) };
+    }
+
+    pub fn get_sent(&self) -> Vec<SentRecovery> {
+        return self.sent.lock().unwrap().clone();
+    }
+}
+
+impl Mailer for RecordingMailer {
+    fn send_recovery_token(&mut self, email: &str, username: &str, token: &str) -> io::Result<()> {
+        self.sent.lock().unwrap().push(SentRecovery { email: email.to_string(), username: username.to_string(), token: token.to_string() });
+        return Ok(());
+    }
+}
@@ -0,0 +1,6 @@
+pub mod password_hash;
+pub mod credentials;
+pub mod auth_message;
+pub mod mailer;
+pub mod two_factor;
+pub mod auth_service;
@@ -0,0 +1,71 @@
+use argon2::{Algorithm, Argon2, Params, Version};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use rand_core::{OsRng, RngCore};
+
+/// Shortest password accepted for an account.
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+/// Longest password accepted, so a huge password can't be used to make hashing slow.
+pub const MAX_PASSWORD_LENGTH: usize = 128;
+
+/* How expensive hashing a password with argon2id is. Hashes store the cost they were made with, so raising it only
+applies to passwords hashed from then on. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct HashingCost {
+    pub memory_kib: u32,
+    pub iterations: u32
+}
+
+impl HashingCost {
+    /// The cost recommended for argon2id.
+    pub fn default() -> HashingCost {
+        return HashingCost { memory_kib: Params::DEFAULT_M_COST, iterations: Params::DEFAULT_T_COST };
+    }
+
+    /// The lowest cost argon2 allows, only for tests.
+    pub fn minimum() -> HashingCost {
+        return HashingCost { memory_kib: Params::MIN_M_COST, iterations: Params::MIN_T_COST };
+    }
+
+    fn create_hasher(self) -> Argon2<'static> {
+        let params = Params::new(self.memory_kib, self.iterations, 1, None).expect("Argon2 hashing cost is out of range");
+        return Argon2::new(Algorithm::Argon2id, Version::V0x13, params);
+    }
+}
+
+/// Whether a password is long enough to use and short enough to hash.
+pub fn is_valid_password(password: &str) -> bool {
+    return (MIN_PASSWORD_LENGTH..=MAX_PASSWORD_LENGTH).contains(&password.chars().count());
+}
+
+/// Hash a password with argon2id and a random salt, in the PHC string format that stores the salt and cost with it.
+/// ```
+/// use immie2d_server::auth::password_hash::{hash_password, verify_password, HashingCost};
+///
+/// let hash = hash_password("correct horse", HashingCost::minimum());
+/// assert!(hash.starts_with("$argon2id$"));
+/// assert!(verify_password("correct horse", &hash));
+/// assert!(!verify_password("correct horse!", &hash));
+/// // Salted, so the same password hashes differently
+/// assert_ne!(hash_password("correct horse", HashingCost::minimum()), hash);
+/// assert!(!verify_password("correct horse", "not a hash"));
+/// ```
+pub fn hash_password(password: &str, cost: HashingCost) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    return cost.create_hasher().hash_password(password.as_bytes(), &salt).expect("Argon2 failed to hash a password").to_string();
+}
+
+/// Check a password against a hash from hash_password(), using the cost stored in the hash. Malformed hashes never
+/// match.
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    let Ok(hash) = PasswordHash::new(hash) else {
+        return false;
+    };
+    return Argon2::default().verify_password(password.as_bytes(), &hash).is_ok();
+}
+
+/// A random token of 128 bits as hex, such as for account recovery.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    return bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
@@ -0,0 +1,173 @@
+use hmac::{Hmac, Mac};
+use rand_core::{OsRng, RngCore};
+use sha1::Sha1;
+use sha2::Sha256;
+
+/// Length of a TOTP secret, the size of an HMAC-SHA1 output as RFC 4226 recommends.
+pub const TOTP_SECRET_BYTES: usize = 20;
+/// Seconds each code is shown for by authenticator apps.
+pub const TOTP_STEP_SECONDS: u64 = 30;
+pub const TOTP_DIGITS: u32 = 6;
+/// Steps either side of the current one whose codes are still accepted, for clocks that drift and codes typed just
+/// before they change.
+pub const TOTP_SKEW_STEPS: u64 = 1;
+/// Name accounts are listed under in authenticator apps.
+pub const TOTP_ISSUER: &str = "Immie2d";
+/// How many single use recovery codes an account gets when it sets up two factor authentication.
+pub const RECOVERY_CODE_COUNT: usize = 10;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/* Whether players must use two factor authentication to log in on this server. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TwoFactorPolicy {
+    /// Players choose whether to set it up.
+    Optional,
+    /// Accounts without it can't log in until they set it up, and can't turn it off.
+    Required
+}
+
+impl TwoFactorPolicy {
+    pub fn get_name(self) -> &'static str {
+        return match self {
+            TwoFactorPolicy::Optional => "optional",
+            TwoFactorPolicy::Required => "required"
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<TwoFactorPolicy> {
+        return match name {
+            "optional" => Some(TwoFactorPolicy::Optional),
+            "required" => Some(TwoFactorPolicy::Required),
+            _ => None
+        };
+    }
+}
+
+/// A random secret for a new authenticator.
+pub fn generate_secret() -> [u8; TOTP_SECRET_BYTES] {
+    let mut secret = [0u8; TOTP_SECRET_BYTES];
+    OsRng.fill_bytes(&mut secret);
+    return secret;
+}
+
+/// Encode bytes as unpadded RFC 4648 base32, the form authenticator apps take secrets in.
+/// ```
+/// use immie2d_server::auth::two_factor::encode_base32;
+///
+/// assert_eq!(encode_base32(b"foobar"), "MZXW6YTBOI");
+/// assert_eq!(encode_base32(b""), "");
+/// ```
+pub fn encode_base32(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | *byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32_ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32_ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    return out;
+}
+
+/// The otpauth URI authenticator apps add an account from, usually shown as a QR code.
+/// ```
+/// use immie2d_server::auth::two_factor::get_provisioning_uri;
+///
+/// assert_eq!(get_provisioning_uri("misty", b"foobar"), "otpauth://totp/Immie2d:misty?secret=MZXW6YTBOI&issuer=Immie2d&algorithm=SHA1&digits=6&period=30");
+/// ```
+pub fn get_provisioning_uri(username: &str, secret: &[u8]) -> String {
+    // Normalized usernames are only letters, digits and underscores, so need no escaping
+    return format!("otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        TOTP_ISSUER, username, encode_base32(secret), TOTP_ISSUER, TOTP_DIGITS, TOTP_STEP_SECONDS);
+}
+
+/// The time step codes are generated for at a unix time.
+pub fn get_step(unix_seconds: u64) -> u64 {
+    return unix_seconds / TOTP_STEP_SECONDS;
+}
+
+/// The code for a time step, as RFC 6238 TOTP with HMAC-SHA1, zero padded to TOTP_DIGITS.
+/// ```
+/// use immie2d_server::auth::two_factor::{get_code, get_step};
+///
+/// // Test vectors from RFC 6238
+/// let secret = b"12345678901234567890";
+/// assert_eq!(get_code(secret, get_step(59)), "287082");
+/// assert_eq!(get_code(secret, get_step(1111111109)), "081804");
+/// assert_eq!(get_code(secret, get_step(2000000000)), "279037");
+/// ```
+pub fn get_code(secret: &[u8], step: u64) -> String {
+    let mut mac = Hmac::<Sha1>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(&step.to_be_bytes());
+    let digest = mac.finalize().into_bytes();
+    let offset = (digest[digest.len() - 1] & 0xf) as usize;
+    let truncated = u32::from_be_bytes([digest[offset], digest[offset + 1], digest[offset + 2], digest[offset + 3]]) & 0x7fff_ffff;
+    return format!("{:0width$}", truncated % 10u32.pow(TOTP_DIGITS), width = TOTP_DIGITS as usize);
+}
+
+/// The time step a code is for, if it's the code for a step within TOTP_SKEW_STEPS of the current one. Spaces people
+/// type to group the digits are ignored.
+/// ```
+/// use immie2d_server::auth::two_factor::{find_step, get_code, get_step, TOTP_STEP_SECONDS};
+///
+/// let secret = b"12345678901234567890";
+/// let now = 1_700_000_000;
+/// let code = get_code(secret, get_step(now));
+/// assert_eq!(find_step(secret, &code, now), Some(get_step(now)));
+/// assert_eq!(find_step(secret, &format!("{} {}", &code[..3], &code[3..]), now), Some(get_step(now)));
+/// // The previous code still works for a step, in case it was typed as it changed
+/// assert_eq!(find_step(secret, &code, now + TOTP_STEP_SECONDS), Some(get_step(now)));
+/// assert_eq!(find_step(secret, &code, now + TOTP_STEP_SECONDS * 2), None);
+/// assert_eq!(find_step(secret, "12345", now), None);
+/// ```
+pub fn find_step(secret: &[u8], code: &str, unix_seconds: u64) -> Option<u64> {
+    let code: String = code.chars().filter(|character| !character.is_whitespace()).collect();
+    if code.len() != TOTP_DIGITS as usize || !code.chars().all(|character| character.is_ascii_digit()) {
+        return None;
+    }
+    let current = get_step(unix_seconds);
+    return (current.saturating_sub(TOTP_SKEW_STEPS)..=current + TOTP_SKEW_STEPS).find(|step| get_code(secret, *step) == code);
+}
+
+/// A random single use recovery code of 40 bits, as two groups of hex digits so it's easy to copy down.
+pub fn generate_recovery_code() -> String {
+    let mut bytes = [0u8; 5];
+    OsRng.fill_bytes(&mut bytes);
+    let digits: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    return format!("{}-{}", &digits[..5], &digits[5..]);
+}
+
+/// The form a recovery code is hashed and checked in, ignoring case, spaces and dashes.
+/// ```
+/// use immie2d_server::auth::two_factor::normalize_recovery_code;
+///
+/// assert_eq!(normalize_recovery_code(" 3F9A0-C71E2 "), "3f9a0c71e2");
+/// ```
+pub fn normalize_recovery_code(code: &str) -> String {
+    return code.chars().filter(|character| !character.is_whitespace() && *character != '-').map(|character| character.to_ascii_lowercase()).collect();
+}
+
+/// A short tag of a normalized recovery code, stored in front of the code's hash so checking a code only runs the slow
+/// hash against the stored codes with the same tag. The tag is an HMAC keyed with the account's TOTP secret, so tags
+/// can't be matched against codes precomputed for every account. At 16 bits it narrows down which code was typed
+/// without revealing enough of it to matter.
+/// ```
+/// use immie2d_server::auth::two_factor::{get_recovery_code_tag, normalize_recovery_code};
+///
+/// let tag = get_recovery_code_tag(b"12345678901234567890", &normalize_recovery_code("3F9A0-C71E2"));
+/// assert_eq!(tag.len(), 4);
+/// assert_eq!(tag, get_recovery_code_tag(b"12345678901234567890", "3f9a0c71e2"));
+/// assert_ne!(tag, get_recovery_code_tag(b"09876543210987654321", "3f9a0c71e2"));
+/// ```
+pub fn get_recovery_code_tag(secret: &[u8], normalized_code: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(normalized_code.as_bytes());
+    return mac.finalize().into_bytes()[..2].iter().map(|byte| format!("{:02x}", byte)).collect();
+}
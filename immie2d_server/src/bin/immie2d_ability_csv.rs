@@ -0,0 +1,59 @@
+#![allow(clippy::needless_return)]
+
+use std::{env, fs, process};
+
+use immie2d_shared::modding::ability_csv::{abilities_to_csv, csv_to_abilities, diff_abilities};
+
+const ABILITY_CSV_USAGE: &str = "Usage: immie2d_ability_csv [--namespace <namespace>] <command>
+commands:
+    export <abilities.json> <abilities.csv>
+    diff <abilities.json> <abilities.csv>
+    import <abilities.csv> <abilities.json>    writes the spreadsheet over the abilities file, listing what changed";
+
+fn read(path: &str) -> Result<String, String> {
+    return fs::read_to_string(path).map_err(|err| format!("Failed to read {}: {}", path, err));
+}
+
+fn write(path: &str, contents: &str) -> Result<(), String> {
+    return fs::write(path, contents).map_err(|err| format!("Failed to write {}: {}", path, err));
+}
+
+/// Run a command from the arguments after the namespace option.
+fn run(args: &[String], namespace: Option<&str>) -> Result<(), String> {
+    let (Some(command), Some(from), Some(to)) = (args.first(), args.get(1), args.get(2)) else {
+        return Err("Missing command or path".to_string());
+    };
+    match command.as_str() {
+        "export" => write(to, &abilities_to_csv(&read(from)?, namespace).map_err(|err| err.to_string())?)?,
+        "diff" => {
+            for change in diff_abilities(&read(from)?, &read(to)?, namespace).map_err(|err| err.to_string())? {
+                println!("{}", change);
+            }
+        },
+        "import" => {
+            let csv = read(from)?;
+            let json = csv_to_abilities(&csv, namespace).map_err(|err| err.to_string())?;
+            // A missing abilities file is a fresh import, where every ability is new
+            if let Ok(current) = fs::read_to_string(to) {
+                for change in diff_abilities(&current, &csv, namespace).map_err(|err| err.to_string())? {
+                    println!("{}", change);
+                }
+            }
+            write(to, &json)?;
+        },
+        _ => return Err(format!("Unknown command [{}]", command))
+    }
+    return Ok(());
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (namespace, command_args) = match args.first().map(|arg| arg.as_str()) {
+        Some("--namespace") if args.len() >= 2 => (Some(args[1].as_str()), &args[2..]),
+        _ => (None, &args[..])
+    };
+    if let Err(err) = run(command_args, namespace) {
+        eprintln!("{}\n{}", err, ABILITY_CSV_USAGE);
+        process::exit(1);
+    }
+}
@@ -0,0 +1,100 @@
+#![allow(clippy::needless_return)]
+
+use std::{env, fs, path::{Path, PathBuf}, process};
+
+use immie2d_server::config::server_config::ServerConfig;
+use immie2d_server::session::replay_bisector::{find_divergence, ReplaySource};
+use immie2d_server::session::session_snapshot::SessionSnapshot;
+use immie2d_shared::gameplay::game_data::{GameData, GameDataHandle};
+
+const REPLAY_BISECT_USAGE: &str = "Usage: immie2d_replay_bisect <replay> [<other replay>] [--data <game data directory>] [--other-data <game data directory>]
+Without another replay, the replay is re-simulated against the other game data, which defaults to --data";
+
+/* What to compare, from the command line. */
+struct BisectArgs {
+    replay: PathBuf,
+    other_replay: Option<PathBuf>,
+    data: PathBuf,
+    other_data: Option<PathBuf>
+}
+
+/// Parse the replays and data directories from the command line arguments, excluding the program name.
+fn parse_args(args: &[String]) -> Result<BisectArgs, String> {
+    let mut replays = Vec::new();
+    let mut data = ServerConfig::default().game_data_directory;
+    let mut other_data = None;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--data" => data = PathBuf::from(rest.next().ok_or("Missing value for --data")?),
+            "--other-data" => other_data = Some(PathBuf::from(rest.next().ok_or("Missing value for --other-data")?)),
+            _ if arg.starts_with("--") => return Err(format!("Unknown option [{}]", arg)),
+            _ => replays.push(PathBuf::from(arg))
+        }
+    }
+    if replays.len() > 2 {
+        return Err("At most two replays can be compared".to_string());
+    }
+    let mut replays = replays.into_iter();
+    let replay = replays.next().ok_or("Missing replay file")?;
+    return Ok(BisectArgs { replay, other_replay: replays.next(), data, other_data });
+}
+
+fn load_data(directory: &Path) -> Result<GameDataHandle, String> {
+    let config = ServerConfig { game_data_directory: directory.to_path_buf(), ..ServerConfig::default() };
+    let core = config.load_game_data(|_| {}).map_err(|errors| {
+        let reasons: Vec<String> = errors.iter().map(|error| error.to_string()).collect();
+        return format!("Failed to load game data from {}:\n{}", directory.display(), reasons.join("\n"));
+    })?;
+    return Ok(GameData::new(0, core.species_map, core.ability_map, core.item_map).into_handle());
+}
+
+fn load_replay(path: &Path) -> Result<SessionSnapshot, String> {
+    let bytes = fs::read(path).map_err(|err| format!("Failed to read {}: {}", path.display(), err))?;
+    return SessionSnapshot::from_bytes(&bytes).map_err(|err| format!("Failed to decode replay {}: {}", path.display(), err));
+}
+
+/// Compare the replays, returning whether they diverge.
+fn run(args: &BisectArgs) -> Result<bool, String> {
+    let data = load_data(&args.data)?;
+    let other_data = match &args.other_data {
+        Some(directory) => load_data(directory)?,
+        None => data.clone()
+    };
+    let replay = load_replay(&args.replay)?;
+    let other_replay = match &args.other_replay {
+        Some(path) => load_replay(path)?,
+        None => replay.clone()
+    };
+    let commands = replay.commands.len().max(other_replay.commands.len());
+    match find_divergence(&ReplaySource::new(replay, data), &ReplaySource::new(other_replay, other_data)) {
+        Some(divergence) => {
+            print!("{}", divergence);
+            return Ok(true);
+        },
+        None => {
+            println!("Replays match through all {} commands", commands);
+            return Ok(false);
+        }
+    }
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{}\n{}", err, REPLAY_BISECT_USAGE);
+            process::exit(2);
+        }
+    };
+    match run(&args) {
+        Ok(false) => {},
+        // Diverging replays fail like a failed check, so the tool can gate CI
+        Ok(true) => process::exit(1),
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    }
+}
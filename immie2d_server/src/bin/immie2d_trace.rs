@@ -0,0 +1,48 @@
+#![allow(clippy::needless_return)]
+
+use std::{env, path::PathBuf, process};
+
+use immie2d_server::network::protocol_trace::{read_trace, TraceDirection, TraceFilter};
+
+const TRACE_USAGE: &str = "Usage: immie2d_trace <trace file> [--connection <id>] [--direction in|out] [--type <message type>] [--since <micros>]";
+
+/// Parse the trace file and filter from the command line arguments, excluding the program name.
+fn parse_args(args: &[String]) -> Result<(PathBuf, TraceFilter), String> {
+    let path = args.first().ok_or("Missing trace file")?;
+    let mut filter = TraceFilter::default();
+    let mut rest = args[1..].iter();
+    while let Some(flag) = rest.next() {
+        let value = rest.next().ok_or(format!("Missing value for {}", flag))?;
+        let parse_number = || value.parse::<u64>().map_err(|_| format!("Invalid number [{}] for {}", value, flag));
+        match flag.as_str() {
+            "--connection" => filter.connection = Some(parse_number()?),
+            "--direction" => filter.direction = Some(TraceDirection::from_name(value).ok_or(format!("Unknown direction [{}]", value))?),
+            "--type" => filter.message_type = Some(value.clone()),
+            "--since" => filter.since = Some(parse_number()?),
+            _ => return Err(format!("Unknown option [{}]", flag))
+        }
+    }
+    return Ok((PathBuf::from(path), filter));
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (path, filter) = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{}\n{}", err, TRACE_USAGE);
+            process::exit(2);
+        }
+    };
+    match read_trace(&path, &filter) {
+        Ok(records) => {
+            for record in records {
+                println!("{}", record.to_pretty_string());
+            }
+        },
+        Err(err) => {
+            eprintln!("Failed to read trace {}: {}", path.display(), err);
+            process::exit(1);
+        }
+    }
+}
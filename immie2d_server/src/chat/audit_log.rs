@@ -0,0 +1,37 @@
+use std::time::SystemTime;
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use super::moderation::ModerationAction;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AuditEntry {
+    pub moderator: PlayerId,
+    pub action: ModerationAction,
+    pub time: SystemTime
+}
+
+/* Append-only record of every moderation action taken. */
+pub struct AuditLog {
+    entries: Vec<AuditEntry>
+}
+
+impl AuditLog {
+    pub fn new() -> AuditLog {
+        return AuditLog { entries: Vec::new() };
+    }
+
+    pub fn record(&mut self, moderator: PlayerId, action: ModerationAction) {
+        self.entries.push(AuditEntry { moderator, action, time: SystemTime::now() });
+    }
+
+    /// Every entry, oldest first.
+    pub fn get_entries(&self) -> &[AuditEntry] {
+        return &self.entries;
+    }
+
+    /// Every entry for actions taken by a moderator, oldest first.
+    pub fn get_entries_by(&self, moderator: PlayerId) -> Vec<AuditEntry> {
+        return self.entries.iter().filter(|entry| entry.moderator == moderator).copied().collect();
+    }
+}
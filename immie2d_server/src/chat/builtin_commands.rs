@@ -0,0 +1,141 @@
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::localization::message_format::MessageValue;
+use immie2d_shared::world::tile_position::WorldPosition;
+
+use crate::storage::player_profile::PlayerProfile;
+
+use super::moderation::Role;
+use super::slash_commands::{CommandArgs, SlashCommandError, SlashCommandRegistry, SlashCommandReply, SlashCommandSpec};
+
+/* The server state the built in commands run against. See register_builtin_commands() */
+pub trait CommandWorld {
+    /// The online player with a name, ignoring case.
+    fn find_player(&self, name: &str) -> Option<PlayerId>;
+
+    /// Where an online player is standing.
+    fn get_position(&self, player: PlayerId) -> Option<WorldPosition>;
+
+    /// The profile of an online player.
+    fn get_profile_mut(&mut self, player: PlayerId) -> Option<&mut PlayerProfile>;
+
+    fn is_item(&self, item: GlobalString) -> bool;
+
+    /// Offer a battle to another player. Returns false if they can't be challenged right now, such as when they are
+    /// already battling.
+    fn challenge(&mut self, challenger: PlayerId, opponent: PlayerId) -> bool;
+}
+
+fn find_player<C: CommandWorld>(world: &C, args: &CommandArgs, index: usize) -> Result<(PlayerId, String), SlashCommandError> {
+    let name = args.get_text(index, "player")?;
+    return match world.find_player(name) {
+        Some(player) => Ok((player, name.to_string())),
+        None => Err(SlashCommandError::Failed { key: "chat.player.offline", values: vec![("player", MessageValue::Text(name.to_string()))] })
+    };
+}
+
+/// Register `/challenge <player>`, `/where` and the admin only `/give <player> <item> [count]`.
+/// ```
+/// use std::collections::HashMap;
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::gameplay::player_id::PlayerId;
+/// use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+/// use immie2d_shared::world::tile_position::{TilePosition, WorldPosition};
+/// use immie2d_server::chat::builtin_commands::{register_builtin_commands, CommandWorld};
+/// use immie2d_server::chat::moderation::Role;
+/// use immie2d_server::chat::slash_commands::SlashCommandRegistry;
+/// use immie2d_server::storage::player_profile::PlayerProfile;
+///
+/// struct World { profiles: HashMap<PlayerId, PlayerProfile>, items: Vec<GlobalString>, battling: Vec<PlayerId>, challenges: Vec<(PlayerId, PlayerId)> }
+///
+/// impl CommandWorld for World {
+///     fn find_player(&self, name: &str) -> Option<PlayerId> {
+///         return self.profiles.values().find(|profile| profile.name.eq_ignore_ascii_case(name)).map(|profile| profile.player);
+///     }
+///     fn get_position(&self, _player: PlayerId) -> Option<WorldPosition> {
+///         return Some(WorldPosition::new(GlobalString::new(&"ember town".to_string()), TilePosition::new(3, -2)));
+///     }
+///     fn get_profile_mut(&mut self, player: PlayerId) -> Option<&mut PlayerProfile> {
+///         return self.profiles.get_mut(&player);
+///     }
+///     fn is_item(&self, item: GlobalString) -> bool {
+///         return self.items.contains(&item);
+///     }
+///     fn challenge(&mut self, challenger: PlayerId, opponent: PlayerId) -> bool {
+///         if self.battling.contains(&opponent) {
+///             return false;
+///         }
+///         self.challenges.push((challenger, opponent));
+///         return true;
+///     }
+/// }
+///
+/// let potion = GlobalString::new(&"potion".to_string());
+/// let (ash, misty, brock) = (PlayerId(1), PlayerId(2), PlayerId(3));
+/// let profiles = [(ash, "Ash"), (misty, "Misty"), (brock, "Brock")].map(|(player, name)| (player, PlayerProfile::new(player, name.to_string())));
+/// let mut world = World { profiles: HashMap::from(profiles), items: vec![potion], battling: vec![brock], challenges: Vec::new() };
+/// let mut commands = SlashCommandRegistry::<World>::new();
+/// register_builtin_commands(&mut commands);
+/// let catalog = LocalizationCatalog::from_json("en", r#"{
+///     "chat.where": "You are in {map} at {x}, {y}",
+///     "chat.challenge.sent": "Challenged {player}",
+///     "chat.challenge.busy": "{player} can't battle right now"
+/// }"#).unwrap();
+/// let mut run = |role, text: &str| commands.handle_message(&mut world, ash, role, text, &catalog).unwrap();
+///
+/// assert_eq!(run(Role::Player, "/where"), Some("You are in ember town at 3, -2".to_string()));
+/// assert_eq!(run(Role::Player, "/challenge misty"), Some("Challenged misty".to_string()));
+/// assert_eq!(run(Role::Player, "/challenge brock"), Some("brock can't battle right now".to_string()));
+/// assert_eq!(run(Role::Player, "/give misty potion 3"), Some("Unknown command /give, try /help".to_string()));
+/// assert_eq!(run(Role::Admin, "/give misty potion 3"), Some("chat.give.given".to_string()));
+/// assert_eq!(run(Role::Admin, "/give misty moon rock"), Some("chat.give.unknown_item".to_string()));
+/// assert_eq!(world.challenges, vec![(ash, misty)]);
+/// assert_eq!(world.profiles[&misty].inventory.get_count(potion), 3);
+/// ```
+pub fn register_builtin_commands<C: CommandWorld + 'static>(registry: &mut SlashCommandRegistry<C>) {
+    registry.register(SlashCommandSpec::new("challenge", "/challenge <player>"), |world, sender, args| {
+        args.expect_at_most(1)?;
+        let (opponent, name) = find_player(world, args, 0)?;
+        if opponent == sender {
+            return Err(SlashCommandError::Failed { key: "chat.challenge.self", values: Vec::new() });
+        }
+        if !world.challenge(sender, opponent) {
+            return Err(SlashCommandError::Failed { key: "chat.challenge.busy", values: vec![("player", MessageValue::Text(name))] });
+        }
+        return Ok(SlashCommandReply::Localized { key: "chat.challenge.sent", values: vec![("player", MessageValue::Text(name))] });
+    });
+    registry.register(SlashCommandSpec::new("where", "/where"), |world, sender, args| {
+        args.expect_at_most(0)?;
+        let Some(position) = world.get_position(sender) else {
+            return Err(SlashCommandError::Failed { key: "chat.where.nowhere", values: Vec::new() });
+        };
+        return Ok(SlashCommandReply::Localized { key: "chat.where", values: vec![
+            ("map", MessageValue::Text(position.map.to_string())),
+            ("x", MessageValue::Text(position.tile.x.to_string())),
+            ("y", MessageValue::Text(position.tile.y.to_string()))
+        ] });
+    });
+    registry.register(SlashCommandSpec::new("give", "/give <player> <item> [count]").with_role(Role::Admin), |world, _, args| {
+        args.expect_at_most(3)?;
+        let (player, name) = find_player(world, args, 0)?;
+        let item_name = args.get_text(1, "item")?;
+        // Only look the item up, so unknown names typed into chat are never interned
+        let item = GlobalString::new_if_exists(&item_name.to_string());
+        if item_name.is_empty() || !world.is_item(item) {
+            return Err(SlashCommandError::Failed { key: "chat.give.unknown_item", values: vec![("item", MessageValue::Text(item_name.to_string()))] });
+        }
+        let count = args.get_optional_number::<u32>(2, "count")?.unwrap_or(1);
+        let Some(profile) = world.get_profile_mut(player) else {
+            return Err(SlashCommandError::Failed { key: "chat.player.offline", values: vec![("player", MessageValue::Text(name))] });
+        };
+        if count == 0 || profile.inventory.get_count(item).checked_add(count).is_none() {
+            return Err(SlashCommandError::InvalidArgument { name: "count", value: count.to_string() });
+        }
+        profile.inventory.add_item(item, count);
+        return Ok(SlashCommandReply::Localized { key: "chat.give.given", values: vec![
+            ("player", MessageValue::Text(profile.name.clone())),
+            ("item", MessageValue::Text(item_name.to_string())),
+            ("count", MessageValue::Number(count as u64))
+        ] });
+    });
+}
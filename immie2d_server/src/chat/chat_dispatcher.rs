@@ -0,0 +1,270 @@
+use std::collections::{HashMap, HashSet};
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+
+use super::audit_log::AuditLog;
+use super::chat_message::{ChatChannel, ChatMessage, MessageId};
+use super::moderation::{ModerationAction, Report, Role};
+use super::slash_commands::SlashCommandRegistry;
+
+/// Longest chat message in bytes.
+pub const MAX_MESSAGE_LENGTH: usize = 256;
+/// Longest report reason in bytes.
+pub const MAX_REPORT_REASON_LENGTH: usize = 256;
+/// Most recent messages kept for reports and deletion. Older messages are forgotten.
+pub const MAX_MESSAGE_HISTORY: u64 = 10_000;
+/// Most reports waiting for review. The oldest report is dropped when another arrives.
+pub const MAX_PENDING_REPORTS: usize = 1000;
+
+/* A chat request from a player. */
+#[derive(Clone, PartialEq, Debug)]
+pub enum ChatCommand {
+    Send { channel: ChatChannel, text: String },
+    Report { message: MessageId, reason: String },
+    Mute { player: PlayerId },
+    Unmute { player: PlayerId },
+    Delete { message: MessageId }
+}
+
+/* What to broadcast to every player in a channel as the result of a command, or to send to one player. */
+#[derive(Clone, PartialEq, Debug)]
+pub enum ChatBroadcast {
+    Message(ChatMessage),
+    /// A message was deleted and clients should replace it with a placeholder.
+    Tombstone { channel: ChatChannel, message: MessageId },
+    /// The result of a slash command, shown only to the player who ran it.
+    Reply { player: PlayerId, text: String }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ChatError {
+    /// The command requires the moderator role or above.
+    PermissionDenied,
+    Muted,
+    EmptyMessage,
+    MessageTooLong,
+    ReasonTooLong,
+    /// The message doesn't exist, or is too old to still be kept. See MAX_MESSAGE_HISTORY
+    UnknownMessage
+}
+
+/* Handles chat commands for the lobby and every battle. Permissions and mutes are checked before a command is run,
+and every moderator action is written to the audit log. Only the last MAX_MESSAGE_HISTORY messages and
+MAX_PENDING_REPORTS reports are kept, so players spamming chat can't grow them without bound. */
+pub struct ChatDispatcher {
+    roles: HashMap<PlayerId, Role>,
+    muted: HashSet<PlayerId>,
+    messages: HashMap<MessageId, ChatMessage>,
+    reports: Vec<Report>,
+    audit_log: AuditLog,
+    next_message_id: u64
+}
+
+impl ChatDispatcher {
+    pub fn new() -> ChatDispatcher {
+        return ChatDispatcher {
+            roles: HashMap::new(),
+            muted: HashSet::new(),
+            messages: HashMap::new(),
+            reports: Vec::new(),
+            audit_log: AuditLog::new(),
+            next_message_id: 0
+        };
+    }
+
+    /// Players without a role set are regular players.
+    pub fn get_role(&self, player: PlayerId) -> Role {
+        return *self.roles.get(&player).unwrap_or(&Role::Player);
+    }
+
+    pub fn set_role(&mut self, player: PlayerId, role: Role) {
+        self.roles.insert(player, role);
+    }
+
+    pub fn is_muted(&self, player: PlayerId) -> bool {
+        return self.muted.contains(&player);
+    }
+
+    pub fn get_message(&self, message: MessageId) -> Option<&ChatMessage> {
+        return self.messages.get(&message);
+    }
+
+    /// Reports waiting for review, oldest first.
+    pub fn get_reports(&self) -> &[Report] {
+        return &self.reports;
+    }
+
+    /// Take every report for review.
+    pub fn take_reports(&mut self) -> Vec<Report> {
+        return std::mem::take(&mut self.reports);
+    }
+
+    pub fn get_audit_log(&self) -> &AuditLog {
+        return &self.audit_log;
+    }
+
+    /// Check whether a player is allowed to run a command, before it has any effect.
+    pub fn check_permission(&self, sender: PlayerId, command: &ChatCommand) -> Result<(), ChatError> {
+        return match command {
+            ChatCommand::Send { .. } => {
+                if self.is_muted(sender) {
+                    Err(ChatError::Muted)
+                }
+                else {
+                    Ok(())
+                }
+            },
+            ChatCommand::Report { .. } => Ok(()),
+            ChatCommand::Mute { .. } | ChatCommand::Unmute { .. } | ChatCommand::Delete { .. } => {
+                if self.get_role(sender) >= Role::Moderator {
+                    Ok(())
+                }
+                else {
+                    Err(ChatError::PermissionDenied)
+                }
+            }
+        };
+    }
+
+    /// Run a command from a player. Returns what should be broadcast to the affected channel, if anything.
+    /// ```
+    /// use immie2d_server::chat::chat_dispatcher::{ChatDispatcher, ChatCommand, ChatBroadcast, ChatError, MAX_MESSAGE_HISTORY, MAX_PENDING_REPORTS, MAX_REPORT_REASON_LENGTH};
+    /// use immie2d_server::chat::chat_message::ChatChannel;
+    /// use immie2d_server::chat::moderation::{Role, ModerationAction};
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    ///
+    /// let moderator = PlayerId(1);
+    /// let player = PlayerId(2);
+    /// let mut chat = ChatDispatcher::new();
+    /// chat.set_role(moderator, Role::Moderator);
+    ///
+    /// let sent = chat.dispatch(player, ChatCommand::Send { channel: ChatChannel::Lobby, text: "hi".to_string() }).unwrap();
+    /// let message = match sent {
+    ///     Some(ChatBroadcast::Message(message)) => message.id,
+    ///     _ => panic!()
+    /// };
+    ///
+    /// assert_eq!(chat.dispatch(player, ChatCommand::Mute { player: moderator }), Err(ChatError::PermissionDenied));
+    /// chat.dispatch(player, ChatCommand::Report { message, reason: "spam".to_string() }).unwrap();
+    /// chat.dispatch(moderator, ChatCommand::Mute { player }).unwrap();
+    /// assert_eq!(chat.dispatch(player, ChatCommand::Send { channel: ChatChannel::Lobby, text: "hi".to_string() }), Err(ChatError::Muted));
+    ///
+    /// let deleted = chat.dispatch(moderator, ChatCommand::Delete { message }).unwrap();
+    /// assert_eq!(deleted, Some(ChatBroadcast::Tombstone { channel: ChatChannel::Lobby, message }));
+    /// assert_eq!(chat.get_reports()[0].message.text, "hi");
+    /// assert_eq!(chat.get_audit_log().get_entries().len(), 2);
+    /// assert_eq!(chat.get_audit_log().get_entries()[1].action, ModerationAction::DeleteMessage(message));
+    ///
+    /// let too_long = ChatCommand::Report { message, reason: "a".repeat(MAX_REPORT_REASON_LENGTH + 1) };
+    /// assert_eq!(chat.dispatch(moderator, too_long), Err(ChatError::ReasonTooLong));
+    /// // Old messages and reports are forgotten once the limits are reached
+    /// for _ in 0..MAX_MESSAGE_HISTORY {
+    ///     chat.dispatch(moderator, ChatCommand::Send { channel: ChatChannel::Lobby, text: "spam".to_string() }).unwrap();
+    /// }
+    /// assert!(chat.get_message(message).is_none());
+    /// let Some(ChatBroadcast::Message(latest)) = chat.dispatch(moderator, ChatCommand::Send { channel: ChatChannel::Lobby, text: "hi".to_string() }).unwrap() else { panic!() };
+    /// for _ in 0..MAX_PENDING_REPORTS {
+    ///     chat.dispatch(player, ChatCommand::Report { message: latest.id, reason: "spam".to_string() }).unwrap();
+    /// }
+    /// assert_eq!(chat.get_reports().len(), MAX_PENDING_REPORTS);
+    /// assert_eq!(chat.get_reports()[0].message.id, latest.id);
+    /// ```
+    pub fn dispatch(&mut self, sender: PlayerId, command: ChatCommand) -> Result<Option<ChatBroadcast>, ChatError> {
+        self.check_permission(sender, &command)?;
+        match command {
+            ChatCommand::Send { channel, text } => {
+                if text.trim().is_empty() {
+                    return Err(ChatError::EmptyMessage);
+                }
+                if text.len() > MAX_MESSAGE_LENGTH {
+                    return Err(ChatError::MessageTooLong);
+                }
+                let id = MessageId(self.next_message_id);
+                self.next_message_id += 1;
+                let message = ChatMessage { id, channel, sender, text, is_deleted: false };
+                self.messages.insert(id, message.clone());
+                if let Some(forgotten) = self.next_message_id.checked_sub(MAX_MESSAGE_HISTORY + 1) {
+                    self.messages.remove(&MessageId(forgotten));
+                }
+                return Ok(Some(ChatBroadcast::Message(message)));
+            },
+            ChatCommand::Report { message, reason } => {
+                if reason.len() > MAX_REPORT_REASON_LENGTH {
+                    return Err(ChatError::ReasonTooLong);
+                }
+                let reported = match self.messages.get(&message) {
+                    Some(reported) => reported.clone(),
+                    None => return Err(ChatError::UnknownMessage)
+                };
+                if self.reports.len() >= MAX_PENDING_REPORTS {
+                    self.reports.remove(0);
+                }
+                self.reports.push(Report { reporter: sender, message: reported, reason });
+                return Ok(None);
+            },
+            ChatCommand::Mute { player } => {
+                self.muted.insert(player);
+                self.audit_log.record(sender, ModerationAction::Mute(player));
+                return Ok(None);
+            },
+            ChatCommand::Unmute { player } => {
+                self.muted.remove(&player);
+                self.audit_log.record(sender, ModerationAction::Unmute(player));
+                return Ok(None);
+            },
+            ChatCommand::Delete { message } => {
+                let deleted = match self.messages.get_mut(&message) {
+                    Some(deleted) if !deleted.is_deleted => deleted,
+                    _ => return Err(ChatError::UnknownMessage)
+                };
+                deleted.is_deleted = true;
+                let channel = deleted.channel;
+                self.audit_log.record(sender, ModerationAction::DeleteMessage(message));
+                return Ok(Some(ChatBroadcast::Tombstone { channel, message }));
+            }
+        }
+    }
+
+    /// Run a command from a player, running chat messages starting with `/` as slash commands instead of sending them.
+    /// Commands are run with the sender's role, even while they are muted, and their replies only go to the sender.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+    /// use immie2d_server::chat::chat_dispatcher::{ChatBroadcast, ChatCommand, ChatDispatcher};
+    /// use immie2d_server::chat::chat_message::ChatChannel;
+    /// use immie2d_server::chat::moderation::Role;
+    /// use immie2d_server::chat::slash_commands::{SlashCommandRegistry, SlashCommandReply, SlashCommandSpec};
+    ///
+    /// let mut commands = SlashCommandRegistry::<u32>::new();
+    /// commands.register(SlashCommandSpec::new("ping", "/ping"), |pings, _, _| {
+    ///     *pings += 1;
+    ///     return Ok(SlashCommandReply::Text("pong".to_string()));
+    /// });
+    /// commands.register(SlashCommandSpec::new("reset", "/reset").with_role(Role::Admin), |pings, _, _| {
+    ///     *pings = 0;
+    ///     return Ok(SlashCommandReply::None);
+    /// });
+    /// let catalog = LocalizationCatalog::from_json("en", "{}").unwrap();
+    /// let (player, mut pings) = (PlayerId(2), 0);
+    /// let mut chat = ChatDispatcher::new();
+    /// let mut send = |chat: &mut ChatDispatcher, text: &str| {
+    ///     let command = ChatCommand::Send { channel: ChatChannel::Lobby, text: text.to_string() };
+    ///     return chat.dispatch_with_commands(player, command, &commands, &mut pings, &catalog);
+    /// };
+    ///
+    /// assert_eq!(send(&mut chat, "/ping"), Ok(Some(ChatBroadcast::Reply { player, text: "pong".to_string() })));
+    /// assert_eq!(send(&mut chat, "/reset"), Ok(Some(ChatBroadcast::Reply { player, text: "Unknown command /reset, try /help".to_string() })));
+    /// assert!(matches!(send(&mut chat, "hello"), Ok(Some(ChatBroadcast::Message(_)))));
+    /// chat.set_role(player, Role::Admin);
+    /// assert_eq!(send(&mut chat, "/reset"), Ok(None));
+    /// ```
+    pub fn dispatch_with_commands<C>(&mut self, sender: PlayerId, command: ChatCommand, commands: &SlashCommandRegistry<C>, context: &mut C, catalog: &LocalizationCatalog) -> Result<Option<ChatBroadcast>, ChatError> {
+        if let ChatCommand::Send { text, .. } = &command {
+            if let Some(reply) = commands.handle_message(context, sender, self.get_role(sender), text, catalog) {
+                return Ok(reply.map(|text| ChatBroadcast::Reply { player: sender, text }));
+            }
+        }
+        return self.dispatch(sender, command);
+    }
+}
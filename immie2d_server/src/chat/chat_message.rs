@@ -0,0 +1,22 @@
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+/// Identifies a chat message for its whole lifetime, including after deletion.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct MessageId(pub u64);
+
+/* Where a chat message was sent. Battle chat is keyed by the id of the battle session. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ChatChannel {
+    Lobby,
+    Battle(u64)
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChatMessage {
+    pub id: MessageId,
+    pub channel: ChatChannel,
+    pub sender: PlayerId,
+    pub text: String,
+    /// Deleted messages are kept as tombstones so reports against them can still be reviewed.
+    pub is_deleted: bool
+}
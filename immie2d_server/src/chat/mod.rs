@@ -0,0 +1,6 @@
+pub mod chat_message;
+pub mod moderation;
+pub mod audit_log;
+pub mod chat_dispatcher;
+pub mod slash_commands;
+pub mod builtin_commands;
@@ -0,0 +1,29 @@
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use super::chat_message::{ChatMessage, MessageId};
+
+/* What a player may do in chat, each role able to do everything the roles before it can. */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Role {
+    Player,
+    /// Can mute and unmute players and delete messages.
+    Moderator,
+    /// Can also run admin chat commands, such as giving items.
+    Admin
+}
+
+/* Something a moderator did, recorded in the audit trail. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ModerationAction {
+    Mute(PlayerId),
+    Unmute(PlayerId),
+    DeleteMessage(MessageId)
+}
+
+/* A player reporting a message for moderators to review. Holds a copy of the message as it was when reported. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Report {
+    pub reporter: PlayerId,
+    pub message: ChatMessage,
+    pub reason: String
+}
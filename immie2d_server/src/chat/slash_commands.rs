@@ -0,0 +1,339 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+use immie2d_shared::localization::message_format::MessageValue;
+
+use super::moderation::Role;
+
+/* A chat message starting with `/`, split into the command name and its arguments. Arguments are separated by
+whitespace, and double quotes group words into a single argument, such as `/challenge "red fox"`. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SlashCommand {
+    /// Lowercase, without the slash.
+    pub name: String,
+    pub args: CommandArgs
+}
+
+impl SlashCommand {
+    /// Parse a chat message, or None if it isn't a command.
+    /// ```
+    /// use immie2d_server::chat::slash_commands::SlashCommand;
+    ///
+    /// let command = SlashCommand::parse("/Challenge \"red fox\"  singles").unwrap();
+    /// assert_eq!(command.name, "challenge");
+    /// assert_eq!(command.args.get_text(0, "player"), Ok("red fox"));
+    /// assert_eq!(command.args.get_text(1, "format"), Ok("singles"));
+    /// assert_eq!(command.args.len(), 2);
+    ///
+    /// assert_eq!(SlashCommand::parse("hello /where"), None);
+    /// assert_eq!(SlashCommand::parse("/ where"), None);
+    /// ```
+    pub fn parse(text: &str) -> Option<SlashCommand> {
+        let rest = text.trim_start().strip_prefix('/')?;
+        let name_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        if name_end == 0 {
+            return None;
+        }
+        let mut args = Vec::new();
+        let mut current = String::new();
+        let mut in_quotes = false;
+        let mut has_arg = false;
+        for c in rest[name_end..].chars() {
+            if c == '"' {
+                in_quotes = !in_quotes;
+                has_arg = true;
+            } else if c.is_whitespace() && !in_quotes {
+                if has_arg {
+                    args.push(std::mem::take(&mut current));
+                    has_arg = false;
+                }
+            } else {
+                current.push(c);
+                has_arg = true;
+            }
+        }
+        // An unterminated quote runs to the end of the message
+        if has_arg {
+            args.push(current);
+        }
+        return Some(SlashCommand { name: rest[..name_end].to_lowercase(), args: CommandArgs(args) });
+    }
+}
+
+/* The arguments of a command, with helpers that fail with the error to show the player. Arguments are named in
+errors by the name the handler asks for them with. */
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct CommandArgs(pub Vec<String>);
+
+impl CommandArgs {
+    pub fn len(&self) -> usize {
+        return self.0.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.0.is_empty();
+    }
+
+    pub fn get_text(&self, index: usize, name: &'static str) -> Result<&str, SlashCommandError> {
+        return self.0.get(index).map(|arg| arg.as_str()).ok_or(SlashCommandError::MissingArgument(name));
+    }
+
+    pub fn get_optional_text(&self, index: usize) -> Option<&str> {
+        return self.0.get(index).map(|arg| arg.as_str());
+    }
+
+    /// Parse an argument, such as a count or a player id.
+    /// ```
+    /// use immie2d_server::chat::slash_commands::{SlashCommand, SlashCommandError};
+    ///
+    /// let args = SlashCommand::parse("/give ash potion five").unwrap().args;
+    /// assert_eq!(args.get_number::<u32>(2, "count"), Err(SlashCommandError::InvalidArgument { name: "count", value: "five".to_string() }));
+    /// assert_eq!(args.get_number::<u32>(3, "count"), Err(SlashCommandError::MissingArgument("count")));
+    /// assert_eq!(args.get_optional_number::<u32>(3, "count"), Ok(None));
+    /// ```
+    pub fn get_number<T: FromStr>(&self, index: usize, name: &'static str) -> Result<T, SlashCommandError> {
+        let arg = self.get_text(index, name)?;
+        return arg.parse::<T>().map_err(|_| SlashCommandError::InvalidArgument { name, value: arg.to_string() });
+    }
+
+    pub fn get_optional_number<T: FromStr>(&self, index: usize, name: &'static str) -> Result<Option<T>, SlashCommandError> {
+        if index >= self.len() {
+            return Ok(None);
+        }
+        return self.get_number(index, name).map(Some);
+    }
+
+    /// Every argument from an index on, joined by spaces, for free text such as a reason.
+    pub fn get_rest(&self, index: usize, name: &'static str) -> Result<String, SlashCommandError> {
+        if index >= self.len() {
+            return Err(SlashCommandError::MissingArgument(name));
+        }
+        return Ok(self.0[index..].join(" "));
+    }
+
+    /// Fail if there are more arguments than a command takes.
+    pub fn expect_at_most(&self, count: usize) -> Result<(), SlashCommandError> {
+        if self.len() > count {
+            return Err(SlashCommandError::TooManyArguments { expected: count });
+        }
+        return Ok(());
+    }
+}
+
+/* What a command tells the player who ran it. Localized replies are formatted in the player's language. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SlashCommandReply {
+    None,
+    Text(String),
+    Localized { key: &'static str, values: Vec<(&'static str, MessageValue)> }
+}
+
+impl SlashCommandReply {
+    /// The text to show the player, or None if there is nothing to show. Localized replies the catalog can't format
+    /// fall back to their key, so a missing entry is visible rather than silent.
+    pub fn to_text(&self, catalog: &LocalizationCatalog) -> Option<String> {
+        return match self {
+            SlashCommandReply::None => None,
+            SlashCommandReply::Text(text) => Some(text.clone()),
+            SlashCommandReply::Localized { key, values } => Some(catalog.format(key, values).unwrap_or_else(|_| key.to_string()))
+        };
+    }
+}
+
+/* Why a command failed, shown to the player who ran it. See SlashCommandError::to_localized() */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SlashCommandError {
+    /// There is no such command, or the player's role can't run it, so commands above a role stay hidden.
+    UnknownCommand(String),
+    MissingArgument(&'static str),
+    InvalidArgument { name: &'static str, value: String },
+    TooManyArguments { expected: usize },
+    /// A failure particular to the command, such as a player that isn't online. Formatted from the catalog.
+    Failed { key: &'static str, values: Vec<(&'static str, MessageValue)> }
+}
+
+impl fmt::Display for SlashCommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            SlashCommandError::UnknownCommand(name) => write!(f, "Unknown command /{}, try /help", name),
+            SlashCommandError::MissingArgument(name) => write!(f, "Missing {}", name),
+            SlashCommandError::InvalidArgument { name, value } => write!(f, "Invalid {} [{}]", name, value),
+            SlashCommandError::TooManyArguments { expected } => write!(f, "Too many arguments, expected at most {}", expected),
+            SlashCommandError::Failed { key, .. } => write!(f, "{}", key)
+        };
+    }
+}
+
+impl SlashCommandError {
+    /// The catalog key and values of the error's message.
+    pub fn get_message(&self) -> (&'static str, Vec<(&'static str, MessageValue)>) {
+        return match self {
+            SlashCommandError::UnknownCommand(name) => ("chat.command.unknown", vec![("command", MessageValue::Text(name.clone()))]),
+            SlashCommandError::MissingArgument(name) => ("chat.command.missing_argument", vec![("argument", MessageValue::Text(name.to_string()))]),
+            SlashCommandError::InvalidArgument { name, value } => {
+                ("chat.command.invalid_argument", vec![("argument", MessageValue::Text(name.to_string())), ("value", MessageValue::Text(value.clone()))])
+            },
+            SlashCommandError::TooManyArguments { expected } => ("chat.command.too_many_arguments", vec![("count", MessageValue::Number(*expected as u64))]),
+            SlashCommandError::Failed { key, values } => (key, values.clone())
+        };
+    }
+
+    /// The error in the player's language, falling back to English if the catalog has no usable entry for it.
+    /// ```
+    /// use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+    /// use immie2d_server::chat::slash_commands::SlashCommandError;
+    ///
+    /// let catalog = LocalizationCatalog::from_json("fr", r#"{ "chat.command.unknown": "Commande inconnue /{command}" }"#).unwrap();
+    /// assert_eq!(SlashCommandError::UnknownCommand("fly".to_string()).to_localized(&catalog), "Commande inconnue /fly");
+    /// assert_eq!(SlashCommandError::MissingArgument("player").to_localized(&catalog), "Missing player");
+    /// ```
+    pub fn to_localized(&self, catalog: &LocalizationCatalog) -> String {
+        let (key, values) = self.get_message();
+        return catalog.format(key, &values).unwrap_or_else(|_| self.to_string());
+    }
+}
+
+/// Runs a command for a player, given the server state the registry was made for.
+pub type SlashCommandHandler<C> = Box<dyn Fn(&mut C, PlayerId, &CommandArgs) -> Result<SlashCommandReply, SlashCommandError> + Send + Sync>;
+
+/* A registered command. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SlashCommandSpec {
+    pub name: &'static str,
+    /// Shown by /help, such as `/give <player> <item> [count]`.
+    pub usage: &'static str,
+    /// The lowest role that can run the command.
+    pub role: Role,
+    pub aliases: Vec<&'static str>
+}
+
+impl SlashCommandSpec {
+    /// A command anyone can run.
+    pub fn new(name: &'static str, usage: &'static str) -> SlashCommandSpec {
+        return SlashCommandSpec { name, usage, role: Role::Player, aliases: Vec::new() };
+    }
+
+    pub fn with_role(mut self, role: Role) -> SlashCommandSpec {
+        self.role = role;
+        return self;
+    }
+
+    pub fn with_alias(mut self, alias: &'static str) -> SlashCommandSpec {
+        self.aliases.push(alias);
+        return self;
+    }
+}
+
+/* Every slash command the server understands, each run against some server state C. `/help`, listing the commands a
+player can run, is built in. */
+pub struct SlashCommandRegistry<C> {
+    commands: Vec<(SlashCommandSpec, SlashCommandHandler<C>)>,
+    /// Index of the command each name and alias belongs to.
+    names: HashMap<&'static str, usize>
+}
+
+impl<C> SlashCommandRegistry<C> {
+    pub fn new() -> SlashCommandRegistry<C> {
+        return SlashCommandRegistry { commands: Vec::new(), names: HashMap::new() };
+    }
+
+    /// Add a command. Names and aliases should be lowercase.
+    /// Will panic if the name or an alias is already taken, including by `help`.
+    pub fn register(&mut self, spec: SlashCommandSpec, handler: impl Fn(&mut C, PlayerId, &CommandArgs) -> Result<SlashCommandReply, SlashCommandError> + Send + Sync + 'static) {
+        let index = self.commands.len();
+        for name in std::iter::once(spec.name).chain(spec.aliases.iter().copied()) {
+            assert!(name != "help" && !self.names.contains_key(name), "Slash command /{} is already registered", name);
+            self.names.insert(name, index);
+        }
+        self.commands.push((spec, Box::new(handler)));
+    }
+
+    pub fn get_spec(&self, name: &str) -> Option<&SlashCommandSpec> {
+        return self.names.get(name).map(|index| &self.commands[*index].0);
+    }
+
+    /// Usage of every command a role can run, sorted by name.
+    pub fn get_help(&self, role: Role) -> Vec<&'static str> {
+        let mut specs: Vec<&SlashCommandSpec> = self.commands.iter().map(|(spec, _)| spec).filter(|spec| spec.role <= role).collect();
+        specs.sort_by_key(|spec| spec.name);
+        return std::iter::once("/help [command]").chain(specs.into_iter().map(|spec| spec.usage)).collect();
+    }
+
+    /// Run a command from a player with a role. Commands the role can't run are unknown, just as they are to /help.
+    pub fn execute(&self, context: &mut C, sender: PlayerId, role: Role, command: &SlashCommand) -> Result<SlashCommandReply, SlashCommandError> {
+        if command.name == "help" {
+            return self.help(role, &command.args);
+        }
+        return match self.names.get(command.name.as_str()).map(|index| &self.commands[*index]) {
+            Some((spec, handler)) if spec.role <= role => handler(context, sender, &command.args),
+            _ => Err(SlashCommandError::UnknownCommand(command.name.clone()))
+        };
+    }
+
+    fn help(&self, role: Role, args: &CommandArgs) -> Result<SlashCommandReply, SlashCommandError> {
+        args.expect_at_most(1)?;
+        let Some(name) = args.get_optional_text(0) else {
+            return Ok(SlashCommandReply::Text(self.get_help(role).join("\n")));
+        };
+        let name = name.trim_start_matches('/').to_lowercase();
+        // Commands a player can't run are described as unknown, so /help doesn't reveal them
+        return match self.get_spec(&name) {
+            Some(spec) if spec.role <= role => Ok(SlashCommandReply::Text(spec.usage.to_string())),
+            _ => Err(SlashCommandError::UnknownCommand(name))
+        };
+    }
+
+    /// Handle a chat message if it is a command. Returns None for ordinary messages, which should be sent to chat,
+    /// otherwise the text to show only the sender, in their language.
+    /// ```
+    /// use std::collections::HashMap;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+    /// use immie2d_shared::localization::message_format::MessageValue;
+    /// use immie2d_server::chat::moderation::Role;
+    /// use immie2d_server::chat::slash_commands::{SlashCommandRegistry, SlashCommandSpec, SlashCommandReply, SlashCommandError};
+    ///
+    /// struct World { locations: HashMap<PlayerId, String>, given: Vec<(String, String, u32)> }
+    ///
+    /// let mut commands = SlashCommandRegistry::<World>::new();
+    /// commands.register(SlashCommandSpec::new("where", "/where").with_alias("w"), |world, sender, args| {
+    ///     args.expect_at_most(0)?;
+    ///     let map = world.locations.get(&sender).cloned().unwrap_or_default();
+    ///     return Ok(SlashCommandReply::Localized { key: "chat.where", values: vec![("map", MessageValue::Text(map))] });
+    /// });
+    /// commands.register(SlashCommandSpec::new("give", "/give <player> <item> [count]").with_role(Role::Admin), |world, _, args| {
+    ///     let count = args.get_optional_number(2, "count")?.unwrap_or(1);
+    ///     if count == 0 {
+    ///         return Err(SlashCommandError::Failed { key: "chat.give.nothing", values: Vec::new() });
+    ///     }
+    ///     world.given.push((args.get_text(0, "player")?.to_string(), args.get_text(1, "item")?.to_string(), count));
+    ///     return Ok(SlashCommandReply::None);
+    /// });
+    ///
+    /// let catalog = LocalizationCatalog::from_json("en", r#"{ "chat.where": "You are in {map}" }"#).unwrap();
+    /// let mut world = World { locations: HashMap::from([(PlayerId(1), "ember town".to_string())]), given: Vec::new() };
+    /// let mut run = |role, text: &str| commands.handle_message(&mut world, PlayerId(1), role, text, &catalog);
+    ///
+    /// assert_eq!(run(Role::Player, "hello"), None);
+    /// assert_eq!(run(Role::Player, "/w"), Some(Some("You are in ember town".to_string())));
+    /// assert_eq!(run(Role::Player, "/where now"), Some(Some("Too many arguments, expected at most 0".to_string())));
+    /// assert_eq!(run(Role::Player, "/help"), Some(Some("/help [command]\n/where".to_string())));
+    /// assert_eq!(run(Role::Admin, "/help"), Some(Some("/help [command]\n/give <player> <item> [count]\n/where".to_string())));
+    /// assert_eq!(run(Role::Player, "/give ash potion"), Some(Some("Unknown command /give, try /help".to_string())));
+    /// assert_eq!(run(Role::Moderator, "/fly"), Some(Some("Unknown command /fly, try /help".to_string())));
+    ///
+    /// assert_eq!(run(Role::Admin, "/give ash potion 3"), Some(None));
+    /// assert_eq!(run(Role::Admin, "/give ash"), Some(Some("Missing item".to_string())));
+    /// assert_eq!(world.given, vec![("ash".to_string(), "potion".to_string(), 3)]);
+    /// ```
+    pub fn handle_message(&self, context: &mut C, sender: PlayerId, role: Role, text: &str, catalog: &LocalizationCatalog) -> Option<Option<String>> {
+        let command = SlashCommand::parse(text)?;
+        return Some(match self.execute(context, sender, role, &command) {
+            Ok(reply) => reply.to_text(catalog),
+            Err(error) => Some(error.to_localized(catalog))
+        });
+    }
+}
@@ -0,0 +1,442 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use immie2d_shared::engine_types::file_transfer::TransferKind;
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::engine_types::load_graph::{LoadError, LoadProgress};
+use immie2d_shared::gameplay::ability::ability_map::AbilityMap;
+use immie2d_shared::gameplay::ability::abilities::{fireball::Fireball, hidden_power::HiddenPower, pursuit::Pursuit};
+use immie2d_shared::gameplay::data_loader::{CoreData, CoreDataLoader};
+use immie2d_shared::modding::data_pack::{DataPack, DataPackError};
+use immie2d_shared::world::tile_position::{TilePosition, WorldPosition};
+use rand_core::{OsRng, RngCore};
+
+use crate::auth::two_factor::TwoFactorPolicy;
+use crate::distribution::gift_distribution::{GiftDistributor, GiftLoadError};
+use crate::network::file_transfer::TransferDirectories;
+use crate::world::region_instances::DEFAULT_REGION_CAPACITY;
+
+use crate::storage::backup::BackupConfig;
+use crate::storage::connection_pool::{ConnectionPool, PooledConnection};
+use crate::storage::file_storage::FileStorage;
+use crate::storage::memory_storage::MemoryStorage;
+use crate::storage::storage::Storage;
+
+/// Connections to whichever backend the config picked. See StorageBackend::open_pool()
+pub type StoragePool = ConnectionPool<Box<dyn Storage + Send>>;
+
+/* Where the server persists its data. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum StorageBackend {
+    /// Nothing is persisted. For local development only.
+    Memory,
+    File { directory: PathBuf },
+    /// Requires the server to be built with the postgres feature.
+    Postgres { url: String, pool_size: u32 }
+}
+
+impl StorageBackend {
+    /// Open a connection to the backend.
+    pub fn open(&self) -> io::Result<Box<dyn Storage + Send>> {
+        return match self {
+            StorageBackend::Memory => Ok(Box::new(MemoryStorage::new())),
+            StorageBackend::File { directory } => Ok(Box::new(FileStorage::open(directory)?)),
+            StorageBackend::Postgres { url, .. } => open_postgres(url)
+        };
+    }
+
+    /// Connections a pool of this backend holds. Only Postgres can usefully serve more than one at once.
+    pub fn get_pool_size(&self) -> usize {
+        return match self {
+            StorageBackend::Memory | StorageBackend::File { .. } => 1,
+            StorageBackend::Postgres { pool_size, .. } => *pool_size as usize
+        };
+    }
+
+    /// Open a pool of get_pool_size() connections for the server's threads to share.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::config::server_config::StorageBackend;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    ///
+    /// let pool = StorageBackend::Memory.open_pool().unwrap();
+    /// assert_eq!(pool.get_size(), 1);
+    /// let profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// pool.acquire(Duration::from_secs(1)).unwrap().save_profiles(&[profile.clone()]).unwrap();
+    /// // Every borrower sees the same storage
+    /// assert_eq!(pool.acquire(Duration::from_secs(1)).unwrap().load_profile(PlayerId(1)).unwrap(), Some(profile));
+    /// ```
+    pub fn open_pool(&self) -> io::Result<Arc<StoragePool>> {
+        let connections = (0..self.get_pool_size()).map(|_| self.open()).collect::<io::Result<Vec<_>>>()?;
+        return Ok(ConnectionPool::new(connections));
+    }
+}
+
+/// Borrow a connection from a storage pool, or fail with a timeout error if none is returned in time.
+pub fn acquire_storage(pool: &Arc<StoragePool>, timeout: Duration) -> io::Result<PooledConnection<Box<dyn Storage + Send>>> {
+    return pool.acquire(timeout).ok_or(io::Error::new(io::ErrorKind::TimedOut, "Timed out waiting for a storage connection"));
+}
+
+#[cfg(feature = "postgres")]
+fn open_postgres(url: &str) -> io::Result<Box<dyn Storage + Send>> {
+    return Ok(Box::new(crate::storage::postgres_storage::PostgresStorage::connect(url)?));
+}
+
+#[cfg(not(feature = "postgres"))]
+fn open_postgres(_url: &str) -> io::Result<Box<dyn Storage + Send>> {
+    return Err(io::Error::new(io::ErrorKind::Unsupported, "The server was built without the postgres feature"));
+}
+
+/// Parse a position written as `<map>,<x>,<y>`.
+fn parse_world_position(value: &str) -> Option<WorldPosition> {
+    let mut parts = value.rsplitn(3, ',');
+    let y = parts.next()?.trim().parse::<i32>().ok()?;
+    let x = parts.next()?.trim().parse::<i32>().ok()?;
+    let map = parts.next()?.trim();
+    if map.is_empty() {
+        return None;
+    }
+    return Some(WorldPosition::new(GlobalString::new(&map.to_string()), TilePosition::new(x, y)));
+}
+
+/* Operator settings for the server, persisted as `name=value` lines. Unlike the client config, mistakes are errors
+rather than ignored, so a typo can't silently start a server without its real storage. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ServerConfig {
+    pub bind_address: String,
+    pub storage: StorageBackend,
+    /// Debug mode logging every protocol frame to this file. See ProtocolTracer
+    pub protocol_trace: Option<PathBuf>,
+    /// Directory data packs are installed in, each in its own subdirectory.
+    pub pack_directory: PathBuf,
+    /// Subdirectories of the pack directory to enable, in install order. Advertised to clients during the handshake.
+    pub data_packs: Vec<String>,
+    /// Address of the file transfer channel, kept off the gameplay connection. See serve_transfer()
+    pub transfer_address: String,
+    /// Directory finished battle replays are stored in and downloaded from.
+    pub replay_directory: PathBuf,
+    /// Directory maps are downloaded from.
+    pub map_directory: PathBuf,
+    /// Directory core data is loaded from at startup. See CoreDataLoader
+    pub game_data_directory: PathBuf,
+    /// Most core data files to load at once, or 0 for one per core.
+    pub load_threads: usize,
+    /// Counts of interned GlobalStrings to log a warning at, to catch interning leaks. See GlobalString::set_warning_hook()
+    pub interned_string_warnings: Vec<usize>,
+    /// Where storage is backed up to, how often, and how many backups are kept. See BackupScheduler
+    pub backup: BackupConfig,
+    /// Whether players must set up two factor authentication. See AuthService::with_two_factor_policy()
+    pub two_factor: TwoFactorPolicy,
+    /// Players each instance of a region holds before another instance is opened. See SessionManager::with_region_capacity()
+    pub region_capacity: usize,
+    /// Where players are placed in the world when they log in.
+    pub start_position: WorldPosition,
+    /// Address to serve the HTTP API on, or None to not serve it. Needs the http_api feature. See ApiService
+    pub http_api: Option<String>,
+    /// Directory crash reports uploaded by clients are stored in, or None to not collect them. Served by the HTTP API.
+    /// See CrashInbox
+    pub crash_report_directory: Option<PathBuf>,
+    /// Address of the debug channel that pauses and steps the world simulation, or None to not serve it. It has no
+    /// authentication, so only set it on dev servers and keep it local. See serve_debug_connection()
+    pub debug_address: Option<String>,
+    /// File holding the key stat audit entries are signed with, generated on first start. It must be kept secret and
+    /// shared by every server of a deployment. See StatAuditLog
+    pub stat_audit_key: PathBuf,
+    /// JSON file of gift distributions handed out by code or at login, or None to not hand any out. See GiftDistributor
+    pub gift_distributions: Option<PathBuf>
+}
+
+impl ServerConfig {
+    pub fn default() -> ServerConfig {
+        return ServerConfig {
+            bind_address: "127.0.0.1:7878".to_string(),
+            storage: StorageBackend::File { directory: PathBuf::from("server_data") },
+            protocol_trace: None,
+            pack_directory: PathBuf::from("data_packs"),
+            data_packs: Vec::new(),
+            transfer_address: "127.0.0.1:7879".to_string(),
+            replay_directory: PathBuf::from("replays"),
+            map_directory: PathBuf::from("maps"),
+            game_data_directory: PathBuf::from("game_data"),
+            load_threads: 0,
+            interned_string_warnings: vec![100_000, 1_000_000, 10_000_000],
+            backup: BackupConfig::default(),
+            two_factor: TwoFactorPolicy::Optional,
+            region_capacity: DEFAULT_REGION_CAPACITY,
+            start_position: WorldPosition::new(GlobalString::new(&"start".to_string()), TilePosition::new(0, 0)),
+            http_api: None,
+            crash_report_directory: None,
+            debug_address: None,
+            stat_audit_key: PathBuf::from("stat_audit.key"),
+            gift_distributions: None
+        };
+    }
+
+    /// Parse a config. Settings that are left out keep their default.
+    /// ```
+    /// use immie2d_shared::world::tile_position::TilePosition;
+    /// use immie2d_server::auth::two_factor::TwoFactorPolicy;
+    /// use immie2d_server::config::server_config::{ServerConfig, StorageBackend};
+    ///
+    /// let config = ServerConfig::from_config_string("bind_address=0.0.0.0:7878\nstorage=postgres\npostgres_url=postgres://localhost/immie2d\npostgres_pool_size=8\n").unwrap();
+    /// assert_eq!(config.storage, StorageBackend::Postgres { url: "postgres://localhost/immie2d".to_string(), pool_size: 8 });
+    /// assert_eq!(ServerConfig::from_config_string(&config.to_config_string()), Ok(config));
+    ///
+    /// let traced = ServerConfig::from_config_string("protocol_trace=trace.jsonl").unwrap();
+    /// assert_eq!(ServerConfig::from_config_string(&traced.to_config_string()), Ok(traced));
+    ///
+    /// let modded = ServerConfig::from_config_string("data_packs=mymod, othermod").unwrap();
+    /// assert_eq!(modded.data_packs, vec!["mymod".to_string(), "othermod".to_string()]);
+    /// assert_eq!(ServerConfig::from_config_string(&modded.to_config_string()), Ok(modded));
+    ///
+    /// let warned = ServerConfig::from_config_string("interned_string_warnings=5000, 20000").unwrap();
+    /// assert_eq!(warned.interned_string_warnings, vec![5000, 20000]);
+    /// assert_eq!(ServerConfig::from_config_string(&warned.to_config_string()), Ok(warned));
+    /// assert!(ServerConfig::from_config_string("interned_string_warnings=lots").is_err());
+    ///
+    /// let loading = ServerConfig::from_config_string("game_data_directory=content\nload_threads=2").unwrap();
+    /// assert_eq!(loading.load_threads, 2);
+    /// assert_eq!(ServerConfig::from_config_string(&loading.to_config_string()), Ok(loading));
+    /// assert!(ServerConfig::from_config_string("load_threads=all").is_err());
+    ///
+    /// let backed_up = ServerConfig::from_config_string("backup_directory=/var/backups/immie2d\nbackup_interval_seconds=86400\nbackup_keep_latest=14\nbackup_max_age_seconds=2592000").unwrap();
+    /// assert_eq!(backed_up.backup.retention.max_age_seconds, Some(2592000));
+    /// assert_eq!(ServerConfig::from_config_string(&backed_up.to_config_string()), Ok(backed_up));
+    /// assert!(ServerConfig::from_config_string("backup_keep_latest=0").is_err());
+    ///
+    /// let secured = ServerConfig::from_config_string("two_factor=required").unwrap();
+    /// assert_eq!(secured.two_factor, TwoFactorPolicy::Required);
+    /// assert_eq!(ServerConfig::from_config_string(&secured.to_config_string()), Ok(secured));
+    /// assert!(ServerConfig::from_config_string("two_factor=sometimes").is_err());
+    ///
+    /// let crowded = ServerConfig::from_config_string("region_capacity=16").unwrap();
+    /// assert_eq!(crowded.region_capacity, 16);
+    /// assert_eq!(ServerConfig::from_config_string(&crowded.to_config_string()), Ok(crowded));
+    /// assert!(ServerConfig::from_config_string("region_capacity=0").is_err());
+    ///
+    /// let harbor = ServerConfig::from_config_string("start_position=ember harbor,4,-2").unwrap();
+    /// assert_eq!((harbor.start_position.map.to_string().as_str(), harbor.start_position.tile), ("ember harbor", TilePosition::new(4, -2)));
+    /// assert_eq!(ServerConfig::from_config_string(&harbor.to_config_string()), Ok(harbor));
+    /// assert!(ServerConfig::from_config_string("start_position=ember harbor,4").is_err());
+    ///
+    /// let public = ServerConfig::from_config_string("http_api=0.0.0.0:8080").unwrap();
+    /// assert_eq!(public.http_api, Some("0.0.0.0:8080".to_string()));
+    /// assert_eq!(ServerConfig::from_config_string(&public.to_config_string()), Ok(public));
+    ///
+    /// let reported = ServerConfig::from_config_string("http_api=0.0.0.0:8080\ncrash_report_directory=crash_reports").unwrap();
+    /// assert_eq!(ServerConfig::from_config_string(&reported.to_config_string()), Ok(reported));
+    ///
+    /// let debugged = ServerConfig::from_config_string("debug_address=127.0.0.1:7880").unwrap();
+    /// assert_eq!(debugged.debug_address, Some("127.0.0.1:7880".to_string()));
+    /// assert_eq!(ServerConfig::from_config_string(&debugged.to_config_string()), Ok(debugged));
+    ///
+    /// let audited = ServerConfig::from_config_string("stat_audit_key=/etc/immie2d/audit.key").unwrap();
+    /// assert_eq!(ServerConfig::from_config_string(&audited.to_config_string()), Ok(audited));
+    ///
+    /// let festive = ServerConfig::from_config_string("gift_distributions=events/gifts.json").unwrap();
+    /// assert_eq!(festive.gift_distributions, Some("events/gifts.json".into()));
+    /// assert_eq!(ServerConfig::from_config_string(&festive.to_config_string()), Ok(festive));
+    ///
+    /// assert!(ServerConfig::from_config_string("storage=postgres").is_err());
+    /// assert!(ServerConfig::from_config_string("storage=mongo").is_err());
+    /// assert!(ServerConfig::from_config_string("bind_adress=0.0.0.0:7878").is_err());
+    /// ```
+    pub fn from_config_string(text: &str) -> Result<ServerConfig, String> {
+        let mut config = ServerConfig::default();
+        let mut storage_kind = "file".to_string();
+        let mut data_directory = PathBuf::from("server_data");
+        let mut postgres_url: Option<String> = None;
+        let mut pool_size = 4;
+        for (line_index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, value) = line.split_once('=').ok_or(format!("Line {} is not a name=value setting", line_index + 1))?;
+            let (name, value) = (name.trim(), value.trim());
+            match name {
+                "bind_address" => config.bind_address = value.to_string(),
+                "storage" => storage_kind = value.to_string(),
+                "data_directory" => data_directory = PathBuf::from(value),
+                "postgres_url" => postgres_url = Some(value.to_string()),
+                "protocol_trace" => config.protocol_trace = Some(PathBuf::from(value)),
+                "pack_directory" => config.pack_directory = PathBuf::from(value),
+                "data_packs" => config.data_packs = value.split(',').map(|pack| pack.trim().to_string()).filter(|pack| !pack.is_empty()).collect(),
+                "transfer_address" => config.transfer_address = value.to_string(),
+                "replay_directory" => config.replay_directory = PathBuf::from(value),
+                "map_directory" => config.map_directory = PathBuf::from(value),
+                "game_data_directory" => config.game_data_directory = PathBuf::from(value),
+                "load_threads" => config.load_threads = value.parse::<usize>().map_err(|_| format!("Invalid load_threads [{}]", value))?,
+                "interned_string_warnings" => {
+                    let counts = value.split(',').map(|count| count.trim()).filter(|count| !count.is_empty()).map(|count| count.parse::<usize>());
+                    config.interned_string_warnings = counts.collect::<Result<Vec<usize>, _>>().map_err(|_| format!("Invalid interned_string_warnings [{}]", value))?;
+                },
+                "backup_directory" => config.backup.directory = PathBuf::from(value),
+                "backup_interval_seconds" => {
+                    config.backup.interval_seconds = value.parse::<u64>().map_err(|_| format!("Invalid backup_interval_seconds [{}]", value))?;
+                },
+                "backup_keep_latest" => {
+                    config.backup.retention.keep_latest = value.parse::<usize>().ok().filter(|count| *count > 0).ok_or(format!("Invalid backup_keep_latest [{}]", value))?;
+                },
+                "backup_max_age_seconds" => {
+                    config.backup.retention.max_age_seconds = Some(value.parse::<u64>().map_err(|_| format!("Invalid backup_max_age_seconds [{}]", value))?);
+                },
+                "two_factor" => config.two_factor = TwoFactorPolicy::from_name(value).ok_or(format!("Invalid two_factor [{}]", value))?,
+                "region_capacity" => {
+                    config.region_capacity = value.parse::<usize>().ok().filter(|capacity| *capacity > 0).ok_or(format!("Invalid region_capacity [{}]", value))?;
+                },
+                "start_position" => config.start_position = parse_world_position(value).ok_or(format!("Invalid start_position [{}]", value))?,
+                "http_api" => config.http_api = Some(value.to_string()),
+                "crash_report_directory" => config.crash_report_directory = Some(PathBuf::from(value)),
+                "debug_address" => config.debug_address = Some(value.to_string()),
+                "stat_audit_key" => config.stat_audit_key = PathBuf::from(value),
+                "gift_distributions" => config.gift_distributions = Some(PathBuf::from(value)),
+                "postgres_pool_size" => {
+                    pool_size = value.parse::<u32>().ok().filter(|size| *size > 0).ok_or(format!("Invalid postgres_pool_size [{}]", value))?;
+                },
+                _ => return Err(format!("Unknown setting [{}] on line {}", name, line_index + 1))
+            }
+        }
+        config.storage = match storage_kind.as_str() {
+            "memory" => StorageBackend::Memory,
+            "file" => StorageBackend::File { directory: data_directory },
+            "postgres" => StorageBackend::Postgres { url: postgres_url.ok_or("storage=postgres requires postgres_url")?, pool_size },
+            _ => return Err(format!("Unknown storage backend [{}]", storage_kind))
+        };
+        return Ok(config);
+    }
+
+    pub fn to_config_string(&self) -> String {
+        let mut out = format!("bind_address={}\n", self.bind_address);
+        match &self.storage {
+            StorageBackend::Memory => out.push_str("storage=memory\n"),
+            StorageBackend::File { directory } => out.push_str(&format!("storage=file\ndata_directory={}\n", directory.display())),
+            StorageBackend::Postgres { url, pool_size } => out.push_str(&format!("storage=postgres\npostgres_url={}\npostgres_pool_size={}\n", url, pool_size))
+        }
+        if let Some(path) = &self.protocol_trace {
+            out.push_str(&format!("protocol_trace={}\n", path.display()));
+        }
+        out.push_str(&format!("pack_directory={}\n", self.pack_directory.display()));
+        if !self.data_packs.is_empty() {
+            out.push_str(&format!("data_packs={}\n", self.data_packs.join(",")));
+        }
+        out.push_str(&format!("transfer_address={}\n", self.transfer_address));
+        out.push_str(&format!("replay_directory={}\n", self.replay_directory.display()));
+        out.push_str(&format!("map_directory={}\n", self.map_directory.display()));
+        out.push_str(&format!("game_data_directory={}\n", self.game_data_directory.display()));
+        out.push_str(&format!("load_threads={}\n", self.load_threads));
+        out.push_str(&format!("interned_string_warnings={}\n", self.interned_string_warnings.iter().map(|count| count.to_string()).collect::<Vec<String>>().join(",")));
+        out.push_str(&format!("backup_directory={}\n", self.backup.directory.display()));
+        out.push_str(&format!("backup_interval_seconds={}\n", self.backup.interval_seconds));
+        out.push_str(&format!("backup_keep_latest={}\n", self.backup.retention.keep_latest));
+        if let Some(max_age) = self.backup.retention.max_age_seconds {
+            out.push_str(&format!("backup_max_age_seconds={}\n", max_age));
+        }
+        out.push_str(&format!("two_factor={}\n", self.two_factor.get_name()));
+        out.push_str(&format!("region_capacity={}\n", self.region_capacity));
+        out.push_str(&format!("start_position={},{},{}\n", self.start_position.map.to_string(), self.start_position.tile.x, self.start_position.tile.y));
+        if let Some(address) = &self.http_api {
+            out.push_str(&format!("http_api={}\n", address));
+        }
+        if let Some(directory) = &self.crash_report_directory {
+            out.push_str(&format!("crash_report_directory={}\n", directory.display()));
+        }
+        if let Some(address) = &self.debug_address {
+            out.push_str(&format!("debug_address={}\n", address));
+        }
+        out.push_str(&format!("stat_audit_key={}\n", self.stat_audit_key.display()));
+        if let Some(path) = &self.gift_distributions {
+            out.push_str(&format!("gift_distributions={}\n", path.display()));
+        }
+        return out;
+    }
+
+    /// Load core data in parallel, starting from the abilities implemented in code. Data packs are installed on top
+    /// of it. See CoreDataLoader::load()
+    pub fn load_game_data(&self, progress: impl FnMut(LoadProgress)) -> Result<CoreData, Vec<LoadError>> {
+        let mut ability_map = AbilityMap::new();
+        ability_map.add_ability::<Fireball>();
+        ability_map.add_ability::<Pursuit>();
+        ability_map.add_ability::<HiddenPower>();
+        let mut loader = CoreDataLoader::new(&self.game_data_directory).with_ability_map(ability_map);
+        if self.load_threads > 0 {
+            loader = loader.with_max_threads(self.load_threads);
+        }
+        return loader.load(progress);
+    }
+
+    /// Load every enabled data pack, in install order. They should be installed after core data, and their manifests
+    /// advertised to clients during the handshake. See install_packs() and PackAdvertisement
+    pub fn load_data_packs(&self) -> Result<Vec<DataPack>, DataPackError> {
+        return self.data_packs.iter().map(|pack| DataPack::load(&self.pack_directory.join(pack))).collect();
+    }
+
+    /// Load the gift distributions, or none if the config doesn't name a file. They should be validated against the
+    /// game data before any are handed out. See GiftDistributor::validate()
+    pub fn load_gift_distributions(&self) -> Result<GiftDistributor, GiftLoadError> {
+        return match &self.gift_distributions {
+            Some(path) => GiftDistributor::load(path),
+            None => Ok(GiftDistributor::new())
+        };
+    }
+
+    /// The directories the file transfer channel serves each kind of file from.
+    pub fn get_transfer_directories(&self) -> TransferDirectories {
+        return TransferDirectories::new()
+            .with_directory(TransferKind::DataPack, self.pack_directory.clone())
+            .with_directory(TransferKind::Replay, self.replay_directory.clone())
+            .with_directory(TransferKind::Map, self.map_directory.clone());
+    }
+
+    /// Read the stat audit key, generating a random one the first time. Only one server generates it if several start
+    /// at once, and the rest read what it wrote.
+    /// ```
+    /// use immie2d_server::config::server_config::ServerConfig;
+    ///
+    /// let path = std::env::temp_dir().join(format!("immie2d_audit_key_doctest_{}.key", std::process::id()));
+    /// # let _ = std::fs::remove_file(&path);
+    /// let config = ServerConfig { stat_audit_key: path.clone(), ..ServerConfig::default() };
+    /// let key = config.load_stat_audit_key().unwrap();
+    /// assert_eq!(key.len(), 32);
+    /// assert_eq!(config.load_stat_audit_key().unwrap(), key);
+    /// std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn load_stat_audit_key(&self) -> io::Result<Vec<u8>> {
+        if !self.stat_audit_key.exists() {
+            let mut key = vec![0u8; 32];
+            OsRng.fill_bytes(&mut key);
+            // Written in full under a temporary name and then linked into place, so no server can read half a key
+            let mut temporary_name = self.stat_audit_key.clone().into_os_string();
+            temporary_name.push(format!(".{}.tmp", std::process::id()));
+            let temporary = PathBuf::from(temporary_name);
+            let mut file = OpenOptions::new().write(true).create(true).truncate(true).open(&temporary)?;
+            file.write_all(&key)?;
+            file.sync_all()?;
+            let linked = fs::hard_link(&temporary, &self.stat_audit_key);
+            fs::remove_file(&temporary)?;
+            match linked {
+                Ok(()) => return Ok(key),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {},
+                Err(err) => return Err(err)
+            }
+        }
+        let key = fs::read(&self.stat_audit_key)?;
+        if key.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Stat audit key {} is empty", self.stat_audit_key.display())));
+        }
+        return Ok(key);
+    }
+
+    /// Load the config, using the defaults if the file doesn't exist.
+    pub fn load(path: &Path) -> io::Result<ServerConfig> {
+        return match fs::read_to_string(path) {
+            Ok(text) => ServerConfig::from_config_string(&text).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(ServerConfig::default()),
+            Err(err) => Err(err)
+        };
+    }
+}
@@ -0,0 +1,379 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde_json::Value;
+
+use immie2d_shared::engine_types::game_rng::GameRng;
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::ability::ability_names::{AbilityNames, MAX_ABILITIES_COUNT};
+use immie2d_shared::gameplay::game_data::GameData;
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::immie::individual_values::IndividualValues;
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::storage::player_profile::PlayerProfile;
+use crate::storage::storage::Storage;
+
+/* Why the gift distributions data file could not be loaded, or doesn't match the game data. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GiftLoadError {
+    Io(String),
+    Parse(String),
+    /// The file is well formed JSON but a distribution is not valid. Includes the reason.
+    Invalid(String)
+}
+
+impl fmt::Display for GiftLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            GiftLoadError::Io(message) => write!(f, "Failed to read gift distributions: {}", message),
+            GiftLoadError::Parse(message) => write!(f, "Failed to parse gift distributions: {}", message),
+            GiftLoadError::Invalid(message) => write!(f, "Invalid gift distributions: {}", message)
+        };
+    }
+}
+
+/* Why a player couldn't redeem a code. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GiftClaimError {
+    UnknownCode,
+    NotStarted,
+    Ended,
+    /// Each account can claim a distribution once.
+    AlreadyClaimed,
+    /// The claim couldn't be recorded, so the gift wasn't given. Includes the reason.
+    Storage(String)
+}
+
+impl fmt::Display for GiftClaimError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            GiftClaimError::UnknownCode => write!(f, "That code doesn't exist"),
+            GiftClaimError::NotStarted => write!(f, "That code can't be redeemed yet"),
+            GiftClaimError::Ended => write!(f, "That code has expired"),
+            GiftClaimError::AlreadyClaimed => write!(f, "That gift has already been claimed on this account"),
+            GiftClaimError::Storage(message) => write!(f, "Failed to claim the gift: {}", message)
+        };
+    }
+}
+
+impl From<io::Error> for GiftClaimError {
+    fn from(err: io::Error) -> GiftClaimError {
+        return GiftClaimError::Storage(err.to_string());
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Gift {
+    /// Sent to the player's box, marked with the distribution it came from.
+    Immie(Immie),
+    Item { item: GlobalString, count: u32 }
+}
+
+/* How players receive a distribution. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum GiftTrigger {
+    /// Redeemed by entering a code, which is matched ignoring case and surrounding whitespace.
+    Code(String),
+    /// Given to every player that logs in during the window.
+    Login
+}
+
+/* A gift handed out to players during a window, such as a special Immie for a festival. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct GiftDistribution {
+    pub name: GlobalString,
+    pub trigger: GiftTrigger,
+    /// Unix seconds the distribution opens at, inclusive.
+    pub start_unix_seconds: u64,
+    /// Unix seconds the distribution closes at, exclusive.
+    pub end_unix_seconds: u64,
+    pub gift: Gift
+}
+
+impl GiftDistribution {
+    pub fn is_active(&self, unix_seconds: u64) -> bool {
+        return unix_seconds >= self.start_unix_seconds && unix_seconds < self.end_unix_seconds;
+    }
+
+    /// Record a player's claim in storage. Returns false if it was already recorded, such as by another server, in
+    /// which case the gift must not be given. Split from give() so the claim can be recorded without holding the lock
+    /// on the player's profile.
+    pub fn record_claim<S: Storage + ?Sized>(&self, storage: &mut S, player: PlayerId) -> io::Result<bool> {
+        return storage.record_gift_claim(player, self.name);
+    }
+
+    /// Give the gift to a player whose claim was recorded, marking it claimed on their profile. Each gifted Immie
+    /// rolls its own individual values.
+    pub fn give(&self, profile: &mut PlayerProfile, rng: &mut GameRng) {
+        match &self.gift {
+            Gift::Immie(immie) => profile.boxed.push(Immie { origin: Some(self.name), individual_values: IndividualValues::roll(rng), ..*immie }),
+            Gift::Item { item, count } => profile.inventory.add_item(*item, *count)
+        }
+        self.mark_claimed(profile);
+    }
+
+    /// Mark the distribution claimed on a profile without giving anything, such as when the claim was recorded by
+    /// another server whose save of the profile hasn't reached this one yet.
+    pub fn mark_claimed(&self, profile: &mut PlayerProfile) {
+        if !profile.claimed_gifts.contains(&self.name) {
+            profile.claimed_gifts.push(self.name);
+        }
+    }
+
+    fn grant<S: Storage + ?Sized>(&self, storage: &mut S, profile: &mut PlayerProfile, rng: &mut GameRng) -> io::Result<bool> {
+        if !self.record_claim(storage, profile.player)? {
+            self.mark_claimed(profile);
+            return Ok(false);
+        }
+        self.give(profile, rng);
+        return Ok(true);
+    }
+}
+
+/* Every gift distribution, loaded from a data file so events can be scheduled without a server update. Claims are
+recorded in storage before the gift is given, and on the player's profile, so each account gets a distribution once
+however many times or on however many servers it's triggered. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct GiftDistributor {
+    distributions: Vec<GiftDistribution>
+}
+
+impl GiftDistributor {
+    pub fn new() -> GiftDistributor {
+        return GiftDistributor { distributions: Vec::new() };
+    }
+
+    /// Load distributions from a JSON array. Names and codes must be unique, and every distribution needs a window
+    /// and exactly one of an Immie or an item to give.
+    /// ```
+    /// use immie2d_server::distribution::gift_distribution::{GiftDistributor, GiftTrigger};
+    ///
+    /// let distributor = GiftDistributor::from_json(r#"[
+    ///     { "name": "festival_lavapup", "code": "SUMMER-FEST", "start": 1000, "end": 2000,
+    ///       "immie": { "species": "lavapup", "level": 10, "abilities": ["fireball"], "held_item": "charcoal" } },
+    ///     { "name": "launch_potions", "start": 0, "end": 5000, "item": "potion", "count": 5 }
+    /// ]"#).unwrap();
+    /// assert_eq!(distributor.get_distributions()[0].trigger, GiftTrigger::Code("SUMMER-FEST".to_string()));
+    /// assert_eq!(distributor.get_distributions()[1].trigger, GiftTrigger::Login);
+    ///
+    /// assert!(GiftDistributor::from_json(r#"[{ "name": "backwards", "start": 10, "end": 5, "item": "potion" }]"#).is_err());
+    /// assert!(GiftDistributor::from_json(r#"[{ "name": "empty", "start": 0, "end": 5 }]"#).is_err());
+    /// ```
+    pub fn from_json(json: &str) -> Result<GiftDistributor, GiftLoadError> {
+        let root: Value = serde_json::from_str(json).map_err(|err| GiftLoadError::Parse(err.to_string()))?;
+        let array = root.as_array().ok_or(GiftLoadError::Invalid("Expected an array of distributions".to_string()))?;
+        let mut distributor = GiftDistributor::new();
+        for entry in array {
+            let distribution = parse_distribution(entry)?;
+            if distributor.get_distribution(distribution.name).is_some() {
+                return Err(GiftLoadError::Invalid(format!("Distribution {} is defined more than once", distribution.name)));
+            }
+            if let GiftTrigger::Code(code) = &distribution.trigger {
+                if distributor.find_code(code).is_some() {
+                    return Err(GiftLoadError::Invalid(format!("Distribution {} reuses the code [{}]", distribution.name, code)));
+                }
+            }
+            distributor.distributions.push(distribution);
+        }
+        return Ok(distributor);
+    }
+
+    pub fn load(path: &Path) -> Result<GiftDistributor, GiftLoadError> {
+        let text = fs::read_to_string(path).map_err(|err| GiftLoadError::Io(err.to_string()))?;
+        return GiftDistributor::from_json(&text);
+    }
+
+    /// Check every gifted species, form, ability and item is in the game data, such as after a data reload.
+    pub fn validate(&self, data: &GameData) -> Result<(), GiftLoadError> {
+        for distribution in self.distributions.iter() {
+            let invalid = |reason: String| GiftLoadError::Invalid(format!("Distribution {} {}", distribution.name, reason));
+            match &distribution.gift {
+                Gift::Immie(immie) => {
+                    if !data.get_species_map().is_species_name(immie.species) {
+                        return Err(invalid(format!("gifts unknown species {}", immie.species)));
+                    }
+                    if let Some(ability) = immie.abilities.iter().find(|ability| !data.get_ability_map().is_ability_name(&ability.to_string())) {
+                        return Err(invalid(format!("gifts unknown ability {}", ability)));
+                    }
+                    if let Some(item) = immie.held_item.filter(|item| data.get_item_map().get_item(*item).is_none()) {
+                        return Err(invalid(format!("gifts unknown item {}", item)));
+                    }
+                    data.get_species_map().validate_immie(immie).map_err(|error| invalid(error.to_string()))?;
+                },
+                Gift::Item { item, .. } => {
+                    if data.get_item_map().get_item(*item).is_none() {
+                        return Err(invalid(format!("gifts unknown item {}", item)));
+                    }
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    pub fn get_distributions(&self) -> &[GiftDistribution] {
+        return &self.distributions;
+    }
+
+    pub fn get_distribution(&self, name: GlobalString) -> Option<&GiftDistribution> {
+        return self.distributions.iter().find(|distribution| distribution.name == name);
+    }
+
+    /// Redeem a code for a player, recording the claim in storage and giving them its gift. Returns the name of the
+    /// distribution it redeemed.
+    /// ```
+    /// use immie2d_shared::engine_types::{game_rng::GameRng, global_string::GlobalString};
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::distribution::gift_distribution::{GiftDistributor, GiftClaimError};
+    /// use immie2d_server::storage::{memory_storage::MemoryStorage, player_profile::PlayerProfile};
+    ///
+    /// let distributor = GiftDistributor::from_json(r#"[
+    ///     { "name": "festival_lavapup", "code": "SUMMER-FEST", "start": 1000, "end": 2000,
+    ///       "immie": { "species": "lavapup", "level": 10, "abilities": ["fireball"] } }
+    /// ]"#).unwrap();
+    /// let mut rng = GameRng::new(7);
+    /// let mut storage = MemoryStorage::new();
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// assert_eq!(distributor.redeem_code(&mut storage, &mut profile, "SUMMER-FEST", 999, &mut rng), Err(GiftClaimError::NotStarted));
+    /// assert_eq!(distributor.redeem_code(&mut storage, &mut profile, "WINTER-FEST", 1500, &mut rng), Err(GiftClaimError::UnknownCode));
+    ///
+    /// // The gifted Immie is marked with where it came from
+    /// let festival = GlobalString::new(&"festival_lavapup".to_string());
+    /// assert_eq!(distributor.redeem_code(&mut storage, &mut profile, " summer-fest ", 1500, &mut rng), Ok(festival));
+    /// assert_eq!(profile.boxed[0].origin, Some(festival));
+    /// assert_eq!(profile.boxed[0].level, 10);
+    ///
+    /// // Once per account
+    /// assert_eq!(distributor.redeem_code(&mut storage, &mut profile, "SUMMER-FEST", 1600, &mut rng), Err(GiftClaimError::AlreadyClaimed));
+    /// assert_eq!(profile.boxed.len(), 1);
+    /// let mut other = PlayerProfile::new(PlayerId(2), "misty".to_string());
+    /// assert_eq!(distributor.redeem_code(&mut storage, &mut other, "SUMMER-FEST", 2000, &mut rng), Err(GiftClaimError::Ended));
+    ///
+    /// // Another server redeeming for the same account with a copy of the profile from before the claim
+    /// let mut stale = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// assert_eq!(distributor.redeem_code(&mut storage, &mut stale, "SUMMER-FEST", 1700, &mut rng), Err(GiftClaimError::AlreadyClaimed));
+    /// assert!(stale.boxed.is_empty());
+    /// assert_eq!(stale.claimed_gifts, vec![festival]);
+    /// ```
+    pub fn redeem_code<S: Storage + ?Sized>(&self, storage: &mut S, profile: &mut PlayerProfile, code: &str, unix_seconds: u64, rng: &mut GameRng) -> Result<GlobalString, GiftClaimError> {
+        let distribution = self.find_redeemable(code, &profile.claimed_gifts, unix_seconds)?;
+        if !distribution.grant(storage, profile, rng)? {
+            return Err(GiftClaimError::AlreadyClaimed);
+        }
+        return Ok(distribution.name);
+    }
+
+    /// Find the distribution a code redeems, if a player who claimed the given distributions can redeem it now. The
+    /// claim still has to be recorded before the gift is given. See GiftDistribution::record_claim()
+    pub fn find_redeemable(&self, code: &str, claimed_gifts: &[GlobalString], unix_seconds: u64) -> Result<&GiftDistribution, GiftClaimError> {
+        let distribution = self.find_code(code).ok_or(GiftClaimError::UnknownCode)?;
+        if unix_seconds < distribution.start_unix_seconds {
+            return Err(GiftClaimError::NotStarted);
+        }
+        if unix_seconds >= distribution.end_unix_seconds {
+            return Err(GiftClaimError::Ended);
+        }
+        if claimed_gifts.contains(&distribution.name) {
+            return Err(GiftClaimError::AlreadyClaimed);
+        }
+        return Ok(distribution);
+    }
+
+    /// Give a player logging in every open login distribution they haven't claimed. Returns the names of the
+    /// distributions given, to tell the player about. If storage fails, gifts given before the failure are kept.
+    /// ```
+    /// use immie2d_shared::engine_types::{game_rng::GameRng, global_string::GlobalString};
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::distribution::gift_distribution::GiftDistributor;
+    /// use immie2d_server::storage::{memory_storage::MemoryStorage, player_profile::PlayerProfile};
+    ///
+    /// let distributor = GiftDistributor::from_json(r#"[
+    ///     { "name": "launch_potions", "start": 0, "end": 5000, "item": "potion", "count": 5 },
+    ///     { "name": "anniversary", "start": 9000, "end": 9500, "item": "rare_candy" }
+    /// ]"#).unwrap();
+    /// let mut rng = GameRng::new(7);
+    /// let mut storage = MemoryStorage::new();
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// assert_eq!(distributor.claim_login_gifts(&mut storage, &mut profile, 100, &mut rng).unwrap(), vec![GlobalString::new(&"launch_potions".to_string())]);
+    /// assert!(distributor.claim_login_gifts(&mut storage, &mut profile, 200, &mut rng).unwrap().is_empty());
+    /// assert_eq!(profile.inventory.get_count(GlobalString::new(&"potion".to_string())), 5);
+    /// ```
+    pub fn claim_login_gifts<S: Storage + ?Sized>(&self, storage: &mut S, profile: &mut PlayerProfile, unix_seconds: u64, rng: &mut GameRng) -> io::Result<Vec<GlobalString>> {
+        let mut claimed = Vec::new();
+        for distribution in self.distributions.iter() {
+            if distribution.trigger != GiftTrigger::Login || !distribution.is_active(unix_seconds) || profile.claimed_gifts.contains(&distribution.name) {
+                continue;
+            }
+            if distribution.grant(storage, profile, rng)? {
+                claimed.push(distribution.name);
+            }
+        }
+        return Ok(claimed);
+    }
+
+    fn find_code(&self, code: &str) -> Option<&GiftDistribution> {
+        let code = code.trim();
+        return self.distributions.iter().find(|distribution| match &distribution.trigger {
+            GiftTrigger::Code(other) => other.eq_ignore_ascii_case(code),
+            GiftTrigger::Login => false
+        });
+    }
+}
+
+fn parse_distribution(json: &Value) -> Result<GiftDistribution, GiftLoadError> {
+    let name = json["name"].as_str().ok_or(GiftLoadError::Invalid("Distribution is missing a name".to_string()))?;
+    let invalid = |reason: &str| GiftLoadError::Invalid(format!("Distribution [{}] {}", name, reason));
+    let start_unix_seconds = json["start"].as_u64().ok_or(invalid("needs a start in unix seconds"))?;
+    let end_unix_seconds = json["end"].as_u64().ok_or(invalid("needs an end in unix seconds"))?;
+    if end_unix_seconds <= start_unix_seconds {
+        return Err(invalid("ends before it starts"));
+    }
+    let trigger = match json.get("code") {
+        None => GiftTrigger::Login,
+        Some(code) => {
+            let code = code.as_str().map(str::trim).filter(|code| !code.is_empty()).ok_or(invalid("has a code that isn't a non empty string"))?;
+            GiftTrigger::Code(code.to_string())
+        }
+    };
+    let gift = match (json.get("immie"), json.get("item")) {
+        (Some(immie), None) => Gift::Immie(parse_immie(immie).map_err(|reason| invalid(&reason))?),
+        (None, Some(item)) => {
+            let item = item.as_str().ok_or(invalid("has an item that is not a string"))?;
+            let count = match json.get("count") {
+                None => 1,
+                Some(count) => count.as_u64().filter(|count| *count > 0 && *count <= u32::MAX as u64).ok_or(invalid("has an invalid item count"))? as u32
+            };
+            Gift::Item { item: GlobalString::new(&item.to_string()), count }
+        },
+        _ => return Err(invalid("needs exactly one of an immie or an item"))
+    };
+    return Ok(GiftDistribution { name: GlobalString::new(&name.to_string()), trigger, start_unix_seconds, end_unix_seconds, gift });
+}
+
+fn parse_immie(json: &Value) -> Result<Immie, String> {
+    let species = json["species"].as_str().ok_or("has an Immie without a species")?;
+    let level = json["level"].as_u64().filter(|level| *level > 0 && *level <= u32::MAX as u64).ok_or("has an Immie without a valid level")?;
+    let abilities = json["abilities"].as_array().filter(|abilities| !abilities.is_empty() && abilities.len() <= MAX_ABILITIES_COUNT as usize)
+        .ok_or(format!("has an Immie without 1 to {} abilities", MAX_ABILITIES_COUNT))?;
+    let mut ability_names = Vec::new();
+    for ability in abilities {
+        let ability = GlobalString::new(&ability.as_str().ok_or("has an ability that is not a string")?.to_string());
+        if ability_names.contains(&ability) {
+            return Err(format!("has an Immie with duplicate ability {}", ability));
+        }
+        ability_names.push(ability);
+    }
+    let mut immie = Immie::new(GlobalString::new(&species.to_string()), level as u32, AbilityNames::new(ability_names));
+    let optional_string = |key: &str| -> Result<Option<GlobalString>, String> {
+        return match json.get(key) {
+            None => Ok(None),
+            Some(value) => Ok(Some(GlobalString::new(&value.as_str().ok_or(format!("has a {} that is not a string", key))?.to_string())))
+        };
+    };
+    immie.held_item = optional_string("held_item")?;
+    immie.form = optional_string("form")?;
+    immie.passive = optional_string("passive")?;
+    return Ok(immie);
+}
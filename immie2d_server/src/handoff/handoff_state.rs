@@ -0,0 +1,79 @@
+use std::io::{self, ErrorKind};
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::session::session_snapshot::{read_snapshot, SessionSnapshot};
+use crate::storage::player_profile::ByteReader;
+
+/// Changed whenever the handoff format changes, so mismatched server versions refuse to hand off rather than
+/// misreading each other.
+pub const HANDOFF_VERSION: u32 = 3;
+
+/* A client connection inherited by the new process, with the player it belongs to. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct HandedOffConnection {
+    pub player: PlayerId,
+    pub fd: i32
+}
+
+/* Everything the old server process passes to the new one in a rolling restart. Sockets are passed as file
+descriptors that the new process inherits, so connections stay open and clients only see a pause. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct HandoffState {
+    pub listener_fd: i32,
+    pub connections: Vec<HandedOffConnection>,
+    pub sessions: Vec<SessionSnapshot>
+}
+
+impl HandoffState {
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::handoff::handoff_state::{HandoffState, HandedOffConnection};
+    ///
+    /// let state = HandoffState { listener_fd: 3, connections: vec![HandedOffConnection { player: PlayerId(9), fd: 5 }], sessions: Vec::new() };
+    /// assert_eq!(HandoffState::from_bytes(&state.to_bytes()).unwrap(), state);
+    /// let mut other_version = state.to_bytes();
+    /// other_version[0] += 1;
+    /// assert!(HandoffState::from_bytes(&other_version).is_err());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&HANDOFF_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&self.listener_fd.to_le_bytes());
+        bytes.extend_from_slice(&(self.connections.len() as u32).to_le_bytes());
+        for connection in self.connections.iter() {
+            bytes.extend_from_slice(&connection.player.0.to_le_bytes());
+            bytes.extend_from_slice(&connection.fd.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.sessions.len() as u32).to_le_bytes());
+        for session in self.sessions.iter() {
+            bytes.extend_from_slice(&session.to_bytes());
+        }
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<HandoffState> {
+        let mut reader = ByteReader::new(bytes);
+        let version = u32::from_le_bytes(reader.take_array()?);
+        if version != HANDOFF_VERSION {
+            return Err(io::Error::new(ErrorKind::InvalidData, format!("Handoff version {} does not match this server's version {}", version, HANDOFF_VERSION)));
+        }
+        let listener_fd = i32::from_le_bytes(reader.take_array()?);
+        let connection_count = u32::from_le_bytes(reader.take_array()?);
+        let mut connections = Vec::new();
+        for _ in 0..connection_count {
+            let player = PlayerId(u64::from_le_bytes(reader.take_array()?));
+            let fd = i32::from_le_bytes(reader.take_array()?);
+            connections.push(HandedOffConnection { player, fd });
+        }
+        let session_count = u32::from_le_bytes(reader.take_array()?);
+        let mut sessions = Vec::new();
+        for _ in 0..session_count {
+            sessions.push(read_snapshot(&mut reader)?);
+        }
+        if reader.get_remaining() != 0 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Trailing bytes after handoff state"));
+        }
+        return Ok(HandoffState { listener_fd, connections, sessions });
+    }
+}
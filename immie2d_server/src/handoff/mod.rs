@@ -0,0 +1,3 @@
+pub mod handoff_state;
+#[cfg(unix)]
+pub mod warm_handoff;
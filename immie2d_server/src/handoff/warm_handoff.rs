@@ -0,0 +1,229 @@
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::admin::ban_list::{BanList, ConnectionDenial};
+use crate::session::session_manager::SessionManager;
+use crate::session::session_snapshot::{SessionRestoreError, SessionSnapshot};
+
+use super::handoff_state::{HandedOffConnection, HandoffState};
+
+/// Environment variable telling a newly started server the unix socket to take over from.
+pub const HANDOFF_SOCKET_ENV: &str = "IMMIE2D_HANDOFF_SOCKET";
+/// How long to wait for the other process at each step of a handoff.
+pub const HANDOFF_TIMEOUT: Duration = Duration::from_secs(10);
+/// Largest handoff state accepted, to avoid allocating whatever length a broken peer sends.
+pub const MAX_HANDOFF_SIZE: usize = 256 * 1024 * 1024;
+
+const HANDOFF_ACK: u8 = 1;
+const ACCEPT_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Set whether a file descriptor stays open in processes started from this one. Std closes every descriptor on exec
+/// by default.
+pub fn set_inheritable(fd: RawFd, inheritable: bool) -> io::Result<()> {
+    // Safety: fcntl only reads and sets the flags of the descriptor, and reports invalid descriptors as an error.
+    unsafe {
+        let flags = libc::fcntl(fd, libc::F_GETFD);
+        if flags == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        let flags = if inheritable { flags & !libc::FD_CLOEXEC } else { flags | libc::FD_CLOEXEC };
+        if libc::fcntl(fd, libc::F_SETFD, flags) == -1 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    return Ok(());
+}
+
+/* The old process's end of a handoff, waiting for the new process to connect. The socket file is removed on drop. */
+pub struct PendingHandoff {
+    listener: UnixListener,
+    path: PathBuf
+}
+
+impl PendingHandoff {
+    /// Listen for the new process at a path, replacing a socket left behind by an earlier handoff.
+    pub fn bind(path: &Path) -> io::Result<PendingHandoff> {
+        match std::fs::remove_file(path) {
+            Err(err) if err.kind() != ErrorKind::NotFound => return Err(err),
+            _ => {}
+        }
+        let listener = UnixListener::bind(path)?;
+        listener.set_nonblocking(true)?;
+        return Ok(PendingHandoff { listener, path: path.to_path_buf() });
+    }
+
+    pub fn get_path(&self) -> &Path {
+        return &self.path;
+    }
+
+    /// Wait for the new process to connect, then send the state and wait for it to confirm it was received.
+    /// ```
+    /// use std::thread;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::handoff::handoff_state::{HandoffState, HandedOffConnection};
+    /// use immie2d_server::handoff::warm_handoff::{PendingHandoff, receive_handoff, HANDOFF_TIMEOUT};
+    ///
+    /// let path = std::env::temp_dir().join(format!("immie2d_handoff_doctest_{}", std::process::id()));
+    /// let pending = PendingHandoff::bind(&path).unwrap();
+    /// let state = HandoffState { listener_fd: 3, connections: vec![HandedOffConnection { player: PlayerId(1), fd: 4 }], sessions: Vec::new() };
+    ///
+    /// let new_process = thread::spawn({ let path = path.clone(); move || receive_handoff(&path).unwrap() });
+    /// pending.send(&state, HANDOFF_TIMEOUT).unwrap();
+    /// assert_eq!(new_process.join().unwrap(), state);
+    /// assert!(!path.exists());
+    /// ```
+    pub fn send(self, state: &HandoffState, timeout: Duration) -> io::Result<()> {
+        let deadline = Instant::now() + timeout;
+        let mut stream = loop {
+            match self.listener.accept() {
+                Ok((stream, _)) => break stream,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                    if Instant::now() >= deadline {
+                        return Err(io::Error::new(ErrorKind::TimedOut, "The new server process did not connect for the handoff"));
+                    }
+                    thread::sleep(ACCEPT_POLL_INTERVAL);
+                },
+                Err(err) => return Err(err)
+            }
+        };
+        stream.set_nonblocking(false)?;
+        stream.set_read_timeout(Some(deadline.saturating_duration_since(Instant::now()).max(ACCEPT_POLL_INTERVAL)))?;
+        let bytes = state.to_bytes();
+        stream.write_all(&(bytes.len() as u64).to_le_bytes())?;
+        stream.write_all(&bytes)?;
+        let mut ack = [0u8; 1];
+        stream.read_exact(&mut ack)?;
+        if ack[0] != HANDOFF_ACK {
+            return Err(io::Error::new(ErrorKind::InvalidData, "The new server process rejected the handoff"));
+        }
+        return Ok(());
+    }
+}
+
+impl Drop for PendingHandoff {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Connect to the old process at a path and receive its state. The state is only confirmed once it has been read
+/// in full, so a failed handoff leaves the old process running.
+pub fn receive_handoff(path: &Path) -> io::Result<HandoffState> {
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(HANDOFF_TIMEOUT))?;
+    let mut length = [0u8; 8];
+    stream.read_exact(&mut length)?;
+    let length = u64::from_le_bytes(length) as usize;
+    if length > MAX_HANDOFF_SIZE {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("Handoff state of {} bytes is too large", length)));
+    }
+    let mut bytes = vec![0u8; length];
+    stream.read_exact(&mut bytes)?;
+    let state = HandoffState::from_bytes(&bytes)?;
+    stream.write_all(&[HANDOFF_ACK])?;
+    return Ok(state);
+}
+
+/// Start a new server process and hand it the listening socket, client connections and running sessions.
+/// Once this returns the new process owns everything handed off, and this process should stop serving and exit.
+/// If the handoff fails the new process is killed and this process keeps serving.
+pub fn hand_off(listener: &TcpListener, connections: &[(PlayerId, &TcpStream)], sessions: &SessionManager, socket_path: &Path, mut command: Command) -> io::Result<Child> {
+    let state = HandoffState {
+        listener_fd: listener.as_raw_fd(),
+        connections: connections.iter().map(|(player, stream)| HandedOffConnection { player: *player, fd: stream.as_raw_fd() }).collect(),
+        sessions: sessions.get_snapshots()
+    };
+    set_inheritable(state.listener_fd, true)?;
+    for connection in state.connections.iter() {
+        set_inheritable(connection.fd, true)?;
+    }
+    let pending = PendingHandoff::bind(socket_path)?;
+    command.env(HANDOFF_SOCKET_ENV, pending.get_path());
+    let result = command.spawn().and_then(|mut child| {
+        return match pending.send(&state, HANDOFF_TIMEOUT) {
+            Ok(()) => Ok(child),
+            Err(err) => {
+                let _ = child.kill();
+                Err(err)
+            }
+        };
+    });
+    // Later processes started by this one shouldn't hold the sockets open.
+    set_inheritable(state.listener_fd, false)?;
+    for connection in state.connections.iter() {
+        set_inheritable(connection.fd, false)?;
+    }
+    return result;
+}
+
+/* What a new server process took over from the old one. */
+pub struct TakenOver {
+    pub listener: TcpListener,
+    pub connections: Vec<(PlayerId, TcpStream)>,
+    /// Players banned since their connection was accepted, whose connections were closed instead of resumed.
+    pub refused: Vec<(PlayerId, ConnectionDenial)>,
+    /// Sessions that could not be rebuilt with this process's game data, so their players can be told.
+    pub failed_sessions: Vec<(SessionSnapshot, SessionRestoreError)>
+}
+
+/// Check a handed off descriptor is an open stream socket, and stop it leaking into processes started from this one.
+/// This can't tell an inherited socket from one this process opened itself.
+fn take_fd(fd: RawFd) -> io::Result<RawFd> {
+    set_inheritable(fd, false)?;
+    let mut socket_type: libc::c_int = 0;
+    let mut length = std::mem::size_of::<libc::c_int>() as libc::socklen_t;
+    // Safety: getsockopt writes at most length bytes to socket_type, and reports descriptors that aren't sockets as
+    // an error.
+    let result = unsafe { libc::getsockopt(fd, libc::SOL_SOCKET, libc::SO_TYPE, &mut socket_type as *mut libc::c_int as *mut libc::c_void, &mut length) };
+    if result == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    if socket_type != libc::SOCK_STREAM {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("Handed off descriptor {} is not a stream socket", fd)));
+    }
+    return Ok(fd);
+}
+
+/// Take over from the old process if this process was started by hand_off(), rebuilding its sessions into a
+/// session manager. Connections of banned accounts are closed rather than resumed. Returns None for a normal start.
+pub fn take_over(sessions: &mut SessionManager, bans: &BanList, now: u64) -> io::Result<Option<TakenOver>> {
+    let path = match std::env::var_os(HANDOFF_SOCKET_ENV) {
+        Some(path) => PathBuf::from(path),
+        None => return Ok(None)
+    };
+    std::env::remove_var(HANDOFF_SOCKET_ENV);
+    let state = receive_handoff(&path)?;
+    let mut fds: Vec<RawFd> = state.connections.iter().map(|connection| connection.fd).collect();
+    fds.push(state.listener_fd);
+    fds.sort();
+    if fds.windows(2).any(|pair| pair[0] == pair[1]) {
+        return Err(io::Error::new(ErrorKind::InvalidData, "The handoff state lists a descriptor more than once"));
+    }
+    // Safety: the old process is trusted to list only descriptors it left open across the exec, which nothing this
+    // process opened since can share a number with. That trust isn't checked: take_fd() only checks each is an open
+    // stream socket, and duplicates are rejected above so none is wrapped twice.
+    let listener = unsafe { TcpListener::from_raw_fd(take_fd(state.listener_fd)?) };
+    let mut connections = Vec::with_capacity(state.connections.len());
+    let mut refused = Vec::new();
+    for connection in state.connections.iter() {
+        // Safety: as for the listener.
+        let stream = unsafe { TcpStream::from_raw_fd(take_fd(connection.fd)?) };
+        match bans.check_account(connection.player, now) {
+            Ok(()) => connections.push((connection.player, stream)),
+            Err(denial) => {
+                let _ = stream.shutdown(std::net::Shutdown::Both);
+                refused.push((connection.player, denial));
+            }
+        }
+    }
+    let failed_sessions = sessions.restore_sessions(state.sessions);
+    return Ok(Some(TakenOver { listener, connections, refused, failed_sessions }));
+}
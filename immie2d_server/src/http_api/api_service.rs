@@ -0,0 +1,228 @@
+use std::net::IpAddr;
+use std::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use immie2d_shared::engine_types::time_sync::get_unix_micros;
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::gameplay::tournament_bracket::MatchDecision;
+
+use super::crash_inbox::{CrashInbox, CrashReportRejection};
+use super::public_index::{PageRequest, PublicIndex, PublicProfile, MAX_PAGE_LIMIT};
+use super::rate_limiter::RateLimiter;
+
+/* A response to an API request, with a JSON body. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct ApiResponse {
+    pub status: u16,
+    pub body: Value,
+    /// Set on rate limited responses, for the Retry-After header.
+    pub retry_after: Option<Duration>
+}
+
+impl ApiResponse {
+    fn ok(body: Value) -> ApiResponse {
+        return ApiResponse { status: 200, body, retry_after: None };
+    }
+
+    fn error(status: u16, message: &str) -> ApiResponse {
+        return ApiResponse { status, body: json!({ "error": message }), retry_after: None };
+    }
+}
+
+/* The HTTP API, without the HTTP server itself so it can be served by anything and tested without one. Routes:
+- `GET /api/leaderboard?offset=&limit=` players by rating
+- `GET /api/players/{id}` a public profile
+- `GET /api/players/{id}/matches?offset=&limit=` match history, most recent first
+- `POST /api/crash_reports` a client crash report as plain text, only if a crash inbox is set. See CrashInbox
+Lists are paged, with at most MAX_PAGE_LIMIT entries a page. */
+pub struct ApiService {
+    index: RwLock<PublicIndex>,
+    limiter: Mutex<RateLimiter>,
+    crash_inbox: Option<CrashInbox>
+}
+
+impl ApiService {
+    pub fn new(index: PublicIndex, limiter: RateLimiter) -> ApiService {
+        return ApiService { index: RwLock::new(index), limiter: Mutex::new(limiter), crash_inbox: None };
+    }
+
+    /// Accept crash reports uploaded by clients into an inbox.
+    pub fn with_crash_inbox(mut self, inbox: CrashInbox) -> ApiService {
+        self.crash_inbox = Some(inbox);
+        return self;
+    }
+
+    /// The index to update as profiles are saved.
+    pub fn get_index(&self) -> &RwLock<PublicIndex> {
+        return &self.index;
+    }
+
+    /// Replace the whole index, such as with every profile freshly read from storage.
+    pub fn replace_index(&self, index: PublicIndex) {
+        *self.index.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = index;
+    }
+
+    /// Forget addresses that are no longer being limited. See RateLimiter::prune()
+    pub fn prune_limiter(&self, now: Instant) {
+        self.limiter.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).prune(now);
+    }
+
+    /// Answer a request from an address. The query is everything after the `?`, if there was one. The body is empty
+    /// for GET requests.
+    /// ```
+    /// use std::net::{IpAddr, Ipv4Addr};
+    /// use std::time::Instant;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::http_api::{api_service::ApiService, public_index::PublicIndex, rate_limiter::RateLimiter};
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    ///
+    /// let service = ApiService::new(PublicIndex::new(), RateLimiter::new().with_limits(5, 1));
+    /// for (id, rating) in [(1, 1200), (2, 1500)] {
+    ///     let mut profile = PlayerProfile::new(PlayerId(id), format!("player{}", id));
+    ///     profile.rating = rating;
+    ///     service.get_index().write().unwrap().update(&profile);
+    /// }
+    /// let address = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    /// let now = Instant::now();
+    ///
+    /// let leaderboard = service.handle(address, "GET", "/api/leaderboard", Some("limit=1"), "", now);
+    /// assert_eq!(leaderboard.status, 200);
+    /// assert_eq!(leaderboard.body["total"], 2);
+    /// assert_eq!(leaderboard.body["players"][0]["name"], "player2");
+    /// assert_eq!(leaderboard.body["players"][0]["rank"], 1);
+    ///
+    /// let profile = service.handle(address, "GET", "/api/players/1", None, "", now);
+    /// assert_eq!(profile.body["rating"], 1200);
+    /// assert_eq!(service.handle(address, "GET", "/api/players/9", None, "", now).status, 404);
+    /// assert_eq!(service.handle(address, "GET", "/api/leaderboard", Some("limit=1000"), "", now).status, 400);
+    /// assert_eq!(service.handle(address, "POST", "/api/leaderboard", None, "", now).status, 405);
+    /// assert_eq!(service.handle(address, "GET", "/api/leaderboard", None, "", now).status, 429);
+    /// ```
+    pub fn handle(&self, address: IpAddr, method: &str, path: &str, query: Option<&str>, body: &str, now: Instant) -> ApiResponse {
+        // A poisoned lock still holds a valid state, and the API only reads from the index, so there's nothing to corrupt
+        if let Err(retry_after) = self.limiter.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).check(address, now) {
+            return ApiResponse { retry_after: Some(retry_after), ..ApiResponse::error(429, "Too many requests") };
+        }
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').skip(1).collect();
+        if method == "POST" && segments[..] == ["api", "crash_reports"] {
+            return self.accept_crash_report(body);
+        }
+        if method != "GET" {
+            return ApiResponse::error(405, "Only GET is supported");
+        }
+        let page = match parse_page(query.unwrap_or_default()) {
+            Ok(page) => page,
+            Err(message) => return ApiResponse::error(400, &message)
+        };
+        let index = self.index.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        return match segments[..] {
+            ["api", "leaderboard"] => {
+                let leaderboard = index.get_leaderboard(page);
+                let players: Vec<Value> = leaderboard.items.iter().enumerate().map(|(position, profile)| json!({
+                    "rank": leaderboard.offset + position + 1,
+                    "id": profile.player.0,
+                    "name": profile.name,
+                    "rating": profile.rating
+                })).collect();
+                ApiResponse::ok(json!({ "offset": leaderboard.offset, "total": leaderboard.total, "players": players }))
+            },
+            ["api", "players", id] => match parse_player(id).and_then(|player| index.get_profile(player)) {
+                Some(profile) => ApiResponse::ok(profile_to_json(profile)),
+                None => ApiResponse::error(404, "No such player")
+            },
+            ["api", "players", id, "matches"] => match parse_player(id).and_then(|player| index.get_match_history(player, page)) {
+                Some(history) => {
+                    let matches: Vec<Value> = history.items.iter().map(|record| json!({
+                        "opponent": record.opponent.0,
+                        "opponent_name": index.get_profile(record.opponent).map(|opponent| opponent.name.clone()),
+                        "won": record.won,
+                        "decision": get_decision_name(record.decision),
+                        "rating_change": record.rating_change,
+                        "tournament": record.tournament,
+                        "unix_seconds": record.unix_seconds
+                    })).collect();
+                    ApiResponse::ok(json!({ "offset": history.offset, "total": history.total, "matches": matches }))
+                },
+                None => ApiResponse::error(404, "No such player")
+            },
+            _ => ApiResponse::error(404, "No such route")
+        };
+    }
+
+    /// ```
+    /// use std::net::{IpAddr, Ipv4Addr};
+    /// use std::time::Instant;
+    /// use immie2d_server::http_api::{api_service::ApiService, crash_inbox::CrashInbox, public_index::PublicIndex, rate_limiter::RateLimiter};
+    ///
+    /// let address = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    /// let report = "client_version=0.1.0\nmessage=oops\n";
+    /// let without_inbox = ApiService::new(PublicIndex::new(), RateLimiter::new());
+    /// assert_eq!(without_inbox.handle(address, "POST", "/api/crash_reports", None, report, Instant::now()).status, 404);
+    ///
+    /// let directory = std::env::temp_dir().join(format!("immie2d_crash_route_doctest_{}", std::process::id()));
+    /// let service = ApiService::new(PublicIndex::new(), RateLimiter::new()).with_crash_inbox(CrashInbox::open(directory.clone()).unwrap());
+    /// assert_eq!(service.handle(address, "POST", "/api/crash_reports", None, report, Instant::now()).status, 201);
+    /// assert_eq!(service.handle(address, "POST", "/api/crash_reports", None, "hello", Instant::now()).status, 400);
+    /// assert_eq!(std::fs::read_dir(&directory).unwrap().count(), 1);
+    /// std::fs::remove_dir_all(&directory).unwrap();
+    /// ```
+    fn accept_crash_report(&self, body: &str) -> ApiResponse {
+        let Some(inbox) = &self.crash_inbox else {
+            return ApiResponse::error(404, "Crash reports are not collected by this server");
+        };
+        return match inbox.accept(body, get_unix_micros()) {
+            Ok(_) => ApiResponse { status: 201, body: json!({}), retry_after: None },
+            Err(err @ CrashReportRejection::TooLarge(_)) => ApiResponse::error(413, &err.to_string()),
+            Err(err @ CrashReportRejection::NotAReport) => ApiResponse::error(400, &err.to_string()),
+            Err(err @ CrashReportRejection::InboxFull) => ApiResponse::error(503, &err.to_string()),
+            Err(err @ CrashReportRejection::Io(_)) => {
+                eprintln!("{}", err);
+                ApiResponse::error(500, "Failed to store crash report")
+            }
+        };
+    }
+}
+
+fn profile_to_json(profile: &PublicProfile) -> Value {
+    let party: Vec<Value> = profile.party.iter().map(|immie| json!({
+        "species": immie.species.to_string(),
+        "form": immie.form.map(|form| form.to_string()),
+        "level": immie.level
+    })).collect();
+    return json!({
+        "id": profile.player.0,
+        "name": profile.name,
+        "rating": profile.rating,
+        "matches": profile.match_history.len(),
+        "wins": profile.get_wins(),
+        "party": party
+    });
+}
+
+fn get_decision_name(decision: MatchDecision) -> &'static str {
+    return match decision {
+        MatchDecision::Played => "played",
+        MatchDecision::Walkover => "walkover",
+        MatchDecision::Bye => "bye"
+    };
+}
+
+fn parse_player(id: &str) -> Option<PlayerId> {
+    return id.parse::<u64>().ok().map(PlayerId);
+}
+
+/// Read the offset and limit of a query, ignoring anything else in it.
+fn parse_page(query: &str) -> Result<PageRequest, String> {
+    let mut page = PageRequest::default();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match key {
+            "offset" => page.offset = value.parse().map_err(|_| format!("Invalid offset [{}]", value))?,
+            "limit" => page.limit = value.parse().ok().filter(|limit| *limit > 0 && *limit <= MAX_PAGE_LIMIT).ok_or(format!("Invalid limit [{}], expected 1 to {}", value, MAX_PAGE_LIMIT))?,
+            _ => ()
+        }
+    }
+    return Ok(page);
+}
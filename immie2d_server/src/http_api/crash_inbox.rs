@@ -0,0 +1,113 @@
+use std::fmt;
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Largest crash report accepted, which is far more than a backtrace and the client's log buffer take.
+pub const MAX_CRASH_REPORT_BYTES: usize = 256 * 1024;
+/// Most reports kept in the inbox. Further reports are refused until some are cleared out, so spam can't fill the disk.
+pub const MAX_STORED_CRASH_REPORTS: usize = 10000;
+
+/* Why a crash report wasn't stored. */
+#[derive(Debug)]
+pub enum CrashReportRejection {
+    TooLarge(usize),
+    /// The body doesn't start like a client crash report.
+    NotAReport,
+    InboxFull,
+    Io(io::Error)
+}
+
+impl fmt::Display for CrashReportRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            CrashReportRejection::TooLarge(length) => write!(f, "Crash report of {} bytes is over the limit of {} bytes", length, MAX_CRASH_REPORT_BYTES),
+            CrashReportRejection::NotAReport => write!(f, "Body is not a crash report"),
+            CrashReportRejection::InboxFull => write!(f, "Crash report inbox already holds {} reports", MAX_STORED_CRASH_REPORTS),
+            CrashReportRejection::Io(err) => write!(f, "Failed to store crash report: {}", err)
+        };
+    }
+}
+
+/* A directory that crash reports uploaded by clients are written into, one file each, for developers to read. Reports
+are stored as they were sent, as clients strip identifying paths before uploading. */
+pub struct CrashInbox {
+    directory: PathBuf,
+    stored: AtomicUsize
+}
+
+impl CrashInbox {
+    /// Open the inbox, creating the directory if needed. Reports already in it count towards MAX_STORED_CRASH_REPORTS.
+    pub fn open(directory: PathBuf) -> io::Result<CrashInbox> {
+        fs::create_dir_all(&directory)?;
+        let stored = fs::read_dir(&directory)?.filter_map(|entry| entry.ok()).filter(|entry| is_report_file(&entry.path())).count();
+        return Ok(CrashInbox { directory, stored: AtomicUsize::new(stored) });
+    }
+
+    pub fn get_directory(&self) -> &Path {
+        return &self.directory;
+    }
+
+    /// Store a report sent as the text the client writes, named after when it arrived. Returns the path it was
+    /// written to.
+    /// ```
+    /// use immie2d_server::http_api::crash_inbox::{CrashInbox, CrashReportRejection, MAX_CRASH_REPORT_BYTES};
+    ///
+    /// let directory = std::env::temp_dir().join(format!("immie2d_crash_inbox_doctest_{}", std::process::id()));
+    /// let inbox = CrashInbox::open(directory.clone()).unwrap();
+    /// let report = "client_version=0.1.0\ncreated_at=1\nmessage=oops\n";
+    /// let first = inbox.accept(report, 5).unwrap();
+    /// let second = inbox.accept(report, 5).unwrap();
+    /// assert_ne!(first, second);
+    /// assert_eq!(std::fs::read_to_string(&first).unwrap(), report);
+    ///
+    /// assert!(matches!(inbox.accept("GET / HTTP/1.1", 5), Err(CrashReportRejection::NotAReport)));
+    /// let huge = format!("client_version=0.1.0\n{}", "a".repeat(MAX_CRASH_REPORT_BYTES));
+    /// assert!(matches!(inbox.accept(&huge, 5), Err(CrashReportRejection::TooLarge(_))));
+    /// assert_eq!(CrashInbox::open(directory.clone()).unwrap().get_stored_count(), 2);
+    /// std::fs::remove_dir_all(&directory).unwrap();
+    /// ```
+    pub fn accept(&self, body: &str, unix_micros: u64) -> Result<PathBuf, CrashReportRejection> {
+        if body.len() > MAX_CRASH_REPORT_BYTES {
+            return Err(CrashReportRejection::TooLarge(body.len()));
+        }
+        if !body.starts_with("client_version=") {
+            return Err(CrashReportRejection::NotAReport);
+        }
+        let reserved = self.stored.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |stored| (stored < MAX_STORED_CRASH_REPORTS).then_some(stored + 1));
+        if reserved.is_err() {
+            return Err(CrashReportRejection::InboxFull);
+        }
+        // Several reports can arrive in the same microsecond, so take the first free name
+        let mut suffix = 0;
+        loop {
+            let path = self.directory.join(format!("crash_{}_{}.txt", unix_micros, suffix));
+            match fs::OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    return match file.write_all(body.as_bytes()) {
+                        Ok(()) => Ok(path),
+                        Err(err) => {
+                            self.stored.fetch_sub(1, Ordering::Relaxed);
+                            let _ = fs::remove_file(&path);
+                            Err(CrashReportRejection::Io(err))
+                        }
+                    };
+                },
+                Err(err) if err.kind() == ErrorKind::AlreadyExists => suffix += 1,
+                Err(err) => {
+                    self.stored.fetch_sub(1, Ordering::Relaxed);
+                    return Err(CrashReportRejection::Io(err));
+                }
+            }
+        }
+    }
+
+    pub fn get_stored_count(&self) -> usize {
+        return self.stored.load(Ordering::Relaxed);
+    }
+}
+
+fn is_report_file(path: &Path) -> bool {
+    return path.is_file() && path.file_name().and_then(|name| name.to_str()).is_some_and(|name| name.starts_with("crash_") && name.ends_with(".txt"));
+}
@@ -0,0 +1,32 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use axum::extract::{ConnectInfo, DefaultBodyLimit, State};
+use axum::http::{header, HeaderValue, Method, StatusCode, Uri};
+use axum::response::{IntoResponse, Response};
+use axum::Router;
+use tokio::net::TcpListener;
+
+use super::api_service::ApiService;
+use super::crash_inbox::MAX_CRASH_REPORT_BYTES;
+
+/// Serve the HTTP API on a listener until it fails. Routing is left to ApiService, so every request goes through the
+/// same rate limiting. See ApiService::handle()
+pub async fn serve_http_api(listener: TcpListener, service: Arc<ApiService>) -> std::io::Result<()> {
+    // Crash reports are the only requests with a body
+    let router = Router::new().fallback(handle_http_request).layer(DefaultBodyLimit::max(MAX_CRASH_REPORT_BYTES)).with_state(service);
+    return axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>()).await;
+}
+
+async fn handle_http_request(State(service): State<Arc<ApiService>>, ConnectInfo(address): ConnectInfo<SocketAddr>, method: Method, uri: Uri, body: String) -> Response {
+    let api_response = service.handle(address.ip(), method.as_str(), uri.path(), uri.query(), &body, Instant::now());
+    let status = StatusCode::from_u16(api_response.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    let mut response = (status, [(header::CONTENT_TYPE, "application/json")], api_response.body.to_string()).into_response();
+    if let Some(retry_after) = api_response.retry_after {
+        // Retry-After is whole seconds, so round up rather than telling the client to retry too early
+        let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+    }
+    return response;
+}
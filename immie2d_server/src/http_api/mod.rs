@@ -0,0 +1,6 @@
+pub mod public_index;
+pub mod rate_limiter;
+pub mod api_service;
+pub mod crash_inbox;
+#[cfg(feature = "http_api")]
+pub mod http_server;
@@ -0,0 +1,152 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, HashMap};
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::storage::player_profile::{MatchRecord, PlayerProfile};
+
+/// Entries per page when a request doesn't say.
+pub const DEFAULT_PAGE_LIMIT: usize = 25;
+/// Most entries a single page can have.
+pub const MAX_PAGE_LIMIT: usize = 100;
+
+/* Which part of a list to return. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PageRequest {
+    pub offset: usize,
+    pub limit: usize
+}
+
+impl PageRequest {
+    pub fn default() -> PageRequest {
+        return PageRequest { offset: 0, limit: DEFAULT_PAGE_LIMIT };
+    }
+}
+
+/* A page of a list, with the length of the whole list so a client can tell how many pages there are. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Page<T> {
+    pub offset: usize,
+    pub total: usize,
+    pub items: Vec<T>
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PublicImmie {
+    pub species: GlobalString,
+    pub form: Option<GlobalString>,
+    pub level: u32
+}
+
+/* What anyone can see about a player. Inventories, boxes, settings and anything else private never leave the game
+server. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PublicProfile {
+    pub player: PlayerId,
+    pub name: String,
+    pub rating: u32,
+    pub party: Vec<PublicImmie>,
+    /// Most recent matches last. See MAX_MATCH_HISTORY
+    pub match_history: Vec<MatchRecord>
+}
+
+impl PublicProfile {
+    pub fn new(profile: &PlayerProfile) -> PublicProfile {
+        return PublicProfile {
+            player: profile.player,
+            name: profile.name.clone(),
+            rating: profile.rating,
+            party: profile.party.iter().map(|immie| PublicImmie { species: immie.species, form: immie.form, level: immie.level }).collect(),
+            match_history: profile.match_history.clone()
+        };
+    }
+
+    pub fn get_wins(&self) -> usize {
+        return self.match_history.iter().filter(|record| record.won).count();
+    }
+}
+
+/* The public side of every player's profile, kept up to date by the server as it saves profiles so the HTTP API never
+touches storage. Banned players are left out. */
+pub struct PublicIndex {
+    profiles: HashMap<PlayerId, PublicProfile>,
+    /// Highest rating first, then lowest player id, so ties always list in the same order.
+    ranking: BTreeSet<(Reverse<u32>, PlayerId)>
+}
+
+impl PublicIndex {
+    pub fn new() -> PublicIndex {
+        return PublicIndex { profiles: HashMap::new(), ranking: BTreeSet::new() };
+    }
+
+    /// Index many profiles at once, such as every profile in storage. See PublicIndex::update()
+    pub fn from_profiles(profiles: &[PlayerProfile]) -> PublicIndex {
+        let mut index = PublicIndex::new();
+        for profile in profiles.iter() {
+            index.update(profile);
+        }
+        return index;
+    }
+
+    /// Add or replace a player's public profile, such as whenever their profile is saved.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::http_api::public_index::{PageRequest, PublicIndex};
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    ///
+    /// let mut index = PublicIndex::new();
+    /// for (id, rating) in [(1, 1200), (2, 1500), (3, 1200), (4, 900)] {
+    ///     let mut profile = PlayerProfile::new(PlayerId(id), format!("player{}", id));
+    ///     profile.rating = rating;
+    ///     index.update(&profile);
+    /// }
+    /// let mut banned = PlayerProfile::new(PlayerId(2), "player2".to_string());
+    /// banned.is_banned = true;
+    /// index.update(&banned);
+    ///
+    /// let page = index.get_leaderboard(PageRequest { offset: 1, limit: 2 });
+    /// assert_eq!(page.total, 3);
+    /// assert_eq!(page.items.iter().map(|profile| profile.player).collect::<Vec<_>>(), vec![PlayerId(3), PlayerId(4)]);
+    /// assert!(index.get_profile(PlayerId(2)).is_none());
+    /// ```
+    pub fn update(&mut self, profile: &PlayerProfile) {
+        self.remove(profile.player);
+        if profile.is_banned {
+            return;
+        }
+        self.ranking.insert((Reverse(profile.rating), profile.player));
+        self.profiles.insert(profile.player, PublicProfile::new(profile));
+    }
+
+    pub fn remove(&mut self, player: PlayerId) {
+        if let Some(old) = self.profiles.remove(&player) {
+            self.ranking.remove(&(Reverse(old.rating), player));
+        }
+    }
+
+    pub fn get_profile(&self, player: PlayerId) -> Option<&PublicProfile> {
+        return self.profiles.get(&player);
+    }
+
+    /// Players by rating, highest first. See PublicIndex::update()
+    pub fn get_leaderboard(&self, page: PageRequest) -> Page<&PublicProfile> {
+        let items = self.ranking.iter().skip(page.offset).take(page.limit).map(|(_, player)| &self.profiles[player]).collect();
+        return Page { offset: page.offset, total: self.ranking.len(), items };
+    }
+
+    /// A player's matches, most recent first. None if the player isn't public.
+    pub fn get_match_history(&self, player: PlayerId, page: PageRequest) -> Option<Page<MatchRecord>> {
+        let history = &self.profiles.get(&player)?.match_history;
+        let items = history.iter().rev().skip(page.offset).take(page.limit).copied().collect();
+        return Some(Page { offset: page.offset, total: history.len(), items });
+    }
+
+    pub fn len(&self) -> usize {
+        return self.profiles.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.profiles.is_empty();
+    }
+}
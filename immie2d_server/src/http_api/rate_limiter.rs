@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// Requests a client can make in a burst.
+pub const DEFAULT_BURST: u32 = 30;
+/// Requests a client regains each second, up to the burst.
+pub const DEFAULT_REQUESTS_PER_SECOND: u32 = 5;
+
+/* Limits how often each address can call the HTTP API, so one scraper can't starve everyone else. Each address has a
+bucket of requests that refills steadily, allowing short bursts. */
+pub struct RateLimiter {
+    burst: u32,
+    requests_per_second: u32,
+    /// Requests left and when they were last counted, for each address seen recently.
+    buckets: HashMap<IpAddr, (f64, Instant)>
+}
+
+impl RateLimiter {
+    pub fn new() -> RateLimiter {
+        return RateLimiter { burst: DEFAULT_BURST, requests_per_second: DEFAULT_REQUESTS_PER_SECOND, buckets: HashMap::new() };
+    }
+
+    /// Will panic if the burst or rate is 0.
+    pub fn with_limits(mut self, burst: u32, requests_per_second: u32) -> RateLimiter {
+        assert!(burst > 0 && requests_per_second > 0, "Rate limits must be above 0");
+        self.burst = burst;
+        self.requests_per_second = requests_per_second;
+        return self;
+    }
+
+    /// Count a request from an address. Returns how long until it can make another if it's out of requests.
+    /// ```
+    /// use std::net::{IpAddr, Ipv4Addr};
+    /// use std::time::{Duration, Instant};
+    /// use immie2d_server::http_api::rate_limiter::RateLimiter;
+    ///
+    /// let mut limiter = RateLimiter::new().with_limits(2, 4);
+    /// let (scraper, visitor) = (IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)), IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)));
+    /// let start = Instant::now();
+    /// assert!(limiter.check(scraper, start).is_ok());
+    /// assert!(limiter.check(scraper, start).is_ok());
+    /// assert_eq!(limiter.check(scraper, start), Err(Duration::from_millis(250)));
+    /// assert!(limiter.check(visitor, start).is_ok());
+    /// assert!(limiter.check(scraper, start + Duration::from_millis(250)).is_ok());
+    /// ```
+    pub fn check(&mut self, address: IpAddr, now: Instant) -> Result<(), Duration> {
+        let (burst, rate) = (self.burst as f64, self.requests_per_second as f64);
+        let (tokens, counted_at) = self.buckets.entry(address).or_insert((burst, now));
+        *tokens = (*tokens + now.saturating_duration_since(*counted_at).as_secs_f64() * rate).min(burst);
+        *counted_at = now;
+        if *tokens < 1.0 {
+            return Err(Duration::from_secs_f64((1.0 - *tokens) / rate));
+        }
+        *tokens -= 1.0;
+        return Ok(());
+    }
+
+    /// Forget addresses whose buckets have refilled, since they are no different to addresses never seen. Call
+    /// every so often so the limiter doesn't grow with every address that ever made a request.
+    pub fn prune(&mut self, now: Instant) {
+        let refill = Duration::from_secs_f64(self.burst as f64 / self.requests_per_second as f64);
+        self.buckets.retain(|_, (_, counted_at)| now.saturating_duration_since(*counted_at) < refill);
+    }
+}
@@ -0,0 +1,22 @@
+// The codebase favours explicit returns and inherent `default()` constructors.
+#![allow(
+    clippy::needless_return,
+    clippy::should_implement_trait,
+    clippy::new_without_default,
+    clippy::module_inception,
+    clippy::len_zero
+)]
+
+pub mod network;
+pub mod matchmaking;
+pub mod session;
+pub mod storage;
+pub mod chat;
+pub mod admin;
+pub mod config;
+pub mod world;
+pub mod handoff;
+pub mod tournament;
+pub mod auth;
+pub mod distribution;
+pub mod http_api;
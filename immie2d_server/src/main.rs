@@ -1,46 +1,536 @@
-use std::{net::TcpListener, net::TcpStream, thread, io::{self, Read, Write}, time};
+#![allow(clippy::needless_return, clippy::never_loop)]
 
-fn  handle_sender(mut stream: TcpStream) -> io::Result<()>{
-    let mut buf = [0;512];
-    for _ in 0..5 {
-        let bytes_read = stream.read(&mut buf)?; // TODO add support for client closing connection.
+use std::{net::{TcpListener, TcpStream}, thread, io::{self, BufReader}, time};
+use std::{env, path::PathBuf, process};
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::{Arc, Mutex, RwLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
 
-        if bytes_read == 0 {
-            println!("no bytes read");
-            return Ok(());
+use immie2d_server::admin::admin_command::{AdminCommand, ADMIN_USAGE};
+use immie2d_server::admin::ban_list::BanList;
+use immie2d_server::admin::debug_channel::serve_debug_connection;
+use immie2d_server::admin::stat_audit::StatAuditLog;
+use immie2d_server::config::server_config::{acquire_storage, ServerConfig, StorageBackend, StoragePool};
+use immie2d_server::network::file_transfer::{serve_transfer, TransferDirectories, MAX_TRANSFER_CONNECTIONS, TRANSFER_IO_TIMEOUT};
+use immie2d_server::auth::auth_service::AuthService;
+use immie2d_server::network::game_connection::{resume_game_connection, serve_game_connection, GameServices};
+use immie2d_server::matchmaking::matchmaker::Matchmaker;
+use immie2d_server::network::protocol_trace::ProtocolTracer;
+use immie2d_server::network::send_queue::OutboundMessage;
+use immie2d_server::session::session_manager::SessionManager;
+use immie2d_server::storage::backup::BackupScheduler;
+use immie2d_server::world::fast_travel_network::FastTravelNetwork;
+use immie2d_server::world::game_world::GameWorld;
+use immie2d_server::world::simulation_clock::{SimulationClock, SIMULATION_STATUS_COALESCE_KEY};
+#[cfg(unix)]
+use immie2d_server::handoff::warm_handoff;
+use immie2d_shared::engine_types::game_protocol::MessageKind;
+use immie2d_shared::engine_types::game_rng::GameRng;
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::engine_types::time_sync::get_unix_micros;
+use immie2d_shared::gameplay::encounter::encounter_roller::EncounterRoller;
+use immie2d_shared::gameplay::game_data::GameData;
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::world::tile_map::{MapObject, TileMap};
+use immie2d_shared::modding::{data_pack::install_packs, pack_advertisement::PackAdvertisement, pack_manifest::PackManifest};
+
+/// Config file read at startup. The defaults are used if it doesn't exist.
+const CONFIG_PATH: &str = "server.cfg";
+/// Longest a background task waits for a free storage connection before giving up until its next run.
+const STORAGE_ACQUIRE_TIMEOUT: time::Duration = time::Duration::from_secs(30);
+/// How often the ban list is read from storage again, so bans made by admin commands or other servers take effect.
+const BAN_LIST_RELOAD_SECONDS: u64 = 30;
+/// How often the world loop checks for steps while the simulation is paused.
+const PAUSED_POLL_INTERVAL: time::Duration = time::Duration::from_millis(10);
+/// How often empty region instances are checked for closing.
+const REGION_UPKEEP_SECONDS: u64 = 10;
+/// How often the HTTP API reads every profile from storage again and forgets rate limited addresses that calmed down.
+/// Profiles are saved by game servers, tournaments and admin commands alike, so storage is the one place to watch.
+#[cfg(feature = "http_api")]
+const HTTP_API_REFRESH_SECONDS: u64 = 60;
+/// How often the handoff watcher checks whether a handoff was requested.
+#[cfg(unix)]
+const HANDOFF_POLL_INTERVAL: time::Duration = time::Duration::from_millis(100);
+
+/// Set by SIGUSR2 to hand the server off to a new process. See spawn_handoff_watcher()
+#[cfg(unix)]
+static HANDOFF_REQUESTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// Run an admin subcommand against the configured storage, or the storage directory given with --data, exiting with an
+/// error code if it fails. Backup commands use the backup settings of the config.
+fn run_admin(args: &[String]) {
+    let config = ServerConfig::load(&PathBuf::from(CONFIG_PATH)).unwrap_or_else(|err| {
+        eprintln!("Failed to load {}: {}", CONFIG_PATH, err);
+        process::exit(1);
+    });
+    let (storage, command_args) = match args.first().map(|arg| arg.as_str()) {
+        Some("--data") if args.len() >= 2 => (StorageBackend::File { directory: PathBuf::from(&args[1]) }, &args[2..]),
+        _ => (config.storage.clone(), args)
+    };
+    let command = match AdminCommand::parse(command_args) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{}\n{}", err, ADMIN_USAGE);
+            process::exit(2);
+        }
+    };
+    let result = storage.open().and_then(|mut storage| {
+        if !command.is_backup_command() {
+            return command.run(storage.as_mut());
+        }
+        return command.run_backup(storage.as_mut(), &config.backup, get_unix_seconds());
+    });
+    match result {
+        Ok(description) => println!("{}", description),
+        Err(err) => {
+            eprintln!("Admin command failed: {}", err);
+            process::exit(1);
+        }
+    }
+}
+
+fn get_unix_seconds() -> u64 {
+    return SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+}
+
+/// Serve file transfers on their own listener, one thread per connection, so bulk downloads never share a connection
+/// with gameplay traffic. At most MAX_TRANSFER_CONNECTIONS are served at once, and a stalled client is dropped after
+/// TRANSFER_IO_TIMEOUT.
+fn spawn_transfer_listener(address: &str, directories: TransferDirectories, bans: Arc<RwLock<BanList>>) -> io::Result<thread::JoinHandle<()>> {
+    let listener = TcpListener::bind(address)?;
+    let active = Arc::new(AtomicUsize::new(0));
+    return Ok(thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let denied = stream.peer_addr().map(|peer| bans.read().unwrap_or_else(|poisoned| poisoned.into_inner()).check_connection(peer.ip(), get_unix_seconds()).is_err()).unwrap_or(true);
+            if denied || active.load(Ordering::Acquire) >= MAX_TRANSFER_CONNECTIONS {
+                let _ = stream.shutdown(std::net::Shutdown::Both);
+                continue;
+            }
+            if stream.set_read_timeout(Some(TRANSFER_IO_TIMEOUT)).and_then(|_| stream.set_write_timeout(Some(TRANSFER_IO_TIMEOUT))).is_err() {
+                continue;
+            }
+            active.fetch_add(1, Ordering::AcqRel);
+            let (directories, active) = (directories.clone(), active.clone());
+            thread::spawn(move || {
+                if let Err(err) = serve_transfer(&mut stream, &directories) {
+                    eprintln!("File transfer to {:?} failed: {}", stream.peer_addr(), err);
+                }
+                active.fetch_sub(1, Ordering::AcqRel);
+            });
+        }
+    }));
+}
+
+/// Read the ban list from storage on its own thread every BAN_LIST_RELOAD_SECONDS. A failed read keeps the bans
+/// already loaded.
+fn spawn_ban_list_reloader(storage: Arc<StoragePool>, bans: Arc<RwLock<BanList>>) -> thread::JoinHandle<()> {
+    return thread::spawn(move || loop {
+        thread::sleep(time::Duration::from_secs(BAN_LIST_RELOAD_SECONDS));
+        match acquire_storage(&storage, STORAGE_ACQUIRE_TIMEOUT).and_then(|mut storage| storage.load_ban_list()) {
+            Ok(loaded) => *bans.write().unwrap_or_else(|poisoned| poisoned.into_inner()) = loaded,
+            Err(err) => eprintln!("Failed to reload the ban list: {}", err)
+        }
+    });
+}
+
+/// Run the world whenever the simulation clock has ticks due, on its own thread. Clients are sent the status after
+/// steps run while paused, so they see the new tick. Battle turns whose timer ran out are ended as well.
+fn spawn_world_loop(services: Arc<GameServices>) -> thread::JoinHandle<()> {
+    return thread::spawn(move || loop {
+        let now = time::Instant::now();
+        let (ticks, status, next_tick_at) = {
+            let mut clock = services.lock_clock();
+            let ticks = clock.poll(now);
+            (ticks, clock.get_status(), clock.get_next_tick_at())
+        };
+        if ticks > 0 {
+            let mut world = services.lock_world();
+            for tick in status.tick - ticks as u64 + 1..=status.tick {
+                world.run_tick(tick);
+            }
+            if status.is_paused {
+                world.broadcast(MessageKind::SimulationStatus, OutboundMessage::snapshot(SIMULATION_STATUS_COALESCE_KEY, status.to_bytes()));
+            }
+        }
+        // Turn timers run on the wall clock, so battles keep moving while the simulation is paused
+        for (id, result) in services.lock_world().get_sessions_mut().end_expired_turns(get_unix_micros()) {
+            if let Err(err) = result {
+                eprintln!("Failed to end the timed out turn of battle session {}: {:?}", id, err);
+            }
         }
-        stream.write(&buf[..bytes_read]).expect("failed to write"); // TODO add support for client closing connection.
+        // Steps requested while paused are picked up within a paused poll interval
+        let wait = next_tick_at.map(|at| at.saturating_duration_since(time::Instant::now())).unwrap_or(PAUSED_POLL_INTERVAL);
+        thread::sleep(wait);
+    });
+}
 
-        println!("From the sender: {}", String::from_utf8_lossy(&buf));
+/// Serve the debug channel on its own listener, one thread per connection. Status changes are broadcast to every
+/// client. See serve_debug_connection()
+fn spawn_debug_listener(address: &str, services: Arc<GameServices>) -> io::Result<thread::JoinHandle<()>> {
+    let listener = TcpListener::bind(address)?;
+    return Ok(thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(mut stream) = stream else {
+                continue;
+            };
+            let services = services.clone();
+            thread::spawn(move || {
+                let reader = match stream.try_clone() {
+                    Ok(reader) => BufReader::new(reader),
+                    Err(err) => return eprintln!("Failed to serve the debug channel to {:?}: {}", stream.peer_addr(), err)
+                };
+                let mut broadcast = |message| services.lock_world().broadcast(MessageKind::SimulationStatus, message);
+                if let Err(err) = serve_debug_connection(reader, &mut stream, &services.clock, &mut broadcast) {
+                    eprintln!("Debug channel connection {:?} failed: {}", stream.peer_addr(), err);
+                }
+            });
+        }
+    }));
+}
+
+/// Close region instances that have been empty for long enough on its own thread, every REGION_UPKEEP_SECONDS.
+fn spawn_region_upkeep(services: Arc<GameServices>) -> thread::JoinHandle<()> {
+    return thread::spawn(move || loop {
+        thread::sleep(time::Duration::from_secs(REGION_UPKEEP_SECONDS));
+        services.lock_world().close_empty_instances(get_unix_seconds());
+    });
+}
+
+/// Back up storage on its own thread whenever the configured interval passes. Does nothing if scheduled backups are off.
+/// Add the fast travel points of every map, in name order so the same data always gives the same network. A map whose
+/// points clash with another's gets none.
+fn build_fast_travel_network(maps: &HashMap<GlobalString, TileMap>) -> FastTravelNetwork {
+    let mut ordered: Vec<&TileMap> = maps.values().collect();
+    ordered.sort_by_key(|map| map.get_name().to_string());
+    let mut network = FastTravelNetwork::new();
+    for map in ordered {
+        if let Err(err) = network.add_map(map) {
+            eprintln!("Skipping the fast travel points of map {}: {}", map.get_name().to_string(), err);
+        }
+    }
+    return network;
+}
 
+fn spawn_backup_scheduler(config: &ServerConfig, storage: Arc<StoragePool>) -> Option<thread::JoinHandle<()>> {
+    if config.backup.interval_seconds == 0 {
+        return None;
+    }
+    let mut scheduler = BackupScheduler::new(config.backup.clone(), get_unix_seconds());
+    return Some(thread::spawn(move || loop {
         thread::sleep(time::Duration::from_secs(1));
+        if !scheduler.is_due(get_unix_seconds()) {
+            continue;
+        }
+        let result = acquire_storage(&storage, STORAGE_ACQUIRE_TIMEOUT).and_then(|mut storage| scheduler.tick(storage.as_mut(), get_unix_seconds()));
+        match result {
+            Ok(Some(backup)) => println!("Backed up storage to {}", backup.path.display()),
+            Ok(None) => {},
+            Err(err) => eprintln!("Scheduled backup failed: {}", err)
+        }
+    }));
+}
+
+/// Serve the read only HTTP API on its own thread if the config has an address for it. The public index is loaded
+/// from storage before serving and again every HTTP_API_REFRESH_SECONDS, so leaderboard and profile changes show up.
+#[cfg(feature = "http_api")]
+fn spawn_http_api(config: &ServerConfig, storage: Arc<StoragePool>) -> Option<thread::JoinHandle<()>> {
+    use immie2d_server::http_api::{api_service::ApiService, crash_inbox::CrashInbox, http_server::serve_http_api, public_index::PublicIndex, rate_limiter::RateLimiter};
+
+    let address = config.http_api.clone()?;
+    let crash_inbox = match &config.crash_report_directory {
+        Some(directory) => match CrashInbox::open(directory.clone()) {
+            Ok(inbox) => Some(inbox),
+            Err(err) => {
+                eprintln!("Failed to open the crash report directory {}, not collecting crash reports: {}", directory.display(), err);
+                None
+            }
+        },
+        None => None
+    };
+    let load_index = move || acquire_storage(&storage, STORAGE_ACQUIRE_TIMEOUT).and_then(|mut storage| storage.export_snapshot()).map(|snapshot| PublicIndex::from_profiles(&snapshot.profiles));
+    return Some(thread::spawn(move || {
+        let runtime = match tokio::runtime::Runtime::new() {
+            Ok(runtime) => runtime,
+            Err(err) => return eprintln!("Failed to start the HTTP API runtime: {}", err)
+        };
+        runtime.block_on(async move {
+            let index = tokio::task::spawn_blocking(load_index.clone()).await.expect("Loading the public index panicked").unwrap_or_else(|err| {
+                eprintln!("Failed to load profiles for the HTTP API, serving an empty index until the next refresh: {}", err);
+                return PublicIndex::new();
+            });
+            let mut service = ApiService::new(index, RateLimiter::new());
+            if let Some(inbox) = crash_inbox {
+                service = service.with_crash_inbox(inbox);
+            }
+            let service = Arc::new(service);
+            let refreshed = service.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(time::Duration::from_secs(HTTP_API_REFRESH_SECONDS));
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    refreshed.prune_limiter(time::Instant::now());
+                    match tokio::task::spawn_blocking(load_index.clone()).await {
+                        Ok(Ok(index)) => refreshed.replace_index(index),
+                        Ok(Err(err)) => eprintln!("Failed to refresh the HTTP API index: {}", err),
+                        Err(err) => eprintln!("Refreshing the HTTP API index panicked: {}", err)
+                    }
+                }
+            });
+            let listener = match tokio::net::TcpListener::bind(&address).await {
+                Ok(listener) => listener,
+                Err(err) => return eprintln!("Failed to bind the HTTP API to {}: {}", address, err)
+            };
+            if let Err(err) = serve_http_api(listener, service).await {
+                eprintln!("The HTTP API stopped: {}", err);
+            }
+        });
+    }));
+}
+
+#[cfg(not(feature = "http_api"))]
+fn spawn_http_api(config: &ServerConfig, _storage: Arc<StoragePool>) -> Option<thread::JoinHandle<()>> {
+    if let Some(address) = &config.http_api {
+        eprintln!("Not serving the HTTP API on {}, the server was built without the http_api feature", address);
     }
-    println!("fully looped");
-    stream.shutdown(std::net::Shutdown::Both).expect("failed to shut donw");
-    return Ok(());
+    return None;
+}
+
+/// Take over the listener, logged in connections and battle sessions of the old process if this one was started by a
+/// warm handoff. Returns None for a normal start. Exits if the handoff fails, which leaves the old process serving.
+#[cfg(unix)]
+fn take_over_old_process(sessions: &mut SessionManager, bans: &BanList) -> Option<(TcpListener, Vec<(PlayerId, TcpStream)>)> {
+    let taken = warm_handoff::take_over(sessions, bans, get_unix_seconds()).unwrap_or_else(|err| {
+        eprintln!("Failed to take over from the old server process, refusing to start: {}", err);
+        process::exit(1);
+    })?;
+    for (player, denial) in taken.refused.iter() {
+        eprintln!("Closed the handed off connection of player {}: {:?}", player, denial);
+    }
+    for (snapshot, err) in taken.failed_sessions.iter() {
+        eprintln!("Failed to restore battle session {} after the handoff: {:?}", snapshot.id, err);
+    }
+    println!("Took over {} connections and {} battle sessions from the old server process", taken.connections.len(), sessions.get_session_count());
+    return Some((taken.listener, taken.connections));
+}
+
+#[cfg(not(unix))]
+fn take_over_old_process(_sessions: &mut SessionManager, _bans: &BanList) -> Option<(TcpListener, Vec<(PlayerId, TcpStream)>)> {
+    return None;
+}
+
+#[cfg(unix)]
+extern "C" fn request_handoff(_signal: libc::c_int) {
+    HANDOFF_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Hand the server off to a new copy of this executable on SIGUSR2, for a rolling restart. Every online player's
+/// profile is saved first for the new process to load, and the world stays locked until this process exits so nothing
+/// changes after the save. This process keeps serving if the handoff fails.
+#[cfg(unix)]
+fn spawn_handoff_watcher(services: Arc<GameServices>, listener: TcpListener, streams: Arc<Mutex<HashMap<u64, TcpStream>>>) -> thread::JoinHandle<()> {
+    // Safety: the handler only stores to an atomic, which is safe to do in a signal handler.
+    unsafe {
+        libc::signal(libc::SIGUSR2, request_handoff as extern "C" fn(libc::c_int) as libc::sighandler_t);
+    }
+    let socket_path = env::temp_dir().join(format!("immie2d_handoff_{}", process::id()));
+    return thread::spawn(move || loop {
+        thread::sleep(HANDOFF_POLL_INTERVAL);
+        if !HANDOFF_REQUESTED.swap(false, Ordering::SeqCst) {
+            continue;
+        }
+        let executable = match env::current_exe() {
+            Ok(executable) => executable,
+            Err(err) => {
+                eprintln!("Failed to find the server executable, not handing off: {}", err);
+                continue;
+            }
+        };
+        let world = services.lock_world();
+        // Saved inside the world's lock on purpose, since this process stops serving once the handoff succeeds
+        let profiles: Vec<_> = world.get_online_players().map(|online| online.profile.clone()).collect();
+        if let Err(err) = acquire_storage(&services.storage, STORAGE_ACQUIRE_TIMEOUT).and_then(|mut storage| storage.save_profiles(&profiles)) {
+            eprintln!("Failed to save the profiles of online players, not handing off: {}", err);
+            continue;
+        }
+        let streams = streams.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let connections: Vec<(PlayerId, &TcpStream)> = world.get_online_players().filter_map(|online| Some((online.profile.player, streams.get(&online.connection)?))).collect();
+        let mut command = process::Command::new(executable);
+        command.args(env::args_os().skip(1));
+        match warm_handoff::hand_off(&listener, &connections, world.get_sessions(), &socket_path, command) {
+            Ok(child) => {
+                println!("Handed off {} connections to server process {}", connections.len(), child.id());
+                process::exit(0);
+            },
+            Err(err) => eprintln!("Failed to hand off to a new server process, still serving: {}", err)
+        }
+    });
+}
+
+/// Serve a game connection on its own thread, tracking its stream while it is open so it can be handed off.
+/// Connections taken over from the old process resume their player.
+fn spawn_game_connection(stream: TcpStream, services: Arc<GameServices>, streams: Arc<Mutex<HashMap<u64, TcpStream>>>, connection: u64, resumed: Option<PlayerId>) {
+    match stream.try_clone() {
+        Ok(tracked) => {
+            streams.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(connection, tracked);
+        },
+        Err(err) => eprintln!("Connection {} can't be handed off: {}", connection, err)
+    }
+    thread::spawn(move || {
+        let result = match resumed {
+            Some(player) => resume_game_connection(stream, &services, connection, player),
+            None => serve_game_connection(stream, &services, connection)
+        };
+        streams.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).remove(&connection);
+        if let Err(err) = result {
+            eprintln!("Connection {} closed: {}", connection, err);
+        }
+    });
 }
 
 fn main() {
-    // bind the server to listen to an address and port
-    let receiver_listener = TcpListener::bind("127.0.0.1:7878").expect("Failed to bind to address and port");
-    // handle multiple client connections through dynamic vec
-    let mut thread_vec: Vec<thread::JoinHandle<()>> = Vec::new();
-    // continually iterate through clients attempting to connect
-    for stream in receiver_listener.incoming() {
-        let stream = stream.expect("failed");
-        // for each connection, create a thread and bind the handle function to it
-        let handle = thread::spawn(move || {
-            handle_sender(stream).unwrap_or_else(|error| eprintln!("[handle_sender thread]: {:?}", error));
-        });
-        // add the created thread to the vec of threads
-        thread_vec.push(handle);
-        break; // break to stop accepting connection requests
+    let args: Vec<String> = env::args().collect();
+    if args.get(1).map(|arg| arg.as_str()) == Some("admin") {
+        run_admin(&args[2..]);
+        return;
+    }
+
+    let config = ServerConfig::load(&PathBuf::from(CONFIG_PATH)).unwrap_or_else(|err| {
+        eprintln!("Failed to load {}, refusing to start: {}", CONFIG_PATH, err);
+        process::exit(1);
+    });
+    let storage = config.storage.open_pool().unwrap_or_else(|err| {
+        eprintln!("Failed to open storage, refusing to start: {}", err);
+        process::exit(1);
+    });
+    let bans = acquire_storage(&storage, STORAGE_ACQUIRE_TIMEOUT).and_then(|mut storage| storage.load_ban_list()).unwrap_or_else(|err| {
+        eprintln!("Failed to load the ban list, refusing to start: {}", err);
+        process::exit(1);
+    });
+    // Loaded up front so a missing or unreadable key stops the server before it hands out unsigned stats
+    let audit_key = config.load_stat_audit_key().unwrap_or_else(|err| {
+        eprintln!("Failed to load the stat audit key {}, refusing to start: {}", config.stat_audit_key.display(), err);
+        process::exit(1);
+    });
+    let bans = Arc::new(RwLock::new(bans));
+    spawn_ban_list_reloader(storage.clone(), bans.clone());
+    GlobalString::set_warning_hook(config.interned_string_warnings.clone(), Box::new(|warning| {
+        eprintln!("Interned {} GlobalStrings using about {} bytes. They are never freed, so check for leaks", warning.count, warning.memory_bytes);
+        if cfg!(debug_assertions) {
+            eprintln!("{}", GlobalString::dump_registry());
+        }
+    }));
+    let mut game_data = config.load_game_data(|progress| println!("[{}/{}] {} {:?}", progress.completed, progress.total, progress.task, progress.outcome)).unwrap_or_else(|errors| {
+        for error in errors.iter() {
+            eprintln!("{}", error);
+        }
+        eprintln!("Failed to load game data from {}, refusing to start", config.game_data_directory.display());
+        process::exit(1);
+    });
+    let packs = config.load_data_packs().unwrap_or_else(|err| {
+        eprintln!("Failed to load the data packs in {}, refusing to start: {}", config.pack_directory.display(), err);
+        process::exit(1);
+    });
+    if let Err(err) = install_packs(&packs, &mut game_data.species_map, &mut game_data.ability_map, &mut game_data.item_map, &mut game_data.maps) {
+        eprintln!("Failed to install the data packs, refusing to start: {}", err);
+        process::exit(1);
+    }
+    let manifests: Vec<PackManifest> = packs.into_iter().map(|pack| pack.manifest).collect();
+    println!("Loaded {} maps and {} encounter tables with {} data packs", game_data.maps.len(), game_data.encounter_tables.len(), manifests.len());
+    spawn_backup_scheduler(&config, storage.clone());
+    spawn_http_api(&config, storage.clone());
+    if let Err(err) = spawn_transfer_listener(&config.transfer_address, config.get_transfer_directories(), bans.clone()) {
+        eprintln!("Failed to start the file transfer channel on {}: {}", config.transfer_address, err);
+    }
+
+    let tracer = config.protocol_trace.as_ref().and_then(|path| match ProtocolTracer::open(path) {
+        Ok(tracer) => Some(Mutex::new(tracer)),
+        Err(err) => {
+            eprintln!("Failed to open the protocol trace {}, not tracing: {}", path.display(), err);
+            return None;
+        }
+    });
+    let data = GameData::new(1, game_data.species_map, game_data.ability_map, game_data.item_map).with_breeding_rules(game_data.breeding_rules);
+    let gifts = config.load_gift_distributions().and_then(|gifts| gifts.validate(&data).map(|()| gifts)).unwrap_or_else(|err| {
+        eprintln!("Failed to load the gift distributions, refusing to start: {}", err);
+        process::exit(1);
+    });
+    let data = data.into_handle();
+    let mut sessions = SessionManager::new(data).with_region_capacity(config.region_capacity);
+    let taken_over = take_over_old_process(&mut sessions, &bans.read().unwrap_or_else(|poisoned| poisoned.into_inner()));
+    let auth = AuthService::new().with_two_factor_policy(config.two_factor);
+    if !game_data.maps.contains_key(&config.start_position.map) {
+        eprintln!("The start map {} isn't loaded, players won't be able to walk until they leave it", config.start_position.map.to_string());
+    }
+    let fast_travel = build_fast_travel_network(&game_data.maps);
+    for map in game_data.maps.values() {
+        for object in map.get_objects() {
+            if let MapObject::CutsceneTrigger { cutscene, .. } = object {
+                if !game_data.cutscenes.contains_key(cutscene) {
+                    eprintln!("Map {} triggers cutscene {}, which isn't loaded", map.get_name().to_string(), cutscene.to_string());
+                }
+            }
+        }
+    }
+    let world = GameWorld::new(sessions, config.start_position)
+        .with_maps(game_data.maps)
+        .with_fast_travel(fast_travel)
+        .with_cutscenes(game_data.cutscenes)
+        .with_encounters(EncounterRoller::new(game_data.encounter_tables), get_unix_seconds())
+        .with_stat_audit(StatAuditLog::new(audit_key.clone()))
+        .with_matchmaker(Matchmaker::new().with_stat_audit(StatAuditLog::new(audit_key)))
+        .with_pack_advertisement(PackAdvertisement::new(&manifests));
+    let clock = Mutex::new(SimulationClock::new(time::Instant::now()));
+    let gift_rng = Mutex::new(GameRng::new(get_unix_seconds()));
+    let services = Arc::new(GameServices { world: Mutex::new(world), clock, auth: Mutex::new(auth), storage: storage.clone(), tracer, gifts, gift_rng });
+    spawn_world_loop(services.clone());
+    spawn_region_upkeep(services.clone());
+    if let Some(address) = &config.debug_address {
+        if let Err(err) = spawn_debug_listener(address, services.clone()) {
+            eprintln!("Failed to start the debug channel on {}: {}", address, err);
+        }
     }
-    
-    println!("no longer accepting connection requests");
+    let mut next_connection: u64 = 0;
+    let streams = Arc::new(Mutex::new(HashMap::new()));
 
-    for handle in thread_vec {
-        // join the threads
-        handle.join().unwrap();
+    // bind the server to listen to an address and port, unless it was handed over by the old process
+    let receiver_listener = match taken_over {
+        Some((listener, connections)) => {
+            for (player, stream) in connections {
+                next_connection += 1;
+                spawn_game_connection(stream, services.clone(), streams.clone(), next_connection, Some(player));
+            }
+            listener
+        },
+        None => TcpListener::bind(&config.bind_address).unwrap_or_else(|err| {
+            eprintln!("Failed to bind to {}, refusing to start: {}", config.bind_address, err);
+            process::exit(1);
+        })
+    };
+    #[cfg(unix)]
+    match receiver_listener.try_clone() {
+        Ok(listener) => {
+            spawn_handoff_watcher(services.clone(), listener, streams.clone());
+        },
+        Err(err) => eprintln!("Failed to start the handoff watcher, the server can't be handed off: {}", err)
     }
-}
\ No newline at end of file
+    // continually iterate through clients attempting to connect, each handled on its own thread
+    for stream in receiver_listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+        if let Ok(peer) = stream.peer_addr() {
+            if let Err(denial) = bans.read().unwrap_or_else(|poisoned| poisoned.into_inner()).check_connection(peer.ip(), get_unix_seconds()) {
+                eprintln!("Refused connection from {}: {:?}", peer, denial);
+                let _ = stream.shutdown(std::net::Shutdown::Both);
+                continue;
+            }
+        }
+        next_connection += 1;
+        spawn_game_connection(stream, services.clone(), streams.clone(), next_connection, None);
+    }
+}
@@ -0,0 +1,244 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::battle::battle_format::BattleFormat;
+use immie2d_shared::gameplay::game_data::GameData;
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::immie::legality_ruleset::{LegalityRuleset, RulesetPreset, RulesetViolation};
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::admin::stat_audit::{StatAuditEntry, StatAuditLog, StatTamper};
+
+/// Format of quick battles, which use rental teams instead of the players' own Immies.
+pub const QUICK_BATTLE_FORMAT: BattleFormat = BattleFormat::Single;
+
+/* Why a team wasn't allowed into a queue. */
+#[derive(Clone, PartialEq, Debug)]
+pub enum TeamRejection {
+    /// Every Immie breaking the queue's ruleset, by team index.
+    Ruleset(Vec<(usize, RulesetViolation)>),
+    /// Every Immie whose stats don't match its audit entry, by team index. See StatAuditLog::validate_team()
+    Tampered(Vec<(usize, StatTamper)>)
+}
+
+impl fmt::Display for TeamRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problems: Vec<String> = match self {
+            TeamRejection::Ruleset(violations) => violations.iter().map(|(index, violation)| format!("Immie {}: {}", index + 1, violation)).collect(),
+            TeamRejection::Tampered(tampered) => tampered.iter().map(|(index, tamper)| format!("Immie {}: {}", index + 1, tamper)).collect()
+        };
+        return write!(f, "{}", problems.join("; "));
+    }
+}
+
+/* Players waiting for a battle, with a separate first-come first-served queue for each battle format, and another
+for quick battles. Each format's queue has a ruleset its teams must follow, which is the standard preset unless
+configured otherwise. */
+pub struct Matchmaker {
+    queues: HashMap<BattleFormat, VecDeque<PlayerId>>,
+    rulesets: HashMap<BattleFormat, LegalityRuleset>,
+    default_ruleset: LegalityRuleset,
+    /// Players waiting for a quick battle, with the rental team they chose.
+    quick_queue: VecDeque<(PlayerId, GlobalString)>,
+    /// Checks the stats of queued teams if set. See Matchmaker::with_stat_audit()
+    stat_audit: Option<StatAuditLog>
+}
+
+impl Matchmaker {
+    pub fn new() -> Matchmaker {
+        return Matchmaker { queues: HashMap::new(), rulesets: HashMap::new(), default_ruleset: LegalityRuleset::from_preset(RulesetPreset::Standard), quick_queue: VecDeque::new(), stat_audit: None };
+    }
+
+    /// Give a format's queue its own ruleset, such as a preset other than standard.
+    pub fn with_ruleset(mut self, format: BattleFormat, ruleset: LegalityRuleset) -> Matchmaker {
+        self.rulesets.insert(format, ruleset);
+        return self;
+    }
+
+    /// Check that every Immie of a queued team has the stats the server last signed for it. The log should use the
+    /// server's audit key. See ServerConfig::load_stat_audit_key()
+    pub fn with_stat_audit(mut self, stat_audit: StatAuditLog) -> Matchmaker {
+        self.stat_audit = Some(stat_audit);
+        return self;
+    }
+
+    /// The ruleset teams queueing for a format must follow, which clients should check their teams with too.
+    pub fn get_ruleset(&self, format: BattleFormat) -> &LegalityRuleset {
+        return self.rulesets.get(&format).unwrap_or(&self.default_ruleset);
+    }
+
+    /// Whether the player is queued for any battle, including quick battles.
+    pub fn is_queued(&self, player: PlayerId) -> bool {
+        return self.get_queued_format(player).is_some() || self.get_quick_rental(player).is_some();
+    }
+
+    /// Add a player to the queue of a format. Returns false if the player is already queued for any format.
+    /// ```
+    /// use immie2d_server::matchmaking::matchmaker::Matchmaker;
+    /// use immie2d_shared::gameplay::{battle::battle_format::BattleFormat, player_id::PlayerId};
+    ///
+    /// let mut matchmaker = Matchmaker::new();
+    /// assert!(matchmaker.enqueue(PlayerId(1), BattleFormat::Single));
+    /// assert!(!matchmaker.enqueue(PlayerId(1), BattleFormat::free_for_all(4)));
+    /// ```
+    pub fn enqueue(&mut self, player: PlayerId, format: BattleFormat) -> bool {
+        if self.is_queued(player) {
+            return false;
+        }
+        self.queues.entry(format).or_default().push_back(player);
+        return true;
+    }
+
+    /// Add a player to the queue of a format after checking their team against the queue's ruleset, with the same
+    /// check the client shows errors with. If the matchmaker audits stats, the team's persisted audit entries are
+    /// checked too, in team order. Returns every problem with the team if it's rejected, otherwise the same as enqueue().
+    /// ```
+    /// use immie2d_server::admin::stat_audit::{StatAuditLog, StatChange, StatTamper};
+    /// use immie2d_server::matchmaking::matchmaker::{Matchmaker, TeamRejection};
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::{battle::battle_format::BattleFormat, player_id::PlayerId};
+    /// use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames, abilities::fireball::Fireball};
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// use immie2d_shared::gameplay::item::item_map::ItemMap;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::immie::{immie::Immie, legality::LegalityError};
+    /// use immie2d_shared::gameplay::immie::legality_ruleset::{LegalityRuleset, RulesetPreset, RulesetViolation};
+    ///
+    /// let name = |name: &str| GlobalString::new(&name.to_string());
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(SpeciesData::new(name("tidepup"), Elements::new(vec![ElementKind::Water]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Fireball>();
+    /// let data = GameData::new(1, species_map, ability_map, ItemMap::new());
+    ///
+    /// let little_cup = BattleFormat::free_for_all(4);
+    /// let mut matchmaker = Matchmaker::new().with_ruleset(little_cup, LegalityRuleset::from_preset(RulesetPreset::LittleCup));
+    /// let team = vec![Immie::new(name("tidepup"), 5, AbilityNames::new(vec![name("fireball")]))];
+    /// let result = matchmaker.enqueue_team(PlayerId(1), BattleFormat::Single, &team, &[], &data);
+    /// assert_eq!(result, Err(TeamRejection::Ruleset(vec![(0, RulesetViolation::Illegal(LegalityError::ElementMismatch(name("fireball"))))])));
+    /// assert!(!matchmaker.is_queued(PlayerId(1)));
+    ///
+    /// // Standard tier species aren't allowed in Little Cup
+    /// let team = vec![Immie::new(name("tidepup"), 5, AbilityNames::default())];
+    /// assert!(matches!(matchmaker.enqueue_team(PlayerId(1), little_cup, &team, &[], &data), Err(TeamRejection::Ruleset(violations)) if matches!(violations[..], [(0, RulesetViolation::TierNotAllowed { .. })])));
+    /// assert_eq!(matchmaker.enqueue_team(PlayerId(1), BattleFormat::Single, &team, &[], &data), Ok(true));
+    ///
+    /// // With auditing on, teams need a valid entry for every Immie
+    /// let mut audit = StatAuditLog::new(b"audit key".to_vec());
+    /// let entry = audit.record(PlayerId(2), &team[0], StatChange::LevelUp, data.get_species_map(), 10).unwrap();
+    /// let mut matchmaker = Matchmaker::new().with_stat_audit(audit);
+    /// assert_eq!(matchmaker.enqueue_team(PlayerId(2), BattleFormat::Single, &team, &[], &data), Err(TeamRejection::Tampered(vec![(0, StatTamper::Missing)])));
+    /// assert_eq!(matchmaker.enqueue_team(PlayerId(2), BattleFormat::Single, &team, &[Some(entry)], &data), Ok(true));
+    /// ```
+    pub fn enqueue_team(&mut self, player: PlayerId, format: BattleFormat, team: &[Immie], audit_entries: &[Option<StatAuditEntry>], data: &GameData) -> Result<bool, TeamRejection> {
+        let violations = self.get_ruleset(format).check_team(team, data);
+        if !violations.is_empty() {
+            return Err(TeamRejection::Ruleset(violations));
+        }
+        if let Some(stat_audit) = &self.stat_audit {
+            let tampered = stat_audit.validate_team(team, audit_entries, data.get_species_map());
+            if !tampered.is_empty() {
+                return Err(TeamRejection::Tampered(tampered));
+            }
+        }
+        return Ok(self.enqueue(player, format));
+    }
+
+    /// Add a player to the quick battle queue with the rental team they chose. The team should be checked against
+    /// the rental catalog first. Returns false if the player is already queued for any battle.
+    /// ```
+    /// use immie2d_server::matchmaking::matchmaker::Matchmaker;
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::{battle::battle_format::BattleFormat, player_id::PlayerId};
+    ///
+    /// let (blaze, tide) = (GlobalString::new(&"blaze".to_string()), GlobalString::new(&"tide".to_string()));
+    /// let mut matchmaker = Matchmaker::new();
+    /// assert!(matchmaker.enqueue_quick(PlayerId(1), blaze));
+    /// assert!(!matchmaker.enqueue(PlayerId(1), BattleFormat::Single));
+    /// assert!(matchmaker.try_form_quick_match().is_none());
+    /// assert!(matchmaker.enqueue_quick(PlayerId(2), tide));
+    /// assert_eq!(matchmaker.try_form_quick_match().unwrap(), vec![(PlayerId(1), blaze), (PlayerId(2), tide)]);
+    /// assert!(!matchmaker.is_queued(PlayerId(1)));
+    /// ```
+    pub fn enqueue_quick(&mut self, player: PlayerId, rental_team: GlobalString) -> bool {
+        if self.is_queued(player) {
+            return false;
+        }
+        self.quick_queue.push_back((player, rental_team));
+        return true;
+    }
+
+    /// The rental team of a player in the quick battle queue.
+    pub fn get_quick_rental(&self, player: PlayerId) -> Option<GlobalString> {
+        return self.quick_queue.iter().find(|(queued, _)| *queued == player).map(|(_, team)| *team);
+    }
+
+    /// Take enough players from the front of the quick battle queue to fill a battle, if there are enough waiting.
+    /// The players are returned in side order with their rental teams.
+    pub fn try_form_quick_match(&mut self) -> Option<Vec<(PlayerId, GlobalString)>> {
+        let required = QUICK_BATTLE_FORMAT.get_participant_count() as usize;
+        if self.quick_queue.len() < required {
+            return None;
+        }
+        return Some(self.quick_queue.drain(..required).collect());
+    }
+
+    /// Remove a player from whichever queue they are in. Returns false if they were not queued.
+    pub fn dequeue(&mut self, player: PlayerId) -> bool {
+        if let Some(position) = self.quick_queue.iter().position(|(queued, _)| *queued == player) {
+            self.quick_queue.remove(position);
+            return true;
+        }
+        for queue in self.queues.values_mut() {
+            if let Some(position) = queue.iter().position(|queued| *queued == player) {
+                queue.remove(position);
+                return true;
+            }
+        }
+        return false;
+    }
+
+    pub fn get_queued_format(&self, player: PlayerId) -> Option<BattleFormat> {
+        for (format, queue) in self.queues.iter() {
+            if queue.contains(&player) {
+                return Some(*format);
+            }
+        }
+        return None;
+    }
+
+    pub fn get_queue_length(&self, format: BattleFormat) -> usize {
+        return match self.queues.get(&format) {
+            Some(queue) => queue.len(),
+            None => 0
+        };
+    }
+
+    /// Take enough players from the front of a format's queue to fill a battle, if there are enough waiting.
+    /// The players are returned in side order.
+    /// ```
+    /// use immie2d_server::matchmaking::matchmaker::Matchmaker;
+    /// use immie2d_shared::gameplay::{battle::battle_format::BattleFormat, player_id::PlayerId};
+    ///
+    /// let mut matchmaker = Matchmaker::new();
+    /// let format = BattleFormat::free_for_all(3);
+    /// matchmaker.enqueue(PlayerId(1), format);
+    /// matchmaker.enqueue(PlayerId(2), format);
+    /// matchmaker.enqueue(PlayerId(3), BattleFormat::Single);
+    /// assert!(matchmaker.try_form_match(format).is_none());
+    ///
+    /// matchmaker.enqueue(PlayerId(4), format);
+    /// assert_eq!(matchmaker.try_form_match(format).unwrap(), vec![PlayerId(1), PlayerId(2), PlayerId(4)]);
+    /// assert_eq!(matchmaker.get_queue_length(format), 0);
+    /// ```
+    pub fn try_form_match(&mut self, format: BattleFormat) -> Option<Vec<PlayerId>> {
+        let required = format.get_participant_count() as usize;
+        let queue = self.queues.get_mut(&format)?;
+        if queue.len() < required {
+            return None;
+        }
+        return Some(queue.drain(..required).collect());
+    }
+}
@@ -0,0 +1,16 @@
+/// Most rating a single battle can move a player by.
+pub const RATING_K_FACTOR: f64 = 32.0;
+
+/// Elo rating the winner of a battle gains, and the loser loses. Beating a higher rated player is worth more, but
+/// every win is worth at least 1.
+/// ```
+/// use immie2d_server::matchmaking::rating::get_rating_change;
+///
+/// assert_eq!(get_rating_change(1000, 1000), 16);
+/// assert!(get_rating_change(1000, 1400) > 16);
+/// assert_eq!(get_rating_change(3000, 100), 1);
+/// ```
+pub fn get_rating_change(winner: u32, loser: u32) -> u32 {
+    let expected = 1.0 / (1.0 + 10f64.powf((loser as f64 - winner as f64) / 400.0));
+    return ((RATING_K_FACTOR * (1.0 - expected)).round() as u32).max(1);
+}
@@ -0,0 +1,156 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use immie2d_shared::engine_types::file_transfer::{hash_reader, is_valid_transfer_name, write_chunk, TransferError, TransferHeader, TransferKind, TransferRequest, TRANSFER_CHUNK_SIZE};
+
+/// How long a transfer connection may go without reading or writing before it is dropped.
+pub const TRANSFER_IO_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How many transfer connections are served at once. Connections past this are closed straight away.
+pub const MAX_TRANSFER_CONNECTIONS: usize = 64;
+
+/* The digest of a served file, kept until the file's size or modification time changes. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct CachedDigest {
+    modified: SystemTime,
+    size: u64,
+    hash: [u8; 32]
+}
+
+/* The directory each kind of transfer is served from. Kinds without a directory are never found. Clones share one
+cache of file digests, so a file is only hashed again once it changes. */
+#[derive(Clone, Debug)]
+pub struct TransferDirectories {
+    directories: HashMap<TransferKind, PathBuf>,
+    digests: Arc<Mutex<HashMap<PathBuf, CachedDigest>>>
+}
+
+impl TransferDirectories {
+    pub fn new() -> TransferDirectories {
+        return TransferDirectories { directories: HashMap::new(), digests: Arc::new(Mutex::new(HashMap::new())) };
+    }
+
+    pub fn with_directory(mut self, kind: TransferKind, directory: PathBuf) -> TransferDirectories {
+        self.directories.insert(kind, directory);
+        return self;
+    }
+
+    /// The path of a requested file. Returns TransferError::Invalid for names that would escape the directory.
+    pub fn resolve(&self, kind: TransferKind, name: &str) -> Result<PathBuf, TransferError> {
+        if !is_valid_transfer_name(name) {
+            return Err(TransferError::Invalid(format!("Invalid file name [{}]", name)));
+        }
+        let directory = self.directories.get(&kind).ok_or(TransferError::NotFound)?;
+        return Ok(name.split('/').fold(directory.clone(), |path, part| path.join(part)));
+    }
+
+    /// The SHA-256 digest of an open file, hashed only if it isn't cached or the file has changed since.
+    /// ```
+    /// use std::fs::{self, File};
+    /// use immie2d_shared::engine_types::file_transfer::{hash_reader, TransferKind};
+    /// use immie2d_server::network::file_transfer::TransferDirectories;
+    ///
+    /// let root = std::env::temp_dir().join(format!("immie2d_digest_doctest_{}", std::process::id()));
+    /// fs::create_dir_all(&root).unwrap();
+    /// let path = root.join("route_1.map");
+    /// fs::write(&path, b"first").unwrap();
+    /// let directories = TransferDirectories::new().with_directory(TransferKind::Map, root.clone());
+    /// let first = directories.get_digest(&path, &mut File::open(&path).unwrap()).unwrap();
+    /// assert_eq!(first, hash_reader(&mut &b"first"[..]).unwrap());
+    /// assert_eq!(directories.clone().get_digest(&path, &mut File::open(&path).unwrap()).unwrap(), first);
+    ///
+    /// fs::write(&path, b"second version").unwrap();
+    /// assert_eq!(directories.get_digest(&path, &mut File::open(&path).unwrap()).unwrap(), hash_reader(&mut &b"second version"[..]).unwrap());
+    /// fs::remove_dir_all(&root).unwrap();
+    /// ```
+    pub fn get_digest(&self, path: &Path, file: &mut File) -> Result<[u8; 32], TransferError> {
+        let metadata = file.metadata()?;
+        let (modified, size) = (metadata.modified()?, metadata.len());
+        let cached = self.digests.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).get(path).cloned();
+        if let Some(cached) = cached {
+            if cached.modified == modified && cached.size == size {
+                return Ok(cached.hash);
+            }
+        }
+        let hash = hash_reader(file)?;
+        self.digests.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).insert(path.to_path_buf(), CachedDigest { modified, size, hash });
+        return Ok(hash);
+    }
+}
+
+/// Answer one request on a transfer connection: read the request, then send the header and the file in chunks from
+/// where the client left off. The gameplay connection never carries files, so large downloads can't delay it.
+/// Returns the number of bytes of the file sent. See download_file()
+/// ```
+/// use std::net::{TcpListener, TcpStream};
+/// use std::{fs, thread};
+/// use immie2d_shared::engine_types::file_transfer::{hash_reader, TransferKind};
+/// use immie2d_shared::engine_types::file_download::{download_file, get_partial_path};
+/// use immie2d_server::network::file_transfer::{serve_transfer, TransferDirectories};
+///
+/// let root = std::env::temp_dir().join(format!("immie2d_download_doctest_{}", std::process::id()));
+/// let (served, downloads) = (root.join("replays"), root.join("downloads"));
+/// fs::create_dir_all(&served).unwrap();
+/// fs::create_dir_all(&downloads).unwrap();
+/// let replay: Vec<u8> = (0..200_000u32).map(|i| (i % 251) as u8).collect();
+/// fs::write(served.join("battle_1.replay"), &replay).unwrap();
+/// let directories = TransferDirectories::new().with_directory(TransferKind::Replay, served);
+///
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let address = listener.local_addr().unwrap();
+/// let server = thread::spawn(move || {
+///     for _ in 0..2 {
+///         let (mut stream, _) = listener.accept().unwrap();
+///         serve_transfer(&mut stream, &directories).unwrap();
+///     }
+/// });
+///
+/// // A previous attempt was interrupted after 100,000 bytes
+/// let destination = downloads.join("battle_1.replay");
+/// fs::write(get_partial_path(&destination), &replay[..100_000]).unwrap();
+/// fs::write(downloads.join("battle_1.replay.part.sha256"), hash_reader(&mut replay.as_slice()).unwrap()).unwrap();
+/// let mut stream = TcpStream::connect(address).unwrap();
+/// assert_eq!(download_file(&mut stream, TransferKind::Replay, "battle_1.replay", &destination), Ok(100_000));
+/// assert_eq!(fs::read(&destination).unwrap(), replay);
+/// assert!(!get_partial_path(&destination).exists());
+///
+/// // Nothing to resume from, so the whole file is sent
+/// fs::remove_file(&destination).unwrap();
+/// let mut stream = TcpStream::connect(address).unwrap();
+/// assert_eq!(download_file(&mut stream, TransferKind::Replay, "battle_1.replay", &destination), Ok(200_000));
+/// server.join().unwrap();
+/// fs::remove_dir_all(&root).unwrap();
+/// ```
+pub fn serve_transfer<S: Read + Write>(stream: &mut S, directories: &TransferDirectories) -> Result<u64, TransferError> {
+    let request = TransferRequest::read_from(stream)?;
+    let path = directories.resolve(request.kind, &request.name)?;
+    let mut file = match File::open(&path) {
+        Ok(file) if file.metadata()?.is_file() => file,
+        _ => {
+            TransferHeader::write_to(None, stream)?;
+            return Err(TransferError::NotFound);
+        }
+    };
+    let total_size = file.metadata()?.len();
+    let hash = directories.get_digest(&path, &mut file)?;
+    let can_resume = request.offset <= total_size && request.resume_hash == hash;
+    let offset = if can_resume { request.offset } else { 0 };
+    TransferHeader::write_to(Some(&TransferHeader { total_size, hash, offset }), stream)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buffer = vec![0; TRANSFER_CHUNK_SIZE];
+    let mut sent = 0;
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        write_chunk(&buffer[..read], stream)?;
+        sent += read as u64;
+    }
+    stream.flush()?;
+    return Ok(sent);
+}
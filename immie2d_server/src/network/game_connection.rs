@@ -0,0 +1,393 @@
+use std::io::{self, BufRead, BufReader, ErrorKind, Read, Write};
+use std::net::TcpStream;
+use std::sync::{mpsc, Arc, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use immie2d_shared::engine_types::game_rng::GameRng;
+use immie2d_shared::engine_types::game_protocol::{encode_message_line, ClientRequest, MessageKind};
+use immie2d_shared::gameplay::immie::immie_release::ImmieLocation;
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::gameplay::synced_settings::SyncedSettings;
+use immie2d_shared::world::tile_position::{Direction, TilePosition};
+
+use crate::auth::auth_message::{AuthError, AuthRequest, AuthResponse};
+use crate::auth::auth_service::AuthService;
+use crate::config::server_config::{acquire_storage, StoragePool};
+use crate::distribution::gift_distribution::{GiftClaimError, GiftDistributor};
+use crate::storage::player_profile::PlayerProfile;
+use crate::world::game_world::{GameWorld, JoinError};
+use crate::world::simulation_clock::{SimulationClock, SIMULATION_STATUS_COALESCE_KEY};
+
+use super::panic_boundary::{catch_task_panic, create_internal_error_message};
+use super::protocol_trace::{ProtocolTracer, TraceDirection};
+use super::send_queue::{MessagePriority, OutboundMessage, SendQueue};
+
+/// Longest line a client may send. A longer line closes the connection, so a client can't make the server buffer
+/// without end.
+pub const MAX_REQUEST_LINE_BYTES: usize = 4096;
+/// Longest a write to a client may stall before the connection is dropped, so a client that stops reading can't
+/// hold its writer forever.
+pub const WRITE_TIMEOUT: Duration = Duration::from_secs(30);
+/// Longest a request waits for a free storage connection before it is refused.
+pub const STORAGE_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Keywords of lines carrying passwords or two factor secrets, which are traced without their arguments.
+const SECRET_KEYWORDS: [&str; 5] = ["create_account", "login", "begin_two_factor", "confirm_two_factor", "two_factor_setup"];
+
+/// Frame a message as a line of the game protocol. See encode_message_line()
+pub fn frame_message(kind: MessageKind, message: OutboundMessage) -> OutboundMessage {
+    return OutboundMessage { payload: encode_message_line(kind, &message.payload), ..message };
+}
+
+fn get_unix_seconds() -> u64 {
+    return SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_secs()).unwrap_or(0);
+}
+
+/* What every game connection of a server shares. Logins hash passwords, so the auth service has its own lock rather
+than holding up the world. Whoever locks both the clock and the world locks the clock first. */
+pub struct GameServices {
+    pub world: Mutex<GameWorld>,
+    pub clock: Mutex<SimulationClock>,
+    pub auth: Mutex<AuthService>,
+    pub storage: Arc<StoragePool>,
+    /// Debug mode logging every line sent and received. See ProtocolTracer
+    pub tracer: Option<Mutex<ProtocolTracer>>,
+    /// Gifts handed out at login and for redeemed codes, validated against the game data.
+    pub gifts: GiftDistributor,
+    /// Rolls the individual values of gifted Immies. Locked last, after the world.
+    pub gift_rng: Mutex<GameRng>
+}
+
+impl GameServices {
+    pub fn lock_world(&self) -> MutexGuard<'_, GameWorld> {
+        return self.world.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    }
+
+    pub fn lock_clock(&self) -> MutexGuard<'_, SimulationClock> {
+        return self.clock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    }
+
+    fn lock_gift_rng(&self) -> MutexGuard<'_, GameRng> {
+        return self.gift_rng.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    }
+
+    fn lock_auth(&self) -> MutexGuard<'_, AuthService> {
+        return self.auth.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    }
+
+    /// Record a line in the protocol trace, if tracing is on. Lines with secrets only have their keyword traced. A
+    /// trace that can't be written is reported but never drops the connection.
+    fn trace(&self, connection: u64, direction: TraceDirection, line: &[u8]) {
+        let Some(tracer) = &self.tracer else {
+            return;
+        };
+        let mut text = String::from_utf8_lossy(line).trim_end().to_string();
+        let keyword = text.split(' ').next().unwrap_or("").to_string();
+        if SECRET_KEYWORDS.contains(&keyword.as_str()) {
+            text = keyword.clone();
+        }
+        let mut tracer = tracer.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(err) = tracer.record_frame(connection, direction, &keyword, text.as_bytes(), text.clone()).and_then(|_| tracer.flush()) {
+            eprintln!("Failed to write the protocol trace: {}", err);
+        }
+    }
+}
+
+/// Serve a client's game connection until it disconnects. Requests are read and handled on this thread, while a
+/// writer thread sends what the world queues for the connection. A panic while handling a request only tears down
+/// this connection, telling the client why. The player's profile is saved once they leave.
+pub fn serve_game_connection(stream: TcpStream, services: &Arc<GameServices>, connection: u64) -> io::Result<()> {
+    return serve(stream, services, connection, None);
+}
+
+/// Serve a connection taken over from another server process by a warm handoff, whose player had already logged in
+/// there. The player joins with their saved profile without logging in again. See warm_handoff::take_over()
+pub fn resume_game_connection(stream: TcpStream, services: &Arc<GameServices>, connection: u64, player: PlayerId) -> io::Result<()> {
+    return serve(stream, services, connection, Some(player));
+}
+
+fn serve(stream: TcpStream, services: &Arc<GameServices>, connection: u64, resumed: Option<PlayerId>) -> io::Result<()> {
+    let (outbox, outgoing) = mpsc::channel();
+    let writer_stream = stream.try_clone()?;
+    writer_stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    let writer_services = services.clone();
+    let writer = thread::spawn(move || write_messages(writer_stream, outgoing, &writer_services, connection));
+    services.lock_world().connect(connection, outbox.clone());
+
+    let context = format!("connection {:?}", stream.peer_addr());
+    let result = catch_task_panic(&context, || {
+        if let Some(player) = resumed {
+            resume_player(services, connection, player)?;
+        }
+        return read_requests(&stream, services, connection);
+    });
+    if result.is_err() {
+        let _ = outbox.send(create_internal_error_message());
+    }
+    drop(outbox);
+    let profile = services.lock_world().disconnect(connection, get_unix_seconds());
+    // The writer stops once the world has dropped the connection's outbox and every queued message is sent
+    let _ = writer.join();
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+    if let Some(profile) = profile {
+        let player = profile.player;
+        save_profile(services, profile);
+        services.lock_world().finish_saving(player);
+    }
+    return result.unwrap_or(Ok(()));
+}
+
+/// Join a player taken over from another process with the profile it saved before handing off.
+fn resume_player(services: &GameServices, connection: u64, player: PlayerId) -> io::Result<()> {
+    // Reserved before loading, like a login, so no other connection can join as the player in the meantime
+    services.lock_world().reserve(player).map_err(|err| io::Error::other(format!("Player {} could not resume: {:?}", player, err)))?;
+    let loaded = acquire_storage(&services.storage, STORAGE_REQUEST_TIMEOUT).and_then(|mut storage| storage.load_profile(player));
+    let profile = match loaded {
+        Ok(Some(profile)) => profile,
+        Ok(None) => {
+            services.lock_world().cancel_reservation(player);
+            return Err(io::Error::new(ErrorKind::NotFound, format!("No profile for player {}", player)));
+        },
+        Err(err) => {
+            services.lock_world().cancel_reservation(player);
+            return Err(err);
+        }
+    };
+    return services.lock_world().join(connection, profile, get_unix_seconds()).map_err(|err| io::Error::other(format!("Player {} could not resume: {:?}", player, err)));
+}
+
+fn save_profile(services: &GameServices, profile: PlayerProfile) {
+    let result = acquire_storage(&services.storage, STORAGE_REQUEST_TIMEOUT).and_then(|mut storage| storage.save_profiles(std::slice::from_ref(&profile)));
+    if let Err(err) = result {
+        eprintln!("Failed to save the profile of player {} after they left: {}", profile.player, err);
+    }
+}
+
+/// Send queued messages as they arrive, most urgent first when several are waiting.
+fn write_messages(mut stream: TcpStream, outgoing: mpsc::Receiver<OutboundMessage>, services: &GameServices, connection: u64) {
+    let mut queue = SendQueue::new();
+    while let Ok(message) = outgoing.recv() {
+        queue.push(message);
+        while let Ok(message) = outgoing.try_recv() {
+            queue.push(message);
+        }
+        while let Some(message) = queue.pop() {
+            services.trace(connection, TraceDirection::Outbound, &message.payload);
+            if stream.write_all(&message.payload).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+fn read_requests(stream: &TcpStream, services: &GameServices, connection: u64) -> io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        let read = (&mut reader).take(MAX_REQUEST_LINE_BYTES as u64 + 1).read_until(b'\n', &mut line)?;
+        if read == 0 {
+            return Ok(());
+        }
+        if line.len() > MAX_REQUEST_LINE_BYTES {
+            return Err(io::Error::new(ErrorKind::InvalidData, format!("Request line longer than {} bytes", MAX_REQUEST_LINE_BYTES)));
+        }
+        services.trace(connection, TraceDirection::Inbound, &line);
+        let request = std::str::from_utf8(&line).map_err(|_| "Requests must be UTF-8 text".to_string()).and_then(ClientRequest::parse);
+        match request {
+            Ok(request) => handle_request(services, connection, request, get_unix_seconds()),
+            Err(err) => services.lock_world().send_error(connection, &err)
+        }
+    }
+}
+
+fn handle_request(services: &GameServices, connection: u64, request: ClientRequest, unix_seconds: u64) {
+    let auth_request = match request {
+        ClientRequest::SyncSettings(settings) => return sync_settings(services, connection, settings),
+        ClientRequest::Walk { from, direction } => return walk(services, connection, from, direction),
+        ClientRequest::FastTravel { point } => return fast_travel(services, connection, &point, unix_seconds),
+        ClientRequest::CloseDialogue => return close_dialogue(services, connection),
+        ClientRequest::RedeemCode { code } => return redeem_code(services, connection, &code, unix_seconds),
+        ClientRequest::QueueBattle => return queue_battle(services, connection),
+        ClientRequest::OfferTrade { partner, location } => return offer_trade(services, connection, &partner, location),
+        ClientRequest::CreateAccount { username, email, password } => AuthRequest::CreateAccount { username, password, email },
+        ClientRequest::Login { username, code: None, password } => AuthRequest::Login { username, password },
+        ClientRequest::Login { username, code: Some(code), password } => AuthRequest::LoginWithCode { username, password, code },
+        ClientRequest::BeginTwoFactor { username, password } => AuthRequest::BeginTwoFactor { username, password },
+        ClientRequest::ConfirmTwoFactor { username, code, password } => AuthRequest::ConfirmTwoFactor { username, password, code }
+    };
+    handle_auth(services, connection, auth_request, unix_seconds);
+}
+
+/// Merge a player's local settings with their profile's and send back the settings to use. The profile is saved with
+/// any uploaded settings when they leave.
+fn sync_settings(services: &GameServices, connection: u64, settings: SyncedSettings) {
+    let mut world = services.lock_world();
+    let Some(player) = world.get_player_of(connection).and_then(|player| world.get_player_mut(player)) else {
+        return world.send_error(connection, "Log in before syncing settings");
+    };
+    let merge = player.profile.sync_settings(settings);
+    world.send(connection, MessageKind::SyncedSettings, OutboundMessage::new(MessagePriority::Chat, merge.settings.to_bytes()));
+}
+
+/// Queue a step of a logged in player for the next tick, which sends them its result.
+fn walk(services: &GameServices, connection: u64, from: TilePosition, direction: Direction) {
+    let mut world = services.lock_world();
+    let Some(player) = world.get_player_of(connection) else {
+        return world.send_error(connection, "Log in before walking");
+    };
+    world.request_move(player, from, direction);
+}
+
+/// Fast travel a logged in player to a point they unlocked.
+fn fast_travel(services: &GameServices, connection: u64, point: &str, unix_seconds: u64) {
+    let mut world = services.lock_world();
+    let Some(player) = world.get_player_of(connection) else {
+        return world.send_error(connection, "Log in before fast travelling");
+    };
+    world.fast_travel(player, point, unix_seconds);
+}
+
+fn queue_battle(services: &GameServices, connection: u64) {
+    let mut world = services.lock_world();
+    let Some(player) = world.get_player_of(connection) else {
+        return world.send_error(connection, "Log in before queueing for a battle");
+    };
+    world.queue_battle(player);
+}
+
+fn offer_trade(services: &GameServices, connection: u64, partner: &str, location: ImmieLocation) {
+    let mut world = services.lock_world();
+    let Some(player) = world.get_player_of(connection) else {
+        return world.send_error(connection, "Log in before trading");
+    };
+    world.offer_trade(player, partner, location);
+}
+
+fn close_dialogue(services: &GameServices, connection: u64) {
+    let mut world = services.lock_world();
+    if let Some(player) = world.get_player_of(connection) {
+        world.close_dialogue(player);
+    }
+}
+
+/// Redeem a gift code for a logged in player. The claim is recorded in storage outside the world's lock, and the gift
+/// is given once it is.
+fn redeem_code(services: &GameServices, connection: u64, code: &str, unix_seconds: u64) {
+    let (player, claimed_gifts) = {
+        let world = services.lock_world();
+        let Some(player) = world.get_player_of(connection).and_then(|player| world.get_player(player)) else {
+            return world.send_error(connection, "Log in before redeeming a code");
+        };
+        (player.profile.player, player.profile.claimed_gifts.clone())
+    };
+    let distribution = match services.gifts.find_redeemable(code, &claimed_gifts, unix_seconds) {
+        Ok(distribution) => distribution,
+        Err(err) => return services.lock_world().send_error(connection, &err.to_string())
+    };
+    let recorded = acquire_storage(&services.storage, STORAGE_REQUEST_TIMEOUT).and_then(|mut storage| distribution.record_claim(storage.as_mut(), player));
+    let mut world = services.lock_world();
+    let Some(online) = world.get_player_mut(player) else {
+        if let Ok(true) = recorded {
+            eprintln!("Player {} left before receiving gift {}, which is recorded as claimed", player, distribution.name.to_string());
+        }
+        return;
+    };
+    let err = match recorded {
+        Ok(true) => {
+            let boxed_count = online.profile.boxed.len();
+            distribution.give(&mut online.profile, &mut services.lock_gift_rng());
+            if online.profile.boxed.len() > boxed_count {
+                world.record_obtained(player, ImmieLocation::Box(boxed_count), unix_seconds);
+            }
+            let name = distribution.name.to_string().into_bytes();
+            return world.send(connection, MessageKind::GiftReceived, OutboundMessage::new(MessagePriority::Chat, name));
+        },
+        Ok(false) => {
+            distribution.mark_claimed(&mut online.profile);
+            GiftClaimError::AlreadyClaimed
+        },
+        Err(err) => {
+            eprintln!("Connection {} failed to record a gift claim: {}", connection, err);
+            GiftClaimError::from(err)
+        }
+    };
+    world.send_error(connection, &err.to_string());
+}
+
+/// Handle an auth request against storage, outside the world's lock. A login brings the player into the world with
+/// their saved profile and any login gifts, telling them where they are, what they were given and if the simulation
+/// is paused.
+fn handle_auth(services: &GameServices, connection: u64, request: AuthRequest, unix_seconds: u64) {
+    let is_login = matches!(request, AuthRequest::Login { .. } | AuthRequest::LoginWithCode { .. });
+    if is_login && services.lock_world().get_player_of(connection).is_some() {
+        return services.lock_world().send_error(connection, "Already logged in");
+    }
+    let result = acquire_storage(&services.storage, STORAGE_REQUEST_TIMEOUT).map_err(AuthError::from).and_then(|mut storage| {
+        let response = services.lock_auth().handle(storage.as_mut(), request, unix_seconds)?;
+        let AuthResponse::LoggedIn(player) = response else {
+            return Ok((response, None));
+        };
+        // Reserved before loading, so the profile can't be saved by the player's last connection after it is read
+        if let Err(err) = services.lock_world().reserve(player) {
+            return Ok((response, Some(Err(err))));
+        }
+        let profile = storage.load_profile(player).map_err(AuthError::from).and_then(|profile| profile.ok_or(AuthError::Storage(format!("No profile for player {}", player))));
+        let mut profile = match profile {
+            Ok(profile) => profile,
+            Err(err) => {
+                services.lock_world().cancel_reservation(player);
+                return Err(err);
+            }
+        };
+        let boxed_count = profile.boxed.len();
+        let gifts = services.gifts.claim_login_gifts(storage.as_mut(), &mut profile, unix_seconds, &mut services.lock_gift_rng()).unwrap_or_else(|err| {
+            eprintln!("Failed to give the login gifts of player {}, trying again next login: {}", player, err);
+            return Vec::new();
+        });
+        let gifted: Vec<ImmieLocation> = (boxed_count..profile.boxed.len()).map(ImmieLocation::Box).collect();
+        return Ok((response, Some(Ok((profile, gifts, gifted)))));
+    });
+    let status = services.lock_clock().get_status();
+    let mut world = services.lock_world();
+    let (mut gifts, mut gifted) = (Vec::new(), Vec::new());
+    let (kind, payload) = match result {
+        Ok((AuthResponse::AccountCreated(player), _)) => (MessageKind::AccountCreated, player.0.to_le_bytes().to_vec()),
+        Ok((AuthResponse::LoggedIn(player), Some(joining))) => match joining.and_then(|(profile, given, locations)| world.join(connection, profile, unix_seconds).map(|()| (given, locations))) {
+            Ok((given, locations)) => {
+                (gifts, gifted) = (given, locations);
+                (MessageKind::LoggedIn, player.0.to_le_bytes().to_vec())
+            },
+            Err(JoinError::AlreadyOnline) => return world.send_error(connection, "This account is already playing on another connection"),
+            Err(JoinError::StillSaving) => return world.send_error(connection, "The last session of this account is still being saved, try again in a moment"),
+            Err(_) => return world.send_error(connection, "Already logged in")
+        },
+        Ok((AuthResponse::TwoFactorSetup(setup), _)) => {
+            let lines: Vec<&str> = [setup.secret.as_str(), setup.uri.as_str()].into_iter().chain(setup.recovery_codes.iter().map(|code| code.as_str())).collect();
+            (MessageKind::TwoFactorSetup, lines.join("\n").into_bytes())
+        },
+        Ok((AuthResponse::TwoFactorEnabled, _)) => (MessageKind::TwoFactorEnabled, Vec::new()),
+        Ok((response, _)) => return eprintln!("Connection {} got auth response {:?}, which no request it can send is answered with", connection, response),
+        Err(AuthError::Storage(message)) => {
+            eprintln!("Auth request of connection {} failed in storage: {}", connection, message);
+            return world.send_error(connection, "The server couldn't handle the request, try again later");
+        },
+        Err(err) => return world.send_error(connection, &err.to_string())
+    };
+    world.send(connection, kind, OutboundMessage::new(MessagePriority::Chat, payload));
+    if kind != MessageKind::LoggedIn {
+        return;
+    }
+    if let Some(player) = world.get_player_of(connection) {
+        for location in gifted {
+            world.record_obtained(player, location, unix_seconds);
+        }
+        world.send_position(player);
+    }
+    for gift in gifts {
+        world.send(connection, MessageKind::GiftReceived, OutboundMessage::new(MessagePriority::Chat, gift.to_string().into_bytes()));
+    }
+    if status.is_paused {
+        world.send(connection, MessageKind::SimulationStatus, OutboundMessage::snapshot(SIMULATION_STATUS_COALESCE_KEY, status.to_bytes()));
+    }
+}
@@ -0,0 +1,6 @@
+pub mod send_queue;
+pub mod time_sync;
+pub mod protocol_trace;
+pub mod panic_boundary;
+pub mod file_transfer;
+pub mod game_connection;
@@ -0,0 +1,95 @@
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Once;
+
+use super::send_queue::{MessagePriority, OutboundMessage};
+
+/// Sent to clients whose connection or battle was torn down by a panic.
+pub const INTERNAL_ERROR_NOTICE: &[u8] = b"internal_error\n";
+
+static INSTALL_HOOK: Once = Once::new();
+
+thread_local! {
+    /// How many catch_task_panic() calls the thread is inside of.
+    static BOUNDARY_DEPTH: Cell<u32> = const { Cell::new(0) };
+    /// Where the last panic caught at a boundary on this thread was raised.
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/* A panic caught at the boundary of a connection or battle task, instead of taking down the whole server. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TaskPanic {
+    /// What the task was doing, such as `battle session 4`.
+    pub context: String,
+    pub message: String,
+    /// The file, line and column the panic was raised at, if known.
+    pub location: Option<String>
+}
+
+impl fmt::Display for TaskPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match &self.location {
+            Some(location) => write!(f, "Panic in {} at {}: {}", self.context, location, self.message),
+            None => write!(f, "Panic in {}: {}", self.context, self.message)
+        };
+    }
+}
+
+/// Wrap the panic hook so panics inside a boundary only record where they were raised, instead of also being printed
+/// by the default hook. catch_task_panic() logs them once with their context. Panics anywhere else still reach the
+/// previous hook.
+fn install_boundary_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let previous = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if BOUNDARY_DEPTH.with(|depth| depth.get()) == 0 {
+                return previous(info);
+            }
+            let location = info.location().map(|location| location.to_string());
+            PANIC_LOCATION.with(|last| *last.borrow_mut() = location);
+        }));
+    });
+}
+
+/// Get the message a panic was raised with, if it was raised with a string.
+fn get_panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        return message.to_string();
+    }
+    if let Some(message) = payload.downcast_ref::<String>() {
+        return message.clone();
+    }
+    return "unknown panic payload".to_string();
+}
+
+/// Run a task, catching and logging any panic along with its context and location. The panic is logged once, here,
+/// rather than also by the default panic hook. The task shouldn't be trusted afterwards, so whatever state it was
+/// working on should be torn down.
+/// ```
+/// use immie2d_server::network::panic_boundary::catch_task_panic;
+///
+/// assert_eq!(catch_task_panic("connection 1", || 5), Ok(5));
+/// let panic = catch_task_panic("connection 2", || -> u32 { panic!("ability {} broke", "fireball") }).unwrap_err();
+/// assert_eq!(panic.context, "connection 2");
+/// assert_eq!(panic.message, "ability fireball broke");
+/// assert!(panic.location.unwrap().contains(".rs:"));
+/// ```
+pub fn catch_task_panic<R, F: FnOnce() -> R>(context: &str, task: F) -> Result<R, TaskPanic> {
+    install_boundary_hook();
+    BOUNDARY_DEPTH.with(|depth| depth.set(depth.get() + 1));
+    let result = panic::catch_unwind(AssertUnwindSafe(task));
+    BOUNDARY_DEPTH.with(|depth| depth.set(depth.get() - 1));
+    return result.map_err(|payload| {
+        let location = PANIC_LOCATION.with(|last| last.borrow_mut().take());
+        let panic = TaskPanic { context: context.to_string(), message: get_panic_message(payload.as_ref()), location };
+        eprintln!("{}", panic);
+        return panic;
+    });
+}
+
+/// Message telling a client its session ended because of an internal error.
+pub fn create_internal_error_message() -> OutboundMessage {
+    return OutboundMessage::new(MessagePriority::Battle, INTERNAL_ERROR_NOTICE.to_vec());
+}
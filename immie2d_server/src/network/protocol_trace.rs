@@ -0,0 +1,189 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Value};
+
+/* Whether a frame was received from or sent to a client. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TraceDirection {
+    Inbound,
+    Outbound
+}
+
+impl TraceDirection {
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            TraceDirection::Inbound => "in",
+            TraceDirection::Outbound => "out"
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<TraceDirection> {
+        return match name {
+            "in" => Some(TraceDirection::Inbound),
+            "out" => Some(TraceDirection::Outbound),
+            _ => None
+        };
+    }
+}
+
+/* A single protocol frame in a trace. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TraceRecord {
+    /// Microseconds since the unix epoch.
+    pub timestamp: u64,
+    pub connection: u64,
+    pub direction: TraceDirection,
+    /// Kind of message in the frame, such as `weather_update`.
+    pub message_type: String,
+    /// Encoded size of the frame in bytes.
+    pub size: usize,
+    /// Human readable description of the decoded message, usually its Debug output.
+    pub summary: String
+}
+
+impl TraceRecord {
+    /// Encode as a single line JSON object.
+    /// ```
+    /// use immie2d_server::network::protocol_trace::{TraceRecord, TraceDirection};
+    ///
+    /// let record = TraceRecord { timestamp: 10, connection: 2, direction: TraceDirection::Outbound, message_type: "pong".to_string(), size: 16, summary: "TimeSyncPong".to_string() };
+    /// assert!(!record.to_json_line().contains('\n'));
+    /// assert_eq!(TraceRecord::from_json_line(&record.to_json_line()), Ok(record));
+    /// assert!(TraceRecord::from_json_line(r#"{ "timestamp": 10 }"#).is_err());
+    /// ```
+    pub fn to_json_line(&self) -> String {
+        return json!({
+            "timestamp": self.timestamp,
+            "connection": self.connection,
+            "direction": self.direction.get_name(),
+            "type": self.message_type,
+            "size": self.size,
+            "summary": self.summary
+        }).to_string();
+    }
+
+    pub fn from_json_line(line: &str) -> Result<TraceRecord, String> {
+        let json: Value = serde_json::from_str(line).map_err(|err| err.to_string())?;
+        let field = |name: &str| json.get(name).ok_or(format!("Trace record is missing [{}]", name));
+        let number = |name: &str| field(name)?.as_u64().ok_or(format!("Trace record [{}] is not a number", name));
+        let string = |name: &str| field(name)?.as_str().map(|value| value.to_string()).ok_or(format!("Trace record [{}] is not a string", name));
+        let direction = string("direction")?;
+        return Ok(TraceRecord {
+            timestamp: number("timestamp")?,
+            connection: number("connection")?,
+            direction: TraceDirection::from_name(&direction).ok_or(format!("Unknown trace direction [{}]", direction))?,
+            message_type: string("type")?,
+            size: number("size")? as usize,
+            summary: string("summary")?
+        });
+    }
+
+    /// Format for reading in a terminal, with the timestamp in seconds.
+    /// ```
+    /// use immie2d_server::network::protocol_trace::{TraceRecord, TraceDirection};
+    ///
+    /// let record = TraceRecord { timestamp: 1_500_000, connection: 2, direction: TraceDirection::Inbound, message_type: "ping".to_string(), size: 8, summary: "TimeSyncPing".to_string() };
+    /// assert_eq!(record.to_pretty_string(), "1.500000 #2 in  ping (8 bytes) TimeSyncPing");
+    /// ```
+    pub fn to_pretty_string(&self) -> String {
+        return format!("{}.{:06} #{} {:<3} {} ({} bytes) {}", self.timestamp / 1_000_000, self.timestamp % 1_000_000, self.connection, self.direction.get_name(), self.message_type, self.size, self.summary);
+    }
+}
+
+/* Appends every traced frame to a trace file as JSON lines, for debugging desyncs. Tracing is enabled with the
+protocol_trace server config setting. */
+pub struct ProtocolTracer {
+    writer: BufWriter<File>
+}
+
+impl ProtocolTracer {
+    /// Open a trace file, appending to it if it exists.
+    pub fn open(path: &Path) -> io::Result<ProtocolTracer> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        return Ok(ProtocolTracer { writer: BufWriter::new(file) });
+    }
+
+    pub fn record(&mut self, record: &TraceRecord) -> io::Result<()> {
+        return writeln!(self.writer, "{}", record.to_json_line());
+    }
+
+    /// Record a frame as it is read from or written to a connection, timestamped now.
+    /// ```
+    /// use immie2d_server::network::protocol_trace::{ProtocolTracer, TraceDirection, TraceFilter, read_trace};
+    ///
+    /// let path = std::env::temp_dir().join(format!("immie2d_trace_frame_doctest_{}.jsonl", std::process::id()));
+    /// # let _ = std::fs::remove_file(&path);
+    /// let mut tracer = ProtocolTracer::open(&path).unwrap();
+    /// tracer.record_frame(3, TraceDirection::Inbound, "chat", b"hello", "hello".to_string()).unwrap();
+    /// tracer.flush().unwrap();
+    ///
+    /// let records = read_trace(&path, &TraceFilter::default()).unwrap();
+    /// assert_eq!((records[0].connection, records[0].size, records[0].summary.as_str()), (3, 5, "hello"));
+    /// std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn record_frame(&mut self, connection: u64, direction: TraceDirection, message_type: &str, frame: &[u8], summary: String) -> io::Result<()> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_micros() as u64).unwrap_or(0);
+        return self.record(&TraceRecord { timestamp, connection, direction, message_type: message_type.to_string(), size: frame.len(), summary });
+    }
+
+    /// Write buffered records to the file. Records are also written when the tracer is dropped.
+    pub fn flush(&mut self) -> io::Result<()> {
+        return self.writer.flush();
+    }
+}
+
+/* Which records of a trace to show. Unset fields match every record. */
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct TraceFilter {
+    pub connection: Option<u64>,
+    pub direction: Option<TraceDirection>,
+    pub message_type: Option<String>,
+    /// Only records at or after this timestamp.
+    pub since: Option<u64>
+}
+
+impl TraceFilter {
+    pub fn matches(&self, record: &TraceRecord) -> bool {
+        return self.connection.is_none_or(|connection| record.connection == connection)
+            && self.direction.is_none_or(|direction| record.direction == direction)
+            && self.message_type.as_ref().is_none_or(|message_type| record.message_type == *message_type)
+            && self.since.is_none_or(|since| record.timestamp >= since);
+    }
+}
+
+/// Read every record of a trace file matching the filter. Returns an error naming the line of any malformed record.
+/// ```
+/// use immie2d_server::network::protocol_trace::{ProtocolTracer, TraceRecord, TraceDirection, TraceFilter, read_trace};
+///
+/// let path = std::env::temp_dir().join(format!("immie2d_trace_doctest_{}.jsonl", std::process::id()));
+/// # let _ = std::fs::remove_file(&path);
+/// let mut tracer = ProtocolTracer::open(&path).unwrap();
+/// for (connection, direction) in [(1, TraceDirection::Inbound), (2, TraceDirection::Outbound), (1, TraceDirection::Outbound)] {
+///     tracer.record(&TraceRecord { timestamp: 0, connection, direction, message_type: "chat".to_string(), size: 4, summary: String::new() }).unwrap();
+/// }
+/// tracer.flush().unwrap();
+///
+/// let filter = TraceFilter { connection: Some(1), ..TraceFilter::default() };
+/// assert_eq!(read_trace(&path, &filter).unwrap().len(), 2);
+/// let filter = TraceFilter { connection: Some(1), direction: Some(TraceDirection::Inbound), ..TraceFilter::default() };
+/// assert_eq!(read_trace(&path, &filter).unwrap().len(), 1);
+/// std::fs::remove_file(&path).unwrap();
+/// ```
+pub fn read_trace(path: &Path, filter: &TraceFilter) -> io::Result<Vec<TraceRecord>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut records = Vec::new();
+    for (line_index, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = TraceRecord::from_json_line(&line).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("Line {}: {}", line_index + 1, err)))?;
+        if filter.matches(&record) {
+            records.push(record);
+        }
+    }
+    return Ok(records);
+}
@@ -0,0 +1,157 @@
+use std::collections::VecDeque;
+use std::io::{self, ErrorKind, Write};
+
+/* Lower values are sent first. */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[repr(u8)]
+pub enum MessagePriority {
+    Battle = 0,
+    Chat = 1,
+    Snapshot = 2
+}
+
+const PRIORITY_COUNT: usize = 3;
+
+/* An already encoded message waiting to be written to a client connection. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct OutboundMessage {
+    pub priority: MessagePriority,
+    /// Snapshot deltas sharing a key supersede each other while the connection is back-pressured.
+    pub coalesce_key: Option<u32>,
+    pub payload: Vec<u8>
+}
+
+impl OutboundMessage {
+    pub fn new(priority: MessagePriority, payload: Vec<u8>) -> OutboundMessage {
+        return OutboundMessage { priority, coalesce_key: None, payload };
+    }
+
+    pub fn snapshot(coalesce_key: u32, payload: Vec<u8>) -> OutboundMessage {
+        return OutboundMessage { priority: MessagePriority::Snapshot, coalesce_key: Some(coalesce_key), payload };
+    }
+}
+
+/* Outbound queue of a single client connection. Battle messages are always written before chat, and chat before snapshots. */
+pub struct SendQueue {
+    queues: [VecDeque<OutboundMessage>; PRIORITY_COUNT],
+    in_flight: Option<(Vec<u8>, usize)>,
+    is_back_pressured: bool
+}
+
+impl SendQueue {
+    pub fn new() -> SendQueue {
+        return SendQueue {
+            queues: [VecDeque::new(), VecDeque::new(), VecDeque::new()],
+            in_flight: None,
+            is_back_pressured: false
+        };
+    }
+
+    /// Queue a message to be sent. While the connection is back-pressured, a snapshot replaces
+    /// any queued snapshot with the same coalesce key instead of being appended.
+    /// ```
+    /// use immie2d_server::network::send_queue::{SendQueue, OutboundMessage, MessagePriority};
+    /// let mut queue = SendQueue::new();
+    /// queue.push(OutboundMessage::snapshot(1, vec![1]));
+    /// queue.push(OutboundMessage::new(MessagePriority::Chat, vec![2]));
+    /// queue.push(OutboundMessage::new(MessagePriority::Battle, vec![3]));
+    /// assert_eq!(queue.pop().unwrap().payload, vec![3]);
+    /// assert_eq!(queue.pop().unwrap().payload, vec![2]);
+    /// assert_eq!(queue.pop().unwrap().payload, vec![1]);
+    /// assert!(queue.pop().is_none());
+    /// ```
+    pub fn push(&mut self, message: OutboundMessage) {
+        let queue = &mut self.queues[message.priority as usize];
+        if self.is_back_pressured && message.coalesce_key.is_some() {
+            let existing = queue.iter_mut().find(|queued| queued.coalesce_key == message.coalesce_key);
+            if let Some(existing) = existing {
+                *existing = message;
+                return;
+            }
+        }
+        queue.push_back(message);
+    }
+
+    /// Take the highest priority queued message.
+    pub fn pop(&mut self) -> Option<OutboundMessage> {
+        for queue in self.queues.iter_mut() {
+            let message = queue.pop_front();
+            if message.is_some() {
+                return message;
+            }
+        }
+        return None;
+    }
+
+    /// Number of messages queued, not including a partially written message.
+    pub fn len(&self) -> usize {
+        return self.queues.iter().map(|queue| queue.len()).sum();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0 && self.in_flight.is_none();
+    }
+
+    pub fn is_back_pressured(&self) -> bool {
+        return self.is_back_pressured;
+    }
+
+    /// Write as many queued messages as the writer accepts. When the writer would block, the
+    /// remainder of the current message is kept and the queue becomes back-pressured until fully flushed.
+    /// ```
+    /// use std::io::{self, Write, ErrorKind};
+    /// use immie2d_server::network::send_queue::{SendQueue, OutboundMessage};
+    ///
+    /// // Accepts a single byte and then blocks.
+    /// struct OneByteWriter { written: Vec<u8> }
+    /// impl Write for OneByteWriter {
+    ///     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    ///         if self.written.len() > 0 { return Err(io::Error::from(ErrorKind::WouldBlock)); }
+    ///         self.written.push(buf[0]);
+    ///         return Ok(1);
+    ///     }
+    ///     fn flush(&mut self) -> io::Result<()> { return Ok(()); }
+    /// }
+    ///
+    /// let mut queue = SendQueue::new();
+    /// queue.push(OutboundMessage::snapshot(7, vec![1, 2]));
+    /// queue.flush_to(&mut OneByteWriter { written: Vec::new() }).unwrap();
+    /// assert!(queue.is_back_pressured());
+    ///
+    /// // Redundant deltas for the same key are coalesced while back-pressured
+    /// queue.push(OutboundMessage::snapshot(7, vec![3]));
+    /// queue.push(OutboundMessage::snapshot(7, vec![4]));
+    /// assert_eq!(queue.len(), 1);
+    ///
+    /// let mut socket: Vec<u8> = Vec::new();
+    /// queue.flush_to(&mut socket).unwrap();
+    /// assert_eq!(socket, vec![2, 4]);
+    /// assert!(!queue.is_back_pressured());
+    /// ```
+    pub fn flush_to<W: Write>(&mut self, writer: &mut W) -> io::Result<()> {
+        loop {
+            if self.in_flight.is_none() {
+                match self.pop() {
+                    Some(message) => self.in_flight = Some((message.payload, 0)),
+                    None => break
+                }
+            }
+            let (payload, written) = self.in_flight.as_mut().unwrap();
+            while *written < payload.len() {
+                match writer.write(&payload[*written..]) {
+                    Ok(0) => return Err(io::Error::from(ErrorKind::WriteZero)),
+                    Ok(count) => *written += count,
+                    Err(err) if err.kind() == ErrorKind::WouldBlock => {
+                        self.is_back_pressured = true;
+                        return Ok(());
+                    },
+                    Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                    Err(err) => return Err(err)
+                }
+            }
+            self.in_flight = None;
+        }
+        self.is_back_pressured = false;
+        return Ok(());
+    }
+}
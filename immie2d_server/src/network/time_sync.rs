@@ -0,0 +1,24 @@
+use immie2d_shared::engine_types::time_sync::{TimeSyncPing, TimeSyncPong};
+
+/// Furthest back in time a client input can be compensated for. Older timestamps are treated as this old, so a
+/// client with a slow or dishonest clock can't act on long outdated state.
+pub const MAX_INPUT_REWIND_MICROS: u64 = 250_000;
+
+/// Answer a client's time sync ping with the current server clock.
+pub fn respond_to_ping(ping_bytes: &[u8], server_time: u64) -> Option<TimeSyncPong> {
+    return Some(TimeSyncPing::from_bytes(ping_bytes)?.respond(server_time));
+}
+
+/// The server time to resolve an input at, from the server time the client stamped it with. Inputs can't be
+/// from the future, or from further back than MAX_INPUT_REWIND_MICROS.
+/// ```
+/// use immie2d_server::network::time_sync::{get_compensated_input_time, MAX_INPUT_REWIND_MICROS};
+///
+/// let now = 10_000_000;
+/// assert_eq!(get_compensated_input_time(now - 80_000, now), now - 80_000);
+/// assert_eq!(get_compensated_input_time(now + 5_000, now), now);
+/// assert_eq!(get_compensated_input_time(0, now), now - MAX_INPUT_REWIND_MICROS);
+/// ```
+pub fn get_compensated_input_time(input_timestamp: u64, server_time: u64) -> u64 {
+    return input_timestamp.clamp(server_time.saturating_sub(MAX_INPUT_REWIND_MICROS), server_time);
+}
@@ -0,0 +1,290 @@
+use std::time::Duration;
+
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::battle::{battle::Battle, battle_format::BattleFormat, battle_side::BattleSide, field_state::FieldState};
+use immie2d_shared::gameplay::battle::battle_command::{BattleCommand, BattleCommandError};
+use immie2d_shared::gameplay::battle::battle_event::BattleEvent;
+use immie2d_shared::gameplay::battle::event_timeline::{EventTimeline, TimelineAck};
+use immie2d_shared::gameplay::game_data::{GameData, GameDataHandle};
+use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use super::timeline_sync::TimelineSync;
+use super::turn_timer::TurnTimer;
+
+/* A battle being run by the server, along with the players controlling each side.
+The session keeps the generation of game data it started with until the battle ends, even if the data is reloaded.
+It also keeps the teams, seed and field it started with and every command applied since, so the battle can be rebuilt in
+another process. See SessionSnapshot
+The events of applied commands are published as timelines, which the players and any spectators animate in sync.
+Each turn is timed from when its timeline finishes, so the pace viewers animate at doesn't change the time to choose. */
+pub struct BattleSession {
+    players: Vec<PlayerId>,
+    ruleset: BattleRuleset,
+    battle: Battle,
+    data: GameDataHandle,
+    initial_teams: Vec<Vec<Immie>>,
+    seed: u64,
+    initial_field: FieldState,
+    commands: Vec<BattleCommand>,
+    timeline: TimelineSync,
+    turn_timer: TurnTimer,
+    /// Whether each side has sent a command this turn.
+    has_chosen: Vec<bool>
+}
+
+impl BattleSession {
+    /// Start a session with the ruleset it was queued with, using the current game data. Players are in side order.
+    /// Will panic if the number of players doesn't match the number of sides.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battler::Battler, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap, species::species_map::SpeciesMap};
+    /// use immie2d_server::session::battle_session::BattleSession;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species)]);
+    /// let data = GameData::new(0, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle();
+    /// let session = BattleSession::new(vec![PlayerId(1), PlayerId(2)], BattleRuleset::InverseTypes, BattleFormat::Single, vec![side.clone(), side], data);
+    /// assert_eq!(session.get_battle().get_rules().get_name(), "inverse_types");
+    /// assert_eq!(session.get_side_of(PlayerId(2)), Some(1));
+    /// ```
+    pub fn new(players: Vec<PlayerId>, ruleset: BattleRuleset, format: BattleFormat, sides: Vec<BattleSide>, data: GameDataHandle) -> BattleSession {
+        assert!(players.len() == sides.len(), "Battle session has {} players but {} sides", players.len(), sides.len());
+        let initial_teams = sides.iter().map(|side| side.get_team().iter().map(|battler| *battler.get_immie()).collect()).collect();
+        let battle = Battle::new(format, sides).with_rules(ruleset.create_plugin());
+        let mut timeline = TimelineSync::new();
+        for player in players.iter() {
+            timeline.add_viewer(*player);
+        }
+        let has_chosen = vec![false; players.len()];
+        return BattleSession {
+            players,
+            ruleset,
+            battle,
+            data,
+            initial_teams,
+            seed: 0,
+            initial_field: FieldState::default(),
+            commands: Vec::new(),
+            timeline,
+            turn_timer: TurnTimer::new(),
+            has_chosen
+        };
+    }
+
+    /// Seed the battle. See Battle::with_seed()
+    /// Will panic if a command has already been applied, since the seed could no longer be replayed from the start.
+    pub fn with_seed(mut self, seed: u64) -> BattleSession {
+        assert!(self.commands.is_empty(), "Cannot seed a battle session after commands have been applied");
+        self.battle = self.battle.with_seed(seed);
+        self.seed = seed;
+        return self;
+    }
+
+    /// Start the battle with field conditions. See Battle::with_field()
+    /// Will panic if a command has already been applied.
+    pub fn with_field(mut self, field: FieldState) -> BattleSession {
+        assert!(self.commands.is_empty(), "Cannot change the starting field of a battle session after commands have been applied");
+        self.battle = self.battle.with_field(field);
+        self.initial_field = field;
+        return self;
+    }
+
+    /// Give each side a time limit other than DEFAULT_TURN_LENGTH to choose its command. Will panic if the length is 0.
+    pub fn with_turn_length(mut self, turn_length: Duration) -> BattleSession {
+        self.turn_timer = TurnTimer::new().with_turn_length(turn_length);
+        return self;
+    }
+
+    /// The game data the session started with.
+    pub fn get_data(&self) -> &GameData {
+        return &self.data;
+    }
+
+    /// Validate and run a command from a client against the session's own generation of game data.
+    /// Commands that succeed are recorded so the session can be rebuilt.
+    pub fn apply_command(&mut self, command: BattleCommand) -> Result<(), BattleCommandError> {
+        let turn = self.battle.get_turn();
+        self.battle.apply_command(command, self.data.get_ability_map(), self.data.get_species_map())?;
+        self.commands.push(command);
+        if self.battle.get_turn() != turn {
+            self.has_chosen.fill(false);
+        } else if let Some(side) = command.get_side() {
+            self.has_chosen[side] = true;
+        }
+        return Ok(());
+    }
+
+    /// Lay out the events of every command applied since the last call as the next timeline, to send to the players
+    /// and spectators. None if nothing has happened. A turn that ended starts timing the next one once the timeline
+    /// finishes, and the timer stops once the battle is over. See TimelineSync::publish()
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap, species::species_map::SpeciesMap};
+    /// use immie2d_shared::gameplay::battle::{battler::Battler, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::{battle_command::BattleCommand, battle_event::BattleEvent, event_timeline::TimelineAck};
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_server::session::battle_session::BattleSession;
+    /// use immie2d_server::session::timeline_sync::TIMELINE_LEAD_MICROS;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species)]);
+    /// let data = GameData::new(0, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle();
+    /// let mut session = BattleSession::new(vec![PlayerId(1), PlayerId(2)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side], data);
+    /// session.add_spectator(PlayerId(3));
+    /// assert!(session.publish_events(0).is_none());
+    ///
+    /// session.apply_command(BattleCommand::EndTurn).unwrap();
+    /// let timeline = session.publish_events(0).unwrap();
+    /// assert_eq!(timeline.start_time, TIMELINE_LEAD_MICROS);
+    /// assert!(timeline.events.iter().any(|timed| matches!(timed.event, BattleEvent::TurnEnded { .. })));
+    ///
+    /// // The battle waits on the spectator as well as both players
+    /// assert!(session.acknowledge_timeline(PlayerId(1), TimelineAck { batch: 0 }));
+    /// assert!(session.acknowledge_timeline(PlayerId(2), TimelineAck { batch: 0 }));
+    /// assert!(!session.get_timeline_sync().is_settled(0));
+    /// assert!(session.acknowledge_timeline(PlayerId(3), TimelineAck { batch: 0 }));
+    /// assert!(session.get_timeline_sync().is_settled(0));
+    /// assert!(!session.acknowledge_timeline(PlayerId(4), TimelineAck { batch: 0 }));
+    /// ```
+    pub fn publish_events(&mut self, server_time: u64) -> Option<EventTimeline> {
+        let events = self.battle.take_events();
+        if events.is_empty() {
+            return None;
+        }
+        let turn_ended = events.iter().any(|event| matches!(event, BattleEvent::TurnEnded { .. }));
+        let timeline = self.timeline.publish(events, server_time);
+        if self.battle.is_finished() {
+            self.turn_timer.stop();
+        } else if turn_ended {
+            self.turn_timer.start_turn(&self.timeline, server_time);
+        }
+        return Some(timeline);
+    }
+
+    /// Start timing the first turn, once the players have been told the battle started. Returns the deadline.
+    pub fn start_turn_timer(&mut self, server_time: u64) -> u64 {
+        return self.turn_timer.start_turn(&self.timeline, server_time);
+    }
+
+    /// The sides still in the battle that haven't sent a command by the deadline of the turn, in order. Empty until the
+    /// timer expires.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap};
+    /// use std::time::Duration;
+    /// use immie2d_shared::gameplay::battle::{battler::Battler, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::battle_command::BattleCommand;
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_server::session::battle_session::BattleSession;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let battler = Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species);
+    /// let side = BattleSide::new(vec![battler.clone(), battler]);
+    /// let data = GameData::new(0, species_map, AbilityMap::new(), ItemMap::new()).into_handle();
+    /// let mut session = BattleSession::new(vec![PlayerId(1), PlayerId(2)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side], data)
+    ///     .with_turn_length(Duration::from_secs(30));
+    /// assert_eq!(session.start_turn_timer(0), 30_000_000);
+    ///
+    /// session.apply_command(BattleCommand::Switch { side: 0, slot: 1 }).unwrap();
+    /// assert!(session.get_timed_out_sides(29_000_000).is_empty());
+    /// assert_eq!(session.get_timed_out_sides(30_000_000), vec![1]);
+    ///
+    /// // The next turn is timed from when its timeline finishes playing
+    /// session.apply_command(BattleCommand::EndTurn).unwrap();
+    /// let timeline = session.publish_events(30_000_000).unwrap();
+    /// assert_eq!(session.get_turn_timer().get_deadline(), Some(timeline.get_end_time() + 30_000_000));
+    /// ```
+    pub fn get_timed_out_sides(&self, server_time: u64) -> Vec<usize> {
+        let has_chosen: Vec<bool> = self.has_chosen.iter().enumerate().map(|(side, chosen)| *chosen || self.battle.get_side(side).is_eliminated()).collect();
+        return self.turn_timer.get_timed_out_sides(&has_chosen, server_time);
+    }
+
+    pub fn get_turn_timer(&self) -> &TurnTimer {
+        return &self.turn_timer;
+    }
+
+    /// Record that a player or spectator finished animating a timeline. See TimelineSync::acknowledge()
+    pub fn acknowledge_timeline(&mut self, viewer: PlayerId, ack: TimelineAck) -> bool {
+        return self.timeline.acknowledge(viewer, ack);
+    }
+
+    /// Start syncing a spectator with the timelines published from now on.
+    pub fn add_spectator(&mut self, spectator: PlayerId) {
+        self.timeline.add_viewer(spectator);
+    }
+
+    /// Stop waiting on a spectator. Players stay viewers for the whole session.
+    pub fn remove_spectator(&mut self, spectator: PlayerId) {
+        if !self.players.contains(&spectator) {
+            self.timeline.remove_viewer(spectator);
+        }
+    }
+
+    pub fn get_timeline_sync(&self) -> &TimelineSync {
+        return &self.timeline;
+    }
+
+    /// Every command applied since the session started, in order.
+    pub fn get_commands(&self) -> &[BattleCommand] {
+        return &self.commands;
+    }
+
+    /// The Immies of each side when the session started.
+    pub fn get_initial_teams(&self) -> &[Vec<Immie>] {
+        return &self.initial_teams;
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        return self.seed;
+    }
+
+    /// The field conditions when the session started.
+    pub fn get_initial_field(&self) -> FieldState {
+        return self.initial_field;
+    }
+
+    pub fn get_players(&self) -> &[PlayerId] {
+        return &self.players;
+    }
+
+    pub fn get_ruleset(&self) -> BattleRuleset {
+        return self.ruleset;
+    }
+
+    pub fn get_battle(&self) -> &Battle {
+        return &self.battle;
+    }
+
+    /// Changes made directly to the battle are not recorded, so are lost if the session is rebuilt from a snapshot.
+    pub fn get_battle_mut(&mut self) -> &mut Battle {
+        return &mut self.battle;
+    }
+
+    /// Get the side a player controls, if they are in this session.
+    pub fn get_side_of(&self, player: PlayerId) -> Option<usize> {
+        return self.players.iter().position(|p| *p == player);
+    }
+}
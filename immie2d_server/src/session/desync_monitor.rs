@@ -0,0 +1,118 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use immie2d_shared::gameplay::battle::state_hash::TurnHash;
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::network::send_queue::{MessagePriority, OutboundMessage};
+
+use super::battle_session::BattleSession;
+use super::session_snapshot::SessionSnapshot;
+
+/// How many of the latest turns the server remembers the hash of. Hashes of older turns are ignored, since a client
+/// that far behind is resynced by the turns it hasn't caught up on yet.
+pub const TURN_HASH_HISTORY: usize = 8;
+
+/* A client's copy of a battle that stopped matching the server's. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct DesyncReport {
+    pub session: u64,
+    pub player: PlayerId,
+    pub turn: u32,
+    pub server_hash: [u8; 32],
+    pub client_hash: [u8; 32],
+    /// Commands applied to the battle so far, to replay it up to the desync.
+    pub command_count: usize
+}
+
+fn to_hex(hash: &[u8; 32]) -> String {
+    return hash.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+impl fmt::Display for DesyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "Battle session {} desynced for player {} on turn {} after {} commands: server {} client {}",
+            self.session, self.player.0, self.turn, self.command_count, to_hex(&self.server_hash), to_hex(&self.client_hash));
+    }
+}
+
+/* Compares the battle state hashes clients send after each turn against the server's, to catch a client whose copy
+of a battle has drifted, such as from a bug that only shows on one platform. The session records its hash after every
+turn, and a client whose hash differs is resynced from a snapshot of the session. */
+pub struct DesyncMonitor {
+    session: u64,
+    turn_hashes: VecDeque<TurnHash>
+}
+
+impl DesyncMonitor {
+    pub fn new(session: u64) -> DesyncMonitor {
+        return DesyncMonitor { session, turn_hashes: VecDeque::with_capacity(TURN_HASH_HISTORY) };
+    }
+
+    /// Record the server's hash once a turn has resolved.
+    pub fn record_turn(&mut self, session: &BattleSession) {
+        let battle = session.get_battle();
+        if self.turn_hashes.back().is_some_and(|latest| latest.turn == battle.get_turn()) {
+            self.turn_hashes.pop_back();
+        }
+        if self.turn_hashes.len() == TURN_HASH_HISTORY {
+            self.turn_hashes.pop_front();
+        }
+        self.turn_hashes.push_back(TurnHash { turn: battle.get_turn(), hash: battle.get_state_hash() });
+    }
+
+    /// Check a hash sent by a player. Returns None if it matches, or is of a turn the server has no hash of. Otherwise
+    /// returns the report to log and the full state message to resync the player with, which holds the session's
+    /// snapshot for the client to replay like SessionSnapshot::restore() does.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::{battle_command::BattleCommand, rules::battle_ruleset::BattleRuleset, state_hash::TurnHash};
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::session::{battle_session::BattleSession, desync_monitor::DesyncMonitor, session_snapshot::SessionSnapshot};
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let data = GameData::new(1, species_map, AbilityMap::new(), ItemMap::new()).into_handle();
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species)]);
+    /// let mut session = BattleSession::new(vec![PlayerId(1), PlayerId(2)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side], data.clone());
+    /// let mut monitor = DesyncMonitor::new(7);
+    /// monitor.record_turn(&session);
+    ///
+    /// // The client's mirror matches until something changes on only one side
+    /// let mut mirror = SessionSnapshot::new(7, &session).restore(data).unwrap();
+    /// let turn = session.get_battle().get_turn();
+    /// assert!(monitor.check(&session, PlayerId(1), TurnHash { turn, hash: mirror.get_battle().get_state_hash() }).is_none());
+    /// mirror.get_battle_mut().apply_damage(BattlerId::new(0, 0), 3);
+    /// let (report, resync) = monitor.check(&session, PlayerId(1), TurnHash { turn, hash: mirror.get_battle().get_state_hash() }).unwrap();
+    /// assert_eq!(report.turn, turn);
+    /// assert!(report.to_string().starts_with("Battle session 7 desynced for player 1"));
+    /// assert_eq!(SessionSnapshot::from_bytes(&resync.payload).unwrap(), SessionSnapshot::new(7, &session));
+    ///
+    /// // Turns the server hasn't reached, or has forgotten, are ignored
+    /// assert!(monitor.check(&session, PlayerId(1), TurnHash { turn: turn + 1, hash: [0; 32] }).is_none());
+    /// ```
+    pub fn check(&self, session: &BattleSession, player: PlayerId, client_hash: TurnHash) -> Option<(DesyncReport, OutboundMessage)> {
+        let server_hash = self.turn_hashes.iter().find(|recorded| recorded.turn == client_hash.turn)?;
+        if server_hash.hash == client_hash.hash {
+            return None;
+        }
+        let report = DesyncReport {
+            session: self.session,
+            player,
+            turn: client_hash.turn,
+            server_hash: server_hash.hash,
+            client_hash: client_hash.hash,
+            command_count: session.get_commands().len()
+        };
+        let resync = OutboundMessage::new(MessagePriority::Battle, SessionSnapshot::new(self.session, session).to_bytes());
+        return Some((report, resync));
+    }
+}
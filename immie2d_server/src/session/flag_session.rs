@@ -0,0 +1,323 @@
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::world::flag_scoreboard::{FlagScoreboard, FLAG_TEAM_COUNT};
+use immie2d_shared::world::move_result::MoveResult;
+use immie2d_shared::world::tile_map::{TileMap, TileRect};
+use immie2d_shared::world::tile_position::TilePosition;
+
+use crate::network::send_queue::OutboundMessage;
+use crate::storage::player_profile::PlayerProfile;
+use crate::world::audio_cues::AUDIO_CUE_COALESCE_KEY;
+use crate::world::tile_reservations::{MoveIntent, TileReservations};
+
+/// Snapshot coalesce key of scoreboards. A player is only ever in one minigame, so a newer scoreboard replaces an older one.
+pub const FLAG_SCOREBOARD_COALESCE_KEY: u32 = AUDIO_CUE_COALESCE_KEY - 1;
+
+/// Ticks a minigame lasts by default, five minutes at 20 ticks a second.
+pub const DEFAULT_FLAG_DURATION_TICKS: u32 = 5 * 60 * 20;
+/// Captures that end a minigame early by default.
+pub const DEFAULT_FLAG_SCORE_LIMIT: u32 = 3;
+/// Ticks a tagged player waits before respawning by default.
+pub const DEFAULT_FLAG_RESPAWN_TICKS: u32 = 5 * 20;
+
+/* The designated area of a map a capture the flag minigame is played in. Each team defends a base holding its flag,
+and spawns in its spawn area. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FlagArena {
+    pub bases: [TileRect; FLAG_TEAM_COUNT],
+    /// Where each team's flag rests while it isn't carried. Should be inside the team's base.
+    pub flags: [TilePosition; FLAG_TEAM_COUNT],
+    pub spawn_areas: [TileRect; FLAG_TEAM_COUNT]
+}
+
+impl FlagArena {
+    /// The team whose base a tile is in, if any.
+    pub fn get_base_team(&self, tile: TilePosition) -> Option<usize> {
+        return self.bases.iter().position(|base| base.contains(tile));
+    }
+}
+
+struct FlagPlayer {
+    player: PlayerId,
+    network_id: u32,
+    team: usize,
+    /// Tick the player respawns at while tagged out, or None while on the map.
+    respawns_at: Option<u32>
+}
+
+/* A server run capture the flag minigame between two teams of players in an overworld arena. Players step into the
+enemy base to pick up its flag, and score by bringing it back to their own base while their own flag is home. A
+player in the enemy base next to an enemy is tagged: they drop any flag they carry, which returns home, and leave the
+arena until they respawn. The minigame ends at the score limit or when time runs out, and the team with more captures
+wins the rewards. */
+pub struct FlagSession {
+    arena: FlagArena,
+    players: Vec<FlagPlayer>,
+    reservations: TileReservations,
+    /// Index of the player carrying each team's flag.
+    carriers: [Option<usize>; FLAG_TEAM_COUNT],
+    scores: [u32; FLAG_TEAM_COUNT],
+    tick: u32,
+    duration_ticks: u32,
+    score_limit: u32,
+    respawn_ticks: u32,
+    rewards: Vec<(GlobalString, u32)>
+}
+
+impl FlagSession {
+    /// Start a minigame with each team's players as player ids and the network ids of their overworld entities.
+    /// Players are placed in their team's spawn area. Will panic if a team is empty or its spawn area has too few
+    /// tiles for it.
+    pub fn new(arena: FlagArena, teams: [Vec<(PlayerId, u32)>; FLAG_TEAM_COUNT]) -> FlagSession {
+        let mut session = FlagSession {
+            arena,
+            players: Vec::new(),
+            reservations: TileReservations::new(),
+            carriers: [None; FLAG_TEAM_COUNT],
+            scores: [0; FLAG_TEAM_COUNT],
+            tick: 0,
+            duration_ticks: DEFAULT_FLAG_DURATION_TICKS,
+            score_limit: DEFAULT_FLAG_SCORE_LIMIT,
+            respawn_ticks: DEFAULT_FLAG_RESPAWN_TICKS,
+            rewards: Vec::new()
+        };
+        for (team, members) in teams.into_iter().enumerate() {
+            assert!(!members.is_empty(), "Capture the flag team {} has no players", team);
+            for (player, network_id) in members {
+                session.players.push(FlagPlayer { player, network_id, team, respawns_at: None });
+                assert!(session.try_spawn(session.players.len() - 1), "Spawn area of team {} is full", team);
+            }
+        }
+        return session;
+    }
+
+    /// Will panic if the duration is 0.
+    pub fn with_duration_ticks(mut self, duration_ticks: u32) -> FlagSession {
+        assert!(duration_ticks > 0, "Capture the flag duration must be at least 1 tick");
+        self.duration_ticks = duration_ticks;
+        return self;
+    }
+
+    /// Will panic if the score limit is 0.
+    pub fn with_score_limit(mut self, score_limit: u32) -> FlagSession {
+        assert!(score_limit > 0, "Capture the flag score limit must be at least 1");
+        self.score_limit = score_limit;
+        return self;
+    }
+
+    pub fn with_respawn_ticks(mut self, respawn_ticks: u32) -> FlagSession {
+        self.respawn_ticks = respawn_ticks;
+        return self;
+    }
+
+    /// Add an item every player of the winning team receives.
+    pub fn with_reward(mut self, item: GlobalString, count: u32) -> FlagSession {
+        self.rewards.push((item, count));
+        return self;
+    }
+
+    pub fn get_arena(&self) -> &FlagArena {
+        return &self.arena;
+    }
+
+    pub fn get_tick(&self) -> u32 {
+        return self.tick;
+    }
+
+    pub fn get_team(&self, player: PlayerId) -> Option<usize> {
+        return self.players.iter().find(|p| p.player == player).map(|p| p.team);
+    }
+
+    /// Where a player stands, or None while they wait to respawn.
+    pub fn get_position(&self, player: PlayerId) -> Option<TilePosition> {
+        let network_id = self.players.iter().find(|p| p.player == player)?.network_id;
+        return self.reservations.get_position(network_id);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.tick >= self.duration_ticks || self.scores.iter().any(|score| *score >= self.score_limit);
+    }
+
+    /// The team that won, once finished. None while running or if the teams tied.
+    pub fn get_winner(&self) -> Option<usize> {
+        if !self.is_finished() {
+            return None;
+        }
+        return self.get_scoreboard().get_leader();
+    }
+
+    /// Place a player on the first free tile of their spawn area, returning false if there is none.
+    fn try_spawn(&mut self, index: usize) -> bool {
+        let player = &self.players[index];
+        let area = self.arena.spawn_areas[player.team];
+        for y in area.y..area.y + area.height as i32 {
+            for x in area.x..area.x + area.width as i32 {
+                let tile = TilePosition::new(x, y);
+                if self.reservations.get_occupant(tile).is_none() {
+                    self.reservations.add_entity(player.network_id, tile);
+                    return true;
+                }
+            }
+        }
+        return false;
+    }
+
+    /// Run a tick of the minigame: tagged players due to respawn do so, the steps of the tick are resolved, then
+    /// players are tagged, flags are picked up and captures are scored, in that order. Steps of tagged players are
+    /// ignored. Returns the result of each step to send to its player. Will panic if the minigame has finished.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::world::tile_map::{TileMap, TileRect};
+    /// use immie2d_shared::world::tile_position::{Direction, TilePosition};
+    /// use immie2d_server::session::flag_session::{FlagArena, FlagSession};
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    /// use immie2d_server::world::tile_reservations::MoveIntent;
+    ///
+    /// // A field 10 tiles wide, with a base 2 tiles wide at each end
+    /// let map = TileMap::new(GlobalString::new(&"arena".to_string()), 10, 3);
+    /// let bases = [TileRect { x: 0, y: 0, width: 2, height: 3 }, TileRect { x: 8, y: 0, width: 2, height: 3 }];
+    /// let arena = FlagArena { bases, flags: [TilePosition::new(0, 1), TilePosition::new(9, 1)], spawn_areas: bases };
+    /// let potion = GlobalString::new(&"potion".to_string());
+    /// let mut session = FlagSession::new(arena, [vec![(PlayerId(1), 1)], vec![(PlayerId(2), 2)]])
+    ///     .with_score_limit(1).with_respawn_ticks(2).with_reward(potion, 3);
+    /// assert_eq!(session.get_position(PlayerId(2)), Some(TilePosition::new(8, 0)));
+    ///
+    /// let step = |session: &mut FlagSession, direction: Direction| {
+    ///     let from = session.get_position(PlayerId(1)).unwrap();
+    ///     return session.tick(&[MoveIntent { network_id: 1, from, direction }], &map);
+    /// };
+    /// // Sneak along the bottom row, out of reach of the defender
+    /// step(&mut session, Direction::Down);
+    /// step(&mut session, Direction::Down);
+    /// for _ in 0..9 {
+    ///     step(&mut session, Direction::Right);
+    /// }
+    /// step(&mut session, Direction::Up);
+    /// assert_eq!(session.get_scoreboard().carriers, [None, Some(1)]);
+    ///
+    /// step(&mut session, Direction::Down);
+    /// for _ in 0..8 {
+    ///     step(&mut session, Direction::Left);
+    /// }
+    /// assert_eq!(session.get_scoreboard().scores, [1, 0]);
+    /// assert!(session.is_finished());
+    /// assert_eq!(session.get_winner(), Some(0));
+    ///
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// session.grant_rewards(&mut profile);
+    /// assert_eq!(profile.inventory.get_count(potion), 3);
+    /// let mut loser = PlayerProfile::new(PlayerId(2), "gary".to_string());
+    /// session.grant_rewards(&mut loser);
+    /// assert_eq!(loser.inventory.get_count(potion), 0);
+    /// ```
+    /// Players in the enemy base next to an enemy are tagged out until they respawn.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::player_id::PlayerId;
+    /// # use immie2d_shared::world::tile_map::{TileMap, TileRect};
+    /// # use immie2d_shared::world::tile_position::{Direction, TilePosition};
+    /// # use immie2d_server::session::flag_session::{FlagArena, FlagSession};
+    /// # use immie2d_server::world::tile_reservations::MoveIntent;
+    /// # let map = TileMap::new(GlobalString::new(&"arena".to_string()), 10, 3);
+    /// # let bases = [TileRect { x: 0, y: 0, width: 2, height: 3 }, TileRect { x: 8, y: 0, width: 2, height: 3 }];
+    /// # let arena = FlagArena { bases, flags: [TilePosition::new(0, 1), TilePosition::new(9, 1)], spawn_areas: bases };
+    /// let mut session = FlagSession::new(arena, [vec![(PlayerId(1), 1)], vec![(PlayerId(2), 2)]]).with_respawn_ticks(2);
+    /// for _ in 0..6 {
+    ///     let from = session.get_position(PlayerId(1)).unwrap();
+    ///     session.tick(&[MoveIntent { network_id: 1, from, direction: Direction::Right }], &map);
+    /// }
+    /// assert_eq!(session.get_position(PlayerId(1)), Some(TilePosition::new(6, 0)));
+    /// session.tick(&[MoveIntent { network_id: 1, from: TilePosition::new(6, 0), direction: Direction::Right }], &map);
+    /// assert_eq!(session.get_position(PlayerId(1)), Some(TilePosition::new(7, 0)));
+    /// // Stepping into the enemy base at (8, 1), next to the defender at (8, 0)
+    /// session.tick(&[MoveIntent { network_id: 1, from: TilePosition::new(7, 0), direction: Direction::Down }], &map);
+    /// session.tick(&[MoveIntent { network_id: 1, from: TilePosition::new(7, 1), direction: Direction::Right }], &map);
+    /// assert_eq!(session.get_position(PlayerId(1)), None);
+    /// session.tick(&[], &map);
+    /// session.tick(&[], &map);
+    /// assert_eq!(session.get_position(PlayerId(1)), Some(TilePosition::new(0, 0)));
+    /// ```
+    pub fn tick(&mut self, intents: &[MoveIntent], map: &TileMap) -> Vec<MoveResult> {
+        assert!(!self.is_finished(), "Cannot tick a finished capture the flag minigame");
+        for index in 0..self.players.len() {
+            if self.players[index].respawns_at.is_some_and(|tick| tick <= self.tick) && self.try_spawn(index) {
+                self.players[index].respawns_at = None;
+            }
+        }
+
+        let results = self.reservations.resolve_tick(intents, map);
+
+        let tagged: Vec<usize> = (0..self.players.len()).filter(|index| self.is_tagged(*index)).collect();
+        for index in tagged {
+            for carrier in self.carriers.iter_mut() {
+                if *carrier == Some(index) {
+                    *carrier = None;
+                }
+            }
+            self.reservations.remove_entity(self.players[index].network_id);
+            self.players[index].respawns_at = Some(self.tick + self.respawn_ticks);
+        }
+
+        for index in 0..self.players.len() {
+            let Some(tile) = self.reservations.get_position(self.players[index].network_id) else {
+                continue;
+            };
+            let team = self.players[index].team;
+            let enemy = 1 - team;
+            if tile == self.arena.flags[enemy] && self.carriers[enemy].is_none() {
+                self.carriers[enemy] = Some(index);
+            }
+            let is_home = self.arena.get_base_team(tile) == Some(team);
+            if self.carriers[enemy] == Some(index) && is_home && self.carriers[team].is_none() {
+                self.carriers[enemy] = None;
+                self.scores[team] += 1;
+            }
+        }
+
+        self.tick += 1;
+        return results;
+    }
+
+    /// Whether a player stands in the enemy base next to an enemy.
+    fn is_tagged(&self, index: usize) -> bool {
+        let player = &self.players[index];
+        let Some(tile) = self.reservations.get_position(player.network_id) else {
+            return false;
+        };
+        if self.arena.get_base_team(tile) != Some(1 - player.team) {
+            return false;
+        }
+        return self.players.iter().filter(|other| other.team != player.team).any(|other| {
+            return self.reservations.get_position(other.network_id).is_some_and(|other_tile| tile.direction_to(other_tile).is_some());
+        });
+    }
+
+    pub fn get_scoreboard(&self) -> FlagScoreboard {
+        let carriers = self.carriers.map(|carrier| carrier.map(|index| self.players[index].network_id));
+        return FlagScoreboard { remaining_ticks: self.duration_ticks.saturating_sub(self.tick), scores: self.scores, carriers, is_finished: self.is_finished() };
+    }
+
+    /// Message to broadcast the scoreboard to every player of the minigame, such as after each tick.
+    pub fn get_scoreboard_message(&self) -> OutboundMessage {
+        return OutboundMessage::snapshot(FLAG_SCOREBOARD_COALESCE_KEY, self.get_scoreboard().to_bytes());
+    }
+
+    /// The items each player receives once the minigame has finished. Only the winning team is rewarded, including
+    /// players waiting to respawn, and a tie rewards no one.
+    pub fn get_rewards(&self) -> Vec<(PlayerId, GlobalString, u32)> {
+        let Some(winner) = self.get_winner() else {
+            return Vec::new();
+        };
+        return self.players.iter().filter(|p| p.team == winner).flat_map(|p| self.rewards.iter().map(move |(item, count)| (p.player, *item, *count))).collect();
+    }
+
+    /// Add a player's rewards to the inventory of their profile.
+    pub fn grant_rewards(&self, profile: &mut PlayerProfile) {
+        for (player, item, count) in self.get_rewards() {
+            if player == profile.player {
+                profile.inventory.add_item(item, count);
+            }
+        }
+    }
+}
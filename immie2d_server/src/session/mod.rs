@@ -0,0 +1,11 @@
+pub mod battle_session;
+pub mod session_manager;
+pub mod session_snapshot;
+pub mod raid_session;
+pub mod timeline_sync;
+pub mod turn_timer;
+pub mod release_confirmations;
+pub mod flag_session;
+pub mod desync_monitor;
+pub mod replay_bisector;
+pub mod spectator_broadcast;
@@ -0,0 +1,230 @@
+use std::fmt;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::battle::{battle::Battle, battle_format::BattleFormat, battle_side::BattleSide};
+use immie2d_shared::gameplay::battle::battle_command::{BattleCommand, BattleCommandError};
+use immie2d_shared::gameplay::game_data::{GameData, GameDataHandle};
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::gameplay::raid::raid_boss::RaidBoss;
+
+/* Why a player's command was not accepted for the turn. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum RaidError {
+    NotInRaid,
+    RaidFinished,
+    /// The player's Immie has fainted, so they have nothing left to command.
+    Eliminated,
+    AlreadySubmitted,
+    /// Players only command their own side, and turns end once every player has acted.
+    InvalidCommand
+}
+
+impl fmt::Display for RaidError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            RaidError::NotInRaid => write!(f, "Player is not in this raid"),
+            RaidError::RaidFinished => write!(f, "The raid has finished"),
+            RaidError::Eliminated => write!(f, "Player has been eliminated"),
+            RaidError::AlreadySubmitted => write!(f, "Player has already chosen a command this turn"),
+            RaidError::InvalidCommand => write!(f, "Command is not allowed in a raid")
+        };
+    }
+}
+
+/* Up to MAX_RAID_PLAYERS players, each controlling one Immie, against a boss. Turns are shared: every player chooses
+a command, then the players act in speed order followed by the boss' actions. */
+pub struct RaidSession {
+    players: Vec<PlayerId>,
+    boss: RaidBoss,
+    battle: Battle,
+    data: GameDataHandle,
+    pending: Vec<Option<BattleCommand>>,
+    /// Which of the boss' scripted moves have been used.
+    used_scripted_moves: Vec<bool>
+}
+
+impl RaidSession {
+    /// Start a raid. Players are in side order, and the boss's health is scaled by the number of players.
+    /// Will panic if the number of players doesn't match the number of sides or is not a valid raid size,
+    /// or the boss' species isn't in the game data.
+    pub fn new(players: Vec<PlayerId>, sides: Vec<BattleSide>, boss: RaidBoss, data: GameDataHandle, seed: u64) -> RaidSession {
+        assert!(players.len() == sides.len(), "Raid has {} players but {} sides", players.len(), sides.len());
+        let format = BattleFormat::raid(players.len() as u32);
+        let species_map = data.get_species_map();
+        assert!(species_map.is_species_name(boss.immie.species), "Raid boss species {} is not in the game data", boss.immie.species);
+        let boss_battler = boss.create_battler(&species_map.get_species_of(&boss.immie), players.len() as u32);
+        let mut all_sides = sides;
+        all_sides.push(BattleSide::new(vec![boss_battler]));
+        let battle = Battle::new(format, all_sides).with_seed(seed);
+        let pending = vec![None; players.len()];
+        let used_scripted_moves = vec![false; boss.scripted_moves.len()];
+        return RaidSession { players, boss, battle, data, pending, used_scripted_moves };
+    }
+
+    pub fn get_players(&self) -> &[PlayerId] {
+        return &self.players;
+    }
+
+    pub fn get_boss(&self) -> &RaidBoss {
+        return &self.boss;
+    }
+
+    pub fn get_battle(&self) -> &Battle {
+        return &self.battle;
+    }
+
+    pub fn get_data(&self) -> &GameData {
+        return &self.data;
+    }
+
+    pub fn get_boss_side(&self) -> usize {
+        return self.players.len();
+    }
+
+    /// Choose a player's command for this turn. The command is validated when the turn is resolved.
+    pub fn submit(&mut self, player: PlayerId, command: BattleCommand) -> Result<(), RaidError> {
+        if self.battle.is_finished() {
+            return Err(RaidError::RaidFinished);
+        }
+        let side = self.players.iter().position(|p| *p == player).ok_or(RaidError::NotInRaid)?;
+        if self.battle.get_side(side).is_eliminated() {
+            return Err(RaidError::Eliminated);
+        }
+        let command_side = command.get_side().ok_or(RaidError::InvalidCommand)?;
+        if command_side != side {
+            return Err(RaidError::InvalidCommand);
+        }
+        if self.pending[side].is_some() {
+            return Err(RaidError::AlreadySubmitted);
+        }
+        self.pending[side] = Some(command);
+        return Ok(());
+    }
+
+    /// Whether every player still standing has chosen a command.
+    pub fn is_turn_ready(&self) -> bool {
+        return (0..self.players.len()).all(|side| self.pending[side].is_some() || self.battle.get_side(side).is_eliminated());
+    }
+
+    /// Run the turn: the players act in speed order, then the boss acts, then the turn ends. Players who haven't
+    /// chosen a command, such as after a turn timer ran out, skip their action. Returns the players whose commands
+    /// were rejected.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::{ability::Ability, ability_map::AbilityMap, ability_names::AbilityNames, abilities::fireball::Fireball};
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::item::item_map::ItemMap;
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_command::BattleCommand};
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::raid::raid_boss::RaidBoss;
+    /// use immie2d_server::session::raid_session::{RaidSession, RaidError};
+    ///
+    /// let hero = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(100, 200, 60, 90));
+    /// let boss_species = SpeciesData::new(GlobalString::new(&"sproutle".to_string()), Elements::new(vec![ElementKind::Nature]), BaseStats::new(40, 10, 20, 10));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(hero);
+    /// species_map.add_species(boss_species);
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Fireball>();
+    /// let data = GameData::new(1, species_map, ability_map, ItemMap::new()).into_handle();
+    ///
+    /// let abilities = AbilityNames::new(vec![GlobalString::new(&Fireball::static_name().to_string())]);
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(hero.name, 50, abilities), &hero)]);
+    /// let potion = GlobalString::new(&"potion".to_string());
+    /// let boss = RaidBoss::new(Immie::new(boss_species.name, 50, abilities), 2).with_actions_per_turn(2).with_reward(potion, 3);
+    /// let mut raid = RaidSession::new(vec![PlayerId(1), PlayerId(2)], vec![side.clone(), side], boss, data, 5);
+    /// assert_eq!(raid.get_battle().get_battler(BattlerId::new(2, 0)).get_health(), 160);
+    ///
+    /// // Players can't attack each other
+    /// raid.submit(PlayerId(1), BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 }).unwrap();
+    /// assert_eq!(raid.submit(PlayerId(1), BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 2 }), Err(RaidError::AlreadySubmitted));
+    /// assert!(!raid.is_turn_ready());
+    /// raid.submit(PlayerId(2), BattleCommand::UseAbility { side: 1, ability_slot: 0, target_side: 2 }).unwrap();
+    /// assert!(raid.is_turn_ready());
+    /// let rejected = raid.resolve_turn();
+    /// assert_eq!(rejected.len(), 1);
+    /// assert_eq!(rejected[0].0, PlayerId(1));
+    ///
+    /// while !raid.get_battle().is_finished() {
+    ///     raid.submit(PlayerId(1), BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 2 }).unwrap();
+    ///     raid.submit(PlayerId(2), BattleCommand::UseAbility { side: 1, ability_slot: 0, target_side: 2 }).unwrap();
+    ///     raid.resolve_turn();
+    /// }
+    /// assert!(raid.is_victory());
+    /// assert_eq!(raid.get_rewards(), vec![(PlayerId(1), potion, 3), (PlayerId(2), potion, 3)]);
+    /// ```
+    pub fn resolve_turn(&mut self) -> Vec<(PlayerId, BattleCommandError)> {
+        assert!(!self.battle.is_finished(), "Cannot resolve a turn of a finished raid");
+        let mut rejected = Vec::new();
+        for side in self.battle.get_turn_order() {
+            if self.battle.is_finished() {
+                break;
+            }
+            let command = match self.pending.get(side).copied().flatten() {
+                Some(command) => command,
+                None => continue
+            };
+            if let Err(err) = self.battle.apply_command(command, self.data.get_ability_map(), self.data.get_species_map()) {
+                rejected.push((self.players[side], err));
+            }
+        }
+        for _ in 0..self.boss.actions_per_turn {
+            if self.battle.is_finished() {
+                break;
+            }
+            self.take_boss_action();
+        }
+        self.pending.iter_mut().for_each(|command| *command = None);
+        if !self.battle.is_finished() {
+            self.battle.end_turn();
+        }
+        return rejected;
+    }
+
+    /// Use a scripted move if the boss' health has dropped to its threshold, otherwise a random ability with uses
+    /// remaining, on a random player.
+    fn take_boss_action(&mut self) {
+        let boss_side = self.get_boss_side();
+        let boss = self.battle.get_side(boss_side).get_active();
+        let scripted = self.boss.get_scripted_move(boss.get_health(), boss.get_stats().health, &self.used_scripted_moves);
+        let ability_map = self.data.get_ability_map();
+        let boss = *boss;
+        let ability_slot = match scripted {
+            Some(index) => {
+                self.used_scripted_moves[index] = true;
+                self.boss.scripted_moves[index].ability_slot
+            },
+            None => {
+                let usable: Vec<usize> = boss.get_abilities().get_names().iter().enumerate().filter(|(slot, name)| {
+                    let name = name.to_string();
+                    return ability_map.is_ability_name(&name) && boss.get_remaining_uses(*slot, ability_map.new_ability(&name).get_base_ability_data().max_uses) > 0;
+                }).map(|(slot, _)| slot).collect();
+                if usable.is_empty() {
+                    return;
+                }
+                usable[self.battle.get_rng_mut().next_below(usable.len() as u32) as usize]
+            }
+        };
+        let targets = self.battle.get_valid_targets(boss_side);
+        let target_side = targets[self.battle.get_rng_mut().next_below(targets.len() as u32) as usize];
+        // A scripted move whose ability can't be used is skipped rather than stalling the raid.
+        let _ = self.battle.apply_command(BattleCommand::UseAbility { side: boss_side, ability_slot, target_side }, ability_map, self.data.get_species_map());
+    }
+
+    /// Whether the players defeated the boss.
+    pub fn is_victory(&self) -> bool {
+        return self.battle.is_finished() && self.battle.get_side(self.get_boss_side()).is_eliminated();
+    }
+
+    /// The items each player receives, once the boss has been defeated. Every player is rewarded, including those
+    /// whose Immie fainted.
+    pub fn get_rewards(&self) -> Vec<(PlayerId, GlobalString, u32)> {
+        if !self.is_victory() {
+            return Vec::new();
+        }
+        return self.players.iter().flat_map(|player| self.boss.rewards.iter().map(move |(item, count)| (*player, *item, *count))).collect();
+    }
+}
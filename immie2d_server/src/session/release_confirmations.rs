@@ -0,0 +1,114 @@
+use std::collections::HashMap;
+
+use immie2d_shared::engine_types::game_rng::GameRng;
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::immie::immie_release::{ImmieLocation, ReleaseError, ReleasePrompt, ReleaseRequest};
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::storage::player_profile::PlayerProfile;
+
+/// Seconds a player has to confirm a release before having to ask again.
+pub const RELEASE_CONFIRMATION_SECONDS: u64 = 60;
+
+struct PendingRelease {
+    location: ImmieLocation,
+    token: u64,
+    /// The Immie as it was when the release was asked for. If the slot holds anything else by the time it is confirmed,
+    /// such as after reordering the box on another device, the release is refused.
+    immie: Immie,
+    expires_at: u64
+}
+
+/* What happened from a release request. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ReleaseOutcome {
+    /// The client should ask the player to confirm.
+    Prompt(ReleasePrompt),
+    Released(Immie),
+    LockChanged
+}
+
+/* The releases each player asked for and hasn't confirmed yet. Releasing is permanent, so the server only releases
+an Immie once the player confirms the exact Immie it was asked about, with a token it can't have guessed beforehand.
+Only one release per player is pending at a time. Times are unix seconds. */
+pub struct ReleaseConfirmations {
+    pending: HashMap<PlayerId, PendingRelease>,
+    rng: GameRng
+}
+
+impl ReleaseConfirmations {
+    pub fn new(seed: u64) -> ReleaseConfirmations {
+        return ReleaseConfirmations { pending: HashMap::new(), rng: GameRng::new(seed) };
+    }
+
+    /// Handle a request from a player for their profile. The profile must be saved afterwards to persist a release or
+    /// lock. Any confirmation, right or wrong, uses up the pending release, so a token can't be guessed by retrying.
+    /// Locking cancels a pending release of the same Immie.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::{ability::ability_names::AbilityNames, immie::immie::Immie, player_id::PlayerId};
+    /// use immie2d_shared::gameplay::immie::immie_release::{ImmieLocation, ReleaseError, ReleaseRequest};
+    /// use immie2d_server::session::release_confirmations::{ReleaseConfirmations, ReleaseOutcome, RELEASE_CONFIRMATION_SECONDS};
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    ///
+    /// let immie = Immie::new(GlobalString::new(&"lavapup".to_string()), 5, AbilityNames::default());
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// profile.boxed = vec![immie, Immie { level: 9, ..immie }];
+    /// let mut confirmations = ReleaseConfirmations::new(3);
+    /// let location = ImmieLocation::Box(0);
+    ///
+    /// // Nothing is released until confirmed with the right token
+    /// let ReleaseOutcome::Prompt(prompt) = confirmations.handle(&mut profile, ReleaseRequest::Release { location }, 100).unwrap() else { panic!() };
+    /// let wrong = ReleaseRequest::Confirm { location, token: prompt.token.wrapping_add(1) };
+    /// assert_eq!(confirmations.handle(&mut profile, wrong, 101), Err(ReleaseError::NotConfirmed));
+    /// assert_eq!(confirmations.handle(&mut profile, ReleaseRequest::Confirm { location, token: prompt.token }, 101), Err(ReleaseError::NotConfirmed));
+    /// let ReleaseOutcome::Prompt(prompt) = confirmations.handle(&mut profile, ReleaseRequest::Release { location }, 101).unwrap() else { panic!() };
+    /// let confirm = ReleaseRequest::Confirm { location, token: prompt.token };
+    /// assert_eq!(confirmations.handle(&mut profile, confirm, 101), Ok(ReleaseOutcome::Released(immie)));
+    /// assert_eq!(profile.boxed.len(), 1);
+    /// assert_eq!(confirmations.handle(&mut profile, confirm, 102), Err(ReleaseError::NotConfirmed));
+    ///
+    /// // Locking blocks releases, including ones already asked for
+    /// let ReleaseOutcome::Prompt(prompt) = confirmations.handle(&mut profile, ReleaseRequest::Release { location }, 200).unwrap() else { panic!() };
+    /// confirmations.handle(&mut profile, ReleaseRequest::SetLocked { location, is_locked: true }, 201).unwrap();
+    /// assert_eq!(confirmations.handle(&mut profile, ReleaseRequest::Confirm { location, token: prompt.token }, 202), Err(ReleaseError::NotConfirmed));
+    /// assert_eq!(confirmations.handle(&mut profile, ReleaseRequest::Release { location }, 203), Err(ReleaseError::Locked));
+    ///
+    /// // Confirmations expire
+    /// confirmations.handle(&mut profile, ReleaseRequest::SetLocked { location, is_locked: false }, 300).unwrap();
+    /// let ReleaseOutcome::Prompt(prompt) = confirmations.handle(&mut profile, ReleaseRequest::Release { location }, 300).unwrap() else { panic!() };
+    /// let late = ReleaseRequest::Confirm { location, token: prompt.token };
+    /// assert_eq!(confirmations.handle(&mut profile, late, 300 + RELEASE_CONFIRMATION_SECONDS), Err(ReleaseError::NotConfirmed));
+    /// ```
+    pub fn handle(&mut self, profile: &mut PlayerProfile, request: ReleaseRequest, unix_seconds: u64) -> Result<ReleaseOutcome, ReleaseError> {
+        match request {
+            ReleaseRequest::Release { location } => {
+                profile.check_removable(location)?;
+                let token = self.rng.next_u64();
+                let immie = *profile.get_immie(location).unwrap();
+                self.pending.insert(profile.player, PendingRelease { location, token, immie, expires_at: unix_seconds + RELEASE_CONFIRMATION_SECONDS });
+                return Ok(ReleaseOutcome::Prompt(ReleasePrompt { location, token }));
+            },
+            ReleaseRequest::Confirm { location, token } => {
+                let pending = self.pending.remove(&profile.player).ok_or(ReleaseError::NotConfirmed)?;
+                let is_same = pending.location == location && pending.token == token && profile.get_immie(location) == Some(&pending.immie);
+                if !is_same || unix_seconds >= pending.expires_at {
+                    return Err(ReleaseError::NotConfirmed);
+                }
+                return Ok(ReleaseOutcome::Released(profile.take_immie(location)?));
+            },
+            ReleaseRequest::SetLocked { location, is_locked } => {
+                profile.set_locked(location, is_locked)?;
+                if is_locked && self.pending.get(&profile.player).is_some_and(|pending| pending.location == location) {
+                    self.pending.remove(&profile.player);
+                }
+                return Ok(ReleaseOutcome::LockChanged);
+            }
+        }
+    }
+
+    /// Forget a player's pending release, such as when they log out.
+    pub fn cancel(&mut self, player: PlayerId) {
+        self.pending.remove(&player);
+    }
+}
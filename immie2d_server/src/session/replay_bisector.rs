@@ -0,0 +1,205 @@
+use std::fmt;
+
+use immie2d_shared::gameplay::battle::{battle_command::BattleCommand, battle_event::BattleEvent};
+use immie2d_shared::gameplay::game_data::GameDataHandle;
+
+use super::session_snapshot::{SessionRestoreError, SessionSnapshot};
+
+/// Most differing state lines a divergence prints. Once one field differs, a lot of what follows usually does too.
+pub const MAX_PRINTED_DIFFERENCES: usize = 40;
+
+/* A recorded battle and the game data to replay it with. */
+pub struct ReplaySource {
+    pub snapshot: SessionSnapshot,
+    pub data: GameDataHandle
+}
+
+impl ReplaySource {
+    pub fn new(snapshot: SessionSnapshot, data: GameDataHandle) -> ReplaySource {
+        return ReplaySource { snapshot, data };
+    }
+
+    /// Replay the first commands of the battle from scratch.
+    fn replay(&self, command_count: usize) -> ReplayPoint {
+        let mut snapshot = self.snapshot.clone();
+        snapshot.commands.truncate(command_count);
+        return match snapshot.replay(self.data.clone()) {
+            Ok(mut session) => {
+                let battle = session.get_battle_mut();
+                ReplayPoint { events: battle.take_events(), state_hash: Some(battle.get_state_hash()), state_dump: battle.get_state_dump(), error: None }
+            },
+            Err(error) => ReplayPoint { events: Vec::new(), state_hash: None, state_dump: String::new(), error: Some(error) }
+        };
+    }
+}
+
+/* A replayed battle after some of its commands. */
+struct ReplayPoint {
+    /// Every event since the battle started.
+    events: Vec<BattleEvent>,
+    state_hash: Option<[u8; 32]>,
+    state_dump: String,
+    error: Option<SessionRestoreError>
+}
+
+impl ReplayPoint {
+    fn matches(&self, other: &ReplayPoint) -> bool {
+        return self.error == other.error && self.state_hash == other.state_hash && self.events == other.events;
+    }
+}
+
+/* A line of the battle state dumps that differs between two replays. See Battle::get_state_dump() */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StateDifference {
+    /// Starting from 1.
+    pub line: usize,
+    pub left: Option<String>,
+    pub right: Option<String>
+}
+
+/* Where two replays of a battle first stop matching. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct ReplayDivergence {
+    /// How many commands were replayed when the replays first differ. 0 if they differ before any command.
+    pub command_count: usize,
+    /// The command each replay applied last. None before any command, or once a replay has run out of commands.
+    pub last_commands: (Option<BattleCommand>, Option<BattleCommand>),
+    /// The first event that differs, counting from the start of the battle. None if only the state differs.
+    pub event_index: Option<usize>,
+    pub events: (Option<BattleEvent>, Option<BattleEvent>),
+    /// Why either replay couldn't be rebuilt up to the divergence, such as a command that is now invalid.
+    pub errors: (Option<SessionRestoreError>, Option<SessionRestoreError>),
+    pub state_differences: Vec<StateDifference>
+}
+
+/// Find the first command after which two replays of a battle no longer match, comparing their events, state hashes
+/// and whether they could be replayed at all. The replays can be two recordings of the same battle, or a recording
+/// and itself under different game data or a different build. Checks O(log n) prefixes of the commands, replaying each
+/// from scratch, so assumes that once replays differ they never match again, which holds for anything deterministic.
+/// Returns None if the replays match after every command.
+/// ```
+/// # use immie2d_shared::engine_types::global_string::GlobalString;
+/// # use immie2d_shared::gameplay::ability::{ability::Ability, ability_names::AbilityNames, ability_map::AbilityMap, abilities::fireball::Fireball};
+/// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+/// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+/// # use immie2d_shared::gameplay::{immie::immie::Immie, item::item_map::ItemMap};
+/// use immie2d_shared::gameplay::battle::{battler::Battler, battle_side::BattleSide, battle_format::BattleFormat};
+/// use immie2d_shared::gameplay::battle::{battle_command::BattleCommand, battle_event::BattleEvent, rules::battle_ruleset::BattleRuleset};
+/// use immie2d_shared::gameplay::game_data::GameData;
+/// use immie2d_shared::gameplay::player_id::PlayerId;
+/// use immie2d_server::session::{battle_session::BattleSession, session_snapshot::SessionSnapshot};
+/// use immie2d_server::session::replay_bisector::{find_divergence, ReplaySource};
+///
+/// let data_with_attack = |attack: u32| {
+///     let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(500, attack, 40, 70));
+///     let mut species_map = SpeciesMap::new();
+///     species_map.add_species(species);
+///     let mut ability_map = AbilityMap::new();
+///     ability_map.add_ability::<Fireball>();
+///     return (species, GameData::new(1, species_map, ability_map, ItemMap::new()).into_handle());
+/// };
+/// let (species, data) = data_with_attack(60);
+/// let abilities = AbilityNames::new(vec![GlobalString::new(&Fireball::static_name().to_string())]);
+/// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, abilities), &species)]);
+/// let mut session = BattleSession::new(vec![PlayerId(1), PlayerId(2)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side], data.clone());
+/// session.apply_command(BattleCommand::EndTurn).unwrap();
+/// session.apply_command(BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 }).unwrap();
+/// session.apply_command(BattleCommand::EndTurn).unwrap();
+/// let snapshot = SessionSnapshot::new(1, &session);
+///
+/// let recorded = ReplaySource::new(snapshot.clone(), data.clone());
+/// assert_eq!(find_divergence(&recorded, &ReplaySource::new(snapshot.clone(), data.clone())), None);
+///
+/// // A recording where the second command was lost
+/// let mut other = snapshot.clone();
+/// other.commands[1] = BattleCommand::EndTurn;
+/// let divergence = find_divergence(&recorded, &ReplaySource::new(other, data)).unwrap();
+/// assert_eq!(divergence.command_count, 2);
+/// assert_eq!(divergence.last_commands, (Some(BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 }), Some(BattleCommand::EndTurn)));
+/// assert!(divergence.event_index.is_some());
+/// assert!(!divergence.state_differences.is_empty());
+///
+/// // Rebalanced species have different stats from the start
+/// let rebalanced = ReplaySource::new(snapshot, data_with_attack(90).1);
+/// assert_eq!(find_divergence(&recorded, &rebalanced).unwrap().command_count, 0);
+/// ```
+pub fn find_divergence(left: &ReplaySource, right: &ReplaySource) -> Option<ReplayDivergence> {
+    let command_count = left.snapshot.commands.len().max(right.snapshot.commands.len());
+    let matches_after = |count: usize| left.replay(count).matches(&right.replay(count));
+    if matches_after(command_count) {
+        return None;
+    }
+    // Replays match after `low` commands, and differ after `high`
+    let (mut low, mut high) = (0, command_count);
+    if !matches_after(0) {
+        high = 0;
+    }
+    while high - low > 1 {
+        let middle = low + (high - low) / 2;
+        if matches_after(middle) {
+            low = middle;
+        } else {
+            high = middle;
+        }
+    }
+    return Some(describe_divergence(left, right, high));
+}
+
+fn describe_divergence(left: &ReplaySource, right: &ReplaySource, command_count: usize) -> ReplayDivergence {
+    let (left_point, right_point) = (left.replay(command_count), right.replay(command_count));
+    let last_command = |source: &ReplaySource| command_count.checked_sub(1).and_then(|index| source.snapshot.commands.get(index).copied());
+    let event_count = left_point.events.len().max(right_point.events.len());
+    let event_index = (0..event_count).find(|index| left_point.events.get(*index) != right_point.events.get(*index));
+    let events = match event_index {
+        Some(index) => (left_point.events.get(index).copied(), right_point.events.get(index).copied()),
+        None => (None, None)
+    };
+    let (left_lines, right_lines): (Vec<&str>, Vec<&str>) = (left_point.state_dump.lines().collect(), right_point.state_dump.lines().collect());
+    let state_differences = (0..left_lines.len().max(right_lines.len())).filter_map(|index| {
+        let (left_line, right_line) = (left_lines.get(index), right_lines.get(index));
+        if left_line == right_line {
+            return None;
+        }
+        return Some(StateDifference { line: index + 1, left: left_line.map(|line| line.to_string()), right: right_line.map(|line| line.to_string()) });
+    }).collect();
+    return ReplayDivergence {
+        command_count,
+        last_commands: (last_command(left), last_command(right)),
+        event_index,
+        events,
+        errors: (left_point.error, right_point.error),
+        state_differences
+    };
+}
+
+impl fmt::Display for ReplayDivergence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.command_count == 0 {
+            writeln!(f, "Replays differ before any command")?;
+        } else {
+            writeln!(f, "Replays first differ after command {}", self.command_count - 1)?;
+            writeln!(f, "  left command:  {:?}", self.last_commands.0)?;
+            writeln!(f, "  right command: {:?}", self.last_commands.1)?;
+        }
+        if let Some(index) = self.event_index {
+            writeln!(f, "First differing event {}", index)?;
+            writeln!(f, "  left:  {:?}", self.events.0)?;
+            writeln!(f, "  right: {:?}", self.events.1)?;
+        }
+        for (side, error) in [("left", &self.errors.0), ("right", &self.errors.1)] {
+            if let Some(error) = error {
+                writeln!(f, "The {} replay failed: {}", side, error)?;
+            }
+        }
+        writeln!(f, "{} differing state lines", self.state_differences.len())?;
+        for difference in self.state_differences.iter().take(MAX_PRINTED_DIFFERENCES) {
+            writeln!(f, "  line {}", difference.line)?;
+            writeln!(f, "  - {}", difference.left.as_deref().unwrap_or("<missing>"))?;
+            writeln!(f, "  + {}", difference.right.as_deref().unwrap_or("<missing>"))?;
+        }
+        if self.state_differences.len() > MAX_PRINTED_DIFFERENCES {
+            writeln!(f, "  ... and {} more", self.state_differences.len() - MAX_PRINTED_DIFFERENCES)?;
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,545 @@
+use std::collections::HashMap;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::battle::{battle_format::BattleFormat, battle_side::BattleSide};
+use immie2d_shared::gameplay::battle::battle_command::{BattleCommand, BattleCommandError};
+use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+use immie2d_shared::gameplay::battle::state_hash::TurnHash;
+use immie2d_shared::gameplay::game_data::{GameData, GameDataHandle};
+use immie2d_shared::gameplay::immie::ability_edit::{AbilityEditError, AbilityEditRequest};
+use immie2d_shared::gameplay::immie::immie_release::{ReleaseError, ReleaseRequest};
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::gameplay::raid::raid_boss::RaidBoss;
+use immie2d_shared::gameplay::rental::rental_team::{RentalCatalog, RentalError};
+
+use crate::matchmaking::matchmaker::QUICK_BATTLE_FORMAT;
+use crate::network::panic_boundary::{catch_task_panic, TaskPanic};
+use crate::network::send_queue::OutboundMessage;
+use crate::storage::player_profile::PlayerProfile;
+use crate::world::region_instances::{RegionInstanceId, RegionInstances, DEFAULT_REGION_CAPACITY};
+
+use super::battle_session::BattleSession;
+use super::desync_monitor::{DesyncMonitor, DesyncReport};
+use super::raid_session::{RaidError, RaidSession};
+use super::release_confirmations::{ReleaseConfirmations, ReleaseOutcome};
+use super::session_snapshot::{SessionRestoreError, SessionSnapshot};
+
+/* Why a command from a client couldn't be run against a session. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SessionCommandError {
+    UnknownSession,
+    Command(BattleCommandError),
+    Raid(RaidError),
+    /// Running the command panicked, so the session or raid was torn down. Its players should be sent
+    /// create_internal_error_message()
+    Panicked { players: Vec<PlayerId>, panic: TaskPanic }
+}
+
+/* Every running battle session and raid, along with the current generation of game data that new ones start with.
+Reloading data only affects sessions started afterwards. Every command is run behind a panic boundary, so a panic
+only tears down the session it happened in. Every session's state hash is recorded after each command, to check the
+hashes of clients mirroring it. Also tracks which instance of a region each player is in. */
+pub struct SessionManager {
+    data: GameDataHandle,
+    sessions: HashMap<u64, BattleSession>,
+    /// Keyed by session id, like sessions.
+    desync_monitors: HashMap<u64, DesyncMonitor>,
+    /// Raids share ids with sessions.
+    raids: HashMap<u64, RaidSession>,
+    next_session_id: u64,
+    regions: RegionInstances
+}
+
+impl SessionManager {
+    pub fn new(data: GameDataHandle) -> SessionManager {
+        return SessionManager {
+            data,
+            sessions: HashMap::new(),
+            desync_monitors: HashMap::new(),
+            raids: HashMap::new(),
+            next_session_id: 0,
+            regions: RegionInstances::new(DEFAULT_REGION_CAPACITY)
+        };
+    }
+
+    /// Hold a number of players in each instance of a region other than DEFAULT_REGION_CAPACITY.
+    /// Will panic if capacity is 0.
+    pub fn with_region_capacity(mut self, capacity: usize) -> SessionManager {
+        self.regions = RegionInstances::new(capacity);
+        return self;
+    }
+
+    /// The generation of game data new sessions will use.
+    pub fn get_data(&self) -> &GameDataHandle {
+        return &self.data;
+    }
+
+    /// Swap in newly loaded game data. Running sessions keep the data they started with.
+    /// Will panic if the new data isn't a later generation than the current data.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap, species::species_map::SpeciesMap};
+    /// use immie2d_shared::gameplay::battle::{battler::Battler, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::session::session_manager::SessionManager;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species)]);
+    /// let mut manager = SessionManager::new(GameData::new(1, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle());
+    /// let old = manager.start_session(vec![PlayerId(1), PlayerId(2)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side.clone()]);
+    ///
+    /// manager.reload_data(GameData::new(2, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()));
+    /// let new = manager.start_session(vec![PlayerId(3), PlayerId(4)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side]);
+    /// assert_eq!(manager.get_session(old).unwrap().get_data().get_generation(), 1);
+    /// assert_eq!(manager.get_session(new).unwrap().get_data().get_generation(), 2);
+    /// ```
+    pub fn reload_data(&mut self, data: GameData) {
+        assert!(data.get_generation() > self.data.get_generation(), "Reloaded game data generation {} must be later than the current generation {}", data.get_generation(), self.data.get_generation());
+        self.data = data.into_handle();
+    }
+
+    /// Start a session using the current game data. Returns the id of the session.
+    /// See BattleSession::new()
+    pub fn start_session(&mut self, players: Vec<PlayerId>, ruleset: BattleRuleset, format: BattleFormat, sides: Vec<BattleSide>) -> u64 {
+        let id = self.next_session_id;
+        self.next_session_id += 1;
+        self.insert_session(id, BattleSession::new(players, ruleset, format, sides, self.data.clone()));
+        return id;
+    }
+
+    fn insert_session(&mut self, id: u64, session: BattleSession) {
+        let mut monitor = DesyncMonitor::new(id);
+        monitor.record_turn(&session);
+        self.desync_monitors.insert(id, monitor);
+        self.sessions.insert(id, session);
+    }
+
+    /// Start a quick battle between players using rental teams, with the current game data. The players are in side
+    /// order with the name of their rental team. Rental teams never come from a profile, so nothing is persisted.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, abilities::fireball::Fireball};
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::item::item_map::ItemMap;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::rental::rental_team::{RentalCatalog, RentalError};
+    /// use immie2d_server::matchmaking::matchmaker::Matchmaker;
+    /// use immie2d_server::session::session_manager::SessionManager;
+    ///
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Fireball>();
+    /// let mut manager = SessionManager::new(GameData::new(1, species_map, ability_map, ItemMap::new()).into_handle());
+    /// let rentals = RentalCatalog::from_json(r#"[{ "name": "blaze", "immies": [{ "species": "lavapup", "level": 30, "abilities": ["fireball"] }] }]"#).unwrap();
+    ///
+    /// let blaze = GlobalString::new(&"blaze".to_string());
+    /// let mut matchmaker = Matchmaker::new();
+    /// matchmaker.enqueue_quick(PlayerId(1), blaze);
+    /// matchmaker.enqueue_quick(PlayerId(2), blaze);
+    /// let id = manager.start_rental_session(matchmaker.try_form_quick_match().unwrap(), &rentals).unwrap();
+    /// assert_eq!(manager.get_session(id).unwrap().get_battle().get_side(1).get_active().get_immie().level, 30);
+    ///
+    /// let missing = manager.start_rental_session(vec![(PlayerId(3), blaze), (PlayerId(4), GlobalString::new(&"tide".to_string()))], &rentals);
+    /// assert!(matches!(missing, Err(RentalError::UnknownTeam(_))));
+    /// ```
+    pub fn start_rental_session(&mut self, players: Vec<(PlayerId, GlobalString)>, rentals: &RentalCatalog) -> Result<u64, RentalError> {
+        let mut sides = Vec::new();
+        for (_, team) in players.iter() {
+            let rental = rentals.get_team(*team).ok_or(RentalError::UnknownTeam(*team))?;
+            sides.push(rental.create_side(&self.data)?);
+        }
+        let players = players.into_iter().map(|(player, _)| player).collect();
+        return Ok(self.start_session(players, BattleRuleset::Standard, QUICK_BATTLE_FORMAT, sides));
+    }
+
+    pub fn get_session(&self, id: u64) -> Option<&BattleSession> {
+        return self.sessions.get(&id);
+    }
+
+    pub fn get_session_mut(&mut self, id: u64) -> Option<&mut BattleSession> {
+        return self.sessions.get_mut(&id);
+    }
+
+    pub fn get_session_count(&self) -> usize {
+        return self.sessions.len();
+    }
+
+    /// Remove a finished session, releasing its handle to the game data it used.
+    pub fn end_session(&mut self, id: u64) -> Option<BattleSession> {
+        self.desync_monitors.remove(&id);
+        return self.sessions.remove(&id);
+    }
+
+    /// Run a command from a client against a session. A panic while running it, such as from a bug in an ability,
+    /// only tears down that session instead of the whole server.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// # use immie2d_shared::gameplay::item::item_map::ItemMap;
+    /// use immie2d_shared::gameplay::ability::{ability::{Ability, BaseAbilityData}, ability_map::AbilityMap};
+    /// use immie2d_shared::gameplay::battle::{battler::Battler, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::battle_command::{BattleCommand, BattleCommandError};
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::session::session_manager::{SessionManager, SessionCommandError};
+    ///
+    /// struct Broken;
+    /// impl Ability for Broken {
+    ///     fn new() -> Box<dyn Ability> { return Box::new(Broken); }
+    ///     fn get_name(&self) -> &'static str { return "broken"; }
+    ///     fn static_name() -> &'static str { return "broken"; }
+    ///     fn get_base_ability_data(&self) -> &BaseAbilityData { panic!("broken ability data"); }
+    ///     fn get_base_ability_data_mut(&mut self) -> &mut BaseAbilityData { panic!("broken ability data"); }
+    /// }
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Broken>();
+    /// let mut manager = SessionManager::new(GameData::new(1, SpeciesMap::new(), ability_map, ItemMap::new()).into_handle());
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::new(vec![GlobalString::new(&"broken".to_string())])), &species)]);
+    /// let healthy = manager.start_session(vec![PlayerId(1), PlayerId(2)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side.clone()]);
+    /// let broken = manager.start_session(vec![PlayerId(3), PlayerId(4)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side]);
+    ///
+    /// assert_eq!(manager.apply_command(healthy, BattleCommand::Switch { side: 0, slot: 0 }), Err(SessionCommandError::Command(BattleCommandError::InvalidSwitch)));
+    /// let result = manager.apply_command(broken, BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 });
+    /// let Err(SessionCommandError::Panicked { players, panic }) = result else { panic!("expected a panic") };
+    /// assert_eq!(players, vec![PlayerId(3), PlayerId(4)]);
+    /// assert_eq!(panic.message, "broken ability data");
+    /// assert!(manager.get_session(broken).is_none());
+    /// assert!(manager.get_session(healthy).is_some());
+    /// ```
+    pub fn apply_command(&mut self, id: u64, command: BattleCommand) -> Result<(), SessionCommandError> {
+        let session = self.sessions.get_mut(&id).ok_or(SessionCommandError::UnknownSession)?;
+        return match catch_task_panic(&format!("battle session {}", id), || session.apply_command(command)) {
+            Ok(result) => {
+                result.map_err(SessionCommandError::Command)?;
+                self.desync_monitors.get_mut(&id).unwrap().record_turn(session);
+                Ok(())
+            },
+            Err(panic) => {
+                self.desync_monitors.remove(&id);
+                let players = self.sessions.remove(&id).unwrap().get_players().to_vec();
+                Err(SessionCommandError::Panicked { players, panic })
+            }
+        };
+    }
+
+    /// Check the state hash a player mirroring a session sent after a turn. Returns None if it matches, or if the
+    /// session or turn is unknown. Otherwise returns the report to log and the message to resync the player with.
+    /// See DesyncMonitor::check()
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battler::Battler, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battle_command::BattleCommand, rules::battle_ruleset::BattleRuleset, state_hash::TurnHash};
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::session::session_manager::SessionManager;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let data = GameData::new(1, species_map, AbilityMap::new(), ItemMap::new()).into_handle();
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species)]);
+    /// let mut manager = SessionManager::new(data.clone());
+    /// let id = manager.start_session(vec![PlayerId(1), PlayerId(2)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side.clone()]);
+    ///
+    /// // The client mirrors the battle and hashes it once the turn resolves
+    /// let mut mirror = Battle::new(BattleFormat::Single, vec![side.clone(), side]).with_rules(BattleRuleset::Standard.create_plugin());
+    /// manager.apply_command(id, BattleCommand::EndTurn).unwrap();
+    /// mirror.apply_command(BattleCommand::EndTurn, data.get_ability_map(), data.get_species_map()).unwrap();
+    /// let hash = TurnHash { turn: mirror.get_turn(), hash: mirror.get_state_hash() };
+    /// assert!(manager.check_turn_hash(id, PlayerId(1), hash).is_none());
+    ///
+    /// let (report, _resync) = manager.check_turn_hash(id, PlayerId(1), TurnHash { turn: mirror.get_turn(), hash: [0; 32] }).unwrap();
+    /// assert_eq!((report.session, report.command_count), (id, 1));
+    /// assert!(manager.check_turn_hash(id + 1, PlayerId(1), hash).is_none());
+    /// ```
+    pub fn check_turn_hash(&self, id: u64, player: PlayerId, hash: TurnHash) -> Option<(DesyncReport, OutboundMessage)> {
+        return self.desync_monitors.get(&id)?.check(self.sessions.get(&id)?, player, hash);
+    }
+
+    /// End the turn of every session whose turn timer ran out, so sides that didn't choose a command in time do
+    /// nothing instead of holding up the battle. Returns the ids of those sessions, in order, with the result of
+    /// ending the turn. See BattleSession::get_timed_out_sides()
+    pub fn end_expired_turns(&mut self, server_time: u64) -> Vec<(u64, Result<(), SessionCommandError>)> {
+        let mut expired: Vec<u64> = self.sessions.iter().filter(|(_, session)| !session.get_timed_out_sides(server_time).is_empty()).map(|(id, _)| *id).collect();
+        expired.sort();
+        return expired.into_iter().map(|id| (id, self.apply_command(id, BattleCommand::EndTurn))).collect();
+    }
+
+    /// Start a raid using the current game data. Returns the id of the raid. See RaidSession::new()
+    pub fn start_raid(&mut self, players: Vec<PlayerId>, sides: Vec<BattleSide>, boss: RaidBoss, seed: u64) -> u64 {
+        let id = self.next_session_id;
+        self.next_session_id += 1;
+        self.raids.insert(id, RaidSession::new(players, sides, boss, self.data.clone(), seed));
+        return id;
+    }
+
+    pub fn get_raid(&self, id: u64) -> Option<&RaidSession> {
+        return self.raids.get(&id);
+    }
+
+    /// Remove a finished raid, releasing its handle to the game data it used.
+    pub fn end_raid(&mut self, id: u64) -> Option<RaidSession> {
+        return self.raids.remove(&id);
+    }
+
+    /// Choose a player's command for a raid's turn, resolving the turn once every player still standing has chosen.
+    /// Returns the players whose commands were rejected when the turn resolved, if it did. A panic while resolving
+    /// only tears down that raid.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::{ability_names::AbilityNames, abilities::fireball::Fireball};
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// # use immie2d_shared::gameplay::item::item_map::ItemMap;
+    /// use immie2d_shared::gameplay::ability::{ability::{Ability, BaseAbilityData}, ability_map::AbilityMap};
+    /// use immie2d_shared::gameplay::battle::{battler::Battler, battle_side::BattleSide, battle_command::BattleCommand};
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::raid::raid_boss::RaidBoss;
+    /// use immie2d_server::session::raid_session::RaidError;
+    /// use immie2d_server::session::session_manager::{SessionManager, SessionCommandError};
+    ///
+    /// struct Broken;
+    /// impl Ability for Broken {
+    ///     fn new() -> Box<dyn Ability> { return Box::new(Broken); }
+    ///     fn get_name(&self) -> &'static str { return "broken"; }
+    ///     fn static_name() -> &'static str { return "broken"; }
+    ///     fn get_base_ability_data(&self) -> &BaseAbilityData { panic!("broken ability data"); }
+    ///     fn get_base_ability_data_mut(&mut self) -> &mut BaseAbilityData { panic!("broken ability data"); }
+    /// }
+    ///
+    /// let hero = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(100, 200, 60, 90));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(hero);
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Fireball>();
+    /// ability_map.add_ability::<Broken>();
+    /// let mut manager = SessionManager::new(GameData::new(1, species_map, ability_map, ItemMap::new()).into_handle());
+    /// let raid = |manager: &mut SessionManager, ability: &str| {
+    ///     let abilities = AbilityNames::new(vec![GlobalString::new(&ability.to_string())]);
+    ///     let sides = vec![BattleSide::new(vec![Battler::new(Immie::new(hero.name, 50, abilities), &hero)])];
+    ///     return manager.start_raid(vec![PlayerId(1)], sides, RaidBoss::new(Immie::new(hero.name, 50, abilities), 2), 5);
+    /// };
+    /// let (healthy, broken) = (raid(&mut manager, "fireball"), raid(&mut manager, "broken"));
+    /// let attack = BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 };
+    ///
+    /// assert_eq!(manager.submit_raid_command(healthy, PlayerId(2), attack), Err(SessionCommandError::Raid(RaidError::NotInRaid)));
+    /// assert_eq!(manager.submit_raid_command(healthy, PlayerId(1), attack), Ok(Vec::new()));
+    /// assert_eq!(manager.get_raid(healthy).unwrap().get_battle().get_turn(), 2);
+    ///
+    /// let Err(SessionCommandError::Panicked { players, panic }) = manager.submit_raid_command(broken, PlayerId(1), attack) else { panic!("expected a panic") };
+    /// assert_eq!(players, vec![PlayerId(1)]);
+    /// assert_eq!(panic.context, format!("raid {}", broken));
+    /// assert!(manager.get_raid(broken).is_none());
+    /// // Players in a raid can't be matched into another battle
+    /// assert!(manager.is_in_session(PlayerId(1)));
+    /// assert!(!manager.is_in_session(PlayerId(2)));
+    /// manager.end_raid(healthy);
+    /// assert!(!manager.is_in_session(PlayerId(1)));
+    /// ```
+    pub fn submit_raid_command(&mut self, id: u64, player: PlayerId, command: BattleCommand) -> Result<Vec<(PlayerId, BattleCommandError)>, SessionCommandError> {
+        return self.run_raid(id, |raid| {
+            raid.submit(player, command).map_err(SessionCommandError::Raid)?;
+            if !raid.is_turn_ready() {
+                return Ok(Vec::new());
+            }
+            return Ok(raid.resolve_turn());
+        });
+    }
+
+    /// Resolve a raid's turn with whichever commands were chosen, such as when its turn timer runs out. Returns the
+    /// players whose commands were rejected.
+    pub fn resolve_raid_turn(&mut self, id: u64) -> Result<Vec<(PlayerId, BattleCommandError)>, SessionCommandError> {
+        return self.run_raid(id, |raid| {
+            if raid.get_battle().is_finished() {
+                return Err(SessionCommandError::Raid(RaidError::RaidFinished));
+            }
+            return Ok(raid.resolve_turn());
+        });
+    }
+
+    /// Run a task against a raid behind a panic boundary, tearing the raid down if it panics.
+    fn run_raid<R>(&mut self, id: u64, task: impl FnOnce(&mut RaidSession) -> Result<R, SessionCommandError>) -> Result<R, SessionCommandError> {
+        let raid = self.raids.get_mut(&id).ok_or(SessionCommandError::UnknownSession)?;
+        return match catch_task_panic(&format!("raid {}", id), || task(raid)) {
+            Ok(result) => result,
+            Err(panic) => {
+                let players = self.raids.remove(&id).unwrap().get_players().to_vec();
+                Err(SessionCommandError::Panicked { players, panic })
+            }
+        };
+    }
+
+    /// Snapshot every running session, to rebuild them in another process.
+    pub fn get_snapshots(&self) -> Vec<SessionSnapshot> {
+        let mut snapshots: Vec<SessionSnapshot> = self.sessions.iter().map(|(id, session)| SessionSnapshot::new(*id, session)).collect();
+        snapshots.sort_by_key(|snapshot| snapshot.id);
+        return snapshots;
+    }
+
+    /// Rebuild sessions from snapshots using the current game data, keeping their ids. Sessions that can't be rebuilt
+    /// are returned with the reason, so their players can be told the battle was lost.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap, species::species_map::SpeciesMap};
+    /// use immie2d_shared::gameplay::battle::{battler::Battler, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::session::session_manager::SessionManager;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let data = || {
+    ///     let mut species_map = SpeciesMap::new();
+    ///     species_map.add_species(species);
+    ///     return GameData::new(1, species_map, AbilityMap::new(), ItemMap::new()).into_handle();
+    /// };
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species)]);
+    /// let mut old = SessionManager::new(data());
+    /// old.start_session(vec![PlayerId(1), PlayerId(2)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side.clone()]);
+    /// let id = old.start_session(vec![PlayerId(3), PlayerId(4)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side.clone()]);
+    ///
+    /// // The new process loads its own copy of the data
+    /// let mut new = SessionManager::new(data());
+    /// assert!(new.restore_sessions(old.get_snapshots()).is_empty());
+    /// assert_eq!(new.get_session(id).unwrap().get_players(), &[PlayerId(3), PlayerId(4)]);
+    /// // New sessions don't reuse restored ids
+    /// assert!(new.start_session(vec![PlayerId(5), PlayerId(6)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side]) > id);
+    /// ```
+    pub fn restore_sessions(&mut self, snapshots: Vec<SessionSnapshot>) -> Vec<(SessionSnapshot, SessionRestoreError)> {
+        let mut failed = Vec::new();
+        for snapshot in snapshots {
+            self.next_session_id = self.next_session_id.max(snapshot.id + 1);
+            let restored = catch_task_panic(&format!("restoring battle session {}", snapshot.id), || snapshot.restore(self.data.clone()));
+            match restored.unwrap_or_else(|panic| Err(SessionRestoreError::Panicked(panic))) {
+                Ok(session) => self.insert_session(snapshot.id, session),
+                Err(err) => failed.push((snapshot, err))
+            }
+        }
+        return failed;
+    }
+
+    /// Whether a player is in any running battle session or raid.
+    pub fn is_in_session(&self, player: PlayerId) -> bool {
+        return self.sessions.values().any(|session| session.get_side_of(player).is_some())
+            || self.raids.values().any(|raid| raid.get_players().contains(&player));
+    }
+
+    pub fn get_regions(&self) -> &RegionInstances {
+        return &self.regions;
+    }
+
+    /// Change the capacity of particular regions. See RegionInstances::set_capacity()
+    pub fn get_regions_mut(&mut self) -> &mut RegionInstances {
+        return &mut self.regions;
+    }
+
+    /// Route a player arriving in a region to an instance of it, with their party members if there is room.
+    /// See RegionInstances::enter()
+    pub fn enter_region(&mut self, player: PlayerId, map: GlobalString, party: &[PlayerId], unix_seconds: u64) -> RegionInstanceId {
+        return self.regions.enter(player, map, party, unix_seconds);
+    }
+
+    /// Take a disconnecting player out of their region instance.
+    pub fn leave_region(&mut self, player: PlayerId, unix_seconds: u64) -> Option<RegionInstanceId> {
+        return self.regions.leave(player, unix_seconds);
+    }
+
+    /// Close region instances left empty for long enough, called periodically alongside the other server upkeep.
+    /// ```
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap, species::species_map::SpeciesMap};
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::session::session_manager::SessionManager;
+    /// use immie2d_server::world::region_instances::EMPTY_INSTANCE_LIFETIME_SECONDS;
+    ///
+    /// let town = GlobalString::new(&"town".to_string());
+    /// let mut manager = SessionManager::new(GameData::new(1, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle()).with_region_capacity(1);
+    /// manager.enter_region(PlayerId(1), town, &[], 0);
+    /// let crowded = manager.enter_region(PlayerId(2), town, &[PlayerId(1)], 0);
+    /// assert_eq!(crowded.index, 1);
+    /// assert_eq!(manager.leave_region(PlayerId(2), 0), Some(crowded));
+    /// assert_eq!(manager.close_empty_instances(EMPTY_INSTANCE_LIFETIME_SECONDS), vec![crowded]);
+    /// assert_eq!(manager.get_regions().get_instance_count(town), 1);
+    /// ```
+    pub fn close_empty_instances(&mut self, unix_seconds: u64) -> Vec<RegionInstanceId> {
+        return self.regions.close_empty_instances(unix_seconds);
+    }
+
+    /// Apply an ability edit from a client to the player's party using the current game data. Edits are rejected
+    /// while the player is battling. The profile must be saved afterwards to persist the edit.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames};
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::item::item_map::ItemMap;
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battler::Battler, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::immie::ability_edit::{AbilityEdit, AbilityEditRequest, AbilityEditError};
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::session::session_manager::SessionManager;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let (a, b) = (GlobalString::new(&"a".to_string()), GlobalString::new(&"b".to_string()));
+    /// let immie = Immie::new(species.name, 5, AbilityNames::new(vec![a, b]));
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// profile.party.push(immie);
+    /// let mut manager = SessionManager::new(GameData::new(1, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle());
+    ///
+    /// let swap = AbilityEditRequest::decode(&AbilityEditRequest { party_slot: 0, edit: AbilityEdit::Swap { a: 0, b: 1 } }.encode()).unwrap();
+    /// manager.apply_ability_edit(&mut profile, swap).unwrap();
+    /// assert_eq!(profile.party[0].abilities.get_names(), vec![b, a]);
+    /// assert_eq!(PlayerProfile::from_bytes(&profile.to_bytes()).unwrap().party[0].abilities.get_names(), vec![b, a]);
+    /// assert_eq!(manager.apply_ability_edit(&mut profile, AbilityEditRequest { party_slot: 3, edit: AbilityEdit::Forget { slot: 0 } }), Err(AbilityEditError::InvalidPartySlot));
+    ///
+    /// let side = BattleSide::new(vec![Battler::new(immie, &species)]);
+    /// manager.start_session(vec![PlayerId(1), PlayerId(2)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side]);
+    /// assert_eq!(manager.apply_ability_edit(&mut profile, swap), Err(AbilityEditError::InBattle));
+    /// ```
+    pub fn apply_ability_edit(&self, profile: &mut PlayerProfile, request: AbilityEditRequest) -> Result<(), AbilityEditError> {
+        if self.is_in_session(profile.player) {
+            return Err(AbilityEditError::InBattle);
+        }
+        let immie = profile.party.get_mut(request.party_slot).ok_or(AbilityEditError::InvalidPartySlot)?;
+        return immie.apply_ability_edit(request.edit, self.data.get_ability_map());
+    }
+
+    /// Handle a release or lock request from a client. Requests are rejected while the player is battling, since their
+    /// party is in use. See ReleaseConfirmations::handle()
+    pub fn apply_release(&self, confirmations: &mut ReleaseConfirmations, profile: &mut PlayerProfile, request: ReleaseRequest, unix_seconds: u64) -> Result<ReleaseOutcome, ReleaseError> {
+        if self.is_in_session(profile.player) {
+            return Err(ReleaseError::InBattle);
+        }
+        return confirmations.handle(profile, request, unix_seconds);
+    }
+}
@@ -0,0 +1,268 @@
+use std::fmt;
+use std::io::{self, ErrorKind};
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::battle::{battler::Battler, battle_side::BattleSide, battle_format::BattleFormat, field_state::FieldState};
+use immie2d_shared::gameplay::battle::battle_command::{BattleCommand, BattleCommandError};
+use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+use immie2d_shared::gameplay::encounter::encounter_conditions::Weather;
+use immie2d_shared::gameplay::game_data::GameDataHandle;
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::network::panic_boundary::TaskPanic;
+use crate::storage::player_profile::{read_immie, write_immie, ByteReader};
+
+use super::battle_session::BattleSession;
+
+/* Why a session could not be rebuilt from a snapshot. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SessionRestoreError {
+    /// The game data has no species that a team uses, such as after a species was removed in a data reload.
+    UnknownSpecies(GlobalString),
+    /// The game data has no form that a team uses.
+    UnknownForm { species: GlobalString, form: GlobalString },
+    /// The number of players, teams and the format don't agree.
+    MismatchedSides,
+    /// A recorded command failed when replayed, meaning the game data changed in a way that affects the battle.
+    Replay { index: usize, error: BattleCommandError },
+    /// Replaying the commands panicked.
+    Panicked(TaskPanic)
+}
+
+impl fmt::Display for SessionRestoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            SessionRestoreError::UnknownSpecies(species) => write!(f, "Unknown species {}", species),
+            SessionRestoreError::UnknownForm { species, form } => write!(f, "Unknown form {} of species {}", form, species),
+            SessionRestoreError::MismatchedSides => write!(f, "The players, teams and format do not match"),
+            SessionRestoreError::Replay { index, error } => write!(f, "Command {} failed on replay: {:?}", index, error),
+            SessionRestoreError::Panicked(panic) => write!(f, "{}", panic)
+        };
+    }
+}
+
+/* Everything needed to rebuild a battle session in another process. Rather than the battle itself, the snapshot holds
+how it started and every command since, and the battle is rebuilt by replaying them. The battle is deterministic, so
+replaying against the same game data always reaches the same state. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct SessionSnapshot {
+    pub id: u64,
+    pub players: Vec<PlayerId>,
+    pub ruleset: BattleRuleset,
+    pub format: BattleFormat,
+    /// Seed of the battle's rng and speed tie breaks. See Battle::with_seed()
+    pub seed: u64,
+    /// Field conditions the battle started with.
+    pub field: FieldState,
+    pub teams: Vec<Vec<Immie>>,
+    pub commands: Vec<BattleCommand>
+}
+
+impl SessionSnapshot {
+    pub fn new(id: u64, session: &BattleSession) -> SessionSnapshot {
+        return SessionSnapshot {
+            id,
+            players: session.get_players().to_vec(),
+            ruleset: session.get_ruleset(),
+            format: session.get_battle().get_format(),
+            seed: session.get_seed(),
+            field: session.get_initial_field(),
+            teams: session.get_initial_teams().to_vec(),
+            commands: session.get_commands().to_vec()
+        };
+    }
+
+    /// Rebuild the session with some game data and replay its commands.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::{battle_command::BattleCommand, rules::battle_ruleset::BattleRuleset};
+    /// use immie2d_shared::gameplay::battle::field_state::FieldState;
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::Weather;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::session::{battle_session::BattleSession, session_snapshot::{SessionSnapshot, SessionRestoreError}};
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let data = GameData::new(1, species_map, AbilityMap::new(), ItemMap::new()).into_handle();
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species)]);
+    /// let mut session = BattleSession::new(vec![PlayerId(1), PlayerId(2)], BattleRuleset::Standard, BattleFormat::Single, vec![side.clone(), side], data.clone())
+    ///     .with_seed(42)
+    ///     .with_field(FieldState::from_weather(Weather::Rain));
+    /// session.apply_command(BattleCommand::EndTurn).unwrap();
+    ///
+    /// let snapshot = SessionSnapshot::from_bytes(&SessionSnapshot::new(7, &session).to_bytes()).unwrap();
+    /// let restored = snapshot.restore(data.clone()).unwrap();
+    /// assert_eq!(restored.get_battle().get_turn(), session.get_battle().get_turn());
+    /// assert_eq!(restored.get_commands(), session.get_commands());
+    /// assert_eq!(restored.get_battle().get_state_hash(), session.get_battle().get_state_hash());
+    ///
+    /// // Handing the restored session off again still reaches the same state
+    /// let handed_off_twice = SessionSnapshot::from_bytes(&SessionSnapshot::new(7, &restored).to_bytes()).unwrap().restore(data.clone()).unwrap();
+    /// assert_eq!(handed_off_twice.get_battle().get_state_hash(), session.get_battle().get_state_hash());
+    /// assert_eq!(handed_off_twice.get_battle().get_field().weather, Weather::Rain);
+    ///
+    /// // Data without the species can't rebuild the session
+    /// let empty = GameData::new(2, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle();
+    /// assert_eq!(snapshot.restore(empty).err(), Some(SessionRestoreError::UnknownSpecies(species.name)));
+    /// ```
+    pub fn restore(&self, data: GameDataHandle) -> Result<BattleSession, SessionRestoreError> {
+        let mut session = self.replay(data)?;
+        // Viewers already saw the replayed events before the session was snapshotted.
+        session.get_battle_mut().take_events();
+        return Ok(session);
+    }
+
+    /// Rebuild the session like restore(), keeping every event since the battle started.
+    pub(crate) fn replay(&self, data: GameDataHandle) -> Result<BattleSession, SessionRestoreError> {
+        if self.players.len() != self.teams.len() || self.teams.len() != self.format.get_participant_count() as usize {
+            return Err(SessionRestoreError::MismatchedSides);
+        }
+        let species_map = data.get_species_map();
+        let mut sides = Vec::with_capacity(self.teams.len());
+        for team in self.teams.iter() {
+            let mut battlers = Vec::with_capacity(team.len());
+            for immie in team.iter() {
+                if !species_map.is_species_name(immie.species) {
+                    return Err(SessionRestoreError::UnknownSpecies(immie.species));
+                }
+                if let Some(form) = immie.form.filter(|form| species_map.get_form(immie.species, *form).is_none()) {
+                    return Err(SessionRestoreError::UnknownForm { species: immie.species, form });
+                }
+                battlers.push(Battler::new(*immie, &species_map.get_species_of(immie)));
+            }
+            sides.push(BattleSide::new(battlers));
+        }
+        let mut session = BattleSession::new(self.players.clone(), self.ruleset, self.format, sides, data).with_seed(self.seed).with_field(self.field);
+        for (index, command) in self.commands.iter().enumerate() {
+            session.apply_command(*command).map_err(|error| SessionRestoreError::Replay { index, error })?;
+        }
+        return Ok(session);
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.id.to_le_bytes());
+        bytes.extend_from_slice(&(self.players.len() as u32).to_le_bytes());
+        for player in self.players.iter() {
+            bytes.extend_from_slice(&player.0.to_le_bytes());
+        }
+        write_ruleset(&mut bytes, self.ruleset);
+        write_format(&mut bytes, self.format);
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.push(self.field.weather.get_id());
+        bytes.extend_from_slice(&(self.teams.len() as u32).to_le_bytes());
+        for team in self.teams.iter() {
+            bytes.extend_from_slice(&(team.len() as u32).to_le_bytes());
+            for immie in team.iter() {
+                write_immie(&mut bytes, immie);
+            }
+        }
+        let mut commands = Vec::new();
+        for command in self.commands.iter() {
+            commands.extend_from_slice(&command.encode());
+        }
+        bytes.extend_from_slice(&(commands.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&commands);
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<SessionSnapshot> {
+        let mut reader = ByteReader::new(bytes);
+        let snapshot = read_snapshot(&mut reader)?;
+        if reader.get_remaining() != 0 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Trailing bytes after session snapshot"));
+        }
+        return Ok(snapshot);
+    }
+}
+
+/// Read a snapshot from a reader that may hold more data after it.
+pub(crate) fn read_snapshot(reader: &mut ByteReader) -> io::Result<SessionSnapshot> {
+    let id = u64::from_le_bytes(reader.take_array()?);
+    let player_count = u32::from_le_bytes(reader.take_array()?);
+    let mut players = Vec::new();
+    for _ in 0..player_count {
+        players.push(PlayerId(u64::from_le_bytes(reader.take_array()?)));
+    }
+    let ruleset = read_ruleset(reader)?;
+    let format = read_format(reader)?;
+    let seed = u64::from_le_bytes(reader.take_array()?);
+    let [weather] = reader.take_array::<1>()?;
+    let weather = Weather::from_id(weather).ok_or_else(|| io::Error::new(ErrorKind::InvalidData, format!("Unknown weather {}", weather)))?;
+    let team_count = u32::from_le_bytes(reader.take_array()?);
+    let mut teams = Vec::new();
+    for _ in 0..team_count {
+        let immie_count = u32::from_le_bytes(reader.take_array()?);
+        let mut team = Vec::new();
+        for _ in 0..immie_count {
+            team.push(read_immie(reader)?);
+        }
+        teams.push(team);
+    }
+    let command_bytes_length = u32::from_le_bytes(reader.take_array()?) as usize;
+    let command_bytes = reader.take(command_bytes_length)?;
+    let mut commands = Vec::new();
+    let mut offset = 0;
+    while offset < command_bytes.len() {
+        let (command, used) = BattleCommand::decode(&command_bytes[offset..])
+            .map_err(|err| io::Error::new(ErrorKind::InvalidData, format!("Invalid recorded command: {:?}", err)))?;
+        commands.push(command);
+        offset += used;
+    }
+    return Ok(SessionSnapshot { id, players, ruleset, format, seed, field: FieldState::from_weather(weather), teams, commands });
+}
+
+fn write_format(bytes: &mut Vec<u8>, format: BattleFormat) {
+    let (tag, value): (u8, u32) = match format {
+        BattleFormat::Single => (0, 0),
+        BattleFormat::FreeForAll { participants } => (1, participants),
+        BattleFormat::Raid { players } => (2, players)
+    };
+    bytes.push(tag);
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn read_format(reader: &mut ByteReader) -> io::Result<BattleFormat> {
+    let [tag] = reader.take_array::<1>()?;
+    let value = u32::from_le_bytes(reader.take_array()?);
+    return match tag {
+        0 => Ok(BattleFormat::Single),
+        1 => Ok(BattleFormat::FreeForAll { participants: value }),
+        2 => Ok(BattleFormat::Raid { players: value }),
+        _ => Err(io::Error::new(ErrorKind::InvalidData, format!("Unknown battle format {}", tag)))
+    };
+}
+
+fn write_ruleset(bytes: &mut Vec<u8>, ruleset: BattleRuleset) {
+    let (tag, value): (u8, u32) = match ruleset {
+        BattleRuleset::Standard => (0, 0),
+        BattleRuleset::InverseTypes => (1, 0),
+        BattleRuleset::SuddenDeath { turn_limit } => (2, turn_limit),
+        BattleRuleset::LevelCapped { level_cap } => (3, level_cap),
+        BattleRuleset::Retaliation => (4, 0)
+    };
+    bytes.push(tag);
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn read_ruleset(reader: &mut ByteReader) -> io::Result<BattleRuleset> {
+    let [tag] = reader.take_array::<1>()?;
+    let value = u32::from_le_bytes(reader.take_array()?);
+    return match tag {
+        0 => Ok(BattleRuleset::Standard),
+        1 => Ok(BattleRuleset::InverseTypes),
+        2 => Ok(BattleRuleset::SuddenDeath { turn_limit: value }),
+        3 => Ok(BattleRuleset::LevelCapped { level_cap: value }),
+        4 => Ok(BattleRuleset::Retaliation),
+        _ => Err(io::Error::new(ErrorKind::InvalidData, format!("Unknown ruleset {}", tag)))
+    };
+}
@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+
+use immie2d_shared::engine_types::encode_buffer::Encode;
+use immie2d_shared::gameplay::battle::battle_event::BattleEvent;
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::network::send_queue::{MessagePriority, OutboundMessage};
+
+/// How often spectators on healthy connections are sent the events of a battle.
+pub const BROADCAST_INTERVAL_MICROS: u64 = 250_000;
+/// Slowest a spectator is sent to, as a multiple of BROADCAST_INTERVAL_MICROS.
+pub const MAX_INTERVAL_MULTIPLIER: u64 = 8;
+/// Spectators above which a battle is popular enough that cosmetic events are dropped for every spectator.
+pub const POPULAR_SPECTATOR_COUNT: usize = 50;
+
+/// Merge runs of events that a viewer would only see the result of, such as several hits on the same battler, into
+/// a single event. Only neighbouring events are merged, so the order of everything else is kept.
+/// ```
+/// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+/// use immie2d_server::session::spectator_broadcast::coalesce_events;
+///
+/// let (battler, other) = (BattlerId::new(1, 0), BattlerId::new(0, 0));
+/// assert_eq!(coalesce_events(&[
+///     BattleEvent::Damaged { battler, amount: 10, remaining_health: 40 },
+///     BattleEvent::Damaged { battler, amount: 5, remaining_health: 35 },
+///     BattleEvent::Damaged { battler: other, amount: 5, remaining_health: 20 },
+///     BattleEvent::EvasionStageChanged { battler, stage: 1 },
+///     BattleEvent::EvasionStageChanged { battler, stage: 2 }
+/// ]), vec![
+///     BattleEvent::Damaged { battler, amount: 15, remaining_health: 35 },
+///     BattleEvent::Damaged { battler: other, amount: 5, remaining_health: 20 },
+///     BattleEvent::EvasionStageChanged { battler, stage: 2 }
+/// ]);
+/// ```
+pub fn coalesce_events(events: &[BattleEvent]) -> Vec<BattleEvent> {
+    let mut coalesced: Vec<BattleEvent> = Vec::new();
+    for event in events {
+        let merged = match (coalesced.last(), *event) {
+            (Some(BattleEvent::Damaged { battler, amount, .. }), BattleEvent::Damaged { battler: next, amount: next_amount, remaining_health }) if *battler == next => {
+                Some(BattleEvent::Damaged { battler: next, amount: amount + next_amount, remaining_health })
+            },
+            (Some(BattleEvent::Healed { battler, amount, .. }), BattleEvent::Healed { battler: next, amount: next_amount, health }) if *battler == next => {
+                Some(BattleEvent::Healed { battler: next, amount: amount + next_amount, health })
+            },
+            (Some(BattleEvent::EvasionStageChanged { battler, .. }), BattleEvent::EvasionStageChanged { battler: next, .. }) if *battler == next => Some(*event),
+            (Some(BattleEvent::StatusChanged { battler, status, .. }), BattleEvent::StatusChanged { battler: next, status: next_status, .. }) if *battler == next && *status == next_status => {
+                Some(*event)
+            },
+            _ => None
+        };
+        match merged {
+            Some(merged) => *coalesced.last_mut().unwrap() = merged,
+            None => coalesced.push(*event)
+        }
+    }
+    return coalesced;
+}
+
+/// A batch of events for a spectator, encoded back to back. See BattleEvent::decode()
+pub fn get_spectator_message(events: &[BattleEvent]) -> OutboundMessage {
+    let mut payload = Vec::new();
+    for event in events {
+        payload.extend_from_slice(&event.encode_to_vec());
+    }
+    return OutboundMessage::new(MessagePriority::Battle, payload);
+}
+
+struct SpectatorLink {
+    /// Events published since the spectator was last sent a batch.
+    pending: Vec<BattleEvent>,
+    /// Doubles while the spectator's connection is back-pressured and halves once it recovers.
+    interval_multiplier: u64,
+    next_send: u64
+}
+
+/* Sends the events of a battle to its spectators in batches, once per broadcast interval instead of as each event
+happens. Spectators whose connections can't keep up are sent to less often, and get only the events that change what
+they see, as do every spectator of a popular battle. Times are microseconds on the server clock. */
+pub struct SpectatorBroadcast {
+    spectators: HashMap<PlayerId, SpectatorLink>
+}
+
+impl SpectatorBroadcast {
+    pub fn new() -> SpectatorBroadcast {
+        return SpectatorBroadcast { spectators: HashMap::new() };
+    }
+
+    /// Start sending a spectator the events published from now on. They are expected to have been sent the battle
+    /// state when they joined.
+    pub fn add_spectator(&mut self, spectator: PlayerId, server_time: u64) {
+        self.spectators.entry(spectator).or_insert(SpectatorLink { pending: Vec::new(), interval_multiplier: 1, next_send: server_time });
+    }
+
+    pub fn remove_spectator(&mut self, spectator: PlayerId) {
+        self.spectators.remove(&spectator);
+    }
+
+    pub fn get_spectator_count(&self) -> usize {
+        return self.spectators.len();
+    }
+
+    pub fn is_popular(&self) -> bool {
+        return self.spectators.len() > POPULAR_SPECTATOR_COUNT;
+    }
+
+    /// How often a spectator is currently sent batches.
+    pub fn get_interval(&self, spectator: PlayerId) -> Option<u64> {
+        return self.spectators.get(&spectator).map(|link| BROADCAST_INTERVAL_MICROS * link.interval_multiplier);
+    }
+
+    /// Queue events the battle emitted for every spectator.
+    pub fn push_events(&mut self, events: &[BattleEvent]) {
+        for link in self.spectators.values_mut() {
+            link.pending.extend_from_slice(events);
+        }
+    }
+
+    /// Adapt how often a spectator is sent to from whether their send queue is keeping up. See SendQueue::is_back_pressured()
+    pub fn report_connection(&mut self, spectator: PlayerId, is_back_pressured: bool) {
+        if let Some(link) = self.spectators.get_mut(&spectator) {
+            link.interval_multiplier = match is_back_pressured {
+                true => (link.interval_multiplier * 2).min(MAX_INTERVAL_MULTIPLIER),
+                false => (link.interval_multiplier / 2).max(1)
+            };
+        }
+    }
+
+    /// Batch the pending events of every spectator that is due a send, in ascending order of spectator. Cosmetic
+    /// events are dropped for spectators on slowed down connections and for every spectator of a popular battle.
+    /// Spectators with nothing pending aren't sent anything.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::session::spectator_broadcast::{SpectatorBroadcast, get_spectator_message, BROADCAST_INTERVAL_MICROS};
+    ///
+    /// let (healthy, lagging) = (PlayerId(1), PlayerId(2));
+    /// let mut broadcast = SpectatorBroadcast::new();
+    /// broadcast.add_spectator(healthy, 0);
+    /// broadcast.add_spectator(lagging, 0);
+    /// broadcast.report_connection(lagging, true);
+    /// assert_eq!(broadcast.get_interval(lagging), Some(BROADCAST_INTERVAL_MICROS * 2));
+    ///
+    /// let battler = BattlerId::new(1, 0);
+    /// let missed = BattleEvent::AbilityMissed { attacker: BattlerId::new(0, 0), defender: battler };
+    /// let hit = BattleEvent::Damaged { battler, amount: 10, remaining_health: 40 };
+    /// broadcast.push_events(&[missed, hit]);
+    /// broadcast.push_events(&[BattleEvent::Damaged { battler, amount: 5, remaining_health: 35 }]);
+    /// let sent = broadcast.flush(0);
+    /// let merged = BattleEvent::Damaged { battler, amount: 15, remaining_health: 35 };
+    /// assert_eq!(sent, vec![(healthy, get_spectator_message(&[missed, merged])), (lagging, get_spectator_message(&[merged]))]);
+    ///
+    /// // Nobody is due another send until their interval passes
+    /// broadcast.push_events(&[BattleEvent::Fainted { battler }]);
+    /// assert!(broadcast.flush(1).is_empty());
+    /// assert_eq!(broadcast.flush(BROADCAST_INTERVAL_MICROS).len(), 1);
+    /// assert_eq!(broadcast.flush(BROADCAST_INTERVAL_MICROS * 2).len(), 1);
+    /// ```
+    pub fn flush(&mut self, server_time: u64) -> Vec<(PlayerId, OutboundMessage)> {
+        let is_popular = self.is_popular();
+        let mut sent = Vec::new();
+        for (spectator, link) in self.spectators.iter_mut() {
+            if server_time < link.next_send || link.pending.is_empty() {
+                continue;
+            }
+            let is_degraded = is_popular || link.interval_multiplier > 1;
+            let events: Vec<BattleEvent> = link.pending.drain(..).filter(|event| !is_degraded || !event.is_cosmetic()).collect();
+            link.next_send = server_time + BROADCAST_INTERVAL_MICROS * link.interval_multiplier;
+            let events = coalesce_events(&events);
+            if !events.is_empty() {
+                sent.push((*spectator, get_spectator_message(&events)));
+            }
+        }
+        sent.sort_by_key(|(spectator, _)| *spectator);
+        return sent;
+    }
+}
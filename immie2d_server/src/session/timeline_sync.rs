@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+
+use immie2d_shared::gameplay::battle::battle_event::BattleEvent;
+use immie2d_shared::gameplay::battle::event_timeline::{EventTimeline, TimelineAck};
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+/// How far in the future a timeline starts when nothing is playing, so it reaches every viewer before its first event.
+pub const TIMELINE_LEAD_MICROS: u64 = 150_000;
+
+/// How long after a timeline finishes a viewer can go without acknowledging it before it is considered lagging.
+pub const MAX_ACK_DELAY_MICROS: u64 = 2_000_000;
+
+/* Tags the events of a battle with timelines for every player and spectator viewing it, and tracks which timelines
+each viewer has finished animating. Timelines are laid out back to back, so a batch published while the previous one
+is still playing starts once it finishes. Times are microseconds on the server clock. */
+pub struct TimelineSync {
+    /// Number of batches each viewer has acknowledged. Batches are acknowledged in order.
+    viewers: HashMap<PlayerId, u32>,
+    next_batch: u32,
+    end_time: u64
+}
+
+impl TimelineSync {
+    pub fn new() -> TimelineSync {
+        return TimelineSync { viewers: HashMap::new(), next_batch: 0, end_time: 0 };
+    }
+
+    /// Start tracking a viewer. Viewers joining partway through only need to acknowledge timelines published afterwards.
+    pub fn add_viewer(&mut self, viewer: PlayerId) {
+        self.viewers.entry(viewer).or_insert(self.next_batch);
+    }
+
+    pub fn remove_viewer(&mut self, viewer: PlayerId) {
+        self.viewers.remove(&viewer);
+    }
+
+    /// Lay out a batch of events to start once the previous timeline has finished, or after TIMELINE_LEAD_MICROS
+    /// if nothing is playing. The timeline should be sent to every viewer.
+    pub fn publish(&mut self, events: Vec<BattleEvent>, server_time: u64) -> EventTimeline {
+        let start_time = self.end_time.max(server_time + TIMELINE_LEAD_MICROS);
+        let timeline = EventTimeline::from_events(self.next_batch, start_time, events);
+        self.next_batch += 1;
+        self.end_time = timeline.get_end_time();
+        return timeline;
+    }
+
+    /// Record that a viewer finished a timeline, along with every timeline before it. Returns false, recording
+    /// nothing, if the sender isn't a viewer or the batch hasn't been published.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    /// use immie2d_shared::gameplay::battle::event_timeline::TimelineAck;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::session::timeline_sync::{TimelineSync, TIMELINE_LEAD_MICROS, MAX_ACK_DELAY_MICROS};
+    ///
+    /// let (fast, slow) = (PlayerId(1), PlayerId(2));
+    /// let mut sync = TimelineSync::new();
+    /// sync.add_viewer(fast);
+    /// sync.add_viewer(slow);
+    ///
+    /// let first = sync.publish(vec![BattleEvent::Fainted { battler: BattlerId::new(1, 0) }], 0);
+    /// assert_eq!(first.start_time, TIMELINE_LEAD_MICROS);
+    /// // Published while the first is still playing, so it starts when the first ends
+    /// let second = sync.publish(vec![BattleEvent::BattleEnded { winner: Some(0) }], 100_000);
+    /// assert_eq!(second.start_time, first.get_end_time());
+    ///
+    /// assert!(sync.acknowledge(fast, TimelineAck { batch: 1 }));
+    /// assert!(!sync.acknowledge(fast, TimelineAck { batch: 2 }));
+    /// assert!(!sync.is_settled(1));
+    /// assert_eq!(sync.get_lagging_viewers(second.get_end_time() + MAX_ACK_DELAY_MICROS + 1), vec![slow]);
+    /// assert!(sync.acknowledge(slow, TimelineAck { batch: 1 }));
+    /// assert!(sync.is_settled(1));
+    /// ```
+    pub fn acknowledge(&mut self, viewer: PlayerId, ack: TimelineAck) -> bool {
+        if ack.batch >= self.next_batch {
+            return false;
+        }
+        return match self.viewers.get_mut(&viewer) {
+            Some(acknowledged) => {
+                *acknowledged = (*acknowledged).max(ack.batch + 1);
+                true
+            },
+            None => false
+        };
+    }
+
+    /// Whether every viewer has finished a batch, so the battle can move on without anyone missing its events.
+    pub fn is_settled(&self, batch: u32) -> bool {
+        return self.viewers.values().all(|acknowledged| *acknowledged > batch);
+    }
+
+    /// Server time the last published timeline finishes.
+    pub fn get_end_time(&self) -> u64 {
+        return self.end_time;
+    }
+
+    /// Viewers that still haven't finished the last published timeline MAX_ACK_DELAY_MICROS after it ended,
+    /// in ascending order. They can be sent the battle state to skip ahead instead of being waited on.
+    pub fn get_lagging_viewers(&self, server_time: u64) -> Vec<PlayerId> {
+        if self.next_batch == 0 || server_time <= self.end_time + MAX_ACK_DELAY_MICROS {
+            return Vec::new();
+        }
+        let mut lagging: Vec<PlayerId> = self.viewers.iter().filter(|(_, acknowledged)| **acknowledged < self.next_batch).map(|(viewer, _)| *viewer).collect();
+        lagging.sort();
+        return lagging;
+    }
+}
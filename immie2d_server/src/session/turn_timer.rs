@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use super::timeline_sync::TimelineSync;
+
+/// How long each player has to choose a command in PvP battles unless configured otherwise.
+pub const DEFAULT_TURN_LENGTH: Duration = Duration::from_secs(45);
+
+/* The time limit for choosing commands in a PvP battle. A turn starts once the last published timeline has finished
+on the server clock, not when each viewer finishes animating it, so a player skipping animations gains no extra time
+and one watching every animation loses none. Times are microseconds on the server clock. */
+pub struct TurnTimer {
+    turn_length: Duration,
+    deadline: Option<u64>
+}
+
+impl TurnTimer {
+    pub fn new() -> TurnTimer {
+        return TurnTimer { turn_length: DEFAULT_TURN_LENGTH, deadline: None };
+    }
+
+    /// Change how long each turn lasts. Will panic if the length is 0.
+    pub fn with_turn_length(mut self, turn_length: Duration) -> TurnTimer {
+        assert!(!turn_length.is_zero(), "The turn length must be more than 0");
+        self.turn_length = turn_length;
+        return self;
+    }
+
+    /// Start timing a turn, returning its deadline.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    /// use immie2d_server::session::timeline_sync::TimelineSync;
+    /// use immie2d_server::session::turn_timer::TurnTimer;
+    ///
+    /// let mut sync = TimelineSync::new();
+    /// let timeline = sync.publish(vec![BattleEvent::Fainted { battler: BattlerId::new(1, 0) }], 0);
+    /// let mut timer = TurnTimer::new().with_turn_length(Duration::from_secs(30));
+    ///
+    /// // The turn starts when the timeline ends, however fast viewers play it
+    /// assert_eq!(timer.start_turn(&sync, 0), timeline.get_end_time() + 30_000_000);
+    /// assert!(!timer.is_expired(timeline.get_end_time() + 29_000_000));
+    /// assert_eq!(timer.get_remaining(timeline.get_end_time() + 29_000_000), Duration::from_secs(1));
+    /// assert_eq!(timer.get_timed_out_sides(&[true, false], timeline.get_end_time() + 30_000_000), vec![1]);
+    ///
+    /// // Nothing left playing, so the turn starts straight away
+    /// assert_eq!(timer.start_turn(&sync, 50_000_000), 80_000_000);
+    /// timer.stop();
+    /// assert!(!timer.is_expired(u64::MAX));
+    /// ```
+    pub fn start_turn(&mut self, sync: &TimelineSync, server_time: u64) -> u64 {
+        let deadline = sync.get_end_time().max(server_time) + self.turn_length.as_micros() as u64;
+        self.deadline = Some(deadline);
+        return deadline;
+    }
+
+    /// Stop timing, such as once every side has chosen its command or the battle ended.
+    pub fn stop(&mut self) {
+        self.deadline = None;
+    }
+
+    pub fn get_deadline(&self) -> Option<u64> {
+        return self.deadline;
+    }
+
+    /// Time left to choose, or zero if the timer is stopped or expired.
+    pub fn get_remaining(&self, server_time: u64) -> Duration {
+        return match self.deadline {
+            Some(deadline) => Duration::from_micros(deadline.saturating_sub(server_time)),
+            None => Duration::ZERO
+        };
+    }
+
+    pub fn is_expired(&self, server_time: u64) -> bool {
+        return self.deadline.is_some_and(|deadline| server_time >= deadline);
+    }
+
+    /// The sides, in order, that haven't chosen a command by the deadline given whether each side has chosen one.
+    /// Empty until the timer expires.
+    pub fn get_timed_out_sides(&self, has_chosen: &[bool], server_time: u64) -> Vec<usize> {
+        if !self.is_expired(server_time) {
+            return Vec::new();
+        }
+        return has_chosen.iter().enumerate().filter(|(_, chosen)| !**chosen).map(|(side, _)| side).collect();
+    }
+}
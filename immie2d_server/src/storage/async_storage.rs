@@ -0,0 +1,176 @@
+use std::future::Future;
+use std::io::{self, ErrorKind};
+use std::sync::Arc;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::admin::ban_list::BanList;
+use crate::auth::credentials::Credentials;
+
+use super::connection_pool::ConnectionPool;
+use super::player_profile::PlayerProfile;
+use super::query_policy::{is_retryable, QueryPolicy};
+use super::region_state::RegionState;
+use super::storage::Storage;
+
+/// Storage for use from async tasks. Same operations as Storage, but awaiting them never blocks a runtime worker thread.
+pub trait AsyncStorage: Send + Sync {
+    fn load_profile(&self, player: PlayerId) -> impl Future<Output = io::Result<Option<PlayerProfile>>> + Send;
+
+    fn save_profiles(&self, profiles: Vec<PlayerProfile>) -> impl Future<Output = io::Result<()>> + Send;
+
+    fn load_region(&self, map: GlobalString) -> impl Future<Output = io::Result<Option<RegionState>>> + Send;
+
+    fn save_region(&self, region: RegionState) -> impl Future<Output = io::Result<()>> + Send;
+
+    fn load_ban_list(&self) -> impl Future<Output = io::Result<BanList>> + Send;
+
+    fn save_ban_list(&self, bans: BanList) -> impl Future<Output = io::Result<()>> + Send;
+
+    fn load_credentials(&self, username: String) -> impl Future<Output = io::Result<Option<Credentials>>> + Send;
+
+    fn save_credentials(&self, credentials: Credentials) -> impl Future<Output = io::Result<()>> + Send;
+
+    fn create_credentials(&self, credentials: Credentials) -> impl Future<Output = io::Result<bool>> + Send;
+
+    fn record_gift_claim(&self, player: PlayerId, distribution: GlobalString) -> impl Future<Output = io::Result<bool>> + Send;
+
+    fn allocate_player_id(&self) -> impl Future<Output = io::Result<PlayerId>> + Send;
+}
+
+/* Runs a pool of blocking storage connections on tokio's blocking threads, applying the query policy to every call.
+A read that times out keeps running on its blocking thread until it finishes, but its result is discarded. A write that
+times out is waited for before it is retried. */
+pub struct PooledStorage<S: Storage + Send + 'static> {
+    pool: Arc<ConnectionPool<S>>,
+    policy: QueryPolicy
+}
+
+impl<S: Storage + Send + 'static> PooledStorage<S> {
+    pub fn new(connections: Vec<S>, policy: QueryPolicy) -> PooledStorage<S> {
+        return PooledStorage { pool: ConnectionPool::new(connections), policy };
+    }
+
+    pub fn get_pool(&self) -> &Arc<ConnectionPool<S>> {
+        return &self.pool;
+    }
+
+    pub fn get_policy(&self) -> QueryPolicy {
+        return self.policy;
+    }
+
+    /// Run a query that only reads on a pooled connection, retrying retryable failures.
+    async fn run<T, F>(&self, query: F) -> io::Result<T>
+    where
+        T: Send + 'static,
+        F: Fn(&mut S) -> io::Result<T> + Send + Sync + 'static
+    {
+        return self.run_attempts(query, false).await;
+    }
+
+    /// Run a query that writes on a pooled connection. Retrying a write while an attempt that timed out is still running
+    /// could apply it twice, or after a later write, so a timed out attempt is waited for and its result is used.
+    async fn run_write<T, F>(&self, query: F) -> io::Result<T>
+    where
+        T: Send + 'static,
+        F: Fn(&mut S) -> io::Result<T> + Send + Sync + 'static
+    {
+        return self.run_attempts(query, true).await;
+    }
+
+    async fn run_attempts<T, F>(&self, query: F, is_write: bool) -> io::Result<T>
+    where
+        T: Send + 'static,
+        F: Fn(&mut S) -> io::Result<T> + Send + Sync + 'static
+    {
+        let query = Arc::new(query);
+        let mut retry = 0;
+        loop {
+            let pool = self.pool.clone();
+            let attempt_query = query.clone();
+            let timeout = self.policy.timeout;
+            let mut attempt = tokio::task::spawn_blocking(move || {
+                let mut connection = match pool.acquire(timeout) {
+                    Some(connection) => connection,
+                    None => return Err(io::Error::new(ErrorKind::TimedOut, "Timed out waiting for a storage connection"))
+                };
+                return attempt_query(&mut connection);
+            });
+            let joined = match tokio::time::timeout(timeout, &mut attempt).await {
+                Ok(joined) => joined,
+                Err(_) if is_write => attempt.await,
+                Err(_) => Ok(Err(io::Error::new(ErrorKind::TimedOut, "Storage query timed out")))
+            };
+            let result = match joined {
+                Ok(result) => result,
+                Err(join_error) => Err(io::Error::other(format!("Storage query panicked: {}", join_error)))
+            };
+            match result {
+                Err(err) if retry < self.policy.max_retries && is_retryable(&err) => {
+                    retry += 1;
+                    tokio::time::sleep(self.policy.get_retry_delay(retry)).await;
+                },
+                result => return result
+            }
+        }
+    }
+}
+
+impl<S: Storage + Send + 'static> AsyncStorage for PooledStorage<S> {
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::storage::{async_storage::{AsyncStorage, PooledStorage}, memory_storage::MemoryStorage, player_profile::PlayerProfile, query_policy::QueryPolicy};
+    ///
+    /// let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap();
+    /// let storage = PooledStorage::new(vec![MemoryStorage::new()], QueryPolicy::default());
+    /// runtime.block_on(async {
+    ///     let profile = PlayerProfile::new(PlayerId(3), "brock".to_string());
+    ///     storage.save_profiles(vec![profile.clone()]).await.unwrap();
+    ///     assert_eq!(storage.load_profile(PlayerId(3)).await.unwrap(), Some(profile));
+    /// });
+    /// ```
+    async fn load_profile(&self, player: PlayerId) -> io::Result<Option<PlayerProfile>> {
+        return self.run(move |storage| storage.load_profile(player)).await;
+    }
+
+    async fn save_profiles(&self, profiles: Vec<PlayerProfile>) -> io::Result<()> {
+        return self.run_write(move |storage| storage.save_profiles(&profiles)).await;
+    }
+
+    async fn load_region(&self, map: GlobalString) -> io::Result<Option<RegionState>> {
+        return self.run(move |storage| storage.load_region(map)).await;
+    }
+
+    async fn save_region(&self, region: RegionState) -> io::Result<()> {
+        return self.run_write(move |storage| storage.save_region(&region)).await;
+    }
+
+    async fn load_ban_list(&self) -> io::Result<BanList> {
+        return self.run(move |storage| storage.load_ban_list()).await;
+    }
+
+    async fn save_ban_list(&self, bans: BanList) -> io::Result<()> {
+        return self.run_write(move |storage| storage.save_ban_list(&bans)).await;
+    }
+
+    async fn load_credentials(&self, username: String) -> io::Result<Option<Credentials>> {
+        return self.run(move |storage| storage.load_credentials(&username)).await;
+    }
+
+    async fn save_credentials(&self, credentials: Credentials) -> io::Result<()> {
+        return self.run_write(move |storage| storage.save_credentials(&credentials)).await;
+    }
+
+    async fn create_credentials(&self, credentials: Credentials) -> io::Result<bool> {
+        return self.run_write(move |storage| storage.create_credentials(&credentials)).await;
+    }
+
+    async fn record_gift_claim(&self, player: PlayerId, distribution: GlobalString) -> io::Result<bool> {
+        return self.run_write(move |storage| storage.record_gift_claim(player, distribution)).await;
+    }
+
+    async fn allocate_player_id(&self) -> io::Result<PlayerId> {
+        return self.run_write(move |storage| storage.allocate_player_id()).await;
+    }
+}
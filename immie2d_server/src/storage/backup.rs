@@ -0,0 +1,370 @@
+use std::fs;
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use sha2::{Digest, Sha256};
+
+use crate::admin::ban_list::BanList;
+use crate::auth::credentials::Credentials;
+
+use super::player_profile::{ByteReader, PlayerProfile};
+use super::region_state::RegionState;
+use super::storage::Storage;
+
+/// First bytes of every backup archive.
+const BACKUP_MAGIC: &[u8; 8] = b"IMMIEBAK";
+/// Version of the archive format, bumped whenever it changes.
+const BACKUP_VERSION: u32 = 1;
+/// Archive names are this prefix, the unix seconds they were taken at, a number if another backup was taken in the same
+/// second, and BACKUP_EXTENSION.
+const BACKUP_PREFIX: &str = "backup-";
+pub const BACKUP_EXTENSION: &str = "backup";
+
+fn write_record(bytes: &mut Vec<u8>, record: Vec<u8>) {
+    bytes.extend_from_slice(&(record.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&record);
+}
+
+/* Every record in storage at one moment, sorted so the same data always encodes the same way. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct StorageSnapshot {
+    pub profiles: Vec<PlayerProfile>,
+    pub regions: Vec<RegionState>,
+    pub bans: BanList,
+    pub credentials: Vec<Credentials>,
+    /// The next id allocate_player_id() would try.
+    pub next_player_id: u64
+}
+
+impl StorageSnapshot {
+    /// Create a snapshot, sorting profiles by player, regions by map and credentials by username.
+    pub fn new(mut profiles: Vec<PlayerProfile>, mut regions: Vec<RegionState>, bans: BanList, mut credentials: Vec<Credentials>, next_player_id: u64) -> StorageSnapshot {
+        profiles.sort_by_key(|profile| profile.player.0);
+        regions.sort_by_key(|region| region.map.to_string());
+        credentials.sort_by(|first, second| first.username.cmp(&second.username));
+        return StorageSnapshot { profiles, regions, bans, credentials, next_player_id };
+    }
+
+    /// Encode every record, each length prefixed in the binary format used by storage.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::admin::ban_list::BanList;
+    /// use immie2d_server::storage::{backup::StorageSnapshot, player_profile::PlayerProfile, region_state::RegionState};
+    ///
+    /// let profiles = vec![PlayerProfile::new(PlayerId(2), "misty".to_string()), PlayerProfile::new(PlayerId(1), "ash".to_string())];
+    /// let snapshot = StorageSnapshot::new(profiles, vec![RegionState::new(GlobalString::new(&"route 1".to_string()))], BanList::new(), Vec::new(), 3);
+    /// assert_eq!(snapshot.profiles[0].name, "ash");
+    /// assert_eq!(StorageSnapshot::from_bytes(&snapshot.to_bytes()).unwrap(), snapshot);
+    /// assert!(StorageSnapshot::from_bytes(&snapshot.to_bytes()[..20]).is_err());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend_from_slice(&(self.profiles.len() as u32).to_le_bytes());
+        for profile in self.profiles.iter() {
+            write_record(&mut bytes, profile.to_bytes());
+        }
+        bytes.extend_from_slice(&(self.regions.len() as u32).to_le_bytes());
+        for region in self.regions.iter() {
+            write_record(&mut bytes, region.to_bytes());
+        }
+        write_record(&mut bytes, self.bans.to_bytes());
+        bytes.extend_from_slice(&(self.credentials.len() as u32).to_le_bytes());
+        for credentials in self.credentials.iter() {
+            write_record(&mut bytes, credentials.to_bytes());
+        }
+        bytes.extend_from_slice(&self.next_player_id.to_le_bytes());
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<StorageSnapshot> {
+        let mut reader = ByteReader::new(bytes);
+        let take_record = |reader: &mut ByteReader| -> io::Result<Vec<u8>> {
+            let length = u32::from_le_bytes(reader.take_array()?) as usize;
+            return Ok(reader.take(length)?.to_vec());
+        };
+        let mut profiles = Vec::new();
+        for _ in 0..u32::from_le_bytes(reader.take_array()?) {
+            profiles.push(PlayerProfile::from_bytes(&take_record(&mut reader)?)?);
+        }
+        let mut regions = Vec::new();
+        for _ in 0..u32::from_le_bytes(reader.take_array()?) {
+            regions.push(RegionState::from_bytes(&take_record(&mut reader)?)?);
+        }
+        let bans = BanList::from_bytes(&take_record(&mut reader)?)?;
+        let mut credentials = Vec::new();
+        for _ in 0..u32::from_le_bytes(reader.take_array()?) {
+            credentials.push(Credentials::from_bytes(&take_record(&mut reader)?)?);
+        }
+        let next_player_id = u64::from_le_bytes(reader.take_array()?);
+        if reader.get_remaining() > 0 {
+            return Err(io::Error::new(ErrorKind::InvalidData, "Unexpected bytes after the end of the snapshot"));
+        }
+        return Ok(StorageSnapshot::new(profiles, regions, bans, credentials, next_player_id));
+    }
+}
+
+/* A backup archive in a backup directory. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BackupInfo {
+    pub path: PathBuf,
+    /// Unix seconds the backup was taken at.
+    pub created_at: u64
+}
+
+/* How many backups to keep. The newest backup is always kept, whatever the policy. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RetentionPolicy {
+    /// Backups beyond this many of the newest are deleted.
+    pub keep_latest: usize,
+    /// Backups older than this many seconds are deleted, or None to keep backups of any age.
+    pub max_age_seconds: Option<u64>
+}
+
+impl RetentionPolicy {
+    /// Keep a week of daily backups.
+    pub fn default() -> RetentionPolicy {
+        return RetentionPolicy { keep_latest: 7, max_age_seconds: None };
+    }
+
+    /// Whether a backup should be kept, given how many backups are newer than it.
+    fn keeps(&self, backup: &BackupInfo, newer_count: usize, now: u64) -> bool {
+        if newer_count == 0 {
+            return true;
+        }
+        let is_expired = self.max_age_seconds.is_some_and(|max_age| now.saturating_sub(backup.created_at) > max_age);
+        return newer_count < self.keep_latest && !is_expired;
+    }
+}
+
+/* Where backups are written, how often they're taken, and how many are kept. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BackupConfig {
+    pub directory: PathBuf,
+    /// Seconds between scheduled backups, or 0 to only back up on demand. See BackupScheduler
+    pub interval_seconds: u64,
+    pub retention: RetentionPolicy
+}
+
+impl BackupConfig {
+    pub fn default() -> BackupConfig {
+        return BackupConfig { directory: PathBuf::from("backups"), interval_seconds: 0, retention: RetentionPolicy::default() };
+    }
+}
+
+fn get_backup_path(directory: &Path, created_at: u64, index: u32) -> PathBuf {
+    if index == 0 {
+        return directory.join(format!("{}{}.{}", BACKUP_PREFIX, created_at, BACKUP_EXTENSION));
+    }
+    return directory.join(format!("{}{}-{}.{}", BACKUP_PREFIX, created_at, index, BACKUP_EXTENSION));
+}
+
+/// The unix seconds a backup was taken at and its number within that second, from its file name, or None if it isn't
+/// named like a backup.
+fn parse_backup_name(path: &Path) -> Option<(u64, u32)> {
+    let stem = path.file_stem()?.to_str()?.strip_prefix(BACKUP_PREFIX)?;
+    return match stem.split_once('-') {
+        Some((time, index)) => Some((time.parse::<u64>().ok()?, index.parse::<u32>().ok()?)),
+        None => Some((stem.parse::<u64>().ok()?, 0))
+    };
+}
+
+/// Distinguishes the temporary files of backups written at once by threads of the same process.
+static NEXT_TEMPORARY_BACKUP: AtomicU64 = AtomicU64::new(0);
+
+/// Export a consistent snapshot of storage to an archive named after the time it was taken, in a directory that is
+/// created if it doesn't exist. The archive records its format version and a SHA-256 checksum of the snapshot, and is
+/// only linked into place once fully written. A backup taken in the same second as another, even by another process, is
+/// numbered after it rather than replacing it.
+/// ```
+/// use immie2d_shared::gameplay::player_id::PlayerId;
+/// use immie2d_server::storage::{backup::{create_backup, read_backup, restore_backup}, memory_storage::MemoryStorage, player_profile::PlayerProfile, storage::Storage};
+///
+/// let directory = std::env::temp_dir().join("immie2d_backup_doctest");
+/// # let _ = std::fs::remove_dir_all(&directory);
+/// let mut storage = MemoryStorage::new();
+/// storage.save_profiles(&[PlayerProfile::new(PlayerId(1), "ash".to_string())]).unwrap();
+/// let backup = create_backup(&mut storage, &directory, 1000).unwrap();
+/// assert_eq!(backup.created_at, 1000);
+/// let same_second = create_backup(&mut storage, &directory, 1000).unwrap();
+/// assert_ne!(same_second.path, backup.path);
+/// assert_eq!(immie2d_server::storage::backup::list_backups(&directory).unwrap().len(), 2);
+///
+/// // Restoring replaces everything saved since
+/// storage.save_profiles(&[PlayerProfile::new(PlayerId(2), "misty".to_string())]).unwrap();
+/// restore_backup(&mut storage, &backup.path).unwrap();
+/// assert!(storage.load_profile(PlayerId(1)).unwrap().is_some());
+/// assert_eq!(storage.load_profile(PlayerId(2)).unwrap(), None);
+///
+/// // A damaged archive is refused before storage is touched
+/// let mut bytes = std::fs::read(&backup.path).unwrap();
+/// *bytes.last_mut().unwrap() ^= 1;
+/// std::fs::write(&backup.path, bytes).unwrap();
+/// assert!(read_backup(&backup.path).is_err());
+/// assert!(restore_backup(&mut storage, &backup.path).is_err());
+/// assert!(storage.load_profile(PlayerId(1)).unwrap().is_some());
+/// ```
+pub fn create_backup<S: Storage + ?Sized>(storage: &mut S, directory: &Path, now: u64) -> io::Result<BackupInfo> {
+    let payload = storage.export_snapshot()?.to_bytes();
+    let mut bytes = Vec::with_capacity(payload.len() + 60);
+    bytes.extend_from_slice(BACKUP_MAGIC);
+    bytes.extend_from_slice(&BACKUP_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&now.to_le_bytes());
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(&Sha256::digest(&payload));
+    bytes.extend_from_slice(&payload);
+    fs::create_dir_all(directory)?;
+    let temporary = directory.join(format!("{}{}-{}.tmp", BACKUP_PREFIX, process::id(), NEXT_TEMPORARY_BACKUP.fetch_add(1, Ordering::Relaxed)));
+    fs::write(&temporary, bytes)?;
+    // Linking fails if the name is taken, so two backups never claim the same name
+    let mut index = 0;
+    let linked = loop {
+        let path = get_backup_path(directory, now, index);
+        match fs::hard_link(&temporary, &path) {
+            Ok(()) => break Ok(path),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => index += 1,
+            Err(err) => break Err(err)
+        }
+    };
+    fs::remove_file(&temporary)?;
+    return Ok(BackupInfo { path: linked?, created_at: now });
+}
+
+/// Read a backup archive, verifying its format version and checksum.
+/// Fails with ErrorKind::InvalidData if the archive is damaged or from an unsupported version.
+pub fn read_backup(path: &Path) -> io::Result<(BackupInfo, StorageSnapshot)> {
+    let bytes = fs::read(path)?;
+    let mut reader = ByteReader::new(&bytes);
+    if reader.take(BACKUP_MAGIC.len())? != BACKUP_MAGIC {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("{} is not a backup archive", path.display())));
+    }
+    let version = u32::from_le_bytes(reader.take_array()?);
+    if version != BACKUP_VERSION {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("Backup format version {} is not supported, expected {}", version, BACKUP_VERSION)));
+    }
+    let created_at = u64::from_le_bytes(reader.take_array()?);
+    let length = u64::from_le_bytes(reader.take_array()?) as usize;
+    let checksum: [u8; 32] = reader.take_array()?;
+    if reader.get_remaining() != length {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("Backup is {} bytes long but should be {}", reader.get_remaining(), length)));
+    }
+    let payload = reader.take(length)?;
+    if Sha256::digest(payload).as_slice() != checksum {
+        return Err(io::Error::new(ErrorKind::InvalidData, "Backup checksum does not match, so the archive is damaged"));
+    }
+    return Ok((BackupInfo { path: path.to_path_buf(), created_at }, StorageSnapshot::from_bytes(payload)?));
+}
+
+/// Replace everything in storage with a verified backup, then check storage reads back exactly what was restored.
+/// Nothing is changed if the archive fails verification. See read_backup()
+pub fn restore_backup<S: Storage + ?Sized>(storage: &mut S, path: &Path) -> io::Result<BackupInfo> {
+    let (info, snapshot) = read_backup(path)?;
+    storage.import_snapshot(&snapshot)?;
+    if storage.export_snapshot()? != snapshot {
+        return Err(io::Error::other("Storage does not match the backup after restoring it"));
+    }
+    return Ok(info);
+}
+
+/// Every backup in a directory, oldest first. A directory that doesn't exist has no backups.
+pub fn list_backups(directory: &Path) -> io::Result<Vec<BackupInfo>> {
+    let entries = match fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err)
+    };
+    let mut backups = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().and_then(|extension| extension.to_str()) != Some(BACKUP_EXTENSION) {
+            continue;
+        }
+        if let Some(name) = parse_backup_name(&path) {
+            backups.push((name, BackupInfo { path, created_at: name.0 }));
+        }
+    }
+    backups.sort_by_key(|(name, _)| *name);
+    return Ok(backups.into_iter().map(|(_, backup)| backup).collect());
+}
+
+/// Delete the backups in a directory the retention policy doesn't keep. Returns the deleted backups, oldest first.
+/// ```
+/// use immie2d_server::storage::{backup::{create_backup, list_backups, prune_backups, RetentionPolicy}, memory_storage::MemoryStorage};
+///
+/// let directory = std::env::temp_dir().join("immie2d_backup_prune_doctest");
+/// # let _ = std::fs::remove_dir_all(&directory);
+/// let mut storage = MemoryStorage::new();
+/// for time in [100, 200, 300, 400] {
+///     create_backup(&mut storage, &directory, time).unwrap();
+/// }
+/// let deleted = prune_backups(&directory, &RetentionPolicy { keep_latest: 3, max_age_seconds: Some(250) }, 500).unwrap();
+/// assert_eq!(deleted.iter().map(|backup| backup.created_at).collect::<Vec<u64>>(), vec![100, 200]);
+/// assert_eq!(list_backups(&directory).unwrap().len(), 2);
+///
+/// // The newest backup is kept however old it is
+/// prune_backups(&directory, &RetentionPolicy { keep_latest: 0, max_age_seconds: Some(0) }, 10_000).unwrap();
+/// assert_eq!(list_backups(&directory).unwrap()[0].created_at, 400);
+/// ```
+pub fn prune_backups(directory: &Path, policy: &RetentionPolicy, now: u64) -> io::Result<Vec<BackupInfo>> {
+    let backups = list_backups(directory)?;
+    let mut deleted = Vec::new();
+    for (index, backup) in backups.iter().enumerate() {
+        if !policy.keeps(backup, backups.len() - index - 1, now) {
+            fs::remove_file(&backup.path)?;
+            deleted.push(backup.clone());
+        }
+    }
+    return Ok(deleted);
+}
+
+/* Takes a backup whenever the configured interval has passed, pruning old backups after each. */
+pub struct BackupScheduler {
+    config: BackupConfig,
+    /// Unix seconds the next backup is due at.
+    next_due: u64
+}
+
+impl BackupScheduler {
+    /// Schedule the first backup an interval after now.
+    pub fn new(config: BackupConfig, now: u64) -> BackupScheduler {
+        let next_due = now + config.interval_seconds;
+        return BackupScheduler { config, next_due };
+    }
+
+    pub fn get_config(&self) -> &BackupConfig {
+        return &self.config;
+    }
+
+    /// Whether a scheduled backup is due. Never true if the interval is 0.
+    pub fn is_due(&self, now: u64) -> bool {
+        return self.config.interval_seconds > 0 && now >= self.next_due;
+    }
+
+    /// Back up if a backup is due, returning it. A failed backup is retried an interval later rather than on every tick.
+    /// ```
+    /// use immie2d_server::storage::{backup::{list_backups, BackupConfig, BackupScheduler, RetentionPolicy}, memory_storage::MemoryStorage};
+    ///
+    /// let directory = std::env::temp_dir().join("immie2d_backup_scheduler_doctest");
+    /// # let _ = std::fs::remove_dir_all(&directory);
+    /// let config = BackupConfig { directory: directory.clone(), interval_seconds: 60, retention: RetentionPolicy { keep_latest: 2, max_age_seconds: None } };
+    /// let mut scheduler = BackupScheduler::new(config, 1000);
+    /// let mut storage = MemoryStorage::new();
+    /// assert_eq!(scheduler.tick(&mut storage, 1059).unwrap(), None);
+    /// assert_eq!(scheduler.tick(&mut storage, 1060).unwrap().unwrap().created_at, 1060);
+    /// assert_eq!(scheduler.tick(&mut storage, 1100).unwrap(), None);
+    /// scheduler.tick(&mut storage, 1120).unwrap().unwrap();
+    /// scheduler.tick(&mut storage, 1180).unwrap().unwrap();
+    /// assert_eq!(list_backups(&directory).unwrap().iter().map(|backup| backup.created_at).collect::<Vec<u64>>(), vec![1120, 1180]);
+    /// ```
+    pub fn tick<S: Storage + ?Sized>(&mut self, storage: &mut S, now: u64) -> io::Result<Option<BackupInfo>> {
+        if !self.is_due(now) {
+            return Ok(None);
+        }
+        self.next_due = now + self.config.interval_seconds;
+        let backup = create_backup(storage, &self.config.directory, now)?;
+        prune_backups(&self.config.directory, &self.config.retention, now)?;
+        return Ok(Some(backup));
+    }
+}
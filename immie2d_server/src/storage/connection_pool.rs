@@ -0,0 +1,95 @@
+use std::ops::{Deref, DerefMut};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+struct PoolState<C> {
+    idle: Vec<C>,
+    size: usize
+}
+
+/* A fixed set of connections shared between threads. Acquiring blocks until a connection is free, so it must only be
+called from blocking threads, never directly from async tasks. */
+pub struct ConnectionPool<C> {
+    state: Mutex<PoolState<C>>,
+    available: Condvar
+}
+
+/// A connection borrowed from a pool, returned to it when dropped.
+pub struct PooledConnection<C> {
+    pool: Arc<ConnectionPool<C>>,
+    connection: Option<C>
+}
+
+impl<C> ConnectionPool<C> {
+    /// Create a pool from already opened connections. Will panic if there are none.
+    pub fn new(connections: Vec<C>) -> Arc<ConnectionPool<C>> {
+        assert!(!connections.is_empty(), "A connection pool needs at least one connection");
+        let size = connections.len();
+        return Arc::new(ConnectionPool { state: Mutex::new(PoolState { idle: connections, size }), available: Condvar::new() });
+    }
+
+    /// Total number of connections, including ones in use.
+    pub fn get_size(&self) -> usize {
+        return self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).size;
+    }
+
+    pub fn get_idle_count(&self) -> usize {
+        return self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).idle.len();
+    }
+
+    /// Borrow a connection, waiting up to a timeout for one to be returned if they are all in use.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_server::storage::connection_pool::ConnectionPool;
+    ///
+    /// let pool = ConnectionPool::new(vec![1, 2]);
+    /// let first = pool.acquire(Duration::from_millis(10)).unwrap();
+    /// let second = pool.acquire(Duration::from_millis(10)).unwrap();
+    /// assert_eq!(*first + *second, 3);
+    /// assert!(pool.acquire(Duration::from_millis(10)).is_none());
+    /// drop(first);
+    /// assert!(pool.acquire(Duration::from_millis(10)).is_some());
+    /// ```
+    pub fn acquire(self: &Arc<Self>, timeout: Duration) -> Option<PooledConnection<C>> {
+        let deadline = Instant::now() + timeout;
+        // Connections are only pushed and popped while locked, so a pool poisoned by a panicking thread is still valid
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        loop {
+            if let Some(connection) = state.idle.pop() {
+                return Some(PooledConnection { pool: self.clone(), connection: Some(connection) });
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return None;
+            }
+            state = self.available.wait_timeout(state, deadline - now).unwrap_or_else(|poisoned| poisoned.into_inner()).0;
+        }
+    }
+
+    fn release(&self, connection: C) {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).idle.push(connection);
+        self.available.notify_one();
+    }
+}
+
+impl<C> Deref for PooledConnection<C> {
+    type Target = C;
+
+    fn deref(&self) -> &C {
+        return self.connection.as_ref().unwrap();
+    }
+}
+
+impl<C> DerefMut for PooledConnection<C> {
+    fn deref_mut(&mut self) -> &mut C {
+        return self.connection.as_mut().unwrap();
+    }
+}
+
+impl<C> Drop for PooledConnection<C> {
+    fn drop(&mut self) {
+        if let Some(connection) = self.connection.take() {
+            self.pool.release(connection);
+        }
+    }
+}
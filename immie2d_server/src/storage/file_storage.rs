@@ -0,0 +1,323 @@
+use std::fs::{self, File};
+use std::io::{self, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::admin::ban_list::BanList;
+use crate::auth::credentials::{normalize_username, Credentials};
+
+use super::backup::StorageSnapshot;
+use super::player_profile::PlayerProfile;
+use super::region_state::RegionState;
+use super::storage::Storage;
+
+const PROFILES_DIRECTORY: &str = "profiles";
+const REGIONS_DIRECTORY: &str = "regions";
+const BAN_LIST_FILE: &str = "bans.list";
+const CREDENTIALS_DIRECTORY: &str = "credentials";
+const GIFT_CLAIMS_DIRECTORY: &str = "gift_claims";
+const NEXT_PLAYER_ID_FILE: &str = "next_player.id";
+/// Locked by every write and export, so an export never sees a save half done, even one made by another process.
+const LOCK_FILE: &str = "storage.lock";
+
+/* Storage as a directory of files, one per profile, region, account and gift claim. Suitable for single-server deployments. */
+pub struct FileStorage {
+    directory: PathBuf
+}
+
+impl FileStorage {
+    /// Open storage in a directory, creating it if it doesn't exist.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::storage::{file_storage::FileStorage, player_profile::PlayerProfile, storage::Storage};
+    ///
+    /// let directory = std::env::temp_dir().join("immie2d_file_storage_doctest");
+    /// # let _ = std::fs::remove_dir_all(&directory);
+    /// let profile = PlayerProfile::new(PlayerId(9), "misty".to_string());
+    /// FileStorage::open(&directory).unwrap().save_profiles(&[profile.clone()]).unwrap();
+    ///
+    /// let mut reopened = FileStorage::open(&directory).unwrap();
+    /// assert_eq!(reopened.load_profile(PlayerId(9)).unwrap(), Some(profile));
+    /// assert_eq!(reopened.load_profile(PlayerId(10)).unwrap(), None);
+    /// ```
+    pub fn open(directory: &Path) -> io::Result<FileStorage> {
+        fs::create_dir_all(directory.join(PROFILES_DIRECTORY))?;
+        fs::create_dir_all(directory.join(REGIONS_DIRECTORY))?;
+        fs::create_dir_all(directory.join(CREDENTIALS_DIRECTORY))?;
+        fs::create_dir_all(directory.join(GIFT_CLAIMS_DIRECTORY))?;
+        return Ok(FileStorage { directory: directory.to_path_buf() });
+    }
+
+    fn get_profile_path(&self, player: PlayerId) -> PathBuf {
+        return self.directory.join(PROFILES_DIRECTORY).join(format!("{}.profile", player.0));
+    }
+
+    fn get_region_path(&self, map: GlobalString) -> PathBuf {
+        return self.directory.join(REGIONS_DIRECTORY).join(format!("{}.region", encode_file_name(map)));
+    }
+
+    /// A claim is an empty file, so creating it is the whole record.
+    fn get_gift_claim_path(&self, player: PlayerId, distribution: GlobalString) -> PathBuf {
+        return self.directory.join(GIFT_CLAIMS_DIRECTORY).join(format!("{}.{}.claim", player.0, encode_file_name(distribution)));
+    }
+
+    /// Normalized usernames are always valid file names.
+    fn get_credentials_path(&self, username: &str) -> PathBuf {
+        return self.directory.join(CREDENTIALS_DIRECTORY).join(format!("{}.credentials", username));
+    }
+
+    /// Lock storage until the returned file is dropped. Writes lock exclusively, and exports share the lock with each other.
+    fn lock(&self, is_exclusive: bool) -> io::Result<File> {
+        let file = File::options().create(true).truncate(false).write(true).open(self.directory.join(LOCK_FILE))?;
+        if is_exclusive {
+            file.lock()?;
+        } else {
+            file.lock_shared()?;
+        }
+        return Ok(file);
+    }
+
+    fn read_next_player_id(&self) -> io::Result<u64> {
+        return match read_if_exists(&self.directory.join(NEXT_PLAYER_ID_FILE))? {
+            Some(bytes) => Ok(u64::from_le_bytes(bytes.try_into().map_err(|_| io::Error::new(ErrorKind::InvalidData, "Invalid next player id"))?)),
+            None => Ok(1)
+        };
+    }
+}
+
+/// Names can contain characters that aren't valid in file names, so they are hex encoded.
+fn encode_file_name(name: GlobalString) -> String {
+    return name.to_string().bytes().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+fn read_if_exists(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    return match fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err)
+    };
+}
+
+fn get_temporary_path(path: &Path) -> PathBuf {
+    return path.with_extension("tmp");
+}
+
+/// Parse every record in a directory with an extension, skipping temporary files left by an interrupted save.
+fn read_records<T>(directory: &Path, extension: &str, parse: impl Fn(&[u8]) -> io::Result<T>) -> io::Result<Vec<T>> {
+    let mut records = Vec::new();
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().and_then(|path_extension| path_extension.to_str()) == Some(extension) {
+            records.push(parse(&fs::read(&path)?)?);
+        }
+    }
+    return Ok(records);
+}
+
+/// Delete every record in a directory with an extension that isn't being kept.
+fn remove_records(directory: &Path, extension: &str, kept: &[PathBuf]) -> io::Result<()> {
+    for entry in fs::read_dir(directory)? {
+        let path = entry?.path();
+        if path.extension().and_then(|path_extension| path_extension.to_str()) == Some(extension) && !kept.contains(&path) {
+            fs::remove_file(&path)?;
+        }
+    }
+    return Ok(());
+}
+
+impl Storage for FileStorage {
+    fn load_profile(&mut self, player: PlayerId) -> io::Result<Option<PlayerProfile>> {
+        return match read_if_exists(&self.get_profile_path(player))? {
+            Some(bytes) => Ok(Some(PlayerProfile::from_bytes(&bytes)?)),
+            None => Ok(None)
+        };
+    }
+
+    /// Every profile is written to a temporary file before any are renamed into place, so failing to write
+    /// leaves every saved profile as it was.
+    fn save_profiles(&mut self, profiles: &[PlayerProfile]) -> io::Result<()> {
+        let _lock = self.lock(true)?;
+        let paths: Vec<PathBuf> = profiles.iter().map(|profile| self.get_profile_path(profile.player)).collect();
+        for (profile, path) in profiles.iter().zip(paths.iter()) {
+            fs::write(get_temporary_path(path), profile.to_bytes())?;
+        }
+        for path in paths.iter() {
+            fs::rename(get_temporary_path(path), path)?;
+        }
+        return Ok(());
+    }
+
+    fn load_region(&mut self, map: GlobalString) -> io::Result<Option<RegionState>> {
+        return match read_if_exists(&self.get_region_path(map))? {
+            Some(bytes) => Ok(Some(RegionState::from_bytes(&bytes)?)),
+            None => Ok(None)
+        };
+    }
+
+    fn save_region(&mut self, region: &RegionState) -> io::Result<()> {
+        let _lock = self.lock(true)?;
+        let path = self.get_region_path(region.map);
+        fs::write(get_temporary_path(&path), region.to_bytes())?;
+        return fs::rename(get_temporary_path(&path), path);
+    }
+
+    fn load_ban_list(&mut self) -> io::Result<BanList> {
+        return match read_if_exists(&self.directory.join(BAN_LIST_FILE))? {
+            Some(bytes) => BanList::from_bytes(&bytes),
+            None => Ok(BanList::new())
+        };
+    }
+
+    fn save_ban_list(&mut self, bans: &BanList) -> io::Result<()> {
+        let _lock = self.lock(true)?;
+        let path = self.directory.join(BAN_LIST_FILE);
+        fs::write(get_temporary_path(&path), bans.to_bytes())?;
+        return fs::rename(get_temporary_path(&path), path);
+    }
+
+    fn load_credentials(&mut self, username: &str) -> io::Result<Option<Credentials>> {
+        if normalize_username(username).as_deref() != Some(username) {
+            return Ok(None);
+        }
+        return match read_if_exists(&self.get_credentials_path(username))? {
+            Some(bytes) => Ok(Some(Credentials::from_bytes(&bytes)?)),
+            None => Ok(None)
+        };
+    }
+
+    fn save_credentials(&mut self, credentials: &Credentials) -> io::Result<()> {
+        let _lock = self.lock(true)?;
+        let path = self.get_credentials_path(&credentials.username);
+        fs::write(get_temporary_path(&path), credentials.to_bytes())?;
+        return fs::rename(get_temporary_path(&path), path);
+    }
+
+    /// The record is linked into place from a temporary file, which fails if the record already exists, so it is
+    /// never seen half written and an existing account is never replaced.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::auth::credentials::Credentials;
+    /// use immie2d_server::storage::{file_storage::FileStorage, storage::Storage};
+    ///
+    /// let directory = std::env::temp_dir().join("immie2d_file_storage_create_credentials_doctest");
+    /// # let _ = std::fs::remove_dir_all(&directory);
+    /// let mut storage = FileStorage::open(&directory).unwrap();
+    /// assert!(storage.create_credentials(&Credentials::new("misty".to_string(), PlayerId(1), "hash".to_string(), None)).unwrap());
+    /// assert!(!storage.create_credentials(&Credentials::new("misty".to_string(), PlayerId(2), "other".to_string(), None)).unwrap());
+    /// assert_eq!(storage.load_credentials("misty").unwrap().unwrap().player, PlayerId(1));
+    /// ```
+    fn create_credentials(&mut self, credentials: &Credentials) -> io::Result<bool> {
+        let _lock = self.lock(true)?;
+        let path = self.get_credentials_path(&credentials.username);
+        let temporary_path = path.with_extension(format!("{}.new", credentials.player.0));
+        fs::write(&temporary_path, credentials.to_bytes())?;
+        let linked = fs::hard_link(&temporary_path, &path);
+        fs::remove_file(&temporary_path)?;
+        return match linked {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(false),
+            Err(err) => Err(err)
+        };
+    }
+
+    /// The claim is created only if it doesn't exist, which the file system checks and does at once.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::storage::{file_storage::FileStorage, storage::Storage};
+    ///
+    /// let directory = std::env::temp_dir().join("immie2d_file_storage_gift_claim_doctest");
+    /// # let _ = std::fs::remove_dir_all(&directory);
+    /// let festival = GlobalString::new(&"festival_lavapup".to_string());
+    /// assert!(FileStorage::open(&directory).unwrap().record_gift_claim(PlayerId(1), festival).unwrap());
+    /// assert!(!FileStorage::open(&directory).unwrap().record_gift_claim(PlayerId(1), festival).unwrap());
+    /// assert!(FileStorage::open(&directory).unwrap().record_gift_claim(PlayerId(2), festival).unwrap());
+    /// ```
+    fn record_gift_claim(&mut self, player: PlayerId, distribution: GlobalString) -> io::Result<bool> {
+        let _lock = self.lock(true)?;
+        return match File::options().write(true).create_new(true).open(self.get_gift_claim_path(player, distribution)) {
+            Ok(_) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => Ok(false),
+            Err(err) => Err(err)
+        };
+    }
+
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::storage::{file_storage::FileStorage, player_profile::PlayerProfile, storage::Storage};
+    ///
+    /// let directory = std::env::temp_dir().join("immie2d_file_storage_player_id_doctest");
+    /// # let _ = std::fs::remove_dir_all(&directory);
+    /// let mut storage = FileStorage::open(&directory).unwrap();
+    /// storage.save_profiles(&[PlayerProfile::new(PlayerId(2), "misty".to_string())]).unwrap();
+    /// assert_eq!(storage.allocate_player_id().unwrap(), PlayerId(1));
+    /// // Skips ids with profiles, and remembers what it gave out after reopening
+    /// assert_eq!(FileStorage::open(&directory).unwrap().allocate_player_id().unwrap(), PlayerId(3));
+    /// ```
+    fn allocate_player_id(&mut self) -> io::Result<PlayerId> {
+        let _lock = self.lock(true)?;
+        let path = self.directory.join(NEXT_PLAYER_ID_FILE);
+        let mut next = self.read_next_player_id()?;
+        while self.get_profile_path(PlayerId(next)).exists() {
+            next += 1;
+        }
+        fs::write(get_temporary_path(&path), (next + 1).to_le_bytes())?;
+        fs::rename(get_temporary_path(&path), path)?;
+        return Ok(PlayerId(next));
+    }
+
+    /// Holds the storage lock while reading, so saves made by this or any other process wait for the export to finish
+    /// and the snapshot is of one moment.
+    fn export_snapshot(&mut self) -> io::Result<StorageSnapshot> {
+        let _lock = self.lock(false)?;
+        let profiles = read_records(&self.directory.join(PROFILES_DIRECTORY), "profile", PlayerProfile::from_bytes)?;
+        let regions = read_records(&self.directory.join(REGIONS_DIRECTORY), "region", RegionState::from_bytes)?;
+        let credentials = read_records(&self.directory.join(CREDENTIALS_DIRECTORY), "credentials", Credentials::from_bytes)?;
+        return Ok(StorageSnapshot::new(profiles, regions, self.load_ban_list()?, credentials, self.read_next_player_id()?));
+    }
+
+    /// Every record is written to a temporary file before any existing record is removed or replaced, so failing to
+    /// write leaves storage as it was.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::storage::{file_storage::FileStorage, memory_storage::MemoryStorage, player_profile::PlayerProfile, storage::Storage};
+    ///
+    /// let directory = std::env::temp_dir().join("immie2d_file_storage_snapshot_doctest");
+    /// # let _ = std::fs::remove_dir_all(&directory);
+    /// let mut storage = FileStorage::open(&directory).unwrap();
+    /// storage.save_profiles(&[PlayerProfile::new(PlayerId(1), "ash".to_string())]).unwrap();
+    ///
+    /// let mut other = MemoryStorage::new();
+    /// other.save_profiles(&[PlayerProfile::new(PlayerId(5), "brock".to_string())]).unwrap();
+    /// let snapshot = other.export_snapshot().unwrap();
+    /// storage.import_snapshot(&snapshot).unwrap();
+    /// assert_eq!(storage.load_profile(PlayerId(1)).unwrap(), None);
+    /// assert_eq!(storage.export_snapshot().unwrap(), snapshot);
+    /// ```
+    fn import_snapshot(&mut self, snapshot: &StorageSnapshot) -> io::Result<()> {
+        let _lock = self.lock(true)?;
+        let mut writes: Vec<(PathBuf, Vec<u8>)> = Vec::new();
+        writes.extend(snapshot.profiles.iter().map(|profile| (self.get_profile_path(profile.player), profile.to_bytes())));
+        writes.extend(snapshot.regions.iter().map(|region| (self.get_region_path(region.map), region.to_bytes())));
+        writes.extend(snapshot.credentials.iter().map(|credentials| (self.get_credentials_path(&credentials.username), credentials.to_bytes())));
+        for profile in snapshot.profiles.iter() {
+            writes.extend(profile.claimed_gifts.iter().map(|gift| (self.get_gift_claim_path(profile.player, *gift), Vec::new())));
+        }
+        writes.push((self.directory.join(BAN_LIST_FILE), snapshot.bans.to_bytes()));
+        writes.push((self.directory.join(NEXT_PLAYER_ID_FILE), snapshot.next_player_id.to_le_bytes().to_vec()));
+        for (path, bytes) in writes.iter() {
+            fs::write(get_temporary_path(path), bytes)?;
+        }
+        let kept: Vec<PathBuf> = writes.into_iter().map(|(path, _)| path).collect();
+        remove_records(&self.directory.join(PROFILES_DIRECTORY), "profile", &kept)?;
+        remove_records(&self.directory.join(REGIONS_DIRECTORY), "region", &kept)?;
+        remove_records(&self.directory.join(CREDENTIALS_DIRECTORY), "credentials", &kept)?;
+        remove_records(&self.directory.join(GIFT_CLAIMS_DIRECTORY), "claim", &kept)?;
+        for path in kept.iter() {
+            fs::rename(get_temporary_path(path), path)?;
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,85 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::player_profile::PlayerProfile;
+
+/* Append only file of profile changes that haven't been flushed to storage yet, so they can be recovered after a crash.
+Each record is a little endian u32 length followed by an encoded profile. */
+pub struct Journal {
+    path: PathBuf,
+    file: File
+}
+
+impl Journal {
+    /// Open a journal, creating the file if it doesn't exist. Existing records are kept for recovery.
+    pub fn open(path: &Path) -> io::Result<Journal> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        return Ok(Journal { path: path.to_path_buf(), file });
+    }
+
+    /// Append a profile and make sure it reached the disk.
+    pub fn append(&mut self, profile: &PlayerProfile) -> io::Result<()> {
+        let bytes = profile.to_bytes();
+        let mut record = Vec::with_capacity(bytes.len() + 4);
+        record.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+        record.extend_from_slice(&bytes);
+        self.file.write_all(&record)?;
+        return self.file.sync_data();
+    }
+
+    /// Remove every record, once they have all been flushed to storage.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.file = File::create(&self.path)?;
+        self.file.sync_all()?;
+        self.file = OpenOptions::new().append(true).open(&self.path)?;
+        return Ok(());
+    }
+
+    /// Read every complete record of a journal file in the order written. A record cut off by a crash is ignored.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::storage::{journal::Journal, player_profile::PlayerProfile};
+    ///
+    /// let path = std::env::temp_dir().join("immie2d_journal_doctest.journal");
+    /// # let _ = std::fs::remove_file(&path);
+    /// let mut journal = Journal::open(&path).unwrap();
+    /// journal.append(&PlayerProfile::new(PlayerId(1), "a".to_string())).unwrap();
+    /// journal.append(&PlayerProfile::new(PlayerId(2), "b".to_string())).unwrap();
+    /// assert_eq!(Journal::read_records(&path).unwrap().len(), 2);
+    ///
+    /// journal.clear().unwrap();
+    /// assert_eq!(Journal::read_records(&path).unwrap().len(), 0);
+    /// # std::fs::remove_file(&path).unwrap();
+    /// ```
+    pub fn read_records(path: &Path) -> io::Result<Vec<PlayerProfile>> {
+        let mut bytes: Vec<u8> = Vec::new();
+        match File::open(path) {
+            Ok(mut file) => { file.read_to_end(&mut bytes)?; },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err)
+        }
+        let mut records: Vec<PlayerProfile> = Vec::new();
+        let mut position = 0;
+        while position + 4 <= bytes.len() {
+            let length = u32::from_le_bytes(bytes[position..position + 4].try_into().unwrap()) as usize;
+            position += 4;
+            if position + length > bytes.len() {
+                break;
+            }
+            match PlayerProfile::from_bytes(&bytes[position..position + length]) {
+                Ok(profile) => records.push(profile),
+                Err(_) => break
+            }
+            position += length;
+        }
+        return Ok(records);
+    }
+
+    pub fn remove(path: &Path) -> io::Result<()> {
+        return match fs::remove_file(path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            result => result
+        };
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::admin::ban_list::BanList;
+use crate::auth::credentials::Credentials;
+
+use super::backup::StorageSnapshot;
+use super::player_profile::PlayerProfile;
+use super::region_state::RegionState;
+use super::storage::Storage;
+
+/* Storage that only lives in memory, for tests and local development servers. */
+pub struct MemoryStorage {
+    profiles: HashMap<PlayerId, PlayerProfile>,
+    regions: HashMap<GlobalString, RegionState>,
+    bans: BanList,
+    credentials: HashMap<String, Credentials>,
+    gift_claims: HashSet<(PlayerId, GlobalString)>,
+    next_player_id: u64,
+    save_count: u32
+}
+
+impl MemoryStorage {
+    pub fn new() -> MemoryStorage {
+        return MemoryStorage { profiles: HashMap::new(), regions: HashMap::new(), bans: BanList::new(), credentials: HashMap::new(), gift_claims: HashSet::new(), next_player_id: 1, save_count: 0 };
+    }
+
+    /// Number of times save_profiles() has been called.
+    pub fn get_save_count(&self) -> u32 {
+        return self.save_count;
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn load_profile(&mut self, player: PlayerId) -> io::Result<Option<PlayerProfile>> {
+        return Ok(self.profiles.get(&player).cloned());
+    }
+
+    fn save_profiles(&mut self, profiles: &[PlayerProfile]) -> io::Result<()> {
+        for profile in profiles {
+            self.profiles.insert(profile.player, profile.clone());
+        }
+        self.save_count += 1;
+        return Ok(());
+    }
+
+    fn load_region(&mut self, map: GlobalString) -> io::Result<Option<RegionState>> {
+        return Ok(self.regions.get(&map).cloned());
+    }
+
+    fn save_region(&mut self, region: &RegionState) -> io::Result<()> {
+        self.regions.insert(region.map, region.clone());
+        return Ok(());
+    }
+
+    fn load_ban_list(&mut self) -> io::Result<BanList> {
+        return Ok(self.bans.clone());
+    }
+
+    fn save_ban_list(&mut self, bans: &BanList) -> io::Result<()> {
+        self.bans = bans.clone();
+        return Ok(());
+    }
+
+    fn load_credentials(&mut self, username: &str) -> io::Result<Option<Credentials>> {
+        return Ok(self.credentials.get(username).cloned());
+    }
+
+    fn save_credentials(&mut self, credentials: &Credentials) -> io::Result<()> {
+        self.credentials.insert(credentials.username.clone(), credentials.clone());
+        return Ok(());
+    }
+
+    fn create_credentials(&mut self, credentials: &Credentials) -> io::Result<bool> {
+        if self.credentials.contains_key(&credentials.username) {
+            return Ok(false);
+        }
+        self.credentials.insert(credentials.username.clone(), credentials.clone());
+        return Ok(true);
+    }
+
+    fn record_gift_claim(&mut self, player: PlayerId, distribution: GlobalString) -> io::Result<bool> {
+        return Ok(self.gift_claims.insert((player, distribution)));
+    }
+
+    fn allocate_player_id(&mut self) -> io::Result<PlayerId> {
+        // Profiles can be saved with ids picked by hand, such as in tests
+        while self.profiles.contains_key(&PlayerId(self.next_player_id)) {
+            self.next_player_id += 1;
+        }
+        let player = PlayerId(self.next_player_id);
+        self.next_player_id += 1;
+        return Ok(player);
+    }
+
+    fn export_snapshot(&mut self) -> io::Result<StorageSnapshot> {
+        let credentials = self.credentials.values().cloned().collect();
+        return Ok(StorageSnapshot::new(self.profiles.values().cloned().collect(), self.regions.values().cloned().collect(), self.bans.clone(), credentials, self.next_player_id));
+    }
+
+    fn import_snapshot(&mut self, snapshot: &StorageSnapshot) -> io::Result<()> {
+        self.profiles = snapshot.profiles.iter().map(|profile| (profile.player, profile.clone())).collect();
+        self.regions = snapshot.regions.iter().map(|region| (region.map, region.clone())).collect();
+        self.bans = snapshot.bans.clone();
+        self.credentials = snapshot.credentials.iter().map(|credentials| (credentials.username.clone(), credentials.clone())).collect();
+        self.gift_claims = snapshot.profiles.iter().flat_map(|profile| profile.claimed_gifts.iter().map(|gift| (profile.player, *gift))).collect();
+        self.next_player_id = snapshot.next_player_id;
+        return Ok(());
+    }
+}
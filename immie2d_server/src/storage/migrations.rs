@@ -0,0 +1,79 @@
+/* A step of the SQL schema. Migrations are applied in version order and never edited once released;
+changes to the schema are made by adding a new migration. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str
+}
+
+/// Table recording which migrations a database has had applied.
+pub const MIGRATIONS_TABLE: &str = "CREATE TABLE IF NOT EXISTS schema_migrations (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+)";
+
+/// Every migration, in version order. Profiles are stored in the same binary format as the journal, with the
+/// fields operators query on copied into their own columns.
+pub const MIGRATIONS: [Migration; 5] = [
+    Migration {
+        version: 1,
+        name: "create_profiles_and_regions",
+        sql: "CREATE TABLE player_profiles (
+                player_id BIGINT PRIMARY KEY,
+                name TEXT NOT NULL,
+                is_banned BOOLEAN NOT NULL,
+                rating INTEGER NOT NULL,
+                data BYTEA NOT NULL
+            );
+            CREATE TABLE region_states (
+                map TEXT PRIMARY KEY,
+                data BYTEA NOT NULL
+            );"
+    },
+    Migration {
+        version: 2,
+        name: "index_profile_rating",
+        sql: "CREATE INDEX player_profiles_rating ON player_profiles (rating DESC);"
+    },
+    Migration {
+        version: 3,
+        name: "create_ban_lists",
+        sql: "CREATE TABLE ban_lists (
+                id INTEGER PRIMARY KEY,
+                data BYTEA NOT NULL
+            );"
+    },
+    Migration {
+        version: 4,
+        name: "create_credentials",
+        sql: "CREATE TABLE credentials (
+                username TEXT PRIMARY KEY,
+                player_id BIGINT NOT NULL UNIQUE,
+                data BYTEA NOT NULL
+            );
+            CREATE SEQUENCE player_ids;"
+    },
+    Migration {
+        version: 5,
+        name: "create_gift_claims",
+        sql: "CREATE TABLE gift_claims (
+                player_id BIGINT NOT NULL,
+                distribution TEXT NOT NULL,
+                PRIMARY KEY (player_id, distribution)
+            );"
+    }
+];
+
+/// Get the migrations that still need to be applied to a database at a schema version, in order.
+/// ```
+/// use immie2d_server::storage::migrations::{get_pending_migrations, MIGRATIONS};
+///
+/// assert_eq!(get_pending_migrations(0).len(), MIGRATIONS.len());
+/// assert_eq!(get_pending_migrations(1)[0].version, 2);
+/// assert!(get_pending_migrations(MIGRATIONS.last().unwrap().version).is_empty());
+/// ```
+pub fn get_pending_migrations(current_version: u32) -> Vec<Migration> {
+    return MIGRATIONS.iter().filter(|migration| migration.version > current_version).copied().collect();
+}
@@ -0,0 +1,15 @@
+pub mod storage;
+pub mod memory_storage;
+pub mod player_profile;
+pub mod journal;
+pub mod write_behind_cache;
+pub mod region_state;
+pub mod world_state_store;
+pub mod file_storage;
+pub mod connection_pool;
+pub mod query_policy;
+pub mod async_storage;
+pub mod migrations;
+pub mod backup;
+#[cfg(feature = "postgres")]
+pub mod postgres_storage;
@@ -0,0 +1,617 @@
+use std::collections::HashMap;
+use std::io::{self, ErrorKind};
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::ability::ability_names::{AbilityNames, MAX_ABILITIES_COUNT};
+use immie2d_shared::gameplay::challenge::challenge_data::{ChallengeCatalog, ChallengeEvent};
+use immie2d_shared::gameplay::elements::element_kinds::{ElementKind, ELEMENT_COUNT};
+use immie2d_shared::gameplay::challenge::challenge_progress::{ChallengeEntry, ChallengeProgress, ChallengeUpdate};
+use immie2d_shared::gameplay::immie::bond::MAX_BOND;
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::immie::immie_release::{ImmieLocation, ReleaseError};
+use immie2d_shared::gameplay::immie::individual_values::{IndividualValues, MAX_INDIVIDUAL_VALUE};
+use immie2d_shared::gameplay::item::inventory::Inventory;
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::gameplay::status_condition::StatusCondition;
+use immie2d_shared::gameplay::synced_settings::{SettingsMerge, SyncedSettings};
+use immie2d_shared::gameplay::tournament_bracket::MatchDecision;
+use immie2d_shared::world::explored_area::{ExploredArea, ExploredAreaUpdate, EXPLORE_RADIUS};
+use immie2d_shared::world::minimap::Minimap;
+use immie2d_shared::world::tile_position::TilePosition;
+
+use crate::admin::stat_audit::{StatAuditEntry, StatInputs};
+
+/// Rating of a player who has never played a ranked battle.
+pub const DEFAULT_RATING: u32 = 1000;
+
+/// Most matches kept in a player's history. The oldest are dropped first.
+pub const MAX_MATCH_HISTORY: usize = 100;
+
+const STATUS_CONDITIONS: [StatusCondition; 5] = [StatusCondition::Burn, StatusCondition::Poison, StatusCondition::Paralysis, StatusCondition::Sleep, StatusCondition::Freeze];
+
+/* A decided match from one player's side. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MatchRecord {
+    pub opponent: PlayerId,
+    pub won: bool,
+    pub decision: MatchDecision,
+    /// How the player's rating moved, which is 0 for matches that weren't played.
+    pub rating_change: i32,
+    /// The tournament the match was part of, if any.
+    pub tournament: Option<u64>,
+    pub unix_seconds: u64
+}
+
+/* Everything persisted about a player. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct PlayerProfile {
+    pub player: PlayerId,
+    pub name: String,
+    pub inventory: Inventory,
+    pub party: Vec<Immie>,
+    /// Immies kept in storage instead of the party.
+    pub boxed: Vec<Immie>,
+    /// Banned players are refused when logging in.
+    pub is_banned: bool,
+    pub rating: u32,
+    /// Minimap cells explored on each map.
+    pub explored: HashMap<GlobalString, ExploredArea>,
+    pub challenges: ChallengeProgress,
+    /// Client settings synced across the player's devices, or None until a client first logs in.
+    pub settings: Option<SyncedSettings>,
+    /// Most recent matches last. See MAX_MATCH_HISTORY
+    pub match_history: Vec<MatchRecord>,
+    /// Names of the gift distributions the player has claimed, each at most once. See GiftDistributor
+    pub claimed_gifts: Vec<GlobalString>,
+    /// Names of the fast travel points the player has visited and can travel to. See FastTravelNetwork
+    pub unlocked_travel_points: Vec<GlobalString>,
+    /// The latest signed stat audit entry for each set of stat inputs the player's Immies have, checked before a
+    /// trade or matchmaking. See PlayerProfile::record_stat_audit()
+    pub stat_audits: Vec<StatAuditEntry>
+}
+
+impl PlayerProfile {
+    /// A profile for a player that has never been saved.
+    pub fn new(player: PlayerId, name: String) -> PlayerProfile {
+        return PlayerProfile { player, name, inventory: Inventory::new(), party: Vec::new(), boxed: Vec::new(), is_banned: false, rating: DEFAULT_RATING, explored: HashMap::new(), challenges: ChallengeProgress::new(), settings: None, match_history: Vec::new(), claimed_gifts: Vec::new(), unlocked_travel_points: Vec::new(), stat_audits: Vec::new() };
+    }
+
+    /// Encode the profile in the binary format used by the journal.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::{ability::ability_names::AbilityNames, immie::immie::Immie, status_condition::StatusCondition};
+    /// use immie2d_shared::gameplay::immie::individual_values::IndividualValues;
+    /// use immie2d_shared::gameplay::elements::element_kinds::ElementKind;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    ///
+    /// let mut profile = PlayerProfile::new(PlayerId(4), "ash".to_string());
+    /// profile.inventory.add_item(GlobalString::new(&"potion".to_string()), 3);
+    /// let mut immie = Immie::new(GlobalString::new(&"lavapup".to_string()), 12, AbilityNames::new(vec![GlobalString::new(&"fireball".to_string())]));
+    /// immie.bond = 140;
+    /// immie.status = Some(StatusCondition::Burn);
+    /// immie.held_item = Some(GlobalString::new(&"lava stone".to_string()));
+    /// immie.ability_uses_spent[0] = 3;
+    /// immie.individual_values = IndividualValues::new(4, 31, 0, 17);
+    /// profile.party.push(immie);
+    /// profile.is_banned = true;
+    /// immie.is_locked = true;
+    /// immie.origin = Some(GlobalString::new(&"festival_lavapup".to_string()));
+    /// immie.attunement = Some(ElementKind::Water);
+    /// profile.boxed.push(immie);
+    /// profile.claimed_gifts.push(GlobalString::new(&"festival_lavapup".to_string()));
+    /// profile.unlocked_travel_points.push(GlobalString::new(&"ember town".to_string()));
+    /// assert_eq!(PlayerProfile::from_bytes(&profile.to_bytes()).unwrap(), profile);
+    /// assert!(PlayerProfile::from_bytes(&profile.to_bytes()[..5]).is_err());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        bytes.extend_from_slice(&self.player.0.to_le_bytes());
+        write_string(&mut bytes, &self.name);
+        let items: Vec<(GlobalString, u32)> = self.inventory.iter().collect();
+        bytes.extend_from_slice(&(items.len() as u32).to_le_bytes());
+        for (item, count) in items {
+            write_string(&mut bytes, &item.to_string());
+            bytes.extend_from_slice(&count.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.party.len() as u32).to_le_bytes());
+        for immie in self.party.iter() {
+            write_immie(&mut bytes, immie);
+        }
+        bytes.push(self.is_banned as u8);
+        bytes.extend_from_slice(&self.rating.to_le_bytes());
+        bytes.extend_from_slice(&(self.explored.len() as u32).to_le_bytes());
+        for (map, area) in self.explored.iter() {
+            write_string(&mut bytes, &map.to_string());
+            bytes.extend_from_slice(&area.get_width().to_le_bytes());
+            bytes.extend_from_slice(&area.get_height().to_le_bytes());
+            for word in area.get_bits() {
+                bytes.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+        let challenges: Vec<(GlobalString, ChallengeEntry)> = self.challenges.iter().collect();
+        bytes.extend_from_slice(&(challenges.len() as u32).to_le_bytes());
+        for (challenge, entry) in challenges {
+            write_string(&mut bytes, &challenge.to_string());
+            bytes.extend_from_slice(&entry.period_index.to_le_bytes());
+            bytes.extend_from_slice(&entry.progress.to_le_bytes());
+            bytes.push(entry.is_completed as u8);
+        }
+        match &self.settings {
+            Some(settings) => {
+                let settings = settings.to_bytes();
+                bytes.push(1);
+                bytes.extend_from_slice(&(settings.len() as u32).to_le_bytes());
+                bytes.extend_from_slice(&settings);
+            },
+            None => bytes.push(0)
+        }
+        bytes.extend_from_slice(&(self.boxed.len() as u32).to_le_bytes());
+        for immie in self.boxed.iter() {
+            write_immie(&mut bytes, immie);
+        }
+        bytes.extend_from_slice(&(self.match_history.len() as u32).to_le_bytes());
+        for record in self.match_history.iter() {
+            bytes.extend_from_slice(&record.opponent.0.to_le_bytes());
+            bytes.push(record.won as u8);
+            bytes.push(record.decision.get_id());
+            bytes.extend_from_slice(&record.rating_change.to_le_bytes());
+            // Tournament ids start from 1, so 0 stands for no tournament
+            bytes.extend_from_slice(&record.tournament.unwrap_or(0).to_le_bytes());
+            bytes.extend_from_slice(&record.unix_seconds.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.claimed_gifts.len() as u32).to_le_bytes());
+        for gift in self.claimed_gifts.iter() {
+            write_string(&mut bytes, &gift.to_string());
+        }
+        bytes.extend_from_slice(&(self.unlocked_travel_points.len() as u32).to_le_bytes());
+        for point in self.unlocked_travel_points.iter() {
+            write_string(&mut bytes, &point.to_string());
+        }
+        bytes.extend_from_slice(&(self.stat_audits.len() as u32).to_le_bytes());
+        for entry in self.stat_audits.iter() {
+            let entry = entry.to_bytes();
+            bytes.extend_from_slice(&(entry.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&entry);
+        }
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<PlayerProfile> {
+        let mut reader = ByteReader::new(bytes);
+        let player = PlayerId(u64::from_le_bytes(reader.take_array()?));
+        let name = reader.take_string()?;
+        let mut inventory = Inventory::new();
+        let item_count = u32::from_le_bytes(reader.take_array()?);
+        for _ in 0..item_count {
+            let item = GlobalString::new(&reader.take_string()?);
+            inventory.add_item(item, u32::from_le_bytes(reader.take_array()?));
+        }
+        let party_count = u32::from_le_bytes(reader.take_array()?);
+        let mut party = Vec::new();
+        for _ in 0..party_count {
+            party.push(read_immie(&mut reader)?);
+        }
+        let [is_banned] = reader.take_array::<1>()?;
+        let rating = u32::from_le_bytes(reader.take_array()?);
+        let explored_count = u32::from_le_bytes(reader.take_array()?);
+        let mut explored = HashMap::new();
+        for _ in 0..explored_count {
+            let map = GlobalString::new(&reader.take_string()?);
+            let width = u32::from_le_bytes(reader.take_array()?);
+            let height = u32::from_le_bytes(reader.take_array()?);
+            let word_count = (width as usize * height as usize).div_ceil(64);
+            if word_count > reader.get_remaining() / 8 {
+                return Err(io::Error::new(ErrorKind::UnexpectedEof, "Stored data is truncated"));
+            }
+            let mut bits = Vec::with_capacity(word_count);
+            for _ in 0..word_count {
+                bits.push(u64::from_le_bytes(reader.take_array()?));
+            }
+            explored.insert(map, ExploredArea::from_bits(width, height, bits).unwrap());
+        }
+        let challenge_count = u32::from_le_bytes(reader.take_array()?);
+        let mut challenges = ChallengeProgress::new();
+        for _ in 0..challenge_count {
+            let challenge = GlobalString::new(&reader.take_string()?);
+            let period_index = u64::from_le_bytes(reader.take_array()?);
+            let progress = u32::from_le_bytes(reader.take_array()?);
+            let [is_completed] = reader.take_array::<1>()?;
+            challenges.insert(challenge, ChallengeEntry { period_index, progress, is_completed: is_completed != 0 });
+        }
+        let [has_settings] = reader.take_array::<1>()?;
+        let settings = match has_settings {
+            0 => None,
+            _ => {
+                let length = u32::from_le_bytes(reader.take_array()?) as usize;
+                Some(SyncedSettings::from_bytes(reader.take(length)?).ok_or(io::Error::new(ErrorKind::InvalidData, "Invalid synced settings"))?)
+            }
+        };
+        let boxed_count = u32::from_le_bytes(reader.take_array()?);
+        let mut boxed = Vec::new();
+        for _ in 0..boxed_count {
+            boxed.push(read_immie(&mut reader)?);
+        }
+        let history_count = u32::from_le_bytes(reader.take_array()?);
+        let mut match_history = Vec::new();
+        for _ in 0..history_count {
+            let opponent = PlayerId(u64::from_le_bytes(reader.take_array()?));
+            let [won, decision] = reader.take_array::<2>()?;
+            let decision = MatchDecision::from_id(decision).ok_or(io::Error::new(ErrorKind::InvalidData, format!("Unknown match decision {}", decision)))?;
+            let rating_change = i32::from_le_bytes(reader.take_array()?);
+            let tournament = match u64::from_le_bytes(reader.take_array()?) {
+                0 => None,
+                tournament => Some(tournament)
+            };
+            let unix_seconds = u64::from_le_bytes(reader.take_array()?);
+            match_history.push(MatchRecord { opponent, won: won != 0, decision, rating_change, tournament, unix_seconds });
+        }
+        let gift_count = u32::from_le_bytes(reader.take_array()?);
+        let mut claimed_gifts = Vec::new();
+        for _ in 0..gift_count {
+            claimed_gifts.push(GlobalString::new(&reader.take_string()?));
+        }
+        let point_count = u32::from_le_bytes(reader.take_array()?);
+        let mut unlocked_travel_points = Vec::new();
+        for _ in 0..point_count {
+            unlocked_travel_points.push(GlobalString::new(&reader.take_string()?));
+        }
+        let audit_count = u32::from_le_bytes(reader.take_array()?);
+        let mut stat_audits = Vec::new();
+        for _ in 0..audit_count {
+            let length = u32::from_le_bytes(reader.take_array()?) as usize;
+            stat_audits.push(StatAuditEntry::from_bytes(reader.take(length)?)?);
+        }
+        return Ok(PlayerProfile { player, name, inventory, party, boxed, is_banned: is_banned != 0, rating, explored, challenges, settings, match_history, claimed_gifts, unlocked_travel_points, stat_audits });
+    }
+
+    /// Explore the minimap cells around the player's tile. Returns the update to send to the client if any cells
+    /// were newly explored. Exploration of a map is reset if its minimap changed size.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::world::{tile_map::TileMap, tile_position::TilePosition, minimap::Minimap};
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    ///
+    /// let minimap = Minimap::generate(&TileMap::new(GlobalString::new(&"town".to_string()), 64, 64), 4);
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// let update = profile.explore(&minimap, TilePosition::new(0, 0)).unwrap();
+    /// assert_eq!(update.cells.len(), 9);
+    /// assert!(profile.explore(&minimap, TilePosition::new(3, 3)).is_none());
+    /// assert_eq!(profile.explore(&minimap, TilePosition::new(4, 0)).unwrap().cells, vec![(3, 0), (3, 1), (3, 2)]);
+    /// assert_eq!(PlayerProfile::from_bytes(&profile.to_bytes()).unwrap(), profile);
+    /// ```
+    pub fn explore(&mut self, minimap: &Minimap, tile: TilePosition) -> Option<ExploredAreaUpdate> {
+        let (x, y) = minimap.get_cell_of(tile)?;
+        let area = self.explored.entry(minimap.get_map()).or_insert_with(|| ExploredArea::new(minimap.get_width(), minimap.get_height()));
+        if area.get_width() != minimap.get_width() || area.get_height() != minimap.get_height() {
+            *area = ExploredArea::new(minimap.get_width(), minimap.get_height());
+        }
+        let cells = area.explore_around(x, y, EXPLORE_RADIUS);
+        if cells.is_empty() {
+            return None;
+        }
+        return Some(ExploredAreaUpdate { map: minimap.get_map(), cells });
+    }
+
+    /// Progress the player's active challenges, adding the rewards of completed challenges to their inventory.
+    /// Returns the updates to send to the client.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::challenge::challenge_data::{ChallengeCatalog, ChallengeEvent, ChallengePeriod};
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    ///
+    /// let catalog = ChallengeCatalog::from_json(r#"[
+    ///     { "name": "fire_wins", "period": "weekly", "objective": { "kind": "win_battles", "count": 1, "element": "fire" }, "rewards": { "ember stone": 1 } }
+    /// ]"#).unwrap();
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// let team = vec![Elements::new(vec![ElementKind::Fire])];
+    /// assert!(profile.record_challenge_event(&catalog, &ChallengeEvent::BattleWon { team }, 1000)[0].is_completed);
+    /// assert_eq!(profile.inventory.get_count(GlobalString::new(&"ember stone".to_string())), 1);
+    ///
+    /// let loaded = PlayerProfile::from_bytes(&profile.to_bytes()).unwrap();
+    /// assert_eq!(loaded, profile);
+    /// assert!(loaded.challenges.get_updates(&catalog, 1000)[0].is_completed);
+    /// ```
+    pub fn record_challenge_event(&mut self, catalog: &ChallengeCatalog, event: &ChallengeEvent, unix_seconds: u64) -> Vec<ChallengeUpdate> {
+        return self.challenges.record(catalog, event, unix_seconds, &mut self.inventory);
+    }
+
+    /// Add a match to the player's history and apply its rating change, dropping the oldest match if the history is full.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::tournament_bracket::MatchDecision;
+    /// use immie2d_server::storage::player_profile::{MatchRecord, PlayerProfile, MAX_MATCH_HISTORY};
+    ///
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// let record = MatchRecord { opponent: PlayerId(2), won: false, decision: MatchDecision::Played, rating_change: -16, tournament: Some(3), unix_seconds: 500 };
+    /// profile.record_match(record);
+    /// assert_eq!(profile.rating, 984);
+    /// assert_eq!(PlayerProfile::from_bytes(&profile.to_bytes()).unwrap(), profile);
+    ///
+    /// for unix_seconds in 0..MAX_MATCH_HISTORY as u64 {
+    ///     profile.record_match(MatchRecord { rating_change: 0, unix_seconds, ..record });
+    /// }
+    /// assert_eq!(profile.match_history.len(), MAX_MATCH_HISTORY);
+    /// assert_eq!(profile.match_history[0].unix_seconds, 0);
+    /// ```
+    pub fn record_match(&mut self, record: MatchRecord) {
+        self.rating = self.rating.saturating_add_signed(record.rating_change);
+        if self.match_history.len() >= MAX_MATCH_HISTORY {
+            self.match_history.remove(0);
+        }
+        self.match_history.push(record);
+    }
+
+    /// Keep a signed stat audit entry for the player's Immies with the entry's stat inputs, replacing the last entry
+    /// for the same inputs. Entries no longer matching any of the player's Immies are dropped, so the list stays as
+    /// long as the player's Immies at most.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::{ability::ability_names::AbilityNames, immie::immie::Immie, player_id::PlayerId};
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// use immie2d_server::admin::stat_audit::{StatAuditLog, StatChange};
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    ///
+    /// let lavapup = GlobalString::new(&"lavapup".to_string());
+    /// let mut species = SpeciesMap::new();
+    /// species.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut log = StatAuditLog::new(b"audit key".to_vec());
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// profile.party.push(Immie::new(lavapup, 5, AbilityNames::default()));
+    /// profile.record_stat_audit(log.record(PlayerId(1), &profile.party[0], StatChange::Obtained, &species, 10).unwrap());
+    /// let entry = log.level_up(PlayerId(1), &mut profile.party[0], 6, &species, 11).unwrap();
+    /// profile.record_stat_audit(entry);
+    ///
+    /// // The entry for level 5 was dropped once no Immie was level 5
+    /// assert_eq!(profile.stat_audits, vec![entry]);
+    /// profile.boxed.push(Immie::new(lavapup, 9, AbilityNames::default()));
+    /// assert_eq!(profile.get_stat_audits(&[profile.boxed[0], profile.party[0]]), vec![None, Some(entry)]);
+    /// assert_eq!(PlayerProfile::from_bytes(&profile.to_bytes()).unwrap(), profile);
+    /// ```
+    pub fn record_stat_audit(&mut self, entry: StatAuditEntry) {
+        self.stat_audits.retain(|other| other.inputs != entry.inputs);
+        self.stat_audits.push(entry);
+        self.prune_stat_audits();
+    }
+
+    fn prune_stat_audits(&mut self) {
+        let immies: Vec<StatInputs> = self.party.iter().chain(self.boxed.iter()).map(StatInputs::of).collect();
+        self.stat_audits.retain(|entry| immies.contains(&entry.inputs));
+    }
+
+    /// The stat audit entry kept for each Immie of a team, in team order. See StatAuditLog::validate_team()
+    pub fn get_stat_audits(&self, team: &[Immie]) -> Vec<Option<StatAuditEntry>> {
+        return team.iter().map(|immie| {
+            let inputs = StatInputs::of(immie);
+            return self.stat_audits.iter().find(|entry| entry.inputs == inputs).copied();
+        }).collect();
+    }
+
+    pub fn get_immie(&self, location: ImmieLocation) -> Option<&Immie> {
+        return match location {
+            ImmieLocation::Party(slot) => self.party.get(slot),
+            ImmieLocation::Box(slot) => self.boxed.get(slot)
+        };
+    }
+
+    /// Lock or unlock an Immie against being released or traded.
+    pub fn set_locked(&mut self, location: ImmieLocation, is_locked: bool) -> Result<(), ReleaseError> {
+        let immie = match location {
+            ImmieLocation::Party(slot) => self.party.get_mut(slot),
+            ImmieLocation::Box(slot) => self.boxed.get_mut(slot)
+        };
+        immie.ok_or(ReleaseError::InvalidLocation)?.is_locked = is_locked;
+        return Ok(());
+    }
+
+    /// Check that an Immie can be taken out of the profile, without taking it.
+    pub fn check_removable(&self, location: ImmieLocation) -> Result<(), ReleaseError> {
+        let immie = self.get_immie(location).ok_or(ReleaseError::InvalidLocation)?;
+        if immie.is_locked {
+            return Err(ReleaseError::Locked);
+        }
+        if let ImmieLocation::Party(_) = location {
+            if self.party.len() == 1 {
+                return Err(ReleaseError::LastPartyImmie);
+            }
+        }
+        return Ok(());
+    }
+
+    /// Take an Immie out of the profile for good, such as to release or trade it. Every removal goes through here so
+    /// locked Immies are never removed. Later slots move down to fill the gap, and stat audit entries left matching
+    /// none of the player's Immies are dropped.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::{ability::ability_names::AbilityNames, immie::immie::Immie, player_id::PlayerId};
+    /// use immie2d_shared::gameplay::immie::immie_release::{ImmieLocation, ReleaseError};
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    ///
+    /// let immie = Immie::new(GlobalString::new(&"lavapup".to_string()), 5, AbilityNames::default());
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// profile.party.push(immie);
+    /// profile.boxed = vec![immie, Immie { level: 30, ..immie }];
+    ///
+    /// assert_eq!(profile.take_immie(ImmieLocation::Party(0)), Err(ReleaseError::LastPartyImmie));
+    /// profile.set_locked(ImmieLocation::Box(1), true).unwrap();
+    /// assert_eq!(profile.take_immie(ImmieLocation::Box(1)), Err(ReleaseError::Locked));
+    /// profile.set_locked(ImmieLocation::Box(1), false).unwrap();
+    /// assert_eq!(profile.take_immie(ImmieLocation::Box(1)).unwrap().level, 30);
+    /// assert_eq!(profile.take_immie(ImmieLocation::Box(1)), Err(ReleaseError::InvalidLocation));
+    /// ```
+    pub fn take_immie(&mut self, location: ImmieLocation) -> Result<Immie, ReleaseError> {
+        self.check_removable(location)?;
+        let immie = match location {
+            ImmieLocation::Party(slot) => self.party.remove(slot),
+            ImmieLocation::Box(slot) => self.boxed.remove(slot)
+        };
+        self.prune_stat_audits();
+        return Ok(immie);
+    }
+
+    /// Merge the local settings of a client logging in with the settings in the profile, storing the local settings
+    /// if they win. Returns the merge, whose settings the client should apply. See SyncedSettings::merge()
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::synced_settings::{SyncedSettings, TextSpeed};
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    ///
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// let laptop = SyncedSettings { text_speed: TextSpeed::Fast, is_modified: true, ..SyncedSettings::default() };
+    /// let laptop = profile.sync_settings(laptop).settings;
+    /// assert_eq!(laptop.revision, 1);
+    /// assert_eq!(profile.settings, Some(laptop.clone()));
+    ///
+    /// // A new phone takes the settings from the laptop
+    /// let phone = profile.sync_settings(SyncedSettings::default());
+    /// assert_eq!(phone.settings, laptop);
+    /// assert_eq!(PlayerProfile::from_bytes(&profile.to_bytes()).unwrap(), profile);
+    /// ```
+    pub fn sync_settings(&mut self, local: SyncedSettings) -> SettingsMerge {
+        let merge = SyncedSettings::merge(&local, self.settings.as_ref());
+        if merge.should_upload {
+            self.settings = Some(merge.settings.clone());
+        }
+        return merge;
+    }
+}
+
+pub(crate) fn write_immie(bytes: &mut Vec<u8>, immie: &Immie) {
+    write_string(bytes, &immie.species.to_string());
+    bytes.extend_from_slice(&immie.level.to_le_bytes());
+    bytes.extend_from_slice(&immie.abilities.get_count().to_le_bytes());
+    for ability in immie.abilities.iter() {
+        write_string(bytes, &ability.to_string());
+    }
+    write_optional_string(bytes, immie.held_item);
+    write_optional_string(bytes, immie.passive);
+    bytes.extend_from_slice(&immie.damage_taken.to_le_bytes());
+    // 0 is no status, otherwise the index into STATUS_CONDITIONS plus 1.
+    let status = match immie.status {
+        Some(status) => STATUS_CONDITIONS.iter().position(|condition| *condition == status).unwrap() as u8 + 1,
+        None => 0
+    };
+    bytes.push(status);
+    for spent in immie.ability_uses_spent.iter() {
+        bytes.extend_from_slice(&spent.to_le_bytes());
+    }
+    bytes.extend_from_slice(&immie.bond.to_le_bytes());
+    write_optional_string(bytes, immie.form);
+    let individual_values = immie.individual_values;
+    bytes.extend_from_slice(&[individual_values.health, individual_values.attack, individual_values.defense, individual_values.speed]);
+    bytes.push(immie.is_locked as u8);
+    write_optional_string(bytes, immie.origin);
+    // 0 is no attunement, otherwise the id of the element.
+    bytes.push(immie.attunement.map_or(0, |element| element as u8));
+}
+
+pub(crate) fn read_immie(reader: &mut ByteReader) -> io::Result<Immie> {
+    let species = GlobalString::new(&reader.take_string()?);
+    let level = u32::from_le_bytes(reader.take_array()?);
+    let ability_count = u32::from_le_bytes(reader.take_array()?);
+    if ability_count > MAX_ABILITIES_COUNT {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("Immie has {} abilities", ability_count)));
+    }
+    let mut ability_names = Vec::new();
+    for _ in 0..ability_count {
+        let ability = GlobalString::new(&reader.take_string()?);
+        if ability_names.contains(&ability) {
+            return Err(io::Error::new(ErrorKind::InvalidData, format!("Immie has duplicate ability {}", ability)));
+        }
+        ability_names.push(ability);
+    }
+    let mut immie = Immie::new(species, level, AbilityNames::new(ability_names));
+    immie.held_item = read_optional_string(reader)?;
+    immie.passive = read_optional_string(reader)?;
+    immie.damage_taken = u32::from_le_bytes(reader.take_array()?);
+    let [status] = reader.take_array::<1>()?;
+    immie.status = match status {
+        0 => None,
+        _ => Some(*STATUS_CONDITIONS.get(status as usize - 1).ok_or(io::Error::new(ErrorKind::InvalidData, format!("Unknown status condition {}", status)))?)
+    };
+    for spent in immie.ability_uses_spent.iter_mut() {
+        *spent = u32::from_le_bytes(reader.take_array()?);
+    }
+    immie.bond = u32::from_le_bytes(reader.take_array()?);
+    if immie.bond > MAX_BOND {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("Immie has bond {} above the max of {}", immie.bond, MAX_BOND)));
+    }
+    immie.form = read_optional_string(reader)?;
+    let [health, attack, defense, speed] = reader.take_array::<4>()?;
+    if [health, attack, defense, speed].iter().any(|value| *value > MAX_INDIVIDUAL_VALUE) {
+        return Err(io::Error::new(ErrorKind::InvalidData, format!("Immie has individual values {:?} above the max", [health, attack, defense, speed])));
+    }
+    immie.individual_values = IndividualValues::new(health, attack, defense, speed);
+    let [is_locked] = reader.take_array::<1>()?;
+    immie.is_locked = is_locked != 0;
+    immie.origin = read_optional_string(reader)?;
+    let [attunement] = reader.take_array::<1>()?;
+    immie.attunement = match attunement as u32 {
+        0 => None,
+        element if element <= ELEMENT_COUNT => Some(ElementKind::from(element)),
+        _ => return Err(io::Error::new(ErrorKind::InvalidData, format!("Unknown attunement element {}", attunement)))
+    };
+    return Ok(immie);
+}
+
+fn write_optional_string(bytes: &mut Vec<u8>, string: Option<GlobalString>) {
+    match string {
+        Some(string) => {
+            bytes.push(1);
+            write_string(bytes, &string.to_string());
+        },
+        None => bytes.push(0)
+    }
+}
+
+fn read_optional_string(reader: &mut ByteReader) -> io::Result<Option<GlobalString>> {
+    let [is_some] = reader.take_array::<1>()?;
+    if is_some == 0 {
+        return Ok(None);
+    }
+    return Ok(Some(GlobalString::new(&reader.take_string()?)));
+}
+
+pub(crate) fn write_string(bytes: &mut Vec<u8>, string: &str) {
+    bytes.extend_from_slice(&(string.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(string.as_bytes());
+}
+
+pub(crate) struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl<'a> ByteReader<'a> {
+    pub(crate) fn new(bytes: &'a [u8]) -> ByteReader<'a> {
+        return ByteReader { bytes, position: 0 };
+    }
+
+    pub(crate) fn get_remaining(&self) -> usize {
+        return self.bytes.len() - self.position;
+    }
+
+    pub(crate) fn take(&mut self, count: usize) -> io::Result<&'a [u8]> {
+        if self.position + count > self.bytes.len() {
+            return Err(io::Error::new(ErrorKind::UnexpectedEof, "Stored data is truncated"));
+        }
+        let taken = &self.bytes[self.position..self.position + count];
+        self.position += count;
+        return Ok(taken);
+    }
+
+    pub(crate) fn take_array<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        return Ok(array);
+    }
+
+    pub(crate) fn take_string(&mut self) -> io::Result<String> {
+        let length = u32::from_le_bytes(self.take_array()?) as usize;
+        return String::from_utf8(self.take(length)?.to_vec()).map_err(|err| io::Error::new(ErrorKind::InvalidData, err));
+    }
+}
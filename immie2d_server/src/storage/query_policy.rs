@@ -0,0 +1,37 @@
+use std::io::{self, ErrorKind};
+use std::time::Duration;
+
+/* How long a storage query may take and how failed queries are retried. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct QueryPolicy {
+    /// Time allowed for each attempt, including waiting for a free connection.
+    pub timeout: Duration,
+    /// Attempts after the first for errors that may succeed when retried.
+    pub max_retries: u32,
+    /// Delay before the first retry, doubling after each one.
+    pub retry_delay: Duration
+}
+
+impl QueryPolicy {
+    pub fn default() -> QueryPolicy {
+        return QueryPolicy { timeout: Duration::from_secs(5), max_retries: 2, retry_delay: Duration::from_millis(50) };
+    }
+
+    /// Delay before a retry, starting from 1 for the first retry.
+    pub fn get_retry_delay(&self, retry: u32) -> Duration {
+        return self.retry_delay.saturating_mul(1 << (retry.saturating_sub(1)).min(16));
+    }
+}
+
+/// Whether a failed query may succeed if tried again, such as after a timeout or dropped connection.
+/// Errors in the data itself are never retried.
+/// ```
+/// use std::io::{Error, ErrorKind};
+/// use immie2d_server::storage::query_policy::is_retryable;
+///
+/// assert!(is_retryable(&Error::new(ErrorKind::TimedOut, "slow")));
+/// assert!(!is_retryable(&Error::new(ErrorKind::InvalidData, "corrupt")));
+/// ```
+pub fn is_retryable(err: &io::Error) -> bool {
+    return matches!(err.kind(), ErrorKind::TimedOut | ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted | ErrorKind::BrokenPipe);
+}
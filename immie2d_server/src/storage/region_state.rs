@@ -0,0 +1,107 @@
+use std::io::{self, ErrorKind};
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::world::tile_position::TilePosition;
+use immie2d_shared::world::world_object::{WorldObject, WorldObjectKind};
+
+use super::player_profile::{write_string, ByteReader};
+
+const DROPPED_ITEM_TAG: u8 = 0;
+const CUT_TREE_TAG: u8 = 1;
+const PUSHED_BOULDER_TAG: u8 = 2;
+const OPENED_CHEST_TAG: u8 = 3;
+
+/* Every persisted world object of a single region, which is a map. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct RegionState {
+    pub map: GlobalString,
+    pub objects: Vec<WorldObject>
+}
+
+impl RegionState {
+    pub fn new(map: GlobalString) -> RegionState {
+        return RegionState { map, objects: Vec::new() };
+    }
+
+    /// Remove every expired object. Returns the objects removed.
+    pub fn remove_expired(&mut self, now: u64) -> Vec<WorldObject> {
+        let (expired, remaining) = self.objects.iter().partition(|object| object.is_expired(now));
+        self.objects = remaining;
+        return expired;
+    }
+
+    /// Encode the region in the binary format used by storage.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::{tile_position::TilePosition, world_object::{WorldObject, WorldObjectKind}};
+    /// use immie2d_server::storage::region_state::RegionState;
+    ///
+    /// let mut region = RegionState::new(GlobalString::new(&"route 1".to_string()));
+    /// region.objects.push(WorldObject::new(WorldObjectKind::DroppedItem { item: GlobalString::new(&"potion".to_string()), count: 2 }, TilePosition::new(1, 2), 100));
+    /// region.objects.push(WorldObject::new(WorldObjectKind::PushedBoulder { origin: TilePosition::new(-4, 0) }, TilePosition::new(-3, 0), 100));
+    /// region.objects.push(WorldObject::new(WorldObjectKind::OpenedChest, TilePosition::new(7, 7), 100));
+    /// assert_eq!(RegionState::from_bytes(&region.to_bytes()).unwrap(), region);
+    /// assert!(RegionState::from_bytes(&region.to_bytes()[..9]).is_err());
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+        write_string(&mut bytes, &self.map.to_string());
+        bytes.extend_from_slice(&(self.objects.len() as u32).to_le_bytes());
+        for object in self.objects.iter() {
+            match object.kind {
+                WorldObjectKind::DroppedItem { item, count } => {
+                    bytes.push(DROPPED_ITEM_TAG);
+                    write_string(&mut bytes, &item.to_string());
+                    bytes.extend_from_slice(&count.to_le_bytes());
+                },
+                WorldObjectKind::CutTree => bytes.push(CUT_TREE_TAG),
+                WorldObjectKind::PushedBoulder { origin } => {
+                    bytes.push(PUSHED_BOULDER_TAG);
+                    write_position(&mut bytes, origin);
+                },
+                WorldObjectKind::OpenedChest => bytes.push(OPENED_CHEST_TAG)
+            }
+            write_position(&mut bytes, object.position);
+            // 0 is never a valid expiry time, so it stands in for permanent objects.
+            bytes.extend_from_slice(&object.expires_at.unwrap_or(0).to_le_bytes());
+        }
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> io::Result<RegionState> {
+        let mut reader = ByteReader::new(bytes);
+        let map = GlobalString::new(&reader.take_string()?);
+        let object_count = u32::from_le_bytes(reader.take_array()?);
+        let mut objects = Vec::new();
+        for _ in 0..object_count {
+            let [tag] = reader.take_array::<1>()?;
+            let kind = match tag {
+                DROPPED_ITEM_TAG => {
+                    let item = GlobalString::new(&reader.take_string()?);
+                    WorldObjectKind::DroppedItem { item, count: u32::from_le_bytes(reader.take_array()?) }
+                },
+                CUT_TREE_TAG => WorldObjectKind::CutTree,
+                PUSHED_BOULDER_TAG => WorldObjectKind::PushedBoulder { origin: read_position(&mut reader)? },
+                OPENED_CHEST_TAG => WorldObjectKind::OpenedChest,
+                _ => return Err(io::Error::new(ErrorKind::InvalidData, format!("Unknown world object tag {}", tag)))
+            };
+            let position = read_position(&mut reader)?;
+            let expires_at = match u64::from_le_bytes(reader.take_array()?) {
+                0 => None,
+                expires_at => Some(expires_at)
+            };
+            objects.push(WorldObject { kind, position, expires_at });
+        }
+        return Ok(RegionState { map, objects });
+    }
+}
+
+fn write_position(bytes: &mut Vec<u8>, position: TilePosition) {
+    bytes.extend_from_slice(&position.x.to_le_bytes());
+    bytes.extend_from_slice(&position.y.to_le_bytes());
+}
+
+fn read_position(reader: &mut ByteReader) -> io::Result<TilePosition> {
+    let x = i32::from_le_bytes(reader.take_array()?);
+    return Ok(TilePosition::new(x, i32::from_le_bytes(reader.take_array()?)));
+}
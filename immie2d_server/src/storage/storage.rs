@@ -0,0 +1,57 @@
+use std::io;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::admin::ban_list::BanList;
+use crate::auth::credentials::Credentials;
+
+use super::backup::StorageSnapshot;
+use super::player_profile::PlayerProfile;
+use super::region_state::RegionState;
+
+/// Persistent backend for player data and world state. Implementations are expected to be slow, so callers should batch writes.
+/// See WriteBehindCache
+pub trait Storage {
+    /// Load a player's profile, or None if the player has never been saved.
+    fn load_profile(&mut self, player: PlayerId) -> io::Result<Option<PlayerProfile>>;
+
+    /// Save many profiles at once. Either every profile is saved or none are.
+    fn save_profiles(&mut self, profiles: &[PlayerProfile]) -> io::Result<()>;
+
+    /// Load the persisted world state of a region, or None if nothing was ever saved for it.
+    fn load_region(&mut self, map: GlobalString) -> io::Result<Option<RegionState>>;
+
+    /// Save the world state of a region, replacing what was saved before.
+    fn save_region(&mut self, region: &RegionState) -> io::Result<()>;
+
+    /// Load the ban and allow lists, which are empty if they were never saved.
+    fn load_ban_list(&mut self) -> io::Result<BanList>;
+
+    /// Save the ban and allow lists, replacing what was saved before.
+    fn save_ban_list(&mut self, bans: &BanList) -> io::Result<()>;
+
+    /// Load the credentials of a normalized username, or None if no account has it. See normalize_username()
+    fn load_credentials(&mut self, username: &str) -> io::Result<Option<Credentials>>;
+
+    /// Save the credentials of an account, replacing what was saved for its username before.
+    fn save_credentials(&mut self, credentials: &Credentials) -> io::Result<()>;
+
+    /// Save the credentials of a new account only if no account has its username, returning whether they were saved.
+    /// Checking and saving happen at once, so two servers creating the same username can't both succeed.
+    fn create_credentials(&mut self, credentials: &Credentials) -> io::Result<bool>;
+
+    /// Record that a player claimed a gift distribution only if they haven't claimed it before, returning whether it
+    /// was recorded. Checking and recording happen at once, so two servers can't both give a player the same gift.
+    /// Claims are rebuilt from the claimed gifts of profiles when a snapshot is imported. See GiftDistributor
+    fn record_gift_claim(&mut self, player: PlayerId, distribution: GlobalString) -> io::Result<bool>;
+
+    /// Reserve a player id no other account or profile has, for a new account.
+    fn allocate_player_id(&mut self) -> io::Result<PlayerId>;
+
+    /// Read every record as of a single moment, for a backup. See create_backup()
+    fn export_snapshot(&mut self) -> io::Result<StorageSnapshot>;
+
+    /// Replace every record with those of a snapshot, such as when restoring a backup. See restore_backup()
+    fn import_snapshot(&mut self, snapshot: &StorageSnapshot) -> io::Result<()>;
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::io;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::world::tile_position::TilePosition;
+use immie2d_shared::world::world_object::WorldObject;
+
+use super::region_state::RegionState;
+use super::storage::Storage;
+
+/* Transient world state of every region that players have visited since the server started. Regions are loaded
+from storage the first time a player enters them, and every change is written straight through since they are rare
+compared to profile changes. Times are unix seconds. */
+pub struct WorldStateStore {
+    regions: HashMap<GlobalString, RegionState>
+}
+
+impl WorldStateStore {
+    pub fn new() -> WorldStateStore {
+        return WorldStateStore { regions: HashMap::new() };
+    }
+
+    pub fn is_region_loaded(&self, map: GlobalString) -> bool {
+        return self.regions.contains_key(&map);
+    }
+
+    /// Get the objects of a loaded region. Regions that aren't loaded have none.
+    pub fn get_objects(&self, map: GlobalString) -> &[WorldObject] {
+        return match self.regions.get(&map) {
+            Some(region) => &region.objects,
+            None => &[]
+        };
+    }
+
+    /// Load a region if needed and get every object in it that hasn't expired, to sync to a client entering it.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::{tile_position::TilePosition, world_object::{WorldObject, WorldObjectKind, DROPPED_ITEM_TTL}};
+    /// use immie2d_server::storage::{memory_storage::MemoryStorage, world_state_store::WorldStateStore};
+    ///
+    /// let mut storage = MemoryStorage::new();
+    /// let route = GlobalString::new(&"route 1".to_string());
+    /// let potion = WorldObject::new(WorldObjectKind::DroppedItem { item: GlobalString::new(&"potion".to_string()), count: 1 }, TilePosition::new(2, 2), 100);
+    /// let chest = WorldObject::new(WorldObjectKind::OpenedChest, TilePosition::new(5, 5), 100);
+    ///
+    /// let mut store = WorldStateStore::new();
+    /// store.add_object(&mut storage, route, potion).unwrap();
+    /// store.add_object(&mut storage, route, chest).unwrap();
+    ///
+    /// // After a restart the state is loaded back from storage, without the items that despawned.
+    /// let mut restarted = WorldStateStore::new();
+    /// assert_eq!(restarted.enter_region(&mut storage, route, 200).unwrap(), vec![potion, chest]);
+    /// let mut restarted = WorldStateStore::new();
+    /// assert_eq!(restarted.enter_region(&mut storage, route, 100 + DROPPED_ITEM_TTL.as_secs()).unwrap(), vec![chest]);
+    /// ```
+    pub fn enter_region<S: Storage>(&mut self, storage: &mut S, map: GlobalString, now: u64) -> io::Result<Vec<WorldObject>> {
+        self.load_region(storage, map)?;
+        let region = self.regions.get_mut(&map).unwrap();
+        if !region.remove_expired(now).is_empty() {
+            storage.save_region(region)?;
+        }
+        return Ok(region.objects.clone());
+    }
+
+    /// Add an object to a region, replacing any object already on the same tile.
+    pub fn add_object<S: Storage>(&mut self, storage: &mut S, map: GlobalString, object: WorldObject) -> io::Result<()> {
+        self.load_region(storage, map)?;
+        let region = self.regions.get_mut(&map).unwrap();
+        region.objects.retain(|existing| existing.position != object.position);
+        region.objects.push(object);
+        return storage.save_region(region);
+    }
+
+    /// Remove the object on a tile of a region, such as an item being picked up. Returns the object removed.
+    pub fn remove_object<S: Storage>(&mut self, storage: &mut S, map: GlobalString, position: TilePosition) -> io::Result<Option<WorldObject>> {
+        self.load_region(storage, map)?;
+        let region = self.regions.get_mut(&map).unwrap();
+        let index = match region.objects.iter().position(|object| object.position == position) {
+            Some(index) => index,
+            None => return Ok(None)
+        };
+        let removed = region.objects.remove(index);
+        storage.save_region(region)?;
+        return Ok(Some(removed));
+    }
+
+    /// Remove expired objects from every loaded region. Returns the objects removed and their region, for
+    /// telling clients in those regions to despawn them.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::{tile_position::TilePosition, world_object::{WorldObject, WorldObjectKind, CUT_TREE_TTL}};
+    /// use immie2d_server::storage::{memory_storage::MemoryStorage, storage::Storage, world_state_store::WorldStateStore};
+    ///
+    /// let mut storage = MemoryStorage::new();
+    /// let forest = GlobalString::new(&"forest".to_string());
+    /// let tree = WorldObject::new(WorldObjectKind::CutTree, TilePosition::new(1, 1), 0);
+    /// let mut store = WorldStateStore::new();
+    /// store.add_object(&mut storage, forest, tree).unwrap();
+    ///
+    /// assert!(store.expire(&mut storage, CUT_TREE_TTL.as_secs() - 1).unwrap().is_empty());
+    /// assert_eq!(store.expire(&mut storage, CUT_TREE_TTL.as_secs()).unwrap(), vec![(forest, tree)]);
+    /// assert!(storage.load_region(forest).unwrap().unwrap().objects.is_empty());
+    /// ```
+    pub fn expire<S: Storage>(&mut self, storage: &mut S, now: u64) -> io::Result<Vec<(GlobalString, WorldObject)>> {
+        let mut expired = Vec::new();
+        for region in self.regions.values_mut() {
+            let removed = region.remove_expired(now);
+            if removed.is_empty() {
+                continue;
+            }
+            storage.save_region(region)?;
+            expired.extend(removed.into_iter().map(|object| (region.map, object)));
+        }
+        return Ok(expired);
+    }
+
+    /// Drop a region from memory once no players are in it. Its state is already in storage.
+    pub fn unload_region(&mut self, map: GlobalString) {
+        self.regions.remove(&map);
+    }
+
+    fn load_region<S: Storage>(&mut self, storage: &mut S, map: GlobalString) -> io::Result<()> {
+        if self.regions.contains_key(&map) {
+            return Ok(());
+        }
+        let region = storage.load_region(map)?.unwrap_or(RegionState::new(map));
+        self.regions.insert(map, region);
+        return Ok(());
+    }
+}
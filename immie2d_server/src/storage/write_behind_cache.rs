@@ -0,0 +1,168 @@
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use super::journal::Journal;
+use super::player_profile::PlayerProfile;
+use super::storage::Storage;
+
+/* In-memory authoritative copy of online players' profiles, written to storage in periodic batches.
+Every change is journaled before it is applied, so changes that weren't flushed yet survive a crash. */
+pub struct WriteBehindCache<S: Storage> {
+    storage: S,
+    journal: Journal,
+    profiles: HashMap<PlayerId, PlayerProfile>,
+    dirty: BTreeSet<PlayerId>,
+    flush_interval: Duration,
+    last_flush: Instant
+}
+
+impl<S: Storage> WriteBehindCache<S> {
+    /// Open the cache, first recovering any changes left in the journal by a crash into storage.
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::storage::{journal::Journal, memory_storage::MemoryStorage, player_profile::PlayerProfile, storage::Storage, write_behind_cache::WriteBehindCache};
+    ///
+    /// let path = std::env::temp_dir().join("immie2d_cache_recovery_doctest.journal");
+    /// # Journal::remove(&path).unwrap();
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// profile.inventory.add_item(GlobalString::new(&"potion".to_string()), 2);
+    /// // A change was journaled but the server crashed before flushing
+    /// Journal::open(&path).unwrap().append(&profile).unwrap();
+    ///
+    /// let cache = WriteBehindCache::open(MemoryStorage::new(), &path, Duration::from_secs(30), Instant::now()).unwrap();
+    /// let mut storage = cache.shutdown().unwrap();
+    /// assert_eq!(storage.load_profile(PlayerId(1)).unwrap(), Some(profile));
+    /// ```
+    pub fn open(mut storage: S, journal_path: &Path, flush_interval: Duration, now: Instant) -> io::Result<WriteBehindCache<S>> {
+        let mut recovered: HashMap<PlayerId, PlayerProfile> = HashMap::new();
+        for profile in Journal::read_records(journal_path)? {
+            recovered.insert(profile.player, profile);
+        }
+        if !recovered.is_empty() {
+            let profiles: Vec<PlayerProfile> = recovered.into_values().collect();
+            storage.save_profiles(&profiles)?;
+        }
+        let mut journal = Journal::open(journal_path)?;
+        journal.clear()?;
+        return Ok(WriteBehindCache {
+            storage,
+            journal,
+            profiles: HashMap::new(),
+            dirty: BTreeSet::new(),
+            flush_interval,
+            last_flush: now
+        });
+    }
+
+    /// Get a cached profile, loading it from storage if it isn't cached yet.
+    pub fn load(&mut self, player: PlayerId) -> io::Result<Option<&PlayerProfile>> {
+        if !self.profiles.contains_key(&player) {
+            match self.storage.load_profile(player)? {
+                Some(profile) => { self.profiles.insert(player, profile); },
+                None => return Ok(None)
+            }
+        }
+        return Ok(self.profiles.get(&player));
+    }
+
+    /// Get a profile only if it is already cached.
+    pub fn get(&self, player: PlayerId) -> Option<&PlayerProfile> {
+        return self.profiles.get(&player);
+    }
+
+    /// Add a new or replacement profile. It will be written on the next flush.
+    pub fn insert(&mut self, profile: PlayerProfile) -> io::Result<()> {
+        self.journal.append(&profile)?;
+        self.dirty.insert(profile.player);
+        self.profiles.insert(profile.player, profile);
+        return Ok(());
+    }
+
+    /// Change a cached profile. Returns false if the profile isn't cached, and leaves it unchanged if the change can't be
+    /// journaled. See WriteBehindCache::load()
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::storage::{journal::Journal, memory_storage::MemoryStorage, player_profile::PlayerProfile, write_behind_cache::WriteBehindCache};
+    ///
+    /// let path = std::env::temp_dir().join("immie2d_cache_modify_doctest.journal");
+    /// # Journal::remove(&path).unwrap();
+    /// let start = Instant::now();
+    /// let mut cache = WriteBehindCache::open(MemoryStorage::new(), &path, Duration::from_secs(30), start).unwrap();
+    /// cache.insert(PlayerProfile::new(PlayerId(1), "ash".to_string())).unwrap();
+    ///
+    /// let potion = GlobalString::new(&"potion".to_string());
+    /// for _ in 0..10 {
+    ///     cache.modify(PlayerId(1), |profile| profile.inventory.add_item(potion, 1)).unwrap();
+    /// }
+    /// // Nothing is written until the flush interval passes, and then it's a single batch
+    /// assert!(!cache.tick(start + Duration::from_secs(10)).unwrap());
+    /// assert_eq!(cache.get_storage().get_save_count(), 0);
+    /// assert!(cache.tick(start + Duration::from_secs(31)).unwrap());
+    /// assert_eq!(cache.get_storage().get_save_count(), 1);
+    /// assert_eq!(cache.get_dirty_count(), 0);
+    /// # Journal::remove(&path).unwrap();
+    /// ```
+    pub fn modify<F: FnOnce(&mut PlayerProfile)>(&mut self, player: PlayerId, change: F) -> io::Result<bool> {
+        let Some(profile) = self.profiles.get(&player) else {
+            return Ok(false);
+        };
+        // Only committed to the cache once journaled, so a failed append leaves the cache matching the journal
+        let mut changed = profile.clone();
+        change(&mut changed);
+        self.journal.append(&changed)?;
+        self.profiles.insert(player, changed);
+        self.dirty.insert(player);
+        return Ok(true);
+    }
+
+    /// Number of profiles changed since the last flush.
+    pub fn get_dirty_count(&self) -> usize {
+        return self.dirty.len();
+    }
+
+    pub fn get_storage(&self) -> &S {
+        return &self.storage;
+    }
+
+    /// Flush if the flush interval has passed since the last flush. Returns if a flush happened.
+    pub fn tick(&mut self, now: Instant) -> io::Result<bool> {
+        if now.duration_since(self.last_flush) < self.flush_interval {
+            return Ok(false);
+        }
+        self.flush()?;
+        self.last_flush = now;
+        return Ok(true);
+    }
+
+    /// Write every changed profile to storage in a single batch, then clear the journal.
+    pub fn flush(&mut self) -> io::Result<()> {
+        if self.dirty.is_empty() {
+            return Ok(());
+        }
+        let profiles: Vec<PlayerProfile> = self.dirty.iter().map(|player| self.profiles[player].clone()).collect();
+        self.storage.save_profiles(&profiles)?;
+        self.dirty.clear();
+        return self.journal.clear();
+    }
+
+    /// Force a flush when a player logs out and stop caching their profile.
+    pub fn logout(&mut self, player: PlayerId) -> io::Result<()> {
+        self.flush()?;
+        self.profiles.remove(&player);
+        return Ok(());
+    }
+
+    /// Flush everything on server shutdown, giving back the storage.
+    pub fn shutdown(mut self) -> io::Result<S> {
+        self.flush()?;
+        return Ok(self.storage);
+    }
+}
@@ -0,0 +1,2 @@
+pub mod tournament;
+pub mod tournament_results;
@@ -0,0 +1,339 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::gameplay::tournament_bracket::{BracketMatch, BracketView, MatchDecision, MatchResult, TournamentPhase};
+
+/// Fewest players a tournament runs with. Tournaments with fewer are cancelled when sign-ups close.
+pub const MIN_ENTRANTS: usize = 2;
+/// Most players a tournament takes unless configured otherwise.
+pub const DEFAULT_MAX_ENTRANTS: usize = 64;
+
+/* Why a player's tournament request was refused. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TournamentError {
+    SignUpClosed,
+    AlreadySignedUp,
+    NotSignedUp,
+    Full,
+    NotRunning,
+    /// The player has no undecided match in the current round, either because they were knocked out or their
+    /// match was already decided.
+    NoMatch,
+    /// Check-ins close at the round's deadline.
+    DeadlinePassed,
+    /// Results are only reported for matches both players checked in to.
+    NotReady
+}
+
+impl fmt::Display for TournamentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            TournamentError::SignUpClosed => write!(f, "Sign-ups for the tournament are closed"),
+            TournamentError::AlreadySignedUp => write!(f, "Player has already signed up"),
+            TournamentError::NotSignedUp => write!(f, "Player has not signed up"),
+            TournamentError::Full => write!(f, "The tournament is full"),
+            TournamentError::NotRunning => write!(f, "The tournament is not running"),
+            TournamentError::NoMatch => write!(f, "Player has no match to play this round"),
+            TournamentError::DeadlinePassed => write!(f, "The round's deadline has passed"),
+            TournamentError::NotReady => write!(f, "Both players must check in before the match is played")
+        };
+    }
+}
+
+/* What happened to a tournament, for the server to tell players about and record. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TournamentEvent {
+    Started,
+    Cancelled,
+    RoundStarted { round: u32, deadline: u64 },
+    /// Both players checked in, so their battle should start.
+    MatchReady { round: u32, index: usize, players: [PlayerId; 2] },
+    /// The loser is None for a bye.
+    MatchDecided { round: u32, index: usize, result: MatchResult, loser: Option<PlayerId> },
+    Finished { champion: PlayerId }
+}
+
+/* A scheduled single-elimination tournament. Players sign up until sign-ups close, then the bracket is seeded by
+rating, with byes going to the top seeds when the entrants don't fill it. Each round has a deadline: both players of a
+match check in before it and their battle starts, while a player whose opponent didn't show wins by walkover. When
+neither shows, the higher seed, the one earlier in get_entrants(), advances by walkover. Decided matches are recorded
+in match history with record_results(). Times are unix seconds. */
+pub struct Tournament {
+    id: u64,
+    name: String,
+    round_seconds: u64,
+    max_entrants: usize,
+    phase: TournamentPhase,
+    entrants: Vec<PlayerId>,
+    rounds: Vec<Vec<BracketMatch>>,
+    /// Players checked in to the current round.
+    checked_in: HashSet<PlayerId>
+}
+
+impl Tournament {
+    /// Will panic if the id or round length is 0, as id 0 stands for no tournament in match history.
+    pub fn new(id: u64, name: String, signup_closes_at: u64, round_seconds: u64) -> Tournament {
+        assert!(id != 0, "Tournament ids start from 1");
+        assert!(round_seconds != 0, "Rounds must last more than 0 seconds");
+        return Tournament {
+            id,
+            name,
+            round_seconds,
+            max_entrants: DEFAULT_MAX_ENTRANTS,
+            phase: TournamentPhase::SignUp { closes_at: signup_closes_at },
+            entrants: Vec::new(),
+            rounds: Vec::new(),
+            checked_in: HashSet::new()
+        };
+    }
+
+    /// Will panic if max_entrants is less than MIN_ENTRANTS.
+    pub fn with_max_entrants(mut self, max_entrants: usize) -> Tournament {
+        assert!(max_entrants >= MIN_ENTRANTS, "A tournament needs room for at least {} players", MIN_ENTRANTS);
+        self.max_entrants = max_entrants;
+        return self;
+    }
+
+    pub fn get_id(&self) -> u64 {
+        return self.id;
+    }
+
+    pub fn get_name(&self) -> &str {
+        return &self.name;
+    }
+
+    pub fn get_phase(&self) -> TournamentPhase {
+        return self.phase;
+    }
+
+    /// Players in sign-up order, then seed order once the tournament starts.
+    pub fn get_entrants(&self) -> &[PlayerId] {
+        return &self.entrants;
+    }
+
+    /// Sign a player up, while sign-ups are open and there is room.
+    pub fn sign_up(&mut self, player: PlayerId, now: u64) -> Result<(), TournamentError> {
+        self.check_signup_open(now)?;
+        if self.entrants.contains(&player) {
+            return Err(TournamentError::AlreadySignedUp);
+        }
+        if self.entrants.len() >= self.max_entrants {
+            return Err(TournamentError::Full);
+        }
+        self.entrants.push(player);
+        return Ok(());
+    }
+
+    /// Take a player back out of the tournament before sign-ups close.
+    pub fn withdraw(&mut self, player: PlayerId, now: u64) -> Result<(), TournamentError> {
+        self.check_signup_open(now)?;
+        let position = self.entrants.iter().position(|entrant| *entrant == player).ok_or(TournamentError::NotSignedUp)?;
+        self.entrants.remove(position);
+        return Ok(());
+    }
+
+    /// Check a player in to their match of the current round. Returns MatchReady once both players have.
+    pub fn check_in(&mut self, player: PlayerId, now: u64) -> Result<Option<TournamentEvent>, TournamentError> {
+        let round = self.get_current_round().ok_or(TournamentError::NotRunning)?;
+        let index = self.find_open_match(player).ok_or(TournamentError::NoMatch)?;
+        let bracket_match = self.rounds[round as usize][index];
+        if now >= bracket_match.deadline {
+            return Err(TournamentError::DeadlinePassed);
+        }
+        if !self.checked_in.insert(player) {
+            return Ok(None);
+        }
+        let [first, second] = bracket_match.players.map(|player| player.unwrap());
+        if self.checked_in.contains(&first) && self.checked_in.contains(&second) {
+            return Ok(Some(TournamentEvent::MatchReady { round, index, players: [first, second] }));
+        }
+        return Ok(None);
+    }
+
+    /// Report the winner of a battle between two checked in players, which may start the next round.
+    pub fn report_result(&mut self, winner: PlayerId, now: u64) -> Result<Vec<TournamentEvent>, TournamentError> {
+        let round = self.get_current_round().ok_or(TournamentError::NotRunning)?;
+        let index = self.find_open_match(winner).ok_or(TournamentError::NoMatch)?;
+        if !self.rounds[round as usize][index].players.iter().all(|player| self.checked_in.contains(&player.unwrap())) {
+            return Err(TournamentError::NotReady);
+        }
+        let mut events = Vec::new();
+        self.decide(round, index, MatchResult { winner, decision: MatchDecision::Played }, &mut events);
+        self.advance(now, &mut events);
+        return Ok(events);
+    }
+
+    /// Close sign-ups and seed the bracket once they are due to close, using ratings to seed, and decide walkovers
+    /// once a round's deadline passes. Matches both players checked in to wait for their result instead.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::tournament_bracket::{MatchDecision, MatchResult, TournamentPhase};
+    /// use immie2d_server::tournament::tournament::{Tournament, TournamentError, TournamentEvent};
+    ///
+    /// let mut tournament = Tournament::new(1, "weekly cup".to_string(), 100, 600);
+    /// for player in 1..=3 {
+    ///     tournament.sign_up(PlayerId(player), 50).unwrap();
+    /// }
+    /// assert_eq!(tournament.sign_up(PlayerId(1), 60), Err(TournamentError::AlreadySignedUp));
+    /// assert!(tournament.tick(99, |_| 1000).is_empty());
+    ///
+    /// // Player 3 is the top seed, so gets the bye
+    /// let events = tournament.tick(100, |player| 1000 + player.0 as u32);
+    /// assert_eq!(events[..2], [TournamentEvent::Started, TournamentEvent::RoundStarted { round: 0, deadline: 700 }]);
+    /// assert_eq!(tournament.get_entrants(), [PlayerId(3), PlayerId(2), PlayerId(1)]);
+    /// assert_eq!(tournament.get_bracket().rounds[0][0].result, Some(MatchResult { winner: PlayerId(3), decision: MatchDecision::Bye }));
+    /// assert_eq!(tournament.sign_up(PlayerId(4), 100), Err(TournamentError::SignUpClosed));
+    ///
+    /// assert_eq!(tournament.check_in(PlayerId(1), 200), Ok(None));
+    /// assert_eq!(tournament.report_result(PlayerId(1), 250), Err(TournamentError::NotReady));
+    /// assert!(tournament.check_in(PlayerId(2), 300).unwrap().is_some());
+    /// let events = tournament.report_result(PlayerId(1), 400).unwrap();
+    /// assert_eq!(events[1], TournamentEvent::RoundStarted { round: 1, deadline: 1000 });
+    ///
+    /// // Only the finalist who checks in shows, so wins by walkover
+    /// tournament.check_in(PlayerId(3), 500).unwrap();
+    /// assert!(tournament.tick(999, |_| 1000).is_empty());
+    /// let events = tournament.tick(1000, |_| 1000);
+    /// assert_eq!(events[0], TournamentEvent::MatchDecided { round: 1, index: 0, result: MatchResult { winner: PlayerId(3), decision: MatchDecision::Walkover }, loser: Some(PlayerId(1)) });
+    /// assert_eq!(tournament.get_phase(), TournamentPhase::Finished { champion: PlayerId(3) });
+    /// ```
+    pub fn tick(&mut self, now: u64, get_rating: impl Fn(PlayerId) -> u32) -> Vec<TournamentEvent> {
+        let mut events = Vec::new();
+        match self.phase {
+            TournamentPhase::SignUp { closes_at } if now >= closes_at => self.start(now, get_rating, &mut events),
+            TournamentPhase::Running { round } => {
+                let matches = &self.rounds[round as usize];
+                if matches.iter().all(|bracket_match| bracket_match.result.is_some() || now < bracket_match.deadline) {
+                    return events;
+                }
+                let walkovers: Vec<(usize, PlayerId)> = matches.iter().enumerate().filter_map(|(index, bracket_match)| {
+                    if bracket_match.result.is_some() {
+                        return None;
+                    }
+                    let [first, second] = bracket_match.players.map(|player| player.unwrap());
+                    return match (self.checked_in.contains(&first), self.checked_in.contains(&second)) {
+                        (true, true) => None,
+                        (true, false) => Some((index, first)),
+                        (false, true) => Some((index, second)),
+                        (false, false) => Some((index, self.get_higher_seed(first, second)))
+                    };
+                }).collect();
+                for (index, winner) in walkovers {
+                    self.decide(round, index, MatchResult { winner, decision: MatchDecision::Walkover }, &mut events);
+                }
+                self.advance(now, &mut events);
+            },
+            _ => {}
+        }
+        return events;
+    }
+
+    /// The bracket as clients see it.
+    pub fn get_bracket(&self) -> BracketView {
+        return BracketView { tournament: self.id, phase: self.phase, entrants: self.entrants.clone(), rounds: self.rounds.clone() };
+    }
+
+    /// Entrants are in seed order once the tournament starts.
+    fn get_higher_seed(&self, first: PlayerId, second: PlayerId) -> PlayerId {
+        let seed = |player: PlayerId| self.entrants.iter().position(|entrant| *entrant == player);
+        return if seed(second) < seed(first) { second } else { first };
+    }
+
+    fn check_signup_open(&self, now: u64) -> Result<(), TournamentError> {
+        return match self.phase {
+            TournamentPhase::SignUp { closes_at } if now < closes_at => Ok(()),
+            _ => Err(TournamentError::SignUpClosed)
+        };
+    }
+
+    fn get_current_round(&self) -> Option<u32> {
+        return match self.phase {
+            TournamentPhase::Running { round } => Some(round),
+            _ => None
+        };
+    }
+
+    /// The player's undecided match in the current round.
+    fn find_open_match(&self, player: PlayerId) -> Option<usize> {
+        let round = &self.rounds[self.get_current_round()? as usize];
+        return round.iter().position(|bracket_match| bracket_match.result.is_none() && bracket_match.has_player(player));
+    }
+
+    fn start(&mut self, now: u64, get_rating: impl Fn(PlayerId) -> u32, events: &mut Vec<TournamentEvent>) {
+        if self.entrants.len() < MIN_ENTRANTS {
+            self.phase = TournamentPhase::Cancelled;
+            events.push(TournamentEvent::Cancelled);
+            return;
+        }
+        // Stable, so players with the same rating are seeded in sign-up order
+        self.entrants.sort_by_key(|player| std::cmp::Reverse(get_rating(*player)));
+        let seeds = get_seed_order(self.entrants.len().next_power_of_two());
+        let first_round = seeds.chunks(2).map(|pair| BracketMatch::new([self.entrants.get(pair[0]).copied(), self.entrants.get(pair[1]).copied()])).collect();
+        self.rounds.push(first_round);
+        self.phase = TournamentPhase::Running { round: 0 };
+        events.push(TournamentEvent::Started);
+        self.start_round(now, events);
+        self.advance(now, events);
+    }
+
+    fn start_round(&mut self, now: u64, events: &mut Vec<TournamentEvent>) {
+        let round = self.rounds.len() as u32 - 1;
+        let deadline = now + self.round_seconds;
+        self.checked_in.clear();
+        events.push(TournamentEvent::RoundStarted { round, deadline });
+        for index in 0..self.rounds[round as usize].len() {
+            let bracket_match = &mut self.rounds[round as usize][index];
+            bracket_match.deadline = deadline;
+            if let [Some(winner), None] | [None, Some(winner)] = bracket_match.players {
+                self.decide(round, index, MatchResult { winner, decision: MatchDecision::Bye }, events);
+            }
+        }
+    }
+
+    /// Start the next round, or finish the tournament, once every match of the current round is decided.
+    fn advance(&mut self, now: u64, events: &mut Vec<TournamentEvent>) {
+        while let Some(round) = self.get_current_round() {
+            let matches = &self.rounds[round as usize];
+            if matches.iter().any(|bracket_match| bracket_match.result.is_none()) {
+                return;
+            }
+            if matches.len() == 1 {
+                let champion = matches[0].result.unwrap().winner;
+                self.phase = TournamentPhase::Finished { champion };
+                events.push(TournamentEvent::Finished { champion });
+                return;
+            }
+            let next_round = matches.chunks(2).map(|pair| BracketMatch::new([pair[0], pair[1]].map(|bracket_match| Some(bracket_match.result.unwrap().winner)))).collect();
+            self.rounds.push(next_round);
+            self.phase = TournamentPhase::Running { round: round + 1 };
+            self.start_round(now, events);
+        }
+    }
+
+    fn decide(&mut self, round: u32, index: usize, result: MatchResult, events: &mut Vec<TournamentEvent>) {
+        let bracket_match = &mut self.rounds[round as usize][index];
+        bracket_match.result = Some(result);
+        let loser = bracket_match.get_opponent(result.winner);
+        events.push(TournamentEvent::MatchDecided { round, index, result, loser });
+    }
+}
+
+/// Bracket positions of each seed, so the top seeds only meet in the last rounds and byes go to the top seeds. Every
+/// pair of positions is a first round match.
+/// ```
+/// use immie2d_server::tournament::tournament::get_seed_order;
+///
+/// assert_eq!(get_seed_order(4), vec![0, 3, 1, 2]);
+/// assert_eq!(get_seed_order(8), vec![0, 7, 3, 4, 1, 6, 2, 5]);
+/// ```
+/// Will panic if size is not a power of two.
+pub fn get_seed_order(size: usize) -> Vec<usize> {
+    assert!(size.is_power_of_two(), "Bracket size {} is not a power of two", size);
+    let mut order = vec![0];
+    while order.len() < size {
+        let length = order.len() * 2;
+        order = order.iter().flat_map(|seed| [*seed, length - 1 - seed]).collect();
+    }
+    return order;
+}
@@ -0,0 +1,98 @@
+use std::io::{self, ErrorKind};
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::gameplay::tournament_bracket::{MatchDecision, MatchResult};
+
+use crate::matchmaking::rating::get_rating_change;
+use crate::storage::player_profile::MatchRecord;
+use crate::storage::storage::Storage;
+
+use super::tournament::TournamentEvent;
+
+/// Record a decided tournament match in both players' match history and save them together. Played matches move
+/// ratings, while walkovers are recorded without changing them. Byes have no opponent, so aren't recorded.
+/// ```
+/// use immie2d_shared::gameplay::player_id::PlayerId;
+/// use immie2d_shared::gameplay::tournament_bracket::{MatchDecision, MatchResult};
+/// use immie2d_server::storage::{memory_storage::MemoryStorage, player_profile::PlayerProfile, storage::Storage};
+/// use immie2d_server::tournament::tournament_results::record_result;
+///
+/// let mut storage = MemoryStorage::new();
+/// storage.save_profiles(&[PlayerProfile::new(PlayerId(1), "ash".to_string()), PlayerProfile::new(PlayerId(2), "misty".to_string())]).unwrap();
+///
+/// record_result(&mut storage, 7, MatchResult { winner: PlayerId(1), decision: MatchDecision::Played }, PlayerId(2), 500).unwrap();
+/// let winner = storage.load_profile(PlayerId(1)).unwrap().unwrap();
+/// let loser = storage.load_profile(PlayerId(2)).unwrap().unwrap();
+/// assert_eq!((winner.rating, loser.rating), (1016, 984));
+/// assert_eq!(loser.match_history[0].opponent, PlayerId(1));
+/// assert_eq!(loser.match_history[0].tournament, Some(7));
+///
+/// record_result(&mut storage, 7, MatchResult { winner: PlayerId(2), decision: MatchDecision::Walkover }, PlayerId(1), 900).unwrap();
+/// let loser = storage.load_profile(PlayerId(1)).unwrap().unwrap();
+/// assert_eq!(loser.rating, 1016);
+/// assert_eq!(loser.match_history.len(), 2);
+/// assert!(record_result(&mut storage, 7, MatchResult { winner: PlayerId(3), decision: MatchDecision::Played }, PlayerId(1), 900).is_err());
+/// ```
+pub fn record_result<S: Storage>(storage: &mut S, tournament: u64, result: MatchResult, loser: PlayerId, unix_seconds: u64) -> io::Result<()> {
+    if result.decision == MatchDecision::Bye {
+        return Ok(());
+    }
+    let load = |storage: &mut S, player: PlayerId| -> io::Result<_> {
+        return storage.load_profile(player)?.ok_or(io::Error::new(ErrorKind::NotFound, format!("No profile for player {}", player)));
+    };
+    let mut winner_profile = load(storage, result.winner)?;
+    let mut loser_profile = load(storage, loser)?;
+    let rating_change = match result.decision {
+        MatchDecision::Played => get_rating_change(winner_profile.rating, loser_profile.rating) as i32,
+        _ => 0
+    };
+    let record = MatchRecord { opponent: loser, won: true, decision: result.decision, rating_change, tournament: Some(tournament), unix_seconds };
+    winner_profile.record_match(record);
+    loser_profile.record_match(MatchRecord { opponent: result.winner, won: false, rating_change: -rating_change, ..record });
+    return storage.save_profiles(&[winner_profile, loser_profile]);
+}
+
+/// Record every match decided by a batch of events from Tournament::tick() or Tournament::report_result(), stopping at
+/// the first that fails to save. See record_result()
+/// ```
+/// use immie2d_shared::gameplay::player_id::PlayerId;
+/// use immie2d_shared::gameplay::tournament_bracket::MatchDecision;
+/// use immie2d_server::storage::{memory_storage::MemoryStorage, player_profile::PlayerProfile, storage::Storage};
+/// use immie2d_server::tournament::tournament::Tournament;
+/// use immie2d_server::tournament::tournament_results::record_results;
+///
+/// let mut storage = MemoryStorage::new();
+/// let mut tournament = Tournament::new(3, "weekly cup".to_string(), 100, 600);
+/// for (id, name) in [(1, "ash"), (2, "misty"), (3, "brock"), (4, "gary")] {
+///     storage.save_profiles(&[PlayerProfile::new(PlayerId(id), name.to_string())]).unwrap();
+///     tournament.sign_up(PlayerId(id), 50).unwrap();
+/// }
+/// let events = tournament.tick(100, |player| 1000 + player.0 as u32);
+/// record_results(&mut storage, tournament.get_id(), &events, 100).unwrap();
+///
+/// // The bottom seed knocks out the top seed, and the second seed wins their match
+/// for (winner, loser) in [(PlayerId(1), PlayerId(4)), (PlayerId(3), PlayerId(2))] {
+///     tournament.check_in(winner, 200).unwrap();
+///     tournament.check_in(loser, 200).unwrap();
+///     let events = tournament.report_result(winner, 300).unwrap();
+///     record_results(&mut storage, tournament.get_id(), &events, 300).unwrap();
+/// }
+/// let top_seed = storage.load_profile(PlayerId(4)).unwrap().unwrap();
+/// assert_eq!((top_seed.match_history[0].opponent, top_seed.match_history[0].tournament), (PlayerId(1), Some(3)));
+///
+/// // Nobody shows for the final, so the higher of the two seeds left wins by walkover
+/// let events = tournament.tick(900, |_| 1000);
+/// record_results(&mut storage, tournament.get_id(), &events, 900).unwrap();
+/// let champion = storage.load_profile(PlayerId(3)).unwrap().unwrap();
+/// assert!(champion.match_history[1].won);
+/// assert_eq!(champion.match_history[1].decision, MatchDecision::Walkover);
+/// assert!(!storage.load_profile(PlayerId(1)).unwrap().unwrap().match_history[1].won);
+/// ```
+pub fn record_results<S: Storage>(storage: &mut S, tournament: u64, events: &[TournamentEvent], unix_seconds: u64) -> io::Result<()> {
+    for event in events {
+        if let TournamentEvent::MatchDecided { result, loser: Some(loser), .. } = *event {
+            record_result(storage, tournament, result, loser, unix_seconds)?;
+        }
+    }
+    return Ok(());
+}
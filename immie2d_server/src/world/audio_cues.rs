@@ -0,0 +1,49 @@
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::world::audio_cue::{AudioCueHint, AudioCueLayer, AudioCueTable, BattleCueKind, AUDIO_CUE_LAYER_COUNT};
+
+use crate::network::send_queue::{MessagePriority, OutboundMessage};
+use crate::world::weather_scheduler::WEATHER_COALESCE_KEY;
+
+/// Snapshot coalesce key of the region and scripted event layers, offset by the layer id. Only the newest hint of a
+/// layer matters, so a newer one replaces an older one that hasn't been sent yet.
+pub const AUDIO_CUE_COALESCE_KEY: u32 = WEATHER_COALESCE_KEY - AUDIO_CUE_LAYER_COUNT as u32;
+
+/// Message to send a cue hint to a client. Battle hints go with the battle's messages, so the music changes as the
+/// battle starts rather than after its first events.
+/// ```
+/// use immie2d_shared::world::audio_cue::{AudioCueHint, AudioCueLayer};
+/// use immie2d_server::network::send_queue::MessagePriority;
+/// use immie2d_server::world::audio_cues::get_audio_cue_message;
+///
+/// let battle = get_audio_cue_message(&AudioCueHint { layer: AudioCueLayer::Battle, cue: None });
+/// assert_eq!(battle.priority, MessagePriority::Battle);
+/// let region = get_audio_cue_message(&AudioCueHint { layer: AudioCueLayer::Region, cue: None });
+/// assert_eq!(region.priority, MessagePriority::Snapshot);
+/// assert_eq!(AudioCueHint::from_bytes(&region.payload), Some(AudioCueHint { layer: AudioCueLayer::Region, cue: None }));
+/// ```
+pub fn get_audio_cue_message(hint: &AudioCueHint) -> OutboundMessage {
+    return match hint.layer {
+        AudioCueLayer::Battle => OutboundMessage::new(MessagePriority::Battle, hint.to_bytes()),
+        layer => OutboundMessage::snapshot(AUDIO_CUE_COALESCE_KEY + layer as u32, hint.to_bytes())
+    };
+}
+
+/// Hint to send a client entering a region. Regions without a cue clear the region layer.
+pub fn get_region_hint(table: &AudioCueTable, map: GlobalString) -> AudioCueHint {
+    return AudioCueHint { layer: AudioCueLayer::Region, cue: table.get_region_cue(map) };
+}
+
+/// Hint to send every player of a battle as it starts. Clear the battle layer once it ends. See get_clear_hint()
+pub fn get_battle_hint(table: &AudioCueTable, kind: BattleCueKind) -> AudioCueHint {
+    return AudioCueHint { layer: AudioCueLayer::Battle, cue: table.get_battle_cue(kind) };
+}
+
+/// Hint to send the players watching a scripted event as it starts.
+pub fn get_event_hint(table: &AudioCueTable, event: GlobalString) -> AudioCueHint {
+    return AudioCueHint { layer: AudioCueLayer::ScriptedEvent, cue: table.get_event_cue(event) };
+}
+
+/// Hint to go back to the lower layers once a battle or scripted event ends.
+pub fn get_clear_hint(layer: AudioCueLayer) -> AudioCueHint {
+    return AudioCueHint { layer, cue: None };
+}
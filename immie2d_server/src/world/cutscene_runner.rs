@@ -0,0 +1,154 @@
+use std::collections::{HashMap, HashSet};
+
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::world::cutscene::{CutsceneCue, CutsceneCueMessage};
+use immie2d_shared::world::cutscene_script::{CutsceneCondition, CutsceneStep};
+use immie2d_shared::world::tile_position::TilePosition;
+
+use crate::network::send_queue::{MessagePriority, OutboundMessage};
+
+/* A path an actor was sent along, from the tick it started walking. */
+struct ActorMove {
+    path: Vec<TilePosition>,
+    ticks_per_tile: u32,
+    start_tick: u32
+}
+
+impl ActorMove {
+    fn get_arrival_tick(&self) -> u32 {
+        return self.start_tick.saturating_add(self.ticks_per_tile.saturating_mul(self.path.len() as u32));
+    }
+}
+
+/* Runs a cutscene on the server, one world tick at a time, so every player in it sees the same moment. Each cue is
+sent to all of its players as it happens, and waits hold the cutscene until their condition is met. */
+pub struct CutsceneRunner {
+    id: u32,
+    steps: Vec<CutsceneStep>,
+    next_step: usize,
+    tick: u32,
+    /// Tick the current wait for ticks started on.
+    wait_started: Option<u32>,
+    players: Vec<PlayerId>,
+    /// Players that haven't closed the current dialogue.
+    reading: Vec<PlayerId>,
+    moves: HashMap<u32, ActorMove>,
+    flags: HashSet<String>
+}
+
+impl CutsceneRunner {
+    /// Run the steps of a cutscene for the players in it. See CutsceneScript::build()
+    pub fn new(id: u32, steps: Vec<CutsceneStep>, players: Vec<PlayerId>) -> CutsceneRunner {
+        return CutsceneRunner { id, steps, next_step: 0, tick: 0, wait_started: None, players, reading: Vec::new(), moves: HashMap::new(), flags: HashSet::new() };
+    }
+
+    pub fn get_id(&self) -> u32 {
+        return self.id;
+    }
+
+    pub fn get_players(&self) -> &[PlayerId] {
+        return &self.players;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.next_step >= self.steps.len();
+    }
+
+    /// Run one world tick of the cutscene. Returns the cues that happened, to send to every player in it.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::world::cutscene::{CutsceneCue, CutsceneCueMessage};
+    /// use immie2d_shared::world::cutscene_script::CutsceneScript;
+    /// use immie2d_shared::world::tile_position::TilePosition;
+    /// use immie2d_server::network::send_queue::OutboundMessage;
+    /// use immie2d_server::world::cutscene_runner::CutsceneRunner;
+    ///
+    /// let script = CutsceneScript::compile("rival_intro", r#"
+    ///     fn play() {
+    ///         this.move_actor(12, [[4, 2], [4, 3]], 2);
+    ///         this.wait_for_arrival(12);
+    ///         this.say("Rival", "There you are!");
+    ///         this.wait_for_dialogue();
+    ///         this.wait_for_flag("rival_defeated");
+    ///     }
+    /// "#).unwrap();
+    /// let mut runner = CutsceneRunner::new(1, script.build().unwrap(), vec![PlayerId(1), PlayerId(2)]);
+    /// let cues = |messages: Vec<OutboundMessage>| messages.iter().map(|message| CutsceneCueMessage::from_bytes(&message.payload).unwrap().cue).collect::<Vec<_>>();
+    ///
+    /// // Started and the move happen at once, then the rival walks for 4 ticks
+    /// assert_eq!(cues(runner.tick()).len(), 2);
+    /// assert!(runner.tick().is_empty());
+    /// assert_eq!(runner.get_actor_position(12), Some(TilePosition::new(4, 2)));
+    /// assert!(runner.tick().is_empty());
+    /// assert!(runner.tick().is_empty());
+    /// assert!(matches!(cues(runner.tick())[..], [CutsceneCue::ShowDialogue { .. }]));
+    /// assert_eq!(runner.get_actor_position(12), Some(TilePosition::new(4, 3)));
+    ///
+    /// // Waits for both players to read the dialogue, and the battle the world starts
+    /// runner.close_dialogue(PlayerId(1));
+    /// assert!(runner.tick().is_empty());
+    /// runner.remove_player(PlayerId(2));
+    /// assert!(runner.tick().is_empty());
+    /// runner.set_flag("rival_defeated");
+    /// assert_eq!(cues(runner.tick()), vec![CutsceneCue::Ended]);
+    /// assert!(runner.is_finished());
+    /// ```
+    pub fn tick(&mut self) -> Vec<OutboundMessage> {
+        let mut messages = Vec::new();
+        while let Some(step) = self.steps.get(self.next_step) {
+            match step {
+                CutsceneStep::Cue(cue) => {
+                    match cue {
+                        CutsceneCue::MoveActor { actor, path, ticks_per_tile } => {
+                            self.moves.insert(*actor, ActorMove { path: path.clone(), ticks_per_tile: *ticks_per_tile, start_tick: self.tick });
+                        },
+                        CutsceneCue::ShowDialogue { .. } => self.reading = self.players.clone(),
+                        _ => ()
+                    }
+                    let message = CutsceneCueMessage { cutscene: self.id, tick: self.tick, cue: cue.clone() };
+                    // Cues are never coalesced, since skipping one would leave a client out of step
+                    messages.push(OutboundMessage::new(MessagePriority::Chat, message.to_bytes()));
+                },
+                CutsceneStep::Wait(condition) => {
+                    let is_met = match condition {
+                        CutsceneCondition::Ticks(ticks) => self.tick >= self.wait_started.get_or_insert(self.tick).saturating_add(*ticks),
+                        CutsceneCondition::Arrival(actor) => self.moves.get(actor).is_none_or(|actor_move| self.tick >= actor_move.get_arrival_tick()),
+                        CutsceneCondition::DialogueClosed => self.reading.is_empty(),
+                        CutsceneCondition::Flag(flag) => self.flags.contains(flag)
+                    };
+                    if !is_met {
+                        break;
+                    }
+                    self.wait_started = None;
+                }
+            }
+            self.next_step += 1;
+        }
+        self.tick = self.tick.saturating_add(1);
+        return messages;
+    }
+
+    /// The tile the cutscene has walked an actor to so far, for the world to move it to. None if it hasn't taken a
+    /// step yet.
+    pub fn get_actor_position(&self, actor: u32) -> Option<TilePosition> {
+        let actor_move = self.moves.get(&actor)?;
+        let steps_taken = (self.tick.saturating_sub(actor_move.start_tick) / actor_move.ticks_per_tile.max(1)) as usize;
+        return actor_move.path.get(steps_taken.min(actor_move.path.len()).checked_sub(1)?).copied();
+    }
+
+    /// A player closed the dialogue being shown.
+    pub fn close_dialogue(&mut self, player: PlayerId) {
+        self.reading.retain(|reading| *reading != player);
+    }
+
+    /// Set a flag waited on by the cutscene. Flags stay set until the cutscene ends.
+    pub fn set_flag(&mut self, flag: &str) {
+        self.flags.insert(flag.to_string());
+    }
+
+    /// Stop waiting on a player that left, such as by disconnecting.
+    pub fn remove_player(&mut self, player: PlayerId) {
+        self.players.retain(|other| *other != player);
+        self.close_dialogue(player);
+    }
+}
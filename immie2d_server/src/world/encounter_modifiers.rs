@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::encounter::encounter_modifier::{EncounterModifierEvent, EncounterModifiers};
+use immie2d_shared::gameplay::item::{inventory::Inventory, item_map::ItemMap, item_use::{use_encounter_modifier, ItemUseError}};
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+use crate::network::send_queue::{MessagePriority, OutboundMessage};
+
+/* The encounter modifiers of every player in the overworld. Modifiers only ever change on the server, which counts
+steps as it grants them, so a client can't extend a repel by not reporting its steps. Modifiers are kept while a
+player is online and lost when they log out. */
+pub struct EncounterModifierTracker {
+    players: HashMap<PlayerId, EncounterModifiers>
+}
+
+impl EncounterModifierTracker {
+    pub fn new() -> EncounterModifierTracker {
+        return EncounterModifierTracker { players: HashMap::new() };
+    }
+
+    /// The active modifiers of a player, to roll their encounters with. See EncounterRoller::roll_modified()
+    pub fn get_modifiers(&self, player: PlayerId) -> EncounterModifiers {
+        return self.players.get(&player).copied().unwrap_or_default();
+    }
+
+    /// Use an encounter modifier item from a player's inventory. Returns the messages to send to the player. Nothing
+    /// is tracked for the player if the item couldn't be used.
+    pub fn use_item(&mut self, player: PlayerId, item_map: &ItemMap, inventory: &mut Inventory, item_name: GlobalString) -> Result<Vec<OutboundMessage>, ItemUseError> {
+        let mut modifiers = self.get_modifiers(player);
+        let events = use_encounter_modifier(item_map, inventory, &mut modifiers, item_name)?;
+        self.players.insert(player, modifiers);
+        return Ok(events.iter().map(get_modifier_event_message).collect());
+    }
+
+    /// Count a step the server granted a player. Returns the messages to send to the player for every modifier that
+    /// wore off.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::elements::element_kinds::ElementKind;
+    /// use immie2d_shared::gameplay::encounter::encounter_modifier::{EncounterModifier, EncounterModifierEvent, EncounterModifierKind};
+    /// use immie2d_shared::gameplay::item::{item_map::ItemMap, item_data::{ItemData, ItemEffect}, inventory::Inventory};
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::world::encounter_modifiers::EncounterModifierTracker;
+    ///
+    /// let lure = GlobalString::new(&"ember lure".to_string());
+    /// let kind = EncounterModifierKind::Lure(ElementKind::Fire);
+    /// let mut item_map = ItemMap::new();
+    /// item_map.add_item(ItemData::new_overworld(lure, ItemEffect::EncounterModifier(EncounterModifier { kind, steps: 2 })));
+    /// let mut inventory = Inventory::new();
+    /// inventory.add_item(lure, 1);
+    /// let mut tracker = EncounterModifierTracker::new();
+    ///
+    /// let messages = tracker.use_item(PlayerId(1), &item_map, &mut inventory, lure).unwrap();
+    /// assert_eq!(EncounterModifierEvent::from_bytes(&messages[0].payload), Some(EncounterModifierEvent::Started { kind, steps: 2 }));
+    /// assert!(tracker.step(PlayerId(1)).is_empty());
+    /// assert_eq!(tracker.get_modifiers(PlayerId(1)).get_steps(kind), 1);
+    /// let messages = tracker.step(PlayerId(1));
+    /// assert_eq!(EncounterModifierEvent::from_bytes(&messages[0].payload), Some(EncounterModifierEvent::Expired { kind }));
+    /// assert!(!tracker.get_modifiers(PlayerId(1)).is_active());
+    /// ```
+    pub fn step(&mut self, player: PlayerId) -> Vec<OutboundMessage> {
+        let Some(modifiers) = self.players.get_mut(&player) else {
+            return Vec::new();
+        };
+        let events = modifiers.step();
+        if !modifiers.is_active() {
+            self.players.remove(&player);
+        }
+        return events.iter().map(get_modifier_event_message).collect();
+    }
+
+    /// Forget the modifiers of a player logging out.
+    pub fn remove_player(&mut self, player: PlayerId) {
+        self.players.remove(&player);
+    }
+}
+
+/// Message to send an encounter modifier event to its player. Events are never coalesced, since the client times each
+/// modifier separately.
+pub fn get_modifier_event_message(event: &EncounterModifierEvent) -> OutboundMessage {
+    return OutboundMessage::new(MessagePriority::Snapshot, event.to_bytes());
+}
@@ -0,0 +1,168 @@
+use std::collections::{HashMap, VecDeque};
+
+/// World ticks a despawned entity's network id waits before it's given to another entity, so a client that missed the
+/// despawn has had plenty of snapshots without the entity before the id means something else.
+pub const NETWORK_ID_QUARANTINE_TICKS: u64 = 200;
+
+/* A reference to an entity in an EntityStore. Slots of despawned entities are reused, and each reuse bumps the slot's
+generation, so an id kept after its entity despawned never refers to the entity that replaced it. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct EntityId {
+    index: u32,
+    generation: u32
+}
+
+impl EntityId {
+    pub fn get_index(&self) -> u32 {
+        return self.index;
+    }
+
+    pub fn get_generation(&self) -> u32 {
+        return self.generation;
+    }
+}
+
+struct EntitySlot<T> {
+    generation: u32,
+    /// The entity and its network id, or None while the slot is free.
+    entity: Option<(T, u32)>
+}
+
+/* The overworld entities of the server, such as players, followers and NPCs. Storage grows as entities spawn and
+reuses the slots of despawned ones, so ids stay small however long the server runs. Clients know entities by a network
+id instead, which is only reused once it has been out of snapshots for NETWORK_ID_QUARANTINE_TICKS. */
+pub struct EntityStore<T> {
+    slots: Vec<EntitySlot<T>>,
+    free_slots: Vec<u32>,
+    count: usize,
+    network_ids: HashMap<u32, EntityId>,
+    next_network_id: u32,
+    free_network_ids: Vec<u32>,
+    /// Network ids of despawned entities, with the tick they can be reused from, oldest first.
+    quarantined_network_ids: VecDeque<(u32, u64)>
+}
+
+impl<T> EntityStore<T> {
+    pub fn new() -> EntityStore<T> {
+        return EntityStore {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            count: 0,
+            network_ids: HashMap::new(),
+            next_network_id: 0,
+            free_network_ids: Vec::new(),
+            quarantined_network_ids: VecDeque::new()
+        };
+    }
+
+    /// Add an entity on a world tick, reusing a free slot and network id if there are any.
+    /// Will panic if every network id is in use.
+    /// ```
+    /// use immie2d_server::world::entity_store::{EntityStore, NETWORK_ID_QUARANTINE_TICKS};
+    ///
+    /// let mut store = EntityStore::new();
+    /// let rival = store.spawn("rival", 0);
+    /// assert_eq!(store.despawn(rival, 10), Some("rival"));
+    ///
+    /// // The slot is reused straight away, but not the network id
+    /// let shopkeeper = store.spawn("shopkeeper", 11);
+    /// assert_eq!(shopkeeper.get_index(), rival.get_index());
+    /// assert_eq!(store.get(rival), None);
+    /// assert_eq!(store.get(shopkeeper), Some(&"shopkeeper"));
+    /// assert_ne!(store.get_network_id(shopkeeper), Some(0));
+    ///
+    /// // Once quarantined for long enough, the network id is reused too
+    /// let guard = store.spawn("guard", 10 + NETWORK_ID_QUARANTINE_TICKS);
+    /// assert_eq!(store.get_network_id(guard), Some(0));
+    /// assert_eq!(store.get_by_network_id(0), Some(guard));
+    /// ```
+    pub fn spawn(&mut self, entity: T, tick: u64) -> EntityId {
+        while let Some((network_id, _)) = self.quarantined_network_ids.front().filter(|(_, free_at)| *free_at <= tick) {
+            self.free_network_ids.push(*network_id);
+            self.quarantined_network_ids.pop_front();
+        }
+        let network_id = match self.free_network_ids.pop() {
+            Some(network_id) => network_id,
+            None => {
+                let network_id = self.next_network_id;
+                self.next_network_id = self.next_network_id.checked_add(1).expect("Every network id is in use");
+                network_id
+            }
+        };
+        let id = match self.free_slots.pop() {
+            Some(index) => {
+                let slot = &mut self.slots[index as usize];
+                slot.entity = Some((entity, network_id));
+                EntityId { index, generation: slot.generation }
+            },
+            None => {
+                let index = u32::try_from(self.slots.len()).expect("Too many entity slots");
+                self.slots.push(EntitySlot { generation: 0, entity: Some((entity, network_id)) });
+                EntityId { index, generation: 0 }
+            }
+        };
+        self.network_ids.insert(network_id, id);
+        self.count += 1;
+        return id;
+    }
+
+    /// Remove an entity on a world tick, returning it. Returns None if the id is stale.
+    pub fn despawn(&mut self, id: EntityId, tick: u64) -> Option<T> {
+        let slot = self.slots.get_mut(id.index as usize).filter(|slot| slot.generation == id.generation)?;
+        let (entity, network_id) = slot.entity.take()?;
+        // A slot that has run out of generations is retired rather than risking an old id matching again.
+        if slot.generation < u32::MAX {
+            slot.generation += 1;
+            self.free_slots.push(id.index);
+        }
+        self.network_ids.remove(&network_id);
+        self.quarantined_network_ids.push_back((network_id, tick.saturating_add(NETWORK_ID_QUARANTINE_TICKS)));
+        self.count -= 1;
+        return Some(entity);
+    }
+
+    /// None if the id is stale, meaning its entity despawned.
+    pub fn get(&self, id: EntityId) -> Option<&T> {
+        return self.get_entry(id).map(|(entity, _)| entity);
+    }
+
+    pub fn get_mut(&mut self, id: EntityId) -> Option<&mut T> {
+        let slot = self.slots.get_mut(id.index as usize).filter(|slot| slot.generation == id.generation)?;
+        return slot.entity.as_mut().map(|(entity, _)| entity);
+    }
+
+    pub fn is_alive(&self, id: EntityId) -> bool {
+        return self.get_entry(id).is_some();
+    }
+
+    /// The id clients know the entity by in snapshots, which stays the same for as long as it's spawned.
+    pub fn get_network_id(&self, id: EntityId) -> Option<u32> {
+        return self.get_entry(id).map(|(_, network_id)| *network_id);
+    }
+
+    /// The entity a client means by a network id, such as the target of an interaction.
+    pub fn get_by_network_id(&self, network_id: u32) -> Option<EntityId> {
+        return self.network_ids.get(&network_id).copied();
+    }
+
+    /// Every entity with its network id, in slot order.
+    pub fn iter(&self) -> impl Iterator<Item = (EntityId, u32, &T)> {
+        return self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let (entity, network_id) = slot.entity.as_ref()?;
+            return Some((EntityId { index: index as u32, generation: slot.generation }, *network_id, entity));
+        });
+    }
+
+    pub fn len(&self) -> usize {
+        return self.count;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.count == 0;
+    }
+
+    fn get_entry(&self, id: EntityId) -> Option<&(T, u32)> {
+        let slot = self.slots.get(id.index as usize).filter(|slot| slot.generation == id.generation)?;
+        return slot.entity.as_ref();
+    }
+}
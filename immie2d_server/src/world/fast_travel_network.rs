@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::world::fast_travel::{FastTravelDenial, FastTravelEvent};
+use immie2d_shared::world::tile_map::{MapObject, TileMap, TileRect};
+use immie2d_shared::world::tile_position::WorldPosition;
+
+use crate::storage::player_profile::PlayerProfile;
+
+/// World ticks a player has to wait after fast travelling before they can again, whichever point they go to.
+pub const DEFAULT_FAST_TRAVEL_COOLDOWN_TICKS: u64 = 600;
+
+/* Why a map's fast travel points couldn't be added. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FastTravelMapError {
+    /// Players unlock points by name, so each name can only be used once across every map.
+    DuplicatePoint { point: GlobalString, map: GlobalString }
+}
+
+impl fmt::Display for FastTravelMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            FastTravelMapError::DuplicatePoint { point, map } => write!(f, "Fast travel point {} of map {} is already used", point, map)
+        };
+    }
+}
+
+/* Every fast travel point and restricted zone on the server's maps. Players unlock points by visiting them, which is
+kept in their profile, and can then travel to any unlocked point from anywhere that isn't restricted. */
+pub struct FastTravelNetwork {
+    /// In the order they were added, so which point a tile unlocks never depends on hashing.
+    points: Vec<(GlobalString, WorldPosition)>,
+    /// Areas of each map fast travel can't be started from.
+    restricted: HashMap<GlobalString, Vec<TileRect>>,
+    cooldown_ticks: u64,
+    /// Tick each player last fast travelled on.
+    last_travelled: HashMap<PlayerId, u64>
+}
+
+impl FastTravelNetwork {
+    pub fn new() -> FastTravelNetwork {
+        return FastTravelNetwork { points: Vec::new(), restricted: HashMap::new(), cooldown_ticks: DEFAULT_FAST_TRAVEL_COOLDOWN_TICKS, last_travelled: HashMap::new() };
+    }
+
+    pub fn with_cooldown(mut self, cooldown_ticks: u64) -> FastTravelNetwork {
+        self.cooldown_ticks = cooldown_ticks;
+        return self;
+    }
+
+    /// Add the fast travel points and restricted zones of a map. Nothing is added if any point has the same name as a
+    /// point already added, or another point on the same map.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::tile_map::{MapObject, TileMap};
+    /// use immie2d_shared::world::tile_position::TilePosition;
+    /// use immie2d_server::world::fast_travel_network::{FastTravelMapError, FastTravelNetwork};
+    ///
+    /// let (town, harbor, cave) = (GlobalString::new(&"ember town".to_string()), GlobalString::new(&"harbor".to_string()), GlobalString::new(&"cave".to_string()));
+    /// let mut town_map = TileMap::new(town, 16, 16);
+    /// town_map.add_object(MapObject::FastTravelPoint { point: town, tile: TilePosition::new(8, 8) });
+    /// let mut cave_map = TileMap::new(cave, 16, 16);
+    /// cave_map.add_object(MapObject::FastTravelPoint { point: harbor, tile: TilePosition::new(1, 1) });
+    /// cave_map.add_object(MapObject::FastTravelPoint { point: town, tile: TilePosition::new(0, 0) });
+    ///
+    /// let mut network = FastTravelNetwork::new();
+    /// assert_eq!(network.add_map(&town_map), Ok(()));
+    /// assert_eq!(network.add_map(&cave_map), Err(FastTravelMapError::DuplicatePoint { point: town, map: cave }));
+    /// assert_eq!(network.get_point(harbor), None);
+    /// ```
+    pub fn add_map(&mut self, map: &TileMap) -> Result<(), FastTravelMapError> {
+        let mut points: Vec<(GlobalString, WorldPosition)> = Vec::new();
+        let mut restricted = Vec::new();
+        for object in map.get_objects() {
+            match *object {
+                MapObject::FastTravelPoint { point, tile } => {
+                    if self.get_point(point).is_some() || points.iter().any(|(added, _)| *added == point) {
+                        return Err(FastTravelMapError::DuplicatePoint { point, map: map.get_name() });
+                    }
+                    points.push((point, WorldPosition::new(map.get_name(), tile)));
+                },
+                MapObject::RestrictedZone { area } => restricted.push(area),
+                _ => {}
+            }
+        }
+        self.points.extend(points);
+        if !restricted.is_empty() {
+            self.restricted.entry(map.get_name()).or_default().extend(restricted);
+        }
+        return Ok(());
+    }
+
+    /// Where travelling to a point puts the player.
+    pub fn get_point(&self, point: GlobalString) -> Option<WorldPosition> {
+        return self.points.iter().find(|(added, _)| *added == point).map(|(_, position)| *position);
+    }
+
+    pub fn is_restricted(&self, position: WorldPosition) -> bool {
+        return self.restricted.get(&position.map).is_some_and(|areas| areas.iter().any(|area| area.contains(position.tile)));
+    }
+
+    /// Unlock the point a player is standing on, if there is one they haven't unlocked yet. If several points share the
+    /// tile, the first added is unlocked first. Call whenever a player arrives on a tile. Returns the event to send them.
+    pub fn visit(&self, profile: &mut PlayerProfile, position: WorldPosition) -> Option<FastTravelEvent> {
+        let (point, _) = self.points.iter().find(|(point, at)| *at == position && !profile.unlocked_travel_points.contains(point))?;
+        profile.unlocked_travel_points.push(*point);
+        return Some(FastTravelEvent::Unlocked { point: *point });
+    }
+
+    /// Check a player's request to fast travel to a point on a world tick, starting the cooldown if it is allowed.
+    /// Returns the event to send them, and the player should only be moved if it is FastTravelEvent::Travelled.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::world::fast_travel::{FastTravelDenial, FastTravelEvent};
+    /// use immie2d_shared::world::tile_map::{MapObject, TileMap, TileRect};
+    /// use immie2d_shared::world::tile_position::{TilePosition, WorldPosition};
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    /// use immie2d_server::world::fast_travel_network::FastTravelNetwork;
+    ///
+    /// let (town, cave) = (GlobalString::new(&"ember town".to_string()), GlobalString::new(&"cave".to_string()));
+    /// let mut town_map = TileMap::new(town, 16, 16);
+    /// town_map.add_object(MapObject::FastTravelPoint { point: town, tile: TilePosition::new(8, 8) });
+    /// let mut cave_map = TileMap::new(cave, 16, 16);
+    /// cave_map.add_object(MapObject::FastTravelPoint { point: cave, tile: TilePosition::new(0, 0) });
+    /// cave_map.add_object(MapObject::RestrictedZone { area: TileRect { x: 4, y: 0, width: 12, height: 16 } });
+    /// let mut network = FastTravelNetwork::new().with_cooldown(100);
+    /// network.add_map(&town_map).unwrap();
+    /// network.add_map(&cave_map).unwrap();
+    ///
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// let entrance = WorldPosition::new(cave, TilePosition::new(0, 0));
+    /// let deep = WorldPosition::new(cave, TilePosition::new(10, 3));
+    /// let denied = |denial| FastTravelEvent::Denied { point: town, denial };
+    /// assert_eq!(network.request_travel(&profile, entrance, false, town, 0), denied(FastTravelDenial::Locked));
+    ///
+    /// assert_eq!(network.visit(&mut profile, WorldPosition::new(town, TilePosition::new(8, 8))), Some(FastTravelEvent::Unlocked { point: town }));
+    /// assert_eq!(network.request_travel(&profile, deep, false, town, 0), denied(FastTravelDenial::RestrictedZone));
+    /// assert_eq!(network.request_travel(&profile, entrance, true, town, 0), denied(FastTravelDenial::InBattle));
+    /// let arrival = WorldPosition::new(town, TilePosition::new(8, 8));
+    /// assert_eq!(network.request_travel(&profile, entrance, false, town, 0), FastTravelEvent::Travelled { point: town, from: entrance, to: arrival });
+    ///
+    /// // The cooldown is shared by every point
+    /// assert_eq!(network.request_travel(&profile, arrival, false, town, 40), denied(FastTravelDenial::CoolingDown { ticks_remaining: 60 }));
+    /// assert!(matches!(network.request_travel(&profile, arrival, false, town, 100), FastTravelEvent::Travelled { .. }));
+    /// ```
+    pub fn request_travel(&mut self, profile: &PlayerProfile, from: WorldPosition, is_in_battle: bool, point: GlobalString, tick: u64) -> FastTravelEvent {
+        let denied = |denial| FastTravelEvent::Denied { point, denial };
+        let Some(to) = self.get_point(point) else {
+            return denied(FastTravelDenial::UnknownPoint);
+        };
+        if !profile.unlocked_travel_points.contains(&point) {
+            return denied(FastTravelDenial::Locked);
+        }
+        if is_in_battle {
+            return denied(FastTravelDenial::InBattle);
+        }
+        if self.is_restricted(from) {
+            return denied(FastTravelDenial::RestrictedZone);
+        }
+        if let Some(last) = self.last_travelled.get(&profile.player) {
+            let ready_at = last.saturating_add(self.cooldown_ticks);
+            if tick < ready_at {
+                return denied(FastTravelDenial::CoolingDown { ticks_remaining: ready_at - tick });
+            }
+        }
+        self.last_travelled.insert(profile.player, tick);
+        return FastTravelEvent::Travelled { point, from, to };
+    }
+
+    /// Forget cooldowns that have run out, so players who left don't stay in memory.
+    pub fn prune(&mut self, tick: u64) {
+        let cooldown_ticks = self.cooldown_ticks;
+        self.last_travelled.retain(|_, last| last.saturating_add(cooldown_ticks) > tick);
+    }
+}
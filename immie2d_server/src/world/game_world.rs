@@ -0,0 +1,1014 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::mpsc::Sender;
+
+use immie2d_shared::engine_types::{game_protocol::MessageKind, game_rng::GameRng, global_string::GlobalString};
+use immie2d_shared::gameplay::encounter::encounter_conditions::{EncounterContext, TimeOfDay, Weather};
+use immie2d_shared::gameplay::encounter::encounter_roller::EncounterRoller;
+use immie2d_shared::gameplay::battle::battle_format::BattleFormat;
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::immie::immie_release::{ImmieLocation, ReleaseError};
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::modding::pack_advertisement::PackAdvertisement;
+use immie2d_shared::world::cutscene_script::CutsceneStep;
+use immie2d_shared::world::fast_travel::FastTravelEvent;
+use immie2d_shared::world::minimap::{Minimap, MINIMAP_CELL_SIZE};
+use immie2d_shared::world::move_result::MoveResult;
+use immie2d_shared::world::tile_map::{MapObject, TileMap};
+use immie2d_shared::world::tile_position::{Direction, TilePosition, WorldPosition, DIRECTIONS};
+
+use crate::admin::stat_audit::{StatAuditLog, StatChange};
+use crate::matchmaking::matchmaker::Matchmaker;
+use crate::network::game_connection::frame_message;
+use crate::network::send_queue::{MessagePriority, OutboundMessage};
+use crate::session::session_manager::SessionManager;
+use crate::storage::player_profile::PlayerProfile;
+
+use super::cutscene_runner::CutsceneRunner;
+use super::entity_store::{EntityId, EntityStore};
+use super::fast_travel_network::FastTravelNetwork;
+use super::region_instances::RegionInstanceId;
+use super::step_effects::StepEffects;
+use super::tile_reservations::{get_move_result_message, MoveIntent, TileReservations};
+
+/// Chance in percent that a step onto a tile of an encounter zone meets a wild Immie.
+pub const WILD_ENCOUNTER_PERCENT: u32 = 10;
+
+/* Why a player who logged in couldn't join the world. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum JoinError {
+    /// The account is already playing on another connection, or logging in on one.
+    AlreadyOnline,
+    /// The profile from the account's last connection is still being saved, so it could be loaded out of date.
+    StillSaving,
+    /// The connection already has a player.
+    AlreadyJoined,
+    UnknownConnection
+}
+
+struct Connection {
+    outbox: Sender<OutboundMessage>,
+    player: Option<PlayerId>
+}
+
+/* An Immie a player offered to another, waiting for them to offer one back. */
+#[derive(Clone, Copy)]
+struct TradeOffer {
+    partner: PlayerId,
+    location: ImmieLocation
+}
+
+/* A player who is logged in. The world changes their profile as they play, and it is saved when they leave. */
+pub struct OnlinePlayer {
+    pub connection: u64,
+    pub profile: PlayerProfile,
+    pub position: WorldPosition,
+    pub entity: EntityId
+}
+
+/* Everything shared by the players online. A server has one, behind a mutex that every connection locks to handle a
+request, so nothing slow such as storage or password hashing runs inside it. Messages are queued on the outbox of a
+connection, which its writer sends in the order they were queued. See serve_game_connection() */
+pub struct GameWorld {
+    connections: HashMap<u64, Connection>,
+    players: HashMap<PlayerId, OnlinePlayer>,
+    /// Players who left and whose profile hasn't been saved yet.
+    saving: HashSet<PlayerId>,
+    /// Players whose profile is being loaded to join. See reserve()
+    reserved: HashSet<PlayerId>,
+    sessions: SessionManager,
+    maps: HashMap<GlobalString, TileMap>,
+    minimaps: HashMap<GlobalString, Minimap>,
+    entities: EntityStore<PlayerId>,
+    /// Which entity stands on each tile of each region instance.
+    reservations: HashMap<RegionInstanceId, TileReservations>,
+    /// Steps requested since the last tick, in the order they arrived.
+    intents: Vec<(PlayerId, TilePosition, Direction)>,
+    step_effects: StepEffects,
+    fast_travel: FastTravelNetwork,
+    /// The steps of each cutscene map triggers can play.
+    cutscenes: HashMap<GlobalString, Vec<CutsceneStep>>,
+    running_cutscenes: Vec<CutsceneRunner>,
+    next_cutscene_id: u32,
+    /// Rolls the wild Immies of encounter zones, or None if there are no encounter tables.
+    encounters: Option<EncounterRoller>,
+    encounter_rng: GameRng,
+    matchmaker: Matchmaker,
+    /// Signs the stats of Immies the server gives players and checks them before a trade, if set.
+    stat_audit: Option<StatAuditLog>,
+    trade_offers: HashMap<PlayerId, TradeOffer>,
+    /// Where players are placed when they join.
+    start_position: WorldPosition,
+    /// Tick of the simulation clock the world was last run on.
+    tick: u64,
+    /// Sent to every connection as it connects, if the server has data packs enabled.
+    pack_advertisement: Option<PackAdvertisement>
+}
+
+impl GameWorld {
+    pub fn new(sessions: SessionManager, start_position: WorldPosition) -> GameWorld {
+        return GameWorld {
+            connections: HashMap::new(),
+            players: HashMap::new(),
+            saving: HashSet::new(),
+            reserved: HashSet::new(),
+            sessions,
+            maps: HashMap::new(),
+            minimaps: HashMap::new(),
+            entities: EntityStore::new(),
+            reservations: HashMap::new(),
+            intents: Vec::new(),
+            step_effects: StepEffects::new(),
+            fast_travel: FastTravelNetwork::new(),
+            cutscenes: HashMap::new(),
+            running_cutscenes: Vec::new(),
+            next_cutscene_id: 0,
+            encounters: None,
+            encounter_rng: GameRng::new(0),
+            matchmaker: Matchmaker::new(),
+            stat_audit: None,
+            trade_offers: HashMap::new(),
+            start_position,
+            tick: 0,
+            pack_advertisement: None
+        };
+    }
+
+    /// The maps players walk on. Nothing can be walked onto in a region without a map.
+    pub fn with_maps(mut self, maps: HashMap<GlobalString, TileMap>) -> GameWorld {
+        self.minimaps = maps.iter().map(|(name, map)| (*name, Minimap::generate(map, MINIMAP_CELL_SIZE))).collect();
+        self.maps = maps;
+        return self;
+    }
+
+    /// The cutscenes the triggers on the maps play, by name. See CutsceneScript::build()
+    pub fn with_cutscenes(mut self, cutscenes: HashMap<GlobalString, Vec<CutsceneStep>>) -> GameWorld {
+        self.cutscenes = cutscenes;
+        return self;
+    }
+
+    /// The encounter tables the encounter zones on the maps roll from, with the seed of every roll.
+    /// ```
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap};
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use std::collections::HashMap;
+    /// use std::sync::mpsc;
+    /// use immie2d_shared::engine_types::{game_protocol::{decode_message_line, MessageKind}, global_string::GlobalString};
+    /// use immie2d_shared::gameplay::{game_data::GameData, player_id::PlayerId};
+    /// use immie2d_shared::gameplay::encounter::{encounter_conditions::EncounterConditions, encounter_roller::{EncounterRoller, WildEncounter}};
+    /// use immie2d_shared::gameplay::encounter::encounter_table::{EncounterEntry, EncounterTable};
+    /// use immie2d_shared::gameplay::species::{base_stats::BaseStats, species_data::SpeciesData, species_form::SpeciesForm, species_map::SpeciesMap};
+    /// use immie2d_shared::world::tile_map::{MapObject, TileMap, TileRect};
+    /// use immie2d_shared::world::tile_position::{Direction, TilePosition, WorldPosition};
+    /// use immie2d_server::session::session_manager::SessionManager;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    /// use immie2d_server::world::game_world::GameWorld;
+    ///
+    /// let (coast, lavapup, grass) = (GlobalString::new(&"coast".to_string()), GlobalString::new(&"lavapup".to_string()), GlobalString::new(&"coast grass".to_string()));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut tidal = SpeciesForm::new(GlobalString::new(&"tidal".to_string()));
+    /// tidal.regions = vec![coast];
+    /// species_map.add_form(lavapup, tidal);
+    /// let table = EncounterTable { name: grass, entries: vec![
+    ///     EncounterEntry { species: lavapup, min_level: 3, max_level: 3, weight: 1, conditions: EncounterConditions::default() }
+    /// ] };
+    /// let mut map = TileMap::new(coast, 2, 1);
+    /// map.add_object(MapObject::EncounterZone { table: grass, area: TileRect { x: 0, y: 0, width: 2, height: 1 } });
+    ///
+    /// let sessions = SessionManager::new(GameData::new(1, species_map, AbilityMap::new(), ItemMap::new()).into_handle());
+    /// let mut world = GameWorld::new(sessions, WorldPosition::new(coast, TilePosition::new(0, 0)))
+    ///     .with_maps(HashMap::from([(coast, map)]))
+    ///     .with_encounters(EncounterRoller::new(vec![table]), 5);
+    /// let (outbox, messages) = mpsc::channel();
+    /// world.connect(1, outbox);
+    /// world.join(1, PlayerProfile::new(PlayerId(7), "misty".to_string()), 0).unwrap();
+    /// let mut encounters = Vec::new();
+    /// for tick in 0..200 {
+    ///     let from = world.get_player(PlayerId(7)).unwrap().position.tile;
+    ///     world.request_move(PlayerId(7), from, if from.x == 0 { Direction::Right } else { Direction::Left });
+    ///     world.run_tick(tick + 1);
+    ///     encounters.extend(messages.try_iter()
+    ///         .map(|message| decode_message_line(std::str::from_utf8(&message.payload).unwrap()).unwrap())
+    ///         .filter(|(kind, _)| *kind == MessageKind::WildEncounter)
+    ///         .map(|(_, payload)| WildEncounter::from_bytes(&payload).unwrap()));
+    /// }
+    /// // About one step in ten meets a lavapup, of its coast form
+    /// assert!(encounters.len() > 5 && encounters.len() < 40);
+    /// assert!(encounters.iter().all(|encounter| encounter.to_immie().get_sprite_id() == "lavapup-tidal"));
+    /// ```
+    pub fn with_encounters(mut self, encounters: EncounterRoller, seed: u64) -> GameWorld {
+        self.encounters = Some(encounters);
+        self.encounter_rng = GameRng::new(seed);
+        return self;
+    }
+
+    /// The fast travel points players can unlock by walking onto them, from the same maps as with_maps().
+    pub fn with_fast_travel(mut self, fast_travel: FastTravelNetwork) -> GameWorld {
+        self.fast_travel = fast_travel;
+        return self;
+    }
+
+    /// Tell each connection which data packs the server has enabled as it connects.
+    /// The matchmaker players queue for battles with. See Matchmaker::with_stat_audit()
+    pub fn with_matchmaker(mut self, matchmaker: Matchmaker) -> GameWorld {
+        self.matchmaker = matchmaker;
+        return self;
+    }
+
+    /// Sign the stats of every Immie the server gives a player, and check them before a trade. The log should use
+    /// the server's audit key. See ServerConfig::load_stat_audit_key()
+    pub fn with_stat_audit(mut self, stat_audit: StatAuditLog) -> GameWorld {
+        self.stat_audit = Some(stat_audit);
+        return self;
+    }
+
+    pub fn with_pack_advertisement(mut self, pack_advertisement: PackAdvertisement) -> GameWorld {
+        self.pack_advertisement = Some(pack_advertisement);
+        return self;
+    }
+
+    /// Add a connection that hasn't logged in yet, with where its messages are queued. It is sent the pack
+    /// advertisement first.
+    pub fn connect(&mut self, connection: u64, outbox: Sender<OutboundMessage>) {
+        self.connections.insert(connection, Connection { outbox, player: None });
+        if let Some(advertisement) = &self.pack_advertisement {
+            self.send(connection, MessageKind::PackAdvertisement, OutboundMessage::new(MessagePriority::Chat, advertisement.to_bytes()));
+        }
+    }
+
+    /// Remove a connection, dropping its outbox and taking its player out of their region instance. Returns the
+    /// profile of its player to save, if it had joined, and they can't join again until finish_saving() is called.
+    /// ```
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap, species::species_map::SpeciesMap};
+    /// use std::sync::mpsc;
+    /// use immie2d_shared::engine_types::{game_protocol::MessageKind, global_string::GlobalString};
+    /// use immie2d_shared::gameplay::{game_data::GameData, player_id::PlayerId};
+    /// use immie2d_shared::world::tile_position::{TilePosition, WorldPosition};
+    /// use immie2d_server::session::session_manager::SessionManager;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    /// use immie2d_server::world::game_world::{GameWorld, JoinError};
+    ///
+    /// let town = GlobalString::new(&"town".to_string());
+    /// let sessions = SessionManager::new(GameData::new(1, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle()).with_region_capacity(1);
+    /// let mut world = GameWorld::new(sessions, WorldPosition::new(town, TilePosition::new(3, 3)));
+    /// let (first, first_messages) = mpsc::channel();
+    /// let (second, _second_messages) = mpsc::channel();
+    /// let (third, _third_messages) = mpsc::channel();
+    /// world.connect(1, first);
+    /// world.connect(2, second);
+    /// world.connect(3, third);
+    /// world.join(1, PlayerProfile::new(PlayerId(7), "misty".to_string()), 0).unwrap();
+    /// assert_eq!(world.join(2, PlayerProfile::new(PlayerId(7), "misty".to_string()), 0), Err(JoinError::AlreadyOnline));
+    /// assert_eq!(world.get_player_of(1), Some(PlayerId(7)));
+    /// assert_eq!(world.get_player(PlayerId(7)).unwrap().position, WorldPosition::new(town, TilePosition::new(3, 3)));
+    ///
+    /// // Each instance of town holds one player
+    /// world.join(3, PlayerProfile::new(PlayerId(8), "brock".to_string()), 0).unwrap();
+    /// assert_eq!(world.get_sessions().get_regions().get_instance_count(town), 2);
+    ///
+    /// world.send_error(1, "Not yet");
+    /// assert!(first_messages.recv().unwrap().payload.starts_with(MessageKind::Error.get_keyword().as_bytes()));
+    /// assert_eq!(world.disconnect(1, 0).unwrap().player, PlayerId(7));
+    /// assert!(first_messages.recv().is_err());
+    /// assert_eq!(world.get_online_count(), 1);
+    /// assert_eq!(world.get_sessions().get_regions().get_instance_of(PlayerId(7)), None);
+    ///
+    /// let (again, _again_messages) = mpsc::channel();
+    /// world.connect(4, again);
+    /// assert_eq!(world.join(4, PlayerProfile::new(PlayerId(7), "misty".to_string()), 0), Err(JoinError::StillSaving));
+    /// world.finish_saving(PlayerId(7));
+    /// assert_eq!(world.join(4, PlayerProfile::new(PlayerId(7), "misty".to_string()), 0), Ok(()));
+    /// ```
+    pub fn disconnect(&mut self, connection: u64, unix_seconds: u64) -> Option<PlayerProfile> {
+        let player = self.connections.remove(&connection)?.player?;
+        let online = self.players.remove(&player)?;
+        let network_id = self.entities.get_network_id(online.entity);
+        if let (Some(instance), Some(network_id)) = (self.sessions.leave_region(player, unix_seconds), network_id) {
+            if let Some(reservations) = self.reservations.get_mut(&instance) {
+                reservations.remove_entity(network_id);
+            }
+        }
+        self.entities.despawn(online.entity, self.tick);
+        self.step_effects.remove_player(player);
+        self.matchmaker.dequeue(player);
+        self.trade_offers.retain(|offerer, offer| *offerer != player && offer.partner != player);
+        for runner in self.running_cutscenes.iter_mut() {
+            runner.remove_player(player);
+        }
+        let profile = online.profile;
+        self.saving.insert(player);
+        return Some(profile);
+    }
+
+    /// Let a player who left join again, once the profile from disconnect() has been saved or failed to save.
+    pub fn finish_saving(&mut self, player: PlayerId) {
+        self.saving.remove(&player);
+    }
+
+    /// Hold a player who logged in while their profile is loaded, so no other connection can join as them or save
+    /// their profile in the meantime. Either join() or cancel_reservation() must follow.
+    /// ```
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap, species::species_map::SpeciesMap};
+    /// use std::sync::mpsc;
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::{game_data::GameData, player_id::PlayerId};
+    /// use immie2d_shared::world::tile_position::{TilePosition, WorldPosition};
+    /// use immie2d_server::session::session_manager::SessionManager;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    /// use immie2d_server::world::game_world::{GameWorld, JoinError};
+    ///
+    /// let sessions = SessionManager::new(GameData::new(1, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle());
+    /// let mut world = GameWorld::new(sessions, WorldPosition::new(GlobalString::new(&"town".to_string()), TilePosition::new(0, 0)));
+    /// let (outbox, _messages) = mpsc::channel();
+    /// world.connect(1, outbox);
+    /// assert_eq!(world.reserve(PlayerId(7)), Ok(()));
+    /// assert_eq!(world.reserve(PlayerId(7)), Err(JoinError::AlreadyOnline));
+    /// world.cancel_reservation(PlayerId(7));
+    /// world.reserve(PlayerId(7)).unwrap();
+    /// world.join(1, PlayerProfile::new(PlayerId(7), "misty".to_string()), 0).unwrap();
+    /// assert_eq!(world.reserve(PlayerId(7)), Err(JoinError::AlreadyOnline));
+    /// ```
+    pub fn reserve(&mut self, player: PlayerId) -> Result<(), JoinError> {
+        if self.players.contains_key(&player) || self.reserved.contains(&player) {
+            return Err(JoinError::AlreadyOnline);
+        }
+        if self.saving.contains(&player) {
+            return Err(JoinError::StillSaving);
+        }
+        self.reserved.insert(player);
+        return Ok(());
+    }
+
+    pub fn cancel_reservation(&mut self, player: PlayerId) {
+        self.reserved.remove(&player);
+    }
+
+    /// Bring a player who logged in on a connection into the world at the start position, in an instance of its
+    /// region with room for them, on the nearest free tile. Ends the player's reservation, whether or not they could
+    /// join.
+    pub fn join(&mut self, connection: u64, profile: PlayerProfile, unix_seconds: u64) -> Result<(), JoinError> {
+        let player = profile.player;
+        self.reserved.remove(&player);
+        if self.players.contains_key(&player) {
+            return Err(JoinError::AlreadyOnline);
+        }
+        if self.saving.contains(&player) {
+            return Err(JoinError::StillSaving);
+        }
+        let state = self.connections.get_mut(&connection).ok_or(JoinError::UnknownConnection)?;
+        if state.player.is_some() {
+            return Err(JoinError::AlreadyJoined);
+        }
+        state.player = Some(player);
+        let start = self.start_position;
+        let instance = self.sessions.enter_region(player, start.map, &[], unix_seconds);
+        let entity = self.entities.spawn(player, self.tick);
+        let network_id = self.entities.get_network_id(entity).unwrap();
+        let tile = self.place(instance, network_id, start.tile);
+        self.players.insert(player, OnlinePlayer { connection, profile, position: WorldPosition::new(start.map, tile), entity });
+        return Ok(());
+    }
+
+    /// Move a player to a position, possibly on another map, putting them in an instance of its region with room for
+    /// them on the nearest free tile.
+    fn teleport(&mut self, player: PlayerId, to: WorldPosition, unix_seconds: u64) {
+        let Some(network_id) = self.players.get(&player).and_then(|online| self.entities.get_network_id(online.entity)) else {
+            return;
+        };
+        if let Some(reservations) = self.sessions.leave_region(player, unix_seconds).and_then(|instance| self.reservations.get_mut(&instance)) {
+            reservations.remove_entity(network_id);
+        }
+        let instance = self.sessions.enter_region(player, to.map, &[], unix_seconds);
+        let tile = self.place(instance, network_id, to.tile);
+        if let Some(online) = self.players.get_mut(&player) {
+            online.position = WorldPosition::new(to.map, tile);
+        }
+    }
+
+    /// Fast travel a player to an unlocked point, sending them the outcome and, if they travelled, their new position.
+    /// See FastTravelNetwork::request_travel()
+    /// ```
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap, species::species_map::SpeciesMap};
+    /// use std::collections::HashMap;
+    /// use std::sync::mpsc;
+    /// use immie2d_shared::engine_types::{game_protocol::{decode_message_line, MessageKind}, global_string::GlobalString};
+    /// use immie2d_shared::gameplay::{game_data::GameData, player_id::PlayerId};
+    /// use immie2d_shared::world::fast_travel::{FastTravelDenial, FastTravelEvent};
+    /// use immie2d_shared::world::tile_map::{MapObject, TileMap};
+    /// use immie2d_shared::world::tile_position::{Direction, TilePosition, WorldPosition};
+    /// use immie2d_server::session::session_manager::SessionManager;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    /// use immie2d_server::world::{fast_travel_network::FastTravelNetwork, game_world::GameWorld};
+    ///
+    /// let (town, harbor) = (GlobalString::new(&"town".to_string()), GlobalString::new(&"harbor".to_string()));
+    /// let mut town_map = TileMap::new(town, 4, 4);
+    /// town_map.add_object(MapObject::FastTravelPoint { point: town, tile: TilePosition::new(1, 0) });
+    /// let mut harbor_map = TileMap::new(harbor, 4, 4);
+    /// harbor_map.add_object(MapObject::FastTravelPoint { point: harbor, tile: TilePosition::new(2, 2) });
+    /// let mut fast_travel = FastTravelNetwork::new();
+    /// fast_travel.add_map(&town_map).unwrap();
+    /// fast_travel.add_map(&harbor_map).unwrap();
+    /// let sessions = SessionManager::new(GameData::new(1, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle());
+    /// let mut world = GameWorld::new(sessions, WorldPosition::new(harbor, TilePosition::new(0, 0)))
+    ///     .with_maps(HashMap::from([(town, town_map), (harbor, harbor_map)]))
+    ///     .with_fast_travel(fast_travel);
+    /// let (outbox, messages) = mpsc::channel();
+    /// world.connect(1, outbox);
+    /// let mut profile = PlayerProfile::new(PlayerId(7), "misty".to_string());
+    /// profile.unlocked_travel_points.push(town);
+    /// world.join(1, profile, 0).unwrap();
+    /// let event = || {
+    ///     let (kind, payload) = decode_message_line(std::str::from_utf8(&messages.recv().unwrap().payload).unwrap()).unwrap();
+    ///     assert_eq!(kind, MessageKind::FastTravel);
+    ///     return FastTravelEvent::from_bytes(&payload).unwrap();
+    /// };
+    ///
+    /// world.fast_travel(PlayerId(7), "harbor", 0);
+    /// assert_eq!(event(), FastTravelEvent::Denied { point: harbor, denial: FastTravelDenial::Locked });
+    /// world.fast_travel(PlayerId(7), "town", 0);
+    /// assert!(matches!(event(), FastTravelEvent::Travelled { .. }));
+    /// assert_eq!(world.get_player(PlayerId(7)).unwrap().position, WorldPosition::new(town, TilePosition::new(1, 0)));
+    /// assert_eq!(world.get_sessions().get_regions().get_instance_of(PlayerId(7)).unwrap().map, town);
+    /// ```
+    pub fn fast_travel(&mut self, player: PlayerId, point: &str, unix_seconds: u64) {
+        let Some(online) = self.players.get(&player) else {
+            return;
+        };
+        let point = GlobalString::new_if_exists(&point.to_string());
+        let is_in_battle = self.sessions.is_in_session(player);
+        let event = self.fast_travel.request_travel(&online.profile, online.position, is_in_battle, point, self.tick);
+        let connection = online.connection;
+        if let FastTravelEvent::Travelled { to, .. } = event {
+            self.teleport(player, to, unix_seconds);
+        }
+        self.send(connection, MessageKind::FastTravel, OutboundMessage::new(MessagePriority::Chat, event.to_bytes()));
+        if matches!(event, FastTravelEvent::Travelled { .. }) {
+            self.send_position(player);
+        }
+    }
+
+    /// Put an entity on the free tile of a region instance nearest to a tile. If every tile it could walk to is taken
+    /// it is left unplaced, and its steps are ignored.
+    fn place(&mut self, instance: RegionInstanceId, network_id: u32, tile: TilePosition) -> TilePosition {
+        let map = self.maps.get(&instance.map);
+        let reservations = self.reservations.entry(instance).or_insert_with(TileReservations::new);
+        let mut queue = VecDeque::from([tile]);
+        let mut seen = HashSet::from([tile]);
+        while let Some(candidate) = queue.pop_front() {
+            if reservations.add_entity(network_id, candidate) {
+                return candidate;
+            }
+            for direction in DIRECTIONS {
+                let next = candidate.offset(direction);
+                if map.is_some_and(|map| map.is_in_bounds(next) && !map.is_blocked(next)) && seen.insert(next) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        return tile;
+    }
+
+    /// Send a player where the server has them, such as after they join.
+    pub fn send_position(&self, player: PlayerId) {
+        let Some(online) = self.players.get(&player) else {
+            return;
+        };
+        let Some(network_id) = self.entities.get_network_id(online.entity) else {
+            return;
+        };
+        let result = MoveResult { network_id, tile: online.position.tile, denial: None };
+        self.send(online.connection, MessageKind::MoveResult, get_move_result_message(&result));
+    }
+
+    /// Queue a step of a player for the next tick, from the tile their client predicts they are on.
+    /// ```
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap, species::species_map::SpeciesMap};
+    /// use std::collections::HashMap;
+    /// use std::sync::mpsc;
+    /// use immie2d_shared::engine_types::{game_protocol::{decode_message_line, MessageKind}, global_string::GlobalString};
+    /// use immie2d_shared::gameplay::{game_data::GameData, player_id::PlayerId};
+    /// use immie2d_shared::world::{move_result::{MoveDenial, MoveResult}, tile_map::TileMap};
+    /// use immie2d_shared::world::tile_position::{Direction, TilePosition, WorldPosition};
+    /// use immie2d_server::session::session_manager::SessionManager;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    /// use immie2d_server::world::game_world::GameWorld;
+    ///
+    /// let town = GlobalString::new(&"town".to_string());
+    /// let sessions = SessionManager::new(GameData::new(1, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle());
+    /// let maps = HashMap::from([(town, TileMap::new(town, 4, 4))]);
+    /// let mut world = GameWorld::new(sessions, WorldPosition::new(town, TilePosition::new(0, 0))).with_maps(maps);
+    /// let (outbox, messages) = mpsc::channel();
+    /// let (other_outbox, _other_messages) = mpsc::channel();
+    /// world.connect(1, outbox);
+    /// world.connect(2, other_outbox);
+    /// world.join(1, PlayerProfile::new(PlayerId(7), "misty".to_string()), 0).unwrap();
+    /// // The start tile is taken, so the second player is put next to it
+    /// world.join(2, PlayerProfile::new(PlayerId(8), "brock".to_string()), 0).unwrap();
+    /// let brock = world.get_player(PlayerId(8)).unwrap().position.tile;
+    /// assert_eq!(TilePosition::new(0, 0).direction_to(brock).is_some(), true);
+    ///
+    /// let result = |message: Vec<u8>| MoveResult::from_bytes(&decode_message_line(std::str::from_utf8(&message).unwrap()).unwrap().1).unwrap();
+    /// world.request_move(PlayerId(7), TilePosition::new(0, 0), Direction::Up);
+    /// world.run_tick(1);
+    /// assert_eq!(result(messages.recv().unwrap().payload).denial, Some(MoveDenial::Blocked));
+    /// world.request_move(PlayerId(7), TilePosition::new(0, 0), TilePosition::new(0, 0).direction_to(brock).unwrap());
+    /// world.run_tick(2);
+    /// assert_eq!(result(messages.recv().unwrap().payload).denial, Some(MoveDenial::Occupied));
+    /// let free = if brock == TilePosition::new(1, 0) { Direction::Down } else { Direction::Right };
+    /// world.request_move(PlayerId(7), TilePosition::new(0, 0), free);
+    /// world.run_tick(3);
+    /// assert!(result(messages.recv().unwrap().payload).is_granted());
+    /// assert_eq!(world.get_player(PlayerId(7)).unwrap().position.tile, TilePosition::new(0, 0).offset(free));
+    /// ```
+    pub fn request_move(&mut self, player: PlayerId, from: TilePosition, direction: Direction) {
+        if self.players.contains_key(&player) && !self.is_in_cutscene(player) {
+            self.intents.push((player, from, direction));
+        }
+    }
+
+    /// Resolve the steps of every region instance together, moving the players whose steps were granted and sending
+    /// each their result. A step onto a new tile counts towards the walking bond of the player's party, which is saved
+    /// with their profile, unlocks the fast travel point there and plays the cutscene of any trigger it walks into.
+    /// Otherwise a step in an encounter zone can meet a wild Immie, of the form for the region. The world has no day
+    /// cycle or weather yet, so encounters roll as a clear day. See StepEffects::on_step()
+    fn resolve_moves(&mut self) {
+        let mut intents: HashMap<RegionInstanceId, Vec<MoveIntent>> = HashMap::new();
+        for (player, from, direction) in std::mem::take(&mut self.intents) {
+            let Some(network_id) = self.players.get(&player).and_then(|online| self.entities.get_network_id(online.entity)) else {
+                continue;
+            };
+            if let Some(instance) = self.sessions.get_regions().get_instance_of(player) {
+                intents.entry(instance).or_default().push(MoveIntent { network_id, from, direction });
+            }
+        }
+        let mut triggered = Vec::new();
+        for (instance, intents) in intents {
+            let missing_map;
+            let map = match self.maps.get(&instance.map) {
+                Some(map) => map,
+                None => {
+                    missing_map = TileMap::new(instance.map, 0, 0);
+                    &missing_map
+                }
+            };
+            let results = self.reservations.entry(instance).or_insert_with(TileReservations::new).resolve_tick(&intents, map);
+            for result in results {
+                let Some(&player) = self.entities.get_by_network_id(result.network_id).and_then(|entity| self.entities.get(entity)) else {
+                    continue;
+                };
+                let Some(online) = self.players.get_mut(&player) else {
+                    continue;
+                };
+                let mut unlocked = None;
+                let mut encounter = None;
+                if result.tile != online.position.tile {
+                    let from = online.position.tile;
+                    let trigger = map.get_objects().iter().find_map(|object| match object {
+                        MapObject::CutsceneTrigger { cutscene, area } if area.contains(result.tile) && !area.contains(from) => Some(*cutscene),
+                        _ => None
+                    });
+                    triggered.extend(trigger.map(|cutscene| (player, cutscene)));
+                    let zone = map.get_objects().iter().find_map(|object| match object {
+                        MapObject::EncounterZone { table, area } if area.contains(result.tile) => Some(*table),
+                        _ => None
+                    });
+                    if let (None, Some(table), Some(encounters)) = (trigger, zone, &self.encounters) {
+                        if self.encounter_rng.next_below(100) < WILD_ENCOUNTER_PERCENT {
+                            let mut context = EncounterContext::new(TimeOfDay::Day, Weather::Clear);
+                            context.region = Some(instance.map);
+                            encounter = encounters.roll_with_form(table, &context, self.sessions.get_data().get_species_map(), &mut self.encounter_rng);
+                        }
+                    }
+                    online.position.tile = result.tile;
+                    if let Some(minimap) = self.minimaps.get(&instance.map) {
+                        self.step_effects.on_step(&mut online.profile, minimap, result.tile);
+                    }
+                    unlocked = self.fast_travel.visit(&mut online.profile, online.position);
+                }
+                let connection = online.connection;
+                self.send(connection, MessageKind::MoveResult, get_move_result_message(&result));
+                if let Some(unlocked) = unlocked {
+                    self.send(connection, MessageKind::FastTravel, OutboundMessage::new(MessagePriority::Chat, unlocked.to_bytes()));
+                }
+                if let Some(encounter) = encounter {
+                    self.send(connection, MessageKind::WildEncounter, OutboundMessage::new(MessagePriority::Chat, encounter.to_bytes()));
+                }
+            }
+        }
+        for (player, cutscene) in triggered {
+            self.play_cutscene(cutscene, vec![player]);
+        }
+    }
+
+    pub fn is_in_cutscene(&self, player: PlayerId) -> bool {
+        return self.running_cutscenes.iter().any(|runner| runner.get_players().contains(&player));
+    }
+
+    /// Start a cutscene for the online players that aren't already in one, which then runs with the world's ticks.
+    /// Their steps are ignored until it ends. Returns its id, or None if the cutscene doesn't exist or none of the
+    /// players are free to watch it.
+    /// ```
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap, species::species_map::SpeciesMap};
+    /// use std::collections::HashMap;
+    /// use std::sync::mpsc;
+    /// use immie2d_shared::engine_types::{game_protocol::{decode_message_line, MessageKind}, global_string::GlobalString};
+    /// use immie2d_shared::gameplay::{game_data::GameData, player_id::PlayerId};
+    /// use immie2d_shared::world::cutscene::{CutsceneCue, CutsceneCueMessage};
+    /// use immie2d_shared::world::cutscene_script::CutsceneScript;
+    /// use immie2d_shared::world::tile_map::{MapObject, TileMap, TileRect};
+    /// use immie2d_shared::world::tile_position::{Direction, TilePosition, WorldPosition};
+    /// use immie2d_server::session::session_manager::SessionManager;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    /// use immie2d_server::world::game_world::GameWorld;
+    ///
+    /// let (town, rival_intro) = (GlobalString::new(&"town".to_string()), GlobalString::new(&"rival_intro".to_string()));
+    /// let mut map = TileMap::new(town, 4, 4);
+    /// map.add_object(MapObject::CutsceneTrigger { cutscene: rival_intro, area: TileRect { x: 1, y: 0, width: 1, height: 4 } });
+    /// let script = CutsceneScript::compile("rival_intro", r#"fn play() { this.say("Rival", "There you are!"); this.wait_for_dialogue(); }"#).unwrap();
+    /// let sessions = SessionManager::new(GameData::new(1, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle());
+    /// let mut world = GameWorld::new(sessions, WorldPosition::new(town, TilePosition::new(0, 0)))
+    ///     .with_maps(HashMap::from([(town, map)]))
+    ///     .with_cutscenes(HashMap::from([(rival_intro, script.build().unwrap())]));
+    /// let (outbox, messages) = mpsc::channel();
+    /// world.connect(1, outbox);
+    /// world.join(1, PlayerProfile::new(PlayerId(7), "misty".to_string()), 0).unwrap();
+    /// let cues = || messages.try_iter()
+    ///     .map(|message| decode_message_line(std::str::from_utf8(&message.payload).unwrap()).unwrap())
+    ///     .filter(|(kind, _)| *kind == MessageKind::Cutscene)
+    ///     .map(|(_, payload)| CutsceneCueMessage::from_bytes(&payload).unwrap().cue)
+    ///     .collect::<Vec<_>>();
+    ///
+    /// // Walking into the trigger plays the cutscene, which holds the player until they close the dialogue
+    /// world.request_move(PlayerId(7), TilePosition::new(0, 0), Direction::Right);
+    /// world.run_tick(1);
+    /// assert!(matches!(cues()[..], [CutsceneCue::Started, CutsceneCue::ShowDialogue { .. }]));
+    /// assert!(world.is_in_cutscene(PlayerId(7)));
+    /// world.request_move(PlayerId(7), TilePosition::new(1, 0), Direction::Right);
+    /// world.run_tick(2);
+    /// assert_eq!(world.get_player(PlayerId(7)).unwrap().position.tile, TilePosition::new(1, 0));
+    /// world.close_dialogue(PlayerId(7));
+    /// world.run_tick(3);
+    /// assert_eq!(cues(), vec![CutsceneCue::Ended]);
+    /// assert!(!world.is_in_cutscene(PlayerId(7)));
+    ///
+    /// // Walking along inside the trigger doesn't play it again
+    /// world.request_move(PlayerId(7), TilePosition::new(1, 0), Direction::Down);
+    /// world.run_tick(4);
+    /// assert!(cues().is_empty());
+    /// assert_eq!(world.play_cutscene(GlobalString::new(&"missing".to_string()), vec![PlayerId(7)]), None);
+    /// ```
+    pub fn play_cutscene(&mut self, cutscene: GlobalString, players: Vec<PlayerId>) -> Option<u32> {
+        let steps = self.cutscenes.get(&cutscene)?.clone();
+        let players: Vec<PlayerId> = players.into_iter().filter(|player| self.players.contains_key(player) && !self.is_in_cutscene(*player)).collect();
+        if players.is_empty() {
+            return None;
+        }
+        let id = self.next_cutscene_id;
+        self.next_cutscene_id = self.next_cutscene_id.wrapping_add(1);
+        self.running_cutscenes.push(CutsceneRunner::new(id, steps, players));
+        return Some(id);
+    }
+
+    /// A player closed the dialogue of the cutscene they are in.
+    pub fn close_dialogue(&mut self, player: PlayerId) {
+        for runner in self.running_cutscenes.iter_mut() {
+            runner.close_dialogue(player);
+        }
+    }
+
+    /// Run every cutscene for a tick, sending its cues to its players, and drop the ones that ended.
+    fn run_cutscenes(&mut self) {
+        let mut outgoing = Vec::new();
+        for runner in self.running_cutscenes.iter_mut() {
+            let messages = runner.tick();
+            let connections = runner.get_players().iter().filter_map(|player| self.players.get(player)).map(|online| online.connection);
+            outgoing.extend(connections.flat_map(|connection| messages.iter().map(move |message| (connection, message.clone()))));
+        }
+        for (connection, message) in outgoing {
+            self.send(connection, MessageKind::Cutscene, message);
+        }
+        self.running_cutscenes.retain(|runner| !runner.is_finished());
+    }
+
+    /// Sign the stats of an Immie the server just gave a player, such as a gift, so it can be traded and brought into
+    /// matchmaking. Does nothing if the world doesn't audit stats.
+    pub fn record_obtained(&mut self, player: PlayerId, location: ImmieLocation, unix_seconds: u64) {
+        let (Some(stat_audit), Some(online)) = (self.stat_audit.as_mut(), self.players.get_mut(&player)) else {
+            return;
+        };
+        let Some(immie) = online.profile.get_immie(location).copied() else {
+            return;
+        };
+        match stat_audit.record(player, &immie, StatChange::Obtained, self.sessions.get_data().get_species_map(), unix_seconds) {
+            Ok(entry) => online.profile.record_stat_audit(entry),
+            Err(err) => eprintln!("Failed to sign the stats of an Immie given to player {}: {}", player, err)
+        }
+    }
+
+    /// Queue a player for a single battle with their party, which must follow the queue's ruleset and, if the
+    /// matchmaker audits stats, have the stats the server signed. Sends the player that they're queued, or why not.
+    /// ```
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap};
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use std::sync::mpsc;
+    /// use immie2d_shared::engine_types::{game_protocol::{decode_message_line, MessageKind}, global_string::GlobalString};
+    /// use immie2d_shared::gameplay::{game_data::GameData, player_id::PlayerId};
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::immie::{immie::Immie, immie_release::ImmieLocation};
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// use immie2d_shared::world::tile_position::{TilePosition, WorldPosition};
+    /// use immie2d_server::admin::stat_audit::StatAuditLog;
+    /// use immie2d_server::matchmaking::matchmaker::Matchmaker;
+    /// use immie2d_server::session::session_manager::SessionManager;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    /// use immie2d_server::world::game_world::GameWorld;
+    ///
+    /// let lavapup = GlobalString::new(&"lavapup".to_string());
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let sessions = SessionManager::new(GameData::new(1, species_map, AbilityMap::new(), ItemMap::new()).into_handle());
+    /// let mut world = GameWorld::new(sessions, WorldPosition::new(GlobalString::new(&"town".to_string()), TilePosition::new(0, 0)))
+    ///     .with_stat_audit(StatAuditLog::new(b"audit key".to_vec()))
+    ///     .with_matchmaker(Matchmaker::new().with_stat_audit(StatAuditLog::new(b"audit key".to_vec())));
+    /// let (outbox, messages) = mpsc::channel();
+    /// world.connect(1, outbox);
+    /// let mut profile = PlayerProfile::new(PlayerId(7), "misty".to_string());
+    /// profile.party.push(Immie::new(lavapup, 5, AbilityNames::default()));
+    /// world.join(1, profile, 0).unwrap();
+    /// let mut replies = move || messages.try_iter().map(|message| decode_message_line(std::str::from_utf8(&message.payload).unwrap()).unwrap().0).collect::<Vec<MessageKind>>();
+    ///
+    /// // An Immie the server never gave out has no signed stats
+    /// assert!(!world.queue_battle(PlayerId(7)));
+    /// assert!(replies().contains(&MessageKind::Error));
+    ///
+    /// world.record_obtained(PlayerId(7), ImmieLocation::Party(0), 10);
+    /// assert!(world.queue_battle(PlayerId(7)));
+    /// assert!(replies().contains(&MessageKind::BattleQueued));
+    ///
+    /// // Editing the saved Immie, such as in the database, breaks its signature
+    /// world.get_player_mut(PlayerId(7)).unwrap().profile.party[0].level = 100;
+    /// let profile = world.disconnect(1, 0).unwrap();
+    /// world.finish_saving(PlayerId(7));
+    /// let (outbox, _messages) = mpsc::channel();
+    /// world.connect(2, outbox);
+    /// world.join(2, profile, 0).unwrap();
+    /// assert!(!world.queue_battle(PlayerId(7)));
+    /// ```
+    pub fn queue_battle(&mut self, player: PlayerId) -> bool {
+        let Some(online) = self.players.get(&player) else {
+            return false;
+        };
+        let connection = online.connection;
+        let audits = online.profile.get_stat_audits(&online.profile.party);
+        let reply = match self.matchmaker.enqueue_team(player, BattleFormat::Single, &online.profile.party, &audits, self.sessions.get_data()) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("Already queued for a battle".to_string()),
+            Err(rejection) => Err(format!("The party can't battle: {}", rejection))
+        };
+        return match reply {
+            Ok(()) => {
+                self.send(connection, MessageKind::BattleQueued, OutboundMessage::new(MessagePriority::Chat, Vec::new()));
+                true
+            },
+            Err(err) => {
+                self.send_error(connection, &err);
+                false
+            }
+        };
+    }
+
+    /// Check one of a player's Immies can be traded away, returning it if it can.
+    fn check_tradable(&self, player: PlayerId, location: ImmieLocation) -> Result<Immie, String> {
+        if self.sessions.is_in_session(player) {
+            return Err("Immies can't be traded during a battle".to_string());
+        }
+        let profile = &self.players.get(&player).ok_or("The player left".to_string())?.profile;
+        profile.check_removable(location).map_err(|err| err.to_string())?;
+        let immie = *profile.get_immie(location).ok_or(ReleaseError::InvalidLocation.to_string())?;
+        if let Some(stat_audit) = &self.stat_audit {
+            let tampered = stat_audit.validate_team(&[immie], &profile.get_stat_audits(&[immie]), self.sessions.get_data().get_species_map());
+            if let Some((_, tamper)) = tampered.first() {
+                return Err(format!("That Immie can't be traded: {}", tamper));
+            }
+        }
+        return Ok(immie);
+    }
+
+    /// Offer one of a player's Immies to another online player by name, replacing the player's last offer. The trade
+    /// goes through once the partner offers one back, after checking both Immies can be traded and, if the world
+    /// audits stats, have the stats the server signed. Each player gets the other's Immie in their box, with its
+    /// signed stats, and is sent its species. Offers are dropped when either player leaves.
+    /// ```
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap};
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use std::sync::mpsc;
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::{game_data::GameData, player_id::PlayerId};
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::immie::{immie::Immie, immie_release::ImmieLocation};
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// use immie2d_shared::world::tile_position::{TilePosition, WorldPosition};
+    /// use immie2d_server::admin::stat_audit::StatAuditLog;
+    /// use immie2d_server::session::session_manager::SessionManager;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    /// use immie2d_server::world::game_world::GameWorld;
+    ///
+    /// let (lavapup, tidepup) = (GlobalString::new(&"lavapup".to_string()), GlobalString::new(&"tidepup".to_string()));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// species_map.add_species(SpeciesData::new(tidepup, Elements::new(vec![ElementKind::Water]), BaseStats::new(60, 40, 60, 50)));
+    /// let sessions = SessionManager::new(GameData::new(1, species_map, AbilityMap::new(), ItemMap::new()).into_handle());
+    /// let mut world = GameWorld::new(sessions, WorldPosition::new(GlobalString::new(&"town".to_string()), TilePosition::new(0, 0)))
+    ///     .with_stat_audit(StatAuditLog::new(b"audit key".to_vec()));
+    /// let mut outboxes = Vec::new();
+    /// for (connection, player, name, species) in [(1, PlayerId(7), "misty", tidepup), (2, PlayerId(8), "brock", lavapup)] {
+    ///     let (outbox, messages) = mpsc::channel();
+    ///     outboxes.push(messages);
+    ///     world.connect(connection, outbox);
+    ///     let mut profile = PlayerProfile::new(player, name.to_string());
+    ///     profile.party.push(Immie::new(species, 5, AbilityNames::default()));
+    ///     profile.boxed.push(Immie::new(species, 9, AbilityNames::default()));
+    ///     world.join(connection, profile, 0).unwrap();
+    ///     world.record_obtained(player, ImmieLocation::Box(0), 0);
+    /// }
+    ///
+    /// // A tampered Immie is refused before it is ever offered
+    /// world.get_player_mut(PlayerId(8)).unwrap().profile.boxed[0].level = 100;
+    /// world.offer_trade(PlayerId(8), "misty", ImmieLocation::Box(0));
+    /// world.get_player_mut(PlayerId(8)).unwrap().profile.boxed[0].level = 9;
+    /// world.offer_trade(PlayerId(7), "brock", ImmieLocation::Box(0));
+    /// assert_eq!(world.get_player(PlayerId(7)).unwrap().profile.boxed[0].species, tidepup);
+    ///
+    /// // Nor can an Immie the server never signed be traded
+    /// world.offer_trade(PlayerId(8), "misty", ImmieLocation::Party(0));
+    /// assert_eq!(world.get_player(PlayerId(7)).unwrap().profile.boxed[0].species, tidepup);
+    ///
+    /// world.offer_trade(PlayerId(8), "misty", ImmieLocation::Box(0));
+    /// let misty = &world.get_player(PlayerId(7)).unwrap().profile;
+    /// assert_eq!((misty.boxed[0].species, misty.boxed[0].level), (lavapup, 9));
+    /// assert_eq!(misty.get_stat_audits(&misty.boxed)[0].unwrap().inputs.species, lavapup);
+    /// assert_eq!(world.get_player(PlayerId(8)).unwrap().profile.boxed[0].species, tidepup);
+    /// ```
+    pub fn offer_trade(&mut self, player: PlayerId, partner: &str, location: ImmieLocation) {
+        let Some(connection) = self.players.get(&player).map(|online| online.connection) else {
+            return;
+        };
+        let Some(partner) = self.players.values().map(|online| online.profile.player).find(|other| *other != player && self.players[other].profile.name == partner) else {
+            return self.send_error(connection, &format!("{} isn't online", partner));
+        };
+        if let Err(err) = self.check_tradable(player, location) {
+            self.trade_offers.remove(&player);
+            return self.send_error(connection, &err);
+        }
+        self.trade_offers.insert(player, TradeOffer { partner, location });
+        if let Some(offer) = self.trade_offers.get(&partner).copied().filter(|offer| offer.partner == player) {
+            self.complete_trade((player, location), (partner, offer.location));
+        }
+    }
+
+    /// Swap the Immies of two players' matching offers, checking both again first.
+    fn complete_trade(&mut self, first: (PlayerId, ImmieLocation), second: (PlayerId, ImmieLocation)) {
+        self.trade_offers.remove(&first.0);
+        self.trade_offers.remove(&second.0);
+        let trades = match (self.check_tradable(first.0, first.1), self.check_tradable(second.0, second.1)) {
+            (Ok(first_immie), Ok(second_immie)) => [(first, second.0, first_immie), (second, first.0, second_immie)],
+            (Err(reason), _) | (_, Err(reason)) => {
+                for player in [first.0, second.0] {
+                    if let Some(online) = self.players.get(&player) {
+                        self.send_error(online.connection, &format!("The trade was cancelled: {}", reason));
+                    }
+                }
+                return;
+            }
+        };
+        // Both are taken before either is given, with the stats signed for them
+        let mut given = Vec::new();
+        for ((giver, location), receiver, immie) in trades {
+            if let Some(online) = self.players.get_mut(&giver) {
+                let entry = online.profile.get_stat_audits(&[immie])[0];
+                if online.profile.take_immie(location).is_ok() {
+                    given.push((receiver, immie, entry));
+                }
+            }
+        }
+        for (receiver, immie, entry) in given {
+            let Some(online) = self.players.get_mut(&receiver) else {
+                continue;
+            };
+            online.profile.boxed.push(immie);
+            if let Some(entry) = entry {
+                online.profile.record_stat_audit(entry);
+            }
+            let connection = online.connection;
+            self.send(connection, MessageKind::Traded, OutboundMessage::new(MessagePriority::Chat, immie.species.to_string().into_bytes()));
+        }
+    }
+
+    /// The player that joined on a connection, if any.
+    pub fn get_player_of(&self, connection: u64) -> Option<PlayerId> {
+        return self.connections.get(&connection)?.player;
+    }
+
+    pub fn get_player(&self, player: PlayerId) -> Option<&OnlinePlayer> {
+        return self.players.get(&player);
+    }
+
+    pub fn get_player_mut(&mut self, player: PlayerId) -> Option<&mut OnlinePlayer> {
+        return self.players.get_mut(&player);
+    }
+
+    /// Every player who is logged in, in no particular order.
+    pub fn get_online_players(&self) -> impl Iterator<Item = &OnlinePlayer> {
+        return self.players.values();
+    }
+
+    pub fn get_online_count(&self) -> usize {
+        return self.players.len();
+    }
+
+    /// Run the world for a tick of the simulation clock, resolving the steps requested since the last one.
+    /// See SimulationClock::poll()
+    /// ```
+    /// # use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, item::item_map::ItemMap, species::species_map::SpeciesMap};
+    /// use std::collections::HashMap;
+    /// use std::sync::mpsc;
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::{game_data::GameData, player_id::PlayerId};
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::immie::{bond::{BASE_BOND, BOND_FROM_WALKING, STEPS_PER_WALKING_BOND}, immie::Immie};
+    /// use immie2d_shared::world::{tile_map::TileMap, tile_position::{Direction, TilePosition, WorldPosition}};
+    /// use immie2d_server::session::session_manager::SessionManager;
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    /// use immie2d_server::world::game_world::GameWorld;
+    ///
+    /// let town = GlobalString::new(&"town".to_string());
+    /// let sessions = SessionManager::new(GameData::new(1, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle());
+    /// let maps = HashMap::from([(town, TileMap::new(town, 4, 4))]);
+    /// let mut world = GameWorld::new(sessions, WorldPosition::new(town, TilePosition::new(0, 0))).with_maps(maps);
+    /// let (outbox, _messages) = mpsc::channel();
+    /// world.connect(1, outbox);
+    /// let mut profile = PlayerProfile::new(PlayerId(7), "misty".to_string());
+    /// profile.party.push(Immie::new(GlobalString::new(&"lavapup".to_string()), 5, AbilityNames::new(Vec::new())));
+    /// world.join(1, profile, 0).unwrap();
+    /// for tick in 0..STEPS_PER_WALKING_BOND as u64 {
+    ///     let from = world.get_player(PlayerId(7)).unwrap().position.tile;
+    ///     world.request_move(PlayerId(7), from, if from.x == 0 { Direction::Right } else { Direction::Left });
+    ///     world.run_tick(tick + 1);
+    /// }
+    /// assert_eq!(world.disconnect(1, 0).unwrap().party[0].bond, BASE_BOND + BOND_FROM_WALKING);
+    /// ```
+    pub fn run_tick(&mut self, tick: u64) {
+        self.tick = tick;
+        self.resolve_moves();
+        self.run_cutscenes();
+        self.fast_travel.prune(tick);
+    }
+
+    /// Close region instances left empty for long enough, along with who stood where in them.
+    /// See SessionManager::close_empty_instances()
+    pub fn close_empty_instances(&mut self, unix_seconds: u64) -> Vec<RegionInstanceId> {
+        let closed = self.sessions.close_empty_instances(unix_seconds);
+        for instance in closed.iter() {
+            self.reservations.remove(instance);
+        }
+        return closed;
+    }
+
+    pub fn get_tick(&self) -> u64 {
+        return self.tick;
+    }
+
+    pub fn get_sessions(&self) -> &SessionManager {
+        return &self.sessions;
+    }
+
+    pub fn get_sessions_mut(&mut self) -> &mut SessionManager {
+        return &mut self.sessions;
+    }
+
+    /// Queue a message for a connection. Messages to a connection that has closed are dropped.
+    pub fn send(&self, connection: u64, kind: MessageKind, message: OutboundMessage) {
+        if let Some(state) = self.connections.get(&connection) {
+            let _ = state.outbox.send(frame_message(kind, message));
+        }
+    }
+
+    /// Queue a message for every connection, whether or not it has logged in.
+    pub fn broadcast(&self, kind: MessageKind, message: OutboundMessage) {
+        for connection in self.connections.keys() {
+            self.send(*connection, kind, message.clone());
+        }
+    }
+
+    /// Queue a message for a player, if they are online.
+    pub fn send_to_player(&self, player: PlayerId, kind: MessageKind, message: OutboundMessage) {
+        if let Some(online) = self.players.get(&player) {
+            self.send(online.connection, kind, message);
+        }
+    }
+
+    /// Tell a connection why its request was refused.
+    pub fn send_error(&self, connection: u64, error: &str) {
+        self.send(connection, MessageKind::Error, OutboundMessage::new(MessagePriority::Chat, error.as_bytes().to_vec()));
+    }
+}
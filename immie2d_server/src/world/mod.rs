@@ -0,0 +1,11 @@
+pub mod weather_scheduler;
+pub mod tile_reservations;
+pub mod encounter_modifiers;
+pub mod audio_cues;
+pub mod simulation_clock;
+pub mod cutscene_runner;
+pub mod entity_store;
+pub mod fast_travel_network;
+pub mod region_instances;
+pub mod step_effects;
+pub mod game_world;
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::player_id::PlayerId;
+
+/// Players each instance of a region holds unless the region sets its own capacity.
+pub const DEFAULT_REGION_CAPACITY: usize = 64;
+/// Seconds an extra instance is kept open after its last player leaves, so players who disconnect or step out briefly
+/// return to the same instance.
+pub const EMPTY_INSTANCE_LIFETIME_SECONDS: u64 = 120;
+
+/* One copy of a region. Index 0 is the main instance, which always exists. Players in different instances of a region
+don't see each other. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct RegionInstanceId {
+    pub map: GlobalString,
+    pub index: u32
+}
+
+struct RegionInstance {
+    index: u32,
+    players: Vec<PlayerId>,
+    /// When the last player left, for closing it once EMPTY_INSTANCE_LIFETIME_SECONDS pass.
+    emptied_at: Option<u64>
+}
+
+/* The players in each instance of every region. A region that reaches its capacity gets another instance for new
+players, and players entering with their party are put with them when there is room. */
+pub struct RegionInstances {
+    default_capacity: usize,
+    capacities: HashMap<GlobalString, usize>,
+    /// The open instances of each region, in order of index.
+    regions: HashMap<GlobalString, Vec<RegionInstance>>,
+    locations: HashMap<PlayerId, RegionInstanceId>
+}
+
+impl RegionInstances {
+    /// Will panic if default_capacity is 0.
+    pub fn new(default_capacity: usize) -> RegionInstances {
+        assert!(default_capacity > 0, "Regions must have room for at least one player");
+        return RegionInstances { default_capacity, capacities: HashMap::new(), regions: HashMap::new(), locations: HashMap::new() };
+    }
+
+    /// Change how many players fit in each instance of a region, such as a small capacity for a busy town. Players
+    /// already in an instance aren't moved out of it.
+    /// Will panic if capacity is 0.
+    pub fn set_capacity(&mut self, map: GlobalString, capacity: usize) {
+        assert!(capacity > 0, "Region {} must have room for at least one player", map);
+        self.capacities.insert(map, capacity);
+    }
+
+    pub fn get_capacity(&self, map: GlobalString) -> usize {
+        return self.capacities.get(&map).copied().unwrap_or(self.default_capacity);
+    }
+
+    /// Put a player into an instance of a region, leaving the instance they were in. They join the instance with the
+    /// most of their party members that has room, otherwise the lowest instance with room, so instances fill up in
+    /// order. A new instance is opened when every instance is full.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::world::region_instances::RegionInstances;
+    ///
+    /// let town = GlobalString::new(&"town".to_string());
+    /// let mut instances = RegionInstances::new(2);
+    /// assert_eq!(instances.enter(PlayerId(1), town, &[], 0).index, 0);
+    /// assert_eq!(instances.enter(PlayerId(2), town, &[], 0).index, 0);
+    /// // The main instance is full, so the next player gets a new one
+    /// assert_eq!(instances.enter(PlayerId(3), town, &[], 0).index, 1);
+    ///
+    /// // A party member with room is joined over the main instance once it frees up
+    /// instances.leave(PlayerId(2), 10);
+    /// assert_eq!(instances.enter(PlayerId(4), town, &[PlayerId(3)], 10).index, 1);
+    /// // Party members in full instances are left behind
+    /// assert_eq!(instances.enter(PlayerId(5), town, &[PlayerId(3)], 10).index, 0);
+    /// assert_eq!(instances.get_players(instances.get_instance_of(PlayerId(3)).unwrap()), vec![PlayerId(3), PlayerId(4)]);
+    /// assert_eq!(instances.get_instance_count(town), 2);
+    /// ```
+    pub fn enter(&mut self, player: PlayerId, map: GlobalString, party: &[PlayerId], now: u64) -> RegionInstanceId {
+        self.leave(player, now);
+        let capacity = self.get_capacity(map);
+        let instances = self.regions.entry(map).or_insert_with(|| vec![RegionInstance { index: 0, players: Vec::new(), emptied_at: None }]);
+        let party_count = |instance: &RegionInstance| instance.players.iter().filter(|member| party.contains(member)).count();
+        let open = instances.iter().position(|instance| instance.players.len() < capacity);
+        let with_party = instances.iter().enumerate()
+            .filter(|(_, instance)| instance.players.len() < capacity && party_count(instance) > 0)
+            .max_by_key(|(position, instance)| (party_count(instance), std::cmp::Reverse(*position)))
+            .map(|(position, _)| position);
+        let position = match with_party.or(open) {
+            Some(position) => position,
+            None => {
+                let index = (0..).find(|index| !instances.iter().any(|instance| instance.index == *index)).unwrap();
+                let position = instances.iter().position(|instance| instance.index > index).unwrap_or(instances.len());
+                instances.insert(position, RegionInstance { index, players: Vec::new(), emptied_at: None });
+                position
+            }
+        };
+        let instance = &mut instances[position];
+        instance.players.push(player);
+        instance.emptied_at = None;
+        let id = RegionInstanceId { map, index: instance.index };
+        self.locations.insert(player, id);
+        return id;
+    }
+
+    /// Take a player out of the instance they are in, such as when they disconnect. Returns the instance they left.
+    pub fn leave(&mut self, player: PlayerId, now: u64) -> Option<RegionInstanceId> {
+        let id = self.locations.remove(&player)?;
+        let instance = self.regions.get_mut(&id.map).and_then(|instances| instances.iter_mut().find(|instance| instance.index == id.index));
+        if let Some(instance) = instance {
+            instance.players.retain(|other| *other != player);
+            if instance.players.is_empty() {
+                instance.emptied_at = Some(now);
+            }
+        }
+        return Some(id);
+    }
+
+    pub fn get_instance_of(&self, player: PlayerId) -> Option<RegionInstanceId> {
+        return self.locations.get(&player).copied();
+    }
+
+    /// The players in an instance, in the order they entered. They are who a player's movement is sent to.
+    pub fn get_players(&self, id: RegionInstanceId) -> Vec<PlayerId> {
+        let instance = self.regions.get(&id.map).and_then(|instances| instances.iter().find(|instance| instance.index == id.index));
+        return instance.map(|instance| instance.players.clone()).unwrap_or_default();
+    }
+
+    /// Open instances of a region, including the main instance once anyone has entered it.
+    pub fn get_instance_count(&self, map: GlobalString) -> usize {
+        return self.regions.get(&map).map_or(0, |instances| instances.len());
+    }
+
+    /// Close extra instances that have been empty for EMPTY_INSTANCE_LIFETIME_SECONDS. The main instance of a region is
+    /// never closed. Returns the instances closed.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_server::world::region_instances::{RegionInstances, EMPTY_INSTANCE_LIFETIME_SECONDS};
+    ///
+    /// let town = GlobalString::new(&"town".to_string());
+    /// let mut instances = RegionInstances::new(1);
+    /// instances.enter(PlayerId(1), town, &[], 0);
+    /// let extra = instances.enter(PlayerId(2), town, &[], 0);
+    /// instances.leave(PlayerId(1), 5);
+    /// instances.leave(PlayerId(2), 5);
+    /// assert!(instances.close_empty_instances(5 + EMPTY_INSTANCE_LIFETIME_SECONDS - 1).is_empty());
+    /// assert_eq!(instances.close_empty_instances(5 + EMPTY_INSTANCE_LIFETIME_SECONDS), vec![extra]);
+    /// assert_eq!(instances.get_instance_count(town), 1);
+    /// ```
+    pub fn close_empty_instances(&mut self, now: u64) -> Vec<RegionInstanceId> {
+        let mut closed = Vec::new();
+        for (map, instances) in self.regions.iter_mut() {
+            instances.retain(|instance| {
+                let is_expired = instance.index != 0 && instance.emptied_at.is_some_and(|emptied_at| now >= emptied_at + EMPTY_INSTANCE_LIFETIME_SECONDS);
+                if is_expired {
+                    closed.push(RegionInstanceId { map: *map, index: instance.index });
+                }
+                return !is_expired;
+            });
+        }
+        return closed;
+    }
+}
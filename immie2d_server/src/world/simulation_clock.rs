@@ -0,0 +1,213 @@
+use std::time::{Duration, Instant};
+
+use immie2d_shared::world::simulation_status::SimulationStatus;
+
+use crate::network::send_queue::OutboundMessage;
+use crate::session::flag_session::FLAG_SCOREBOARD_COALESCE_KEY;
+
+/// Ticks per second the world is simulated at by default.
+pub const DEFAULT_TICK_RATE: u32 = 20;
+/// Highest tick rate the simulation can be set to.
+pub const MAX_TICK_RATE: u32 = 1000;
+/// Most ticks a single poll catches up on after the server stalls, so a long stall doesn't run a burst of ticks.
+pub const MAX_CATCH_UP_TICKS: u32 = 5;
+/// Most ticks that can be waiting to be stepped while paused. All of them run in one poll, so this bounds the stall.
+pub const MAX_STEP_TICKS: u32 = 100;
+/// Snapshot coalesce key of simulation status broadcasts. Only the latest status matters.
+pub const SIMULATION_STATUS_COALESCE_KEY: u32 = FLAG_SCOREBOARD_COALESCE_KEY - 1;
+
+/* A debug control of the world simulation, for diagnosing movement and battle desyncs on a dev server. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SimulationControl {
+    Pause,
+    Resume,
+    /// Run a number of ticks while paused.
+    Step(u32),
+    SetTickRate(u32),
+    /// Change nothing, only describe the simulation.
+    Status
+}
+
+impl SimulationControl {
+    /// Parse a line sent over the debug channel.
+    /// ```
+    /// use immie2d_server::world::simulation_clock::SimulationControl;
+    ///
+    /// assert_eq!(SimulationControl::parse("pause"), Ok(SimulationControl::Pause));
+    /// assert_eq!(SimulationControl::parse("step"), Ok(SimulationControl::Step(1)));
+    /// assert_eq!(SimulationControl::parse(" step 10 "), Ok(SimulationControl::Step(10)));
+    /// assert_eq!(SimulationControl::parse("tick-rate 5"), Ok(SimulationControl::SetTickRate(5)));
+    /// assert!(SimulationControl::parse("tick-rate 0").is_err());
+    /// assert!(SimulationControl::parse("tick-rate 5000").is_err());
+    /// assert!(SimulationControl::parse("step 0").is_err());
+    /// assert!(SimulationControl::parse("step 4294967295").is_err());
+    /// assert!(SimulationControl::parse("rewind").is_err());
+    /// ```
+    pub fn parse(line: &str) -> Result<SimulationControl, String> {
+        let mut words = line.split_whitespace();
+        let command = words.next().ok_or("Missing simulation command".to_string())?;
+        let mut parse_count = |name: &str, max: u32| -> Result<Option<u32>, String> {
+            return match words.next() {
+                Some(word) => match word.parse::<u32>() {
+                    Ok(count) if count > 0 && count <= max => Ok(Some(count)),
+                    _ => Err(format!("Invalid {} [{}], expected 1 to {}", name, word, max))
+                },
+                None => Ok(None)
+            };
+        };
+        let control = match command {
+            "pause" => SimulationControl::Pause,
+            "resume" => SimulationControl::Resume,
+            "step" => SimulationControl::Step(parse_count("tick count", MAX_STEP_TICKS)?.unwrap_or(1)),
+            "tick-rate" => SimulationControl::SetTickRate(parse_count("tick rate", MAX_TICK_RATE)?.ok_or("Missing tick rate".to_string())?),
+            "status" => SimulationControl::Status,
+            _ => return Err(format!("Unknown simulation command [{}]", command))
+        };
+        return Ok(control);
+    }
+}
+
+/* Decides when the server's world ticks, and lets the debug channel pause it, step single ticks and change the tick
+rate while it runs. The world loop polls it and runs however many ticks are due. */
+pub struct SimulationClock {
+    tick: u64,
+    tick_rate: u32,
+    is_paused: bool,
+    pending_steps: u32,
+    next_tick_at: Instant
+}
+
+impl SimulationClock {
+    pub fn new(now: Instant) -> SimulationClock {
+        let mut clock = SimulationClock { tick: 0, tick_rate: DEFAULT_TICK_RATE, is_paused: false, pending_steps: 0, next_tick_at: now };
+        clock.next_tick_at = now + clock.get_tick_interval();
+        return clock;
+    }
+
+    /// Will panic if the tick rate is 0 or above MAX_TICK_RATE.
+    pub fn with_tick_rate(mut self, tick_rate: u32) -> SimulationClock {
+        assert!(tick_rate > 0 && tick_rate <= MAX_TICK_RATE, "Tick rate {} must be from 1 to {}", tick_rate, MAX_TICK_RATE);
+        self.next_tick_at -= self.get_tick_interval();
+        self.tick_rate = tick_rate;
+        self.next_tick_at += self.get_tick_interval();
+        return self;
+    }
+
+    pub fn get_tick_interval(&self) -> Duration {
+        return Duration::from_secs(1) / self.tick_rate;
+    }
+
+    /// Steps requested while paused that haven't run yet.
+    pub fn get_pending_steps(&self) -> u32 {
+        return self.pending_steps;
+    }
+
+    pub fn get_status(&self) -> SimulationStatus {
+        return SimulationStatus { is_paused: self.is_paused, tick: self.tick, tick_rate: self.tick_rate };
+    }
+
+    /// Message to broadcast the status to every client.
+    pub fn get_status_message(&self) -> OutboundMessage {
+        return OutboundMessage::snapshot(SIMULATION_STATUS_COALESCE_KEY, self.get_status().to_bytes());
+    }
+
+    /// Apply a debug control. Returns the status message to broadcast if clients should be told about it.
+    /// Fails without changing anything if the control doesn't make sense right now, such as stepping while running.
+    /// ```
+    /// use std::time::{Duration, Instant};
+    /// use immie2d_server::world::simulation_clock::{SimulationClock, SimulationControl, MAX_STEP_TICKS};
+    ///
+    /// let start = Instant::now();
+    /// let mut clock = SimulationClock::new(start);
+    /// assert!(clock.apply(SimulationControl::Step(1), start).is_err());
+    /// assert!(clock.apply(SimulationControl::Pause, start).unwrap().is_some());
+    /// assert!(clock.apply(SimulationControl::Pause, start).is_err());
+    ///
+    /// // Paused, only steps run
+    /// assert_eq!(clock.poll(start + Duration::from_secs(10)), 0);
+    /// clock.apply(SimulationControl::Step(3), start).unwrap();
+    /// assert_eq!(clock.poll(start + Duration::from_secs(10)), 3);
+    /// assert!(clock.get_status().is_paused);
+    /// assert_eq!(clock.get_status().tick, 3);
+    /// clock.apply(SimulationControl::Step(MAX_STEP_TICKS), start).unwrap();
+    /// assert!(clock.apply(SimulationControl::Step(1), start).is_err());
+    /// assert_eq!(clock.poll(start + Duration::from_secs(10)), MAX_STEP_TICKS);
+    ///
+    /// // Resuming doesn't catch up on the time spent paused
+    /// let resumed = start + Duration::from_secs(20);
+    /// clock.apply(SimulationControl::SetTickRate(10), resumed).unwrap();
+    /// clock.apply(SimulationControl::Resume, resumed).unwrap();
+    /// assert_eq!(clock.poll(resumed + Duration::from_millis(50)), 0);
+    /// assert_eq!(clock.poll(resumed + Duration::from_millis(100)), 1);
+    /// assert_eq!(clock.poll(resumed + Duration::from_millis(300)), 2);
+    /// assert!(clock.apply(SimulationControl::Status, resumed).unwrap().is_none());
+    /// ```
+    pub fn apply(&mut self, control: SimulationControl, now: Instant) -> Result<Option<OutboundMessage>, String> {
+        match control {
+            SimulationControl::Pause => {
+                if self.is_paused {
+                    return Err("The simulation is already paused".to_string());
+                }
+                self.is_paused = true;
+                self.pending_steps = 0;
+            },
+            SimulationControl::Resume => {
+                if !self.is_paused {
+                    return Err("The simulation is already running".to_string());
+                }
+                self.is_paused = false;
+                self.pending_steps = 0;
+                self.next_tick_at = now + self.get_tick_interval();
+            },
+            SimulationControl::Step(ticks) => {
+                if !self.is_paused {
+                    return Err("Pause the simulation before stepping it".to_string());
+                }
+                if ticks == 0 || self.pending_steps + ticks > MAX_STEP_TICKS {
+                    return Err(format!("At most {} ticks can be stepped at once, {} are still waiting", MAX_STEP_TICKS, self.pending_steps));
+                }
+                self.pending_steps += ticks;
+                // The status is broadcast once the steps have run, so clients see the new tick.
+                return Ok(None);
+            },
+            SimulationControl::SetTickRate(tick_rate) => {
+                if tick_rate == 0 || tick_rate > MAX_TICK_RATE {
+                    return Err(format!("Tick rate {} must be from 1 to {}", tick_rate, MAX_TICK_RATE));
+                }
+                self.tick_rate = tick_rate;
+                self.next_tick_at = now + self.get_tick_interval();
+            },
+            SimulationControl::Status => return Ok(None)
+        }
+        return Ok(Some(self.get_status_message()));
+    }
+
+    /// How many ticks the world should run now. While paused only requested steps run, and all of them at once, after
+    /// which the status should be broadcast so clients see the new tick.
+    pub fn poll(&mut self, now: Instant) -> u32 {
+        let ticks = if self.is_paused {
+            std::mem::take(&mut self.pending_steps)
+        } else {
+            let mut due = 0;
+            while now >= self.next_tick_at && due < MAX_CATCH_UP_TICKS {
+                self.next_tick_at += self.get_tick_interval();
+                due += 1;
+            }
+            if now >= self.next_tick_at {
+                // Too far behind to catch up, so drop the missed ticks.
+                self.next_tick_at = now + self.get_tick_interval();
+            }
+            due
+        };
+        self.tick += ticks as u64;
+        return ticks;
+    }
+
+    /// When the next tick is due, for the world loop to sleep until. None while paused.
+    pub fn get_next_tick_at(&self) -> Option<Instant> {
+        if self.is_paused {
+            return None;
+        }
+        return Some(self.next_tick_at);
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+
+use immie2d_shared::gameplay::immie::bond::{BondEvent, STEPS_PER_WALKING_BOND};
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::world::explored_area::ExploredAreaUpdate;
+use immie2d_shared::world::minimap::Minimap;
+use immie2d_shared::world::tile_position::TilePosition;
+
+use crate::storage::player_profile::PlayerProfile;
+
+/* What a granted step changed, to send to the stepping player's client. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct StepOutcome {
+    /// Whether each Immie in the player's party gained walking bond.
+    pub gained_walking_bond: bool,
+    /// Minimap cells the step explored for the first time, if any.
+    pub explored: Option<ExploredAreaUpdate>
+}
+
+/* What happens to a player's profile as they walk the overworld. Only steps the server granted are counted, so a
+client can't earn walking bond by claiming steps it never took. Step counts are kept while a player is online and lost
+when they log out. */
+pub struct StepEffects {
+    /// Steps each player walked since their party last gained walking bond.
+    steps: HashMap<PlayerId, u32>
+}
+
+impl StepEffects {
+    pub fn new() -> StepEffects {
+        return StepEffects { steps: HashMap::new() };
+    }
+
+    /// Count a step the server granted a player onto a tile of the map of a minimap. Every STEPS_PER_WALKING_BOND
+    /// steps, each Immie in their party gains walking bond. The minimap cells around the tile are explored. See
+    /// PlayerProfile::explore()
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::immie::bond::{BASE_BOND, BOND_FROM_WALKING, STEPS_PER_WALKING_BOND};
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::world::{tile_map::TileMap, tile_position::TilePosition, minimap::Minimap};
+    /// use immie2d_server::storage::player_profile::PlayerProfile;
+    /// use immie2d_server::world::step_effects::StepEffects;
+    ///
+    /// let mut profile = PlayerProfile::new(PlayerId(1), "ash".to_string());
+    /// profile.party.push(Immie::new(GlobalString::new(&"lavapup".to_string()), 5, AbilityNames::new(Vec::new())));
+    /// let minimap = Minimap::generate(&TileMap::new(GlobalString::new(&"town".to_string()), 64, 64), 4);
+    /// let mut steps = StepEffects::new();
+    /// let first = steps.on_step(&mut profile, &minimap, TilePosition::new(0, 0));
+    /// assert_eq!(first.explored.unwrap().cells.len(), 9);
+    /// for _ in 2..STEPS_PER_WALKING_BOND {
+    ///     let outcome = steps.on_step(&mut profile, &minimap, TilePosition::new(1, 0));
+    ///     assert!(!outcome.gained_walking_bond);
+    ///     assert!(outcome.explored.is_none());
+    /// }
+    /// let outcome = steps.on_step(&mut profile, &minimap, TilePosition::new(4, 0));
+    /// assert!(outcome.gained_walking_bond);
+    /// assert_eq!(outcome.explored.unwrap().cells, vec![(3, 0), (3, 1), (3, 2)]);
+    /// assert_eq!(profile.party[0].bond, BASE_BOND + BOND_FROM_WALKING);
+    /// assert!(!steps.on_step(&mut profile, &minimap, TilePosition::new(4, 0)).gained_walking_bond);
+    /// ```
+    pub fn on_step(&mut self, profile: &mut PlayerProfile, minimap: &Minimap, tile: TilePosition) -> StepOutcome {
+        let explored = profile.explore(minimap, tile);
+        let steps = self.steps.entry(profile.player).or_insert(0);
+        *steps += 1;
+        if *steps < STEPS_PER_WALKING_BOND {
+            return StepOutcome { gained_walking_bond: false, explored };
+        }
+        *steps = 0;
+        for immie in profile.party.iter_mut() {
+            immie.apply_bond_event(BondEvent::Walked);
+        }
+        return StepOutcome { gained_walking_bond: true, explored };
+    }
+
+    /// Forget the steps of a player logging out.
+    pub fn remove_player(&mut self, player: PlayerId) {
+        self.steps.remove(&player);
+    }
+}
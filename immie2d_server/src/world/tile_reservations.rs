@@ -0,0 +1,197 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use immie2d_shared::world::move_result::{MoveDenial, MoveResult};
+use immie2d_shared::world::tile_map::TileMap;
+use immie2d_shared::world::tile_position::{Direction, TilePosition};
+
+use crate::network::send_queue::{MessagePriority, OutboundMessage};
+
+/* A step an entity wants to take this tick. `from` is where the client predicted the entity to be. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MoveIntent {
+    pub network_id: u32,
+    pub from: TilePosition,
+    pub direction: Direction
+}
+
+/* Which entity stands on each tile of a map, so no two players or NPCs ever share one. Steps are collected over a tick
+and resolved together, so the result doesn't depend on the order they arrived in. Followers walk through their
+leader's tiles and are not tracked. */
+pub struct TileReservations {
+    occupants: HashMap<TilePosition, u32>,
+    positions: HashMap<u32, TilePosition>,
+    /// How many contested tiles each entity lost since it last stepped. Longer waits win, so no one is starved.
+    waiting: HashMap<u32, u32>
+}
+
+impl TileReservations {
+    pub fn new() -> TileReservations {
+        return TileReservations { occupants: HashMap::new(), positions: HashMap::new(), waiting: HashMap::new() };
+    }
+
+    /// Place an entity on a tile, such as when it enters the map. Returns false without placing it if the tile is
+    /// occupied. Will panic if the entity is already placed.
+    pub fn add_entity(&mut self, network_id: u32, tile: TilePosition) -> bool {
+        assert!(!self.positions.contains_key(&network_id), "Entity {} already has a tile", network_id);
+        if self.occupants.contains_key(&tile) {
+            return false;
+        }
+        self.occupants.insert(tile, network_id);
+        self.positions.insert(network_id, tile);
+        return true;
+    }
+
+    /// Free the tile of an entity leaving the map.
+    pub fn remove_entity(&mut self, network_id: u32) {
+        if let Some(tile) = self.positions.remove(&network_id) {
+            self.occupants.remove(&tile);
+        }
+        self.waiting.remove(&network_id);
+    }
+
+    pub fn get_position(&self, network_id: u32) -> Option<TilePosition> {
+        return self.positions.get(&network_id).copied();
+    }
+
+    pub fn get_occupant(&self, tile: TilePosition) -> Option<u32> {
+        return self.occupants.get(&tile).copied();
+    }
+
+    /// Grant or deny every step of a tick, returning a result per intent in the order given. Intents from unknown
+    /// entities are ignored, and only the first intent of an entity counts. Entities can step onto a tile being left
+    /// this tick, but two entities can't swap tiles. When several entities step onto the same tile, the one that has
+    /// waited the most ticks wins, then the lowest network id.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::move_result::MoveDenial;
+    /// use immie2d_shared::world::tile_map::TileMap;
+    /// use immie2d_shared::world::tile_position::{Direction, TilePosition};
+    /// use immie2d_server::world::tile_reservations::{MoveIntent, TileReservations};
+    ///
+    /// let map = TileMap::new(GlobalString::new(&"town".to_string()), 8, 8);
+    /// let mut reservations = TileReservations::new();
+    /// reservations.add_entity(1, TilePosition::new(1, 1));
+    /// reservations.add_entity(2, TilePosition::new(3, 1));
+    /// reservations.add_entity(3, TilePosition::new(4, 1));
+    ///
+    /// // 1 and 2 both step onto (2, 1), while 3 follows 2 into the tile 2 is trying to leave
+    /// let intents = [
+    ///     MoveIntent { network_id: 2, from: TilePosition::new(3, 1), direction: Direction::Left },
+    ///     MoveIntent { network_id: 1, from: TilePosition::new(1, 1), direction: Direction::Right },
+    ///     MoveIntent { network_id: 3, from: TilePosition::new(4, 1), direction: Direction::Left }
+    /// ];
+    /// let results = reservations.resolve_tick(&intents, &map);
+    /// assert_eq!(results[0].denial, Some(MoveDenial::Contested));
+    /// assert_eq!(results[0].tile, TilePosition::new(3, 1));
+    /// assert!(results[1].is_granted());
+    /// assert_eq!(results[2].denial, Some(MoveDenial::Occupied));
+    /// assert_eq!(reservations.get_occupant(TilePosition::new(2, 1)), Some(1));
+    ///
+    /// // Having waited, 2 wins the next contest against 3
+    /// reservations.remove_entity(1);
+    /// reservations.add_entity(4, TilePosition::new(2, 0));
+    /// let intents = [
+    ///     MoveIntent { network_id: 2, from: TilePosition::new(3, 1), direction: Direction::Up },
+    ///     MoveIntent { network_id: 4, from: TilePosition::new(2, 0), direction: Direction::Right }
+    /// ];
+    /// let results = reservations.resolve_tick(&intents, &map);
+    /// assert!(results[0].is_granted());
+    /// assert_eq!(results[1].denial, Some(MoveDenial::Contested));
+    /// ```
+    pub fn resolve_tick(&mut self, intents: &[MoveIntent], map: &TileMap) -> Vec<MoveResult> {
+        // Each entity's target, or its denial
+        let mut outcomes: HashMap<u32, Result<TilePosition, MoveDenial>> = HashMap::new();
+        for intent in intents {
+            let Some(position) = self.get_position(intent.network_id) else {
+                continue;
+            };
+            if outcomes.contains_key(&intent.network_id) {
+                continue;
+            }
+            let target = position.offset(intent.direction);
+            let outcome = if intent.from != position {
+                Err(MoveDenial::OutOfSync)
+            } else if !map.is_in_bounds(target) || map.is_blocked(target) {
+                Err(MoveDenial::Blocked)
+            } else {
+                Ok(target)
+            };
+            outcomes.insert(intent.network_id, outcome);
+        }
+
+        let mut contenders: HashMap<TilePosition, Vec<u32>> = HashMap::new();
+        for (network_id, outcome) in outcomes.iter() {
+            if let Ok(target) = outcome {
+                contenders.entry(*target).or_default().push(*network_id);
+            }
+        }
+        for ids in contenders.values() {
+            let winner = *ids.iter().min_by_key(|id| (Reverse(self.waiting.get(id).copied().unwrap_or(0)), **id)).unwrap();
+            for id in ids.iter().filter(|id| **id != winner) {
+                outcomes.insert(*id, Err(MoveDenial::Contested));
+            }
+        }
+
+        // A step onto an occupied tile only succeeds if the occupant successfully steps away, other than onto the
+        // stepping entity's tile. Denying one step can deny the steps queued behind it, so repeat until settled.
+        loop {
+            let mut denied = Vec::new();
+            for (network_id, outcome) in outcomes.iter() {
+                let Ok(target) = outcome else {
+                    continue;
+                };
+                let Some(occupant) = self.get_occupant(*target) else {
+                    continue;
+                };
+                let is_leaving = match outcomes.get(&occupant) {
+                    Some(Ok(occupant_target)) => *occupant_target != self.positions[network_id],
+                    _ => false
+                };
+                if !is_leaving {
+                    denied.push(*network_id);
+                }
+            }
+            if denied.is_empty() {
+                break;
+            }
+            for network_id in denied {
+                outcomes.insert(network_id, Err(MoveDenial::Occupied));
+            }
+        }
+
+        for (network_id, outcome) in outcomes.iter() {
+            if outcome.is_ok() {
+                let from = self.positions[network_id];
+                self.occupants.remove(&from);
+            }
+        }
+        for (network_id, outcome) in outcomes.iter() {
+            match outcome {
+                Ok(target) => {
+                    self.occupants.insert(*target, *network_id);
+                    self.positions.insert(*network_id, *target);
+                    self.waiting.remove(network_id);
+                },
+                Err(MoveDenial::Contested) => *self.waiting.entry(*network_id).or_insert(0) += 1,
+                Err(_) => ()
+            }
+        }
+
+        let mut results = Vec::new();
+        for intent in intents {
+            let Some(outcome) = outcomes.remove(&intent.network_id) else {
+                continue;
+            };
+            let tile = self.positions[&intent.network_id];
+            results.push(MoveResult { network_id: intent.network_id, tile, denial: outcome.err() });
+        }
+        return results;
+    }
+}
+
+/// Message to send the result of a step to the client that took it. Results are never coalesced, since a client
+/// needs every denial to roll back its prediction.
+pub fn get_move_result_message(result: &MoveResult) -> OutboundMessage {
+    return OutboundMessage::new(MessagePriority::Snapshot, result.to_bytes());
+}
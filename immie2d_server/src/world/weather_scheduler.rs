@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use immie2d_shared::engine_types::game_rng::GameRng;
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::battle::field_state::FieldState;
+use immie2d_shared::gameplay::encounter::encounter_conditions::{EncounterContext, TimeOfDay, Weather};
+use immie2d_shared::world::region_weather::{WeatherForecast, WeatherUpdate};
+
+use crate::network::send_queue::OutboundMessage;
+
+/// How often the weather of each region is rolled again.
+pub const WEATHER_CHANGE_INTERVAL: Duration = Duration::from_secs(20 * 60);
+
+/// Snapshot coalesce key of weather updates. A client is only ever in one region, so a newer update replaces an older one.
+pub const WEATHER_COALESCE_KEY: u32 = u32::MAX;
+
+/// Each region has its own rng, so the order regions are updated in doesn't change their weather.
+struct RegionWeather {
+    forecast: WeatherForecast,
+    rng: GameRng,
+    weather: Weather,
+    changes_at: u64
+}
+
+/* Rolls the overworld weather of every region on a schedule. The server is the only source of weather, so every client
+in a region sees the same weather, and it decides both the encounters and the field of battles started there.
+Times are unix seconds. */
+pub struct WeatherScheduler {
+    regions: HashMap<GlobalString, RegionWeather>,
+    interval: Duration,
+    rng: GameRng
+}
+
+impl WeatherScheduler {
+    pub fn new(seed: u64) -> WeatherScheduler {
+        return WeatherScheduler { regions: HashMap::new(), interval: WEATHER_CHANGE_INTERVAL, rng: GameRng::new(seed) };
+    }
+
+    /// Change how often the weather is rolled. Will panic if the interval is 0.
+    pub fn with_interval(mut self, interval: Duration) -> WeatherScheduler {
+        assert!(interval.as_secs() > 0, "The weather change interval must be at least a second");
+        self.interval = interval;
+        return self;
+    }
+
+    /// Start scheduling the weather of a region, rolling its first weather straight away.
+    /// Replaces the forecast of a region that was already added.
+    pub fn add_region(&mut self, map: GlobalString, forecast: WeatherForecast, now: u64) -> Weather {
+        let mut rng = GameRng::new(self.rng.next_u64());
+        let weather = forecast.roll(&mut rng);
+        self.regions.insert(map, RegionWeather { forecast, rng, weather, changes_at: now + self.interval.as_secs() });
+        return weather;
+    }
+
+    /// The current weather of a region. Regions without a forecast are always clear.
+    pub fn get_weather(&self, map: GlobalString) -> Weather {
+        return match self.regions.get(&map) {
+            Some(region) => region.weather,
+            None => Weather::Clear
+        };
+    }
+
+    /// The field that battles started in a region begin with.
+    pub fn get_field_state(&self, map: GlobalString) -> FieldState {
+        return FieldState::from_weather(self.get_weather(map));
+    }
+
+    /// The context to roll encounters in a region with.
+    pub fn get_encounter_context(&self, map: GlobalString, time_of_day: TimeOfDay) -> EncounterContext {
+        let mut context = EncounterContext::new(time_of_day, self.get_weather(map));
+        context.region = Some(map);
+        return context;
+    }
+
+    /// Roll the weather of every region that is due. Returns the regions whose weather actually changed, to broadcast
+    /// to the clients in them.
+    /// ```
+    /// use std::time::Duration;
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::{TimeOfDay, Weather};
+    /// use immie2d_shared::world::region_weather::WeatherForecast;
+    /// use immie2d_server::world::weather_scheduler::WeatherScheduler;
+    ///
+    /// let route = GlobalString::new(&"route 1".to_string());
+    /// let mut scheduler = WeatherScheduler::new(9).with_interval(Duration::from_secs(60));
+    /// scheduler.add_region(route, WeatherForecast::new(vec![(Weather::Clear, 1), (Weather::Rain, 1)]), 0);
+    ///
+    /// // Nothing is due before the interval passes
+    /// assert!(scheduler.update(59).is_empty());
+    /// let mut changes = 0;
+    /// for minute in 1..50 {
+    ///     for update in scheduler.update(minute * 60) {
+    ///         assert_eq!(update.map, route);
+    ///         changes += 1;
+    ///     }
+    /// }
+    /// assert!(changes > 0);
+    /// assert_eq!(scheduler.get_field_state(route).weather, scheduler.get_weather(route));
+    /// assert_eq!(scheduler.get_encounter_context(route, TimeOfDay::Day).weather, scheduler.get_weather(route));
+    /// ```
+    pub fn update(&mut self, now: u64) -> Vec<WeatherUpdate> {
+        let mut updates = Vec::new();
+        for (map, region) in self.regions.iter_mut() {
+            if now < region.changes_at {
+                continue;
+            }
+            // Catch up on missed changes without rolling every one of them.
+            let elapsed_intervals = (now - region.changes_at) / self.interval.as_secs() + 1;
+            region.changes_at += elapsed_intervals * self.interval.as_secs();
+            let weather = region.forecast.roll(&mut region.rng);
+            if weather != region.weather {
+                region.weather = weather;
+                updates.push(WeatherUpdate { map: *map, weather });
+            }
+        }
+        return updates;
+    }
+}
+
+/// Message to send a weather update to a client.
+pub fn get_weather_message(update: &WeatherUpdate) -> OutboundMessage {
+    return OutboundMessage::snapshot(WEATHER_COALESCE_KEY, update.to_bytes());
+}
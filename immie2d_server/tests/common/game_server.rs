@@ -0,0 +1,115 @@
+// Shared by the tests that drive a server over real game connections.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use immie2d_shared::engine_types::game_protocol::{decode_message_line, ClientRequest, MessageKind};
+use immie2d_shared::engine_types::game_rng::GameRng;
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::{ability::ability_map::AbilityMap, game_data::GameData, item::item_map::ItemMap, species::species_map::SpeciesMap};
+use immie2d_shared::gameplay::player_id::PlayerId;
+use immie2d_shared::world::tile_position::{TilePosition, WorldPosition};
+use immie2d_server::auth::auth_service::AuthService;
+use immie2d_server::config::server_config::StorageBackend;
+use immie2d_server::distribution::gift_distribution::GiftDistributor;
+use immie2d_server::network::game_connection::{resume_game_connection, serve_game_connection, GameServices};
+use immie2d_server::session::session_manager::SessionManager;
+use immie2d_server::world::game_world::GameWorld;
+use immie2d_server::world::simulation_clock::SimulationClock;
+
+/// Longest a test waits for a message before failing.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serve game connections on a free local port with memory storage and no game data. Returns the port's address.
+pub fn start_server() -> String {
+    return start_server_with_gifts(GiftDistributor::new());
+}
+
+/// Like start_server(), handing out the given gift distributions.
+pub fn start_server_with_gifts(gifts: GiftDistributor) -> String {
+    return serve_connections(create_services(gifts), None);
+}
+
+/// The services of a server with memory storage and no game data, handing out the given gift distributions.
+pub fn create_services(gifts: GiftDistributor) -> Arc<GameServices> {
+    let sessions = SessionManager::new(GameData::new(1, SpeciesMap::new(), AbilityMap::new(), ItemMap::new()).into_handle());
+    let world = GameWorld::new(sessions, WorldPosition::new(GlobalString::new(&"town".to_string()), TilePosition::new(0, 0)));
+    return Arc::new(GameServices {
+        world: Mutex::new(world),
+        clock: Mutex::new(SimulationClock::new(Instant::now())),
+        auth: Mutex::new(AuthService::new()),
+        storage: StorageBackend::Memory.open_pool().unwrap(),
+        tracer: None,
+        gifts,
+        gift_rng: Mutex::new(GameRng::new(1))
+    });
+}
+
+/// Serve game connections with some services on a free local port, returning its address. Every connection resumes
+/// the given player as if handed off by another server process, if there is one.
+pub fn serve_connections(services: Arc<GameServices>, resumed: Option<PlayerId>) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap().to_string();
+    thread::spawn(move || {
+        for (connection, stream) in listener.incoming().enumerate() {
+            let services = services.clone();
+            thread::spawn(move || match resumed {
+                Some(player) => resume_game_connection(stream.unwrap(), &services, connection as u64, player),
+                None => serve_game_connection(stream.unwrap(), &services, connection as u64)
+            });
+        }
+    });
+    return address;
+}
+
+/* A client connection to a test server, sending requests and reading the messages sent back. */
+pub struct TestClient {
+    stream: TcpStream,
+    reader: BufReader<TcpStream>
+}
+
+impl TestClient {
+    pub fn connect(address: &str) -> TestClient {
+        let stream = TcpStream::connect(address).unwrap();
+        stream.set_read_timeout(Some(READ_TIMEOUT)).unwrap();
+        let reader = BufReader::new(stream.try_clone().unwrap());
+        return TestClient { stream, reader };
+    }
+
+    pub fn send(&mut self, request: ClientRequest) {
+        self.stream.write_all(request.to_line().as_bytes()).unwrap();
+    }
+
+    /// Read the next message. Will panic if the server closes the connection.
+    pub fn receive(&mut self) -> (MessageKind, Vec<u8>) {
+        let mut line = String::new();
+        assert!(self.reader.read_line(&mut line).unwrap() > 0, "The server closed the connection");
+        return decode_message_line(&line).unwrap();
+    }
+
+    /// Read messages until one of a kind arrives, returning its payload. Will panic if the server reports an error first.
+    pub fn expect(&mut self, kind: MessageKind) -> Vec<u8> {
+        loop {
+            let (received, payload) = self.receive();
+            if received == kind {
+                return payload;
+            }
+            assert_ne!(received, MessageKind::Error, "Expected {:?} but the server refused: {}", kind, String::from_utf8_lossy(&payload));
+        }
+    }
+
+    /// Create an account and log in to it.
+    pub fn create_and_log_in(&mut self, username: &str, password: &str) {
+        self.send(ClientRequest::CreateAccount { username: username.to_string(), email: None, password: password.to_string() });
+        self.expect(MessageKind::AccountCreated);
+        self.log_in(username, password);
+    }
+
+    pub fn log_in(&mut self, username: &str, password: &str) {
+        self.send(ClientRequest::Login { username: username.to_string(), code: None, password: password.to_string() });
+        self.expect(MessageKind::LoggedIn);
+    }
+}
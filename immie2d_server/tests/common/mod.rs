@@ -0,0 +1,3 @@
+// Each test binary compiles its own copy and uses only some of the helpers
+#[allow(dead_code)]
+pub mod game_server;
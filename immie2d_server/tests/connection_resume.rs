@@ -0,0 +1,40 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use immie2d_shared::engine_types::game_protocol::{ClientRequest, MessageKind};
+use immie2d_shared::gameplay::synced_settings::{SyncedSettings, TextSpeed};
+use immie2d_server::distribution::gift_distribution::GiftDistributor;
+use common::game_server::{create_services, serve_connections, TestClient};
+
+fn sync(client: &mut TestClient, settings: SyncedSettings) -> SyncedSettings {
+    client.send(ClientRequest::SyncSettings(settings));
+    return SyncedSettings::from_bytes(&client.expect(MessageKind::SyncedSettings)).unwrap();
+}
+
+#[test]
+fn a_handed_off_connection_plays_on_with_its_saved_profile_without_logging_in() {
+    let services = create_services(GiftDistributor::new());
+    let mut old = TestClient::connect(&serve_connections(services.clone(), None));
+    old.create_and_log_in("misty", "starmie123");
+    let player = services.lock_world().get_online_players().next().unwrap().profile.player;
+    let uploaded = sync(&mut old, SyncedSettings { text_speed: TextSpeed::Fast, is_modified: true, ..SyncedSettings::default() });
+    drop(old);
+
+    // The old process saves every profile before handing off
+    for attempt in 0.. {
+        assert!(attempt < 100, "The old connection never saved its profile");
+        if services.lock_world().reserve(player).is_ok() {
+            services.lock_world().cancel_reservation(player);
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    let mut resumed = TestClient::connect(&serve_connections(services.clone(), Some(player)));
+    assert_eq!(sync(&mut resumed, SyncedSettings::default()), uploaded);
+    resumed.send(ClientRequest::Login { username: "misty".to_string(), code: None, password: "starmie123".to_string() });
+    assert_eq!(resumed.expect(MessageKind::Error), b"Already logged in");
+}
@@ -0,0 +1,48 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use immie2d_shared::engine_types::game_protocol::{ClientRequest, MessageKind};
+use immie2d_server::distribution::gift_distribution::GiftDistributor;
+use common::game_server::{start_server_with_gifts, TestClient};
+
+fn start_festival() -> String {
+    return start_server_with_gifts(GiftDistributor::from_json(r#"[
+        { "name": "launch_potions", "start": 0, "end": 18446744073709551615, "item": "potion", "count": 5 },
+        { "name": "festival_candy", "code": "SUMMER-FEST", "start": 0, "end": 18446744073709551615, "item": "rare_candy" },
+        { "name": "retired_candy", "code": "OLD-FEST", "start": 0, "end": 1000, "item": "rare_candy" }
+    ]"#).unwrap());
+}
+
+fn redeem(client: &mut TestClient, code: &str) -> (MessageKind, String) {
+    client.send(ClientRequest::RedeemCode { code: code.to_string() });
+    loop {
+        let (kind, payload) = client.receive();
+        if kind == MessageKind::GiftReceived || kind == MessageKind::Error {
+            return (kind, String::from_utf8(payload).unwrap());
+        }
+    }
+}
+
+#[test]
+fn login_gifts_arrive_with_the_login() {
+    let address = start_festival();
+    let mut client = TestClient::connect(&address);
+    client.create_and_log_in("ash", "pikachu123");
+    assert_eq!(client.expect(MessageKind::GiftReceived), b"launch_potions".to_vec());
+}
+
+#[test]
+fn codes_are_redeemed_once_per_account() {
+    let address = start_festival();
+    let mut client = TestClient::connect(&address);
+    client.send(ClientRequest::RedeemCode { code: "SUMMER-FEST".to_string() });
+    assert_eq!(client.receive().0, MessageKind::Error);
+
+    client.create_and_log_in("ash", "pikachu123");
+    client.expect(MessageKind::GiftReceived);
+    assert_eq!(redeem(&mut client, " summer-fest "), (MessageKind::GiftReceived, "festival_candy".to_string()));
+    assert_eq!(redeem(&mut client, "SUMMER-FEST").0, MessageKind::Error);
+    assert_eq!(redeem(&mut client, "OLD-FEST"), (MessageKind::Error, "That code has expired".to_string()));
+    assert_eq!(redeem(&mut client, "WINTER-FEST"), (MessageKind::Error, "That code doesn't exist".to_string()));
+}
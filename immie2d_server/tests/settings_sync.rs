@@ -0,0 +1,52 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use immie2d_shared::engine_types::game_protocol::{ClientRequest, MessageKind};
+use immie2d_shared::gameplay::synced_settings::{BattlePace, SyncedSettings, TextSpeed};
+use common::game_server::{start_server, TestClient};
+
+fn sync(client: &mut TestClient, settings: SyncedSettings) -> SyncedSettings {
+    client.send(ClientRequest::SyncSettings(settings));
+    return SyncedSettings::from_bytes(&client.expect(MessageKind::SyncedSettings)).unwrap();
+}
+
+#[test]
+fn settings_changed_on_one_device_reach_the_next_device_to_log_in() {
+    let address = start_server();
+    let mut laptop = TestClient::connect(&address);
+    laptop.create_and_log_in("misty", "starmie123");
+    let edited = SyncedSettings { text_speed: TextSpeed::Fast, battle_pace: BattlePace::Instant, is_modified: true, ..SyncedSettings::default() };
+    let uploaded = sync(&mut laptop, edited.clone());
+    assert_eq!(uploaded, SyncedSettings { revision: 1, is_modified: false, ..edited });
+    drop(laptop);
+
+    // Logging in is refused until the laptop's connection is torn down and its profile saved
+    let mut phone = TestClient::connect(&address);
+    for attempt in 0.. {
+        assert!(attempt < 100, "The laptop never left the world");
+        phone.send(ClientRequest::Login { username: "misty".to_string(), code: None, password: "starmie123".to_string() });
+        let reply = loop {
+            let (kind, _) = phone.receive();
+            if kind == MessageKind::LoggedIn || kind == MessageKind::Error {
+                break kind;
+            }
+        };
+        if reply == MessageKind::LoggedIn {
+            break;
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    assert_eq!(sync(&mut phone, SyncedSettings::default()), uploaded);
+}
+
+#[test]
+fn syncing_before_logging_in_is_refused() {
+    let address = start_server();
+    let mut client = TestClient::connect(&address);
+    client.send(ClientRequest::SyncSettings(SyncedSettings::default()));
+    assert_eq!(client.expect(MessageKind::Error), b"Log in before syncing settings");
+}
@@ -0,0 +1,12 @@
+#![no_main]
+
+// Run with `cargo +nightly fuzz run battle_commands` from immie2d_shared/.
+
+use libfuzzer_sys::fuzz_target;
+
+#[path = "../../tests/common/battle_harness.rs"]
+mod battle_harness;
+
+fuzz_target!(|bytes: &[u8]| {
+    battle_harness::run_command_stream(bytes);
+});
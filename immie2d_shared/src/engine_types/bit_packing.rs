@@ -0,0 +1,166 @@
+use bytes::{BufMut, BytesMut};
+
+/// Bits needed to store every value from 0 to max_value.
+/// ```
+/// use immie2d_shared::engine_types::bit_packing::get_bits_needed;
+///
+/// assert_eq!(get_bits_needed(0), 0);
+/// assert_eq!(get_bits_needed(1), 1);
+/// assert_eq!(get_bits_needed(3), 2);
+/// assert_eq!(get_bits_needed(4), 3);
+/// assert_eq!(get_bits_needed(u64::MAX), 64);
+/// ```
+pub fn get_bits_needed(max_value: u64) -> u32 {
+    return 64 - max_value.leading_zeros();
+}
+
+/// Map signed values to unsigned so small negative values stay small: 0, -1, 1, -2 become 0, 1, 2, 3.
+pub fn zigzag_encode(value: i64) -> u64 {
+    return ((value << 1) ^ (value >> 63)) as u64;
+}
+
+pub fn zigzag_decode(value: u64) -> i64 {
+    return (value >> 1) as i64 ^ -((value & 1) as i64);
+}
+
+/* Writes values in as few bits as they need, least significant bit first. The last byte is padded with zeros. */
+pub struct BitWriter {
+    bytes: BytesMut,
+    bit_length: usize
+}
+
+impl BitWriter {
+    pub fn new() -> BitWriter {
+        return BitWriter::with_buffer(BytesMut::new());
+    }
+
+    /// Write after the existing contents of a buffer, such as one from an EncodeBufferPool.
+    pub fn with_buffer(buffer: BytesMut) -> BitWriter {
+        return BitWriter { bytes: buffer, bit_length: 0 };
+    }
+
+    /// Write the lowest bits of a value.
+    /// Will panic if bits is more than 64.
+    pub fn write_bits(&mut self, value: u64, bits: u32) {
+        assert!(bits <= 64, "Cannot write {} bits at once", bits);
+        for bit in 0..bits {
+            if self.bit_length.is_multiple_of(8) {
+                self.bytes.put_u8(0);
+            }
+            if (value >> bit) & 1 == 1 {
+                *self.bytes.last_mut().unwrap() |= 1 << (self.bit_length % 8);
+            }
+            self.bit_length += 1;
+        }
+    }
+
+    pub fn write_bool(&mut self, value: bool) {
+        self.write_bits(value as u64, 1);
+    }
+
+    /// Write 7 bits at a time with a continuation bit, so small values such as entity ids take 8 bits rather than 32.
+    pub fn write_varint(&mut self, mut value: u64) {
+        loop {
+            self.write_bits(value & 0x7f, 7);
+            value >>= 7;
+            self.write_bool(value != 0);
+            if value == 0 {
+                return;
+            }
+        }
+    }
+
+    /// Write a zigzag encoded varint. See zigzag_encode()
+    pub fn write_signed_varint(&mut self, value: i64) {
+        self.write_varint(zigzag_encode(value));
+    }
+
+    pub fn get_bit_length(&self) -> usize {
+        return self.bit_length;
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        return self.bytes.into();
+    }
+
+    /// The buffer written to, including anything it held before.
+    pub fn into_buffer(self) -> BytesMut {
+        return self.bytes;
+    }
+}
+
+/* Reads values written by a BitWriter. Every read returns None once the bytes run out. */
+pub struct BitReader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl<'a> BitReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> BitReader<'a> {
+        return BitReader { bytes, position: 0 };
+    }
+
+    /// Will panic if bits is more than 64.
+    /// ```
+    /// use immie2d_shared::engine_types::bit_packing::{BitReader, BitWriter};
+    ///
+    /// let mut writer = BitWriter::new();
+    /// writer.write_bits(5, 3);
+    /// writer.write_bool(true);
+    /// writer.write_varint(300);
+    /// writer.write_signed_varint(-2);
+    /// assert_eq!(writer.get_bit_length(), 3 + 1 + 16 + 8);
+    /// let bytes = writer.into_bytes();
+    /// assert_eq!(bytes.len(), 4);
+    ///
+    /// let mut reader = BitReader::new(&bytes);
+    /// assert_eq!(reader.read_bits(3), Some(5));
+    /// assert_eq!(reader.read_bool(), Some(true));
+    /// assert_eq!(reader.read_varint(), Some(300));
+    /// assert_eq!(reader.read_signed_varint(), Some(-2));
+    /// // Only the padding of the last byte is left
+    /// assert_eq!(reader.get_remaining_bits(), 4);
+    /// assert_eq!(reader.read_bits(5), None);
+    /// ```
+    pub fn read_bits(&mut self, bits: u32) -> Option<u64> {
+        assert!(bits <= 64, "Cannot read {} bits at once", bits);
+        if self.get_remaining_bits() < bits as usize {
+            return None;
+        }
+        let mut value = 0;
+        for bit in 0..bits {
+            let byte = self.bytes[self.position / 8];
+            value |= (((byte >> (self.position % 8)) & 1) as u64) << bit;
+            self.position += 1;
+        }
+        return Some(value);
+    }
+
+    pub fn read_bool(&mut self) -> Option<bool> {
+        return self.read_bits(1).map(|bit| bit == 1);
+    }
+
+    /// Read a varint, or None if it is cut off or longer than a u64.
+    pub fn read_varint(&mut self) -> Option<u64> {
+        let mut value: u64 = 0;
+        for group in 0..10 {
+            let bits = self.read_bits(7)?;
+            if group == 9 && bits > 1 {
+                return None;
+            }
+            value |= bits << (group * 7);
+            if !self.read_bool()? {
+                return Some(value);
+            }
+        }
+        return None;
+    }
+
+    pub fn read_signed_varint(&mut self) -> Option<i64> {
+        return self.read_varint().map(zigzag_decode);
+    }
+
+    pub fn get_remaining_bits(&self) -> usize {
+        return self.bytes.len() * 8 - self.position;
+    }
+}
@@ -0,0 +1,82 @@
+use std::sync::Mutex;
+
+use bytes::BytesMut;
+
+/// Capacity buffers of a pool start with, enough for every message of a typical tick.
+pub const DEFAULT_BUFFER_CAPACITY: usize = 4096;
+
+/// Most buffers a pool keeps for reuse. Buffers given back past this are freed.
+pub const DEFAULT_MAX_POOLED_BUFFERS: usize = 64;
+
+/* A network message that can be appended to a buffer. The hot path of a tick encodes every message into a buffer
+taken from an EncodeBufferPool, so encoding doesn't allocate once the buffer has grown to fit a tick. */
+pub trait Encode {
+    /// Append the encoded message to the end of a buffer.
+    fn encode_into(&self, buffer: &mut BytesMut);
+
+    /// Encode the message into a new buffer, for messages off the hot path.
+    fn encode_to_vec(&self) -> Vec<u8> {
+        let mut buffer = BytesMut::new();
+        self.encode_into(&mut buffer);
+        return buffer.into();
+    }
+}
+
+/* Buffers to encode messages into, given back once sent so the next tick reuses their capacity instead of
+allocating. */
+pub struct EncodeBufferPool {
+    buffers: Mutex<Vec<BytesMut>>,
+    buffer_capacity: usize,
+    max_pooled: usize
+}
+
+impl EncodeBufferPool {
+    pub fn new() -> EncodeBufferPool {
+        return EncodeBufferPool::with_limits(DEFAULT_BUFFER_CAPACITY, DEFAULT_MAX_POOLED_BUFFERS);
+    }
+
+    pub fn with_limits(buffer_capacity: usize, max_pooled: usize) -> EncodeBufferPool {
+        return EncodeBufferPool { buffers: Mutex::new(Vec::with_capacity(max_pooled)), buffer_capacity, max_pooled };
+    }
+
+    /// Take an empty buffer, reusing one that was given back if there is any.
+    /// ```
+    /// use immie2d_shared::engine_types::encode_buffer::{EncodeBufferPool, Encode};
+    /// use immie2d_shared::gameplay::battle::event_timeline::TimelineAck;
+    ///
+    /// let pool = EncodeBufferPool::with_limits(64, 1);
+    /// let mut buffer = pool.take();
+    /// TimelineAck { batch: 3 }.encode_into(&mut buffer);
+    /// assert_eq!(&buffer[..], &[3, 0, 0, 0]);
+    /// let capacity = buffer.capacity();
+    ///
+    /// pool.give_back(buffer);
+    /// pool.give_back(pool.take());
+    /// // Past the limit, so it is dropped
+    /// pool.give_back(EncodeBufferPool::new().take());
+    /// assert_eq!(pool.get_pooled_count(), 1);
+    /// let buffer = pool.take();
+    /// assert!(buffer.is_empty());
+    /// assert_eq!(buffer.capacity(), capacity);
+    /// ```
+    pub fn take(&self) -> BytesMut {
+        // The pool is only a cache of empty buffers, so it is still usable after a panic poisoned it
+        return match self.buffers.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).pop() {
+            Some(buffer) => buffer,
+            None => BytesMut::with_capacity(self.buffer_capacity)
+        };
+    }
+
+    /// Give a buffer back to be reused, clearing it but keeping its capacity.
+    pub fn give_back(&self, mut buffer: BytesMut) {
+        buffer.clear();
+        let mut buffers = self.buffers.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if buffers.len() < self.max_pooled {
+            buffers.push(buffer);
+        }
+    }
+
+    pub fn get_pooled_count(&self) -> usize {
+        return self.buffers.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).len();
+    }
+}
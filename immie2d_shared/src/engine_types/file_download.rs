@@ -0,0 +1,72 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::file_transfer::{hash_reader, read_chunk, TransferError, TransferHeader, TransferKind, TransferRequest};
+
+/// Where the bytes of an unfinished download are kept.
+pub fn get_partial_path(destination: &Path) -> PathBuf {
+    let mut path = destination.as_os_str().to_owned();
+    path.push(".part");
+    return PathBuf::from(path);
+}
+
+/// Where the hash of the file an unfinished download came from is kept, so it can be resumed.
+fn get_partial_hash_path(destination: &Path) -> PathBuf {
+    let mut path = destination.as_os_str().to_owned();
+    path.push(".part.sha256");
+    return PathBuf::from(path);
+}
+
+/// How much of a file was already downloaded, and the hash of the file it came from.
+fn get_resume_point(destination: &Path) -> (u64, [u8; 32]) {
+    let hash = fs::read(get_partial_hash_path(destination)).ok().and_then(|bytes| <[u8; 32]>::try_from(bytes.as_slice()).ok());
+    let length = fs::metadata(get_partial_path(destination)).map(|metadata| metadata.len());
+    return match (hash, length) {
+        (Some(hash), Ok(length)) => (length, hash),
+        _ => (0, [0; 32])
+    };
+}
+
+fn discard_partial(destination: &Path) {
+    let _ = fs::remove_file(get_partial_path(destination));
+    let _ = fs::remove_file(get_partial_hash_path(destination));
+}
+
+/// Download a file over a transfer connection, separate from the gameplay connection. See serve_transfer() Chunks are written to a
+/// `.part` file next to the destination as they arrive, so an interrupted download resumes where it stopped on the
+/// next attempt, unless the file changed on the server since. The finished file is checked against the server's hash
+/// before it is moved to the destination. Returns the number of bytes received.
+pub fn download_file<S: Read + Write>(stream: &mut S, kind: TransferKind, name: &str, destination: &Path) -> Result<u64, TransferError> {
+    let (offset, resume_hash) = get_resume_point(destination);
+    TransferRequest { kind, name: name.to_string(), offset, resume_hash }.write_to(stream)?;
+    let header = TransferHeader::read_from(stream)?;
+    let partial_path = get_partial_path(destination);
+    let mut partial = if header.offset == 0 {
+        fs::write(get_partial_hash_path(destination), header.hash)?;
+        File::create(&partial_path)?
+    } else if header.offset == offset {
+        OpenOptions::new().append(true).open(&partial_path)?
+    } else {
+        return Err(TransferError::Invalid(format!("Server resumed from {} instead of {}", header.offset, offset)));
+    };
+    let mut received = 0;
+    while header.offset + received < header.total_size {
+        let chunk = read_chunk(stream)?;
+        received += chunk.len() as u64;
+        if header.offset + received > header.total_size {
+            discard_partial(destination);
+            return Err(TransferError::Invalid("Received more than the size of the file".to_string()));
+        }
+        partial.write_all(&chunk)?;
+    }
+    partial.sync_all()?;
+    drop(partial);
+    if hash_reader(&mut File::open(&partial_path)?)? != header.hash {
+        discard_partial(destination);
+        return Err(TransferError::HashMismatch);
+    }
+    fs::rename(&partial_path, destination)?;
+    let _ = fs::remove_file(get_partial_hash_path(destination));
+    return Ok(received);
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use sha2::{Digest, Sha256};
+
+/// Largest chunk of a file sent in one frame. Chunks are written to disk as they arrive, so at most one chunk is lost
+/// when a transfer is interrupted.
+pub const TRANSFER_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Longest file name that can be requested, in bytes.
+pub const MAX_TRANSFER_NAME_LENGTH: usize = 1024;
+
+/* Why a file transfer failed. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TransferError {
+    Io(String),
+    NotFound,
+    /// A malformed request or header, or a name escaping the directory it is served from.
+    Invalid(String),
+    /// The downloaded file doesn't hash to what the server advertised. The partial download is discarded.
+    HashMismatch
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            TransferError::Io(message) => write!(f, "File transfer failed: {}", message),
+            TransferError::NotFound => write!(f, "The requested file does not exist"),
+            TransferError::Invalid(message) => write!(f, "Invalid file transfer: {}", message),
+            TransferError::HashMismatch => write!(f, "The downloaded file does not match its hash")
+        };
+    }
+}
+
+impl From<io::Error> for TransferError {
+    fn from(err: io::Error) -> TransferError {
+        return TransferError::Io(err.to_string());
+    }
+}
+
+/* Which kind of large payload a transfer is for. Each is served from its own directory. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TransferKind {
+    DataPack,
+    Replay,
+    Map
+}
+
+impl TransferKind {
+    pub fn get_id(self) -> u8 {
+        return match self {
+            TransferKind::DataPack => 0,
+            TransferKind::Replay => 1,
+            TransferKind::Map => 2
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<TransferKind> {
+        return match id {
+            0 => Some(TransferKind::DataPack),
+            1 => Some(TransferKind::Replay),
+            2 => Some(TransferKind::Map),
+            _ => None
+        };
+    }
+}
+
+/* The first thing a client sends on the transfer channel. To resume, the client sends how much it already has and the
+hash of the file it was downloading. If the file changed since, the server starts over from the beginning. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TransferRequest {
+    pub kind: TransferKind,
+    /// Path relative to the directory of the kind, with `/` separators, such as `mymod/species.json`.
+    pub name: String,
+    pub offset: u64,
+    /// Hash of the file the first `offset` bytes came from. Ignored if the offset is 0.
+    pub resume_hash: [u8; 32]
+}
+
+impl TransferRequest {
+    /// A request for a whole file.
+    pub fn new(kind: TransferKind, name: &str) -> TransferRequest {
+        return TransferRequest { kind, name: name.to_string(), offset: 0, resume_hash: [0; 32] };
+    }
+
+    /// Encode as the kind, offset, resume hash, and the length prefixed name.
+    /// ```
+    /// use immie2d_shared::engine_types::file_transfer::{TransferKind, TransferRequest};
+    ///
+    /// let request = TransferRequest { offset: 4096, resume_hash: [3; 32], ..TransferRequest::new(TransferKind::Replay, "battle_17.replay") };
+    /// let mut bytes = Vec::new();
+    /// request.write_to(&mut bytes).unwrap();
+    /// assert_eq!(TransferRequest::read_from(&mut bytes.as_slice()), Ok(request));
+    /// assert!(TransferRequest::read_from(&mut &bytes[..10]).is_err());
+    /// ```
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut bytes = Vec::with_capacity(43 + self.name.len());
+        bytes.push(self.kind.get_id());
+        bytes.extend_from_slice(&self.offset.to_le_bytes());
+        bytes.extend_from_slice(&self.resume_hash);
+        bytes.extend_from_slice(&(self.name.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(self.name.as_bytes());
+        return writer.write_all(&bytes);
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<TransferRequest, TransferError> {
+        let mut fixed = [0; 43];
+        reader.read_exact(&mut fixed)?;
+        let kind = TransferKind::from_id(fixed[0]).ok_or(TransferError::Invalid(format!("Unknown transfer kind {}", fixed[0])))?;
+        let offset = u64::from_le_bytes(fixed[1..9].try_into().unwrap());
+        let resume_hash: [u8; 32] = fixed[9..41].try_into().unwrap();
+        let length = u16::from_le_bytes([fixed[41], fixed[42]]) as usize;
+        if length > MAX_TRANSFER_NAME_LENGTH {
+            return Err(TransferError::Invalid(format!("Name of {} bytes is too long", length)));
+        }
+        let mut name = vec![0; length];
+        reader.read_exact(&mut name)?;
+        let name = String::from_utf8(name).map_err(|_| TransferError::Invalid("Name is not UTF-8".to_string()))?;
+        return Ok(TransferRequest { kind, name, offset, resume_hash });
+    }
+}
+
+/* The server's answer to a request, followed by the chunks of the file from `offset` if it was found. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TransferHeader {
+    pub total_size: u64,
+    pub hash: [u8; 32],
+    /// Where the chunks start. Either the requested offset, or 0 if the transfer can't be resumed.
+    pub offset: u64
+}
+
+impl TransferHeader {
+    /// Encode as a found flag, then the total size, hash and offset if found.
+    /// ```
+    /// use immie2d_shared::engine_types::file_transfer::{TransferHeader, TransferError};
+    ///
+    /// let header = TransferHeader { total_size: 100_000, hash: [9; 32], offset: 65_536 };
+    /// let mut bytes = Vec::new();
+    /// TransferHeader::write_to(Some(&header), &mut bytes).unwrap();
+    /// assert_eq!(TransferHeader::read_from(&mut bytes.as_slice()), Ok(header));
+    ///
+    /// let mut bytes = Vec::new();
+    /// TransferHeader::write_to(None, &mut bytes).unwrap();
+    /// assert_eq!(TransferHeader::read_from(&mut bytes.as_slice()), Err(TransferError::NotFound));
+    /// ```
+    pub fn write_to<W: Write>(header: Option<&TransferHeader>, writer: &mut W) -> io::Result<()> {
+        let Some(header) = header else {
+            return writer.write_all(&[0]);
+        };
+        let mut bytes = Vec::with_capacity(49);
+        bytes.push(1);
+        bytes.extend_from_slice(&header.total_size.to_le_bytes());
+        bytes.extend_from_slice(&header.hash);
+        bytes.extend_from_slice(&header.offset.to_le_bytes());
+        return writer.write_all(&bytes);
+    }
+
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<TransferHeader, TransferError> {
+        let mut found = [0; 1];
+        reader.read_exact(&mut found)?;
+        match found[0] {
+            0 => return Err(TransferError::NotFound),
+            1 => (),
+            flag => return Err(TransferError::Invalid(format!("Unknown header flag {}", flag)))
+        }
+        let mut fixed = [0; 48];
+        reader.read_exact(&mut fixed)?;
+        let total_size = u64::from_le_bytes(fixed[0..8].try_into().unwrap());
+        let hash: [u8; 32] = fixed[8..40].try_into().unwrap();
+        let offset = u64::from_le_bytes(fixed[40..48].try_into().unwrap());
+        if offset > total_size {
+            return Err(TransferError::Invalid(format!("Offset {} is past the end of the file", offset)));
+        }
+        return Ok(TransferHeader { total_size, hash, offset });
+    }
+}
+
+/// Write a chunk frame, as its length followed by its bytes. Will panic if the chunk is larger than
+/// TRANSFER_CHUNK_SIZE.
+pub fn write_chunk<W: Write>(chunk: &[u8], writer: &mut W) -> io::Result<()> {
+    assert!(chunk.len() <= TRANSFER_CHUNK_SIZE, "Chunk of {} bytes is larger than the chunk size", chunk.len());
+    writer.write_all(&(chunk.len() as u32).to_le_bytes())?;
+    return writer.write_all(chunk);
+}
+
+/// Read a chunk frame written by write_chunk().
+/// ```
+/// use immie2d_shared::engine_types::file_transfer::{read_chunk, write_chunk};
+///
+/// let mut bytes = Vec::new();
+/// write_chunk(b"immie", &mut bytes).unwrap();
+/// assert_eq!(read_chunk(&mut bytes.as_slice()).unwrap(), b"immie".to_vec());
+/// assert!(read_chunk(&mut &bytes[..6]).is_err());
+/// ```
+pub fn read_chunk<R: Read>(reader: &mut R) -> Result<Vec<u8>, TransferError> {
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+    let length = u32::from_le_bytes(length) as usize;
+    if length == 0 || length > TRANSFER_CHUNK_SIZE {
+        return Err(TransferError::Invalid(format!("Chunk of {} bytes", length)));
+    }
+    let mut chunk = vec![0; length];
+    reader.read_exact(&mut chunk)?;
+    return Ok(chunk);
+}
+
+/// SHA-256 of everything a reader produces, read a chunk at a time.
+pub fn hash_reader<R: Read>(reader: &mut R) -> io::Result<[u8; 32]> {
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0; TRANSFER_CHUNK_SIZE];
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hasher.finalize().into());
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
+
+/// Whether a requested name stays inside the directory it is served from: relative, `/` separated, and without
+/// empty, `.` or `..` components.
+/// ```
+/// use immie2d_shared::engine_types::file_transfer::is_valid_transfer_name;
+///
+/// assert!(is_valid_transfer_name("mymod/species.json"));
+/// assert!(!is_valid_transfer_name("../server_data/bans.list"));
+/// assert!(!is_valid_transfer_name("/etc/passwd"));
+/// assert!(!is_valid_transfer_name("mymod\\..\\secret"));
+/// assert!(!is_valid_transfer_name(""));
+/// ```
+pub fn is_valid_transfer_name(name: &str) -> bool {
+    return !name.is_empty() && !name.contains('\\') && !name.contains(':') && name.split('/').all(|part| !part.is_empty() && part != "." && part != "..");
+}
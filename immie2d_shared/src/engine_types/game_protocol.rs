@@ -0,0 +1,289 @@
+use std::fmt::Write;
+
+use crate::gameplay::immie::immie_release::ImmieLocation;
+use crate::gameplay::synced_settings::SyncedSettings;
+use crate::world::tile_position::{Direction, TilePosition};
+
+/// Encode bytes as lowercase hex, two characters a byte.
+/// ```
+/// use immie2d_shared::engine_types::game_protocol::{from_hex, to_hex};
+///
+/// assert_eq!(to_hex(&[0, 15, 255]), "000fff");
+/// assert_eq!(from_hex("000fFF"), Some(vec![0, 15, 255]));
+/// assert_eq!(from_hex("abc"), None);
+/// assert_eq!(from_hex("zz"), None);
+/// ```
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        let _ = write!(hex, "{:02x}", byte);
+    }
+    return hex;
+}
+
+/// Decode hex from to_hex(), or None if it isn't whole bytes of hex digits.
+pub fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+    return (0..hex.len()).step_by(2).map(|index| u8::from_str_radix(&hex[index..index + 2], 16).ok()).collect();
+}
+
+/* A request a client sends on the game connection, as a line of a keyword and its arguments, so a connection can be
+driven by hand from a terminal. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ClientRequest {
+    /// Passwords are always the rest of the line, so they may hold spaces.
+    CreateAccount { username: String, email: Option<String>, password: String },
+    /// The code is for accounts with two factor authentication.
+    Login { username: String, code: Option<String>, password: String },
+    /// Start setting up two factor authentication, answered with the secret and recovery codes.
+    BeginTwoFactor { username: String, password: String },
+    /// Finish setting up two factor authentication with a code from the authenticator.
+    ConfirmTwoFactor { username: String, code: String, password: String },
+    /// The client's synced settings, sent after logging in and answered with the settings it should use. The
+    /// settings are sent as hex, after whether they were changed on the device. See SyncedSettings::merge()
+    SyncSettings(SyncedSettings),
+    /// Step once from the tile the client predicts the player is on, answered with a move result.
+    Walk { from: TilePosition, direction: Direction },
+    /// Travel to an unlocked fast travel point, which is the rest of the line, answered with a fast travel event.
+    FastTravel { point: String },
+    /// Close the dialogue of the cutscene the player is in, so it can go on once everyone in it has.
+    CloseDialogue,
+    /// Redeem a gift code, which is the rest of the line, answered with the gift received.
+    RedeemCode { code: String },
+    /// Queue for a single battle with the party, answered once the player is queued.
+    QueueBattle,
+    /// Offer an Immie to another player by name, traded once they offer one back.
+    OfferTrade { partner: String, location: ImmieLocation }
+}
+
+/// Split the arguments of a line into its first words and the rest of the line, or None if there are too few.
+fn split_arguments<const N: usize>(arguments: &str) -> Option<[&str; N]> {
+    let mut parts = arguments.splitn(N, ' ');
+    let split: Vec<&str> = (0..N).map_while(|_| parts.next()).collect();
+    return split.try_into().ok();
+}
+
+/// An Immie's location as two arguments, `party` or `box` and then the slot.
+fn parse_location(kind: &str, slot: &str) -> Option<ImmieLocation> {
+    let slot = slot.parse::<usize>().ok()?;
+    return match kind {
+        "party" => Some(ImmieLocation::Party(slot)),
+        "box" => Some(ImmieLocation::Box(slot)),
+        _ => None
+    };
+}
+
+/// An optional argument, which is `-` when left out.
+fn get_optional(argument: &str) -> Option<String> {
+    return (argument != "-").then(|| argument.to_string());
+}
+
+impl ClientRequest {
+    /// Encode as a newline terminated line. Optional arguments that are left out have `-` in their place.
+    /// ```
+    /// use immie2d_shared::engine_types::game_protocol::ClientRequest;
+    /// use immie2d_shared::gameplay::immie::immie_release::ImmieLocation;
+    /// use immie2d_shared::gameplay::synced_settings::SyncedSettings;
+    /// use immie2d_shared::world::tile_position::{Direction, TilePosition};
+    ///
+    /// let login = ClientRequest::Login { username: "misty".to_string(), code: None, password: "star mie 123".to_string() };
+    /// assert_eq!(login.to_line(), "login misty - star mie 123\n");
+    /// let requests = [
+    ///     login,
+    ///     ClientRequest::Login { username: "misty".to_string(), code: Some("287082".to_string()), password: "starmie123".to_string() },
+    ///     ClientRequest::CreateAccount { username: "brock".to_string(), email: Some("brock@example.com".to_string()), password: "onix12345".to_string() },
+    ///     ClientRequest::BeginTwoFactor { username: "brock".to_string(), password: "onix12345".to_string() },
+    ///     ClientRequest::ConfirmTwoFactor { username: "brock".to_string(), code: "287082".to_string(), password: "onix12345".to_string() },
+    ///     ClientRequest::SyncSettings(SyncedSettings { revision: 3, is_modified: true, ..SyncedSettings::default() }),
+    ///     ClientRequest::Walk { from: TilePosition::new(-3, 12), direction: Direction::Left },
+    ///     ClientRequest::FastTravel { point: "ember town".to_string() },
+    ///     ClientRequest::CloseDialogue,
+    ///     ClientRequest::RedeemCode { code: "SUMMER-FEST".to_string() },
+    ///     ClientRequest::QueueBattle,
+    ///     ClientRequest::OfferTrade { partner: "brock".to_string(), location: ImmieLocation::Box(12) }
+    /// ];
+    /// for request in requests {
+    ///     assert_eq!(ClientRequest::parse(&request.to_line()), Ok(request));
+    /// }
+    ///
+    /// assert!(ClientRequest::parse("login misty").is_err());
+    /// assert!(ClientRequest::parse("sync_settings modified 00").is_err());
+    /// assert!(ClientRequest::parse("walk 1 2 sideways").is_err());
+    /// assert!(ClientRequest::parse("fast_travel").is_err());
+    /// assert!(ClientRequest::parse("redeem_code").is_err());
+    /// assert!(ClientRequest::parse("offer_trade brock pocket 1").is_err());
+    /// assert!(ClientRequest::parse("dance").is_err());
+    /// ```
+    pub fn to_line(&self) -> String {
+        let optional = |argument: &Option<String>| argument.clone().unwrap_or("-".to_string());
+        return match self {
+            ClientRequest::CreateAccount { username, email, password } => format!("create_account {} {} {}\n", username, optional(email), password),
+            ClientRequest::Login { username, code, password } => format!("login {} {} {}\n", username, optional(code), password),
+            ClientRequest::BeginTwoFactor { username, password } => format!("begin_two_factor {} {}\n", username, password),
+            ClientRequest::ConfirmTwoFactor { username, code, password } => format!("confirm_two_factor {} {} {}\n", username, code, password),
+            ClientRequest::SyncSettings(settings) => {
+                format!("sync_settings {} {}\n", if settings.is_modified { "modified" } else { "unchanged" }, to_hex(&settings.to_bytes()))
+            },
+            ClientRequest::Walk { from, direction } => format!("walk {} {} {}\n", from.x, from.y, direction.get_name()),
+            ClientRequest::FastTravel { point } => format!("fast_travel {}\n", point),
+            ClientRequest::CloseDialogue => "close_dialogue\n".to_string(),
+            ClientRequest::RedeemCode { code } => format!("redeem_code {}\n", code),
+            ClientRequest::QueueBattle => "queue_battle\n".to_string(),
+            ClientRequest::OfferTrade { partner, location: ImmieLocation::Party(slot) } => format!("offer_trade {} party {}\n", partner, slot),
+            ClientRequest::OfferTrade { partner, location: ImmieLocation::Box(slot) } => format!("offer_trade {} box {}\n", partner, slot)
+        };
+    }
+
+    /// Parse a line from to_line(), with or without its line ending.
+    pub fn parse(line: &str) -> Result<ClientRequest, String> {
+        let line = line.strip_suffix('\n').unwrap_or(line);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let (keyword, arguments) = line.split_once(' ').unwrap_or((line, ""));
+        let usage = |usage: &str| format!("Usage: {} {}", keyword, usage);
+        return match keyword {
+            "create_account" => {
+                let [username, email, password] = split_arguments(arguments).ok_or(usage("<username> <email or -> <password>"))?;
+                Ok(ClientRequest::CreateAccount { username: username.to_string(), email: get_optional(email), password: password.to_string() })
+            },
+            "login" => {
+                let [username, code, password] = split_arguments(arguments).ok_or(usage("<username> <code or -> <password>"))?;
+                Ok(ClientRequest::Login { username: username.to_string(), code: get_optional(code), password: password.to_string() })
+            },
+            "begin_two_factor" => {
+                let [username, password] = split_arguments(arguments).ok_or(usage("<username> <password>"))?;
+                Ok(ClientRequest::BeginTwoFactor { username: username.to_string(), password: password.to_string() })
+            },
+            "confirm_two_factor" => {
+                let [username, code, password] = split_arguments(arguments).ok_or(usage("<username> <code> <password>"))?;
+                Ok(ClientRequest::ConfirmTwoFactor { username: username.to_string(), code: code.to_string(), password: password.to_string() })
+            },
+            "sync_settings" => {
+                let [modified, hex] = split_arguments(arguments).ok_or(usage("<modified or unchanged> <settings hex>"))?;
+                let is_modified = match modified {
+                    "modified" => true,
+                    "unchanged" => false,
+                    _ => return Err(usage("<modified or unchanged> <settings hex>"))
+                };
+                let settings = from_hex(hex).and_then(|bytes| SyncedSettings::from_bytes(&bytes)).ok_or("Invalid settings".to_string())?;
+                Ok(ClientRequest::SyncSettings(SyncedSettings { is_modified, ..settings }))
+            },
+            "walk" => {
+                let walk_usage = usage("<x> <y> <up, down, left or right>");
+                let [x, y, direction] = split_arguments(arguments).ok_or(walk_usage.clone())?;
+                let (Ok(x), Ok(y), Some(direction)) = (x.parse::<i32>(), y.parse::<i32>(), Direction::from_name(direction)) else {
+                    return Err(walk_usage);
+                };
+                Ok(ClientRequest::Walk { from: TilePosition::new(x, y), direction })
+            },
+            "fast_travel" if !arguments.is_empty() => Ok(ClientRequest::FastTravel { point: arguments.to_string() }),
+            "fast_travel" => Err(usage("<point>")),
+            "close_dialogue" => Ok(ClientRequest::CloseDialogue),
+            "redeem_code" if !arguments.is_empty() => Ok(ClientRequest::RedeemCode { code: arguments.to_string() }),
+            "redeem_code" => Err(usage("<code>")),
+            "queue_battle" => Ok(ClientRequest::QueueBattle),
+            "offer_trade" => {
+                let trade_usage = usage("<partner> <party or box> <slot>");
+                let [partner, kind, slot] = split_arguments(arguments).ok_or(trade_usage.clone())?;
+                let location = parse_location(kind, slot).ok_or(trade_usage)?;
+                Ok(ClientRequest::OfferTrade { partner: partner.to_string(), location })
+            },
+            _ => Err(format!("Unknown request [{}]", keyword))
+        };
+    }
+}
+
+/* What a line from the server carries. Each line is the keyword of its kind and its payload as hex. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MessageKind {
+    /// The player id of a new account, as 8 little endian bytes.
+    AccountCreated,
+    /// The player id of the account logged in to, as 8 little endian bytes.
+    LoggedIn,
+    /// The base32 secret, its otpauth URI and then each recovery code, as lines of UTF-8 text. Shown to the player once.
+    TwoFactorSetup,
+    /// Two factor authentication was confirmed, with no payload.
+    TwoFactorEnabled,
+    /// Why a request was refused, as UTF-8 text.
+    Error,
+    /// The server tore down the connection or battle after an internal error, with no payload.
+    InternalError,
+    /// The world simulation was paused, stepped, resumed or changed tick rate. See SimulationStatus
+    SimulationStatus,
+    /// The data packs the server has enabled, sent on connect. See PackAdvertisement
+    PackAdvertisement,
+    /// The synced settings the client should use, in reply to ClientRequest::SyncSettings. See SyncedSettings::to_bytes()
+    SyncedSettings,
+    /// The result of a step the player took, or where they were placed after logging in. See MoveResult
+    MoveResult,
+    /// A fast travel point was unlocked, or the answer to ClientRequest::FastTravel. See FastTravelEvent
+    FastTravel,
+    /// A cue of a cutscene the player is in. See CutsceneCueMessage
+    Cutscene,
+    /// A wild Immie appeared as the player walked through an encounter zone. See WildEncounter::to_bytes()
+    WildEncounter,
+    /// The name of a gift distribution the player received, at login or for a redeemed code, as UTF-8 text.
+    GiftReceived,
+    /// The player was queued for a battle, with no payload.
+    BattleQueued,
+    /// A trade went through. The species of the Immie received, which was put in the box, as UTF-8 text.
+    Traded
+}
+
+const MESSAGE_KINDS: [MessageKind; 16] = [
+    MessageKind::AccountCreated, MessageKind::LoggedIn, MessageKind::TwoFactorSetup, MessageKind::TwoFactorEnabled, MessageKind::Error,
+    MessageKind::InternalError, MessageKind::SimulationStatus, MessageKind::PackAdvertisement, MessageKind::SyncedSettings, MessageKind::MoveResult,
+    MessageKind::FastTravel, MessageKind::Cutscene, MessageKind::WildEncounter, MessageKind::GiftReceived,
+    MessageKind::BattleQueued, MessageKind::Traded
+];
+
+impl MessageKind {
+    pub fn get_keyword(self) -> &'static str {
+        return match self {
+            MessageKind::AccountCreated => "account_created",
+            MessageKind::LoggedIn => "logged_in",
+            MessageKind::TwoFactorSetup => "two_factor_setup",
+            MessageKind::TwoFactorEnabled => "two_factor_enabled",
+            MessageKind::Error => "error",
+            MessageKind::InternalError => "internal_error",
+            MessageKind::SimulationStatus => "simulation_status",
+            MessageKind::PackAdvertisement => "pack_advertisement",
+            MessageKind::SyncedSettings => "synced_settings",
+            MessageKind::MoveResult => "move_result",
+            MessageKind::FastTravel => "fast_travel",
+            MessageKind::Cutscene => "cutscene",
+            MessageKind::WildEncounter => "wild_encounter",
+            MessageKind::GiftReceived => "gift_received",
+            MessageKind::BattleQueued => "battle_queued",
+            MessageKind::Traded => "traded"
+        };
+    }
+
+    pub fn from_keyword(keyword: &str) -> Option<MessageKind> {
+        return MESSAGE_KINDS.iter().copied().find(|kind| kind.get_keyword() == keyword);
+    }
+}
+
+/// Encode a message from the server as a newline terminated line.
+/// ```
+/// use immie2d_shared::engine_types::game_protocol::{decode_message_line, encode_message_line, MessageKind};
+///
+/// let line = encode_message_line(MessageKind::Error, b"Log in first");
+/// assert_eq!(String::from_utf8(line.clone()).unwrap(), "error 4c6f6720696e206669727374\n");
+/// assert_eq!(decode_message_line(std::str::from_utf8(&line).unwrap()), Some((MessageKind::Error, b"Log in first".to_vec())));
+/// assert_eq!(decode_message_line("internal_error\n"), Some((MessageKind::InternalError, Vec::new())));
+/// assert_eq!(decode_message_line("shout 00"), None);
+/// assert_eq!(decode_message_line("error 0"), None);
+/// ```
+pub fn encode_message_line(kind: MessageKind, payload: &[u8]) -> Vec<u8> {
+    return format!("{} {}\n", kind.get_keyword(), to_hex(payload)).into_bytes();
+}
+
+/// Decode a line from encode_message_line(), with or without its line ending. None if the kind is unknown or the
+/// payload isn't hex.
+pub fn decode_message_line(line: &str) -> Option<(MessageKind, Vec<u8>)> {
+    let line = line.trim_end_matches(['\r', '\n']);
+    let (keyword, payload) = line.split_once(' ').unwrap_or((line, ""));
+    return Some((MessageKind::from_keyword(keyword)?, from_hex(payload)?));
+}
@@ -1,30 +1,18 @@
-use std::{collections::HashMap , fmt};
+use std::fmt;
 
 use lazy_static::lazy_static;
-use std::sync::Mutex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-struct GlobalStringMaps {
-    map: HashMap<String, u32>,
-    vec: Vec<String>,
-    next_id: u32
-}
+use super::string_interner::{InternedEntry, InternerWarningHook, StringInterner};
 
-lazy_static! {
-    static ref GLOBAL_STRING_MAP: Mutex<GlobalStringMaps> = {
-        let mut maps = GlobalStringMaps {
-            map: HashMap::new(),
-            vec: Vec::new(),
-            next_id: 0
-        };
-        maps.map.insert("".to_string(), 0);
-        maps.next_id = 1;
-        maps.vec.push("".to_string());
-        Mutex::new(maps)
-    };
+/// Number of shards of the global string interner. See StringInterner
+pub const GLOBAL_STRING_SHARD_COUNT: usize = 16;
 
+lazy_static! {
+    static ref GLOBAL_STRING_MAP: StringInterner = StringInterner::new(GLOBAL_STRING_SHARD_COUNT);
 }
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct GlobalString {
     string_id: u32 
 }
@@ -49,20 +37,10 @@ impl GlobalString {
     /// let gstr = GlobalString::new(&"hello world!".to_string());
     /// assert_eq!(gstr.to_string(), "hello world!".to_string());
     /// ```
+    #[track_caller]
     pub fn new(in_string: &String) -> GlobalString {
-        //println!("Adding GlobalString {}", in_string);
-        let mut maps = GLOBAL_STRING_MAP.lock().unwrap();
-        let next_id = maps.next_id;
-        let exists = maps.map.insert(in_string.clone(), next_id.clone());
-        if exists.is_some() { // If the value already exists in the map, just use the existing id
-            return GlobalString {
-                string_id: exists.unwrap()
-            };
-        }
-        maps.next_id += 1;
-        maps.vec.push(in_string.clone());
         return GlobalString {
-            string_id: next_id
+            string_id: GLOBAL_STRING_MAP.intern(in_string)
         };
     }
 
@@ -74,14 +52,10 @@ impl GlobalString {
     /// assert_eq!(gstr.to_string(), "".to_string());
     /// ```
     pub fn new_if_exists(in_string: &String) -> GlobalString {
-        let maps = GLOBAL_STRING_MAP.lock().unwrap();
-        let exists: Option<&u32> = maps.map.get(in_string);
-        if exists.is_none() {
-            return GlobalString::default();
-        }
-        return GlobalString {
-            string_id: exists.unwrap().clone()
-        };  
+        return match GLOBAL_STRING_MAP.find(in_string) {
+            Some(string_id) => GlobalString { string_id },
+            None => GlobalString::default()
+        };
     }
 
     /// Gets an copy to the String of the id held by GlobalString.
@@ -92,10 +66,85 @@ impl GlobalString {
     /// # assert_eq!(ref_str, "hello world!".to_string());
     /// ```
     pub fn to_string(&self) -> String {
-        let maps = GLOBAL_STRING_MAP.lock().unwrap();
-        let as_string = &maps.vec[self.string_id as usize];
-        return as_string.clone();
+        return GLOBAL_STRING_MAP.resolve(self.string_id);
     }
+
+    /// Call a function with the string without copying it. The function must not create or read GlobalStrings.
+    /// See StringInterner::resolve_with()
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// let gstr = GlobalString::new(&"hello world!".to_string());
+    /// assert_eq!(gstr.with_str(|string| string.len()), 12);
+    /// ```
+    pub fn with_str<R, F: FnOnce(&str) -> R>(&self, function: F) -> R {
+        return GLOBAL_STRING_MAP.resolve_with(self.string_id, function);
+    }
+
+    /// Total number of strings ever interned as a GlobalString, including the empty string. Strings are never freed,
+    /// so a count that keeps growing on a long running server is a leak.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// let count = GlobalString::get_interned_count();
+    /// GlobalString::new(&"a string only this doctest interns".to_string());
+    /// assert!(GlobalString::get_interned_count() > count);
+    /// ```
+    pub fn get_interned_count() -> usize {
+        return GLOBAL_STRING_MAP.get_count();
+    }
+
+    /// Estimate of the bytes used by every interned GlobalString. See StringInterner::get_memory_usage()
+    pub fn get_memory_usage() -> usize {
+        return GLOBAL_STRING_MAP.get_memory_usage();
+    }
+
+    /// Every interned GlobalString, with where it was first created in debug builds.
+    pub fn get_interned_entries() -> Vec<InternedEntry> {
+        return GLOBAL_STRING_MAP.get_entries();
+    }
+
+    /// Readable dump of the whole table for tracking down leaks. Lists how many strings each origin created, most
+    /// first, followed by every string. Origins are only known in debug builds.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// GlobalString::new(&"dumped string".to_string());
+    /// let dump = GlobalString::dump_registry();
+    /// let line = dump.lines().find(|line| line.contains("\"dumped string\"")).unwrap();
+    /// assert_eq!(line.ends_with("from unknown"), !cfg!(debug_assertions));
+    /// ```
+    pub fn dump_registry() -> String {
+        let entries = GlobalString::get_interned_entries();
+        let mut origins: Vec<(String, usize)> = Vec::new();
+        for entry in entries.iter() {
+            let origin = get_origin_name(entry);
+            match origins.iter_mut().find(|(name, _)| *name == origin) {
+                Some((_, count)) => *count += 1,
+                None => origins.push((origin, 1))
+            }
+        }
+        origins.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+        let mut out = format!("{} interned strings, about {} bytes\n", entries.len(), GlobalString::get_memory_usage());
+        for (origin, count) in origins.iter() {
+            out.push_str(&format!("{:>8} from {}\n", count, origin));
+        }
+        for entry in entries.iter() {
+            out.push_str(&format!("{:>8} {:?} from {}\n", entry.id, entry.string, get_origin_name(entry)));
+        }
+        return out;
+    }
+
+    /// Call a hook once each time the number of interned strings reaches one of the thresholds. See
+    /// StringInterner::set_warning_hook()
+    pub fn set_warning_hook(thresholds: Vec<usize>, hook: InternerWarningHook) {
+        GLOBAL_STRING_MAP.set_warning_hook(thresholds, hook);
+    }
+}
+
+fn get_origin_name(entry: &InternedEntry) -> String {
+    return match entry.origin {
+        Some(origin) => format!("{}:{}", origin.file(), origin.line()),
+        None => "unknown".to_string()
+    };
 }
 
 impl fmt::Debug for GlobalString {
@@ -110,3 +159,22 @@ impl fmt::Display for GlobalString {
     }
 }
 
+
+// Serialized as the string itself, since ids are only meaningful to the process that interned them.
+impl Serialize for GlobalString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return self.with_str(|string| serializer.serialize_str(string));
+    }
+}
+
+// Only resolves strings that are already interned, such as names loaded from game data. Interned strings are never
+// freed, so interning whatever a peer sends would let it grow the map without bound.
+impl<'de> Deserialize<'de> for GlobalString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<GlobalString, D::Error> {
+        let string = String::deserialize(deserializer)?;
+        return match GLOBAL_STRING_MAP.find(&string) {
+            Some(string_id) => Ok(GlobalString { string_id }),
+            None => Err(serde::de::Error::custom(format!("unknown string {:?}", string)))
+        };
+    }
+}
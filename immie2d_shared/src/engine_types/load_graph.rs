@@ -0,0 +1,183 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::thread;
+
+/* How a load task ended. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LoadOutcome {
+    Loaded,
+    Failed,
+    /// Never ran, because a task it depends on failed or was skipped.
+    Skipped
+}
+
+/* Reported as each task of a load graph ends, in the order they end. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LoadProgress<'a> {
+    pub task: &'a str,
+    pub outcome: LoadOutcome,
+    /// Tasks that have ended, including this one.
+    pub completed: usize,
+    pub total: usize
+}
+
+/* Why a task of a load graph didn't load. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LoadError {
+    Failed { task: String, message: String },
+    /// Includes the task that failed or was skipped before it.
+    Skipped { task: String, dependency: String }
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            LoadError::Failed { task, message } => write!(f, "Failed to load {}: {}", task, message),
+            LoadError::Skipped { task, dependency } => write!(f, "Skipped loading {} because {} did not load", task, dependency)
+        };
+    }
+}
+
+type LoadFn<'a, C> = Box<dyn FnOnce(&C) -> Result<(), String> + Send + 'a>;
+
+/* Tasks that load data in parallel, each starting as soon as every task it depends on has loaded. Tasks share a
+context, such as a struct of mutexes they store what they load in. Every task that can run does, so one bad file
+reports everything wrong with the data rather than only the first problem. */
+pub struct LoadGraph<'a, C> {
+    names: Vec<String>,
+    dependencies: Vec<Vec<usize>>,
+    runs: Vec<Option<LoadFn<'a, C>>>,
+    indices: HashMap<String, usize>,
+    max_threads: usize
+}
+
+impl<'a, C: Sync> LoadGraph<'a, C> {
+    /// Runs as many tasks at once as there are cores.
+    pub fn new() -> LoadGraph<'a, C> {
+        let max_threads = thread::available_parallelism().map(|threads| threads.get()).unwrap_or(1);
+        return LoadGraph { names: Vec::new(), dependencies: Vec::new(), runs: Vec::new(), indices: HashMap::new(), max_threads };
+    }
+
+    /// Limit how many tasks run at once. 0 is treated as 1.
+    pub fn with_max_threads(mut self, max_threads: usize) -> LoadGraph<'a, C> {
+        self.max_threads = max_threads.max(1);
+        return self;
+    }
+
+    /// Add a task that runs once every task in dependencies has loaded. Dependencies must be added first, so the
+    /// graph can't have cycles.
+    /// Will panic if a task with the same name was already added, or a dependency hasn't been added.
+    pub fn add_task(&mut self, name: &str, dependencies: &[&str], run: impl FnOnce(&C) -> Result<(), String> + Send + 'a) {
+        assert!(!self.indices.contains_key(name), "Load task [{}] was added twice", name);
+        let dependencies = dependencies.iter()
+            .map(|dependency| *self.indices.get(*dependency).unwrap_or_else(|| panic!("Load task [{}] depends on [{}], which hasn't been added", name, dependency)))
+            .collect();
+        self.indices.insert(name.to_string(), self.names.len());
+        self.names.push(name.to_string());
+        self.dependencies.push(dependencies);
+        self.runs.push(Some(Box::new(run)));
+    }
+
+    pub fn get_task_count(&self) -> usize {
+        return self.names.len();
+    }
+
+    /// Run every task, calling progress on this thread as each one ends. A task that panics fails rather than
+    /// taking down the load. Returns every error once nothing else can run.
+    /// ```
+    /// use std::sync::Mutex;
+    /// use immie2d_shared::engine_types::load_graph::{LoadError, LoadGraph, LoadOutcome};
+    ///
+    /// let loaded: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+    /// let mut graph = LoadGraph::new();
+    /// graph.add_task("abilities", &[], |loaded: &Mutex<Vec<&str>>| { loaded.lock().unwrap().push("abilities"); return Ok(()); });
+    /// graph.add_task("species", &[], |loaded: &Mutex<Vec<&str>>| { loaded.lock().unwrap().push("species"); return Ok(()); });
+    /// graph.add_task("learnsets", &["abilities", "species"], |loaded: &Mutex<Vec<&str>>| {
+    ///     // Runs after both of its dependencies
+    ///     assert_eq!(loaded.lock().unwrap().len(), 2);
+    ///     return Ok(());
+    /// });
+    /// graph.add_task("maps", &[], |_: &Mutex<Vec<&str>>| Err("bad tile".to_string()));
+    /// graph.add_task("encounters", &["maps"], |_: &Mutex<Vec<&str>>| Ok(()));
+    ///
+    /// let mut reports = Vec::new();
+    /// let errors = graph.run(&loaded, |progress| reports.push((progress.task.to_string(), progress.outcome, progress.completed))).unwrap_err();
+    /// assert_eq!(reports.len(), 5);
+    /// assert_eq!(reports.last().unwrap().2, 5);
+    /// assert!(reports.iter().any(|report| report.0 == "learnsets" && report.1 == LoadOutcome::Loaded));
+    /// assert!(errors.contains(&LoadError::Failed { task: "maps".to_string(), message: "bad tile".to_string() }));
+    /// assert!(errors.contains(&LoadError::Skipped { task: "encounters".to_string(), dependency: "maps".to_string() }));
+    /// assert_eq!(errors.len(), 2);
+    /// ```
+    pub fn run(mut self, context: &C, mut progress: impl FnMut(LoadProgress)) -> Result<(), Vec<LoadError>> {
+        let total = self.names.len();
+        let mut waiting_on: Vec<usize> = self.dependencies.iter().map(|dependencies| dependencies.len()).collect();
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); total];
+        for (task, dependencies) in self.dependencies.iter().enumerate() {
+            for dependency in dependencies.iter() {
+                dependents[*dependency].push(task);
+            }
+        }
+        let mut ready: VecDeque<usize> = (0..total).filter(|task| waiting_on[*task] == 0).collect();
+        let mut ended = vec![false; total];
+        let mut errors = Vec::new();
+        let mut completed = 0;
+        let names = &self.names;
+        thread::scope(|scope| {
+            let (sender, receiver) = mpsc::channel();
+            let mut running = 0;
+            loop {
+                while running < self.max_threads && !ready.is_empty() {
+                    let task = ready.pop_front().unwrap();
+                    let run = self.runs[task].take().expect("Load task was started twice");
+                    let sender = sender.clone();
+                    scope.spawn(move || {
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| run(context))).unwrap_or(Err("Panicked while loading".to_string()));
+                        let _ = sender.send((task, result));
+                    });
+                    running += 1;
+                }
+                if running == 0 {
+                    break;
+                }
+                let (task, result) = receiver.recv().expect("Load task ended without reporting");
+                running -= 1;
+                ended[task] = true;
+                completed += 1;
+                match result {
+                    Ok(()) => {
+                        progress(LoadProgress { task: &names[task], outcome: LoadOutcome::Loaded, completed, total });
+                        for dependent in dependents[task].iter() {
+                            waiting_on[*dependent] -= 1;
+                            if waiting_on[*dependent] == 0 && !ended[*dependent] {
+                                ready.push_back(*dependent);
+                            }
+                        }
+                    },
+                    Err(message) => {
+                        progress(LoadProgress { task: &names[task], outcome: LoadOutcome::Failed, completed, total });
+                        errors.push(LoadError::Failed { task: names[task].clone(), message });
+                        // Skip everything downstream, naming the task each was waiting on
+                        let mut skipping: Vec<(usize, usize)> = dependents[task].iter().map(|dependent| (*dependent, task)).collect();
+                        while let Some((skipped, dependency)) = skipping.pop() {
+                            if ended[skipped] {
+                                continue;
+                            }
+                            ended[skipped] = true;
+                            completed += 1;
+                            progress(LoadProgress { task: &names[skipped], outcome: LoadOutcome::Skipped, completed, total });
+                            errors.push(LoadError::Skipped { task: names[skipped].clone(), dependency: names[dependency].clone() });
+                            skipping.extend(dependents[skipped].iter().map(|dependent| (*dependent, skipped)));
+                        }
+                    }
+                }
+            }
+        });
+        if errors.is_empty() {
+            return Ok(());
+        }
+        return Err(errors);
+    }
+}
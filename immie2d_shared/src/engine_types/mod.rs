@@ -1 +1,11 @@
-pub mod global_string;
\ No newline at end of file
+pub mod global_string;
+pub use immie2d_core::game_rng;
+pub mod string_interner;
+pub mod time_sync;
+pub mod file_transfer;
+pub mod file_download;
+pub mod load_graph;
+pub mod bit_packing;
+pub mod encode_buffer;
+pub mod weighted_table;
+pub mod game_protocol;
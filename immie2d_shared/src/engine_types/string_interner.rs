@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+use std::panic::Location;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct InternerShard {
+    map: HashMap<String, u32>,
+    vec: Vec<String>,
+    /// Where each string was first interned, by index. Only tracked in debug builds.
+    #[cfg(debug_assertions)]
+    origins: Vec<Option<&'static Location<'static>>>
+}
+
+/// Estimated bytes per interned string on top of its characters: a map entry and its id, and a vec entry.
+const ENTRY_OVERHEAD_BYTES: usize = 2 * size_of::<String>() + size_of::<u32>();
+
+/* Sent to the warning hook when an interner grows past one of its thresholds. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct InternerWarning {
+    /// The threshold that was reached.
+    pub count: usize,
+    pub memory_bytes: usize
+}
+
+/* An interned string, for dumping the whole table. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct InternedEntry {
+    pub id: u32,
+    pub string: String,
+    /// Where the string was first interned. Always None in release builds.
+    pub origin: Option<&'static Location<'static>>
+}
+
+/// Called when an interner's count reaches a threshold. See StringInterner::set_warning_hook()
+pub type InternerWarningHook = Box<dyn Fn(InternerWarning) + Send + Sync>;
+
+struct WarningThresholds {
+    thresholds: Vec<usize>,
+    hook: Arc<dyn Fn(InternerWarning) + Send + Sync>
+}
+
+/* Thread safe string interner split into shards selected by string hash, so that concurrent interning of
+different strings rarely contends on the same lock. An id stores its shard in the low bits and its index
+within the shard in the remaining bits. The empty string is always id 0. */
+pub struct StringInterner {
+    shards: Vec<Mutex<InternerShard>>,
+    shard_bits: u32,
+    count: AtomicUsize,
+    string_bytes: AtomicUsize,
+    warnings: Mutex<Option<WarningThresholds>>
+}
+
+impl StringInterner {
+    /// Create an interner with a number of shards, which must be a power of two.
+    /// ```
+    /// use immie2d_shared::engine_types::string_interner::StringInterner;
+    /// let interner = StringInterner::new(8);
+    /// assert_eq!(interner.intern(""), 0);
+    /// assert_eq!(interner.get_count(), 1);
+    /// ```
+    /// ``` should_panic
+    /// # use immie2d_shared::engine_types::string_interner::StringInterner;
+    /// // Will panic
+    /// let interner = StringInterner::new(3);
+    /// ```
+    pub fn new(shard_count: usize) -> StringInterner {
+        assert!(shard_count.is_power_of_two(), "StringInterner shard count must be a power of two. Got {}", shard_count);
+        let mut shards: Vec<Mutex<InternerShard>> = Vec::with_capacity(shard_count);
+        for _ in 0..shard_count {
+            shards.push(Mutex::new(InternerShard {
+                map: HashMap::new(),
+                vec: Vec::new(),
+                #[cfg(debug_assertions)]
+                origins: Vec::new()
+            }));
+        }
+        {
+            let first = shards[0].get_mut().unwrap();
+            first.map.insert("".to_string(), 0);
+            first.vec.push("".to_string());
+            #[cfg(debug_assertions)]
+            first.origins.push(None);
+        }
+        return StringInterner {
+            shards,
+            shard_bits: shard_count.trailing_zeros(),
+            count: AtomicUsize::new(1),
+            string_bytes: AtomicUsize::new(0),
+            warnings: Mutex::new(None)
+        };
+    }
+
+    pub fn get_shard_count(&self) -> usize {
+        return self.shards.len();
+    }
+
+    /// A shard is never left half updated, so one poisoned by a panicking resolve_with() callback is still valid.
+    /// Recovering it keeps a single panicking task from breaking every GlobalString in the process.
+    fn lock_shard(&self, shard_index: usize) -> MutexGuard<'_, InternerShard> {
+        return self.shards[shard_index].lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    }
+
+    fn shard_of(&self, string: &str) -> usize {
+        // FNV-1a. Only needs to be fast and stable, not secure.
+        let mut hash: u32 = 0x811C9DC5;
+        for byte in string.bytes() {
+            hash ^= byte as u32;
+            hash = hash.wrapping_mul(0x01000193);
+        }
+        return (hash as usize) & (self.shards.len() - 1);
+    }
+
+    /// Get the id of a string, adding it if it isn't interned yet. In debug builds, the caller is recorded as the
+    /// origin of new strings. Callers that intern on behalf of others should also be `#[track_caller]`.
+    /// ```
+    /// use immie2d_shared::engine_types::string_interner::StringInterner;
+    /// let interner = StringInterner::new(4);
+    /// let id = interner.intern("hello");
+    /// assert_eq!(interner.intern("hello"), id);
+    /// assert_eq!(interner.resolve(id), "hello");
+    /// ```
+    #[track_caller]
+    pub fn intern(&self, string: &str) -> u32 {
+        if string.is_empty() {
+            return 0;
+        }
+        let shard_index = self.shard_of(string);
+        let id = {
+            let mut shard = self.lock_shard(shard_index);
+            if let Some(id) = shard.map.get(string) {
+                return *id;
+            }
+            let index = shard.vec.len() as u32;
+            assert!(index < (u32::MAX >> self.shard_bits), "StringInterner shard {} is full", shard_index);
+            let id = (index << self.shard_bits) | shard_index as u32;
+            shard.vec.push(string.to_string());
+            shard.map.insert(string.to_string(), id);
+            #[cfg(debug_assertions)]
+            shard.origins.push(Some(Location::caller()));
+            id
+        };
+        // Nothing is locked while the hook runs, so it may intern strings itself.
+        let memory_bytes = self.string_bytes.fetch_add(string.len(), Ordering::Relaxed) + string.len();
+        let count = self.count.fetch_add(1, Ordering::Relaxed) + 1;
+        self.check_thresholds(count, memory_bytes);
+        return id;
+    }
+
+    fn check_thresholds(&self, count: usize, memory_bytes: usize) {
+        let hook = match self.warnings.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).as_ref() {
+            Some(warnings) if warnings.thresholds.contains(&count) => warnings.hook.clone(),
+            _ => return
+        };
+        hook(InternerWarning { count, memory_bytes: memory_bytes * 2 + count * ENTRY_OVERHEAD_BYTES });
+    }
+
+    /// Get the id of a string only if it is already interned.
+    /// ```
+    /// use immie2d_shared::engine_types::string_interner::StringInterner;
+    /// let interner = StringInterner::new(4);
+    /// assert!(interner.find("hello").is_none());
+    /// let id = interner.intern("hello");
+    /// assert_eq!(interner.find("hello"), Some(id));
+    /// ```
+    pub fn find(&self, string: &str) -> Option<u32> {
+        if string.is_empty() {
+            return Some(0);
+        }
+        let shard = self.lock_shard(self.shard_of(string));
+        return shard.map.get(string).copied();
+    }
+
+    /// Get a copy of the string of an id. Will panic if the id was not created by this interner.
+    pub fn resolve(&self, id: u32) -> String {
+        return self.resolve_with(id, |string| string.to_string());
+    }
+
+    /// Call a function with the string of an id without copying it, such as to encode it without allocating. The
+    /// shard of the id is locked during the call, so the function must not intern or resolve strings itself.
+    /// Will panic if the id was not created by this interner.
+    /// ```
+    /// use immie2d_shared::engine_types::string_interner::StringInterner;
+    /// let interner = StringInterner::new(4);
+    /// let id = interner.intern("lavapup");
+    /// assert_eq!(interner.resolve_with(id, |string| string.len()), 7);
+    /// ```
+    pub fn resolve_with<R, F: FnOnce(&str) -> R>(&self, id: u32, function: F) -> R {
+        let shard_index = (id & ((1 << self.shard_bits) - 1)) as usize;
+        let index = (id >> self.shard_bits) as usize;
+        let shard = self.lock_shard(shard_index);
+        let string = shard.vec.get(index).unwrap_or_else(|| panic!("Interned string id {} is not valid", id));
+        return function(string);
+    }
+
+    /// Total number of interned strings, including the empty string.
+    pub fn get_count(&self) -> usize {
+        return self.count.load(Ordering::Relaxed);
+    }
+
+    /// Estimate of the bytes used by the interned strings. Each string is stored twice, as a map key and by index,
+    /// and unused capacity of the tables isn't counted.
+    /// ```
+    /// use immie2d_shared::engine_types::string_interner::StringInterner;
+    /// let interner = StringInterner::new(4);
+    /// let empty = interner.get_memory_usage();
+    /// interner.intern("hello");
+    /// assert!(interner.get_memory_usage() >= empty + 2 * "hello".len());
+    /// ```
+    pub fn get_memory_usage(&self) -> usize {
+        return self.string_bytes.load(Ordering::Relaxed) * 2 + self.get_count() * ENTRY_OVERHEAD_BYTES;
+    }
+
+    /// Call a hook once each time the count of interned strings reaches one of the thresholds, to notice tables that
+    /// keep growing, such as from interning player input. Replaces any previous hook.
+    /// ```
+    /// use std::sync::{Arc, Mutex};
+    /// use immie2d_shared::engine_types::string_interner::StringInterner;
+    ///
+    /// let interner = StringInterner::new(4);
+    /// let warnings = Arc::new(Mutex::new(Vec::new()));
+    /// let hook_warnings = warnings.clone();
+    /// interner.set_warning_hook(vec![3, 5], Box::new(move |warning| hook_warnings.lock().unwrap().push(warning.count)));
+    /// for name in ["a", "b", "c", "a", "d", "e", "f"] {
+    ///     interner.intern(name);
+    /// }
+    /// assert_eq!(*warnings.lock().unwrap(), vec![3, 5]);
+    /// ```
+    pub fn set_warning_hook(&self, thresholds: Vec<usize>, hook: InternerWarningHook) {
+        *self.warnings.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(WarningThresholds { thresholds, hook: Arc::from(hook) });
+    }
+
+    /// Every interned string, ordered by id, with where it was first interned in debug builds.
+    /// ```
+    /// use immie2d_shared::engine_types::string_interner::StringInterner;
+    /// let interner = StringInterner::new(4);
+    /// let id = interner.intern("hello");
+    /// let entries = interner.get_entries();
+    /// assert_eq!(entries.len(), 2);
+    /// let entry = entries.iter().find(|entry| entry.id == id).unwrap();
+    /// assert_eq!(entry.string, "hello");
+    /// assert_eq!(entry.origin.is_some(), cfg!(debug_assertions));
+    /// ```
+    pub fn get_entries(&self) -> Vec<InternedEntry> {
+        let mut entries = Vec::new();
+        for shard_index in 0..self.shards.len() {
+            let shard = self.lock_shard(shard_index);
+            for (index, string) in shard.vec.iter().enumerate() {
+                #[cfg(debug_assertions)]
+                let origin = shard.origins[index];
+                #[cfg(not(debug_assertions))]
+                let origin = None;
+                let id = ((index as u32) << self.shard_bits) | shard_index as u32;
+                entries.push(InternedEntry { id, string: string.clone(), origin });
+            }
+        }
+        entries.sort_by_key(|entry| entry.id);
+        return entries;
+    }
+}
@@ -0,0 +1,66 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Microseconds since the unix epoch on the local clock.
+pub fn get_unix_micros() -> u64 {
+    return SystemTime::now().duration_since(UNIX_EPOCH).map(|elapsed| elapsed.as_micros() as u64).unwrap_or(0);
+}
+
+/* Sent by a client to sample the server clock. Times are microseconds since the unix epoch. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimeSyncPing {
+    /// Client clock when the ping was sent.
+    pub client_send: u64
+}
+
+impl TimeSyncPing {
+    /// Answer the ping with the server clock. The server should respond as soon as the ping is read.
+    pub fn respond(&self, server_time: u64) -> TimeSyncPong {
+        return TimeSyncPong { client_send: self.client_send, server_time };
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        return self.client_send.to_le_bytes().to_vec();
+    }
+
+    /// Decode a ping, or None if the bytes are not a valid ping.
+    pub fn from_bytes(bytes: &[u8]) -> Option<TimeSyncPing> {
+        return Some(TimeSyncPing { client_send: u64::from_le_bytes(bytes.try_into().ok()?) });
+    }
+}
+
+/* The server's answer to a TimeSyncPing. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimeSyncPong {
+    /// Echoed from the ping, so the client doesn't need to remember pings in flight.
+    pub client_send: u64,
+    /// Server clock when the ping was answered.
+    pub server_time: u64
+}
+
+impl TimeSyncPong {
+    /// Encode as the echoed client time followed by the server time.
+    /// ```
+    /// use immie2d_shared::engine_types::time_sync::{TimeSyncPing, TimeSyncPong};
+    ///
+    /// let ping = TimeSyncPing::from_bytes(&TimeSyncPing { client_send: 1000 }.to_bytes()).unwrap();
+    /// let pong = ping.respond(5000);
+    /// assert_eq!(TimeSyncPong::from_bytes(&pong.to_bytes()), Some(pong));
+    /// assert_eq!(TimeSyncPong::from_bytes(&pong.to_bytes()[..8]), None);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(16);
+        bytes.extend_from_slice(&self.client_send.to_le_bytes());
+        bytes.extend_from_slice(&self.server_time.to_le_bytes());
+        return bytes;
+    }
+
+    /// Decode a pong, or None if the bytes are not a valid pong.
+    pub fn from_bytes(bytes: &[u8]) -> Option<TimeSyncPong> {
+        if bytes.len() != 16 {
+            return None;
+        }
+        let client_send = u64::from_le_bytes(bytes[0..8].try_into().unwrap());
+        let server_time = u64::from_le_bytes(bytes[8..16].try_into().unwrap());
+        return Some(TimeSyncPong { client_send, server_time });
+    }
+}
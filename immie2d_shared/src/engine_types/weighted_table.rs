@@ -0,0 +1,144 @@
+use crate::engine_types::game_rng::GameRng;
+
+/// A random number in the range [0, total), drawn from the full 64 bits of the rng.
+fn next_below_u64(rng: &mut GameRng, total: u64) -> u64 {
+    return ((rng.next_u64() as u128 * total as u128) >> 64) as u64;
+}
+
+/// Will panic if the weights add up to more than u64::MAX.
+fn get_total_weight<T>(entries: &[(T, u64)]) -> u64 {
+    return entries.iter().try_fold(0u64, |total, (_, weight)| total.checked_add(*weight)).expect("Weights add up to more than u64::MAX");
+}
+
+/// Choose one entry, where the chance of an entry is its weight divided by the total weight. Returns None if no entry
+/// has a weight above 0. Draws one number from the rng. For a table rolled many times, see WeightedTable
+/// Will panic if the weights add up to more than u64::MAX.
+/// ```
+/// use immie2d_shared::engine_types::game_rng::GameRng;
+/// use immie2d_shared::engine_types::weighted_table::weighted_choice;
+///
+/// let mut rng = GameRng::new(8);
+/// let entries = [("potion", 3), ("nothing", 0), ("rare_candy", 1)];
+/// for _ in 0..100 {
+///     assert_ne!(weighted_choice(&entries, &mut rng), Some(&"nothing"));
+/// }
+/// assert_eq!(weighted_choice(&[("nothing", 0)], &mut rng), None);
+/// assert_eq!(weighted_choice::<&str>(&[], &mut rng), None);
+/// ```
+pub fn weighted_choice<'a, T>(entries: &'a [(T, u64)], rng: &mut GameRng) -> Option<&'a T> {
+    let total = get_total_weight(entries);
+    if total == 0 {
+        return None;
+    }
+    let mut roll = next_below_u64(rng, total);
+    for (value, weight) in entries.iter() {
+        if roll < *weight {
+            return Some(value);
+        }
+        roll -= *weight;
+    }
+    unreachable!();
+}
+
+/* Entries with weights, sampled in constant time however many entries there are using the alias method. Every column
+holds the chance of its own entry, and the entry the rest of the column goes to, so a roll picks a column then one of its
+two entries. Building the table takes linear time, so it suits tables rolled often, such as encounter and loot tables.
+The chance of an entry is exactly its weight divided by the total weight. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WeightedTable<T> {
+    entries: Vec<(T, u64)>,
+    total_weight: u64,
+    /// Rolls below the threshold of a column pick its own entry, and the rest pick its alias.
+    columns: Vec<(u64, usize)>
+}
+
+impl<T> WeightedTable<T> {
+    /// Build a table, or None if no entry has a weight above 0.
+    /// Will panic if the weights add up to more than u64::MAX, or there are more than u32::MAX entries.
+    pub fn new(entries: Vec<(T, u64)>) -> Option<WeightedTable<T>> {
+        assert!(entries.len() <= u32::MAX as usize, "A weighted table can't have more than {} entries", u32::MAX);
+        let total_weight = get_total_weight(&entries);
+        if total_weight == 0 {
+            return None;
+        }
+        // Scaled so an average column is exactly the total weight, which keeps the arithmetic in whole numbers.
+        let count = entries.len() as u128;
+        let mut scaled: Vec<u128> = entries.iter().map(|(_, weight)| *weight as u128 * count).collect();
+        let mut columns = vec![(total_weight, 0); entries.len()];
+        let (mut small, mut large): (Vec<usize>, Vec<usize>) = (0..entries.len()).partition(|index| scaled[*index] < total_weight as u128);
+        while let (Some(&under), Some(&over)) = (small.last(), large.last()) {
+            small.pop();
+            columns[under] = (scaled[under] as u64, over);
+            scaled[over] -= total_weight as u128 - scaled[under];
+            if scaled[over] < total_weight as u128 {
+                large.pop();
+                small.push(over);
+            }
+        }
+        // What's left is exactly full, so always picks its own entry.
+        for index in small.into_iter().chain(large) {
+            columns[index] = (total_weight, index);
+        }
+        return Some(WeightedTable { entries, total_weight, columns });
+    }
+
+    pub fn get_entries(&self) -> &[(T, u64)] {
+        return &self.entries;
+    }
+
+    pub fn get_total_weight(&self) -> u64 {
+        return self.total_weight;
+    }
+
+    /// The chance of rolling an entry, worked out from the columns it's in rather than its weight, so it shows what
+    /// sampling actually does.
+    /// ```
+    /// use immie2d_shared::engine_types::weighted_table::WeightedTable;
+    ///
+    /// let table = WeightedTable::new(vec![("a", 1), ("b", 3), ("c", 0), ("d", 4)]).unwrap();
+    /// assert_eq!(table.get_chance(0), 0.125);
+    /// assert_eq!(table.get_chance(1), 0.375);
+    /// assert_eq!(table.get_chance(2), 0.0);
+    /// assert_eq!(table.get_chance(3), 0.5);
+    /// ```
+    pub fn get_chance(&self, index: usize) -> f64 {
+        let mut numerator = 0u128;
+        for (column, (threshold, alias)) in self.columns.iter().enumerate() {
+            if column == index {
+                numerator += *threshold as u128;
+            }
+            if *alias == index {
+                numerator += (self.total_weight - threshold) as u128;
+            }
+        }
+        return numerator as f64 / (self.total_weight as f64 * self.columns.len() as f64);
+    }
+
+    /// Roll an entry. Draws two numbers from the rng, so the same rng always rolls the same entries.
+    /// ```
+    /// use immie2d_shared::engine_types::game_rng::GameRng;
+    /// use immie2d_shared::engine_types::weighted_table::WeightedTable;
+    ///
+    /// let table = WeightedTable::new(vec![("common", 90), ("rare", 10), ("never", 0)]).unwrap();
+    /// let mut rng = GameRng::new(4);
+    /// let rare = (0..10_000).filter(|_| *table.sample(&mut rng) == "rare").count();
+    /// assert!(rare > 900 && rare < 1100);
+    /// assert!((0..10_000).all(|_| *table.sample(&mut rng) != "never"));
+    ///
+    /// assert_eq!(table.get_total_weight(), 100);
+    /// assert!(WeightedTable::new(vec![("never", 0)]).is_none());
+    /// ```
+    pub fn sample(&self, rng: &mut GameRng) -> &T {
+        return &self.entries[self.sample_index(rng)].0;
+    }
+
+    /// Roll the index of an entry. See WeightedTable::sample()
+    pub fn sample_index(&self, rng: &mut GameRng) -> usize {
+        let column = rng.next_below(self.columns.len() as u32) as usize;
+        let (threshold, alias) = self.columns[column];
+        if next_below_u64(rng, self.total_weight) < threshold {
+            return column;
+        }
+        return alias;
+    }
+}
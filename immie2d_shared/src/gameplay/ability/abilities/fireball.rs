@@ -0,0 +1,40 @@
+use crate::gameplay::ability::ability::{Ability, AbilityCategory, BaseAbilityData};
+use crate::gameplay::ability::ability_flags::AbilityFlags;
+use crate::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+
+pub struct Fireball {
+    base_data: BaseAbilityData
+}
+
+impl Ability for Fireball {
+    fn new() -> Box<dyn Ability> {
+        return Box::new(Fireball {
+            base_data: BaseAbilityData {
+                category: AbilityCategory::Attack,
+                types: Elements::new(vec![ElementKind::Fire]),
+                power: 40.0,
+                speed: 1.0,
+                max_uses: 25,
+                accuracy: 100,
+                flags: AbilityFlags::PROJECTILE,
+                combo: None
+            }
+        });
+    }
+
+    fn get_name(&self) -> &'static str {
+        return Fireball::static_name();
+    }
+
+    fn static_name() -> &'static str {
+        return "fireball";
+    }
+
+    fn get_base_ability_data(&self) -> &BaseAbilityData {
+        return &self.base_data;
+    }
+
+    fn get_base_ability_data_mut(&mut self) -> &mut BaseAbilityData {
+        return &mut self.base_data;
+    }
+}
@@ -0,0 +1,42 @@
+use crate::gameplay::ability::ability::{Ability, AbilityCategory, BaseAbilityData};
+use crate::gameplay::ability::ability_flags::AbilityFlags;
+use crate::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+use crate::gameplay::immie::individual_values::MIN_HIDDEN_POWER;
+
+/* Element and power are resolved from the user's individual values. The data only holds placeholders. */
+pub struct HiddenPower {
+    base_data: BaseAbilityData
+}
+
+impl Ability for HiddenPower {
+    fn new() -> Box<dyn Ability> {
+        return Box::new(HiddenPower {
+            base_data: BaseAbilityData {
+                category: AbilityCategory::Attack,
+                types: Elements::new(vec![ElementKind::Standard]),
+                power: MIN_HIDDEN_POWER,
+                speed: 1.0,
+                max_uses: 15,
+                accuracy: 100,
+                flags: AbilityFlags::HIDDEN_POWER,
+                combo: None
+            }
+        });
+    }
+
+    fn get_name(&self) -> &'static str {
+        return HiddenPower::static_name();
+    }
+
+    fn static_name() -> &'static str {
+        return "hidden_power";
+    }
+
+    fn get_base_ability_data(&self) -> &BaseAbilityData {
+        return &self.base_data;
+    }
+
+    fn get_base_ability_data_mut(&mut self) -> &mut BaseAbilityData {
+        return &mut self.base_data;
+    }
+}
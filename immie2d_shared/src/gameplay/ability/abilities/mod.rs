@@ -0,0 +1,3 @@
+pub mod fireball;
+pub mod pursuit;
+pub mod hidden_power;
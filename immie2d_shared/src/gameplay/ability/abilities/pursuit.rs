@@ -0,0 +1,40 @@
+use crate::gameplay::ability::ability::{Ability, AbilityCategory, BaseAbilityData};
+use crate::gameplay::ability::ability_flags::AbilityFlags;
+use crate::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+
+pub struct Pursuit {
+    base_data: BaseAbilityData
+}
+
+impl Ability for Pursuit {
+    fn new() -> Box<dyn Ability> {
+        return Box::new(Pursuit {
+            base_data: BaseAbilityData {
+                category: AbilityCategory::Attack,
+                types: Elements::new(vec![ElementKind::Dark]),
+                power: 40.0,
+                speed: 1.0,
+                max_uses: 20,
+                accuracy: 100,
+                flags: AbilityFlags::CONTACT | AbilityFlags::INTERCEPTS_SWITCH,
+                combo: None
+            }
+        });
+    }
+
+    fn get_name(&self) -> &'static str {
+        return Pursuit::static_name();
+    }
+
+    fn static_name() -> &'static str {
+        return "pursuit";
+    }
+
+    fn get_base_ability_data(&self) -> &BaseAbilityData {
+        return &self.base_data;
+    }
+
+    fn get_base_ability_data_mut(&mut self) -> &mut BaseAbilityData {
+        return &mut self.base_data;
+    }
+}
@@ -1,4 +1,12 @@
+use std::collections::HashSet;
+use std::sync::RwLock;
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Deserializer, Serialize};
+
 use super::super::elements::elements_data::Elements;
+use super::ability_flags::AbilityFlags;
+use super::ability_script::AbilityScript;
 
 pub trait Ability {
     fn new() -> Box<dyn Ability>
@@ -12,18 +20,75 @@ pub trait Ability {
     fn get_base_ability_data(&self) -> &BaseAbilityData;
     
     fn get_base_ability_data_mut(&mut self) -> &mut BaseAbilityData;
+
+    /// Script with the effect hooks of the ability, if its data references one. See AbilityScript
+    fn get_script(&self) -> Option<&AbilityScript> {
+        return None;
+    }
 }
 
+/* A bonus an ability gets when its user used another ability on the previous turn. */
+#[derive(Clone, Copy, PartialEq, Debug, Serialize)]
+pub struct AbilityCombo {
+    /// Name of the ability that must be used the turn before.
+    pub follows: &'static str,
+    pub power_multiplier: f32
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum AbilityCategory {
     Attack,
     Status
 }
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct BaseAbilityData {
     pub category: AbilityCategory,
     pub types: Elements,
     pub power: f32,
     pub speed: f32,
+    /// How many times the ability can be used before it must be restored.
+    pub max_uses: u32,
+    /// Percent chance of hitting a target with no evasion stages. See get_hit_chance()
+    pub accuracy: u32,
+    pub flags: AbilityFlags,
+    pub combo: Option<AbilityCombo>
+}
+
+
+
+lazy_static! {
+    /// Names of every ability added to an AbilityMap. Deserialized combos may only follow one of these.
+    static ref ABILITY_NAMES: RwLock<HashSet<&'static str>> = RwLock::new(HashSet::new());
 }
 
+/// Record an ability name as known, returning the static copy of it. Names added more than once are only leaked once.
+pub(crate) fn register_ability_name(name: &str) -> &'static str {
+    let mut names = ABILITY_NAMES.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+    return match names.get(name) {
+        Some(existing) => existing,
+        None => {
+            let leaked: &'static str = Box::leak(name.to_string().into_boxed_str());
+            names.insert(leaked);
+            leaked
+        }
+    };
+}
+
+impl<'de> Deserialize<'de> for AbilityCombo {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<AbilityCombo, D::Error> {
+        #[derive(Deserialize)]
+        struct OwnedCombo {
+            follows: String,
+            power_multiplier: f32
+        }
 
+        let combo = OwnedCombo::deserialize(deserializer)?;
+        let names = ABILITY_NAMES.read().unwrap_or_else(|poisoned| poisoned.into_inner());
+        let follows = match names.get(combo.follows.as_str()) {
+            Some(name) => *name,
+            None => return Err(serde::de::Error::custom(format!("combo follows unknown ability {:?}", combo.follows)))
+        };
+        return Ok(AbilityCombo { follows, power_multiplier: combo.power_multiplier });
+    }
+}
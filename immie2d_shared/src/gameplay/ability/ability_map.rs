@@ -1,9 +1,14 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
-use super::ability::Ability;
+use super::ability::{register_ability_name, Ability, BaseAbilityData};
+use super::ability_script::AbilityScript;
+use super::data_ability::DataAbility;
+
+type AbilityConstructor = Arc<dyn Fn() -> Box<dyn Ability> + Send + Sync>;
 
 pub struct AbilityMap {
-    map: HashMap<&'static str, fn() -> Box<dyn Ability>>
+    map: HashMap<&'static str, AbilityConstructor>
 }
 
 impl AbilityMap {
@@ -18,7 +23,40 @@ impl AbilityMap {
     /// map.add_ability::<Fireball>();
     /// ```
     pub fn add_ability<T: Ability>(&mut self) {
-        self.map.insert(T::static_name(), T::new);
+        let constructor: fn() -> Box<dyn Ability> = T::new;
+        self.map.insert(register_ability_name(T::static_name()), Arc::new(constructor));
+    }
+
+    /// Add an ability defined by data, such as from a data pack. Will replace any ability already using the same name.
+    /// Ability names live for the rest of the program, so data abilities should only be added while loading data.
+    /// ```
+    /// use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability::{AbilityCategory, BaseAbilityData}, ability_flags::AbilityFlags};
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    ///
+    /// let mut map = AbilityMap::new();
+    /// map.add_data_ability("mymod:magma_ball", BaseAbilityData {
+    ///     category: AbilityCategory::Attack,
+    ///     types: Elements::new(vec![ElementKind::Fire]),
+    ///     power: 70.0,
+    ///     speed: 1.0,
+    ///     max_uses: 10,
+    ///     accuracy: 100,
+    ///     flags: AbilityFlags::NONE,
+    ///     combo: None
+    /// });
+    /// let ability = map.new_ability("mymod:magma_ball");
+    /// assert_eq!(ability.get_name(), "mymod:magma_ball");
+    /// assert_eq!(ability.get_base_ability_data().power, 70.0);
+    /// ```
+    pub fn add_data_ability(&mut self, name: &str, data: BaseAbilityData) {
+        let name = register_ability_name(name);
+        self.map.insert(name, Arc::new(move || DataAbility::from_data(name, data)));
+    }
+
+    /// Add an ability defined by data with a script implementing its effect hooks. See AbilityMap::add_data_ability()
+    pub fn add_scripted_ability(&mut self, name: &str, data: BaseAbilityData, script: Arc<AbilityScript>) {
+        let name = register_ability_name(name);
+        self.map.insert(name, Arc::new(move || DataAbility::from_scripted_data(name, data, script.clone())));
     }
 
     /// Create a new instance of Ability.
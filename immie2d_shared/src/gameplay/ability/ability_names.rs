@@ -1,11 +1,13 @@
 use std::fmt;
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 use crate::engine_types::global_string::GlobalString;
 
 pub const MAX_ABILITIES_COUNT: u32 = 5;
 
 /* Container to store the names of abilities */
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, PartialEq)]
 pub struct AbilityNames {
     names: [GlobalString; MAX_ABILITIES_COUNT as usize],
     count: u32
@@ -106,6 +108,48 @@ impl AbilityNames {
         self.count += 1;
     }
 
+    /// Swap the abilities in two slots.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// let (a, b) = (GlobalString::new(&"a".to_string()), GlobalString::new(&"b".to_string()));
+    /// let mut abilities = AbilityNames::new(vec![a, b]);
+    /// abilities.swap(0, 1);
+    /// assert_eq!(abilities.get_names(), vec![b, a]);
+    /// ```
+    /// Will panic if either slot doesn't hold an ability.
+    /// ``` should_panic
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// let mut abilities = AbilityNames::new(vec![GlobalString::new(&"a".to_string())]);
+    /// // Will panic
+    /// abilities.swap(0, 1);
+    /// ```
+    pub fn swap(&mut self, a: usize, b: usize) {
+        assert!(a < self.count as usize && b < self.count as usize, "Cannot swap ability slots {} and {}. There are only {} abilities", a, b, self.count);
+        self.names.swap(a, b);
+    }
+
+    /// Remove the ability in a slot, moving every later ability down a slot. Returns the removed ability.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// let (a, b, c) = (GlobalString::new(&"a".to_string()), GlobalString::new(&"b".to_string()), GlobalString::new(&"c".to_string()));
+    /// let mut abilities = AbilityNames::new(vec![a, b, c]);
+    /// assert_eq!(abilities.remove(0), a);
+    /// assert_eq!(abilities, AbilityNames::new(vec![b, c]));
+    /// ```
+    /// Will panic if the slot doesn't hold an ability.
+    pub fn remove(&mut self, slot: usize) -> GlobalString {
+        assert!(slot < self.count as usize, "Cannot remove ability slot {}. There are only {} abilities", slot, self.count);
+        let removed = self.names[slot];
+        self.names.copy_within(slot + 1..self.count as usize, slot);
+        self.count -= 1;
+        // Cleared so equality only depends on the held abilities.
+        self.names[self.count as usize] = GlobalString::default();
+        return removed;
+    }
+
     /// Get the number of ability names contained.
     /// ```
     /// use immie2d_shared::engine_types::global_string::GlobalString;
@@ -194,4 +238,26 @@ impl fmt::Display for AbilityNames {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         return write!(f, "{:?}", self);
     }
-}
\ No newline at end of file
+}
+
+// A list of ability names. Lists that AbilityNames::new() would panic on are errors instead.
+impl Serialize for AbilityNames {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        return serializer.collect_seq(self.iter());
+    }
+}
+
+impl<'de> Deserialize<'de> for AbilityNames {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<AbilityNames, D::Error> {
+        let names = Vec::<GlobalString>::deserialize(deserializer)?;
+        if names.len() > MAX_ABILITIES_COUNT as usize {
+            return Err(serde::de::Error::custom(format!("more than {} abilities", MAX_ABILITIES_COUNT)));
+        }
+        for (i, name) in names.iter().enumerate() {
+            if names[..i].contains(name) {
+                return Err(serde::de::Error::custom(format!("duplicate ability {}", name)));
+            }
+        }
+        return Ok(AbilityNames::new(names));
+    }
+}
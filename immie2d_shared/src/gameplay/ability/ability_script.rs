@@ -0,0 +1,168 @@
+use std::cell::Cell;
+
+use lazy_static::lazy_static;
+use rhai::{CallFnOptions, Dynamic, Engine, EvalAltResult, Position, Scope, AST};
+
+use crate::engine_types::game_rng::GameRng;
+
+/// Hook called after the battle's rules modify the damage inputs, which can change the ability's power and multiplier.
+pub const MODIFY_DAMAGE_HOOK: &str = "modify_damage";
+/// Hook called once the ability has hit, including status abilities, which can deal extra damage or recoil.
+pub const ON_HIT_HOOK: &str = "on_hit";
+/// Most operations a single hook runs before it is stopped, so a looping script can't stall a battle.
+pub const MAX_SCRIPT_OPERATIONS: u64 = 10_000;
+/// Most random numbers a single hook draws before it is stopped, well within the rolls a pipeline stage may draw. See
+/// MAX_STAGE_ROLLS
+pub const MAX_SCRIPT_ROLLS: u32 = 32;
+/// Highest power or multiplier a script can set.
+pub const MAX_SCRIPT_VALUE: f64 = 1000.0;
+
+const HOOKS: [&str; 2] = [MODIFY_DAMAGE_HOOK, ON_HIT_HOOK];
+
+lazy_static! {
+    static ref SCRIPT_ENGINE: Engine = create_engine();
+}
+
+thread_local! {
+    /// Random numbers drawn by the hook running on this thread. Hooks run to completion on the thread that called them.
+    static HOOK_ROLLS: Cell<u32> = const { Cell::new(0) };
+}
+
+/* What an ability script can see and do, bound to `this` in its hooks. Scripts only see copies of battle values, and
+what they change is applied by the ability pipeline once the hook returns. */
+#[derive(Clone, Debug)]
+pub struct AbilityContext {
+    pub turn: u32,
+    pub attacker_level: u32,
+    pub attacker_health: u32,
+    pub defender_health: u32,
+    pub power: f32,
+    pub multiplier: f32,
+    pub effectiveness: f32,
+    /// Damage the ability dealt, which is 0 until it has hit.
+    pub damage_dealt: u32,
+    /// Extra damage the script deals to the defender and attacker.
+    pub defender_damage: u32,
+    pub recoil: u32,
+    /// The battle's rng, so scripted randomness is replayed like the rest of the battle.
+    pub rng: GameRng
+}
+
+/* Effect hooks of an ability too exotic for its data alone, written in rhai so content updates can ship without
+rebuilding. Hooks are functions without parameters named after MODIFY_DAMAGE_HOOK or ON_HIT_HOOK, such as
+`fn on_hit() { this.recoil(this.damage_dealt / 4); }`. Scripts run sandboxed: they can't import modules, read the time or
+print, and each hook is limited to MAX_SCRIPT_OPERATIONS and MAX_SCRIPT_ROLLS. */
+pub struct AbilityScript {
+    ast: AST
+}
+
+impl AbilityScript {
+    /// Compile a script, checking it defines at least one hook and that its hooks take no parameters.
+    /// ```
+    /// use immie2d_shared::engine_types::game_rng::GameRng;
+    /// use immie2d_shared::gameplay::ability::ability_script::{AbilityContext, AbilityScript, MODIFY_DAMAGE_HOOK, ON_HIT_HOOK};
+    ///
+    /// let script = AbilityScript::compile("desperation", r#"
+    ///     fn modify_damage() {
+    ///         if this.attacker_health < 20 { this.power *= 2.0; }
+    ///     }
+    /// "#).unwrap();
+    /// assert!(script.has_hook(MODIFY_DAMAGE_HOOK));
+    /// assert!(!script.has_hook(ON_HIT_HOOK));
+    ///
+    /// let mut context = AbilityContext {
+    ///     turn: 1, attacker_level: 20, attacker_health: 10, defender_health: 80, power: 40.0, multiplier: 1.0,
+    ///     effectiveness: 1.0, damage_dealt: 0, defender_damage: 0, recoil: 0, rng: GameRng::new(1)
+    /// };
+    /// script.run_hook(MODIFY_DAMAGE_HOOK, &mut context).unwrap();
+    /// assert_eq!(context.power, 80.0);
+    ///
+    /// assert!(AbilityScript::compile("nothing", "let x = 1;").is_err());
+    /// assert!(AbilityScript::compile("bad", "fn on_hit(target) {}").is_err());
+    /// let endless = AbilityScript::compile("endless", "fn on_hit() { loop {} }").unwrap();
+    /// assert!(endless.run_hook(ON_HIT_HOOK, &mut context).is_err());
+    /// let gambler = AbilityScript::compile("gambler", "fn on_hit() { for i in 0..100 { this.damage_defender(this.random(2)); } }").unwrap();
+    /// assert!(gambler.run_hook(ON_HIT_HOOK, &mut context).is_err());
+    /// ```
+    pub fn compile(name: &str, source: &str) -> Result<AbilityScript, String> {
+        let ast = SCRIPT_ENGINE.compile(source).map_err(|err| format!("Script of [{}] doesn't compile: {}", name, err))?;
+        let mut has_hook = false;
+        for function in ast.iter_functions() {
+            if !HOOKS.contains(&function.name) {
+                continue;
+            }
+            if !function.params.is_empty() {
+                return Err(format!("Hook [{}] of [{}] can't take parameters, it uses `this` instead", function.name, name));
+            }
+            has_hook = true;
+        }
+        if !has_hook {
+            return Err(format!("Script of [{}] has none of the hooks {:?}", name, HOOKS));
+        }
+        return Ok(AbilityScript { ast });
+    }
+
+    pub fn has_hook(&self, hook: &str) -> bool {
+        return self.ast.iter_functions().any(|function| function.name == hook);
+    }
+
+    /// Run a hook on the context. A hook that fails, such as by running too many operations, leaves the context as it
+    /// was. Does nothing if the script doesn't have the hook.
+    pub fn run_hook(&self, hook: &str, context: &mut AbilityContext) -> Result<(), String> {
+        if !self.has_hook(hook) {
+            return Ok(());
+        }
+        HOOK_ROLLS.with(|rolls| rolls.set(0));
+        let mut this = Dynamic::from(context.clone());
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut this);
+        // Hooks work through `this`, so whatever they return is ignored
+        let _ = SCRIPT_ENGINE.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, hook, ()).map_err(|err| err.to_string())?;
+        *context = this.try_cast::<AbilityContext>().ok_or(format!("Hook [{}] replaced its context", hook))?;
+        return Ok(());
+    }
+}
+
+/// An engine with only what ability scripts need, and the AbilityContext API.
+fn create_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_SCRIPT_OPERATIONS);
+    engine.set_max_call_levels(16);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(1024);
+    engine.set_max_array_size(256);
+    engine.set_max_map_size(256);
+    engine.disable_symbol("eval");
+    engine.on_print(|_| {});
+    engine.on_debug(|_, _, _| {});
+
+    engine.register_type_with_name::<AbilityContext>("AbilityContext");
+    engine.register_get("turn", |context: &mut AbilityContext| context.turn as i64);
+    engine.register_get("attacker_level", |context: &mut AbilityContext| context.attacker_level as i64);
+    engine.register_get("attacker_health", |context: &mut AbilityContext| context.attacker_health as i64);
+    engine.register_get("defender_health", |context: &mut AbilityContext| context.defender_health as i64);
+    engine.register_get("effectiveness", |context: &mut AbilityContext| context.effectiveness as f64);
+    engine.register_get("damage_dealt", |context: &mut AbilityContext| context.damage_dealt as i64);
+    engine.register_get_set("power", |context: &mut AbilityContext| context.power as f64, |context: &mut AbilityContext, power: f64| context.power = clamp_script_value(power));
+    engine.register_get_set("multiplier", |context: &mut AbilityContext| context.multiplier as f64, |context: &mut AbilityContext, multiplier: f64| context.multiplier = clamp_script_value(multiplier));
+    engine.register_fn("damage_defender", |context: &mut AbilityContext, amount: i64| context.defender_damage = context.defender_damage.saturating_add(amount.clamp(0, u32::MAX as i64) as u32));
+    engine.register_fn("recoil", |context: &mut AbilityContext, amount: i64| context.recoil = context.recoil.saturating_add(amount.clamp(0, u32::MAX as i64) as u32));
+    // A random number from 0 to below - 1, or 0 if below isn't positive
+    engine.register_fn("random", |context: &mut AbilityContext, below: i64| -> Result<i64, Box<EvalAltResult>> {
+        if below <= 0 {
+            return Ok(0);
+        }
+        let rolls = HOOK_ROLLS.with(|rolls| rolls.replace(rolls.get() + 1));
+        if rolls >= MAX_SCRIPT_ROLLS {
+            return Err(Box::new(EvalAltResult::ErrorRuntime(format!("Hooks can draw at most {} random numbers", MAX_SCRIPT_ROLLS).into(), Position::NONE)));
+        }
+        return Ok(context.rng.next_below(below.min(u32::MAX as i64) as u32) as i64);
+    });
+    return engine;
+}
+
+fn clamp_script_value(value: f64) -> f32 {
+    if !value.is_finite() {
+        return 0.0;
+    }
+    return value.clamp(0.0, MAX_SCRIPT_VALUE) as f32;
+}
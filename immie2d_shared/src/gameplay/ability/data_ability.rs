@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use super::ability::{Ability, BaseAbilityData};
+use super::ability_script::AbilityScript;
+
+/* An ability defined entirely by data, such as one added by a data pack, rather than by its own type. Data abilities
+have no behaviour beyond what the battle pipeline does with their base data and flags, unless they have a script.
+See AbilityMap::add_data_ability() */
+pub struct DataAbility {
+    name: &'static str,
+    base_data: BaseAbilityData,
+    script: Option<Arc<AbilityScript>>
+}
+
+impl DataAbility {
+    pub fn from_data(name: &'static str, base_data: BaseAbilityData) -> Box<dyn Ability> {
+        return Box::new(DataAbility { name, base_data, script: None });
+    }
+
+    /// A data ability whose effect hooks are implemented by a script, which every instance shares.
+    pub fn from_scripted_data(name: &'static str, base_data: BaseAbilityData, script: Arc<AbilityScript>) -> Box<dyn Ability> {
+        return Box::new(DataAbility { name, base_data, script: Some(script) });
+    }
+}
+
+impl Ability for DataAbility {
+    /// Data abilities have no default data to create one from. Will always panic. See DataAbility::from_data()
+    fn new() -> Box<dyn Ability> {
+        panic!("Data abilities must be created from their data with DataAbility::from_data()");
+    }
+
+    fn get_name(&self) -> &'static str {
+        return self.name;
+    }
+
+    /// Data abilities don't share a name. Will always panic. See DataAbility::get_name()
+    fn static_name() -> &'static str {
+        panic!("Data abilities have no static name");
+    }
+
+    fn get_base_ability_data(&self) -> &BaseAbilityData {
+        return &self.base_data;
+    }
+
+    fn get_base_ability_data_mut(&mut self) -> &mut BaseAbilityData {
+        return &mut self.base_data;
+    }
+
+    fn get_script(&self) -> Option<&AbilityScript> {
+        return self.script.as_deref();
+    }
+}
@@ -1,4 +1,7 @@
 pub mod ability;
 pub mod abilities;
 pub mod ability_map;
-pub mod ability_names;
\ No newline at end of file
+pub mod ability_names;
+pub use immie2d_core::ability_flags;
+pub mod data_ability;
+pub mod ability_script;
@@ -0,0 +1,336 @@
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::ability::ability::{AbilityCategory, BaseAbilityData};
+use crate::gameplay::ability::ability_flags::AbilityFlags;
+use crate::gameplay::ability::ability_script::{AbilityContext, AbilityScript, MODIFY_DAMAGE_HOOK, ON_HIT_HOOK};
+use crate::gameplay::species::base_stats::BaseStats;
+
+use super::battle::Battle;
+use super::battle_event::BattleEvent;
+use super::battler_id::BattlerId;
+use super::damage::{apply_weather, get_active_combo, DamageBreakdown, DamageContext};
+use super::effect_order::{ABILITY_SCRIPT_EFFECT, RULES_EFFECT, WEATHER_EFFECT};
+use super::hit_resolution::{get_hit_chance, resolve_hit, HitOutcome, LockOn};
+
+/// Most rng rolls a single stage is expected to draw. More than this is treated as a bug.
+pub const MAX_STAGE_ROLLS: u64 = 64;
+
+/* The stages of using an ability, in the order they run. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PipelineStage {
+    /// Check whether the ability is blocked, misses, or hits a substitute. Status abilities lock on here.
+    ResolveHit,
+    /// Gather the damage inputs from the attacker, defender and ability.
+    GatherInputs,
+    /// Let the weather, the battle's rules and the ability's script modify the damage inputs, in the battle's effect
+    /// order.
+    ApplyRules,
+    CalculateDamage,
+    /// Deal the damage to the defender or its substitute.
+    ApplyDamage,
+    /// Run the on hit hook of the ability's script. Skipped by abilities without one.
+    OnHit,
+    Finished
+}
+
+impl PipelineStage {
+    fn get_next(&self) -> PipelineStage {
+        return match self {
+            PipelineStage::ResolveHit => PipelineStage::GatherInputs,
+            PipelineStage::GatherInputs => PipelineStage::ApplyRules,
+            PipelineStage::ApplyRules => PipelineStage::CalculateDamage,
+            PipelineStage::CalculateDamage => PipelineStage::ApplyDamage,
+            PipelineStage::ApplyDamage => PipelineStage::OnHit,
+            PipelineStage::OnHit | PipelineStage::Finished => PipelineStage::Finished
+        };
+    }
+}
+
+/* The intermediate values of an ability use, filled in as each stage runs. Values of stages that haven't run are None. */
+#[derive(Clone, Debug, Default)]
+pub struct BattleInspector {
+    pub hit_outcome: Option<HitOutcome>,
+    /// Stats of the attacker and defender when the inputs were gathered, after any transformation.
+    pub attacker_stats: Option<BaseStats>,
+    pub defender_stats: Option<BaseStats>,
+    /// Whether the ability's combo powered it up.
+    pub combo_triggered: Option<bool>,
+    /// Damage inputs before and after the rules modified them.
+    pub gathered_inputs: Option<DamageContext>,
+    pub rules_inputs: Option<DamageContext>,
+    pub breakdown: Option<DamageBreakdown>,
+    /// Damage dealt to the defender, or health lost by its substitute.
+    pub damage_dealt: Option<u32>,
+    /// Every value drawn from the battle's rng, with the stage that drew it. Only recorded with roll tracking on, and
+    /// stages that drew more than MAX_STAGE_ROLLS are left out. See AbilityPipeline::with_roll_tracking()
+    pub rng_rolls: Vec<(PipelineStage, u64)>,
+    /// Why the last script hook failed, if one did. Failed hooks change nothing.
+    pub script_error: Option<String>
+}
+
+/* An attacker using an ability on a defender, which can be advanced one stage at a time to inspect the intermediate
+values. Battle::use_ability() runs the whole pipeline at once. */
+pub struct AbilityPipeline<'a> {
+    attacker: BattlerId,
+    defender: BattlerId,
+    ability: &'a BaseAbilityData,
+    script: Option<&'a AbilityScript>,
+    power_multiplier: f32,
+    stage: PipelineStage,
+    inspector: BattleInspector,
+    is_tracking_rolls: bool
+}
+
+impl<'a> AbilityPipeline<'a> {
+    pub fn new(attacker: BattlerId, defender: BattlerId, ability: &'a BaseAbilityData) -> AbilityPipeline<'a> {
+        return AbilityPipeline {
+            attacker,
+            defender,
+            ability,
+            script: None,
+            power_multiplier: 1.0,
+            stage: PipelineStage::ResolveHit,
+            inspector: BattleInspector::default(),
+            is_tracking_rolls: false
+        };
+    }
+
+    /// Multiply the ability's power when the inputs are gathered, such as when intercepting a switch.
+    pub fn with_power_multiplier(mut self, power_multiplier: f32) -> AbilityPipeline<'a> {
+        self.power_multiplier = power_multiplier;
+        return self;
+    }
+
+    /// Run the hooks of the ability's script. See Ability::get_script()
+    pub fn with_script(mut self, script: Option<&'a AbilityScript>) -> AbilityPipeline<'a> {
+        self.script = script;
+        return self;
+    }
+
+    /// Record every rng roll in the inspector, for debugging tools. Off by default, as it costs an allocation per stage.
+    pub fn with_roll_tracking(mut self) -> AbilityPipeline<'a> {
+        self.is_tracking_rolls = true;
+        return self;
+    }
+
+    /// The stage that will run on the next step.
+    pub fn get_stage(&self) -> PipelineStage {
+        return self.stage;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.stage == PipelineStage::Finished;
+    }
+
+    pub fn get_inspector(&self) -> &BattleInspector {
+        return &self.inspector;
+    }
+
+    /// Run the next stage, returning the stage that ran. Status abilities, and abilities that are blocked or miss,
+    /// finish early.
+    /// Will panic if the pipeline is already finished, or the battle has ended.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::ability::abilities::fireball::Fireball;
+    /// # use immie2d_shared::gameplay::ability::ability::Ability;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::ability_pipeline::{AbilityPipeline, PipelineStage};
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(200, 60, 40, 70));
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 50, AbilityNames::default()), &species)]);
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![side.clone(), side]).with_rules(BattleRuleset::LevelCapped { level_cap: 10 }.create_plugin());
+    /// let fireball = Fireball::new();
+    ///
+    /// let mut pipeline = AbilityPipeline::new(BattlerId::new(0, 0), BattlerId::new(1, 0), fireball.get_base_ability_data());
+    /// assert_eq!(pipeline.step(&mut battle), PipelineStage::ResolveHit);
+    /// assert_eq!(pipeline.step(&mut battle), PipelineStage::GatherInputs);
+    /// assert_eq!(pipeline.get_inspector().gathered_inputs.unwrap().attacker_level, 50);
+    /// assert_eq!(pipeline.step(&mut battle), PipelineStage::ApplyRules);
+    /// assert_eq!(pipeline.get_inspector().rules_inputs.unwrap().attacker_level, 10);
+    /// pipeline.step(&mut battle);
+    /// // Nothing is dealt until the last stage
+    /// assert_eq!(battle.get_battler(BattlerId::new(1, 0)).get_health(), 200);
+    /// assert_eq!(pipeline.step(&mut battle), PipelineStage::ApplyDamage);
+    /// let dealt = pipeline.get_inspector().breakdown.unwrap().damage;
+    /// assert_eq!(battle.get_battler(BattlerId::new(1, 0)).get_health(), 200 - dealt);
+    /// assert!(pipeline.is_finished());
+    /// // Rolls are only recorded with roll tracking on
+    /// assert!(pipeline.get_inspector().rng_rolls.is_empty());
+    /// let mut tracked = AbilityPipeline::new(BattlerId::new(0, 0), BattlerId::new(1, 0), fireball.get_base_ability_data()).with_roll_tracking();
+    /// let before = *battle.get_rng_mut();
+    /// tracked.run(&mut battle);
+    /// let rolls: Vec<u64> = tracked.get_inspector().rng_rolls.iter().map(|(_, roll)| *roll).collect();
+    /// assert_eq!(rolls, battle.get_rng_mut().get_rolls_since(before, 64));
+    /// ```
+    pub fn step(&mut self, battle: &mut Battle) -> PipelineStage {
+        assert!(!self.is_finished(), "Cannot step an ability pipeline that has finished");
+        assert!(!battle.is_finished(), "Cannot use an ability after the battle has ended");
+        let stage = self.stage;
+        let rng_before = *battle.get_rng_mut();
+        self.stage = stage.get_next();
+        if self.stage == PipelineStage::OnHit && !self.has_hook(ON_HIT_HOOK) {
+            self.stage = PipelineStage::Finished;
+        }
+        match stage {
+            PipelineStage::ResolveHit => self.resolve_hit(battle),
+            PipelineStage::GatherInputs => {
+                self.inspector.attacker_stats = Some(battle.get_battler(self.attacker).get_stats());
+                self.inspector.defender_stats = Some(battle.get_battler(self.defender).get_stats());
+                let combo = get_active_combo(battle, self.attacker, self.ability);
+                if let Some(combo) = combo {
+                    battle.push_event(BattleEvent::ComboTriggered { battler: self.attacker, follows: GlobalString::new(&combo.follows.to_string()) });
+                }
+                self.inspector.combo_triggered = Some(combo.is_some());
+                let mut context = DamageContext::new(battle, self.attacker, self.defender, self.ability);
+                context.power *= self.power_multiplier;
+                self.inspector.gathered_inputs = Some(context);
+            },
+            PipelineStage::ApplyRules => {
+                let mut context = self.inspector.gathered_inputs.unwrap();
+                let mut effects = [WEATHER_EFFECT, RULES_EFFECT, ABILITY_SCRIPT_EFFECT];
+                battle.get_effect_order().sort(&mut effects, |effect| *effect);
+                for effect in effects {
+                    match effect {
+                        WEATHER_EFFECT => apply_weather(battle, &mut context),
+                        RULES_EFFECT => battle.get_rules_handle().pre_damage(battle, &mut context),
+                        _ => self.modify_damage_with_script(battle, &mut context)
+                    }
+                }
+                self.inspector.rules_inputs = Some(context);
+            },
+            PipelineStage::CalculateDamage => self.inspector.breakdown = Some(self.inspector.rules_inputs.unwrap().get_breakdown()),
+            PipelineStage::ApplyDamage => self.apply_damage(battle),
+            PipelineStage::OnHit => {
+                let context = DamageContext::new(battle, self.attacker, self.defender, self.ability);
+                let mut script_context = self.get_script_context(battle, &context);
+                if self.run_hook(ON_HIT_HOOK, &mut script_context) {
+                    self.apply_script_effects(battle, script_context);
+                }
+            },
+            PipelineStage::Finished => unreachable!()
+        }
+        if self.is_tracking_rolls {
+            let rolls = battle.get_rng_mut().try_get_rolls_since(rng_before, MAX_STAGE_ROLLS).unwrap_or_default();
+            self.inspector.rng_rolls.extend(rolls.into_iter().map(|roll| (stage, roll)));
+        }
+        return stage;
+    }
+
+    /// Run every remaining stage. Returns the damage dealt.
+    pub fn run(&mut self, battle: &mut Battle) -> u32 {
+        while !self.is_finished() {
+            self.step(battle);
+        }
+        return self.inspector.damage_dealt.unwrap_or(0);
+    }
+
+    fn resolve_hit(&mut self, battle: &mut Battle) {
+        if let AbilityCategory::Status = self.ability.category {
+            if self.ability.flags.contains(AbilityFlags::LOCKS_ON) {
+                let last_turn = battle.get_turn() + 1;
+                battle.get_battler_mut(self.attacker).set_lock_on(Some(LockOn { target: self.defender, last_turn }));
+                battle.push_event(BattleEvent::LockedOn { attacker: self.attacker, target: self.defender });
+            }
+            self.stage = if self.has_hook(ON_HIT_HOOK) { PipelineStage::OnHit } else { PipelineStage::Finished };
+            return;
+        }
+        let mut outcome = resolve_hit(self.ability.flags, battle.get_battler(self.defender), battle.get_effect_order());
+        if !matches!(outcome, HitOutcome::Blocked(_)) && !self.roll_accuracy(battle) {
+            outcome = HitOutcome::Missed;
+        }
+        self.inspector.hit_outcome = Some(outcome);
+        match outcome {
+            HitOutcome::Blocked(blocker) => battle.push_event(BattleEvent::AbilityBlocked { defender: self.defender, blocker }),
+            HitOutcome::Missed => battle.push_event(BattleEvent::AbilityMissed { attacker: self.attacker, defender: self.defender }),
+            HitOutcome::Hit | HitOutcome::HitSubstitute => return
+        }
+        self.inspector.damage_dealt = Some(0);
+        self.stage = PipelineStage::Finished;
+    }
+
+    /// Whether an ability that wasn't blocked hits. A lock on the defender always hits and is used up. Only rolls
+    /// when the ability could miss, so abilities that can't miss never change the rng.
+    fn roll_accuracy(&mut self, battle: &mut Battle) -> bool {
+        let turn = battle.get_turn();
+        if battle.get_battler_mut(self.attacker).take_lock_on(self.defender, turn) {
+            return true;
+        }
+        let chance = get_hit_chance(self.ability.accuracy, battle.get_battler(self.defender).get_evasion_stage(), self.ability.flags);
+        return chance >= 100 || battle.get_rng_mut().next_below(100) < chance;
+    }
+
+    fn modify_damage_with_script(&mut self, battle: &mut Battle, context: &mut DamageContext) {
+        if !self.has_hook(MODIFY_DAMAGE_HOOK) {
+            return;
+        }
+        let mut script_context = self.get_script_context(battle, context);
+        if self.run_hook(MODIFY_DAMAGE_HOOK, &mut script_context) {
+            context.power = script_context.power;
+            context.multiplier = script_context.multiplier;
+            self.apply_script_effects(battle, script_context);
+        }
+    }
+
+    fn has_hook(&self, hook: &str) -> bool {
+        return self.script.is_some_and(|script| script.has_hook(hook));
+    }
+
+    /// What the script sees of the battle, with the damage inputs as they are so far.
+    fn get_script_context(&self, battle: &mut Battle, context: &DamageContext) -> AbilityContext {
+        return AbilityContext {
+            turn: battle.get_turn(),
+            attacker_level: context.attacker_level,
+            attacker_health: battle.get_battler(self.attacker).get_health(),
+            defender_health: battle.get_battler(self.defender).get_health(),
+            power: context.power,
+            multiplier: context.multiplier,
+            effectiveness: context.effectiveness,
+            damage_dealt: self.inspector.damage_dealt.unwrap_or(0),
+            defender_damage: 0,
+            recoil: 0,
+            rng: *battle.get_rng_mut()
+        };
+    }
+
+    /// Run a hook of the script, remembering why it failed if it did. Returns whether it succeeded.
+    fn run_hook(&mut self, hook: &str, context: &mut AbilityContext) -> bool {
+        let Some(script) = self.script else {
+            return false;
+        };
+        if let Err(error) = script.run_hook(hook, context) {
+            self.inspector.script_error = Some(error);
+            return false;
+        }
+        return true;
+    }
+
+    /// Apply what a hook did to the battle. The defender takes its damage first, so recoil never lands after the
+    /// battle is won.
+    fn apply_script_effects(&mut self, battle: &mut Battle, context: AbilityContext) {
+        *battle.get_rng_mut() = context.rng;
+        if context.defender_damage > 0 && !battle.is_finished() {
+            battle.apply_damage(self.defender, context.defender_damage);
+        }
+        if context.recoil > 0 && !battle.is_finished() {
+            battle.apply_damage(self.attacker, context.recoil);
+        }
+    }
+
+    fn apply_damage(&mut self, battle: &mut Battle) {
+        let damage = self.inspector.breakdown.unwrap().damage;
+        if self.inspector.hit_outcome == Some(HitOutcome::HitSubstitute) {
+            let defender_data = battle.get_battler_mut(self.defender);
+            let lost = defender_data.damage_substitute(damage);
+            let remaining_health = defender_data.get_substitute_health();
+            battle.push_event(BattleEvent::SubstituteDamaged { battler: self.defender, amount: lost, remaining_health });
+            self.inspector.damage_dealt = Some(lost);
+            return;
+        }
+        battle.apply_damage(self.defender, damage);
+        self.inspector.damage_dealt = Some(damage);
+    }
+}
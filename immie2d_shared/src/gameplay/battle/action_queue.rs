@@ -0,0 +1,143 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+
+use super::battle_command::BattleCommand;
+
+/// Priority of switch commands, which go before every other command of the turn.
+pub const SWITCH_PRIORITY: i32 = 1;
+/// Priority of every other command of the turn.
+pub const COMMAND_PRIORITY: i32 = 0;
+/// Priority of actions that run before anything else queued. See ActionQueue::interrupt()
+pub const INTERRUPT_PRIORITY: i32 = i32::MAX;
+/// Priority of actions put back to run once the interrupts queued before them have run.
+const RESUME_PRIORITY: i32 = i32::MAX - 1;
+
+/* Something that happens while a turn is resolved. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum BattleAction {
+    /// A command a side chose for the turn, with its index in the turn's commands to report it being rejected.
+    Command { index: usize, command: BattleCommand },
+    /// Use an ability on a side that is switching out, before it leaves. Replaces the command at `index`.
+    /// See AbilityFlags::INTERCEPTS_SWITCH
+    SwitchIntercept { index: usize, side: usize, ability_slot: usize, target_side: usize },
+    /// Use an ability in reaction to another action, such as a counter or a trait activating. Skipped if the ability
+    /// can't be used, since no side chose it.
+    ReactiveAbility { side: usize, ability_slot: usize, target_side: usize, power_multiplier: f32 }
+}
+
+/* An action waiting in the queue. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct QueuedAction {
+    pub action: BattleAction,
+    pub priority: i32,
+    /// Position of the acting side in the turn order, where lower acts first. See Battle::get_turn_order()
+    pub speed_rank: usize,
+    /// Whether the action was put back behind interrupts, which only happens once.
+    pub is_resumed: bool,
+    sequence: u64
+}
+
+impl Eq for QueuedAction {}
+
+impl Ord for QueuedAction {
+    /// Higher priority first, then faster sides, then in the order queued. Resumed actions run in the reverse order
+    /// they were put back, so an interrupt that was itself interrupted finishes before the action it interrupted.
+    fn cmp(&self, other: &QueuedAction) -> Ordering {
+        let sequence = match self.priority {
+            RESUME_PRIORITY => self.sequence.cmp(&other.sequence),
+            _ => other.sequence.cmp(&self.sequence)
+        };
+        return self.priority.cmp(&other.priority).then(other.speed_rank.cmp(&self.speed_rank)).then(sequence);
+    }
+}
+
+impl PartialOrd for QueuedAction {
+    fn partial_cmp(&self, other: &QueuedAction) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+/* Actions left to run this turn. Effects can queue reactions mid-turn, which is how mechanics respond to other actions.
+See BattleRulesPlugin::pre_action() and BattleRulesPlugin::post_action() */
+pub struct ActionQueue {
+    actions: BinaryHeap<QueuedAction>,
+    next_sequence: u64
+}
+
+impl ActionQueue {
+    pub fn new() -> ActionQueue {
+        return ActionQueue { actions: BinaryHeap::new(), next_sequence: 0 };
+    }
+
+    fn push_queued(&mut self, action: BattleAction, priority: i32, speed_rank: usize, is_resumed: bool) {
+        self.actions.push(QueuedAction { action, priority, speed_rank, is_resumed, sequence: self.next_sequence });
+        self.next_sequence += 1;
+    }
+
+    /// Queue an action to run by priority and speed rank. Actions with the same priority and speed rank run in the
+    /// order they were queued.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::action_queue::{ActionQueue, BattleAction, COMMAND_PRIORITY, SWITCH_PRIORITY};
+    /// use immie2d_shared::gameplay::battle::battle_command::BattleCommand;
+    ///
+    /// let command = |index: usize, command: BattleCommand| BattleAction::Command { index, command };
+    /// let mut queue = ActionQueue::new();
+    /// queue.push(command(0, BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 }), COMMAND_PRIORITY, 1);
+    /// queue.push(command(1, BattleCommand::UseAbility { side: 1, ability_slot: 0, target_side: 0 }), COMMAND_PRIORITY, 0);
+    /// queue.push(command(2, BattleCommand::Switch { side: 0, slot: 1 }), SWITCH_PRIORITY, 1);
+    /// let counter = BattleAction::ReactiveAbility { side: 1, ability_slot: 0, target_side: 0, power_multiplier: 1.0 };
+    /// queue.interrupt(counter);
+    ///
+    /// assert_eq!(queue.pop().unwrap().action, counter);
+    /// let order: Vec<usize> = std::iter::from_fn(|| queue.pop()).map(|queued| match queued.action {
+    ///     BattleAction::Command { index, .. } => index,
+    ///     _ => unreachable!()
+    /// }).collect();
+    /// assert_eq!(order, vec![2, 1, 0]);
+    /// ```
+    pub fn push(&mut self, action: BattleAction, priority: i32, speed_rank: usize) {
+        self.push_queued(action, priority, speed_rank, false);
+    }
+
+    /// Queue an action to run before anything else queued, after any other interrupts.
+    pub fn interrupt(&mut self, action: BattleAction) {
+        self.push_queued(action, INTERRUPT_PRIORITY, 0, false);
+    }
+
+    /// Put an action back to run once every interrupt queued so far has run.
+    pub fn resume(&mut self, queued: QueuedAction) {
+        self.push_queued(queued.action, RESUME_PRIORITY, 0, true);
+    }
+
+    pub fn pop(&mut self) -> Option<QueuedAction> {
+        return self.actions.pop();
+    }
+
+    /// Whether an interrupt is waiting to run.
+    pub fn has_interrupts(&self) -> bool {
+        return self.actions.peek().is_some_and(|queued| queued.priority == INTERRUPT_PRIORITY);
+    }
+
+    /// Remove every queued action matching a predicate, returning them in the order they would have run.
+    pub fn take_where<F: Fn(&BattleAction) -> bool>(&mut self, predicate: F) -> Vec<QueuedAction> {
+        let mut taken: Vec<QueuedAction> = self.actions.iter().filter(|queued| predicate(&queued.action)).copied().collect();
+        self.actions.retain(|queued| !predicate(&queued.action));
+        taken.sort_by(|a, b| b.cmp(a));
+        return taken;
+    }
+
+    /// Every queued action in the order it would run.
+    pub fn get_pending(&self) -> Vec<BattleAction> {
+        let mut pending: Vec<QueuedAction> = self.actions.iter().copied().collect();
+        pending.sort_by(|a, b| b.cmp(a));
+        return pending.iter().map(|queued| queued.action).collect();
+    }
+
+    pub fn len(&self) -> usize {
+        return self.actions.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.actions.is_empty();
+    }
+}
@@ -0,0 +1,1270 @@
+use std::sync::Arc;
+
+use bytes::{BufMut, BytesMut};
+use sha2::{Digest, Sha256};
+
+use crate::engine_types::game_rng::GameRng;
+use crate::gameplay::ability::{ability::{Ability, BaseAbilityData}, ability_flags::AbilityFlags, ability_map::AbilityMap};
+use crate::gameplay::capture::{capture_attempt::CaptureAttempt, capture_device::CaptureDevice};
+use crate::gameplay::game_rules::GameRules;
+use crate::gameplay::immie::bond::BondEvent;
+use crate::gameplay::species::species_map::SpeciesMap;
+
+use super::ability_pipeline::AbilityPipeline;
+use super::action_queue::{ActionQueue, BattleAction, QueuedAction, COMMAND_PRIORITY, SWITCH_PRIORITY};
+use super::battle_command::{BattleCommand, BattleCommandError};
+use super::battle_event::BattleEvent;
+use super::battle_format::BattleFormat;
+use super::battle_side::BattleSide;
+use super::battler::Battler;
+use super::battler_id::BattlerId;
+use super::campaign::{Boon, CarryOver};
+use super::damage::{apply_weather, DamageContext};
+use super::effect_order::{EffectOrderRegistry, RULES_EFFECT, WEATHER_EFFECT};
+use super::entry_hazard::{get_spikes_damage, HazardKind, WEBS_EVASION_STAGES};
+use super::field_state::FieldState;
+use super::forced_action::{ForcedAction, ForcedActionKind, CHARGE_TURNS, LOCKED_IN_TURNS, RECHARGE_TURNS};
+use super::hit_resolution::SemiInvulnerability;
+use super::rules::battle_rules_plugin::{BattleRulesPlugin, StandardRules};
+use super::state_diff::BattleStateSnapshot;
+use super::state_hash::{put_flag, put_format, put_option, put_str, put_usize};
+
+/// Prefix of the data hashed for a battle's state hash, so it can't be mistaken for any other hash.
+const STATE_HASH_DOMAIN: &[u8] = b"immie2d-battle-state";
+
+/// Power multiplier of an ability intercepting a switch. See Battle::resolve_turn()
+pub const SWITCH_INTERCEPT_POWER_MULTIPLIER: f32 = 2.0;
+
+/* A single battle between sides. Events are accumulated until they are taken to be sent to the clients. Each action's
+changes to battlers that no event announced are announced after it with canonical events, so clients never miss a
+change. See BattleStateSnapshot::diff() */
+pub struct Battle {
+    format: BattleFormat,
+    sides: Vec<BattleSide>,
+    rules: Arc<dyn BattleRulesPlugin>,
+    events: Vec<BattleEvent>,
+    turn: u32,
+    is_finished: bool,
+    winner: Option<usize>,
+    rng: GameRng,
+    /// Seed of the speed tie breaks. Kept apart from the rng so checking the turn order never changes a roll.
+    tie_break_seed: u64,
+    field: FieldState,
+    effect_order: Arc<EffectOrderRegistry>,
+    /// Index of the first event of the action resolve_turn() ran last. See Battle::get_action_events()
+    action_first_event: usize
+}
+
+impl Battle {
+    /// Create a battle from the sides taking part in it, using the standard rules.
+    /// Will panic if the number of sides doesn't match the format.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battle_side::BattleSide, battle_format::BattleFormat};
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species)]);
+    /// let battle = Battle::new(BattleFormat::Single, vec![side.clone(), side.clone()]);
+    /// assert_eq!(battle.get_side_count(), 2);
+    /// ```
+    pub fn new(format: BattleFormat, sides: Vec<BattleSide>) -> Battle {
+        assert!(sides.len() == format.get_participant_count() as usize, "Battle format {:?} requires {} sides. Got {}", format, format.get_participant_count(), sides.len());
+        return Battle {
+            format,
+            sides,
+            rules: Arc::new(StandardRules),
+            events: Vec::new(),
+            turn: 1,
+            is_finished: false,
+            winner: None,
+            rng: GameRng::new(0),
+            tie_break_seed: 0,
+            field: FieldState::default(),
+            effect_order: EffectOrderRegistry::get_standard(),
+            action_first_event: 0
+        };
+    }
+
+    /// Use a different ruleset for this battle. See BattleRuleset::create_plugin()
+    pub fn with_rules(mut self, rules: Arc<dyn BattleRulesPlugin>) -> Battle {
+        self.rules = rules;
+        return self;
+    }
+
+    /// Seed the random number generator of this battle, and the speed tie breaks. See SeedNegotiation for agreeing on
+    /// a seed with a client.
+    pub fn with_seed(mut self, seed: u64) -> Battle {
+        self.rng = GameRng::new(seed);
+        self.tie_break_seed = seed;
+        return self;
+    }
+
+    /// The random number generator of this battle. Anything random in the battle should use it so the battle can be replayed from its seed.
+    pub fn get_rng_mut(&mut self) -> &mut GameRng {
+        return &mut self.rng;
+    }
+
+    /// Start the battle with field conditions, such as the weather of the region it started in.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::ability::{ability::Ability, abilities::fireball::Fireball};
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::field_state::FieldState;
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::Weather;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(200, 60, 40, 70));
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 30, AbilityNames::default()), &species)]);
+    /// let fireball = Fireball::new();
+    ///
+    /// let mut clear = Battle::new(BattleFormat::Single, vec![side.clone(), side.clone()]);
+    /// let mut rain = Battle::new(BattleFormat::Single, vec![side.clone(), side]).with_field(FieldState::from_weather(Weather::Rain));
+    /// assert_eq!(rain.get_field().weather, Weather::Rain);
+    /// let clear_damage = clear.use_ability(BattlerId::new(0, 0), BattlerId::new(1, 0), fireball.get_base_ability_data());
+    /// let rain_damage = rain.use_ability(BattlerId::new(0, 0), BattlerId::new(1, 0), fireball.get_base_ability_data());
+    /// assert!(rain_damage < clear_damage);
+    /// ```
+    pub fn with_field(mut self, field: FieldState) -> Battle {
+        self.field = field;
+        return self;
+    }
+
+    pub fn get_field(&self) -> FieldState {
+        return self.field;
+    }
+
+    /// Resolve effect hooks in a different order, such as one with content effects registered. Every effect the engine
+    /// hooks in must stay registered. See EffectOrderRegistry::standard()
+    pub fn with_effect_order(mut self, effect_order: Arc<EffectOrderRegistry>) -> Battle {
+        self.effect_order = effect_order;
+        return self;
+    }
+
+    pub fn get_effect_order(&self) -> &EffectOrderRegistry {
+        return &self.effect_order;
+    }
+
+    pub fn get_rules(&self) -> &dyn BattleRulesPlugin {
+        return self.rules.as_ref();
+    }
+
+    /// Let the weather and the battle's rules modify damage inputs, in the battle's effect order. The ability pipeline
+    /// also runs ability scripts among them, so this is for previewing damage without using an ability.
+    pub fn apply_damage_effects(&self, context: &mut DamageContext) {
+        let mut effects = [WEATHER_EFFECT, RULES_EFFECT];
+        self.effect_order.sort(&mut effects, |effect| *effect);
+        for effect in effects {
+            match effect {
+                WEATHER_EFFECT => apply_weather(self, context),
+                _ => self.rules.pre_damage(self, context)
+            }
+        }
+    }
+
+    /// Shared handle to the rules, so hooks can be given mutable access to the battle.
+    pub(crate) fn get_rules_handle(&self) -> Arc<dyn BattleRulesPlugin> {
+        return self.rules.clone();
+    }
+
+    pub(crate) fn get_battler_mut(&mut self, battler: BattlerId) -> &mut Battler {
+        return self.sides[battler.side].get_battler_mut(battler.slot);
+    }
+
+    pub(crate) fn push_event(&mut self, event: BattleEvent) {
+        self.events.push(event);
+    }
+
+    /// Run an action, then emit canonical events for whatever it changed without announcing. They go before the end of
+    /// the battle if the action ended it, so BattleEnded is always the last event.
+    fn with_state_events<T>(&mut self, action: impl FnOnce(&mut Battle) -> T) -> T {
+        let before = BattleStateSnapshot::capture(self);
+        let first_event = self.events.len();
+        let result = action(self);
+        let missed = before.diff(&self.events[first_event..], &BattleStateSnapshot::capture(self));
+        let at = match self.events.last() {
+            Some(BattleEvent::BattleEnded { .. }) => self.events.len() - 1,
+            _ => self.events.len()
+        };
+        self.events.splice(at..at, missed);
+        return result;
+    }
+
+    /// The current turn, starting from 1.
+    pub fn get_turn(&self) -> u32 {
+        return self.turn;
+    }
+
+    pub fn get_format(&self) -> BattleFormat {
+        return self.format;
+    }
+
+    /// Get a side by index. Will panic if the index is out of bounds.
+    pub fn get_side(&self, side: usize) -> &BattleSide {
+        return &self.sides[side];
+    }
+
+    pub fn get_side_count(&self) -> usize {
+        return self.sides.len();
+    }
+
+    /// Get a battler by id. Will panic if the id is out of bounds.
+    pub fn get_battler(&self, battler: BattlerId) -> &Battler {
+        return self.sides[battler.side].get_battler(battler.slot);
+    }
+
+    /// Get the id of the active battler of a side.
+    pub fn get_active_battler_id(&self, side: usize) -> BattlerId {
+        return BattlerId::new(side, self.sides[side].get_active_slot());
+    }
+
+    /// The multi-turn ability the active battler of a side is forced to continue, if any. Clients disable every
+    /// command other than BattleCommand::Continue while a side has one.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability::{AbilityCategory, BaseAbilityData}, ability_flags::AbilityFlags};
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat, battle_event::BattleEvent};
+    /// use immie2d_shared::gameplay::battle::battle_command::{BattleCommand, BattleCommandError};
+    /// use immie2d_shared::gameplay::battle::forced_action::ForcedActionKind;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(500, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_data_ability("solar_flare", BaseAbilityData {
+    ///     category: AbilityCategory::Attack,
+    ///     types: Elements::new(vec![ElementKind::Fire]),
+    ///     power: 120.0,
+    ///     speed: 1.0,
+    ///     max_uses: 5,
+    ///     accuracy: 100,
+    ///     flags: AbilityFlags::CHARGES | AbilityFlags::RECHARGES,
+    ///     combo: None
+    /// });
+    /// let abilities = AbilityNames::new(vec![GlobalString::new(&"solar_flare".to_string())]);
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, abilities), &species)]);
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![side.clone(), side]);
+    /// let (user, target) = (BattlerId::new(0, 0), BattlerId::new(1, 0));
+    /// let use_flare = BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 };
+    ///
+    /// // Charging on the first turn
+    /// battle.apply_command(use_flare, &ability_map, &species_map).unwrap();
+    /// assert!(battle.take_events().contains(&BattleEvent::MultiTurnProgress { battler: user, kind: ForcedActionKind::Charging, turn: 1, total_turns: 2 }));
+    /// assert_eq!(battle.get_battler(target).get_health(), 500);
+    /// assert_eq!(battle.apply_command(use_flare, &ability_map, &species_map), Err(BattleCommandError::ForcedAction));
+    /// assert_eq!(battle.apply_command(BattleCommand::Switch { side: 0, slot: 0 }, &ability_map, &species_map), Err(BattleCommandError::ForcedAction));
+    ///
+    /// // Hitting on the second, then recharging on the third
+    /// battle.apply_command(BattleCommand::Continue { side: 0 }, &ability_map, &species_map).unwrap();
+    /// assert!(battle.get_battler(target).get_health() < 500);
+    /// assert!(battle.take_events().contains(&BattleEvent::MultiTurnProgress { battler: user, kind: ForcedActionKind::Recharging, turn: 0, total_turns: 1 }));
+    /// assert_eq!(battle.get_forced_action(0).unwrap().kind, ForcedActionKind::Recharging);
+    /// battle.apply_command(BattleCommand::Continue { side: 0 }, &ability_map, &species_map).unwrap();
+    /// assert_eq!(battle.get_forced_action(0), None);
+    /// assert_eq!(battle.apply_command(BattleCommand::Continue { side: 0 }, &ability_map, &species_map), Err(BattleCommandError::NoForcedAction));
+    /// assert_eq!(battle.get_battler(user).get_immie().ability_uses_spent[0], 1);
+    /// ```
+    pub fn get_forced_action(&self, side: usize) -> Option<ForcedAction> {
+        if side >= self.sides.len() || self.sides[side].is_eliminated() {
+            return None;
+        }
+        return self.sides[side].get_active().get_forced_action();
+    }
+
+    pub fn is_finished(&self) -> bool {
+        return self.is_finished;
+    }
+
+    /// The winning side, if the battle has finished with one. When raid players win, this is the first player side
+    /// still standing.
+    pub fn get_winner(&self) -> Option<usize> {
+        return self.winner;
+    }
+
+    /// The state a side takes from this battle into the next battle of a campaign run. Each Immie keeps the health it
+    /// lost, its status and its spent ability uses, and every boon has one battle less left. Transformations are never
+    /// carried over. Will panic if the battle hasn't ended or the side is out of bounds.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::campaign::{Boon, BoonKind, CarryOver};
+    /// use immie2d_shared::gameplay::status_condition::StatusCondition;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(100, 50, 50, 50));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let mut immie = Immie::new(species.name, 10, AbilityNames::default());
+    /// immie.status = Some(StatusCondition::Burn);
+    /// let run = CarryOver::new(vec![immie])
+    ///     .with_boon(Boon { kind: BoonKind::Attack, battles: 1 })
+    ///     .with_boon(Boon { kind: BoonKind::Speed, battles: 3 });
+    ///
+    /// let wild = BattleSide::new(vec![Battler::new(Immie::new(species.name, 3, AbilityNames::default()), &species)]);
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![run.create_side(&species_map), wild]);
+    /// assert_eq!(battle.get_battler(BattlerId::new(0, 0)).get_stats().attack, 60);
+    /// battle.apply_damage(BattlerId::new(0, 0), 70);
+    /// battle.apply_damage(BattlerId::new(1, 0), 100);
+    ///
+    /// let mut run = battle.get_carry_over(0);
+    /// assert_eq!(run.team[0].get_health(&species), 30);
+    /// assert_eq!(run.team[0].status, Some(StatusCondition::Burn));
+    /// assert_eq!(run.boons, vec![Boon { kind: BoonKind::Speed, battles: 2 }]);
+    /// run.rest(&species_map);
+    /// assert_eq!(run.team[0].get_health(&species), 55);
+    /// assert!(!run.is_defeated(&species_map));
+    /// ```
+    pub fn get_carry_over(&self, side: usize) -> CarryOver {
+        assert!(self.is_finished, "Cannot carry over from a battle that hasn't ended");
+        let side = &self.sides[side];
+        let team = side.get_team().iter().map(|battler| battler.get_carried_immie()).collect();
+        let boons = side.get_boons().iter().filter(|boon| boon.battles > 1).map(|boon| Boon { kind: boon.kind, battles: boon.battles - 1 }).collect();
+        return CarryOver { team, boons };
+    }
+
+    /// Get the sides that have not been eliminated, in order of acting this turn.
+    /// Sides act by the speed of their active battler, fastest first. Equal speeds are ordered by a tie break key
+    /// drawn from the battle's seed, the turn and the side, then by side index. The keys are the same for every query
+    /// within a turn and on every platform, and don't consume the battle's rng. See get_speed_tie_key()
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battle_side::BattleSide, battle_format::BattleFormat};
+    ///
+    /// let slow = SpeciesData::new(GlobalString::new(&"slow".to_string()), Elements::new(vec![ElementKind::Ground]), BaseStats::new(50, 50, 50, 10));
+    /// let fast = SpeciesData::new(GlobalString::new(&"fast".to_string()), Elements::new(vec![ElementKind::Air]), BaseStats::new(50, 50, 50, 90));
+    /// let side = |species: &SpeciesData| BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::default()), species)]);
+    /// let battle = Battle::new(BattleFormat::free_for_all(3), vec![side(&slow), side(&fast), side(&slow)]).with_seed(7);
+    /// let order = battle.get_turn_order();
+    /// assert_eq!(order[0], 1);
+    /// assert_eq!(order, battle.get_turn_order());
+    ///
+    /// // The same seed always breaks the tie the same way, while different seeds don't always agree
+    /// let first_tied = |seed: u64| Battle::new(BattleFormat::free_for_all(3), vec![side(&slow), side(&fast), side(&slow)]).with_seed(seed).get_turn_order()[1];
+    /// assert_eq!(first_tied(7), order[1]);
+    /// assert!((0..16).any(|seed| first_tied(seed) == 0) && (0..16).any(|seed| first_tied(seed) == 2));
+    /// ```
+    pub fn get_turn_order(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.sides.len()).filter(|side| !self.sides[*side].is_eliminated()).collect();
+        order.sort_by(|a, b| {
+            let a_speed = self.sides[*a].get_active().get_stats().speed;
+            let b_speed = self.sides[*b].get_active().get_stats().speed;
+            return b_speed.cmp(&a_speed).then(self.get_speed_tie_key(*a).cmp(&self.get_speed_tie_key(*b))).then(a.cmp(b));
+        });
+        return order;
+    }
+
+    /// Key ordering a side among the sides it ties on speed with this turn, where lower acts first. Every turn and
+    /// side gets a distinct key, so a tie never falls through to side order and no side is always favoured.
+    fn get_speed_tie_key(&self, side: usize) -> u64 {
+        return GameRng::new(self.tie_break_seed ^ ((self.turn as u64) << 32) ^ side as u64).next_u64();
+    }
+
+    /// Get the sides that a side is allowed to target, which is every side that is not an ally and has not been eliminated.
+    pub fn get_valid_targets(&self, side: usize) -> Vec<usize> {
+        return (0..self.sides.len()).filter(|target| !self.format.are_allies(side, *target) && !self.sides[*target].is_eliminated()).collect();
+    }
+
+    /// Whether every side that has not been eliminated is on the same team, which ends the battle.
+    fn is_one_team_remaining(&self) -> bool {
+        let remaining: Vec<usize> = (0..self.sides.len()).filter(|side| !self.sides[*side].is_eliminated()).collect();
+        return remaining.iter().all(|side| self.format.are_allies(remaining[0], *side));
+    }
+
+    /// Transform a battler into the alternate form of its species, emitting BattleEvent::Transformed.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::{SpeciesData, TransformationData}, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat, battle_event::BattleEvent};
+    ///
+    /// let stone = GlobalString::new(&"lava stone".to_string());
+    /// let form_name = GlobalString::new(&"mega lavapup".to_string());
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70))
+    ///     .with_transformation(TransformationData {
+    ///         form_name,
+    ///         required_item: stone,
+    ///         elements: Elements::new(vec![ElementKind::Fire, ElementKind::Dragon]),
+    ///         base_stats: BaseStats::new(50, 90, 60, 80)
+    ///     });
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let mut immie = Immie::new(species.name, 5, AbilityNames::default());
+    /// let opponent = BattleSide::new(vec![Battler::new(immie, &species)]);
+    /// immie.held_item = Some(stone);
+    ///
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![BattleSide::new(vec![Battler::new(immie, &species)]), opponent]);
+    /// let battler = BattlerId::new(0, 0);
+    /// battle.transform(battler, &species_map);
+    /// assert!(battle.get_battler(battler).get_elements().has_elements(ElementKind::Dragon));
+    /// assert_eq!(battle.take_events(), vec![BattleEvent::Transformed { battler, form_name }]);
+    ///
+    /// battle.end();
+    /// assert!(!battle.get_battler(battler).is_transformed());
+    /// assert_eq!(battle.take_events(), vec![BattleEvent::Reverted { battler }, BattleEvent::BattleEnded { winner: None }]);
+    /// ```
+    /// Will panic if the battler cannot transform, such as transforming a second time.
+    /// ``` should_panic
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// # use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species)]);
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![side.clone(), side]);
+    /// // Will panic
+    /// battle.transform(BattlerId::new(0, 0), &species_map);
+    /// ```
+    pub fn transform(&mut self, battler: BattlerId, species_map: &SpeciesMap) {
+        assert!(!self.is_finished, "Cannot transform a battler after the battle has ended");
+        let battler_data = self.sides[battler.side].get_battler_mut(battler.slot);
+        let species = species_map.get_species(battler_data.get_immie().species);
+        battler_data.transform(species);
+        self.events.push(BattleEvent::Transformed { battler, form_name: species.transformation.unwrap().form_name });
+    }
+
+    /// Attune a battler, making the element of its Immie's attunement its only element for attacking and defending for
+    /// the rest of the battle. Emits BattleEvent::Attuned. Each side may attune once per battle.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat, battle_event::BattleEvent};
+    /// use immie2d_shared::gameplay::battle::battle_command::{BattleCommand, BattleCommandError};
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let mut immie = Immie::new(species.name, 5, AbilityNames::default());
+    /// let opponent = BattleSide::new(vec![Battler::new(immie, &species)]);
+    /// immie.attunement = Some(ElementKind::Water);
+    /// let team = BattleSide::new(vec![Battler::new(immie, &species), Battler::new(immie, &species)]);
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![team, opponent]);
+    /// let ability_map = AbilityMap::new();
+    ///
+    /// assert_eq!(battle.apply_command(BattleCommand::Attune { side: 1 }, &ability_map, &species_map), Err(BattleCommandError::CannotAttune));
+    /// battle.apply_command(BattleCommand::Attune { side: 0 }, &ability_map, &species_map).unwrap();
+    /// let battler = BattlerId::new(0, 0);
+    /// assert_eq!(battle.take_events(), vec![BattleEvent::Attuned { battler, element: ElementKind::Water }]);
+    /// assert!(battle.get_battler(battler).get_elements().has_elements(ElementKind::Water));
+    ///
+    /// // Attuning lasts through switching out, and the side can't attune another battler
+    /// battle.apply_command(BattleCommand::Switch { side: 0, slot: 1 }, &ability_map, &species_map).unwrap();
+    /// assert_eq!(battle.get_battler(battler).get_attuned_element(), Some(ElementKind::Water));
+    /// assert_eq!(battle.apply_command(BattleCommand::Attune { side: 0 }, &ability_map, &species_map), Err(BattleCommandError::CannotAttune));
+    /// ```
+    /// Will panic if the side has already attuned or the battler can't attune. See Battler::can_attune()
+    pub fn attune(&mut self, battler: BattlerId) {
+        assert!(!self.is_finished, "Cannot attune a battler after the battle has ended");
+        self.sides[battler.side].attune(battler.slot);
+        let element = self.get_battler(battler).get_attuned_element().unwrap();
+        self.events.push(BattleEvent::Attuned { battler, element });
+    }
+
+    /// Throw a capture device at a battler, emitting the shake events of the attempt.
+    /// A successful capture ends the battle. Returns if the capture succeeded.
+    pub fn capture(&mut self, battler: BattlerId, device: &CaptureDevice, rules: &GameRules, species_map: &SpeciesMap, rng: &mut GameRng) -> bool {
+        assert!(!self.is_finished, "Cannot capture after the battle has ended");
+        let target = self.get_battler(battler);
+        let catch_rate = species_map.get_species(target.get_immie().species).catch_rate;
+        let attempt = CaptureAttempt::roll(rules, device, target, catch_rate, rng);
+        self.events.append(&mut attempt.to_events(battler));
+        if attempt.is_success {
+            self.end();
+        }
+        return attempt.is_success;
+    }
+
+    /// Have an attacker use an ability on a defender, dealing damage through the rules' pre-damage hook.
+    /// Returns the damage dealt, including damage to a substitute. Status abilities deal no damage.
+    /// Runs every stage of an AbilityPipeline at once. Step through the pipeline directly to inspect intermediate values.
+    /// See resolve_hit() for how protection, deflection and substitutes interact with the ability.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::ability::{ability::Ability, abilities::fireball::Fireball};
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    ///
+    /// let fire = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(80, 60, 40, 70));
+    /// let nature = SpeciesData::new(GlobalString::new(&"sproutle".to_string()), Elements::new(vec![ElementKind::Nature]), BaseStats::new(80, 60, 40, 70));
+    /// let side = |species: &SpeciesData| BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, AbilityNames::default()), species)]);
+    /// let fireball = Fireball::new();
+    ///
+    /// let mut standard = Battle::new(BattleFormat::Single, vec![side(&fire), side(&nature)]);
+    /// let super_effective = standard.use_ability(BattlerId::new(0, 0), BattlerId::new(1, 0), fireball.get_base_ability_data());
+    ///
+    /// let mut inverse = Battle::new(BattleFormat::Single, vec![side(&fire), side(&nature)]).with_rules(BattleRuleset::InverseTypes.create_plugin());
+    /// let not_very_effective = inverse.use_ability(BattlerId::new(0, 0), BattlerId::new(1, 0), fireball.get_base_ability_data());
+    /// assert!(super_effective > not_very_effective);
+    /// ```
+    pub fn use_ability(&mut self, attacker: BattlerId, defender: BattlerId, ability: &BaseAbilityData) -> u32 {
+        return AbilityPipeline::new(attacker, defender, ability).run(self);
+    }
+
+    /// Preview the type chart multiplier of an attacker's ability against a defender under this battle's rules,
+    /// without using the ability. See preview_effectiveness()
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::ability::{ability::Ability, abilities::fireball::Fireball};
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    /// use immie2d_shared::gameplay::elements::type_chart::{SUPER_EFFECTIVE, NOT_VERY_EFFECTIVE};
+    ///
+    /// let fire = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(80, 60, 40, 70));
+    /// let nature = SpeciesData::new(GlobalString::new(&"sproutle".to_string()), Elements::new(vec![ElementKind::Nature]), BaseStats::new(80, 60, 40, 70));
+    /// let side = |species: &SpeciesData| BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, AbilityNames::default()), species)]);
+    /// let fireball = Fireball::new();
+    ///
+    /// let standard = Battle::new(BattleFormat::Single, vec![side(&fire), side(&nature)]);
+    /// assert_eq!(standard.preview_effectiveness(BattlerId::new(0, 0), BattlerId::new(1, 0), fireball.get_base_ability_data()), SUPER_EFFECTIVE);
+    /// let inverse = Battle::new(BattleFormat::Single, vec![side(&fire), side(&nature)]).with_rules(BattleRuleset::InverseTypes.create_plugin());
+    /// assert_eq!(inverse.preview_effectiveness(BattlerId::new(0, 0), BattlerId::new(1, 0), fireball.get_base_ability_data()), NOT_VERY_EFFECTIVE);
+    /// // Nothing was used
+    /// assert_eq!(inverse.get_battler(BattlerId::new(1, 0)).get_health(), 80);
+    /// ```
+    pub fn preview_effectiveness(&self, attacker: BattlerId, defender: BattlerId, ability: &BaseAbilityData) -> f32 {
+        let mut context = DamageContext::new(self, attacker, defender, ability);
+        self.apply_damage_effects(&mut context);
+        return context.effectiveness;
+    }
+
+    /// Switch the active battler of a side, calling the rules' pre-switch and on-switch hooks. The switch doesn't
+    /// happen if the battle ends in the pre-switch hook. Switching out cancels any forced action, and the battler
+    /// switching in is affected by the hazards on its side before the on-switch hook.
+    /// Will panic if the slot cannot be switched to. See BattleSide::switch_active()
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::{ability_names::AbilityNames, ability_map::AbilityMap};
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::ability::{ability::{AbilityCategory, BaseAbilityData}, ability_flags::AbilityFlags};
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::{battle_command::BattleCommand, battle_event::BattleEvent, entry_hazard::HazardKind};
+    ///
+    /// let hazard = |flags: AbilityFlags| BaseAbilityData {
+    ///     category: AbilityCategory::Status, types: Elements::new(vec![ElementKind::Ground]), power: 0.0, speed: 1.0, max_uses: 10, accuracy: 100, flags, combo: None
+    /// };
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_data_ability("spikes", hazard(AbilityFlags::SETS_SPIKES));
+    /// ability_map.add_data_ability("sticky_web", hazard(AbilityFlags::SETS_WEBS));
+    /// ability_map.add_data_ability("sweep", hazard(AbilityFlags::CLEARS_HAZARDS));
+    /// let grounded = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Ground]), BaseStats::new(400, 60, 40, 70));
+    /// let flying = SpeciesData::new(GlobalString::new(&"breezel".to_string()), Elements::new(vec![ElementKind::Air]), BaseStats::new(400, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(grounded);
+    /// species_map.add_species(flying);
+    /// let names = ["spikes", "sticky_web", "sweep"].map(|name| GlobalString::new(&name.to_string()));
+    /// let battler = |species: &SpeciesData| Battler::new(Immie::new(species.name, 20, AbilityNames::new(names.to_vec())), species);
+    /// let team = vec![battler(&grounded), battler(&grounded), battler(&flying)];
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![BattleSide::new(team.clone()), BattleSide::new(team)]);
+    /// let use_ability = |battle: &mut Battle, side: usize, ability_slot: usize| {
+    ///     battle.apply_command(BattleCommand::UseAbility { side, ability_slot, target_side: 1 - side }, &ability_map, &species_map).unwrap();
+    /// };
+    ///
+    /// use_ability(&mut battle, 0, 0);
+    /// use_ability(&mut battle, 0, 0);
+    /// use_ability(&mut battle, 0, 1);
+    /// assert_eq!(battle.get_side(1).get_hazards().get_layers(HazardKind::Spikes), 2);
+    /// assert!(battle.take_events().contains(&BattleEvent::HazardSet { side: 1, kind: HazardKind::Webs, layers: 1 }));
+    ///
+    /// battle.apply_command(BattleCommand::Switch { side: 1, slot: 1 }, &ability_map, &species_map).unwrap();
+    /// let switched_in = BattlerId::new(1, 1);
+    /// assert_eq!(battle.get_battler(switched_in).get_health(), 300);
+    /// assert_eq!(battle.get_battler(switched_in).get_evasion_stage(), -1);
+    /// assert!(battle.take_events().contains(&BattleEvent::HazardTriggered { battler: switched_in, kind: HazardKind::Spikes, layers: 2 }));
+    ///
+    /// // Air battlers fly over spikes, but still get caught in webs
+    /// battle.apply_command(BattleCommand::Switch { side: 1, slot: 2 }, &ability_map, &species_map).unwrap();
+    /// assert_eq!(battle.get_battler(BattlerId::new(1, 2)).get_health(), 400);
+    /// let flyer = BattlerId::new(1, 2);
+    /// assert_eq!(battle.take_events(), vec![
+    ///     BattleEvent::Switched { side: 1, slot: 2 },
+    ///     BattleEvent::HazardAvoided { battler: flyer, kind: HazardKind::Spikes },
+    ///     BattleEvent::HazardTriggered { battler: flyer, kind: HazardKind::Webs, layers: 1 },
+    ///     // Evasion changes aren't announced by the switch itself, so canonical events follow it
+    ///     BattleEvent::EvasionStageChanged { battler: switched_in, stage: 0 },
+    ///     BattleEvent::EvasionStageChanged { battler: flyer, stage: -1 }
+    /// ]);
+    ///
+    /// use_ability(&mut battle, 1, 2);
+    /// assert!(battle.get_side(1).get_hazards().is_empty());
+    /// assert_eq!(battle.take_events(), vec![BattleEvent::HazardsCleared { side: 1 }]);
+    /// ```
+    pub fn switch(&mut self, side: usize, slot: usize) {
+        assert!(!self.is_finished, "Cannot switch after the battle has ended");
+        self.with_state_events(|battle| {
+            let rules = battle.rules.clone();
+            rules.pre_switch(battle, battle.get_active_battler_id(side));
+            if battle.is_finished {
+                return;
+            }
+            let outgoing = battle.get_active_battler_id(side);
+            battle.cancel_forced_action(outgoing);
+            battle.get_battler_mut(outgoing).clear_volatile_state();
+            battle.sides[side].switch_active(slot);
+            battle.events.push(BattleEvent::Switched { side, slot });
+            battle.trigger_hazards(BattlerId::new(side, slot));
+            if battle.is_finished {
+                return;
+            }
+            rules.on_switch(battle, BattlerId::new(side, slot));
+        });
+    }
+
+    /// Finish the current turn, calling the rules' post-turn hook. Protection only lasts for the turn it was used, and
+    /// lock ons run out after the turn following the one they were made on. In free-for-all battles, every fainted
+    /// active battler is then replaced by the first healthy battler of its team, as no player gets a turn of their
+    /// own to choose a replacement in.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(80, 60, 40, 70));
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, AbilityNames::default()), &species)]);
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![side.clone(), side]).with_rules(BattleRuleset::SuddenDeath { turn_limit: 1 }.create_plugin());
+    /// battle.end_turn();
+    /// assert_eq!(battle.get_battler(BattlerId::new(0, 0)).get_health(), 80);
+    /// battle.end_turn();
+    /// assert_eq!(battle.get_battler(BattlerId::new(0, 0)).get_health(), 70);
+    ///
+    /// let team = BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, AbilityNames::default()), &species); 3]);
+    /// let mut battle = Battle::new(BattleFormat::free_for_all(3), vec![team.clone(), team.clone(), team]);
+    /// battle.apply_damage(BattlerId::new(1, 0), 80);
+    /// battle.apply_damage(BattlerId::new(1, 1), 80);
+    /// battle.end_turn();
+    /// assert_eq!(battle.get_side(1).get_active_slot(), 2);
+    /// assert_eq!(battle.get_side(0).get_active_slot(), 0);
+    /// ```
+    pub fn end_turn(&mut self) {
+        assert!(!self.is_finished, "Cannot end a turn after the battle has ended");
+        self.with_state_events(|battle| {
+            battle.events.push(BattleEvent::TurnEnded { turn: battle.turn });
+            battle.turn += 1;
+            let turn = battle.turn;
+            for side in battle.sides.iter_mut() {
+                for battler in side.get_team_mut() {
+                    battler.set_protected(false);
+                    if battler.get_lock_on().is_some_and(|lock_on| lock_on.last_turn < turn) {
+                        battler.set_lock_on(None);
+                    }
+                }
+            }
+            let rules = battle.rules.clone();
+            rules.post_turn(battle);
+            battle.replace_fainted_battlers();
+        });
+    }
+
+    /// Switch in the first healthy battler of every free-for-all side whose active battler fainted.
+    fn replace_fainted_battlers(&mut self) {
+        if !matches!(self.format, BattleFormat::FreeForAll { .. }) {
+            return;
+        }
+        for side in 0..self.sides.len() {
+            // Hazards can faint the replacement as it switches in
+            while !self.is_finished && !self.sides[side].is_eliminated() && self.sides[side].get_active().is_fainted() {
+                let slot = self.sides[side].get_team().iter().position(|battler| !battler.is_fainted()).unwrap();
+                self.switch(side, slot);
+            }
+        }
+    }
+
+    /// Damage a battler. When it faints and its side is eliminated, the battle ends once at most one side remains.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat, battle_event::BattleEvent};
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species)]);
+    /// let mut battle = Battle::new(BattleFormat::free_for_all(3), vec![side.clone(), side.clone(), side]);
+    ///
+    /// battle.apply_damage(BattlerId::new(1, 0), 50);
+    /// assert_eq!(battle.get_valid_targets(0), vec![2]);
+    /// assert!(!battle.is_finished());
+    ///
+    /// battle.apply_damage(BattlerId::new(2, 0), 50);
+    /// assert!(battle.is_finished());
+    /// assert_eq!(battle.get_winner(), Some(0));
+    /// assert_eq!(*battle.take_events().last().unwrap(), BattleEvent::BattleEnded { winner: Some(0) });
+    /// ```
+    pub fn apply_damage(&mut self, battler: BattlerId, amount: u32) {
+        assert!(!self.is_finished, "Cannot damage a battler after the battle has ended");
+        let battler_data = self.sides[battler.side].get_battler_mut(battler.slot);
+        if battler_data.is_fainted() {
+            return;
+        }
+        let lost = battler_data.apply_damage(amount);
+        self.events.push(BattleEvent::Damaged { battler, amount: lost, remaining_health: battler_data.get_health() });
+        if !battler_data.is_fainted() {
+            return;
+        }
+        self.events.push(BattleEvent::Fainted { battler });
+        if !self.sides[battler.side].is_eliminated() {
+            return;
+        }
+        self.events.push(BattleEvent::SideEliminated { side: battler.side });
+        if self.is_one_team_remaining() {
+            self.winner = (0..self.sides.len()).find(|side| !self.sides[*side].is_eliminated());
+            self.end();
+        }
+    }
+
+    /// End the battle, reverting every transformed battler to its original form and forgetting anything copied.
+    /// Every battler that didn't faint gains bond from having battled.
+    pub fn end(&mut self) {
+        assert!(!self.is_finished, "Battle has already ended");
+        for side in 0..self.sides.len() {
+            for slot in 0..self.sides[side].get_team().len() {
+                let battler = self.sides[side].get_battler_mut(slot);
+                battler.clear_copies();
+                if !battler.is_fainted() {
+                    battler.apply_bond_event(BondEvent::Battled);
+                }
+                if !battler.is_transformed() {
+                    continue;
+                }
+                battler.revert();
+                self.events.push(BattleEvent::Reverted { battler: BattlerId::new(side, slot) });
+            }
+        }
+        self.is_finished = true;
+        self.events.push(BattleEvent::BattleEnded { winner: self.winner });
+    }
+
+    /// Validate and run a command sent by a client. Unlike the other actions, invalid commands never panic
+    /// and leave the battle unchanged.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::ability::{ability::Ability, ability_map::AbilityMap, abilities::fireball::Fireball};
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    /// use immie2d_shared::gameplay::battle::battle_command::{BattleCommand, BattleCommandError};
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(80, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Fireball>();
+    /// let abilities = AbilityNames::new(vec![GlobalString::new(&Fireball::static_name().to_string())]);
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, abilities), &species)]);
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![side.clone(), side]);
+    ///
+    /// assert_eq!(battle.apply_command(BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 0 }, &ability_map, &species_map), Err(BattleCommandError::InvalidTarget));
+    /// assert_eq!(battle.apply_command(BattleCommand::Switch { side: 5, slot: 0 }, &ability_map, &species_map), Err(BattleCommandError::InvalidSide));
+    /// assert_eq!(battle.apply_command(BattleCommand::Transform { side: 1 }, &ability_map, &species_map), Err(BattleCommandError::CannotTransform));
+    /// battle.apply_command(BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 }, &ability_map, &species_map).unwrap();
+    /// assert!(battle.get_battler(BattlerId::new(1, 0)).get_health() < 80);
+    /// assert_eq!(battle.get_battler(BattlerId::new(0, 0)).get_immie().ability_uses_spent[0], 1);
+    /// assert!(battle.check_invariants().is_ok());
+    /// ```
+    pub fn apply_command(&mut self, command: BattleCommand, ability_map: &AbilityMap, species_map: &SpeciesMap) -> Result<(), BattleCommandError> {
+        return self.with_state_events(|battle| battle.run_command(command, ability_map, species_map));
+    }
+
+    fn run_command(&mut self, command: BattleCommand, ability_map: &AbilityMap, species_map: &SpeciesMap) -> Result<(), BattleCommandError> {
+        if self.is_finished {
+            return Err(BattleCommandError::BattleFinished);
+        }
+        match command {
+            BattleCommand::UseAbility { side, .. } | BattleCommand::Switch { side, .. } | BattleCommand::Transform { side } | BattleCommand::Attune { side } if self.get_forced_action(side).is_some() => {
+                return Err(BattleCommandError::ForcedAction);
+            },
+            _ => ()
+        }
+        match command {
+            BattleCommand::UseAbility { side, ability_slot, target_side } => self.use_ability_command(side, ability_slot, target_side, ability_map, 1.0)?,
+            BattleCommand::Switch { side, slot } => {
+                if side >= self.sides.len() {
+                    return Err(BattleCommandError::InvalidSide);
+                }
+                let battle_side = &self.sides[side];
+                if battle_side.is_eliminated() {
+                    return Err(BattleCommandError::SideEliminated);
+                }
+                if slot >= battle_side.get_team().len() || slot == battle_side.get_active_slot() || battle_side.get_battler(slot).is_fainted() {
+                    return Err(BattleCommandError::InvalidSwitch);
+                }
+                self.switch(side, slot);
+            },
+            BattleCommand::Transform { side } => {
+                let battler = self.get_acting_battler_id(side)?;
+                let species = self.get_battler(battler).get_immie().species;
+                if !species_map.is_species_name(species) || !self.get_battler(battler).can_transform(species_map.get_species(species)) {
+                    return Err(BattleCommandError::CannotTransform);
+                }
+                self.transform(battler, species_map);
+            },
+            BattleCommand::Attune { side } => {
+                let battler = self.get_acting_battler_id(side)?;
+                if self.sides[side].has_attuned() || !self.get_battler(battler).can_attune() {
+                    return Err(BattleCommandError::CannotAttune);
+                }
+                self.attune(battler);
+            },
+            BattleCommand::Continue { side } => self.continue_command(side, ability_map)?,
+            BattleCommand::EndTurn => self.end_turn()
+        }
+        return Ok(());
+    }
+
+    /// Validate and run a UseAbility command, multiplying the ability's power. Abilities flagged AbilityFlags::CHARGES
+    /// or AbilityFlags::LOCKS_IN start a forced action instead of only hitting once. Abilities flagged
+    /// AbilityFlags::COPIES_TARGET or AbilityFlags::COPIES_LAST_ABILITY copy, and abilities that lay or clear hazards
+    /// do so, instead of hitting.
+    fn use_ability_command(&mut self, side: usize, ability_slot: usize, target_side: usize, ability_map: &AbilityMap, power_multiplier: f32) -> Result<(), BattleCommandError> {
+        let attacker = self.get_acting_battler_id(side)?;
+        if !self.get_valid_targets(side).contains(&target_side) {
+            return Err(BattleCommandError::InvalidTarget);
+        }
+        let battler = self.get_battler(attacker);
+        let abilities = battler.get_abilities();
+        if ability_slot >= abilities.get_count() as usize {
+            return Err(BattleCommandError::InvalidAbilitySlot);
+        }
+        let name = abilities.get_names()[ability_slot].to_string();
+        if !ability_map.is_ability_name(&name) {
+            return Err(BattleCommandError::UnknownAbility);
+        }
+        let ability = ability_map.new_ability(&name);
+        let max_uses = ability.get_base_ability_data().max_uses;
+        if battler.get_remaining_uses(ability_slot, max_uses) == 0 {
+            return Err(BattleCommandError::NoUsesRemaining);
+        }
+        self.sides[side].get_battler_mut(attacker.slot).spend_ability_use(ability_slot, max_uses);
+        let flags = ability.get_base_ability_data().flags;
+        if flags.intersects(AbilityFlags::COPIES_TARGET | AbilityFlags::COPIES_LAST_ABILITY) {
+            self.copy_from_target(attacker, ability_slot, target_side, flags, ability_map);
+        } else if let Some(kind) = HazardKind::from_flags(flags) {
+            self.set_hazard(attacker, ability_slot, target_side, kind);
+        } else if flags.contains(AbilityFlags::CLEARS_HAZARDS) {
+            self.clear_hazards(attacker, ability_slot);
+        } else if flags.contains(AbilityFlags::CHARGES) {
+            self.run_forced_turn(attacker, ForcedAction::new(ForcedActionKind::Charging, ability_slot, target_side, CHARGE_TURNS), ability.as_ref(), power_multiplier);
+        } else if flags.contains(AbilityFlags::LOCKS_IN) {
+            self.run_forced_turn(attacker, ForcedAction::new(ForcedActionKind::LockedIn, ability_slot, target_side, LOCKED_IN_TURNS), ability.as_ref(), power_multiplier);
+        } else {
+            self.hit_with_ability(attacker, ability_slot, target_side, ability.as_ref(), power_multiplier);
+        }
+        return Ok(());
+    }
+
+    /// Validate and run a Continue command, taking the next turn of the side's forced action. Uses are only spent on
+    /// the first turn. If the target can no longer be hit, the forced action is cancelled instead.
+    fn continue_command(&mut self, side: usize, ability_map: &AbilityMap) -> Result<(), BattleCommandError> {
+        let attacker = self.get_acting_battler_id(side)?;
+        let action = self.get_battler(attacker).get_forced_action().ok_or(BattleCommandError::NoForcedAction)?;
+        if action.kind == ForcedActionKind::Recharging {
+            self.events.push(BattleEvent::MultiTurnProgress { battler: attacker, kind: action.kind, turn: action.turn, total_turns: action.total_turns });
+            self.get_battler_mut(attacker).set_forced_action(action.get_next_turn());
+            return Ok(());
+        }
+        let name = self.get_battler(attacker).get_abilities().get_names()[action.ability_slot].to_string();
+        if !self.get_valid_targets(side).contains(&action.target_side) || !ability_map.is_ability_name(&name) {
+            self.cancel_forced_action(attacker);
+            return Ok(());
+        }
+        let ability = ability_map.new_ability(&name);
+        self.run_forced_turn(attacker, action, ability.as_ref(), 1.0);
+        return Ok(());
+    }
+
+    /// Take a turn of a charging or locked in ability, announcing its progress and hitting on the turns it hits.
+    /// Abilities flagged AbilityFlags::FLIES or AbilityFlags::DIGS make the user semi-invulnerable while charging.
+    fn run_forced_turn(&mut self, attacker: BattlerId, action: ForcedAction, ability: &dyn Ability, power_multiplier: f32) {
+        self.events.push(BattleEvent::MultiTurnProgress { battler: attacker, kind: action.kind, turn: action.turn, total_turns: action.total_turns });
+        // Set before hitting, so fainting during the hit clears it
+        self.get_battler_mut(attacker).set_forced_action(action.get_next_turn());
+        let semi_invulnerability = SemiInvulnerability::from_flags(ability.get_base_ability_data().flags);
+        if let (ForcedActionKind::Charging, false, Some(state)) = (action.kind, action.is_last_turn(), semi_invulnerability) {
+            self.get_battler_mut(attacker).set_semi_invulnerability(Some(state));
+            self.events.push(BattleEvent::Vanished { battler: attacker, state });
+        }
+        if action.kind == ForcedActionKind::LockedIn || action.is_last_turn() {
+            self.hit_with_ability(attacker, action.ability_slot, action.target_side, ability, power_multiplier);
+        }
+    }
+
+    /// Run an ability through the pipeline, with its script if it has one, and remember it was used. Abilities flagged
+    /// AbilityFlags::RECHARGES force the user to recharge once nothing else is forced.
+    fn hit_with_ability(&mut self, attacker: BattlerId, ability_slot: usize, target_side: usize, ability: &dyn Ability, power_multiplier: f32) {
+        let defender = self.get_active_battler_id(target_side);
+        let data = ability.get_base_ability_data();
+        AbilityPipeline::new(attacker, defender, data).with_script(ability.get_script()).with_power_multiplier(power_multiplier).run(self);
+        let turn = self.turn;
+        let name = self.get_battler(attacker).get_abilities().get_names()[ability_slot];
+        self.get_battler_mut(attacker).record_ability_use(turn, name);
+        let battler = self.get_battler(attacker);
+        if !data.flags.contains(AbilityFlags::RECHARGES) || self.is_finished || battler.is_fainted() || battler.get_forced_action().is_some() {
+            return;
+        }
+        self.events.push(BattleEvent::MultiTurnProgress { battler: attacker, kind: ForcedActionKind::Recharging, turn: 0, total_turns: RECHARGE_TURNS });
+        self.get_battler_mut(attacker).set_forced_action(Some(ForcedAction::new(ForcedActionKind::Recharging, ability_slot, target_side, RECHARGE_TURNS)));
+    }
+
+    /// Have an attacker copy the active battler of a side, or the last ability it used, and remember the copying ability
+    /// was used. Nothing is copied from a target that is protected, behind a substitute or out of reach, and an ability
+    /// isn't copied if the attacker already has it or it copies too.
+    fn copy_from_target(&mut self, attacker: BattlerId, ability_slot: usize, target_side: usize, flags: AbilityFlags, ability_map: &AbilityMap) {
+        let target = self.get_active_battler_id(target_side);
+        let turn = self.turn;
+        let name = self.get_battler(attacker).get_abilities().get_names()[ability_slot];
+        self.get_battler_mut(attacker).record_ability_use(turn, name);
+        let target_data = *self.get_battler(target);
+        let is_reachable = !target_data.is_protected() && !target_data.has_substitute() && target_data.get_semi_invulnerability().is_none();
+        if is_reachable && flags.contains(AbilityFlags::COPIES_TARGET) {
+            self.get_battler_mut(attacker).copy_identity(&target_data);
+            self.events.push(BattleEvent::IdentityCopied { battler: attacker, target, species: target_data.get_species() });
+            return;
+        }
+        let copyable = target_data.get_ability_history().first().map(|used| used.ability).filter(|ability| {
+            let name = ability.to_string();
+            return ability_map.is_ability_name(&name)
+                && !ability_map.new_ability(&name).get_base_ability_data().flags.intersects(AbilityFlags::COPIES_TARGET | AbilityFlags::COPIES_LAST_ABILITY)
+                && !self.get_battler(attacker).get_abilities().has_ability(*ability);
+        });
+        match copyable {
+            Some(ability) if is_reachable => {
+                self.get_battler_mut(attacker).copy_ability(ability_slot, ability);
+                self.events.push(BattleEvent::AbilityCopied { battler: attacker, target, ability });
+            },
+            _ => self.events.push(BattleEvent::CopyFailed { battler: attacker, target })
+        }
+    }
+
+    /// Have an attacker lay another layer of a hazard on a side, and remember the ability was used. Protection and
+    /// substitutes don't stop hazards, since they're laid on the field rather than on the target. Nothing happens if
+    /// the side already has the most layers of the hazard it can.
+    fn set_hazard(&mut self, attacker: BattlerId, ability_slot: usize, target_side: usize, kind: HazardKind) {
+        let turn = self.turn;
+        let name = self.get_battler(attacker).get_abilities().get_names()[ability_slot];
+        self.get_battler_mut(attacker).record_ability_use(turn, name);
+        if let Some(layers) = self.sides[target_side].get_hazards_mut().add_layer(kind) {
+            self.events.push(BattleEvent::HazardSet { side: target_side, kind, layers });
+        }
+    }
+
+    /// Have an attacker remove every hazard from its own side, and remember the ability was used.
+    fn clear_hazards(&mut self, attacker: BattlerId, ability_slot: usize) {
+        let turn = self.turn;
+        let name = self.get_battler(attacker).get_abilities().get_names()[ability_slot];
+        self.get_battler_mut(attacker).record_ability_use(turn, name);
+        let hazards = self.sides[attacker.side].get_hazards_mut();
+        if hazards.is_empty() {
+            return;
+        }
+        hazards.clear();
+        self.events.push(BattleEvent::HazardsCleared { side: attacker.side });
+    }
+
+    /// Affect a battler that just switched in with each hazard on its side it isn't immune to. Spikes damage it more
+    /// for each layer, and webs lower its evasion. Stops once the battler faints.
+    fn trigger_hazards(&mut self, battler: BattlerId) {
+        let hazards = *self.sides[battler.side].get_hazards();
+        for kind in HazardKind::ALL {
+            let layers = hazards.get_layers(kind);
+            if layers == 0 {
+                continue;
+            }
+            if kind.is_immune(self.get_battler(battler).get_elements()) {
+                self.events.push(BattleEvent::HazardAvoided { battler, kind });
+                continue;
+            }
+            self.events.push(BattleEvent::HazardTriggered { battler, kind, layers });
+            match kind {
+                HazardKind::Spikes => self.apply_damage(battler, get_spikes_damage(self.get_battler(battler).get_stats().health, layers)),
+                HazardKind::Webs => {
+                    self.get_battler_mut(battler).change_evasion_stage(-WEBS_EVASION_STAGES * layers as i32);
+                }
+            }
+            if self.get_battler(battler).is_fainted() {
+                return;
+            }
+        }
+    }
+
+    /// Stop a battler's forced action early, if it has one.
+    fn cancel_forced_action(&mut self, battler: BattlerId) {
+        if self.get_battler(battler).get_forced_action().is_none() {
+            return;
+        }
+        self.get_battler_mut(battler).set_forced_action(None);
+        self.events.push(BattleEvent::MultiTurnCancelled { battler });
+    }
+
+    /// Whether a command uses an ability that intercepts switches on a side's active battler.
+    fn is_switch_intercept(&self, command: BattleCommand, switching_side: usize, ability_map: &AbilityMap) -> bool {
+        let BattleCommand::UseAbility { side, ability_slot, target_side } = command else {
+            return false;
+        };
+        if target_side != switching_side || side == switching_side || side >= self.sides.len() || self.sides[side].is_eliminated() || self.get_forced_action(side).is_some() {
+            return false;
+        }
+        let names = self.sides[side].get_active().get_abilities().get_names();
+        return match names.get(ability_slot) {
+            Some(name) => ability_map.is_ability_name(&name.to_string()) && ability_map.new_ability(&name.to_string()).get_base_ability_data().flags.contains(AbilityFlags::INTERCEPTS_SWITCH),
+            None => false
+        };
+    }
+
+    /// Run every side's command for the turn through an ActionQueue, then end the turn. Switches go first, then the
+    /// other commands in turn order. Before a side switches, abilities flagged AbilityFlags::INTERCEPTS_SWITCH
+    /// targeting it are used on the retreating battler with SWITCH_INTERCEPT_POWER_MULTIPLIER times the power,
+    /// instead of in turn order. The rules can queue reactions before and after every action.
+    /// EndTurn commands are ignored. Returns the index of each rejected command with its error.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::ability::{ability::Ability, ability_map::AbilityMap, abilities::pursuit::Pursuit};
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat, battle_event::BattleEvent};
+    /// use immie2d_shared::gameplay::battle::battle_command::BattleCommand;
+    ///
+    /// let slow = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(500, 60, 40, 10));
+    /// let fast = SpeciesData::new(GlobalString::new(&"sproutle".to_string()), Elements::new(vec![ElementKind::Nature]), BaseStats::new(500, 60, 40, 90));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(slow);
+    /// species_map.add_species(fast);
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Pursuit>();
+    /// let abilities = AbilityNames::new(vec![GlobalString::new(&Pursuit::static_name().to_string())]);
+    /// let attacker = BattleSide::new(vec![Battler::new(Immie::new(slow.name, 20, abilities), &slow)]);
+    /// let defender = BattleSide::new(vec![Battler::new(Immie::new(fast.name, 20, abilities), &fast), Battler::new(Immie::new(fast.name, 20, abilities), &fast)]);
+    /// let use_pursuit = BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 };
+    /// let switch = BattleCommand::Switch { side: 1, slot: 1 };
+    ///
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![attacker.clone(), defender.clone()]);
+    /// assert!(battle.resolve_turn(&[use_pursuit], &ability_map, &species_map).is_empty());
+    /// let normal_damage = 500 - battle.get_battler(BattlerId::new(1, 0)).get_health();
+    /// assert_eq!(battle.get_turn(), 2);
+    ///
+    /// // The slower attacker hits the retreating battler before it leaves
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![attacker, defender]);
+    /// assert!(battle.resolve_turn(&[use_pursuit, switch], &ability_map, &species_map).is_empty());
+    /// assert!(battle.take_events().contains(&BattleEvent::SwitchIntercepted { attacker: BattlerId::new(0, 0), retreating: BattlerId::new(1, 0) }));
+    /// assert!(500 - battle.get_battler(BattlerId::new(1, 0)).get_health() > normal_damage);
+    /// assert_eq!(battle.get_battler(BattlerId::new(1, 1)).get_health(), 500);
+    /// assert_eq!(battle.get_side(1).get_active_slot(), 1);
+    /// ```
+    pub fn resolve_turn(&mut self, commands: &[BattleCommand], ability_map: &AbilityMap, species_map: &SpeciesMap) -> Vec<(usize, BattleCommandError)> {
+        assert!(!self.is_finished, "Cannot resolve a turn after the battle has ended");
+        let turn_order = self.get_turn_order();
+        let mut queue = ActionQueue::new();
+        for (index, command) in commands.iter().enumerate() {
+            let (priority, side) = match *command {
+                BattleCommand::Switch { side, .. } => (SWITCH_PRIORITY, side),
+                BattleCommand::UseAbility { side, .. } | BattleCommand::Transform { side } | BattleCommand::Attune { side } | BattleCommand::Continue { side } => (COMMAND_PRIORITY, side),
+                BattleCommand::EndTurn => continue
+            };
+            let speed_rank = turn_order.iter().position(|s| *s == side).unwrap_or(usize::MAX);
+            queue.push(BattleAction::Command { index, command: *command }, priority, speed_rank);
+        }
+        let rules = self.get_rules_handle();
+        let mut rejected = Vec::new();
+        while let Some(queued) = queue.pop() {
+            if self.is_finished {
+                break;
+            }
+            if !queued.is_resumed {
+                self.queue_switch_intercepts(&queued, &mut queue, ability_map);
+                self.with_state_events(|battle| rules.pre_action(battle, &queued.action, &mut queue));
+                if queue.has_interrupts() {
+                    queue.resume(queued);
+                    continue;
+                }
+            }
+            self.action_first_event = self.events.len();
+            match self.run_action(queued.action, ability_map, species_map) {
+                Ok(()) => self.with_state_events(|battle| rules.post_action(battle, &queued.action, &mut queue)),
+                Err(err) => match queued.action {
+                    BattleAction::Command { index, .. } | BattleAction::SwitchIntercept { index, .. } => rejected.push((index, err)),
+                    BattleAction::ReactiveAbility { .. } => ()
+                }
+            }
+        }
+        if !self.is_finished {
+            self.end_turn();
+        }
+        return rejected;
+    }
+
+    /// Before a switch, replace the queued commands that intercept it with interrupts.
+    fn queue_switch_intercepts(&self, queued: &QueuedAction, queue: &mut ActionQueue, ability_map: &AbilityMap) {
+        let BattleAction::Command { command: BattleCommand::Switch { side: switching_side, .. }, .. } = queued.action else {
+            return;
+        };
+        let intercepts = queue.take_where(|action| match *action {
+            BattleAction::Command { command, .. } => self.is_switch_intercept(command, switching_side, ability_map),
+            _ => false
+        });
+        for intercept in intercepts {
+            let BattleAction::Command { index, command: BattleCommand::UseAbility { side, ability_slot, target_side } } = intercept.action else {
+                unreachable!();
+            };
+            queue.interrupt(BattleAction::SwitchIntercept { index, side, ability_slot, target_side });
+        }
+    }
+
+    fn run_action(&mut self, action: BattleAction, ability_map: &AbilityMap, species_map: &SpeciesMap) -> Result<(), BattleCommandError> {
+        return match action {
+            BattleAction::Command { command, .. } => self.apply_command(command, ability_map, species_map),
+            BattleAction::SwitchIntercept { side, ability_slot, target_side, .. } => self.with_state_events(|battle| {
+                let (attacker, retreating) = (battle.get_active_battler_id(side), battle.get_active_battler_id(target_side));
+                let first_event = battle.events.len();
+                battle.use_ability_command(side, ability_slot, target_side, ability_map, SWITCH_INTERCEPT_POWER_MULTIPLIER)?;
+                // Only announced once the ability was accepted, but ahead of the events of its hit
+                battle.events.insert(first_event, BattleEvent::SwitchIntercepted { attacker, retreating });
+                return Ok(());
+            }),
+            BattleAction::ReactiveAbility { side, ability_slot, target_side, power_multiplier } => {
+                self.with_state_events(|battle| battle.use_ability_command(side, ability_slot, target_side, ability_map, power_multiplier))
+            }
+        };
+    }
+
+    /// Get the active battler of a side that is able to act.
+    fn get_acting_battler_id(&self, side: usize) -> Result<BattlerId, BattleCommandError> {
+        if side >= self.sides.len() {
+            return Err(BattleCommandError::InvalidSide);
+        }
+        if self.sides[side].is_eliminated() {
+            return Err(BattleCommandError::SideEliminated);
+        }
+        if self.sides[side].get_active().is_fainted() {
+            return Err(BattleCommandError::ActiveFainted);
+        }
+        return Ok(self.get_active_battler_id(side));
+    }
+
+    /// Check that the battle is in a consistent state, describing the first problem found.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        if self.turn == 0 {
+            return Err("Turn counter is 0".to_string());
+        }
+        for (index, side) in self.sides.iter().enumerate() {
+            if side.get_active_slot() >= side.get_team().len() {
+                return Err(format!("Side {} has active slot {} but only {} battlers", index, side.get_active_slot(), side.get_team().len()));
+            }
+        }
+        if !self.is_finished && self.is_one_team_remaining() {
+            return Err("Battle is still running with only one team remaining".to_string());
+        }
+        if let Some(winner) = self.winner {
+            if !self.is_finished {
+                return Err(format!("Side {} won but the battle is still running", winner));
+            }
+            if winner >= self.sides.len() || self.sides[winner].is_eliminated() {
+                return Err(format!("Side {} won but has been eliminated", winner));
+            }
+        }
+        return Ok(());
+    }
+
+    /// Hash everything that decides how the battle plays out from here, for a client mirroring the battle to check it
+    /// agrees with the server. Events already emitted aren't included, since they don't change what happens next.
+    /// The state is hashed as a fixed byte encoding, so hashes agree across platforms and terminals. See state_hash
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species)]);
+    /// let battle = Battle::new(BattleFormat::Single, vec![side.clone(), side.clone()]).with_seed(3);
+    /// let mut mirror = Battle::new(BattleFormat::Single, vec![side.clone(), side]).with_seed(3);
+    /// assert_eq!(battle.get_state_hash(), mirror.get_state_hash());
+    ///
+    /// mirror.apply_damage(BattlerId::new(1, 0), 1);
+    /// assert_ne!(battle.get_state_hash(), mirror.get_state_hash());
+    /// ```
+    pub fn get_state_hash(&self) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(STATE_HASH_DOMAIN);
+        let mut buffer = BytesMut::new();
+        put_str(&mut buffer, self.rules.get_name());
+        put_format(&mut buffer, self.format);
+        buffer.put_u32_le(self.turn);
+        put_flag(&mut buffer, self.is_finished);
+        put_option(&mut buffer, self.winner, put_usize);
+        buffer.put_u64_le(self.rng.get_state());
+        buffer.put_u64_le(self.tie_break_seed);
+        buffer.put_u8(self.field.weather.get_id());
+        put_usize(&mut buffer, self.sides.len());
+        for side in self.sides.iter() {
+            side.put_state(&mut buffer);
+        }
+        hasher.update(&buffer);
+        return hasher.finalize().into();
+    }
+
+    /// Everything get_state_hash() covers as readable text, a field per line, for finding where two battles that should
+    /// match differ.
+    pub fn get_state_dump(&self) -> String {
+        let mut dump = format!("rules: {}\nformat: {:?}\nturn: {}\nis_finished: {}\nwinner: {:?}\n", self.rules.get_name(), self.format, self.turn, self.is_finished, self.winner);
+        dump.push_str(&format!("rng: {}\ntie_break_seed: {}\nfield: {:#?}\n", self.rng.get_state(), self.tie_break_seed, self.field));
+        for (index, side) in self.sides.iter().enumerate() {
+            dump.push_str(&format!("side {}: {:#?}\n", index, side));
+        }
+        return dump;
+    }
+
+    /// The events of the action resolve_turn() ran last, for BattleRulesPlugin::post_action() to react to what the
+    /// action did.
+    pub fn get_action_events(&self) -> &[BattleEvent] {
+        return &self.events[self.action_first_event.min(self.events.len())..];
+    }
+
+    /// Take all events emitted since the last call, leaving none remaining.
+    pub fn take_events(&mut self) -> Vec<BattleEvent> {
+        return std::mem::take(&mut self.events);
+    }
+}
@@ -0,0 +1,98 @@
+use super::battle::Battle;
+use super::battle_format::BattleFormat;
+use super::battle_side::BattleSide;
+use super::battler::Battler;
+use super::rules::battle_ruleset::BattleRuleset;
+
+/// Marks a part of a BattleBuilder that has not been set yet.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Missing;
+
+/// Both teams of a two-sided battle, with the player's side first.
+pub struct Teams {
+    pub player: BattleSide,
+    pub opponent: BattleSide
+}
+
+/* Builds a single battle between two sides. Each type parameter is Missing until its part is set, and build() only
+exists once the teams, ruleset and seed have all been set, so a half-initialized battle doesn't compile. */
+pub struct BattleBuilder<T, R, S> {
+    teams: T,
+    ruleset: R,
+    seed: S
+}
+
+impl BattleBuilder<Missing, Missing, Missing> {
+    pub fn new() -> BattleBuilder<Missing, Missing, Missing> {
+        return BattleBuilder { teams: Missing, ruleset: Missing, seed: Missing };
+    }
+
+    /// A battle against a single wild Immie using the standard rules. Only the seed is left to set.
+    pub fn wild_encounter(player_team: BattleSide, wild_immie: Battler) -> BattleBuilder<Teams, BattleRuleset, Missing> {
+        return BattleBuilder::new().teams(player_team, BattleSide::new(vec![wild_immie])).ruleset(BattleRuleset::Standard);
+    }
+
+    /// A battle against another trainer's team. Only the seed is left to set.
+    pub fn trainer_battle(player_team: BattleSide, trainer_team: BattleSide, ruleset: BattleRuleset) -> BattleBuilder<Teams, BattleRuleset, Missing> {
+        return BattleBuilder::new().teams(player_team, trainer_team).ruleset(ruleset);
+    }
+}
+
+impl<R, S> BattleBuilder<Missing, R, S> {
+    pub fn teams(self, player: BattleSide, opponent: BattleSide) -> BattleBuilder<Teams, R, S> {
+        return BattleBuilder { teams: Teams { player, opponent }, ruleset: self.ruleset, seed: self.seed };
+    }
+}
+
+impl<T, S> BattleBuilder<T, Missing, S> {
+    pub fn ruleset(self, ruleset: BattleRuleset) -> BattleBuilder<T, BattleRuleset, S> {
+        return BattleBuilder { teams: self.teams, ruleset, seed: self.seed };
+    }
+}
+
+impl<T, R> BattleBuilder<T, R, Missing> {
+    /// Seed for the battle's random number generator. See SeedNegotiation::finish()
+    pub fn seed(self, seed: u64) -> BattleBuilder<T, R, u64> {
+        return BattleBuilder { teams: self.teams, ruleset: self.ruleset, seed };
+    }
+}
+
+impl BattleBuilder<Teams, BattleRuleset, u64> {
+    /// Create the battle.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::engine_types::game_rng::GameRng;
+    /// use immie2d_shared::gameplay::battle::{battle_builder::BattleBuilder, battler::Battler, battle_side::BattleSide, battler_id::BattlerId};
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let battler = Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species);
+    /// let player_team = BattleSide::new(vec![battler, battler]);
+    ///
+    /// let mut wild = BattleBuilder::wild_encounter(player_team.clone(), battler).seed(42).build();
+    /// assert_eq!(wild.get_side(1).get_team().len(), 1);
+    /// assert_eq!(wild.get_rng_mut().next_u64(), GameRng::new(42).next_u64());
+    ///
+    /// let trainer = BattleBuilder::new()
+    ///     .seed(7)
+    ///     .ruleset(BattleRuleset::InverseTypes)
+    ///     .teams(player_team.clone(), player_team)
+    ///     .build();
+    /// assert_eq!(trainer.get_rules().get_name(), "inverse_types");
+    /// ```
+    /// Will not compile unless every part has been set.
+    /// ``` compile_fail
+    /// use immie2d_shared::gameplay::battle::{battle_builder::BattleBuilder, rules::battle_ruleset::BattleRuleset};
+    /// // No teams or seed
+    /// let battle = BattleBuilder::new().ruleset(BattleRuleset::Standard).build();
+    /// ```
+    pub fn build(self) -> Battle {
+        return Battle::new(BattleFormat::Single, vec![self.teams.player, self.teams.opponent])
+            .with_rules(self.ruleset.create_plugin())
+            .with_seed(self.seed);
+    }
+}
@@ -0,0 +1,137 @@
+use bytes::{BufMut, BytesMut};
+use serde::{Deserialize, Serialize};
+
+use crate::engine_types::encode_buffer::Encode;
+
+/* An action requested by a client for the side it controls. Commands come straight from the network, so they are
+validated by Battle::apply_command() instead of being trusted. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum BattleCommand {
+    /// Use the ability in a slot of the side's active battler on the active battler of another side.
+    UseAbility { side: usize, ability_slot: usize, target_side: usize },
+    Switch { side: usize, slot: usize },
+    Transform { side: usize },
+    /// Change the side's active battler to the element of its attunement for the rest of the battle. See Battle::attune()
+    Attune { side: usize },
+    /// Take the next turn of the multi-turn ability the side's active battler is forced to take. See ForcedAction
+    Continue { side: usize },
+    EndTurn
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BattleCommandError {
+    /// Not enough bytes to decode a whole command.
+    Truncated,
+    UnknownCommand(u8),
+    BattleFinished,
+    InvalidSide,
+    SideEliminated,
+    /// The side's active battler has fainted and must be switched out first.
+    ActiveFainted,
+    InvalidAbilitySlot,
+    UnknownAbility,
+    NoUsesRemaining,
+    InvalidTarget,
+    InvalidSwitch,
+    CannotTransform,
+    /// The side's active battler has no attunement, or the side already attuned this battle.
+    CannotAttune,
+    /// The side's active battler is in the middle of a multi-turn ability and can only Continue.
+    ForcedAction,
+    /// Continue was sent but the side's active battler has no forced action.
+    NoForcedAction
+}
+
+const USE_ABILITY_TAG: u8 = 0;
+const SWITCH_TAG: u8 = 1;
+const TRANSFORM_TAG: u8 = 2;
+const END_TURN_TAG: u8 = 3;
+const CONTINUE_TAG: u8 = 4;
+const ATTUNE_TAG: u8 = 5;
+
+impl Encode for BattleCommand {
+    /// Encode as a tag byte followed by a byte for each field.
+    /// Will panic if a field doesn't fit in a byte.
+    fn encode_into(&self, buffer: &mut BytesMut) {
+        let (tag, fields): (u8, &[usize]) = match *self {
+            BattleCommand::UseAbility { side, ability_slot, target_side } => (USE_ABILITY_TAG, &[side, ability_slot, target_side]),
+            BattleCommand::Switch { side, slot } => (SWITCH_TAG, &[side, slot]),
+            BattleCommand::Transform { side } => (TRANSFORM_TAG, &[side]),
+            BattleCommand::Attune { side } => (ATTUNE_TAG, &[side]),
+            BattleCommand::Continue { side } => (CONTINUE_TAG, &[side]),
+            BattleCommand::EndTurn => (END_TURN_TAG, &[])
+        };
+        buffer.put_u8(tag);
+        for field in fields {
+            assert!(*field <= u8::MAX as usize, "Battle command field {} does not fit in a byte", field);
+            buffer.put_u8(*field as u8);
+        }
+    }
+}
+
+impl BattleCommand {
+    /// The side the command is for, or None for EndTurn.
+    pub fn get_side(&self) -> Option<usize> {
+        return match *self {
+            BattleCommand::UseAbility { side, .. } | BattleCommand::Switch { side, .. } | BattleCommand::Transform { side } | BattleCommand::Attune { side } | BattleCommand::Continue { side } => Some(side),
+            BattleCommand::EndTurn => None
+        };
+    }
+
+    /// Encode as a tag byte followed by a byte for each field. See BattleCommand::encode_into()
+    /// Will panic if a field doesn't fit in a byte.
+    pub fn encode(&self) -> Vec<u8> {
+        return self.encode_to_vec();
+    }
+
+    /// Decode a single command from the start of some bytes. Returns the command and the number of bytes it used.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::battle_command::{BattleCommand, BattleCommandError};
+    ///
+    /// let command = BattleCommand::UseAbility { side: 1, ability_slot: 2, target_side: 0 };
+    /// let mut bytes = command.encode();
+    /// bytes.push(3);
+    /// assert_eq!(BattleCommand::decode(&bytes), Ok((command, 4)));
+    /// assert_eq!(BattleCommand::decode(&bytes[4..]), Ok((BattleCommand::EndTurn, 1)));
+    /// assert_eq!(BattleCommand::decode(&bytes[..2]), Err(BattleCommandError::Truncated));
+    /// assert_eq!(BattleCommand::decode(&[200]), Err(BattleCommandError::UnknownCommand(200)));
+    /// assert_eq!(BattleCommand::decode(&BattleCommand::Continue { side: 2 }.encode()), Ok((BattleCommand::Continue { side: 2 }, 2)));
+    /// assert_eq!(BattleCommand::decode(&BattleCommand::Attune { side: 1 }.encode()), Ok((BattleCommand::Attune { side: 1 }, 2)));
+    /// ```
+    pub fn decode(bytes: &[u8]) -> Result<(BattleCommand, usize), BattleCommandError> {
+        let tag = *bytes.first().ok_or(BattleCommandError::Truncated)?;
+        let field_count = match tag {
+            USE_ABILITY_TAG => 3,
+            SWITCH_TAG => 2,
+            TRANSFORM_TAG => 1,
+            END_TURN_TAG => 0,
+            CONTINUE_TAG => 1,
+            ATTUNE_TAG => 1,
+            _ => return Err(BattleCommandError::UnknownCommand(tag))
+        };
+        if bytes.len() < 1 + field_count {
+            return Err(BattleCommandError::Truncated);
+        }
+        let field = |index: usize| bytes[1 + index] as usize;
+        let command = match tag {
+            USE_ABILITY_TAG => BattleCommand::UseAbility { side: field(0), ability_slot: field(1), target_side: field(2) },
+            SWITCH_TAG => BattleCommand::Switch { side: field(0), slot: field(1) },
+            TRANSFORM_TAG => BattleCommand::Transform { side: field(0) },
+            CONTINUE_TAG => BattleCommand::Continue { side: field(0) },
+            ATTUNE_TAG => BattleCommand::Attune { side: field(0) },
+            _ => BattleCommand::EndTurn
+        };
+        return Ok((command, 1 + field_count));
+    }
+
+    /// Decode every command in a byte stream, stopping at the first one that fails to decode.
+    pub fn decode_stream(bytes: &[u8]) -> Vec<BattleCommand> {
+        let mut commands = Vec::new();
+        let mut offset = 0;
+        while let Ok((command, used)) = BattleCommand::decode(&bytes[offset..]) {
+            commands.push(command);
+            offset += used;
+        }
+        return commands;
+    }
+}
@@ -0,0 +1,188 @@
+use crate::gameplay::ability::{ability::{Ability, AbilityCategory, BaseAbilityData}, ability_map::AbilityMap};
+
+use super::battle::Battle;
+use super::battle_command::BattleCommand;
+use super::battler_id::BattlerId;
+use super::damage::DamageContext;
+
+/// Weight of the difference in standing battlers in a score. See BattleEvaluation::get_score()
+pub const MATERIAL_WEIGHT: f32 = 1.0;
+/// Weight of the difference in remaining health in a score.
+pub const HEALTH_WEIGHT: f32 = 1.0;
+/// Weight of the type matchup between the active battlers in a score.
+pub const MATCHUP_WEIGHT: f32 = 0.25;
+
+/* How well a battle is going for a side, where each part is positive when the side is ahead of its opponents on
+average. Evaluating never changes the battle or rolls its rng, so the AI and the client's tutor hints see exactly what
+the battle would do. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BattleEvaluation {
+    /// Fraction of the side's team still standing, minus that of its opponents. From -1 to 1.
+    pub material: f32,
+    /// Fraction of the side's total max health remaining, minus that of its opponents. From -1 to 1.
+    pub health: f32,
+    /// How much more effective the side's active battler is against its opponents' than they are against it.
+    /// See get_matchup()
+    pub matchup: f32
+}
+
+impl BattleEvaluation {
+    /// A single number to compare evaluations by, where higher is better for the side.
+    pub fn get_score(&self) -> f32 {
+        return self.material * MATERIAL_WEIGHT + self.health * HEALTH_WEIGHT + self.matchup * MATCHUP_WEIGHT;
+    }
+}
+
+/// Evaluate a battle from the point of view of a side, against every side that isn't eliminated.
+/// ```
+/// # use immie2d_shared::engine_types::global_string::GlobalString;
+/// # use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames, abilities::{fireball::Fireball, pursuit::Pursuit}};
+/// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+/// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+/// # use immie2d_shared::gameplay::immie::immie::Immie;
+/// use immie2d_shared::gameplay::battle::{battle::Battle, battle_format::BattleFormat, battle_side::BattleSide, battler::Battler, battler_id::BattlerId};
+/// use immie2d_shared::gameplay::battle::{battle_command::BattleCommand, battle_evaluation::{choose_heuristic_command, evaluate_battle}};
+///
+/// let name = |name: &str| GlobalString::new(&name.to_string());
+/// let fire = SpeciesData::new(name("lavapup"), Elements::new(vec![ElementKind::Fire]), BaseStats::new(80, 60, 40, 70));
+/// let nature = SpeciesData::new(name("sproutle"), Elements::new(vec![ElementKind::Nature]), BaseStats::new(80, 60, 40, 70));
+/// let mut ability_map = AbilityMap::new();
+/// ability_map.add_ability::<Fireball>();
+/// ability_map.add_ability::<Pursuit>();
+/// let abilities = AbilityNames::new(vec![name("pursuit"), name("fireball")]);
+/// let side = |species: &SpeciesData| BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, abilities), species)]);
+/// let mut battle = Battle::new(BattleFormat::Single, vec![side(&fire), side(&nature)]);
+///
+/// let evaluation = evaluate_battle(&battle, 0, &ability_map);
+/// assert_eq!((evaluation.material, evaluation.health), (0.0, 0.0));
+/// assert!(evaluation.matchup > 0.0);
+/// assert!(evaluate_battle(&battle, 1, &ability_map).get_score() < 0.0);
+///
+/// // Fireball is super effective, so it's chosen over pursuit
+/// assert_eq!(choose_heuristic_command(&battle, 0, &ability_map), BattleCommand::UseAbility { side: 0, ability_slot: 1, target_side: 1 });
+/// battle.apply_damage(BattlerId::new(1, 0), 40);
+/// assert_eq!(evaluate_battle(&battle, 0, &ability_map).health, 0.5);
+/// ```
+pub fn evaluate_battle(battle: &Battle, side: usize, ability_map: &AbilityMap) -> BattleEvaluation {
+    let opponents: Vec<usize> = (0..battle.get_side_count()).filter(|other| *other != side && !battle.get_side(*other).is_eliminated()).collect();
+    if opponents.is_empty() {
+        return BattleEvaluation { material: get_standing_fraction(battle, side), health: get_health_fraction(battle, side), matchup: 0.0 };
+    }
+    let average = |value: &dyn Fn(usize) -> f32| opponents.iter().map(|opponent| value(*opponent)).sum::<f32>() / opponents.len() as f32;
+    let active = battle.get_active_battler_id(side);
+    return BattleEvaluation {
+        material: get_standing_fraction(battle, side) - average(&|opponent| get_standing_fraction(battle, opponent)),
+        health: get_health_fraction(battle, side) - average(&|opponent| get_health_fraction(battle, opponent)),
+        matchup: average(&|opponent| get_matchup(battle, active, battle.get_active_battler_id(opponent), ability_map))
+    };
+}
+
+/// The best type chart multiplier of a battler's attacks against an opponent, minus the opponent's best against it,
+/// under the battle's rules. Only abilities with uses left count, and a battler without any has a multiplier of 0.
+pub fn get_matchup(battle: &Battle, battler: BattlerId, opponent: BattlerId, ability_map: &AbilityMap) -> f32 {
+    let best_effectiveness = |attacker: BattlerId, defender: BattlerId| -> f32 {
+        return get_usable_abilities(battle, attacker, ability_map).iter()
+            .map(|(_, ability)| ability.get_base_ability_data())
+            .filter(|data| data.category == AbilityCategory::Attack)
+            .map(|data| battle.preview_effectiveness(attacker, defender, data))
+            .fold(0.0, f32::max);
+    };
+    return best_effectiveness(battler, opponent) - best_effectiveness(opponent, battler);
+}
+
+/// How good using an ability on a defender is expected to be: the fraction of the defender's max health it would
+/// remove, plus 1 if it would faint the defender, scaled by the ability's accuracy. Status abilities score 0.
+pub fn score_ability(battle: &Battle, attacker: BattlerId, defender: BattlerId, ability: &BaseAbilityData) -> f32 {
+    if ability.category == AbilityCategory::Status {
+        return 0.0;
+    }
+    let mut context = DamageContext::new(battle, attacker, defender, ability);
+    battle.apply_damage_effects(&mut context);
+    let damage = context.calculate();
+    let defender_data = battle.get_battler(defender);
+    let health = defender_data.get_health();
+    let max_health = defender_data.get_stats().health.max(1);
+    let faint_bonus = if health > 0 && damage >= health { 1.0 } else { 0.0 };
+    return (damage.min(health) as f32 / max_health as f32 + faint_bonus) * ability.accuracy.min(100) as f32 / 100.0;
+}
+
+/// Choose the command that looks best for a side, for the AI and for suggesting commands to new players. Switches to
+/// the healthy battler with the best health and matchup if the active one fainted and continues any forced multi-turn
+/// ability, otherwise uses the ability and target with the highest score_ability(), preferring earlier slots and
+/// targets on ties. Ends the turn if nothing else is possible. See choose_random_command()
+pub fn choose_heuristic_command(battle: &Battle, side: usize, ability_map: &AbilityMap) -> BattleCommand {
+    if battle.is_finished() || side >= battle.get_side_count() || battle.get_side(side).is_eliminated() {
+        return BattleCommand::EndTurn;
+    }
+    let battle_side = battle.get_side(side);
+    if battle_side.get_active().is_fainted() {
+        let opponents: Vec<usize> = (0..battle.get_side_count()).filter(|other| *other != side && !battle.get_side(*other).is_eliminated()).collect();
+        let switch_score = |slot: usize| -> f32 {
+            let battler = battle_side.get_battler(slot);
+            let health = battler.get_health() as f32 / battler.get_stats().health.max(1) as f32;
+            let matchup: f32 = opponents.iter().map(|opponent| get_matchup(battle, BattlerId::new(side, slot), battle.get_active_battler_id(*opponent), ability_map)).sum();
+            return health * HEALTH_WEIGHT + matchup * MATCHUP_WEIGHT;
+        };
+        let mut best: Option<(usize, f32)> = None;
+        for slot in (0..battle_side.get_team().len()).filter(|slot| !battle_side.get_battler(*slot).is_fainted()) {
+            let score = switch_score(slot);
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((slot, score));
+            }
+        }
+        return match best {
+            Some((slot, _)) => BattleCommand::Switch { side, slot },
+            None => BattleCommand::EndTurn
+        };
+    }
+    if battle.get_forced_action(side).is_some() {
+        return BattleCommand::Continue { side };
+    }
+    let attacker = battle.get_active_battler_id(side);
+    let mut best: Option<(BattleCommand, f32)> = None;
+    for (ability_slot, ability) in get_usable_abilities(battle, attacker, ability_map) {
+        for target_side in battle.get_valid_targets(side) {
+            let score = score_ability(battle, attacker, battle.get_active_battler_id(target_side), ability.get_base_ability_data());
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((BattleCommand::UseAbility { side, ability_slot, target_side }, score));
+            }
+        }
+    }
+    return match best {
+        Some((command, _)) => command,
+        None => BattleCommand::EndTurn
+    };
+}
+
+/// Abilities of a battler that have uses left, with their slots.
+fn get_usable_abilities(battle: &Battle, battler: BattlerId, ability_map: &AbilityMap) -> Vec<(usize, Box<dyn Ability>)> {
+    let battler = battle.get_battler(battler);
+    return battler.get_abilities().iter().enumerate().filter_map(|(slot, ability)| {
+        let name = ability.to_string();
+        if !ability_map.is_ability_name(&name) {
+            return None;
+        }
+        let ability = ability_map.new_ability(&name);
+        if battler.get_remaining_uses(slot, ability.get_base_ability_data().max_uses) == 0 {
+            return None;
+        }
+        return Some((slot, ability));
+    }).collect();
+}
+
+fn get_standing_fraction(battle: &Battle, side: usize) -> f32 {
+    let team = battle.get_side(side).get_team();
+    if team.is_empty() {
+        return 0.0;
+    }
+    return team.iter().filter(|battler| !battler.is_fainted()).count() as f32 / team.len() as f32;
+}
+
+fn get_health_fraction(battle: &Battle, side: usize) -> f32 {
+    let team = battle.get_side(side).get_team();
+    let max_health: u64 = team.iter().map(|battler| battler.get_stats().health as u64).sum();
+    if max_health == 0 {
+        return 0.0;
+    }
+    return team.iter().map(|battler| battler.get_health() as u64).sum::<u64>() as f32 / max_health as f32;
+}
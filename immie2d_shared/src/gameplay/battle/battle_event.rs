@@ -0,0 +1,416 @@
+use bytes::{BufMut, BytesMut};
+use serde::{Deserialize, Serialize};
+
+use crate::engine_types::encode_buffer::Encode;
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::elements::element_kinds::{ElementKind, ELEMENT_COUNT};
+
+use super::battler_id::BattlerId;
+use super::entry_hazard::HazardKind;
+use super::forced_action::ForcedActionKind;
+use super::hit_resolution::{HitBlocker, SemiInvulnerability};
+use super::state_diff::BattlerStatus;
+
+/* Events emitted by a battle for the client to display and animate, in the order they occurred. */
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum BattleEvent {
+    /// A battler transformed into the alternate form of its species.
+    Transformed { battler: BattlerId, form_name: GlobalString },
+    /// A transformed battler returned to its original form.
+    Reverted { battler: BattlerId },
+    /// A capture device was thrown and will only need a single shake check.
+    CriticalCapture { battler: BattlerId },
+    /// A capture device shook, starting from 1.
+    CaptureShake { battler: BattlerId, shake: u32 },
+    Captured { battler: BattlerId },
+    CaptureFailed { battler: BattlerId },
+    /// The active battler of a side was replaced by the battler in another slot.
+    Switched { side: usize, slot: usize },
+    /// A battler used an ability on a battler that was about to switch out, before it could leave.
+    SwitchIntercepted { attacker: BattlerId, retreating: BattlerId },
+    /// An ability didn't hit the defender because of a blocker.
+    AbilityBlocked { defender: BattlerId, blocker: HitBlocker },
+    /// An ability missed the defender from its accuracy or the defender's evasion.
+    AbilityMissed { attacker: BattlerId, defender: BattlerId },
+    /// A battler's next ability on the target can't miss. See LockOn
+    LockedOn { attacker: BattlerId, target: BattlerId },
+    /// A battler charging an ability flew up or dug underground. It comes back when the ability hits or is cancelled.
+    Vanished { battler: BattlerId, state: SemiInvulnerability },
+    /// The substitute of a battler took damage in its place.
+    SubstituteDamaged { battler: BattlerId, amount: u32, remaining_health: u32 },
+    /// An ability was powered up by following the ability its user used on the previous turn.
+    ComboTriggered { battler: BattlerId, follows: GlobalString },
+    /// A battler finished a turn of a multi-turn ability. The battler is forced to act next turn while turn is less
+    /// than total_turns. Recharging starts from turn 0 on the turn the ability hit.
+    MultiTurnProgress { battler: BattlerId, kind: ForcedActionKind, turn: u32, total_turns: u32 },
+    /// A multi-turn ability stopped early, such as when its target left or its user switched out.
+    MultiTurnCancelled { battler: BattlerId },
+    Damaged { battler: BattlerId, amount: u32, remaining_health: u32 },
+    Fainted { battler: BattlerId },
+    /// Every battler of a side has fainted and it can no longer act.
+    SideEliminated { side: usize },
+    /// Every side has acted. Turns start from 1.
+    TurnEnded { turn: u32 },
+    /// The battle is over. The winner is None if no side remains.
+    BattleEnded { winner: Option<usize> },
+    /// A battler started posing as a target, appearing as the target's species. See CopiedIdentity
+    IdentityCopied { battler: BattlerId, target: BattlerId, species: GlobalString },
+    /// A battler copied the last ability a target used. See CopiedAbility
+    AbilityCopied { battler: BattlerId, target: BattlerId, ability: GlobalString },
+    /// A battler failed to copy a target or its last ability.
+    CopyFailed { battler: BattlerId, target: BattlerId },
+    /// A hazard was laid on a side, which now has some layers of it. It stays on the field until cleared.
+    HazardSet { side: usize, kind: HazardKind, layers: u32 },
+    /// Every hazard was removed from a side.
+    HazardsCleared { side: usize },
+    /// A battler switching in was affected by the hazard on its side, which has some layers.
+    HazardTriggered { battler: BattlerId, kind: HazardKind, layers: u32 },
+    /// A battler switching in was immune to a hazard on its side.
+    HazardAvoided { battler: BattlerId, kind: HazardKind },
+    /// A battler regained health, which is now `health`.
+    Healed { battler: BattlerId, amount: u32, health: u32 },
+    /// A battler's evasion stage changed, and is now `stage`.
+    EvasionStageChanged { battler: BattlerId, stage: i32 },
+    /// A battler entered or left a status.
+    StatusChanged { battler: BattlerId, status: BattlerStatus, is_active: bool },
+    /// A battler attuned, making `element` its only element for the rest of the battle.
+    Attuned { battler: BattlerId, element: ElementKind }
+}
+
+/// Tag byte of each event in the encoding, in the order of the variants.
+const TRANSFORMED_TAG: u8 = 0;
+const REVERTED_TAG: u8 = 1;
+const CRITICAL_CAPTURE_TAG: u8 = 2;
+const CAPTURE_SHAKE_TAG: u8 = 3;
+const CAPTURED_TAG: u8 = 4;
+const CAPTURE_FAILED_TAG: u8 = 5;
+const SWITCHED_TAG: u8 = 6;
+const SWITCH_INTERCEPTED_TAG: u8 = 7;
+const ABILITY_BLOCKED_TAG: u8 = 8;
+const ABILITY_MISSED_TAG: u8 = 9;
+const LOCKED_ON_TAG: u8 = 10;
+const VANISHED_TAG: u8 = 11;
+const SUBSTITUTE_DAMAGED_TAG: u8 = 12;
+const COMBO_TRIGGERED_TAG: u8 = 13;
+const MULTI_TURN_PROGRESS_TAG: u8 = 14;
+const MULTI_TURN_CANCELLED_TAG: u8 = 15;
+const DAMAGED_TAG: u8 = 16;
+const FAINTED_TAG: u8 = 17;
+const SIDE_ELIMINATED_TAG: u8 = 18;
+const TURN_ENDED_TAG: u8 = 19;
+const BATTLE_ENDED_TAG: u8 = 20;
+const IDENTITY_COPIED_TAG: u8 = 21;
+const ABILITY_COPIED_TAG: u8 = 22;
+const COPY_FAILED_TAG: u8 = 23;
+const HAZARD_SET_TAG: u8 = 24;
+const HAZARDS_CLEARED_TAG: u8 = 25;
+const HAZARD_TRIGGERED_TAG: u8 = 26;
+const HAZARD_AVOIDED_TAG: u8 = 27;
+const HEALED_TAG: u8 = 28;
+const EVASION_STAGE_CHANGED_TAG: u8 = 29;
+const STATUS_CHANGED_TAG: u8 = 30;
+const ATTUNED_TAG: u8 = 31;
+
+impl Encode for BattleEvent {
+    /// Encode as a tag byte followed by each field. Sides and slots take a byte, numbers are little endian u32s and
+    /// names are u16 length prefixed. Flags take a byte of 0 or 1. Will panic if a side or slot doesn't fit in a byte,
+    /// or a name is longer than u16::MAX bytes.
+    /// ```should_panic
+    /// use immie2d_shared::engine_types::{encode_buffer::Encode, global_string::GlobalString};
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    ///
+    /// let form_name = GlobalString::new(&"a".repeat(u16::MAX as usize + 1));
+    /// // Will panic
+    /// BattleEvent::Transformed { battler: BattlerId::new(0, 0), form_name }.encode_to_vec();
+    /// ```
+    fn encode_into(&self, buffer: &mut BytesMut) {
+        match *self {
+            BattleEvent::Transformed { battler, form_name } => {
+                buffer.put_u8(TRANSFORMED_TAG);
+                put_battler(buffer, battler);
+                put_name(buffer, form_name);
+            },
+            BattleEvent::Reverted { battler } => put_tagged_battler(buffer, REVERTED_TAG, battler),
+            BattleEvent::CriticalCapture { battler } => put_tagged_battler(buffer, CRITICAL_CAPTURE_TAG, battler),
+            BattleEvent::CaptureShake { battler, shake } => {
+                put_tagged_battler(buffer, CAPTURE_SHAKE_TAG, battler);
+                buffer.put_u32_le(shake);
+            },
+            BattleEvent::Captured { battler } => put_tagged_battler(buffer, CAPTURED_TAG, battler),
+            BattleEvent::CaptureFailed { battler } => put_tagged_battler(buffer, CAPTURE_FAILED_TAG, battler),
+            BattleEvent::Switched { side, slot } => {
+                buffer.put_u8(SWITCHED_TAG);
+                put_battler(buffer, BattlerId::new(side, slot));
+            },
+            BattleEvent::SwitchIntercepted { attacker, retreating } => {
+                put_tagged_battler(buffer, SWITCH_INTERCEPTED_TAG, attacker);
+                put_battler(buffer, retreating);
+            },
+            BattleEvent::AbilityBlocked { defender, blocker } => {
+                put_tagged_battler(buffer, ABILITY_BLOCKED_TAG, defender);
+                buffer.put_u8(blocker.get_id());
+            },
+            BattleEvent::AbilityMissed { attacker, defender } => {
+                put_tagged_battler(buffer, ABILITY_MISSED_TAG, attacker);
+                put_battler(buffer, defender);
+            },
+            BattleEvent::LockedOn { attacker, target } => {
+                put_tagged_battler(buffer, LOCKED_ON_TAG, attacker);
+                put_battler(buffer, target);
+            },
+            BattleEvent::Vanished { battler, state } => {
+                put_tagged_battler(buffer, VANISHED_TAG, battler);
+                buffer.put_u8(state.get_id());
+            },
+            BattleEvent::SubstituteDamaged { battler, amount, remaining_health } => {
+                put_tagged_battler(buffer, SUBSTITUTE_DAMAGED_TAG, battler);
+                buffer.put_u32_le(amount);
+                buffer.put_u32_le(remaining_health);
+            },
+            BattleEvent::ComboTriggered { battler, follows } => {
+                put_tagged_battler(buffer, COMBO_TRIGGERED_TAG, battler);
+                put_name(buffer, follows);
+            },
+            BattleEvent::MultiTurnProgress { battler, kind, turn, total_turns } => {
+                put_tagged_battler(buffer, MULTI_TURN_PROGRESS_TAG, battler);
+                buffer.put_u8(kind.get_id());
+                buffer.put_u32_le(turn);
+                buffer.put_u32_le(total_turns);
+            },
+            BattleEvent::MultiTurnCancelled { battler } => put_tagged_battler(buffer, MULTI_TURN_CANCELLED_TAG, battler),
+            BattleEvent::Damaged { battler, amount, remaining_health } => {
+                put_tagged_battler(buffer, DAMAGED_TAG, battler);
+                buffer.put_u32_le(amount);
+                buffer.put_u32_le(remaining_health);
+            },
+            BattleEvent::Fainted { battler } => put_tagged_battler(buffer, FAINTED_TAG, battler),
+            BattleEvent::SideEliminated { side } => {
+                buffer.put_u8(SIDE_ELIMINATED_TAG);
+                buffer.put_u8(get_byte(side));
+            },
+            BattleEvent::TurnEnded { turn } => {
+                buffer.put_u8(TURN_ENDED_TAG);
+                buffer.put_u32_le(turn);
+            },
+            BattleEvent::BattleEnded { winner } => {
+                buffer.put_u8(BATTLE_ENDED_TAG);
+                // 0 for no winner, otherwise the winning side plus 1
+                buffer.put_u8(winner.map(|side| get_byte(side + 1)).unwrap_or(0));
+            },
+            BattleEvent::IdentityCopied { battler, target, species } => {
+                put_tagged_battler(buffer, IDENTITY_COPIED_TAG, battler);
+                put_battler(buffer, target);
+                put_name(buffer, species);
+            },
+            BattleEvent::AbilityCopied { battler, target, ability } => {
+                put_tagged_battler(buffer, ABILITY_COPIED_TAG, battler);
+                put_battler(buffer, target);
+                put_name(buffer, ability);
+            },
+            BattleEvent::CopyFailed { battler, target } => {
+                put_tagged_battler(buffer, COPY_FAILED_TAG, battler);
+                put_battler(buffer, target);
+            },
+            BattleEvent::HazardSet { side, kind, layers } => {
+                buffer.put_u8(HAZARD_SET_TAG);
+                buffer.put_u8(get_byte(side));
+                buffer.put_u8(kind.get_id());
+                buffer.put_u32_le(layers);
+            },
+            BattleEvent::HazardsCleared { side } => {
+                buffer.put_u8(HAZARDS_CLEARED_TAG);
+                buffer.put_u8(get_byte(side));
+            },
+            BattleEvent::HazardTriggered { battler, kind, layers } => {
+                put_tagged_battler(buffer, HAZARD_TRIGGERED_TAG, battler);
+                buffer.put_u8(kind.get_id());
+                buffer.put_u32_le(layers);
+            },
+            BattleEvent::HazardAvoided { battler, kind } => {
+                put_tagged_battler(buffer, HAZARD_AVOIDED_TAG, battler);
+                buffer.put_u8(kind.get_id());
+            },
+            BattleEvent::Healed { battler, amount, health } => {
+                put_tagged_battler(buffer, HEALED_TAG, battler);
+                buffer.put_u32_le(amount);
+                buffer.put_u32_le(health);
+            },
+            BattleEvent::EvasionStageChanged { battler, stage } => {
+                put_tagged_battler(buffer, EVASION_STAGE_CHANGED_TAG, battler);
+                buffer.put_i32_le(stage);
+            },
+            BattleEvent::StatusChanged { battler, status, is_active } => {
+                put_tagged_battler(buffer, STATUS_CHANGED_TAG, battler);
+                buffer.put_u8(status.get_id());
+                buffer.put_u8(is_active as u8);
+            },
+            BattleEvent::Attuned { battler, element } => {
+                put_tagged_battler(buffer, ATTUNED_TAG, battler);
+                buffer.put_u8(element as u8);
+            }
+        }
+    }
+}
+
+impl BattleEvent {
+    /// Whether the event only animates something, such as a capture device shaking or an ability missing, leaving
+    /// everything a viewer shows about the battle unchanged. Cosmetic events can be dropped for viewers that can't keep
+    /// up, while every other event must reach them.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    ///
+    /// let battler = BattlerId::new(1, 0);
+    /// assert!(BattleEvent::CaptureShake { battler, shake: 1 }.is_cosmetic());
+    /// assert!(!BattleEvent::Damaged { battler, amount: 5, remaining_health: 10 }.is_cosmetic());
+    /// // Viewers show which battler is locked on until its next ability
+    /// assert!(!BattleEvent::LockedOn { attacker: battler, target: BattlerId::new(0, 0) }.is_cosmetic());
+    /// ```
+    pub fn is_cosmetic(&self) -> bool {
+        return matches!(self,
+            BattleEvent::CriticalCapture { .. } | BattleEvent::CaptureShake { .. } | BattleEvent::SwitchIntercepted { .. }
+            | BattleEvent::AbilityBlocked { .. } | BattleEvent::AbilityMissed { .. }
+            | BattleEvent::ComboTriggered { .. } | BattleEvent::CopyFailed { .. } | BattleEvent::HazardTriggered { .. }
+            | BattleEvent::HazardAvoided { .. }
+        );
+    }
+
+    /// Decode a single event from the start of some bytes. Returns the event and the number of bytes it used, or
+    /// None if the bytes don't start with a valid event.
+    /// ```
+    /// use immie2d_shared::engine_types::{encode_buffer::Encode, global_string::GlobalString};
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId, forced_action::ForcedActionKind, entry_hazard::HazardKind, state_diff::BattlerStatus};
+    /// use immie2d_shared::gameplay::elements::element_kinds::ElementKind;
+    ///
+    /// let battler = BattlerId::new(1, 2);
+    /// let events = [
+    ///     BattleEvent::ComboTriggered { battler, follows: GlobalString::new(&"fireball".to_string()) },
+    ///     BattleEvent::MultiTurnProgress { battler, kind: ForcedActionKind::Charging, turn: 1, total_turns: 2 },
+    ///     BattleEvent::IdentityCopied { battler, target: BattlerId::new(0, 0), species: GlobalString::new(&"tidefin".to_string()) },
+    ///     BattleEvent::HazardSet { side: 1, kind: HazardKind::Spikes, layers: 2 },
+    ///     BattleEvent::HazardAvoided { battler, kind: HazardKind::Webs },
+    ///     BattleEvent::EvasionStageChanged { battler, stage: -2 },
+    ///     BattleEvent::StatusChanged { battler, status: BattlerStatus::Substitute, is_active: true },
+    ///     BattleEvent::Attuned { battler, element: ElementKind::Water },
+    ///     BattleEvent::BattleEnded { winner: None },
+    ///     BattleEvent::BattleEnded { winner: Some(0) }
+    /// ];
+    /// for event in events {
+    ///     let bytes = event.encode_to_vec();
+    ///     assert_eq!(BattleEvent::decode(&bytes), Some((event, bytes.len())));
+    ///     assert_eq!(BattleEvent::decode(&bytes[..bytes.len() - 1]), None);
+    /// }
+    /// assert_eq!(BattleEvent::decode(&[200]), None);
+    /// ```
+    pub fn decode(bytes: &[u8]) -> Option<(BattleEvent, usize)> {
+        let mut offset = 0;
+        let take_u8 = |offset: &mut usize| -> Option<u8> {
+            let value = *bytes.get(*offset)?;
+            *offset += 1;
+            return Some(value);
+        };
+        let take_u32 = |offset: &mut usize| -> Option<u32> {
+            let value = u32::from_le_bytes(bytes.get(*offset..*offset + 4)?.try_into().ok()?);
+            *offset += 4;
+            return Some(value);
+        };
+        let take_flag = |offset: &mut usize| -> Option<bool> {
+            return match take_u8(offset)? {
+                0 => Some(false),
+                1 => Some(true),
+                _ => None
+            };
+        };
+        let take_battler = |offset: &mut usize| -> Option<BattlerId> {
+            let side = take_u8(offset)? as usize;
+            return Some(BattlerId::new(side, take_u8(offset)? as usize));
+        };
+        let take_name = |offset: &mut usize| -> Option<GlobalString> {
+            let length = u16::from_le_bytes(bytes.get(*offset..*offset + 2)?.try_into().ok()?) as usize;
+            let name = std::str::from_utf8(bytes.get(*offset + 2..*offset + 2 + length)?).ok()?;
+            *offset += 2 + length;
+            return Some(GlobalString::new(&name.to_string()));
+        };
+        let event = match take_u8(&mut offset)? {
+            TRANSFORMED_TAG => BattleEvent::Transformed { battler: take_battler(&mut offset)?, form_name: take_name(&mut offset)? },
+            REVERTED_TAG => BattleEvent::Reverted { battler: take_battler(&mut offset)? },
+            CRITICAL_CAPTURE_TAG => BattleEvent::CriticalCapture { battler: take_battler(&mut offset)? },
+            CAPTURE_SHAKE_TAG => BattleEvent::CaptureShake { battler: take_battler(&mut offset)?, shake: take_u32(&mut offset)? },
+            CAPTURED_TAG => BattleEvent::Captured { battler: take_battler(&mut offset)? },
+            CAPTURE_FAILED_TAG => BattleEvent::CaptureFailed { battler: take_battler(&mut offset)? },
+            SWITCHED_TAG => {
+                let battler = take_battler(&mut offset)?;
+                BattleEvent::Switched { side: battler.side, slot: battler.slot }
+            },
+            SWITCH_INTERCEPTED_TAG => BattleEvent::SwitchIntercepted { attacker: take_battler(&mut offset)?, retreating: take_battler(&mut offset)? },
+            ABILITY_BLOCKED_TAG => BattleEvent::AbilityBlocked { defender: take_battler(&mut offset)?, blocker: HitBlocker::from_id(take_u8(&mut offset)?)? },
+            ABILITY_MISSED_TAG => BattleEvent::AbilityMissed { attacker: take_battler(&mut offset)?, defender: take_battler(&mut offset)? },
+            LOCKED_ON_TAG => BattleEvent::LockedOn { attacker: take_battler(&mut offset)?, target: take_battler(&mut offset)? },
+            VANISHED_TAG => BattleEvent::Vanished { battler: take_battler(&mut offset)?, state: SemiInvulnerability::from_id(take_u8(&mut offset)?)? },
+            SUBSTITUTE_DAMAGED_TAG => BattleEvent::SubstituteDamaged { battler: take_battler(&mut offset)?, amount: take_u32(&mut offset)?, remaining_health: take_u32(&mut offset)? },
+            COMBO_TRIGGERED_TAG => BattleEvent::ComboTriggered { battler: take_battler(&mut offset)?, follows: take_name(&mut offset)? },
+            MULTI_TURN_PROGRESS_TAG => BattleEvent::MultiTurnProgress {
+                battler: take_battler(&mut offset)?,
+                kind: ForcedActionKind::from_id(take_u8(&mut offset)?)?,
+                turn: take_u32(&mut offset)?,
+                total_turns: take_u32(&mut offset)?
+            },
+            MULTI_TURN_CANCELLED_TAG => BattleEvent::MultiTurnCancelled { battler: take_battler(&mut offset)? },
+            DAMAGED_TAG => BattleEvent::Damaged { battler: take_battler(&mut offset)?, amount: take_u32(&mut offset)?, remaining_health: take_u32(&mut offset)? },
+            FAINTED_TAG => BattleEvent::Fainted { battler: take_battler(&mut offset)? },
+            SIDE_ELIMINATED_TAG => BattleEvent::SideEliminated { side: take_u8(&mut offset)? as usize },
+            TURN_ENDED_TAG => BattleEvent::TurnEnded { turn: take_u32(&mut offset)? },
+            BATTLE_ENDED_TAG => BattleEvent::BattleEnded { winner: (take_u8(&mut offset)? as usize).checked_sub(1) },
+            IDENTITY_COPIED_TAG => BattleEvent::IdentityCopied { battler: take_battler(&mut offset)?, target: take_battler(&mut offset)?, species: take_name(&mut offset)? },
+            ABILITY_COPIED_TAG => BattleEvent::AbilityCopied { battler: take_battler(&mut offset)?, target: take_battler(&mut offset)?, ability: take_name(&mut offset)? },
+            COPY_FAILED_TAG => BattleEvent::CopyFailed { battler: take_battler(&mut offset)?, target: take_battler(&mut offset)? },
+            HAZARD_SET_TAG => BattleEvent::HazardSet {
+                side: take_u8(&mut offset)? as usize,
+                kind: HazardKind::from_id(take_u8(&mut offset)?)?,
+                layers: take_u32(&mut offset)?
+            },
+            HAZARDS_CLEARED_TAG => BattleEvent::HazardsCleared { side: take_u8(&mut offset)? as usize },
+            HAZARD_TRIGGERED_TAG => BattleEvent::HazardTriggered { battler: take_battler(&mut offset)?, kind: HazardKind::from_id(take_u8(&mut offset)?)?, layers: take_u32(&mut offset)? },
+            HAZARD_AVOIDED_TAG => BattleEvent::HazardAvoided { battler: take_battler(&mut offset)?, kind: HazardKind::from_id(take_u8(&mut offset)?)? },
+            HEALED_TAG => BattleEvent::Healed { battler: take_battler(&mut offset)?, amount: take_u32(&mut offset)?, health: take_u32(&mut offset)? },
+            EVASION_STAGE_CHANGED_TAG => BattleEvent::EvasionStageChanged { battler: take_battler(&mut offset)?, stage: take_u32(&mut offset)? as i32 },
+            STATUS_CHANGED_TAG => BattleEvent::StatusChanged {
+                battler: take_battler(&mut offset)?,
+                status: BattlerStatus::from_id(take_u8(&mut offset)?)?,
+                is_active: take_flag(&mut offset)?
+            },
+            ATTUNED_TAG => {
+                let battler = take_battler(&mut offset)?;
+                let element = take_u8(&mut offset)? as u32;
+                if element == 0 || element > ELEMENT_COUNT {
+                    return None;
+                }
+                BattleEvent::Attuned { battler, element: ElementKind::from(element) }
+            },
+            _ => return None
+        };
+        return Some((event, offset));
+    }
+}
+
+fn get_byte(value: usize) -> u8 {
+    assert!(value <= u8::MAX as usize, "Battle event field {} does not fit in a byte", value);
+    return value as u8;
+}
+
+fn put_battler(buffer: &mut BytesMut, battler: BattlerId) {
+    buffer.put_u8(get_byte(battler.side));
+    buffer.put_u8(get_byte(battler.slot));
+}
+
+fn put_tagged_battler(buffer: &mut BytesMut, tag: u8, battler: BattlerId) {
+    buffer.put_u8(tag);
+    put_battler(buffer, battler);
+}
+
+fn put_name(buffer: &mut BytesMut, name: GlobalString) {
+    name.with_str(|name| {
+        assert!(name.len() <= u16::MAX as usize, "Battle event name of {} bytes does not fit its u16 length", name.len());
+        buffer.put_u16_le(name.len() as u16);
+        buffer.put_slice(name.as_bytes());
+    });
+}
@@ -0,0 +1,71 @@
+/* How many participants take part in a battle and how they relate to each other. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BattleFormat {
+    /// One participant against another.
+    Single,
+    /// Every participant against every other participant, with the last one standing winning.
+    FreeForAll { participants: u32 },
+    /// Players allied against a single boss, which is always the last side.
+    Raid { players: u32 }
+}
+
+pub const MIN_FREE_FOR_ALL_PARTICIPANTS: u32 = 3;
+pub const MAX_FREE_FOR_ALL_PARTICIPANTS: u32 = 4;
+pub const MAX_RAID_PLAYERS: u32 = 4;
+
+impl BattleFormat {
+    /// Create a free-for-all format.
+    /// Will panic if the number of participants is outside of MIN_FREE_FOR_ALL_PARTICIPANTS and MAX_FREE_FOR_ALL_PARTICIPANTS.
+    /// ``` should_panic
+    /// use immie2d_shared::gameplay::battle::battle_format::BattleFormat;
+    /// // Will panic
+    /// let format = BattleFormat::free_for_all(5);
+    /// ```
+    pub fn free_for_all(participants: u32) -> BattleFormat {
+        assert!((MIN_FREE_FOR_ALL_PARTICIPANTS..=MAX_FREE_FOR_ALL_PARTICIPANTS).contains(&participants),
+            "Free-for-all battles require between {} and {} participants. Got {}", MIN_FREE_FOR_ALL_PARTICIPANTS, MAX_FREE_FOR_ALL_PARTICIPANTS, participants);
+        return BattleFormat::FreeForAll { participants };
+    }
+
+    /// Create a raid format. Will panic if there are no players or more than MAX_RAID_PLAYERS.
+    pub fn raid(players: u32) -> BattleFormat {
+        assert!((1..=MAX_RAID_PLAYERS).contains(&players), "Raids require between 1 and {} players. Got {}", MAX_RAID_PLAYERS, players);
+        return BattleFormat::Raid { players };
+    }
+
+    /// Get the number of sides a battle of this format has.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::battle_format::BattleFormat;
+    /// assert_eq!(BattleFormat::Single.get_participant_count(), 2);
+    /// assert_eq!(BattleFormat::free_for_all(4).get_participant_count(), 4);
+    /// ```
+    pub fn get_participant_count(&self) -> u32 {
+        return match *self {
+            BattleFormat::Single => 2,
+            BattleFormat::FreeForAll { participants } => participants,
+            BattleFormat::Raid { players } => players + 1
+        };
+    }
+
+    /// The side of the raid boss, if this is a raid.
+    pub fn get_boss_side(&self) -> Option<usize> {
+        return match *self {
+            BattleFormat::Raid { players } => Some(players as usize),
+            _ => None
+        };
+    }
+
+    /// Whether two sides are on the same team. Every side is its own ally, and raid players are allies of each other.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::battle_format::BattleFormat;
+    /// assert!(!BattleFormat::Single.are_allies(0, 1));
+    /// assert!(BattleFormat::raid(3).are_allies(0, 2));
+    /// assert!(!BattleFormat::raid(3).are_allies(0, 3));
+    /// ```
+    pub fn are_allies(&self, a: usize, b: usize) -> bool {
+        return match *self {
+            BattleFormat::Raid { players } => a == b || (a < players as usize && b < players as usize),
+            _ => a == b
+        };
+    }
+}
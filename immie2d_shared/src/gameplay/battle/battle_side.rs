@@ -0,0 +1,121 @@
+use bytes::{BufMut, BytesMut};
+
+use super::battler::Battler;
+use super::campaign::Boon;
+use super::entry_hazard::{EntryHazards, HazardKind};
+use super::state_hash::{put_flag, put_usize};
+
+/* One participant's team within a battle, of which a single battler is active at a time. */
+#[derive(Clone, Debug)]
+pub struct BattleSide {
+    team: Vec<Battler>,
+    active_slot: usize,
+    /// Campaign boons the side entered the battle with. See CarryOver
+    boons: Vec<Boon>,
+    hazards: EntryHazards,
+    /// Whether a battler of the side has attuned this battle. See Battle::attune()
+    has_attuned: bool
+}
+
+impl BattleSide {
+    /// Create a side from a team, with the first battler active.
+    /// Will panic if the team is empty.
+    pub fn new(team: Vec<Battler>) -> BattleSide {
+        assert!(team.len() > 0, "Cannot create a battle side with no battlers");
+        return BattleSide { team, active_slot: 0, boons: Vec::new(), hazards: EntryHazards::new(), has_attuned: false };
+    }
+
+    /// Give every battler of the side campaign boons for the battle.
+    pub fn with_boons(mut self, boons: Vec<Boon>) -> BattleSide {
+        for boon in boons.iter() {
+            for battler in self.team.iter_mut() {
+                battler.add_boon(boon.kind);
+            }
+        }
+        self.boons = boons;
+        return self;
+    }
+
+    pub fn get_boons(&self) -> &[Boon] {
+        return &self.boons;
+    }
+
+    /// Hazards laid on the side, affecting each battler that switches in.
+    pub fn get_hazards(&self) -> &EntryHazards {
+        return &self.hazards;
+    }
+
+    pub fn get_hazards_mut(&mut self) -> &mut EntryHazards {
+        return &mut self.hazards;
+    }
+
+    /// Whether the side has used its one attunement of the battle.
+    pub fn has_attuned(&self) -> bool {
+        return self.has_attuned;
+    }
+
+    /// Attune a battler of the side, using up the side's attunement.
+    /// Will panic if the side has already attuned or the battler can't attune. See Battler::can_attune()
+    pub fn attune(&mut self, slot: usize) {
+        assert!(!self.has_attuned, "Side has already attuned a battler this battle");
+        self.team[slot].attune();
+        self.has_attuned = true;
+    }
+
+    pub fn get_active_slot(&self) -> usize {
+        return self.active_slot;
+    }
+
+    pub fn get_active(&self) -> &Battler {
+        return &self.team[self.active_slot];
+    }
+
+    /// Get a battler by slot. Will panic if the slot is out of bounds.
+    pub fn get_battler(&self, slot: usize) -> &Battler {
+        return &self.team[slot];
+    }
+
+    pub fn get_battler_mut(&mut self, slot: usize) -> &mut Battler {
+        return &mut self.team[slot];
+    }
+
+    pub fn get_team(&self) -> &[Battler] {
+        return &self.team;
+    }
+
+    pub fn get_team_mut(&mut self) -> &mut [Battler] {
+        return &mut self.team;
+    }
+
+    /// Make a different battler active.
+    /// Will panic if the slot is out of bounds, already active, or has fainted.
+    pub fn switch_active(&mut self, slot: usize) {
+        assert!(slot < self.team.len(), "Cannot switch to slot {}. The team only has {} battlers", slot, self.team.len());
+        assert!(slot != self.active_slot, "Cannot switch to the battler that is already active");
+        assert!(!self.team[slot].is_fainted(), "Cannot switch to a fainted battler");
+        self.active_slot = slot;
+    }
+
+    /// A side is eliminated once every battler in its team has fainted.
+    pub fn is_eliminated(&self) -> bool {
+        return self.team.iter().all(|battler| battler.is_fainted());
+    }
+
+    /// Append the side's state to the encoding hashed by Battle::get_state_hash().
+    pub(crate) fn put_state(&self, buffer: &mut BytesMut) {
+        put_usize(buffer, self.team.len());
+        for battler in self.team.iter() {
+            battler.put_state(buffer);
+        }
+        put_usize(buffer, self.active_slot);
+        put_usize(buffer, self.boons.len());
+        for boon in self.boons.iter() {
+            buffer.put_u8(boon.kind as u8);
+            buffer.put_u32_le(boon.battles);
+        }
+        for kind in HazardKind::ALL {
+            buffer.put_u32_le(self.hazards.get_layers(kind));
+        }
+        put_flag(buffer, self.has_attuned);
+    }
+}
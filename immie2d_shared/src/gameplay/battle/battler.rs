@@ -0,0 +1,633 @@
+use bytes::{BufMut, BytesMut};
+
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::ability::ability_names::AbilityNames;
+use crate::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+use crate::gameplay::immie::{bond::BondEvent, immie::Immie};
+use crate::gameplay::species::{base_stats::BaseStats, species_data::SpeciesData};
+
+use super::battler_id::BattlerId;
+use super::campaign::{BoonKind, BOON_KIND_COUNT, BOON_STAT_PERCENT};
+use super::copied_identity::{get_copied_remaining_uses, CopiedAbility, CopiedIdentity};
+use super::forced_action::ForcedAction;
+use super::hit_resolution::{LockOn, SemiInvulnerability, MAX_EVASION_STAGE};
+use super::state_hash::{put_battler_id, put_element, put_elements, put_flag, put_immie, put_name, put_option, put_stats, put_usize};
+
+/// How many of a battler's most recent ability uses are remembered.
+pub const ABILITY_HISTORY_LENGTH: usize = 4;
+
+/* An ability a battler used, and the turn it was used on. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct UsedAbility {
+    pub turn: u32,
+    pub ability: GlobalString
+}
+
+/* The in-battle state of an Immie. Anything modified here is discarded once the battle ends, unless it is carried
+over to the next battle of a campaign run. See Battle::get_carry_over() */
+#[derive(Clone, Copy, Debug)]
+pub struct Battler {
+    immie: Immie,
+    species_elements: Elements,
+    species_stats: BaseStats,
+    elements: Elements,
+    stats: BaseStats,
+    health: u32,
+    is_transformed: bool,
+    has_transformed: bool,
+    /// Protected from abilities for the rest of the turn.
+    is_protected: bool,
+    /// Health of a substitute taking hits in place of the battler. 0 if there is no substitute.
+    substitute_health: u32,
+    /// Most recently used abilities, newest first.
+    ability_history: [Option<UsedAbility>; ABILITY_HISTORY_LENGTH],
+    /// The turn of a multi-turn ability the battler must take next instead of a chosen command.
+    forced_action: Option<ForcedAction>,
+    /// Number of campaign boons of each kind. See BoonKind
+    boons: [u32; BOON_KIND_COUNT],
+    /// Between -MAX_EVASION_STAGE and MAX_EVASION_STAGE. See get_hit_chance()
+    evasion_stage: i32,
+    /// Out of reach while charging a flying or digging ability.
+    semi_invulnerability: Option<SemiInvulnerability>,
+    lock_on: Option<LockOn>,
+    /// Overrides the battler's species, elements, stats and abilities while it is out.
+    copied_identity: Option<CopiedIdentity>,
+    /// Overrides a single ability slot while the battler is out.
+    copied_ability: Option<CopiedAbility>,
+    /// The only element of the battler for the rest of the battle once it attunes, overriding every other element.
+    attuned_element: Option<ElementKind>
+}
+
+impl Battler {
+    /// Create a battler from an Immie and the data of its species, starting with the Immie's current health.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::battler::Battler;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let immie = Immie::new(species.name, 5, AbilityNames::default());
+    /// let battler = Battler::new(immie, &species);
+    /// assert_eq!(battler.get_health(), 50);
+    /// assert!(battler.get_elements().has_elements(ElementKind::Fire));
+    /// ```
+    pub fn new(immie: Immie, species: &SpeciesData) -> Battler {
+        assert!(immie.species == species.name, "Species data {} does not match the species of the Immie {}", species.name, immie.species);
+        return Battler {
+            immie,
+            species_elements: species.elements,
+            species_stats: species.base_stats,
+            elements: species.elements,
+            stats: species.base_stats,
+            health: immie.get_health(species),
+            is_transformed: false,
+            has_transformed: false,
+            is_protected: false,
+            substitute_health: 0,
+            ability_history: [None; ABILITY_HISTORY_LENGTH],
+            forced_action: None,
+            boons: [0; BOON_KIND_COUNT],
+            evasion_stage: 0,
+            semi_invulnerability: None,
+            lock_on: None,
+            copied_identity: None,
+            copied_ability: None,
+            attuned_element: None
+        };
+    }
+
+    /// Multiply the max and current health of the battler, such as for a raid boss. Health is capped at u32::MAX
+    /// rather than overflowing. Transforming uses the transformation's unscaled max health, so scaled battlers
+    /// shouldn't be able to transform.
+    pub fn scale_health(&mut self, scale: u32) {
+        self.species_stats.health = self.species_stats.health.saturating_mul(scale);
+        self.stats.health = self.stats.health.saturating_mul(scale);
+        self.health = self.health.saturating_mul(scale);
+    }
+
+    pub fn get_immie(&self) -> &Immie {
+        return &self.immie;
+    }
+
+    /// The species the battler appears as, which is the species of a copied target while copying one.
+    pub fn get_species(&self) -> GlobalString {
+        return match self.copied_identity {
+            Some(identity) => identity.species,
+            None => self.immie.species
+        };
+    }
+
+    /// The elements the battler attacks and defends with. An attuned battler only has the element it attuned to.
+    pub fn get_elements(&self) -> Elements {
+        if let Some(element) = self.attuned_element {
+            return Elements::new(vec![element]);
+        }
+        return match self.copied_identity {
+            Some(identity) => identity.elements,
+            None => self.elements
+        };
+    }
+
+    /// Current stats, including campaign boons.
+    pub fn get_stats(&self) -> BaseStats {
+        let stats = self.get_unboosted_stats();
+        let boost = |stat: u32, kind: BoonKind| stat * (100 + BOON_STAT_PERCENT * self.boons[kind as usize]) / 100;
+        return BaseStats {
+            health: stats.health,
+            attack: boost(stats.attack, BoonKind::Attack),
+            defense: boost(stats.defense, BoonKind::Defense),
+            speed: boost(stats.speed, BoonKind::Speed)
+        };
+    }
+
+    /// Current stats without campaign boons. A copied identity never changes max health.
+    fn get_unboosted_stats(&self) -> BaseStats {
+        return match self.copied_identity {
+            Some(identity) => BaseStats { health: self.stats.health, ..identity.stats },
+            None => self.stats
+        };
+    }
+
+    /// The abilities the battler can use right now, including any copied abilities. Battles should always read
+    /// abilities from here rather than from the Immie.
+    pub fn get_abilities(&self) -> AbilityNames {
+        if let Some(identity) = self.copied_identity {
+            return identity.abilities;
+        }
+        return match self.copied_ability {
+            Some(copied) => {
+                let mut names = self.immie.abilities.get_names();
+                names[copied.slot] = copied.ability;
+                AbilityNames::new(names)
+            },
+            None => self.immie.abilities
+        };
+    }
+
+    /// Uses left of the ability in a slot of get_abilities(). Copied abilities have their own uses.
+    /// See COPIED_ABILITY_USES
+    pub fn get_remaining_uses(&self, ability_slot: usize, max_uses: u32) -> u32 {
+        if let Some(identity) = self.copied_identity {
+            return get_copied_remaining_uses(identity.ability_uses_spent[ability_slot], max_uses);
+        }
+        return match self.copied_ability {
+            Some(copied) if copied.slot == ability_slot => get_copied_remaining_uses(copied.uses_spent, max_uses),
+            _ => self.immie.get_remaining_uses(ability_slot, max_uses)
+        };
+    }
+
+    /// Raise a stat for the whole battle, including through transformations. See Boon
+    pub fn add_boon(&mut self, kind: BoonKind) {
+        self.boons[kind as usize] += 1;
+    }
+
+    /// The Immie as it leaves the battle, with its health lost kept as damage taken.
+    pub fn get_carried_immie(&self) -> Immie {
+        let mut immie = self.immie;
+        immie.damage_taken = self.species_stats.health - self.health;
+        return immie;
+    }
+
+    pub fn get_health(&self) -> u32 {
+        return self.health;
+    }
+
+    pub fn is_fainted(&self) -> bool {
+        return self.health == 0;
+    }
+
+    /// Reduce the health of the battler, not going below 0. Returns the amount of health actually lost.
+    /// Fainting cancels any forced action.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::battler::Battler;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut battler = Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species);
+    /// assert_eq!(battler.apply_damage(20), 20);
+    /// assert_eq!(battler.apply_damage(100), 30);
+    /// assert!(battler.is_fainted());
+    /// // Fainting lowers bond
+    /// assert!(battler.get_immie().bond < immie2d_shared::gameplay::immie::bond::BASE_BOND);
+    /// ```
+    pub fn apply_damage(&mut self, amount: u32) -> u32 {
+        let lost = amount.min(self.health);
+        self.health -= lost;
+        if lost > 0 && self.health == 0 {
+            self.immie.apply_bond_event(BondEvent::Fainted);
+            self.set_forced_action(None);
+        }
+        return lost;
+    }
+
+    /// Change the bond of the Immie in response to an event. Returns the new bond.
+    pub fn apply_bond_event(&mut self, event: BondEvent) -> u32 {
+        return self.immie.apply_bond_event(event);
+    }
+
+    /// Spend a use of the ability in a slot of get_abilities(). Will panic if it has no uses remaining.
+    pub fn spend_ability_use(&mut self, ability_slot: usize, max_uses: u32) {
+        assert!(self.get_remaining_uses(ability_slot, max_uses) > 0, "Ability in slot {} has no uses remaining", ability_slot);
+        if let Some(identity) = self.copied_identity.as_mut() {
+            identity.ability_uses_spent[ability_slot] += 1;
+            return;
+        }
+        match self.copied_ability.as_mut() {
+            Some(copied) if copied.slot == ability_slot => copied.uses_spent += 1,
+            _ => self.immie.ability_uses_spent[ability_slot] += 1
+        }
+    }
+
+    pub fn get_copied_identity(&self) -> Option<CopiedIdentity> {
+        return self.copied_identity;
+    }
+
+    /// Pose as a target until switching out, taking its species, elements, stats and abilities but keeping this
+    /// battler's own health. Copying a battler that is itself copying takes what it is posing as. Replaces any copied
+    /// ability. See CopiedIdentity
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::battler::Battler;
+    /// use immie2d_shared::gameplay::battle::copied_identity::COPIED_ABILITY_USES;
+    ///
+    /// let fire = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let water = SpeciesData::new(GlobalString::new(&"tidefin".to_string()), Elements::new(vec![ElementKind::Water]), BaseStats::new(90, 80, 80, 30));
+    /// let splash = GlobalString::new(&"splash".to_string());
+    /// let mut copier = Battler::new(Immie::new(fire.name, 5, AbilityNames::default()), &fire);
+    /// let target = Battler::new(Immie::new(water.name, 5, AbilityNames::new(vec![splash])), &water);
+    ///
+    /// copier.copy_identity(&target);
+    /// assert_eq!(copier.get_species(), water.name);
+    /// assert!(copier.get_elements().has_elements(ElementKind::Water));
+    /// assert_eq!(copier.get_stats(), BaseStats::new(50, 80, 80, 30));
+    /// assert_eq!(copier.get_abilities().get_names(), vec![splash]);
+    /// copier.spend_ability_use(0, 20);
+    /// assert_eq!(copier.get_remaining_uses(0, 20), COPIED_ABILITY_USES - 1);
+    ///
+    /// // Nothing copied is kept
+    /// copier.clear_volatile_state();
+    /// assert_eq!(copier.get_species(), fire.name);
+    /// assert_eq!(copier.get_abilities().get_count(), 0);
+    /// assert_eq!(copier.get_carried_immie().ability_uses_spent[0], 0);
+    /// ```
+    pub fn copy_identity(&mut self, target: &Battler) {
+        self.copied_identity = Some(CopiedIdentity::new(target.get_species(), target.get_elements(), target.get_unboosted_stats(), target.get_abilities()));
+        self.copied_ability = None;
+    }
+
+    pub fn get_copied_ability(&self) -> Option<CopiedAbility> {
+        return self.copied_ability;
+    }
+
+    /// Replace the ability in a slot with a copied one until switching out. Replaces any other copied ability.
+    /// Will panic if the slot doesn't hold an ability, or if the battler already has the ability.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::battler::Battler;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let (fireball, mimic, splash) = (GlobalString::new(&"fireball".to_string()), GlobalString::new(&"mimic".to_string()), GlobalString::new(&"splash".to_string()));
+    /// let mut immie = Immie::new(species.name, 5, AbilityNames::new(vec![fireball, mimic]));
+    /// immie.ability_uses_spent[1] = 1;
+    /// let mut battler = Battler::new(immie, &species);
+    ///
+    /// battler.copy_ability(1, splash);
+    /// assert_eq!(battler.get_abilities().get_names(), vec![fireball, splash]);
+    /// assert_eq!(battler.get_remaining_uses(1, 10), 5);
+    /// battler.clear_volatile_state();
+    /// assert_eq!(battler.get_abilities().get_names(), vec![fireball, mimic]);
+    /// assert_eq!(battler.get_remaining_uses(1, 10), 9);
+    /// ```
+    pub fn copy_ability(&mut self, ability_slot: usize, ability: GlobalString) {
+        let abilities = self.get_abilities();
+        assert!(ability_slot < abilities.get_count() as usize, "Cannot copy an ability into empty slot {}", ability_slot);
+        assert!(!abilities.has_ability(ability), "Battler already has the ability {}", ability);
+        match self.copied_identity.as_mut() {
+            // Posing as another battler, so the copy replaces one of its abilities instead
+            Some(identity) => {
+                let mut names = identity.abilities.get_names();
+                names[ability_slot] = ability;
+                identity.abilities = AbilityNames::new(names);
+                identity.ability_uses_spent[ability_slot] = 0;
+            },
+            None => self.copied_ability = Some(CopiedAbility { slot: ability_slot, ability, uses_spent: 0 })
+        }
+    }
+
+    /// Forget any copied identity or ability, such as when the battle ends.
+    pub fn clear_copies(&mut self) {
+        self.copied_identity = None;
+        self.copied_ability = None;
+    }
+
+    /// Remember that the battler used an ability, forgetting the oldest use if the history is full.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::battler::{Battler, ABILITY_HISTORY_LENGTH};
+    ///
+    /// # let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut battler = Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species);
+    /// let fireball = GlobalString::new(&"fireball".to_string());
+    /// for turn in 1..=ABILITY_HISTORY_LENGTH as u32 + 1 {
+    ///     battler.record_ability_use(turn, fireball);
+    /// }
+    /// assert_eq!(battler.get_ability_history().len(), ABILITY_HISTORY_LENGTH);
+    /// assert_eq!(battler.get_ability_history()[0].turn, ABILITY_HISTORY_LENGTH as u32 + 1);
+    /// assert_eq!(battler.get_ability_used_on(2), Some(fireball));
+    /// assert_eq!(battler.get_ability_used_on(1), None);
+    /// ```
+    pub fn record_ability_use(&mut self, turn: u32, ability: GlobalString) {
+        self.ability_history.rotate_right(1);
+        self.ability_history[0] = Some(UsedAbility { turn, ability });
+    }
+
+    /// Remembered ability uses, newest first.
+    pub fn get_ability_history(&self) -> Vec<UsedAbility> {
+        return self.ability_history.iter().flatten().copied().collect();
+    }
+
+    /// The ability the battler used on a turn, if it is still remembered.
+    pub fn get_ability_used_on(&self, turn: u32) -> Option<GlobalString> {
+        return self.ability_history.iter().flatten().find(|used| used.turn == turn).map(|used| used.ability);
+    }
+
+    pub fn get_forced_action(&self) -> Option<ForcedAction> {
+        return self.forced_action;
+    }
+
+    /// Set the forced action. Ending the forced action also ends any semi-invulnerability from charging it.
+    pub fn set_forced_action(&mut self, forced_action: Option<ForcedAction>) {
+        self.forced_action = forced_action;
+        if forced_action.is_none() {
+            self.semi_invulnerability = None;
+        }
+    }
+
+    pub fn get_semi_invulnerability(&self) -> Option<SemiInvulnerability> {
+        return self.semi_invulnerability;
+    }
+
+    /// Become semi-invulnerable until the forced action being charged ends. See set_forced_action()
+    pub fn set_semi_invulnerability(&mut self, semi_invulnerability: Option<SemiInvulnerability>) {
+        self.semi_invulnerability = semi_invulnerability;
+    }
+
+    pub fn get_evasion_stage(&self) -> i32 {
+        return self.evasion_stage;
+    }
+
+    /// Raise or lower evasion by a number of stages, within MAX_EVASION_STAGE. Returns the new stage.
+    pub fn change_evasion_stage(&mut self, stages: i32) -> i32 {
+        self.evasion_stage = (self.evasion_stage + stages).clamp(-MAX_EVASION_STAGE, MAX_EVASION_STAGE);
+        return self.evasion_stage;
+    }
+
+    pub fn get_lock_on(&self) -> Option<LockOn> {
+        return self.lock_on;
+    }
+
+    pub fn set_lock_on(&mut self, lock_on: Option<LockOn>) {
+        self.lock_on = lock_on;
+    }
+
+    /// Use up the lock on if it is on the target and still lasts on the turn. Returns whether it was used. A lock on
+    /// hits through any evasion, but only once.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::ability::{ability::{AbilityCategory, BaseAbilityData}, ability_flags::AbilityFlags};
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat, battle_event::BattleEvent};
+    /// use immie2d_shared::gameplay::battle::hit_resolution::MAX_EVASION_STAGE;
+    ///
+    /// let ability = |category: AbilityCategory, accuracy: u32, flags: AbilityFlags| BaseAbilityData {
+    ///     category, types: Elements::new(vec![ElementKind::Fire]), power: 40.0, speed: 1.0, max_uses: 10, accuracy, flags, combo: None
+    /// };
+    /// let (lock_on, inaccurate) = (ability(AbilityCategory::Status, 100, AbilityFlags::LOCKS_ON), ability(AbilityCategory::Attack, 1, AbilityFlags::NONE));
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(500, 60, 40, 70));
+    /// let mut evasive = Battler::new(Immie::new(species.name, 20, AbilityNames::default()), &species);
+    /// evasive.change_evasion_stage(MAX_EVASION_STAGE);
+    /// let attacker = Battler::new(Immie::new(species.name, 20, AbilityNames::default()), &species);
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![BattleSide::new(vec![attacker]), BattleSide::new(vec![evasive])]);
+    /// let (user, target) = (BattlerId::new(0, 0), BattlerId::new(1, 0));
+    ///
+    /// battle.use_ability(user, target, &lock_on);
+    /// assert!(battle.take_events().contains(&BattleEvent::LockedOn { attacker: user, target }));
+    /// battle.end_turn();
+    /// assert!(battle.use_ability(user, target, &inaccurate) > 0);
+    /// assert_eq!(battle.get_battler(user).get_lock_on(), None);
+    /// ```
+    pub fn take_lock_on(&mut self, target: BattlerId, turn: u32) -> bool {
+        let is_locked_on = self.lock_on.is_some_and(|lock_on| lock_on.target == target && turn <= lock_on.last_turn);
+        if is_locked_on {
+            self.lock_on = None;
+        }
+        return is_locked_on;
+    }
+
+    /// Forget the volatile state that only lasts while the battler is out, when it switches out.
+    pub fn clear_volatile_state(&mut self) {
+        self.evasion_stage = 0;
+        self.lock_on = None;
+        self.clear_copies();
+    }
+
+    pub fn is_protected(&self) -> bool {
+        return self.is_protected;
+    }
+
+    pub fn set_protected(&mut self, is_protected: bool) {
+        self.is_protected = is_protected;
+    }
+
+    pub fn has_substitute(&self) -> bool {
+        return self.substitute_health > 0;
+    }
+
+    pub fn get_substitute_health(&self) -> u32 {
+        return self.substitute_health;
+    }
+
+    /// Put up a substitute with some health, replacing any existing substitute.
+    pub fn set_substitute(&mut self, health: u32) {
+        self.substitute_health = health;
+    }
+
+    /// Damage the substitute instead of the battler. Returns the damage the substitute actually took.
+    pub fn damage_substitute(&mut self, amount: u32) -> u32 {
+        let lost = amount.min(self.substitute_health);
+        self.substitute_health -= lost;
+        return lost;
+    }
+
+    pub fn is_transformed(&self) -> bool {
+        return self.is_transformed;
+    }
+
+    /// Check if this battler is able to transform. A battler may only transform once per battle,
+    /// and only when its species has a transformation and it is holding the required item. A battler posing as
+    /// another with a copied identity can't transform.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::{SpeciesData, TransformationData}, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::battler::Battler;
+    ///
+    /// let stone = GlobalString::new(&"lava stone".to_string());
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70))
+    ///     .with_transformation(TransformationData {
+    ///         form_name: GlobalString::new(&"mega lavapup".to_string()),
+    ///         required_item: stone,
+    ///         elements: Elements::new(vec![ElementKind::Fire, ElementKind::Dragon]),
+    ///         base_stats: BaseStats::new(50, 90, 60, 80)
+    ///     });
+    /// let mut immie = Immie::new(species.name, 5, AbilityNames::default());
+    /// assert!(!Battler::new(immie, &species).can_transform(&species));
+    /// immie.held_item = Some(stone);
+    /// assert!(Battler::new(immie, &species).can_transform(&species));
+    /// ```
+    pub fn can_transform(&self, species: &SpeciesData) -> bool {
+        if self.has_transformed || self.copied_identity.is_some() {
+            return false;
+        }
+        return match species.transformation {
+            Some(transformation) => self.immie.held_item == Some(transformation.required_item),
+            None => false
+        };
+    }
+
+    /// Transform into the alternate form of the species. Current health is kept.
+    /// Will panic if the battler cannot transform. See Battler::can_transform()
+    pub fn transform(&mut self, species: &SpeciesData) {
+        assert!(self.can_transform(species), "Battler of species {} cannot transform", species.name);
+        let transformation = species.transformation.unwrap();
+        self.elements = transformation.elements;
+        self.stats = transformation.base_stats;
+        self.is_transformed = true;
+        self.has_transformed = true;
+    }
+
+    pub fn get_attuned_element(&self) -> Option<ElementKind> {
+        return self.attuned_element;
+    }
+
+    /// Check if this battler is able to attune, which needs its Immie to have an attunement and the battler to not have
+    /// attuned already. A side may only attune one battler per battle. See Battle::attune()
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::battle::battler::Battler;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire, ElementKind::Dark]), BaseStats::new(50, 60, 40, 70));
+    /// let mut immie = Immie::new(species.name, 5, AbilityNames::default());
+    /// assert!(!Battler::new(immie, &species).can_attune());
+    /// immie.attunement = Some(ElementKind::Water);
+    /// let mut battler = Battler::new(immie, &species);
+    /// battler.attune();
+    /// assert!(battler.get_elements().has_elements(ElementKind::Water));
+    /// assert!(!battler.get_elements().has_elements(ElementKind::Fire));
+    /// assert!(!battler.can_attune());
+    /// ```
+    pub fn can_attune(&self) -> bool {
+        return self.immie.attunement.is_some() && self.attuned_element.is_none();
+    }
+
+    /// Change the elements of the battler to its attunement for the rest of the battle, even through switching out,
+    /// transforming or copying a target.
+    /// Will panic if the battler cannot attune. See Battler::can_attune()
+    pub fn attune(&mut self) {
+        assert!(self.can_attune(), "Battler of species {} cannot attune", self.immie.species);
+        self.attuned_element = self.immie.attunement;
+    }
+
+    /// Return to the original form of the species. Does nothing if the battler is not transformed.
+    /// Reverting does not allow the battler to transform again.
+    pub fn revert(&mut self) {
+        if !self.is_transformed {
+            return;
+        }
+        self.elements = self.species_elements;
+        self.stats = self.species_stats;
+        self.is_transformed = false;
+    }
+
+    /// Append the battler's state to the encoding hashed by Battle::get_state_hash().
+    pub(crate) fn put_state(&self, buffer: &mut BytesMut) {
+        put_immie(buffer, &self.immie);
+        put_elements(buffer, &self.species_elements);
+        put_stats(buffer, &self.species_stats);
+        put_elements(buffer, &self.elements);
+        put_stats(buffer, &self.stats);
+        buffer.put_u32_le(self.health);
+        put_flag(buffer, self.is_transformed);
+        put_flag(buffer, self.has_transformed);
+        put_flag(buffer, self.is_protected);
+        buffer.put_u32_le(self.substitute_health);
+        for used in self.ability_history {
+            put_option(buffer, used, |buffer, used| {
+                buffer.put_u32_le(used.turn);
+                put_name(buffer, used.ability);
+            });
+        }
+        put_option(buffer, self.forced_action, |buffer, action| {
+            buffer.put_u8(action.kind.get_id());
+            put_usize(buffer, action.ability_slot);
+            put_usize(buffer, action.target_side);
+            buffer.put_u32_le(action.turn);
+            buffer.put_u32_le(action.total_turns);
+        });
+        for boons in self.boons {
+            buffer.put_u32_le(boons);
+        }
+        buffer.put_i32_le(self.evasion_stage);
+        put_option(buffer, self.semi_invulnerability, |buffer, state| buffer.put_u8(state.get_id()));
+        put_option(buffer, self.lock_on, |buffer, lock_on| {
+            put_battler_id(buffer, lock_on.target);
+            buffer.put_u32_le(lock_on.last_turn);
+        });
+        put_option(buffer, self.copied_identity, |buffer, identity| {
+            put_name(buffer, identity.species);
+            put_elements(buffer, &identity.elements);
+            put_stats(buffer, &identity.stats);
+            buffer.put_u32_le(identity.abilities.get_count());
+            for ability in identity.abilities.iter() {
+                put_name(buffer, ability);
+            }
+            for uses in identity.ability_uses_spent {
+                buffer.put_u32_le(uses);
+            }
+        });
+        put_option(buffer, self.copied_ability, |buffer, copied| {
+            put_usize(buffer, copied.slot);
+            put_name(buffer, copied.ability);
+            buffer.put_u32_le(copied.uses_spent);
+        });
+        put_option(buffer, self.attuned_element, put_element);
+    }
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/* Identifies a battler within a battle by the side it belongs to and its slot in that side's team. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct BattlerId {
+    pub side: usize,
+    pub slot: usize
+}
+
+impl BattlerId {
+    pub fn new(side: usize, slot: usize) -> BattlerId {
+        return BattlerId { side, slot };
+    }
+}
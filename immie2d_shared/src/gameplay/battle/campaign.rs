@@ -0,0 +1,76 @@
+use crate::gameplay::immie::immie::Immie;
+use crate::gameplay::species::species_map::SpeciesMap;
+
+use super::battle_side::BattleSide;
+use super::battler::Battler;
+
+/// Percent a boon raises its stat by, for each boon of that kind.
+pub const BOON_STAT_PERCENT: u32 = 20;
+
+/// Percent of max health restored to each Immie still standing between the battles of a campaign run.
+pub const REST_HEAL_PERCENT: u32 = 25;
+
+/// Number of BoonKind variants.
+pub const BOON_KIND_COUNT: usize = 3;
+
+/* A stat a campaign boon raises. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum BoonKind {
+    Attack,
+    Defense,
+    Speed
+}
+
+/* A temporary bonus to every battler of a side, lasting a number of campaign battles. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Boon {
+    pub kind: BoonKind,
+    /// Battles left, including the one the boon is currently used in.
+    pub battles: u32
+}
+
+/* The state a side carries from one battle of a roguelike campaign run into the next. Health lost, lingering
+statuses and spent ability uses are kept on the Immies, and boons wear off after their number of battles. Battles
+only take and give carry over explicitly, so outside of campaigns nothing done in battle lasts. See
+Battle::get_carry_over() */
+#[derive(Clone, PartialEq, Debug)]
+pub struct CarryOver {
+    pub team: Vec<Immie>,
+    pub boons: Vec<Boon>
+}
+
+impl CarryOver {
+    /// Start a run with a team and no boons.
+    pub fn new(team: Vec<Immie>) -> CarryOver {
+        return CarryOver { team, boons: Vec::new() };
+    }
+
+    pub fn with_boon(mut self, boon: Boon) -> CarryOver {
+        self.boons.push(boon);
+        return self;
+    }
+
+    /// Create the side to enter the next battle with. Fainted Immies stay in the team but can't be switched in.
+    /// Will panic if the team is empty or an Immie's species isn't in the species map.
+    pub fn create_side(&self, species_map: &SpeciesMap) -> BattleSide {
+        let team = self.team.iter().map(|immie| Battler::new(*immie, &species_map.get_species_of(immie))).collect();
+        return BattleSide::new(team).with_boons(self.boons.clone());
+    }
+
+    /// Rest between battles, restoring REST_HEAL_PERCENT of max health to each Immie that hasn't fainted. Fainted
+    /// Immies stay fainted for the rest of the run.
+    pub fn rest(&mut self, species_map: &SpeciesMap) {
+        for immie in self.team.iter_mut() {
+            let species = species_map.get_species_of(immie);
+            if !immie.is_fainted(&species) {
+                immie.heal(immie.get_max_health(&species) * REST_HEAL_PERCENT / 100);
+            }
+        }
+    }
+
+    /// The run is over once every Immie has fainted.
+    pub fn is_defeated(&self, species_map: &SpeciesMap) -> bool {
+        return self.team.iter().all(|immie| immie.is_fainted(&species_map.get_species_of(immie)));
+    }
+}
@@ -0,0 +1,46 @@
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::ability::ability_names::{AbilityNames, MAX_ABILITIES_COUNT};
+use crate::gameplay::elements::elements_data::Elements;
+use crate::gameplay::species::base_stats::BaseStats;
+
+/// Most uses a copied ability has, however many the ability normally has.
+pub const COPIED_ABILITY_USES: u32 = 5;
+
+/* Who a battler is posing as after copying a target with an AbilityFlags::COPIES_TARGET ability. The battler keeps its
+own health and max health, but takes the target's species, elements, other stats and abilities until it switches out
+or the battle ends. The battler's own Immie is never changed, so nothing copied is carried out of the battle. */
+#[derive(Clone, Copy, Debug)]
+pub struct CopiedIdentity {
+    pub species: GlobalString,
+    pub elements: Elements,
+    pub stats: BaseStats,
+    pub abilities: AbilityNames,
+    /// Uses spent of each copied ability, out of at most COPIED_ABILITY_USES.
+    pub ability_uses_spent: [u32; MAX_ABILITIES_COUNT as usize]
+}
+
+impl CopiedIdentity {
+    pub fn new(species: GlobalString, elements: Elements, stats: BaseStats, abilities: AbilityNames) -> CopiedIdentity {
+        return CopiedIdentity { species, elements, stats, abilities, ability_uses_spent: [0; MAX_ABILITIES_COUNT as usize] };
+    }
+}
+
+/* An ability copied from a target with an AbilityFlags::COPIES_LAST_ABILITY ability, taking the place of the copying
+ability in its slot until the battler switches out or the battle ends. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct CopiedAbility {
+    pub slot: usize,
+    pub ability: GlobalString,
+    /// Out of at most COPIED_ABILITY_USES.
+    pub uses_spent: u32
+}
+
+/// Uses left of a copied ability.
+/// ```
+/// use immie2d_shared::gameplay::battle::copied_identity::{get_copied_remaining_uses, COPIED_ABILITY_USES};
+/// assert_eq!(get_copied_remaining_uses(1, 40), COPIED_ABILITY_USES - 1);
+/// assert_eq!(get_copied_remaining_uses(1, 2), 1);
+/// ```
+pub fn get_copied_remaining_uses(uses_spent: u32, max_uses: u32) -> u32 {
+    return max_uses.min(COPIED_ABILITY_USES).saturating_sub(uses_spent);
+}
@@ -0,0 +1,207 @@
+use crate::gameplay::ability::{ability::{AbilityCombo, BaseAbilityData}, ability_flags::AbilityFlags};
+use crate::gameplay::elements::elements_data::Elements;
+use crate::gameplay::immie::bond::get_bond_power_multiplier;
+use crate::gameplay::immie::immie::Immie;
+
+use super::battle::Battle;
+use super::battler_id::BattlerId;
+
+pub use immie2d_core::damage::{get_combined_effectiveness, get_damage_breakdown, DamageBreakdown, SAME_ELEMENT_BONUS};
+
+/// Preview the type chart multiplier of an ability against a defender's elements, such as for a tooltip when
+/// hovering an ability or for AI move choice. Doesn't account for the rules of a battle. See Battle::preview_effectiveness()
+/// ```
+/// use immie2d_shared::gameplay::ability::{ability::Ability, abilities::fireball::Fireball};
+/// use immie2d_shared::gameplay::battle::damage::preview_effectiveness;
+/// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+/// use immie2d_shared::gameplay::elements::type_chart::SUPER_EFFECTIVE;
+///
+/// let fireball = Fireball::new();
+/// assert_eq!(preview_effectiveness(fireball.get_base_ability_data(), &Elements::new(vec![ElementKind::Nature])), SUPER_EFFECTIVE);
+/// ```
+pub fn preview_effectiveness(ability: &BaseAbilityData, defender_elements: &Elements) -> f32 {
+    return get_combined_effectiveness(&ability.types, defender_elements);
+}
+
+/// The elements and power an ability has when used by an Immie. Most abilities always use their data, but abilities
+/// flagged AbilityFlags::HIDDEN_POWER resolve them from the user's individual values on every use.
+/// ```
+/// # use immie2d_shared::engine_types::global_string::GlobalString;
+/// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+/// use immie2d_shared::gameplay::ability::{ability::Ability, abilities::{fireball::Fireball, hidden_power::HiddenPower}};
+/// use immie2d_shared::gameplay::battle::damage::resolve_ability_elements;
+/// use immie2d_shared::gameplay::elements::element_kinds::ElementKind;
+/// use immie2d_shared::gameplay::immie::{immie::Immie, individual_values::IndividualValues};
+///
+/// let mut immie = Immie::new(GlobalString::new(&"lavapup".to_string()), 5, AbilityNames::default());
+/// immie.individual_values = IndividualValues::new(31, 31, 31, 31);
+/// let (elements, power) = resolve_ability_elements(HiddenPower::new().get_base_ability_data(), &immie);
+/// assert!(elements.has_elements(ElementKind::Dragon));
+/// assert_eq!(power, 70.0);
+///
+/// let (elements, power) = resolve_ability_elements(Fireball::new().get_base_ability_data(), &immie);
+/// assert!(elements.has_elements(ElementKind::Fire));
+/// assert_eq!(power, 40.0);
+/// ```
+pub fn resolve_ability_elements(ability: &BaseAbilityData, user: &Immie) -> (Elements, f32) {
+    if !ability.flags.contains(AbilityFlags::HIDDEN_POWER) {
+        return (ability.types, ability.power);
+    }
+    let individual_values = user.individual_values;
+    return (Elements::new(vec![individual_values.get_hidden_power_element()]), individual_values.get_hidden_power());
+}
+
+/// The combo of an ability if the attacker used the ability it follows on the previous turn.
+/// Only abilities used through Battle::apply_command() are remembered by battlers.
+/// ```
+/// # use immie2d_shared::engine_types::global_string::GlobalString;
+/// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+/// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+/// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+/// # use immie2d_shared::gameplay::immie::immie::Immie;
+/// use immie2d_shared::gameplay::ability::{ability::{Ability, AbilityCombo}, ability_map::AbilityMap, abilities::fireball::Fireball};
+/// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+/// use immie2d_shared::gameplay::battle::battle_command::BattleCommand;
+/// use immie2d_shared::gameplay::battle::damage::{get_active_combo, DamageContext};
+///
+/// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(500, 60, 40, 70));
+/// let mut ability_map = AbilityMap::new();
+/// ability_map.add_ability::<Fireball>();
+/// let abilities = AbilityNames::new(vec![GlobalString::new(&Fireball::static_name().to_string())]);
+/// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, abilities), &species)]);
+/// let mut battle = Battle::new(BattleFormat::Single, vec![side.clone(), side]);
+/// let (attacker, defender) = (BattlerId::new(0, 0), BattlerId::new(1, 0));
+///
+/// let mut follow_up = Fireball::new();
+/// follow_up.get_base_ability_data_mut().combo = Some(AbilityCombo { follows: Fireball::static_name(), power_multiplier: 2.0 });
+/// let follow_up = follow_up.get_base_ability_data();
+/// let uncombined = DamageContext::new(&battle, attacker, defender, follow_up).power;
+///
+/// battle.apply_command(BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 }, &ability_map, &SpeciesMap::new()).unwrap();
+/// // The combo only applies on the next turn
+/// assert!(get_active_combo(&battle, attacker, follow_up).is_none());
+/// battle.end_turn();
+/// assert!(get_active_combo(&battle, attacker, follow_up).is_some());
+/// assert_eq!(DamageContext::new(&battle, attacker, defender, follow_up).power, uncombined * 2.0);
+/// battle.end_turn();
+/// assert!(get_active_combo(&battle, attacker, follow_up).is_none());
+/// ```
+pub fn get_active_combo(battle: &Battle, attacker: BattlerId, ability: &BaseAbilityData) -> Option<AbilityCombo> {
+    let combo = ability.combo?;
+    let previous = battle.get_battler(attacker).get_ability_used_on(battle.get_turn().checked_sub(1)?)?;
+    if !previous.with_str(|name| name == combo.follows) {
+        return None;
+    }
+    return Some(combo);
+}
+
+/// Multiply damage inputs by the weather's multiplier of every element of the ability. Resolves as the weather effect
+/// in the battle's effect order. See WEATHER_EFFECT
+pub fn apply_weather(battle: &Battle, context: &mut DamageContext) {
+    let field = battle.get_field();
+    context.multiplier *= context.ability_elements.iter().map(|element| field.get_element_multiplier(element)).product::<f32>();
+}
+
+/* Every input of a single damage calculation. Rules plugins may modify these before the damage is calculated. */
+#[derive(Clone, Copy, Debug)]
+pub struct DamageContext {
+    pub attacker: BattlerId,
+    pub defender: BattlerId,
+    pub attacker_level: u32,
+    pub ability_elements: Elements,
+    pub defender_elements: Elements,
+    pub power: f32,
+    pub attack: u32,
+    pub defense: u32,
+    /// Type chart multiplier of the ability elements against the defender elements.
+    pub effectiveness: f32,
+    /// Every other multiplier, such as the same element bonus, and the weather once it has applied. See apply_weather()
+    pub multiplier: f32
+}
+
+impl DamageContext {
+    /// Gather the damage inputs of an attacker using an ability on a defender, before any effect has modified them.
+    pub fn new(battle: &Battle, attacker: BattlerId, defender: BattlerId, ability: &BaseAbilityData) -> DamageContext {
+        let attacker_data = battle.get_battler(attacker);
+        let defender_data = battle.get_battler(defender);
+        let attacker_elements = attacker_data.get_elements();
+        let defender_elements = defender_data.get_elements();
+        let (ability_elements, ability_power) = resolve_ability_elements(ability, attacker_data.get_immie());
+        let shares_element = ability_elements.iter().any(|element| attacker_elements.has_elements(element));
+        let mut power = if ability.flags.contains(AbilityFlags::BOND_SCALED) {
+            ability_power * get_bond_power_multiplier(attacker_data.get_immie().bond)
+        }
+        else {
+            ability_power
+        };
+        if let Some(combo) = get_active_combo(battle, attacker, ability) {
+            power *= combo.power_multiplier;
+        }
+        let same_element_bonus = if shares_element { SAME_ELEMENT_BONUS } else { 1.0 };
+        return DamageContext {
+            attacker,
+            defender,
+            attacker_level: attacker_data.get_immie().level,
+            ability_elements,
+            defender_elements,
+            power,
+            attack: attacker_data.get_stats().attack,
+            defense: defender_data.get_stats().defense,
+            effectiveness: get_combined_effectiveness(&ability_elements, &defender_elements),
+            multiplier: same_element_bonus
+        };
+    }
+
+    /// Calculate the damage. Abilities that have any effect always deal at least 1 damage.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::{damage::DamageContext, battler_id::BattlerId};
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    ///
+    /// let mut context = DamageContext {
+    ///     attacker: BattlerId::new(0, 0),
+    ///     defender: BattlerId::new(1, 0),
+    ///     attacker_level: 50,
+    ///     ability_elements: Elements::new(vec![ElementKind::Fire]),
+    ///     defender_elements: Elements::new(vec![ElementKind::Nature]),
+    ///     power: 40.0,
+    ///     attack: 60,
+    ///     defense: 60,
+    ///     effectiveness: 1.0,
+    ///     multiplier: 1.0
+    /// };
+    /// let neutral = context.calculate();
+    /// context.effectiveness = 2.0;
+    /// assert!(context.calculate() > neutral);
+    /// context.effectiveness = 0.0;
+    /// assert_eq!(context.calculate(), 0);
+    /// ```
+    pub fn calculate(&self) -> u32 {
+        return self.get_breakdown().damage;
+    }
+
+    /// Calculate the damage, keeping every intermediate value. See DamageContext::calculate() and get_damage_breakdown()
+    /// ```
+    /// use immie2d_shared::gameplay::battle::{damage::DamageContext, battler_id::BattlerId};
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    ///
+    /// let context = DamageContext {
+    ///     attacker: BattlerId::new(0, 0),
+    ///     defender: BattlerId::new(1, 0),
+    ///     attacker_level: 50,
+    ///     ability_elements: Elements::new(vec![ElementKind::Fire]),
+    ///     defender_elements: Elements::new(vec![ElementKind::Nature]),
+    ///     power: 40.0,
+    ///     attack: 60,
+    ///     defense: 60,
+    ///     effectiveness: 2.0,
+    ///     multiplier: 1.5
+    /// };
+    /// let breakdown = context.get_breakdown();
+    /// assert_eq!(breakdown.level_factor, 22.0);
+    /// assert_eq!(breakdown.damage, (breakdown.base * 2.0 * 1.5) as u32);
+    /// assert_eq!(breakdown.damage, context.calculate());
+    /// ```
+    pub fn get_breakdown(&self) -> DamageBreakdown {
+        return get_damage_breakdown(self.attacker_level, self.power, self.attack, self.defense, self.effectiveness, self.multiplier);
+    }
+}
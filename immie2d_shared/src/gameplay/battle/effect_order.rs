@@ -0,0 +1,177 @@
+use std::fmt;
+use std::sync::Arc;
+
+use lazy_static::lazy_static;
+
+/// Effect of the battle's rules plugin. See BattleRulesPlugin
+pub const RULES_EFFECT: &str = "rules";
+/// Effect of the script of the ability being used. See AbilityScript
+pub const ABILITY_SCRIPT_EFFECT: &str = "ability_script";
+/// Effect of the weather on the damage of abilities. See FieldState::get_element_multiplier()
+pub const WEATHER_EFFECT: &str = "weather";
+/// Effects of the states that can stop an ability from hitting a defender directly. The first that applies decides
+/// what the ability hits. See HitBlocker
+pub const AIRBORNE_EFFECT: &str = "airborne";
+pub const UNDERGROUND_EFFECT: &str = "underground";
+pub const PROTECT_EFFECT: &str = "protect";
+pub const DEFLECTION_EFFECT: &str = "deflection";
+pub const SUBSTITUTE_EFFECT: &str = "substitute";
+
+lazy_static! {
+    static ref STANDARD_EFFECT_ORDER: Arc<EffectOrderRegistry> = Arc::new(EffectOrderRegistry::standard());
+}
+
+/* What kind of thing an effect comes from. Effects of earlier sources resolve first, so the field sets the stage that
+an Immie's status, trait and held item react to, and the battle's rules and the ability's own script have the last
+word. */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum EffectSource {
+    Field,
+    Status,
+    Trait,
+    HeldItem,
+    Rules,
+    AbilityScript
+}
+
+impl EffectSource {
+    pub fn get_id(self) -> u8 {
+        return match self {
+            EffectSource::Field => 0,
+            EffectSource::Status => 1,
+            EffectSource::Trait => 2,
+            EffectSource::HeldItem => 3,
+            EffectSource::Rules => 4,
+            EffectSource::AbilityScript => 5
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<EffectSource> {
+        return match id {
+            0 => Some(EffectSource::Field),
+            1 => Some(EffectSource::Status),
+            2 => Some(EffectSource::Trait),
+            3 => Some(EffectSource::HeldItem),
+            4 => Some(EffectSource::Rules),
+            5 => Some(EffectSource::AbilityScript),
+            _ => None
+        };
+    }
+}
+
+/* Where an effect resolves among the effects hooked into the same point of a battle. Keys sort by source, then by
+rank within the source. */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct EffectOrderKey {
+    pub source: EffectSource,
+    pub rank: u16
+}
+
+impl EffectOrderKey {
+    pub fn new(source: EffectSource, rank: u16) -> EffectOrderKey {
+        return EffectOrderKey { source, rank };
+    }
+}
+
+/* Why an effect couldn't be registered. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum EffectOrderError {
+    DuplicateName(String),
+    /// Another effect already has the key. Keys are unique so no two effects ever resolve in an order that depends on
+    /// when they were registered.
+    KeyTaken { key: EffectOrderKey, existing: String }
+}
+
+impl fmt::Display for EffectOrderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            EffectOrderError::DuplicateName(name) => write!(f, "Effect [{}] is already registered", name),
+            EffectOrderError::KeyTaken { key, existing } => write!(f, "Effect order {:?} rank {} is already taken by [{}]", key.source, key.rank, existing)
+        };
+    }
+}
+
+/* The order every effect hook of a battle resolves in. Every effect is registered with a unique key, and the engine
+sorts the hooks of each point of the battle by their keys before running them, so adding content never reshuffles
+the effects already there. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EffectOrderRegistry {
+    /// Sorted by key.
+    effects: Vec<(String, EffectOrderKey)>
+}
+
+impl EffectOrderRegistry {
+    /// A registry without any effects.
+    pub fn new() -> EffectOrderRegistry {
+        return EffectOrderRegistry { effects: Vec::new() };
+    }
+
+    /// The effects the engine itself hooks in. The deflection passive is ranked with the other blockers as the state
+    /// it puts its holder in, so a projectile is deflected before it can hit a substitute.
+    pub fn standard() -> EffectOrderRegistry {
+        let mut registry = EffectOrderRegistry::new();
+        registry.register(WEATHER_EFFECT, EffectOrderKey::new(EffectSource::Field, 0)).unwrap();
+        let blockers = [AIRBORNE_EFFECT, UNDERGROUND_EFFECT, PROTECT_EFFECT, DEFLECTION_EFFECT, SUBSTITUTE_EFFECT];
+        for (rank, blocker) in blockers.into_iter().enumerate() {
+            registry.register(blocker, EffectOrderKey::new(EffectSource::Status, rank as u16)).unwrap();
+        }
+        registry.register(RULES_EFFECT, EffectOrderKey::new(EffectSource::Rules, 0)).unwrap();
+        registry.register(ABILITY_SCRIPT_EFFECT, EffectOrderKey::new(EffectSource::AbilityScript, 0)).unwrap();
+        return registry;
+    }
+
+    /// The standard registry shared by every battle that doesn't use its own. See Battle::with_effect_order()
+    pub fn get_standard() -> Arc<EffectOrderRegistry> {
+        return STANDARD_EFFECT_ORDER.clone();
+    }
+
+    /// Register an effect. Names and keys must both be unique.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::effect_order::{EffectOrderError, EffectOrderKey, EffectOrderRegistry, EffectSource};
+    ///
+    /// let mut registry = EffectOrderRegistry::standard();
+    /// registry.register("leftovers", EffectOrderKey::new(EffectSource::HeldItem, 10)).unwrap();
+    /// assert_eq!(registry.register("leftovers", EffectOrderKey::new(EffectSource::HeldItem, 20)), Err(EffectOrderError::DuplicateName("leftovers".to_string())));
+    /// let err = registry.register("shell_bell", EffectOrderKey::new(EffectSource::HeldItem, 10)).unwrap_err();
+    /// assert_eq!(err, EffectOrderError::KeyTaken { key: EffectOrderKey::new(EffectSource::HeldItem, 10), existing: "leftovers".to_string() });
+    /// ```
+    pub fn register(&mut self, name: &str, key: EffectOrderKey) -> Result<(), EffectOrderError> {
+        if self.get_key(name).is_some() {
+            return Err(EffectOrderError::DuplicateName(name.to_string()));
+        }
+        let index = match self.effects.binary_search_by_key(&key, |(_, existing)| *existing) {
+            Ok(index) => return Err(EffectOrderError::KeyTaken { key, existing: self.effects[index].0.clone() }),
+            Err(index) => index
+        };
+        self.effects.insert(index, (name.to_string(), key));
+        return Ok(());
+    }
+
+    pub fn get_key(&self, name: &str) -> Option<EffectOrderKey> {
+        return self.effects.iter().find(|(existing, _)| existing == name).map(|(_, key)| *key);
+    }
+
+    /// Names of every registered effect, in the order they resolve.
+    pub fn get_order(&self) -> Vec<&str> {
+        return self.effects.iter().map(|(name, _)| name.as_str()).collect();
+    }
+
+    /// Sort hooks into the order they resolve in, by the name of the effect each belongs to.
+    /// Will panic if an effect isn't registered.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::effect_order::{EffectOrderKey, EffectOrderRegistry, EffectSource, ABILITY_SCRIPT_EFFECT, RULES_EFFECT};
+    ///
+    /// let mut registry = EffectOrderRegistry::standard();
+    /// registry.register("burn", EffectOrderKey::new(EffectSource::Status, 10)).unwrap();
+    /// registry.register("gravity", EffectOrderKey::new(EffectSource::Field, 5)).unwrap();
+    /// let mut hooks = [ABILITY_SCRIPT_EFFECT, "burn", RULES_EFFECT, "gravity"];
+    /// registry.sort(&mut hooks, |hook| *hook);
+    /// assert_eq!(hooks, ["gravity", "burn", RULES_EFFECT, ABILITY_SCRIPT_EFFECT]);
+    /// ```
+    pub fn sort<T, F: Fn(&T) -> &str>(&self, hooks: &mut [T], get_name: F) {
+        hooks.sort_by_key(|hook| {
+            let name = get_name(hook);
+            return self.get_key(name).unwrap_or_else(|| panic!("Effect [{}] has no registered order", name));
+        });
+    }
+}
@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+
+use crate::gameplay::ability::ability_flags::AbilityFlags;
+use crate::gameplay::elements::{element_kinds::ElementKind, elements_data::Elements};
+
+/// Number of kinds of hazard.
+pub const HAZARD_KIND_COUNT: usize = 2;
+/// Most layers of spikes a side can have.
+pub const MAX_SPIKES_LAYERS: u32 = 3;
+/// Each layer of spikes deals this fraction of a battler's max health when it switches in.
+pub const SPIKES_HEALTH_DIVISOR: u32 = 8;
+/// Evasion stages a battler caught in webs loses.
+pub const WEBS_EVASION_STAGES: i32 = 1;
+
+/* Something laid on a side of the field that affects each battler switching in on that side, until it is cleared.
+Battlers of an immune element aren't affected. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum HazardKind {
+    /// Damages a battler switching in, more for each layer. Air battlers fly over them.
+    Spikes,
+    /// Lower the evasion of a battler switching in. Fire battlers burn through them.
+    Webs
+}
+
+impl HazardKind {
+    /// Every kind, in the order they affect a battler switching in.
+    pub const ALL: [HazardKind; HAZARD_KIND_COUNT] = [HazardKind::Spikes, HazardKind::Webs];
+
+    pub fn get_id(self) -> u8 {
+        return match self {
+            HazardKind::Spikes => 0,
+            HazardKind::Webs => 1
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<HazardKind> {
+        return match id {
+            0 => Some(HazardKind::Spikes),
+            1 => Some(HazardKind::Webs),
+            _ => None
+        };
+    }
+
+    /// The hazard an ability lays, if any.
+    pub fn from_flags(flags: AbilityFlags) -> Option<HazardKind> {
+        if flags.contains(AbilityFlags::SETS_SPIKES) {
+            return Some(HazardKind::Spikes);
+        }
+        if flags.contains(AbilityFlags::SETS_WEBS) {
+            return Some(HazardKind::Webs);
+        }
+        return None;
+    }
+
+    pub fn get_max_layers(self) -> u32 {
+        return match self {
+            HazardKind::Spikes => MAX_SPIKES_LAYERS,
+            HazardKind::Webs => 1
+        };
+    }
+
+    /// Whether a battler of some elements is unaffected by the hazard.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::entry_hazard::HazardKind;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    ///
+    /// assert!(HazardKind::Spikes.is_immune(Elements::new(vec![ElementKind::Water, ElementKind::Air])));
+    /// assert!(!HazardKind::Webs.is_immune(Elements::new(vec![ElementKind::Air])));
+    /// assert!(HazardKind::Webs.is_immune(Elements::new(vec![ElementKind::Fire])));
+    /// ```
+    pub fn is_immune(self, elements: Elements) -> bool {
+        return match self {
+            HazardKind::Spikes => elements.has_elements(ElementKind::Air),
+            HazardKind::Webs => elements.has_elements(ElementKind::Fire)
+        };
+    }
+}
+
+/// Damage spikes deal to a battler switching in, at least 1.
+/// ```
+/// use immie2d_shared::gameplay::battle::entry_hazard::get_spikes_damage;
+/// assert_eq!(get_spikes_damage(200, 1), 25);
+/// assert_eq!(get_spikes_damage(200, 3), 75);
+/// assert_eq!(get_spikes_damage(4, 1), 1);
+/// assert_eq!(get_spikes_damage(u32::MAX, 3), 1_610_612_735);
+/// ```
+pub fn get_spikes_damage(max_health: u32, layers: u32) -> u32 {
+    return (max_health as u64 * layers as u64 / SPIKES_HEALTH_DIVISOR as u64).max(1) as u32;
+}
+
+/* The hazards laid on a side of the field. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct EntryHazards {
+    layers: [u32; HAZARD_KIND_COUNT]
+}
+
+impl EntryHazards {
+    pub fn new() -> EntryHazards {
+        return EntryHazards { layers: [0; HAZARD_KIND_COUNT] };
+    }
+
+    pub fn get_layers(&self, kind: HazardKind) -> u32 {
+        return self.layers[kind.get_id() as usize];
+    }
+
+    /// Lay another layer of a hazard. Returns the new number of layers, or None if it already has the most it can.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::entry_hazard::{EntryHazards, HazardKind, MAX_SPIKES_LAYERS};
+    ///
+    /// let mut hazards = EntryHazards::new();
+    /// assert_eq!(hazards.add_layer(HazardKind::Webs), Some(1));
+    /// assert_eq!(hazards.add_layer(HazardKind::Webs), None);
+    /// for layers in 1..=MAX_SPIKES_LAYERS {
+    ///     assert_eq!(hazards.add_layer(HazardKind::Spikes), Some(layers));
+    /// }
+    /// assert_eq!(hazards.add_layer(HazardKind::Spikes), None);
+    /// hazards.clear();
+    /// assert!(hazards.is_empty());
+    /// ```
+    pub fn add_layer(&mut self, kind: HazardKind) -> Option<u32> {
+        let layers = &mut self.layers[kind.get_id() as usize];
+        if *layers >= kind.get_max_layers() {
+            return None;
+        }
+        *layers += 1;
+        return Some(*layers);
+    }
+
+    pub fn clear(&mut self) {
+        self.layers = [0; HAZARD_KIND_COUNT];
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.layers.iter().all(|layers| *layers == 0);
+    }
+}
@@ -0,0 +1,188 @@
+use std::time::Duration;
+
+use bytes::{BufMut, BytesMut};
+
+use crate::engine_types::encode_buffer::Encode;
+
+use super::battle_event::BattleEvent;
+
+/// How long the animation for a battle event plays for. The server lays out timelines with the same durations
+/// clients animate with, so every viewer finishes an event at the same time.
+pub fn get_event_duration(event: &BattleEvent) -> Duration {
+    let millis = match event {
+        BattleEvent::Transformed { .. } | BattleEvent::Reverted { .. } | BattleEvent::IdentityCopied { .. } | BattleEvent::Attuned { .. } => 800,
+        BattleEvent::AbilityCopied { .. } | BattleEvent::CopyFailed { .. } => 400,
+        BattleEvent::CriticalCapture { .. } => 400,
+        BattleEvent::CaptureShake { .. } => 600,
+        BattleEvent::Captured { .. } | BattleEvent::CaptureFailed { .. } => 700,
+        BattleEvent::Switched { .. } => 600,
+        BattleEvent::SwitchIntercepted { .. } => 300,
+        BattleEvent::AbilityBlocked { .. } | BattleEvent::ComboTriggered { .. } => 400,
+        BattleEvent::AbilityMissed { .. } | BattleEvent::LockedOn { .. } => 400,
+        BattleEvent::Vanished { .. } => 500,
+        BattleEvent::HazardSet { .. } | BattleEvent::HazardsCleared { .. } => 600,
+        BattleEvent::HazardTriggered { .. } => 500,
+        BattleEvent::HazardAvoided { .. } => 300,
+        BattleEvent::MultiTurnProgress { .. } => 400,
+        BattleEvent::MultiTurnCancelled { .. } => 300,
+        BattleEvent::SubstituteDamaged { .. } | BattleEvent::Damaged { .. } | BattleEvent::Healed { .. } => 500,
+        BattleEvent::EvasionStageChanged { .. } => 400,
+        BattleEvent::StatusChanged { .. } => 300,
+        BattleEvent::Fainted { .. } => 700,
+        BattleEvent::SideEliminated { .. } => 500,
+        BattleEvent::TurnEnded { .. } => 0,
+        BattleEvent::BattleEnded { .. } => 1000
+    };
+    return Duration::from_millis(millis);
+}
+
+/* A battle event tagged with when it starts, in milliseconds from the start of its timeline. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TimelineEvent {
+    pub offset_millis: u32,
+    pub event: BattleEvent
+}
+
+impl TimelineEvent {
+    /// Milliseconds from the start of the timeline that the event's animation finishes.
+    pub fn get_end_millis(&self) -> u32 {
+        return self.offset_millis + get_event_duration(&self.event).as_millis() as u32;
+    }
+}
+
+/* A batch of battle events laid out one after another, starting at a server time. Every viewer of a battle plays a
+timeline against the same start time, so spectators on fast and slow connections see the same event at once.
+Times are microseconds since the unix epoch on the server clock. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct EventTimeline {
+    /// Increases by one for each timeline of a battle, starting from 0.
+    pub batch: u32,
+    pub start_time: u64,
+    pub events: Vec<TimelineEvent>
+}
+
+impl EventTimeline {
+    /// Lay out events back to back from a start time, each starting once the previous one's animation finishes.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    /// use immie2d_shared::gameplay::battle::event_timeline::EventTimeline;
+    ///
+    /// let battler = BattlerId::new(1, 0);
+    /// let timeline = EventTimeline::from_events(3, 1_000_000, vec![
+    ///     BattleEvent::Damaged { battler, amount: 50, remaining_health: 0 },
+    ///     BattleEvent::Fainted { battler },
+    ///     BattleEvent::TurnEnded { turn: 1 }
+    /// ]);
+    /// assert_eq!(timeline.events.iter().map(|event| event.offset_millis).collect::<Vec<u32>>(), vec![0, 500, 1200]);
+    /// assert_eq!(timeline.get_duration_millis(), 1200);
+    /// assert_eq!(timeline.get_end_time(), 2_200_000);
+    /// assert_eq!(timeline.get_event_time(1), 1_500_000);
+    /// ```
+    pub fn from_events(batch: u32, start_time: u64, events: Vec<BattleEvent>) -> EventTimeline {
+        let mut offset_millis = 0;
+        let mut timeline_events = Vec::with_capacity(events.len());
+        for event in events {
+            let timeline_event = TimelineEvent { offset_millis, event };
+            offset_millis = timeline_event.get_end_millis();
+            timeline_events.push(timeline_event);
+        }
+        return EventTimeline { batch, start_time, events: timeline_events };
+    }
+
+    /// Milliseconds from the start until the last event finishes.
+    pub fn get_duration_millis(&self) -> u32 {
+        return self.events.iter().map(|event| event.get_end_millis()).max().unwrap_or(0);
+    }
+
+    /// Server time the last event finishes.
+    pub fn get_end_time(&self) -> u64 {
+        return self.start_time + self.get_duration_millis() as u64 * 1000;
+    }
+
+    /// Server time an event starts. Will panic if the index is out of bounds.
+    pub fn get_event_time(&self, index: usize) -> u64 {
+        return self.start_time + self.events[index].offset_millis as u64 * 1000;
+    }
+}
+
+impl Encode for EventTimeline {
+    /// Encode the batch, start time and event count, then each event after its offset.
+    fn encode_into(&self, buffer: &mut BytesMut) {
+        buffer.put_u32_le(self.batch);
+        buffer.put_u64_le(self.start_time);
+        buffer.put_u32_le(self.events.len() as u32);
+        for event in self.events.iter() {
+            buffer.put_u32_le(event.offset_millis);
+            event.event.encode_into(buffer);
+        }
+    }
+}
+
+impl EventTimeline {
+    /// Decode a timeline, or None if the bytes are not a valid timeline.
+    /// ```
+    /// use immie2d_shared::engine_types::encode_buffer::Encode;
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    /// use immie2d_shared::gameplay::battle::event_timeline::EventTimeline;
+    ///
+    /// let battler = BattlerId::new(1, 0);
+    /// let timeline = EventTimeline::from_events(3, 1_000_000, vec![
+    ///     BattleEvent::Damaged { battler, amount: 50, remaining_health: 0 },
+    ///     BattleEvent::Fainted { battler }
+    /// ]);
+    /// let bytes = timeline.encode_to_vec();
+    /// assert_eq!(EventTimeline::from_bytes(&bytes), Some(timeline));
+    /// assert_eq!(EventTimeline::from_bytes(&bytes[..bytes.len() - 1]), None);
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Option<EventTimeline> {
+        let batch = u32::from_le_bytes(bytes.get(0..4)?.try_into().ok()?);
+        let start_time = u64::from_le_bytes(bytes.get(4..12)?.try_into().ok()?);
+        let count = u32::from_le_bytes(bytes.get(12..16)?.try_into().ok()?) as usize;
+        // Every event takes at least 5 bytes, which bounds the allocation for hostile counts
+        if count > bytes.len() / 5 {
+            return None;
+        }
+        let mut offset = 16;
+        let mut events = Vec::with_capacity(count);
+        for _ in 0..count {
+            let offset_millis = u32::from_le_bytes(bytes.get(offset..offset + 4)?.try_into().ok()?);
+            let (event, used) = BattleEvent::decode(&bytes[offset + 4..])?;
+            offset += 4 + used;
+            events.push(TimelineEvent { offset_millis, event });
+        }
+        if offset != bytes.len() {
+            return None;
+        }
+        return Some(EventTimeline { batch, start_time, events });
+    }
+}
+
+/* Sent by a client once it has finished animating every event of a timeline. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TimelineAck {
+    pub batch: u32
+}
+
+impl Encode for TimelineAck {
+    fn encode_into(&self, buffer: &mut BytesMut) {
+        buffer.put_u32_le(self.batch);
+    }
+}
+
+impl TimelineAck {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        return self.encode_to_vec();
+    }
+
+    /// Decode an acknowledgement, or None if the bytes are not a valid acknowledgement.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::event_timeline::TimelineAck;
+    ///
+    /// let ack = TimelineAck { batch: 12 };
+    /// assert_eq!(TimelineAck::from_bytes(&ack.to_bytes()), Some(ack));
+    /// assert_eq!(TimelineAck::from_bytes(&[1, 2]), None);
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Option<TimelineAck> {
+        return Some(TimelineAck { batch: u32::from_le_bytes(bytes.try_into().ok()?) });
+    }
+}
@@ -0,0 +1,44 @@
+use crate::gameplay::elements::element_kinds::ElementKind;
+use crate::gameplay::encounter::encounter_conditions::Weather;
+
+/// Damage multiplier of abilities of an element favoured by the weather.
+pub const WEATHER_BOOST: f32 = 1.5;
+/// Damage multiplier of abilities of an element hindered by the weather.
+pub const WEATHER_PENALTY: f32 = 0.5;
+
+/* Conditions affecting every battler on the field. Battles started in the overworld take the weather of their region. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FieldState {
+    pub weather: Weather
+}
+
+impl FieldState {
+    pub fn default() -> FieldState {
+        return FieldState { weather: Weather::Clear };
+    }
+
+    pub fn from_weather(weather: Weather) -> FieldState {
+        return FieldState { weather };
+    }
+
+    /// Damage multiplier of abilities of an element under the current weather. Rain favours water over fire,
+    /// and sandstorms favour ground. Other weathers only affect encounters.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::field_state::{FieldState, WEATHER_BOOST, WEATHER_PENALTY};
+    /// use immie2d_shared::gameplay::elements::element_kinds::ElementKind;
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::Weather;
+    ///
+    /// let rain = FieldState::from_weather(Weather::Rain);
+    /// assert_eq!(rain.get_element_multiplier(ElementKind::Water), WEATHER_BOOST);
+    /// assert_eq!(rain.get_element_multiplier(ElementKind::Fire), WEATHER_PENALTY);
+    /// assert_eq!(FieldState::default().get_element_multiplier(ElementKind::Fire), 1.0);
+    /// ```
+    pub fn get_element_multiplier(&self, element: ElementKind) -> f32 {
+        return match (self.weather, element) {
+            (Weather::Rain, ElementKind::Water) => WEATHER_BOOST,
+            (Weather::Rain, ElementKind::Fire) => WEATHER_PENALTY,
+            (Weather::Sandstorm, ElementKind::Ground) => WEATHER_BOOST,
+            _ => 1.0
+        };
+    }
+}
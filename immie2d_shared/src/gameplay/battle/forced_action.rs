@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+
+/// Turns an ability flagged AbilityFlags::CHARGES takes, including the turn it hits on.
+pub const CHARGE_TURNS: u32 = 2;
+
+/// Turns spent recharging after hitting with an ability flagged AbilityFlags::RECHARGES.
+pub const RECHARGE_TURNS: u32 = 1;
+
+/// Turns in a row an ability flagged AbilityFlags::LOCKS_IN is used for.
+pub const LOCKED_IN_TURNS: u32 = 3;
+
+/* Why a battler can't choose its command. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ForcedActionKind {
+    /// Charging up an ability that hits on the last turn.
+    Charging,
+    /// Recovering after an ability, doing nothing.
+    Recharging,
+    /// Using the same ability every turn.
+    LockedIn
+}
+
+impl ForcedActionKind {
+    pub fn get_id(self) -> u8 {
+        return match self {
+            ForcedActionKind::Charging => 0,
+            ForcedActionKind::Recharging => 1,
+            ForcedActionKind::LockedIn => 2
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<ForcedActionKind> {
+        return match id {
+            0 => Some(ForcedActionKind::Charging),
+            1 => Some(ForcedActionKind::Recharging),
+            2 => Some(ForcedActionKind::LockedIn),
+            _ => None
+        };
+    }
+}
+
+/* A turn of a multi-turn ability a battler is forced to take instead of choosing a command. The side can only send
+BattleCommand::Continue until the ability finishes. Turns count from 1. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ForcedAction {
+    pub kind: ForcedActionKind,
+    pub ability_slot: usize,
+    pub target_side: usize,
+    /// The turn of the ability this action is.
+    pub turn: u32,
+    pub total_turns: u32
+}
+
+impl ForcedAction {
+    /// The first turn of a multi-turn ability. Will panic if total_turns is 0.
+    pub fn new(kind: ForcedActionKind, ability_slot: usize, target_side: usize, total_turns: u32) -> ForcedAction {
+        assert!(total_turns > 0, "A forced action must last at least 1 turn");
+        return ForcedAction { kind, ability_slot, target_side, turn: 1, total_turns };
+    }
+
+    pub fn is_last_turn(&self) -> bool {
+        return self.turn >= self.total_turns;
+    }
+
+    /// The turn after this one, or None if this is the last.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::forced_action::{ForcedAction, ForcedActionKind, LOCKED_IN_TURNS};
+    ///
+    /// let first = ForcedAction::new(ForcedActionKind::LockedIn, 0, 1, LOCKED_IN_TURNS);
+    /// let second = first.get_next_turn().unwrap();
+    /// assert_eq!(second.turn, 2);
+    /// assert!(second.get_next_turn().unwrap().is_last_turn());
+    /// assert_eq!(second.get_next_turn().unwrap().get_next_turn(), None);
+    /// ```
+    pub fn get_next_turn(&self) -> Option<ForcedAction> {
+        if self.is_last_turn() {
+            return None;
+        }
+        return Some(ForcedAction { turn: self.turn + 1, ..*self });
+    }
+}
@@ -0,0 +1,230 @@
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::ability::ability_flags::AbilityFlags;
+
+use super::battler::Battler;
+use super::battler_id::BattlerId;
+use super::effect_order::{EffectOrderRegistry, AIRBORNE_EFFECT, DEFLECTION_EFFECT, PROTECT_EFFECT, SUBSTITUTE_EFFECT, UNDERGROUND_EFFECT};
+
+/// Name of the passive that blocks projectile abilities.
+pub const DEFLECTION_PASSIVE: &str = "deflection";
+
+lazy_static! {
+    static ref DEFLECTION_PASSIVE_NAME: GlobalString = GlobalString::new(&DEFLECTION_PASSIVE.to_string());
+}
+
+pub use immie2d_core::accuracy::{get_hit_chance, MAX_EVASION_STAGE};
+
+/* Where a battler is while charging a flying or digging ability, out of reach of most abilities. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum SemiInvulnerability {
+    Airborne,
+    Underground
+}
+
+impl SemiInvulnerability {
+    pub fn get_id(self) -> u8 {
+        return match self {
+            SemiInvulnerability::Airborne => 0,
+            SemiInvulnerability::Underground => 1
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<SemiInvulnerability> {
+        return match id {
+            0 => Some(SemiInvulnerability::Airborne),
+            1 => Some(SemiInvulnerability::Underground),
+            _ => None
+        };
+    }
+
+    /// The state a charging ability puts its user in, if any.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability::{AbilityCategory, BaseAbilityData}, ability_flags::AbilityFlags};
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat, battle_event::BattleEvent};
+    /// use immie2d_shared::gameplay::battle::battle_command::BattleCommand;
+    /// use immie2d_shared::gameplay::battle::hit_resolution::{HitBlocker, SemiInvulnerability};
+    ///
+    /// let ability = |flags: AbilityFlags| BaseAbilityData {
+    ///     category: AbilityCategory::Attack, types: Elements::new(vec![ElementKind::Air]), power: 40.0, speed: 1.0, max_uses: 10, accuracy: 100, flags, combo: None
+    /// };
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_data_ability("sky_dive", ability(AbilityFlags::CHARGES | AbilityFlags::FLIES));
+    /// ability_map.add_data_ability("tackle", ability(AbilityFlags::CONTACT));
+    /// ability_map.add_data_ability("gust", ability(AbilityFlags::HITS_AIRBORNE));
+    /// let species = SpeciesData::new(GlobalString::new(&"breezel".to_string()), Elements::new(vec![ElementKind::Air]), BaseStats::new(500, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(species);
+    /// let names = ["sky_dive", "tackle", "gust"].map(|name| GlobalString::new(&name.to_string()));
+    /// let side = BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, AbilityNames::new(names.to_vec())), &species)]);
+    /// let mut battle = Battle::new(BattleFormat::Single, vec![side.clone(), side]);
+    /// let (flyer, other) = (BattlerId::new(0, 0), BattlerId::new(1, 0));
+    ///
+    /// battle.apply_command(BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 }, &ability_map, &species_map).unwrap();
+    /// assert_eq!(battle.get_battler(flyer).get_semi_invulnerability(), Some(SemiInvulnerability::Airborne));
+    /// battle.apply_command(BattleCommand::UseAbility { side: 1, ability_slot: 1, target_side: 0 }, &ability_map, &species_map).unwrap();
+    /// assert!(battle.take_events().contains(&BattleEvent::AbilityBlocked { defender: flyer, blocker: HitBlocker::Airborne }));
+    /// battle.apply_command(BattleCommand::UseAbility { side: 1, ability_slot: 2, target_side: 0 }, &ability_map, &species_map).unwrap();
+    /// assert!(battle.get_battler(flyer).get_health() < 500);
+    ///
+    /// // Coming down to hit ends the semi-invulnerability
+    /// battle.end_turn();
+    /// battle.apply_command(BattleCommand::Continue { side: 0 }, &ability_map, &species_map).unwrap();
+    /// assert_eq!(battle.get_battler(flyer).get_semi_invulnerability(), None);
+    /// assert!(battle.get_battler(other).get_health() < 500);
+    /// ```
+    pub fn from_flags(flags: AbilityFlags) -> Option<SemiInvulnerability> {
+        if flags.contains(AbilityFlags::FLIES) {
+            return Some(SemiInvulnerability::Airborne);
+        }
+        if flags.contains(AbilityFlags::DIGS) {
+            return Some(SemiInvulnerability::Underground);
+        }
+        return None;
+    }
+}
+
+/* A battler's next ability on the target can't miss. Lasts until it is used on the target or the turn ends. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LockOn {
+    pub target: BattlerId,
+    /// Last turn the lock on can be used on.
+    pub last_turn: u32
+}
+
+/* Something on the defender that can stop an ability from hitting it directly. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum HitBlocker {
+    /// The defender is in the air, out of reach of abilities without AbilityFlags::HITS_AIRBORNE.
+    Airborne,
+    /// The defender is underground, out of reach of abilities without AbilityFlags::HITS_UNDERGROUND.
+    Underground,
+    Protect,
+    Deflection,
+    Substitute
+}
+
+impl HitBlocker {
+    pub fn get_id(self) -> u8 {
+        return match self {
+            HitBlocker::Airborne => 0,
+            HitBlocker::Underground => 1,
+            HitBlocker::Protect => 2,
+            HitBlocker::Deflection => 3,
+            HitBlocker::Substitute => 4
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<HitBlocker> {
+        return match id {
+            0 => Some(HitBlocker::Airborne),
+            1 => Some(HitBlocker::Underground),
+            2 => Some(HitBlocker::Protect),
+            3 => Some(HitBlocker::Deflection),
+            4 => Some(HitBlocker::Substitute),
+            _ => None
+        };
+    }
+
+    /// Name of the blocker's effect in the effect order, which decides which blocker is checked first.
+    pub fn get_effect_name(self) -> &'static str {
+        return match self {
+            HitBlocker::Airborne => AIRBORNE_EFFECT,
+            HitBlocker::Underground => UNDERGROUND_EFFECT,
+            HitBlocker::Protect => PROTECT_EFFECT,
+            HitBlocker::Deflection => DEFLECTION_EFFECT,
+            HitBlocker::Substitute => SUBSTITUTE_EFFECT
+        };
+    }
+}
+
+/* What an ability hits. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HitOutcome {
+    Hit,
+    /// The ability hit the defender's substitute instead of the defender.
+    HitSubstitute,
+    Blocked(HitBlocker),
+    /// The ability missed from its accuracy or the defender's evasion. Only decided once the ability is used, so
+    /// resolve_hit() never returns it. See get_hit_chance()
+    Missed
+}
+
+/* How a blocker interacts with abilities. A blocker only applies to abilities with any of the applies_to flags
+(or every ability if NONE), and never to abilities with any of the bypassed_by flags. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct InteractionRule {
+    pub blocker: HitBlocker,
+    pub applies_to: AbilityFlags,
+    pub bypassed_by: AbilityFlags
+}
+
+/// The interaction rules, consulted in the effect order of their blockers. The first blocker that applies decides the
+/// outcome.
+pub const INTERACTION_RULES: [InteractionRule; 5] = [
+    InteractionRule { blocker: HitBlocker::Airborne, applies_to: AbilityFlags::NONE, bypassed_by: AbilityFlags::HITS_AIRBORNE },
+    InteractionRule { blocker: HitBlocker::Underground, applies_to: AbilityFlags::NONE, bypassed_by: AbilityFlags::HITS_UNDERGROUND },
+    InteractionRule { blocker: HitBlocker::Protect, applies_to: AbilityFlags::NONE, bypassed_by: AbilityFlags::SOUND },
+    InteractionRule { blocker: HitBlocker::Deflection, applies_to: AbilityFlags::PROJECTILE, bypassed_by: AbilityFlags::NONE },
+    InteractionRule { blocker: HitBlocker::Substitute, applies_to: AbilityFlags::NONE, bypassed_by: AbilityFlags::SOUND }
+];
+
+fn is_blocker_active(blocker: HitBlocker, defender: &Battler) -> bool {
+    return match blocker {
+        HitBlocker::Airborne => defender.get_semi_invulnerability() == Some(SemiInvulnerability::Airborne),
+        HitBlocker::Underground => defender.get_semi_invulnerability() == Some(SemiInvulnerability::Underground),
+        HitBlocker::Protect => defender.is_protected(),
+        HitBlocker::Deflection => defender.get_immie().passive == Some(*DEFLECTION_PASSIVE_NAME),
+        HitBlocker::Substitute => defender.has_substitute()
+    };
+}
+
+/// Decide what an ability with some flags hits when used on a defender, checking blockers in the battle's effect order.
+/// Will panic if a blocker's effect isn't registered.
+/// ```
+/// # use immie2d_shared::engine_types::global_string::GlobalString;
+/// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+/// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+/// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+/// # use immie2d_shared::gameplay::immie::immie::Immie;
+/// use immie2d_shared::gameplay::ability::ability_flags::AbilityFlags;
+/// use immie2d_shared::gameplay::battle::{battler::Battler, effect_order::EffectOrderRegistry};
+/// use immie2d_shared::gameplay::battle::hit_resolution::{resolve_hit, HitOutcome, HitBlocker};
+///
+/// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+/// let mut immie = Immie::new(species.name, 5, AbilityNames::default());
+/// immie.passive = Some(GlobalString::new(&"deflection".to_string()));
+/// let mut defender = Battler::new(immie, &species);
+/// let order = EffectOrderRegistry::standard();
+///
+/// assert_eq!(resolve_hit(AbilityFlags::PROJECTILE, &defender, &order), HitOutcome::Blocked(HitBlocker::Deflection));
+/// defender.set_substitute(10);
+/// assert_eq!(resolve_hit(AbilityFlags::PROJECTILE, &defender, &order), HitOutcome::Blocked(HitBlocker::Deflection));
+/// assert_eq!(resolve_hit(AbilityFlags::CONTACT, &defender, &order), HitOutcome::HitSubstitute);
+/// assert_eq!(resolve_hit(AbilityFlags::SOUND, &defender, &order), HitOutcome::Hit);
+/// defender.set_protected(true);
+/// assert_eq!(resolve_hit(AbilityFlags::CONTACT, &defender, &order), HitOutcome::Blocked(HitBlocker::Protect));
+/// assert_eq!(resolve_hit(AbilityFlags::SOUND, &defender, &order), HitOutcome::Hit);
+/// ```
+pub fn resolve_hit(ability_flags: AbilityFlags, defender: &Battler, effect_order: &EffectOrderRegistry) -> HitOutcome {
+    let mut rules = INTERACTION_RULES;
+    effect_order.sort(&mut rules, |rule| rule.blocker.get_effect_name());
+    for rule in rules.iter() {
+        let applies = rule.applies_to == AbilityFlags::NONE || ability_flags.intersects(rule.applies_to);
+        if !applies || ability_flags.intersects(rule.bypassed_by) || !is_blocker_active(rule.blocker, defender) {
+            continue;
+        }
+        if rule.blocker == HitBlocker::Substitute {
+            return HitOutcome::HitSubstitute;
+        }
+        return HitOutcome::Blocked(rule.blocker);
+    }
+    return HitOutcome::Hit;
+}
@@ -0,0 +1,25 @@
+pub mod battle;
+pub mod battler;
+pub mod battler_id;
+pub mod battle_side;
+pub mod battle_format;
+pub mod battle_event;
+pub mod seed_commitment;
+pub mod damage;
+pub mod rules;
+pub mod hit_resolution;
+pub mod battle_command;
+pub mod battle_builder;
+pub mod ability_pipeline;
+pub mod field_state;
+pub mod random_ai;
+pub mod event_timeline;
+pub mod action_queue;
+pub mod forced_action;
+pub mod campaign;
+pub mod battle_evaluation;
+pub mod effect_order;
+pub mod state_hash;
+pub mod copied_identity;
+pub mod entry_hazard;
+pub mod state_diff;
@@ -0,0 +1,67 @@
+use crate::engine_types::game_rng::GameRng;
+use crate::gameplay::ability::ability_map::AbilityMap;
+
+use super::battle::Battle;
+use super::battle_command::BattleCommand;
+
+/// Choose a random valid command for a side, for bots and load testing rather than real opponents. Switches to a
+/// random healthy battler if the active one fainted and continues any forced multi-turn ability, otherwise uses a
+/// random ability with uses left on a random target. Ends the turn if nothing else is possible.
+/// ```
+/// # use immie2d_shared::engine_types::global_string::GlobalString;
+/// # use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames, abilities::fireball::Fireball};
+/// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+/// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+/// # use immie2d_shared::gameplay::immie::immie::Immie;
+/// use immie2d_shared::engine_types::game_rng::GameRng;
+/// use immie2d_shared::gameplay::battle::{battle::Battle, battle_format::BattleFormat, battle_side::BattleSide, battler::Battler};
+/// use immie2d_shared::gameplay::battle::{battle_command::BattleCommand, random_ai::choose_random_command};
+///
+/// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+/// let mut species_map = SpeciesMap::new();
+/// species_map.add_species(species);
+/// let mut ability_map = AbilityMap::new();
+/// ability_map.add_ability::<Fireball>();
+/// let immie = Immie::new(species.name, 5, AbilityNames::new(vec![GlobalString::new(&"fireball".to_string())]));
+/// let side = BattleSide::new(vec![Battler::new(immie, &species), Battler::new(immie, &species)]);
+/// let mut battle = Battle::new(BattleFormat::Single, vec![side.clone(), side]);
+///
+/// let mut rng = GameRng::new(5);
+/// while !battle.is_finished() {
+///     for side in battle.get_turn_order() {
+///         let command = choose_random_command(&battle, side, &ability_map, &mut rng);
+///         if !battle.is_finished() && command != BattleCommand::EndTurn {
+///             battle.apply_command(command, &ability_map, &species_map).unwrap();
+///         }
+///     }
+///     if !battle.is_finished() {
+///         battle.apply_command(BattleCommand::EndTurn, &ability_map, &species_map).unwrap();
+///     }
+/// }
+/// assert!(battle.get_winner().is_some());
+/// ```
+pub fn choose_random_command(battle: &Battle, side: usize, ability_map: &AbilityMap, rng: &mut GameRng) -> BattleCommand {
+    if battle.is_finished() || side >= battle.get_side_count() || battle.get_side(side).is_eliminated() {
+        return BattleCommand::EndTurn;
+    }
+    let battle_side = battle.get_side(side);
+    if battle_side.get_active().is_fainted() {
+        let healthy: Vec<usize> = (0..battle_side.get_team().len()).filter(|slot| !battle_side.get_battler(*slot).is_fainted()).collect();
+        return BattleCommand::Switch { side, slot: healthy[rng.next_below(healthy.len() as u32) as usize] };
+    }
+    if battle.get_forced_action(side).is_some() {
+        return BattleCommand::Continue { side };
+    }
+    let targets = battle.get_valid_targets(side);
+    let battler = battle_side.get_active();
+    let usable: Vec<usize> = battler.get_abilities().iter().enumerate().filter(|(slot, ability)| {
+        let name = ability.to_string();
+        return ability_map.is_ability_name(&name) && battler.get_remaining_uses(*slot, ability_map.new_ability(&name).get_base_ability_data().max_uses) > 0;
+    }).map(|(slot, _)| slot).collect();
+    if usable.is_empty() || targets.is_empty() {
+        return BattleCommand::EndTurn;
+    }
+    let ability_slot = usable[rng.next_below(usable.len() as u32) as usize];
+    let target_side = targets[rng.next_below(targets.len() as u32) as usize];
+    return BattleCommand::UseAbility { side, ability_slot, target_side };
+}
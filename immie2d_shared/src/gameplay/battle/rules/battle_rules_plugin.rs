@@ -0,0 +1,39 @@
+use crate::gameplay::battle::action_queue::{ActionQueue, BattleAction};
+use crate::gameplay::battle::battle::Battle;
+use crate::gameplay::battle::battler_id::BattlerId;
+use crate::gameplay::battle::damage::DamageContext;
+
+/// Hooks into fixed points of the battle pipeline, so alternative rulesets can change mechanics without changing the engine.
+/// Every hook does nothing by default.
+pub trait BattleRulesPlugin: Send + Sync {
+    fn get_name(&self) -> &'static str;
+
+    /// Called after the damage inputs are gathered and before damage is calculated.
+    fn pre_damage(&self, _battle: &Battle, _context: &mut DamageContext) {}
+
+    /// Called before a battler is switched out, while it is still its side's active battler.
+    fn pre_switch(&self, _battle: &mut Battle, _retreating: BattlerId) {}
+
+    /// Called after a battler has been switched in as its side's active battler.
+    fn on_switch(&self, _battle: &mut Battle, _switched_in: BattlerId) {}
+
+    /// Called before an action of Battle::resolve_turn() runs. Reactions queued with ActionQueue::interrupt() run
+    /// before the action does.
+    fn pre_action(&self, _battle: &Battle, _action: &BattleAction, _queue: &mut ActionQueue) {}
+
+    /// Called after an action of Battle::resolve_turn() ran without being rejected. Reactions queued with
+    /// ActionQueue::interrupt() run before the rest of the turn, such as a counter striking back.
+    fn post_action(&self, _battle: &Battle, _action: &BattleAction, _queue: &mut ActionQueue) {}
+
+    /// Called once every side has acted and the turn counter has advanced.
+    fn post_turn(&self, _battle: &mut Battle) {}
+}
+
+/* The normal rules, which change nothing. */
+pub struct StandardRules;
+
+impl BattleRulesPlugin for StandardRules {
+    fn get_name(&self) -> &'static str {
+        return "standard";
+    }
+}
@@ -0,0 +1,34 @@
+use std::sync::Arc;
+
+use super::battle_rules_plugin::{BattleRulesPlugin, StandardRules};
+use super::inverse_types::InverseTypesRules;
+use super::level_capped::LevelCappedRules;
+use super::retaliation::RetaliationRules;
+use super::sudden_death::SuddenDeathRules;
+
+/* The built in rulesets that a session can be created with. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BattleRuleset {
+    Standard,
+    InverseTypes,
+    SuddenDeath { turn_limit: u32 },
+    LevelCapped { level_cap: u32 },
+    Retaliation
+}
+
+impl BattleRuleset {
+    /// Create the plugin implementing this ruleset.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+    /// assert_eq!(BattleRuleset::InverseTypes.create_plugin().get_name(), "inverse_types");
+    /// ```
+    pub fn create_plugin(&self) -> Arc<dyn BattleRulesPlugin> {
+        return match *self {
+            BattleRuleset::Standard => Arc::new(StandardRules),
+            BattleRuleset::InverseTypes => Arc::new(InverseTypesRules),
+            BattleRuleset::SuddenDeath { turn_limit } => Arc::new(SuddenDeathRules { turn_limit }),
+            BattleRuleset::LevelCapped { level_cap } => Arc::new(LevelCappedRules { level_cap }),
+            BattleRuleset::Retaliation => Arc::new(RetaliationRules)
+        };
+    }
+}
@@ -0,0 +1,25 @@
+use crate::gameplay::battle::battle::Battle;
+use crate::gameplay::battle::damage::DamageContext;
+use crate::gameplay::elements::type_chart::{get_effectiveness, NEUTRAL, NOT_VERY_EFFECTIVE, SUPER_EFFECTIVE};
+
+use super::battle_rules_plugin::BattleRulesPlugin;
+
+/* Inverts the type chart. Weaknesses become resistances, and resistances and immunities become weaknesses. */
+pub struct InverseTypesRules;
+
+impl BattleRulesPlugin for InverseTypesRules {
+    fn get_name(&self) -> &'static str {
+        return "inverse_types";
+    }
+
+    fn pre_damage(&self, _battle: &Battle, context: &mut DamageContext) {
+        let mut effectiveness = 1.0;
+        for attacking in context.ability_elements.iter() {
+            for defending in context.defender_elements.iter() {
+                let normal = get_effectiveness(attacking, defending);
+                effectiveness *= if normal > NEUTRAL { NOT_VERY_EFFECTIVE } else if normal < NEUTRAL { SUPER_EFFECTIVE } else { NEUTRAL };
+            }
+        }
+        context.effectiveness = effectiveness;
+    }
+}
@@ -0,0 +1,19 @@
+use crate::gameplay::battle::battle::Battle;
+use crate::gameplay::battle::damage::DamageContext;
+
+use super::battle_rules_plugin::BattleRulesPlugin;
+
+/* Immies above the level cap battle as if they were at the cap. */
+pub struct LevelCappedRules {
+    pub level_cap: u32
+}
+
+impl BattleRulesPlugin for LevelCappedRules {
+    fn get_name(&self) -> &'static str {
+        return "level_capped";
+    }
+
+    fn pre_damage(&self, _battle: &Battle, context: &mut DamageContext) {
+        context.attacker_level = context.attacker_level.min(self.level_cap);
+    }
+}
@@ -0,0 +1,6 @@
+pub mod battle_rules_plugin;
+pub mod battle_ruleset;
+pub mod inverse_types;
+pub mod sudden_death;
+pub mod level_capped;
+pub mod retaliation;
@@ -0,0 +1,34 @@
+use crate::gameplay::battle::action_queue::{ActionQueue, BattleAction};
+use crate::gameplay::battle::battle::Battle;
+use crate::gameplay::battle::battle_command::BattleCommand;
+use crate::gameplay::battle::battle_event::BattleEvent;
+
+use super::battle_rules_plugin::BattleRulesPlugin;
+
+/// Power multiplier of a retaliating battler's counter.
+pub const RETALIATION_POWER_MULTIPLIER: f32 = 0.5;
+
+/* A battler damaged by another side's ability strikes back straight away with its first ability at reduced power,
+before anything else in the turn runs. Counters are never countered themselves. */
+pub struct RetaliationRules;
+
+impl BattleRulesPlugin for RetaliationRules {
+    fn get_name(&self) -> &'static str {
+        return "retaliation";
+    }
+
+    fn post_action(&self, battle: &Battle, action: &BattleAction, queue: &mut ActionQueue) {
+        let BattleAction::Command { command: BattleCommand::UseAbility { side, target_side, .. }, .. } = *action else {
+            return;
+        };
+        if side == target_side || battle.is_finished() {
+            return;
+        }
+        let defender = battle.get_active_battler_id(target_side);
+        let was_damaged = battle.get_action_events().iter().any(|event| matches!(*event, BattleEvent::Damaged { battler, .. } if battler == defender));
+        if !was_damaged || battle.get_battler(defender).is_fainted() {
+            return;
+        }
+        queue.interrupt(BattleAction::ReactiveAbility { side: target_side, ability_slot: 0, target_side: side, power_multiplier: RETALIATION_POWER_MULTIPLIER });
+    }
+}
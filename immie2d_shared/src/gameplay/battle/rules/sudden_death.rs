@@ -0,0 +1,32 @@
+use crate::gameplay::battle::battle::Battle;
+
+use super::battle_rules_plugin::BattleRulesPlugin;
+
+/// Fraction of max health every active battler loses each turn once sudden death begins.
+pub const SUDDEN_DEATH_DAMAGE_FRACTION: u32 = 8;
+
+/* Once the turn limit is passed, every active battler loses health at the end of each turn, forcing the battle to end. */
+pub struct SuddenDeathRules {
+    pub turn_limit: u32
+}
+
+impl BattleRulesPlugin for SuddenDeathRules {
+    fn get_name(&self) -> &'static str {
+        return "sudden_death";
+    }
+
+    fn post_turn(&self, battle: &mut Battle) {
+        let ended_turn = battle.get_turn() - 1;
+        if ended_turn <= self.turn_limit {
+            return;
+        }
+        for side in battle.get_turn_order() {
+            if battle.is_finished() {
+                return;
+            }
+            let battler = battle.get_active_battler_id(side);
+            let damage = (battle.get_battler(battler).get_stats().health / SUDDEN_DEATH_DAMAGE_FRACTION).max(1);
+            battle.apply_damage(battler, damage);
+        }
+    }
+}
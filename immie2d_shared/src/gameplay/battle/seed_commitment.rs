@@ -0,0 +1,126 @@
+use sha2::{Digest, Sha256};
+
+pub const SEED_BYTES: usize = 32;
+
+const COMMITMENT_DOMAIN: &[u8] = b"immie2d-seed-commitment";
+const COMBINE_DOMAIN: &[u8] = b"immie2d-seed-combine";
+
+/* Hash of the server's secret, sent to clients before they contribute entropy so the server can't change its secret afterwards. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SeedCommitment {
+    pub hash: [u8; 32]
+}
+
+/* Everything needed to recompute and verify a battle seed. Published with the replay once the battle is over. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SeedReveal {
+    pub server_secret: [u8; SEED_BYTES],
+    /// Entropy from each client, in side order.
+    pub client_entropy: Vec<[u8; SEED_BYTES]>
+}
+
+impl SeedCommitment {
+    /// Commit to a server secret.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::seed_commitment::SeedCommitment;
+    /// assert_eq!(SeedCommitment::commit(&[1; 32]), SeedCommitment::commit(&[1; 32]));
+    /// assert_ne!(SeedCommitment::commit(&[1; 32]), SeedCommitment::commit(&[2; 32]));
+    /// ```
+    pub fn commit(server_secret: &[u8; SEED_BYTES]) -> SeedCommitment {
+        let mut hasher = Sha256::new();
+        hasher.update(COMMITMENT_DOMAIN);
+        hasher.update(server_secret);
+        return SeedCommitment { hash: hasher.finalize().into() };
+    }
+}
+
+impl SeedReveal {
+    pub fn new(server_secret: [u8; SEED_BYTES], client_entropy: Vec<[u8; SEED_BYTES]>) -> SeedReveal {
+        return SeedReveal { server_secret, client_entropy };
+    }
+
+    /// Check that the revealed secret is the one that was committed to.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::seed_commitment::{SeedCommitment, SeedReveal};
+    /// let commitment = SeedCommitment::commit(&[7; 32]);
+    /// assert!(SeedReveal::new([7; 32], vec![[1; 32], [2; 32]]).verify(&commitment));
+    /// assert!(!SeedReveal::new([8; 32], vec![[1; 32], [2; 32]]).verify(&commitment));
+    /// ```
+    pub fn verify(&self, commitment: &SeedCommitment) -> bool {
+        return SeedCommitment::commit(&self.server_secret) == *commitment;
+    }
+
+    /// The seed that drives the battle's GameRng, combining the server secret with every client's entropy.
+    /// Neither the server nor any single client can choose the result.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::seed_commitment::SeedReveal;
+    /// let seed = SeedReveal::new([7; 32], vec![[1; 32], [2; 32]]).combined_seed();
+    /// assert_eq!(seed, SeedReveal::new([7; 32], vec![[1; 32], [2; 32]]).combined_seed());
+    /// // Order of entropy matters, so sides can't swap contributions.
+    /// assert_ne!(seed, SeedReveal::new([7; 32], vec![[2; 32], [1; 32]]).combined_seed());
+    /// ```
+    pub fn combined_seed(&self) -> u64 {
+        let mut hasher = Sha256::new();
+        hasher.update(COMBINE_DOMAIN);
+        hasher.update(self.server_secret);
+        for entropy in self.client_entropy.iter() {
+            hasher.update(entropy);
+        }
+        let digest = hasher.finalize();
+        let mut seed_bytes = [0u8; 8];
+        seed_bytes.copy_from_slice(&digest[..8]);
+        return u64::from_le_bytes(seed_bytes);
+    }
+}
+
+/* Server side state of agreeing on a battle seed. The commitment is sent first, then each side's client entropy is collected. */
+pub struct SeedNegotiation {
+    server_secret: [u8; SEED_BYTES],
+    client_entropy: Vec<Option<[u8; SEED_BYTES]>>
+}
+
+impl SeedNegotiation {
+    /// Start negotiating with a freshly generated server secret. The secret must never be reused between battles.
+    pub fn new(server_secret: [u8; SEED_BYTES], side_count: usize) -> SeedNegotiation {
+        return SeedNegotiation { server_secret, client_entropy: vec![None; side_count] };
+    }
+
+    pub fn get_commitment(&self) -> SeedCommitment {
+        return SeedCommitment::commit(&self.server_secret);
+    }
+
+    /// Record the entropy of a side. Returns false if the side is invalid or has already contributed.
+    pub fn submit_entropy(&mut self, side: usize, entropy: [u8; SEED_BYTES]) -> bool {
+        return match self.client_entropy.get_mut(side) {
+            Some(slot) if slot.is_none() => {
+                *slot = Some(entropy);
+                true
+            },
+            _ => false
+        };
+    }
+
+    pub fn is_complete(&self) -> bool {
+        return self.client_entropy.iter().all(|entropy| entropy.is_some());
+    }
+
+    /// Finish negotiating once every side has contributed, getting the reveal to publish after the battle.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::seed_commitment::SeedNegotiation;
+    /// let mut negotiation = SeedNegotiation::new([9; 32], 2);
+    /// let commitment = negotiation.get_commitment();
+    /// assert!(negotiation.submit_entropy(0, [1; 32]));
+    /// assert!(!negotiation.submit_entropy(0, [3; 32]));
+    /// assert!(!negotiation.is_complete());
+    /// assert!(negotiation.submit_entropy(1, [2; 32]));
+    ///
+    /// let reveal = negotiation.finish();
+    /// assert!(reveal.verify(&commitment));
+    /// assert_eq!(reveal.client_entropy, vec![[1; 32], [2; 32]]);
+    /// ```
+    /// Will panic if a side hasn't contributed entropy yet.
+    pub fn finish(self) -> SeedReveal {
+        assert!(self.is_complete(), "Cannot finish seed negotiation before every side has contributed entropy");
+        return SeedReveal::new(self.server_secret, self.client_entropy.into_iter().map(|entropy| entropy.unwrap()).collect());
+    }
+}
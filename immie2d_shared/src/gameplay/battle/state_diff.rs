@@ -0,0 +1,170 @@
+use serde::{Deserialize, Serialize};
+
+use super::battle::Battle;
+use super::battle_event::BattleEvent;
+use super::battler::Battler;
+use super::battler_id::BattlerId;
+
+/// Number of kinds of status.
+pub const BATTLER_STATUS_COUNT: usize = 3;
+
+/* A state a battler is in or not, shown to clients with StatusChanged events. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum BattlerStatus {
+    /// Abilities used on the battler this turn are blocked.
+    Protected,
+    /// A substitute takes damage in the battler's place.
+    Substitute,
+    /// The battler flew up or dug underground while charging an ability. See Vanished
+    SemiInvulnerable
+}
+
+impl BattlerStatus {
+    pub const ALL: [BattlerStatus; BATTLER_STATUS_COUNT] = [BattlerStatus::Protected, BattlerStatus::Substitute, BattlerStatus::SemiInvulnerable];
+
+    pub fn get_id(self) -> u8 {
+        return match self {
+            BattlerStatus::Protected => 0,
+            BattlerStatus::Substitute => 1,
+            BattlerStatus::SemiInvulnerable => 2
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<BattlerStatus> {
+        return match id {
+            0 => Some(BattlerStatus::Protected),
+            1 => Some(BattlerStatus::Substitute),
+            2 => Some(BattlerStatus::SemiInvulnerable),
+            _ => None
+        };
+    }
+}
+
+/* The state of a battler that clients display, so what changed can be found by comparing it before and after. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct BattlerState {
+    pub health: u32,
+    pub evasion_stage: i32,
+    statuses: [bool; BATTLER_STATUS_COUNT]
+}
+
+impl BattlerState {
+    pub fn from_battler(battler: &Battler) -> BattlerState {
+        let mut statuses = [false; BATTLER_STATUS_COUNT];
+        statuses[BattlerStatus::Protected.get_id() as usize] = battler.is_protected();
+        statuses[BattlerStatus::Substitute.get_id() as usize] = battler.has_substitute();
+        statuses[BattlerStatus::SemiInvulnerable.get_id() as usize] = battler.get_semi_invulnerability().is_some();
+        return BattlerState { health: battler.get_health(), evasion_stage: battler.get_evasion_stage(), statuses };
+    }
+
+    pub fn has_status(&self, status: BattlerStatus) -> bool {
+        return self.statuses[status.get_id() as usize];
+    }
+
+    fn set_status(&mut self, status: BattlerStatus, is_active: bool) {
+        self.statuses[status.get_id() as usize] = is_active;
+    }
+}
+
+/* The displayed state of every battler in a battle at some moment. A battle diffs its state against a snapshot taken
+before each action, so a change an effect forgot to announce still reaches clients. */
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct BattleStateSnapshot {
+    sides: Vec<Vec<BattlerState>>
+}
+
+impl BattleStateSnapshot {
+    pub fn capture(battle: &Battle) -> BattleStateSnapshot {
+        let sides = (0..battle.get_side_count()).map(|side| battle.get_side(side).get_team().iter().map(BattlerState::from_battler).collect()).collect();
+        return BattleStateSnapshot { sides };
+    }
+
+    pub fn get_battler(&self, battler: BattlerId) -> BattlerState {
+        return self.sides[battler.side][battler.slot];
+    }
+
+    /// Update the state as a client shown an event would. Events that don't say what a battler's state now is, such as
+    /// a hazard being triggered, change nothing, as the canonical events after them do.
+    pub fn apply_event(&mut self, event: &BattleEvent) {
+        let mut update = |battler: BattlerId, change: &dyn Fn(&mut BattlerState)| {
+            if let Some(state) = self.sides.get_mut(battler.side).and_then(|side| side.get_mut(battler.slot)) {
+                change(state);
+            }
+        };
+        match *event {
+            BattleEvent::Damaged { battler, remaining_health, .. } => update(battler, &|state| state.health = remaining_health),
+            BattleEvent::Healed { battler, health, .. } => update(battler, &|state| state.health = health),
+            BattleEvent::Fainted { battler } => update(battler, &|state| state.health = 0),
+            BattleEvent::EvasionStageChanged { battler, stage } => update(battler, &|state| state.evasion_stage = stage),
+            BattleEvent::Vanished { battler, .. } => update(battler, &|state| state.set_status(BattlerStatus::SemiInvulnerable, true)),
+            BattleEvent::SubstituteDamaged { battler, remaining_health, .. } => update(battler, &|state| state.set_status(BattlerStatus::Substitute, remaining_health > 0)),
+            BattleEvent::StatusChanged { battler, status, is_active } => update(battler, &|state| state.set_status(status, is_active)),
+            _ => ()
+        }
+    }
+
+    /// The canonical events announcing every change from this state to a later one that the events emitted in between
+    /// don't already announce, in order of side, slot, then health, evasion and statuses.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat, battle_event::BattleEvent};
+    /// use immie2d_shared::gameplay::battle::state_diff::{BattleStateSnapshot, BattlerStatus};
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut protected = Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species);
+    /// protected.set_protected(true);
+    /// let side = |battler: Battler| BattleSide::new(vec![battler]);
+    /// let before = BattleStateSnapshot::capture(&Battle::new(BattleFormat::Single, vec![side(protected.clone()), side(protected.clone())]));
+    ///
+    /// let mut damaged = protected.clone();
+    /// damaged.apply_damage(20);
+    /// damaged.change_evasion_stage(-1);
+    /// damaged.set_protected(false);
+    /// let after = BattleStateSnapshot::capture(&Battle::new(BattleFormat::Single, vec![side(protected), side(damaged)]));
+    /// let battler = BattlerId::new(1, 0);
+    /// assert_eq!(before.diff(&[], &after), vec![
+    ///     BattleEvent::Damaged { battler, amount: 20, remaining_health: 30 },
+    ///     BattleEvent::EvasionStageChanged { battler, stage: -1 },
+    ///     BattleEvent::StatusChanged { battler, status: BattlerStatus::Protected, is_active: false }
+    /// ]);
+    /// // Changes that were announced aren't announced again
+    /// assert_eq!(before.diff(&[BattleEvent::Damaged { battler, amount: 20, remaining_health: 30 }], &after).len(), 2);
+    /// assert!(after.diff(&[], &after).is_empty());
+    /// ```
+    pub fn diff(&self, announced: &[BattleEvent], after: &BattleStateSnapshot) -> Vec<BattleEvent> {
+        let mut expected = self.clone();
+        for event in announced {
+            expected.apply_event(event);
+        }
+        let mut events = Vec::new();
+        for (side, team) in after.sides.iter().enumerate() {
+            for (slot, state) in team.iter().enumerate() {
+                let battler = BattlerId::new(side, slot);
+                let Some(shown) = expected.sides.get(side).and_then(|team| team.get(slot)) else {
+                    continue;
+                };
+                if state.health < shown.health {
+                    events.push(BattleEvent::Damaged { battler, amount: shown.health - state.health, remaining_health: state.health });
+                    if state.health == 0 {
+                        events.push(BattleEvent::Fainted { battler });
+                    }
+                } else if state.health > shown.health {
+                    events.push(BattleEvent::Healed { battler, amount: state.health - shown.health, health: state.health });
+                }
+                if state.evasion_stage != shown.evasion_stage {
+                    events.push(BattleEvent::EvasionStageChanged { battler, stage: state.evasion_stage });
+                }
+                for status in BattlerStatus::ALL {
+                    if state.has_status(status) != shown.has_status(status) {
+                        events.push(BattleEvent::StatusChanged { battler, status, is_active: state.has_status(status) });
+                    }
+                }
+            }
+        }
+        return events;
+    }
+}
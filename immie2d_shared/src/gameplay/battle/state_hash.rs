@@ -0,0 +1,136 @@
+use bytes::{BufMut, BytesMut};
+
+use crate::engine_types::encode_buffer::Encode;
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::elements::{element_kinds::ElementKind, elements_data::Elements};
+use crate::gameplay::immie::immie::Immie;
+use crate::gameplay::species::base_stats::BaseStats;
+
+use super::battle_format::BattleFormat;
+use super::battler_id::BattlerId;
+
+/* Sent by a client mirroring a battle after each turn, with the hash of its copy of the battle. The server compares
+it against its own and resyncs the client if they differ. See Battle::get_state_hash() */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TurnHash {
+    /// The turn the battle is on once the turn resolved. See Battle::get_turn()
+    pub turn: u32,
+    pub hash: [u8; 32]
+}
+
+impl Encode for TurnHash {
+    fn encode_into(&self, buffer: &mut BytesMut) {
+        buffer.put_u32_le(self.turn);
+        buffer.put_slice(&self.hash);
+    }
+}
+
+impl TurnHash {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        return self.encode_to_vec();
+    }
+
+    /// Decode a turn hash, or None if the bytes are not a valid turn hash.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::state_hash::TurnHash;
+    ///
+    /// let hash = TurnHash { turn: 4, hash: [7; 32] };
+    /// assert_eq!(TurnHash::from_bytes(&hash.to_bytes()), Some(hash));
+    /// assert_eq!(TurnHash::from_bytes(&hash.to_bytes()[1..]), None);
+    /// ```
+    pub fn from_bytes(bytes: &[u8]) -> Option<TurnHash> {
+        if bytes.len() != 36 {
+            return None;
+        }
+        return Some(TurnHash { turn: u32::from_le_bytes(bytes[..4].try_into().unwrap()), hash: bytes[4..].try_into().unwrap() });
+    }
+}
+
+
+// The byte encoding of battle state that Battle::get_state_hash() hashes. Numbers are little endian, with usizes widened
+// to u64 so every platform agrees. Names are u32 length prefixed and elements and statuses are written as their names,
+// never as Debug output, whose formatting isn't stable. Options are a flag byte followed by the value if there is one.
+
+pub(crate) fn put_usize(buffer: &mut BytesMut, value: usize) {
+    buffer.put_u64_le(value as u64);
+}
+
+pub(crate) fn put_flag(buffer: &mut BytesMut, flag: bool) {
+    buffer.put_u8(flag as u8);
+}
+
+pub(crate) fn put_str(buffer: &mut BytesMut, string: &str) {
+    buffer.put_u32_le(string.len() as u32);
+    buffer.put_slice(string.as_bytes());
+}
+
+pub(crate) fn put_name(buffer: &mut BytesMut, name: GlobalString) {
+    name.with_str(|name| put_str(buffer, name));
+}
+
+pub(crate) fn put_option<T>(buffer: &mut BytesMut, value: Option<T>, put: impl FnOnce(&mut BytesMut, T)) {
+    put_flag(buffer, value.is_some());
+    if let Some(value) = value {
+        put(buffer, value);
+    }
+}
+
+pub(crate) fn put_element(buffer: &mut BytesMut, element: ElementKind) {
+    put_str(buffer, element.get_name());
+}
+
+pub(crate) fn put_elements(buffer: &mut BytesMut, elements: &Elements) {
+    let elements = elements.get_elements();
+    buffer.put_u8(elements.len() as u8);
+    for element in elements {
+        put_element(buffer, element);
+    }
+}
+
+pub(crate) fn put_stats(buffer: &mut BytesMut, stats: &BaseStats) {
+    for stat in [stats.health, stats.attack, stats.defense, stats.speed] {
+        buffer.put_u32_le(stat);
+    }
+}
+
+pub(crate) fn put_battler_id(buffer: &mut BytesMut, battler: BattlerId) {
+    put_usize(buffer, battler.side);
+    put_usize(buffer, battler.slot);
+}
+
+pub(crate) fn put_format(buffer: &mut BytesMut, format: BattleFormat) {
+    match format {
+        BattleFormat::Single => buffer.put_u8(0),
+        BattleFormat::FreeForAll { participants } => {
+            buffer.put_u8(1);
+            buffer.put_u32_le(participants);
+        },
+        BattleFormat::Raid { players } => {
+            buffer.put_u8(2);
+            buffer.put_u32_le(players);
+        }
+    }
+}
+
+pub(crate) fn put_immie(buffer: &mut BytesMut, immie: &Immie) {
+    put_name(buffer, immie.species);
+    buffer.put_u32_le(immie.level);
+    buffer.put_u32_le(immie.abilities.get_count());
+    for ability in immie.abilities.iter() {
+        put_name(buffer, ability);
+    }
+    put_option(buffer, immie.held_item, put_name);
+    put_option(buffer, immie.passive, put_name);
+    buffer.put_u32_le(immie.damage_taken);
+    put_option(buffer, immie.status, |buffer, status| put_str(buffer, status.get_name()));
+    for uses in immie.ability_uses_spent {
+        buffer.put_u32_le(uses);
+    }
+    buffer.put_u32_le(immie.bond);
+    put_option(buffer, immie.form, put_name);
+    let values = immie.individual_values;
+    buffer.put_slice(&[values.health, values.attack, values.defense, values.speed]);
+    put_flag(buffer, immie.is_locked);
+    put_option(buffer, immie.origin, put_name);
+    put_option(buffer, immie.attunement, put_element);
+}
@@ -0,0 +1,93 @@
+use crate::engine_types::game_rng::GameRng;
+use crate::gameplay::battle::{battle_event::BattleEvent, battler::Battler, battler_id::BattlerId};
+use crate::gameplay::game_rules::GameRules;
+
+use super::capture_device::CaptureDevice;
+
+/* The result of throwing a capture device, computed up front so the client can animate each shake. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CaptureAttempt {
+    pub shakes: u32,
+    pub is_critical: bool,
+    pub is_success: bool
+}
+
+impl CaptureAttempt {
+    /// Roll a capture attempt against a battler. Lower health and a higher species catch rate (max 255) make captures easier.
+    /// ```
+    /// use immie2d_shared::engine_types::{game_rng::GameRng, global_string::GlobalString};
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::battle::battler::Battler;
+    /// use immie2d_shared::gameplay::capture::capture_attempt::CaptureAttempt;
+    /// use immie2d_shared::gameplay::game_rules::GameRules;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let target = Battler::new(Immie::new(species.name, 5, AbilityNames::default()), &species);
+    /// let rules = GameRules::default();
+    ///
+    /// let master_orb = rules.get_capture_device(GlobalString::new(&"master orb".to_string())).unwrap();
+    /// let attempt = CaptureAttempt::roll(&rules, master_orb, &target, species.catch_rate, &mut GameRng::new(0));
+    /// assert!(attempt.is_success);
+    /// assert_eq!(attempt.shakes, 3);
+    ///
+    /// // The same seed always produces the same attempt
+    /// let orb = rules.get_capture_device(GlobalString::new(&"capture orb".to_string())).unwrap();
+    /// let first = CaptureAttempt::roll(&rules, orb, &target, species.catch_rate, &mut GameRng::new(42));
+    /// let second = CaptureAttempt::roll(&rules, orb, &target, species.catch_rate, &mut GameRng::new(42));
+    /// assert_eq!(first, second);
+    /// ```
+    pub fn roll(rules: &GameRules, device: &CaptureDevice, target: &Battler, catch_rate: u32, rng: &mut GameRng) -> CaptureAttempt {
+        let shake_checks = rules.capture_shake_checks;
+        if device.is_guaranteed {
+            return CaptureAttempt { shakes: shake_checks, is_critical: false, is_success: true };
+        }
+
+        let max_health = target.get_stats().health.max(1) as f32;
+        let health = target.get_health() as f32;
+        let modified_rate = ((3.0 * max_health - 2.0 * health) * catch_rate as f32 * device.catch_rate_multiplier) / (3.0 * max_health);
+        if modified_rate >= 255.0 {
+            return CaptureAttempt { shakes: shake_checks, is_critical: false, is_success: true };
+        }
+
+        let is_critical = rng.chance(rules.critical_capture_chance);
+        let required_checks = if is_critical { 1 } else { shake_checks };
+        // Probability of passing each individual shake check, such that passing all of them is modified_rate / 255.
+        let shake_probability = (modified_rate.max(1.0) / 255.0).powf(1.0 / shake_checks as f32);
+        let mut shakes = 0;
+        while shakes < required_checks {
+            if !rng.chance(shake_probability) {
+                return CaptureAttempt { shakes, is_critical, is_success: false };
+            }
+            shakes += 1;
+        }
+        return CaptureAttempt { shakes, is_critical, is_success: true };
+    }
+
+    /// The events for the client to animate this attempt against a battler.
+    /// ```
+    /// use immie2d_shared::gameplay::battle::{battle_event::BattleEvent, battler_id::BattlerId};
+    /// use immie2d_shared::gameplay::capture::capture_attempt::CaptureAttempt;
+    ///
+    /// let battler = BattlerId::new(1, 0);
+    /// let attempt = CaptureAttempt { shakes: 1, is_critical: false, is_success: false };
+    /// assert_eq!(attempt.to_events(battler), vec![BattleEvent::CaptureShake { battler, shake: 1 }, BattleEvent::CaptureFailed { battler }]);
+    /// ```
+    pub fn to_events(&self, battler: BattlerId) -> Vec<BattleEvent> {
+        let mut events: Vec<BattleEvent> = Vec::new();
+        if self.is_critical {
+            events.push(BattleEvent::CriticalCapture { battler });
+        }
+        for shake in 1..=self.shakes {
+            events.push(BattleEvent::CaptureShake { battler, shake });
+        }
+        if self.is_success {
+            events.push(BattleEvent::Captured { battler });
+        } else {
+            events.push(BattleEvent::CaptureFailed { battler });
+        }
+        return events;
+    }
+}
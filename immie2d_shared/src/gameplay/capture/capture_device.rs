@@ -0,0 +1,20 @@
+use crate::engine_types::global_string::GlobalString;
+
+/* A tier of capture item. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct CaptureDevice {
+    pub name: GlobalString,
+    pub catch_rate_multiplier: f32,
+    /// Captures always succeed without any shake checks.
+    pub is_guaranteed: bool
+}
+
+impl CaptureDevice {
+    pub fn new(name: GlobalString, catch_rate_multiplier: f32) -> CaptureDevice {
+        return CaptureDevice { name, catch_rate_multiplier, is_guaranteed: false };
+    }
+
+    pub fn guaranteed(name: GlobalString) -> CaptureDevice {
+        return CaptureDevice { name, catch_rate_multiplier: 1.0, is_guaranteed: true };
+    }
+}
@@ -0,0 +1,2 @@
+pub mod capture_device;
+pub mod capture_attempt;
@@ -0,0 +1,231 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::elements::element_kinds::ElementKind;
+use crate::gameplay::elements::elements_data::Elements;
+
+/// How many challenges of each period are active at once.
+pub const ACTIVE_CHALLENGES_PER_PERIOD: usize = 3;
+
+const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+
+/* How often a challenge rotates. Periods start at midnight UTC, and weeks start on the unix epoch's weekday. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ChallengePeriod {
+    Daily,
+    Weekly
+}
+
+impl ChallengePeriod {
+    pub fn get_length_seconds(&self) -> u64 {
+        return match self {
+            ChallengePeriod::Daily => SECONDS_PER_DAY,
+            ChallengePeriod::Weekly => SECONDS_PER_DAY * 7
+        };
+    }
+
+    /// Which period a unix timestamp falls in, counting from the unix epoch.
+    /// ```
+    /// use immie2d_shared::gameplay::challenge::challenge_data::ChallengePeriod;
+    ///
+    /// assert_eq!(ChallengePeriod::Daily.get_index(86399), 0);
+    /// assert_eq!(ChallengePeriod::Daily.get_index(86400), 1);
+    /// assert_eq!(ChallengePeriod::Weekly.get_index(86400 * 13), 1);
+    /// ```
+    pub fn get_index(&self, unix_seconds: u64) -> u64 {
+        return unix_seconds / self.get_length_seconds();
+    }
+}
+
+/* Something a player must do to complete a challenge. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ChallengeObjective {
+    /// Win battles. If there is an element, every Immie on the player's team must have it.
+    WinBattles { count: u32, element: Option<ElementKind> },
+    CatchWild { count: u32 }
+}
+
+impl ChallengeObjective {
+    pub fn get_count(&self) -> u32 {
+        return match self {
+            ChallengeObjective::WinBattles { count, .. } | ChallengeObjective::CatchWild { count } => *count
+        };
+    }
+
+    /// Whether an event counts towards the objective.
+    /// ```
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::challenge::challenge_data::{ChallengeObjective, ChallengeEvent};
+    ///
+    /// let objective = ChallengeObjective::WinBattles { count: 3, element: Some(ElementKind::Fire) };
+    /// let fire = Elements::new(vec![ElementKind::Fire, ElementKind::Dragon]);
+    /// let water = Elements::new(vec![ElementKind::Water]);
+    /// assert!(objective.is_progressed_by(&ChallengeEvent::BattleWon { team: vec![fire, fire] }));
+    /// assert!(!objective.is_progressed_by(&ChallengeEvent::BattleWon { team: vec![fire, water] }));
+    /// assert!(!objective.is_progressed_by(&ChallengeEvent::WildCaught));
+    /// ```
+    pub fn is_progressed_by(&self, event: &ChallengeEvent) -> bool {
+        return match (self, event) {
+            (ChallengeObjective::WinBattles { element: None, .. }, ChallengeEvent::BattleWon { .. }) => true,
+            (ChallengeObjective::WinBattles { element: Some(element), .. }, ChallengeEvent::BattleWon { team }) => {
+                !team.is_empty() && team.iter().all(|elements| elements.has_elements(*element))
+            },
+            (ChallengeObjective::CatchWild { .. }, ChallengeEvent::WildCaught) => true,
+            _ => false
+        };
+    }
+}
+
+/* Something a player did that may progress their challenges. The server reports these as they happen. */
+#[derive(Clone, Debug)]
+pub enum ChallengeEvent {
+    /// The player won a battle. Contains the elements of each Immie on their team.
+    BattleWon { team: Vec<Elements> },
+    WildCaught
+}
+
+/* A challenge as defined in the challenges data file. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChallengeData {
+    pub name: GlobalString,
+    pub period: ChallengePeriod,
+    pub objective: ChallengeObjective,
+    /// Items added to the player's inventory on completion.
+    pub rewards: Vec<(GlobalString, u32)>
+}
+
+/* Why the challenges data file could not be loaded. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ChallengeLoadError {
+    Io(String),
+    Parse(String),
+    /// The file is well formed JSON but a challenge is not valid. Includes the reason.
+    Invalid(String)
+}
+
+impl fmt::Display for ChallengeLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ChallengeLoadError::Io(message) => write!(f, "Failed to read challenges: {}", message),
+            ChallengeLoadError::Parse(message) => write!(f, "Failed to parse challenges: {}", message),
+            ChallengeLoadError::Invalid(message) => write!(f, "Invalid challenges: {}", message)
+        };
+    }
+}
+
+/* Every challenge that can be rotated in, in data file order. The active challenges of a period are chosen the same
+way on the client and server, so only progress needs to be synced. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChallengeCatalog {
+    challenges: Vec<ChallengeData>
+}
+
+impl ChallengeCatalog {
+    pub fn new() -> ChallengeCatalog {
+        return ChallengeCatalog { challenges: Vec::new() };
+    }
+
+    /// Load challenges from a JSON array.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::elements::element_kinds::ElementKind;
+    /// use immie2d_shared::gameplay::challenge::challenge_data::{ChallengeCatalog, ChallengeLoadError, ChallengeObjective, ChallengePeriod};
+    ///
+    /// let catalog = ChallengeCatalog::from_json(r#"[
+    ///     { "name": "fire_wins", "period": "daily", "objective": { "kind": "win_battles", "count": 3, "element": "fire" }, "rewards": { "potion": 2 } },
+    ///     { "name": "catcher", "period": "weekly", "objective": { "kind": "catch_wild", "count": 5 }, "rewards": {} }
+    /// ]"#).unwrap();
+    /// let fire_wins = catalog.get_challenge(GlobalString::new(&"fire_wins".to_string())).unwrap();
+    /// assert_eq!(fire_wins.period, ChallengePeriod::Daily);
+    /// assert_eq!(fire_wins.objective, ChallengeObjective::WinBattles { count: 3, element: Some(ElementKind::Fire) });
+    /// assert_eq!(fire_wins.rewards, vec![(GlobalString::new(&"potion".to_string()), 2)]);
+    ///
+    /// let invalid = ChallengeCatalog::from_json(r#"[{ "name": "a", "period": "monthly", "objective": { "kind": "catch_wild", "count": 1 }, "rewards": {} }]"#);
+    /// assert!(matches!(invalid, Err(ChallengeLoadError::Invalid(_))));
+    /// ```
+    pub fn from_json(json: &str) -> Result<ChallengeCatalog, ChallengeLoadError> {
+        let root: Value = serde_json::from_str(json).map_err(|err| ChallengeLoadError::Parse(err.to_string()))?;
+        let array = root.as_array().ok_or(ChallengeLoadError::Invalid("Expected an array of challenges".to_string()))?;
+        let mut catalog = ChallengeCatalog::new();
+        for challenge in array {
+            let challenge = parse_challenge(challenge)?;
+            if catalog.get_challenge(challenge.name).is_some() {
+                return Err(ChallengeLoadError::Invalid(format!("Challenge {} is defined more than once", challenge.name)));
+            }
+            catalog.challenges.push(challenge);
+        }
+        return Ok(catalog);
+    }
+
+    pub fn load(path: &Path) -> Result<ChallengeCatalog, ChallengeLoadError> {
+        let text = fs::read_to_string(path).map_err(|err| ChallengeLoadError::Io(err.to_string()))?;
+        return ChallengeCatalog::from_json(&text);
+    }
+
+    pub fn get_challenge(&self, name: GlobalString) -> Option<&ChallengeData> {
+        return self.challenges.iter().find(|challenge| challenge.name == name);
+    }
+
+    /// The challenges of a period active at a unix timestamp. Each period moves the window of
+    /// ACTIVE_CHALLENGES_PER_PERIOD challenges along the challenges of that period, wrapping around.
+    /// ```
+    /// use immie2d_shared::gameplay::challenge::challenge_data::{ChallengeCatalog, ChallengePeriod};
+    ///
+    /// let json: Vec<String> = (0..4).map(|i| format!(r#"{{ "name": "c{}", "period": "daily", "objective": {{ "kind": "catch_wild", "count": 1 }}, "rewards": {{}} }}"#, i)).collect();
+    /// let catalog = ChallengeCatalog::from_json(&format!("[{}]", json.join(","))).unwrap();
+    /// let names = |seconds: u64| catalog.get_active(ChallengePeriod::Daily, seconds).iter().map(|c| c.name.to_string()).collect::<Vec<String>>();
+    /// assert_eq!(names(0), vec!["c0", "c1", "c2"]);
+    /// assert_eq!(names(86400), vec!["c3", "c0", "c1"]);
+    /// assert!(catalog.get_active(ChallengePeriod::Weekly, 0).is_empty());
+    /// ```
+    pub fn get_active(&self, period: ChallengePeriod, unix_seconds: u64) -> Vec<&ChallengeData> {
+        let pool: Vec<&ChallengeData> = self.challenges.iter().filter(|challenge| challenge.period == period).collect();
+        if pool.is_empty() {
+            return Vec::new();
+        }
+        let count = ACTIVE_CHALLENGES_PER_PERIOD.min(pool.len());
+        let start = (period.get_index(unix_seconds) as usize * count) % pool.len();
+        return (0..count).map(|i| pool[(start + i) % pool.len()]).collect();
+    }
+
+    /// Every active challenge of every period.
+    pub fn get_all_active(&self, unix_seconds: u64) -> Vec<&ChallengeData> {
+        let mut active = self.get_active(ChallengePeriod::Daily, unix_seconds);
+        active.extend(self.get_active(ChallengePeriod::Weekly, unix_seconds));
+        return active;
+    }
+}
+
+fn parse_challenge(json: &Value) -> Result<ChallengeData, ChallengeLoadError> {
+    let name = json["name"].as_str().ok_or(ChallengeLoadError::Invalid("Challenge is missing a name".to_string()))?;
+    let invalid = |reason: &str| ChallengeLoadError::Invalid(format!("Challenge [{}] {}", name, reason));
+    let period = match json["period"].as_str() {
+        Some("daily") => ChallengePeriod::Daily,
+        Some("weekly") => ChallengePeriod::Weekly,
+        _ => return Err(invalid("has an unknown period"))
+    };
+    let objective = &json["objective"];
+    let count = objective["count"].as_u64().filter(|count| *count > 0 && *count <= u32::MAX as u64).ok_or(invalid("needs a count above 0"))? as u32;
+    let objective = match objective["kind"].as_str() {
+        Some("win_battles") => {
+            let element = match objective.get("element") {
+                None => None,
+                Some(element) => Some(element.as_str().and_then(ElementKind::from_name).ok_or(invalid("has an unknown element"))?)
+            };
+            ChallengeObjective::WinBattles { count, element }
+        },
+        Some("catch_wild") => ChallengeObjective::CatchWild { count },
+        _ => return Err(invalid("has an unknown objective kind"))
+    };
+    let rewards = json["rewards"].as_object().ok_or(invalid("needs an object of reward items to counts"))?;
+    let mut reward_items = Vec::new();
+    for (item, count) in rewards {
+        let count = count.as_u64().filter(|count| *count > 0 && *count <= u32::MAX as u64).ok_or(invalid("has an invalid reward count"))?;
+        reward_items.push((GlobalString::new(item), count as u32));
+    }
+    return Ok(ChallengeData { name: GlobalString::new(&name.to_string()), period, objective, rewards: reward_items });
+}
@@ -0,0 +1,155 @@
+use std::collections::HashMap;
+
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::item::inventory::Inventory;
+
+use super::challenge_data::{ChallengeCatalog, ChallengeEvent};
+
+/* A player's progress on one challenge, during the period it was last progressed in. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChallengeEntry {
+    pub period_index: u64,
+    pub progress: u32,
+    pub is_completed: bool
+}
+
+/* Sent to a client when a challenge progresses, so it can update the challenge list without refetching it. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ChallengeUpdate {
+    pub challenge: GlobalString,
+    pub progress: u32,
+    pub is_completed: bool
+}
+
+impl ChallengeUpdate {
+    /// Encode as the progress, the completed flag, then the length prefixed challenge name.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::challenge::challenge_progress::ChallengeUpdate;
+    ///
+    /// let update = ChallengeUpdate { challenge: GlobalString::new(&"catcher".to_string()), progress: 4, is_completed: false };
+    /// assert_eq!(ChallengeUpdate::from_bytes(&update.to_bytes()), Some(update));
+    /// assert_eq!(ChallengeUpdate::from_bytes(&update.to_bytes()[..6]), None);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let challenge = self.challenge.to_string();
+        let mut bytes = Vec::with_capacity(7 + challenge.len());
+        bytes.extend_from_slice(&self.progress.to_le_bytes());
+        bytes.push(self.is_completed as u8);
+        bytes.extend_from_slice(&(challenge.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(challenge.as_bytes());
+        return bytes;
+    }
+
+    /// Decode an update, or None if the bytes are not a valid update.
+    pub fn from_bytes(bytes: &[u8]) -> Option<ChallengeUpdate> {
+        let progress = u32::from_le_bytes(bytes.get(0..4)?.try_into().unwrap());
+        let is_completed = *bytes.get(4)? != 0;
+        let length = u16::from_le_bytes([*bytes.get(5)?, *bytes.get(6)?]) as usize;
+        let challenge = std::str::from_utf8(bytes.get(7..7 + length)?).ok()?;
+        return Some(ChallengeUpdate { challenge: GlobalString::new(&challenge.to_string()), progress, is_completed });
+    }
+}
+
+/* A player's progress on their challenges. Progress from an earlier period is ignored, so challenges reset when
+they rotate back in. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct ChallengeProgress {
+    entries: HashMap<GlobalString, ChallengeEntry>
+}
+
+impl ChallengeProgress {
+    pub fn new() -> ChallengeProgress {
+        return ChallengeProgress { entries: HashMap::new() };
+    }
+
+    /// Progress on a challenge during a period.
+    pub fn get_progress(&self, challenge: GlobalString, period_index: u64) -> u32 {
+        return match self.entries.get(&challenge) {
+            Some(entry) if entry.period_index == period_index => entry.progress,
+            _ => 0
+        };
+    }
+
+    pub fn is_completed(&self, challenge: GlobalString, period_index: u64) -> bool {
+        return self.entries.get(&challenge).is_some_and(|entry| entry.period_index == period_index && entry.is_completed);
+    }
+
+    /// Record an event against every active challenge it progresses. Completed challenges add their rewards to the
+    /// inventory. Returns the updates to send to the client.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::item::inventory::Inventory;
+    /// use immie2d_shared::gameplay::challenge::challenge_data::{ChallengeCatalog, ChallengeEvent, ChallengePeriod};
+    /// use immie2d_shared::gameplay::challenge::challenge_progress::ChallengeProgress;
+    ///
+    /// let catalog = ChallengeCatalog::from_json(r#"[
+    ///     { "name": "catcher", "period": "daily", "objective": { "kind": "catch_wild", "count": 2 }, "rewards": { "potion": 3 } }
+    /// ]"#).unwrap();
+    /// let catcher = GlobalString::new(&"catcher".to_string());
+    /// let potion = GlobalString::new(&"potion".to_string());
+    /// let mut progress = ChallengeProgress::new();
+    /// let mut inventory = Inventory::new();
+    ///
+    /// assert_eq!(progress.record(&catalog, &ChallengeEvent::WildCaught, 100, &mut inventory)[0].progress, 1);
+    /// assert!(progress.record(&catalog, &ChallengeEvent::BattleWon { team: Vec::new() }, 100, &mut inventory).is_empty());
+    /// let updates = progress.record(&catalog, &ChallengeEvent::WildCaught, 100, &mut inventory);
+    /// assert!(updates[0].is_completed);
+    /// assert_eq!(inventory.get_count(potion), 3);
+    /// // Completed challenges don't progress or reward again
+    /// assert!(progress.record(&catalog, &ChallengeEvent::WildCaught, 100, &mut inventory).is_empty());
+    /// assert_eq!(inventory.get_count(potion), 3);
+    ///
+    /// // The next day starts over
+    /// let tomorrow = 100 + ChallengePeriod::Daily.get_length_seconds();
+    /// assert_eq!(progress.get_progress(catcher, ChallengePeriod::Daily.get_index(tomorrow)), 0);
+    /// assert_eq!(progress.record(&catalog, &ChallengeEvent::WildCaught, tomorrow, &mut inventory)[0].progress, 1);
+    /// ```
+    pub fn record(&mut self, catalog: &ChallengeCatalog, event: &ChallengeEvent, unix_seconds: u64, inventory: &mut Inventory) -> Vec<ChallengeUpdate> {
+        let mut updates = Vec::new();
+        for challenge in catalog.get_all_active(unix_seconds) {
+            if !challenge.objective.is_progressed_by(event) {
+                continue;
+            }
+            let period_index = challenge.period.get_index(unix_seconds);
+            let entry = self.entries.entry(challenge.name).or_insert(ChallengeEntry { period_index, progress: 0, is_completed: false });
+            if entry.period_index != period_index {
+                *entry = ChallengeEntry { period_index, progress: 0, is_completed: false };
+            }
+            if entry.is_completed {
+                continue;
+            }
+            entry.progress += 1;
+            if entry.progress >= challenge.objective.get_count() {
+                entry.is_completed = true;
+                for (item, count) in challenge.rewards.iter() {
+                    inventory.add_item(*item, *count);
+                }
+            }
+            updates.push(ChallengeUpdate { challenge: challenge.name, progress: entry.progress, is_completed: entry.is_completed });
+        }
+        return updates;
+    }
+
+    /// The current progress of every active challenge, sent to a client when it logs in.
+    pub fn get_updates(&self, catalog: &ChallengeCatalog, unix_seconds: u64) -> Vec<ChallengeUpdate> {
+        return catalog.get_all_active(unix_seconds).iter().map(|challenge| {
+            let period_index = challenge.period.get_index(unix_seconds);
+            return ChallengeUpdate {
+                challenge: challenge.name,
+                progress: self.get_progress(challenge.name, period_index),
+                is_completed: self.is_completed(challenge.name, period_index)
+            };
+        }).collect();
+    }
+
+    /// Replace the entry of a challenge, such as when loading persisted progress.
+    pub fn insert(&mut self, challenge: GlobalString, entry: ChallengeEntry) {
+        self.entries.insert(challenge, entry);
+    }
+
+    /// Iterate over every challenge entry, including those of past periods.
+    pub fn iter(&self) -> impl Iterator<Item = (GlobalString, ChallengeEntry)> + '_ {
+        return self.entries.iter().map(|(challenge, entry)| (*challenge, *entry));
+    }
+}
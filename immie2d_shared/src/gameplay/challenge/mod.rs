@@ -0,0 +1,2 @@
+pub mod challenge_data;
+pub mod challenge_progress;
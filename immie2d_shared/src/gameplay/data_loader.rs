@@ -0,0 +1,363 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde_json::Value;
+
+use crate::engine_types::global_string::GlobalString;
+use crate::engine_types::load_graph::{LoadError, LoadGraph, LoadProgress};
+use crate::modding::data_pack::{list_map_files, load_map_file, load_ability_scripts, parse_abilities_json, parse_items_json, parse_species_forms_json, parse_species_json, DataPackError, ABILITIES_FILE, ITEMS_FILE, MAPS_DIRECTORY, SPECIES_FILE};
+use crate::world::audio_cue::AudioCueTable;
+use crate::world::cutscene_script::{CutsceneScript, CutsceneStep};
+use crate::world::tile_map::TileMap;
+
+use super::ability::ability_map::AbilityMap;
+use super::encounter::encounter_table::{load_encounter_tables, EncounterTable};
+use super::immie::breeding::{BreedingRules, InheritFrom};
+use super::item::item_map::ItemMap;
+use super::species::{learnset::Learnset, species_map::SpeciesMap};
+
+/// File in the core data directory with the learnset of each species. Like the other files, it is optional.
+pub const LEARNSETS_FILE: &str = "learnsets.json";
+pub const ENCOUNTERS_FILE: &str = "encounters.json";
+/// File in the core data directory with the breeding rules. Without it, both parents pass down any ability.
+pub const BREEDING_FILE: &str = "breeding.json";
+/// File in the core data directory with the music and ambience of regions, battles and scripted events.
+pub const AUDIO_CUES_FILE: &str = "audio_cues.json";
+/// Directory in the core data directory of cutscene scripts, each named after its file without the .rhai extension.
+pub const CUTSCENES_DIRECTORY: &str = "cutscenes";
+
+/* Everything loaded from the core data directory, ready for data packs to be installed on top of. See install_packs() */
+pub struct CoreData {
+    pub species_map: SpeciesMap,
+    pub ability_map: AbilityMap,
+    pub item_map: ItemMap,
+    pub maps: HashMap<GlobalString, TileMap>,
+    pub encounter_tables: Vec<EncounterTable>,
+    pub breeding_rules: BreedingRules,
+    pub audio_cues: AudioCueTable,
+    /// The steps of each cutscene, built once since scripts take no input.
+    pub cutscenes: HashMap<GlobalString, Vec<CutsceneStep>>
+}
+
+/* What each task has loaded so far. */
+struct LoadedData {
+    species_map: Mutex<SpeciesMap>,
+    ability_map: Mutex<AbilityMap>,
+    item_map: Mutex<ItemMap>,
+    learnsets: Mutex<Vec<(GlobalString, Learnset)>>,
+    maps: Mutex<HashMap<GlobalString, TileMap>>,
+    encounter_tables: Mutex<Vec<EncounterTable>>,
+    breeding_rules: Mutex<BreedingRules>,
+    audio_cues: Mutex<AudioCueTable>,
+    cutscenes: Mutex<HashMap<GlobalString, Vec<CutsceneStep>>>
+}
+
+/* Loads core data with a task per file, so the server's cold start only takes as long as the slowest chain of files
+rather than all of them. Abilities and species load first, then learnsets are checked against both while encounter
+tables are checked against species and breeding rules against abilities. Every map, cutscene and the audio cues
+load on their own. */
+pub struct CoreDataLoader {
+    directory: PathBuf,
+    ability_map: AbilityMap,
+    max_threads: Option<usize>
+}
+
+impl CoreDataLoader {
+    pub fn new(directory: &Path) -> CoreDataLoader {
+        return CoreDataLoader { directory: directory.to_path_buf(), ability_map: AbilityMap::new(), max_threads: None };
+    }
+
+    /// Start from abilities implemented in code, so data can use them alongside data abilities.
+    pub fn with_ability_map(mut self, ability_map: AbilityMap) -> CoreDataLoader {
+        self.ability_map = ability_map;
+        return self;
+    }
+
+    /// Limit how many files load at once. Defaults to one per core. See LoadGraph::with_max_threads()
+    pub fn with_max_threads(mut self, max_threads: usize) -> CoreDataLoader {
+        self.max_threads = Some(max_threads);
+        return self;
+    }
+
+    /// Load every file, calling progress as each one ends. Returns the errors of every file that failed, and every
+    /// file skipped because something it depends on failed. See LoadGraph::run()
+    /// ```
+    /// use std::fs;
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::engine_types::load_graph::LoadError;
+    /// use immie2d_shared::gameplay::data_loader::CoreDataLoader;
+    ///
+    /// let directory = std::env::temp_dir().join(format!("immie2d_core_data_{}", std::process::id()));
+    /// fs::create_dir_all(&directory).unwrap();
+    /// fs::write(directory.join("species.json"), r#"[{ "name": "lavapup", "elements": ["fire"], "base_stats": [50, 60, 40, 70] }]"#).unwrap();
+    /// fs::write(directory.join("abilities.json"), r#"[{ "name": "ember", "category": "attack", "elements": ["fire"], "power": 40, "max_uses": 25 }]"#).unwrap();
+    /// fs::write(directory.join("learnsets.json"), r#"{ "lavapup": { "level_up": [[1, "ember"]], "inherited": ["ember"] } }"#).unwrap();
+    /// fs::write(directory.join("encounters.json"), r#"{ "tables": [{ "name": "route 1", "entries": [{ "species": "lavapup", "levels": [2, 4], "weight": 1 }] }] }"#).unwrap();
+    /// fs::write(directory.join("breeding.json"), r#"{ "inherit_from": "same_species", "banned_inherited": ["ember"] }"#).unwrap();
+    /// fs::write(directory.join("audio_cues.json"), r#"{ "regions": { "route 1": { "music": "route_theme" } } }"#).unwrap();
+    /// fs::create_dir_all(directory.join("cutscenes")).unwrap();
+    /// fs::write(directory.join("cutscenes").join("rival_intro.rhai"), r#"fn play() { this.say("Rival", "There you are!"); }"#).unwrap();
+    ///
+    /// let mut ended = 0;
+    /// let data = CoreDataLoader::new(&directory).load(|progress| ended = progress.completed).unwrap();
+    /// assert_eq!(ended, 8);
+    /// let lavapup = GlobalString::new(&"lavapup".to_string());
+    /// assert_eq!(data.species_map.get_learnset(lavapup).unwrap().get_learn_level(GlobalString::new(&"ember".to_string())), Some(1));
+    /// assert!(data.species_map.get_learnset(lavapup).unwrap().is_inherited(GlobalString::new(&"ember".to_string())));
+    /// assert_eq!(data.encounter_tables.len(), 1);
+    /// assert!(data.breeding_rules.banned_inherited.contains(&GlobalString::new(&"ember".to_string())));
+    /// assert!(data.audio_cues.get_region_cue(GlobalString::new(&"route 1".to_string())).is_some());
+    /// assert_eq!(data.cutscenes[&GlobalString::new(&"rival_intro".to_string())].len(), 3);
+    ///
+    /// // A learnset with an unknown ability fails, and a bad species file skips everything that needs species
+    /// fs::write(directory.join("learnsets.json"), r#"{ "lavapup": { "level_up": [[1, "pyroblast"]] } }"#).unwrap();
+    /// let errors = CoreDataLoader::new(&directory).load(|_| {}).err().unwrap();
+    /// assert!(matches!(&errors[..], [LoadError::Failed { task, .. }] if task == "learnsets"));
+    /// fs::write(directory.join("species.json"), "[{}]").unwrap();
+    /// let errors = CoreDataLoader::new(&directory).load(|_| {}).err().unwrap();
+    /// assert_eq!(errors.len(), 3);
+    /// assert!(errors.contains(&LoadError::Skipped { task: "encounters".to_string(), dependency: "species".to_string() }));
+    /// fs::remove_dir_all(&directory).unwrap();
+    /// ```
+    pub fn load(self, progress: impl FnMut(LoadProgress)) -> Result<CoreData, Vec<LoadError>> {
+        let map_paths = list_map_files(&self.directory.join(MAPS_DIRECTORY))
+            .map_err(|error| vec![LoadError::Failed { task: MAPS_DIRECTORY.to_string(), message: get_message(error) }])?;
+        let cutscene_paths = list_map_files(&self.directory.join(CUTSCENES_DIRECTORY))
+            .map_err(|error| vec![LoadError::Failed { task: CUTSCENES_DIRECTORY.to_string(), message: get_message(error) }])?;
+        let loaded = LoadedData {
+            species_map: Mutex::new(SpeciesMap::new()),
+            ability_map: Mutex::new(self.ability_map),
+            item_map: Mutex::new(ItemMap::new()),
+            learnsets: Mutex::new(Vec::new()),
+            maps: Mutex::new(HashMap::new()),
+            encounter_tables: Mutex::new(Vec::new()),
+            breeding_rules: Mutex::new(BreedingRules::new()),
+            audio_cues: Mutex::new(AudioCueTable::new()),
+            cutscenes: Mutex::new(HashMap::new())
+        };
+        let directory = &self.directory;
+        let mut graph = LoadGraph::new();
+        if let Some(max_threads) = self.max_threads {
+            graph = graph.with_max_threads(max_threads);
+        }
+        graph.add_task("abilities", &[], move |loaded: &LoadedData| {
+            let Some(json) = read_optional(&directory.join(ABILITIES_FILE))? else {
+                return Ok(());
+            };
+            let abilities = parse_abilities_json(&json, None).map_err(get_message)?;
+            let mut scripts: HashMap<GlobalString, _> = load_ability_scripts(&json, None, directory).map_err(get_message)?.into_iter().collect();
+            let mut ability_map = loaded.ability_map.lock().unwrap();
+            for (name, data) in abilities {
+                match scripts.remove(&name) {
+                    Some(script) => ability_map.add_scripted_ability(&name.to_string(), data, script),
+                    None => ability_map.add_data_ability(&name.to_string(), data)
+                }
+            }
+            return Ok(());
+        });
+        graph.add_task("species", &[], move |loaded: &LoadedData| {
+            let Some(json) = read_optional(&directory.join(SPECIES_FILE))? else {
+                return Ok(());
+            };
+            let mut species_map = SpeciesMap::new();
+            for species in parse_species_json(&json, None).map_err(get_message)? {
+                species_map.add_species(species);
+            }
+            for (species, form) in parse_species_forms_json(&json, None).map_err(get_message)? {
+                species_map.add_form(species, form);
+            }
+            *loaded.species_map.lock().unwrap() = species_map;
+            return Ok(());
+        });
+        graph.add_task("items", &[], move |loaded: &LoadedData| {
+            let Some(json) = read_optional(&directory.join(ITEMS_FILE))? else {
+                return Ok(());
+            };
+            let mut item_map = ItemMap::new();
+            for item in parse_items_json(&json, None).map_err(get_message)? {
+                item_map.add_item(item);
+            }
+            *loaded.item_map.lock().unwrap() = item_map;
+            return Ok(());
+        });
+        graph.add_task("learnsets", &["abilities", "species"], move |loaded: &LoadedData| {
+            let Some(json) = read_optional(&directory.join(LEARNSETS_FILE))? else {
+                return Ok(());
+            };
+            let learnsets = parse_learnsets(&json, &loaded.species_map.lock().unwrap(), &loaded.ability_map.lock().unwrap())?;
+            *loaded.learnsets.lock().unwrap() = learnsets;
+            return Ok(());
+        });
+        graph.add_task("encounters", &["species"], move |loaded: &LoadedData| {
+            let Some(json) = read_optional(&directory.join(ENCOUNTERS_FILE))? else {
+                return Ok(());
+            };
+            let tables = load_encounter_tables(&json, &loaded.species_map.lock().unwrap()).map_err(|error| error.to_string())?;
+            *loaded.encounter_tables.lock().unwrap() = tables;
+            return Ok(());
+        });
+        graph.add_task("breeding", &["abilities"], move |loaded: &LoadedData| {
+            let Some(json) = read_optional(&directory.join(BREEDING_FILE))? else {
+                return Ok(());
+            };
+            *loaded.breeding_rules.lock().unwrap() = parse_breeding_rules(&json, &loaded.ability_map.lock().unwrap())?;
+            return Ok(());
+        });
+        graph.add_task("audio_cues", &[], move |loaded: &LoadedData| {
+            let Some(json) = read_optional(&directory.join(AUDIO_CUES_FILE))? else {
+                return Ok(());
+            };
+            *loaded.audio_cues.lock().unwrap() = AudioCueTable::from_json(&json)?;
+            return Ok(());
+        });
+        for path in map_paths {
+            let name = format!("{}/{}", MAPS_DIRECTORY, path.file_name().map(|name| name.to_string_lossy().to_string()).unwrap_or_default());
+            graph.add_task(&name, &[], move |loaded: &LoadedData| {
+                if let Some(map) = load_map_file(&path, None).map_err(get_message)? {
+                    loaded.maps.lock().unwrap().insert(map.get_name(), map);
+                }
+                return Ok(());
+            });
+        }
+        for path in cutscene_paths.into_iter().filter(|path| path.extension().is_some_and(|extension| extension == "rhai")) {
+            let name = path.file_stem().map(|name| name.to_string_lossy().to_string()).unwrap_or_default();
+            graph.add_task(&format!("{}/{}.rhai", CUTSCENES_DIRECTORY, name), &[], move |loaded: &LoadedData| {
+                let source = fs::read_to_string(&path).map_err(|err| format!("{}: {}", path.display(), err))?;
+                let steps = CutsceneScript::compile(&name, &source)?.build()?;
+                loaded.cutscenes.lock().unwrap().insert(GlobalString::new(&name), steps);
+                return Ok(());
+            });
+        }
+        graph.run(&loaded, progress)?;
+
+        let mut species_map = loaded.species_map.into_inner().unwrap();
+        for (species, learnset) in loaded.learnsets.into_inner().unwrap() {
+            species_map.set_learnset(species, learnset);
+        }
+        return Ok(CoreData {
+            species_map,
+            ability_map: loaded.ability_map.into_inner().unwrap(),
+            item_map: loaded.item_map.into_inner().unwrap(),
+            maps: loaded.maps.into_inner().unwrap(),
+            encounter_tables: loaded.encounter_tables.into_inner().unwrap(),
+            breeding_rules: loaded.breeding_rules.into_inner().unwrap(),
+            audio_cues: loaded.audio_cues.into_inner().unwrap(),
+            cutscenes: loaded.cutscenes.into_inner().unwrap()
+        });
+    }
+}
+
+/// Parse learnsets such as `{ "lavapup": { "level_up": [[1, "ember"], [20, "fireball"]], "event_only": ["pursuit"], "inherited": ["howl"] } }`,
+/// checking every species and ability exists. Every list is optional. Reports every problem at once.
+pub fn parse_learnsets(json: &str, species_map: &SpeciesMap, ability_map: &AbilityMap) -> Result<Vec<(GlobalString, Learnset)>, String> {
+    let root: Value = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    let entries = root.as_object().ok_or("Expected an object of learnsets by species")?;
+    let mut learnsets = Vec::new();
+    let mut problems = Vec::new();
+    for (species, entry) in entries.iter() {
+        let name = GlobalString::new(species);
+        if !species_map.is_species_name(name) {
+            problems.push(format!("Unknown species [{}]", species));
+            continue;
+        }
+        let mut learnset = Learnset::new();
+        let check_ability = |ability: &str| -> Result<GlobalString, String> {
+            if !ability_map.is_ability_name(ability) {
+                return Err(format!("Species [{}] learns unknown ability [{}]", species, ability));
+            }
+            return Ok(GlobalString::new(&ability.to_string()));
+        };
+        for learned in entry.get("level_up").and_then(|level_up| level_up.as_array()).map(|level_up| level_up.as_slice()).unwrap_or_default() {
+            match (learned.get(0).and_then(|level| level.as_u64()), learned.get(1).and_then(|ability| ability.as_str())) {
+                (Some(level), Some(ability)) => match check_ability(ability) {
+                    Ok(ability) => learnset = learnset.with_level_up(level as u32, ability),
+                    Err(problem) => problems.push(problem)
+                },
+                _ => problems.push(format!("Species [{}] has a level up ability that isn't [level, ability]", species))
+            }
+        }
+        for ability in entry.get("event_only").and_then(|event_only| event_only.as_array()).map(|event_only| event_only.as_slice()).unwrap_or_default() {
+            match ability.as_str() {
+                Some(ability) => match check_ability(ability) {
+                    Ok(ability) => learnset = learnset.with_event_only(ability),
+                    Err(problem) => problems.push(problem)
+                },
+                None => problems.push(format!("Species [{}] has an event only ability that isn't a name", species))
+            }
+        }
+        for ability in entry.get("inherited").and_then(|inherited| inherited.as_array()).map(|inherited| inherited.as_slice()).unwrap_or_default() {
+            match ability.as_str() {
+                Some(ability) => match check_ability(ability) {
+                    Ok(ability) => learnset = learnset.with_inherited(ability),
+                    Err(problem) => problems.push(problem)
+                },
+                None => problems.push(format!("Species [{}] has an inherited ability that isn't a name", species))
+            }
+        }
+        learnsets.push((name, learnset));
+    }
+    if !problems.is_empty() {
+        return Err(problems.join("; "));
+    }
+    return Ok(learnsets);
+}
+
+/// Parse breeding rules such as `{ "inherit_from": "same_species", "banned_inherited": ["pursuit"] }`, checking every
+/// ability exists. `inherit_from` is both_parents or same_species and defaults to both_parents, and
+/// `banned_inherited` is optional. Reports every problem at once.
+/// ```
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::gameplay::ability::{ability::Ability, ability_map::AbilityMap, abilities::fireball::Fireball};
+/// use immie2d_shared::gameplay::data_loader::parse_breeding_rules;
+/// use immie2d_shared::gameplay::immie::breeding::{BreedingRules, InheritFrom};
+///
+/// let mut ability_map = AbilityMap::new();
+/// ability_map.add_ability::<Fireball>();
+/// let rules = parse_breeding_rules(r#"{ "inherit_from": "same_species", "banned_inherited": ["fireball"] }"#, &ability_map).unwrap();
+/// assert_eq!(rules, BreedingRules::new().with_inherit_from(InheritFrom::SameSpecies).with_banned_inherited(GlobalString::new(&Fireball::static_name().to_string())));
+/// assert_eq!(parse_breeding_rules("{}", &ability_map), Ok(BreedingRules::new()));
+///
+/// let problems = parse_breeding_rules(r#"{ "inherit_from": "anyone", "banned_inherited": ["pyroblast", 3] }"#, &ability_map).unwrap_err();
+/// assert_eq!(problems, "Unknown inherit_from [anyone]; Unknown banned inherited ability [pyroblast]; A banned inherited ability isn't a name");
+/// ```
+pub fn parse_breeding_rules(json: &str, ability_map: &AbilityMap) -> Result<BreedingRules, String> {
+    let root: Value = serde_json::from_str(json).map_err(|err| err.to_string())?;
+    let entries = root.as_object().ok_or("Expected an object of breeding rules")?;
+    let mut rules = BreedingRules::new();
+    let mut problems = Vec::new();
+    match entries.get("inherit_from").map(|inherit_from| inherit_from.as_str()) {
+        None | Some(Some("both_parents")) => (),
+        Some(Some("same_species")) => rules = rules.with_inherit_from(InheritFrom::SameSpecies),
+        Some(Some(other)) => problems.push(format!("Unknown inherit_from [{}]", other)),
+        Some(None) => problems.push("inherit_from isn't a name".to_string())
+    }
+    for ability in entries.get("banned_inherited").and_then(|banned| banned.as_array()).map(|banned| banned.as_slice()).unwrap_or_default() {
+        match ability.as_str() {
+            Some(ability) if ability_map.is_ability_name(ability) => rules = rules.with_banned_inherited(GlobalString::new(&ability.to_string())),
+            Some(ability) => problems.push(format!("Unknown banned inherited ability [{}]", ability)),
+            None => problems.push("A banned inherited ability isn't a name".to_string())
+        }
+    }
+    if !problems.is_empty() {
+        return Err(problems.join("; "));
+    }
+    return Ok(rules);
+}
+
+/// Contents of a core data file, or None if it doesn't exist.
+fn read_optional(path: &Path) -> Result<Option<String>, String> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    return fs::read_to_string(path).map(Some).map_err(|err| format!("{}: {}", path.display(), err));
+}
+
+/// The problem itself, without data pack wording.
+fn get_message(error: DataPackError) -> String {
+    return match error {
+        DataPackError::Io(message) | DataPackError::Parse(message) | DataPackError::Invalid(message) => message,
+        other => other.to_string()
+    };
+}
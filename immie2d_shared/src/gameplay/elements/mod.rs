@@ -1,2 +1 @@
-pub mod elements_data;
-pub mod element_kinds;
\ No newline at end of file
+pub use immie2d_core::elements::{elements_data, element_kinds, type_chart};
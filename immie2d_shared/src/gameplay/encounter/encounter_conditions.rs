@@ -0,0 +1,122 @@
+use std::collections::HashSet;
+
+use crate::engine_types::global_string::GlobalString;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum TimeOfDay {
+    Morning,
+    Day,
+    Evening,
+    Night
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[repr(u8)]
+pub enum Weather {
+    Clear,
+    Rain,
+    Snow,
+    Sandstorm,
+    Fog
+}
+
+impl TimeOfDay {
+    /// Parse the name used in data files.
+    pub fn from_name(name: &str) -> Option<TimeOfDay> {
+        return match name {
+            "morning" => Some(TimeOfDay::Morning),
+            "day" => Some(TimeOfDay::Day),
+            "evening" => Some(TimeOfDay::Evening),
+            "night" => Some(TimeOfDay::Night),
+            _ => None
+        };
+    }
+}
+
+impl Weather {
+    /// Parse the name used in data files.
+    pub fn from_name(name: &str) -> Option<Weather> {
+        return match name {
+            "clear" => Some(Weather::Clear),
+            "rain" => Some(Weather::Rain),
+            "snow" => Some(Weather::Snow),
+            "sandstorm" => Some(Weather::Sandstorm),
+            "fog" => Some(Weather::Fog),
+            _ => None
+        };
+    }
+
+    /// Id used when sending the weather to clients.
+    pub fn get_id(&self) -> u8 {
+        return *self as u8;
+    }
+
+    /// ```
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::Weather;
+    /// assert_eq!(Weather::from_id(Weather::Fog.get_id()), Some(Weather::Fog));
+    /// assert_eq!(Weather::from_id(200), None);
+    /// ```
+    pub fn from_id(id: u8) -> Option<Weather> {
+        return match id {
+            0 => Some(Weather::Clear),
+            1 => Some(Weather::Rain),
+            2 => Some(Weather::Snow),
+            3 => Some(Weather::Sandstorm),
+            4 => Some(Weather::Fog),
+            _ => None
+        };
+    }
+}
+
+/* The state of the world and the player when rolling an encounter. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct EncounterContext {
+    pub time_of_day: TimeOfDay,
+    pub weather: Weather,
+    /// Badges and story flags the player has.
+    pub player_flags: HashSet<GlobalString>,
+    /// How many of the same species the player has encountered in a row with the radar.
+    pub radar_chain: u32,
+    /// Region the encounter happens in, used to pick the form of wild Immies. See SpeciesForm
+    pub region: Option<GlobalString>
+}
+
+impl EncounterContext {
+    pub fn new(time_of_day: TimeOfDay, weather: Weather) -> EncounterContext {
+        return EncounterContext { time_of_day, weather, player_flags: HashSet::new(), radar_chain: 0, region: None };
+    }
+}
+
+/* Conditions for an encounter entry to be possible. Empty lists allow anything. */
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct EncounterConditions {
+    /// Any of these times.
+    pub times_of_day: Vec<TimeOfDay>,
+    /// Any of these weathers.
+    pub weathers: Vec<Weather>,
+    /// Every one of these flags.
+    pub required_flags: Vec<GlobalString>,
+    pub min_radar_chain: u32
+}
+
+impl EncounterConditions {
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::{EncounterConditions, EncounterContext, TimeOfDay, Weather};
+    ///
+    /// let badge = GlobalString::new(&"badge_1".to_string());
+    /// let conditions = EncounterConditions { times_of_day: vec![TimeOfDay::Night], required_flags: vec![badge], ..Default::default() };
+    /// let mut context = EncounterContext::new(TimeOfDay::Night, Weather::Rain);
+    /// assert!(!conditions.is_met(&context));
+    /// context.player_flags.insert(badge);
+    /// assert!(conditions.is_met(&context));
+    /// context.time_of_day = TimeOfDay::Day;
+    /// assert!(!conditions.is_met(&context));
+    /// ```
+    pub fn is_met(&self, context: &EncounterContext) -> bool {
+        return (self.times_of_day.is_empty() || self.times_of_day.contains(&context.time_of_day))
+            && (self.weathers.is_empty() || self.weathers.contains(&context.weather))
+            && self.required_flags.iter().all(|flag| context.player_flags.contains(flag))
+            && context.radar_chain >= self.min_radar_chain;
+    }
+}
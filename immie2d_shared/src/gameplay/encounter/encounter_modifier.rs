@@ -0,0 +1,214 @@
+use crate::gameplay::elements::element_kinds::{ElementKind, ELEMENT_COUNT};
+use crate::gameplay::elements::elements_data::Elements;
+
+/// Most steps a modifier can have left, however many are stacked.
+pub const MAX_MODIFIER_STEPS: u32 = 1000;
+
+/// How many times more likely encounters of the lured element are.
+pub const LURE_WEIGHT_MULTIPLIER: u64 = 4;
+
+/* How an overworld item changes wild encounters while it lasts. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EncounterModifierKind {
+    /// Prevents encounters below the level of the party lead.
+    Repel,
+    /// Makes encounters with an element more likely. See LURE_WEIGHT_MULTIPLIER
+    Lure(ElementKind)
+}
+
+/* The effect of an encounter modifier item, lasting a number of steps. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EncounterModifier {
+    pub kind: EncounterModifierKind,
+    pub steps: u32
+}
+
+/* Sent to the client so it can show how many steps each modifier has left. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EncounterModifierEvent {
+    /// A modifier started or was extended, and now has steps left.
+    Started { kind: EncounterModifierKind, steps: u32 },
+    /// A modifier wore off or was replaced.
+    Expired { kind: EncounterModifierKind }
+}
+
+const REPEL_TAG: u8 = 0;
+const LURE_TAG: u8 = 1;
+
+const STARTED_TAG: u8 = 0;
+const EXPIRED_TAG: u8 = 1;
+
+impl EncounterModifierKind {
+    fn encode(&self) -> [u8; 2] {
+        return match *self {
+            EncounterModifierKind::Repel => [REPEL_TAG, 0],
+            EncounterModifierKind::Lure(element) => [LURE_TAG, element as u8]
+        };
+    }
+
+    fn decode(bytes: [u8; 2]) -> Option<EncounterModifierKind> {
+        return match bytes {
+            [REPEL_TAG, 0] => Some(EncounterModifierKind::Repel),
+            [LURE_TAG, element] if element >= 1 && element as u32 <= ELEMENT_COUNT => Some(EncounterModifierKind::Lure(ElementKind::from(element as u32))),
+            _ => None
+        };
+    }
+}
+
+impl EncounterModifierEvent {
+    /// Encode as the event tag, the modifier kind, and the steps left for Started.
+    /// ```
+    /// use immie2d_shared::gameplay::elements::element_kinds::ElementKind;
+    /// use immie2d_shared::gameplay::encounter::encounter_modifier::{EncounterModifierEvent, EncounterModifierKind};
+    ///
+    /// let started = EncounterModifierEvent::Started { kind: EncounterModifierKind::Lure(ElementKind::Water), steps: 250 };
+    /// assert_eq!(EncounterModifierEvent::from_bytes(&started.to_bytes()), Some(started));
+    /// let expired = EncounterModifierEvent::Expired { kind: EncounterModifierKind::Repel };
+    /// assert_eq!(EncounterModifierEvent::from_bytes(&expired.to_bytes()), Some(expired));
+    /// assert_eq!(EncounterModifierEvent::from_bytes(&[1, 1, 0]), None);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(7);
+        match *self {
+            EncounterModifierEvent::Started { kind, steps } => {
+                bytes.push(STARTED_TAG);
+                bytes.extend_from_slice(&kind.encode());
+                bytes.extend_from_slice(&steps.to_le_bytes());
+            },
+            EncounterModifierEvent::Expired { kind } => {
+                bytes.push(EXPIRED_TAG);
+                bytes.extend_from_slice(&kind.encode());
+            }
+        }
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<EncounterModifierEvent> {
+        let kind = EncounterModifierKind::decode(bytes.get(1..3)?.try_into().ok()?)?;
+        return match (bytes[0], &bytes[3..]) {
+            (STARTED_TAG, steps) if steps.len() == 4 => Some(EncounterModifierEvent::Started { kind, steps: u32::from_le_bytes(steps.try_into().unwrap()) }),
+            (EXPIRED_TAG, []) => Some(EncounterModifierEvent::Expired { kind }),
+            _ => None
+        };
+    }
+}
+
+/* The encounter modifiers active for a player. A repel and a lure can be active together. Using a modifier of the
+same kind as an active one adds its steps, up to MAX_MODIFIER_STEPS, while a lure of a different element replaces
+the active lure. */
+#[derive(Clone, Copy, PartialEq, Debug, Default)]
+pub struct EncounterModifiers {
+    repel_steps: u32,
+    lure: Option<(ElementKind, u32)>
+}
+
+impl EncounterModifiers {
+    pub fn new() -> EncounterModifiers {
+        return EncounterModifiers::default();
+    }
+
+    /// Start or extend a modifier. Returns the events to send to the client.
+    /// ```
+    /// use immie2d_shared::gameplay::elements::element_kinds::ElementKind;
+    /// use immie2d_shared::gameplay::encounter::encounter_modifier::{EncounterModifier, EncounterModifierEvent, EncounterModifierKind, EncounterModifiers, MAX_MODIFIER_STEPS};
+    ///
+    /// let mut modifiers = EncounterModifiers::new();
+    /// let repel = EncounterModifier { kind: EncounterModifierKind::Repel, steps: 600 };
+    /// modifiers.apply(repel);
+    /// assert_eq!(modifiers.apply(repel), vec![EncounterModifierEvent::Started { kind: EncounterModifierKind::Repel, steps: MAX_MODIFIER_STEPS }]);
+    ///
+    /// let fire = EncounterModifierKind::Lure(ElementKind::Fire);
+    /// let water = EncounterModifierKind::Lure(ElementKind::Water);
+    /// modifiers.apply(EncounterModifier { kind: fire, steps: 100 });
+    /// assert_eq!(modifiers.apply(EncounterModifier { kind: water, steps: 50 }), vec![
+    ///     EncounterModifierEvent::Expired { kind: fire },
+    ///     EncounterModifierEvent::Started { kind: water, steps: 50 }
+    /// ]);
+    /// assert_eq!(modifiers.get_steps(EncounterModifierKind::Repel), MAX_MODIFIER_STEPS);
+    /// assert_eq!(modifiers.get_steps(fire), 0);
+    /// // Steps from data are capped rather than overflowing
+    /// modifiers.apply(EncounterModifier { kind: water, steps: u32::MAX });
+    /// modifiers.apply(EncounterModifier { kind: EncounterModifierKind::Repel, steps: u32::MAX });
+    /// assert_eq!(modifiers.get_steps(water), MAX_MODIFIER_STEPS);
+    /// assert_eq!(modifiers.get_steps(EncounterModifierKind::Repel), MAX_MODIFIER_STEPS);
+    /// ```
+    pub fn apply(&mut self, modifier: EncounterModifier) -> Vec<EncounterModifierEvent> {
+        let mut events = Vec::new();
+        let steps = match modifier.kind {
+            EncounterModifierKind::Repel => {
+                self.repel_steps = self.repel_steps.saturating_add(modifier.steps).min(MAX_MODIFIER_STEPS);
+                self.repel_steps
+            },
+            EncounterModifierKind::Lure(element) => {
+                let steps = match self.lure {
+                    Some((active, steps)) if active == element => steps.saturating_add(modifier.steps).min(MAX_MODIFIER_STEPS),
+                    Some((active, _)) => {
+                        events.push(EncounterModifierEvent::Expired { kind: EncounterModifierKind::Lure(active) });
+                        modifier.steps.min(MAX_MODIFIER_STEPS)
+                    },
+                    None => modifier.steps.min(MAX_MODIFIER_STEPS)
+                };
+                self.lure = Some((element, steps));
+                steps
+            }
+        };
+        events.push(EncounterModifierEvent::Started { kind: modifier.kind, steps });
+        return events;
+    }
+
+    /// Count a step the player took, returning an event for every modifier that wore off.
+    /// ```
+    /// use immie2d_shared::gameplay::encounter::encounter_modifier::{EncounterModifier, EncounterModifierEvent, EncounterModifierKind, EncounterModifiers};
+    ///
+    /// let mut modifiers = EncounterModifiers::new();
+    /// modifiers.apply(EncounterModifier { kind: EncounterModifierKind::Repel, steps: 2 });
+    /// assert!(modifiers.step().is_empty());
+    /// assert_eq!(modifiers.step(), vec![EncounterModifierEvent::Expired { kind: EncounterModifierKind::Repel }]);
+    /// assert!(modifiers.step().is_empty());
+    /// assert!(!modifiers.is_active());
+    /// ```
+    pub fn step(&mut self) -> Vec<EncounterModifierEvent> {
+        let mut events = Vec::new();
+        if self.repel_steps > 0 {
+            self.repel_steps -= 1;
+            if self.repel_steps == 0 {
+                events.push(EncounterModifierEvent::Expired { kind: EncounterModifierKind::Repel });
+            }
+        }
+        if let Some((element, steps)) = self.lure {
+            self.lure = if steps > 1 { Some((element, steps - 1)) } else { None };
+            if self.lure.is_none() {
+                events.push(EncounterModifierEvent::Expired { kind: EncounterModifierKind::Lure(element) });
+            }
+        }
+        return events;
+    }
+
+    /// Steps left of a modifier, or 0 if it isn't active.
+    pub fn get_steps(&self, kind: EncounterModifierKind) -> u32 {
+        return match kind {
+            EncounterModifierKind::Repel => self.repel_steps,
+            EncounterModifierKind::Lure(element) => match self.lure {
+                Some((active, steps)) if active == element => steps,
+                _ => 0
+            }
+        };
+    }
+
+    pub fn is_active(&self) -> bool {
+        return self.repel_steps > 0 || self.lure.is_some();
+    }
+
+    /// Whether an encounter at a level is prevented, given the level of the party lead.
+    pub fn is_repelled(&self, level: u32, lead_level: u32) -> bool {
+        return self.repel_steps > 0 && level < lead_level;
+    }
+
+    /// Weight of an encounter entry after the active lure.
+    pub fn get_weight(&self, weight: u32, elements: Elements) -> u64 {
+        return match self.lure {
+            Some((element, _)) if elements.has_elements(element) => weight as u64 * LURE_WEIGHT_MULTIPLIER,
+            _ => weight as u64
+        };
+    }
+}
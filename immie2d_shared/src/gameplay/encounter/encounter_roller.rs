@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+
+use crate::engine_types::game_rng::GameRng;
+use crate::engine_types::global_string::GlobalString;
+use crate::engine_types::weighted_table::WeightedTable;
+use crate::gameplay::ability::ability_names::AbilityNames;
+use crate::gameplay::immie::immie::Immie;
+use crate::gameplay::immie::individual_values::{IndividualValues, MAX_INDIVIDUAL_VALUE};
+use crate::gameplay::species::species_map::SpeciesMap;
+
+use super::encounter_conditions::EncounterContext;
+use super::encounter_modifier::EncounterModifiers;
+use super::encounter_table::{EncounterEntry, EncounterTable};
+
+/* A rolled wild Immie. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WildEncounter {
+    pub species: GlobalString,
+    pub level: u32,
+    /// Form of the species, or None for the base species. See SpeciesMap::select_form()
+    pub form: Option<GlobalString>,
+    /// Rolled with the encounter, so the Immie keeps them if it's captured. They are drawn after the species and level,
+    /// so a given rng state still rolls the same species and level as before they were added, but every encounter now
+    /// takes four more draws from the rng. See IndividualValues::roll()
+    pub individual_values: IndividualValues
+}
+
+impl WildEncounter {
+    /// The wild Immie, with no abilities.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::encounter::encounter_roller::WildEncounter;
+    /// use immie2d_shared::gameplay::immie::individual_values::IndividualValues;
+    ///
+    /// let form = Some(GlobalString::new(&"tidal".to_string()));
+    /// let encounter = WildEncounter { species: GlobalString::new(&"lavapup".to_string()), level: 4, form, individual_values: IndividualValues::new(1, 2, 3, 4) };
+    /// assert_eq!(encounter.to_immie().get_sprite_id(), "lavapup-tidal");
+    /// assert_eq!(WildEncounter::from_bytes(&encounter.to_bytes()), Some(encounter));
+    /// assert_eq!(WildEncounter::from_bytes(&encounter.to_bytes()[1..]), None);
+    /// ```
+    pub fn to_immie(&self) -> Immie {
+        let mut immie = Immie::new(self.species, self.level, AbilityNames::default());
+        immie.form = self.form;
+        immie.individual_values = self.individual_values;
+        return immie;
+    }
+
+    /// Encode to send to the player who encountered it. The form is empty for the base species.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for name in [self.species.to_string(), self.form.map(|form| form.to_string()).unwrap_or_default()] {
+            bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+            bytes.extend_from_slice(name.as_bytes());
+        }
+        bytes.extend_from_slice(&self.level.to_le_bytes());
+        let values = self.individual_values;
+        bytes.extend_from_slice(&[values.health, values.attack, values.defense, values.speed]);
+        return bytes;
+    }
+
+    /// Decode an encounter from to_bytes(), or None if the bytes are not a valid encounter.
+    pub fn from_bytes(bytes: &[u8]) -> Option<WildEncounter> {
+        let mut offset = 0;
+        let take = |offset: &mut usize, count: usize| -> Option<&[u8]> {
+            let taken = bytes.get(*offset..*offset + count)?;
+            *offset += count;
+            return Some(taken);
+        };
+        let take_name = |offset: &mut usize| -> Option<String> {
+            let length = u16::from_le_bytes(take(offset, 2)?.try_into().unwrap()) as usize;
+            return Some(std::str::from_utf8(take(offset, length)?).ok()?.to_string());
+        };
+        let species = GlobalString::new(&take_name(&mut offset)?);
+        let form = Some(take_name(&mut offset)?).filter(|form| !form.is_empty()).map(|form| GlobalString::new(&form));
+        let level = u32::from_le_bytes(take(&mut offset, 4)?.try_into().unwrap());
+        let values = take(&mut offset, 4)?;
+        if offset != bytes.len() || values.iter().any(|value| *value > MAX_INDIVIDUAL_VALUE) {
+            return None;
+        }
+        let individual_values = IndividualValues::new(values[0], values[1], values[2], values[3]);
+        return Some(WildEncounter { species, level, form, individual_values });
+    }
+}
+
+/* Rolls wild encounters from every loaded encounter table. The server uses a single roller for all encounters. */
+pub struct EncounterRoller {
+    tables: HashMap<GlobalString, EncounterTable>,
+    /// Weighted tables of every entry by index, built once for rolls where every entry's conditions are met and the
+    /// weights aren't modified. None if every entry has a weight of 0.
+    unconditional: HashMap<GlobalString, Option<WeightedTable<usize>>>
+}
+
+impl EncounterRoller {
+    /// Will panic if an entry's levels aren't valid, which they always are for tables from load_encounter_tables().
+    /// See EncounterEntry::validate_levels()
+    pub fn new(tables: Vec<EncounterTable>) -> EncounterRoller {
+        for table in tables.iter() {
+            for entry in table.entries.iter() {
+                if let Err(message) = entry.validate_levels() {
+                    panic!("Encounter table {} has an entry for {} with invalid levels: {}", table.name, entry.species, message);
+                }
+            }
+        }
+        let unconditional = tables.iter().map(|table| (table.name, build_weighted_entries(table.entries.iter().enumerate(), |entry| entry.weight as u64))).collect();
+        return EncounterRoller { tables: tables.into_iter().map(|table| (table.name, table)).collect(), unconditional };
+    }
+
+    pub fn get_table(&self, name: GlobalString) -> Option<&EncounterTable> {
+        return self.tables.get(&name);
+    }
+
+    /// Roll an encounter from a table, only considering entries whose conditions are met. The entry is sampled from a
+    /// WeightedTable, taking two draws from the rng, then the level takes one. Entries used to be chosen with a single
+    /// draw, so a seed rolls different encounters than it did before weighted tables.
+    /// Returns None if the table doesn't exist or no entry is possible.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::engine_types::game_rng::GameRng;
+    /// use immie2d_shared::gameplay::encounter::encounter_table::{EncounterTable, EncounterEntry};
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::{EncounterConditions, EncounterContext, TimeOfDay, Weather};
+    /// use immie2d_shared::gameplay::encounter::encounter_roller::EncounterRoller;
+    ///
+    /// let common = GlobalString::new(&"sproutle".to_string());
+    /// let rare = GlobalString::new(&"lavapup".to_string());
+    /// let table = EncounterTable { name: GlobalString::new(&"grass".to_string()), entries: vec![
+    ///     EncounterEntry { species: common, min_level: 2, max_level: 4, weight: 9, conditions: EncounterConditions::default() },
+    ///     EncounterEntry { species: rare, min_level: 5, max_level: 5, weight: 1, conditions: EncounterConditions { times_of_day: vec![TimeOfDay::Night], ..Default::default() } }
+    /// ] };
+    /// let roller = EncounterRoller::new(vec![table.clone()]);
+    /// let mut rng = GameRng::new(3);
+    ///
+    /// let day = EncounterContext::new(TimeOfDay::Day, Weather::Clear);
+    /// for _ in 0..100 {
+    ///     let encounter = roller.roll(table.name, &day, &mut rng).unwrap();
+    ///     assert_eq!(encounter.species, common);
+    ///     assert!(encounter.level >= 2 && encounter.level <= 4);
+    /// }
+    /// let night = EncounterContext::new(TimeOfDay::Night, Weather::Clear);
+    /// let rare_count = (0..1000).filter(|_| roller.roll(table.name, &night, &mut rng).unwrap().species == rare).count();
+    /// assert!(rare_count > 50 && rare_count < 150);
+    /// assert!(roller.roll(GlobalString::new(&"cave".to_string()), &night, &mut rng).is_none());
+    ///
+    /// // Every encounter rolls its own individual values
+    /// let (first, second) = (roller.roll(table.name, &day, &mut rng).unwrap(), roller.roll(table.name, &day, &mut rng).unwrap());
+    /// assert_ne!(first.individual_values, second.individual_values);
+    /// ```
+    pub fn roll(&self, table: GlobalString, context: &EncounterContext, rng: &mut GameRng) -> Option<WildEncounter> {
+        let entries = &self.tables.get(&table)?.entries;
+        if entries.iter().all(|entry| entry.conditions.is_met(context)) {
+            let index = *self.unconditional.get(&table)?.as_ref()?.sample(rng);
+            return Some(roll_entry(&entries[index], rng));
+        }
+        return self.roll_weighted(table, context, rng, |entry| entry.weight as u64);
+    }
+
+    /// Roll an encounter like EncounterRoller::roll_with_form(), changed by a player's encounter modifiers. Repelled
+    /// encounters are rolled then discarded, so a repel doesn't make the higher level entries more likely.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// use immie2d_shared::engine_types::game_rng::GameRng;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::encounter::encounter_table::{EncounterTable, EncounterEntry};
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::{EncounterConditions, EncounterContext, TimeOfDay, Weather};
+    /// use immie2d_shared::gameplay::encounter::encounter_modifier::{EncounterModifier, EncounterModifierKind, EncounterModifiers};
+    /// use immie2d_shared::gameplay::encounter::encounter_roller::EncounterRoller;
+    ///
+    /// let sproutle = SpeciesData::new(GlobalString::new(&"sproutle".to_string()), Elements::new(vec![ElementKind::Nature]), BaseStats::new(50, 50, 50, 50));
+    /// let lavapup = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(sproutle);
+    /// species_map.add_species(lavapup);
+    /// let table = EncounterTable { name: GlobalString::new(&"grass".to_string()), entries: vec![
+    ///     EncounterEntry { species: sproutle.name, min_level: 2, max_level: 4, weight: 9, conditions: EncounterConditions::default() },
+    ///     EncounterEntry { species: lavapup.name, min_level: 10, max_level: 10, weight: 1, conditions: EncounterConditions::default() }
+    /// ] };
+    /// let roller = EncounterRoller::new(vec![table.clone()]);
+    /// let context = EncounterContext::new(TimeOfDay::Day, Weather::Clear);
+    /// let mut rng = GameRng::new(9);
+    ///
+    /// let mut modifiers = EncounterModifiers::new();
+    /// modifiers.apply(EncounterModifier { kind: EncounterModifierKind::Repel, steps: 100 });
+    /// for _ in 0..100 {
+    ///     if let Some(encounter) = roller.roll_modified(table.name, &context, &modifiers, 8, &species_map, &mut rng) {
+    ///         assert_eq!(encounter.species, lavapup.name);
+    ///     }
+    /// }
+    ///
+    /// let mut modifiers = EncounterModifiers::new();
+    /// modifiers.apply(EncounterModifier { kind: EncounterModifierKind::Lure(ElementKind::Fire), steps: 100 });
+    /// let lured = (0..1000).filter(|_| roller.roll_modified(table.name, &context, &modifiers, 1, &species_map, &mut rng).unwrap().species == lavapup.name).count();
+    /// assert!(lured > 200 && lured < 400);
+    /// ```
+    pub fn roll_modified(&self, table: GlobalString, context: &EncounterContext, modifiers: &EncounterModifiers, lead_level: u32, species_map: &SpeciesMap, rng: &mut GameRng) -> Option<WildEncounter> {
+        let mut encounter = self.roll_weighted(table, context, rng, |entry| {
+            let elements = species_map.get_species(entry.species).elements;
+            return modifiers.get_weight(entry.weight, elements);
+        })?;
+        if modifiers.is_repelled(encounter.level, lead_level) {
+            return None;
+        }
+        encounter.form = species_map.select_form(encounter.species, context);
+        return Some(encounter);
+    }
+
+    fn roll_weighted<F: Fn(&EncounterEntry) -> u64>(&self, table: GlobalString, context: &EncounterContext, rng: &mut GameRng, get_weight: F) -> Option<WildEncounter> {
+        let table = self.tables.get(&table)?;
+        let possible = build_weighted_entries(table.entries.iter().enumerate().filter(|(_, entry)| entry.conditions.is_met(context)), get_weight)?;
+        return Some(roll_entry(&table.entries[*possible.sample(rng)], rng));
+    }
+
+    /// Roll an encounter like EncounterRoller::roll(), then pick the form of the rolled species for the context.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_form::SpeciesForm, species_map::SpeciesMap, base_stats::BaseStats};
+    /// use immie2d_shared::engine_types::game_rng::GameRng;
+    /// use immie2d_shared::gameplay::encounter::encounter_table::{EncounterTable, EncounterEntry};
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::{EncounterConditions, EncounterContext, TimeOfDay, Weather};
+    /// use immie2d_shared::gameplay::encounter::encounter_roller::EncounterRoller;
+    ///
+    /// let lavapup = GlobalString::new(&"lavapup".to_string());
+    /// let tundra = GlobalString::new(&"tundra".to_string());
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut form = SpeciesForm::new(tundra);
+    /// form.conditions = EncounterConditions { weathers: vec![Weather::Snow], ..Default::default() };
+    /// species_map.add_form(lavapup, form);
+    ///
+    /// let table = EncounterTable { name: GlobalString::new(&"grass".to_string()), entries: vec![
+    ///     EncounterEntry { species: lavapup, min_level: 5, max_level: 5, weight: 1, conditions: EncounterConditions::default() }
+    /// ] };
+    /// let roller = EncounterRoller::new(vec![table.clone()]);
+    /// let mut rng = GameRng::new(3);
+    /// let clear = EncounterContext::new(TimeOfDay::Day, Weather::Clear);
+    /// assert_eq!(roller.roll_with_form(table.name, &clear, &species_map, &mut rng).unwrap().form, None);
+    /// let snow = EncounterContext::new(TimeOfDay::Day, Weather::Snow);
+    /// assert_eq!(roller.roll_with_form(table.name, &snow, &species_map, &mut rng).unwrap().form, Some(tundra));
+    /// ```
+    pub fn roll_with_form(&self, table: GlobalString, context: &EncounterContext, species_map: &SpeciesMap, rng: &mut GameRng) -> Option<WildEncounter> {
+        let mut encounter = self.roll(table, context, rng)?;
+        encounter.form = species_map.select_form(encounter.species, context);
+        return Some(encounter);
+    }
+}
+
+/// A weighted table of entry indices, or None if every weight is 0.
+fn build_weighted_entries<'a, I, F>(entries: I, get_weight: F) -> Option<WeightedTable<usize>>
+where
+    I: Iterator<Item = (usize, &'a EncounterEntry)>,
+    F: Fn(&EncounterEntry) -> u64
+{
+    return WeightedTable::new(entries.map(|(index, entry)| (index, get_weight(entry))).collect());
+}
+
+/// Roll the level and individual values of an Immie from a chosen entry.
+fn roll_entry(entry: &EncounterEntry, rng: &mut GameRng) -> WildEncounter {
+    let level = entry.min_level + rng.next_below(entry.max_level - entry.min_level + 1);
+    return WildEncounter { species: entry.species, level, form: None, individual_values: IndividualValues::roll(rng) };
+}
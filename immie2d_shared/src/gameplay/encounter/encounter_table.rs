@@ -0,0 +1,179 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::species::species_map::SpeciesMap;
+
+use super::encounter_conditions::{EncounterConditions, TimeOfDay, Weather};
+
+/* A possible wild encounter. The chance of an entry is its weight divided by the total weight of every entry
+whose conditions are met. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EncounterEntry {
+    pub species: GlobalString,
+    pub min_level: u32,
+    pub max_level: u32,
+    pub weight: u32,
+    pub conditions: EncounterConditions
+}
+
+impl EncounterEntry {
+    /// Check the levels are an inclusive range starting at 1 or above, so rolling a level in it can't overflow.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::encounter::{encounter_table::EncounterEntry, encounter_conditions::EncounterConditions};
+    ///
+    /// let entry = |min_level, max_level| EncounterEntry { species: GlobalString::new(&"lavapup".to_string()), min_level, max_level, weight: 1, conditions: EncounterConditions::default() };
+    /// assert!(entry(2, 4).validate_levels().is_ok());
+    /// assert!(entry(1, u32::MAX).validate_levels().is_ok());
+    /// assert!(entry(4, 2).validate_levels().is_err());
+    /// assert!(entry(0, u32::MAX).validate_levels().is_err());
+    /// ```
+    pub fn validate_levels(&self) -> Result<(), String> {
+        if self.min_level == 0 {
+            return Err("Min level must be at least 1".to_string());
+        }
+        if self.min_level > self.max_level {
+            return Err(format!("Min level {} is above max level {}", self.min_level, self.max_level));
+        }
+        return Ok(());
+    }
+}
+
+/* Wild encounters of an area, referenced by name from encounter zones on maps. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct EncounterTable {
+    pub name: GlobalString,
+    pub entries: Vec<EncounterEntry>
+}
+
+/* Why encounter tables could not be loaded. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum EncounterTableError {
+    Io(String),
+    Parse(String),
+    /// The data is well formed but not valid, such as an unknown species. Includes where the problem is.
+    Invalid(String)
+}
+
+impl fmt::Display for EncounterTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            EncounterTableError::Io(message) => write!(f, "Failed to read encounter tables: {}", message),
+            EncounterTableError::Parse(message) => write!(f, "Failed to parse encounter tables: {}", message),
+            EncounterTableError::Invalid(message) => write!(f, "Invalid encounter tables: {}", message)
+        };
+    }
+}
+
+pub fn load_encounter_tables_file(path: &Path, species_map: &SpeciesMap) -> Result<Vec<EncounterTable>, EncounterTableError> {
+    let text = fs::read_to_string(path).map_err(|err| EncounterTableError::Io(err.to_string()))?;
+    return load_encounter_tables(&text, species_map);
+}
+
+/// Load and validate encounter tables from JSON. Levels are an inclusive range and every condition is optional.
+/// ```
+/// # use immie2d_shared::engine_types::global_string::GlobalString;
+/// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+/// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+/// use immie2d_shared::gameplay::encounter::{encounter_table::{load_encounter_tables, EncounterTableError}, encounter_conditions::TimeOfDay};
+///
+/// let mut species_map = SpeciesMap::new();
+/// species_map.add_species(SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+/// let json = r#"{ "tables": [
+///     { "name": "route 1 grass", "entries": [
+///         { "species": "lavapup", "levels": [2, 4], "weight": 30 },
+///         { "species": "lavapup", "levels": [8, 8], "weight": 1,
+///           "conditions": { "time_of_day": ["night"], "weather": ["rain"], "flags": ["badge_1"], "min_radar_chain": 5 } }
+///     ] }
+/// ] }"#;
+/// let tables = load_encounter_tables(json, &species_map).unwrap();
+/// assert_eq!(tables[0].entries[1].conditions.times_of_day, vec![TimeOfDay::Night]);
+///
+/// let unknown_species = json.replace("\"weight\": 30", "\"weight\": 30 }, { \"species\": \"missingno\", \"levels\": [1, 1], \"weight\": 1");
+/// assert!(matches!(load_encounter_tables(&unknown_species, &species_map), Err(EncounterTableError::Invalid(_))));
+/// let backwards_levels = json.replace("[2, 4]", "[4, 2]");
+/// assert!(matches!(load_encounter_tables(&backwards_levels, &species_map), Err(EncounterTableError::Invalid(_))));
+/// ```
+pub fn load_encounter_tables(json: &str, species_map: &SpeciesMap) -> Result<Vec<EncounterTable>, EncounterTableError> {
+    let root: Value = serde_json::from_str(json).map_err(|err| EncounterTableError::Parse(err.to_string()))?;
+    let tables = root.get("tables").and_then(|tables| tables.as_array()).ok_or(EncounterTableError::Invalid("Missing tables array".to_string()))?;
+    let mut loaded: Vec<EncounterTable> = Vec::new();
+    for (table_index, table) in tables.iter().enumerate() {
+        let name = table.get("name").and_then(|name| name.as_str())
+            .ok_or(EncounterTableError::Invalid(format!("Table {} is missing its name", table_index)))?;
+        let name = GlobalString::new(&name.to_string());
+        if loaded.iter().any(|existing| existing.name == name) {
+            return Err(EncounterTableError::Invalid(format!("Duplicate table {}", name)));
+        }
+        let entries = table.get("entries").and_then(|entries| entries.as_array())
+            .ok_or(EncounterTableError::Invalid(format!("Table {} is missing its entries", name)))?;
+        if entries.is_empty() {
+            return Err(EncounterTableError::Invalid(format!("Table {} has no entries", name)));
+        }
+        let mut table = EncounterTable { name, entries: Vec::new() };
+        for (entry_index, entry) in entries.iter().enumerate() {
+            let entry = parse_entry(entry, species_map).map_err(|message| EncounterTableError::Invalid(format!("Table {} entry {}: {}", name, entry_index, message)))?;
+            table.entries.push(entry);
+        }
+        loaded.push(table);
+    }
+    return Ok(loaded);
+}
+
+fn parse_entry(entry: &Value, species_map: &SpeciesMap) -> Result<EncounterEntry, String> {
+    let species = entry.get("species").and_then(|species| species.as_str()).ok_or("Missing species")?;
+    let species = GlobalString::new(&species.to_string());
+    if !species_map.is_species_name(species) {
+        return Err(format!("Unknown species {}", species));
+    }
+    let levels = entry.get("levels").and_then(|levels| levels.as_array()).ok_or("Missing levels")?;
+    let level = |index: usize| levels.get(index).and_then(|level| level.as_u64()).filter(|level| *level >= 1 && *level <= u32::MAX as u64);
+    let (min_level, max_level) = match (levels.len(), level(0), level(1)) {
+        (2, Some(min), Some(max)) => (min as u32, max as u32),
+        _ => return Err("Levels must be a pair of positive integers".to_string())
+    };
+    let weight = entry.get("weight").and_then(|weight| weight.as_u64()).filter(|weight| *weight >= 1 && *weight <= u32::MAX as u64)
+        .ok_or("Weight must be a positive integer")? as u32;
+    let conditions = match entry.get("conditions") {
+        Some(conditions) => parse_conditions(conditions)?,
+        None => EncounterConditions::default()
+    };
+    let entry = EncounterEntry { species, min_level, max_level, weight, conditions };
+    entry.validate_levels()?;
+    return Ok(entry);
+}
+
+/// Parse the conditions of an encounter entry, such as `{ "time_of_day": ["night"], "weather": ["rain"] }`. Also
+/// used for the conditions of species forms. See SpeciesForm
+pub fn parse_conditions(conditions: &Value) -> Result<EncounterConditions, String> {
+    let object = conditions.as_object().ok_or("Conditions must be an object")?;
+    let mut parsed = EncounterConditions::default();
+    for (key, value) in object.iter() {
+        let names = || -> Result<Vec<&str>, String> {
+            return value.as_array().and_then(|names| names.iter().map(|name| name.as_str()).collect::<Option<Vec<&str>>>())
+                .ok_or(format!("Condition {} must be a list of names", key));
+        };
+        match key.as_str() {
+            "time_of_day" => {
+                for name in names()? {
+                    parsed.times_of_day.push(TimeOfDay::from_name(name).ok_or(format!("Unknown time of day {}", name))?);
+                }
+            },
+            "weather" => {
+                for name in names()? {
+                    parsed.weathers.push(Weather::from_name(name).ok_or(format!("Unknown weather {}", name))?);
+                }
+            },
+            "flags" => parsed.required_flags = names()?.iter().map(|flag| GlobalString::new(&flag.to_string())).collect(),
+            "min_radar_chain" => {
+                parsed.min_radar_chain = value.as_u64().filter(|chain| *chain <= u32::MAX as u64).ok_or("min_radar_chain must be a non negative integer")? as u32;
+            },
+            _ => return Err(format!("Unknown condition {}", key))
+        }
+    }
+    return Ok(parsed);
+}
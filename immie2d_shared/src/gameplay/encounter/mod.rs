@@ -0,0 +1,4 @@
+pub mod encounter_conditions;
+pub mod encounter_table;
+pub mod encounter_roller;
+pub mod encounter_modifier;
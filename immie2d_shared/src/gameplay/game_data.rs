@@ -0,0 +1,56 @@
+use std::sync::Arc;
+
+use super::ability::ability_map::AbilityMap;
+use super::immie::breeding::BreedingRules;
+use super::item::item_map::ItemMap;
+use super::species::species_map::SpeciesMap;
+
+/* One generation of the data registries. Once created it is never modified: reloading data creates a new generation,
+and anything that must not change mid-way, such as a battle, keeps a handle to the generation it started with. */
+pub struct GameData {
+    generation: u64,
+    species: SpeciesMap,
+    abilities: AbilityMap,
+    items: ItemMap,
+    breeding_rules: BreedingRules
+}
+
+/// Shared immutable handle to a generation of game data.
+pub type GameDataHandle = Arc<GameData>;
+
+impl GameData {
+    pub fn new(generation: u64, species: SpeciesMap, abilities: AbilityMap, items: ItemMap) -> GameData {
+        return GameData { generation, species, abilities, items, breeding_rules: BreedingRules::new() };
+    }
+
+    pub fn with_breeding_rules(mut self, breeding_rules: BreedingRules) -> GameData {
+        self.breeding_rules = breeding_rules;
+        return self;
+    }
+
+    /// Wrap the data in a handle that can be shared between threads and battles.
+    pub fn into_handle(self) -> GameDataHandle {
+        return Arc::new(self);
+    }
+
+    /// Increases every time the data is reloaded.
+    pub fn get_generation(&self) -> u64 {
+        return self.generation;
+    }
+
+    pub fn get_species_map(&self) -> &SpeciesMap {
+        return &self.species;
+    }
+
+    pub fn get_ability_map(&self) -> &AbilityMap {
+        return &self.abilities;
+    }
+
+    pub fn get_item_map(&self) -> &ItemMap {
+        return &self.items;
+    }
+
+    pub fn get_breeding_rules(&self) -> &BreedingRules {
+        return &self.breeding_rules;
+    }
+}
@@ -0,0 +1,46 @@
+use crate::engine_types::global_string::GlobalString;
+
+use super::capture::capture_device::CaptureDevice;
+
+/* Tunable gameplay values shared by the server and client. */
+#[derive(Clone, Debug)]
+pub struct GameRules {
+    pub capture_devices: Vec<CaptureDevice>,
+    /// Probability of a capture attempt being a critical capture, which only needs to pass a single shake check.
+    pub critical_capture_chance: f32,
+    /// Number of shake checks a normal capture must pass to succeed.
+    pub capture_shake_checks: u32
+}
+
+impl GameRules {
+    /// The standard rules.
+    /// ```
+    /// use immie2d_shared::gameplay::game_rules::GameRules;
+    /// let rules = GameRules::default();
+    /// assert_eq!(rules.capture_shake_checks, 3);
+    /// ```
+    pub fn default() -> GameRules {
+        return GameRules {
+            capture_devices: vec![
+                CaptureDevice::new(GlobalString::new(&"capture orb".to_string()), 1.0),
+                CaptureDevice::new(GlobalString::new(&"great orb".to_string()), 1.5),
+                CaptureDevice::new(GlobalString::new(&"ultra orb".to_string()), 2.0),
+                CaptureDevice::guaranteed(GlobalString::new(&"master orb".to_string()))
+            ],
+            critical_capture_chance: 0.05,
+            capture_shake_checks: 3
+        };
+    }
+
+    /// Find a capture device by item name.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::game_rules::GameRules;
+    /// let rules = GameRules::default();
+    /// assert!(rules.get_capture_device(GlobalString::new(&"great orb".to_string())).is_some());
+    /// assert!(rules.get_capture_device(GlobalString::new(&"fireball".to_string())).is_none());
+    /// ```
+    pub fn get_capture_device(&self, item_name: GlobalString) -> Option<&CaptureDevice> {
+        return self.capture_devices.iter().find(|device| device.name == item_name);
+    }
+}
@@ -0,0 +1,74 @@
+use std::fmt;
+
+/* Rearranging the abilities of an Immie in the player's party, outside of battle. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AbilityEdit {
+    Swap { a: usize, b: usize },
+    Forget { slot: usize }
+}
+
+/* Sent by the client to edit the abilities of an Immie in its party. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AbilityEditRequest {
+    pub party_slot: usize,
+    pub edit: AbilityEdit
+}
+
+/* Why an ability edit was rejected. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AbilityEditError {
+    /// The request bytes are not a valid request.
+    Malformed,
+    InvalidPartySlot,
+    InvalidAbilitySlot,
+    /// Every Immie must keep at least one ability.
+    LastAbility,
+    /// The ability has AbilityFlags::CANNOT_FORGET
+    CannotForget,
+    /// Abilities can't be edited while the player is battling.
+    InBattle
+}
+
+impl fmt::Display for AbilityEditError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            AbilityEditError::Malformed => write!(f, "Malformed ability edit"),
+            AbilityEditError::InvalidPartySlot => write!(f, "No Immie in that party slot"),
+            AbilityEditError::InvalidAbilitySlot => write!(f, "No ability in that slot"),
+            AbilityEditError::LastAbility => write!(f, "An Immie must keep at least one ability"),
+            AbilityEditError::CannotForget => write!(f, "That ability cannot be forgotten"),
+            AbilityEditError::InBattle => write!(f, "Abilities cannot be edited during a battle")
+        };
+    }
+}
+
+const SWAP_TAG: u8 = 0;
+const FORGET_TAG: u8 = 1;
+
+impl AbilityEditRequest {
+    /// Encode as the party slot, the edit tag and one byte for each slot of the edit.
+    /// ```
+    /// use immie2d_shared::gameplay::immie::ability_edit::{AbilityEdit, AbilityEditRequest, AbilityEditError};
+    ///
+    /// let request = AbilityEditRequest { party_slot: 2, edit: AbilityEdit::Swap { a: 0, b: 3 } };
+    /// assert_eq!(AbilityEditRequest::decode(&request.encode()), Ok(request));
+    /// let forget = AbilityEditRequest { party_slot: 0, edit: AbilityEdit::Forget { slot: 1 } };
+    /// assert_eq!(AbilityEditRequest::decode(&forget.encode()), Ok(forget));
+    /// assert_eq!(AbilityEditRequest::decode(&[0, 9, 0]), Err(AbilityEditError::Malformed));
+    /// ```
+    pub fn encode(&self) -> Vec<u8> {
+        return match self.edit {
+            AbilityEdit::Swap { a, b } => vec![self.party_slot as u8, SWAP_TAG, a as u8, b as u8],
+            AbilityEdit::Forget { slot } => vec![self.party_slot as u8, FORGET_TAG, slot as u8]
+        };
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<AbilityEditRequest, AbilityEditError> {
+        let edit = match bytes {
+            [_, SWAP_TAG, a, b] => AbilityEdit::Swap { a: *a as usize, b: *b as usize },
+            [_, FORGET_TAG, slot] => AbilityEdit::Forget { slot: *slot as usize },
+            _ => return Err(AbilityEditError::Malformed)
+        };
+        return Ok(AbilityEditRequest { party_slot: bytes[0] as usize, edit });
+    }
+}
@@ -0,0 +1,55 @@
+/// Highest bond an Immie can have.
+pub const MAX_BOND: u32 = 255;
+/// Bond of a newly caught or hatched Immie.
+pub const BASE_BOND: u32 = 70;
+pub const BOND_FROM_BATTLE: u32 = 2;
+pub const BOND_FROM_WALKING: u32 = 1;
+pub const BOND_LOST_ON_FAINT: u32 = 5;
+/// Steps the player walks with an Immie in the party for each BondEvent::Walked.
+pub const STEPS_PER_WALKING_BOND: u32 = 256;
+/// Extra power of bond-scaled abilities at max bond. At 0 bond they have their normal power.
+pub const MAX_BOND_POWER_BONUS: f32 = 0.5;
+
+/* Something that changes how bonded an Immie is with its trainer. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BondEvent {
+    /// Took part in a battle without fainting.
+    Battled,
+    /// Was in the party for STEPS_PER_WALKING_BOND steps.
+    Walked,
+    /// An item increased bond by an amount.
+    Item(u32),
+    Fainted
+}
+
+impl BondEvent {
+    /// Apply the event to a bond value, keeping it within 0 and MAX_BOND.
+    /// ```
+    /// use immie2d_shared::gameplay::immie::bond::{BondEvent, MAX_BOND};
+    ///
+    /// assert_eq!(BondEvent::Battled.apply(70), 72);
+    /// assert_eq!(BondEvent::Item(50).apply(MAX_BOND - 10), MAX_BOND);
+    /// assert_eq!(BondEvent::Fainted.apply(3), 0);
+    /// assert_eq!(BondEvent::Walked.apply(u32::MAX), MAX_BOND);
+    /// ```
+    pub fn apply(&self, bond: u32) -> u32 {
+        let changed = match self {
+            BondEvent::Battled => bond.saturating_add(BOND_FROM_BATTLE),
+            BondEvent::Walked => bond.saturating_add(BOND_FROM_WALKING),
+            BondEvent::Item(amount) => bond.saturating_add(*amount),
+            BondEvent::Fainted => bond.saturating_sub(BOND_LOST_ON_FAINT)
+        };
+        return changed.min(MAX_BOND);
+    }
+}
+
+/// Power multiplier of a bond-scaled ability used by an Immie with some bond.
+/// ```
+/// use immie2d_shared::gameplay::immie::bond::{get_bond_power_multiplier, MAX_BOND};
+///
+/// assert_eq!(get_bond_power_multiplier(0), 1.0);
+/// assert_eq!(get_bond_power_multiplier(MAX_BOND), 1.5);
+/// ```
+pub fn get_bond_power_multiplier(bond: u32) -> f32 {
+    return 1.0 + MAX_BOND_POWER_BONUS * (bond.min(MAX_BOND) as f32 / MAX_BOND as f32);
+}
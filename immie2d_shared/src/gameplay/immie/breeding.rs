@@ -0,0 +1,155 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::engine_types::game_rng::GameRng;
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::ability::ability_names::{AbilityNames, MAX_ABILITIES_COUNT};
+use crate::gameplay::game_data::GameData;
+
+use super::immie::Immie;
+use super::individual_values::IndividualValues;
+use super::legality::{check_immie, LegalityError, LegalityRules};
+
+/// Level offspring hatch at.
+pub const BRED_LEVEL: u32 = 1;
+
+/* Which parents pass their abilities down to an offspring. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum InheritFrom {
+    BothParents,
+    /// Only parents of the offspring's species, so a species can't pick up abilities through a partner of another.
+    SameSpecies
+}
+
+/* How breeding works for every species. Which abilities each species can inherit is in its learnset. See Learnset */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BreedingRules {
+    pub inherit_from: InheritFrom,
+    /// Abilities that are never passed down, even to species whose learnset lists them, and that Immies which can
+    /// only have inherited them aren't legal with.
+    pub banned_inherited: HashSet<GlobalString>
+}
+
+impl BreedingRules {
+    /// Rules where both parents pass down any ability.
+    pub fn new() -> BreedingRules {
+        return BreedingRules { inherit_from: InheritFrom::BothParents, banned_inherited: HashSet::new() };
+    }
+
+    pub fn with_inherit_from(mut self, inherit_from: InheritFrom) -> BreedingRules {
+        self.inherit_from = inherit_from;
+        return self;
+    }
+
+    pub fn with_banned_inherited(mut self, ability: GlobalString) -> BreedingRules {
+        self.banned_inherited.insert(ability);
+        return self;
+    }
+}
+
+/* Why two Immies can't breed. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum BreedingError {
+    /// The species don't share a breeding group, or aren't in any.
+    Incompatible { first: GlobalString, second: GlobalString },
+    /// A parent isn't legal itself, so nothing it passes down can be trusted.
+    IllegalParent(LegalityError)
+}
+
+impl fmt::Display for BreedingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            BreedingError::Incompatible { first, second } => write!(f, "{} and {} can't breed", first, second),
+            BreedingError::IllegalParent(error) => write!(f, "A parent isn't legal: {}", error)
+        };
+    }
+}
+
+/// Breed two Immies, hatching an offspring of the first parent's species and base form at BRED_LEVEL, with newly
+/// rolled individual values. The offspring knows the abilities its species learns by that level, then as many abilities inherited from the parents as fit,
+/// first parent first. An ability is inherited if the rules let its parent pass abilities down, the offspring's
+/// learnset lists it as inherited, and it isn't banned. Abilities the offspring wouldn't be legal with are skipped, so
+/// it always passes check_immie() without event only abilities and can battle under any ruleset its species is in.
+/// ```
+/// use immie2d_shared::engine_types::{game_rng::GameRng, global_string::GlobalString};
+/// use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames, abilities::{fireball::Fireball, pursuit::Pursuit, hidden_power::HiddenPower}};
+/// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+/// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats, learnset::Learnset};
+/// use immie2d_shared::gameplay::item::item_map::ItemMap;
+/// use immie2d_shared::gameplay::game_data::GameData;
+/// use immie2d_shared::gameplay::immie::{immie::Immie, legality::{check_immie, LegalityError, LegalityRules}};
+/// use immie2d_shared::gameplay::immie::breeding::{breed, BreedingError, BreedingRules, InheritFrom};
+///
+/// let name = |name: &str| GlobalString::new(&name.to_string());
+/// let data_with_rules = |rules: BreedingRules| {
+///     let field = name("field");
+///     let mut species_map = SpeciesMap::new();
+///     species_map.add_species(SpeciesData::new(name("lavapup"), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)).with_breeding_group(field));
+///     species_map.add_species(SpeciesData::new(name("shadefox"), Elements::new(vec![ElementKind::Dark, ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)).with_breeding_group(field));
+///     species_map.add_species(SpeciesData::new(name("infernodon"), Elements::new(vec![ElementKind::Fire]), BaseStats::new(150, 160, 140, 170)));
+///     let learnset = Learnset::new().with_level_up(1, name("hidden_power")).with_level_up(30, name("fireball")).with_inherited(name("fireball")).with_inherited(name("pursuit"));
+///     species_map.set_learnset(name("lavapup"), learnset);
+///     let mut ability_map = AbilityMap::new();
+///     ability_map.add_ability::<Fireball>();
+///     ability_map.add_ability::<Pursuit>();
+///     ability_map.add_ability::<HiddenPower>();
+///     return GameData::new(1, species_map, ability_map, ItemMap::new()).with_breeding_rules(rules);
+/// };
+/// let data = data_with_rules(BreedingRules::new());
+/// let mut rng = GameRng::new(4);
+///
+/// let mother = Immie::new(name("lavapup"), 5, AbilityNames::new(vec![name("hidden_power")]));
+/// let father = Immie::new(name("shadefox"), 20, AbilityNames::new(vec![name("pursuit"), name("fireball")]));
+/// let egg = breed(&mother, &father, &data, &mut rng).unwrap();
+/// assert_eq!((egg.species, egg.level), (name("lavapup"), 1));
+/// // Pursuit can be inherited, but isn't legal on a Fire Immie
+/// assert_eq!(egg.abilities.get_names(), vec![name("hidden_power"), name("fireball")]);
+/// assert_eq!(check_immie(&egg, &data, LegalityRules { allow_event_only: false }), Ok(()));
+///
+/// // Fireball is learned at level 30, so a level 1 Immie can only have inherited it
+/// let banned = data_with_rules(BreedingRules::new().with_banned_inherited(name("fireball")));
+/// assert_eq!(breed(&mother, &father, &banned, &mut rng).unwrap().abilities.get_names(), vec![name("hidden_power")]);
+/// assert_eq!(check_immie(&egg, &banned, LegalityRules::new()), Err(LegalityError::BannedInheritance(name("fireball"))));
+///
+/// let same_species = data_with_rules(BreedingRules::new().with_inherit_from(InheritFrom::SameSpecies));
+/// assert_eq!(breed(&mother, &father, &same_species, &mut rng).unwrap().abilities.get_names(), vec![name("hidden_power")]);
+///
+/// let dragon = Immie::new(name("infernodon"), 50, AbilityNames::default());
+/// assert_eq!(breed(&mother, &dragon, &data, &mut rng), Err(BreedingError::Incompatible { first: name("lavapup"), second: name("infernodon") }));
+/// ```
+pub fn breed(first: &Immie, second: &Immie, data: &GameData, rng: &mut GameRng) -> Result<Immie, BreedingError> {
+    let rules = LegalityRules { allow_event_only: false };
+    for parent in [first, second] {
+        // Parents may well have been distributed by an event, and can pass down what they were given
+        check_immie(parent, data, LegalityRules::new()).map_err(BreedingError::IllegalParent)?;
+    }
+    let species_map = data.get_species_map();
+    if !species_map.get_species(first.species).can_breed_with(species_map.get_species(second.species)) {
+        return Err(BreedingError::Incompatible { first: first.species, second: second.species });
+    }
+    let learnset = species_map.get_learnset(first.species);
+    let breeding_rules = data.get_breeding_rules();
+    let learned = learnset.map(|learnset| learnset.level_up.as_slice()).unwrap_or_default().iter()
+        .filter(|(level, _)| *level <= BRED_LEVEL)
+        .map(|(_, ability)| *ability);
+    let inherited = [first, second].into_iter()
+        .filter(|parent| breeding_rules.inherit_from == InheritFrom::BothParents || parent.species == first.species)
+        .flat_map(|parent| parent.abilities.iter())
+        .filter(|ability| !breeding_rules.banned_inherited.contains(ability) && learnset.is_some_and(|learnset| learnset.is_inherited(*ability)));
+    let mut offspring = Immie::new(first.species, BRED_LEVEL, AbilityNames::default());
+    offspring.individual_values = IndividualValues::roll(rng);
+    for ability in learned.chain(inherited) {
+        if offspring.abilities.get_count() == MAX_ABILITIES_COUNT {
+            break;
+        }
+        if offspring.abilities.has_ability(ability) {
+            continue;
+        }
+        let mut with_ability = offspring;
+        with_ability.abilities.add_ability(ability);
+        if check_immie(&with_ability, data, rules).is_ok() {
+            offspring = with_ability;
+        }
+    }
+    return Ok(offspring);
+}
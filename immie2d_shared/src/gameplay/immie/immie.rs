@@ -0,0 +1,239 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::elements::element_kinds::ElementKind;
+use crate::gameplay::ability::ability_flags::AbilityFlags;
+use crate::gameplay::ability::ability_map::AbilityMap;
+use crate::gameplay::ability::ability_names::{AbilityNames, MAX_ABILITIES_COUNT};
+use crate::gameplay::species::species_data::SpeciesData;
+use crate::gameplay::status_condition::StatusCondition;
+
+use super::ability_edit::{AbilityEdit, AbilityEditError};
+use super::bond::{BondEvent, BASE_BOND, MAX_BOND};
+use super::individual_values::IndividualValues;
+
+/* A single owned creature. Species wide data is looked up through the SpeciesMap. */
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Immie {
+    pub species: GlobalString,
+    pub level: u32,
+    pub abilities: AbilityNames,
+    pub held_item: Option<GlobalString>,
+    /// Name of the passive trait, which takes effect without being used.
+    pub passive: Option<GlobalString>,
+    /// Health lost, kept between battles. Stored as damage so it doesn't depend on the species' max health.
+    pub damage_taken: u32,
+    pub status: Option<StatusCondition>,
+    /// Uses spent of each ability, in the same order as the ability names.
+    pub ability_uses_spent: [u32; MAX_ABILITIES_COUNT as usize],
+    /// How bonded the Immie is with its trainer, up to MAX_BOND. See BondEvent
+    #[serde(deserialize_with = "deserialize_bond")]
+    pub bond: u32,
+    /// Name of the species form, or None for the base species. See SpeciesForm
+    pub form: Option<GlobalString>,
+    pub individual_values: IndividualValues,
+    /// Favourited by the player. Locked Immies can't be released or traded until they are unlocked.
+    pub is_locked: bool,
+    /// Name of the event distribution that gifted the Immie, or None if the player got it in the game.
+    pub origin: Option<GlobalString>,
+    /// Element the Immie can attune to once per battle, or None if it can't attune. Set by using an ItemEffect::Attune
+    /// item. See Battler::attune()
+    pub attunement: Option<ElementKind>
+}
+
+/// Rejects bond above MAX_BOND. The rest of an Immie is checked by the Deserialize impls of its fields.
+fn deserialize_bond<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let bond = u32::deserialize(deserializer)?;
+    if bond > MAX_BOND {
+        return Err(serde::de::Error::custom(format!("bond {} is above the max of {}", bond, MAX_BOND)));
+    }
+    return Ok(bond);
+}
+
+impl Immie {
+    /// Create a new fully healthy Immie that is not holding any item. Its individual values are all 0 until rolled.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    ///
+    /// let immie = Immie::new(GlobalString::new(&"lavapup".to_string()), 5, AbilityNames::new(vec![GlobalString::new(&"fireball".to_string())]));
+    /// assert_eq!(immie.level, 5);
+    /// assert!(immie.held_item.is_none());
+    /// assert_eq!(immie.damage_taken, 0);
+    /// assert_eq!(immie.bond, immie2d_shared::gameplay::immie::bond::BASE_BOND);
+    /// ```
+    pub fn new(species: GlobalString, level: u32, abilities: AbilityNames) -> Immie {
+        return Immie {
+            species,
+            level,
+            abilities,
+            held_item: None,
+            passive: None,
+            damage_taken: 0,
+            status: None,
+            ability_uses_spent: [0; MAX_ABILITIES_COUNT as usize],
+            bond: BASE_BOND,
+            form: None,
+            individual_values: IndividualValues::default(),
+            is_locked: false,
+            origin: None,
+            attunement: None
+        };
+    }
+
+    /// Id of the sprite the client draws the Immie with. Forms have their own sprites.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    ///
+    /// let mut immie = Immie::new(GlobalString::new(&"lavapup".to_string()), 5, AbilityNames::default());
+    /// assert_eq!(immie.get_sprite_id(), "lavapup");
+    /// immie.form = Some(GlobalString::new(&"tundra".to_string()));
+    /// assert_eq!(immie.get_sprite_id(), "lavapup-tundra");
+    /// ```
+    pub fn get_sprite_id(&self) -> String {
+        return match self.form {
+            Some(form) => format!("{}-{}", self.species.to_string(), form.to_string()),
+            None => self.species.to_string()
+        };
+    }
+
+    pub fn get_max_health(&self, species: &SpeciesData) -> u32 {
+        return species.base_stats.health;
+    }
+
+    pub fn get_health(&self, species: &SpeciesData) -> u32 {
+        return self.get_max_health(species).saturating_sub(self.damage_taken);
+    }
+
+    pub fn is_fainted(&self, species: &SpeciesData) -> bool {
+        return self.get_health(species) == 0;
+    }
+
+    /// Restore health, not going above the max. Returns the amount of health restored.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let mut immie = Immie::new(species.name, 5, AbilityNames::default());
+    /// immie.damage_taken = 30;
+    /// assert_eq!(immie.heal(20), 20);
+    /// assert_eq!(immie.heal(20), 10);
+    /// assert_eq!(immie.get_health(&species), 50);
+    /// ```
+    pub fn heal(&mut self, amount: u32) -> u32 {
+        let healed = amount.min(self.damage_taken);
+        self.damage_taken -= healed;
+        return healed;
+    }
+
+    /// Get the remaining uses of the ability in a slot, given the ability's max uses.
+    pub fn get_remaining_uses(&self, ability_slot: usize, max_uses: u32) -> u32 {
+        return max_uses.saturating_sub(self.ability_uses_spent[ability_slot]);
+    }
+
+    /// Restore uses of the ability in a slot. Returns the amount of uses restored.
+    pub fn restore_uses(&mut self, ability_slot: usize, amount: u32) -> u32 {
+        let restored = amount.min(self.ability_uses_spent[ability_slot]);
+        self.ability_uses_spent[ability_slot] -= restored;
+        return restored;
+    }
+
+    /// Change bond in response to an event. Returns the new bond.
+    pub fn apply_bond_event(&mut self, event: BondEvent) -> u32 {
+        self.bond = event.apply(self.bond);
+        return self.bond;
+    }
+
+    /// Whether the Immie meets the level and bond requirements to evolve.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::species::{species_data::{SpeciesData, EvolutionData}, base_stats::BaseStats};
+    /// use immie2d_shared::gameplay::immie::{immie::Immie, bond::BondEvent};
+    ///
+    /// let evolved = GlobalString::new(&"lavahound".to_string());
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70))
+    ///     .with_evolution(EvolutionData { species: evolved, min_level: 10, min_bond: Some(80) });
+    /// let mut immie = Immie::new(species.name, 12, AbilityNames::default());
+    /// assert!(!immie.can_evolve(&species));
+    /// immie.apply_bond_event(BondEvent::Item(10));
+    /// assert!(immie.can_evolve(&species));
+    /// immie.evolve(&species);
+    /// assert_eq!(immie.species, evolved);
+    /// ```
+    pub fn can_evolve(&self, species: &SpeciesData) -> bool {
+        return match species.evolution {
+            Some(evolution) => self.level >= evolution.min_level && self.bond >= evolution.min_bond.unwrap_or(0),
+            None => false
+        };
+    }
+
+    /// Evolve into the species given by the current species' evolution data.
+    /// Will panic if the Immie cannot evolve. See Immie::can_evolve()
+    pub fn evolve(&mut self, species: &SpeciesData) {
+        assert!(self.can_evolve(species), "Immie of species {} cannot evolve", species.name);
+        self.species = species.evolution.unwrap().species;
+    }
+
+    /// Swap or forget abilities, keeping the uses spent of each ability with it. An Immie must keep at least one
+    /// ability, and can't forget abilities flagged with AbilityFlags::CANNOT_FORGET
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::{ability::Ability, ability_map::AbilityMap, ability_names::AbilityNames, abilities::fireball::Fireball};
+    /// use immie2d_shared::gameplay::immie::{immie::Immie, ability_edit::{AbilityEdit, AbilityEditError}};
+    ///
+    /// let fireball = GlobalString::new(&Fireball::static_name().to_string());
+    /// let ember = GlobalString::new(&"ember".to_string());
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Fireball>();
+    /// let mut immie = Immie::new(GlobalString::new(&"lavapup".to_string()), 5, AbilityNames::new(vec![fireball, ember]));
+    /// immie.ability_uses_spent[0] = 4;
+    ///
+    /// immie.apply_ability_edit(AbilityEdit::Swap { a: 0, b: 1 }, &ability_map).unwrap();
+    /// assert_eq!(immie.abilities.get_names(), vec![ember, fireball]);
+    /// assert_eq!(immie.ability_uses_spent[1], 4);
+    ///
+    /// immie.apply_ability_edit(AbilityEdit::Forget { slot: 0 }, &ability_map).unwrap();
+    /// assert_eq!(immie.abilities.get_names(), vec![fireball]);
+    /// assert_eq!(immie.ability_uses_spent[0], 4);
+    /// assert_eq!(immie.apply_ability_edit(AbilityEdit::Forget { slot: 0 }, &ability_map), Err(AbilityEditError::LastAbility));
+    /// assert_eq!(immie.apply_ability_edit(AbilityEdit::Swap { a: 0, b: 3 }, &ability_map), Err(AbilityEditError::InvalidAbilitySlot));
+    /// ```
+    pub fn apply_ability_edit(&mut self, edit: AbilityEdit, ability_map: &AbilityMap) -> Result<(), AbilityEditError> {
+        let count = self.abilities.get_count() as usize;
+        match edit {
+            AbilityEdit::Swap { a, b } => {
+                if a >= count || b >= count {
+                    return Err(AbilityEditError::InvalidAbilitySlot);
+                }
+                self.abilities.swap(a, b);
+                self.ability_uses_spent.swap(a, b);
+            },
+            AbilityEdit::Forget { slot } => {
+                if slot >= count {
+                    return Err(AbilityEditError::InvalidAbilitySlot);
+                }
+                if count == 1 {
+                    return Err(AbilityEditError::LastAbility);
+                }
+                let name = self.abilities.get_names()[slot].to_string();
+                // Abilities no longer in the game data can always be forgotten.
+                if ability_map.is_ability_name(&name) && ability_map.new_ability(&name).get_base_ability_data().flags.contains(AbilityFlags::CANNOT_FORGET) {
+                    return Err(AbilityEditError::CannotForget);
+                }
+                self.abilities.remove(slot);
+                self.ability_uses_spent.copy_within(slot + 1.., slot);
+                self.ability_uses_spent[MAX_ABILITIES_COUNT as usize - 1] = 0;
+            }
+        }
+        return Ok(());
+    }
+}
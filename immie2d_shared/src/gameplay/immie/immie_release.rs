@@ -0,0 +1,152 @@
+use std::fmt;
+
+/* Where one of a player's Immies is kept. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ImmieLocation {
+    Party(usize),
+    Box(usize)
+}
+
+/* Sent by the client to release an Immie for good, or to lock one against being released or traded. A release only
+happens once the client confirms it with the token from the server's ReleasePrompt. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReleaseRequest {
+    Release { location: ImmieLocation },
+    Confirm { location: ImmieLocation, token: u64 },
+    SetLocked { location: ImmieLocation, is_locked: bool }
+}
+
+/* Sent by the server in answer to ReleaseRequest::Release, for the client to ask the player to confirm. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ReleasePrompt {
+    pub location: ImmieLocation,
+    pub token: u64
+}
+
+/* Why a release or lock request was rejected. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ReleaseError {
+    /// The request bytes are not a valid request.
+    Malformed,
+    InvalidLocation,
+    /// The Immie is locked, so it can't be released or traded until it is unlocked.
+    Locked,
+    /// The party must keep at least one Immie.
+    LastPartyImmie,
+    /// The confirmation doesn't match a release the server is waiting on, or it expired.
+    NotConfirmed,
+    /// Immies can't be released while the player is battling.
+    InBattle
+}
+
+impl fmt::Display for ReleaseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            ReleaseError::Malformed => write!(f, "Malformed release request"),
+            ReleaseError::InvalidLocation => write!(f, "No Immie in that slot"),
+            ReleaseError::Locked => write!(f, "That Immie is locked"),
+            ReleaseError::LastPartyImmie => write!(f, "The party must keep at least one Immie"),
+            ReleaseError::NotConfirmed => write!(f, "The release was not confirmed"),
+            ReleaseError::InBattle => write!(f, "Immies cannot be released during a battle")
+        };
+    }
+}
+
+const PARTY_TAG: u8 = 0;
+const BOX_TAG: u8 = 1;
+
+const RELEASE_TAG: u8 = 0;
+const CONFIRM_TAG: u8 = 1;
+const SET_LOCKED_TAG: u8 = 2;
+
+impl ImmieLocation {
+    /// Encode as a tag and a 2 byte slot. Will panic if the slot doesn't fit in 2 bytes.
+    pub fn encode(&self) -> [u8; 3] {
+        let (tag, slot) = match *self {
+            ImmieLocation::Party(slot) => (PARTY_TAG, slot),
+            ImmieLocation::Box(slot) => (BOX_TAG, slot)
+        };
+        assert!(slot <= u16::MAX as usize, "Slot {} does not fit in 2 bytes", slot);
+        let [low, high] = (slot as u16).to_le_bytes();
+        return [tag, low, high];
+    }
+
+    pub fn decode(bytes: [u8; 3]) -> Option<ImmieLocation> {
+        let slot = u16::from_le_bytes([bytes[1], bytes[2]]) as usize;
+        return match bytes[0] {
+            PARTY_TAG => Some(ImmieLocation::Party(slot)),
+            BOX_TAG => Some(ImmieLocation::Box(slot)),
+            _ => None
+        };
+    }
+}
+
+impl ReleaseRequest {
+    /// Encode as the request tag, the location, then the token or locked flag.
+    /// ```
+    /// use immie2d_shared::gameplay::immie::immie_release::{ImmieLocation, ReleaseError, ReleaseRequest};
+    ///
+    /// let requests = [
+    ///     ReleaseRequest::Release { location: ImmieLocation::Box(300) },
+    ///     ReleaseRequest::Confirm { location: ImmieLocation::Party(2), token: 0xdead_beef },
+    ///     ReleaseRequest::SetLocked { location: ImmieLocation::Box(0), is_locked: true }
+    /// ];
+    /// for request in requests {
+    ///     assert_eq!(ReleaseRequest::decode(&request.encode()), Ok(request));
+    /// }
+    /// assert_eq!(ReleaseRequest::decode(&[1, 0, 2, 0]), Err(ReleaseError::Malformed));
+    /// assert_eq!(ReleaseRequest::decode(&[0, 7, 0, 0]), Err(ReleaseError::Malformed));
+    /// ```
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match *self {
+            ReleaseRequest::Release { location } => {
+                bytes.push(RELEASE_TAG);
+                bytes.extend_from_slice(&location.encode());
+            },
+            ReleaseRequest::Confirm { location, token } => {
+                bytes.push(CONFIRM_TAG);
+                bytes.extend_from_slice(&location.encode());
+                bytes.extend_from_slice(&token.to_le_bytes());
+            },
+            ReleaseRequest::SetLocked { location, is_locked } => {
+                bytes.push(SET_LOCKED_TAG);
+                bytes.extend_from_slice(&location.encode());
+                bytes.push(is_locked as u8);
+            }
+        }
+        return bytes;
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<ReleaseRequest, ReleaseError> {
+        let location = bytes.get(1..4).and_then(|location| ImmieLocation::decode(location.try_into().unwrap())).ok_or(ReleaseError::Malformed)?;
+        return match (bytes[0], &bytes[4..]) {
+            (RELEASE_TAG, []) => Ok(ReleaseRequest::Release { location }),
+            (CONFIRM_TAG, token) if token.len() == 8 => Ok(ReleaseRequest::Confirm { location, token: u64::from_le_bytes(token.try_into().unwrap()) }),
+            (SET_LOCKED_TAG, [is_locked]) => Ok(ReleaseRequest::SetLocked { location, is_locked: *is_locked != 0 }),
+            _ => Err(ReleaseError::Malformed)
+        };
+    }
+}
+
+impl ReleasePrompt {
+    /// Encode as the location followed by the token.
+    /// ```
+    /// use immie2d_shared::gameplay::immie::immie_release::{ImmieLocation, ReleasePrompt};
+    ///
+    /// let prompt = ReleasePrompt { location: ImmieLocation::Box(12), token: 99 };
+    /// assert_eq!(ReleasePrompt::from_bytes(&prompt.to_bytes()), Some(prompt));
+    /// assert_eq!(ReleasePrompt::from_bytes(&[1, 12]), None);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.location.encode().to_vec();
+        bytes.extend_from_slice(&self.token.to_le_bytes());
+        return bytes;
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Option<ReleasePrompt> {
+        let location = ImmieLocation::decode(bytes.get(0..3)?.try_into().ok()?)?;
+        let token = u64::from_le_bytes(bytes.get(3..11)?.try_into().ok()?);
+        return Some(ReleasePrompt { location, token });
+    }
+}
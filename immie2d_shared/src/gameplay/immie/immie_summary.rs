@@ -0,0 +1,33 @@
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::species::species_data::SpeciesData;
+use crate::gameplay::status_condition::StatusCondition;
+
+use super::immie::Immie;
+
+/* Everything shown on an Immie's summary screen, resolved against its species. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ImmieSummary {
+    pub species: GlobalString,
+    pub level: u32,
+    pub health: u32,
+    pub max_health: u32,
+    pub status: Option<StatusCondition>,
+    pub held_item: Option<GlobalString>,
+    pub bond: u32,
+    pub can_evolve: bool
+}
+
+impl ImmieSummary {
+    pub fn new(immie: &Immie, species: &SpeciesData) -> ImmieSummary {
+        return ImmieSummary {
+            species: immie.species,
+            level: immie.level,
+            health: immie.get_health(species),
+            max_health: immie.get_max_health(species),
+            status: immie.status,
+            held_item: immie.held_item,
+            bond: immie.bond,
+            can_evolve: immie.can_evolve(species)
+        };
+    }
+}
@@ -0,0 +1,103 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::engine_types::game_rng::GameRng;
+use crate::gameplay::elements::element_kinds::ElementKind;
+
+/// Highest individual value of a stat.
+pub const MAX_INDIVIDUAL_VALUE: u8 = 31;
+/// Power of a hidden power ability when every bit it is derived from is clear.
+pub const MIN_HIDDEN_POWER: f32 = 30.0;
+/// Power of a hidden power ability when every bit it is derived from is set.
+pub const MAX_HIDDEN_POWER: f32 = 70.0;
+
+/// Elements a hidden power can resolve to, in order of the bits that select them. Standard is never chosen.
+const HIDDEN_POWER_ELEMENTS: [ElementKind; 10] = [
+    ElementKind::Fire,
+    ElementKind::Water,
+    ElementKind::Nature,
+    ElementKind::Electric,
+    ElementKind::Air,
+    ElementKind::Ground,
+    ElementKind::Metal,
+    ElementKind::Light,
+    ElementKind::Dark,
+    ElementKind::Dragon
+];
+
+/* Hidden per-Immie values of each stat, rolled once when the Immie is created and never changed. Players can't see
+them directly, but they decide the element and power of abilities flagged AbilityFlags::HIDDEN_POWER. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct IndividualValues {
+    #[serde(deserialize_with = "deserialize_value")]
+    pub health: u8,
+    #[serde(deserialize_with = "deserialize_value")]
+    pub attack: u8,
+    #[serde(deserialize_with = "deserialize_value")]
+    pub defense: u8,
+    #[serde(deserialize_with = "deserialize_value")]
+    pub speed: u8
+}
+
+/// Rejects values above MAX_INDIVIDUAL_VALUE, the same as IndividualValues::new() and read_immie().
+fn deserialize_value<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    let value = u8::deserialize(deserializer)?;
+    if value > MAX_INDIVIDUAL_VALUE {
+        return Err(serde::de::Error::custom(format!("individual value {} is above the max of {}", value, MAX_INDIVIDUAL_VALUE)));
+    }
+    return Ok(value);
+}
+
+impl IndividualValues {
+    /// Create individual values. Will panic if any is above MAX_INDIVIDUAL_VALUE.
+    pub fn new(health: u8, attack: u8, defense: u8, speed: u8) -> IndividualValues {
+        for value in [health, attack, defense, speed] {
+            assert!(value <= MAX_INDIVIDUAL_VALUE, "Individual value {} is above the max of {}", value, MAX_INDIVIDUAL_VALUE);
+        }
+        return IndividualValues { health, attack, defense, speed };
+    }
+
+    /// Every value at 0, which Immie::new() starts with until values are rolled for it.
+    pub fn default() -> IndividualValues {
+        return IndividualValues { health: 0, attack: 0, defense: 0, speed: 0 };
+    }
+
+    /// Roll each value uniformly from 0 to MAX_INDIVIDUAL_VALUE, taking four draws from the rng in the order health,
+    /// attack, defense, speed.
+    pub fn roll(rng: &mut GameRng) -> IndividualValues {
+        let mut roll = || rng.next_below(MAX_INDIVIDUAL_VALUE as u32 + 1) as u8;
+        return IndividualValues { health: roll(), attack: roll(), defense: roll(), speed: roll() };
+    }
+
+    /// Take one bit of every value, from health as the lowest bit to speed as the highest.
+    fn get_bits(&self, bit: u32) -> u32 {
+        let values = [self.health, self.attack, self.defense, self.speed];
+        return values.iter().enumerate().map(|(index, value)| ((*value as u32 >> bit) & 1) << index).sum();
+    }
+
+    /// The element of a hidden power ability used by the Immie, from the lowest bit of each value.
+    /// ```
+    /// use immie2d_shared::gameplay::elements::element_kinds::ElementKind;
+    /// use immie2d_shared::gameplay::immie::individual_values::IndividualValues;
+    ///
+    /// assert!(IndividualValues::new(0, 0, 0, 0).get_hidden_power_element() == ElementKind::Fire);
+    /// assert!(IndividualValues::new(31, 31, 31, 31).get_hidden_power_element() == ElementKind::Dragon);
+    /// assert!(IndividualValues::new(31, 30, 30, 30).get_hidden_power_element() == ElementKind::Fire);
+    /// assert!(IndividualValues::new(30, 30, 30, 31).get_hidden_power_element() == ElementKind::Air);
+    /// ```
+    pub fn get_hidden_power_element(&self) -> ElementKind {
+        let index = self.get_bits(0) as usize * (HIDDEN_POWER_ELEMENTS.len() - 1) / 15;
+        return HIDDEN_POWER_ELEMENTS[index];
+    }
+
+    /// The power of a hidden power ability used by the Immie, from the second lowest bit of each value.
+    /// ```
+    /// use immie2d_shared::gameplay::immie::individual_values::{IndividualValues, MIN_HIDDEN_POWER, MAX_HIDDEN_POWER};
+    ///
+    /// assert_eq!(IndividualValues::new(0, 0, 0, 0).get_hidden_power(), MIN_HIDDEN_POWER);
+    /// assert_eq!(IndividualValues::new(2, 2, 2, 2).get_hidden_power(), MAX_HIDDEN_POWER);
+    /// assert_eq!(IndividualValues::new(31, 31, 31, 31).get_hidden_power(), MAX_HIDDEN_POWER);
+    /// ```
+    pub fn get_hidden_power(&self) -> f32 {
+        return MIN_HIDDEN_POWER + (MAX_HIDDEN_POWER - MIN_HIDDEN_POWER) * self.get_bits(1) as f32 / 15.0;
+    }
+}
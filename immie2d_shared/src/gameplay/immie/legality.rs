@@ -0,0 +1,160 @@
+use std::fmt;
+
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::ability::ability_flags::AbilityFlags;
+use crate::gameplay::elements::element_kinds::ElementKind;
+use crate::gameplay::game_data::GameData;
+use crate::gameplay::species::species_form::FormError;
+
+use super::immie::Immie;
+
+/// Most Immies a team can battle with.
+pub const MAX_TEAM_SIZE: usize = 6;
+
+/* Why an Immie isn't legal to battle with. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LegalityError {
+    UnknownAbility(GlobalString),
+    UnknownItem(GlobalString),
+    /// The Immie is past the first MAX_TEAM_SIZE of its team.
+    BeyondTeamSize,
+    /// The species or form doesn't exist, or the form can't learn one of the abilities.
+    Form(FormError),
+    /// The ability isn't in the species' learnset at all.
+    NotLearnable { species: GlobalString, ability: GlobalString },
+    /// The ability is learned at a higher level than the Immie is.
+    LevelTooLow { ability: GlobalString, required_level: u32 },
+    /// The ability is event only, and the rules don't allow event only abilities.
+    EventOnly(GlobalString),
+    /// The ability is neither Standard nor shares an element with the Immie.
+    ElementMismatch(GlobalString),
+    /// The Immie can only have inherited the ability, and the breeding rules don't pass it down. See BreedingRules
+    BannedInheritance(GlobalString)
+}
+
+impl fmt::Display for LegalityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            LegalityError::UnknownAbility(ability) => write!(f, "There is no ability {}", ability),
+            LegalityError::UnknownItem(item) => write!(f, "There is no item {}", item),
+            LegalityError::BeyondTeamSize => write!(f, "Teams can't have more than {} Immies", MAX_TEAM_SIZE),
+            LegalityError::Form(error) => write!(f, "{}", error),
+            LegalityError::NotLearnable { species, ability } => write!(f, "{} can't learn {}", species, ability),
+            LegalityError::LevelTooLow { ability, required_level } => write!(f, "{} is learned at level {}", ability, required_level),
+            LegalityError::EventOnly(ability) => write!(f, "{} is only available from events", ability),
+            LegalityError::ElementMismatch(ability) => write!(f, "{} doesn't match the Immie's elements", ability),
+            LegalityError::BannedInheritance(ability) => write!(f, "{} can't be inherited", ability)
+        };
+    }
+}
+
+/* Options of the legality check that differ between where it is enforced. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LegalityRules {
+    /// Immies don't record whether they were distributed by an event, so event only abilities are either allowed
+    /// for everyone or for no one.
+    pub allow_event_only: bool
+}
+
+impl LegalityRules {
+    /// Rules allowing event only abilities.
+    pub fn new() -> LegalityRules {
+        return LegalityRules { allow_event_only: true };
+    }
+}
+
+/// Check that an Immie is legal to battle with. This is the same check on the client, which uses it to show errors
+/// while the team is edited, and on the server, which enforces it when queueing. An Immie can only know Standard
+/// abilities and abilities sharing one of its elements, except hidden power abilities, which take their element from
+/// the Immie. Abilities its species can inherit are legal at any level, unless the breeding rules ban them. Returns
+/// the first problem found.
+/// ```
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames, abilities::{fireball::Fireball, pursuit::Pursuit, hidden_power::HiddenPower}};
+/// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+/// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats, learnset::Learnset};
+/// use immie2d_shared::gameplay::item::item_map::ItemMap;
+/// use immie2d_shared::gameplay::game_data::GameData;
+/// use immie2d_shared::gameplay::immie::{immie::Immie, legality::{check_immie, check_team, LegalityError, LegalityRules, MAX_TEAM_SIZE}};
+///
+/// let name = |name: &str| GlobalString::new(&name.to_string());
+/// let mut species_map = SpeciesMap::new();
+/// species_map.add_species(SpeciesData::new(name("lavapup"), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+/// species_map.set_learnset(name("lavapup"), Learnset::new().with_level_up(10, name("fireball")).with_event_only(name("hidden_power")).with_level_up(1, name("pursuit")));
+/// let mut ability_map = AbilityMap::new();
+/// ability_map.add_ability::<Fireball>();
+/// ability_map.add_ability::<Pursuit>();
+/// ability_map.add_ability::<HiddenPower>();
+/// let data = GameData::new(1, species_map, ability_map, ItemMap::new());
+/// let rules = LegalityRules::new();
+///
+/// let immie = Immie::new(name("lavapup"), 10, AbilityNames::new(vec![name("fireball"), name("hidden_power")]));
+/// assert_eq!(check_immie(&immie, &data, rules), Ok(()));
+/// assert_eq!(check_immie(&immie, &data, LegalityRules { allow_event_only: false }), Err(LegalityError::EventOnly(name("hidden_power"))));
+/// let young = Immie::new(name("lavapup"), 9, AbilityNames::new(vec![name("fireball")]));
+/// assert_eq!(check_immie(&young, &data, rules), Err(LegalityError::LevelTooLow { ability: name("fireball"), required_level: 10 }));
+/// // In the learnset, but a Dark ability on a Fire Immie
+/// let dark = Immie::new(name("lavapup"), 10, AbilityNames::new(vec![name("pursuit")]));
+/// assert_eq!(check_immie(&dark, &data, rules), Err(LegalityError::ElementMismatch(name("pursuit"))));
+/// let mut holding = immie;
+/// holding.held_item = Some(name("charcoal"));
+/// assert_eq!(check_immie(&holding, &data, rules), Err(LegalityError::UnknownItem(name("charcoal"))));
+///
+/// let team = vec![immie; MAX_TEAM_SIZE + 1];
+/// assert_eq!(check_team(&team, &data, rules), vec![(MAX_TEAM_SIZE, LegalityError::BeyondTeamSize)]);
+/// ```
+pub fn check_immie(immie: &Immie, data: &GameData, rules: LegalityRules) -> Result<(), LegalityError> {
+    let species_map = data.get_species_map();
+    species_map.validate_immie(immie).map_err(LegalityError::Form)?;
+    if let Some(item) = immie.held_item.filter(|item| data.get_item_map().get_item(*item).is_none()) {
+        return Err(LegalityError::UnknownItem(item));
+    }
+    let elements = species_map.get_species_of(immie).elements;
+    let learnset = species_map.get_learnset(immie.species);
+    for ability in immie.abilities.iter() {
+        if !data.get_ability_map().is_ability_name(&ability.to_string()) {
+            return Err(LegalityError::UnknownAbility(ability));
+        }
+        if let Some(learnset) = learnset {
+            match learnset.get_learn_level(ability) {
+                Some(required_level) if required_level <= immie.level => (),
+                _ if learnset.is_inherited(ability) => {
+                    if data.get_breeding_rules().banned_inherited.contains(&ability) {
+                        return Err(LegalityError::BannedInheritance(ability));
+                    }
+                },
+                Some(required_level) => return Err(LegalityError::LevelTooLow { ability, required_level }),
+                None if learnset.is_event_only(ability) => {
+                    if !rules.allow_event_only {
+                        return Err(LegalityError::EventOnly(ability));
+                    }
+                },
+                None => return Err(LegalityError::NotLearnable { species: immie.species, ability })
+            }
+        }
+        let instance = data.get_ability_map().new_ability(&ability.to_string());
+        let ability_data = instance.get_base_ability_data();
+        if ability_data.flags.contains(AbilityFlags::HIDDEN_POWER) {
+            continue;
+        }
+        let matches = ability_data.types.iter().any(|element| element == ElementKind::Standard || elements.has_elements(element));
+        if !matches {
+            return Err(LegalityError::ElementMismatch(ability));
+        }
+    }
+    return Ok(());
+}
+
+/// Check every Immie of a team, returning the party slot and problem of each illegal Immie in slot order. Immies past
+/// the first MAX_TEAM_SIZE are illegal.
+pub fn check_team(immies: &[Immie], data: &GameData, rules: LegalityRules) -> Vec<(usize, LegalityError)> {
+    return immies.iter().enumerate().filter_map(|(slot, immie)| check_team_slot(slot, immie, data, rules).err().map(|error| (slot, error))).collect();
+}
+
+/// Check the Immie in a slot of a team. See check_team()
+pub(crate) fn check_team_slot(slot: usize, immie: &Immie, data: &GameData, rules: LegalityRules) -> Result<(), LegalityError> {
+    if slot >= MAX_TEAM_SIZE {
+        return Err(LegalityError::BeyondTeamSize);
+    }
+    return check_immie(immie, data, rules);
+}
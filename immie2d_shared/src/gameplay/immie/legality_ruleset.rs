@@ -0,0 +1,184 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::game_data::GameData;
+use crate::gameplay::species::species_data::SpeciesTier;
+
+use super::immie::Immie;
+use super::legality::{check_team_slot, LegalityError, LegalityRules};
+
+/// Highest level allowed in Little Cup.
+pub const LITTLE_CUP_MAX_LEVEL: u32 = 5;
+
+/* The rulesets every matchmaking queue can be given without configuring one by hand. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RulesetPreset {
+    /// Every species below ubers, with the species clause.
+    Standard,
+    /// Only Little Cup species, at level 5 or below, with the species clause.
+    LittleCup,
+    /// Every species, with the species clause.
+    Ubers
+}
+
+impl RulesetPreset {
+    pub fn get_id(self) -> u8 {
+        return match self {
+            RulesetPreset::Standard => 0,
+            RulesetPreset::LittleCup => 1,
+            RulesetPreset::Ubers => 2
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<RulesetPreset> {
+        return match id {
+            0 => Some(RulesetPreset::Standard),
+            1 => Some(RulesetPreset::LittleCup),
+            2 => Some(RulesetPreset::Ubers),
+            _ => None
+        };
+    }
+
+    pub fn get_name(self) -> &'static str {
+        return match self {
+            RulesetPreset::Standard => "standard",
+            RulesetPreset::LittleCup => "little_cup",
+            RulesetPreset::Ubers => "ubers"
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<RulesetPreset> {
+        return [RulesetPreset::Standard, RulesetPreset::LittleCup, RulesetPreset::Ubers].into_iter().find(|preset| preset.get_name() == name);
+    }
+}
+
+/* Why a team breaks a queue's ruleset. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RulesetViolation {
+    /// The Immie isn't legal to battle with in any ruleset. See check_team()
+    Illegal(LegalityError),
+    TierNotAllowed { species: GlobalString, tier: SpeciesTier },
+    LevelOutOfRange { level: u32, min_level: u32, max_level: u32 },
+    BannedAbility(GlobalString),
+    BannedItem(GlobalString),
+    /// Another Immie earlier in the team is the same species, which the species clause forbids.
+    DuplicateSpecies(GlobalString)
+}
+
+impl fmt::Display for RulesetViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            RulesetViolation::Illegal(error) => write!(f, "{}", error),
+            RulesetViolation::TierNotAllowed { species, tier } => write!(f, "{} is in the {} tier, which isn't allowed", species, tier.get_name()),
+            RulesetViolation::LevelOutOfRange { level, min_level, max_level } => write!(f, "Level {} is outside of the allowed levels {} to {}", level, min_level, max_level),
+            RulesetViolation::BannedAbility(ability) => write!(f, "{} is banned", ability),
+            RulesetViolation::BannedItem(item) => write!(f, "{} is banned", item),
+            RulesetViolation::DuplicateSpecies(species) => write!(f, "Only one {} is allowed per team", species)
+        };
+    }
+}
+
+/* What a queue allows on a team, on top of the legality check every battle has. The client checks teams against the
+same ruleset as the server's queue, so the team builder shows the reasons a team would be rejected. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LegalityRuleset {
+    pub allowed_tiers: HashSet<SpeciesTier>,
+    pub min_level: u32,
+    pub max_level: u32,
+    pub banned_abilities: HashSet<GlobalString>,
+    pub banned_items: HashSet<GlobalString>,
+    /// Whether a team can have only one Immie of each species.
+    pub species_clause: bool,
+    pub rules: LegalityRules
+}
+
+impl LegalityRuleset {
+    pub fn from_preset(preset: RulesetPreset) -> LegalityRuleset {
+        let (allowed_tiers, max_level) = match preset {
+            RulesetPreset::Standard => (vec![SpeciesTier::LittleCup, SpeciesTier::Standard], u32::MAX),
+            RulesetPreset::LittleCup => (vec![SpeciesTier::LittleCup], LITTLE_CUP_MAX_LEVEL),
+            RulesetPreset::Ubers => (vec![SpeciesTier::LittleCup, SpeciesTier::Standard, SpeciesTier::Ubers], u32::MAX)
+        };
+        return LegalityRuleset {
+            allowed_tiers: allowed_tiers.into_iter().collect(),
+            min_level: 1,
+            max_level,
+            banned_abilities: HashSet::new(),
+            banned_items: HashSet::new(),
+            species_clause: true,
+            rules: LegalityRules::new()
+        };
+    }
+
+    pub fn with_banned_ability(mut self, ability: GlobalString) -> LegalityRuleset {
+        self.banned_abilities.insert(ability);
+        return self;
+    }
+
+    pub fn with_banned_item(mut self, item: GlobalString) -> LegalityRuleset {
+        self.banned_items.insert(item);
+        return self;
+    }
+
+    /// Check every Immie of a team, returning each problem with the party slot it is in, in slot order. An Immie
+    /// that isn't legal at all only reports why, as the rest of the ruleset can't be checked for it.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames, abilities::fireball::Fireball};
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::species::{species_data::{SpeciesData, SpeciesTier}, species_map::SpeciesMap, base_stats::BaseStats};
+    /// use immie2d_shared::gameplay::item::item_map::ItemMap;
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::immie::legality_ruleset::{LegalityRuleset, RulesetPreset, RulesetViolation};
+    ///
+    /// let name = |name: &str| GlobalString::new(&name.to_string());
+    /// let fire = Elements::new(vec![ElementKind::Fire]);
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(SpeciesData::new(name("lavapup"), fire, BaseStats::new(50, 60, 40, 70)).with_tier(SpeciesTier::LittleCup));
+    /// species_map.add_species(SpeciesData::new(name("infernodon"), fire, BaseStats::new(150, 160, 140, 170)).with_tier(SpeciesTier::Ubers));
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Fireball>();
+    /// let data = GameData::new(1, species_map, ability_map, ItemMap::new());
+    ///
+    /// let pup = Immie::new(name("lavapup"), 5, AbilityNames::new(vec![name("fireball")]));
+    /// let dragon = Immie::new(name("infernodon"), 50, AbilityNames::default());
+    /// let team = vec![pup, dragon, pup];
+    /// assert_eq!(LegalityRuleset::from_preset(RulesetPreset::Ubers).check_team(&team, &data), vec![(2, RulesetViolation::DuplicateSpecies(name("lavapup")))]);
+    ///
+    /// let little_cup = LegalityRuleset::from_preset(RulesetPreset::LittleCup).with_banned_ability(name("fireball"));
+    /// assert_eq!(little_cup.check_team(&team[..2], &data), vec![
+    ///     (0, RulesetViolation::BannedAbility(name("fireball"))),
+    ///     (1, RulesetViolation::TierNotAllowed { species: name("infernodon"), tier: SpeciesTier::Ubers }),
+    ///     (1, RulesetViolation::LevelOutOfRange { level: 50, min_level: 1, max_level: 5 })
+    /// ]);
+    /// ```
+    pub fn check_team(&self, immies: &[Immie], data: &GameData) -> Vec<(usize, RulesetViolation)> {
+        let mut violations = Vec::new();
+        let mut seen_species = HashSet::new();
+        for (slot, immie) in immies.iter().enumerate() {
+            if let Err(error) = check_team_slot(slot, immie, data, self.rules) {
+                violations.push((slot, RulesetViolation::Illegal(error)));
+                continue;
+            }
+            let tier = data.get_species_map().get_species(immie.species).tier;
+            if !self.allowed_tiers.contains(&tier) {
+                violations.push((slot, RulesetViolation::TierNotAllowed { species: immie.species, tier }));
+            }
+            if immie.level < self.min_level || immie.level > self.max_level {
+                violations.push((slot, RulesetViolation::LevelOutOfRange { level: immie.level, min_level: self.min_level, max_level: self.max_level }));
+            }
+            for ability in immie.abilities.iter().filter(|ability| self.banned_abilities.contains(ability)) {
+                violations.push((slot, RulesetViolation::BannedAbility(ability)));
+            }
+            if let Some(item) = immie.held_item.filter(|item| self.banned_items.contains(item)) {
+                violations.push((slot, RulesetViolation::BannedItem(item)));
+            }
+            if !seen_species.insert(immie.species) && self.species_clause {
+                violations.push((slot, RulesetViolation::DuplicateSpecies(immie.species)));
+            }
+        }
+        return violations;
+    }
+}
@@ -0,0 +1,9 @@
+pub mod immie;
+pub mod bond;
+pub mod immie_summary;
+pub mod ability_edit;
+pub mod individual_values;
+pub mod legality;
+pub mod immie_release;
+pub mod legality_ruleset;
+pub mod breeding;
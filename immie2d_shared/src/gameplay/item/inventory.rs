@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use crate::engine_types::global_string::GlobalString;
+
+/* The items a player owns, and how many of each. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Inventory {
+    items: HashMap<GlobalString, u32>
+}
+
+impl Inventory {
+    pub fn new() -> Inventory {
+        return Inventory { items: HashMap::new() };
+    }
+
+    pub fn add_item(&mut self, item: GlobalString, count: u32) {
+        *self.items.entry(item).or_insert(0) += count;
+    }
+
+    /// Remove some of an item. Returns false, removing nothing, if there aren't enough.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::item::inventory::Inventory;
+    ///
+    /// let potion = GlobalString::new(&"potion".to_string());
+    /// let mut inventory = Inventory::new();
+    /// inventory.add_item(potion, 2);
+    /// assert!(!inventory.remove_item(potion, 3));
+    /// assert!(inventory.remove_item(potion, 2));
+    /// assert_eq!(inventory.get_count(potion), 0);
+    /// ```
+    pub fn remove_item(&mut self, item: GlobalString, count: u32) -> bool {
+        let Some(owned) = self.items.get_mut(&item) else {
+            return count == 0;
+        };
+        if *owned < count {
+            return false;
+        }
+        *owned -= count;
+        if *owned == 0 {
+            self.items.remove(&item);
+        }
+        return true;
+    }
+
+    pub fn get_count(&self, item: GlobalString) -> u32 {
+        return self.items.get(&item).copied().unwrap_or(0);
+    }
+
+    /// Iterate over every owned item and its count.
+    pub fn iter(&self) -> impl Iterator<Item = (GlobalString, u32)> + '_ {
+        return self.items.iter().map(|(item, count)| (*item, *count));
+    }
+}
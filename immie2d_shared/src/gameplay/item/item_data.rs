@@ -0,0 +1,45 @@
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::elements::element_kinds::ElementKind;
+use crate::gameplay::encounter::encounter_modifier::EncounterModifier;
+use crate::gameplay::status_condition::StatusCondition;
+
+/* What happens when an item is used. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ItemEffect {
+    RestoreHealth(u32),
+    /// Restores uses of a single chosen ability.
+    RestoreAbilityUses(u32),
+    /// Cures a specific status, or any status if None.
+    CureStatus(Option<StatusCondition>),
+    IncreaseBond(u32),
+    /// Sets the element the Immie can attune to in battle. See Immie::attunement
+    Attune(ElementKind),
+    /// Used on the player instead of an Immie, changing wild encounters for some steps.
+    EncounterModifier(EncounterModifier)
+}
+
+/* Definition of an item. The same definition is used both in and out of battle. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ItemData {
+    pub name: GlobalString,
+    pub effect: ItemEffect,
+    pub usable_in_battle: bool,
+    pub usable_outside_battle: bool
+}
+
+impl ItemData {
+    /// Create an item usable both in and out of battle.
+    pub fn new(name: GlobalString, effect: ItemEffect) -> ItemData {
+        return ItemData {
+            name,
+            effect,
+            usable_in_battle: true,
+            usable_outside_battle: true
+        };
+    }
+
+    /// Create an item only usable outside of battle.
+    pub fn new_overworld(name: GlobalString, effect: ItemEffect) -> ItemData {
+        return ItemData { usable_in_battle: false, ..ItemData::new(name, effect) };
+    }
+}
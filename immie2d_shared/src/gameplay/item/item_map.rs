@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+use crate::engine_types::global_string::GlobalString;
+
+use super::item_data::ItemData;
+
+/* Registry of all item definitions, keyed by item name. */
+pub struct ItemMap {
+    map: HashMap<GlobalString, ItemData>
+}
+
+impl ItemMap {
+    pub fn new() -> Self {
+        return ItemMap { map: HashMap::new() };
+    }
+
+    /// Add an item definition. Will replace any item already using the same name.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::item::{item_map::ItemMap, item_data::{ItemData, ItemEffect}};
+    ///
+    /// let mut map = ItemMap::new();
+    /// let potion = GlobalString::new(&"potion".to_string());
+    /// map.add_item(ItemData::new(potion, ItemEffect::RestoreHealth(20)));
+    /// assert!(map.get_item(potion).is_some());
+    /// ```
+    pub fn add_item(&mut self, item: ItemData) {
+        self.map.insert(item.name, item);
+    }
+
+    pub fn get_item(&self, name: GlobalString) -> Option<&ItemData> {
+        return self.map.get(&name);
+    }
+}
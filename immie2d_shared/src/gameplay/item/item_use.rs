@@ -0,0 +1,156 @@
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::ability::ability_map::AbilityMap;
+use crate::gameplay::encounter::encounter_modifier::{EncounterModifierEvent, EncounterModifiers};
+use crate::gameplay::immie::{bond::BondEvent, immie::Immie};
+use crate::gameplay::species::species_map::SpeciesMap;
+
+use super::inventory::Inventory;
+use super::item_data::ItemEffect;
+use super::item_map::ItemMap;
+
+/* Why an item could not be used. Item use requests come from clients, so they are validated rather than asserted. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ItemUseError {
+    UnknownItem,
+    NotInInventory,
+    NotUsableOutsideBattle,
+    InvalidPartySlot,
+    /// The item restores ability uses but no valid ability slot was chosen.
+    InvalidAbilitySlot,
+    /// Using the item would not change anything, so it is not consumed.
+    NoEffect,
+    Fainted,
+    /// The item is used on the player rather than an Immie, or the other way around.
+    WrongTarget
+}
+
+/* What the player chose to use an item on. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ItemTarget {
+    pub party_slot: usize,
+    pub ability_slot: Option<usize>
+}
+
+/// Use an item from the overworld menu on a party member, consuming one from the inventory if it had an effect.
+/// Fainted Immies can't be targeted by any item.
+/// ```
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+/// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+/// use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames, abilities::fireball::Fireball};
+/// use immie2d_shared::gameplay::immie::immie::Immie;
+/// use immie2d_shared::gameplay::item::{item_map::ItemMap, item_data::{ItemData, ItemEffect}, inventory::Inventory, item_use::{use_item_outside_battle, ItemTarget, ItemUseError}};
+///
+/// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+/// let mut species_map = SpeciesMap::new();
+/// species_map.add_species(species);
+/// let mut ability_map = AbilityMap::new();
+/// ability_map.add_ability::<Fireball>();
+///
+/// let ether = GlobalString::new(&"ether".to_string());
+/// let mut item_map = ItemMap::new();
+/// item_map.add_item(ItemData::new(ether, ItemEffect::RestoreAbilityUses(10)));
+/// let mut inventory = Inventory::new();
+/// inventory.add_item(ether, 1);
+///
+/// let mut immie = Immie::new(species.name, 5, AbilityNames::new(vec![GlobalString::new(&"fireball".to_string())]));
+/// let mut party = vec![immie];
+/// let target = ItemTarget { party_slot: 0, ability_slot: Some(0) };
+/// // Nothing to restore yet, so the ether is kept
+/// assert_eq!(use_item_outside_battle(&item_map, &species_map, &ability_map, &mut inventory, &mut party, ether, target), Err(ItemUseError::NoEffect));
+///
+/// party[0].ability_uses_spent[0] = 15;
+/// assert_eq!(use_item_outside_battle(&item_map, &species_map, &ability_map, &mut inventory, &mut party, ether, target), Ok(()));
+/// assert_eq!(party[0].ability_uses_spent[0], 5);
+/// assert_eq!(use_item_outside_battle(&item_map, &species_map, &ability_map, &mut inventory, &mut party, ether, target), Err(ItemUseError::NotInInventory));
+/// ```
+pub fn use_item_outside_battle(item_map: &ItemMap, species_map: &SpeciesMap, ability_map: &AbilityMap, inventory: &mut Inventory,
+    party: &mut [Immie], item_name: GlobalString, target: ItemTarget) -> Result<(), ItemUseError> {
+    let item = item_map.get_item(item_name).ok_or(ItemUseError::UnknownItem)?;
+    if !item.usable_outside_battle {
+        return Err(ItemUseError::NotUsableOutsideBattle);
+    }
+    if inventory.get_count(item_name) == 0 {
+        return Err(ItemUseError::NotInInventory);
+    }
+    if let ItemEffect::EncounterModifier(_) = item.effect {
+        return Err(ItemUseError::WrongTarget);
+    }
+    let immie = party.get_mut(target.party_slot).ok_or(ItemUseError::InvalidPartySlot)?;
+    if species_map.validate_immie(immie).is_err() {
+        return Err(ItemUseError::InvalidPartySlot);
+    }
+    if immie.is_fainted(&species_map.get_species_of(immie)) {
+        return Err(ItemUseError::Fainted);
+    }
+
+    let had_effect = match item.effect {
+        ItemEffect::RestoreHealth(amount) => immie.heal(amount) > 0,
+        ItemEffect::RestoreAbilityUses(amount) => {
+            let ability_slot = target.ability_slot.ok_or(ItemUseError::InvalidAbilitySlot)?;
+            let ability_name = immie.abilities.get_names().get(ability_slot).copied().ok_or(ItemUseError::InvalidAbilitySlot)?;
+            if !ability_map.is_ability_name(&ability_name.to_string()) {
+                return Err(ItemUseError::InvalidAbilitySlot);
+            }
+            immie.restore_uses(ability_slot, amount) > 0
+        },
+        ItemEffect::CureStatus(cured) => {
+            let cures = immie.status.is_some() && (cured.is_none() || cured == immie.status);
+            if cures {
+                immie.status = None;
+            }
+            cures
+        },
+        ItemEffect::IncreaseBond(amount) => {
+            let bond = immie.bond;
+            immie.apply_bond_event(BondEvent::Item(amount)) > bond
+        },
+        ItemEffect::Attune(element) => {
+            let changes = immie.attunement != Some(element);
+            immie.attunement = Some(element);
+            changes
+        },
+        ItemEffect::EncounterModifier(_) => unreachable!()
+    };
+    if !had_effect {
+        return Err(ItemUseError::NoEffect);
+    }
+    inventory.remove_item(item_name, 1);
+    return Ok(());
+}
+
+/// Use an encounter modifier item from the overworld menu, consuming one from the inventory. Returns the events to send
+/// to the client. See EncounterModifiers::apply()
+/// ```
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::gameplay::encounter::encounter_modifier::{EncounterModifier, EncounterModifierEvent, EncounterModifierKind, EncounterModifiers};
+/// use immie2d_shared::gameplay::item::{item_map::ItemMap, item_data::{ItemData, ItemEffect}, inventory::Inventory, item_use::{use_encounter_modifier, ItemUseError}};
+///
+/// let repel = GlobalString::new(&"repel".to_string());
+/// let potion = GlobalString::new(&"potion".to_string());
+/// let mut item_map = ItemMap::new();
+/// item_map.add_item(ItemData::new_overworld(repel, ItemEffect::EncounterModifier(EncounterModifier { kind: EncounterModifierKind::Repel, steps: 100 })));
+/// item_map.add_item(ItemData::new(potion, ItemEffect::RestoreHealth(20)));
+/// let mut inventory = Inventory::new();
+/// inventory.add_item(repel, 1);
+/// inventory.add_item(potion, 1);
+/// let mut modifiers = EncounterModifiers::new();
+///
+/// let events = use_encounter_modifier(&item_map, &mut inventory, &mut modifiers, repel).unwrap();
+/// assert_eq!(events, vec![EncounterModifierEvent::Started { kind: EncounterModifierKind::Repel, steps: 100 }]);
+/// assert_eq!(use_encounter_modifier(&item_map, &mut inventory, &mut modifiers, repel), Err(ItemUseError::NotInInventory));
+/// assert_eq!(use_encounter_modifier(&item_map, &mut inventory, &mut modifiers, potion), Err(ItemUseError::WrongTarget));
+/// ```
+pub fn use_encounter_modifier(item_map: &ItemMap, inventory: &mut Inventory, modifiers: &mut EncounterModifiers, item_name: GlobalString) -> Result<Vec<EncounterModifierEvent>, ItemUseError> {
+    let item = item_map.get_item(item_name).ok_or(ItemUseError::UnknownItem)?;
+    if !item.usable_outside_battle {
+        return Err(ItemUseError::NotUsableOutsideBattle);
+    }
+    let ItemEffect::EncounterModifier(modifier) = item.effect else {
+        return Err(ItemUseError::WrongTarget);
+    };
+    if !inventory.remove_item(item_name, 1) {
+        return Err(ItemUseError::NotInInventory);
+    }
+    return Ok(modifiers.apply(modifier));
+}
@@ -0,0 +1,4 @@
+pub mod item_data;
+pub mod item_map;
+pub mod inventory;
+pub mod item_use;
@@ -1,2 +1,19 @@
 pub mod elements;
-pub mod ability;
\ No newline at end of file
+pub mod ability;
+pub mod species;
+pub mod immie;
+pub mod battle;
+pub mod capture;
+pub mod item;
+pub mod game_rules;
+pub mod player_id;
+pub use immie2d_core::status_condition;
+pub mod game_data;
+pub mod encounter;
+pub mod tooltip;
+pub mod raid;
+pub mod challenge;
+pub mod rental;
+pub mod synced_settings;
+pub mod data_loader;
+pub mod tournament_bracket;
@@ -0,0 +1,96 @@
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::battle::battler::Battler;
+use crate::gameplay::immie::immie::Immie;
+use crate::gameplay::species::species_data::SpeciesData;
+
+/* An ability the boss uses once its health first drops to a percentage of its max health, instead of a normal action. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ScriptedMove {
+    /// Percentage of max health, from 0 to 100.
+    pub health_threshold: u32,
+    pub ability_slot: usize
+}
+
+/* Definition of a raid boss. Its health is scaled by the number of players, so raids are as hard with one player
+as with four. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct RaidBoss {
+    pub immie: Immie,
+    /// Multiplier of the boss' max health per player.
+    pub health_scale: u32,
+    /// How many abilities the boss uses each turn.
+    pub actions_per_turn: u32,
+    pub scripted_moves: Vec<ScriptedMove>,
+    /// Items every player receives when the boss is defeated.
+    pub rewards: Vec<(GlobalString, u32)>
+}
+
+impl RaidBoss {
+    /// A boss acting once per turn, without scripted moves or rewards. Will panic if the health scale is 0.
+    pub fn new(immie: Immie, health_scale: u32) -> RaidBoss {
+        assert!(health_scale > 0, "Raid boss health scale must be at least 1");
+        return RaidBoss { immie, health_scale, actions_per_turn: 1, scripted_moves: Vec::new(), rewards: Vec::new() };
+    }
+
+    /// Will panic if the boss would never act.
+    pub fn with_actions_per_turn(mut self, actions_per_turn: u32) -> RaidBoss {
+        assert!(actions_per_turn > 0, "Raid boss must act at least once per turn");
+        self.actions_per_turn = actions_per_turn;
+        return self;
+    }
+
+    /// Will panic if the threshold is above 100%, or the Immie has no ability in the slot.
+    pub fn with_scripted_move(mut self, health_threshold: u32, ability_slot: usize) -> RaidBoss {
+        assert!(health_threshold <= 100, "Scripted move health threshold {} is above 100%", health_threshold);
+        assert!(ability_slot < self.immie.abilities.get_count() as usize, "Raid boss has no ability in slot {}", ability_slot);
+        self.scripted_moves.push(ScriptedMove { health_threshold, ability_slot });
+        return self;
+    }
+
+    pub fn with_reward(mut self, item: GlobalString, count: u32) -> RaidBoss {
+        self.rewards.push((item, count));
+        return self;
+    }
+
+    /// Create the boss' battler for a raid with some number of players.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::raid::raid_boss::RaidBoss;
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"magmadon".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(100, 80, 80, 40));
+    /// let boss = RaidBoss::new(Immie::new(species.name, 50, AbilityNames::default()), 2);
+    /// let battler = boss.create_battler(&species, 3);
+    /// assert_eq!(battler.get_health(), 600);
+    /// assert_eq!(battler.get_stats().health, 600);
+    /// // Huge scales from data cap the health instead of overflowing
+    /// let battler = RaidBoss::new(Immie::new(species.name, 50, AbilityNames::default()), u32::MAX).create_battler(&species, 3);
+    /// assert_eq!(battler.get_health(), u32::MAX);
+    /// ```
+    pub fn create_battler(&self, species: &SpeciesData, player_count: u32) -> Battler {
+        let mut battler = Battler::new(self.immie, species);
+        battler.scale_health(self.health_scale.saturating_mul(player_count));
+        return battler;
+    }
+
+    /// Get the scripted move to use at some health, skipping moves that were already used.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    /// use immie2d_shared::gameplay::raid::raid_boss::RaidBoss;
+    ///
+    /// let abilities = AbilityNames::new(vec![GlobalString::new(&"fireball".to_string()), GlobalString::new(&"eruption".to_string())]);
+    /// let boss = RaidBoss::new(Immie::new(GlobalString::new(&"magmadon".to_string()), 50, abilities), 2).with_scripted_move(50, 1);
+    /// assert_eq!(boss.get_scripted_move(60, 100, &[false]), None);
+    /// assert_eq!(boss.get_scripted_move(50, 100, &[false]), Some(0));
+    /// assert_eq!(boss.get_scripted_move(10, 100, &[true]), None);
+    /// ```
+    pub fn get_scripted_move(&self, health: u32, max_health: u32, used: &[bool]) -> Option<usize> {
+        let health_percent = health as u64 * 100 / max_health.max(1) as u64;
+        return (0..self.scripted_moves.len()).find(|index| !used[*index] && health_percent <= self.scripted_moves[*index].health_threshold as u64);
+    }
+}
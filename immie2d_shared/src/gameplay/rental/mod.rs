@@ -0,0 +1 @@
+pub mod rental_team;
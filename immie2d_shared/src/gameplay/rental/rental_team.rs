@@ -0,0 +1,193 @@
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::ability::ability_names::{AbilityNames, MAX_ABILITIES_COUNT};
+use crate::gameplay::battle::{battle_side::BattleSide, battler::Battler};
+use crate::gameplay::game_data::GameData;
+use crate::gameplay::immie::immie::Immie;
+use crate::gameplay::immie::legality::MAX_TEAM_SIZE;
+use crate::gameplay::species::species_form::FormError;
+
+/* Why the rental teams data file could not be loaded, or a rental team can't be used with the current game data. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum RentalError {
+    Io(String),
+    Parse(String),
+    /// The file is well formed JSON but a team is not valid. Includes the reason.
+    Invalid(String),
+    UnknownTeam(GlobalString),
+    UnknownSpecies { team: GlobalString, species: GlobalString },
+    UnknownAbility { team: GlobalString, ability: GlobalString },
+    UnknownItem { team: GlobalString, item: GlobalString },
+    /// An Immie's form doesn't exist, or can't learn one of its abilities.
+    Form { team: GlobalString, error: FormError }
+}
+
+impl fmt::Display for RentalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            RentalError::Io(message) => write!(f, "Failed to read rental teams: {}", message),
+            RentalError::Parse(message) => write!(f, "Failed to parse rental teams: {}", message),
+            RentalError::Invalid(message) => write!(f, "Invalid rental teams: {}", message),
+            RentalError::UnknownTeam(team) => write!(f, "There is no rental team {}", team),
+            RentalError::UnknownSpecies { team, species } => write!(f, "Rental team {} uses unknown species {}", team, species),
+            RentalError::UnknownAbility { team, ability } => write!(f, "Rental team {} uses unknown ability {}", team, ability),
+            RentalError::UnknownItem { team, item } => write!(f, "Rental team {} uses unknown item {}", team, item),
+            RentalError::Form { team, error } => write!(f, "Rental team {}: {}", team, error)
+        };
+    }
+}
+
+/* A preset team lent to players for quick battles, so players without a roster can battle. Rental Immies are never
+owned, so nothing that happens to them in battle is persisted. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct RentalTeam {
+    pub name: GlobalString,
+    pub immies: Vec<Immie>
+}
+
+impl RentalTeam {
+    /// Check that every species, form, ability and held item of the team is in the game data, and that each form can
+    /// learn its Immie's abilities.
+    pub fn validate(&self, data: &GameData) -> Result<(), RentalError> {
+        for immie in self.immies.iter() {
+            if !data.get_species_map().is_species_name(immie.species) {
+                return Err(RentalError::UnknownSpecies { team: self.name, species: immie.species });
+            }
+            if let Some(ability) = immie.abilities.iter().find(|ability| !data.get_ability_map().is_ability_name(&ability.to_string())) {
+                return Err(RentalError::UnknownAbility { team: self.name, ability });
+            }
+            if let Some(item) = immie.held_item.filter(|item| data.get_item_map().get_item(*item).is_none()) {
+                return Err(RentalError::UnknownItem { team: self.name, item });
+            }
+            data.get_species_map().validate_immie(immie).map_err(|error| RentalError::Form { team: self.name, error })?;
+        }
+        return Ok(());
+    }
+
+    /// Create the battle side of a player using the team, with every Immie fully healthy.
+    /// ```
+    /// # use immie2d_shared::engine_types::global_string::GlobalString;
+    /// # use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, abilities::fireball::Fireball};
+    /// # use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// # use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    /// # use immie2d_shared::gameplay::item::{item_map::ItemMap, item_data::{ItemData, ItemEffect}};
+    /// use immie2d_shared::gameplay::game_data::GameData;
+    /// use immie2d_shared::gameplay::rental::rental_team::{RentalCatalog, RentalError};
+    ///
+    /// let mut species_map = SpeciesMap::new();
+    /// species_map.add_species(SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut ability_map = AbilityMap::new();
+    /// ability_map.add_ability::<Fireball>();
+    /// let mut item_map = ItemMap::new();
+    /// item_map.add_item(ItemData::new(GlobalString::new(&"oran_berry".to_string()), ItemEffect::RestoreHealth(10)));
+    /// let data = GameData::new(1, species_map, ability_map, item_map);
+    ///
+    /// let catalog = RentalCatalog::from_json(r#"[
+    ///     { "name": "blaze", "immies": [{ "species": "lavapup", "level": 50, "abilities": ["fireball"], "held_item": "oran_berry" }] },
+    ///     { "name": "broken", "immies": [{ "species": "lavapup", "level": 50, "abilities": ["tackle"] }] },
+    ///     { "name": "sticky", "immies": [{ "species": "lavapup", "level": 50, "abilities": ["fireball"], "held_item": "tar" }] }
+    /// ]"#).unwrap();
+    /// let side = catalog.get_team(GlobalString::new(&"blaze".to_string())).unwrap().create_side(&data).unwrap();
+    /// assert_eq!(side.get_active().get_health(), 50);
+    /// assert_eq!(side.get_active().get_immie().held_item, Some(GlobalString::new(&"oran_berry".to_string())));
+    ///
+    /// let broken = catalog.get_team(GlobalString::new(&"broken".to_string())).unwrap();
+    /// assert!(matches!(broken.create_side(&data), Err(RentalError::UnknownAbility { .. })));
+    /// let sticky = catalog.get_team(GlobalString::new(&"sticky".to_string())).unwrap();
+    /// assert!(matches!(sticky.create_side(&data), Err(RentalError::UnknownItem { .. })));
+    /// ```
+    pub fn create_side(&self, data: &GameData) -> Result<BattleSide, RentalError> {
+        self.validate(data)?;
+        let species_map = data.get_species_map();
+        return Ok(BattleSide::new(self.immies.iter().map(|immie| Battler::new(*immie, &species_map.get_species_of(immie))).collect()));
+    }
+}
+
+/* Every rental team, in data file order. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct RentalCatalog {
+    teams: Vec<RentalTeam>
+}
+
+impl RentalCatalog {
+    pub fn new() -> RentalCatalog {
+        return RentalCatalog { teams: Vec::new() };
+    }
+
+    /// Load rental teams from a JSON array. Teams aren't checked against the game data until they are used.
+    /// ```
+    /// use immie2d_shared::gameplay::rental::rental_team::RentalCatalog;
+    ///
+    /// let immie = r#"{ "species": "lavapup", "level": 50, "abilities": ["fireball"] }"#;
+    /// let team = |size: usize| format!(r#"[{{ "name": "blaze", "immies": [{}] }}]"#, vec![immie; size].join(", "));
+    /// assert!(RentalCatalog::from_json(&team(6)).is_ok());
+    /// assert!(RentalCatalog::from_json(&team(7)).is_err());
+    /// assert!(RentalCatalog::from_json(&team(0)).is_err());
+    /// ```
+    pub fn from_json(json: &str) -> Result<RentalCatalog, RentalError> {
+        let root: Value = serde_json::from_str(json).map_err(|err| RentalError::Parse(err.to_string()))?;
+        let array = root.as_array().ok_or(RentalError::Invalid("Expected an array of rental teams".to_string()))?;
+        let mut catalog = RentalCatalog::new();
+        for team in array {
+            let team = parse_team(team)?;
+            if catalog.get_team(team.name).is_some() {
+                return Err(RentalError::Invalid(format!("Rental team {} is defined more than once", team.name)));
+            }
+            catalog.teams.push(team);
+        }
+        return Ok(catalog);
+    }
+
+    pub fn load(path: &Path) -> Result<RentalCatalog, RentalError> {
+        let text = fs::read_to_string(path).map_err(|err| RentalError::Io(err.to_string()))?;
+        return RentalCatalog::from_json(&text);
+    }
+
+    pub fn get_team(&self, name: GlobalString) -> Option<&RentalTeam> {
+        return self.teams.iter().find(|team| team.name == name);
+    }
+
+    pub fn get_teams(&self) -> &[RentalTeam] {
+        return &self.teams;
+    }
+}
+
+fn parse_team(json: &Value) -> Result<RentalTeam, RentalError> {
+    let name = json["name"].as_str().ok_or(RentalError::Invalid("Rental team is missing a name".to_string()))?;
+    let invalid = |reason: &str| RentalError::Invalid(format!("Rental team [{}] {}", name, reason));
+    let immies = json["immies"].as_array().filter(|immies| !immies.is_empty() && immies.len() <= MAX_TEAM_SIZE)
+        .ok_or(invalid(&format!("needs 1 to {} Immies", MAX_TEAM_SIZE)))?;
+    let mut team = Vec::new();
+    for immie in immies {
+        let species = immie["species"].as_str().ok_or(invalid("has an Immie without a species"))?;
+        let level = immie["level"].as_u64().filter(|level| *level > 0 && *level <= u32::MAX as u64).ok_or(invalid("has an Immie without a valid level"))?;
+        let abilities = immie["abilities"].as_array().ok_or(invalid("has an Immie without abilities"))?;
+        if abilities.is_empty() || abilities.len() > MAX_ABILITIES_COUNT as usize {
+            return Err(invalid(&format!("has an Immie without 1 to {} abilities", MAX_ABILITIES_COUNT)));
+        }
+        let mut ability_names = Vec::new();
+        for ability in abilities {
+            let ability = GlobalString::new(&ability.as_str().ok_or(invalid("has an ability that is not a string"))?.to_string());
+            if ability_names.contains(&ability) {
+                return Err(invalid(&format!("has an Immie with duplicate ability {}", ability)));
+            }
+            ability_names.push(ability);
+        }
+        let mut rental = Immie::new(GlobalString::new(&species.to_string()), level as u32, AbilityNames::new(ability_names));
+        rental.held_item = match immie.get("held_item") {
+            None => None,
+            Some(item) => Some(GlobalString::new(&item.as_str().ok_or(invalid("has a held item that is not a string"))?.to_string()))
+        };
+        rental.form = match immie.get("form") {
+            None => None,
+            Some(form) => Some(GlobalString::new(&form.as_str().ok_or(invalid("has a form that is not a string"))?.to_string()))
+        };
+        team.push(rental);
+    }
+    return Ok(RentalTeam { name: GlobalString::new(&name.to_string()), immies: team });
+}
@@ -0,0 +1,23 @@
+use serde::{Deserialize, Serialize};
+
+/* The raw stat values of a species or form, before any in-battle modification. */
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct BaseStats {
+    pub health: u32,
+    pub attack: u32,
+    pub defense: u32,
+    pub speed: u32
+}
+
+impl BaseStats {
+    /// Create a new set of base stats.
+    /// ```
+    /// use immie2d_shared::gameplay::species::base_stats::BaseStats;
+    /// let stats = BaseStats::new(60, 70, 50, 90);
+    /// assert_eq!(stats.health, 60);
+    /// assert_eq!(stats.speed, 90);
+    /// ```
+    pub fn new(health: u32, attack: u32, defense: u32, speed: u32) -> BaseStats {
+        return BaseStats { health, attack, defense, speed };
+    }
+}
@@ -0,0 +1,56 @@
+use crate::engine_types::global_string::GlobalString;
+
+/* The abilities Immies of a species can know. Level up abilities can be known once the Immie reaches their level, event
+only abilities are only obtainable through distributions, and inherited abilities are passed down by a parent when
+breeding. Species without a learnset can know any ability. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Learnset {
+    /// Abilities and the level they are learned at.
+    pub level_up: Vec<(u32, GlobalString)>,
+    pub event_only: Vec<GlobalString>,
+    /// Abilities an offspring of the species can inherit from a parent that knows them, at any level.
+    pub inherited: Vec<GlobalString>
+}
+
+impl Learnset {
+    pub fn new() -> Learnset {
+        return Learnset { level_up: Vec::new(), event_only: Vec::new(), inherited: Vec::new() };
+    }
+
+    pub fn with_level_up(mut self, level: u32, ability: GlobalString) -> Learnset {
+        self.level_up.push((level, ability));
+        return self;
+    }
+
+    pub fn with_event_only(mut self, ability: GlobalString) -> Learnset {
+        self.event_only.push(ability);
+        return self;
+    }
+
+    pub fn with_inherited(mut self, ability: GlobalString) -> Learnset {
+        self.inherited.push(ability);
+        return self;
+    }
+
+    /// The lowest level an ability is learned at by leveling up, or None if it isn't.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::species::learnset::Learnset;
+    ///
+    /// let (ember, fireball) = (GlobalString::new(&"ember".to_string()), GlobalString::new(&"fireball".to_string()));
+    /// let learnset = Learnset::new().with_level_up(1, ember).with_level_up(20, fireball).with_level_up(5, fireball);
+    /// assert_eq!(learnset.get_learn_level(fireball), Some(5));
+    /// assert_eq!(learnset.get_learn_level(GlobalString::new(&"pursuit".to_string())), None);
+    /// ```
+    pub fn get_learn_level(&self, ability: GlobalString) -> Option<u32> {
+        return self.level_up.iter().filter(|(_, learned)| *learned == ability).map(|(level, _)| *level).min();
+    }
+
+    pub fn is_event_only(&self, ability: GlobalString) -> bool {
+        return self.event_only.contains(&ability);
+    }
+
+    pub fn is_inherited(&self, ability: GlobalString) -> bool {
+        return self.inherited.contains(&ability);
+    }
+}
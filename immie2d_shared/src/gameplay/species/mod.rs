@@ -0,0 +1,5 @@
+pub mod base_stats;
+pub mod species_data;
+pub mod species_form;
+pub mod species_map;
+pub mod learnset;
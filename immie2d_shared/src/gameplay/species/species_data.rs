@@ -0,0 +1,147 @@
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::elements::elements_data::Elements;
+
+use super::base_stats::BaseStats;
+
+pub const DEFAULT_CATCH_RATE: u32 = 45;
+/// Most breeding groups a species can be in.
+pub const MAX_BREEDING_GROUPS: usize = 2;
+
+/* An alternate form that an Immie of a species can temporarily take in battle while holding the required item. */
+#[derive(Clone, Copy, Debug)]
+pub struct TransformationData {
+    pub form_name: GlobalString,
+    pub required_item: GlobalString,
+    pub elements: Elements,
+    pub base_stats: BaseStats
+}
+
+/* How an Immie of a species permanently becomes another species. */
+#[derive(Clone, Copy, Debug)]
+pub struct EvolutionData {
+    pub species: GlobalString,
+    pub min_level: u32,
+    /// Bond required as well as the level, if any. See BondEvent
+    pub min_bond: Option<u32>
+}
+
+/* How strong a species is, which decides the rulesets it can battle in. Tiers are ordered from weakest to strongest. */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum SpeciesTier {
+    /// Unevolved species weak enough for Little Cup.
+    LittleCup,
+    Standard,
+    /// Species too strong for standard play.
+    Ubers
+}
+
+impl SpeciesTier {
+    pub fn get_name(self) -> &'static str {
+        return match self {
+            SpeciesTier::LittleCup => "little_cup",
+            SpeciesTier::Standard => "standard",
+            SpeciesTier::Ubers => "ubers"
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<SpeciesTier> {
+        return [SpeciesTier::LittleCup, SpeciesTier::Standard, SpeciesTier::Ubers].into_iter().find(|tier| tier.get_name() == name);
+    }
+}
+
+/* Data shared by every Immie of the same species. */
+#[derive(Clone, Copy, Debug)]
+pub struct SpeciesData {
+    pub name: GlobalString,
+    pub elements: Elements,
+    pub base_stats: BaseStats,
+    /// How easily wild Immies of this species are captured, from 1 to 255.
+    pub catch_rate: u32,
+    pub transformation: Option<TransformationData>,
+    pub evolution: Option<EvolutionData>,
+    pub tier: SpeciesTier,
+    /// Species can breed with species sharing a group. Species in no group can't breed at all.
+    pub breeding_groups: [Option<GlobalString>; MAX_BREEDING_GROUPS]
+}
+
+impl SpeciesData {
+    /// Create a species in the standard tier with no transformation and the default catch rate.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// assert!(species.transformation.is_none());
+    /// ```
+    pub fn new(name: GlobalString, elements: Elements, base_stats: BaseStats) -> SpeciesData {
+        return SpeciesData {
+            name,
+            elements,
+            base_stats,
+            catch_rate: DEFAULT_CATCH_RATE,
+            transformation: None,
+            evolution: None,
+            tier: SpeciesTier::Standard,
+            breeding_groups: [None; MAX_BREEDING_GROUPS]
+        };
+    }
+
+    /// Set the transformation of this species, returning the modified species data.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::species::{species_data::{SpeciesData, TransformationData}, base_stats::BaseStats};
+    ///
+    /// let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70))
+    ///     .with_transformation(TransformationData {
+    ///         form_name: GlobalString::new(&"mega lavapup".to_string()),
+    ///         required_item: GlobalString::new(&"lava stone".to_string()),
+    ///         elements: Elements::new(vec![ElementKind::Fire, ElementKind::Dragon]),
+    ///         base_stats: BaseStats::new(50, 90, 60, 80)
+    ///     });
+    /// assert!(species.transformation.is_some());
+    /// ```
+    pub fn with_transformation(mut self, transformation: TransformationData) -> SpeciesData {
+        self.transformation = Some(transformation);
+        return self;
+    }
+
+    pub fn with_evolution(mut self, evolution: EvolutionData) -> SpeciesData {
+        self.evolution = Some(evolution);
+        return self;
+    }
+
+    pub fn with_tier(mut self, tier: SpeciesTier) -> SpeciesData {
+        self.tier = tier;
+        return self;
+    }
+
+    /// Add the species to a breeding group.
+    /// Will panic if the species is already in MAX_BREEDING_GROUPS groups.
+    pub fn with_breeding_group(mut self, group: GlobalString) -> SpeciesData {
+        let slot = self.breeding_groups.iter().position(|slot| slot.is_none()).expect("Species can't be in more breeding groups");
+        self.breeding_groups[slot] = Some(group);
+        return self;
+    }
+
+    /// Whether Immies of the two species can breed, which they can if the species share a breeding group.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, base_stats::BaseStats};
+    ///
+    /// let name = |name: &str| GlobalString::new(&name.to_string());
+    /// let species = |species: &str| SpeciesData::new(name(species), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    /// let lavapup = species("lavapup").with_breeding_group(name("field"));
+    /// let cinderbat = species("cinderbat").with_breeding_group(name("flying")).with_breeding_group(name("field"));
+    /// let emberling = species("emberling").with_breeding_group(name("flying"));
+    /// assert!(lavapup.can_breed_with(&cinderbat));
+    /// assert!(!lavapup.can_breed_with(&emberling));
+    /// // Species in no group can't even breed with themselves
+    /// assert!(!species("infernodon").can_breed_with(&species("infernodon")));
+    /// ```
+    pub fn can_breed_with(&self, other: &SpeciesData) -> bool {
+        return self.breeding_groups.iter().flatten().any(|group| other.breeding_groups.contains(&Some(*group)));
+    }
+}
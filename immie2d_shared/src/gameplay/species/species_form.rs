@@ -0,0 +1,97 @@
+use std::fmt;
+
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::elements::elements_data::Elements;
+use crate::gameplay::encounter::encounter_conditions::{EncounterConditions, EncounterContext};
+
+use super::base_stats::BaseStats;
+use super::species_data::SpeciesData;
+
+/* A permanent variant of a species, such as a regional form. Forms only store what differs from the base species,
+so a form doesn't duplicate the whole species entry. Unlike a transformation, an Immie keeps its form for life. */
+#[derive(Clone, Debug)]
+pub struct SpeciesForm {
+    pub name: GlobalString,
+    /// Replaces the elements of the species, if set.
+    pub elements: Option<Elements>,
+    /// Replaces the base stats of the species, if set.
+    pub base_stats: Option<BaseStats>,
+    /// Abilities an Immie of this form can know. Empty allows any ability.
+    pub learnset: Vec<GlobalString>,
+    /// Regions that wild Immies of this form spawn in. Empty allows any region.
+    pub regions: Vec<GlobalString>,
+    pub conditions: EncounterConditions
+}
+
+impl SpeciesForm {
+    /// Create a form that changes nothing and spawns anywhere.
+    pub fn new(name: GlobalString) -> SpeciesForm {
+        return SpeciesForm {
+            name,
+            elements: None,
+            base_stats: None,
+            learnset: Vec::new(),
+            regions: Vec::new(),
+            conditions: EncounterConditions::default()
+        };
+    }
+
+    /// Whether a wild Immie spawned in this context can be of this form.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::{EncounterConditions, EncounterContext, TimeOfDay, Weather};
+    /// use immie2d_shared::gameplay::species::species_form::SpeciesForm;
+    ///
+    /// let desert = GlobalString::new(&"sunscorch desert".to_string());
+    /// let mut form = SpeciesForm::new(GlobalString::new(&"dune".to_string()));
+    /// form.regions = vec![desert];
+    /// form.conditions = EncounterConditions { times_of_day: vec![TimeOfDay::Day], ..Default::default() };
+    ///
+    /// let mut context = EncounterContext::new(TimeOfDay::Day, Weather::Clear);
+    /// assert!(!form.is_available(&context));
+    /// context.region = Some(desert);
+    /// assert!(form.is_available(&context));
+    /// context.time_of_day = TimeOfDay::Night;
+    /// assert!(!form.is_available(&context));
+    /// ```
+    pub fn is_available(&self, context: &EncounterContext) -> bool {
+        let in_region = self.regions.is_empty() || context.region.is_some_and(|region| self.regions.contains(&region));
+        return in_region && self.conditions.is_met(context);
+    }
+
+    /// Whether an Immie of this form can know an ability.
+    pub fn can_learn(&self, ability: GlobalString) -> bool {
+        return self.learnset.is_empty() || self.learnset.contains(&ability);
+    }
+
+    /// The species data with this form's elements and base stats applied.
+    pub fn apply(&self, species: &SpeciesData) -> SpeciesData {
+        let mut data = *species;
+        if let Some(elements) = self.elements {
+            data.elements = elements;
+        }
+        if let Some(base_stats) = self.base_stats {
+            data.base_stats = base_stats;
+        }
+        return data;
+    }
+}
+
+/* Why an Immie isn't valid for the forms of its species. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FormError {
+    UnknownSpecies(GlobalString),
+    UnknownForm { species: GlobalString, form: GlobalString },
+    /// The ability isn't in the learnset of the Immie's form.
+    AbilityNotLearnable { form: GlobalString, ability: GlobalString }
+}
+
+impl fmt::Display for FormError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            FormError::UnknownSpecies(species) => write!(f, "There is no species {}", species),
+            FormError::UnknownForm { species, form } => write!(f, "Species {} has no form {}", species, form),
+            FormError::AbilityNotLearnable { form, ability } => write!(f, "Form {} can't learn ability {}", form, ability)
+        };
+    }
+}
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::encounter::encounter_conditions::EncounterContext;
+use crate::gameplay::immie::immie::Immie;
+
+use super::learnset::Learnset;
+use super::species_data::SpeciesData;
+use super::species_form::{FormError, SpeciesForm};
+
+/* Registry of all species data, keyed by species name. */
+pub struct SpeciesMap {
+    map: HashMap<GlobalString, SpeciesData>,
+    forms: HashMap<GlobalString, Vec<SpeciesForm>>,
+    learnsets: HashMap<GlobalString, Learnset>
+}
+
+impl SpeciesMap {
+    pub fn new() -> Self {
+        return SpeciesMap { map: HashMap::new(), forms: HashMap::new(), learnsets: HashMap::new() };
+    }
+
+    /// Add a species to the registry. Will replace any species already using the same name.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_map::SpeciesMap, base_stats::BaseStats};
+    ///
+    /// let mut map = SpeciesMap::new();
+    /// map.add_species(SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// assert!(map.is_species_name(GlobalString::new(&"lavapup".to_string())));
+    /// ```
+    pub fn add_species(&mut self, species: SpeciesData) {
+        self.map.insert(species.name, species);
+    }
+
+    /// Get the data of a species.
+    /// Will panic if the species name doesn't exist. See SpeciesMap::is_species_name()
+    /// ``` should_panic
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::species::species_map::SpeciesMap;
+    /// let map = SpeciesMap::new();
+    /// // Will panic
+    /// let species = map.get_species(GlobalString::new(&"not a species".to_string()));
+    /// ```
+    pub fn get_species(&self, name: GlobalString) -> &SpeciesData {
+        return self.map.get(&name).expect(format!("Species name [{}] is not valid", name).as_str());
+    }
+
+    /// Check if a species name is valid.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::species::species_map::SpeciesMap;
+    /// let map = SpeciesMap::new();
+    /// assert!(map.is_species_name(GlobalString::new(&"lavapup".to_string())) == false);
+    /// ```
+    pub fn is_species_name(&self, name: GlobalString) -> bool {
+        return self.map.contains_key(&name);
+    }
+
+    /// Add a form to a species. Will replace any form of the species using the same name.
+    /// Will panic if the species name doesn't exist.
+    pub fn add_form(&mut self, species: GlobalString, form: SpeciesForm) {
+        assert!(self.is_species_name(species), "Cannot add form [{}] to unknown species [{}]", form.name, species);
+        let forms = self.forms.entry(species).or_default();
+        forms.retain(|existing| existing.name != form.name);
+        forms.push(form);
+    }
+
+    /// Get every form of a species, in the order they were added.
+    pub fn get_forms(&self, species: GlobalString) -> &[SpeciesForm] {
+        return match self.forms.get(&species) {
+            Some(forms) => forms,
+            None => &[]
+        };
+    }
+
+    pub fn get_form(&self, species: GlobalString, form: GlobalString) -> Option<&SpeciesForm> {
+        return self.get_forms(species).iter().find(|existing| existing.name == form);
+    }
+
+    /// Set the learnset of a species, replacing any it already has.
+    /// Will panic if the species name doesn't exist.
+    pub fn set_learnset(&mut self, species: GlobalString, learnset: Learnset) {
+        assert!(self.is_species_name(species), "Cannot set the learnset of unknown species [{}]", species);
+        self.learnsets.insert(species, learnset);
+    }
+
+    /// Get the learnset of a species, or None if it can know any ability.
+    pub fn get_learnset(&self, species: GlobalString) -> Option<&Learnset> {
+        return self.learnsets.get(&species);
+    }
+
+    /// Get the species data of an Immie with its form applied.
+    /// Will panic if the species or form doesn't exist. See SpeciesMap::validate_immie()
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_form::SpeciesForm, species_map::SpeciesMap, base_stats::BaseStats};
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    ///
+    /// let mut map = SpeciesMap::new();
+    /// let lavapup = GlobalString::new(&"lavapup".to_string());
+    /// map.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut tundra = SpeciesForm::new(GlobalString::new(&"tundra".to_string()));
+    /// tundra.elements = Some(Elements::new(vec![ElementKind::Water]));
+    /// map.add_form(lavapup, tundra);
+    ///
+    /// let mut immie = Immie::new(lavapup, 5, AbilityNames::default());
+    /// assert!(map.get_species_of(&immie).elements.has_elements(ElementKind::Fire));
+    /// immie.form = Some(GlobalString::new(&"tundra".to_string()));
+    /// let species = map.get_species_of(&immie);
+    /// assert!(species.elements.has_elements(ElementKind::Water));
+    /// assert_eq!(species.base_stats.health, 50);
+    /// ```
+    pub fn get_species_of(&self, immie: &Immie) -> SpeciesData {
+        return self.find_species_of(immie).unwrap_or_else(|err| panic!("{}", err));
+    }
+
+    /// Get the species data of an Immie with its form applied, or why it has none. For Immies that came from outside
+    /// the server, such as from storage or a client.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::species::{species_form::FormError, species_map::SpeciesMap};
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    ///
+    /// let missingno = GlobalString::new(&"missingno".to_string());
+    /// let immie = Immie::new(missingno, 5, AbilityNames::default());
+    /// assert_eq!(SpeciesMap::new().find_species_of(&immie).unwrap_err(), FormError::UnknownSpecies(missingno));
+    /// ```
+    pub fn find_species_of(&self, immie: &Immie) -> Result<SpeciesData, FormError> {
+        let species = self.map.get(&immie.species).ok_or(FormError::UnknownSpecies(immie.species))?;
+        return match immie.form {
+            Some(form) => Ok(self.get_form(immie.species, form).ok_or(FormError::UnknownForm { species: immie.species, form })?.apply(species)),
+            None => Ok(*species)
+        };
+    }
+
+    /// Pick the form of a wild Immie spawned in a context. Returns the first available form, or None for the base
+    /// species if no form is available.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::{EncounterContext, TimeOfDay, Weather};
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_form::SpeciesForm, species_map::SpeciesMap, base_stats::BaseStats};
+    ///
+    /// let mut map = SpeciesMap::new();
+    /// let lavapup = GlobalString::new(&"lavapup".to_string());
+    /// let tundra = GlobalString::new(&"tundra".to_string());
+    /// map.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut form = SpeciesForm::new(tundra);
+    /// form.regions = vec![GlobalString::new(&"frostpeak".to_string())];
+    /// map.add_form(lavapup, form);
+    ///
+    /// let mut context = EncounterContext::new(TimeOfDay::Day, Weather::Snow);
+    /// assert_eq!(map.select_form(lavapup, &context), None);
+    /// context.region = Some(GlobalString::new(&"frostpeak".to_string()));
+    /// assert_eq!(map.select_form(lavapup, &context), Some(tundra));
+    /// ```
+    pub fn select_form(&self, species: GlobalString, context: &EncounterContext) -> Option<GlobalString> {
+        return self.get_forms(species).iter().find(|form| form.is_available(context)).map(|form| form.name);
+    }
+
+    /// Check that an Immie's species and form exist, and that its form can learn all of its abilities.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+    /// use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+    /// use immie2d_shared::gameplay::species::{species_data::SpeciesData, species_form::{SpeciesForm, FormError}, species_map::SpeciesMap, base_stats::BaseStats};
+    /// use immie2d_shared::gameplay::immie::immie::Immie;
+    ///
+    /// let mut map = SpeciesMap::new();
+    /// let lavapup = GlobalString::new(&"lavapup".to_string());
+    /// let tundra = GlobalString::new(&"tundra".to_string());
+    /// let fireball = GlobalString::new(&"fireball".to_string());
+    /// map.add_species(SpeciesData::new(lavapup, Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70)));
+    /// let mut form = SpeciesForm::new(tundra);
+    /// form.learnset = vec![GlobalString::new(&"ice shard".to_string())];
+    /// map.add_form(lavapup, form);
+    ///
+    /// let mut immie = Immie::new(lavapup, 5, AbilityNames::new(vec![fireball]));
+    /// assert_eq!(map.validate_immie(&immie), Ok(()));
+    /// immie.form = Some(tundra);
+    /// assert_eq!(map.validate_immie(&immie), Err(FormError::AbilityNotLearnable { form: tundra, ability: fireball }));
+    /// immie.form = Some(GlobalString::new(&"desert".to_string()));
+    /// assert!(matches!(map.validate_immie(&immie), Err(FormError::UnknownForm { .. })));
+    /// ```
+    pub fn validate_immie(&self, immie: &Immie) -> Result<(), FormError> {
+        if !self.is_species_name(immie.species) {
+            return Err(FormError::UnknownSpecies(immie.species));
+        }
+        let Some(form_name) = immie.form else {
+            return Ok(());
+        };
+        let form = self.get_form(immie.species, form_name).ok_or(FormError::UnknownForm { species: immie.species, form: form_name })?;
+        if let Some(ability) = immie.abilities.iter().find(|ability| !form.can_learn(*ability)) {
+            return Err(FormError::AbilityNotLearnable { form: form_name, ability });
+        }
+        return Ok(());
+    }
+}
@@ -0,0 +1,214 @@
+/// Longest language tag in bytes, such as `zh-Hant-TW`.
+pub const MAX_LANGUAGE_LENGTH: usize = 16;
+
+/* How quickly dialogue and battle text is revealed. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum TextSpeed {
+    Slow,
+    Normal,
+    Fast
+}
+
+pub const ALL_TEXT_SPEEDS: [TextSpeed; 3] = [TextSpeed::Slow, TextSpeed::Normal, TextSpeed::Fast];
+
+impl TextSpeed {
+    /// Name used in config files.
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            TextSpeed::Slow => "slow",
+            TextSpeed::Normal => "normal",
+            TextSpeed::Fast => "fast"
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<TextSpeed> {
+        return ALL_TEXT_SPEEDS.iter().copied().find(|speed| speed.get_name() == name);
+    }
+
+    pub fn from_id(id: u8) -> Option<TextSpeed> {
+        return ALL_TEXT_SPEEDS.get(id as usize).copied();
+    }
+
+    /// How many characters a text box reveals each second.
+    pub fn get_characters_per_second(&self) -> u32 {
+        return match self {
+            TextSpeed::Slow => 20,
+            TextSpeed::Normal => 40,
+            TextSpeed::Fast => 80
+        };
+    }
+}
+
+/* How battles are animated on the client. Only changes how the client plays battle events, never how long the server
+waits for commands, so opponents with different paces battle on equal terms. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BattlePace {
+    /// Every animation at its full length.
+    Full,
+    /// Animations at half length.
+    Fast,
+    /// No animations, only the battle text.
+    Instant
+}
+
+pub const ALL_BATTLE_PACES: [BattlePace; 3] = [BattlePace::Full, BattlePace::Fast, BattlePace::Instant];
+
+impl BattlePace {
+    /// Name used in config files.
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            BattlePace::Full => "full",
+            BattlePace::Fast => "fast",
+            BattlePace::Instant => "instant"
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<BattlePace> {
+        return ALL_BATTLE_PACES.iter().copied().find(|pace| pace.get_name() == name);
+    }
+
+    /// Ids 0 and 1 are Instant and Full, so settings encoded when animations could only be turned off or on decode
+    /// as the matching pace.
+    pub fn get_id(&self) -> u8 {
+        return match self {
+            BattlePace::Instant => 0,
+            BattlePace::Full => 1,
+            BattlePace::Fast => 2
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<BattlePace> {
+        return match id {
+            0 => Some(BattlePace::Instant),
+            1 => Some(BattlePace::Full),
+            2 => Some(BattlePace::Fast),
+            _ => None
+        };
+    }
+
+    /// How long animations play for relative to their full length.
+    pub fn get_animation_scale(&self) -> f32 {
+        return match self {
+            BattlePace::Full => 1.0,
+            BattlePace::Fast => 0.5,
+            BattlePace::Instant => 0.0
+        };
+    }
+}
+
+/* The client settings stored in the player's profile, so they follow the player to every device. Only preferences
+that make sense on any device are synced. Key bindings and volumes depend on the device's keyboard and speakers, so
+they stay in the local config. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SyncedSettings {
+    /// Language of the localization catalog to use, such as `en`. At most MAX_LANGUAGE_LENGTH bytes.
+    pub language: String,
+    pub text_speed: TextSpeed,
+    pub battle_pace: BattlePace,
+    /// Assigned by the server, counting up each time the settings stored in the profile change. On a client, the
+    /// revision its settings were last synced at, or 0 if they never were. Client clocks are never compared.
+    pub revision: u64,
+    /// The player changed the settings on this device since they were last synced. Never encoded, since the settings
+    /// stored in a profile are never modified.
+    pub is_modified: bool
+}
+
+/* The result of merging a client's local settings with the settings stored in its profile. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct SettingsMerge {
+    pub settings: SyncedSettings,
+    /// The local settings won, so they should replace the settings stored in the profile.
+    pub should_upload: bool
+}
+
+impl SyncedSettings {
+    pub fn default() -> SyncedSettings {
+        return SyncedSettings { language: "en".to_string(), text_speed: TextSpeed::Normal, battle_pace: BattlePace::Full, revision: 0, is_modified: false };
+    }
+
+    pub fn is_valid_language(language: &str) -> bool {
+        return !language.is_empty() && language.len() <= MAX_LANGUAGE_LENGTH;
+    }
+
+    /// Merge the local settings of a client logging in with the settings stored in its profile, if any. Local changes
+    /// win only if they were made to the latest stored revision, and are then stored as the next revision. Otherwise
+    /// the stored settings win as a whole, so a device that was offline while another changed the settings takes
+    /// the newer settings instead of overwriting them. With nothing stored, the local settings are uploaded. An
+    /// invalid local language is replaced with the default before it can be uploaded.
+    /// ```
+    /// use immie2d_shared::gameplay::synced_settings::{BattlePace, SyncedSettings, TextSpeed};
+    ///
+    /// let stored = SyncedSettings { text_speed: TextSpeed::Fast, revision: 3, ..SyncedSettings::default() };
+    /// let fresh_device = SyncedSettings::default();
+    /// let merge = SyncedSettings::merge(&fresh_device, Some(&stored));
+    /// assert_eq!(merge.settings, stored);
+    /// assert!(!merge.should_upload);
+    ///
+    /// let edited = SyncedSettings { battle_pace: BattlePace::Instant, is_modified: true, ..stored.clone() };
+    /// let merge = SyncedSettings::merge(&edited, Some(&stored));
+    /// assert_eq!(merge.settings, SyncedSettings { revision: 4, is_modified: false, ..edited.clone() });
+    /// assert!(merge.should_upload);
+    ///
+    /// // Edited offline while another device changed the settings, whatever the device's clock says
+    /// let stale = SyncedSettings { revision: 2, ..edited.clone() };
+    /// assert_eq!(SyncedSettings::merge(&stale, Some(&stored)).settings, stored);
+    ///
+    /// let first = SyncedSettings::merge(&fresh_device, None);
+    /// assert!(first.should_upload);
+    /// assert_eq!(first.settings.revision, 1);
+    /// let too_long = SyncedSettings { language: "x".repeat(100), ..SyncedSettings::default() };
+    /// assert_eq!(SyncedSettings::merge(&too_long, None).settings.language, "en");
+    /// ```
+    pub fn merge(local: &SyncedSettings, stored: Option<&SyncedSettings>) -> SettingsMerge {
+        let mut local = local.clone();
+        if !SyncedSettings::is_valid_language(&local.language) {
+            local.language = SyncedSettings::default().language;
+        }
+        let revision = match stored {
+            None => 1,
+            Some(stored) if local.is_modified && local.revision == stored.revision => stored.revision.saturating_add(1),
+            Some(stored) => return SettingsMerge { settings: SyncedSettings { is_modified: false, ..stored.clone() }, should_upload: false }
+        };
+        return SettingsMerge { settings: SyncedSettings { revision, is_modified: false, ..local }, should_upload: true };
+    }
+
+    /// Encode as the revision, text speed, battle pace, and the length prefixed language.
+    /// ```
+    /// use immie2d_shared::gameplay::synced_settings::{BattlePace, SyncedSettings, TextSpeed};
+    ///
+    /// let settings = SyncedSettings { language: "fr".to_string(), text_speed: TextSpeed::Slow, battle_pace: BattlePace::Fast, revision: 42, is_modified: false };
+    /// assert_eq!(SyncedSettings::from_bytes(&settings.to_bytes()), Some(settings.clone()));
+    /// assert_eq!(SyncedSettings::from_bytes(&settings.to_bytes()[..11]), None);
+    /// let too_long = SyncedSettings { language: "x".repeat(100), ..settings };
+    /// assert_eq!(SyncedSettings::from_bytes(&too_long.to_bytes()), None);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(14 + self.language.len());
+        bytes.extend_from_slice(&self.revision.to_le_bytes());
+        bytes.push(self.text_speed as u8);
+        bytes.push(self.battle_pace.get_id());
+        bytes.extend_from_slice(&(self.language.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(self.language.as_bytes());
+        return bytes;
+    }
+
+    /// Decode settings, or None if the bytes are not valid settings.
+    pub fn from_bytes(bytes: &[u8]) -> Option<SyncedSettings> {
+        if bytes.len() < 14 {
+            return None;
+        }
+        let revision = u64::from_le_bytes(bytes[0..8].try_into().ok()?);
+        let text_speed = TextSpeed::from_id(bytes[8])?;
+        let battle_pace = BattlePace::from_id(bytes[9])?;
+        let language_length = u32::from_le_bytes(bytes[10..14].try_into().ok()?) as usize;
+        if language_length > MAX_LANGUAGE_LENGTH || bytes.len() != 14 + language_length {
+            return None;
+        }
+        let language = String::from_utf8(bytes[14..].to_vec()).ok()?;
+        if !SyncedSettings::is_valid_language(&language) {
+            return None;
+        }
+        return Some(SyncedSettings { language, text_speed, battle_pace, revision, is_modified: false });
+    }
+}
@@ -0,0 +1,113 @@
+use std::fmt;
+
+use crate::gameplay::ability::ability::BaseAbilityData;
+use crate::gameplay::elements::elements_data::Elements;
+use crate::gameplay::encounter::encounter_modifier::EncounterModifierKind;
+use crate::gameplay::item::item_data::{ItemData, ItemEffect};
+use crate::localization::description_template::{format_template, TemplateError};
+use crate::localization::localization_catalog::LocalizationCatalog;
+
+/// Separates the names of each element of a multi element ability.
+pub const ELEMENT_SEPARATOR: &str = "/";
+
+/* Why a tooltip could not be made. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TooltipError {
+    /// The catalog has no text for a key.
+    MissingEntry(String),
+    Template { key: String, error: TemplateError }
+}
+
+impl fmt::Display for TooltipError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            TooltipError::MissingEntry(key) => write!(f, "No localized text for [{}]", key),
+            TooltipError::Template { key, error } => write!(f, "Invalid description template [{}]: {}", key, error)
+        };
+    }
+}
+
+fn get_entry<'a>(catalog: &'a LocalizationCatalog, key: &str) -> Result<&'a str, TooltipError> {
+    return catalog.get(key).ok_or(TooltipError::MissingEntry(key.to_string()));
+}
+
+fn format_entry(catalog: &LocalizationCatalog, key: &str, values: &[(&str, String)]) -> Result<String, TooltipError> {
+    return format_template(get_entry(catalog, key)?, values).map_err(|error| TooltipError::Template { key: key.to_string(), error });
+}
+
+fn get_elements_text(catalog: &LocalizationCatalog, elements: &Elements) -> Result<String, TooltipError> {
+    let mut names = Vec::new();
+    for element in elements.iter() {
+        names.push(get_entry(catalog, &format!("element.{}", element.get_name()))?);
+    }
+    return Ok(names.join(ELEMENT_SEPARATOR));
+}
+
+/// Format the description of an ability from the `ability.<name>.description` template, filling in
+/// `{power}`, `{element}`, `{speed}` and `{max_uses}` from the ability's data.
+/// ```
+/// use immie2d_shared::gameplay::ability::{ability::Ability, abilities::fireball::Fireball};
+/// use immie2d_shared::gameplay::tooltip::{get_ability_tooltip, TooltipError};
+/// use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+///
+/// let mut catalog = LocalizationCatalog::from_json("en", r#"{
+///     "element.fire": "Fire",
+///     "ability.fireball.description": "Hurls a ball of flame for {power} {element} damage. {max_uses} uses."
+/// }"#).unwrap();
+/// let fireball = Fireball::new();
+/// assert_eq!(get_ability_tooltip(fireball.get_name(), fireball.get_base_ability_data(), &catalog), Ok("Hurls a ball of flame for 40 Fire damage. 25 uses.".to_string()));
+///
+/// catalog.insert("ability.fireball.description", "{accuracy}% accurate");
+/// assert!(matches!(get_ability_tooltip(fireball.get_name(), fireball.get_base_ability_data(), &catalog), Err(TooltipError::Template { .. })));
+/// ```
+pub fn get_ability_tooltip(name: &str, ability: &BaseAbilityData, catalog: &LocalizationCatalog) -> Result<String, TooltipError> {
+    let values = [
+        ("power", ability.power.to_string()),
+        ("element", get_elements_text(catalog, &ability.types)?),
+        ("speed", ability.speed.to_string()),
+        ("max_uses", ability.max_uses.to_string())
+    ];
+    return format_entry(catalog, &format!("ability.{}.description", name), &values);
+}
+
+/// Format the description of an item from the `item.<name>.description` template. Items with an amount fill in
+/// `{amount}`, and items curing a status fill in `{status}` from `status.<name>`, or `status.any` if it cures every status.
+/// ```
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::gameplay::item::item_data::{ItemData, ItemEffect};
+/// use immie2d_shared::gameplay::status_condition::StatusCondition;
+/// use immie2d_shared::gameplay::tooltip::{get_item_tooltip, TooltipError};
+/// use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+///
+/// let catalog = LocalizationCatalog::from_json("en", r#"{
+///     "item.potion.description": "Restores {amount} health.",
+///     "item.burn salve.description": "Cures {status}.",
+///     "status.burn": "a burn"
+/// }"#).unwrap();
+/// let potion = ItemData::new(GlobalString::new(&"potion".to_string()), ItemEffect::RestoreHealth(20));
+/// assert_eq!(get_item_tooltip(&potion, &catalog), Ok("Restores 20 health.".to_string()));
+/// let salve = ItemData::new(GlobalString::new(&"burn salve".to_string()), ItemEffect::CureStatus(Some(StatusCondition::Burn)));
+/// assert_eq!(get_item_tooltip(&salve, &catalog), Ok("Cures a burn.".to_string()));
+/// let elixir = ItemData::new(GlobalString::new(&"elixir".to_string()), ItemEffect::RestoreAbilityUses(5));
+/// assert_eq!(get_item_tooltip(&elixir, &catalog), Err(TooltipError::MissingEntry("item.elixir.description".to_string())));
+/// ```
+pub fn get_item_tooltip(item: &ItemData, catalog: &LocalizationCatalog) -> Result<String, TooltipError> {
+    let values = match item.effect {
+        ItemEffect::RestoreHealth(amount) | ItemEffect::RestoreAbilityUses(amount) | ItemEffect::IncreaseBond(amount) => {
+            vec![("amount", amount.to_string())]
+        },
+        ItemEffect::CureStatus(status) => {
+            let key = match status {
+                Some(status) => format!("status.{}", status.get_name()),
+                None => "status.any".to_string()
+            };
+            vec![("status", get_entry(catalog, &key)?.to_string())]
+        },
+        ItemEffect::Attune(element) => vec![("element", get_entry(catalog, &format!("element.{}", element.get_name()))?.to_string())],
+        ItemEffect::EncounterModifier(modifier) => match modifier.kind {
+            EncounterModifierKind::Repel => vec![("steps", modifier.steps.to_string())],
+            EncounterModifierKind::Lure(element) => vec![("steps", modifier.steps.to_string()), ("element", get_entry(catalog, &format!("element.{}", element.get_name()))?.to_string())]
+        }
+    };
+    return format_entry(catalog, &format!("item.{}.description", item.name.to_string()), &values);
+}
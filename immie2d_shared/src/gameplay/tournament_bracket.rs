@@ -0,0 +1,211 @@
+use super::player_id::PlayerId;
+
+/* How a tournament match was decided. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum MatchDecision {
+    Played,
+    /// The opponent didn't check in before the round's deadline.
+    Walkover,
+    /// There was no opponent, because the bracket had fewer entrants than slots.
+    Bye
+}
+
+impl MatchDecision {
+    pub fn get_id(self) -> u8 {
+        return match self {
+            MatchDecision::Played => 0,
+            MatchDecision::Walkover => 1,
+            MatchDecision::Bye => 2
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<MatchDecision> {
+        return match id {
+            0 => Some(MatchDecision::Played),
+            1 => Some(MatchDecision::Walkover),
+            2 => Some(MatchDecision::Bye),
+            _ => None
+        };
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MatchResult {
+    pub winner: PlayerId,
+    pub decision: MatchDecision
+}
+
+/* A match of a bracket. Players are None until the matches feeding into it are decided, or for a bye. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BracketMatch {
+    pub players: [Option<PlayerId>; 2],
+    /// Unix seconds both players must check in by, or the one who did wins by walkover. 0 until the round starts.
+    pub deadline: u64,
+    pub result: Option<MatchResult>
+}
+
+impl BracketMatch {
+    pub fn new(players: [Option<PlayerId>; 2]) -> BracketMatch {
+        return BracketMatch { players, deadline: 0, result: None };
+    }
+
+    pub fn has_player(&self, player: PlayerId) -> bool {
+        return self.players.contains(&Some(player));
+    }
+
+    /// The player facing another in this match, or None for a bye or a player not in it.
+    pub fn get_opponent(&self, player: PlayerId) -> Option<PlayerId> {
+        return match self.players {
+            [Some(first), second] if first == player => second,
+            [first, Some(second)] if second == player => first,
+            _ => None
+        };
+    }
+}
+
+/* Where a tournament is in its schedule. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum TournamentPhase {
+    SignUp { closes_at: u64 },
+    /// Rounds count from 0, the first round.
+    Running { round: u32 },
+    Finished { champion: PlayerId },
+    /// Too few players signed up.
+    Cancelled
+}
+
+/* The state of a tournament's bracket, sent to clients when they ask for it. Each round has half the matches of the
+one before it, and the winners of matches 2i and 2i + 1 meet in match i of the next round. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct BracketView {
+    pub tournament: u64,
+    pub phase: TournamentPhase,
+    pub entrants: Vec<PlayerId>,
+    pub rounds: Vec<Vec<BracketMatch>>
+}
+
+impl BracketView {
+    /// Encode the bracket for a client.
+    /// ```
+    /// use immie2d_shared::gameplay::player_id::PlayerId;
+    /// use immie2d_shared::gameplay::tournament_bracket::{BracketMatch, BracketView, MatchDecision, MatchResult, TournamentPhase};
+    ///
+    /// let mut first = BracketMatch::new([Some(PlayerId(1)), None]);
+    /// first.result = Some(MatchResult { winner: PlayerId(1), decision: MatchDecision::Bye });
+    /// let mut second = BracketMatch::new([Some(PlayerId(2)), Some(PlayerId(3))]);
+    /// second.deadline = 1_700_000_000;
+    /// let view = BracketView {
+    ///     tournament: 9,
+    ///     phase: TournamentPhase::Running { round: 0 },
+    ///     entrants: vec![PlayerId(1), PlayerId(2), PlayerId(3)],
+    ///     rounds: vec![vec![first, second], vec![BracketMatch::new([Some(PlayerId(1)), None])]]
+    /// };
+    /// assert_eq!(BracketView::from_bytes(&view.to_bytes()), Some(view.clone()));
+    /// assert_eq!(second.get_opponent(PlayerId(3)), Some(PlayerId(2)));
+    /// assert_eq!(first.get_opponent(PlayerId(1)), None);
+    ///
+    /// let finished = BracketView { phase: TournamentPhase::Finished { champion: PlayerId(1) }, ..view.clone() };
+    /// assert_eq!(BracketView::from_bytes(&finished.to_bytes()), Some(finished));
+    /// assert_eq!(BracketView::from_bytes(&view.to_bytes()[..20]), None);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.tournament.to_le_bytes());
+        match self.phase {
+            TournamentPhase::SignUp { closes_at } => {
+                bytes.push(0);
+                bytes.extend_from_slice(&closes_at.to_le_bytes());
+            },
+            TournamentPhase::Running { round } => {
+                bytes.push(1);
+                bytes.extend_from_slice(&round.to_le_bytes());
+            },
+            TournamentPhase::Finished { champion } => {
+                bytes.push(2);
+                bytes.extend_from_slice(&champion.0.to_le_bytes());
+            },
+            TournamentPhase::Cancelled => bytes.push(3)
+        }
+        bytes.extend_from_slice(&(self.entrants.len() as u32).to_le_bytes());
+        for entrant in self.entrants.iter() {
+            bytes.extend_from_slice(&entrant.0.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.rounds.len() as u32).to_le_bytes());
+        for round in self.rounds.iter() {
+            bytes.extend_from_slice(&(round.len() as u32).to_le_bytes());
+            for bracket_match in round.iter() {
+                for player in bracket_match.players {
+                    // Player 0 is never a real account, so it stands for no player
+                    bytes.extend_from_slice(&player.map(|player| player.0).unwrap_or(0).to_le_bytes());
+                }
+                bytes.extend_from_slice(&bracket_match.deadline.to_le_bytes());
+                match bracket_match.result {
+                    Some(result) => {
+                        bytes.push(result.decision.get_id() + 1);
+                        bytes.extend_from_slice(&result.winner.0.to_le_bytes());
+                    },
+                    None => bytes.push(0)
+                }
+            }
+        }
+        return bytes;
+    }
+
+    /// Decode a bracket, or None if the bytes are not a valid bracket.
+    pub fn from_bytes(bytes: &[u8]) -> Option<BracketView> {
+        let mut offset = 0;
+        let take_u64 = |offset: &mut usize| -> Option<u64> {
+            let value = u64::from_le_bytes(bytes.get(*offset..*offset + 8)?.try_into().ok()?);
+            *offset += 8;
+            return Some(value);
+        };
+        let take_u32 = |offset: &mut usize| -> Option<u32> {
+            let value = u32::from_le_bytes(bytes.get(*offset..*offset + 4)?.try_into().ok()?);
+            *offset += 4;
+            return Some(value);
+        };
+        let take_u8 = |offset: &mut usize| -> Option<u8> {
+            let value = *bytes.get(*offset)?;
+            *offset += 1;
+            return Some(value);
+        };
+        let tournament = take_u64(&mut offset)?;
+        let phase = match take_u8(&mut offset)? {
+            0 => TournamentPhase::SignUp { closes_at: take_u64(&mut offset)? },
+            1 => TournamentPhase::Running { round: take_u32(&mut offset)? },
+            2 => TournamentPhase::Finished { champion: PlayerId(take_u64(&mut offset)?) },
+            3 => TournamentPhase::Cancelled,
+            _ => return None
+        };
+        let entrant_count = take_u32(&mut offset)? as usize;
+        if entrant_count > bytes.len() / 8 {
+            return None;
+        }
+        let mut entrants = Vec::with_capacity(entrant_count);
+        for _ in 0..entrant_count {
+            entrants.push(PlayerId(take_u64(&mut offset)?));
+        }
+        let round_count = take_u32(&mut offset)?;
+        let mut rounds = Vec::new();
+        for _ in 0..round_count {
+            let match_count = take_u32(&mut offset)?;
+            let mut round = Vec::new();
+            for _ in 0..match_count {
+                let first = take_u64(&mut offset)?;
+                let second = take_u64(&mut offset)?;
+                let players = [first, second].map(|player| if player == 0 { None } else { Some(PlayerId(player)) });
+                let deadline = take_u64(&mut offset)?;
+                let result = match take_u8(&mut offset)? {
+                    0 => None,
+                    id => Some(MatchResult { decision: MatchDecision::from_id(id - 1)?, winner: PlayerId(take_u64(&mut offset)?) })
+                };
+                round.push(BracketMatch { players, deadline, result });
+            }
+            rounds.push(round);
+        }
+        if offset != bytes.len() {
+            return None;
+        }
+        return Some(BracketView { tournament, phase, entrants, rounds });
+    }
+}
@@ -1,2 +1,21 @@
+// The codebase favours explicit returns and inherent `default()`/`to_string()` constructors.
+#![allow(
+    clippy::needless_return,
+    clippy::should_implement_trait,
+    clippy::inherent_to_string_shadow_display,
+    clippy::new_without_default,
+    clippy::new_ret_no_self,
+    clippy::module_inception,
+    clippy::ptr_arg,
+    clippy::clone_on_copy,
+    clippy::unnecessary_unwrap,
+    clippy::expect_fun_call,
+    clippy::len_zero,
+    clippy::needless_borrow
+)]
+
 pub mod gameplay;
-pub mod engine_types;
\ No newline at end of file
+pub mod engine_types;
+pub mod world;
+pub mod localization;
+pub mod modding;
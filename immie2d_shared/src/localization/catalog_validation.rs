@@ -0,0 +1,104 @@
+use std::fmt;
+
+use super::localization_catalog::LocalizationCatalog;
+use super::message_format::{Message, MessageError};
+use super::plural_rules::PluralCategory;
+
+/* A problem with a translated catalog, found by comparing it to the reference catalog it was translated from. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CatalogIssue {
+    /// The reference has the key, but the translation doesn't.
+    MissingKey(String),
+    /// The translation has a key the reference doesn't, usually a renamed or removed entry.
+    UnusedKey(String),
+    /// The entry isn't a valid message.
+    Malformed { key: String, error: MessageError },
+    /// The entry uses an argument the reference entry doesn't, which the game will not provide.
+    UnknownArgument { key: String, argument: String },
+    /// A plural placeholder lacks a branch for one of the plural categories of the translation's language.
+    MissingPluralCategory { key: String, argument: String, category: PluralCategory }
+}
+
+impl CatalogIssue {
+    pub fn get_key(&self) -> &str {
+        return match self {
+            CatalogIssue::MissingKey(key) | CatalogIssue::UnusedKey(key) => key,
+            CatalogIssue::Malformed { key, .. } | CatalogIssue::UnknownArgument { key, .. } | CatalogIssue::MissingPluralCategory { key, .. } => key
+        };
+    }
+}
+
+impl fmt::Display for CatalogIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            CatalogIssue::MissingKey(key) => write!(f, "[{}] is missing", key),
+            CatalogIssue::UnusedKey(key) => write!(f, "[{}] is not in the reference catalog", key),
+            CatalogIssue::Malformed { key, error } => write!(f, "[{}] is malformed: {}", key, error),
+            CatalogIssue::UnknownArgument { key, argument } => write!(f, "[{}] uses unknown argument {{{}}}", key, argument),
+            CatalogIssue::MissingPluralCategory { key, argument, category } => {
+                write!(f, "[{}] has no {} branch for {{{}}}", key, category.get_name(), argument)
+            }
+        };
+    }
+}
+
+/// Check a translated catalog against the reference catalog it was translated from, returning every issue sorted by
+/// key. A catalog checked against itself reports only malformed entries and missing plural categories.
+/// ```
+/// use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+/// use immie2d_shared::localization::catalog_validation::{validate_catalog, CatalogIssue};
+/// use immie2d_shared::localization::plural_rules::PluralCategory;
+///
+/// let english = LocalizationCatalog::from_json("en", r#"{
+///     "battle.multi_hit": "It hit {count, plural, one {once} other {# times}}!",
+///     "battle.crit": "A critical hit!",
+///     "element.fire": "Fire"
+/// }"#).unwrap();
+/// assert_eq!(validate_catalog(&english, &english), vec![]);
+///
+/// let russian = LocalizationCatalog::from_json("ru", r#"{
+///     "battle.multi_hit": "Попадание {count, plural, one {# раз} other {# раза}} по {target}!",
+///     "element.fire": "Огонь {",
+///     "element.water": "Вода"
+/// }"#).unwrap();
+/// let issues = validate_catalog(&english, &russian);
+/// assert_eq!(issues[0], CatalogIssue::MissingKey("battle.crit".to_string()));
+/// assert_eq!(issues[1], CatalogIssue::UnknownArgument { key: "battle.multi_hit".to_string(), argument: "target".to_string() });
+/// assert_eq!(issues[2], CatalogIssue::MissingPluralCategory { key: "battle.multi_hit".to_string(), argument: "count".to_string(), category: PluralCategory::Few });
+/// assert!(matches!(issues[4], CatalogIssue::Malformed { .. }));
+/// assert_eq!(issues[5], CatalogIssue::UnusedKey("element.water".to_string()));
+/// ```
+pub fn validate_catalog(reference: &LocalizationCatalog, catalog: &LocalizationCatalog) -> Vec<CatalogIssue> {
+    let mut issues = Vec::new();
+    for key in reference.get_keys() {
+        if catalog.get(key).is_none() {
+            issues.push(CatalogIssue::MissingKey(key.to_string()));
+        }
+    }
+    let rule = catalog.get_plural_rule();
+    for key in catalog.get_keys() {
+        let Some(reference_text) = reference.get(key) else {
+            issues.push(CatalogIssue::UnusedKey(key.to_string()));
+            continue;
+        };
+        let message = match Message::parse(catalog.get(key).unwrap()) {
+            Ok(message) => message,
+            Err(error) => {
+                issues.push(CatalogIssue::Malformed { key: key.to_string(), error });
+                continue;
+            }
+        };
+        // A malformed reference entry is reported when checking the reference itself
+        if let Ok(reference_message) = Message::parse(reference_text) {
+            let known = reference_message.get_arguments();
+            for argument in message.get_arguments().into_iter().filter(|argument| !known.contains(argument)) {
+                issues.push(CatalogIssue::UnknownArgument { key: key.to_string(), argument });
+            }
+        }
+        for (argument, category) in message.get_missing_plural_categories(rule) {
+            issues.push(CatalogIssue::MissingPluralCategory { key: key.to_string(), argument, category });
+        }
+    }
+    issues.sort_by(|a, b| a.get_key().cmp(b.get_key()));
+    return issues;
+}
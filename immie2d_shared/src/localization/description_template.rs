@@ -0,0 +1,59 @@
+use std::fmt;
+
+/* Why a description template could not be formatted. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TemplateError {
+    /// The template uses a placeholder that has no value, usually a typo in the data.
+    UnknownPlaceholder(String),
+    /// A `{` without a matching `}`.
+    Unterminated
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            TemplateError::UnknownPlaceholder(name) => write!(f, "Unknown placeholder {{{}}}", name),
+            TemplateError::Unterminated => write!(f, "Unterminated placeholder")
+        };
+    }
+}
+
+/// Replace every `{name}` placeholder in a template with its value. `{{` and `}}` are a literal brace.
+/// ```
+/// use immie2d_shared::localization::description_template::{format_template, TemplateError};
+///
+/// let values = [("power", "40".to_string()), ("element", "Fire".to_string())];
+/// assert_eq!(format_template("Deals {power} {element} damage. {{sic}}", &values), Ok("Deals 40 Fire damage. {sic}".to_string()));
+/// assert_eq!(format_template("Deals {pwoer} damage", &values), Err(TemplateError::UnknownPlaceholder("pwoer".to_string())));
+/// assert_eq!(format_template("Deals {power damage", &values), Err(TemplateError::Unterminated));
+/// ```
+pub fn format_template(template: &str, values: &[(&str, String)]) -> Result<String, TemplateError> {
+    let mut out = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            },
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            },
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(TemplateError::Unterminated)
+                    }
+                }
+                let value = values.iter().find(|(key, _)| *key == name).ok_or(TemplateError::UnknownPlaceholder(name.clone()))?;
+                out.push_str(&value.1);
+            },
+            _ => out.push(c)
+        }
+    }
+    return Ok(out);
+}
@@ -0,0 +1,27 @@
+/* The grammatical gender of a noun in a language that has them. Nouns of languages without grammatical gender, such as
+English, have none. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Gender {
+    Masculine,
+    Feminine,
+    Neuter
+}
+
+impl Gender {
+    pub fn get_name(self) -> &'static str {
+        return match self {
+            Gender::Masculine => "masculine",
+            Gender::Feminine => "feminine",
+            Gender::Neuter => "neuter"
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<Gender> {
+        return match name {
+            "masculine" => Some(Gender::Masculine),
+            "feminine" => Some(Gender::Feminine),
+            "neuter" => Some(Gender::Neuter),
+            _ => None
+        };
+    }
+}
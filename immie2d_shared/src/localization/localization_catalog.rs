@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use serde_json::Value;
+
+use super::gender::Gender;
+use super::message_format::{Message, MessageError, MessageValue};
+use super::plural_rules::PluralRule;
+
+/* Why a localization catalog could not be loaded. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum LocalizationError {
+    Io(String),
+    Parse(String),
+    /// The catalog is well formed JSON but not a flat object of strings or gendered entries. Includes the offending key.
+    Invalid(String)
+}
+
+impl fmt::Display for LocalizationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            LocalizationError::Io(message) => write!(f, "Failed to read localization catalog: {}", message),
+            LocalizationError::Parse(message) => write!(f, "Failed to parse localization catalog: {}", message),
+            LocalizationError::Invalid(message) => write!(f, "Invalid localization catalog: {}", message)
+        };
+    }
+}
+
+/* The text of one language, looked up by key such as `ability.fireball.description`. Nouns can also have a
+grammatical gender, which messages select on. See Message */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LocalizationCatalog {
+    language: String,
+    entries: HashMap<String, String>,
+    genders: HashMap<String, Gender>
+}
+
+impl LocalizationCatalog {
+    pub fn new(language: &str) -> LocalizationCatalog {
+        return LocalizationCatalog { language: language.to_string(), entries: HashMap::new(), genders: HashMap::new() };
+    }
+
+    /// Load a catalog from a JSON object mapping keys to text, or to an object with the `text` and `gender` of a noun.
+    /// ```
+    /// use immie2d_shared::localization::localization_catalog::{LocalizationCatalog, LocalizationError};
+    /// use immie2d_shared::localization::gender::Gender;
+    ///
+    /// let catalog = LocalizationCatalog::from_json("fr", r#"{
+    ///     "element.fire": "Feu",
+    ///     "item.potion.name": { "text": "Potion", "gender": "feminine" }
+    /// }"#).unwrap();
+    /// assert_eq!(catalog.get("element.fire"), Some("Feu"));
+    /// assert_eq!(catalog.get("element.water"), None);
+    /// assert_eq!(catalog.get("item.potion.name"), Some("Potion"));
+    /// assert_eq!(catalog.get_gender("item.potion.name"), Some(Gender::Feminine));
+    /// assert_eq!(catalog.get_gender("element.fire"), None);
+    ///
+    /// assert!(matches!(LocalizationCatalog::from_json("en", r#"{ "element.fire": 2 }"#), Err(LocalizationError::Invalid(_))));
+    /// assert!(matches!(LocalizationCatalog::from_json("fr", r#"{ "item.potion.name": { "text": "Potion", "gender": "plural" } }"#), Err(LocalizationError::Invalid(_))));
+    /// ```
+    pub fn from_json(language: &str, json: &str) -> Result<LocalizationCatalog, LocalizationError> {
+        let root: Value = serde_json::from_str(json).map_err(|err| LocalizationError::Parse(err.to_string()))?;
+        let object = root.as_object().ok_or(LocalizationError::Invalid("Expected an object of keys to text".to_string()))?;
+        let mut catalog = LocalizationCatalog::new(language);
+        for (key, entry) in object {
+            if let Some(text) = entry.as_str() {
+                catalog.insert(key, text);
+                continue;
+            }
+            let invalid = || LocalizationError::Invalid(format!("Entry [{}] is not a string or an object with a text and gender", key));
+            let text = entry.get("text").and_then(Value::as_str).ok_or_else(invalid)?;
+            let gender = entry.get("gender").and_then(Value::as_str).and_then(Gender::from_name).ok_or_else(invalid)?;
+            catalog.insert_with_gender(key, text, gender);
+        }
+        return Ok(catalog);
+    }
+
+    pub fn load(language: &str, path: &Path) -> Result<LocalizationCatalog, LocalizationError> {
+        let text = fs::read_to_string(path).map_err(|err| LocalizationError::Io(err.to_string()))?;
+        return LocalizationCatalog::from_json(language, &text);
+    }
+
+    pub fn get_language(&self) -> &str {
+        return &self.language;
+    }
+
+    /// Add an entry, replacing any existing text for the key.
+    pub fn insert(&mut self, key: &str, text: &str) {
+        self.entries.insert(key.to_string(), text.to_string());
+    }
+
+    /// Add a noun entry, replacing any existing text and gender for the key.
+    pub fn insert_with_gender(&mut self, key: &str, text: &str, gender: Gender) {
+        self.insert(key, text);
+        self.genders.insert(key.to_string(), gender);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        return self.entries.get(key).map(|text| text.as_str());
+    }
+
+    pub fn get_gender(&self, key: &str) -> Option<Gender> {
+        return self.genders.get(key).copied();
+    }
+
+    /// Every key of the catalog, sorted.
+    pub fn get_keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = self.entries.keys().map(|key| key.as_str()).collect();
+        keys.sort();
+        return keys;
+    }
+
+    pub fn get_plural_rule(&self) -> PluralRule {
+        return PluralRule::for_language(&self.language);
+    }
+
+    /// Format the message of a key. Returns MessageError::MissingEntry if the catalog has no such key.
+    /// ```
+    /// use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+    /// use immie2d_shared::localization::message_format::{MessageError, MessageValue};
+    ///
+    /// let catalog = LocalizationCatalog::from_json("en", r#"{ "battle.multi_hit": "It hit {count, plural, one {once} other {# times}}!" }"#).unwrap();
+    /// assert_eq!(catalog.format("battle.multi_hit", &[("count", MessageValue::Number(3))]), Ok("It hit 3 times!".to_string()));
+    /// assert_eq!(catalog.format("battle.crit", &[]), Err(MessageError::MissingEntry("battle.crit".to_string())));
+    /// ```
+    pub fn format(&self, key: &str, values: &[(&str, MessageValue)]) -> Result<String, MessageError> {
+        let text = self.get(key).ok_or(MessageError::MissingEntry(key.to_string()))?;
+        return Message::parse(text)?.format(self, values);
+    }
+}
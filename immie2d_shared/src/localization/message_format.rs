@@ -0,0 +1,343 @@
+use std::fmt;
+
+use super::gender::Gender;
+use super::localization_catalog::LocalizationCatalog;
+use super::plural_rules::{PluralCategory, PluralRule};
+
+/* Why a message could not be parsed or formatted. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MessageError {
+    /// The message uses an argument that has no value, usually a typo in the catalog.
+    UnknownArgument(String),
+    /// A `{` without a matching `}`.
+    Unterminated,
+    /// A placeholder of a kind other than `plural` or `select`.
+    UnknownKind(String),
+    /// A plural branch that isn't a plural category or an exact `=N` count.
+    UnknownCategory(String),
+    /// A plural or select placeholder without an `other` branch. Includes the argument.
+    MissingOther(String),
+    /// A plural placeholder of an argument that isn't a number.
+    NotANumber(String),
+    /// A noun argument whose key isn't in the catalog.
+    MissingEntry(String)
+}
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            MessageError::UnknownArgument(name) => write!(f, "Unknown argument {{{}}}", name),
+            MessageError::Unterminated => write!(f, "Unterminated placeholder"),
+            MessageError::UnknownKind(kind) => write!(f, "Unknown placeholder kind [{}]", kind),
+            MessageError::UnknownCategory(category) => write!(f, "Unknown plural category [{}]", category),
+            MessageError::MissingOther(name) => write!(f, "Placeholder {{{}}} has no other branch", name),
+            MessageError::NotANumber(name) => write!(f, "Argument {{{}}} is not a number", name),
+            MessageError::MissingEntry(key) => write!(f, "Missing localization entry [{}]", key)
+        };
+    }
+}
+
+/* The value of a message argument. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum MessageValue {
+    Text(String),
+    Number(u64),
+    Gender(Gender),
+    /// The key of a catalog entry, such as `item.potion.name`. Formats as the entry's text, and selects by its gender.
+    Noun(String)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum MessagePart {
+    Text(String),
+    Argument(String),
+    /// `#` in a plural branch, the count.
+    Count,
+    Plural { name: String, branches: Vec<(String, Vec<MessagePart>)> },
+    Select { name: String, branches: Vec<(String, Vec<MessagePart>)> }
+}
+
+/* A parsed catalog message. `{name}` is replaced by an argument, `{name, plural, one {...} other {...}}` picks a branch
+by the plural category of a number in the catalog's language, with `=N` branches matching an exact count and `#`
+standing for the count, and `{name, select, feminine {...} other {...}}` picks a branch by a gender or text. Outside of
+branches `{{` and `}}` are a literal brace. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Message {
+    parts: Vec<MessagePart>
+}
+
+struct MessageParser {
+    chars: Vec<char>,
+    position: usize
+}
+
+impl MessageParser {
+    fn peek(&self) -> Option<char> {
+        return self.chars.get(self.position).copied();
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_whitespace()) {
+            self.position += 1;
+        }
+    }
+
+    /// Read up to, but not including, the first of the stop characters.
+    fn read_until(&mut self, stops: &[char]) -> Result<String, MessageError> {
+        let mut word = String::new();
+        loop {
+            match self.peek() {
+                Some(c) if stops.contains(&c) => return Ok(word.trim().to_string()),
+                Some(c) => word.push(c),
+                None => return Err(MessageError::Unterminated)
+            }
+            self.position += 1;
+        }
+    }
+
+    /// Parse parts until the end of the message, or the `}` closing a branch if `is_branch`.
+    fn parse_parts(&mut self, is_branch: bool, is_plural: bool) -> Result<Vec<MessagePart>, MessageError> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            self.position += 1;
+            let next = self.peek();
+            match c {
+                '{' if !is_branch && next == Some('{') => {
+                    self.position += 1;
+                    text.push('{');
+                },
+                '}' if !is_branch && next == Some('}') => {
+                    self.position += 1;
+                    text.push('}');
+                },
+                '}' if is_branch => {
+                    if !text.is_empty() {
+                        parts.push(MessagePart::Text(text));
+                    }
+                    return Ok(parts);
+                },
+                '#' if is_plural => {
+                    if !text.is_empty() {
+                        parts.push(MessagePart::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(MessagePart::Count);
+                },
+                '{' => {
+                    if !text.is_empty() {
+                        parts.push(MessagePart::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(self.parse_placeholder(is_plural)?);
+                },
+                _ => text.push(c)
+            }
+        }
+        if is_branch {
+            return Err(MessageError::Unterminated);
+        }
+        if !text.is_empty() {
+            parts.push(MessagePart::Text(text));
+        }
+        return Ok(parts);
+    }
+
+    /// Parse a placeholder after its opening `{`, through its closing `}`.
+    fn parse_placeholder(&mut self, is_plural: bool) -> Result<MessagePart, MessageError> {
+        let name = self.read_until(&['}', ','])?;
+        self.position += 1;
+        if self.chars[self.position - 1] == '}' {
+            return Ok(MessagePart::Argument(name));
+        }
+        let kind = self.read_until(&[','])?;
+        self.position += 1;
+        let is_plural = match kind.as_str() {
+            "plural" => true,
+            "select" => is_plural,
+            _ => return Err(MessageError::UnknownKind(kind))
+        };
+        let mut branches = Vec::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                Some('}') => {
+                    self.position += 1;
+                    break;
+                },
+                Some(_) => (),
+                None => return Err(MessageError::Unterminated)
+            }
+            let selector = self.read_until(&['{', '}'])?;
+            if self.peek() != Some('{') {
+                return Err(MessageError::Unterminated);
+            }
+            self.position += 1;
+            if kind == "plural" && PluralCategory::from_name(&selector).is_none() && !is_exact_selector(&selector) {
+                return Err(MessageError::UnknownCategory(selector));
+            }
+            branches.push((selector, self.parse_parts(true, is_plural)?));
+        }
+        if !branches.iter().any(|(selector, _)| selector == "other") {
+            return Err(MessageError::MissingOther(name));
+        }
+        return Ok(match kind.as_str() {
+            "plural" => MessagePart::Plural { name, branches },
+            _ => MessagePart::Select { name, branches }
+        });
+    }
+}
+
+fn is_exact_selector(selector: &str) -> bool {
+    return selector.strip_prefix('=').is_some_and(|count| count.parse::<u64>().is_ok());
+}
+
+fn get_branch<'a>(branches: &'a [(String, Vec<MessagePart>)], selectors: &[&str]) -> &'a [MessagePart] {
+    for selector in selectors {
+        if let Some((_, parts)) = branches.iter().find(|(branch, _)| branch == selector) {
+            return parts;
+        }
+    }
+    return &branches.iter().find(|(branch, _)| branch == "other").expect("Parsed messages always have an other branch").1;
+}
+
+fn get_value<'a>(values: &'a [(&str, MessageValue)], name: &str) -> Result<&'a MessageValue, MessageError> {
+    return values.iter().find(|(key, _)| *key == name).map(|(_, value)| value).ok_or(MessageError::UnknownArgument(name.to_string()));
+}
+
+fn format_parts(parts: &[MessagePart], catalog: &LocalizationCatalog, values: &[(&str, MessageValue)], count: Option<u64>, out: &mut String) -> Result<(), MessageError> {
+    for part in parts {
+        match part {
+            MessagePart::Text(text) => out.push_str(text),
+            MessagePart::Count => out.push_str(&count.expect("Counts are only parsed in plural branches").to_string()),
+            MessagePart::Argument(name) => match get_value(values, name)? {
+                MessageValue::Text(text) => out.push_str(text),
+                MessageValue::Number(number) => out.push_str(&number.to_string()),
+                MessageValue::Gender(gender) => out.push_str(gender.get_name()),
+                MessageValue::Noun(key) => out.push_str(catalog.get(key).ok_or(MessageError::MissingEntry(key.clone()))?)
+            },
+            MessagePart::Plural { name, branches } => {
+                let MessageValue::Number(number) = get_value(values, name)? else {
+                    return Err(MessageError::NotANumber(name.clone()));
+                };
+                let exact = format!("={}", number);
+                let category = catalog.get_plural_rule().get_category(*number).get_name();
+                format_parts(get_branch(branches, &[&exact, category]), catalog, values, Some(*number), out)?;
+            },
+            MessagePart::Select { name, branches } => {
+                let selector = match get_value(values, name)? {
+                    MessageValue::Text(text) => text.clone(),
+                    MessageValue::Number(number) => number.to_string(),
+                    MessageValue::Gender(gender) => gender.get_name().to_string(),
+                    MessageValue::Noun(key) => catalog.get_gender(key).map(|gender| gender.get_name()).unwrap_or("other").to_string()
+                };
+                format_parts(get_branch(branches, &[&selector]), catalog, values, count, out)?;
+            }
+        }
+    }
+    return Ok(());
+}
+
+fn collect_arguments(parts: &[MessagePart], out: &mut Vec<String>) {
+    for part in parts {
+        let (name, branches) = match part {
+            MessagePart::Text(_) | MessagePart::Count => continue,
+            MessagePart::Argument(name) => (name, None),
+            MessagePart::Plural { name, branches } | MessagePart::Select { name, branches } => (name, Some(branches))
+        };
+        if !out.contains(name) {
+            out.push(name.clone());
+        }
+        for (_, parts) in branches.into_iter().flatten() {
+            collect_arguments(parts, out);
+        }
+    }
+}
+
+fn collect_missing_categories(parts: &[MessagePart], rule: PluralRule, out: &mut Vec<(String, PluralCategory)>) {
+    for part in parts {
+        let branches = match part {
+            MessagePart::Plural { name, branches } => {
+                for category in rule.get_categories() {
+                    if !branches.iter().any(|(selector, _)| selector == category.get_name()) {
+                        out.push((name.clone(), *category));
+                    }
+                }
+                branches
+            },
+            MessagePart::Select { branches, .. } => branches,
+            _ => continue
+        };
+        for (_, parts) in branches {
+            collect_missing_categories(parts, rule, out);
+        }
+    }
+}
+
+impl Message {
+    /// Parse a message.
+    /// ```
+    /// use immie2d_shared::localization::message_format::{Message, MessageError};
+    ///
+    /// assert!(Message::parse("It hit {count, plural, one {once} other {# times}}!").is_ok());
+    /// assert_eq!(Message::parse("It hit {count, plural, one {once}}!"), Err(MessageError::MissingOther("count".to_string())));
+    /// assert_eq!(Message::parse("It hit {count, plural, single {once} other {# times}}!"), Err(MessageError::UnknownCategory("single".to_string())));
+    /// assert_eq!(Message::parse("It hit {count, plural, other {# times}"), Err(MessageError::Unterminated));
+    /// ```
+    pub fn parse(template: &str) -> Result<Message, MessageError> {
+        let mut parser = MessageParser { chars: template.chars().collect(), position: 0 };
+        return Ok(Message { parts: parser.parse_parts(false, false)? });
+    }
+
+    /// Format the message in the language of a catalog, which also provides the text and gender of noun arguments.
+    /// ```
+    /// use immie2d_shared::localization::localization_catalog::LocalizationCatalog;
+    /// use immie2d_shared::localization::message_format::{Message, MessageValue};
+    ///
+    /// let english = LocalizationCatalog::new("en");
+    /// let hits = Message::parse("It hit {count, plural, =0 {nothing} one {once} other {# times}}!").unwrap();
+    /// assert_eq!(hits.format(&english, &[("count", MessageValue::Number(3))]), Ok("It hit 3 times!".to_string()));
+    /// assert_eq!(hits.format(&english, &[("count", MessageValue::Number(1))]), Ok("It hit once!".to_string()));
+    /// assert_eq!(hits.format(&english, &[("count", MessageValue::Number(0))]), Ok("It hit nothing!".to_string()));
+    ///
+    /// let russian = LocalizationCatalog::new("ru");
+    /// let hits = Message::parse("Попадание {count, plural, one {# раз} few {# раза} many {# раз} other {# раза}}!").unwrap();
+    /// assert_eq!(hits.format(&russian, &[("count", MessageValue::Number(3))]), Ok("Попадание 3 раза!".to_string()));
+    ///
+    /// let french = LocalizationCatalog::from_json("fr", r#"{
+    ///     "item.potion.name": { "text": "Potion", "gender": "feminine" },
+    ///     "item.repel.name": { "text": "Repousse", "gender": "masculine" }
+    /// }"#).unwrap();
+    /// let used = Message::parse("{item, select, feminine {La} other {Le}} {item} a été utilisé{item, select, feminine {e} other {}}.").unwrap();
+    /// let potion = [("item", MessageValue::Noun("item.potion.name".to_string()))];
+    /// assert_eq!(used.format(&french, &potion), Ok("La Potion a été utilisée.".to_string()));
+    /// let repel = [("item", MessageValue::Noun("item.repel.name".to_string()))];
+    /// assert_eq!(used.format(&french, &repel), Ok("Le Repousse a été utilisé.".to_string()));
+    /// ```
+    pub fn format(&self, catalog: &LocalizationCatalog, values: &[(&str, MessageValue)]) -> Result<String, MessageError> {
+        let mut out = String::new();
+        format_parts(&self.parts, catalog, values, None, &mut out)?;
+        return Ok(out);
+    }
+
+    /// The name of every argument used, in the order first used.
+    pub fn get_arguments(&self) -> Vec<String> {
+        let mut arguments = Vec::new();
+        collect_arguments(&self.parts, &mut arguments);
+        return arguments;
+    }
+
+    /// The argument and category of every plural placeholder lacking a branch for one of the categories of a rule.
+    /// These fall back to the `other` branch, which is usually a translation mistake.
+    /// ```
+    /// use immie2d_shared::localization::message_format::Message;
+    /// use immie2d_shared::localization::plural_rules::{PluralCategory, PluralRule};
+    ///
+    /// let hits = Message::parse("{count, plural, one {# раз} other {# раза}}").unwrap();
+    /// assert_eq!(hits.get_missing_plural_categories(PluralRule::OneOther), vec![]);
+    /// assert_eq!(hits.get_missing_plural_categories(PluralRule::EastSlavic), vec![("count".to_string(), PluralCategory::Few), ("count".to_string(), PluralCategory::Many)]);
+    /// ```
+    pub fn get_missing_plural_categories(&self, rule: PluralRule) -> Vec<(String, PluralCategory)> {
+        let mut missing = Vec::new();
+        collect_missing_categories(&self.parts, rule, &mut missing);
+        return missing;
+    }
+}
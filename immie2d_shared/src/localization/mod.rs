@@ -0,0 +1,6 @@
+pub mod localization_catalog;
+pub mod description_template;
+pub mod plural_rules;
+pub mod gender;
+pub mod message_format;
+pub mod catalog_validation;
@@ -0,0 +1,115 @@
+/* The plural form of a count, named as in the Unicode CLDR. Which categories are used depends on the language. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum PluralCategory {
+    Zero,
+    One,
+    Two,
+    Few,
+    Many,
+    Other
+}
+
+impl PluralCategory {
+    pub fn get_name(self) -> &'static str {
+        return match self {
+            PluralCategory::Zero => "zero",
+            PluralCategory::One => "one",
+            PluralCategory::Two => "two",
+            PluralCategory::Few => "few",
+            PluralCategory::Many => "many",
+            PluralCategory::Other => "other"
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<PluralCategory> {
+        return match name {
+            "zero" => Some(PluralCategory::Zero),
+            "one" => Some(PluralCategory::One),
+            "two" => Some(PluralCategory::Two),
+            "few" => Some(PluralCategory::Few),
+            "many" => Some(PluralCategory::Many),
+            "other" => Some(PluralCategory::Other),
+            _ => None
+        };
+    }
+}
+
+/* How a language picks the plural form of a whole number. Languages are grouped by rule rather than listed one by
+one, since many share the same rule. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PluralRule {
+    /// One for 1, other for the rest, such as English, German and Spanish.
+    OneOther,
+    /// One for 0 and 1, other for the rest, such as French and Portuguese.
+    ZeroOneOther,
+    /// One for 1, 21, 31..., few for 2-4, 22-24..., many for the rest, such as Russian and Ukrainian.
+    EastSlavic,
+    /// One for 1 only, few for 2-4, 22-24..., many for the rest.
+    Polish,
+    /// One for 1, few for 2-4, other for the rest, such as Czech and Slovak.
+    CzechSlovak,
+    /// No plural forms, such as Japanese, Korean and Chinese.
+    NoPlural
+}
+
+impl PluralRule {
+    /// The rule of a language code such as `en` or `pt-BR`. Unknown languages use OneOther.
+    /// ```
+    /// use immie2d_shared::localization::plural_rules::PluralRule;
+    ///
+    /// assert_eq!(PluralRule::for_language("en"), PluralRule::OneOther);
+    /// assert_eq!(PluralRule::for_language("pt-BR"), PluralRule::ZeroOneOther);
+    /// assert_eq!(PluralRule::for_language("ja"), PluralRule::NoPlural);
+    /// ```
+    pub fn for_language(language: &str) -> PluralRule {
+        let base = language.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+        return match base.as_str() {
+            "fr" | "pt" => PluralRule::ZeroOneOther,
+            "ru" | "uk" | "be" => PluralRule::EastSlavic,
+            "pl" => PluralRule::Polish,
+            "cs" | "sk" => PluralRule::CzechSlovak,
+            "ja" | "ko" | "zh" | "th" | "vi" | "id" => PluralRule::NoPlural,
+            _ => PluralRule::OneOther
+        };
+    }
+
+    /// The plural category of a count.
+    /// ```
+    /// use immie2d_shared::localization::plural_rules::{PluralCategory, PluralRule};
+    ///
+    /// assert_eq!(PluralRule::OneOther.get_category(1), PluralCategory::One);
+    /// assert_eq!(PluralRule::OneOther.get_category(0), PluralCategory::Other);
+    /// assert_eq!(PluralRule::ZeroOneOther.get_category(0), PluralCategory::One);
+    /// assert_eq!(PluralRule::EastSlavic.get_category(21), PluralCategory::One);
+    /// assert_eq!(PluralRule::EastSlavic.get_category(23), PluralCategory::Few);
+    /// assert_eq!(PluralRule::EastSlavic.get_category(12), PluralCategory::Many);
+    /// assert_eq!(PluralRule::Polish.get_category(21), PluralCategory::Many);
+    /// ```
+    pub fn get_category(self, count: u64) -> PluralCategory {
+        let (last, last_two) = (count % 10, count % 100);
+        let is_few = (2..=4).contains(&last) && !(12..=14).contains(&last_two);
+        return match self {
+            PluralRule::OneOther if count == 1 => PluralCategory::One,
+            PluralRule::ZeroOneOther if count <= 1 => PluralCategory::One,
+            PluralRule::EastSlavic if last == 1 && last_two != 11 => PluralCategory::One,
+            PluralRule::EastSlavic if is_few => PluralCategory::Few,
+            PluralRule::EastSlavic => PluralCategory::Many,
+            PluralRule::Polish if count == 1 => PluralCategory::One,
+            PluralRule::Polish if is_few => PluralCategory::Few,
+            PluralRule::Polish => PluralCategory::Many,
+            PluralRule::CzechSlovak if count == 1 => PluralCategory::One,
+            PluralRule::CzechSlovak if (2..=4).contains(&count) => PluralCategory::Few,
+            _ => PluralCategory::Other
+        };
+    }
+
+    /// Every category a count can have in the language, which a plural message should have a branch for.
+    pub fn get_categories(self) -> &'static [PluralCategory] {
+        return match self {
+            PluralRule::OneOther | PluralRule::ZeroOneOther => &[PluralCategory::One, PluralCategory::Other],
+            PluralRule::EastSlavic | PluralRule::Polish => &[PluralCategory::One, PluralCategory::Few, PluralCategory::Many],
+            PluralRule::CzechSlovak => &[PluralCategory::One, PluralCategory::Few, PluralCategory::Other],
+            PluralRule::NoPlural => &[PluralCategory::Other]
+        };
+    }
+}
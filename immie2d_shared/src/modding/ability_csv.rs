@@ -0,0 +1,223 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde_json::{Map, Number, Value};
+
+use super::data_pack::{parse_abilities_json, DataPackError};
+
+/// Columns of an ability spreadsheet, in order. Each is the key of the same name in an abilities file.
+pub const ABILITY_CSV_COLUMNS: [&str; 9] = ["name", "category", "elements", "power", "speed", "accuracy", "max_uses", "flags", "script"];
+
+/// Separates the entries of the list columns, elements and flags, within a cell.
+pub const CSV_LIST_SEPARATOR: char = '|';
+
+const LIST_COLUMNS: [&str; 2] = ["elements", "flags"];
+const NUMBER_COLUMNS: [&str; 4] = ["power", "speed", "accuracy", "max_uses"];
+
+/* A difference between the abilities in a data file and a spreadsheet. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum AbilityChange {
+    Added(String),
+    Removed(String),
+    Changed { name: String, column: &'static str, old: String, new: String }
+}
+
+impl fmt::Display for AbilityChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            AbilityChange::Added(name) => write!(f, "+ {}", name),
+            AbilityChange::Removed(name) => write!(f, "- {}", name),
+            AbilityChange::Changed { name, column, old, new } => write!(f, "~ {}.{}: [{}] -> [{}]", name, column, old, new)
+        };
+    }
+}
+
+/// The cells of every ability in an abilities file, in file order. Optional keys that are missing are empty cells, so
+/// an export never adds defaults that weren't in the file.
+fn get_rows(json: &str, namespace: Option<&str>) -> Result<Vec<Vec<String>>, DataPackError> {
+    // Only valid data is exported, so a spreadsheet always starts from something the server would load.
+    parse_abilities_json(json, namespace)?;
+    let root: Value = serde_json::from_str(json).map_err(|err| DataPackError::Parse(err.to_string()))?;
+    let mut rows = Vec::new();
+    for entry in root.as_array().into_iter().flatten() {
+        let entry = entry.as_object().ok_or(DataPackError::Invalid("Expected an array of entries".to_string()))?;
+        if let Some(key) = entry.keys().find(|key| !ABILITY_CSV_COLUMNS.contains(&key.as_str())) {
+            return Err(DataPackError::Invalid(format!("Ability key [{}] has no spreadsheet column", key)));
+        }
+        let row = ABILITY_CSV_COLUMNS.iter().map(|column| match entry.get(*column) {
+            None => String::new(),
+            Some(Value::String(text)) => text.clone(),
+            Some(Value::Array(list)) => list.iter().map(|item| item.as_str().unwrap_or_default()).collect::<Vec<_>>().join(&CSV_LIST_SEPARATOR.to_string()),
+            Some(value) => value.to_string()
+        }).collect();
+        rows.push(row);
+    }
+    return Ok(rows);
+}
+
+/// Convert an abilities file to CSV for editing in a spreadsheet, with a header row of ABILITY_CSV_COLUMNS. Fails if
+/// the file isn't valid, or has data the spreadsheet has no column for.
+/// ```
+/// use immie2d_shared::modding::ability_csv::abilities_to_csv;
+///
+/// let json = r#"[
+///     { "name": "magma_ball", "category": "attack", "elements": ["fire", "ground"], "power": 70, "max_uses": 10, "flags": ["projectile"] },
+///     { "name": "howl", "category": "status", "elements": ["standard"], "power": 0, "speed": 1.5, "accuracy": 90, "max_uses": 20, "script": "scripts/howl.rhai" }
+/// ]"#;
+/// assert_eq!(abilities_to_csv(json, None).unwrap(), "name,category,elements,power,speed,accuracy,max_uses,flags,script
+/// magma_ball,attack,fire|ground,70,,,10,projectile,
+/// howl,status,standard,0,1.5,90,20,,scripts/howl.rhai
+/// ");
+/// assert!(abilities_to_csv(&json.replace("\"max_uses\": 10", "\"max_uses\": 10, \"notes\": \"nerf?\""), None).is_err());
+/// ```
+pub fn abilities_to_csv(json: &str, namespace: Option<&str>) -> Result<String, DataPackError> {
+    let mut csv = ABILITY_CSV_COLUMNS.join(",");
+    csv.push('\n');
+    for row in get_rows(json, namespace)? {
+        csv.push_str(&row.iter().map(|cell| quote_cell(cell)).collect::<Vec<_>>().join(","));
+        csv.push('\n');
+    }
+    return Ok(csv);
+}
+
+/// Convert a spreadsheet saved as CSV back into an abilities file. The result is checked the same way the server
+/// loads it, and converted back to CSV to check nothing in the spreadsheet was lost or changed on the way.
+/// ```
+/// use immie2d_shared::modding::ability_csv::{abilities_to_csv, csv_to_abilities};
+///
+/// let csv = "name,category,elements,power,speed,accuracy,max_uses,flags,script
+/// magma_ball,attack,fire|ground,75,,95,10,projectile|contact,
+/// \"howl, loudly\",status,standard,0,1.5,,20,,
+/// ";
+/// let json = csv_to_abilities(csv, None).unwrap();
+/// assert!(json.contains("\"flags\": [\n      \"projectile\",\n      \"contact\"\n    ]"));
+/// assert_eq!(abilities_to_csv(&json, None).unwrap(), csv);
+///
+/// // Problems are reported, with the line if it's a problem with the spreadsheet itself
+/// assert_eq!(csv_to_abilities(&csv.replace("fire|ground", "fire|lava"), None).unwrap_err().to_string(), "Invalid data pack: Unknown element [lava]");
+/// assert_eq!(csv_to_abilities(&csv.replace(",75,", ",strong,"), None).unwrap_err().to_string(), "Invalid data pack: Line 2 has power [strong], which isn't a non negative number");
+/// assert!(csv_to_abilities(&csv.replace("name,category", "category,name"), None).is_err());
+/// ```
+pub fn csv_to_abilities(csv: &str, namespace: Option<&str>) -> Result<String, DataPackError> {
+    let mut lines = csv.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    let header = lines.next().map(|(_, line)| parse_csv_line(line)).transpose()?.unwrap_or_default();
+    if header != ABILITY_CSV_COLUMNS {
+        return Err(DataPackError::Invalid(format!("Expected the columns {}", ABILITY_CSV_COLUMNS.join(","))));
+    }
+    let mut entries = Vec::new();
+    let mut expected_rows = Vec::new();
+    for (index, line) in lines {
+        let cells = parse_csv_line(line)?;
+        if cells.len() != ABILITY_CSV_COLUMNS.len() {
+            return Err(DataPackError::Invalid(format!("Line {} has {} cells, expected {}", index + 1, cells.len(), ABILITY_CSV_COLUMNS.len())));
+        }
+        let mut entry = Map::new();
+        for (column, cell) in ABILITY_CSV_COLUMNS.iter().zip(cells.iter()) {
+            if cell.is_empty() {
+                continue;
+            }
+            let value = if LIST_COLUMNS.contains(column) {
+                Value::Array(cell.split(CSV_LIST_SEPARATOR).map(|item| Value::String(item.trim().to_string())).collect())
+            } else if NUMBER_COLUMNS.contains(column) {
+                parse_number(cell).ok_or(DataPackError::Invalid(format!("Line {} has {} [{}], which isn't a non negative number", index + 1, column, cell)))?
+            } else {
+                Value::String(cell.clone())
+            };
+            entry.insert(column.to_string(), value);
+        }
+        entries.push(Value::Object(entry));
+        expected_rows.push(cells);
+    }
+    let json = serde_json::to_string_pretty(&Value::Array(entries)).map_err(|err| DataPackError::Parse(err.to_string()))?;
+    // Round trip, which also validates the abilities
+    for (index, (row, expected)) in get_rows(&json, namespace)?.iter().zip(expected_rows.iter()).enumerate() {
+        if let Some(column) = (0..row.len()).find(|column| row[*column] != expected[*column].split(CSV_LIST_SEPARATOR).map(str::trim).collect::<Vec<_>>().join(&CSV_LIST_SEPARATOR.to_string())) {
+            return Err(DataPackError::Invalid(format!("Ability {} changed {} from [{}] to [{}] when converted", index + 1, ABILITY_CSV_COLUMNS[column], expected[column], row[column])));
+        }
+    }
+    return Ok(json);
+}
+
+/// Every difference between the abilities in a data file and a spreadsheet, to review before importing it. Abilities
+/// are matched by name, and reported in the order of the data file followed by any added abilities.
+/// ```
+/// use immie2d_shared::modding::ability_csv::{abilities_to_csv, diff_abilities, AbilityChange};
+///
+/// let json = r#"[
+///     { "name": "magma_ball", "category": "attack", "elements": ["fire"], "power": 70, "max_uses": 10 },
+///     { "name": "howl", "category": "status", "elements": ["standard"], "power": 0, "max_uses": 20 }
+/// ]"#;
+/// let csv = abilities_to_csv(json, None).unwrap()
+///     .replace("magma_ball,attack,fire,70", "magma_ball,attack,fire,65")
+///     .replace("howl", "growl");
+/// let changes = diff_abilities(json, &csv, None).unwrap();
+/// assert_eq!(changes, vec![
+///     AbilityChange::Changed { name: "magma_ball".to_string(), column: "power", old: "70".to_string(), new: "65".to_string() },
+///     AbilityChange::Removed("howl".to_string()),
+///     AbilityChange::Added("growl".to_string())
+/// ]);
+/// assert_eq!(changes[0].to_string(), "~ magma_ball.power: [70] -> [65]");
+/// ```
+pub fn diff_abilities(json: &str, csv: &str, namespace: Option<&str>) -> Result<Vec<AbilityChange>, DataPackError> {
+    let old_rows = get_rows(json, namespace)?;
+    let new_rows = get_rows(&csv_to_abilities(csv, namespace)?, namespace)?;
+    let new_by_name: HashMap<&str, &Vec<String>> = new_rows.iter().map(|row| (row[0].as_str(), row)).collect();
+    let mut changes = Vec::new();
+    for old in old_rows.iter() {
+        let Some(new) = new_by_name.get(old[0].as_str()) else {
+            changes.push(AbilityChange::Removed(old[0].clone()));
+            continue;
+        };
+        for (column, name) in ABILITY_CSV_COLUMNS.iter().enumerate().skip(1) {
+            if old[column] != new[column] {
+                changes.push(AbilityChange::Changed { name: old[0].clone(), column: name, old: old[column].clone(), new: new[column].clone() });
+            }
+        }
+    }
+    for new in new_rows.iter().filter(|new| !old_rows.iter().any(|old| old[0] == new[0])) {
+        changes.push(AbilityChange::Added(new[0].clone()));
+    }
+    return Ok(changes);
+}
+
+/// Whole numbers stay whole, so `70` doesn't come back as `70.0`.
+fn parse_number(cell: &str) -> Option<Value> {
+    if let Ok(whole) = cell.parse::<u64>() {
+        return Some(Value::Number(whole.into()));
+    }
+    let number = cell.parse::<f64>().ok().filter(|number| *number >= 0.0)?;
+    return Number::from_f64(number).map(Value::Number);
+}
+
+/// Quote a cell if it has a comma or quote, doubling its quotes.
+fn quote_cell(cell: &str) -> String {
+    if !cell.contains([',', '"']) {
+        return cell.to_string();
+    }
+    return format!("\"{}\"", cell.replace('"', "\"\""));
+}
+
+/// Split a line into cells, unquoting quoted cells. Cells can't span lines.
+fn parse_csv_line(line: &str) -> Result<Vec<String>, DataPackError> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut is_quoted = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, is_quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            },
+            ('"', true) => is_quoted = false,
+            ('"', false) if cell.is_empty() => is_quoted = true,
+            (',', false) => cells.push(std::mem::take(&mut cell)),
+            _ => cell.push(c)
+        }
+    }
+    if is_quoted {
+        return Err(DataPackError::Parse(format!("Unterminated quote in [{}]", line)));
+    }
+    cells.push(cell);
+    return Ok(cells);
+}
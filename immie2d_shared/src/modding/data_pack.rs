@@ -0,0 +1,509 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::ability::ability::{AbilityCategory, BaseAbilityData};
+use crate::gameplay::ability::ability_flags::AbilityFlags;
+use crate::gameplay::ability::ability_map::AbilityMap;
+use crate::gameplay::ability::ability_script::AbilityScript;
+use crate::gameplay::elements::{element_kinds::ElementKind, elements_data::Elements};
+use crate::gameplay::encounter::encounter_modifier::{EncounterModifier, EncounterModifierKind};
+use crate::gameplay::encounter::encounter_table::parse_conditions;
+use crate::gameplay::item::item_data::{ItemData, ItemEffect};
+use crate::gameplay::item::item_map::ItemMap;
+use crate::gameplay::species::{base_stats::BaseStats, species_data::{SpeciesData, SpeciesTier, MAX_BREEDING_GROUPS}, species_form::SpeciesForm, species_map::SpeciesMap};
+use crate::world::tile_map::TileMap;
+use crate::world::tiled_import::{import_tmj, import_tmx, TiledImportError};
+
+use super::namespace::{apply_namespace, get_namespace};
+use super::pack_manifest::PackManifest;
+
+/// File in a pack directory with the pack's manifest. The content files are optional.
+pub const MANIFEST_FILE: &str = "pack.json";
+pub const SPECIES_FILE: &str = "species.json";
+pub const ABILITIES_FILE: &str = "abilities.json";
+pub const ITEMS_FILE: &str = "items.json";
+/// Directory in a pack directory with Tiled maps, each named after its file name under the pack's namespace.
+pub const MAPS_DIRECTORY: &str = "maps";
+
+/* Why a data pack could not be loaded or installed. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum DataPackError {
+    Io(String),
+    Parse(String),
+    /// The files are well formed JSON but not valid pack data. Includes the reason.
+    Invalid(String),
+    Map(TiledImportError),
+    /// Something the pack adds already exists, either in core data, an earlier pack or the pack itself.
+    Conflict { namespace: String, name: GlobalString },
+    /// Two enabled packs use the same namespace.
+    DuplicateNamespace(String)
+}
+
+impl fmt::Display for DataPackError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            DataPackError::Io(message) => write!(f, "Failed to read data pack: {}", message),
+            DataPackError::Parse(message) => write!(f, "Failed to parse data pack: {}", message),
+            DataPackError::Invalid(message) => write!(f, "Invalid data pack: {}", message),
+            DataPackError::Map(error) => write!(f, "{}", error),
+            DataPackError::Conflict { namespace, name } => write!(f, "Data pack {} adds {}, which already exists", namespace, name),
+            DataPackError::DuplicateNamespace(namespace) => write!(f, "More than one data pack uses the namespace {}", namespace)
+        };
+    }
+}
+
+/* Species, abilities, items and maps added by a third party, all named under the pack's namespace such as
+`mymod:lavapup`. Packs are installed after core data and can only add, never replace. */
+pub struct DataPack {
+    pub manifest: PackManifest,
+    pub species: Vec<SpeciesData>,
+    /// Forms of the pack's species, with the name of the species each belongs to. See parse_species_forms_json()
+    pub forms: Vec<(GlobalString, SpeciesForm)>,
+    pub abilities: Vec<(GlobalString, BaseAbilityData)>,
+    /// Scripts of the pack's abilities that reference one. See load_ability_scripts()
+    pub ability_scripts: HashMap<GlobalString, Arc<AbilityScript>>,
+    pub items: Vec<ItemData>,
+    pub maps: Vec<TileMap>
+}
+
+impl DataPack {
+    pub fn new(manifest: PackManifest) -> DataPack {
+        return DataPack { manifest, species: Vec::new(), forms: Vec::new(), abilities: Vec::new(), ability_scripts: HashMap::new(), items: Vec::new(), maps: Vec::new() };
+    }
+
+    /// Load a pack from its directory. See MANIFEST_FILE
+    pub fn load(directory: &Path) -> Result<DataPack, DataPackError> {
+        let mut pack = DataPack::new(PackManifest::from_json(&read_file(&directory.join(MANIFEST_FILE))?)?);
+        if directory.join(SPECIES_FILE).exists() {
+            pack.add_species_json(&read_file(&directory.join(SPECIES_FILE))?)?;
+        }
+        if directory.join(ABILITIES_FILE).exists() {
+            let json = read_file(&directory.join(ABILITIES_FILE))?;
+            pack.add_abilities_json(&json)?;
+            pack.ability_scripts.extend(load_ability_scripts(&json, Some(&pack.manifest.namespace), directory)?);
+        }
+        if directory.join(ITEMS_FILE).exists() {
+            pack.add_items_json(&read_file(&directory.join(ITEMS_FILE))?)?;
+        }
+        for path in list_map_files(&directory.join(MAPS_DIRECTORY))? {
+            if let Some(map) = load_map_file(&path, Some(&pack.manifest.namespace))? {
+                pack.maps.push(map);
+            }
+        }
+        return Ok(pack);
+    }
+
+    /// Add species and their forms from a JSON array. See parse_species_json() and parse_species_forms_json()
+    pub fn add_species_json(&mut self, json: &str) -> Result<(), DataPackError> {
+        self.species.extend(parse_species_json(json, Some(&self.manifest.namespace))?);
+        self.forms.extend(parse_species_forms_json(json, Some(&self.manifest.namespace))?);
+        return Ok(());
+    }
+
+    /// Add abilities from a JSON array. Scripts they reference aren't loaded, as there is no directory to load them
+    /// from. See parse_abilities_json()
+    pub fn add_abilities_json(&mut self, json: &str) -> Result<(), DataPackError> {
+        self.abilities.extend(parse_abilities_json(json, Some(&self.manifest.namespace))?);
+        return Ok(());
+    }
+
+    /// Add items from a JSON array. See parse_items_json()
+    pub fn add_items_json(&mut self, json: &str) -> Result<(), DataPackError> {
+        self.items.extend(parse_items_json(json, Some(&self.manifest.namespace))?);
+        return Ok(());
+    }
+
+    /// Add everything in the pack to the registries and maps. Nothing is added if anything conflicts with what
+    /// already exists, or with something else in the pack.
+    /// ```
+    /// use std::collections::HashMap;
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::ability::ability_map::AbilityMap;
+    /// use immie2d_shared::gameplay::item::item_map::ItemMap;
+    /// use immie2d_shared::gameplay::species::species_map::SpeciesMap;
+    /// use immie2d_shared::modding::{data_pack::{DataPack, DataPackError}, pack_manifest::PackManifest};
+    ///
+    /// let manifest = PackManifest::from_json(r#"{ "namespace": "mymod", "name": "My Mod", "version": "1.0.0" }"#).unwrap();
+    /// let mut pack = DataPack::new(manifest);
+    /// pack.add_species_json(r#"[{ "name": "embercat", "elements": ["fire"], "base_stats": [50, 60, 40, 70], "forms": [{ "name": "ashen" }] }]"#).unwrap();
+    /// pack.add_abilities_json(r#"[{ "name": "magma_ball", "category": "attack", "elements": ["fire"], "power": 70, "max_uses": 10 }]"#).unwrap();
+    /// pack.add_items_json(r#"[{ "name": "mega_potion", "effect": "restore_health", "amount": 80 }]"#).unwrap();
+    /// assert!(pack.add_items_json(r#"[{ "name": "othermod:potion", "effect": "cure_status" }]"#).is_err());
+    ///
+    /// let (mut species, mut abilities, mut items, mut maps) = (SpeciesMap::new(), AbilityMap::new(), ItemMap::new(), HashMap::new());
+    /// pack.install(&mut species, &mut abilities, &mut items, &mut maps).unwrap();
+    /// assert!(species.is_species_name(GlobalString::new(&"mymod:embercat".to_string())));
+    /// assert_eq!(species.get_forms(GlobalString::new(&"mymod:embercat".to_string())).len(), 1);
+    /// assert!(abilities.is_ability_name("mymod:magma_ball"));
+    /// assert!(items.get_item(GlobalString::new(&"mymod:mega_potion".to_string())).is_some());
+    ///
+    /// // Installing again conflicts with what the first install added
+    /// let error = pack.install(&mut species, &mut abilities, &mut items, &mut maps).unwrap_err();
+    /// assert!(matches!(error, DataPackError::Conflict { .. }));
+    /// ```
+    pub fn install(&self, species_map: &mut SpeciesMap, ability_map: &mut AbilityMap, item_map: &mut ItemMap, maps: &mut HashMap<GlobalString, TileMap>) -> Result<(), DataPackError> {
+        let mut added: HashSet<GlobalString> = HashSet::new();
+        let names = self.species.iter().map(|species| (species.name, species_map.is_species_name(species.name)))
+            .chain(self.abilities.iter().map(|(name, _)| (*name, ability_map.is_ability_name(&name.to_string()))))
+            .chain(self.items.iter().map(|item| (item.name, item_map.get_item(item.name).is_some())))
+            .chain(self.maps.iter().map(|map| (map.get_name(), maps.contains_key(&map.get_name()))));
+        for (name, exists) in names {
+            if exists || !added.insert(name) {
+                return Err(DataPackError::Conflict { namespace: self.manifest.namespace.clone(), name });
+            }
+        }
+        for species in self.species.iter() {
+            species_map.add_species(*species);
+        }
+        for (species, form) in self.forms.iter() {
+            species_map.add_form(*species, form.clone());
+        }
+        for (name, data) in self.abilities.iter() {
+            match self.ability_scripts.get(name) {
+                Some(script) => ability_map.add_scripted_ability(&name.to_string(), *data, script.clone()),
+                None => ability_map.add_data_ability(&name.to_string(), *data)
+            }
+        }
+        for item in self.items.iter() {
+            item_map.add_item(*item);
+        }
+        for map in self.maps.iter() {
+            maps.insert(map.get_name(), map.clone());
+        }
+        return Ok(());
+    }
+}
+
+/// Install enabled packs in order, after core data has been loaded. Fails on the first pack that conflicts, leaving
+/// the packs before it installed.
+pub fn install_packs(packs: &[DataPack], species_map: &mut SpeciesMap, ability_map: &mut AbilityMap, item_map: &mut ItemMap, maps: &mut HashMap<GlobalString, TileMap>) -> Result<(), DataPackError> {
+    let mut namespaces: HashSet<&str> = HashSet::new();
+    for pack in packs {
+        if !namespaces.insert(&pack.manifest.namespace) {
+            return Err(DataPackError::DuplicateNamespace(pack.manifest.namespace.clone()));
+        }
+    }
+    for pack in packs {
+        pack.install(species_map, ability_map, item_map, maps)?;
+    }
+    return Ok(());
+}
+
+/// Parse species from a JSON array such as `[{ "name": "embercat", "elements": ["fire"], "base_stats": [50, 60, 40, 70] }]`.
+/// Base stats are health, attack, defense and speed. `catch_rate` is optional, as is `tier`, which is little_cup,
+/// standard or ubers and defaults to standard. `breeding_groups` is an optional list of up to MAX_BREEDING_GROUPS
+/// names. Names are put under the namespace of a pack, or None for core data, except breeding groups, which are shared
+/// so pack species can breed with core species.
+/// ```
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::modding::data_pack::parse_species_json;
+///
+/// let json = r#"[{ "name": "embercat", "elements": ["fire"], "base_stats": [50, 60, 40, 70], "breeding_groups": ["field"] }]"#;
+/// assert_eq!(parse_species_json(json, Some("mymod")).unwrap()[0].name, GlobalString::new(&"mymod:embercat".to_string()));
+/// assert_eq!(parse_species_json(json, Some("mymod")).unwrap()[0].breeding_groups, [Some(GlobalString::new(&"field".to_string())), None]);
+/// assert_eq!(parse_species_json(json, None).unwrap()[0].name, GlobalString::new(&"embercat".to_string()));
+/// assert!(parse_species_json(&json.replace("embercat", "mymod:embercat"), None).is_err());
+/// assert!(parse_species_json(&json.replace(r#"["field"]"#, r#"["field", "flying", "water"]"#), None).is_err());
+/// ```
+pub fn parse_species_json(json: &str, namespace: Option<&str>) -> Result<Vec<SpeciesData>, DataPackError> {
+    let mut parsed = Vec::new();
+    for entry in parse_array(json)?.iter() {
+        let name = get_name(namespace, get_str(entry, "name")?)?;
+        let base_stats = parse_base_stats(entry).ok_or(DataPackError::Invalid(format!("Species [{}] needs base_stats of 4 numbers", name)))?;
+        let mut species = SpeciesData::new(name, parse_elements(entry)?, base_stats);
+        if let Some(catch_rate) = entry.get("catch_rate") {
+            species.catch_rate = catch_rate.as_u64().filter(|rate| (1..=255).contains(rate))
+                .ok_or(DataPackError::Invalid(format!("Species [{}] has an invalid catch_rate", name)))? as u32;
+        }
+        if let Some(tier) = entry.get("tier") {
+            species.tier = tier.as_str().and_then(SpeciesTier::from_name)
+                .ok_or(DataPackError::Invalid(format!("Species [{}] has an unknown tier", name)))?;
+        }
+        if let Some(groups) = entry.get("breeding_groups") {
+            let groups = groups.as_array().filter(|groups| groups.len() <= MAX_BREEDING_GROUPS)
+                .and_then(|groups| groups.iter().map(|group| group.as_str()).collect::<Option<Vec<&str>>>())
+                .ok_or(DataPackError::Invalid(format!("Species [{}] needs breeding_groups of at most {} names", name, MAX_BREEDING_GROUPS)))?;
+            for group in groups {
+                species = species.with_breeding_group(GlobalString::new(&group.to_string()));
+            }
+        }
+        parsed.push(species);
+    }
+    return Ok(parsed);
+}
+
+/// Parse the forms of species from the same JSON array as parse_species_json(), such as
+/// `[{ "name": "lavapup", ..., "forms": [{ "name": "tidal", "elements": ["water"], "regions": ["coast"] }] }]`. A form
+/// can replace the `elements` and `base_stats` of its species, and limit the abilities it can know with `learnset`,
+/// the regions it spawns in with `regions` and when with `conditions` like an encounter entry's. All are optional.
+/// Returns each form with the name of its species, which is put under the namespace like parse_species_json(). Form
+/// names aren't, since they only have to be unique within their species.
+/// ```
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::gameplay::encounter::encounter_conditions::Weather;
+/// use immie2d_shared::modding::data_pack::parse_species_forms_json;
+///
+/// let json = r#"[{ "name": "lavapup", "elements": ["fire"], "base_stats": [50, 60, 40, 70], "forms": [
+///     { "name": "tidal", "elements": ["water"], "regions": ["coast"], "conditions": { "weather": ["rain"] } }
+/// ] }]"#;
+/// let forms = parse_species_forms_json(json, Some("mymod")).unwrap();
+/// assert_eq!(forms[0].0, GlobalString::new(&"mymod:lavapup".to_string()));
+/// assert_eq!(forms[0].1.name, GlobalString::new(&"tidal".to_string()));
+/// assert_eq!(forms[0].1.regions, vec![GlobalString::new(&"coast".to_string())]);
+/// assert_eq!(forms[0].1.conditions.weathers, vec![Weather::Rain]);
+/// assert!(forms[0].1.base_stats.is_none());
+/// assert!(parse_species_forms_json(&json.replace("rain", "hail"), None).is_err());
+/// assert!(parse_species_forms_json(&json.replace(r#""name": "tidal""#, r#""name": "tidal", "base_stats": [1]"#), None).is_err());
+/// ```
+pub fn parse_species_forms_json(json: &str, namespace: Option<&str>) -> Result<Vec<(GlobalString, SpeciesForm)>, DataPackError> {
+    let mut parsed = Vec::new();
+    for entry in parse_array(json)?.iter() {
+        let species = get_name(namespace, get_str(entry, "name")?)?;
+        let Some(forms) = entry.get("forms") else {
+            continue;
+        };
+        let forms = forms.as_array().ok_or(DataPackError::Invalid(format!("Forms of species [{}] must be an array", species)))?;
+        for form_entry in forms {
+            let mut form = SpeciesForm::new(GlobalString::new(&get_str(form_entry, "name")?.to_string()));
+            let invalid = |message: &str| DataPackError::Invalid(format!("Form [{}] of species [{}] {}", form.name, species, message));
+            if form_entry.get("elements").is_some() {
+                form.elements = Some(parse_elements(form_entry)?);
+            }
+            if form_entry.get("base_stats").is_some() {
+                form.base_stats = Some(parse_base_stats(form_entry).ok_or(invalid("needs base_stats of 4 numbers"))?);
+            }
+            let get_names = |key: &str| -> Result<Vec<GlobalString>, DataPackError> {
+                let Some(names) = form_entry.get(key) else {
+                    return Ok(Vec::new());
+                };
+                return names.as_array().and_then(|names| names.iter().map(|name| name.as_str().map(|name| GlobalString::new(&name.to_string()))).collect())
+                    .ok_or(invalid(&format!("needs {} of names", key)));
+            };
+            form.learnset = get_names("learnset")?;
+            form.regions = get_names("regions")?;
+            if let Some(conditions) = form_entry.get("conditions") {
+                form.conditions = parse_conditions(conditions).map_err(|message| invalid(&format!("has invalid conditions: {}", message)))?;
+            }
+            parsed.push((species, form));
+        }
+    }
+    return Ok(parsed);
+}
+
+/// Parse abilities from a JSON array such as `[{ "name": "magma_ball", "category": "attack", "elements": ["fire"], "power": 70, "max_uses": 10 }]`.
+/// `speed` is optional and defaults to 1, and `accuracy` is an optional percent from 1 to 100 that defaults to 100. `flags` is an
+/// optional list of flag names such as `["contact", "sound"]`. `script` is an optional path to a script with the
+/// ability's effect hooks. See load_ability_scripts()
+/// ```
+/// use immie2d_shared::gameplay::ability::ability_flags::AbilityFlags;
+/// use immie2d_shared::modding::data_pack::parse_abilities_json;
+///
+/// let json = r#"[{ "name": "howl", "category": "status", "elements": ["standard"], "power": 0, "max_uses": 20, "flags": ["sound"] }]"#;
+/// assert_eq!(parse_abilities_json(json, None).unwrap()[0].1.flags, AbilityFlags::SOUND);
+/// assert!(parse_abilities_json(&json.replace("sound", "loud"), None).is_err());
+/// assert_eq!(parse_abilities_json(&json.replace(r#""power""#, r#""accuracy": 75, "power""#), None).unwrap()[0].1.accuracy, 75);
+/// assert!(parse_abilities_json(&json.replace(r#""power""#, r#""accuracy": 4294967296, "power""#), None).is_err());
+/// assert!(parse_abilities_json(&json.replace(r#""power""#, r#""accuracy": 0, "power""#), None).is_err());
+/// ```
+pub fn parse_abilities_json(json: &str, namespace: Option<&str>) -> Result<Vec<(GlobalString, BaseAbilityData)>, DataPackError> {
+    let mut parsed = Vec::new();
+    for entry in parse_array(json)?.iter() {
+        let name = get_name(namespace, get_str(entry, "name")?)?;
+        let category = match get_str(entry, "category")? {
+            "attack" => AbilityCategory::Attack,
+            "status" => AbilityCategory::Status,
+            other => return Err(DataPackError::Invalid(format!("Ability [{}] has unknown category [{}]", name, other)))
+        };
+        let accuracy = match entry.get("accuracy") {
+            Some(accuracy) => accuracy.as_u64().filter(|accuracy| (1..=100).contains(accuracy))
+                .ok_or(DataPackError::Invalid(format!("Ability [{}] needs an accuracy from 1 to 100", name)))? as u32,
+            None => 100
+        };
+        let data = BaseAbilityData {
+            category,
+            types: parse_elements(entry)?,
+            power: get_number(entry, "power")? as f32,
+            speed: entry.get("speed").and_then(|speed| speed.as_f64()).unwrap_or(1.0) as f32,
+            max_uses: get_number(entry, "max_uses")? as u32,
+            accuracy,
+            flags: parse_flags(entry).map_err(|err| DataPackError::Invalid(format!("Ability [{}] {}", name, err)))?,
+            combo: None
+        };
+        parsed.push((name, data));
+    }
+    return Ok(parsed);
+}
+
+/// Compile the scripts of abilities with a `script` path, such as `"script": "scripts/gravity_well.rhai"`, relative to
+/// the directory the abilities file is in. Paths can't leave the directory. See AbilityScript
+/// ```
+/// use std::fs;
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::modding::data_pack::load_ability_scripts;
+///
+/// let directory = std::env::temp_dir().join(format!("immie2d_ability_scripts_{}", std::process::id()));
+/// fs::create_dir_all(directory.join("scripts")).unwrap();
+/// fs::write(directory.join("scripts/backfire.rhai"), "fn on_hit() { this.recoil(this.damage_dealt / 2); }").unwrap();
+/// let json = r#"[
+///     { "name": "backfire", "category": "attack", "elements": ["fire"], "power": 90, "max_uses": 5, "script": "scripts/backfire.rhai" },
+///     { "name": "ember", "category": "attack", "elements": ["fire"], "power": 40, "max_uses": 25 }
+/// ]"#;
+/// let scripts = load_ability_scripts(json, Some("mymod"), &directory).unwrap();
+/// assert_eq!(scripts.len(), 1);
+/// assert_eq!(scripts[0].0, GlobalString::new(&"mymod:backfire".to_string()));
+///
+/// let escaping = r#"[{ "name": "sneaky", "category": "status", "elements": ["fire"], "power": 0, "max_uses": 5, "script": "../sneaky.rhai" }]"#;
+/// assert!(load_ability_scripts(escaping, Some("mymod"), &directory).is_err());
+/// fs::remove_dir_all(&directory).unwrap();
+/// ```
+pub fn load_ability_scripts(json: &str, namespace: Option<&str>, directory: &Path) -> Result<Vec<(GlobalString, Arc<AbilityScript>)>, DataPackError> {
+    let mut scripts = Vec::new();
+    for entry in parse_array(json)?.iter() {
+        let Some(path) = entry.get("script") else {
+            continue;
+        };
+        let name = get_name(namespace, get_str(entry, "name")?)?;
+        let path = Path::new(path.as_str().ok_or(DataPackError::Invalid(format!("Ability [{}] has a script that isn't a path", name)))?);
+        if !path.components().all(|component| matches!(component, Component::Normal(_) | Component::CurDir)) {
+            return Err(DataPackError::Invalid(format!("Script of ability [{}] is outside of its data directory", name)));
+        }
+        let script = AbilityScript::compile(&name.to_string(), &read_file(&directory.join(path))?).map_err(DataPackError::Invalid)?;
+        scripts.push((name, Arc::new(script)));
+    }
+    return Ok(scripts);
+}
+
+/// Parse items from a JSON array such as `[{ "name": "mega_potion", "effect": "restore_health", "amount": 80 }]`.
+/// Effects are restore_health, restore_ability_uses and increase_bond with an amount, cure_status, which cures
+/// any status, attune with the element to attune to, and repel and lure with a number of steps. Lures also have an
+/// element. Attuning, repels and lures can only be used outside of battle.
+pub fn parse_items_json(json: &str, namespace: Option<&str>) -> Result<Vec<ItemData>, DataPackError> {
+    let mut parsed = Vec::new();
+    for entry in parse_array(json)?.iter() {
+        let name = get_name(namespace, get_str(entry, "name")?)?;
+        let effect = match get_str(entry, "effect")? {
+            "restore_health" => ItemEffect::RestoreHealth(get_number(entry, "amount")? as u32),
+            "restore_ability_uses" => ItemEffect::RestoreAbilityUses(get_number(entry, "amount")? as u32),
+            "increase_bond" => ItemEffect::IncreaseBond(get_number(entry, "amount")? as u32),
+            "cure_status" => ItemEffect::CureStatus(None),
+            "attune" => {
+                // Every Immie can already use standard abilities, so attuning to standard would do nothing
+                let element = get_str(entry, "element")?;
+                match ElementKind::from_name(element) {
+                    Some(ElementKind::Standard) | None => return Err(DataPackError::Invalid(format!("Item [{}] can't attune to element [{}]", name, element))),
+                    Some(element) => ItemEffect::Attune(element)
+                }
+            },
+            "repel" => ItemEffect::EncounterModifier(EncounterModifier { kind: EncounterModifierKind::Repel, steps: get_number(entry, "steps")? as u32 }),
+            "lure" => {
+                let element = get_str(entry, "element")?;
+                let element = ElementKind::from_name(element).ok_or(DataPackError::Invalid(format!("Item [{}] has unknown element [{}]", name, element)))?;
+                ItemEffect::EncounterModifier(EncounterModifier { kind: EncounterModifierKind::Lure(element), steps: get_number(entry, "steps")? as u32 })
+            },
+            other => return Err(DataPackError::Invalid(format!("Item [{}] has unknown effect [{}]", name, other)))
+        };
+        let item = match effect {
+            ItemEffect::EncounterModifier(_) | ItemEffect::Attune(_) => ItemData::new_overworld(name, effect),
+            _ => ItemData::new(name, effect)
+        };
+        parsed.push(item);
+    }
+    return Ok(parsed);
+}
+
+/// Put a name under a data pack's namespace, failing if it is under another namespace. Core data has no namespace,
+/// so its names can't have one.
+fn get_name(namespace: Option<&str>, name: &str) -> Result<GlobalString, DataPackError> {
+    return match namespace {
+        Some(namespace) => apply_namespace(namespace, name).ok_or(DataPackError::Invalid(format!("[{}] is outside of the namespace {}", name, namespace))),
+        None if get_namespace(name).is_some() => Err(DataPackError::Invalid(format!("Core data [{}] can't be under a namespace", name))),
+        None => Ok(GlobalString::new(&name.to_string()))
+    };
+}
+
+/// Paths of the map files in a maps directory, sorted since directory order isn't stable and maps are installed in
+/// order. Empty if the directory doesn't exist.
+pub fn list_map_files(directory: &Path) -> Result<Vec<PathBuf>, DataPackError> {
+    if !directory.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut paths: Vec<PathBuf> = fs::read_dir(directory).map_err(|err| DataPackError::Io(err.to_string()))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .collect();
+    paths.sort();
+    return Ok(paths);
+}
+
+/// Import a Tiled map file, named after its file name under the namespace. Returns None for files that aren't
+/// .tmj, .json or .tmx maps.
+pub fn load_map_file(path: &Path, namespace: Option<&str>) -> Result<Option<TileMap>, DataPackError> {
+    let import = match path.extension().and_then(|extension| extension.to_str()) {
+        Some("tmj") | Some("json") => import_tmj,
+        Some("tmx") => import_tmx,
+        _ => return Ok(None)
+    };
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    let text = read_file(path)?;
+    return import(get_name(namespace, &stem)?, &text).map(Some).map_err(DataPackError::Map);
+}
+
+fn read_file(path: &Path) -> Result<String, DataPackError> {
+    return fs::read_to_string(path).map_err(|err| DataPackError::Io(format!("{}: {}", path.display(), err)));
+}
+
+fn parse_array(json: &str) -> Result<Vec<Value>, DataPackError> {
+    let root: Value = serde_json::from_str(json).map_err(|err| DataPackError::Parse(err.to_string()))?;
+    return match root {
+        Value::Array(entries) => Ok(entries),
+        _ => Err(DataPackError::Invalid("Expected an array of entries".to_string()))
+    };
+}
+
+fn get_str<'a>(entry: &'a Value, key: &str) -> Result<&'a str, DataPackError> {
+    return entry.get(key).and_then(|value| value.as_str()).ok_or(DataPackError::Invalid(format!("Entry is missing [{}]", key)));
+}
+
+fn get_number(entry: &Value, key: &str) -> Result<f64, DataPackError> {
+    return entry.get(key).and_then(|value| value.as_f64()).filter(|value| *value >= 0.0).ok_or(DataPackError::Invalid(format!("Entry is missing a non negative [{}]", key)));
+}
+
+fn parse_flags(entry: &Value) -> Result<AbilityFlags, String> {
+    let Some(names) = entry.get("flags") else {
+        return Ok(AbilityFlags::NONE);
+    };
+    let names = names.as_array().ok_or("has flags that aren't a list".to_string())?;
+    let mut flags = AbilityFlags::NONE;
+    for name in names {
+        let name = name.as_str().unwrap_or_default();
+        flags = flags | AbilityFlags::from_name(name).ok_or(format!("has unknown flag [{}]", name))?;
+    }
+    return Ok(flags);
+}
+
+/// Health, attack, defense and speed, or None if they aren't 4 numbers.
+fn parse_base_stats(entry: &Value) -> Option<BaseStats> {
+    let stats = entry.get("base_stats").and_then(|stats| stats.as_array()).filter(|stats| stats.len() == 4)
+        .and_then(|stats| stats.iter().map(|stat| stat.as_u64().map(|stat| stat as u32)).collect::<Option<Vec<u32>>>())?;
+    return Some(BaseStats::new(stats[0], stats[1], stats[2], stats[3]));
+}
+
+fn parse_elements(entry: &Value) -> Result<Elements, DataPackError> {
+    let names = entry.get("elements").and_then(|elements| elements.as_array()).filter(|elements| !elements.is_empty())
+        .ok_or(DataPackError::Invalid("Entry needs at least one element".to_string()))?;
+    let mut elements = Vec::new();
+    for name in names {
+        let name = name.as_str().unwrap_or_default();
+        let element = ElementKind::from_name(name).ok_or(DataPackError::Invalid(format!("Unknown element [{}]", name)))?;
+        if elements.contains(&element) {
+            return Err(DataPackError::Invalid(format!("Element [{}] is listed twice", name)));
+        }
+        elements.push(element);
+    }
+    return Ok(Elements::new(elements));
+}
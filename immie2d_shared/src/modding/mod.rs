@@ -0,0 +1,5 @@
+pub mod namespace;
+pub mod pack_manifest;
+pub mod data_pack;
+pub mod pack_advertisement;
+pub mod ability_csv;
@@ -0,0 +1,54 @@
+use crate::engine_types::global_string::GlobalString;
+
+/// Separates a data pack's namespace from the name of what it adds, as in `mymod:lavapup`.
+pub const NAMESPACE_SEPARATOR: char = ':';
+
+/// Longest namespace a data pack can use.
+pub const MAX_NAMESPACE_LENGTH: usize = 32;
+
+/// Whether a data pack can use a namespace. Namespaces are lowercase letters, digits and underscores, starting with a
+/// letter. Core data has no namespace.
+/// ```
+/// use immie2d_shared::modding::namespace::is_valid_namespace;
+///
+/// assert!(is_valid_namespace("mymod"));
+/// assert!(is_valid_namespace("my_mod2"));
+/// assert!(!is_valid_namespace("MyMod"));
+/// assert!(!is_valid_namespace("2mod"));
+/// assert!(!is_valid_namespace("my:mod"));
+/// assert!(!is_valid_namespace(""));
+/// ```
+pub fn is_valid_namespace(namespace: &str) -> bool {
+    let starts_with_letter = namespace.chars().next().is_some_and(|first| first.is_ascii_lowercase());
+    let valid_characters = namespace.chars().all(|character| character.is_ascii_lowercase() || character.is_ascii_digit() || character == '_');
+    return starts_with_letter && valid_characters && namespace.len() <= MAX_NAMESPACE_LENGTH;
+}
+
+/// Get the namespace of a name, or None for core data.
+/// ```
+/// use immie2d_shared::modding::namespace::get_namespace;
+///
+/// assert_eq!(get_namespace("mymod:lavapup"), Some("mymod"));
+/// assert_eq!(get_namespace("lavapup"), None);
+/// ```
+pub fn get_namespace(name: &str) -> Option<&str> {
+    return name.split_once(NAMESPACE_SEPARATOR).map(|(namespace, _)| namespace);
+}
+
+/// Put a name under a namespace. A name already under the same namespace is left as it is, so pack authors can write
+/// either `lavapup` or `mymod:lavapup`. Returns None if the name is under another namespace.
+/// ```
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::modding::namespace::apply_namespace;
+///
+/// assert_eq!(apply_namespace("mymod", "lavapup"), Some(GlobalString::new(&"mymod:lavapup".to_string())));
+/// assert_eq!(apply_namespace("mymod", "mymod:lavapup"), Some(GlobalString::new(&"mymod:lavapup".to_string())));
+/// assert_eq!(apply_namespace("mymod", "othermod:lavapup"), None);
+/// ```
+pub fn apply_namespace(namespace: &str, name: &str) -> Option<GlobalString> {
+    return match get_namespace(name) {
+        Some(existing) if existing == namespace => Some(GlobalString::new(&name.to_string())),
+        Some(_) => None,
+        None => Some(GlobalString::new(&format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, name)))
+    };
+}
@@ -0,0 +1,79 @@
+use super::pack_manifest::{PackManifest, PackVersion};
+
+/* The data packs a server has enabled, sent to clients during the handshake so a client missing a pack, or with an
+incompatible version of one, can say so instead of failing on the first unknown name. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PackAdvertisement {
+    /// Namespace and version of each enabled pack, in install order.
+    pub packs: Vec<(String, PackVersion)>
+}
+
+impl PackAdvertisement {
+    pub fn new(manifests: &[PackManifest]) -> PackAdvertisement {
+        return PackAdvertisement { packs: manifests.iter().map(|manifest| (manifest.namespace.clone(), manifest.version)).collect() };
+    }
+
+    /// The advertised packs a client doesn't have a compatible version of, in install order.
+    /// ```
+    /// use immie2d_shared::modding::pack_advertisement::PackAdvertisement;
+    /// use immie2d_shared::modding::pack_manifest::{PackManifest, PackVersion};
+    ///
+    /// let manifest = |namespace: &str, version: PackVersion| PackManifest { namespace: namespace.to_string(), display_name: namespace.to_string(), version };
+    /// let advertisement = PackAdvertisement::new(&[manifest("mymod", PackVersion::new(1, 2, 0)), manifest("othermod", PackVersion::new(2, 0, 0))]);
+    /// let installed = vec![manifest("mymod", PackVersion::new(1, 3, 0)), manifest("othermod", PackVersion::new(1, 9, 0))];
+    /// assert_eq!(advertisement.get_missing(&installed), vec![("othermod".to_string(), PackVersion::new(2, 0, 0))]);
+    /// ```
+    pub fn get_missing(&self, installed: &[PackManifest]) -> Vec<(String, PackVersion)> {
+        return self.packs.iter().filter(|(namespace, version)| {
+            return !installed.iter().any(|manifest| manifest.namespace == *namespace && manifest.version.is_compatible_with(*version));
+        }).cloned().collect();
+    }
+
+    /// Encode as the pack count, then each pack's length prefixed namespace and its version numbers.
+    /// ```
+    /// use immie2d_shared::modding::pack_advertisement::PackAdvertisement;
+    /// use immie2d_shared::modding::pack_manifest::PackVersion;
+    ///
+    /// let advertisement = PackAdvertisement { packs: vec![("mymod".to_string(), PackVersion::new(1, 2, 3))] };
+    /// assert_eq!(PackAdvertisement::from_bytes(&advertisement.to_bytes()), Some(advertisement.clone()));
+    /// assert_eq!(PackAdvertisement::from_bytes(&advertisement.to_bytes()[..9]), None);
+    /// assert_eq!(PackAdvertisement::from_bytes(&PackAdvertisement { packs: Vec::new() }.to_bytes()), Some(PackAdvertisement { packs: Vec::new() }));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(self.packs.len() as u32).to_le_bytes());
+        for (namespace, version) in self.packs.iter() {
+            bytes.extend_from_slice(&(namespace.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(namespace.as_bytes());
+            for number in [version.major, version.minor, version.patch] {
+                bytes.extend_from_slice(&number.to_le_bytes());
+            }
+        }
+        return bytes;
+    }
+
+    /// Decode an advertisement, or None if the bytes are not a valid advertisement.
+    pub fn from_bytes(bytes: &[u8]) -> Option<PackAdvertisement> {
+        let mut position = 0;
+        let count = take_u32(bytes, &mut position)?;
+        let mut packs = Vec::new();
+        for _ in 0..count {
+            let length = take_u32(bytes, &mut position)? as usize;
+            let namespace = String::from_utf8(bytes.get(position..position + length)?.to_vec()).ok()?;
+            position += length;
+            let version = PackVersion::new(take_u32(bytes, &mut position)?, take_u32(bytes, &mut position)?, take_u32(bytes, &mut position)?);
+            packs.push((namespace, version));
+        }
+        if position != bytes.len() {
+            return None;
+        }
+        return Some(PackAdvertisement { packs });
+    }
+}
+
+/// Read a little endian u32 at a position, moving the position past it.
+fn take_u32(bytes: &[u8], position: &mut usize) -> Option<u32> {
+    let value = u32::from_le_bytes(bytes.get(*position..*position + 4)?.try_into().ok()?);
+    *position += 4;
+    return Some(value);
+}
@@ -0,0 +1,103 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use serde_json::Value;
+
+use super::data_pack::DataPackError;
+use super::namespace::is_valid_namespace;
+
+/* Semantic version of a data pack. Packs with the same major version are compatible, so a newer minor or patch
+version can stand in for an older one. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct PackVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32
+}
+
+impl PackVersion {
+    pub fn new(major: u32, minor: u32, patch: u32) -> PackVersion {
+        return PackVersion { major, minor, patch };
+    }
+
+    /// Parse a `major.minor.patch` version.
+    /// ```
+    /// use immie2d_shared::modding::pack_manifest::PackVersion;
+    ///
+    /// assert_eq!(PackVersion::parse("1.4.2"), Some(PackVersion::new(1, 4, 2)));
+    /// assert_eq!(PackVersion::parse("1.4"), None);
+    /// assert_eq!(PackVersion::parse("1.x.0"), None);
+    /// ```
+    pub fn parse(text: &str) -> Option<PackVersion> {
+        let parts: Vec<u32> = text.split('.').map(|part| part.parse::<u32>().ok()).collect::<Option<Vec<u32>>>()?;
+        return match parts.as_slice() {
+            [major, minor, patch] => Some(PackVersion::new(*major, *minor, *patch)),
+            _ => None
+        };
+    }
+
+    /// Whether this version can be used where `required` is expected.
+    /// ```
+    /// use immie2d_shared::modding::pack_manifest::PackVersion;
+    ///
+    /// let required = PackVersion::new(1, 2, 0);
+    /// assert!(PackVersion::new(1, 2, 0).is_compatible_with(required));
+    /// assert!(PackVersion::new(1, 3, 1).is_compatible_with(required));
+    /// assert!(!PackVersion::new(1, 1, 9).is_compatible_with(required));
+    /// assert!(!PackVersion::new(2, 0, 0).is_compatible_with(required));
+    /// ```
+    pub fn is_compatible_with(&self, required: PackVersion) -> bool {
+        return self.major == required.major && self.cmp(&required) != Ordering::Less;
+    }
+}
+
+impl PartialOrd for PackVersion {
+    fn partial_cmp(&self, other: &PackVersion) -> Option<Ordering> {
+        return Some(self.cmp(other));
+    }
+}
+
+impl Ord for PackVersion {
+    fn cmp(&self, other: &PackVersion) -> Ordering {
+        return (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch));
+    }
+}
+
+impl fmt::Display for PackVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return write!(f, "{}.{}.{}", self.major, self.minor, self.patch);
+    }
+}
+
+/* Identifies a data pack. Everything the pack adds is named under its namespace. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct PackManifest {
+    pub namespace: String,
+    /// Name shown to players.
+    pub display_name: String,
+    pub version: PackVersion
+}
+
+impl PackManifest {
+    /// Load a manifest from a JSON object.
+    /// ```
+    /// use immie2d_shared::modding::pack_manifest::{PackManifest, PackVersion};
+    ///
+    /// let manifest = PackManifest::from_json(r#"{ "namespace": "mymod", "name": "My Mod", "version": "1.0.3" }"#).unwrap();
+    /// assert_eq!(manifest.namespace, "mymod");
+    /// assert_eq!(manifest.version, PackVersion::new(1, 0, 3));
+    /// assert!(PackManifest::from_json(r#"{ "namespace": "My Mod", "name": "My Mod", "version": "1.0.3" }"#).is_err());
+    /// assert!(PackManifest::from_json(r#"{ "namespace": "mymod", "name": "My Mod", "version": "one" }"#).is_err());
+    /// ```
+    pub fn from_json(json: &str) -> Result<PackManifest, DataPackError> {
+        let root: Value = serde_json::from_str(json).map_err(|err| DataPackError::Parse(err.to_string()))?;
+        let get_string = |key: &str| root.get(key).and_then(|value| value.as_str()).ok_or(DataPackError::Invalid(format!("Manifest is missing [{}]", key)));
+        let namespace = get_string("namespace")?;
+        if !is_valid_namespace(namespace) {
+            return Err(DataPackError::Invalid(format!("Invalid namespace [{}]", namespace)));
+        }
+        let version_text = get_string("version")?;
+        let version = PackVersion::parse(version_text).ok_or(DataPackError::Invalid(format!("Invalid version [{}]", version_text)))?;
+        return Ok(PackManifest { namespace: namespace.to_string(), display_name: get_string("name")?.to_string(), version });
+    }
+}
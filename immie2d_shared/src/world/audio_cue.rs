@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::engine_types::global_string::GlobalString;
+
+/// Number of AudioCueLayer variants.
+pub const AUDIO_CUE_LAYER_COUNT: usize = 3;
+
+/* Where a cue comes from. Higher layers override lower ones, so battle music plays over a scripted event's, which
+plays over the region's. */
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+#[repr(u8)]
+pub enum AudioCueLayer {
+    Region,
+    ScriptedEvent,
+    Battle
+}
+
+impl AudioCueLayer {
+    pub fn from_id(id: u8) -> Option<AudioCueLayer> {
+        return match id {
+            0 => Some(AudioCueLayer::Region),
+            1 => Some(AudioCueLayer::ScriptedEvent),
+            2 => Some(AudioCueLayer::Battle),
+            _ => None
+        };
+    }
+}
+
+/* The kinds of battle that can have their own music. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum BattleCueKind {
+    Wild,
+    Trainer,
+    Raid,
+    /// Against another player.
+    Online
+}
+
+impl BattleCueKind {
+    /// Lowercase name used in data files.
+    pub fn get_name(&self) -> &'static str {
+        return match self {
+            BattleCueKind::Wild => "wild",
+            BattleCueKind::Trainer => "trainer",
+            BattleCueKind::Raid => "raid",
+            BattleCueKind::Online => "online"
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<BattleCueKind> {
+        return [BattleCueKind::Wild, BattleCueKind::Trainer, BattleCueKind::Raid, BattleCueKind::Online].into_iter().find(|kind| kind.get_name() == name);
+    }
+}
+
+/* The music track and ambience loop to play. Either can be left out to keep what a lower layer plays. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct AudioCue {
+    pub music: Option<GlobalString>,
+    pub ambience: Option<GlobalString>
+}
+
+/* The cues of every region, battle kind and scripted event, from the audio cue data file. */
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct AudioCueTable {
+    regions: HashMap<GlobalString, AudioCue>,
+    battles: HashMap<BattleCueKind, AudioCue>,
+    events: HashMap<GlobalString, AudioCue>
+}
+
+impl AudioCueTable {
+    pub fn new() -> AudioCueTable {
+        return AudioCueTable::default();
+    }
+
+    /// Parse a table such as `{ "regions": { "route 1": { "music": "route_theme", "ambience": "birds" } },
+    /// "battles": { "wild": { "music": "wild_battle" } }, "events": { "rival_appears": { "music": "rival_theme" } } }`.
+    /// Every section is optional.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::audio_cue::{AudioCueTable, BattleCueKind};
+    ///
+    /// let table = AudioCueTable::from_json(r#"{
+    ///     "regions": { "route 1": { "music": "route_theme", "ambience": "birds" } },
+    ///     "battles": { "wild": { "music": "wild_battle" } }
+    /// }"#).unwrap();
+    /// let route = table.get_region_cue(GlobalString::new(&"route 1".to_string())).unwrap();
+    /// assert_eq!(route.ambience, Some(GlobalString::new(&"birds".to_string())));
+    /// assert_eq!(table.get_battle_cue(BattleCueKind::Wild).unwrap().ambience, None);
+    /// assert!(table.get_battle_cue(BattleCueKind::Raid).is_none());
+    ///
+    /// assert!(AudioCueTable::from_json(r#"{ "battles": { "boss": { "music": "boss_theme" } } }"#).is_err());
+    /// assert!(AudioCueTable::from_json(r#"{ "regions": { "route 1": { "music": 3 } } }"#).is_err());
+    /// ```
+    pub fn from_json(json: &str) -> Result<AudioCueTable, String> {
+        let root: Value = serde_json::from_str(json).map_err(|err| err.to_string())?;
+        let mut table = AudioCueTable::new();
+        for (name, cue) in parse_section(&root, "regions")? {
+            table.regions.insert(GlobalString::new(&name), cue);
+        }
+        for (name, cue) in parse_section(&root, "battles")? {
+            let kind = BattleCueKind::from_name(&name).ok_or(format!("Unknown battle kind [{}]", name))?;
+            table.battles.insert(kind, cue);
+        }
+        for (name, cue) in parse_section(&root, "events")? {
+            table.events.insert(GlobalString::new(&name), cue);
+        }
+        return Ok(table);
+    }
+
+    pub fn get_region_cue(&self, map: GlobalString) -> Option<AudioCue> {
+        return self.regions.get(&map).copied();
+    }
+
+    pub fn get_battle_cue(&self, kind: BattleCueKind) -> Option<AudioCue> {
+        return self.battles.get(&kind).copied();
+    }
+
+    pub fn get_event_cue(&self, event: GlobalString) -> Option<AudioCue> {
+        return self.events.get(&event).copied();
+    }
+}
+
+fn parse_section(root: &Value, section: &str) -> Result<Vec<(String, AudioCue)>, String> {
+    let Some(entries) = root.get(section) else {
+        return Ok(Vec::new());
+    };
+    let entries = entries.as_object().ok_or(format!("[{}] must be an object", section))?;
+    let mut cues = Vec::new();
+    for (name, entry) in entries.iter() {
+        let get_track = |key: &str| -> Result<Option<GlobalString>, String> {
+            return match entry.get(key) {
+                Some(Value::String(track)) => Ok(Some(GlobalString::new(track))),
+                Some(_) => Err(format!("[{}] of [{}] must be a string", key, name)),
+                None => Ok(None)
+            };
+        };
+        cues.push((name.clone(), AudioCue { music: get_track("music")?, ambience: get_track("ambience")? }));
+    }
+    return Ok(cues);
+}
+
+/* Sent by the server to set the cue of a layer, or clear it with None, such as when a battle or scripted event ends. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AudioCueHint {
+    pub layer: AudioCueLayer,
+    pub cue: Option<AudioCue>
+}
+
+impl AudioCueHint {
+    /// Encode as the layer id, then a flag for whether there is a cue, then each track as a length prefixed name, or
+    /// length 0 if the cue leaves it out.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::audio_cue::{AudioCue, AudioCueHint, AudioCueLayer};
+    ///
+    /// let cue = AudioCue { music: Some(GlobalString::new(&"wild_battle".to_string())), ambience: None };
+    /// let hint = AudioCueHint { layer: AudioCueLayer::Battle, cue: Some(cue) };
+    /// assert_eq!(AudioCueHint::from_bytes(&hint.to_bytes()), Some(hint));
+    /// let clear = AudioCueHint { layer: AudioCueLayer::ScriptedEvent, cue: None };
+    /// assert_eq!(AudioCueHint::from_bytes(&clear.to_bytes()), Some(clear));
+    /// assert_eq!(AudioCueHint::from_bytes(&[3, 0]), None);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.layer as u8, self.cue.is_some() as u8];
+        if let Some(cue) = self.cue {
+            for track in [cue.music, cue.ambience] {
+                let name = track.map(|track| track.to_string()).unwrap_or_default();
+                bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+                bytes.extend_from_slice(name.as_bytes());
+            }
+        }
+        return bytes;
+    }
+
+    /// Decode a hint, or None if the bytes are not a valid hint.
+    pub fn from_bytes(bytes: &[u8]) -> Option<AudioCueHint> {
+        let layer = AudioCueLayer::from_id(*bytes.first()?)?;
+        return match *bytes.get(1)? {
+            0 if bytes.len() == 2 => Some(AudioCueHint { layer, cue: None }),
+            1 => {
+                let mut offset = 2;
+                let mut tracks = [None, None];
+                for track in tracks.iter_mut() {
+                    let length = u16::from_le_bytes([*bytes.get(offset)?, *bytes.get(offset + 1)?]) as usize;
+                    let name = std::str::from_utf8(bytes.get(offset + 2..offset + 2 + length)?).ok()?;
+                    *track = if name.is_empty() { None } else { Some(GlobalString::new(&name.to_string())) };
+                    offset += 2 + length;
+                }
+                if offset != bytes.len() {
+                    return None;
+                }
+                Some(AudioCueHint { layer, cue: Some(AudioCue { music: tracks[0], ambience: tracks[1] }) })
+            },
+            _ => None
+        };
+    }
+}
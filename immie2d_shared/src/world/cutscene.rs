@@ -0,0 +1,175 @@
+use super::tile_position::TilePosition;
+
+/// Longest dialogue line or speaker name a cue can carry, in bytes.
+pub const MAX_DIALOGUE_BYTES: usize = u16::MAX as usize;
+
+const STARTED_TAG: u8 = 0;
+const MOVE_ACTOR_TAG: u8 = 1;
+const PAN_CAMERA_TAG: u8 = 2;
+const SHOW_DIALOGUE_TAG: u8 = 3;
+const FADE_TAG: u8 = 4;
+const ENDED_TAG: u8 = 5;
+
+/* Something every client in a cutscene does at the same cutscene tick. The server runs the cutscene and sends cues as
+they happen, so clients only play them back. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CutsceneCue {
+    /// Player input is ignored until the cutscene ends.
+    Started,
+    /// Walk a player or NPC by its network id along a path of adjacent tiles, taking a number of ticks for each tile.
+    MoveActor { actor: u32, path: Vec<TilePosition>, ticks_per_tile: u32 },
+    PanCamera { to: TilePosition, ticks: u32 },
+    /// Show a line of dialogue until the player closes it. The speaker is empty for narration.
+    ShowDialogue { speaker: String, text: String },
+    /// Fade the screen to black, or back from black.
+    Fade { is_out: bool, ticks: u32 },
+    /// Return control to the player and the camera to them.
+    Ended
+}
+
+/* A cue of a cutscene, with the cutscene tick it happened on so clients can keep it in step with movement. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct CutsceneCueMessage {
+    pub cutscene: u32,
+    pub tick: u32,
+    pub cue: CutsceneCue
+}
+
+fn push_u32(bytes: &mut Vec<u8>, value: u32) {
+    bytes.extend_from_slice(&value.to_le_bytes());
+}
+
+fn push_position(bytes: &mut Vec<u8>, position: TilePosition) {
+    bytes.extend_from_slice(&position.x.to_le_bytes());
+    bytes.extend_from_slice(&position.y.to_le_bytes());
+}
+
+fn push_text(bytes: &mut Vec<u8>, text: &str) {
+    bytes.extend_from_slice(&(text.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(text.as_bytes());
+}
+
+/* Reads the fields of a message in order, failing once the bytes run out. */
+struct CueReader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl<'a> CueReader<'a> {
+    fn take(&mut self, count: usize) -> Option<&'a [u8]> {
+        let taken = self.bytes.get(self.position..self.position + count)?;
+        self.position += count;
+        return Some(taken);
+    }
+
+    fn take_u32(&mut self) -> Option<u32> {
+        return Some(u32::from_le_bytes(self.take(4)?.try_into().unwrap()));
+    }
+
+    fn take_position(&mut self) -> Option<TilePosition> {
+        let x = i32::from_le_bytes(self.take(4)?.try_into().unwrap());
+        let y = i32::from_le_bytes(self.take(4)?.try_into().unwrap());
+        return Some(TilePosition::new(x, y));
+    }
+
+    fn take_text(&mut self) -> Option<String> {
+        let length = u16::from_le_bytes(self.take(2)?.try_into().unwrap()) as usize;
+        return String::from_utf8(self.take(length)?.to_vec()).ok();
+    }
+}
+
+impl CutsceneCueMessage {
+    /// Encode as the cutscene id, tick and cue tag followed by the fields of the cue.
+    /// Will panic if dialogue is longer than MAX_DIALOGUE_BYTES.
+    /// ```
+    /// use immie2d_shared::world::cutscene::{CutsceneCue, CutsceneCueMessage};
+    /// use immie2d_shared::world::tile_position::TilePosition;
+    ///
+    /// let cues = [
+    ///     CutsceneCue::Started,
+    ///     CutsceneCue::MoveActor { actor: 7, path: vec![TilePosition::new(3, 4), TilePosition::new(3, 5)], ticks_per_tile: 8 },
+    ///     CutsceneCue::PanCamera { to: TilePosition::new(-2, 10), ticks: 40 },
+    ///     CutsceneCue::ShowDialogue { speaker: "Professor".to_string(), text: "Welcome to the world of Immies!".to_string() },
+    ///     CutsceneCue::Fade { is_out: true, ticks: 20 },
+    ///     CutsceneCue::Ended
+    /// ];
+    /// for cue in cues {
+    ///     let message = CutsceneCueMessage { cutscene: 2, tick: 90, cue };
+    ///     let bytes = message.to_bytes();
+    ///     assert_eq!(CutsceneCueMessage::from_bytes(&bytes), Some(message));
+    ///     assert_eq!(CutsceneCueMessage::from_bytes(&bytes[..bytes.len() - 1]), None);
+    /// }
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        push_u32(&mut bytes, self.cutscene);
+        push_u32(&mut bytes, self.tick);
+        match &self.cue {
+            CutsceneCue::Started => bytes.push(STARTED_TAG),
+            CutsceneCue::MoveActor { actor, path, ticks_per_tile } => {
+                bytes.push(MOVE_ACTOR_TAG);
+                push_u32(&mut bytes, *actor);
+                push_u32(&mut bytes, *ticks_per_tile);
+                push_u32(&mut bytes, path.len() as u32);
+                for tile in path.iter() {
+                    push_position(&mut bytes, *tile);
+                }
+            },
+            CutsceneCue::PanCamera { to, ticks } => {
+                bytes.push(PAN_CAMERA_TAG);
+                push_position(&mut bytes, *to);
+                push_u32(&mut bytes, *ticks);
+            },
+            CutsceneCue::ShowDialogue { speaker, text } => {
+                assert!(speaker.len() <= MAX_DIALOGUE_BYTES && text.len() <= MAX_DIALOGUE_BYTES, "Dialogue is longer than {} bytes", MAX_DIALOGUE_BYTES);
+                bytes.push(SHOW_DIALOGUE_TAG);
+                push_text(&mut bytes, speaker);
+                push_text(&mut bytes, text);
+            },
+            CutsceneCue::Fade { is_out, ticks } => {
+                bytes.push(FADE_TAG);
+                bytes.push(*is_out as u8);
+                push_u32(&mut bytes, *ticks);
+            },
+            CutsceneCue::Ended => bytes.push(ENDED_TAG)
+        }
+        return bytes;
+    }
+
+    /// Decode a message, or None if the bytes are not a valid message.
+    pub fn from_bytes(bytes: &[u8]) -> Option<CutsceneCueMessage> {
+        let mut reader = CueReader { bytes, position: 0 };
+        let cutscene = reader.take_u32()?;
+        let tick = reader.take_u32()?;
+        let cue = match reader.take(1)?[0] {
+            STARTED_TAG => CutsceneCue::Started,
+            MOVE_ACTOR_TAG => {
+                let actor = reader.take_u32()?;
+                let ticks_per_tile = reader.take_u32()?;
+                let length = reader.take_u32()? as usize;
+                // Checked before allocating, so a corrupt length can't reserve gigabytes
+                if length > (bytes.len() - reader.position) / 8 {
+                    return None;
+                }
+                let path = (0..length).map(|_| reader.take_position()).collect::<Option<Vec<TilePosition>>>()?;
+                CutsceneCue::MoveActor { actor, path, ticks_per_tile }
+            },
+            PAN_CAMERA_TAG => CutsceneCue::PanCamera { to: reader.take_position()?, ticks: reader.take_u32()? },
+            SHOW_DIALOGUE_TAG => CutsceneCue::ShowDialogue { speaker: reader.take_text()?, text: reader.take_text()? },
+            FADE_TAG => {
+                let is_out = match reader.take(1)?[0] {
+                    0 => false,
+                    1 => true,
+                    _ => return None
+                };
+                CutsceneCue::Fade { is_out, ticks: reader.take_u32()? }
+            },
+            ENDED_TAG => CutsceneCue::Ended,
+            _ => return None
+        };
+        if reader.position != bytes.len() {
+            return None;
+        }
+        return Some(CutsceneCueMessage { cutscene, tick, cue });
+    }
+}
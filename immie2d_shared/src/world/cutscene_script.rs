@@ -0,0 +1,212 @@
+use lazy_static::lazy_static;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, Scope, AST};
+
+use super::cutscene::{CutsceneCue, MAX_DIALOGUE_BYTES};
+use super::tile_position::TilePosition;
+
+/// Function of a cutscene script that describes the cutscene.
+pub const PLAY_FUNCTION: &str = "play";
+/// Most operations a script runs while describing its cutscene.
+pub const MAX_CUTSCENE_OPERATIONS: u64 = 100_000;
+/// Most cues and waits a single cutscene can have.
+pub const MAX_CUTSCENE_STEPS: usize = 1024;
+
+lazy_static! {
+    static ref CUTSCENE_ENGINE: Engine = create_engine();
+}
+
+/* Something a running cutscene waits for before its next step. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CutsceneCondition {
+    Ticks(u32),
+    /// An actor reaching the end of the path it was last moved along.
+    Arrival(u32),
+    /// Every player in the cutscene closing the dialogue.
+    DialogueClosed,
+    /// A flag set by the world, such as an NPC battle ending.
+    Flag(String)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum CutsceneStep {
+    Cue(CutsceneCue),
+    Wait(CutsceneCondition)
+}
+
+/* The steps a script has described so far, bound to `this` in its play function. */
+#[derive(Clone, Debug, Default)]
+struct CutsceneBuilder {
+    steps: Vec<CutsceneStep>
+}
+
+impl CutsceneBuilder {
+    fn push(&mut self, step: CutsceneStep) -> Result<(), Box<EvalAltResult>> {
+        if self.steps.len() >= MAX_CUTSCENE_STEPS {
+            return Err(format!("Cutscene has more than {} steps", MAX_CUTSCENE_STEPS).into());
+        }
+        self.steps.push(step);
+        return Ok(());
+    }
+}
+
+/* A story moment written in rhai, such as a rival walking up to the player and challenging them. The script's
+`play` function describes the cutscene through `this`, which the server then runs tick by tick:
+```text
+fn play() {
+    this.fade_in(20);
+    this.move_actor(12, [[4, 2], [4, 3], [4, 4]], 8);
+    this.wait_for_arrival(12);
+    this.say("Rival", "There you are!");
+    this.wait_for_dialogue();
+}
+```
+Scripts run sandboxed like ability scripts, limited to MAX_CUTSCENE_OPERATIONS. See AbilityScript */
+pub struct CutsceneScript {
+    ast: AST
+}
+
+impl CutsceneScript {
+    /// Compile a script, checking it has a play function without parameters.
+    pub fn compile(name: &str, source: &str) -> Result<CutsceneScript, String> {
+        let ast = CUTSCENE_ENGINE.compile(source).map_err(|err| format!("Cutscene [{}] doesn't compile: {}", name, err))?;
+        let params = ast.iter_functions().find(|function| function.name == PLAY_FUNCTION).map(|function| function.params.len());
+        return match params {
+            Some(0) => Ok(CutsceneScript { ast }),
+            Some(_) => Err(format!("Function [{}] of cutscene [{}] can't take parameters, it uses `this` instead", PLAY_FUNCTION, name)),
+            None => Err(format!("Cutscene [{}] has no [{}] function", name, PLAY_FUNCTION))
+        };
+    }
+
+    /// Run the script to get the steps of its cutscene, ending with the Ended cue.
+    /// ```
+    /// use immie2d_shared::world::cutscene::CutsceneCue;
+    /// use immie2d_shared::world::cutscene_script::{CutsceneCondition, CutsceneScript, CutsceneStep};
+    /// use immie2d_shared::world::tile_position::TilePosition;
+    ///
+    /// let script = CutsceneScript::compile("rival_intro", r#"
+    ///     fn play() {
+    ///         this.move_actor(12, [[4, 2], [4, 3]]);
+    ///         this.wait_for_arrival(12);
+    ///         this.say("Rival", "There you are!");
+    ///         this.wait_for_dialogue();
+    ///         this.fade_out(20);
+    ///         this.wait(20);
+    ///     }
+    /// "#).unwrap();
+    /// assert_eq!(script.build().unwrap(), vec![
+    ///     CutsceneStep::Cue(CutsceneCue::Started),
+    ///     CutsceneStep::Cue(CutsceneCue::MoveActor { actor: 12, path: vec![TilePosition::new(4, 2), TilePosition::new(4, 3)], ticks_per_tile: 8 }),
+    ///     CutsceneStep::Wait(CutsceneCondition::Arrival(12)),
+    ///     CutsceneStep::Cue(CutsceneCue::ShowDialogue { speaker: "Rival".to_string(), text: "There you are!".to_string() }),
+    ///     CutsceneStep::Wait(CutsceneCondition::DialogueClosed),
+    ///     CutsceneStep::Cue(CutsceneCue::Fade { is_out: true, ticks: 20 }),
+    ///     CutsceneStep::Wait(CutsceneCondition::Ticks(20)),
+    ///     CutsceneStep::Cue(CutsceneCue::Ended)
+    /// ]);
+    ///
+    /// // Paths must be made of adjacent tiles
+    /// let teleport = CutsceneScript::compile("teleport", "fn play() { this.move_actor(1, [[0, 0], [5, 5]]); }").unwrap();
+    /// assert!(teleport.build().is_err());
+    /// assert!(CutsceneScript::compile("empty", "let x = 1;").is_err());
+    /// let endless = CutsceneScript::compile("endless", "fn play() { loop { this.wait(1); } }").unwrap();
+    /// assert!(endless.build().is_err());
+    /// ```
+    pub fn build(&self) -> Result<Vec<CutsceneStep>, String> {
+        let mut this = Dynamic::from(CutsceneBuilder { steps: vec![CutsceneStep::Cue(CutsceneCue::Started)] });
+        let options = CallFnOptions::new().eval_ast(false).bind_this_ptr(&mut this);
+        let _ = CUTSCENE_ENGINE.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, PLAY_FUNCTION, ()).map_err(|err| err.to_string())?;
+        let mut builder = this.try_cast::<CutsceneBuilder>().ok_or(format!("Function [{}] replaced `this`", PLAY_FUNCTION))?;
+        builder.steps.push(CutsceneStep::Cue(CutsceneCue::Ended));
+        return Ok(builder.steps);
+    }
+}
+
+/// Ticks each tile of a path takes when a script doesn't say, which is walking speed.
+const DEFAULT_TICKS_PER_TILE: i64 = 8;
+
+fn to_ticks(ticks: i64) -> Result<u32, Box<EvalAltResult>> {
+    return u32::try_from(ticks).map_err(|_| format!("Invalid tick count {}", ticks).into());
+}
+
+fn to_actor(actor: i64) -> Result<u32, Box<EvalAltResult>> {
+    return u32::try_from(actor).map_err(|_| format!("Invalid actor {}", actor).into());
+}
+
+fn to_position(x: i64, y: i64) -> Result<TilePosition, Box<EvalAltResult>> {
+    return match (i32::try_from(x), i32::try_from(y)) {
+        (Ok(x), Ok(y)) => Ok(TilePosition::new(x, y)),
+        _ => Err(format!("Invalid tile [{}, {}]", x, y).into())
+    };
+}
+
+/// A path written as an array of `[x, y]` arrays, each adjacent to the one before.
+fn to_path(path: Array) -> Result<Vec<TilePosition>, Box<EvalAltResult>> {
+    let mut tiles: Vec<TilePosition> = Vec::new();
+    for tile in path {
+        let coordinates = tile.into_typed_array::<i64>().map_err(|_| "Path tiles must be [x, y] arrays".to_string())?;
+        let [x, y] = coordinates[..] else {
+            return Err("Path tiles must be [x, y] arrays".into());
+        };
+        let tile = to_position(x, y)?;
+        if let Some(previous) = tiles.last() {
+            if previous.direction_to(tile).is_none() {
+                return Err(format!("Path tile [{}, {}] isn't next to [{}, {}]", tile.x, tile.y, previous.x, previous.y).into());
+            }
+        }
+        tiles.push(tile);
+    }
+    if tiles.is_empty() {
+        return Err("Path is empty".into());
+    }
+    return Ok(tiles);
+}
+
+fn move_actor(builder: &mut CutsceneBuilder, actor: i64, path: Array, ticks_per_tile: i64) -> Result<(), Box<EvalAltResult>> {
+    let cue = CutsceneCue::MoveActor { actor: to_actor(actor)?, path: to_path(path)?, ticks_per_tile: to_ticks(ticks_per_tile)?.max(1) };
+    return builder.push(CutsceneStep::Cue(cue));
+}
+
+fn say(builder: &mut CutsceneBuilder, speaker: &str, text: &str) -> Result<(), Box<EvalAltResult>> {
+    if speaker.len() > MAX_DIALOGUE_BYTES || text.len() > MAX_DIALOGUE_BYTES {
+        return Err(format!("Dialogue is longer than {} bytes", MAX_DIALOGUE_BYTES).into());
+    }
+    return builder.push(CutsceneStep::Cue(CutsceneCue::ShowDialogue { speaker: speaker.to_string(), text: text.to_string() }));
+}
+
+/// An engine with only what cutscene scripts need, and the CutsceneBuilder API.
+fn create_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_CUTSCENE_OPERATIONS);
+    engine.set_max_call_levels(16);
+    engine.set_max_expr_depths(64, 32);
+    engine.set_max_string_size(4096);
+    engine.set_max_array_size(1024);
+    engine.set_max_map_size(256);
+    engine.disable_symbol("eval");
+    engine.on_print(|_| {});
+    engine.on_debug(|_, _, _| {});
+
+    engine.register_type_with_name::<CutsceneBuilder>("Cutscene");
+    engine.register_fn("move_actor", move_actor);
+    engine.register_fn("move_actor", |builder: &mut CutsceneBuilder, actor: i64, path: Array| move_actor(builder, actor, path, DEFAULT_TICKS_PER_TILE));
+    engine.register_fn("pan_camera", |builder: &mut CutsceneBuilder, x: i64, y: i64, ticks: i64| -> Result<(), Box<EvalAltResult>> {
+        return builder.push(CutsceneStep::Cue(CutsceneCue::PanCamera { to: to_position(x, y)?, ticks: to_ticks(ticks)? }));
+    });
+    engine.register_fn("say", say);
+    engine.register_fn("narrate", |builder: &mut CutsceneBuilder, text: &str| say(builder, "", text));
+    engine.register_fn("fade_out", |builder: &mut CutsceneBuilder, ticks: i64| -> Result<(), Box<EvalAltResult>> {
+        return builder.push(CutsceneStep::Cue(CutsceneCue::Fade { is_out: true, ticks: to_ticks(ticks)? }));
+    });
+    engine.register_fn("fade_in", |builder: &mut CutsceneBuilder, ticks: i64| -> Result<(), Box<EvalAltResult>> {
+        return builder.push(CutsceneStep::Cue(CutsceneCue::Fade { is_out: false, ticks: to_ticks(ticks)? }));
+    });
+    engine.register_fn("wait", |builder: &mut CutsceneBuilder, ticks: i64| -> Result<(), Box<EvalAltResult>> {
+        return builder.push(CutsceneStep::Wait(CutsceneCondition::Ticks(to_ticks(ticks)?)));
+    });
+    engine.register_fn("wait_for_arrival", |builder: &mut CutsceneBuilder, actor: i64| -> Result<(), Box<EvalAltResult>> {
+        return builder.push(CutsceneStep::Wait(CutsceneCondition::Arrival(to_actor(actor)?)));
+    });
+    engine.register_fn("wait_for_dialogue", |builder: &mut CutsceneBuilder| builder.push(CutsceneStep::Wait(CutsceneCondition::DialogueClosed)));
+    engine.register_fn("wait_for_flag", |builder: &mut CutsceneBuilder, flag: &str| builder.push(CutsceneStep::Wait(CutsceneCondition::Flag(flag.to_string()))));
+    return engine;
+}
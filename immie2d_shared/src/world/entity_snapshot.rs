@@ -0,0 +1,22 @@
+use crate::engine_types::global_string::GlobalString;
+
+use super::tile_position::{Direction, WorldPosition};
+
+/* What an overworld entity is, so clients know how to display it. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum EntityKind {
+    Player,
+    /// An Immie following the entity with the owner network id.
+    Follower { owner: u32, species: GlobalString },
+    Npc
+}
+
+/* The state of a single overworld entity at a tick, as sent to clients. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct EntitySnapshot {
+    pub network_id: u32,
+    pub kind: EntityKind,
+    pub position: WorldPosition,
+    pub facing: Direction,
+    pub is_visible: bool
+}
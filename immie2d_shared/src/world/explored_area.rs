@@ -0,0 +1,89 @@
+use crate::engine_types::global_string::GlobalString;
+
+/// How many minimap cells around the player's cell are revealed as they move.
+pub const EXPLORE_RADIUS: u32 = 2;
+
+/* Sent to a client when it explores new minimap cells of a map, so it can clear the fog over them. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExploredAreaUpdate {
+    pub map: GlobalString,
+    pub cells: Vec<(u32, u32)>
+}
+
+/* Which minimap cells of a single map a player has explored, as a bitset in row order. Anything unexplored is
+covered by fog of war. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ExploredArea {
+    width: u32,
+    height: u32,
+    bits: Vec<u64>
+}
+
+impl ExploredArea {
+    /// Create an area the size of a minimap, with nothing explored.
+    pub fn new(width: u32, height: u32) -> ExploredArea {
+        return ExploredArea { width, height, bits: vec![0; (width as usize * height as usize).div_ceil(64)] };
+    }
+
+    /// Recreate an area from its persisted bits. Returns None if the bits are the wrong length for the size.
+    pub fn from_bits(width: u32, height: u32, bits: Vec<u64>) -> Option<ExploredArea> {
+        if bits.len() != (width as usize * height as usize).div_ceil(64) {
+            return None;
+        }
+        return Some(ExploredArea { width, height, bits });
+    }
+
+    pub fn get_bits(&self) -> &[u64] {
+        return &self.bits;
+    }
+
+    pub fn get_width(&self) -> u32 {
+        return self.width;
+    }
+
+    pub fn get_height(&self) -> u32 {
+        return self.height;
+    }
+
+    /// Cells outside the area are never explored.
+    pub fn is_explored(&self, x: u32, y: u32) -> bool {
+        if x >= self.width || y >= self.height {
+            return false;
+        }
+        let index = (y * self.width + x) as usize;
+        return self.bits[index / 64] & (1 << (index % 64)) != 0;
+    }
+
+    pub fn get_explored_count(&self) -> u32 {
+        return self.bits.iter().map(|word| word.count_ones()).sum();
+    }
+
+    /// Explore every cell within a radius of a cell, in a square. Returns the cells that weren't explored before, in row order.
+    /// ```
+    /// use immie2d_shared::world::explored_area::ExploredArea;
+    ///
+    /// let mut area = ExploredArea::new(10, 10);
+    /// assert_eq!(area.explore_around(0, 0, 1), vec![(0, 0), (1, 0), (0, 1), (1, 1)]);
+    /// assert_eq!(area.explore_around(1, 0, 1), vec![(2, 0), (2, 1)]);
+    /// assert!(area.explore_around(0, 0, 1).is_empty());
+    /// assert_eq!(area.get_explored_count(), 6);
+    /// assert_eq!(ExploredArea::from_bits(10, 10, area.get_bits().to_vec()), Some(area));
+    /// ```
+    pub fn explore_around(&mut self, x: u32, y: u32, radius: u32) -> Vec<(u32, u32)> {
+        let mut newly_explored = Vec::new();
+        if self.width == 0 || self.height == 0 {
+            return newly_explored;
+        }
+        for cell_y in y.saturating_sub(radius)..=(y.saturating_add(radius)).min(self.height - 1) {
+            for cell_x in x.saturating_sub(radius)..=(x.saturating_add(radius)).min(self.width - 1) {
+                if self.is_explored(cell_x, cell_y) {
+                    continue;
+                }
+                let index = (cell_y * self.width + cell_x) as usize;
+                self.bits[index / 64] |= 1 << (index % 64);
+                newly_explored.push((cell_x, cell_y));
+            }
+        }
+        return newly_explored;
+    }
+}
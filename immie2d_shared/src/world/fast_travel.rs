@@ -0,0 +1,145 @@
+use crate::engine_types::global_string::GlobalString;
+
+use super::tile_position::{TilePosition, WorldPosition};
+
+const UNLOCKED_TAG: u8 = 0;
+const TRAVELLED_TAG: u8 = 1;
+const DENIED_TAG: u8 = 2;
+
+/* Why the server refused a fast travel request. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FastTravelDenial {
+    /// No fast travel point has the name.
+    UnknownPoint,
+    /// The player hasn't visited the point yet.
+    Locked,
+    InBattle,
+    /// The player is in an area fast travel can't be started from, such as a dungeon.
+    RestrictedZone,
+    /// The player fast travelled too recently, and can again after some world ticks.
+    CoolingDown { ticks_remaining: u64 }
+}
+
+impl FastTravelDenial {
+    pub fn get_id(self) -> u8 {
+        return match self {
+            FastTravelDenial::UnknownPoint => 0,
+            FastTravelDenial::Locked => 1,
+            FastTravelDenial::InBattle => 2,
+            FastTravelDenial::RestrictedZone => 3,
+            FastTravelDenial::CoolingDown { .. } => 4
+        };
+    }
+}
+
+/* Fast travel news for a single player. Travelling is sent before the player is moved, so the client can play its
+departure effect on the old map and its arrival effect once the new one is loaded. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum FastTravelEvent {
+    /// The player visited a point for the first time, and can now travel to it.
+    Unlocked { point: GlobalString },
+    Travelled { point: GlobalString, from: WorldPosition, to: WorldPosition },
+    Denied { point: GlobalString, denial: FastTravelDenial }
+}
+
+fn push_name(bytes: &mut Vec<u8>, name: GlobalString) {
+    name.with_str(|name| {
+        bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(name.as_bytes());
+    });
+}
+
+fn push_position(bytes: &mut Vec<u8>, position: WorldPosition) {
+    push_name(bytes, position.map);
+    bytes.extend_from_slice(&position.tile.x.to_le_bytes());
+    bytes.extend_from_slice(&position.tile.y.to_le_bytes());
+}
+
+impl FastTravelEvent {
+    /// Encode as a tag byte followed by the fields of the event. Names are u16 length prefixed.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::fast_travel::{FastTravelEvent, FastTravelDenial};
+    /// use immie2d_shared::world::tile_position::{TilePosition, WorldPosition};
+    ///
+    /// let point = GlobalString::new(&"ember town".to_string());
+    /// let from = WorldPosition::new(GlobalString::new(&"route 2".to_string()), TilePosition::new(-3, 8));
+    /// let to = WorldPosition::new(GlobalString::new(&"ember town".to_string()), TilePosition::new(12, 4));
+    /// let events = [
+    ///     FastTravelEvent::Unlocked { point },
+    ///     FastTravelEvent::Travelled { point, from, to },
+    ///     FastTravelEvent::Denied { point, denial: FastTravelDenial::InBattle },
+    ///     FastTravelEvent::Denied { point, denial: FastTravelDenial::CoolingDown { ticks_remaining: 240 } }
+    /// ];
+    /// for event in events {
+    ///     let bytes = event.to_bytes();
+    ///     assert_eq!(FastTravelEvent::from_bytes(&bytes), Some(event));
+    ///     assert_eq!(FastTravelEvent::from_bytes(&bytes[..bytes.len() - 1]), None);
+    /// }
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        match *self {
+            FastTravelEvent::Unlocked { point } => {
+                bytes.push(UNLOCKED_TAG);
+                push_name(&mut bytes, point);
+            },
+            FastTravelEvent::Travelled { point, from, to } => {
+                bytes.push(TRAVELLED_TAG);
+                push_name(&mut bytes, point);
+                push_position(&mut bytes, from);
+                push_position(&mut bytes, to);
+            },
+            FastTravelEvent::Denied { point, denial } => {
+                bytes.push(DENIED_TAG);
+                push_name(&mut bytes, point);
+                bytes.push(denial.get_id());
+                if let FastTravelDenial::CoolingDown { ticks_remaining } = denial {
+                    bytes.extend_from_slice(&ticks_remaining.to_le_bytes());
+                }
+            }
+        }
+        return bytes;
+    }
+
+    /// Decode an event, or None if the bytes are not a valid event.
+    pub fn from_bytes(bytes: &[u8]) -> Option<FastTravelEvent> {
+        let mut offset = 0;
+        let take = |offset: &mut usize, count: usize| -> Option<&[u8]> {
+            let taken = bytes.get(*offset..*offset + count)?;
+            *offset += count;
+            return Some(taken);
+        };
+        let take_name = |offset: &mut usize| -> Option<GlobalString> {
+            let length = u16::from_le_bytes(take(offset, 2)?.try_into().unwrap()) as usize;
+            return Some(GlobalString::new(&std::str::from_utf8(take(offset, length)?).ok()?.to_string()));
+        };
+        let take_position = |offset: &mut usize| -> Option<WorldPosition> {
+            let map = take_name(offset)?;
+            let x = i32::from_le_bytes(take(offset, 4)?.try_into().unwrap());
+            let y = i32::from_le_bytes(take(offset, 4)?.try_into().unwrap());
+            return Some(WorldPosition::new(map, TilePosition::new(x, y)));
+        };
+        let event = match take(&mut offset, 1)?[0] {
+            UNLOCKED_TAG => FastTravelEvent::Unlocked { point: take_name(&mut offset)? },
+            TRAVELLED_TAG => FastTravelEvent::Travelled { point: take_name(&mut offset)?, from: take_position(&mut offset)?, to: take_position(&mut offset)? },
+            DENIED_TAG => {
+                let point = take_name(&mut offset)?;
+                let denial = match take(&mut offset, 1)?[0] {
+                    0 => FastTravelDenial::UnknownPoint,
+                    1 => FastTravelDenial::Locked,
+                    2 => FastTravelDenial::InBattle,
+                    3 => FastTravelDenial::RestrictedZone,
+                    4 => FastTravelDenial::CoolingDown { ticks_remaining: u64::from_le_bytes(take(&mut offset, 8)?.try_into().unwrap()) },
+                    _ => return None
+                };
+                FastTravelEvent::Denied { point, denial }
+            },
+            _ => return None
+        };
+        if offset != bytes.len() {
+            return None;
+        }
+        return Some(event);
+    }
+}
@@ -0,0 +1,84 @@
+use std::cmp::Ordering;
+
+/// Capture the flag is always played by two teams.
+pub const FLAG_TEAM_COUNT: usize = 2;
+
+const SCOREBOARD_LENGTH: usize = 23;
+
+/* The state of a capture the flag minigame that every player in it is shown. Teams are indexed 0 and 1. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FlagScoreboard {
+    /// Ticks left until the minigame ends on time.
+    pub remaining_ticks: u32,
+    /// Flags each team has captured.
+    pub scores: [u32; FLAG_TEAM_COUNT],
+    /// Network id of the player carrying each team's flag, or None while the flag is at its base.
+    pub carriers: [Option<u32>; FLAG_TEAM_COUNT],
+    pub is_finished: bool
+}
+
+impl FlagScoreboard {
+    /// The team with more captures, or None while tied.
+    /// ```
+    /// use immie2d_shared::world::flag_scoreboard::FlagScoreboard;
+    ///
+    /// let mut scoreboard = FlagScoreboard { remaining_ticks: 0, scores: [1, 1], carriers: [None, None], is_finished: true };
+    /// assert_eq!(scoreboard.get_leader(), None);
+    /// scoreboard.scores[1] = 2;
+    /// assert_eq!(scoreboard.get_leader(), Some(1));
+    /// ```
+    pub fn get_leader(&self) -> Option<usize> {
+        return match self.scores[0].cmp(&self.scores[1]) {
+            Ordering::Greater => Some(0),
+            Ordering::Less => Some(1),
+            Ordering::Equal => None
+        };
+    }
+
+    /// Encode the scoreboard to broadcast to the players of the minigame.
+    /// ```
+    /// use immie2d_shared::world::flag_scoreboard::FlagScoreboard;
+    ///
+    /// let scoreboard = FlagScoreboard { remaining_ticks: 1200, scores: [2, 0], carriers: [None, Some(7)], is_finished: false };
+    /// assert_eq!(FlagScoreboard::from_bytes(&scoreboard.to_bytes()), Some(scoreboard));
+    /// assert_eq!(FlagScoreboard::from_bytes(&scoreboard.to_bytes()[..22]), None);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(SCOREBOARD_LENGTH);
+        bytes.extend_from_slice(&self.remaining_ticks.to_le_bytes());
+        for score in self.scores {
+            bytes.extend_from_slice(&score.to_le_bytes());
+        }
+        for carrier in self.carriers {
+            bytes.push(carrier.is_some() as u8);
+            bytes.extend_from_slice(&carrier.unwrap_or(0).to_le_bytes());
+        }
+        bytes.push(self.is_finished as u8);
+        return bytes;
+    }
+
+    /// Decode a scoreboard, or None if the bytes are not a valid scoreboard.
+    pub fn from_bytes(bytes: &[u8]) -> Option<FlagScoreboard> {
+        if bytes.len() != SCOREBOARD_LENGTH {
+            return None;
+        }
+        let read_u32 = |offset: usize| u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let remaining_ticks = read_u32(0);
+        let scores = [read_u32(4), read_u32(8)];
+        let mut carriers = [None; FLAG_TEAM_COUNT];
+        for (team, carrier) in carriers.iter_mut().enumerate() {
+            let offset = 12 + team * 5;
+            *carrier = match bytes[offset] {
+                0 => None,
+                1 => Some(read_u32(offset + 1)),
+                _ => return None
+            };
+        }
+        let is_finished = match bytes[22] {
+            0 => false,
+            1 => true,
+            _ => return None
+        };
+        return Some(FlagScoreboard { remaining_ticks, scores, carriers, is_finished });
+    }
+}
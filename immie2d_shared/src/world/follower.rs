@@ -0,0 +1,95 @@
+use crate::engine_types::global_string::GlobalString;
+
+use super::entity_snapshot::{EntityKind, EntitySnapshot};
+use super::tile_position::{Direction, WorldPosition};
+
+/* An Immie that trails its leader one tile behind in the overworld, stepping into the tile the leader just left.
+Since it only ever enters tiles the leader has already walked through, it can never be blocked by map collision. */
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Follower {
+    species: GlobalString,
+    position: WorldPosition,
+    facing: Direction,
+    is_visible: bool
+}
+
+impl Follower {
+    /// Create a follower on the leader's tile. It stays hidden until the leader takes a step.
+    pub fn new(species: GlobalString, leader_position: WorldPosition, leader_facing: Direction) -> Follower {
+        return Follower {
+            species,
+            position: leader_position,
+            facing: leader_facing,
+            is_visible: false
+        };
+    }
+
+    pub fn get_species(&self) -> GlobalString {
+        return self.species;
+    }
+
+    pub fn get_position(&self) -> WorldPosition {
+        return self.position;
+    }
+
+    pub fn get_facing(&self) -> Direction {
+        return self.facing;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        return self.is_visible;
+    }
+
+    /// Update the follower after its leader moved between two positions.
+    /// A single step moves the follower into the tile the leader left. Anything else, such as a map transition
+    /// or a warp within a map, places the follower on the leader's tile hidden until the leader's next step.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::tile_position::{TilePosition, WorldPosition, Direction};
+    /// use immie2d_shared::world::follower::Follower;
+    ///
+    /// let town = GlobalString::new(&"town".to_string());
+    /// let at = |x, y| WorldPosition::new(town, TilePosition::new(x, y));
+    /// let mut follower = Follower::new(GlobalString::new(&"lavapup".to_string()), at(0, 0), Direction::Down);
+    /// assert!(!follower.is_visible());
+    ///
+    /// follower.on_leader_moved(at(0, 0), at(1, 0));
+    /// assert_eq!(follower.get_position(), at(0, 0));
+    /// assert!(follower.is_visible());
+    ///
+    /// follower.on_leader_moved(at(1, 0), at(1, 1));
+    /// assert_eq!(follower.get_position(), at(1, 0));
+    /// assert_eq!(follower.get_facing(), Direction::Right);
+    ///
+    /// let route = WorldPosition::new(GlobalString::new(&"route".to_string()), TilePosition::new(5, 5));
+    /// follower.on_leader_moved(at(1, 1), route);
+    /// assert_eq!(follower.get_position(), route);
+    /// assert!(!follower.is_visible());
+    /// ```
+    pub fn on_leader_moved(&mut self, from: WorldPosition, to: WorldPosition) {
+        if !from.is_adjacent(to) {
+            self.position = to;
+            self.is_visible = false;
+            return;
+        }
+        if self.position.is_adjacent(from) {
+            self.facing = self.position.tile.direction_to(from.tile).unwrap();
+        } else {
+            // The follower was hidden or out of step, so face where the leader is heading.
+            self.facing = from.tile.direction_to(to.tile).unwrap();
+        }
+        self.position = from;
+        self.is_visible = true;
+    }
+
+    /// Create the snapshot of this follower for the snapshot system.
+    pub fn to_snapshot(&self, network_id: u32, owner: u32) -> EntitySnapshot {
+        return EntitySnapshot {
+            network_id,
+            kind: EntityKind::Follower { owner, species: self.species },
+            position: self.position,
+            facing: self.facing,
+            is_visible: self.is_visible
+        };
+    }
+}
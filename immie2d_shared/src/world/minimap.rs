@@ -0,0 +1,134 @@
+use crate::engine_types::global_string::GlobalString;
+
+use super::tile_map::{MapObject, TileMap};
+use super::tile_position::TilePosition;
+
+/// Width and height in tiles of a single minimap cell.
+pub const MINIMAP_CELL_SIZE: u32 = 4;
+
+/* What a minimap cell shows, summarizing the tiles it covers. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MinimapCell {
+    Open,
+    /// Most of the tiles in the cell are blocked.
+    Blocked,
+    /// Part of the cell has wild encounters.
+    Encounter,
+    /// The cell contains a warp to another map.
+    Warp
+}
+
+/* Downsampled view of a map, with one cell for each square of tiles. Cells on the right and bottom edges may cover
+fewer tiles if the map size isn't a multiple of the cell size. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct Minimap {
+    map: GlobalString,
+    cell_size: u32,
+    width: u32,
+    height: u32,
+    cells: Vec<MinimapCell>
+}
+
+impl Minimap {
+    /// Generate the minimap of a map. Will panic if the cell size is 0.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::{tile_map::{TileMap, MapObject}, tile_position::{TilePosition, WorldPosition}};
+    /// use immie2d_shared::world::minimap::{Minimap, MinimapCell};
+    ///
+    /// let town = GlobalString::new(&"town".to_string());
+    /// let mut map = TileMap::new(town, 5, 2);
+    /// map.set_blocked(TilePosition::new(0, 0), true);
+    /// map.set_blocked(TilePosition::new(0, 1), true);
+    /// map.set_blocked(TilePosition::new(1, 0), true);
+    /// map.add_object(MapObject::Warp { tile: TilePosition::new(4, 1), target: WorldPosition::new(town, TilePosition::new(0, 0)) });
+    ///
+    /// let minimap = Minimap::generate(&map, 2);
+    /// assert_eq!((minimap.get_width(), minimap.get_height()), (3, 1));
+    /// assert_eq!(minimap.get_cell(0, 0), MinimapCell::Blocked);
+    /// assert_eq!(minimap.get_cell(1, 0), MinimapCell::Open);
+    /// assert_eq!(minimap.get_cell(2, 0), MinimapCell::Warp);
+    /// assert_eq!(minimap.get_cell_of(TilePosition::new(3, 1)), Some((1, 0)));
+    /// ```
+    pub fn generate(map: &TileMap, cell_size: u32) -> Minimap {
+        assert!(cell_size > 0, "Minimap cell size must be at least 1");
+        let width = map.get_width().div_ceil(cell_size);
+        let height = map.get_height().div_ceil(cell_size);
+        let mut minimap = Minimap { map: map.get_name(), cell_size, width, height, cells: Vec::with_capacity(width as usize * height as usize) };
+        for cell_y in 0..height {
+            for cell_x in 0..width {
+                minimap.cells.push(summarize_cell(map, cell_x * cell_size, cell_y * cell_size, cell_size));
+            }
+        }
+        return minimap;
+    }
+
+    pub fn get_map(&self) -> GlobalString {
+        return self.map;
+    }
+
+    pub fn get_cell_size(&self) -> u32 {
+        return self.cell_size;
+    }
+
+    /// Width in cells.
+    pub fn get_width(&self) -> u32 {
+        return self.width;
+    }
+
+    /// Height in cells.
+    pub fn get_height(&self) -> u32 {
+        return self.height;
+    }
+
+    /// Get a cell. Will panic if it is outside the minimap.
+    pub fn get_cell(&self, x: u32, y: u32) -> MinimapCell {
+        assert!(x < self.width && y < self.height, "Minimap cell ({}, {}) is outside of the {}x{} minimap of {}", x, y, self.width, self.height, self.map);
+        return self.cells[y as usize * self.width as usize + x as usize];
+    }
+
+    /// Get the cell covering a tile, or None if the tile is outside the map.
+    pub fn get_cell_of(&self, tile: TilePosition) -> Option<(u32, u32)> {
+        if tile.x < 0 || tile.y < 0 {
+            return None;
+        }
+        let cell = (tile.x as u32 / self.cell_size, tile.y as u32 / self.cell_size);
+        if cell.0 >= self.width || cell.1 >= self.height {
+            return None;
+        }
+        return Some(cell);
+    }
+}
+
+fn summarize_cell(map: &TileMap, start_x: u32, start_y: u32, cell_size: u32) -> MinimapCell {
+    let mut tile_count = 0;
+    let mut blocked_count = 0;
+    let mut has_encounter = false;
+    let mut has_warp = false;
+    for y in start_y..(start_y + cell_size).min(map.get_height()) {
+        for x in start_x..(start_x + cell_size).min(map.get_width()) {
+            let tile = TilePosition::new(x as i32, y as i32);
+            tile_count += 1;
+            if map.is_blocked(tile) {
+                blocked_count += 1;
+            }
+            for object in map.get_objects() {
+                match object {
+                    MapObject::Warp { tile: warp, .. } if *warp == tile => has_warp = true,
+                    MapObject::EncounterZone { area, .. } if area.contains(tile) => has_encounter = true,
+                    _ => {}
+                }
+            }
+        }
+    }
+    if has_warp {
+        return MinimapCell::Warp;
+    }
+    if blocked_count * 2 > tile_count {
+        return MinimapCell::Blocked;
+    }
+    if has_encounter {
+        return MinimapCell::Encounter;
+    }
+    return MinimapCell::Open;
+}
@@ -0,0 +1,17 @@
+pub mod tile_position;
+pub mod entity_snapshot;
+pub mod follower;
+pub mod tile_map;
+pub mod tiled_import;
+pub mod world_object;
+pub mod minimap;
+pub mod explored_area;
+pub mod region_weather;
+pub mod move_result;
+pub mod audio_cue;
+pub mod snapshot_codec;
+pub mod flag_scoreboard;
+pub mod simulation_status;
+pub mod cutscene;
+pub mod cutscene_script;
+pub mod fast_travel;
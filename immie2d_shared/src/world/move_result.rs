@@ -0,0 +1,86 @@
+use super::tile_position::TilePosition;
+
+/* Why the server denied a step. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum MoveDenial {
+    /// The tile is blocked by the map, or out of bounds.
+    Blocked,
+    /// Another entity is standing on the tile.
+    Occupied,
+    /// Another entity was granted the tile this tick.
+    Contested,
+    /// The entity wasn't where it thought it was, such as after an earlier denial it hasn't received yet.
+    OutOfSync
+}
+
+impl MoveDenial {
+    pub fn get_id(self) -> u8 {
+        return match self {
+            MoveDenial::Blocked => 0,
+            MoveDenial::Occupied => 1,
+            MoveDenial::Contested => 2,
+            MoveDenial::OutOfSync => 3
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<MoveDenial> {
+        return match id {
+            0 => Some(MoveDenial::Blocked),
+            1 => Some(MoveDenial::Occupied),
+            2 => Some(MoveDenial::Contested),
+            3 => Some(MoveDenial::OutOfSync),
+            _ => None
+        };
+    }
+}
+
+/* The server's answer to a step an entity tried to take in a tick. Clients predict their steps, so a denied step is
+rolled back to `tile`, the tile the server has the entity at. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct MoveResult {
+    pub network_id: u32,
+    pub tile: TilePosition,
+    /// None if the step was granted.
+    pub denial: Option<MoveDenial>
+}
+
+impl MoveResult {
+    pub fn is_granted(&self) -> bool {
+        return self.denial.is_none();
+    }
+
+    /// Encode the result to send to the client that tried to step.
+    /// ```
+    /// use immie2d_shared::world::move_result::{MoveResult, MoveDenial};
+    /// use immie2d_shared::world::tile_position::TilePosition;
+    ///
+    /// let denied = MoveResult { network_id: 7, tile: TilePosition::new(-2, 5), denial: Some(MoveDenial::Contested) };
+    /// assert_eq!(MoveResult::from_bytes(&denied.to_bytes()), Some(denied));
+    /// let granted = MoveResult { network_id: 7, tile: TilePosition::new(-1, 5), denial: None };
+    /// assert_eq!(MoveResult::from_bytes(&granted.to_bytes()), Some(granted));
+    /// assert_eq!(MoveResult::from_bytes(&[1, 2]), None);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(13);
+        bytes.extend_from_slice(&self.network_id.to_le_bytes());
+        bytes.extend_from_slice(&self.tile.x.to_le_bytes());
+        bytes.extend_from_slice(&self.tile.y.to_le_bytes());
+        bytes.push(match self.denial {
+            Some(denial) => denial.get_id() + 1,
+            None => 0
+        });
+        return bytes;
+    }
+
+    /// Decode a result, or None if the bytes are not a valid result.
+    pub fn from_bytes(bytes: &[u8]) -> Option<MoveResult> {
+        let network_id = u32::from_le_bytes(bytes.get(0..4)?.try_into().ok()?);
+        let x = i32::from_le_bytes(bytes.get(4..8)?.try_into().ok()?);
+        let y = i32::from_le_bytes(bytes.get(8..12)?.try_into().ok()?);
+        let denial = match *bytes.get(12)? {
+            0 => None,
+            id => Some(MoveDenial::from_id(id - 1)?)
+        };
+        return Some(MoveResult { network_id, tile: TilePosition::new(x, y), denial });
+    }
+}
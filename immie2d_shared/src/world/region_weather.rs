@@ -0,0 +1,89 @@
+use crate::engine_types::game_rng::GameRng;
+use crate::engine_types::weighted_table::WeightedTable;
+use crate::engine_types::global_string::GlobalString;
+use crate::gameplay::encounter::encounter_conditions::Weather;
+
+/* The weathers a region can have, each with a weight. The chance of a weather is its weight divided by the total,
+which is never 0. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct WeatherForecast {
+    weathers: Vec<(Weather, u32)>,
+    table: WeightedTable<Weather>
+}
+
+impl WeatherForecast {
+    /// A region that is always clear.
+    pub fn default() -> WeatherForecast {
+        return WeatherForecast::new(vec![(Weather::Clear, 1)]);
+    }
+
+    /// Will panic if there are no weathers or every weight is 0.
+    /// ``` should_panic
+    /// # use immie2d_shared::gameplay::encounter::encounter_conditions::Weather;
+    /// # use immie2d_shared::world::region_weather::WeatherForecast;
+    /// // Will panic
+    /// let forecast = WeatherForecast::new(vec![(Weather::Rain, 0), (Weather::Fog, 0)]);
+    /// ```
+    pub fn new(weathers: Vec<(Weather, u32)>) -> WeatherForecast {
+        let table = WeightedTable::new(weathers.iter().map(|(weather, weight)| (*weather, *weight as u64)).collect())
+            .expect("A weather forecast needs at least one weather with a weight above 0");
+        return WeatherForecast { weathers, table };
+    }
+
+    pub fn get_weathers(&self) -> &[(Weather, u32)] {
+        return &self.weathers;
+    }
+
+    /// Roll the next weather, sampled from a WeightedTable with two draws from the rng. Weather used to be chosen with a
+    /// single draw, so a seed rolls a different forecast than it did before weighted tables.
+    /// ```
+    /// use immie2d_shared::engine_types::game_rng::GameRng;
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::Weather;
+    /// use immie2d_shared::world::region_weather::WeatherForecast;
+    ///
+    /// let forecast = WeatherForecast::new(vec![(Weather::Rain, 1), (Weather::Fog, 0)]);
+    /// let mut rng = GameRng::new(3);
+    /// for _ in 0..20 {
+    ///     assert_eq!(forecast.roll(&mut rng), Weather::Rain);
+    /// }
+    /// ```
+    pub fn roll(&self, rng: &mut GameRng) -> Weather {
+        return *self.table.sample(rng);
+    }
+}
+
+/* Sent to every client in a region when its weather changes, and to clients entering a region. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WeatherUpdate {
+    pub map: GlobalString,
+    pub weather: Weather
+}
+
+impl WeatherUpdate {
+    /// Encode as the weather id followed by the length prefixed map name.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::gameplay::encounter::encounter_conditions::Weather;
+    /// use immie2d_shared::world::region_weather::WeatherUpdate;
+    ///
+    /// let update = WeatherUpdate { map: GlobalString::new(&"route 1".to_string()), weather: Weather::Snow };
+    /// assert_eq!(WeatherUpdate::from_bytes(&update.to_bytes()), Some(update));
+    /// assert_eq!(WeatherUpdate::from_bytes(&[]), None);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let map = self.map.to_string();
+        let mut bytes = Vec::with_capacity(3 + map.len());
+        bytes.push(self.weather.get_id());
+        bytes.extend_from_slice(&(map.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(map.as_bytes());
+        return bytes;
+    }
+
+    /// Decode an update, or None if the bytes are not a valid update.
+    pub fn from_bytes(bytes: &[u8]) -> Option<WeatherUpdate> {
+        let weather = Weather::from_id(*bytes.first()?)?;
+        let length = u16::from_le_bytes([*bytes.get(1)?, *bytes.get(2)?]) as usize;
+        let map = std::str::from_utf8(bytes.get(3..3 + length)?).ok()?;
+        return Some(WeatherUpdate { map: GlobalString::new(&map.to_string()), weather });
+    }
+}
@@ -0,0 +1,62 @@
+const STATUS_LENGTH: usize = 13;
+
+/* Whether the server's world simulation is running, sent to every client whenever it is paused, stepped, resumed or
+its tick rate changes. Clients show a notice while it is paused, so players on a dev server know the world isn't
+frozen by a bug. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SimulationStatus {
+    pub is_paused: bool,
+    /// Ticks simulated since the server started.
+    pub tick: u64,
+    /// Ticks per second while running.
+    pub tick_rate: u32
+}
+
+impl SimulationStatus {
+    /// The notice to show while the simulation is paused, or None while it is running.
+    /// ```
+    /// use immie2d_shared::world::simulation_status::SimulationStatus;
+    ///
+    /// let mut status = SimulationStatus { is_paused: false, tick: 1200, tick_rate: 20 };
+    /// assert_eq!(status.get_notice(), None);
+    /// status.is_paused = true;
+    /// assert_eq!(status.get_notice(), Some("Simulation paused at tick 1200".to_string()));
+    /// ```
+    pub fn get_notice(&self) -> Option<String> {
+        if !self.is_paused {
+            return None;
+        }
+        return Some(format!("Simulation paused at tick {}", self.tick));
+    }
+
+    /// Encode the status to broadcast to every client.
+    /// ```
+    /// use immie2d_shared::world::simulation_status::SimulationStatus;
+    ///
+    /// let status = SimulationStatus { is_paused: true, tick: 1 << 40, tick_rate: 20 };
+    /// assert_eq!(SimulationStatus::from_bytes(&status.to_bytes()), Some(status));
+    /// assert_eq!(SimulationStatus::from_bytes(&status.to_bytes()[..12]), None);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(STATUS_LENGTH);
+        bytes.push(self.is_paused as u8);
+        bytes.extend_from_slice(&self.tick.to_le_bytes());
+        bytes.extend_from_slice(&self.tick_rate.to_le_bytes());
+        return bytes;
+    }
+
+    /// Decode a status, or None if the bytes are not a valid status.
+    pub fn from_bytes(bytes: &[u8]) -> Option<SimulationStatus> {
+        if bytes.len() != STATUS_LENGTH {
+            return None;
+        }
+        let is_paused = match bytes[0] {
+            0 => false,
+            1 => true,
+            _ => return None
+        };
+        let tick = u64::from_le_bytes(bytes[1..9].try_into().unwrap());
+        let tick_rate = u32::from_le_bytes(bytes[9..13].try_into().unwrap());
+        return Some(SimulationStatus { is_paused, tick, tick_rate });
+    }
+}
@@ -0,0 +1,286 @@
+use std::mem;
+
+use bytes::BytesMut;
+
+use crate::engine_types::bit_packing::{get_bits_needed, BitReader, BitWriter};
+use crate::engine_types::global_string::GlobalString;
+
+use super::entity_snapshot::{EntityKind, EntitySnapshot};
+use super::tile_position::{Direction, TilePosition, WorldPosition};
+
+/// Bits of an entity kind or facing direction in the packed encoding.
+const KIND_BITS: u32 = 2;
+const DIRECTION_BITS: u32 = 2;
+/// Bits of the per tick bit counts, each up to 32.
+const BIT_COUNT_BITS: u32 = 6;
+
+fn get_kind_id(kind: &EntityKind) -> u8 {
+    return match kind {
+        EntityKind::Player => 0,
+        EntityKind::Follower { .. } => 1,
+        EntityKind::Npc => 2
+    };
+}
+
+/// Encode the snapshots of a tick with every field at its full width and names as length prefixed strings. Kept to
+/// measure the packed encoding against. See encode_packed()
+/// ```
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::world::entity_snapshot::{EntityKind, EntitySnapshot};
+/// use immie2d_shared::world::snapshot_codec::{decode_naive, encode_naive};
+/// use immie2d_shared::world::tile_position::{Direction, TilePosition, WorldPosition};
+///
+/// let town = GlobalString::new(&"town".to_string());
+/// let player = EntitySnapshot { network_id: 4, kind: EntityKind::Player, position: WorldPosition::new(town, TilePosition::new(-3, 9)), facing: Direction::Left, is_visible: true };
+/// let follower = EntitySnapshot { network_id: 5, kind: EntityKind::Follower { owner: 4, species: GlobalString::new(&"lavapup".to_string()) }, ..player };
+/// let bytes = encode_naive(&[player, follower]);
+/// assert_eq!(bytes.len(), 2 + 21 + 34);
+/// assert_eq!(decode_naive(&bytes), Some(vec![player, follower]));
+/// assert_eq!(decode_naive(&bytes[..bytes.len() - 1]), None);
+/// ```
+pub fn encode_naive(snapshots: &[EntitySnapshot]) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(&(snapshots.len() as u16).to_le_bytes());
+    for snapshot in snapshots {
+        bytes.extend_from_slice(&snapshot.network_id.to_le_bytes());
+        bytes.push(get_kind_id(&snapshot.kind));
+        if let EntityKind::Follower { owner, species } = snapshot.kind {
+            bytes.extend_from_slice(&owner.to_le_bytes());
+            push_name(&mut bytes, species);
+        }
+        push_name(&mut bytes, snapshot.position.map);
+        bytes.extend_from_slice(&snapshot.position.tile.x.to_le_bytes());
+        bytes.extend_from_slice(&snapshot.position.tile.y.to_le_bytes());
+        bytes.push(snapshot.facing.get_id());
+        bytes.push(snapshot.is_visible as u8);
+    }
+    return bytes;
+}
+
+/// Decode the snapshots of a tick, or None if the bytes are not a valid naive encoding.
+pub fn decode_naive(bytes: &[u8]) -> Option<Vec<EntitySnapshot>> {
+    let mut offset = 0;
+    let count = u16::from_le_bytes(take(bytes, &mut offset, 2)?.try_into().ok()?);
+    let mut snapshots = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let network_id = u32::from_le_bytes(take(bytes, &mut offset, 4)?.try_into().ok()?);
+        let kind = match take(bytes, &mut offset, 1)?[0] {
+            0 => EntityKind::Player,
+            1 => {
+                let owner = u32::from_le_bytes(take(bytes, &mut offset, 4)?.try_into().ok()?);
+                EntityKind::Follower { owner, species: take_name(bytes, &mut offset)? }
+            },
+            2 => EntityKind::Npc,
+            _ => return None
+        };
+        let map = take_name(bytes, &mut offset)?;
+        let x = i32::from_le_bytes(take(bytes, &mut offset, 4)?.try_into().ok()?);
+        let y = i32::from_le_bytes(take(bytes, &mut offset, 4)?.try_into().ok()?);
+        let facing = Direction::from_id(take(bytes, &mut offset, 1)?[0])?;
+        let is_visible = match take(bytes, &mut offset, 1)?[0] {
+            0 => false,
+            1 => true,
+            _ => return None
+        };
+        snapshots.push(EntitySnapshot { network_id, kind, position: WorldPosition::new(map, TilePosition::new(x, y)), facing, is_visible });
+    }
+    if offset != bytes.len() {
+        return None;
+    }
+    return Some(snapshots);
+}
+
+fn push_name(bytes: &mut Vec<u8>, name: GlobalString) {
+    let name = name.to_string();
+    bytes.extend_from_slice(&(name.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(name.as_bytes());
+}
+
+fn take<'a>(bytes: &'a [u8], offset: &mut usize, length: usize) -> Option<&'a [u8]> {
+    let taken = bytes.get(*offset..*offset + length)?;
+    *offset += length;
+    return Some(taken);
+}
+
+fn take_name(bytes: &[u8], offset: &mut usize) -> Option<GlobalString> {
+    let length = u16::from_le_bytes(take(bytes, offset, 2)?.try_into().ok()?) as usize;
+    let name = std::str::from_utf8(take(bytes, offset, length)?).ok()?;
+    return Some(GlobalString::new(&name.to_string()));
+}
+
+/// Encode the snapshots of a tick in as few bits as their values need. Map and species names are sent once per tick
+/// in a table and referenced by index. Tiles are quantized to the box around every entity, so each coordinate takes
+/// only the bits needed to span it. Network ids are varints of the difference from the previous entity, which stays
+/// small since the server sends entities in id order. Kinds and facings take 2 bits and visibility 1.
+/// ```
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::world::entity_snapshot::{EntityKind, EntitySnapshot};
+/// use immie2d_shared::world::snapshot_codec::{decode_packed, encode_naive, encode_packed};
+/// use immie2d_shared::world::tile_position::{Direction, TilePosition, WorldPosition};
+///
+/// let town = GlobalString::new(&"town".to_string());
+/// let player = EntitySnapshot { network_id: 4, kind: EntityKind::Player, position: WorldPosition::new(town, TilePosition::new(-3, 9)), facing: Direction::Left, is_visible: true };
+/// let follower = EntitySnapshot { network_id: 5, kind: EntityKind::Follower { owner: 4, species: GlobalString::new(&"lavapup".to_string()) }, ..player };
+/// let npc = EntitySnapshot { network_id: 9, kind: EntityKind::Npc, position: WorldPosition::new(town, TilePosition::new(12, 0)), facing: Direction::Down, is_visible: false };
+/// let snapshots = vec![player, follower, npc];
+/// let bytes = encode_packed(&snapshots);
+/// assert_eq!(decode_packed(&bytes), Some(snapshots.clone()));
+/// assert!(bytes.len() < encode_naive(&snapshots).len() / 2);
+///
+/// assert_eq!(decode_packed(&encode_packed(&[])), Some(Vec::new()));
+/// assert_eq!(decode_packed(&bytes[..bytes.len() - 1]), None);
+/// assert_eq!(decode_packed(&[bytes.clone(), vec![0]].concat()), None);
+/// ```
+pub fn encode_packed(snapshots: &[EntitySnapshot]) -> Vec<u8> {
+    let mut buffer = BytesMut::new();
+    SnapshotEncoder::new().encode_packed_into(snapshots, &mut buffer);
+    return buffer.into();
+}
+
+/* Encodes the snapshots of every tick with encode_packed(), reusing its name table between ticks so encoding into a
+pooled buffer doesn't allocate once the table has grown to fit a tick. */
+pub struct SnapshotEncoder {
+    names: Vec<GlobalString>
+}
+
+impl SnapshotEncoder {
+    pub fn new() -> SnapshotEncoder {
+        return SnapshotEncoder { names: Vec::new() };
+    }
+
+    /// Append the packed encoding of a tick's snapshots to a buffer. See encode_packed()
+    /// ```
+    /// use bytes::BytesMut;
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::entity_snapshot::{EntityKind, EntitySnapshot};
+    /// use immie2d_shared::world::snapshot_codec::{decode_packed, encode_packed, SnapshotEncoder};
+    /// use immie2d_shared::world::tile_position::{Direction, TilePosition, WorldPosition};
+    ///
+    /// let town = GlobalString::new(&"town".to_string());
+    /// let player = EntitySnapshot { network_id: 4, kind: EntityKind::Player, position: WorldPosition::new(town, TilePosition::new(-3, 9)), facing: Direction::Left, is_visible: true };
+    /// let mut encoder = SnapshotEncoder::new();
+    /// let mut buffer = BytesMut::from(&[7u8][..]);
+    /// encoder.encode_packed_into(&[player], &mut buffer);
+    /// assert_eq!(buffer[0], 7);
+    /// assert_eq!(&buffer[1..], &encode_packed(&[player])[..]);
+    /// assert_eq!(decode_packed(&buffer[1..]), Some(vec![player]));
+    /// ```
+    pub fn encode_packed_into(&mut self, snapshots: &[EntitySnapshot], buffer: &mut BytesMut) {
+        let names = &mut self.names;
+        names.clear();
+        for snapshot in snapshots {
+            if let EntityKind::Follower { species, .. } = snapshot.kind {
+                if !names.contains(&species) {
+                    names.push(species);
+                }
+            }
+            if !names.contains(&snapshot.position.map) {
+                names.push(snapshot.position.map);
+            }
+        }
+        let mut writer = BitWriter::with_buffer(mem::take(buffer));
+        writer.write_varint(snapshots.len() as u64);
+        if snapshots.is_empty() {
+            *buffer = writer.into_buffer();
+            return;
+        }
+        writer.write_varint(names.len() as u64);
+        for name in names.iter() {
+            name.with_str(|name| {
+                writer.write_varint(name.len() as u64);
+                for byte in name.bytes() {
+                    writer.write_bits(byte as u64, 8);
+                }
+            });
+        }
+        let name_bits = get_bits_needed(names.len() as u64 - 1);
+        let min_x = snapshots.iter().map(|snapshot| snapshot.position.tile.x).min().unwrap();
+        let min_y = snapshots.iter().map(|snapshot| snapshot.position.tile.y).min().unwrap();
+        let max_x = snapshots.iter().map(|snapshot| snapshot.position.tile.x).max().unwrap();
+        let max_y = snapshots.iter().map(|snapshot| snapshot.position.tile.y).max().unwrap();
+        let x_bits = get_bits_needed((max_x as i64 - min_x as i64) as u64);
+        let y_bits = get_bits_needed((max_y as i64 - min_y as i64) as u64);
+        writer.write_signed_varint(min_x as i64);
+        writer.write_signed_varint(min_y as i64);
+        writer.write_bits(x_bits as u64, BIT_COUNT_BITS);
+        writer.write_bits(y_bits as u64, BIT_COUNT_BITS);
+
+        let get_index = |name: GlobalString| names.iter().position(|existing| *existing == name).unwrap() as u64;
+        let mut previous_id: u32 = 0;
+        for snapshot in snapshots {
+            writer.write_signed_varint(snapshot.network_id as i64 - previous_id as i64);
+            previous_id = snapshot.network_id;
+            writer.write_bits(get_kind_id(&snapshot.kind) as u64, KIND_BITS);
+            if let EntityKind::Follower { owner, species } = snapshot.kind {
+                writer.write_varint(owner as u64);
+                writer.write_bits(get_index(species), name_bits);
+            }
+            writer.write_bits(get_index(snapshot.position.map), name_bits);
+            writer.write_bits((snapshot.position.tile.x as i64 - min_x as i64) as u64, x_bits);
+            writer.write_bits((snapshot.position.tile.y as i64 - min_y as i64) as u64, y_bits);
+            writer.write_bits(snapshot.facing.get_id() as u64, DIRECTION_BITS);
+            writer.write_bool(snapshot.is_visible);
+        }
+        *buffer = writer.into_buffer();
+    }
+}
+
+/// Decode the snapshots of a tick, or None if the bytes are not a valid packed encoding.
+pub fn decode_packed(bytes: &[u8]) -> Option<Vec<EntitySnapshot>> {
+    let mut reader = BitReader::new(bytes);
+    let count = reader.read_varint()?;
+    // Every entity takes at least a byte, which bounds the allocation for hostile counts
+    if count > bytes.len() as u64 {
+        return None;
+    }
+    let mut snapshots = Vec::with_capacity(count as usize);
+    if count > 0 {
+        let name_count = reader.read_varint()?;
+        if name_count == 0 || name_count > bytes.len() as u64 {
+            return None;
+        }
+        let mut names = Vec::with_capacity(name_count as usize);
+        for _ in 0..name_count {
+            let length = reader.read_varint()?;
+            if length > (reader.get_remaining_bits() / 8) as u64 {
+                return None;
+            }
+            let name: Vec<u8> = (0..length).map(|_| reader.read_bits(8).map(|byte| byte as u8)).collect::<Option<Vec<u8>>>()?;
+            names.push(GlobalString::new(&String::from_utf8(name).ok()?));
+        }
+        let name_bits = get_bits_needed(name_count - 1);
+        let min_x = i32::try_from(reader.read_signed_varint()?).ok()?;
+        let min_y = i32::try_from(reader.read_signed_varint()?).ok()?;
+        let x_bits = reader.read_bits(BIT_COUNT_BITS)? as u32;
+        let y_bits = reader.read_bits(BIT_COUNT_BITS)? as u32;
+        if x_bits > 32 || y_bits > 32 {
+            return None;
+        }
+        let read_name = |reader: &mut BitReader| names.get(reader.read_bits(name_bits)? as usize).copied();
+        let mut previous_id: u32 = 0;
+        for _ in 0..count {
+            let network_id = u32::try_from((previous_id as i64).checked_add(reader.read_signed_varint()?)?).ok()?;
+            previous_id = network_id;
+            let kind = match reader.read_bits(KIND_BITS)? {
+                0 => EntityKind::Player,
+                1 => {
+                    let owner = u32::try_from(reader.read_varint()?).ok()?;
+                    EntityKind::Follower { owner, species: read_name(&mut reader)? }
+                },
+                2 => EntityKind::Npc,
+                _ => return None
+            };
+            let map = read_name(&mut reader)?;
+            let x = i32::try_from(min_x as i64 + reader.read_bits(x_bits)? as i64).ok()?;
+            let y = i32::try_from(min_y as i64 + reader.read_bits(y_bits)? as i64).ok()?;
+            let facing = Direction::from_id(reader.read_bits(DIRECTION_BITS)? as u8)?;
+            let is_visible = reader.read_bool()?;
+            snapshots.push(EntitySnapshot { network_id, kind, position: WorldPosition::new(map, TilePosition::new(x, y)), facing, is_visible });
+        }
+    }
+    // Only the padding of the last byte can be left
+    if reader.get_remaining_bits() >= 8 || reader.read_bits(reader.get_remaining_bits() as u32)? != 0 {
+        return None;
+    }
+    return Some(snapshots);
+}
@@ -0,0 +1,149 @@
+use crate::engine_types::global_string::GlobalString;
+
+use super::tile_position::{TilePosition, WorldPosition};
+
+/// Most tiles a map can have, which keeps every layer of a map loaded from a file to a reasonable size.
+pub const MAX_MAP_TILE_COUNT: usize = 4096 * 4096;
+
+/// Get the number of tiles of a map size, or None if it's above MAX_MAP_TILE_COUNT.
+/// ```
+/// use immie2d_shared::world::tile_map::{get_tile_count, MAX_MAP_TILE_COUNT};
+/// assert_eq!(get_tile_count(3, 2), Some(6));
+/// assert_eq!(get_tile_count(4096, 4097), None);
+/// assert_eq!(get_tile_count(u32::MAX, u32::MAX), None);
+/// ```
+pub fn get_tile_count(width: u32, height: u32) -> Option<usize> {
+    return (width as usize).checked_mul(height as usize).filter(|count| *count <= MAX_MAP_TILE_COUNT);
+}
+
+/* A rectangle of tiles. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TileRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32
+}
+
+impl TileRect {
+    /// ```
+    /// use immie2d_shared::world::{tile_map::TileRect, tile_position::TilePosition};
+    /// let rect = TileRect { x: 2, y: 2, width: 2, height: 1 };
+    /// assert!(rect.contains(TilePosition::new(3, 2)));
+    /// assert!(!rect.contains(TilePosition::new(4, 2)));
+    /// ```
+    pub fn contains(&self, tile: TilePosition) -> bool {
+        return tile.x >= self.x && tile.y >= self.y && tile.x < self.x + self.width as i32 && tile.y < self.y + self.height as i32;
+    }
+}
+
+/* Something placed on a map that isn't a tile. */
+#[derive(Clone, PartialEq, Debug)]
+pub enum MapObject {
+    NpcSpawn { npc: GlobalString, tile: TilePosition },
+    /// Wild encounters in the area are rolled from the encounter table.
+    EncounterZone { table: GlobalString, area: TileRect },
+    /// Stepping on the tile moves the player to the target.
+    Warp { tile: TilePosition, target: WorldPosition },
+    /// Visiting the tile unlocks fast travel to it. Point names are unique across every map.
+    FastTravelPoint { point: GlobalString, tile: TilePosition },
+    /// Fast travel can't be started from inside the area, such as in a dungeon.
+    RestrictedZone { area: TileRect },
+    /// Walking into the area plays the cutscene, named after its script file. See CutsceneScript
+    CutsceneTrigger { cutscene: GlobalString, area: TileRect }
+}
+
+/* A visual layer of tile ids, row by row. Id 0 is an empty tile. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct TileLayer {
+    pub name: String,
+    pub tiles: Vec<u32>
+}
+
+/* A single overworld map. */
+#[derive(Clone, PartialEq, Debug)]
+pub struct TileMap {
+    name: GlobalString,
+    width: u32,
+    height: u32,
+    layers: Vec<TileLayer>,
+    collision: Vec<bool>,
+    objects: Vec<MapObject>
+}
+
+impl TileMap {
+    /// Create an empty map with no collision. Will panic if the map has more than MAX_MAP_TILE_COUNT tiles.
+    pub fn new(name: GlobalString, width: u32, height: u32) -> TileMap {
+        let tile_count = get_tile_count(width, height).unwrap_or_else(|| panic!("Map {} of {} by {} tiles is larger than {} tiles", name, width, height, MAX_MAP_TILE_COUNT));
+        return TileMap {
+            name,
+            width,
+            height,
+            layers: Vec::new(),
+            collision: vec![false; tile_count],
+            objects: Vec::new()
+        };
+    }
+
+    pub fn get_name(&self) -> GlobalString {
+        return self.name;
+    }
+
+    pub fn get_width(&self) -> u32 {
+        return self.width;
+    }
+
+    pub fn get_height(&self) -> u32 {
+        return self.height;
+    }
+
+    pub fn get_layers(&self) -> &[TileLayer] {
+        return &self.layers;
+    }
+
+    pub fn get_objects(&self) -> &[MapObject] {
+        return &self.objects;
+    }
+
+    /// Add a layer. Will panic if the layer isn't the size of the map.
+    pub fn add_layer(&mut self, layer: TileLayer) {
+        assert!(layer.tiles.len() == self.collision.len(), "Tile layer {} has {} tiles but the map has {}", layer.name, layer.tiles.len(), self.collision.len());
+        self.layers.push(layer);
+    }
+
+    pub fn add_object(&mut self, object: MapObject) {
+        self.objects.push(object);
+    }
+
+    pub fn is_in_bounds(&self, tile: TilePosition) -> bool {
+        return tile.x >= 0 && tile.y >= 0 && (tile.x as u32) < self.width && (tile.y as u32) < self.height;
+    }
+
+    /// Check if a tile can't be walked on. Tiles outside the map are always blocked.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::{tile_map::TileMap, tile_position::TilePosition};
+    /// let mut map = TileMap::new(GlobalString::new(&"town".to_string()), 4, 4);
+    /// map.set_blocked(TilePosition::new(1, 1), true);
+    /// assert!(map.is_blocked(TilePosition::new(1, 1)));
+    /// assert!(!map.is_blocked(TilePosition::new(1, 2)));
+    /// assert!(map.is_blocked(TilePosition::new(-1, 0)));
+    /// ```
+    pub fn is_blocked(&self, tile: TilePosition) -> bool {
+        if !self.is_in_bounds(tile) {
+            return true;
+        }
+        return self.collision[self.index_of(tile)];
+    }
+
+    /// Set if a tile is blocked. Will panic if the tile is outside the map.
+    pub fn set_blocked(&mut self, tile: TilePosition, blocked: bool) {
+        assert!(self.is_in_bounds(tile), "Tile {:?} is outside of map {}", tile, self.name);
+        let index = self.index_of(tile);
+        self.collision[index] = blocked;
+    }
+
+    fn index_of(&self, tile: TilePosition) -> usize {
+        return tile.y as usize * self.width as usize + tile.x as usize;
+    }
+}
@@ -0,0 +1,118 @@
+use crate::engine_types::global_string::GlobalString;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+/// Every direction, in id order.
+pub const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+impl Direction {
+    pub fn get_name(self) -> &'static str {
+        return match self {
+            Direction::Up => "up",
+            Direction::Down => "down",
+            Direction::Left => "left",
+            Direction::Right => "right"
+        };
+    }
+
+    pub fn from_name(name: &str) -> Option<Direction> {
+        return DIRECTIONS.iter().copied().find(|direction| direction.get_name() == name);
+    }
+
+    pub fn get_id(self) -> u8 {
+        return match self {
+            Direction::Up => 0,
+            Direction::Down => 1,
+            Direction::Left => 2,
+            Direction::Right => 3
+        };
+    }
+
+    pub fn from_id(id: u8) -> Option<Direction> {
+        return match id {
+            0 => Some(Direction::Up),
+            1 => Some(Direction::Down),
+            2 => Some(Direction::Left),
+            3 => Some(Direction::Right),
+            _ => None
+        };
+    }
+}
+
+/* A tile coordinate within a single map. Y increases downwards. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct TilePosition {
+    pub x: i32,
+    pub y: i32
+}
+
+impl TilePosition {
+    pub fn new(x: i32, y: i32) -> TilePosition {
+        return TilePosition { x, y };
+    }
+
+    /// Get the neighbouring tile in a direction.
+    /// ```
+    /// use immie2d_shared::world::tile_position::{TilePosition, Direction};
+    /// assert_eq!(TilePosition::new(3, 3).offset(Direction::Up), TilePosition::new(3, 2));
+    /// ```
+    pub fn offset(&self, direction: Direction) -> TilePosition {
+        return match direction {
+            Direction::Up => TilePosition::new(self.x, self.y - 1),
+            Direction::Down => TilePosition::new(self.x, self.y + 1),
+            Direction::Left => TilePosition::new(self.x - 1, self.y),
+            Direction::Right => TilePosition::new(self.x + 1, self.y)
+        };
+    }
+
+    /// Get the direction of a neighbouring tile, or None if the tile isn't directly adjacent.
+    /// ```
+    /// use immie2d_shared::world::tile_position::{TilePosition, Direction};
+    /// let tile = TilePosition::new(3, 3);
+    /// assert_eq!(tile.direction_to(TilePosition::new(4, 3)), Some(Direction::Right));
+    /// assert_eq!(tile.direction_to(TilePosition::new(4, 4)), None);
+    /// assert_eq!(tile.direction_to(tile), None);
+    /// ```
+    pub fn direction_to(&self, other: TilePosition) -> Option<Direction> {
+        return match (other.x - self.x, other.y - self.y) {
+            (0, -1) => Some(Direction::Up),
+            (0, 1) => Some(Direction::Down),
+            (-1, 0) => Some(Direction::Left),
+            (1, 0) => Some(Direction::Right),
+            _ => None
+        };
+    }
+}
+
+/* A tile on a specific map. */
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct WorldPosition {
+    pub map: GlobalString,
+    pub tile: TilePosition
+}
+
+impl WorldPosition {
+    pub fn new(map: GlobalString, tile: TilePosition) -> WorldPosition {
+        return WorldPosition { map, tile };
+    }
+
+    /// Check if another position is one step away on the same map.
+    /// ```
+    /// use immie2d_shared::engine_types::global_string::GlobalString;
+    /// use immie2d_shared::world::tile_position::{TilePosition, WorldPosition};
+    /// let town = GlobalString::new(&"town".to_string());
+    /// let route = GlobalString::new(&"route".to_string());
+    /// let position = WorldPosition::new(town, TilePosition::new(0, 0));
+    /// assert!(position.is_adjacent(WorldPosition::new(town, TilePosition::new(0, 1))));
+    /// assert!(!position.is_adjacent(WorldPosition::new(route, TilePosition::new(0, 1))));
+    /// ```
+    pub fn is_adjacent(&self, other: WorldPosition) -> bool {
+        return self.map == other.map && self.tile.direction_to(other.tile).is_some();
+    }
+}
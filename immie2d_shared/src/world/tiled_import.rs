@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::engine_types::global_string::GlobalString;
+
+use super::tile_map::{get_tile_count, MapObject, TileLayer, TileMap, TileRect, MAX_MAP_TILE_COUNT};
+use super::tile_position::{TilePosition, WorldPosition};
+
+/// Tile layers with this name (case insensitive) become the map's collision instead of a visual layer. Any non empty tile is blocked.
+pub const COLLISION_LAYER_NAME: &str = "collision";
+
+/// Tiled stores flip and rotation flags in the highest bits of a tile id.
+const TILE_FLAG_MASK: u32 = 0xF0000000;
+
+/* Why a Tiled map could not be imported. */
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum TiledImportError {
+    Io(String),
+    /// The file isn't valid JSON or XML.
+    Parse(String),
+    /// Uses a Tiled feature that isn't supported, such as compressed layer data or infinite maps.
+    Unsupported(String),
+    /// Valid Tiled data that can't be converted, such as a warp missing its target.
+    Invalid(String)
+}
+
+impl fmt::Display for TiledImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        return match self {
+            TiledImportError::Io(message) => write!(f, "Failed to read Tiled map: {}", message),
+            TiledImportError::Parse(message) => write!(f, "Failed to parse Tiled map: {}", message),
+            TiledImportError::Unsupported(message) => write!(f, "Unsupported Tiled feature: {}", message),
+            TiledImportError::Invalid(message) => write!(f, "Invalid Tiled map: {}", message)
+        };
+    }
+}
+
+/* Format independent contents of a Tiled map, before conversion to a TileMap. */
+struct RawObject {
+    name: String,
+    kind: String,
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    properties: HashMap<String, String>
+}
+
+struct RawMap {
+    width: u32,
+    height: u32,
+    tile_width: u32,
+    tile_height: u32,
+    layers: Vec<TileLayer>,
+    objects: Vec<RawObject>
+}
+
+/// Import a .tmj or .tmx file, chosen by extension. The map is named after the file name without its extension.
+pub fn import_tiled_file(path: &Path) -> Result<TileMap, TiledImportError> {
+    let text = fs::read_to_string(path).map_err(|err| TiledImportError::Io(err.to_string()))?;
+    let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    let name = GlobalString::new(&name);
+    return match path.extension().and_then(|extension| extension.to_str()) {
+        Some("tmj") | Some("json") => import_tmj(name, &text),
+        Some("tmx") => import_tmx(name, &text),
+        _ => Err(TiledImportError::Unsupported(format!("Unknown map file extension of {}", path.display())))
+    };
+}
+
+/// Import a map saved in Tiled's JSON format.
+/// ```
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::world::{tiled_import::import_tmj, tile_map::MapObject, tile_position::TilePosition};
+///
+/// let json = r#"{
+///     "width": 3, "height": 2, "tilewidth": 16, "tileheight": 16,
+///     "layers": [
+///         { "type": "tilelayer", "name": "ground", "data": [1, 1, 1, 2, 2, 2] },
+///         { "type": "tilelayer", "name": "Collision", "data": [0, 5, 0, 0, 0, 0] },
+///         { "type": "objectgroup", "name": "objects", "objects": [
+///             { "name": "to route", "type": "warp", "x": 32, "y": 16, "width": 16, "height": 16,
+///               "properties": [
+///                 { "name": "target_map", "type": "string", "value": "route" },
+///                 { "name": "target_x", "type": "int", "value": 4 },
+///                 { "name": "target_y", "type": "int", "value": 0 }
+///               ] }
+///         ] }
+///     ]
+/// }"#;
+/// let map = import_tmj(GlobalString::new(&"town".to_string()), json).unwrap();
+/// assert_eq!(map.get_layers().len(), 1);
+/// assert!(map.is_blocked(TilePosition::new(1, 0)));
+/// assert!(!map.is_blocked(TilePosition::new(0, 0)));
+/// match map.get_objects()[0] {
+///     MapObject::Warp { tile, target } => {
+///         assert_eq!(tile, TilePosition::new(2, 1));
+///         assert_eq!(target.map, GlobalString::new(&"route".to_string()));
+///     },
+///     _ => panic!("Expected a warp")
+/// }
+/// ```
+pub fn import_tmj(name: GlobalString, text: &str) -> Result<TileMap, TiledImportError> {
+    let json: serde_json::Value = serde_json::from_str(text).map_err(|err| TiledImportError::Parse(err.to_string()))?;
+    if json["infinite"].as_bool() == Some(true) {
+        return Err(TiledImportError::Unsupported("infinite maps".to_string()));
+    }
+    let mut raw = RawMap {
+        width: json_u32(&json, "width")?,
+        height: json_u32(&json, "height")?,
+        tile_width: json_u32(&json, "tilewidth")?,
+        tile_height: json_u32(&json, "tileheight")?,
+        layers: Vec::new(),
+        objects: Vec::new()
+    };
+    let layers = json["layers"].as_array().ok_or(TiledImportError::Invalid("map has no layers".to_string()))?;
+    read_tmj_layers(layers, &mut raw)?;
+    return build_tile_map(name, raw);
+}
+
+fn json_u32(json: &serde_json::Value, key: &str) -> Result<u32, TiledImportError> {
+    return json[key].as_u64().map(|value| value as u32).ok_or(TiledImportError::Invalid(format!("missing integer {}", key)));
+}
+
+fn read_tmj_layers(layers: &[serde_json::Value], raw: &mut RawMap) -> Result<(), TiledImportError> {
+    for layer in layers {
+        let layer_name = layer["name"].as_str().unwrap_or_default().to_string();
+        match layer["type"].as_str() {
+            Some("tilelayer") => {
+                if layer.get("encoding").and_then(|encoding| encoding.as_str()).is_some_and(|encoding| encoding != "csv") {
+                    return Err(TiledImportError::Unsupported(format!("encoded data in layer {}. Save with CSV layer format", layer_name)));
+                }
+                let data = layer["data"].as_array().ok_or(TiledImportError::Invalid(format!("layer {} has no data", layer_name)))?;
+                let tiles = data.iter().map(|tile| (tile.as_u64().unwrap_or(0) as u32) & !TILE_FLAG_MASK).collect();
+                raw.layers.push(TileLayer { name: layer_name, tiles });
+            },
+            Some("objectgroup") => {
+                for object in layer["objects"].as_array().into_iter().flatten() {
+                    let mut properties = HashMap::new();
+                    for property in object["properties"].as_array().into_iter().flatten() {
+                        let value = match &property["value"] {
+                            serde_json::Value::String(string) => string.clone(),
+                            other => other.to_string()
+                        };
+                        properties.insert(property["name"].as_str().unwrap_or_default().to_string(), value);
+                    }
+                    // Tiled 1.9 renamed an object's type to class.
+                    let kind = object["type"].as_str().filter(|kind| !kind.is_empty()).or(object["class"].as_str()).unwrap_or_default();
+                    raw.objects.push(RawObject {
+                        name: object["name"].as_str().unwrap_or_default().to_string(),
+                        kind: kind.to_string(),
+                        x: object["x"].as_f64().unwrap_or(0.0),
+                        y: object["y"].as_f64().unwrap_or(0.0),
+                        width: object["width"].as_f64().unwrap_or(0.0),
+                        height: object["height"].as_f64().unwrap_or(0.0),
+                        properties
+                    });
+                }
+            },
+            Some("group") => read_tmj_layers(layer["layers"].as_array().map(|layers| layers.as_slice()).unwrap_or_default(), raw)?,
+            _ => {}
+        }
+    }
+    return Ok(());
+}
+
+/// Import a map saved in Tiled's XML format.
+/// ```
+/// use immie2d_shared::engine_types::global_string::GlobalString;
+/// use immie2d_shared::world::{tiled_import::import_tmx, tile_map::{MapObject, TileRect}, tile_position::TilePosition};
+///
+/// let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+/// <map version="1.10" orientation="orthogonal" width="2" height="2" tilewidth="16" tileheight="16" infinite="0">
+///   <layer id="1" name="ground" width="2" height="2"><data encoding="csv">1,2,3,4</data></layer>
+///   <objectgroup id="2" name="objects">
+///     <object id="1" name="grass" class="encounter" x="0" y="0" width="32" height="16">
+///       <properties><property name="table" value="route 1 grass"/></properties>
+///     </object>
+///     <object id="2" name="professor" type="npc" x="16" y="16" width="16" height="16"/>
+///     <object id="3" name="route 1" type="fast_travel" x="16" y="0" width="16" height="16"/>
+///     <object id="4" name="cave" type="restricted" x="0" y="16" width="32" height="16"/>
+///     <object id="5" name="rival_intro" type="cutscene" x="0" y="0" width="16" height="32"/>
+///   </objectgroup>
+/// </map>"#;
+/// let map = import_tmx(GlobalString::new(&"route".to_string()), xml).unwrap();
+/// assert_eq!(map.get_layers()[0].tiles, vec![1, 2, 3, 4]);
+/// assert_eq!(map.get_objects()[0], MapObject::EncounterZone {
+///     table: GlobalString::new(&"route 1 grass".to_string()),
+///     area: TileRect { x: 0, y: 0, width: 2, height: 1 }
+/// });
+/// assert!(matches!(map.get_objects()[1], MapObject::NpcSpawn { .. }));
+/// assert_eq!(map.get_objects()[2], MapObject::FastTravelPoint { point: GlobalString::new(&"route 1".to_string()), tile: TilePosition::new(1, 0) });
+/// assert_eq!(map.get_objects()[3], MapObject::RestrictedZone { area: TileRect { x: 0, y: 1, width: 2, height: 1 } });
+/// assert_eq!(map.get_objects()[4], MapObject::CutsceneTrigger {
+///     cutscene: GlobalString::new(&"rival_intro".to_string()),
+///     area: TileRect { x: 0, y: 0, width: 1, height: 2 }
+/// });
+/// ```
+pub fn import_tmx(name: GlobalString, text: &str) -> Result<TileMap, TiledImportError> {
+    let document = roxmltree::Document::parse(text).map_err(|err| TiledImportError::Parse(err.to_string()))?;
+    let root = document.root_element();
+    if root.attribute("infinite") == Some("1") {
+        return Err(TiledImportError::Unsupported("infinite maps".to_string()));
+    }
+    let mut raw = RawMap {
+        width: xml_u32(&root, "width")?,
+        height: xml_u32(&root, "height")?,
+        tile_width: xml_u32(&root, "tilewidth")?,
+        tile_height: xml_u32(&root, "tileheight")?,
+        layers: Vec::new(),
+        objects: Vec::new()
+    };
+    read_tmx_layers(root, &mut raw)?;
+    return build_tile_map(name, raw);
+}
+
+fn xml_u32(node: &roxmltree::Node, key: &str) -> Result<u32, TiledImportError> {
+    return node.attribute(key).and_then(|value| value.parse().ok()).ok_or(TiledImportError::Invalid(format!("missing integer {}", key)));
+}
+
+fn xml_f64(node: &roxmltree::Node, key: &str) -> f64 {
+    return node.attribute(key).and_then(|value| value.parse().ok()).unwrap_or(0.0);
+}
+
+fn read_tmx_layers(parent: roxmltree::Node, raw: &mut RawMap) -> Result<(), TiledImportError> {
+    for node in parent.children().filter(|node| node.is_element()) {
+        let layer_name = node.attribute("name").unwrap_or_default().to_string();
+        match node.tag_name().name() {
+            "layer" => {
+                let data = node.children().find(|child| child.has_tag_name("data")).ok_or(TiledImportError::Invalid(format!("layer {} has no data", layer_name)))?;
+                if data.attribute("encoding") != Some("csv") {
+                    return Err(TiledImportError::Unsupported(format!("non CSV data in layer {}. Save with CSV layer format", layer_name)));
+                }
+                let mut tiles: Vec<u32> = Vec::new();
+                for value in data.text().unwrap_or_default().split(',') {
+                    let value = value.trim();
+                    if value.is_empty() {
+                        continue;
+                    }
+                    let tile: u32 = value.parse().map_err(|_| TiledImportError::Parse(format!("invalid tile id {} in layer {}", value, layer_name)))?;
+                    tiles.push(tile & !TILE_FLAG_MASK);
+                }
+                raw.layers.push(TileLayer { name: layer_name, tiles });
+            },
+            "objectgroup" => {
+                for object in node.children().filter(|child| child.has_tag_name("object")) {
+                    let mut properties = HashMap::new();
+                    for property in object.descendants().filter(|child| child.has_tag_name("property")) {
+                        let value = property.attribute("value").map(|value| value.to_string()).or(property.text().map(|text| text.to_string())).unwrap_or_default();
+                        properties.insert(property.attribute("name").unwrap_or_default().to_string(), value);
+                    }
+                    raw.objects.push(RawObject {
+                        name: object.attribute("name").unwrap_or_default().to_string(),
+                        kind: object.attribute("type").or(object.attribute("class")).unwrap_or_default().to_string(),
+                        x: xml_f64(&object, "x"),
+                        y: xml_f64(&object, "y"),
+                        width: xml_f64(&object, "width"),
+                        height: xml_f64(&object, "height"),
+                        properties
+                    });
+                }
+            },
+            "group" => read_tmx_layers(node, raw)?,
+            _ => {}
+        }
+    }
+    return Ok(());
+}
+
+fn build_tile_map(name: GlobalString, raw: RawMap) -> Result<TileMap, TiledImportError> {
+    if raw.tile_width == 0 || raw.tile_height == 0 {
+        return Err(TiledImportError::Invalid("tile size must not be 0".to_string()));
+    }
+    let tile_count = get_tile_count(raw.width, raw.height)
+        .ok_or(TiledImportError::Invalid(format!("map of {} by {} tiles is larger than {} tiles", raw.width, raw.height, MAX_MAP_TILE_COUNT)))?;
+    let mut map = TileMap::new(name, raw.width, raw.height);
+    for layer in raw.layers {
+        if layer.tiles.len() != tile_count {
+            return Err(TiledImportError::Invalid(format!("layer {} has {} tiles but the map has {}", layer.name, layer.tiles.len(), tile_count)));
+        }
+        if layer.name.eq_ignore_ascii_case(COLLISION_LAYER_NAME) {
+            for (index, tile) in layer.tiles.iter().enumerate() {
+                let position = TilePosition::new((index % raw.width as usize) as i32, (index / raw.width as usize) as i32);
+                map.set_blocked(position, *tile != 0);
+            }
+        } else {
+            map.add_layer(layer);
+        }
+    }
+
+    let to_tile = |x: f64, y: f64| TilePosition::new((x / raw.tile_width as f64).floor() as i32, (y / raw.tile_height as f64).floor() as i32);
+    let to_area = |tile: TilePosition, width: f64, height: f64| TileRect {
+        x: tile.x,
+        y: tile.y,
+        width: ((width / raw.tile_width as f64).ceil() as u32).max(1),
+        height: ((height / raw.tile_height as f64).ceil() as u32).max(1)
+    };
+    for object in raw.objects {
+        let tile = to_tile(object.x, object.y);
+        let required = |property: &str| object.properties.get(property).ok_or(TiledImportError::Invalid(format!("{} object {} is missing property {}", object.kind, object.name, property)));
+        match object.kind.as_str() {
+            "npc" => {
+                let npc = object.properties.get("npc").unwrap_or(&object.name);
+                map.add_object(MapObject::NpcSpawn { npc: GlobalString::new(npc), tile });
+            },
+            "encounter" => {
+                map.add_object(MapObject::EncounterZone { table: GlobalString::new(required("table")?), area: to_area(tile, object.width, object.height) });
+            },
+            "warp" => {
+                let parse = |property: &str| -> Result<i32, TiledImportError> {
+                    let value = required(property)?;
+                    return value.parse().map_err(|_| TiledImportError::Invalid(format!("warp {} property {} is not an integer", object.name, property)));
+                };
+                let target = WorldPosition::new(GlobalString::new(required("target_map")?), TilePosition::new(parse("target_x")?, parse("target_y")?));
+                map.add_object(MapObject::Warp { tile, target });
+            },
+            "fast_travel" => {
+                let point = object.properties.get("point").unwrap_or(&object.name);
+                map.add_object(MapObject::FastTravelPoint { point: GlobalString::new(point), tile });
+            },
+            "restricted" => map.add_object(MapObject::RestrictedZone { area: to_area(tile, object.width, object.height) }),
+            "cutscene" => {
+                let cutscene = object.properties.get("cutscene").unwrap_or(&object.name);
+                map.add_object(MapObject::CutsceneTrigger { cutscene: GlobalString::new(cutscene), area: to_area(tile, object.width, object.height) });
+            },
+            _ => {}
+        }
+    }
+    return Ok(map);
+}
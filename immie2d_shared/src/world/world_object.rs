@@ -0,0 +1,67 @@
+use std::time::Duration;
+
+use crate::engine_types::global_string::GlobalString;
+
+use super::tile_position::TilePosition;
+
+/// How long dropped items stay on the ground before despawning.
+pub const DROPPED_ITEM_TTL: Duration = Duration::from_secs(15 * 60);
+/// How long until a cut tree grows back.
+pub const CUT_TREE_TTL: Duration = Duration::from_secs(10 * 60);
+/// How long until a pushed boulder returns to where it started.
+pub const PUSHED_BOULDER_TTL: Duration = Duration::from_secs(5 * 60);
+
+/* A change players made to the world that should outlive the players who made it. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum WorldObjectKind {
+    DroppedItem { item: GlobalString, count: u32 },
+    CutTree,
+    /// A boulder pushed away from its starting tile. The object's position is where it was pushed to.
+    PushedBoulder { origin: TilePosition },
+    OpenedChest
+}
+
+impl WorldObjectKind {
+    /// How long objects of this kind last by default, or None if they never expire.
+    pub fn get_default_ttl(&self) -> Option<Duration> {
+        return match self {
+            WorldObjectKind::DroppedItem { .. } => Some(DROPPED_ITEM_TTL),
+            WorldObjectKind::CutTree => Some(CUT_TREE_TTL),
+            WorldObjectKind::PushedBoulder { .. } => Some(PUSHED_BOULDER_TTL),
+            WorldObjectKind::OpenedChest => None
+        };
+    }
+}
+
+/* Persisted world state at a tile of a region. */
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct WorldObject {
+    pub kind: WorldObjectKind,
+    pub position: TilePosition,
+    /// Unix time in seconds that the object expires at, or None if it is permanent.
+    pub expires_at: Option<u64>
+}
+
+impl WorldObject {
+    /// Create an object that expires after the default TTL of its kind.
+    /// ```
+    /// use immie2d_shared::world::{tile_position::TilePosition, world_object::{WorldObject, WorldObjectKind, CUT_TREE_TTL}};
+    ///
+    /// let tree = WorldObject::new(WorldObjectKind::CutTree, TilePosition::new(3, 4), 1000);
+    /// assert_eq!(tree.expires_at, Some(1000 + CUT_TREE_TTL.as_secs()));
+    /// assert!(!tree.is_expired(1000));
+    /// assert!(tree.is_expired(1000 + CUT_TREE_TTL.as_secs()));
+    /// assert!(!WorldObject::new(WorldObjectKind::OpenedChest, TilePosition::new(0, 0), 1000).is_expired(u64::MAX));
+    /// ```
+    pub fn new(kind: WorldObjectKind, position: TilePosition, now: u64) -> WorldObject {
+        let expires_at = kind.get_default_ttl().map(|ttl| now + ttl.as_secs());
+        return WorldObject { kind, position, expires_at };
+    }
+
+    pub fn is_expired(&self, now: u64) -> bool {
+        return match self.expires_at {
+            Some(expires_at) => now >= expires_at,
+            None => false
+        };
+    }
+}
@@ -0,0 +1,79 @@
+#![allow(clippy::needless_return)]
+
+use std::sync::Arc;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::ability::{ability::{AbilityCategory, BaseAbilityData}, ability_flags::AbilityFlags, ability_map::AbilityMap, ability_names::AbilityNames};
+use immie2d_shared::gameplay::ability::ability_script::AbilityScript;
+use immie2d_shared::gameplay::battle::{battle::Battle, battle_command::BattleCommand, battle_event::BattleEvent, battle_format::BattleFormat, battle_side::BattleSide, battler::Battler, battler_id::BattlerId};
+use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::species::{base_stats::BaseStats, species_data::SpeciesData, species_map::SpeciesMap};
+
+fn ability_data(category: AbilityCategory, power: f32) -> BaseAbilityData {
+    return BaseAbilityData {
+        category,
+        types: Elements::new(vec![ElementKind::Water]),
+        power,
+        speed: 1.0,
+        max_uses: 10,
+        accuracy: 100,
+        flags: AbilityFlags::NONE,
+        combo: None
+    };
+}
+
+/// Use the only ability of side 0's battler on side 1, returning the events and the health of both battlers.
+fn use_ability(ability_map: &AbilityMap, ability: &str, seed: u64) -> (Vec<BattleEvent>, u32, u32) {
+    let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(500, 60, 40, 70));
+    let mut species_map = SpeciesMap::new();
+    species_map.add_species(species);
+    let abilities = AbilityNames::new(vec![GlobalString::new(&ability.to_string())]);
+    let side = || BattleSide::new(vec![Battler::new(Immie::new(species.name, 30, abilities), &species)]);
+    let mut battle = Battle::new(BattleFormat::Single, vec![side(), side()]).with_seed(seed);
+    battle.apply_command(BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 }, ability_map, &species_map).unwrap();
+    let attacker_health = battle.get_battler(BattlerId::new(0, 0)).get_health();
+    let defender_health = battle.get_battler(BattlerId::new(1, 0)).get_health();
+    return (battle.take_events(), attacker_health, defender_health);
+}
+
+#[test]
+fn scripts_modify_damage_and_add_recoil() {
+    let mut ability_map = AbilityMap::new();
+    ability_map.add_data_ability("splash", ability_data(AbilityCategory::Attack, 40.0));
+    let script = AbilityScript::compile("reckless_splash", r#"
+        fn modify_damage() { this.power *= 2.0; }
+        fn on_hit() { this.recoil(this.damage_dealt / 4); }
+    "#).unwrap();
+    ability_map.add_scripted_ability("reckless_splash", ability_data(AbilityCategory::Attack, 40.0), Arc::new(script));
+
+    let (_, _, plain_defender) = use_ability(&ability_map, "splash", 1);
+    let (_, attacker, scripted_defender) = use_ability(&ability_map, "reckless_splash", 1);
+    let plain_damage = 500 - plain_defender;
+    let scripted_damage = 500 - scripted_defender;
+    assert!(scripted_damage > plain_damage * 3 / 2, "Doubled power dealt {} against {}", scripted_damage, plain_damage);
+    assert_eq!(500 - attacker, scripted_damage / 4);
+}
+
+#[test]
+fn status_abilities_run_on_hit_with_battle_randomness() {
+    let mut ability_map = AbilityMap::new();
+    let script = AbilityScript::compile("curse", "fn on_hit() { this.damage_defender(10 + this.random(40)); }").unwrap();
+    ability_map.add_scripted_ability("curse", ability_data(AbilityCategory::Status, 0.0), Arc::new(script));
+
+    let (events, _, defender) = use_ability(&ability_map, "curse", 7);
+    assert!((451..=490).contains(&defender), "Curse left {} health", defender);
+    assert!(events.contains(&BattleEvent::Damaged { battler: BattlerId::new(1, 0), amount: 500 - defender, remaining_health: defender }));
+    // The same seed replays the same roll
+    assert_eq!(use_ability(&ability_map, "curse", 7).0, events);
+}
+
+#[test]
+fn failing_scripts_fall_back_to_ability_data() {
+    let mut ability_map = AbilityMap::new();
+    ability_map.add_data_ability("splash", ability_data(AbilityCategory::Attack, 40.0));
+    let script = AbilityScript::compile("stuck_splash", "fn modify_damage() { this.power = 0.0; loop {} }").unwrap();
+    ability_map.add_scripted_ability("stuck_splash", ability_data(AbilityCategory::Attack, 40.0), Arc::new(script));
+
+    assert_eq!(use_ability(&ability_map, "stuck_splash", 3).2, use_ability(&ability_map, "splash", 3).2);
+}
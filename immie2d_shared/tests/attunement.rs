@@ -0,0 +1,61 @@
+#![allow(clippy::needless_return)]
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::ability::{ability_map::AbilityMap, ability_names::AbilityNames};
+use immie2d_shared::gameplay::battle::{battle::Battle, battle_command::{BattleCommand, BattleCommandError}, battle_event::BattleEvent};
+use immie2d_shared::gameplay::battle::{battle_format::BattleFormat, battle_side::BattleSide, battler::Battler, battler_id::BattlerId};
+use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::item::{inventory::Inventory, item_map::ItemMap, item_use::{use_item_outside_battle, ItemTarget, ItemUseError}};
+use immie2d_shared::gameplay::species::{base_stats::BaseStats, species_data::SpeciesData, species_map::SpeciesMap};
+use immie2d_shared::modding::data_pack::parse_items_json;
+
+fn name(name: &str) -> GlobalString {
+    return GlobalString::new(&name.to_string());
+}
+
+fn load_items(json: &str) -> ItemMap {
+    let mut item_map = ItemMap::new();
+    for item in parse_items_json(json, None).unwrap() {
+        item_map.add_item(item);
+    }
+    return item_map;
+}
+
+#[test]
+fn attune_items_must_name_an_element_other_than_standard() {
+    assert!(parse_items_json(r#"[{ "name": "tide stone", "effect": "attune", "element": "water" }]"#, None).is_ok());
+    assert!(parse_items_json(r#"[{ "name": "plain stone", "effect": "attune", "element": "standard" }]"#, None).is_err());
+    assert!(parse_items_json(r#"[{ "name": "odd stone", "effect": "attune", "element": "cheese" }]"#, None).is_err());
+    assert!(parse_items_json(r#"[{ "name": "no stone", "effect": "attune" }]"#, None).is_err());
+}
+
+#[test]
+fn attunement_set_by_an_item_can_be_used_in_battle() {
+    let item_map = load_items(r#"[{ "name": "tide stone", "effect": "attune", "element": "water" }]"#);
+    let stone = name("tide stone");
+    assert!(!item_map.get_item(stone).unwrap().usable_in_battle);
+
+    let fire = SpeciesData::new(name("lavapup"), Elements::new(vec![ElementKind::Fire]), BaseStats::new(50, 60, 40, 70));
+    let mut species_map = SpeciesMap::new();
+    species_map.add_species(fire);
+    let ability_map = AbilityMap::new();
+    let mut inventory = Inventory::new();
+    inventory.add_item(stone, 2);
+    let mut party = vec![Immie::new(fire.name, 5, AbilityNames::default())];
+    let target = ItemTarget { party_slot: 0, ability_slot: None };
+
+    use_item_outside_battle(&item_map, &species_map, &ability_map, &mut inventory, &mut party, stone, target).unwrap();
+    assert_eq!(party[0].attunement, Some(ElementKind::Water));
+    // Already attuned to water, so the second stone is kept
+    assert_eq!(use_item_outside_battle(&item_map, &species_map, &ability_map, &mut inventory, &mut party, stone, target), Err(ItemUseError::NoEffect));
+    assert_eq!(inventory.get_count(stone), 1);
+
+    let opponent = Battler::new(Immie::new(fire.name, 5, AbilityNames::default()), &fire);
+    let mut battle = Battle::new(BattleFormat::Single, vec![BattleSide::new(vec![Battler::new(party[0], &fire)]), BattleSide::new(vec![opponent])]);
+    assert_eq!(battle.apply_command(BattleCommand::Attune { side: 1 }, &ability_map, &species_map), Err(BattleCommandError::CannotAttune));
+    battle.apply_command(BattleCommand::Attune { side: 0 }, &ability_map, &species_map).unwrap();
+    let battler = BattlerId::new(0, 0);
+    assert_eq!(battle.take_events(), vec![BattleEvent::Attuned { battler, element: ElementKind::Water }]);
+    assert!(battle.get_battler(battler).get_elements().has_elements(ElementKind::Water));
+}
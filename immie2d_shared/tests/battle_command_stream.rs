@@ -0,0 +1,29 @@
+#![allow(clippy::needless_return)]
+
+mod common;
+
+use common::battle_harness::run_command_stream;
+use immie2d_shared::engine_types::game_rng::GameRng;
+
+/// Random streams are mostly invalid commands, so bias the bytes towards small values that decode into plausible ones.
+fn random_stream(rng: &mut GameRng, length: usize) -> Vec<u8> {
+    return (0..length).map(|_| if rng.chance(0.9) { rng.next_below(4) as u8 } else { rng.next_u32() as u8 }).collect();
+}
+
+#[test]
+fn random_command_streams_never_break_invariants() {
+    let mut rng = GameRng::new(0x5eed_1666);
+    for _ in 0..2000 {
+        let length = rng.next_below(256) as usize;
+        run_command_stream(&random_stream(&mut rng, length));
+    }
+}
+
+#[test]
+fn degenerate_streams() {
+    run_command_stream(&[]);
+    run_command_stream(&[0]);
+    run_command_stream(&[2, 0, 0, 0]);
+    run_command_stream(&[0, 255, 255, 255, 255]);
+    run_command_stream(&vec![3; 512]);
+}
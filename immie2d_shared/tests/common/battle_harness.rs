@@ -0,0 +1,68 @@
+// Shared by the battle command stream test and the fuzz target in fuzz/.
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::ability::{ability::Ability, ability_map::AbilityMap, ability_names::AbilityNames, abilities::fireball::Fireball};
+use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battler_id::BattlerId, battle_side::BattleSide, battle_format::BattleFormat};
+use immie2d_shared::gameplay::battle::battle_command::BattleCommand;
+use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::species::{base_stats::BaseStats, species_data::{SpeciesData, TransformationData}, species_map::SpeciesMap};
+
+/// Run a byte stream against a free for all battle. The first byte picks the ruleset and the rest are decoded into commands.
+/// Panics if the battle panics or an invariant is broken.
+pub fn run_command_stream(bytes: &[u8]) {
+    let (ruleset_byte, command_bytes) = match bytes.split_first() {
+        Some(split) => split,
+        None => return
+    };
+    let ruleset = match ruleset_byte % 5 {
+        0 => BattleRuleset::Standard,
+        1 => BattleRuleset::InverseTypes,
+        2 => BattleRuleset::SuddenDeath { turn_limit: 2 },
+        3 => BattleRuleset::LevelCapped { level_cap: 10 },
+        _ => BattleRuleset::Retaliation
+    };
+
+    let stone = GlobalString::new(&"lava stone".to_string());
+    let fire = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(60, 60, 40, 70))
+        .with_transformation(TransformationData {
+            form_name: GlobalString::new(&"mega lavapup".to_string()),
+            required_item: stone,
+            elements: Elements::new(vec![ElementKind::Fire, ElementKind::Dragon]),
+            base_stats: BaseStats::new(60, 90, 60, 80)
+        });
+    let nature = SpeciesData::new(GlobalString::new(&"sproutle".to_string()), Elements::new(vec![ElementKind::Nature]), BaseStats::new(70, 50, 50, 40));
+    let mut species_map = SpeciesMap::new();
+    species_map.add_species(fire);
+    species_map.add_species(nature);
+    let mut ability_map = AbilityMap::new();
+    ability_map.add_ability::<Fireball>();
+
+    let abilities = AbilityNames::new(vec![GlobalString::new(&Fireball::static_name().to_string()), GlobalString::new(&"unknown ability".to_string())]);
+    let mut holder = Immie::new(fire.name, 15, abilities);
+    holder.held_item = Some(stone);
+    let side = || BattleSide::new(vec![
+        Battler::new(holder, &fire),
+        Battler::new(Immie::new(nature.name, 12, abilities), &nature)
+    ]);
+    let mut battle = Battle::new(BattleFormat::free_for_all(3), vec![side(), side(), side()]).with_rules(ruleset.create_plugin());
+
+    let battler_ids: Vec<BattlerId> = (0..3).flat_map(|side| (0..2).map(move |slot| BattlerId::new(side, slot))).collect();
+    for command in BattleCommand::decode_stream(command_bytes) {
+        let health_before: Vec<u32> = battler_ids.iter().map(|id| battle.get_battler(*id).get_health()).collect();
+        let turn_before = battle.get_turn();
+        let result = battle.apply_command(command, &ability_map, &species_map);
+        if let Err(invariant) = battle.check_invariants() {
+            panic!("Invariant broken after {:?}: {}", command, invariant);
+        }
+        let events = battle.take_events();
+        if result.is_err() {
+            assert!(events.is_empty(), "Rejected command {:?} emitted events {:?}", command, events);
+            assert_eq!(battle.get_turn(), turn_before, "Rejected command {:?} changed the turn", command);
+        }
+        for (id, before) in battler_ids.iter().zip(health_before) {
+            assert!(battle.get_battler(*id).get_health() <= before, "{:?} gained health from {:?}", id, command);
+        }
+    }
+}
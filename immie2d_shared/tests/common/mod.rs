@@ -0,0 +1 @@
+pub mod battle_harness;
@@ -0,0 +1,107 @@
+#![allow(clippy::needless_return)]
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::ability::{ability::{Ability, AbilityCategory, BaseAbilityData}, ability_flags::AbilityFlags, ability_map::AbilityMap, ability_names::AbilityNames};
+use immie2d_shared::gameplay::ability::abilities::fireball::Fireball;
+use immie2d_shared::gameplay::battle::{battle::Battle, battle_command::BattleCommand, battle_event::BattleEvent, battle_format::BattleFormat, battle_side::BattleSide, battler::Battler, battler_id::BattlerId};
+use immie2d_shared::gameplay::battle::copied_identity::COPIED_ABILITY_USES;
+use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::species::{base_stats::BaseStats, species_data::SpeciesData, species_map::SpeciesMap};
+
+const USER: BattlerId = BattlerId { side: 1, slot: 0 };
+const TARGET: BattlerId = BattlerId { side: 0, slot: 0 };
+
+fn name(name: &str) -> GlobalString {
+    return GlobalString::new(&name.to_string());
+}
+
+fn copier_data(flags: AbilityFlags) -> BaseAbilityData {
+    return BaseAbilityData {
+        category: AbilityCategory::Status,
+        types: Elements::new(vec![ElementKind::Standard]),
+        power: 0.0,
+        speed: 1.0,
+        max_uses: 10,
+        accuracy: 100,
+        flags,
+        combo: None
+    };
+}
+
+/// A fire battler knowing fireball on side 0, against a water battler knowing mimic and transform on side 1.
+fn setup() -> (Battle, AbilityMap, SpeciesMap) {
+    let mut ability_map = AbilityMap::new();
+    ability_map.add_ability::<Fireball>();
+    ability_map.add_data_ability("mimic", copier_data(AbilityFlags::COPIES_LAST_ABILITY));
+    ability_map.add_data_ability("transform", copier_data(AbilityFlags::COPIES_TARGET));
+    let fire = SpeciesData::new(name("lavapup"), Elements::new(vec![ElementKind::Fire]), BaseStats::new(300, 90, 40, 70));
+    let water = SpeciesData::new(name("tidefin"), Elements::new(vec![ElementKind::Water]), BaseStats::new(200, 60, 80, 30));
+    let mut species_map = SpeciesMap::new();
+    species_map.add_species(fire);
+    species_map.add_species(water);
+    let attacker = Battler::new(Immie::new(fire.name, 20, AbilityNames::new(vec![name(Fireball::static_name())])), &fire);
+    let copier = Battler::new(Immie::new(water.name, 20, AbilityNames::new(vec![name("mimic"), name("transform")])), &water);
+    let battle = Battle::new(BattleFormat::Single, vec![BattleSide::new(vec![attacker]), BattleSide::new(vec![copier])]);
+    return (battle, ability_map, species_map);
+}
+
+fn use_ability(battle: &mut Battle, ability_map: &AbilityMap, species_map: &SpeciesMap, side: usize, ability_slot: usize) -> Vec<BattleEvent> {
+    battle.apply_command(BattleCommand::UseAbility { side, ability_slot, target_side: 1 - side }, ability_map, species_map).unwrap();
+    return battle.take_events();
+}
+
+#[test]
+fn copying_the_last_ability_needs_one_to_have_been_used() {
+    let (mut battle, ability_map, species_map) = setup();
+    let events = use_ability(&mut battle, &ability_map, &species_map, 1, 0);
+    assert!(events.contains(&BattleEvent::CopyFailed { battler: USER, target: TARGET }));
+    assert_eq!(battle.get_battler(USER).get_copied_ability(), None);
+}
+
+#[test]
+fn copied_ability_takes_the_copying_slot_with_its_own_uses() {
+    let (mut battle, ability_map, species_map) = setup();
+    use_ability(&mut battle, &ability_map, &species_map, 0, 0);
+    let events = use_ability(&mut battle, &ability_map, &species_map, 1, 0);
+    assert!(events.contains(&BattleEvent::AbilityCopied { battler: USER, target: TARGET, ability: name(Fireball::static_name()) }));
+    assert_eq!(battle.get_battler(USER).get_abilities().get_names(), vec![name(Fireball::static_name()), name("transform")]);
+
+    let max_uses = Fireball::new().get_base_ability_data().max_uses;
+    let health = battle.get_battler(TARGET).get_health();
+    use_ability(&mut battle, &ability_map, &species_map, 1, 0);
+    assert!(battle.get_battler(TARGET).get_health() < health);
+    assert_eq!(battle.get_battler(USER).get_remaining_uses(0, max_uses), max_uses.min(COPIED_ABILITY_USES) - 1);
+    // Only the use of mimic itself is spent from the Immie
+    assert_eq!(battle.get_battler(USER).get_immie().ability_uses_spent[0], 1);
+}
+
+#[test]
+fn copied_identity_keeps_health_and_is_forgotten_when_the_battle_ends() {
+    let (mut battle, ability_map, species_map) = setup();
+    let events = use_ability(&mut battle, &ability_map, &species_map, 1, 1);
+    assert!(events.contains(&BattleEvent::IdentityCopied { battler: USER, target: TARGET, species: name("lavapup") }));
+    let user = battle.get_battler(USER);
+    assert!(user.get_elements().has_elements(ElementKind::Fire));
+    assert_eq!(user.get_stats(), BaseStats::new(200, 90, 40, 70));
+    assert_eq!(user.get_abilities().get_names(), vec![name(Fireball::static_name())]);
+    assert_eq!(user.get_immie().species, name("tidefin"));
+    assert!(battle.check_invariants().is_ok());
+
+    battle.end();
+    let user = battle.get_battler(USER);
+    assert!(user.get_copied_identity().is_none());
+    assert_eq!(user.get_species(), name("tidefin"));
+    assert_eq!(user.get_carried_immie().abilities.get_names(), vec![name("mimic"), name("transform")]);
+}
+
+#[test]
+fn nothing_is_copied_through_a_substitute() {
+    let (battle, ability_map, species_map) = setup();
+    let mut target = *battle.get_battler(TARGET);
+    target.set_substitute(10);
+    let mut battle = Battle::new(BattleFormat::Single, vec![BattleSide::new(vec![target]), BattleSide::new(vec![*battle.get_battler(USER)])]);
+    let events = use_ability(&mut battle, &ability_map, &species_map, 1, 1);
+    assert!(events.contains(&BattleEvent::CopyFailed { battler: USER, target: TARGET }));
+    assert!(battle.get_battler(USER).get_copied_identity().is_none());
+}
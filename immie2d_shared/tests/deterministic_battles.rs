@@ -0,0 +1,57 @@
+#![allow(clippy::needless_return)]
+
+use sha2::{Digest, Sha256};
+
+use immie2d_shared::engine_types::game_rng::GameRng;
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::ability::{ability::Ability, ability_map::AbilityMap, ability_names::AbilityNames, abilities::{fireball::Fireball, pursuit::Pursuit}};
+use immie2d_shared::gameplay::battle::{battle::Battle, battler::Battler, battle_side::BattleSide, battle_format::BattleFormat, battle_event::BattleEvent};
+use immie2d_shared::gameplay::battle::random_ai::choose_random_command;
+use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::species::{base_stats::BaseStats, species_data::SpeciesData, species_map::SpeciesMap};
+
+/// Event stream digest of the seed 1707 battle. Changing it means replays recorded before the change no longer play
+/// back the same, so only update it alongside a deliberate change to battle resolution.
+const GOLDEN_DIGEST: &str = "7e1b728bc26983242ae42b696b24717c43cd74724c347af7d7e4057398d54985";
+
+/// Play a free for all between identical sides to the end, so every turn is full of speed ties. Commands come from the
+/// random AI seeded by the same seed as the battle.
+fn run_seeded_battle(seed: u64) -> Vec<BattleEvent> {
+    let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(60, 60, 40, 70));
+    let mut species_map = SpeciesMap::new();
+    species_map.add_species(species);
+    let mut ability_map = AbilityMap::new();
+    ability_map.add_ability::<Fireball>();
+    ability_map.add_ability::<Pursuit>();
+    let abilities = AbilityNames::new(vec![GlobalString::new(&Fireball::static_name().to_string()), GlobalString::new(&Pursuit::static_name().to_string())]);
+    let side = || BattleSide::new(vec![Battler::new(Immie::new(species.name, 15, abilities), &species), Battler::new(Immie::new(species.name, 12, abilities), &species)]);
+    let mut battle = Battle::new(BattleFormat::free_for_all(4), vec![side(), side(), side(), side()]).with_seed(seed);
+
+    let mut rng = GameRng::new(seed);
+    let mut events = Vec::new();
+    while !battle.is_finished() {
+        let commands: Vec<_> = (0..battle.get_side_count()).map(|side| choose_random_command(&battle, side, &ability_map, &mut rng)).collect();
+        battle.resolve_turn(&commands, &ability_map, &species_map);
+        events.extend(battle.take_events());
+        assert!(battle.get_turn() < 500, "Seed {} battle never ended", seed);
+    }
+    return events;
+}
+
+fn get_digest(events: &[BattleEvent]) -> String {
+    let digest = Sha256::digest(format!("{:?}", events).as_bytes());
+    return digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+}
+
+#[test]
+fn same_seed_same_event_stream() {
+    for seed in 0..32 {
+        assert_eq!(run_seeded_battle(seed), run_seeded_battle(seed), "Seed {} diverged between runs", seed);
+    }
+}
+
+#[test]
+fn event_stream_matches_golden_digest() {
+    assert_eq!(get_digest(&run_seeded_battle(1707)), GOLDEN_DIGEST);
+}
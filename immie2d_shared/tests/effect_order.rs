@@ -0,0 +1,148 @@
+#![allow(clippy::needless_return)]
+
+use std::sync::Arc;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::ability::{ability::{AbilityCategory, BaseAbilityData}, ability_flags::AbilityFlags, ability_names::AbilityNames};
+use immie2d_shared::gameplay::ability::ability_script::AbilityScript;
+use immie2d_shared::gameplay::battle::{battle::Battle, battle_format::BattleFormat, battle_side::BattleSide, battler::Battler, battler_id::BattlerId};
+use immie2d_shared::gameplay::battle::ability_pipeline::AbilityPipeline;
+use immie2d_shared::gameplay::battle::damage::DamageContext;
+use immie2d_shared::gameplay::battle::field_state::{FieldState, WEATHER_BOOST};
+use immie2d_shared::gameplay::battle::hit_resolution::{resolve_hit, HitBlocker, HitOutcome, DEFLECTION_PASSIVE};
+use immie2d_shared::gameplay::battle::effect_order::{EffectOrderKey, EffectOrderRegistry, EffectSource, ABILITY_SCRIPT_EFFECT, RULES_EFFECT};
+use immie2d_shared::gameplay::battle::effect_order::{AIRBORNE_EFFECT, DEFLECTION_EFFECT, PROTECT_EFFECT, SUBSTITUTE_EFFECT, UNDERGROUND_EFFECT, WEATHER_EFFECT};
+use immie2d_shared::gameplay::battle::rules::battle_rules_plugin::BattleRulesPlugin;
+use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+use immie2d_shared::gameplay::encounter::encounter_conditions::Weather;
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::species::{base_stats::BaseStats, species_data::SpeciesData};
+
+/* Rules that undo every other multiplier, so whether they run before or after a script shows in the damage inputs. */
+struct FlatMultiplierRules;
+
+impl BattleRulesPlugin for FlatMultiplierRules {
+    fn get_name(&self) -> &'static str {
+        return "flat_multiplier";
+    }
+
+    fn pre_damage(&self, _battle: &Battle, context: &mut DamageContext) {
+        context.multiplier = 1.0;
+    }
+}
+
+fn ability_data() -> BaseAbilityData {
+    return BaseAbilityData {
+        category: AbilityCategory::Attack,
+        types: Elements::new(vec![ElementKind::Water]),
+        power: 40.0,
+        speed: 1.0,
+        max_uses: 10,
+        accuracy: 100,
+        flags: AbilityFlags::NONE,
+        combo: None
+    };
+}
+
+/// The damage multiplier after the weather, the rules and a script that triples it have all run, in the battle's
+/// effect order.
+fn get_multiplier_in(effect_order: Arc<EffectOrderRegistry>, weather: Weather) -> f32 {
+    let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(500, 60, 40, 70));
+    let side = || BattleSide::new(vec![Battler::new(Immie::new(species.name, 30, AbilityNames::default()), &species)]);
+    let mut battle = Battle::new(BattleFormat::Single, vec![side(), side()]).with_rules(Arc::new(FlatMultiplierRules)).with_effect_order(effect_order)
+        .with_field(FieldState::from_weather(weather));
+    let script = AbilityScript::compile("surge", "fn modify_damage() { this.multiplier *= 3.0; }").unwrap();
+    let data = ability_data();
+    let mut pipeline = AbilityPipeline::new(BattlerId::new(0, 0), BattlerId::new(1, 0), &data).with_script(Some(&script));
+    pipeline.run(&mut battle);
+    return pipeline.get_inspector().rules_inputs.unwrap().multiplier;
+}
+
+fn get_multiplier(effect_order: Arc<EffectOrderRegistry>) -> f32 {
+    return get_multiplier_in(effect_order, Weather::Clear);
+}
+
+/// The standard order with some effects moved to new keys.
+fn reorder(moved: &[(&str, EffectOrderKey)]) -> EffectOrderRegistry {
+    let standard = EffectOrderRegistry::standard();
+    let mut registry = EffectOrderRegistry::new();
+    for name in standard.get_order() {
+        let key = moved.iter().find(|(moved, _)| *moved == name).map_or(standard.get_key(name).unwrap(), |(_, key)| *key);
+        registry.register(name, key).unwrap();
+    }
+    return registry;
+}
+
+#[test]
+fn standard_effect_order_is_locked() {
+    // Changing this order changes mechanics. Register new effects with unused keys instead.
+    let registry = EffectOrderRegistry::standard();
+    assert_eq!(registry.get_order(), vec![
+        WEATHER_EFFECT, AIRBORNE_EFFECT, UNDERGROUND_EFFECT, PROTECT_EFFECT, DEFLECTION_EFFECT, SUBSTITUTE_EFFECT, RULES_EFFECT, ABILITY_SCRIPT_EFFECT
+    ]);
+    assert_eq!(registry.get_key(WEATHER_EFFECT), Some(EffectOrderKey::new(EffectSource::Field, 0)));
+    assert_eq!(registry.get_key(SUBSTITUTE_EFFECT), Some(EffectOrderKey::new(EffectSource::Status, 4)));
+    assert_eq!(registry.get_key(RULES_EFFECT), Some(EffectOrderKey::new(EffectSource::Rules, 0)));
+    assert_eq!(registry.get_key(ABILITY_SCRIPT_EFFECT), Some(EffectOrderKey::new(EffectSource::AbilityScript, 0)));
+    let sources: Vec<EffectSource> = (0..=5).map(|id| EffectSource::from_id(id).unwrap()).collect();
+    assert_eq!(sources, vec![EffectSource::Field, EffectSource::Status, EffectSource::Trait, EffectSource::HeldItem, EffectSource::Rules, EffectSource::AbilityScript]);
+    assert!(sources.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(EffectSource::from_id(6), None);
+}
+
+#[test]
+fn registering_content_does_not_reshuffle_existing_effects() {
+    let mut registry = EffectOrderRegistry::standard();
+    registry.register("choice_band", EffectOrderKey::new(EffectSource::HeldItem, 0)).unwrap();
+    registry.register("burn", EffectOrderKey::new(EffectSource::Status, 10)).unwrap();
+    registry.register("gravity", EffectOrderKey::new(EffectSource::Field, 10)).unwrap();
+    registry.register("life_orb", EffectOrderKey::new(EffectSource::HeldItem, 1)).unwrap();
+    assert_eq!(registry.get_order(), vec![
+        WEATHER_EFFECT, "gravity", AIRBORNE_EFFECT, UNDERGROUND_EFFECT, PROTECT_EFFECT, DEFLECTION_EFFECT, SUBSTITUTE_EFFECT, "burn",
+        "choice_band", "life_orb", RULES_EFFECT, ABILITY_SCRIPT_EFFECT
+    ]);
+    assert!(registry.register("poison", EffectOrderKey::new(EffectSource::Status, 0)).is_err());
+    // Registration order never matters
+    let mut reversed = EffectOrderRegistry::new();
+    for name in registry.get_order().into_iter().rev() {
+        reversed.register(name, registry.get_key(name).unwrap()).unwrap();
+    }
+    assert_eq!(reversed, registry);
+    assert!(registry.register("expert_belt", EffectOrderKey::new(EffectSource::HeldItem, 1)).is_err());
+}
+
+#[test]
+fn scripts_modify_damage_after_the_rules() {
+    assert_eq!(get_multiplier(EffectOrderRegistry::get_standard()), 3.0);
+
+    let scripts_first = reorder(&[(ABILITY_SCRIPT_EFFECT, EffectOrderKey::new(EffectSource::Rules, 0)), (RULES_EFFECT, EffectOrderKey::new(EffectSource::Rules, 1))]);
+    assert_eq!(get_multiplier(Arc::new(scripts_first)), 1.0);
+}
+
+#[test]
+fn weather_modifies_damage_before_the_rules() {
+    // The rules undo the rain's boost to water abilities
+    assert_eq!(get_multiplier_in(EffectOrderRegistry::get_standard(), Weather::Rain), 3.0);
+
+    let weather_after_rules = reorder(&[(WEATHER_EFFECT, EffectOrderKey::new(EffectSource::Rules, 1))]);
+    assert_eq!(get_multiplier_in(Arc::new(weather_after_rules), Weather::Rain), WEATHER_BOOST * 3.0);
+}
+
+#[test]
+fn blockers_are_checked_in_effect_order() {
+    let species = SpeciesData::new(GlobalString::new(&"lavapup".to_string()), Elements::new(vec![ElementKind::Fire]), BaseStats::new(500, 60, 40, 70));
+    let mut immie = Immie::new(species.name, 30, AbilityNames::default());
+    immie.passive = Some(GlobalString::new(&DEFLECTION_PASSIVE.to_string()));
+    let mut defender = Battler::new(immie, &species);
+    defender.set_substitute(10);
+    assert_eq!(resolve_hit(AbilityFlags::PROJECTILE, &defender, &EffectOrderRegistry::standard()), HitOutcome::Blocked(HitBlocker::Deflection));
+
+    let substitute_first = reorder(&[(SUBSTITUTE_EFFECT, EffectOrderKey::new(EffectSource::Field, 1))]);
+    assert_eq!(resolve_hit(AbilityFlags::PROJECTILE, &defender, &substitute_first), HitOutcome::HitSubstitute);
+}
+
+#[test]
+#[should_panic(expected = "has no registered order")]
+fn engine_effects_must_be_registered() {
+    get_multiplier(Arc::new(EffectOrderRegistry::new()));
+}
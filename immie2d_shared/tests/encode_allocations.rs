@@ -0,0 +1,112 @@
+#![allow(clippy::needless_return)]
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use immie2d_shared::engine_types::encode_buffer::{Encode, EncodeBufferPool};
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::battle::{battle_command::BattleCommand, battle_event::BattleEvent, battler_id::BattlerId};
+use immie2d_shared::gameplay::battle::{event_timeline::{EventTimeline, TimelineAck}, forced_action::ForcedActionKind};
+use immie2d_shared::world::entity_snapshot::{EntityKind, EntitySnapshot};
+use immie2d_shared::world::snapshot_codec::{decode_packed, SnapshotEncoder};
+use immie2d_shared::world::tile_position::{Direction, TilePosition, WorldPosition};
+
+/* Counts the allocations of the current thread, so tests running in parallel don't count each other's. */
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        return unsafe { System.alloc(layout) };
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) };
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        return unsafe { System.realloc(ptr, layout, new_size) };
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Number of allocations the current thread makes while running a function.
+fn count_allocations<F: FnMut()>(mut function: F) -> usize {
+    let before = ALLOCATIONS.with(|allocations| allocations.get());
+    function();
+    return ALLOCATIONS.with(|allocations| allocations.get()) - before;
+}
+
+fn get_snapshots(tick: i32) -> Vec<EntitySnapshot> {
+    let town = GlobalString::new(&"town".to_string());
+    let lavapup = GlobalString::new(&"lavapup".to_string());
+    return (0..50).map(|id| {
+        let kind = if id % 2 == 0 { EntityKind::Player } else { EntityKind::Follower { owner: id - 1, species: lavapup } };
+        let position = WorldPosition::new(town, TilePosition::new(id as i32 + tick, tick - id as i32));
+        return EntitySnapshot { network_id: id, kind, position, facing: Direction::Left, is_visible: true };
+    }).collect();
+}
+
+fn get_timeline(batch: u32) -> EventTimeline {
+    let battler = BattlerId::new(1, 0);
+    return EventTimeline::from_events(batch, 1_000_000, vec![
+        BattleEvent::ComboTriggered { battler, follows: GlobalString::new(&"fireball".to_string()) },
+        BattleEvent::MultiTurnProgress { battler, kind: ForcedActionKind::LockedIn, turn: 1, total_turns: 3 },
+        BattleEvent::Damaged { battler, amount: 50, remaining_health: 0 },
+        BattleEvent::Fainted { battler },
+        BattleEvent::TurnEnded { turn: batch }
+    ]);
+}
+
+/// Encode every message of a tick into a pooled buffer and give it back, as the server's send loop does.
+fn encode_tick(pool: &EncodeBufferPool, encoder: &mut SnapshotEncoder, snapshots: &[EntitySnapshot], timeline: &EventTimeline) -> usize {
+    let mut buffer = pool.take();
+    encoder.encode_packed_into(snapshots, &mut buffer);
+    timeline.encode_into(&mut buffer);
+    TimelineAck { batch: timeline.batch }.encode_into(&mut buffer);
+    BattleCommand::UseAbility { side: 0, ability_slot: 1, target_side: 1 }.encode_into(&mut buffer);
+    let length = buffer.len();
+    pool.give_back(buffer);
+    return length;
+}
+
+#[test]
+fn encoding_a_tick_does_not_allocate_once_warm() {
+    // The harness sees allocations
+    assert_eq!(count_allocations(|| drop(vec![0u8; 16])), 1);
+    let pool = EncodeBufferPool::new();
+    let mut encoder = SnapshotEncoder::new();
+    let (snapshots, timeline) = (get_snapshots(0), get_timeline(0));
+    // The first tick grows the pooled buffer and the encoder's name table
+    encode_tick(&pool, &mut encoder, &snapshots, &timeline);
+
+    for tick in 1..20 {
+        let (snapshots, timeline) = (get_snapshots(tick), get_timeline(tick as u32));
+        let mut length = 0;
+        let allocations = count_allocations(|| length = encode_tick(&pool, &mut encoder, &snapshots, &timeline));
+        assert_eq!(allocations, 0, "Tick {} allocated {} times", tick, allocations);
+        assert!(length > 0);
+    }
+}
+
+#[test]
+fn pooled_encodings_match_standalone_encodings() {
+    let pool = EncodeBufferPool::new();
+    let mut encoder = SnapshotEncoder::new();
+    let snapshots = get_snapshots(3);
+    let mut buffer = pool.take();
+    encoder.encode_packed_into(&snapshots, &mut buffer);
+    let snapshot_length = buffer.len();
+    get_timeline(3).encode_into(&mut buffer);
+
+    assert_eq!(decode_packed(&buffer[..snapshot_length]), Some(snapshots));
+    assert_eq!(EventTimeline::from_bytes(&buffer[snapshot_length..]), Some(get_timeline(3)));
+    assert_eq!(&buffer[snapshot_length..], &get_timeline(3).encode_to_vec()[..]);
+}
@@ -0,0 +1,68 @@
+#![allow(clippy::needless_return)]
+
+use std::sync::Arc;
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::ability::{ability::Ability, ability_map::AbilityMap, ability_names::AbilityNames, abilities::fireball::Fireball};
+use immie2d_shared::gameplay::battle::{battle::Battle, battle_command::BattleCommand, battle_event::BattleEvent};
+use immie2d_shared::gameplay::battle::{battle_format::BattleFormat, battle_side::BattleSide, battler::Battler, battler_id::BattlerId};
+use immie2d_shared::gameplay::battle::rules::battle_rules_plugin::BattleRulesPlugin;
+use immie2d_shared::gameplay::battle::rules::battle_ruleset::BattleRuleset;
+use immie2d_shared::gameplay::elements::{elements_data::Elements, element_kinds::ElementKind};
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::species::{base_stats::BaseStats, species_data::SpeciesData, species_map::SpeciesMap};
+
+fn name(name: &str) -> GlobalString {
+    return GlobalString::new(&name.to_string());
+}
+
+/// Which battlers were damaged in the events, in order.
+fn get_damaged(events: &[BattleEvent]) -> Vec<BattlerId> {
+    return events.iter().filter_map(|event| match *event {
+        BattleEvent::Damaged { battler, .. } => Some(battler),
+        _ => None
+    }).collect();
+}
+
+/// Resolve a turn of a slow side 0 and a fast side 1 attacking each other under the rules, returning its events.
+fn resolve_exchange(rules: Arc<dyn BattleRulesPlugin>, commands: &[BattleCommand]) -> Vec<BattleEvent> {
+    let slow = SpeciesData::new(name("lavapup"), Elements::new(vec![ElementKind::Nature]), BaseStats::new(500, 60, 40, 10));
+    let fast = SpeciesData::new(name("sproutle"), Elements::new(vec![ElementKind::Nature]), BaseStats::new(500, 60, 40, 90));
+    let mut species_map = SpeciesMap::new();
+    species_map.add_species(slow);
+    species_map.add_species(fast);
+    let mut ability_map = AbilityMap::new();
+    ability_map.add_ability::<Fireball>();
+    let abilities = AbilityNames::new(vec![name(Fireball::static_name())]);
+    let side = |species: SpeciesData| BattleSide::new(vec![Battler::new(Immie::new(species.name, 20, abilities), &species)]);
+
+    let mut battle = Battle::new(BattleFormat::Single, vec![side(slow), side(fast)]).with_rules(rules);
+    assert!(battle.resolve_turn(commands, &ability_map, &species_map).is_empty());
+    return battle.take_events();
+}
+
+#[test]
+fn counters_strike_right_after_the_hit_and_before_the_rest_of_the_turn() {
+    let (slow, fast) = (BattlerId::new(0, 0), BattlerId::new(1, 0));
+    let commands = [BattleCommand::UseAbility { side: 0, ability_slot: 0, target_side: 1 }, BattleCommand::UseAbility { side: 1, ability_slot: 0, target_side: 0 }];
+
+    let standard = resolve_exchange(BattleRuleset::Standard.create_plugin(), &commands);
+    assert_eq!(get_damaged(&standard), vec![slow, fast]);
+
+    // The fast side hits, the slow side counters, then the slow side attacks and the fast side counters
+    let events = resolve_exchange(BattleRuleset::Retaliation.create_plugin(), &commands);
+    assert_eq!(get_damaged(&events), vec![slow, fast, fast, slow]);
+    let amounts: Vec<u32> = events.iter().filter_map(|event| match *event {
+        BattleEvent::Damaged { amount, .. } => Some(amount),
+        _ => None
+    }).collect();
+    assert!(amounts[1] < amounts[2], "A counter should hit for less than a full attack: {:?}", amounts);
+    assert!(matches!(events.last(), Some(BattleEvent::TurnEnded { turn: 1 })));
+}
+
+#[test]
+fn counters_are_not_countered() {
+    let commands = [BattleCommand::UseAbility { side: 1, ability_slot: 0, target_side: 0 }];
+    let events = resolve_exchange(BattleRuleset::Retaliation.create_plugin(), &commands);
+    assert_eq!(get_damaged(&events), vec![BattlerId::new(0, 0), BattlerId::new(1, 0)]);
+}
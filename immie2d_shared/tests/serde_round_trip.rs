@@ -0,0 +1,136 @@
+#![allow(clippy::needless_return)]
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::gameplay::ability::ability::{AbilityCategory, AbilityCombo, BaseAbilityData};
+use immie2d_shared::gameplay::ability::ability_flags::AbilityFlags;
+use immie2d_shared::gameplay::ability::ability_map::AbilityMap;
+use immie2d_shared::gameplay::ability::ability_names::AbilityNames;
+use immie2d_shared::gameplay::battle::{battle_command::BattleCommand, battle_event::BattleEvent, battler_id::BattlerId};
+use immie2d_shared::gameplay::battle::{entry_hazard::HazardKind, hit_resolution::HitBlocker, state_diff::BattlerStatus};
+use immie2d_shared::gameplay::elements::{element_kinds::ElementKind, elements_data::Elements};
+use immie2d_shared::gameplay::immie::immie::Immie;
+use immie2d_shared::gameplay::status_condition::StatusCondition;
+
+fn round_trip<T: Serialize + DeserializeOwned>(value: &T) -> T {
+    return serde_json::from_str(&serde_json::to_string(value).unwrap()).unwrap();
+}
+
+fn name(string: &str) -> GlobalString {
+    return GlobalString::new(&string.to_string());
+}
+
+#[test]
+fn elements_are_names() {
+    let elements = Elements::new(vec![ElementKind::Fire, ElementKind::Dragon]);
+    assert_eq!(serde_json::to_string(&elements).unwrap(), r#"["fire","dragon"]"#);
+    assert_eq!(round_trip(&elements).get_elements(), elements.get_elements());
+    assert!(round_trip(&ElementKind::Water) == ElementKind::Water);
+}
+
+#[test]
+fn invalid_elements_are_rejected() {
+    assert!(serde_json::from_str::<ElementKind>(r#""invalid""#).is_err());
+    assert!(serde_json::from_str::<Elements>("[]").is_err());
+    assert!(serde_json::from_str::<Elements>(r#"["fire","fire"]"#).is_err());
+}
+
+#[test]
+fn global_strings_are_their_string() {
+    let string = name("a string sent over the network");
+    assert_eq!(serde_json::to_string(&string).unwrap(), r#""a string sent over the network""#);
+    assert_eq!(round_trip(&string), string);
+}
+
+#[test]
+fn unknown_global_strings_are_rejected() {
+    assert!(serde_json::from_str::<GlobalString>(r#""a string never interned by this process""#).is_err());
+    assert_eq!(GlobalString::new_if_exists(&"a string never interned by this process".to_string()).to_string(), "");
+}
+
+#[test]
+fn ability_names() {
+    let abilities = AbilityNames::new(vec![name("fireball"), name("tackle")]);
+    assert_eq!(serde_json::to_string(&abilities).unwrap(), r#"["fireball","tackle"]"#);
+    assert_eq!(round_trip(&abilities), abilities);
+    assert_eq!(round_trip(&AbilityNames::default()), AbilityNames::default());
+    assert!(serde_json::from_str::<AbilityNames>(r#"["a","b","c","d","e","f"]"#).is_err());
+    assert!(serde_json::from_str::<AbilityNames>(r#"["fireball","fireball"]"#).is_err());
+}
+
+#[test]
+fn base_ability_data() {
+    let mut map = AbilityMap::new();
+    let data = BaseAbilityData {
+        category: AbilityCategory::Attack,
+        types: Elements::new(vec![ElementKind::Fire]),
+        power: 40.0,
+        speed: 1.5,
+        max_uses: 20,
+        accuracy: 90,
+        flags: AbilityFlags::CONTACT | AbilityFlags::SOUND,
+        combo: Some(AbilityCombo { follows: "ember", power_multiplier: 2.0 })
+    };
+    map.add_data_ability("ember", BaseAbilityData { combo: None, ..data });
+    let copy = round_trip(&data);
+    assert_eq!(copy.category, data.category);
+    assert_eq!(copy.types.get_elements(), data.types.get_elements());
+    assert_eq!((copy.power, copy.speed, copy.max_uses, copy.accuracy), (data.power, data.speed, data.max_uses, data.accuracy));
+    assert_eq!(copy.flags, data.flags);
+    assert_eq!(copy.combo, data.combo);
+    // Combos resolve to the name the ability was added with
+    assert!(std::ptr::eq(round_trip(&data).combo.unwrap().follows, copy.combo.unwrap().follows));
+    let unknown = serde_json::to_string(&data).unwrap().replace("ember", "not an ability");
+    assert!(serde_json::from_str::<BaseAbilityData>(&unknown).is_err());
+}
+
+#[test]
+fn immie() {
+    let mut immie = Immie::new(name("lavapup"), 12, AbilityNames::new(vec![name("fireball")]));
+    immie.held_item = Some(name("ember stone"));
+    immie.status = Some(StatusCondition::Burn);
+    immie.ability_uses_spent[0] = 3;
+    immie.attunement = Some(ElementKind::Dragon);
+    assert_eq!(round_trip(&immie), immie);
+}
+
+#[test]
+fn invalid_immies_are_rejected() {
+    let immie = Immie::new(name("lavapup"), 12, AbilityNames::new(vec![name("fireball")]));
+    let json = serde_json::to_value(immie).unwrap();
+
+    let mut high_individual_value = json.clone();
+    high_individual_value["individual_values"]["attack"] = 40.into();
+    assert!(serde_json::from_value::<Immie>(high_individual_value).is_err());
+
+    let mut high_bond = json.clone();
+    high_bond["bond"] = 300.into();
+    assert!(serde_json::from_value::<Immie>(high_bond).is_err());
+
+    let mut too_many_abilities = json;
+    too_many_abilities["abilities"] = serde_json::json!(["fireball", "fireball", "fireball", "fireball", "fireball", "fireball"]);
+    assert!(serde_json::from_value::<Immie>(too_many_abilities).is_err());
+}
+
+#[test]
+fn battle_commands_and_events() {
+    let commands = vec![
+        BattleCommand::UseAbility { side: 0, ability_slot: 1, target_side: 1 },
+        BattleCommand::Attune { side: 1 },
+        BattleCommand::EndTurn
+    ];
+    assert_eq!(round_trip(&commands), commands);
+
+    let battler = BattlerId::new(1, 0);
+    let events = vec![
+        BattleEvent::Damaged { battler, amount: 10, remaining_health: 40 },
+        BattleEvent::AbilityBlocked { defender: battler, blocker: HitBlocker::Protect },
+        BattleEvent::ComboTriggered { battler, follows: name("ember") },
+        BattleEvent::HazardSet { side: 1, kind: HazardKind::Spikes, layers: 2 },
+        BattleEvent::StatusChanged { battler, status: BattlerStatus::Substitute, is_active: true },
+        BattleEvent::Attuned { battler, element: ElementKind::Metal },
+        BattleEvent::BattleEnded { winner: None }
+    ];
+    assert_eq!(round_trip(&events), events);
+}
@@ -0,0 +1,102 @@
+#![allow(clippy::needless_return)]
+
+use immie2d_shared::engine_types::game_rng::GameRng;
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::world::entity_snapshot::{EntityKind, EntitySnapshot};
+use immie2d_shared::world::snapshot_codec::{decode_naive, decode_packed, encode_naive, encode_packed};
+use immie2d_shared::world::tile_position::{Direction, TilePosition, WorldPosition};
+
+const MAPS: [&str; 3] = ["town", "route 1", "cave of trials"];
+const SPECIES: [&str; 4] = ["lavapup", "embercat", "tidalfin", "mossling"];
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+fn pick<T: Copy>(rng: &mut GameRng, values: &[T]) -> T {
+    return values[rng.next_below(values.len() as u32) as usize];
+}
+
+/// Entities of a busy area, in id order as the server sends them. Spread is how many tiles they are scattered over.
+fn random_tick(rng: &mut GameRng, count: usize, spread: u32) -> Vec<EntitySnapshot> {
+    let mut network_id = rng.next_below(1000);
+    let map = GlobalString::new(&pick(rng, &MAPS).to_string());
+    let mut snapshots = Vec::new();
+    for _ in 0..count {
+        network_id += 1 + rng.next_below(3);
+        let kind = match rng.next_below(3) {
+            0 => EntityKind::Player,
+            1 => EntityKind::Follower { owner: network_id - 1, species: GlobalString::new(&pick(rng, &SPECIES).to_string()) },
+            _ => EntityKind::Npc
+        };
+        let tile = TilePosition::new(rng.next_below(spread) as i32 - 20, rng.next_below(spread) as i32 - 20);
+        snapshots.push(EntitySnapshot { network_id, kind, position: WorldPosition::new(map, tile), facing: pick(rng, &DIRECTIONS), is_visible: rng.chance(0.9) });
+    }
+    return snapshots;
+}
+
+/// Any state at all, including extreme coordinates and ids out of order.
+fn random_state(rng: &mut GameRng) -> Vec<EntitySnapshot> {
+    let count = rng.next_below(40) as usize;
+    return (0..count).map(|_| {
+        let kind = match rng.next_below(3) {
+            0 => EntityKind::Player,
+            1 => EntityKind::Follower { owner: rng.next_u32(), species: GlobalString::new(&pick(rng, &SPECIES).to_string()) },
+            _ => EntityKind::Npc
+        };
+        let coordinate = |rng: &mut GameRng| if rng.chance(0.1) { pick(rng, &[i32::MIN, i32::MAX]) } else { rng.next_u32() as i32 };
+        let tile = TilePosition::new(coordinate(rng), coordinate(rng));
+        let map = GlobalString::new(&pick(rng, &MAPS).to_string());
+        return EntitySnapshot { network_id: rng.next_u32(), kind, position: WorldPosition::new(map, tile), facing: pick(rng, &DIRECTIONS), is_visible: rng.chance(0.5) };
+    }).collect();
+}
+
+#[test]
+fn random_states_round_trip() {
+    let mut rng = GameRng::new(0x5eed_1715);
+    for _ in 0..2000 {
+        let snapshots = random_state(&mut rng);
+        assert_eq!(decode_packed(&encode_packed(&snapshots)), Some(snapshots.clone()));
+        assert_eq!(decode_naive(&encode_naive(&snapshots)), Some(snapshots));
+    }
+    for _ in 0..200 {
+        let count = rng.next_below(200) as usize;
+        let snapshots = random_tick(&mut rng, count, 64);
+        assert_eq!(decode_packed(&encode_packed(&snapshots)), Some(snapshots));
+    }
+}
+
+#[test]
+fn random_bytes_never_panic() {
+    let mut rng = GameRng::new(0xbad_b17e);
+    for _ in 0..5000 {
+        let length = rng.next_below(64) as usize;
+        let bytes: Vec<u8> = (0..length).map(|_| rng.next_u32() as u8).collect();
+        let _ = decode_packed(&bytes);
+        let _ = decode_naive(&bytes);
+    }
+    // Runs of set bits decode as long or max value varints, such as hostile counts and name lengths
+    assert_eq!(decode_packed(&[0x01, 0x01, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0x01]), None);
+    for _ in 0..5000 {
+        let mut bytes: Vec<u8> = (0..rng.next_below(4)).map(|_| rng.next_u32() as u8).collect();
+        bytes.extend(std::iter::repeat_n(0xff, rng.next_below(12) as usize));
+        bytes.extend((0..rng.next_below(8)).map(|_| rng.next_u32() as u8));
+        let _ = decode_packed(&bytes);
+        let _ = decode_naive(&bytes);
+    }
+}
+
+/// Bytes per tick of both encodings for areas of different sizes. Run with --nocapture to see the table.
+#[test]
+fn packed_encoding_is_smaller_per_tick() {
+    let mut rng = GameRng::new(0x5eed_b175);
+    println!("{:>8} {:>8} {:>8} {:>8} {:>6}", "entities", "spread", "naive", "packed", "ratio");
+    for (count, spread) in [(1, 4), (10, 16), (50, 64), (200, 256), (1000, 1024)] {
+        let ticks: Vec<Vec<EntitySnapshot>> = (0..20).map(|_| random_tick(&mut rng, count, spread)).collect();
+        let naive = ticks.iter().map(|tick| encode_naive(tick).len()).sum::<usize>() / ticks.len();
+        let packed = ticks.iter().map(|tick| encode_packed(tick).len()).sum::<usize>() / ticks.len();
+        let ratio = packed as f64 / naive as f64;
+        println!("{:>8} {:>8} {:>8} {:>8} {:>6.2}", count, spread, naive, packed, ratio);
+        assert!(packed < naive, "Packed encoding of {} entities is {} bytes, naive is {}", count, packed, naive);
+        if count >= 10 {
+            assert!(ratio < 0.35, "Packed encoding of {} entities is {:.2} of naive, expected under 0.35", count, ratio);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+#![allow(clippy::needless_return)]
+
+use std::sync::{Arc, Barrier};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use immie2d_shared::engine_types::string_interner::StringInterner;
+
+const THREAD_COUNT: usize = 8;
+const STRINGS_PER_THREAD: usize = 5000;
+
+/// Every thread interns an overlapping set of strings, so both new inserts and lookups of existing strings contend.
+fn intern_concurrently(interner: Arc<StringInterner>) -> (Duration, Vec<Vec<u32>>) {
+    // Wait for every thread to finish building its strings so only interning is timed.
+    let barrier = Arc::new(Barrier::new(THREAD_COUNT + 1));
+    let mut handles = Vec::new();
+    for thread_index in 0..THREAD_COUNT {
+        let interner = interner.clone();
+        let barrier = barrier.clone();
+        handles.push(thread::spawn(move || {
+            // Half of the strings are shared between every thread.
+            let strings: Vec<String> = (0..STRINGS_PER_THREAD)
+                .map(|i| if i % 2 == 0 { format!("shared {}", i) } else { format!("thread {} string {}", thread_index, i) })
+                .collect();
+            barrier.wait();
+            return strings.iter().map(|string| interner.intern(string)).collect::<Vec<u32>>();
+        }));
+    }
+    barrier.wait();
+    let start = Instant::now();
+    let ids = handles.into_iter().map(|handle| handle.join().unwrap()).collect();
+    return (start.elapsed(), ids);
+}
+
+fn check_consistency(interner: &StringInterner, ids: &[Vec<u32>]) {
+    for (thread_index, thread_ids) in ids.iter().enumerate() {
+        for (i, id) in thread_ids.iter().enumerate() {
+            let expected = if i % 2 == 0 { format!("shared {}", i) } else { format!("thread {} string {}", thread_index, i) };
+            assert_eq!(interner.resolve(*id), expected);
+            if i % 2 == 0 {
+                assert_eq!(*id, ids[0][i], "Shared strings must get the same id on every thread");
+            }
+        }
+    }
+    let unique = STRINGS_PER_THREAD / 2 + THREAD_COUNT * STRINGS_PER_THREAD / 2;
+    assert_eq!(interner.get_count(), unique + 1);
+}
+
+#[test]
+fn sharded_interner_is_consistent_under_contention() {
+    let interner = Arc::new(StringInterner::new(16));
+    let (_, ids) = intern_concurrently(interner.clone());
+    check_consistency(&interner, &ids);
+}
+
+#[test]
+fn single_shard_interner_is_consistent_under_contention() {
+    let interner = Arc::new(StringInterner::new(1));
+    let (_, ids) = intern_concurrently(interner.clone());
+    check_consistency(&interner, &ids);
+}
+
+/// Compares throughput of a single locked map against the sharded interner.
+/// Timing depends on the machine, so run explicitly with `cargo test --release -- --ignored --nocapture`.
+#[test]
+#[ignore]
+fn sharded_interner_throughput() {
+    let (single, _) = intern_concurrently(Arc::new(StringInterner::new(1)));
+    let (sharded, _) = intern_concurrently(Arc::new(StringInterner::new(16)));
+    let operations = (THREAD_COUNT * STRINGS_PER_THREAD) as f64;
+    println!("1 shard: {:?} ({:.0} interns/s)", single, operations / single.as_secs_f64());
+    println!("16 shards: {:?} ({:.0} interns/s)", sharded, operations / sharded.as_secs_f64());
+    // Sharding can only reduce contention when threads actually run in parallel.
+    if thread::available_parallelism().map(|cores| cores.get()).unwrap_or(1) >= 4 {
+        assert!(sharded < single, "Sharding should improve concurrent throughput");
+    }
+}
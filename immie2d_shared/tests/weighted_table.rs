@@ -0,0 +1,156 @@
+#![allow(clippy::needless_return)]
+
+use immie2d_shared::engine_types::game_rng::GameRng;
+use immie2d_shared::engine_types::global_string::GlobalString;
+use immie2d_shared::engine_types::weighted_table::{weighted_choice, WeightedTable};
+use immie2d_shared::gameplay::encounter::encounter_conditions::{EncounterConditions, EncounterContext, TimeOfDay, Weather};
+use immie2d_shared::gameplay::encounter::encounter_roller::EncounterRoller;
+use immie2d_shared::gameplay::encounter::encounter_table::{EncounterEntry, EncounterTable};
+use immie2d_shared::world::region_weather::WeatherForecast;
+
+/// Rolls per statistical test. Enough that a wrong distribution stands out, while staying fast in debug builds.
+const SAMPLES: usize = 200_000;
+
+/// Random weights, some of them 0, with at least one above 0.
+fn random_weights(rng: &mut GameRng, count: usize, max_weight: u64) -> Vec<(usize, u64)> {
+    let mut entries: Vec<(usize, u64)> = (0..count).map(|index| (index, if rng.chance(0.2) { 0 } else { rng.next_u64() % max_weight })).collect();
+    entries[count - 1].1 = entries[count - 1].1.max(1);
+    return entries;
+}
+
+/// Pearson's chi squared statistic of counts against the chances of the entries. Entries that can't be rolled must
+/// never be rolled.
+fn chi_squared(entries: &[(usize, u64)], counts: &[usize]) -> f64 {
+    let total: u64 = entries.iter().map(|(_, weight)| weight).sum();
+    let mut statistic = 0.0;
+    for ((_, weight), count) in entries.iter().zip(counts.iter()) {
+        if *weight == 0 {
+            assert_eq!(*count, 0, "An entry without weight was rolled");
+            continue;
+        }
+        let expected = *weight as f64 / total as f64 * SAMPLES as f64;
+        statistic += (*count as f64 - expected).powi(2) / expected;
+    }
+    return statistic;
+}
+
+/// A generous upper bound of the chi squared statistic with the given degrees of freedom, several standard deviations
+/// above its mean. The rng is seeded, so a correct distribution always passes.
+fn chi_squared_limit(degrees_of_freedom: usize) -> f64 {
+    let degrees_of_freedom = degrees_of_freedom as f64;
+    return degrees_of_freedom + 6.0 * (2.0 * degrees_of_freedom).sqrt() + 10.0;
+}
+
+fn count_rolls(count: usize, mut roll: impl FnMut() -> usize) -> Vec<usize> {
+    let mut counts = vec![0; count];
+    for _ in 0..SAMPLES {
+        counts[roll()] += 1;
+    }
+    return counts;
+}
+
+#[test]
+fn table_chances_are_exactly_the_weights() {
+    let mut rng = GameRng::new(1731);
+    for count in 1..=64 {
+        for max_weight in [2, 100, u32::MAX as u64, u64::MAX / 64] {
+            let entries = random_weights(&mut rng, count, max_weight);
+            let total: u64 = entries.iter().map(|(_, weight)| weight).sum();
+            let table = WeightedTable::new(entries.clone()).unwrap();
+            let mut chance_total = 0.0;
+            for (index, (_, weight)) in entries.iter().enumerate() {
+                let chance = table.get_chance(index);
+                assert!((chance - *weight as f64 / total as f64).abs() < 1e-9, "Entry {} of {} has chance {} for weight {} of {}", index, count, chance, weight, total);
+                chance_total += chance;
+            }
+            assert!((chance_total - 1.0).abs() < 1e-9);
+        }
+    }
+}
+
+#[test]
+fn table_samples_match_the_weights() {
+    let mut rng = GameRng::new(17);
+    for count in [1, 2, 3, 7, 20, 100] {
+        let entries = random_weights(&mut rng, count, 1000);
+        let table = WeightedTable::new(entries.clone()).unwrap();
+        let counts = count_rolls(count, || table.sample_index(&mut rng));
+        let statistic = chi_squared(&entries, &counts);
+        assert!(statistic < chi_squared_limit(count), "Chi squared {} of {} entries is too high", statistic, count);
+    }
+}
+
+#[test]
+fn choice_samples_match_the_weights() {
+    let mut rng = GameRng::new(31);
+    for count in [1, 2, 5, 30] {
+        let entries = random_weights(&mut rng, count, 1000);
+        let counts = count_rolls(count, || *weighted_choice(&entries, &mut rng).unwrap());
+        let statistic = chi_squared(&entries, &counts);
+        assert!(statistic < chi_squared_limit(count), "Chi squared {} of {} entries is too high", statistic, count);
+    }
+}
+
+#[test]
+fn lopsided_weights_roll_the_rare_entry() {
+    // A one in a million entry next to a huge one, as in a shiny or legendary encounter
+    let table = WeightedTable::new(vec![("common", 999_999), ("rare", 1)]).unwrap();
+    assert_eq!(table.get_chance(1), 1.0 / 1_000_000.0);
+    let mut rng = GameRng::new(5);
+    let rare = (0..5_000_000).filter(|_| *table.sample(&mut rng) == "rare").count();
+    assert!((1..=15).contains(&rare), "Rolled the rare entry {} times", rare);
+}
+
+#[test]
+fn sampling_is_deterministic() {
+    let entries = random_weights(&mut GameRng::new(2), 50, 500);
+    let table = WeightedTable::new(entries.clone()).unwrap();
+    let rolls = |seed: u64| {
+        let mut rng = GameRng::new(seed);
+        let from_table: Vec<usize> = (0..1000).map(|_| table.sample_index(&mut rng)).collect();
+        let from_choice: Vec<usize> = (0..1000).map(|_| *weighted_choice(&entries, &mut rng).unwrap()).collect();
+        return (from_table, from_choice, rng.get_state());
+    };
+    assert_eq!(rolls(99), rolls(99));
+    assert_ne!(rolls(99), rolls(100));
+
+    // Table samples draw exactly two numbers and choices one, so rolls can be replayed from the rng's state
+    let mut rng = GameRng::new(3);
+    let before = rng;
+    table.sample(&mut rng);
+    weighted_choice(&entries, &mut rng);
+    assert_eq!(rng.get_rolls_since(before, 8).len(), 3);
+
+    // Rebuilding a table from the same entries builds the same table
+    assert_eq!(WeightedTable::new(entries.clone()), Some(table));
+}
+
+#[test]
+fn encounter_and_weather_rolls_match_golden() {
+    let name = |name: &str| GlobalString::new(&name.to_string());
+    let entry = |species: &str, weight: u32, conditions: EncounterConditions| EncounterEntry { species: name(species), min_level: 2, max_level: 9, weight, conditions };
+    let night = EncounterConditions { times_of_day: vec![TimeOfDay::Night], ..Default::default() };
+    let table = EncounterTable { name: name("grass"), entries: vec![
+        entry("sproutle", 6, EncounterConditions::default()),
+        entry("lavapup", 3, EncounterConditions::default()),
+        entry("shadefox", 1, night)
+    ] };
+    let roller = EncounterRoller::new(vec![table]);
+    let mut rng = GameRng::new(1731);
+    let mut encounters = Vec::new();
+    // Day filters out the night entry, while night has every entry and uses the table built with the roller
+    for time_of_day in [TimeOfDay::Day, TimeOfDay::Night] {
+        let context = EncounterContext::new(time_of_day, Weather::Clear);
+        encounters.extend((0..4).map(|_| {
+            let encounter = roller.roll(name("grass"), &context, &mut rng).unwrap();
+            return (encounter.species, encounter.level);
+        }));
+    }
+    let forecast = WeatherForecast::new(vec![(Weather::Clear, 5), (Weather::Rain, 3), (Weather::Snow, 2)]);
+    let weathers: Vec<Weather> = (0..4).map(|_| forecast.roll(&mut rng)).collect();
+
+    // Changing how tables are sampled changes every seeded encounter and forecast, so it must be deliberate
+    let (sproutle, lavapup) = (name("sproutle"), name("lavapup"));
+    assert_eq!(encounters, vec![(lavapup, 3), (sproutle, 8), (sproutle, 5), (sproutle, 4), (lavapup, 5), (lavapup, 2), (sproutle, 8), (sproutle, 9)]);
+    assert_eq!(weathers, vec![Weather::Rain, Weather::Clear, Weather::Clear, Weather::Clear]);
+}